@@ -17,6 +17,13 @@ SKIP=0
 # Repository root (where this script lives)
 REPO_ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
 
+# `./check.sh --solutions` runs the tests against the reference solutions,
+# to check the tests themselves rather than your code.
+CARGO_FEATURES=()
+if [ "${1:-}" = "--solutions" ]; then
+    CARGO_FEATURES=(--features solutions)
+fi
+
 # Module 4 uses riscv64 target
 RISCV64_TARGET="riscv64gc-unknown-linux-gnu"
 RISCV64_SYSROOT="${RISCV64_SYSROOT:-/usr/riscv64-linux-gnu}"
@@ -63,31 +70,89 @@ exercises=(
     "01_concurrency_sync:mutex_counter:Mutex Shared State"
     "01_concurrency_sync:channel:Channel Communication"
     "01_concurrency_sync:process_pipe:Process Pipes"
+    "01_concurrency_sync:parallel_map_reduce:Parallel Map/Reduce"
+    "01_concurrency_sync:blocking_queue:Blocking Queue"
     # Module 2: no_std Development
     "02_no_std_dev:mem_primitives:Memory Primitives"
     "02_no_std_dev:bump_allocator:Bump Allocator"
     "02_no_std_dev:free_list_allocator:Free-List Allocator"
     "02_no_std_dev:syscall_wrapper:Syscall Wrapper"
     "02_no_std_dev:fd_table:File Descriptor Table"
+    "02_no_std_dev:stack_canary:Stack Canary"
+    "02_no_std_dev:intrusive_list:Intrusive List"
+    "02_no_std_dev:klog:Kernel Log"
     # Module 3: OS Concurrency Advanced
     "03_os_concurrency:atomic_counter:Atomic Counter"
     "03_os_concurrency:atomic_ordering:Memory Ordering"
     "03_os_concurrency:spinlock:Spinlock"
     "03_os_concurrency:spinlock_guard:RAII Spinlock Guard"
     "03_os_concurrency:rwlock:Read-Write Lock"
+    "03_os_concurrency:wait_queue:Wait Queue"
+    "03_os_concurrency:msg_queue:Message Queue"
+    "03_os_concurrency:pipe:Pipe"
+    "03_os_concurrency:spsc_ring:SPSC Ring Buffer"
+    "03_os_concurrency:cpu_affinity:CPU Affinity"
+    "03_os_concurrency:thread_priority:Thread Priority"
+    "03_os_concurrency:mpmc_ring:MPMC Ring Buffer"
     # Module 4: Context Switching
     "04_context_switch:stack_coroutine:Stackful Coroutine"
     "04_context_switch:green_threads:Green Threads"
+    "04_context_switch:generator:Generators"
+    "04_context_switch:switch_bench:Switch Benchmark"
     # Module 5: Async Programming
     "05_async_programming:basic_future:Manual Future"
     "05_async_programming:tokio_tasks:Tokio Tasks"
     "05_async_programming:async_channel_ex:Async Channel"
     "05_async_programming:select_timeout:Select/Timeout"
+    "05_async_programming:mini_executor:Mini Executor"
+    "05_async_programming:raw_waker:Raw Waker"
+    "05_async_programming:timer_wheel:Timer Wheel"
+    "05_async_programming:async_mutex:Async Mutex"
+    "05_async_programming:async_semaphore:Async Semaphore"
+    "05_async_programming:join_combinators:Join Combinators"
+    "05_async_programming:select2:Select2"
+    "05_async_programming:streams:Streams"
+    "05_async_programming:mpsc_channel:MPSC Channel"
+    "05_async_programming:sync_channels:oneshot / watch / broadcast"
+    "05_async_programming:graceful_shutdown:Graceful Shutdown"
+    "05_async_programming:tcp_echo:TCP Echo"
+    "05_async_programming:async_file:Async File"
+    "05_async_programming:futures_unordered:FuturesUnordered"
+    "05_async_programming:io_uring_intro:io_uring"
+    "05_async_programming:hierarchical_timer:Hierarchical Timer"
+    "05_async_programming:job_outcomes:Job Outcomes"
+    "05_async_programming:executor_fairness:Executor Fairness"
+    "05_async_programming:notify_event:Notify Event / Queue"
+    "05_async_programming:worker_pool:Worker Pool"
+    "05_async_programming:pipeline_models:Pipeline Models"
     # Module 6: Page Tables
     "06_page_table:pte_flags:PTE Flags"
     "06_page_table:page_table_walk:Page Table Walk"
     "06_page_table:multi_level_pt:SV39 Multi-Level PT"
     "06_page_table:tlb_sim:TLB Simulation"
+    "06_page_table:shm:Shared Memory"
+    "06_page_table:cache_sim:Cache Simulation"
+    "06_page_table:rv64_interp:RV64 Interpreter"
+    "06_page_table:user_copy:copy_from_user"
+    "06_page_table:memory_set:Memory Set / ASLR"
+    "06_page_table:radix_tree:Radix Tree"
+    # Module 7: Traps & Interrupts
+    "07_trap_interrupt:scause_decode:scause Decoder"
+    "07_trap_interrupt:timer_tick:Timer Tick"
+    "07_trap_interrupt:plic:PLIC"
+    "07_trap_interrupt:uart16550:UART 16550"
+    "07_trap_interrupt:sbi_call:SBI Call"
+    "07_trap_interrupt:csr_bits:sstatus/sie Bitfields"
+    "07_trap_interrupt:preempt_sched:Timer Preemption"
+    # Module 8: ELF Loading
+    "08_elf_loader:elf_parse:ELF Parser"
+    "08_elf_loader:elf_load:ELF Loader"
+    "08_elf_loader:elf_reloc:ELF Relocations"
+    "08_elf_loader:user_stack:Initial User Stack"
+    # Module 9: Networking
+    "09_networking:packet_parse:Ethernet/IPv4/UDP Packets"
+    "09_networking:arp_cache:ARP Cache"
+    "09_networking:udp_socket:UDP Socket"
 )
 
 echo -e "${BLUE}========================================${NC}"
@@ -117,7 +182,7 @@ for entry in "${exercises[@]}"; do
         if [ "$(uname -s)" = "Darwin" ]; then
             echo -e "${YELLOW}SKIP (macOS)${NC}"
             ((SKIP++))
-        elif cargo test -p "$package" --target "$RISCV64_TARGET" --quiet -- --nocapture 2>/dev/null; then
+        elif cargo test -p "$package" "${CARGO_FEATURES[@]}" --target "$RISCV64_TARGET" --quiet -- --nocapture 2>/dev/null; then
             echo -e "${GREEN}PASS${NC}"
             ((PASS++))
         else
@@ -125,7 +190,7 @@ for entry in "${exercises[@]}"; do
             ((FAIL++))
         fi
     else
-        if cargo test -p "$package" --quiet 2>/dev/null; then
+        if cargo test -p "$package" "${CARGO_FEATURES[@]}" --quiet 2>/dev/null; then
             echo -e "${GREEN}PASS${NC}"
             ((PASS++))
         else
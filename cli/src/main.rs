@@ -4,6 +4,7 @@ use crossterm::{
     execute,
     terminal::{self, Clear, ClearType},
 };
+use grader::{Report, Status};
 use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::Deserialize;
 use std::io::{self, Write};
@@ -48,7 +49,7 @@ fn main() {
     match args.get(1).map(String::as_str) {
         None | Some("watch") => watch_mode(&exercises),
         Some("list") => list_mode(&exercises),
-        Some("check") => check_mode(&exercises),
+        Some("check") => check_mode(&exercises, &args[2..]),
         Some("run") => run_mode(&exercises, args.get(2)),
         Some("hint") => hint_mode(&exercises, args.get(2)),
         Some("help" | "--help" | "-h") => print_usage(),
@@ -73,23 +74,16 @@ fn load_exercises() -> Vec<Exercise> {
     std::process::exit(1);
 }
 
-/// 第四章（上下文切换）需用 riscv64 target，在 x86 上通过 .cargo/config 的 runner 用 QEMU 运行。
-const RISCV64_TARGET: &str = "riscv64gc-unknown-linux-gnu";
-const RISCV64_PACKAGES: &[&str] = &["stack_coroutine", "green_threads"];
-
-fn need_riscv64_target(package: &str) -> bool {
-    RISCV64_PACKAGES.contains(&package)
-}
-
+/// Run the tests of `ex` for display: colored, stderr (compiler messages) first.
 fn test_exercise(ex: &Exercise) -> TestResult {
     let mut args = vec!["test", "-p", &ex.package];
-    if need_riscv64_target(&ex.package) {
-        args.extend(["--target", RISCV64_TARGET]);
+    let target = grader::target_for(&ex.package);
+    if let Some(target) = target {
+        args.extend(["--target", target]);
     }
-    if need_riscv64_target(&ex.package) {
-        args.extend(["--", "--color=always", "--nocapture"]);
-    } else {
-        args.extend(["--", "--color=always"]);
+    args.extend(["--", "--color=always"]);
+    if target.is_some() {
+        args.push("--nocapture");
     }
 
     let output = Command::new("cargo")
@@ -107,23 +101,9 @@ fn test_exercise(ex: &Exercise) -> TestResult {
     }
 }
 
-fn test_quiet(ex: &Exercise) -> bool {
-    let mut args = vec!["test", "-p", &ex.package];
-    if need_riscv64_target(&ex.package) {
-        args.extend(["--target", RISCV64_TARGET]);
-    }
-    args.push("--quiet");
-    if need_riscv64_target(&ex.package) {
-        args.extend(["--", "--nocapture"]);
-    }
-
-    Command::new("cargo")
-        .args(&args)
-        .stderr(std::process::Stdio::null())
-        .stdout(std::process::Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
+/// Whether every test of `ex` passes, graded the same way as `oscamp check`.
+fn exercise_passes(ex: &Exercise) -> bool {
+    grader::run_exercise(&ex.package, &ex.name, &ex.module).status == Status::Pass
 }
 
 /// In raw-mode, \n must be \r\n
@@ -150,7 +130,7 @@ fn watch_mode(exercises: &[Exercise]) {
     for (i, ex) in exercises.iter().enumerate() {
         print!("  [{:2}/{total}] Checking {:<25}\r", i + 1, ex.package);
         stdout.flush().unwrap();
-        if test_quiet(ex) {
+        if exercise_passes(ex) {
             done[i] = true;
         } else if current == total {
             current = i;
@@ -267,7 +247,6 @@ fn watch_mode(exercises: &[Exercise]) {
                             &mut stdout,
                             exercises,
                             current,
-                            count_done(&done),
                             &last_result,
                             show_hint,
                             false,
@@ -280,7 +259,6 @@ fn watch_mode(exercises: &[Exercise]) {
                             &mut stdout,
                             exercises,
                             current,
-                            count_done(&done),
                             &last_result,
                             show_hint,
                             show_list,
@@ -345,9 +323,9 @@ fn wait_for_quit() {
 // ─────────────────────── rendering ───────────────────────
 
 fn progress_bar(done: usize, total: usize, width: usize) -> String {
-    let filled = if total > 0 { done * width / total } else { 0 };
+    let filled = (done * width).checked_div(total).unwrap_or(0);
     let empty = width - filled;
-    let pct = if total > 0 { done * 100 / total } else { 0 };
+    let pct = (done * 100).checked_div(total).unwrap_or(0);
     format!(
         "{GREEN}{}{}  {done}/{total} ({pct}%){RESET}",
         "█".repeat(filled),
@@ -452,13 +430,13 @@ fn full_redraw(
     out: &mut impl Write,
     exercises: &[Exercise],
     current: usize,
-    done_n: usize,
     result: &Option<TestResult>,
     show_hint: bool,
     show_list: bool,
     done: &[bool],
 ) {
     execute!(io::stdout(), Clear(ClearType::All), cursor::MoveTo(0, 0)).unwrap();
+    let done_n = done.iter().filter(|&&d| d).count();
 
     if show_list {
         render_header(out, exercises, current, done_n);
@@ -493,7 +471,7 @@ fn list_mode(exercises: &[Exercise]) {
             cur_module.clone_from(&ex.module);
             println!("\n  {YELLOW}[{cur_module}]{RESET}");
         }
-        let passed = test_quiet(ex);
+        let passed = exercise_passes(ex);
         if passed {
             done += 1;
         }
@@ -515,27 +493,67 @@ fn list_mode(exercises: &[Exercise]) {
     println!("\n  Progress: {bar}\n");
 }
 
-fn check_mode(exercises: &[Exercise]) {
+/// Points the classroom autograding result is scaled to.
+const CLASSROOM_POINTS: u32 = 100;
+
+fn check_mode(exercises: &[Exercise], args: &[String]) {
+    let mut json_path = None;
+    let mut classroom_path = None;
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, path] if flag == "--json" => json_path = Some(path),
+            [flag, path] if flag == "--classroom" => classroom_path = Some(path),
+            _ => {
+                eprintln!("Usage: oscamp check [--json <file>] [--classroom <file>]");
+                std::process::exit(1);
+            }
+        }
+    }
+
     println!("{BOLD}{BLUE}OS Camp - Check all exercises{RESET}\n");
 
     let total = exercises.len();
-    let mut done = 0;
+    let mut results = Vec::with_capacity(total);
 
     for (i, ex) in exercises.iter().enumerate() {
         print!("  [{:2}/{total}] {:<22} ", i + 1, ex.name);
         io::stdout().flush().unwrap();
-        if test_quiet(ex) {
-            done += 1;
-            println!("{GREEN}✅ PASS{RESET}");
-        } else {
-            println!("{RED}❌ FAIL{RESET}");
+        let result = grader::run_exercise(&ex.package, &ex.name, &ex.module);
+        match result.status {
+            Status::Pass => println!("{GREEN}✅ PASS{RESET}"),
+            Status::Fail => println!(
+                "{RED}❌ FAIL{RESET} {DIM}({}/{} tests failed){RESET}",
+                result.failed,
+                result.passed + result.failed
+            ),
+            Status::BuildError => println!("{RED}❌ FAIL{RESET} {DIM}(build error){RESET}"),
         }
+        results.push(result);
     }
 
+    let report = Report::new(results);
+    let (done, total) = (report.completed, report.total);
     println!("\n  {BOLD}Result: {done}/{total} passed{RESET}");
     if done == total {
         println!("  {GREEN}🎉 All passed!{RESET}");
     }
+
+    if let Some(path) = json_path {
+        write_report(path, &report.to_json());
+    }
+    if let Some(path) = classroom_path {
+        write_report(path, &report.to_classroom(CLASSROOM_POINTS).to_json());
+    }
+}
+
+fn write_report(path: &str, json: &str) {
+    match std::fs::write(path, json) {
+        Ok(()) => println!("  {DIM}Report written to {path}{RESET}"),
+        Err(e) => {
+            eprintln!("{RED}Error:{RESET} could not write {path}: {e}");
+            std::process::exit(1);
+        }
+    }
 }
 
 fn run_mode(exercises: &[Exercise], name: Option<&String>) {
@@ -588,7 +606,9 @@ fn print_usage() {
         "  {BOLD}watch{RESET}    Interactive exercise mode (default) - real-time file monitoring"
     );
     println!("  {BOLD}list{RESET}     View completion status of all exercises");
-    println!("  {BOLD}check{RESET}    Check all exercises in batch");
+    println!(
+        "  {BOLD}check{RESET}    Check all exercises in batch  (oscamp check [--json <file>] [--classroom <file>])"
+    );
     println!("  {BOLD}run{RESET}      Run specified exercise  (oscamp run <package>)");
     println!("  {BOLD}hint{RESET}     View exercise hint  (oscamp hint <package>)");
     println!("  {BOLD}help{RESET}     Show this help message");
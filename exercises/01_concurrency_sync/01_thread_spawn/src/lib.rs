@@ -27,12 +27,15 @@
 //!
 //! ## Exercise Structure
 //! 1. **Basic exercises** (`double_in_thread`, `parallel_sum`) – introduce fundamental thread creation.
-//! 2. **Advanced exercises** (`named_sleeper`, `increment_thread_local`, `scoped_slice_sum`, `handle_panic`) – explore additional thread operations.
+//! 2. **Advanced exercises** (`named_sleeper`, `increment_thread_local`, `StatRegistry`, `scoped_slice_sum`, `handle_panic`) – explore additional thread operations.
 //! Each function includes a `TODO` comment indicating where you need to write code.
 //! Run `cargo test` to check your implementations.
 
 #[allow(unused_imports)]
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 #[allow(unused_imports)]
 use std::thread;
 #[allow(unused_imports)]
@@ -203,6 +206,114 @@ pub fn increment_thread_local() -> usize {
     todo!()
 }
 
+/// Per-thread counters with a global total: `add` touches only the calling thread's own cell
+/// (no lock, no shared cache line), while `snapshot` walks every registered cell.
+///
+/// Each thread registers its cell with the registry the first time it calls `add`. When the
+/// thread exits, its thread-local handle is dropped and folds the cell's final value into
+/// `retired`, so counts of dead threads are kept without keeping their cells around.
+pub struct StatRegistry {
+    inner: Arc<RegistryInner>,
+}
+
+struct RegistryInner {
+    /// Key of this registry in every thread's `LOCAL_STATS` map.
+    id: usize,
+    state: Mutex<RegistryState>,
+}
+
+struct RegistryState {
+    /// Cells of threads that are still running.
+    live: Vec<Arc<AtomicU64>>,
+    /// Sum of the final values of exited threads' cells.
+    retired: u64,
+}
+
+/// A thread's registration with one registry.
+struct LocalStat {
+    registry: Arc<RegistryInner>,
+    cell: Arc<AtomicU64>,
+}
+
+static NEXT_REGISTRY_ID: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The calling thread's cells, by registry id.
+    static LOCAL_STATS: RefCell<HashMap<usize, LocalStat>> = RefCell::new(HashMap::new());
+}
+
+impl Default for StatRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatRegistry {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(RegistryInner {
+                id: NEXT_REGISTRY_ID.fetch_add(1, Ordering::Relaxed),
+                state: Mutex::new(RegistryState {
+                    live: Vec::new(),
+                    retired: 0,
+                }),
+            }),
+        }
+    }
+
+    /// A fresh cell for the calling thread, pushed onto `live`.
+    fn register(&self) -> LocalStat {
+        // TODO: New Arc<AtomicU64> cell, push a clone onto live (under the lock), wrap it in a LocalStat
+        todo!()
+    }
+
+    /// Add `n` to the calling thread's counter.
+    ///
+    /// Look up (or `register` and insert) this registry's entry in `LOCAL_STATS`. Only this
+    /// thread ever writes its cell, so a `Relaxed` load and store is enough: no `fetch_add`, no lock.
+    #[allow(unused_variables)]
+    pub fn add(&self, n: u64) {
+        // TODO: LOCAL_STATS.with: entry(self.inner.id).or_insert_with(|| self.register()),
+        // then store(load + n) on the cell with Ordering::Relaxed
+        todo!()
+    }
+
+    pub fn incr(&self) {
+        self.add(1);
+    }
+
+    /// The calling thread's own count (0 if it never called `add`).
+    pub fn local(&self) -> u64 {
+        LOCAL_STATS.with(|stats| {
+            stats
+                .borrow()
+                .get(&self.inner.id)
+                .map_or(0, |stat| stat.cell.load(Ordering::Relaxed))
+        })
+    }
+
+    /// Total over all threads, live and exited: `retired` plus every live cell, read under the
+    /// `state` lock so that a thread exiting at the same time is counted exactly once.
+    pub fn snapshot(&self) -> u64 {
+        // TODO: Lock state, return retired + the sum of every live cell
+        todo!()
+    }
+
+    /// Number of threads currently registered.
+    pub fn live_threads(&self) -> usize {
+        self.inner.state.lock().unwrap().live.len()
+    }
+}
+
+impl Drop for LocalStat {
+    /// Thread exit: under the `state` lock, add the cell's value to `retired` and remove the
+    /// cell from `live` (find it with `Arc::ptr_eq`).
+    fn drop(&mut self) {
+        // TODO: Lock the registry's state, retired += cell value, remove the cell from live
+        todo!()
+    }
+}
+
 /// Spawn two threads using a **scoped thread** to compute the sum of two slices without moving ownership.
 ///
 /// Use `thread::scope` to allow threads to borrow the slices `&[i32]`.
@@ -236,9 +347,49 @@ pub fn handle_panic(value: i32, should_panic: bool) -> Result<i32, ()> {
     todo!()
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::Arc;
+
+    const HINTS: Hints = &[
+        (
+            "test_parallel_sum",
+            "spawn both threads before joining either, then join each handle for its sum",
+        ),
+        (
+            "test_thread_local",
+            "THREAD_COUNT must be a thread_local! (not a static shared by all threads): each thread starts counting at 0",
+        ),
+        (
+            "test_stat_registry_survives_thread_exit",
+            "LocalStat's Drop runs when the thread exits: fold the cell into retired and remove it from live, under the lock",
+        ),
+        (
+            "test_stat_registry_per_thread_cells",
+            "key LOCAL_STATS by the registry's id: each thread needs its own cell for every registry it adds to",
+        ),
+        (
+            "test_stat_registry_snapshot_during_churn",
+            "read retired and the live cells under one lock; an exiting thread must move its value over under that same lock",
+        ),
+        (
+            "test_scoped_slice_sum",
+            "spawn both workers with s.spawn inside thread::scope and join them before the scope closure returns",
+        ),
+        (
+            "test_handle_panic_error",
+            "a panicking thread makes join() return Err: map that to Err(()) instead of unwrapping it",
+        ),
+    ];
 
     #[test]
     fn test_double_basic() {
@@ -260,7 +411,7 @@ mod tests {
     fn test_parallel_sum() {
         let a = vec![1, 2, 3];
         let b = vec![10, 20, 30];
-        assert_eq!(parallel_sum(a, b), (6, 60));
+        assert_eq_with_hint!(parallel_sum(a, b), (6, 60));
     }
 
     #[test]
@@ -298,8 +449,86 @@ mod tests {
         }
         let results = counters.lock().unwrap();
         // Each thread should have counted (1, 2) independently.
-        assert_eq!(results.len(), 2);
-        assert!(results.contains(&(1, 2)));
+        assert_eq_with_hint!(results.len(), 2);
+        assert_with_hint!(results.contains(&(1, 2)));
+    }
+
+    #[test]
+    fn test_stat_registry_per_thread_cells() {
+        let a = StatRegistry::new();
+        let b = StatRegistry::new();
+        a.add(5);
+        b.incr();
+        assert_eq_with_hint!(a.local(), 5);
+        assert_eq_with_hint!(b.local(), 1);
+        thread::scope(|s| {
+            s.spawn(|| {
+                assert_eq_with_hint!(a.local(), 0);
+                a.add(10);
+                assert_eq!(a.live_threads(), 2);
+            });
+        });
+        assert_eq!(a.local(), 5);
+        assert_eq!(a.snapshot(), 15);
+        assert_eq!(b.snapshot(), 1);
+    }
+
+    #[test]
+    fn test_stat_registry_survives_thread_exit() {
+        let reg = Arc::new(StatRegistry::new());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let reg = reg.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        reg.incr();
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq_with_hint!(reg.snapshot(), 8000);
+        assert_eq_with_hint!(reg.live_threads(), 0);
+    }
+
+    #[test]
+    fn test_stat_registry_snapshot_during_churn() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let reg = Arc::new(StatRegistry::new());
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let (reg, done) = (reg.clone(), done.clone());
+            thread::spawn(move || {
+                let mut last = 0;
+                while !done.load(Ordering::SeqCst) {
+                    let now = reg.snapshot();
+                    assert_with_hint!(now >= last, "snapshot went back from {last} to {now}");
+                    last = now;
+                }
+            })
+        };
+        for _wave in 0..20 {
+            let workers: Vec<_> = (0..4)
+                .map(|_| {
+                    let reg = reg.clone();
+                    thread::spawn(move || {
+                        for _ in 0..250 {
+                            reg.incr();
+                        }
+                    })
+                })
+                .collect();
+            for w in workers {
+                w.join().unwrap();
+            }
+        }
+        done.store(true, Ordering::SeqCst);
+        reader.join().unwrap();
+        assert_eq_with_hint!(reg.snapshot(), 20 * 4 * 250);
+        assert_eq!(reg.live_threads(), 0);
     }
 
     #[test]
@@ -307,11 +536,11 @@ mod tests {
         let a = [1, 2, 3];
         let b = [10, 20, 30];
         let (sum_a, sum_b) = scoped_slice_sum(&a, &b);
-        assert_eq!(sum_a, 6);
-        assert_eq!(sum_b, 60);
+        assert_eq_with_hint!(sum_a, 6);
+        assert_eq_with_hint!(sum_b, 60);
         // Ensure slices are still accessible (they are borrowed, not moved).
-        assert_eq!(a.len(), 3);
-        assert_eq!(b.len(), 3);
+        assert_eq_with_hint!(a.len(), 3);
+        assert_eq_with_hint!(b.len(), 3);
     }
 
     #[test]
@@ -323,6 +552,6 @@ mod tests {
     #[test]
     fn test_handle_panic_error() {
         let result = handle_panic(100, true);
-        assert_eq!(result, Err(()));
+        assert_eq_with_hint!(result, Err(()));
     }
 }
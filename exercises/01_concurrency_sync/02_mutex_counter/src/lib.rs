@@ -35,9 +35,28 @@ pub fn concurrent_collect(n_threads: usize) -> Vec<usize> {
     todo!()
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_counter_multi_thread",
+            "give every thread its own Arc::clone of the counter, lock it for each increment, and join all threads before reading it",
+        ),
+        (
+            "test_collect",
+            "threads push in any order: sort the vector after joining them all",
+        ),
+    ];
 
     #[test]
     fn test_counter_single_thread() {
@@ -46,7 +65,7 @@ mod tests {
 
     #[test]
     fn test_counter_multi_thread() {
-        assert_eq!(concurrent_counter(10, 100), 1000);
+        assert_eq_with_hint!(concurrent_counter(10, 100), 1000);
     }
 
     #[test]
@@ -57,7 +76,7 @@ mod tests {
     #[test]
     fn test_collect() {
         let result = concurrent_collect(5);
-        assert_eq!(result, vec![0, 1, 2, 3, 4]);
+        assert_eq_with_hint!(result, vec![0, 1, 2, 3, 4]);
     }
 
     #[test]
@@ -0,0 +1,56 @@
+//! # Mutex Shared State
+//!
+//! In this exercise, you will use `Arc<Mutex<T>>` to safely share and modify data between multiple threads.
+//!
+//! ## Concepts
+//! - `Mutex<T>` mutex protects shared data
+//! - `Arc<T>` atomic reference counting enables cross-thread sharing
+//! - `lock()` acquires the lock and accesses data
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Increment a counter concurrently using `n_threads` threads.
+/// Each thread increments the counter `count_per_thread` times.
+/// Returns the final counter value.
+///
+/// Hint: Use `Arc<Mutex<usize>>` as the shared counter.
+pub fn concurrent_counter(n_threads: usize, count_per_thread: usize) -> usize {
+    let counter = Arc::new(Mutex::new(0usize));
+    let handles: Vec<_> = (0..n_threads)
+        .map(|_| {
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                for _ in 0..count_per_thread {
+                    *counter.lock().unwrap() += 1;
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    let total = *counter.lock().unwrap();
+    total
+}
+
+/// Add elements to a shared vector concurrently using multiple threads.
+/// Each thread pushes its own id (0..n_threads) to the vector.
+/// Returns the sorted vector.
+///
+/// Hint: Use `Arc<Mutex<Vec<usize>>>`.
+pub fn concurrent_collect(n_threads: usize) -> Vec<usize> {
+    let ids = Arc::new(Mutex::new(Vec::new()));
+    let handles: Vec<_> = (0..n_threads)
+        .map(|id| {
+            let ids = Arc::clone(&ids);
+            thread::spawn(move || ids.lock().unwrap().push(id))
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    let mut ids = ids.lock().unwrap().clone();
+    ids.sort();
+    ids
+}
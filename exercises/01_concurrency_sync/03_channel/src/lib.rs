@@ -33,39 +33,71 @@ pub fn multi_producer(n_producers: usize) -> Vec<String> {
     todo!()
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+    use std::time::Duration;
+    use watchdog::run_with_timeout;
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    const HINTS: Hints = &[
+        (
+            "test_simple_send_recv",
+            "drop the sender (or move it into the sending thread) so that iterating over the receiver ends",
+        ),
+        (
+            "test_multi_producer",
+            "every producer thread needs its own tx.clone(); drop the original tx, then sort the received messages",
+        ),
+    ];
 
     #[test]
     fn test_simple_send_recv() {
-        let items = vec!["hello".into(), "world".into(), "rust".into()];
-        let result = simple_send_recv(items.clone());
-        assert_eq!(result, items);
+        run_with_timeout(TIMEOUT, || {
+            let items = vec!["hello".into(), "world".into(), "rust".into()];
+            let result = simple_send_recv(items.clone());
+            assert_eq_with_hint!(result, items);
+        });
     }
 
     #[test]
     fn test_simple_empty() {
-        let result = simple_send_recv(vec![]);
-        assert!(result.is_empty());
+        run_with_timeout(TIMEOUT, || {
+            let result = simple_send_recv(vec![]);
+            assert!(result.is_empty());
+        });
     }
 
     #[test]
     fn test_multi_producer() {
-        let result = multi_producer(3);
-        assert_eq!(
-            result,
-            vec![
-                "msg from 0".to_string(),
-                "msg from 1".to_string(),
-                "msg from 2".to_string(),
-            ]
-        );
+        run_with_timeout(TIMEOUT, || {
+            let result = multi_producer(3);
+            assert_eq_with_hint!(
+                result,
+                vec![
+                    "msg from 0".to_string(),
+                    "msg from 1".to_string(),
+                    "msg from 2".to_string(),
+                ]
+            );
+        });
     }
 
     #[test]
     fn test_multi_producer_single() {
-        let result = multi_producer(1);
-        assert_eq!(result, vec!["msg from 0".to_string()]);
+        run_with_timeout(TIMEOUT, || {
+            let result = multi_producer(1);
+            assert_eq!(result, vec!["msg from 0".to_string()]);
+        });
     }
 }
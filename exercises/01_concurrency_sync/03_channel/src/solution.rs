@@ -0,0 +1,42 @@
+//! # Channel Communication
+//!
+//! In this exercise, you will use `std::sync::mpsc` channels to pass messages between threads.
+//!
+//! ## Concepts
+//! - `mpsc::channel()` creates a multiple producer, single consumer channel
+//! - `Sender::send()` sends a message
+//! - `Receiver::recv()` receives a message
+//! - Multiple producers can be created via `Sender::clone()`
+
+use std::sync::mpsc;
+use std::thread;
+
+/// Create a producer thread that sends each element from items into the channel.
+/// The main thread receives all messages and returns them.
+pub fn simple_send_recv(items: Vec<String>) -> Vec<String> {
+    let (tx, rx) = mpsc::channel();
+    let producer = thread::spawn(move || {
+        for item in items {
+            tx.send(item).unwrap();
+        }
+    });
+    let received = rx.iter().collect();
+    producer.join().unwrap();
+    received
+}
+
+/// Create `n_producers` producer threads, each sending a message in format `"msg from {id}"`.
+/// Collect all messages, sort them lexicographically, and return.
+///
+/// Hint: Use `tx.clone()` to create multiple senders. Note that the original tx must also be dropped.
+pub fn multi_producer(n_producers: usize) -> Vec<String> {
+    let (tx, rx) = mpsc::channel();
+    for id in 0..n_producers {
+        let tx = tx.clone();
+        thread::spawn(move || tx.send(format!("msg from {id}")).unwrap());
+    }
+    drop(tx);
+    let mut msgs: Vec<String> = rx.iter().collect();
+    msgs.sort();
+    msgs
+}
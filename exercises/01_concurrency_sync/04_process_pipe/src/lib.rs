@@ -170,9 +170,36 @@ pub fn pipe_through_grep(pattern: &str, input: &str) -> String {
     todo!()
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_pipe_cat",
+            "drop the child's stdin before reading stdout: cat only exits (and flushes) once it sees EOF",
+        ),
+        (
+            "test_exit_code_failure",
+            "run the command through `sh -c` and return status.code(), not just whether it succeeded",
+        ),
+        (
+            "test_run_command_with_result_nonexistent",
+            "propagate the io::Error of .output() with `?` instead of unwrapping it",
+        ),
+        (
+            "test_pipe_through_grep_no_match",
+            "grep exits with status 1 when nothing matches: that is an empty result, not an error",
+        ),
+    ];
 
     #[test]
     fn test_run_echo() {
@@ -189,7 +216,7 @@ mod tests {
     #[test]
     fn test_pipe_cat() {
         let output = pipe_through_cat("hello pipe!");
-        assert_eq!(output, "hello pipe!");
+        assert_eq_with_hint!(output, "hello pipe!");
     }
 
     #[test]
@@ -205,7 +232,7 @@ mod tests {
 
     #[test]
     fn test_exit_code_failure() {
-        assert_eq!(get_exit_code("false"), 1);
+        assert_eq_with_hint!(get_exit_code("false"), 1);
     }
 
     #[test]
@@ -219,7 +246,7 @@ mod tests {
     fn test_run_command_with_result_nonexistent() {
         let result = run_command_with_result("nonexistent_command_xyz", &[]);
         // Should be an error because command not found
-        assert!(result.is_err());
+        assert_with_hint!(result.is_err());
     }
 
     #[test]
@@ -235,7 +262,7 @@ mod tests {
         let input = "apple\nbanana\ncherry\n";
         let output = pipe_through_grep("z", input);
         // No lines match -> empty string
-        assert_eq!(output, "");
+        assert_eq_with_hint!(output, "");
     }
 
     #[test]
@@ -0,0 +1,230 @@
+//! # Parallel Map/Reduce
+//!
+//! In this exercise, you will build a tiny version of what `rayon`'s `par_iter().map().reduce()`
+//! does: split a slice into chunks, map and reduce each chunk on its own scoped thread, then
+//! combine the per-chunk results.
+//!
+//! ## Concepts
+//! - `thread::scope` lets worker threads borrow the input slice and the closures
+//! - Splitting work into balanced chunks so no thread finishes long after the others
+//! - `reduce_fn` only has to be associative: the partial results are combined in chunk order,
+//!   so the answer equals the sequential `iter().map(map_fn).reduce(reduce_fn)` even when
+//!   `reduce_fn` is not commutative (string concatenation, for example)
+
+use std::ops::Range;
+#[allow(unused_imports)]
+use std::thread;
+
+/// Split `0..len` into at most `n_parts` contiguous, non-empty ranges whose lengths differ by
+/// at most one. The longer ranges come first.
+///
+/// Example: `split_evenly(10, 4)` is `[0..3, 3..6, 6..8, 8..10]`; `split_evenly(2, 4)` is
+/// `[0..1, 1..2]`; `split_evenly(0, 4)` is empty.
+///
+/// Panics if `n_parts == 0`.
+#[allow(unused_variables)]
+pub fn split_evenly(len: usize, n_parts: usize) -> Vec<Range<usize>> {
+    // TODO: assert n_parts > 0
+    // TODO: parts = min(n_parts, len); each gets len / parts items, and the first
+    // len % parts of them get one more
+    // TODO: Build the ranges back to back, starting at 0
+    todo!()
+}
+
+/// Map every element with `map_fn` and fold the results with `reduce_fn`, using up to
+/// `n_threads` threads. Returns `None` for an empty slice.
+///
+/// The result must equal `data.iter().map(map_fn).reduce(reduce_fn)` for any associative
+/// `reduce_fn`.
+///
+/// Panics if `n_threads == 0`.
+///
+/// Hint: `thread::scope`, one `s.spawn` per `split_evenly` range, then join the handles in order.
+#[allow(unused_variables)]
+pub fn parallel_map_reduce<T, R, M, F>(
+    data: &[T],
+    map_fn: M,
+    reduce_fn: F,
+    n_threads: usize,
+) -> Option<R>
+where
+    T: Sync,
+    R: Send,
+    M: Fn(&T) -> R + Sync,
+    F: Fn(R, R) -> R + Sync,
+{
+    // TODO: assert n_threads > 0
+    // TODO: Inside thread::scope, spawn a thread per split_evenly(data.len(), n_threads) range;
+    // each returns data[range].iter().map(&map_fn).reduce(&reduce_fn)
+    // TODO: Join the handles in order and reduce their results with reduce_fn
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    const HINTS: Hints = &[
+        (
+            "test_split_evenly_balanced",
+            "give every part len / parts items and the first len % parts parts one extra",
+        ),
+        (
+            "test_split_evenly_fewer_items_than_parts",
+            "use min(n_parts, len) parts so that no range is empty",
+        ),
+        (
+            "test_matches_sequential",
+            "reduce each chunk, then reduce the partial results: both steps use reduce_fn",
+        ),
+        (
+            "test_keeps_order_for_non_commutative_reduce",
+            "join the handles in spawn order and combine acc = reduce_fn(acc, next), never the other way round",
+        ),
+        (
+            "test_uses_n_threads",
+            "spawn one scoped thread per range; do not map everything on the calling thread",
+        ),
+        (
+            "test_speedup_on_large_input",
+            "spawn every chunk's thread before joining any of them, or the chunks run one after another",
+        ),
+    ];
+
+    #[test]
+    fn test_split_evenly_exact() {
+        assert_eq!(split_evenly(8, 4), vec![0..2, 2..4, 4..6, 6..8]);
+    }
+
+    #[test]
+    fn test_split_evenly_balanced() {
+        assert_eq_with_hint!(split_evenly(10, 4), vec![0..3, 3..6, 6..8, 8..10]);
+        for len in 0..50 {
+            for n in 1..9 {
+                let parts = split_evenly(len, n);
+                let sizes: Vec<usize> = parts.iter().map(|r| r.len()).collect();
+                assert_with_hint!(
+                    sizes.iter().max().unwrap_or(&0) - sizes.iter().min().unwrap_or(&0) <= 1,
+                    "split_evenly({len}, {n}) = {parts:?}"
+                );
+                assert_eq!(sizes.iter().sum::<usize>(), len);
+                assert!(parts.windows(2).all(|w| w[0].end == w[1].start));
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_evenly_fewer_items_than_parts() {
+        assert_eq_with_hint!(split_evenly(2, 4), vec![0..1, 1..2]);
+        assert_eq_with_hint!(split_evenly(0, 4), vec![]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_evenly_zero_parts() {
+        split_evenly(10, 0);
+    }
+
+    #[test]
+    fn test_matches_sequential() {
+        let data: Vec<u64> = (1..=10_000).collect();
+        let expected = data.iter().map(|x| x * x % 1_000).reduce(|a, b| a + b);
+        for n in [1, 2, 3, 4, 7, 16] {
+            let got = parallel_map_reduce(&data, |x| x * x % 1_000, |a, b| a + b, n);
+            assert_eq_with_hint!(got, expected);
+        }
+        let max = parallel_map_reduce(&data, |&x| (x * 7_919) % 10_007, u64::max, 4);
+        assert_eq!(max, data.iter().map(|&x| (x * 7_919) % 10_007).max());
+    }
+
+    #[test]
+    fn test_keeps_order_for_non_commutative_reduce() {
+        let words: Vec<String> = (0..26).map(|i| ((b'a' + i) as char).to_string()).collect();
+        let joined = parallel_map_reduce(&words, |w| w.clone(), |a, b| a + &b, 5);
+        assert_eq_with_hint!(joined.as_deref(), Some("abcdefghijklmnopqrstuvwxyz"));
+    }
+
+    #[test]
+    fn test_empty_and_tiny_inputs() {
+        let empty: [i32; 0] = [];
+        assert_eq!(parallel_map_reduce(&empty, |x| *x, |a, b| a + b, 4), None);
+        assert_eq!(
+            parallel_map_reduce(&[5], |x| x * 2, |a, b| a + b, 4),
+            Some(10)
+        );
+        assert_eq!(
+            parallel_map_reduce(&[1, 2], |x| *x, |a, b| a + b, 8),
+            Some(3)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_threads_panics() {
+        parallel_map_reduce(&[1, 2, 3], |x| *x, |a, b| a + b, 0);
+    }
+
+    #[test]
+    fn test_uses_n_threads() {
+        let caller = std::thread::current().id();
+        let seen = Mutex::new(HashSet::new());
+        let data: Vec<u32> = (0..100).collect();
+        parallel_map_reduce(
+            &data,
+            |x| {
+                seen.lock().unwrap().insert(std::thread::current().id());
+                *x
+            },
+            |a, b| a + b,
+            4,
+        );
+        let seen = seen.into_inner().unwrap();
+        assert_eq_with_hint!(seen.len(), 4);
+        assert_with_hint!(!seen.contains(&caller));
+    }
+
+    /// Busy work the optimizer cannot skip.
+    fn spin(x: &u64) -> u64 {
+        let mut h = *x;
+        for _ in 0..2_000 {
+            h = std::hint::black_box(h.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1));
+        }
+        h >> 60
+    }
+
+    #[test]
+    fn test_speedup_on_large_input() {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        if cores < 2 {
+            eprintln!("skipping: only one CPU available");
+            return;
+        }
+        let threads = cores.min(4);
+        let data: Vec<u64> = (0..20_000).collect();
+        let time = |n| {
+            let start = Instant::now();
+            let sum = parallel_map_reduce(&data, spin, |a, b| a + b, n);
+            (sum, start.elapsed())
+        };
+        let (seq_sum, seq) = time(1);
+        let (par_sum, par) = time(threads);
+        assert_eq!(par_sum, seq_sum);
+        // Ideal is seq / threads; allow plenty of slack for a busy machine.
+        let bound = seq.mul_f64(0.8) + Duration::from_millis(5);
+        assert_with_hint!(
+            par < bound,
+            "{threads} threads took {par:?}, 1 thread took {seq:?}"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! # Parallel Map/Reduce
+//!
+//! In this exercise, you will build a tiny version of what `rayon`'s `par_iter().map().reduce()`
+//! does: split a slice into chunks, map and reduce each chunk on its own scoped thread, then
+//! combine the per-chunk results.
+//!
+//! ## Concepts
+//! - `thread::scope` lets worker threads borrow the input slice and the closures
+//! - Splitting work into balanced chunks so no thread finishes long after the others
+//! - `reduce_fn` only has to be associative: the partial results are combined in chunk order,
+//!   so the answer equals the sequential `iter().map(map_fn).reduce(reduce_fn)` even when
+//!   `reduce_fn` is not commutative (string concatenation, for example)
+
+use std::ops::Range;
+use std::thread;
+
+/// Split `0..len` into at most `n_parts` contiguous, non-empty ranges whose lengths differ by
+/// at most one. The longer ranges come first.
+///
+/// Example: `split_evenly(10, 4)` is `[0..3, 3..6, 6..8, 8..10]`; `split_evenly(2, 4)` is
+/// `[0..1, 1..2]`; `split_evenly(0, 4)` is empty.
+///
+/// Panics if `n_parts == 0`.
+pub fn split_evenly(len: usize, n_parts: usize) -> Vec<Range<usize>> {
+    assert!(n_parts > 0, "n_parts must be positive");
+    let parts = n_parts.min(len);
+    let mut ranges = Vec::with_capacity(parts);
+    let mut start = 0;
+    for i in 0..parts {
+        let size = len / parts + usize::from(i < len % parts);
+        ranges.push(start..start + size);
+        start += size;
+    }
+    ranges
+}
+
+/// Map every element with `map_fn` and fold the results with `reduce_fn`, using up to
+/// `n_threads` threads. Returns `None` for an empty slice.
+///
+/// The result must equal `data.iter().map(map_fn).reduce(reduce_fn)` for any associative
+/// `reduce_fn`.
+///
+/// Panics if `n_threads == 0`.
+///
+/// Hint: `thread::scope`, one `s.spawn` per `split_evenly` range, then join the handles in order.
+pub fn parallel_map_reduce<T, R, M, F>(
+    data: &[T],
+    map_fn: M,
+    reduce_fn: F,
+    n_threads: usize,
+) -> Option<R>
+where
+    T: Sync,
+    R: Send,
+    M: Fn(&T) -> R + Sync,
+    F: Fn(R, R) -> R + Sync,
+{
+    assert!(n_threads > 0, "n_threads must be positive");
+    let (map_fn, reduce_fn) = (&map_fn, &reduce_fn);
+    thread::scope(|s| {
+        let handles: Vec<_> = split_evenly(data.len(), n_threads)
+            .into_iter()
+            .map(|range| s.spawn(move || data[range].iter().map(map_fn).reduce(reduce_fn)))
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|h| h.join().unwrap())
+            .reduce(reduce_fn)
+    })
+}
@@ -0,0 +1,396 @@
+//! # Bounded Blocking Queue
+//!
+//! In this exercise, you will build the queue behind every thread pool and pipeline stage: a
+//! FIFO with a fixed capacity, shared by any number of producer and consumer threads.
+//!
+//! ## Concepts
+//! - `Condvar::wait_while` sleeps until a condition on the `Mutex`-protected state holds
+//! - Two condition variables, one per reason to wait: `not_empty` for consumers, `not_full` for
+//!   producers. Each side notifies the *other* side's condvar after changing the queue
+//! - Backpressure: when consumers fall behind, `push` blocks, so producers slow down to the
+//!   consumers' pace instead of growing the queue without bound
+//! - Closing: after `close()`, pushes fail (the item is handed back) and pops drain what is left
+//!   before reporting the end. Every blocked thread must be woken to notice it
+//!
+//! ## Semantics
+//! - `push` blocks while the queue is full; `pop` blocks while it is empty.
+//! - `try_push` / `try_pop` never block: they fail with `Full` / `Empty` instead.
+//! - Once closed, `push` and `try_push` fail with the item, including pushes that were blocked
+//!   when `close()` ran. `pop` keeps returning the remaining items, then `None`; `try_pop`
+//!   reports `Closed` only once the queue is closed *and* empty.
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+
+/// `push` on a closed queue; carries the item back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Closed<T>(pub T);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryPushError<T> {
+    /// The queue is at capacity.
+    Full(T),
+    /// The queue was closed.
+    Closed(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryPopError {
+    /// Nothing queued right now.
+    Empty,
+    /// Closed and fully drained: nothing will ever arrive.
+    Closed,
+}
+
+struct State<T> {
+    items: VecDeque<T>,
+    closed: bool,
+}
+
+pub struct BlockingQueue<T> {
+    state: Mutex<State<T>>,
+    capacity: usize,
+    /// Consumers waiting for an item.
+    not_empty: Condvar,
+    /// Producers waiting for space.
+    not_full: Condvar,
+}
+
+impl<T> BlockingQueue<T> {
+    /// An empty queue holding at most `capacity` items. Panics if `capacity == 0`.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            state: Mutex::new(State {
+                items: VecDeque::with_capacity(capacity),
+                closed: false,
+            }),
+            capacity,
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// Append `item`, blocking while the queue is full.
+    ///
+    /// 1. `wait_while` on `not_full` as long as the queue is full and not closed.
+    /// 2. Closed: return `Err(Closed(item))`.
+    /// 3. Push, then `not_empty.notify_one()`.
+    #[allow(unused_variables)]
+    pub fn push(&self, item: T) -> Result<(), Closed<T>> {
+        // TODO: wait_while on not_full (full and not closed), fail with Closed(item) if closed,
+        // otherwise push_back and notify not_empty after dropping the guard
+        todo!()
+    }
+
+    /// Take the oldest item, blocking while the queue is empty. `None` once the queue is closed
+    /// and drained.
+    ///
+    /// 1. `wait_while` on `not_empty` as long as the queue is empty and not closed.
+    /// 2. Pop the front (`None` here means closed and drained).
+    /// 3. Got an item: `not_full.notify_one()`.
+    pub fn pop(&self) -> Option<T> {
+        // TODO: wait_while on not_empty (empty and not closed), pop_front, and notify not_full
+        // if an item was taken
+        todo!()
+    }
+
+    /// `push` without blocking.
+    #[allow(unused_variables)]
+    pub fn try_push(&self, item: T) -> Result<(), TryPushError<T>> {
+        // TODO: Closed before Full; on success notify not_empty
+        todo!()
+    }
+
+    /// `pop` without blocking.
+    pub fn try_pop(&self) -> Result<T, TryPopError> {
+        // TODO: An item if there is one (notify not_full), else Closed or Empty
+        todo!()
+    }
+
+    /// Close the queue and wake every blocked producer and consumer. Idempotent.
+    pub fn close(&self) {
+        // TODO: Set closed, then wake everyone on both condvars
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const HINTS: Hints = &[
+        (
+            "test_try_push_full",
+            "try_push must fail with Full(item) once len() == capacity instead of growing the queue",
+        ),
+        (
+            "test_producer_blocks_when_full",
+            "push must wait on not_full while the queue is full, not return early or grow past capacity",
+        ),
+        (
+            "test_slow_consumer_paces_producer",
+            "after popping, notify not_full so the blocked producer can put in the next item",
+        ),
+        (
+            "test_pop_blocks_until_push",
+            "after pushing, notify not_empty so a blocked consumer wakes up",
+        ),
+        (
+            "test_close_drains_then_ends",
+            "pop returns the queued items after close(); only an empty closed queue yields None",
+        ),
+        (
+            "test_close_wakes_blocked_consumer",
+            "close() must notify_all on not_empty, and pop's wait condition must stop waiting once closed",
+        ),
+        (
+            "test_close_wakes_blocked_producer",
+            "close() must notify_all on not_full too, and a producer woken by close gets Err(Closed(item))",
+        ),
+        (
+            "test_many_producers_many_consumers",
+            "notify the other side's condvar after every successful push/pop, including try_push/try_pop",
+        ),
+    ];
+
+    const SETTLE: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn test_fifo() {
+        let q = BlockingQueue::new(4);
+        for i in 0..4 {
+            q.push(i).unwrap();
+        }
+        assert_eq!(q.len(), 4);
+        assert_eq!(
+            (0..4).map(|_| q.pop().unwrap()).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_capacity_panics() {
+        BlockingQueue::<u8>::new(0);
+    }
+
+    #[test]
+    fn test_try_push_full() {
+        let q = BlockingQueue::new(2);
+        assert_eq!(q.try_push('a'), Ok(()));
+        assert_eq!(q.try_push('b'), Ok(()));
+        assert_eq_with_hint!(q.try_push('c'), Err(TryPushError::Full('c')));
+        assert_eq!(q.len(), 2);
+    }
+
+    #[test]
+    fn test_try_pop_empty() {
+        let q = BlockingQueue::new(2);
+        assert_eq!(q.try_pop(), Err(TryPopError::Empty));
+        q.try_push(7).unwrap();
+        assert_eq!(q.try_pop(), Ok(7));
+        assert_eq!(q.try_pop(), Err(TryPopError::Empty));
+    }
+
+    #[test]
+    fn test_producer_blocks_when_full() {
+        let q = Arc::new(BlockingQueue::new(2));
+        let pushed = Arc::new(AtomicUsize::new(0));
+        let producer = {
+            let (q, pushed) = (q.clone(), pushed.clone());
+            thread::spawn(move || {
+                for i in 0..5 {
+                    q.push(i).unwrap();
+                    pushed.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        };
+        thread::sleep(SETTLE);
+        assert_eq_with_hint!(pushed.load(Ordering::SeqCst), 2);
+        assert_eq_with_hint!(q.len(), 2);
+        assert!(!producer.is_finished());
+        for i in 0..5 {
+            assert_eq!(q.pop(), Some(i));
+        }
+        producer.join().unwrap();
+        assert_eq!(pushed.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_slow_consumer_paces_producer() {
+        const ITEMS: usize = 10;
+        const CONSUME: Duration = Duration::from_millis(10);
+        let q = Arc::new(BlockingQueue::new(2));
+        let consumer = {
+            let q = q.clone();
+            thread::spawn(move || {
+                let mut got = Vec::new();
+                let mut max_len = 0;
+                while let Some(i) = q.pop() {
+                    max_len = max_len.max(q.len() + 1);
+                    thread::sleep(CONSUME);
+                    got.push(i);
+                }
+                (got, max_len)
+            })
+        };
+        let start = Instant::now();
+        for i in 0..ITEMS {
+            q.push(i).unwrap();
+        }
+        let producer_time = start.elapsed();
+        q.close();
+        let (got, max_len) = consumer.join().unwrap();
+        assert_eq_with_hint!(got, (0..ITEMS).collect::<Vec<_>>());
+        assert!(max_len <= 2, "queue grew to {max_len}");
+        // Item k goes in only once the consumer took item k - 2, so the last push waits about
+        // (ITEMS - 3) * CONSUME; one CONSUME of slack.
+        assert_with_hint!(
+            producer_time >= CONSUME * (ITEMS as u32 - 4),
+            "producer finished after {producer_time:?} without waiting for the consumer"
+        );
+    }
+
+    #[test]
+    fn test_pop_blocks_until_push() {
+        let q = Arc::new(BlockingQueue::new(1));
+        let consumer = {
+            let q = q.clone();
+            thread::spawn(move || q.pop())
+        };
+        thread::sleep(SETTLE);
+        assert!(!consumer.is_finished(), "pop returned on an empty queue");
+        q.push("hello").unwrap();
+        assert_eq_with_hint!(consumer.join().unwrap(), Some("hello"));
+    }
+
+    #[test]
+    fn test_close_drains_then_ends() {
+        let q = BlockingQueue::new(3);
+        q.push(1).unwrap();
+        q.push(2).unwrap();
+        q.close();
+        assert!(q.is_closed());
+        assert_eq!(q.push(3), Err(Closed(3)));
+        assert_eq!(q.try_push(3), Err(TryPushError::Closed(3)));
+        assert_eq_with_hint!(q.pop(), Some(1));
+        assert_eq_with_hint!(q.try_pop(), Ok(2));
+        assert_eq_with_hint!(q.pop(), None);
+        assert_eq!(q.try_pop(), Err(TryPopError::Closed));
+        q.close();
+    }
+
+    #[test]
+    fn test_close_wakes_blocked_consumer() {
+        let q = Arc::new(BlockingQueue::<u32>::new(1));
+        let consumers: Vec<_> = (0..3)
+            .map(|_| {
+                let q = q.clone();
+                thread::spawn(move || q.pop())
+            })
+            .collect();
+        thread::sleep(SETTLE);
+        q.close();
+        for c in consumers {
+            assert_eq_with_hint!(c.join().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_close_wakes_blocked_producer() {
+        let q = Arc::new(BlockingQueue::new(1));
+        q.push(0).unwrap();
+        let producers: Vec<_> = (1..4)
+            .map(|i| {
+                let q = q.clone();
+                thread::spawn(move || q.push(i))
+            })
+            .collect();
+        thread::sleep(SETTLE);
+        q.close();
+        let mut results: Vec<_> = producers.into_iter().map(|p| p.join().unwrap()).collect();
+        results.sort_by_key(|r| r.as_ref().err().map(|c| c.0));
+        assert_eq_with_hint!(
+            results,
+            vec![Err(Closed(1)), Err(Closed(2)), Err(Closed(3))]
+        );
+        assert_eq!(q.pop(), Some(0));
+    }
+
+    #[test]
+    fn test_many_producers_many_consumers() {
+        const PER_PRODUCER: usize = 1_000;
+        let q = Arc::new(BlockingQueue::new(8));
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let q = q.clone();
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        let item = p * PER_PRODUCER + i;
+                        if i % 2 == 0 {
+                            q.push(item).unwrap();
+                        } else {
+                            let mut item = item;
+                            while let Err(TryPushError::Full(back)) = q.try_push(item) {
+                                item = back;
+                                thread::yield_now();
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        let consumers: Vec<_> = (0..4)
+            .map(|_| {
+                let q = q.clone();
+                thread::spawn(move || {
+                    let mut got = Vec::new();
+                    while let Some(i) = q.pop() {
+                        got.push(i);
+                    }
+                    got
+                })
+            })
+            .collect();
+        for p in producers {
+            p.join().unwrap();
+        }
+        q.close();
+        let mut all: Vec<usize> = consumers
+            .into_iter()
+            .flat_map(|c| c.join().unwrap())
+            .collect();
+        all.sort();
+        assert_eq_with_hint!(all, (0..4 * PER_PRODUCER).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,167 @@
+//! # Bounded Blocking Queue
+//!
+//! In this exercise, you will build the queue behind every thread pool and pipeline stage: a
+//! FIFO with a fixed capacity, shared by any number of producer and consumer threads.
+//!
+//! ## Concepts
+//! - `Condvar::wait_while` sleeps until a condition on the `Mutex`-protected state holds
+//! - Two condition variables, one per reason to wait: `not_empty` for consumers, `not_full` for
+//!   producers. Each side notifies the *other* side's condvar after changing the queue
+//! - Backpressure: when consumers fall behind, `push` blocks, so producers slow down to the
+//!   consumers' pace instead of growing the queue without bound
+//! - Closing: after `close()`, pushes fail (the item is handed back) and pops drain what is left
+//!   before reporting the end. Every blocked thread must be woken to notice it
+//!
+//! ## Semantics
+//! - `push` blocks while the queue is full; `pop` blocks while it is empty.
+//! - `try_push` / `try_pop` never block: they fail with `Full` / `Empty` instead.
+//! - Once closed, `push` and `try_push` fail with the item, including pushes that were blocked
+//!   when `close()` ran. `pop` keeps returning the remaining items, then `None`; `try_pop`
+//!   reports `Closed` only once the queue is closed *and* empty.
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+
+/// `push` on a closed queue; carries the item back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Closed<T>(pub T);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryPushError<T> {
+    /// The queue is at capacity.
+    Full(T),
+    /// The queue was closed.
+    Closed(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryPopError {
+    /// Nothing queued right now.
+    Empty,
+    /// Closed and fully drained: nothing will ever arrive.
+    Closed,
+}
+
+struct State<T> {
+    items: VecDeque<T>,
+    closed: bool,
+}
+
+pub struct BlockingQueue<T> {
+    state: Mutex<State<T>>,
+    capacity: usize,
+    /// Consumers waiting for an item.
+    not_empty: Condvar,
+    /// Producers waiting for space.
+    not_full: Condvar,
+}
+
+impl<T> BlockingQueue<T> {
+    /// An empty queue holding at most `capacity` items. Panics if `capacity == 0`.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            state: Mutex::new(State {
+                items: VecDeque::with_capacity(capacity),
+                closed: false,
+            }),
+            capacity,
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// Append `item`, blocking while the queue is full.
+    ///
+    /// 1. `wait_while` on `not_full` as long as the queue is full and not closed.
+    /// 2. Closed: return `Err(Closed(item))`.
+    /// 3. Push, then `not_empty.notify_one()`.
+    pub fn push(&self, item: T) -> Result<(), Closed<T>> {
+        let mut st = self
+            .not_full
+            .wait_while(self.state.lock().unwrap(), |st| {
+                st.items.len() == self.capacity && !st.closed
+            })
+            .unwrap();
+        if st.closed {
+            return Err(Closed(item));
+        }
+        st.items.push_back(item);
+        drop(st);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Take the oldest item, blocking while the queue is empty. `None` once the queue is closed
+    /// and drained.
+    ///
+    /// 1. `wait_while` on `not_empty` as long as the queue is empty and not closed.
+    /// 2. Pop the front (`None` here means closed and drained).
+    /// 3. Got an item: `not_full.notify_one()`.
+    pub fn pop(&self) -> Option<T> {
+        let mut st = self
+            .not_empty
+            .wait_while(self.state.lock().unwrap(), |st| {
+                st.items.is_empty() && !st.closed
+            })
+            .unwrap();
+        let item = st.items.pop_front();
+        drop(st);
+        if item.is_some() {
+            self.not_full.notify_one();
+        }
+        item
+    }
+
+    /// `push` without blocking.
+    pub fn try_push(&self, item: T) -> Result<(), TryPushError<T>> {
+        let mut st = self.state.lock().unwrap();
+        if st.closed {
+            return Err(TryPushError::Closed(item));
+        }
+        if st.items.len() == self.capacity {
+            return Err(TryPushError::Full(item));
+        }
+        st.items.push_back(item);
+        drop(st);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// `pop` without blocking.
+    pub fn try_pop(&self) -> Result<T, TryPopError> {
+        let mut st = self.state.lock().unwrap();
+        match st.items.pop_front() {
+            Some(item) => {
+                drop(st);
+                self.not_full.notify_one();
+                Ok(item)
+            }
+            None if st.closed => Err(TryPopError::Closed),
+            None => Err(TryPopError::Empty),
+        }
+    }
+
+    /// Close the queue and wake every blocked producer and consumer. Idempotent.
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
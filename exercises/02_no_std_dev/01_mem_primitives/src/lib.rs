@@ -11,6 +11,11 @@
 //! - Do not call `core::ptr::copy`, `core::ptr::copy_nonoverlapping`, etc. (write your own loops)
 //! - Handle edge cases correctly (n=0, overlapping memory regions, etc.)
 //! - Pass all tests
+//!
+//! Besides the examples, the tests fuzz each function against the standard library on random
+//! buffers (random lengths, every overlap direction, bytes above 0x7f), with guard bytes around
+//! the destination to catch writes past the end. The generator is seeded, so a failure names its
+//! case and reproduces on every run.
 
 // Force no_std in production; allow std in tests (cargo test framework requires it)
 #![cfg_attr(not(test), no_std)]
@@ -83,9 +88,36 @@ pub unsafe extern "C" fn my_strcmp(s1: *const u8, s2: *const u8) -> i32 {
 // ============================================================
 // Tests (std is available under #[cfg(test)])
 // ============================================================
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_memset_basic",
+            "write the byte `c as u8` to every one of the n bytes, and return dest",
+        ),
+        (
+            "test_memmove_overlap_forward",
+            "when dest lies above src the ranges overlap the wrong way for a forward copy: copy from the last byte down",
+        ),
+        (
+            "test_strlen_empty",
+            "count bytes before the first NUL; an empty string is just the NUL, so the length is 0",
+        ),
+        (
+            "test_strcmp_less",
+            "return the difference of the first differing bytes (as unsigned), and stop at the NUL of either string",
+        ),
+    ];
 
     #[test]
     fn test_memcpy_basic() {
@@ -107,7 +139,7 @@ mod tests {
     fn test_memset_basic() {
         let mut buf = [0u8; 8];
         unsafe { my_memset(buf.as_mut_ptr(), 0xAB, 8) };
-        assert!(buf.iter().all(|&b| b == 0xAB));
+        assert_with_hint!(buf.iter().all(|&b| b == 0xAB));
     }
 
     #[test]
@@ -131,7 +163,7 @@ mod tests {
         // Copy buf[0..4] to buf[1..5], shifting right by 1
         let mut buf = [1u8, 2, 3, 4, 5];
         unsafe { my_memmove(buf.as_mut_ptr().add(1), buf.as_ptr(), 4) };
-        assert_eq!(buf, [1, 1, 2, 3, 4]);
+        assert_eq_with_hint!(buf, [1, 1, 2, 3, 4]);
     }
 
     #[test]
@@ -143,7 +175,7 @@ mod tests {
     #[test]
     fn test_strlen_empty() {
         let s = b"\0";
-        assert_eq!(unsafe { my_strlen(s.as_ptr()) }, 0);
+        assert_eq_with_hint!(unsafe { my_strlen(s.as_ptr()) }, 0);
     }
 
     #[test]
@@ -157,7 +189,7 @@ mod tests {
     fn test_strcmp_less() {
         let a = b"abc\0";
         let b = b"abd\0";
-        assert!(unsafe { my_strcmp(a.as_ptr(), b.as_ptr()) } < 0);
+        assert_with_hint!(unsafe { my_strcmp(a.as_ptr(), b.as_ptr()) } < 0);
     }
 
     #[test]
@@ -166,4 +198,137 @@ mod tests {
         let b = b"abc\0";
         assert!(unsafe { my_strcmp(a.as_ptr(), b.as_ptr()) } > 0);
     }
+
+    // --------------------------------------------------------
+    // Pseudo-fuzzing against std reference behaviour
+    // --------------------------------------------------------
+
+    const CASES: usize = if cfg!(miri) { 20 } else { 2000 };
+    const GUARD: usize = 16;
+    const GUARD_BYTE: u8 = 0xA5;
+
+    /// xorshift64*: deterministic, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    /// A buffer of `len` random bytes between two guard zones; returns it and the data offset.
+    fn guarded(rng: &mut Rng, len: usize) -> (Vec<u8>, usize) {
+        let mut buf = vec![GUARD_BYTE; GUARD];
+        buf.extend(rng.bytes(len));
+        buf.extend([GUARD_BYTE; GUARD]);
+        (buf, GUARD)
+    }
+
+    fn assert_guards(buf: &[u8], case: usize) {
+        let (head, tail) = (&buf[..GUARD], &buf[buf.len() - GUARD..]);
+        assert!(
+            head.iter().chain(tail).all(|&b| b == GUARD_BYTE),
+            "case {case}: wrote outside the buffer"
+        );
+    }
+
+    #[test]
+    fn fuzz_memcpy_and_memset() {
+        let mut rng = Rng(0x1234_5678);
+        for case in 0..CASES {
+            let n = rng.below(300);
+            let src = rng.bytes(n);
+            let (mut dst, off) = guarded(&mut rng, n);
+            let ret = unsafe { my_memcpy(dst.as_mut_ptr().add(off), src.as_ptr(), n) };
+            assert_eq!(ret, unsafe { dst.as_mut_ptr().add(off) }, "case {case}");
+            assert_eq!(&dst[off..off + n], &src[..], "case {case}: memcpy n={n}");
+            assert_guards(&dst, case);
+
+            let c = rng.next() as u8;
+            unsafe { my_memset(dst.as_mut_ptr().add(off), c, n) };
+            assert!(
+                dst[off..off + n].iter().all(|&b| b == c),
+                "case {case}: memset"
+            );
+            assert_guards(&dst, case);
+        }
+    }
+
+    #[test]
+    fn fuzz_memmove_overlapping() {
+        let mut rng = Rng(0x9E37_79B9);
+        for case in 0..CASES {
+            let len = 1 + rng.below(200);
+            let (mut buf, off) = guarded(&mut rng, len);
+            // Any src/dst pair inside the buffer: forward, backward, equal, disjoint.
+            let src = rng.below(len);
+            let dst = rng.below(len);
+            let n = rng.below(len - src.max(dst) + 1);
+            let mut want = buf.clone();
+            want.copy_within(off + src..off + src + n, off + dst);
+
+            let base = buf.as_mut_ptr();
+            unsafe { my_memmove(base.add(off + dst), base.add(off + src), n) };
+            assert_eq!(buf, want, "case {case}: memmove src={src} dst={dst} n={n}");
+            assert_guards(&buf, case);
+        }
+    }
+
+    #[test]
+    fn fuzz_strlen() {
+        let mut rng = Rng(0xDEAD_BEEF);
+        for case in 0..CASES {
+            let len = rng.below(100);
+            // Non-zero bytes (including >= 0x80), the terminator, then junk that must be ignored.
+            let mut s: Vec<u8> = (0..len).map(|_| 1 + rng.below(255) as u8).collect();
+            s.push(0);
+            s.extend(rng.bytes(8));
+            assert_eq!(unsafe { my_strlen(s.as_ptr()) }, len, "case {case}: {s:?}");
+        }
+    }
+
+    #[test]
+    fn fuzz_strcmp() {
+        let mut rng = Rng(0xC0FF_EE00);
+        let cstr = |rng: &mut Rng, prefix: &[u8]| {
+            let mut s = prefix.to_vec();
+            s.extend((0..rng.below(6)).map(|_| match rng.below(3) {
+                0 => b'a' + rng.below(3) as u8,
+                1 => 0x80 + rng.below(128) as u8,
+                _ => 1 + rng.below(255) as u8,
+            }));
+            s
+        };
+        for case in 0..CASES {
+            // A shared prefix makes the interesting comparisons (first difference, one string a
+            // prefix of the other) common.
+            let prefix: Vec<u8> = (0..rng.below(10))
+                .map(|_| b'a' + rng.below(26) as u8)
+                .collect();
+            let a = cstr(&mut rng, &prefix);
+            let b = if rng.below(4) == 0 {
+                a.clone()
+            } else {
+                cstr(&mut rng, &prefix)
+            };
+            // C compares as `unsigned char`, which is what `Ord` on `[u8]` does.
+            let want = a.cmp(&b) as i32;
+            let (mut za, mut zb) = (a.clone(), b.clone());
+            za.push(0);
+            zb.push(0);
+            let got = unsafe { my_strcmp(za.as_ptr(), zb.as_ptr()) }.signum();
+            assert_eq!(got, want, "case {case}: {a:?} vs {b:?}");
+        }
+    }
 }
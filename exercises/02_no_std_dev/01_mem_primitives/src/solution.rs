@@ -0,0 +1,107 @@
+//! # no_std Memory Primitives
+//!
+//! In a `#![no_std]` environment, you have no standard library — only `core`.
+//! These memory operation functions are the most fundamental building blocks in an OS kernel.
+//! Functions like memcpy/memset in libc must be implemented by ourselves in bare-metal environments.
+//!
+//! ## Task
+//!
+//! Implement the following five functions:
+//! - Only use the `core` crate, no `std`
+//! - Do not call `core::ptr::copy`, `core::ptr::copy_nonoverlapping`, etc. (write your own loops)
+//! - Handle edge cases correctly (n=0, overlapping memory regions, etc.)
+//! - Pass all tests
+//!
+//! Besides the examples, the tests fuzz each function against the standard library on random
+//! buffers (random lengths, every overlap direction, bytes above 0x7f), with guard bytes around
+//! the destination to catch writes past the end. The generator is seeded, so a failure names its
+//! case and reproduces on every run.
+
+// Force no_std in production; allow std in tests (cargo test framework requires it)
+#![allow(unused_variables)]
+
+/// Copy `n` bytes from `src` to `dst`.
+///
+/// - `dst` and `src` must not overlap (use `my_memmove` for overlapping regions)
+/// - Returns `dst`
+///
+/// # Safety
+/// `dst` and `src` must each point to at least `n` bytes of valid memory.
+pub unsafe extern "C" fn my_memcpy(dst: *mut u8, src: *const u8, n: usize) -> *mut u8 {
+    let mut i = 0;
+    while i < n {
+        *dst.add(i) = *src.add(i);
+        i += 1;
+    }
+    dst
+}
+
+/// Set `n` bytes starting at `dst` to the value `c`.
+///
+/// Returns `dst`.
+///
+/// # Safety
+/// `dst` must point to at least `n` bytes of valid writable memory.
+pub unsafe extern "C" fn my_memset(dst: *mut u8, c: u8, n: usize) -> *mut u8 {
+    let mut i = 0;
+    while i < n {
+        *dst.add(i) = c;
+        i += 1;
+    }
+    dst
+}
+
+/// Copy `n` bytes from `src` to `dst`, correctly handling overlapping memory.
+///
+/// Returns `dst`.
+///
+/// # Safety
+/// `dst` and `src` must each point to at least `n` bytes of valid memory.
+pub unsafe extern "C" fn my_memmove(dst: *mut u8, src: *const u8, n: usize) -> *mut u8 {
+    if (dst as usize) <= (src as usize) {
+        let mut i = 0;
+        while i < n {
+            *dst.add(i) = *src.add(i);
+            i += 1;
+        }
+    } else {
+        let mut i = n;
+        while i > 0 {
+            i -= 1;
+            *dst.add(i) = *src.add(i);
+        }
+    }
+    dst
+}
+
+/// Return the length of a null-terminated byte string, excluding the trailing null.
+///
+/// # Safety
+/// `s` must point to a valid null-terminated byte string.
+pub unsafe extern "C" fn my_strlen(s: *const u8) -> usize {
+    let mut n = 0;
+    while *s.add(n) != 0 {
+        n += 1;
+    }
+    n
+}
+
+/// Compare two null-terminated byte strings.
+///
+/// Returns:
+/// - `0`  : strings are equal
+/// - `< 0`: `s1` is lexicographically less than `s2`
+/// - `> 0`: `s1` is lexicographically greater than `s2`
+///
+/// # Safety
+/// `s1` and `s2` must each point to a valid null-terminated byte string.
+pub unsafe extern "C" fn my_strcmp(s1: *const u8, s2: *const u8) -> i32 {
+    let mut i = 0;
+    loop {
+        let (a, b) = (*s1.add(i), *s2.add(i));
+        if a != b || a == 0 {
+            return a as i32 - b as i32;
+        }
+        i += 1;
+    }
+}
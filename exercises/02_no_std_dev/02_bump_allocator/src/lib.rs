@@ -82,12 +82,35 @@ unsafe impl GlobalAlloc for BumpAllocator {
     }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 // ============================================================
 // Tests
 // ============================================================
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_alloc_alignment",
+            "round next up to the layout's alignment first: (next + align - 1) & !(align - 1)",
+        ),
+        (
+            "test_alloc_oom",
+            "return null when aligned start + size would go past heap_end (use checked_add), instead of handing out memory outside the heap",
+        ),
+        (
+            "test_reset",
+            "reset only moves next back to heap_start; the next allocation starts from there again",
+        ),
+    ];
 
     const HEAP_SIZE: usize = 4096;
 
@@ -112,8 +135,8 @@ mod tests {
         for align in [1, 2, 4, 8, 16, 64] {
             let layout = Layout::from_size_align(1, align).unwrap();
             let ptr = unsafe { alloc.alloc(layout) };
-            assert!(!ptr.is_null());
-            assert_eq!(
+            assert_with_hint!(!ptr.is_null());
+            assert_eq_with_hint!(
                 ptr as usize % align,
                 0,
                 "returned address must satisfy align={align}"
@@ -138,7 +161,7 @@ mod tests {
         let (alloc, _heap) = make_allocator();
         let layout = Layout::from_size_align(HEAP_SIZE + 1, 1).unwrap();
         let ptr = unsafe { alloc.alloc(layout) };
-        assert!(ptr.is_null(), "should return null when exceeding heap");
+        assert_with_hint!(ptr.is_null(), "should return null when exceeding heap");
     }
 
     #[test]
@@ -158,12 +181,13 @@ mod tests {
         let (alloc, _heap) = make_allocator();
         let layout = Layout::from_size_align(HEAP_SIZE, 1).unwrap();
         let p1 = unsafe { alloc.alloc(layout) };
-        assert!(!p1.is_null());
+        assert_with_hint!(!p1.is_null());
         alloc.reset();
         let p2 = unsafe { alloc.alloc(layout) };
-        assert!(!p2.is_null(), "should be able to allocate after reset");
-        assert_eq!(
-            p1, p2,
+        assert_with_hint!(!p2.is_null(), "should be able to allocate after reset");
+        assert_eq_with_hint!(
+            p1,
+            p2,
             "address after reset should match the first allocation"
         );
     }
@@ -0,0 +1,84 @@
+//! # Bump Allocator (no_std)
+//!
+//! Implement the simplest heap memory allocator: a Bump Allocator (bump pointer allocator).
+//!
+//! ## How It Works
+//!
+//! A Bump Allocator maintains a pointer `next` to the "next available address".
+//! On each allocation, it aligns `next` to the requested alignment, then advances by `size` bytes.
+//! It does not support freeing individual objects (`dealloc` is a no-op).
+//!
+//! ```text
+//! heap_start                              heap_end
+//! |----[allocated]----[allocated]----| next |---[free]---|
+//!                                        ^
+//!                                    next allocation starts here
+//! ```
+//!
+//! ## Task
+//!
+//! Implement `BumpAllocator`'s `GlobalAlloc::alloc` method:
+//! 1. Align the current `next` up to `layout.align()`
+//!    Hint: `align_up(addr, align) = (addr + align - 1) & !(align - 1)`
+//! 2. Check if the aligned address plus `layout.size()` exceeds `heap_end`
+//! 3. If it exceeds, return `null_mut()`; otherwise atomically update `next` with `compare_exchange`
+//!
+//! ## Key Concepts
+//!
+//! - `core::alloc::{GlobalAlloc, Layout}`
+//! - Memory alignment calculation
+//! - `AtomicUsize` and `compare_exchange` (CAS loop)
+
+pub(crate) use core::alloc::{GlobalAlloc, Layout};
+pub(crate) use core::ptr::null_mut;
+pub(crate) use core::sync::atomic::{AtomicUsize, Ordering};
+
+pub struct BumpAllocator {
+    heap_start: usize,
+    heap_end: usize,
+    next: AtomicUsize,
+}
+
+impl BumpAllocator {
+    /// Create a new BumpAllocator.
+    ///
+    /// # Safety
+    /// `heap_start..heap_end` must be a valid, readable and writable memory region,
+    /// and must not be used by other code during this allocator's lifetime.
+    pub const unsafe fn new(heap_start: usize, heap_end: usize) -> Self {
+        Self {
+            heap_start,
+            heap_end,
+            next: AtomicUsize::new(heap_start),
+        }
+    }
+
+    /// Reset the allocator (free all allocated memory).
+    pub fn reset(&self) {
+        self.next.store(self.heap_start, Ordering::SeqCst);
+    }
+}
+
+unsafe impl GlobalAlloc for BumpAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut current = self.next.load(Ordering::SeqCst);
+        loop {
+            let aligned = (current + layout.align() - 1) & !(layout.align() - 1);
+            let end = match aligned.checked_add(layout.size()) {
+                Some(end) if end <= self.heap_end => end,
+                _ => return null_mut(),
+            };
+            match self
+                .next
+                .compare_exchange(current, end, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => return aligned as *mut u8,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+        // Bump allocator does not reclaim individual objects — leave empty
+    }
+}
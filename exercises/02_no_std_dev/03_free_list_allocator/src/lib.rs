@@ -33,6 +33,13 @@
 //! - Intrusive linked list
 //! - `*mut T` read/write: `ptr.write(val)` / `ptr.read()`
 //! - Memory alignment checks
+//!
+//! ## Testing
+//!
+//! Besides the handwritten tests, `proptest` runs random alloc/dealloc sequences against a shadow
+//! model of the live blocks: every block must be aligned, inside the heap, disjoint from the other
+//! live blocks and keep its contents until freed, and `null` is only allowed when nothing fits.
+//! A failing sequence is shrunk to a minimal one and printed.
 
 #![cfg_attr(not(test), no_std)]
 
@@ -46,6 +53,8 @@ struct FreeBlock {
 }
 
 pub struct FreeListAllocator {
+    /// Pointer to the start of the heap: every pointer handed out is derived from it.
+    base: *mut u8,
     heap_start: usize,
     heap_end: usize,
     /// Bump pointer: unallocated region starts here
@@ -70,9 +79,23 @@ impl FreeListAllocator {
     /// # Safety
     /// `heap_start..heap_end` must be a valid readable and writable memory region.
     pub unsafe fn new(heap_start: usize, heap_end: usize) -> Self {
+        Self::from_ptr(
+            core::ptr::with_exposed_provenance_mut(heap_start),
+            heap_end - heap_start,
+        )
+    }
+
+    /// Like `new`, but keeps the provenance of `heap` (see `ptr_at`). Prefer this one when the
+    /// heap comes from a pointer, as in the tests.
+    ///
+    /// # Safety
+    /// `heap..heap + size` must be a valid readable and writable memory region.
+    pub unsafe fn from_ptr(heap: *mut u8, size: usize) -> Self {
+        let heap_start = heap.addr();
         Self {
+            base: heap,
             heap_start,
-            heap_end,
+            heap_end: heap_start + size,
             bump_next: core::sync::atomic::AtomicUsize::new(heap_start),
             #[cfg(test)]
             free_list: std::sync::Mutex::new(null_mut()),
@@ -81,6 +104,16 @@ impl FreeListAllocator {
         }
     }
 
+    /// The pointer to heap address `addr`.
+    ///
+    /// A pointer is more than an address: it also carries *provenance*, the allocation it may
+    /// access. `addr as *mut u8` has none of its own, which Miri (`cargo miri test`) flags;
+    /// deriving the pointer from the heap's base pointer keeps it.
+    fn ptr_at(&self, addr: usize) -> *mut u8 {
+        debug_assert!(self.heap_start <= addr && addr <= self.heap_end);
+        self.base.with_addr(addr)
+    }
+
     #[cfg(test)]
     fn free_list_head(&self) -> *mut FreeBlock {
         *self.free_list.lock().unwrap()
@@ -118,7 +151,8 @@ unsafe impl GlobalAlloc for FreeListAllocator {
 
         // TODO: Step 2 — no suitable block in free_list, allocate from bump region
         //
-        // Same logic as 02_bump_allocator's alloc
+        // Same logic as 02_bump_allocator's alloc, but return `self.ptr_at(start)`
+        // rather than `start as *mut u8`
         todo!()
     }
 
@@ -135,19 +169,45 @@ unsafe impl GlobalAlloc for FreeListAllocator {
     }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 // ============================================================
 // Tests
 // ============================================================
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_alloc_alignment",
+            "round the bump pointer up with (next + align - 1) & !(align - 1), where align is at least align_of::<FreeBlock>()",
+        ),
+        (
+            "test_dealloc_and_reuse",
+            "dealloc must push the block onto the free list head, and alloc must search the free list before bumping",
+        ),
+        (
+            "test_multiple_alloc_dealloc",
+            "a block taken from the free list must be unlinked, and when no free block fits alloc falls back to the bump region",
+        ),
+        (
+            "test_oom",
+            "return null_mut() when start + size would pass heap_end, never hand out memory past the heap",
+        ),
+    ];
 
     const HEAP_SIZE: usize = 4096;
 
     fn make_allocator() -> (FreeListAllocator, Vec<u8>) {
         let mut heap = vec![0u8; HEAP_SIZE];
-        let start = heap.as_mut_ptr() as usize;
-        let alloc = unsafe { FreeListAllocator::new(start, start + HEAP_SIZE) };
+        let alloc = unsafe { FreeListAllocator::from_ptr(heap.as_mut_ptr(), HEAP_SIZE) };
         (alloc, heap)
     }
 
@@ -165,8 +225,8 @@ mod tests {
         for align in [1, 2, 4, 8, 16] {
             let layout = Layout::from_size_align(8, align).unwrap();
             let ptr = unsafe { alloc.alloc(layout) };
-            assert!(!ptr.is_null());
-            assert_eq!(ptr as usize % align, 0, "align={align}");
+            assert_with_hint!(!ptr.is_null());
+            assert_eq_with_hint!(ptr as usize % align, 0, "align={align}");
         }
     }
 
@@ -176,13 +236,13 @@ mod tests {
         let layout = Layout::from_size_align(64, 8).unwrap();
 
         let p1 = unsafe { alloc.alloc(layout) };
-        assert!(!p1.is_null());
+        assert_with_hint!(!p1.is_null());
 
         // After freeing, the next allocation should reuse the same block
         unsafe { alloc.dealloc(p1, layout) };
         let p2 = unsafe { alloc.alloc(layout) };
-        assert!(!p2.is_null());
-        assert_eq!(p1, p2, "should reuse the freed block");
+        assert_with_hint!(!p2.is_null());
+        assert_eq_with_hint!(p1, p2, "should reuse the freed block");
     }
 
     #[test]
@@ -193,14 +253,14 @@ mod tests {
         let p1 = unsafe { alloc.alloc(layout) };
         let p2 = unsafe { alloc.alloc(layout) };
         let p3 = unsafe { alloc.alloc(layout) };
-        assert!(!p1.is_null() && !p2.is_null() && !p3.is_null());
+        assert_with_hint!(!p1.is_null() && !p2.is_null() && !p3.is_null());
 
         unsafe { alloc.dealloc(p2, layout) };
         unsafe { alloc.dealloc(p1, layout) };
 
         let q1 = unsafe { alloc.alloc(layout) };
         let q2 = unsafe { alloc.alloc(layout) };
-        assert!(!q1.is_null() && !q2.is_null());
+        assert_with_hint!(!q1.is_null() && !q2.is_null());
     }
 
     #[test]
@@ -208,6 +268,163 @@ mod tests {
         let (alloc, _heap) = make_allocator();
         let layout = Layout::from_size_align(HEAP_SIZE + 1, 1).unwrap();
         let ptr = unsafe { alloc.alloc(layout) };
-        assert!(ptr.is_null(), "should return null when exceeding heap");
+        assert_with_hint!(ptr.is_null(), "should return null when exceeding heap");
+    }
+
+    // --------------------------------------------------------
+    // Differential tests: random alloc/dealloc sequences checked
+    // against a shadow model of the live allocations.
+    // --------------------------------------------------------
+
+    use proptest::prelude::*;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Alloc {
+            size: usize,
+            align: usize,
+        },
+        /// Free the live allocation at `index % live.len()`.
+        Free {
+            index: usize,
+        },
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            3 => (1usize..=256, 0u32..=6).prop_map(|(size, p)| Op::Alloc { size, align: 1 << p }),
+            2 => any::<usize>().prop_map(|index| Op::Free { index }),
+        ]
+    }
+
+    /// Bytes an allocation really occupies: at least a `FreeBlock` header, written on dealloc.
+    fn footprint(layout: Layout) -> usize {
+        layout.size().max(core::mem::size_of::<FreeBlock>())
+    }
+
+    /// Whether `layout` fits in some free block or in the bump region: only then may the
+    /// allocator not return null.
+    fn fits_somewhere(alloc: &FreeListAllocator, layout: Layout) -> bool {
+        let size = footprint(layout);
+        let align = layout.align().max(core::mem::align_of::<FreeBlock>());
+        let mut curr = alloc.free_list_head();
+        while !curr.is_null() {
+            unsafe {
+                if curr as usize & (align - 1) == 0 && (*curr).size >= size {
+                    return true;
+                }
+                curr = (*curr).next;
+            }
+        }
+        let next = alloc.bump_next.load(core::sync::atomic::Ordering::SeqCst);
+        let start = (next + align - 1) & !(align - 1);
+        start + size <= alloc.heap_end
+    }
+
+    /// Live allocations: pointer, layout and the byte they were filled with.
+    struct Shadow {
+        live: Vec<(*mut u8, Layout, u8)>,
+        next_tag: u8,
+    }
+
+    impl Shadow {
+        fn check_new(&self, alloc: &FreeListAllocator, ptr: *mut u8, layout: Layout) {
+            let (start, end) = (ptr as usize, ptr as usize + footprint(layout));
+            assert_eq!(start & (layout.align() - 1), 0, "misaligned: {layout:?}");
+            assert!(
+                alloc.heap_start <= start && end <= alloc.heap_end,
+                "{start:#x}..{end:#x} outside the heap"
+            );
+            for &(p, l, _) in &self.live {
+                let (s, e) = (p as usize, p as usize + footprint(l));
+                assert!(
+                    end <= s || e <= start,
+                    "{start:#x}..{end:#x} overlaps live {s:#x}..{e:#x}"
+                );
+            }
+        }
+    }
+
+    fn run(ops: &[Op]) {
+        let (alloc, _heap) = make_allocator();
+        let mut shadow = Shadow {
+            live: Vec::new(),
+            next_tag: 1,
+        };
+        for op in ops {
+            match *op {
+                Op::Alloc { size, align } => {
+                    let layout = Layout::from_size_align(size, align).unwrap();
+                    let could_fit = fits_somewhere(&alloc, layout);
+                    let ptr = unsafe { alloc.alloc(layout) };
+                    if ptr.is_null() {
+                        assert!(!could_fit, "null although {layout:?} fits");
+                        continue;
+                    }
+                    shadow.check_new(&alloc, ptr, layout);
+                    let tag = shadow.next_tag;
+                    shadow.next_tag = shadow.next_tag.wrapping_add(1).max(1);
+                    unsafe { ptr.write_bytes(tag, size) };
+                    shadow.live.push((ptr, layout, tag));
+                }
+                Op::Free { index } if !shadow.live.is_empty() => {
+                    let (ptr, layout, tag) = shadow.live.swap_remove(index % shadow.live.len());
+                    let data = unsafe { std::slice::from_raw_parts(ptr, layout.size()) };
+                    assert!(
+                        data.iter().all(|&b| b == tag),
+                        "block {ptr:p} was overwritten"
+                    );
+                    unsafe { alloc.dealloc(ptr, layout) };
+                }
+                Op::Free { .. } => {}
+            }
+        }
+        // Whatever is still live must be intact.
+        for &(ptr, layout, tag) in &shadow.live {
+            let data = unsafe { std::slice::from_raw_parts(ptr, layout.size()) };
+            assert!(
+                data.iter().all(|&b| b == tag),
+                "block {ptr:p} was overwritten"
+            );
+        }
+    }
+
+    fn config() -> ProptestConfig {
+        if cfg!(miri) {
+            // Miri is orders of magnitude slower, and isolation forbids writing the
+            // regression file.
+            ProptestConfig {
+                cases: 8,
+                failure_persistence: None,
+                ..ProptestConfig::default()
+            }
+        } else {
+            ProptestConfig::default()
+        }
+    }
+
+    proptest! {
+        #![proptest_config(config())]
+
+        #[test]
+        fn prop_random_sequences_match_shadow_model(ops in prop::collection::vec(op(), 1..200)) {
+            run(&ops);
+        }
+
+        #[test]
+        fn prop_freed_block_is_reused_first(
+            sizes in prop::collection::vec(1usize..=128, 1..16),
+            pick in any::<usize>(),
+        ) {
+            let (alloc, _heap) = make_allocator();
+            let layouts: Vec<Layout> =
+                sizes.iter().map(|&s| Layout::from_size_align(s, 8).unwrap()).collect();
+            let ptrs: Vec<*mut u8> = layouts.iter().map(|&l| unsafe { alloc.alloc(l) }).collect();
+            prop_assume!(ptrs.iter().all(|p| !p.is_null()));
+            let i = pick % ptrs.len();
+            unsafe { alloc.dealloc(ptrs[i], layouts[i]) };
+            // The freed block is the head of the list and fits: first-fit takes it.
+            prop_assert_eq!(unsafe { alloc.alloc(layouts[i]) }, ptrs[i]);
+        }
     }
 }
@@ -0,0 +1,186 @@
+//! # Free-List Allocator
+//!
+//! Building on the bump allocator, implement a Free-List Allocator that supports memory reclamation.
+//!
+//! ## How It Works
+//!
+//! A Free-List Allocator uses a linked list to track all freed memory blocks.
+//! On allocation, it first searches the list for a suitable block (first-fit strategy);
+//! if none is found, it falls back to allocating from the unused region.
+//! On deallocation, the block is inserted at the head of the list.
+//!
+//! ```text
+//! free_list -> [block A: 64B] -> [block B: 128B] -> [block C: 32B] -> null
+//! ```
+//!
+//! Each free block stores a `FreeBlock` struct at its head (containing block size and next pointer).
+//!
+//! ## Task
+//!
+//! Implement `FreeListAllocator`'s `alloc` and `dealloc` methods:
+//!
+//! ### alloc
+//! 1. Traverse the free_list, find the first block with `size >= layout.size()` and proper alignment (first-fit)
+//! 2. If found, remove it from the list and return it
+//! 3. If not found, allocate from the `bump` region (same as bump allocator)
+//!
+//! ### dealloc
+//! 1. Write `FreeBlock` header info at the freed block
+//! 2. Insert it at the head of free_list
+//!
+//! ## Key Concepts
+//!
+//! - Intrusive linked list
+//! - `*mut T` read/write: `ptr.write(val)` / `ptr.read()`
+//! - Memory alignment checks
+//!
+//! ## Testing
+//!
+//! Besides the handwritten tests, `proptest` runs random alloc/dealloc sequences against a shadow
+//! model of the live blocks: every block must be aligned, inside the heap, disjoint from the other
+//! live blocks and keep its contents until freed, and `null` is only allowed when nothing fits.
+//! A failing sequence is shrunk to a minimal one and printed.
+
+pub(crate) use core::alloc::{GlobalAlloc, Layout};
+pub(crate) use core::ptr::null_mut;
+
+/// Free block header, stored at the beginning of each free memory block
+pub(crate) struct FreeBlock {
+    pub(crate) size: usize,
+    pub(crate) next: *mut FreeBlock,
+}
+
+pub struct FreeListAllocator {
+    /// Pointer to the start of the heap: every pointer handed out is derived from it.
+    base: *mut u8,
+    pub(crate) heap_start: usize,
+    pub(crate) heap_end: usize,
+    /// Bump pointer: unallocated region starts here
+    pub(crate) bump_next: core::sync::atomic::AtomicUsize,
+    /// Free list head (protected by Mutex in test, UnsafeCell otherwise)
+    #[cfg(test)]
+    free_list: std::sync::Mutex<*mut FreeBlock>,
+    #[cfg(not(test))]
+    free_list: core::cell::UnsafeCell<*mut FreeBlock>,
+}
+
+#[cfg(test)]
+unsafe impl Send for FreeListAllocator {}
+#[cfg(test)]
+unsafe impl Sync for FreeListAllocator {}
+#[cfg(not(test))]
+unsafe impl Send for FreeListAllocator {}
+#[cfg(not(test))]
+unsafe impl Sync for FreeListAllocator {}
+
+impl FreeListAllocator {
+    /// # Safety
+    /// `heap_start..heap_end` must be a valid readable and writable memory region.
+    pub unsafe fn new(heap_start: usize, heap_end: usize) -> Self {
+        Self::from_ptr(
+            core::ptr::with_exposed_provenance_mut(heap_start),
+            heap_end - heap_start,
+        )
+    }
+
+    /// Like `new`, but keeps the provenance of `heap` (see `ptr_at`). Prefer this one when the
+    /// heap comes from a pointer, as in the tests.
+    ///
+    /// # Safety
+    /// `heap..heap + size` must be a valid readable and writable memory region.
+    pub unsafe fn from_ptr(heap: *mut u8, size: usize) -> Self {
+        let heap_start = heap.addr();
+        Self {
+            base: heap,
+            heap_start,
+            heap_end: heap_start + size,
+            bump_next: core::sync::atomic::AtomicUsize::new(heap_start),
+            #[cfg(test)]
+            free_list: std::sync::Mutex::new(null_mut()),
+            #[cfg(not(test))]
+            free_list: core::cell::UnsafeCell::new(null_mut()),
+        }
+    }
+
+    /// The pointer to heap address `addr`.
+    ///
+    /// A pointer is more than an address: it also carries *provenance*, the allocation it may
+    /// access. `addr as *mut u8` has none of its own, which Miri (`cargo miri test`) flags;
+    /// deriving the pointer from the heap's base pointer keeps it.
+    fn ptr_at(&self, addr: usize) -> *mut u8 {
+        debug_assert!(self.heap_start <= addr && addr <= self.heap_end);
+        self.base.with_addr(addr)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn free_list_head(&self) -> *mut FreeBlock {
+        *self.free_list.lock().unwrap()
+    }
+
+    #[cfg(test)]
+    fn set_free_list_head(&self, head: *mut FreeBlock) {
+        *self.free_list.lock().unwrap() = head;
+    }
+
+    #[cfg(not(test))]
+    pub(crate) fn free_list_head(&self) -> *mut FreeBlock {
+        unsafe { *self.free_list.get() }
+    }
+
+    #[cfg(not(test))]
+    fn set_free_list_head(&self, head: *mut FreeBlock) {
+        unsafe { *self.free_list.get() = head }
+    }
+}
+
+unsafe impl GlobalAlloc for FreeListAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // Ensure block is at least large enough to hold a FreeBlock header (for future dealloc)
+        let size = layout.size().max(core::mem::size_of::<FreeBlock>());
+        let align = layout.align().max(core::mem::align_of::<FreeBlock>());
+
+        let mut prev: *mut FreeBlock = null_mut();
+        let mut curr = self.free_list_head();
+        while !curr.is_null() {
+            if (curr as usize) & (align - 1) == 0 && (*curr).size >= size {
+                let next = (*curr).next;
+                if prev.is_null() {
+                    self.set_free_list_head(next);
+                } else {
+                    (*prev).next = next;
+                }
+                return curr as *mut u8;
+            }
+            prev = curr;
+            curr = (*curr).next;
+        }
+
+        use core::sync::atomic::Ordering;
+        loop {
+            let current = self.bump_next.load(Ordering::SeqCst);
+            let start = (current + align - 1) & !(align - 1);
+            let end = match start.checked_add(size) {
+                Some(end) if end <= self.heap_end => end,
+                _ => return null_mut(),
+            };
+            if self
+                .bump_next
+                .compare_exchange(current, end, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                return self.ptr_at(start);
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let size = layout.size().max(core::mem::size_of::<FreeBlock>());
+
+        let block = ptr as *mut FreeBlock;
+        block.write(FreeBlock {
+            size,
+            next: self.free_list_head(),
+        });
+        self.set_free_list_head(block);
+    }
+}
@@ -17,16 +17,57 @@
 //!
 //! 1. Implement `x86_64_abi()`, `aarch64_abi()`, `riscv64_abi()` — return structs describing each arch's ABI
 //! 2. (Conditional compilation) Implement real `syscall3` inline assembly on the current platform
-//! 3. Build `sys_write` / `sys_read` / `sys_close` / `sys_exit` on top of `syscall3`
+//! 3. Build `sys_write` / `sys_read` / `sys_close` / `sys_exit` / `sys_exit_group` on top of `syscall3`,
+//!    and `fatal` (report to stderr, then end the process) on top of those
+//! 4. Implement `syscall6` for calls that take more arguments (`mmap`, `io_uring_enter`, ...)
+//! 5. Give each ABI a `SyscallSpec` table saying what the arguments of `read`, `write`, `openat`
+//!    and `mmap` are, and format traced calls strace-style from it with `TracedCall`
 //!
 //! ## Hints
 //!
 //! - Linux syscall numbers differ across architectures; x86_64 vs aarch64/riscv64 are quite different
 //! - The x86_64 `syscall` instruction clobbers the rcx and r11 registers
+//! - `exit` only ends the calling thread; `exit_group` ends every thread of the process
 //! - aarch64 and riscv64 share the unified syscall number table (from asm-generic)
+//! - The argument kinds of a syscall are the same on every architecture; only its number
+//!   differs. With the kinds in a table, one formatter prints the calls of all three.
 
 #![cfg_attr(not(test), no_std)]
 
+use core::fmt;
+use ArgKind::*;
+
+/// How a syscall argument (or return value) is interpreted, and so how it is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// File descriptor, printed as a signed decimal; `AT_FDCWD` by name
+    Fd,
+    /// Address in the caller's memory, printed in hex; `NULL` for 0
+    UserPtr,
+    /// Byte count or offset, printed in decimal
+    Len,
+    /// Bit flags, printed in hex
+    Flags,
+    /// File permission bits, printed in octal
+    Mode,
+}
+
+/// `dirfd` value meaning "relative to the current directory" (the same on all three arches)
+pub const AT_FDCWD: isize = -100;
+
+/// Describes the arguments of one syscall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallSpec {
+    /// Syscall number on the architecture whose table this entry is in
+    pub nr: usize,
+    /// Name as in the man pages: "write", "openat", ...
+    pub name: &'static str,
+    /// Kind of each argument, in order; the syscall takes exactly this many
+    pub args: &'static [ArgKind],
+    /// Kind of a successful return value
+    pub ret: ArgKind,
+}
+
 /// Describes a Linux Syscall ABI for a specific architecture
 pub struct SyscallABI {
     /// Architecture name: "x86_64", "aarch64", "riscv64"
@@ -49,12 +90,24 @@ pub struct SyscallABI {
     pub sys_close: usize,
     /// exit syscall number
     pub sys_exit: usize,
+    /// exit_group syscall number
+    pub sys_exit_group: usize,
+    /// Argument descriptions of read, write, openat and mmap
+    pub specs: &'static [SyscallSpec],
+}
+
+impl SyscallABI {
+    /// Look up the description of syscall `nr`.
+    pub fn spec(&self, nr: usize) -> Option<&SyscallSpec> {
+        self.specs.iter().find(|spec| spec.nr == nr)
+    }
 }
 
 /// Return the x86_64 Linux syscall ABI description
 pub fn x86_64_abi() -> SyscallABI {
     // TODO: Fill in the x86_64 syscall ABI
     // Hint: x86_64 uses the "syscall" instruction, syscall number in rax
+    // Hint: `specs` lists read, write, openat and mmap with their x86_64 numbers (0, 1, 257, 9)
     todo!()
 }
 
@@ -62,6 +115,7 @@ pub fn x86_64_abi() -> SyscallABI {
 pub fn aarch64_abi() -> SyscallABI {
     // TODO: Fill in the aarch64 syscall ABI
     // Hint: aarch64 uses the "svc #0" instruction, syscall number in x8
+    // Hint: `specs` numbers come from asm-generic/unistd.h: read 63, write 64, openat 56, mmap 222
     todo!()
 }
 
@@ -69,9 +123,69 @@ pub fn aarch64_abi() -> SyscallABI {
 pub fn riscv64_abi() -> SyscallABI {
     // TODO: Fill in the riscv64 syscall ABI
     // Hint: riscv64 uses the "ecall" instruction, syscall number in a7
+    // Hint: the same `specs` as aarch64; a shared const avoids writing the table twice
     todo!()
 }
 
+// ============================================================
+// strace-style formatting
+// ============================================================
+
+/// Print one raw register value as `kind` says.
+pub fn fmt_arg(f: &mut fmt::Formatter<'_>, kind: ArgKind, value: usize) -> fmt::Result {
+    // TODO: Write `value` as `kind` says (see the `ArgKind` docs)
+    // Hints:
+    //   - Fd: `value as isize`, so -1 prints as -1; AT_FDCWD prints by name
+    //   - UserPtr: "NULL" for 0, else {:#x}; Flags: {:#x}; Len: decimal; Mode: "0" then {:o}
+    todo!()
+}
+
+/// A syscall as a tracer records it: the raw number, argument registers and return register.
+///
+/// `Display` prints it the way strace does, e.g. `write(1, 0x5581a2c0, 13) = 13`, using only the
+/// ABI's `SyscallSpec` table:
+/// - only as many arguments as the syscall takes, each formatted by its `ArgKind`
+/// - a return value in `-4095..=-1` is an error: `= -1 (errno 9)`
+/// - a number without a spec prints as `syscall_<nr>(...)` with all six arguments in hex
+pub struct TracedCall<'a> {
+    pub abi: &'a SyscallABI,
+    pub nr: usize,
+    pub args: [usize; 6],
+    pub ret: isize,
+}
+
+impl fmt::Display for TracedCall<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spec = self.abi.spec(self.nr);
+        match spec {
+            Some(spec) => {
+                write!(f, "{}(", spec.name)?;
+                for (i, (&kind, &value)) in spec.args.iter().zip(&self.args).enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    fmt_arg(f, kind, value)?;
+                }
+            }
+            None => {
+                write!(f, "syscall_{}(", self.nr)?;
+                for (i, value) in self.args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{value:#x}")?;
+                }
+            }
+        }
+        f.write_str(") = ")?;
+        if (-4095..=-1).contains(&self.ret) {
+            write!(f, "-1 (errno {})", -self.ret)
+        } else {
+            fmt_arg(f, spec.map_or(Len, |spec| spec.ret), self.ret as usize)
+        }
+    }
+}
+
 // ============================================================
 // Real syscall implementation (conditional compilation, only active on matching platform)
 // ============================================================
@@ -108,6 +222,30 @@ pub unsafe fn syscall3(_id: usize, _arg0: usize, _arg1: usize, _arg2: usize) ->
     panic!("syscall3 is only available on Linux")
 }
 
+/// Issue a Linux syscall with up to 6 arguments.
+///
+/// # Safety
+/// The caller must ensure the syscall number and arguments are valid.
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub unsafe fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    // TODO: Like syscall3, with the remaining argument registers
+    // Hints:
+    //   - in("r10") args[3], in("r8") args[4], in("r9") args[5]
+    //   - the 4th argument goes in r10, not rcx: `syscall` overwrites rcx
+    todo!()
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+pub unsafe fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    // TODO: Like syscall3, with arguments in x0..x5
+    todo!()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn syscall6(_id: usize, _args: [usize; 6]) -> isize {
+    panic!("syscall6 is only available on Linux")
+}
+
 // Platform-specific write syscall number
 #[cfg(target_arch = "x86_64")]
 const NATIVE_SYS_WRITE: usize = 1;
@@ -117,6 +255,8 @@ const NATIVE_SYS_READ: usize = 0;
 const NATIVE_SYS_CLOSE: usize = 3;
 #[cfg(target_arch = "x86_64")]
 const NATIVE_SYS_EXIT: usize = 60;
+#[cfg(target_arch = "x86_64")]
+const NATIVE_SYS_EXIT_GROUP: usize = 231;
 
 #[cfg(target_arch = "aarch64")]
 const NATIVE_SYS_WRITE: usize = 64;
@@ -126,6 +266,8 @@ const NATIVE_SYS_READ: usize = 63;
 const NATIVE_SYS_CLOSE: usize = 57;
 #[cfg(target_arch = "aarch64")]
 const NATIVE_SYS_EXIT: usize = 93;
+#[cfg(target_arch = "aarch64")]
+const NATIVE_SYS_EXIT_GROUP: usize = 94;
 
 // Fallback for other architectures (not actually used, just for compilation)
 #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
@@ -136,6 +278,8 @@ const NATIVE_SYS_READ: usize = 0;
 const NATIVE_SYS_CLOSE: usize = 0;
 #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
 const NATIVE_SYS_EXIT: usize = 0;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const NATIVE_SYS_EXIT_GROUP: usize = 0;
 
 /// Write data from `buf` to file descriptor `fd`.
 pub fn sys_write(fd: usize, buf: &[u8]) -> isize {
@@ -155,18 +299,91 @@ pub fn sys_close(fd: usize) -> isize {
     todo!()
 }
 
-/// Terminate the current process.
+/// Terminate the calling thread. Other threads keep running; the process only ends with its
+/// last thread (see `sys_exit_group`).
 pub fn sys_exit(code: i32) -> ! {
     // TODO: Call syscall3 to implement exit
     todo!()
 }
 
+/// Terminate the whole process, every thread of it, with exit status `code`.
+pub fn sys_exit_group(code: i32) -> ! {
+    // TODO: Call syscall3 with NATIVE_SYS_EXIT_GROUP
+    todo!()
+}
+
+/// Exit status of a process ended by `fatal`.
+pub const FATAL_EXIT_CODE: i32 = 101;
+
+/// Write `msg` and a newline to stderr, then end the process with `FATAL_EXIT_CODE`.
+///
+/// Needs nothing but two syscalls: no allocator, no locks, no unwinding. That makes it safe to
+/// call from a `#[panic_handler]` or after memory corruption has been detected.
+pub fn fatal(msg: &str) -> ! {
+    // TODO: Write all of `msg`, then "\n", to fd 2 with sys_write, then sys_exit_group
+    // Hints:
+    //   - sys_write may write fewer bytes than asked: loop on the rest of the buffer
+    //   - on an error (negative return) give up writing, but still exit
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 // ============================================================
 // Tests
 // ============================================================
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_x86_64_registers",
+            "x86_64 passes arguments in rdi, rsi, rdx, r10, r8, r9: the fourth is r10, not rcx (syscall overwrites rcx)",
+        ),
+        (
+            "test_x86_64_clobbered",
+            "the syscall instruction itself overwrites rcx (return address) and r11 (saved rflags)",
+        ),
+        (
+            "test_aarch64_riscv64_share_numbers",
+            "aarch64 and riscv64 both use the generic Linux syscall table (asm-generic/unistd.h), so their numbers agree",
+        ),
+        (
+            "test_sys_exit_group_status",
+            "exit_group is 231 on x86_64 and 94 on aarch64/riscv64; plain exit would only end the test's thread",
+        ),
+        (
+            "test_fatal_reports_and_exits",
+            "write msg and then \"\\n\" to fd 2 (looping until everything is written), then sys_exit_group(FATAL_EXIT_CODE)",
+        ),
+        (
+            "test_specs_x86_64",
+            "x86_64 numbers: read 0, write 1, openat 257, mmap 9 (arch/x86/entry/syscalls/syscall_64.tbl)",
+        ),
+        (
+            "test_specs_asm_generic",
+            "aarch64 and riscv64 numbers: read 63, write 64, openat 56, mmap 222 (asm-generic/unistd.h)",
+        ),
+        (
+            "test_specs_same_kinds_on_every_arch",
+            "a syscall's arguments mean the same on every arch: openat(dirfd, path, flags, mode), mmap(addr, len, prot, flags, fd, offset)",
+        ),
+        (
+            "test_format_openat",
+            "fmt_arg: Fd prints AT_FDCWD by name, Flags in hex with 0x, Mode in octal with a leading 0",
+        ),
+        (
+            "test_format_mmap",
+            "fmt_arg: a zero UserPtr prints NULL, an Fd goes through `as isize` so that !0 prints -1",
+        ),
+    ];
 
     // ---- ABI knowledge tests (run on any platform) ----
 
@@ -180,9 +397,9 @@ mod tests {
     #[test]
     fn test_x86_64_registers() {
         let abi = x86_64_abi();
-        assert_eq!(abi.id_reg, "rax");
-        assert_eq!(abi.ret_reg, "rax");
-        assert_eq!(
+        assert_eq_with_hint!(abi.id_reg, "rax");
+        assert_eq_with_hint!(abi.ret_reg, "rax");
+        assert_eq_with_hint!(
             abi.arg_regs,
             &["rdi", "rsi", "rdx", "r10", "r8", "r9"],
             "x86_64 argument register order is incorrect"
@@ -192,7 +409,7 @@ mod tests {
     #[test]
     fn test_x86_64_clobbered() {
         let abi = x86_64_abi();
-        assert!(
+        assert_with_hint!(
             abi.clobbered.contains(&"rcx") && abi.clobbered.contains(&"r11"),
             "x86_64 syscall clobbers rcx and r11"
         );
@@ -205,6 +422,7 @@ mod tests {
         assert_eq!(abi.sys_read, 0);
         assert_eq!(abi.sys_close, 3);
         assert_eq!(abi.sys_exit, 60);
+        assert_eq!(abi.sys_exit_group, 231);
     }
 
     #[test]
@@ -242,6 +460,7 @@ mod tests {
         assert_eq!(abi.sys_read, 63);
         assert_eq!(abi.sys_close, 57);
         assert_eq!(abi.sys_exit, 93);
+        assert_eq!(abi.sys_exit_group, 94);
     }
 
     #[test]
@@ -279,26 +498,156 @@ mod tests {
         assert_eq!(abi.sys_read, 63);
         assert_eq!(abi.sys_close, 57);
         assert_eq!(abi.sys_exit, 93);
+        assert_eq!(abi.sys_exit_group, 94);
     }
 
     #[test]
     fn test_aarch64_riscv64_share_numbers() {
         let aarch64 = aarch64_abi();
         let riscv64 = riscv64_abi();
-        assert_eq!(
-            aarch64.sys_write, riscv64.sys_write,
+        assert_eq_with_hint!(
+            aarch64.sys_write,
+            riscv64.sys_write,
             "aarch64 and riscv64 share asm-generic syscall numbers"
         );
-        assert_eq!(aarch64.sys_read, riscv64.sys_read);
-        assert_eq!(aarch64.sys_close, riscv64.sys_close);
-        assert_eq!(aarch64.sys_exit, riscv64.sys_exit);
+        assert_eq_with_hint!(aarch64.sys_read, riscv64.sys_read);
+        assert_eq_with_hint!(aarch64.sys_close, riscv64.sys_close);
+        assert_eq_with_hint!(aarch64.sys_exit, riscv64.sys_exit);
+        assert_eq_with_hint!(aarch64.sys_exit_group, riscv64.sys_exit_group);
+    }
+
+    // ---- SyscallSpec tables and strace-style formatting ----
+
+    use ArgKind::*;
+
+    const RW: &[ArgKind] = &[Fd, UserPtr, Len];
+    const OPENAT: &[ArgKind] = &[Fd, UserPtr, Flags, Mode];
+    const MMAP: &[ArgKind] = &[UserPtr, Len, Flags, Flags, Fd, Len];
+
+    fn abis() -> [SyscallABI; 3] {
+        [x86_64_abi(), aarch64_abi(), riscv64_abi()]
+    }
+
+    /// `(name, nr)` of every spec in `abi`, to compare the tables without caring about order.
+    fn numbers(abi: &SyscallABI) -> Vec<(&'static str, usize)> {
+        let mut v: Vec<_> = abi.specs.iter().map(|s| (s.name, s.nr)).collect();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn test_specs_x86_64() {
+        let abi = x86_64_abi();
+        assert_eq_with_hint!(
+            numbers(&abi),
+            vec![("mmap", 9), ("openat", 257), ("read", 0), ("write", 1)]
+        );
     }
 
-    // ---- Real syscall tests (only run on Linux) ----
+    #[test]
+    fn test_specs_asm_generic() {
+        for abi in [aarch64_abi(), riscv64_abi()] {
+            assert_eq_with_hint!(
+                numbers(&abi),
+                vec![("mmap", 222), ("openat", 56), ("read", 63), ("write", 64)],
+                "{}",
+                abi.arch
+            );
+        }
+    }
 
-    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_specs_same_kinds_on_every_arch() {
+        for abi in abis() {
+            for (name, args, ret) in [
+                ("read", RW, Len),
+                ("write", RW, Len),
+                ("openat", OPENAT, Fd),
+                ("mmap", MMAP, UserPtr),
+            ] {
+                let spec = abi.specs.iter().find(|s| s.name == name);
+                assert_with_hint!(spec.is_some(), "{} has no spec for {name}", abi.arch);
+                let spec = spec.unwrap();
+                assert_eq_with_hint!(spec.args, args, "{} {name} arguments", abi.arch);
+                assert_eq_with_hint!(spec.ret, ret, "{} {name} return value", abi.arch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_specs_match_syscall_numbers() {
+        for abi in abis() {
+            assert_eq!(abi.spec(abi.sys_read).map(|s| s.name), Some("read"));
+            assert_eq!(abi.spec(abi.sys_write).map(|s| s.name), Some("write"));
+            assert!(abi.spec(abi.sys_exit).is_none(), "exit has no spec");
+        }
+    }
+
+    /// Format the same call on every arch, from its name instead of its number.
+    fn trace(name: &str, args: [usize; 6], ret: isize) -> [String; 3] {
+        abis().map(|abi| {
+            let nr = abi.specs.iter().find(|s| s.name == name).unwrap().nr;
+            TracedCall {
+                abi: &abi,
+                nr,
+                args,
+                ret,
+            }
+            .to_string()
+        })
+    }
+
+    #[test]
+    fn test_format_write() {
+        for line in trace("write", [1, 0x5581_a2c0, 13, 7, 7, 7], 13) {
+            assert_eq!(line, "write(1, 0x5581a2c0, 13) = 13");
+        }
+    }
+
+    #[test]
+    fn test_format_openat() {
+        let path = 0x7ffd_0010;
+        for line in trace("openat", [-100isize as usize, path, 0x241, 0o644, 0, 0], 3) {
+            assert_eq_with_hint!(line, "openat(AT_FDCWD, 0x7ffd0010, 0x241, 0644) = 3");
+        }
+    }
+
+    #[test]
+    fn test_format_mmap() {
+        let args = [0, 4096, 0x3, 0x22, !0, 0];
+        for line in trace("mmap", args, 0x7f12_3456_7000) {
+            assert_eq_with_hint!(line, "mmap(NULL, 4096, 0x3, 0x22, -1, 0) = 0x7f1234567000");
+        }
+    }
+
+    #[test]
+    fn test_format_error_return() {
+        for line in trace("read", [999, 0x1000, 5, 0, 0, 0], -9) {
+            assert_eq!(line, "read(999, 0x1000, 5) = -1 (errno 9)");
+        }
+    }
+
+    #[test]
+    fn test_format_unknown_syscall() {
+        let abi = x86_64_abi();
+        let call = TracedCall {
+            abi: &abi,
+            nr: 39,
+            args: [0, 1, 0, 0, 0, 0],
+            ret: 1234,
+        };
+        assert_eq!(
+            call.to_string(),
+            "syscall_39(0x0, 0x1, 0x0, 0x0, 0x0, 0x0) = 1234"
+        );
+    }
+
+    // ---- Real syscall tests (only run on Linux; Miri cannot execute inline assembly) ----
+
+    #[cfg(all(target_os = "linux", not(miri)))]
     mod linux_tests {
         use super::*;
+        use subprocess::in_child;
 
         #[test]
         fn test_sys_write_stdout() {
@@ -324,10 +673,41 @@ mod tests {
             assert!(ret < 0, "invalid fd should return negative, got {ret}");
         }
 
+        #[test]
+        fn test_syscall6_write() {
+            let msg = b"[syscall_wrapper] syscall6 test\n";
+            let ret = unsafe {
+                syscall6(
+                    NATIVE_SYS_WRITE,
+                    [1, msg.as_ptr() as usize, msg.len(), 0, 0, 0],
+                )
+            };
+            assert_eq!(ret, msg.len() as isize);
+        }
+
         #[test]
         fn test_sys_close_invalid_fd() {
             let ret = sys_close(999);
             assert!(ret < 0, "closing invalid fd should return negative");
         }
+
+        #[test]
+        fn test_sys_exit_group_status() {
+            // The child runs this on a test thread: only exit_group also ends the harness's
+            // main thread.
+            let out = in_child(|| sys_exit_group(42));
+            assert_eq_with_hint!(out.status.code(), Some(42));
+        }
+
+        #[test]
+        fn test_fatal_reports_and_exits() {
+            let out = in_child(|| fatal("stack canary clobbered"));
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            assert_eq_with_hint!(out.status.code(), Some(FATAL_EXIT_CODE));
+            assert_with_hint!(
+                stderr.ends_with("stack canary clobbered\n"),
+                "stderr was {stderr:?}"
+            );
+        }
     }
 }
@@ -0,0 +1,463 @@
+//! # Cross-Architecture Syscall ABI Description and Wrapper
+//!
+//! Describe the syscall ABI for x86_64, aarch64, and riscv64 on Linux by filling in struct fields.
+//! Also implement real syscall invocations on the current platform via conditional compilation.
+//!
+//! ## Background
+//!
+//! Different CPU architectures use different instructions and registers to trigger system calls:
+//!
+//! | Arch     | Instruction | Syscall ID Reg | Return Reg | Argument Registers              |
+//! |----------|-------------|----------------|------------|---------------------------------|
+//! | x86_64   | `syscall`   | rax            | rax        | rdi, rsi, rdx, r10, r8, r9     |
+//! | aarch64  | `svc #0`    | x8             | x0         | x0, x1, x2, x3, x4, x5        |
+//! | riscv64  | `ecall`     | a7             | a0         | a0, a1, a2, a3, a4, a5         |
+//!
+//! ## Task
+//!
+//! 1. Implement `x86_64_abi()`, `aarch64_abi()`, `riscv64_abi()` — return structs describing each arch's ABI
+//! 2. (Conditional compilation) Implement real `syscall3` inline assembly on the current platform
+//! 3. Build `sys_write` / `sys_read` / `sys_close` / `sys_exit` / `sys_exit_group` on top of `syscall3`,
+//!    and `fatal` (report to stderr, then end the process) on top of those
+//! 4. Implement `syscall6` for calls that take more arguments (`mmap`, `io_uring_enter`, ...)
+//! 5. Give each ABI a `SyscallSpec` table saying what the arguments of `read`, `write`, `openat`
+//!    and `mmap` are, and format traced calls strace-style from it with `TracedCall`
+//!
+//! ## Hints
+//!
+//! - Linux syscall numbers differ across architectures; x86_64 vs aarch64/riscv64 are quite different
+//! - The x86_64 `syscall` instruction clobbers the rcx and r11 registers
+//! - `exit` only ends the calling thread; `exit_group` ends every thread of the process
+//! - aarch64 and riscv64 share the unified syscall number table (from asm-generic)
+//! - The argument kinds of a syscall are the same on every architecture; only its number
+//!   differs. With the kinds in a table, one formatter prints the calls of all three.
+
+use core::fmt;
+use ArgKind::*;
+
+/// How a syscall argument (or return value) is interpreted, and so how it is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// File descriptor, printed as a signed decimal; `AT_FDCWD` by name
+    Fd,
+    /// Address in the caller's memory, printed in hex; `NULL` for 0
+    UserPtr,
+    /// Byte count or offset, printed in decimal
+    Len,
+    /// Bit flags, printed in hex
+    Flags,
+    /// File permission bits, printed in octal
+    Mode,
+}
+
+/// `dirfd` value meaning "relative to the current directory" (the same on all three arches)
+pub const AT_FDCWD: isize = -100;
+
+/// Describes the arguments of one syscall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallSpec {
+    /// Syscall number on the architecture whose table this entry is in
+    pub nr: usize,
+    /// Name as in the man pages: "write", "openat", ...
+    pub name: &'static str,
+    /// Kind of each argument, in order; the syscall takes exactly this many
+    pub args: &'static [ArgKind],
+    /// Kind of a successful return value
+    pub ret: ArgKind,
+}
+
+/// Describes a Linux Syscall ABI for a specific architecture
+pub struct SyscallABI {
+    /// Architecture name: "x86_64", "aarch64", "riscv64"
+    pub arch: &'static str,
+    /// Instruction that triggers the syscall: "syscall", "svc #0", "ecall"
+    pub instruction: &'static str,
+    /// Register holding the syscall number
+    pub id_reg: &'static str,
+    /// Register holding the return value
+    pub ret_reg: &'static str,
+    /// Argument registers (in order)
+    pub arg_regs: &'static [&'static str],
+    /// Registers additionally clobbered by the syscall instruction
+    pub clobbered: &'static [&'static str],
+    /// write syscall number
+    pub sys_write: usize,
+    /// read syscall number
+    pub sys_read: usize,
+    /// close syscall number
+    pub sys_close: usize,
+    /// exit syscall number
+    pub sys_exit: usize,
+    /// exit_group syscall number
+    pub sys_exit_group: usize,
+    /// Argument descriptions of read, write, openat and mmap
+    pub specs: &'static [SyscallSpec],
+}
+
+impl SyscallABI {
+    /// Look up the description of syscall `nr`.
+    pub fn spec(&self, nr: usize) -> Option<&SyscallSpec> {
+        self.specs.iter().find(|spec| spec.nr == nr)
+    }
+}
+
+/// aarch64 and riscv64 both number their syscalls by asm-generic/unistd.h.
+const ASM_GENERIC_SPECS: &[SyscallSpec] = &[
+    SyscallSpec {
+        nr: 63,
+        name: "read",
+        args: &[Fd, UserPtr, Len],
+        ret: Len,
+    },
+    SyscallSpec {
+        nr: 64,
+        name: "write",
+        args: &[Fd, UserPtr, Len],
+        ret: Len,
+    },
+    SyscallSpec {
+        nr: 56,
+        name: "openat",
+        args: &[Fd, UserPtr, Flags, Mode],
+        ret: Fd,
+    },
+    SyscallSpec {
+        nr: 222,
+        name: "mmap",
+        args: &[UserPtr, Len, Flags, Flags, Fd, Len],
+        ret: UserPtr,
+    },
+];
+
+/// Return the x86_64 Linux syscall ABI description
+pub fn x86_64_abi() -> SyscallABI {
+    SyscallABI {
+        arch: "x86_64",
+        instruction: "syscall",
+        id_reg: "rax",
+        ret_reg: "rax",
+        arg_regs: &["rdi", "rsi", "rdx", "r10", "r8", "r9"],
+        clobbered: &["rcx", "r11"],
+        sys_write: 1,
+        sys_read: 0,
+        sys_close: 3,
+        sys_exit: 60,
+        sys_exit_group: 231,
+        specs: &[
+            SyscallSpec {
+                nr: 0,
+                name: "read",
+                args: &[Fd, UserPtr, Len],
+                ret: Len,
+            },
+            SyscallSpec {
+                nr: 1,
+                name: "write",
+                args: &[Fd, UserPtr, Len],
+                ret: Len,
+            },
+            SyscallSpec {
+                nr: 257,
+                name: "openat",
+                args: &[Fd, UserPtr, Flags, Mode],
+                ret: Fd,
+            },
+            SyscallSpec {
+                nr: 9,
+                name: "mmap",
+                args: &[UserPtr, Len, Flags, Flags, Fd, Len],
+                ret: UserPtr,
+            },
+        ],
+    }
+}
+
+/// Return the aarch64 Linux syscall ABI description
+pub fn aarch64_abi() -> SyscallABI {
+    SyscallABI {
+        arch: "aarch64",
+        instruction: "svc #0",
+        id_reg: "x8",
+        ret_reg: "x0",
+        arg_regs: &["x0", "x1", "x2", "x3", "x4", "x5"],
+        clobbered: &[],
+        sys_write: 64,
+        sys_read: 63,
+        sys_close: 57,
+        sys_exit: 93,
+        sys_exit_group: 94,
+        specs: ASM_GENERIC_SPECS,
+    }
+}
+
+/// Return the riscv64 Linux syscall ABI description
+pub fn riscv64_abi() -> SyscallABI {
+    SyscallABI {
+        arch: "riscv64",
+        instruction: "ecall",
+        id_reg: "a7",
+        ret_reg: "a0",
+        arg_regs: &["a0", "a1", "a2", "a3", "a4", "a5"],
+        clobbered: &[],
+        sys_write: 64,
+        sys_read: 63,
+        sys_close: 57,
+        sys_exit: 93,
+        sys_exit_group: 94,
+        specs: ASM_GENERIC_SPECS,
+    }
+}
+
+// ============================================================
+// strace-style formatting
+// ============================================================
+
+/// Print one raw register value as `kind` says.
+pub fn fmt_arg(f: &mut fmt::Formatter<'_>, kind: ArgKind, value: usize) -> fmt::Result {
+    match kind {
+        ArgKind::Fd if value as isize == AT_FDCWD => f.write_str("AT_FDCWD"),
+        ArgKind::Fd => write!(f, "{}", value as isize),
+        ArgKind::UserPtr if value == 0 => f.write_str("NULL"),
+        ArgKind::UserPtr | ArgKind::Flags => write!(f, "{value:#x}"),
+        ArgKind::Len => write!(f, "{value}"),
+        ArgKind::Mode => write!(f, "0{value:o}"),
+    }
+}
+
+/// A syscall as a tracer records it: the raw number, argument registers and return register.
+///
+/// `Display` prints it the way strace does, e.g. `write(1, 0x5581a2c0, 13) = 13`, using only the
+/// ABI's `SyscallSpec` table:
+/// - only as many arguments as the syscall takes, each formatted by its `ArgKind`
+/// - a return value in `-4095..=-1` is an error: `= -1 (errno 9)`
+/// - a number without a spec prints as `syscall_<nr>(...)` with all six arguments in hex
+pub struct TracedCall<'a> {
+    pub abi: &'a SyscallABI,
+    pub nr: usize,
+    pub args: [usize; 6],
+    pub ret: isize,
+}
+
+impl fmt::Display for TracedCall<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spec = self.abi.spec(self.nr);
+        match spec {
+            Some(spec) => {
+                write!(f, "{}(", spec.name)?;
+                for (i, (&kind, &value)) in spec.args.iter().zip(&self.args).enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    fmt_arg(f, kind, value)?;
+                }
+            }
+            None => {
+                write!(f, "syscall_{}(", self.nr)?;
+                for (i, value) in self.args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{value:#x}")?;
+                }
+            }
+        }
+        f.write_str(") = ")?;
+        if (-4095..=-1).contains(&self.ret) {
+            write!(f, "-1 (errno {})", -self.ret)
+        } else {
+            fmt_arg(f, spec.map_or(Len, |spec| spec.ret), self.ret as usize)
+        }
+    }
+}
+
+// ============================================================
+// Real syscall implementation (conditional compilation, only active on matching platform)
+// ============================================================
+
+/// Issue a Linux syscall with up to 3 arguments.
+///
+/// # Safety
+/// The caller must ensure the syscall number and arguments are valid.
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub unsafe fn syscall3(id: usize, arg0: usize, arg1: usize, arg2: usize) -> isize {
+    // TODO: Implement x86_64 syscall using core::arch::asm!
+    // Hints:
+    //   - "syscall" instruction
+    //   - inlateout("rax") id => ret
+    //   - in("rdi") arg0, in("rsi") arg1, in("rdx") arg2
+    //   - out("rcx") _, out("r11") _
+    let ret: isize;
+    core::arch::asm!(
+        "syscall",
+        inlateout("rax") id as isize => ret,
+        in("rdi") arg0,
+        in("rsi") arg1,
+        in("rdx") arg2,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack),
+    );
+    ret
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+pub unsafe fn syscall3(id: usize, arg0: usize, arg1: usize, arg2: usize) -> isize {
+    // TODO: Implement aarch64 syscall using core::arch::asm!
+    // Hints:
+    //   - "svc #0" instruction
+    //   - in("x8") id
+    //   - inlateout("x0") arg0 => ret
+    //   - in("x1") arg1, in("x2") arg2
+    let ret: isize;
+    core::arch::asm!(
+        "svc #0",
+        in("x8") id,
+        inlateout("x0") arg0 as isize => ret,
+        in("x1") arg1,
+        in("x2") arg2,
+        options(nostack),
+    );
+    ret
+}
+
+// Non-Linux platforms: provide a stub so the code compiles
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn syscall3(_id: usize, _arg0: usize, _arg1: usize, _arg2: usize) -> isize {
+    panic!("syscall3 is only available on Linux")
+}
+
+/// Issue a Linux syscall with up to 6 arguments.
+///
+/// # Safety
+/// The caller must ensure the syscall number and arguments are valid.
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub unsafe fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    // TODO: Like syscall3, with the remaining argument registers
+    // Hints:
+    //   - in("r10") args[3], in("r8") args[4], in("r9") args[5]
+    //   - the 4th argument goes in r10, not rcx: `syscall` overwrites rcx
+    let ret: isize;
+    core::arch::asm!(
+        "syscall",
+        inlateout("rax") id as isize => ret,
+        in("rdi") args[0],
+        in("rsi") args[1],
+        in("rdx") args[2],
+        in("r10") args[3],
+        in("r8") args[4],
+        in("r9") args[5],
+        out("rcx") _,
+        out("r11") _,
+        options(nostack),
+    );
+    ret
+}
+
+#[cfg(all(target_arch = "aarch64", target_os = "linux"))]
+pub unsafe fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    let ret: isize;
+    core::arch::asm!(
+        "svc #0",
+        in("x8") id,
+        inlateout("x0") args[0] as isize => ret,
+        in("x1") args[1],
+        in("x2") args[2],
+        in("x3") args[3],
+        in("x4") args[4],
+        in("x5") args[5],
+        options(nostack),
+    );
+    ret
+}
+
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn syscall6(_id: usize, _args: [usize; 6]) -> isize {
+    panic!("syscall6 is only available on Linux")
+}
+
+// Platform-specific write syscall number
+#[cfg(target_arch = "x86_64")]
+pub(crate) const NATIVE_SYS_WRITE: usize = 1;
+#[cfg(target_arch = "x86_64")]
+const NATIVE_SYS_READ: usize = 0;
+#[cfg(target_arch = "x86_64")]
+const NATIVE_SYS_CLOSE: usize = 3;
+#[cfg(target_arch = "x86_64")]
+const NATIVE_SYS_EXIT: usize = 60;
+#[cfg(target_arch = "x86_64")]
+const NATIVE_SYS_EXIT_GROUP: usize = 231;
+
+#[cfg(target_arch = "aarch64")]
+pub(crate) const NATIVE_SYS_WRITE: usize = 64;
+#[cfg(target_arch = "aarch64")]
+const NATIVE_SYS_READ: usize = 63;
+#[cfg(target_arch = "aarch64")]
+const NATIVE_SYS_CLOSE: usize = 57;
+#[cfg(target_arch = "aarch64")]
+const NATIVE_SYS_EXIT: usize = 93;
+#[cfg(target_arch = "aarch64")]
+const NATIVE_SYS_EXIT_GROUP: usize = 94;
+
+// Fallback for other architectures (not actually used, just for compilation)
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub(crate) const NATIVE_SYS_WRITE: usize = 0;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const NATIVE_SYS_READ: usize = 0;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const NATIVE_SYS_CLOSE: usize = 0;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const NATIVE_SYS_EXIT: usize = 0;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const NATIVE_SYS_EXIT_GROUP: usize = 0;
+
+/// Write data from `buf` to file descriptor `fd`.
+pub fn sys_write(fd: usize, buf: &[u8]) -> isize {
+    unsafe { syscall3(NATIVE_SYS_WRITE, fd, buf.as_ptr() as usize, buf.len()) }
+}
+
+/// Read data from file descriptor `fd` into `buf`.
+pub fn sys_read(fd: usize, buf: &mut [u8]) -> isize {
+    unsafe { syscall3(NATIVE_SYS_READ, fd, buf.as_mut_ptr() as usize, buf.len()) }
+}
+
+/// Close file descriptor `fd`.
+pub fn sys_close(fd: usize) -> isize {
+    unsafe { syscall3(NATIVE_SYS_CLOSE, fd, 0, 0) }
+}
+
+/// Terminate the calling thread. Other threads keep running; the process only ends with its
+/// last thread (see `sys_exit_group`).
+pub fn sys_exit(code: i32) -> ! {
+    unsafe { syscall3(NATIVE_SYS_EXIT, code as usize, 0, 0) };
+    unreachable!()
+}
+
+/// Terminate the whole process, every thread of it, with exit status `code`.
+pub fn sys_exit_group(code: i32) -> ! {
+    unsafe { syscall3(NATIVE_SYS_EXIT_GROUP, code as usize, 0, 0) };
+    unreachable!()
+}
+
+/// Exit status of a process ended by `fatal`.
+pub const FATAL_EXIT_CODE: i32 = 101;
+
+/// Write `msg` and a newline to stderr, then end the process with `FATAL_EXIT_CODE`.
+///
+/// Needs nothing but two syscalls: no allocator, no locks, no unwinding. That makes it safe to
+/// call from a `#[panic_handler]` or after memory corruption has been detected.
+pub fn fatal(msg: &str) -> ! {
+    write_all(2, msg.as_bytes());
+    write_all(2, b"\n");
+    sys_exit_group(FATAL_EXIT_CODE)
+}
+
+/// `sys_write` until all of `buf` is written or the write fails.
+fn write_all(fd: usize, mut buf: &[u8]) {
+    while !buf.is_empty() {
+        let n = sys_write(fd, buf);
+        if n <= 0 {
+            return;
+        }
+        buf = &buf[n as usize..];
+    }
+}
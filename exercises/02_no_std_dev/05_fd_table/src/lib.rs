@@ -29,7 +29,15 @@
 //!   - If no free slot, extend the table
 //! - `get(fd)` -> `Option<Arc<dyn File>>` — get the file object for an fd
 //! - `close(fd)` -> `bool` — close an fd, return whether it succeeded (false if fd doesn't exist)
+//!   - Call the file's `on_close` once its last fd is closed
+//! - `dup(fd)` -> `Option<usize>` — a new fd (the lowest free one) for the same open file
 //! - `count()` -> `usize` — return the number of currently allocated fds (excluding closed ones)
+//! - `fds()` -> `Vec<usize>` — the open fd numbers, in increasing order
+//!
+//! Then two pieces for `/proc`-style files, whose contents are computed when they are read:
+//!
+//! - `CallbackFile::new(render)` — a read-only `File` whose contents are `render()`
+//! - `Vfs` — a path -> file registry: `register(path, file)`, then `open(path, &mut table)`
 //!
 //! ## Key Concepts
 //!
@@ -37,20 +45,45 @@
 //! - `Vec<Option<T>>` as a sparse table
 //! - fd number reuse strategy (find smallest free slot)
 //! - `Arc` reference counting and resource release
+//! - Open file descriptions: `dup` shares one between fds, like Linux's `struct file`
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Bad file descriptor: returned by operations a file does not support.
+const EBADF: isize = 9;
 
 /// File abstraction trait — all "files" in the kernel (regular files, pipes, sockets) implement this
 pub trait File: Send + Sync {
     fn read(&self, buf: &mut [u8]) -> isize;
     fn write(&self, buf: &[u8]) -> isize;
+
+    /// Called when the last fd referring to this open file is closed (or its table is dropped).
+    /// Clones of the `Arc` handed out by `FdTable::get` do not keep the file open.
+    fn on_close(&self) {}
+}
+
+/// An open file description: what `alloc` creates and `dup` shares between fds.
+///
+/// The table holds one `Arc` of it per fd, so it is dropped exactly when its last fd is closed.
+struct OpenFile {
+    file: Arc<dyn File>,
+}
+
+impl Drop for OpenFile {
+    fn drop(&mut self) {
+        // TODO: The last fd of this open file is gone: tell the file
+        todo!()
+    }
 }
 
 /// File descriptor table
 pub struct FdTable {
     // TODO: Design the internal structure
-    // Hint: use Vec<Option<Arc<dyn File>>>
+    // Hint: use Vec<Option<Arc<OpenFile>>>
     //       the index is the fd number, None means the fd is closed or unallocated
+    //       fds made by dup hold clones of the same Arc<OpenFile>
 }
 
 impl FdTable {
@@ -63,7 +96,14 @@ impl FdTable {
     /// Allocate a new fd, return the fd number.
     ///
     /// Prefers reusing the smallest closed fd number; if no free slot, appends to the end.
+    /// Each call opens the file anew: two `alloc`s of the same `Arc` get two `on_close` calls.
     pub fn alloc(&mut self, file: Arc<dyn File>) -> usize {
+        // TODO: Wrap `file` in a new OpenFile and install it
+        todo!()
+    }
+
+    /// Put `open` in the lowest free slot.
+    fn install(&mut self, open: Arc<OpenFile>) -> usize {
         // TODO
         todo!()
     }
@@ -74,7 +114,16 @@ impl FdTable {
         todo!()
     }
 
+    /// Duplicate an fd: the lowest free fd now refers to the same open file as `fd`.
+    /// Returns None if `fd` doesn't exist or is closed.
+    pub fn dup(&mut self, fd: usize) -> Option<usize> {
+        // TODO: Install a clone of fd's Arc<OpenFile>, not a new OpenFile
+        todo!()
+    }
+
     /// Close an fd. Returns true on success, false if the fd doesn't exist or is already closed.
+    ///
+    /// Closing the last fd of an open file drops its `OpenFile`, which calls `on_close`.
     pub fn close(&mut self, fd: usize) -> bool {
         // TODO
         todo!()
@@ -85,6 +134,12 @@ impl FdTable {
         // TODO
         todo!()
     }
+
+    /// Return the open fd numbers in increasing order
+    pub fn fds(&self) -> Vec<usize> {
+        // TODO
+        todo!()
+    }
 }
 
 impl Default for FdTable {
@@ -93,17 +148,137 @@ impl Default for FdTable {
     }
 }
 
+// ============================================================
+// Proc-style files
+// ============================================================
+
+/// A read-only file whose contents come from a closure, like the files under `/proc`.
+///
+/// A read that starts at offset 0 calls `render` and keeps the result as a snapshot; later reads
+/// continue through that snapshot, so one pass sees consistent contents even if the state
+/// changes meanwhile. The read that returns 0 (end of file) rewinds, and the next read renders
+/// again.
+pub struct CallbackFile {
+    render: Box<dyn Fn() -> String + Send + Sync>,
+    /// The snapshot being read and the offset into it; `None` between passes.
+    snapshot: Mutex<Option<(Vec<u8>, usize)>>,
+}
+
+impl CallbackFile {
+    pub fn new(render: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        Self {
+            render: Box::new(render),
+            snapshot: Mutex::new(None),
+        }
+    }
+}
+
+impl File for CallbackFile {
+    fn read(&self, buf: &mut [u8]) -> isize {
+        // TODO: Copy the next part of the snapshot into `buf`
+        // Hints:
+        //   - an empty `buf` reads nothing and must not end the pass
+        //   - no snapshot yet: render one, starting at offset 0 (Option::get_or_insert_with)
+        //   - copy min(buf.len(), bytes left) and advance the offset
+        //   - a read that copies 0 bytes is end of file: drop the snapshot
+        todo!()
+    }
+
+    fn write(&self, _buf: &[u8]) -> isize {
+        -EBADF
+    }
+}
+
+/// A minimal VFS: absolute paths mapped to the files registered under them.
+#[derive(Default)]
+pub struct Vfs {
+    files: BTreeMap<String, Arc<dyn File>>,
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `file` reachable as `path`, replacing what was registered there before.
+    pub fn register(&mut self, path: &str, file: Arc<dyn File>) {
+        // TODO
+        todo!()
+    }
+
+    /// Open `path` in `table`: a new fd for the registered file. None if nothing is registered.
+    pub fn open(&self, path: &str, table: &mut FdTable) -> Option<usize> {
+        // TODO: Look the file up, then alloc an fd for it in `table`
+        todo!()
+    }
+
+    /// The registered paths, sorted
+    pub fn paths(&self) -> Vec<&str> {
+        // TODO
+        todo!()
+    }
+}
+
 // ============================================================
 // Test File implementation
 // ============================================================
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
-    use std::sync::Mutex;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Mutex, Weak};
+
+    const HINTS: Hints = &[
+        (
+            "test_close_and_reuse",
+            "alloc must take the lowest None slot before pushing a new one at the end",
+        ),
+        (
+            "test_close_invalid",
+            "closing an fd past the end of the table, or one already None, returns false",
+        ),
+        (
+            "test_count",
+            "count only the Some slots: closed fds stay in the table as None",
+        ),
+        (
+            "test_close_calls_on_close",
+            "OpenFile's Drop calls file.on_close(); close only has to drop the slot's Arc<OpenFile>",
+        ),
+        (
+            "test_dup_only_last_close_calls_on_close",
+            "dup must install a clone of the existing Arc<OpenFile>; a new OpenFile would be closed on its own",
+        ),
+        (
+            "test_get_does_not_keep_file_open",
+            "get hands out the inner Arc<dyn File>, never the Arc<OpenFile>, so only fds keep the open file alive",
+        ),
+        (
+            "test_callback_file_reads_live_state",
+            "render only when a pass starts (no snapshot), and drop the snapshot on the read that returns 0",
+        ),
+        (
+            "test_callback_file_snapshot",
+            "keep the rendered bytes and an offset between reads: rendering again mid-pass mixes two versions",
+        ),
+        (
+            "test_proc_self_fd",
+            "fds() lists the Some slots; the callback reaches the table through a Weak, so the table can still be dropped",
+        ),
+    ];
 
     struct MockFile {
         id: usize,
         write_log: Mutex<Vec<Vec<u8>>>,
+        closes: AtomicUsize,
     }
 
     impl MockFile {
@@ -111,8 +286,13 @@ mod tests {
             Arc::new(Self {
                 id,
                 write_log: Mutex::new(vec![]),
+                closes: AtomicUsize::new(0),
             })
         }
+
+        fn closes(&self) -> usize {
+            self.closes.load(Ordering::SeqCst)
+        }
     }
 
     impl File for MockFile {
@@ -124,6 +304,9 @@ mod tests {
             self.write_log.lock().unwrap().push(buf.to_vec());
             buf.len() as isize
         }
+        fn on_close(&self) {
+            self.closes.fetch_add(1, Ordering::SeqCst);
+        }
     }
 
     #[test]
@@ -161,15 +344,15 @@ mod tests {
         let fd1 = table.alloc(MockFile::new(1)); // fd=1
         let fd2 = table.alloc(MockFile::new(2)); // fd=2
 
-        assert!(table.close(fd1), "closing fd=1 should succeed");
-        assert!(
+        assert_with_hint!(table.close(fd1), "closing fd=1 should succeed");
+        assert_with_hint!(
             table.get(fd1).is_none(),
             "get should return None after close"
         );
 
         // Next allocation should reuse fd=1 (smallest free)
         let fd_new = table.alloc(MockFile::new(99));
-        assert_eq!(fd_new, fd1, "should reuse the smallest closed fd");
+        assert_eq_with_hint!(fd_new, fd1, "should reuse the smallest closed fd");
 
         let _ = (fd0, fd2);
     }
@@ -177,7 +360,7 @@ mod tests {
     #[test]
     fn test_close_invalid() {
         let mut table = FdTable::new();
-        assert!(
+        assert_with_hint!(
             !table.close(0),
             "closing non-existent fd should return false"
         );
@@ -186,14 +369,14 @@ mod tests {
     #[test]
     fn test_count() {
         let mut table = FdTable::new();
-        assert_eq!(table.count(), 0);
+        assert_eq_with_hint!(table.count(), 0);
         let fd0 = table.alloc(MockFile::new(0));
         let fd1 = table.alloc(MockFile::new(1));
-        assert_eq!(table.count(), 2);
+        assert_eq_with_hint!(table.count(), 2);
         table.close(fd0);
-        assert_eq!(table.count(), 1);
+        assert_eq_with_hint!(table.count(), 1);
         table.close(fd1);
-        assert_eq!(table.count(), 0);
+        assert_eq_with_hint!(table.count(), 0);
     }
 
     #[test]
@@ -205,4 +388,235 @@ mod tests {
         let n = f.write(b"hello");
         assert_eq!(n, 5);
     }
+
+    #[test]
+    fn test_close_calls_on_close() {
+        let mut table = FdTable::new();
+        let file = MockFile::new(0);
+        let fd = table.alloc(file.clone());
+        assert_eq_with_hint!(file.closes(), 0, "on_close before close");
+        assert!(table.close(fd));
+        assert_eq_with_hint!(file.closes(), 1, "on_close after close");
+        assert!(!table.close(fd));
+        assert_eq_with_hint!(file.closes(), 1, "a failed close must not call on_close");
+    }
+
+    #[test]
+    fn test_dup() {
+        let mut table = FdTable::new();
+        let fd = table.alloc(MockFile::new(7));
+        table.alloc(MockFile::new(8));
+        table.close(fd);
+        assert_eq!(table.dup(fd), None, "dup of a closed fd");
+        assert_eq!(table.dup(99), None, "dup of a missing fd");
+
+        let fd = table.alloc(MockFile::new(9));
+        let copy = table.dup(fd).unwrap();
+        assert_eq!(copy, 2, "dup takes the lowest free fd");
+        let mut buf = [0u8; 1];
+        table.get(copy).unwrap().read(&mut buf);
+        assert_eq!(buf[0], 9, "the dup'd fd refers to the same file");
+    }
+
+    #[test]
+    fn test_dup_only_last_close_calls_on_close() {
+        let mut table = FdTable::new();
+        let file = MockFile::new(0);
+        let fd = table.alloc(file.clone());
+        let copy = table.dup(fd).unwrap();
+        let copy2 = table.dup(copy).unwrap();
+
+        table.close(fd);
+        table.close(copy2);
+        assert_eq_with_hint!(file.closes(), 0, "an fd of the file is still open");
+        table.close(copy);
+        assert_eq_with_hint!(file.closes(), 1, "closed the last fd");
+    }
+
+    #[test]
+    fn test_alloc_twice_is_two_opens() {
+        let mut table = FdTable::new();
+        let file = MockFile::new(0);
+        let a = table.alloc(file.clone());
+        let b = table.alloc(file.clone());
+        table.close(a);
+        assert_eq!(file.closes(), 1);
+        table.close(b);
+        assert_eq!(file.closes(), 2);
+    }
+
+    #[test]
+    fn test_get_does_not_keep_file_open() {
+        let mut table = FdTable::new();
+        let file = MockFile::new(0);
+        let fd = table.alloc(file.clone());
+        let held = table.get(fd).unwrap();
+        table.close(fd);
+        assert_eq_with_hint!(file.closes(), 1, "a get() result is not an fd");
+        assert_eq!(held.write(b"x"), 1, "the Arc from get() stays usable");
+    }
+
+    #[test]
+    fn test_drop_table_closes_files() {
+        let mut table = FdTable::new();
+        let a = MockFile::new(0);
+        let b = MockFile::new(1);
+        let fd = table.alloc(a.clone());
+        table.dup(fd);
+        table.alloc(b.clone());
+        drop(table);
+        assert_eq!(a.closes(), 1, "dup'd file closed once");
+        assert_eq!(b.closes(), 1);
+    }
+
+    #[test]
+    fn test_fds() {
+        let mut table = FdTable::new();
+        assert!(table.fds().is_empty());
+        for id in 0..4 {
+            table.alloc(MockFile::new(id));
+        }
+        table.close(1);
+        table.close(3);
+        assert_eq!(table.fds(), vec![0, 2]);
+    }
+
+    // ---- Proc-style files ----
+
+    /// Read `file` to the end, a few bytes at a time.
+    fn read_to_string(file: &dyn File) -> String {
+        let mut out = Vec::new();
+        let mut buf = [0u8; 4];
+        loop {
+            let n = file.read(&mut buf);
+            assert!(n >= 0, "read failed: {n}");
+            if n == 0 {
+                return String::from_utf8(out).unwrap();
+            }
+            out.extend_from_slice(&buf[..n as usize]);
+        }
+    }
+
+    #[test]
+    fn test_callback_file_reads_live_state() {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let t = ticks.clone();
+        let file = CallbackFile::new(move || format!("ticks: {}\n", t.load(Ordering::SeqCst)));
+        assert_eq_with_hint!(read_to_string(&file), "ticks: 0\n");
+        ticks.store(1234, Ordering::SeqCst);
+        assert_eq_with_hint!(read_to_string(&file), "ticks: 1234\n");
+        assert_eq!(file.read(&mut []), 0, "an empty buffer reads nothing");
+    }
+
+    #[test]
+    fn test_callback_file_snapshot() {
+        let ticks = Arc::new(AtomicUsize::new(10));
+        let t = ticks.clone();
+        let file = CallbackFile::new(move || format!("ticks={}", t.load(Ordering::SeqCst)));
+        let mut buf = [0u8; 6];
+        assert_eq!(file.read(&mut buf), 6);
+        assert_eq!(&buf, b"ticks=");
+        ticks.store(99, Ordering::SeqCst);
+        assert_eq_with_hint!(read_to_string(&file), "10", "the rest of the first pass");
+        assert_eq_with_hint!(
+            read_to_string(&file),
+            "ticks=99",
+            "a new pass renders again"
+        );
+    }
+
+    #[test]
+    fn test_callback_file_is_read_only() {
+        let file = CallbackFile::new(|| String::from("x"));
+        assert!(file.write(b"y") < 0);
+        assert_eq!(read_to_string(&file), "x");
+    }
+
+    #[test]
+    fn test_vfs_open() {
+        let mut vfs = Vfs::new();
+        let mut table = FdTable::new();
+        vfs.register(
+            "/proc/version",
+            Arc::new(CallbackFile::new(|| "oscamp\n".into())),
+        );
+        vfs.register("/dev/mock", MockFile::new(5));
+        assert_eq!(vfs.paths(), vec!["/dev/mock", "/proc/version"]);
+        assert_eq!(vfs.open("/proc/missing", &mut table), None);
+
+        let fd = vfs.open("/proc/version", &mut table).unwrap();
+        assert_eq!(fd, 0);
+        assert_eq!(read_to_string(&*table.get(fd).unwrap()), "oscamp\n");
+        let fd = vfs.open("/dev/mock", &mut table).unwrap();
+        let mut buf = [0u8; 1];
+        table.get(fd).unwrap().read(&mut buf);
+        assert_eq!(buf[0], 5);
+    }
+
+    /// Allocator statistics, as a heap would keep them.
+    #[derive(Default)]
+    struct HeapStats {
+        total: AtomicUsize,
+        used: AtomicUsize,
+    }
+
+    #[test]
+    fn test_proc_meminfo() {
+        let stats = Arc::new(HeapStats::default());
+        stats.total.store(64 * 1024, Ordering::SeqCst);
+        let s = stats.clone();
+        let meminfo = CallbackFile::new(move || {
+            let total = s.total.load(Ordering::SeqCst);
+            let used = s.used.load(Ordering::SeqCst);
+            format!(
+                "MemTotal: {} kB\nMemUsed: {} kB\nMemFree: {} kB\n",
+                total / 1024,
+                used / 1024,
+                (total - used) / 1024
+            )
+        });
+        let mut vfs = Vfs::new();
+        vfs.register("/proc/meminfo", Arc::new(meminfo));
+        let mut table = FdTable::new();
+        let fd = vfs.open("/proc/meminfo", &mut table).unwrap();
+        let file = table.get(fd).unwrap();
+
+        assert_eq!(
+            read_to_string(&*file),
+            "MemTotal: 64 kB\nMemUsed: 0 kB\nMemFree: 64 kB\n"
+        );
+        stats.used.store(24 * 1024, Ordering::SeqCst);
+        assert_eq!(
+            read_to_string(&*file),
+            "MemTotal: 64 kB\nMemUsed: 24 kB\nMemFree: 40 kB\n"
+        );
+    }
+
+    #[test]
+    fn test_proc_self_fd() {
+        let table = Arc::new(Mutex::new(FdTable::new()));
+        let weak: Weak<Mutex<FdTable>> = Arc::downgrade(&table);
+        let self_fd = CallbackFile::new(move || match weak.upgrade() {
+            Some(table) => {
+                let fds = table.lock().unwrap().fds();
+                fds.iter().map(|fd| format!("{fd}\n")).collect()
+            }
+            None => String::new(),
+        });
+        let mut vfs = Vfs::new();
+        vfs.register("/proc/self/fd", Arc::new(self_fd));
+
+        let fd = vfs
+            .open("/proc/self/fd", &mut table.lock().unwrap())
+            .unwrap();
+        // Release the table lock before reading: rendering takes it again.
+        let file = table.lock().unwrap().get(fd).unwrap();
+        assert_eq_with_hint!(read_to_string(&*file), "0\n");
+
+        let mock = table.lock().unwrap().alloc(MockFile::new(1));
+        table.lock().unwrap().dup(mock).unwrap();
+        assert_eq_with_hint!(read_to_string(&*file), "0\n1\n2\n");
+        table.lock().unwrap().close(mock);
+        assert_eq_with_hint!(read_to_string(&*file), "0\n2\n");
+    }
 }
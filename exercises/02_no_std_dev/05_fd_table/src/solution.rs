@@ -0,0 +1,230 @@
+//! # File Descriptor Table
+//!
+//! Implement a simple file descriptor (fd) table — the core data structure
+//! for managing open files in an OS kernel.
+//!
+//! ## Background
+//!
+//! In the Linux kernel, each process has an fd table that maps integer fds to kernel file objects.
+//! User programs perform read/write/close via fds, and the kernel looks up the corresponding
+//! file object through the fd table.
+//!
+//! ```text
+//! fd table:
+//!   0 -> Stdin
+//!   1 -> Stdout
+//!   2 -> Stderr
+//!   3 -> File("/etc/passwd")
+//!   4 -> (empty)
+//!   5 -> Socket(...)
+//! ```
+//!
+//! ## Task
+//!
+//! Implement the following methods on `FdTable`:
+//!
+//! - `new()` — create an empty fd table
+//! - `alloc(file)` -> `usize` — allocate a new fd, return the fd number
+//!   - Prefer reusing the smallest closed fd number
+//!   - If no free slot, extend the table
+//! - `get(fd)` -> `Option<Arc<dyn File>>` — get the file object for an fd
+//! - `close(fd)` -> `bool` — close an fd, return whether it succeeded (false if fd doesn't exist)
+//!   - Call the file's `on_close` once its last fd is closed
+//! - `dup(fd)` -> `Option<usize>` — a new fd (the lowest free one) for the same open file
+//! - `count()` -> `usize` — return the number of currently allocated fds (excluding closed ones)
+//! - `fds()` -> `Vec<usize>` — the open fd numbers, in increasing order
+//!
+//! Then two pieces for `/proc`-style files, whose contents are computed when they are read:
+//!
+//! - `CallbackFile::new(render)` — a read-only `File` whose contents are `render()`
+//! - `Vfs` — a path -> file registry: `register(path, file)`, then `open(path, &mut table)`
+//!
+//! ## Key Concepts
+//!
+//! - Trait objects: `Arc<dyn File>`
+//! - `Vec<Option<T>>` as a sparse table
+//! - fd number reuse strategy (find smallest free slot)
+//! - `Arc` reference counting and resource release
+//! - Open file descriptions: `dup` shares one between fds, like Linux's `struct file`
+
+use std::collections::BTreeMap;
+pub(crate) use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Bad file descriptor: returned by operations a file does not support.
+const EBADF: isize = 9;
+
+/// File abstraction trait — all "files" in the kernel (regular files, pipes, sockets) implement this
+pub trait File: Send + Sync {
+    fn read(&self, buf: &mut [u8]) -> isize;
+    fn write(&self, buf: &[u8]) -> isize;
+
+    /// Called when the last fd referring to this open file is closed (or its table is dropped).
+    /// Clones of the `Arc` handed out by `FdTable::get` do not keep the file open.
+    fn on_close(&self) {}
+}
+
+/// An open file description: what `alloc` creates and `dup` shares between fds.
+///
+/// The table holds one `Arc` of it per fd, so it is dropped exactly when its last fd is closed.
+struct OpenFile {
+    file: Arc<dyn File>,
+}
+
+impl Drop for OpenFile {
+    fn drop(&mut self) {
+        self.file.on_close();
+    }
+}
+
+/// File descriptor table
+pub struct FdTable {
+    files: Vec<Option<Arc<OpenFile>>>,
+}
+
+impl FdTable {
+    /// Create an empty fd table
+    pub fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    /// Allocate a new fd, return the fd number.
+    ///
+    /// Prefers reusing the smallest closed fd number; if no free slot, appends to the end.
+    /// Each call opens the file anew: two `alloc`s of the same `Arc` get two `on_close` calls.
+    pub fn alloc(&mut self, file: Arc<dyn File>) -> usize {
+        self.install(Arc::new(OpenFile { file }))
+    }
+
+    /// Put `open` in the lowest free slot.
+    fn install(&mut self, open: Arc<OpenFile>) -> usize {
+        match self.files.iter().position(Option::is_none) {
+            Some(fd) => {
+                self.files[fd] = Some(open);
+                fd
+            }
+            None => {
+                self.files.push(Some(open));
+                self.files.len() - 1
+            }
+        }
+    }
+
+    /// Get the file object for an fd. Returns None if the fd doesn't exist or is closed.
+    pub fn get(&self, fd: usize) -> Option<Arc<dyn File>> {
+        let open = self.files.get(fd)?.as_ref()?;
+        Some(open.file.clone())
+    }
+
+    /// Duplicate an fd: the lowest free fd now refers to the same open file as `fd`.
+    /// Returns None if `fd` doesn't exist or is closed.
+    pub fn dup(&mut self, fd: usize) -> Option<usize> {
+        let open = self.files.get(fd)?.clone()?;
+        Some(self.install(open))
+    }
+
+    /// Close an fd. Returns true on success, false if the fd doesn't exist or is already closed.
+    ///
+    /// Closing the last fd of an open file drops its `OpenFile`, which calls `on_close`.
+    pub fn close(&mut self, fd: usize) -> bool {
+        match self.files.get_mut(fd) {
+            Some(slot) => slot.take().is_some(),
+            None => false,
+        }
+    }
+
+    /// Return the number of currently allocated fds (excluding closed ones)
+    pub fn count(&self) -> usize {
+        self.files.iter().filter(|f| f.is_some()).count()
+    }
+
+    /// Return the open fd numbers in increasing order
+    pub fn fds(&self) -> Vec<usize> {
+        (0..self.files.len())
+            .filter(|&fd| self.files[fd].is_some())
+            .collect()
+    }
+}
+
+impl Default for FdTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// Proc-style files
+// ============================================================
+
+/// A read-only file whose contents come from a closure, like the files under `/proc`.
+///
+/// A read that starts at offset 0 calls `render` and keeps the result as a snapshot; later reads
+/// continue through that snapshot, so one pass sees consistent contents even if the state
+/// changes meanwhile. The read that returns 0 (end of file) rewinds, and the next read renders
+/// again.
+pub struct CallbackFile {
+    render: Box<dyn Fn() -> String + Send + Sync>,
+    /// The snapshot being read and the offset into it; `None` between passes.
+    snapshot: Mutex<Option<(Vec<u8>, usize)>>,
+}
+
+impl CallbackFile {
+    pub fn new(render: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        Self {
+            render: Box::new(render),
+            snapshot: Mutex::new(None),
+        }
+    }
+}
+
+impl File for CallbackFile {
+    fn read(&self, buf: &mut [u8]) -> isize {
+        if buf.is_empty() {
+            return 0;
+        }
+        let mut snapshot = self.snapshot.lock().unwrap();
+        let (data, pos) = snapshot.get_or_insert_with(|| ((self.render)().into_bytes(), 0));
+        let n = buf.len().min(data.len() - *pos);
+        buf[..n].copy_from_slice(&data[*pos..*pos + n]);
+        *pos += n;
+        if n == 0 {
+            *snapshot = None;
+        }
+        n as isize
+    }
+
+    fn write(&self, _buf: &[u8]) -> isize {
+        -EBADF
+    }
+}
+
+/// A minimal VFS: absolute paths mapped to the files registered under them.
+#[derive(Default)]
+pub struct Vfs {
+    files: BTreeMap<String, Arc<dyn File>>,
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `file` reachable as `path`, replacing what was registered there before.
+    pub fn register(&mut self, path: &str, file: Arc<dyn File>) {
+        self.files.insert(path.to_string(), file);
+    }
+
+    /// Open `path` in `table`: a new fd for the registered file. None if nothing is registered.
+    pub fn open(&self, path: &str, table: &mut FdTable) -> Option<usize> {
+        let file = self.files.get(path)?.clone();
+        Some(table.alloc(file))
+    }
+
+    /// The registered paths, sorted
+    pub fn paths(&self) -> Vec<&str> {
+        self.files.keys().map(String::as_str).collect()
+    }
+}
+
+// ============================================================
+// Test File implementation
@@ -0,0 +1,155 @@
+//! # Stack Canaries
+//!
+//! A `memcpy` with the wrong length is all it takes to overwrite the return address of a function
+//! and hijack the control flow. In this exercise, a stack frame is simulated in a byte buffer; you
+//! protect it with a **canary** that an overflow must trample before it reaches the saved
+//! registers, detect the corruption before "returning", and finally write a bounds-checked copy
+//! that cannot overflow in the first place.
+//!
+//! ## Frame Layout
+//! ```text
+//! offset 0                 32        40         48               56
+//!        ┌─────────────────┬─────────┬──────────┬────────────────┐
+//!        │ local buffer    │ canary  │ saved fp │ return address │   (addresses grow →)
+//!        └─────────────────┴─────────┴──────────┴────────────────┘
+//!          an overflow of the buffer runs into the canary first
+//! ```
+//!
+//! ## Concepts
+//! - The prologue stores a secret canary right above the locals; the epilogue compares it before
+//!   using the return address (`__stack_chk_fail` on mismatch)
+//! - **Terminator canary**: the lowest byte is `0`. String functions stop at a NUL, so an overflow
+//!   through `strcpy` cannot write the canary back even if the attacker knows its value
+//! - Canaries detect, they do not prevent: a leaked canary plus a raw `memcpy` defeats them —
+//!   bounds checks at the write site are the real fix
+//!
+//! Like `01_mem_primitives`, only `core` is used.
+
+/// Size of the local buffer at the bottom of the frame.
+pub const BUF_LEN: usize = 32;
+pub const CANARY_OFFSET: usize = BUF_LEN;
+pub const SAVED_FP_OFFSET: usize = CANARY_OFFSET + 8;
+pub const RET_ADDR_OFFSET: usize = SAVED_FP_OFFSET + 8;
+pub const FRAME_LEN: usize = RET_ADDR_OFFSET + 8;
+
+/// The epilogue found a different canary than the prologue stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackSmashed {
+    pub expected: u64,
+    pub found: u64,
+}
+
+/// `guarded_copy` refused a write that would leave the local buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Derive a canary from a random `seed` (a real kernel uses its RNG at boot).
+///
+/// 1. Scramble the seed with the splitmix64 finalizer:
+///    `z = seed + 0x9E37_79B9_7F4A_7C15` (wrapping), then
+///    `z = (z ^ (z >> 30)) * 0xBF58_476D_1CE4_E5B9`, `z = (z ^ (z >> 27)) * 0x94D0_49BB_1331_11EB`,
+///    `z ^= z >> 31` (multiplications wrapping)
+/// 2. Clear the lowest byte: that is the byte stored at the lowest address (little-endian), the
+///    first one an overflow reaches — the terminator.
+pub fn make_canary(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    z & !0xff
+}
+
+/// A simulated stack frame.
+pub struct Frame {
+    bytes: [u8; FRAME_LEN],
+}
+
+impl Frame {
+    /// The function prologue: zeroed locals, then `canary`, `saved_fp` and `ret_addr` stored
+    /// little-endian at their offsets.
+    pub fn enter(canary: u64, saved_fp: u64, ret_addr: u64) -> Self {
+        let mut bytes = [0; FRAME_LEN];
+        bytes[CANARY_OFFSET..SAVED_FP_OFFSET].copy_from_slice(&canary.to_le_bytes());
+        bytes[SAVED_FP_OFFSET..RET_ADDR_OFFSET].copy_from_slice(&saved_fp.to_le_bytes());
+        bytes[RET_ADDR_OFFSET..].copy_from_slice(&ret_addr.to_le_bytes());
+        Self { bytes }
+    }
+
+    pub fn bytes(&self) -> &[u8; FRAME_LEN] {
+        &self.bytes
+    }
+
+    fn read_u64(&self, offset: usize) -> u64 {
+        let mut raw = [0; 8];
+        raw.copy_from_slice(&self.bytes[offset..offset + 8]);
+        u64::from_le_bytes(raw)
+    }
+
+    pub fn canary(&self) -> u64 {
+        self.read_u64(CANARY_OFFSET)
+    }
+
+    pub fn saved_fp(&self) -> u64 {
+        self.read_u64(SAVED_FP_OFFSET)
+    }
+
+    pub fn ret_addr(&self) -> u64 {
+        self.read_u64(RET_ADDR_OFFSET)
+    }
+
+    /// The local buffer.
+    pub fn buf(&self) -> &[u8] {
+        &self.bytes[..BUF_LEN]
+    }
+
+    /// The bug: `memcpy(buf + offset, data, data.len())` with no check against `BUF_LEN`.
+    /// (Writes past the whole frame are outside the simulation and panic.)
+    pub fn unchecked_copy(&mut self, offset: usize, data: &[u8]) {
+        for (i, &b) in data.iter().enumerate() {
+            self.bytes[offset + i] = b;
+        }
+    }
+
+    /// The other classic: `strcpy(buf, src)` copies up to and including the first NUL of `src`
+    /// (or all of `src` if it has none), however long that is.
+    pub fn unchecked_strcpy(&mut self, src: &[u8]) {
+        for (i, &b) in src.iter().enumerate() {
+            self.bytes[i] = b;
+            if b == 0 {
+                break;
+            }
+        }
+    }
+
+    /// The epilogue: compare the canary in the frame with `expected`.
+    ///
+    /// Equal: return the return address (the function may now return there).
+    /// Different: `Err(StackSmashed { expected, found })` — the return address must not be used.
+    pub fn check(&self, expected: u64) -> Result<u64, StackSmashed> {
+        let found = self.canary();
+        if found != expected {
+            return Err(StackSmashed { expected, found });
+        }
+        Ok(self.ret_addr())
+    }
+
+    /// The fix: copy `data` to `buf + offset` only if it fits entirely inside the local buffer;
+    /// otherwise write nothing and return `Err(Overflow { offset, len })`.
+    ///
+    /// Careful: `offset + data.len()` itself may overflow (`checked_add`).
+    pub fn guarded_copy(&mut self, offset: usize, data: &[u8]) -> Result<(), Overflow> {
+        let err = Overflow {
+            offset,
+            len: data.len(),
+        };
+        let end = offset.checked_add(data.len()).ok_or(err)?;
+        if end > BUF_LEN {
+            return Err(err);
+        }
+        self.bytes[offset..end].copy_from_slice(data);
+        Ok(())
+    }
+}
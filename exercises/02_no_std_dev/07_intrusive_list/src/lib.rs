@@ -0,0 +1,488 @@
+//! # Intrusive Doubly Linked List
+//!
+//! Kernel run queues and wait queues do not allocate list nodes: the links live **inside** the
+//! object (`struct task { ...; struct list_head run_list; }`), so a task can move from one queue
+//! to another, or be removed from the middle of one, in O(1) and without allocating.
+//! In this exercise, you implement such a list over raw pointers.
+//!
+//! ```text
+//!  List { head, tail, len }
+//!    │                                          │
+//!    ▼                                          ▼
+//!  Task 1               Task 2                Task 3
+//!  ┌──────────┐         ┌──────────┐          ┌──────────┐
+//!  │ id       │         │ id       │          │ id       │
+//!  │ node ────┼── next ─► node ────┼── next ──► node     │
+//!  │          ◄── prev ─┼          ◄── prev ──┼          │
+//!  └──────────┘         └──────────┘          └──────────┘
+//! ```
+//!
+//! ## Concepts
+//! - `ListNode` is embedded in the user's struct; the list only ever sees `*mut ListNode`
+//! - `container_of!` goes back from the node to the struct that contains it (pointer minus the
+//!   field offset)
+//! - Link/unlink touch only the neighbours: O(1), no allocation, no search
+//! - Splicing a whole list onto another is O(1) as well
+//! - The list does not own its nodes: the caller keeps them alive (and in place) while linked,
+//!   which is why the operations are `unsafe`
+//!
+//! Only `core` is used.
+
+#![cfg_attr(not(test), no_std)]
+
+use core::ptr::null_mut;
+
+/// Pointer to the `$Container` whose field `$field` is at `$ptr`.
+///
+/// # Safety
+/// `$ptr` must point to the `$field` of a live `$Container`. Use inside `unsafe`.
+#[macro_export]
+macro_rules! container_of {
+    ($ptr:expr, $Container:ty, $field:ident) => {
+        ($ptr as *mut u8).sub(core::mem::offset_of!($Container, $field)) as *mut $Container
+    };
+}
+
+/// Links embedded in a list element.
+#[derive(Debug)]
+pub struct ListNode {
+    prev: *mut ListNode,
+    next: *mut ListNode,
+    linked: bool,
+}
+
+impl ListNode {
+    pub const fn new() -> Self {
+        Self {
+            prev: null_mut(),
+            next: null_mut(),
+            linked: false,
+        }
+    }
+
+    /// Whether this node is currently in a list.
+    pub fn is_linked(&self) -> bool {
+        self.linked
+    }
+
+    pub fn next(&self) -> *mut ListNode {
+        self.next
+    }
+
+    pub fn prev(&self) -> *mut ListNode {
+        self.prev
+    }
+}
+
+impl Default for ListNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A doubly linked list of `ListNode`s; `head.prev` and `tail.next` are null.
+///
+/// # Safety (all `unsafe` methods)
+/// Node pointers must be valid, and nodes must not move or be freed while linked. A node can be
+/// in at most one list at a time; pushing requires an unlinked node, `unlink` a node of *this*
+/// list.
+pub struct List {
+    head: *mut ListNode,
+    tail: *mut ListNode,
+    len: usize,
+}
+
+impl List {
+    pub const fn new() -> Self {
+        Self {
+            head: null_mut(),
+            tail: null_mut(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<*mut ListNode> {
+        (!self.head.is_null()).then_some(self.head)
+    }
+
+    pub fn back(&self) -> Option<*mut ListNode> {
+        (!self.tail.is_null()).then_some(self.tail)
+    }
+
+    /// Append `node` at the tail.
+    ///
+    /// `node.prev = tail`, `node.next = null`; then the old tail's `next` (or `head`, if the list
+    /// was empty) points to `node`, and `tail = node`. Mark it linked, `len += 1`.
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn push_back(&mut self, node: *mut ListNode) {
+        assert!(!(*node).linked, "node is already in a list");
+        // TODO
+        todo!()
+    }
+
+    /// Insert `node` at the head (mirror image of `push_back`).
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn push_front(&mut self, node: *mut ListNode) {
+        assert!(!(*node).linked, "node is already in a list");
+        // TODO
+        todo!()
+    }
+
+    /// Insert `node` right after `at` (a node of this list).
+    ///
+    /// Four links change: `node.prev`, `node.next`, `at.next`, and the `prev` of the node after
+    /// `at` — or `tail`, if `at` was the tail.
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn insert_after(&mut self, at: *mut ListNode, node: *mut ListNode) {
+        assert!(!(*node).linked, "node is already in a list");
+        // TODO
+        todo!()
+    }
+
+    /// Remove `node` (a node of this list) from wherever it is.
+    ///
+    /// Its predecessor's `next` (or `head`) skips it, its successor's `prev` (or `tail`) too.
+    /// Reset the node (null links, not linked), `len -= 1`.
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn unlink(&mut self, node: *mut ListNode) {
+        assert!((*node).linked, "node is not in a list");
+        // TODO
+        todo!()
+    }
+
+    /// Remove and return the head.
+    pub fn pop_front(&mut self) -> Option<*mut ListNode> {
+        let node = self.front()?;
+        // SAFETY: the head is a node of this list.
+        unsafe { self.unlink(node) };
+        Some(node)
+    }
+
+    /// Move all nodes of `other` to the end of this list, in O(1); `other` is left empty.
+    ///
+    /// Connect `self.tail` and `other.head` (if both lists are non-empty), take over `other.tail`
+    /// (and `other.head` if `self` was empty), add the lengths, reset `other`.
+    pub fn splice_back(&mut self, other: &mut List) {
+        // TODO
+        todo!()
+    }
+
+    /// Nodes from head to tail.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            next: self.head,
+            _list: self,
+        }
+    }
+
+    /// A cursor on the head, able to remove nodes while walking.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_> {
+        CursorMut {
+            current: self.head,
+            list: self,
+        }
+    }
+}
+
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a> {
+    next: *mut ListNode,
+    _list: &'a List,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = *mut ListNode;
+
+    fn next(&mut self) -> Option<*mut ListNode> {
+        if self.next.is_null() {
+            return None;
+        }
+        let node = self.next;
+        // SAFETY: nodes of a borrowed list are live.
+        self.next = unsafe { (*node).next };
+        Some(node)
+    }
+}
+
+pub struct CursorMut<'a> {
+    current: *mut ListNode,
+    list: &'a mut List,
+}
+
+impl CursorMut<'_> {
+    /// The node under the cursor; `None` once it has walked past the tail.
+    pub fn current(&self) -> Option<*mut ListNode> {
+        (!self.current.is_null()).then_some(self.current)
+    }
+
+    pub fn move_next(&mut self) {
+        if !self.current.is_null() {
+            // SAFETY: the current node is in the list.
+            self.current = unsafe { (*self.current).next };
+        }
+    }
+
+    /// Unlink the current node and return it; the cursor moves on to the node that followed it.
+    pub fn remove_current(&mut self) -> Option<*mut ListNode> {
+        // TODO: remember `next` *before* unlinking (unlink resets the node's links)
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use core::ptr::addr_of_mut;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_container_of",
+            "subtract the offset of the node field (core::mem::offset_of!) from the node pointer to get back to the struct",
+        ),
+        (
+            "test_unlink_middle_head_and_tail",
+            "unlinking the first or last node must also update the list's head or tail, and reset the node's prev/next to null",
+        ),
+        (
+            "test_splice_back",
+            "splice moves the whole other list in O(1): link its head after our tail, take its tail, then empty it",
+        ),
+        (
+            "test_cursor_remove_while_walking",
+            "remember the next node before unlinking the current one, and let the cursor move there",
+        ),
+    ];
+
+    struct Task {
+        id: u32,
+        node: ListNode,
+    }
+
+    /// Tasks live on the heap and are only accessed through raw pointers while linked.
+    fn task(id: u32) -> *mut Task {
+        Box::into_raw(Box::new(Task {
+            id,
+            node: ListNode::new(),
+        }))
+    }
+
+    fn node(t: *mut Task) -> *mut ListNode {
+        unsafe { addr_of_mut!((*t).node) }
+    }
+
+    fn tasks(ids: impl IntoIterator<Item = u32>) -> Vec<*mut Task> {
+        ids.into_iter().map(task).collect()
+    }
+
+    fn free(tasks: Vec<*mut Task>) {
+        for t in tasks {
+            unsafe {
+                assert!(!(*t).node.is_linked(), "freeing a linked task");
+                drop(Box::from_raw(t));
+            }
+        }
+    }
+
+    /// Ids from head to tail, checking the `prev` links and the length on the way.
+    fn ids(list: &List) -> Vec<u32> {
+        let mut out = vec![];
+        let mut prev: *mut ListNode = null_mut();
+        for n in list.iter() {
+            unsafe {
+                assert_eq!((*n).prev(), prev, "broken prev link");
+                out.push((*container_of!(n, Task, node)).id);
+            }
+            prev = n;
+        }
+        assert_eq!(
+            list.back().unwrap_or(null_mut()),
+            prev,
+            "tail is not the last node"
+        );
+        assert_eq!(list.len(), out.len());
+        out
+    }
+
+    fn clear(list: &mut List) {
+        while list.pop_front().is_some() {}
+    }
+
+    #[test]
+    fn test_push_back_and_front() {
+        let ts = tasks(1..=4);
+        let mut list = List::new();
+        assert!(list.is_empty() && list.front().is_none());
+        unsafe {
+            list.push_back(node(ts[1]));
+            list.push_back(node(ts[2]));
+            list.push_front(node(ts[0]));
+            list.push_back(node(ts[3]));
+        }
+        assert_eq!(ids(&list), [1, 2, 3, 4]);
+        assert!(unsafe { (*ts[2]).node.is_linked() });
+        clear(&mut list);
+        free(ts);
+    }
+
+    #[test]
+    fn test_container_of() {
+        let t = task(42);
+        unsafe {
+            let back = container_of!(node(t), Task, node);
+            assert_eq_with_hint!(back, t);
+            assert_eq_with_hint!((*back).id, 42);
+        }
+        free(vec![t]);
+    }
+
+    #[test]
+    fn test_unlink_middle_head_and_tail() {
+        let ts = tasks(1..=5);
+        let mut list = List::new();
+        for &t in &ts {
+            unsafe { list.push_back(node(t)) };
+        }
+        unsafe {
+            list.unlink(node(ts[2]));
+            assert_eq_with_hint!(ids(&list), [1, 2, 4, 5]);
+            let n = &(*ts[2]).node;
+            assert_with_hint!(!n.is_linked() && n.next().is_null() && n.prev().is_null());
+
+            list.unlink(node(ts[0]));
+            list.unlink(node(ts[4]));
+            assert_eq_with_hint!(ids(&list), [2, 4]);
+            list.unlink(node(ts[1]));
+            list.unlink(node(ts[3]));
+        }
+        assert_eq_with_hint!(ids(&list), []);
+        assert_with_hint!(list.front().is_none() && list.back().is_none());
+        free(ts);
+    }
+
+    #[test]
+    fn test_insert_after() {
+        let ts = tasks(1..=4);
+        let mut list = List::new();
+        unsafe {
+            list.push_back(node(ts[0]));
+            list.insert_after(node(ts[0]), node(ts[3])); // after the tail
+            list.insert_after(node(ts[0]), node(ts[1])); // in the middle
+            list.insert_after(node(ts[1]), node(ts[2]));
+        }
+        assert_eq!(ids(&list), [1, 2, 3, 4]);
+        clear(&mut list);
+        free(ts);
+    }
+
+    #[test]
+    fn test_relink_after_unlink() {
+        let ts = tasks(1..=2);
+        let mut list = List::new();
+        unsafe {
+            list.push_back(node(ts[0]));
+            list.push_back(node(ts[1]));
+            list.unlink(node(ts[0]));
+            list.push_back(node(ts[0]));
+        }
+        assert_eq!(ids(&list), [2, 1]);
+        clear(&mut list);
+        free(ts);
+    }
+
+    #[test]
+    fn test_move_between_queues() {
+        // A run queue hands its head to a wait queue, then gets it back.
+        let ts = tasks(1..=3);
+        let (mut run, mut wait) = (List::new(), List::new());
+        for &t in &ts {
+            unsafe { run.push_back(node(t)) };
+        }
+        let blocked = run.pop_front().unwrap();
+        unsafe { wait.push_back(blocked) };
+        assert_eq!((ids(&run), ids(&wait)), (vec![2, 3], vec![1]));
+        let woken = wait.pop_front().unwrap();
+        unsafe { run.push_back(woken) };
+        assert_eq!((ids(&run), ids(&wait)), (vec![2, 3, 1], vec![]));
+        clear(&mut run);
+        free(ts);
+    }
+
+    #[test]
+    fn test_splice_back() {
+        let ts = tasks(1..=5);
+        let (mut a, mut b, mut c) = (List::new(), List::new(), List::new());
+        unsafe {
+            a.push_back(node(ts[0]));
+            a.push_back(node(ts[1]));
+            b.push_back(node(ts[2]));
+            b.push_back(node(ts[3]));
+            c.push_back(node(ts[4]));
+        }
+        a.splice_back(&mut b);
+        assert_eq_with_hint!((ids(&a), ids(&b)), (vec![1, 2, 3, 4], vec![]));
+        a.splice_back(&mut b); // splicing an empty list changes nothing
+        assert_eq_with_hint!(ids(&a), [1, 2, 3, 4]);
+        b.splice_back(&mut a); // into an empty list
+        b.splice_back(&mut c);
+        assert_eq_with_hint!(
+            (ids(&a), ids(&b), ids(&c)),
+            (vec![], vec![1, 2, 3, 4, 5], vec![])
+        );
+        clear(&mut b);
+        free(ts);
+    }
+
+    #[test]
+    fn test_cursor_remove_while_walking() {
+        let ts = tasks(1..=6);
+        let mut list = List::new();
+        for &t in &ts {
+            unsafe { list.push_back(node(t)) };
+        }
+        let mut removed = vec![];
+        let mut cursor = list.cursor_front_mut();
+        while let Some(n) = cursor.current() {
+            let id = unsafe { (*container_of!(n, Task, node)).id };
+            if id % 2 == 0 {
+                assert_eq_with_hint!(cursor.remove_current(), Some(n));
+                removed.push(id);
+            } else {
+                cursor.move_next();
+            }
+        }
+        assert_eq_with_hint!(cursor.remove_current(), None);
+        assert_eq_with_hint!(removed, [2, 4, 6]);
+        assert_eq_with_hint!(ids(&list), [1, 3, 5]);
+        clear(&mut list);
+        free(ts);
+    }
+}
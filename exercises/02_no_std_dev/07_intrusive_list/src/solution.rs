@@ -0,0 +1,301 @@
+//! # Intrusive Doubly Linked List
+//!
+//! Kernel run queues and wait queues do not allocate list nodes: the links live **inside** the
+//! object (`struct task { ...; struct list_head run_list; }`), so a task can move from one queue
+//! to another, or be removed from the middle of one, in O(1) and without allocating.
+//! In this exercise, you implement such a list over raw pointers.
+//!
+//! ```text
+//!  List { head, tail, len }
+//!    │                                          │
+//!    ▼                                          ▼
+//!  Task 1               Task 2                Task 3
+//!  ┌──────────┐         ┌──────────┐          ┌──────────┐
+//!  │ id       │         │ id       │          │ id       │
+//!  │ node ────┼── next ─► node ────┼── next ──► node     │
+//!  │          ◄── prev ─┼          ◄── prev ──┼          │
+//!  └──────────┘         └──────────┘          └──────────┘
+//! ```
+//!
+//! ## Concepts
+//! - `ListNode` is embedded in the user's struct; the list only ever sees `*mut ListNode`
+//! - `container_of!` goes back from the node to the struct that contains it (pointer minus the
+//!   field offset)
+//! - Link/unlink touch only the neighbours: O(1), no allocation, no search
+//! - Splicing a whole list onto another is O(1) as well
+//! - The list does not own its nodes: the caller keeps them alive (and in place) while linked,
+//!   which is why the operations are `unsafe`
+//!
+//! Only `core` is used.
+
+pub(crate) use core::ptr::null_mut;
+
+/// Pointer to the `$Container` whose field `$field` is at `$ptr`.
+///
+/// # Safety
+/// `$ptr` must point to the `$field` of a live `$Container`. Use inside `unsafe`.
+#[allow(unused_macros)]
+macro_rules! container_of {
+    ($ptr:expr, $Container:ty, $field:ident) => {
+        ($ptr as *mut u8).sub(core::mem::offset_of!($Container, $field)) as *mut $Container
+    };
+}
+
+/// Links embedded in a list element.
+#[derive(Debug)]
+pub struct ListNode {
+    prev: *mut ListNode,
+    next: *mut ListNode,
+    linked: bool,
+}
+
+impl ListNode {
+    pub const fn new() -> Self {
+        Self {
+            prev: null_mut(),
+            next: null_mut(),
+            linked: false,
+        }
+    }
+
+    /// Whether this node is currently in a list.
+    pub fn is_linked(&self) -> bool {
+        self.linked
+    }
+
+    pub fn next(&self) -> *mut ListNode {
+        self.next
+    }
+
+    pub fn prev(&self) -> *mut ListNode {
+        self.prev
+    }
+}
+
+impl Default for ListNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A doubly linked list of `ListNode`s; `head.prev` and `tail.next` are null.
+///
+/// # Safety (all `unsafe` methods)
+/// Node pointers must be valid, and nodes must not move or be freed while linked. A node can be
+/// in at most one list at a time; pushing requires an unlinked node, `unlink` a node of *this*
+/// list.
+pub struct List {
+    head: *mut ListNode,
+    tail: *mut ListNode,
+    len: usize,
+}
+
+impl List {
+    pub const fn new() -> Self {
+        Self {
+            head: null_mut(),
+            tail: null_mut(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<*mut ListNode> {
+        (!self.head.is_null()).then_some(self.head)
+    }
+
+    pub fn back(&self) -> Option<*mut ListNode> {
+        (!self.tail.is_null()).then_some(self.tail)
+    }
+
+    /// Append `node` at the tail.
+    ///
+    /// `node.prev = tail`, `node.next = null`; then the old tail's `next` (or `head`, if the list
+    /// was empty) points to `node`, and `tail = node`. Mark it linked, `len += 1`.
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn push_back(&mut self, node: *mut ListNode) {
+        assert!(!(*node).linked, "node is already in a list");
+        (*node).prev = self.tail;
+        (*node).next = null_mut();
+        if self.tail.is_null() {
+            self.head = node;
+        } else {
+            (*self.tail).next = node;
+        }
+        self.tail = node;
+        (*node).linked = true;
+        self.len += 1;
+    }
+
+    /// Insert `node` at the head (mirror image of `push_back`).
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn push_front(&mut self, node: *mut ListNode) {
+        assert!(!(*node).linked, "node is already in a list");
+        (*node).prev = null_mut();
+        (*node).next = self.head;
+        if self.head.is_null() {
+            self.tail = node;
+        } else {
+            (*self.head).prev = node;
+        }
+        self.head = node;
+        (*node).linked = true;
+        self.len += 1;
+    }
+
+    /// Insert `node` right after `at` (a node of this list).
+    ///
+    /// Four links change: `node.prev`, `node.next`, `at.next`, and the `prev` of the node after
+    /// `at` — or `tail`, if `at` was the tail.
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn insert_after(&mut self, at: *mut ListNode, node: *mut ListNode) {
+        assert!(!(*node).linked, "node is already in a list");
+        let next = (*at).next;
+        (*node).prev = at;
+        (*node).next = next;
+        (*at).next = node;
+        if next.is_null() {
+            self.tail = node;
+        } else {
+            (*next).prev = node;
+        }
+        (*node).linked = true;
+        self.len += 1;
+    }
+
+    /// Remove `node` (a node of this list) from wherever it is.
+    ///
+    /// Its predecessor's `next` (or `head`) skips it, its successor's `prev` (or `tail`) too.
+    /// Reset the node (null links, not linked), `len -= 1`.
+    ///
+    /// # Safety
+    /// See [`List`].
+    pub unsafe fn unlink(&mut self, node: *mut ListNode) {
+        assert!((*node).linked, "node is not in a list");
+        let (prev, next) = ((*node).prev, (*node).next);
+        if prev.is_null() {
+            self.head = next;
+        } else {
+            (*prev).next = next;
+        }
+        if next.is_null() {
+            self.tail = prev;
+        } else {
+            (*next).prev = prev;
+        }
+        *node = ListNode::new();
+        self.len -= 1;
+    }
+
+    /// Remove and return the head.
+    pub fn pop_front(&mut self) -> Option<*mut ListNode> {
+        let node = self.front()?;
+        // SAFETY: the head is a node of this list.
+        unsafe { self.unlink(node) };
+        Some(node)
+    }
+
+    /// Move all nodes of `other` to the end of this list, in O(1); `other` is left empty.
+    ///
+    /// Connect `self.tail` and `other.head` (if both lists are non-empty), take over `other.tail`
+    /// (and `other.head` if `self` was empty), add the lengths, reset `other`.
+    pub fn splice_back(&mut self, other: &mut List) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            self.head = other.head;
+        } else {
+            unsafe {
+                (*self.tail).next = other.head;
+                (*other.head).prev = self.tail;
+            }
+        }
+        self.tail = other.tail;
+        self.len += other.len;
+        *other = List::new();
+    }
+
+    /// Nodes from head to tail.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            next: self.head,
+            _list: self,
+        }
+    }
+
+    /// A cursor on the head, able to remove nodes while walking.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_> {
+        CursorMut {
+            current: self.head,
+            list: self,
+        }
+    }
+}
+
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a> {
+    next: *mut ListNode,
+    _list: &'a List,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = *mut ListNode;
+
+    fn next(&mut self) -> Option<*mut ListNode> {
+        if self.next.is_null() {
+            return None;
+        }
+        let node = self.next;
+        // SAFETY: nodes of a borrowed list are live.
+        self.next = unsafe { (*node).next };
+        Some(node)
+    }
+}
+
+pub struct CursorMut<'a> {
+    current: *mut ListNode,
+    list: &'a mut List,
+}
+
+impl CursorMut<'_> {
+    /// The node under the cursor; `None` once it has walked past the tail.
+    pub fn current(&self) -> Option<*mut ListNode> {
+        (!self.current.is_null()).then_some(self.current)
+    }
+
+    pub fn move_next(&mut self) {
+        if !self.current.is_null() {
+            // SAFETY: the current node is in the list.
+            self.current = unsafe { (*self.current).next };
+        }
+    }
+
+    /// Unlink the current node and return it; the cursor moves on to the node that followed it.
+    pub fn remove_current(&mut self) -> Option<*mut ListNode> {
+        let node = self.current()?;
+        unsafe {
+            self.current = (*node).next;
+            self.list.unlink(node);
+        }
+        Some(node)
+    }
+}
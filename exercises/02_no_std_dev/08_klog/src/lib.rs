@@ -0,0 +1,679 @@
+//! # Kernel Log Ring Buffer
+//!
+//! A kernel cannot `println!`: there may be no console yet, and the code that logs may run in an
+//! interrupt handler, holding a spinlock, or on several CPUs at once. Like Linux's `printk`, it
+//! formats the message into a fixed-size record and stores it in a ring buffer in memory; a
+//! reader (`dmesg`, a console driver) drains the records later. In this exercise, you build that
+//! ring: writers never take a lock and never wait for the reader, and when the reader falls
+//! behind, the oldest records are overwritten and counted as lost. The one wait a writer can hit
+//! is short and bounded, see "When a writer waits" below.
+//!
+//! ## Concepts
+//! - Formatting without an allocator: `core::fmt::Write` into a fixed buffer (`FixedBuf`), cut
+//!   at a `char` boundary when the message does not fit
+//! - Runtime level filtering: records less severe than `level()` are dropped before formatting
+//! - Claiming a sequence number with `fetch_add` on `tail`: it fixes the record's order and its
+//!   slot, `seq % N`
+//! - Per-slot stamps as a seqlock: odd while a writer fills the slot, even once committed; the
+//!   reader copies a record and only trusts it if the stamp did not change meanwhile
+//! - Loss accounting: a reader more than `N` records behind skips to `tail - N`, and a record
+//!   overwritten while (or before) it is read is counted instead of returned
+//!
+//! ## Slot stamps
+//! ```text
+//! stamp = 0            never written
+//! stamp = 2 * seq + 1  the writer of `seq` is filling the slot
+//! stamp = 2 * seq + 2  the record `seq` is committed
+//! ```
+//! Every state of a later record is larger than every state of an earlier one, so comparing
+//! stamps tells "not written yet", "ready" and "overwritten by a newer lap" apart.
+//!
+//! ## When a writer waits
+//! A writer never waits for the reader, nor for the writers of other slots. It only waits when
+//! the writer of its slot one lap earlier (record `seq - N`) is still copying its message, that
+//! is, when `N` more records were claimed during that copy. Taking the slot anyway would mix the
+//! two messages, so it spins until that writer commits: at most the rest of one copy of
+//! `MSG_MAX` bytes, as long as that writer keeps running. An interrupt handler that preempts it
+//! on the same CPU and logs `N` records itself would spin forever, so size `N` well above what a
+//! handler logs.
+//!
+//! The record bytes are atomics accessed with `Relaxed`, so a reader racing with a writer sees
+//! torn data (and then a changed stamp) rather than undefined behaviour. Only `core` is used.
+
+#![cfg_attr(not(test), no_std)]
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+/// Severity of a record; lower is more severe.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl Level {
+    pub fn from_u8(v: u8) -> Option<Level> {
+        match v {
+            0 => Some(Level::Error),
+            1 => Some(Level::Warn),
+            2 => Some(Level::Info),
+            3 => Some(Level::Debug),
+            4 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+/// Longest message a record holds, in bytes; longer ones are truncated.
+pub const MSG_MAX: usize = 120;
+
+/// A `core::fmt::Write` target of `N` bytes on the stack. Text that does not fit is dropped,
+/// cut at a `char` boundary so the contents stay valid UTF-8; writing never fails.
+pub struct FixedBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> FixedBuf<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub fn as_str(&self) -> &str {
+        // `write_str` only ever copies whole `char`s.
+        core::str::from_utf8(self.as_bytes()).unwrap()
+    }
+
+    /// Whether some text was dropped for lack of room.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<const N: usize> Default for FixedBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Write for FixedBuf<N> {
+    /// Copy as much of `s` as fits. If not all of it does, back off to the last `char`
+    /// boundary at or before the room left (`s.is_char_boundary`) and set `truncated`.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // TODO
+        todo!()
+    }
+}
+
+/// A record copied out of the ring by `drain`.
+#[derive(Clone)]
+pub struct Record {
+    /// Position in the log: records are numbered from 0 in the order they claimed a slot.
+    pub seq: u64,
+    pub level: Level,
+    len: usize,
+    msg: [u8; MSG_MAX],
+}
+
+impl Record {
+    pub fn msg(&self) -> &str {
+        core::str::from_utf8(&self.msg[..self.len]).unwrap_or("<torn>")
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("seq", &self.seq)
+            .field("level", &self.level)
+            .field("msg", &self.msg())
+            .finish()
+    }
+}
+
+/// `dmesg` style: `[    42] INFO  booted in 3 ms`.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:>6}] {:<5} {}", self.seq, self.level, self.msg())
+    }
+}
+
+const fn writing(seq: u64) -> u64 {
+    2 * seq + 1
+}
+
+const fn committed(seq: u64) -> u64 {
+    2 * seq + 2
+}
+
+struct Slot {
+    stamp: AtomicU64,
+    level: AtomicU8,
+    len: AtomicUsize,
+    msg: [AtomicU8; MSG_MAX],
+}
+
+/// What `Slot::read` found for the record it was asked for.
+enum Read {
+    Ready(Record),
+    /// Its writer has not committed it yet.
+    Pending,
+    /// A record one or more laps later took the slot.
+    Overwritten,
+}
+
+impl Slot {
+    const fn new() -> Self {
+        Self {
+            stamp: AtomicU64::new(0),
+            level: AtomicU8::new(0),
+            len: AtomicUsize::new(0),
+            msg: [const { AtomicU8::new(0) }; MSG_MAX],
+        }
+    }
+
+    /// Store record `seq` in this slot.
+    ///
+    /// 1. Open the slot: `compare_exchange` the stamp from its current value to `writing(seq)`.
+    ///    If the stamp is already `writing(seq)` or larger, a record a lap later owns the slot:
+    ///    give up, the record is lost. If it is odd (smaller), the writer a lap earlier is still
+    ///    filling it: spin until it commits (the bounded wait of "When a writer waits").
+    /// 2. `fence(Release)`, so a reader that sees any of the bytes below also sees the odd stamp.
+    /// 3. Store level, length and bytes (`Relaxed`).
+    /// 4. Commit: store `committed(seq)` with `Release`.
+    fn write(&self, seq: u64, level: Level, msg: &[u8]) {
+        // TODO
+        todo!()
+    }
+
+    /// Copy record `seq` out of this slot.
+    ///
+    /// 1. Load the stamp (`Acquire`): larger than `committed(seq)` means `Overwritten`, anything
+    ///    else but `committed(seq)` means `Pending`.
+    /// 2. Copy level, length and bytes (`Relaxed`).
+    /// 3. `fence(Acquire)`, then load the stamp again: if it changed, a writer of a later lap
+    ///    was in the slot during the copy, so the copy may be torn: `Overwritten`.
+    fn read(&self, seq: u64) -> Read {
+        // TODO
+        todo!()
+    }
+}
+
+/// Outcome of one `drain`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Drained {
+    /// Records handed to the callback.
+    pub records: usize,
+    /// Records skipped because they were overwritten before they could be read.
+    pub lost: u64,
+}
+
+/// Multi-writer log ring of `N` records (`N > 0`).
+///
+/// Writers (`log`) are lock-free with respect to the reader and to each other, except that a
+/// writer waits for the rest of one copy if the writer of the same slot one lap earlier has
+/// not committed yet (see the module docs). One `drain` runs at a time; a second one waits for
+/// the first.
+pub struct KLog<const N: usize> {
+    slots: [Slot; N],
+    /// Next sequence number to hand out: also the number of records logged so far.
+    tail: AtomicU64,
+    /// Next sequence number `drain` will read.
+    head: AtomicU64,
+    /// Records lost over all drains.
+    lost: AtomicU64,
+    /// The most verbose level still recorded, as `Level as u8`.
+    max_level: AtomicU8,
+    /// Held by the running `drain`.
+    reading: AtomicBool,
+}
+
+impl<const N: usize> KLog<N> {
+    /// An empty log recording `Level::Info` and more severe.
+    pub const fn new() -> Self {
+        assert!(N > 0, "a log needs at least one slot");
+        Self {
+            slots: [const { Slot::new() }; N],
+            tail: AtomicU64::new(0),
+            head: AtomicU64::new(0),
+            lost: AtomicU64::new(0),
+            max_level: AtomicU8::new(Level::Info as u8),
+            reading: AtomicBool::new(false),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Record `level` and everything more severe from now on.
+    pub fn set_level(&self, level: Level) {
+        self.max_level.store(level as u8, Ordering::Relaxed);
+    }
+
+    pub fn level(&self) -> Level {
+        Level::from_u8(self.max_level.load(Ordering::Relaxed)).unwrap()
+    }
+
+    /// Whether a record of `level` would be kept.
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= self.level()
+    }
+
+    /// Format `args` and append it as a record; returns its sequence number, or `None` if
+    /// `level` is filtered out (then nothing is formatted).
+    ///
+    /// 1. Check `enabled(level)`.
+    /// 2. Format into a `FixedBuf<MSG_MAX>` on the stack, before claiming anything, so the slot
+    ///    is held open only for the copy.
+    /// 3. `seq = tail.fetch_add(1)`; write the record into slot `seq % N`.
+    pub fn log(&self, level: Level, args: fmt::Arguments<'_>) -> Option<u64> {
+        // TODO
+        todo!()
+    }
+
+    /// Hand every record not read yet to `f`, oldest first, and report how many were lost.
+    ///
+    /// Starting at `head`, loop while `head < tail`:
+    /// - If `tail - head > N`, the records before `tail - N` have been overwritten: count them
+    ///   as lost and jump `head` to `tail - N` (reading their slots one by one would find them
+    ///   overwritten too, just more slowly).
+    /// - Read slot `head % N`: `Ready` goes to `f`, `Overwritten` is one more lost record, and
+    ///   either way `head += 1`. `Pending` stops the drain: records come out in order, so a
+    ///   later drain picks up from there.
+    ///
+    /// Store `head` back and add the lost records to `lost()`.
+    pub fn drain(&self, mut f: impl FnMut(&Record)) -> Drained {
+        // TODO
+        todo!()
+    }
+
+    /// Records logged so far (not counting filtered ones).
+    pub fn written(&self) -> u64 {
+        self.tail.load(Ordering::Relaxed)
+    }
+
+    /// Records lost over all drains so far.
+    pub fn lost(&self) -> u64 {
+        self.lost.load(Ordering::Relaxed)
+    }
+}
+
+impl<const N: usize> Default for KLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Slots in the global `KLOG`.
+pub const LOG_CAPACITY: usize = 256;
+
+/// The kernel log written by `log!` and friends.
+pub static KLOG: KLog<LOG_CAPACITY> = KLog::new();
+
+// With `--features solutions` the reference solution defines these macros instead.
+
+/// `log!(level, "fmt", args...)`: append a record to `KLOG`; evaluates to its sequence number,
+/// or `None` if `level` is filtered out.
+#[cfg(not(feature = "solutions"))]
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::KLOG.log($level, format_args!($($arg)+))
+    };
+}
+
+#[cfg(not(feature = "solutions"))]
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => { $crate::log!($crate::Level::Error, $($arg)+) };
+}
+
+#[cfg(not(feature = "solutions"))]
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => { $crate::log!($crate::Level::Warn, $($arg)+) };
+}
+
+#[cfg(not(feature = "solutions"))]
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::log!($crate::Level::Info, $($arg)+) };
+}
+
+#[cfg(not(feature = "solutions"))]
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::log!($crate::Level::Debug, $($arg)+) };
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use core::fmt::Write;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_fixed_buf_truncates_at_char_boundary",
+            "copy min(s.len(), room) bytes, but step back while !s.is_char_boundary(n)",
+        ),
+        (
+            "test_log_and_drain_in_order",
+            "log: seq = tail.fetch_add(1), slot seq % N; drain reads from head while head < tail",
+        ),
+        (
+            "test_level_filtering",
+            "check enabled(level) first: a filtered record takes no sequence number",
+        ),
+        (
+            "test_wrap_counts_lost",
+            "a slot whose stamp is past committed(seq) was overwritten: count the record as lost and move on",
+        ),
+        (
+            "test_concurrent_writers_keep_order",
+            "commit with a Release store of committed(seq); drain stops at a slot that is not committed yet",
+        ),
+        (
+            "test_concurrent_drain_accounts_every_record",
+            "every record is drained or counted as lost exactly once; a Pending slot ends the drain without moving head past it",
+        ),
+    ];
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Fewer records under Miri, which is orders of magnitude slower.
+    const fn scaled(n: usize) -> usize {
+        if cfg!(miri) {
+            n / 100
+        } else {
+            n
+        }
+    }
+
+    fn drain_all<const N: usize>(log: &KLog<N>) -> (Vec<Record>, Drained) {
+        let mut records = Vec::new();
+        let drained = log.drain(|r| records.push(r.clone()));
+        (records, drained)
+    }
+
+    /// `(writer, i)` out of a message `"t{writer} {i}"`.
+    fn parse(msg: &str) -> (usize, usize) {
+        let (t, i) = msg.strip_prefix('t').unwrap().split_once(' ').unwrap();
+        (t.parse().unwrap(), i.parse().unwrap())
+    }
+
+    #[test]
+    fn test_fixed_buf_truncates_at_char_boundary() {
+        let mut b = FixedBuf::<8>::new();
+        let c = 'b';
+        write!(b, "a{c}").unwrap();
+        b.write_char('c').unwrap();
+        assert_eq_with_hint!(b.as_str(), "abc");
+        assert_with_hint!(!b.truncated());
+        write!(b, "défghij").unwrap();
+        assert_eq_with_hint!(b.as_str(), "abcdéfg");
+        assert_with_hint!(b.truncated());
+
+        let mut b = FixedBuf::<5>::new();
+        write!(b, "abcdé").unwrap();
+        assert_eq_with_hint!(b.as_str(), "abcd", "never split a multi-byte char");
+        write!(b, "x").unwrap();
+        assert_eq_with_hint!(b.as_str(), "abcdx", "later text that fits is kept");
+    }
+
+    #[test]
+    fn test_log_and_drain_in_order() {
+        let log: KLog<8> = KLog::new();
+        assert_eq_with_hint!(log.log(Level::Info, format_args!("hello {}", 1)), Some(0));
+        assert_eq_with_hint!(
+            log.log(Level::Warn, format_args!("disk {}% full", 93)),
+            Some(1)
+        );
+        assert_eq_with_hint!(log.log(Level::Error, format_args!("panic")), Some(2));
+        let (records, drained) = drain_all(&log);
+        assert_eq_with_hint!(
+            drained,
+            Drained {
+                records: 3,
+                lost: 0
+            }
+        );
+        let got: Vec<(u64, Level, &str)> =
+            records.iter().map(|r| (r.seq, r.level, r.msg())).collect();
+        assert_eq_with_hint!(
+            got,
+            vec![
+                (0, Level::Info, "hello 1"),
+                (1, Level::Warn, "disk 93% full"),
+                (2, Level::Error, "panic"),
+            ]
+        );
+        assert_eq!(records[0].to_string(), "[     0] INFO  hello 1");
+        assert_eq_with_hint!(drain_all(&log).1, Drained::default(), "nothing new");
+        assert_eq!(log.written(), 3);
+    }
+
+    #[test]
+    fn test_long_message_is_truncated() {
+        let log: KLog<4> = KLog::new();
+        let long = "x".repeat(MSG_MAX + 50);
+        log.log(Level::Info, format_args!("{long}"));
+        let (records, _) = drain_all(&log);
+        assert_eq!(records[0].msg(), &long[..MSG_MAX]);
+    }
+
+    #[test]
+    fn test_level_filtering() {
+        let log: KLog<8> = KLog::new();
+        assert_eq!(log.level(), Level::Info);
+        assert_eq_with_hint!(log.log(Level::Debug, format_args!("noise")), None);
+        assert_eq_with_hint!(log.written(), 0, "a filtered record takes no slot");
+        log.set_level(Level::Debug);
+        assert_with_hint!(log.enabled(Level::Debug) && !log.enabled(Level::Trace));
+        assert_eq_with_hint!(log.log(Level::Debug, format_args!("details")), Some(0));
+        log.set_level(Level::Error);
+        assert_eq_with_hint!(log.log(Level::Warn, format_args!("warning")), None);
+        assert_eq_with_hint!(log.log(Level::Error, format_args!("error")), Some(1));
+        let (records, _) = drain_all(&log);
+        let msgs: Vec<&str> = records.iter().map(|r| r.msg()).collect();
+        assert_eq_with_hint!(msgs, ["details", "error"]);
+    }
+
+    #[test]
+    fn test_wrap_counts_lost() {
+        let log: KLog<8> = KLog::new();
+        for i in 0..20 {
+            log.log(Level::Info, format_args!("{i}"));
+        }
+        let (records, drained) = drain_all(&log);
+        assert_eq_with_hint!(
+            drained,
+            Drained {
+                records: 8,
+                lost: 12
+            }
+        );
+        let seqs: Vec<u64> = records.iter().map(|r| r.seq).collect();
+        assert_eq_with_hint!(seqs, (12..20).collect::<Vec<_>>(), "the newest N survive");
+        assert_eq_with_hint!(records[0].msg(), "12");
+        assert_eq_with_hint!(log.lost(), 12);
+
+        for i in 20..23 {
+            log.log(Level::Info, format_args!("{i}"));
+        }
+        assert_eq_with_hint!(
+            drain_all(&log).1,
+            Drained {
+                records: 3,
+                lost: 0
+            }
+        );
+
+        // The reader is 10 records behind a ring of 8.
+        for i in 23..33 {
+            log.log(Level::Info, format_args!("{i}"));
+        }
+        let (records, drained) = drain_all(&log);
+        assert_eq_with_hint!(
+            drained,
+            Drained {
+                records: 8,
+                lost: 2
+            }
+        );
+        assert_eq_with_hint!(records[0].seq, 25);
+        assert_eq_with_hint!(log.lost(), 14);
+        assert_eq!(log.written(), 33);
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_order() {
+        static LOG: KLog<4096> = KLog::new();
+        const WRITERS: usize = 4;
+        let per_writer = scaled(1000);
+        run_with_timeout(TIMEOUT, move || {
+            let start = Barrier::new(WRITERS);
+            thread::scope(|s| {
+                for t in 0..WRITERS {
+                    let start = &start;
+                    s.spawn(move || {
+                        start.wait();
+                        for i in 0..per_writer {
+                            LOG.log(Level::Info, format_args!("t{t} {i}"));
+                        }
+                    });
+                }
+            });
+            let (records, drained) = drain_all(&LOG);
+            assert_eq_with_hint!(
+                drained,
+                Drained {
+                    records: WRITERS * per_writer,
+                    lost: 0
+                }
+            );
+            let mut next = [0; WRITERS];
+            for (k, r) in records.iter().enumerate() {
+                assert_eq_with_hint!(r.seq, k as u64, "records come out in sequence order");
+                let (t, i) = parse(r.msg());
+                assert_eq_with_hint!(i, next[t], "each writer's records stay in its order");
+                next[t] += 1;
+            }
+        });
+    }
+
+    #[test]
+    fn test_concurrent_drain_accounts_every_record() {
+        static LOG: KLog<16> = KLog::new();
+        const WRITERS: usize = 4;
+        let per_writer = scaled(5000);
+        run_with_timeout(TIMEOUT, move || {
+            let done = AtomicBool::new(false);
+            let mut records = Vec::new();
+            let mut lost = 0;
+            thread::scope(|s| {
+                let writers: Vec<_> = (0..WRITERS)
+                    .map(|t| {
+                        s.spawn(move || {
+                            for i in 0..per_writer {
+                                LOG.log(Level::Info, format_args!("t{t} {i}"));
+                            }
+                        })
+                    })
+                    .collect();
+                s.spawn(|| {
+                    for w in writers {
+                        w.join().unwrap();
+                    }
+                    done.store(true, Ordering::SeqCst);
+                });
+                while !done.load(Ordering::SeqCst) {
+                    let drained = LOG.drain(|r| records.push(r.clone()));
+                    lost += drained.lost;
+                    thread::yield_now();
+                }
+            });
+            let drained = LOG.drain(|r| records.push(r.clone()));
+            lost += drained.lost;
+
+            assert_eq_with_hint!(
+                records.len() as u64 + lost,
+                (WRITERS * per_writer) as u64,
+                "every record is either drained or counted as lost"
+            );
+            assert_eq_with_hint!(LOG.lost(), lost);
+            let mut last = [None; WRITERS];
+            for pair in records.windows(2) {
+                assert_with_hint!(pair[0].seq < pair[1].seq, "sequence order");
+            }
+            for r in &records {
+                let (t, i) = parse(r.msg());
+                assert_with_hint!(
+                    last[t].is_none_or(|prev| prev < i),
+                    "writer {t}: {i} after {last:?}"
+                );
+                last[t] = Some(i);
+            }
+        });
+    }
+
+    #[test]
+    fn test_global_log_macros() {
+        KLOG.set_level(Level::Info);
+        let seq = crate::info!("booted in {} ms", 3).unwrap();
+        assert_eq!(crate::debug!("hidden"), None);
+        crate::log!(Level::Warn, "low memory: {} pages", 12);
+        let mut msgs = Vec::new();
+        KLOG.drain(|r| {
+            if r.seq >= seq {
+                msgs.push((r.level, r.msg().to_string()));
+            }
+        });
+        assert_eq!(
+            msgs,
+            vec![
+                (Level::Info, "booted in 3 ms".to_string()),
+                (Level::Warn, "low memory: 12 pages".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! # Kernel Log Ring Buffer
+//!
+//! A kernel cannot `println!`: there may be no console yet, and the code that logs may run in an
+//! interrupt handler, holding a spinlock, or on several CPUs at once. Like Linux's `printk`, it
+//! formats the message into a fixed-size record and stores it in a ring buffer in memory; a
+//! reader (`dmesg`, a console driver) drains the records later. In this exercise, you build that
+//! ring: writers never take a lock and never wait for the reader, and when the reader falls
+//! behind, the oldest records are overwritten and counted as lost. The one wait a writer can hit
+//! is short and bounded, see "When a writer waits" below.
+//!
+//! ## Concepts
+//! - Formatting without an allocator: `core::fmt::Write` into a fixed buffer (`FixedBuf`), cut
+//!   at a `char` boundary when the message does not fit
+//! - Runtime level filtering: records less severe than `level()` are dropped before formatting
+//! - Claiming a sequence number with `fetch_add` on `tail`: it fixes the record's order and its
+//!   slot, `seq % N`
+//! - Per-slot stamps as a seqlock: odd while a writer fills the slot, even once committed; the
+//!   reader copies a record and only trusts it if the stamp did not change meanwhile
+//! - Loss accounting: a reader more than `N` records behind skips to `tail - N`, and a record
+//!   overwritten while (or before) it is read is counted instead of returned
+//!
+//! ## Slot stamps
+//! ```text
+//! stamp = 0            never written
+//! stamp = 2 * seq + 1  the writer of `seq` is filling the slot
+//! stamp = 2 * seq + 2  the record `seq` is committed
+//! ```
+//! Every state of a later record is larger than every state of an earlier one, so comparing
+//! stamps tells "not written yet", "ready" and "overwritten by a newer lap" apart.
+//!
+//! ## When a writer waits
+//! A writer never waits for the reader, nor for the writers of other slots. It only waits when
+//! the writer of its slot one lap earlier (record `seq - N`) is still copying its message, that
+//! is, when `N` more records were claimed during that copy. Taking the slot anyway would mix the
+//! two messages, so it spins until that writer commits: at most the rest of one copy of
+//! `MSG_MAX` bytes, as long as that writer keeps running. An interrupt handler that preempts it
+//! on the same CPU and logs `N` records itself would spin forever, so size `N` well above what a
+//! handler logs.
+//!
+//! The record bytes are atomics accessed with `Relaxed`, so a reader racing with a writer sees
+//! torn data (and then a changed stamp) rather than undefined behaviour. Only `core` is used.
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+
+/// Severity of a record; lower is more severe.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl Level {
+    pub fn from_u8(v: u8) -> Option<Level> {
+        match v {
+            0 => Some(Level::Error),
+            1 => Some(Level::Warn),
+            2 => Some(Level::Info),
+            3 => Some(Level::Debug),
+            4 => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+/// Longest message a record holds, in bytes; longer ones are truncated.
+pub const MSG_MAX: usize = 120;
+
+/// A `core::fmt::Write` target of `N` bytes on the stack. Text that does not fit is dropped,
+/// cut at a `char` boundary so the contents stay valid UTF-8; writing never fails.
+pub struct FixedBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    truncated: bool,
+}
+
+impl<const N: usize> FixedBuf<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub fn as_str(&self) -> &str {
+        // `write_str` only ever copies whole `char`s.
+        core::str::from_utf8(self.as_bytes()).unwrap()
+    }
+
+    /// Whether some text was dropped for lack of room.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<const N: usize> Default for FixedBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Write for FixedBuf<N> {
+    /// Copy as much of `s` as fits. If not all of it does, back off to the last `char`
+    /// boundary at or before the room left (`s.is_char_boundary`) and set `truncated`.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let room = N - self.len;
+        let mut n = s.len();
+        if n > room {
+            n = room;
+            while !s.is_char_boundary(n) {
+                n -= 1;
+            }
+            self.truncated = true;
+        }
+        self.buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        Ok(())
+    }
+}
+
+/// A record copied out of the ring by `drain`.
+#[derive(Clone)]
+pub struct Record {
+    /// Position in the log: records are numbered from 0 in the order they claimed a slot.
+    pub seq: u64,
+    pub level: Level,
+    len: usize,
+    msg: [u8; MSG_MAX],
+}
+
+impl Record {
+    pub fn msg(&self) -> &str {
+        core::str::from_utf8(&self.msg[..self.len]).unwrap_or("<torn>")
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("seq", &self.seq)
+            .field("level", &self.level)
+            .field("msg", &self.msg())
+            .finish()
+    }
+}
+
+/// `dmesg` style: `[    42] INFO  booted in 3 ms`.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:>6}] {:<5} {}", self.seq, self.level, self.msg())
+    }
+}
+
+const fn writing(seq: u64) -> u64 {
+    2 * seq + 1
+}
+
+const fn committed(seq: u64) -> u64 {
+    2 * seq + 2
+}
+
+struct Slot {
+    stamp: AtomicU64,
+    level: AtomicU8,
+    len: AtomicUsize,
+    msg: [AtomicU8; MSG_MAX],
+}
+
+/// What `Slot::read` found for the record it was asked for.
+enum Read {
+    Ready(Record),
+    /// Its writer has not committed it yet.
+    Pending,
+    /// A record one or more laps later took the slot.
+    Overwritten,
+}
+
+impl Slot {
+    const fn new() -> Self {
+        Self {
+            stamp: AtomicU64::new(0),
+            level: AtomicU8::new(0),
+            len: AtomicUsize::new(0),
+            msg: [const { AtomicU8::new(0) }; MSG_MAX],
+        }
+    }
+
+    /// Store record `seq` in this slot.
+    ///
+    /// 1. Open the slot: `compare_exchange` the stamp from its current value to `writing(seq)`.
+    ///    If the stamp is already `writing(seq)` or larger, a record a lap later owns the slot:
+    ///    give up, the record is lost. If it is odd (smaller), the writer a lap earlier is still
+    ///    filling it: spin until it commits (the bounded wait of "When a writer waits").
+    /// 2. `fence(Release)`, so a reader that sees any of the bytes below also sees the odd stamp.
+    /// 3. Store level, length and bytes (`Relaxed`).
+    /// 4. Commit: store `committed(seq)` with `Release`.
+    fn write(&self, seq: u64, level: Level, msg: &[u8]) {
+        let mut cur = self.stamp.load(Ordering::Relaxed);
+        loop {
+            if cur >= writing(seq) {
+                return;
+            }
+            if cur % 2 == 1 {
+                core::hint::spin_loop();
+                cur = self.stamp.load(Ordering::Relaxed);
+                continue;
+            }
+            match self.stamp.compare_exchange_weak(
+                cur,
+                writing(seq),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(now) => cur = now,
+            }
+        }
+        fence(Ordering::Release);
+        self.level.store(level as u8, Ordering::Relaxed);
+        self.len.store(msg.len(), Ordering::Relaxed);
+        for (dst, &b) in self.msg.iter().zip(msg) {
+            dst.store(b, Ordering::Relaxed);
+        }
+        self.stamp.store(committed(seq), Ordering::Release);
+    }
+
+    /// Copy record `seq` out of this slot.
+    ///
+    /// 1. Load the stamp (`Acquire`): larger than `committed(seq)` means `Overwritten`, anything
+    ///    else but `committed(seq)` means `Pending`.
+    /// 2. Copy level, length and bytes (`Relaxed`).
+    /// 3. `fence(Acquire)`, then load the stamp again: if it changed, a writer of a later lap
+    ///    was in the slot during the copy, so the copy may be torn: `Overwritten`.
+    fn read(&self, seq: u64) -> Read {
+        let stamp = self.stamp.load(Ordering::Acquire);
+        if stamp > committed(seq) {
+            return Read::Overwritten;
+        }
+        if stamp != committed(seq) {
+            return Read::Pending;
+        }
+        let level = self.level.load(Ordering::Relaxed);
+        let len = self.len.load(Ordering::Relaxed).min(MSG_MAX);
+        let mut msg = [0; MSG_MAX];
+        for (dst, src) in msg.iter_mut().zip(&self.msg) {
+            *dst = src.load(Ordering::Relaxed);
+        }
+        fence(Ordering::Acquire);
+        if self.stamp.load(Ordering::Relaxed) != stamp {
+            return Read::Overwritten;
+        }
+        Read::Ready(Record {
+            seq,
+            level: Level::from_u8(level).unwrap(),
+            len,
+            msg,
+        })
+    }
+}
+
+/// Outcome of one `drain`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Drained {
+    /// Records handed to the callback.
+    pub records: usize,
+    /// Records skipped because they were overwritten before they could be read.
+    pub lost: u64,
+}
+
+/// Multi-writer log ring of `N` records (`N > 0`).
+///
+/// Writers (`log`) are lock-free with respect to the reader and to each other, except that a
+/// writer waits for the rest of one copy if the writer of the same slot one lap earlier has
+/// not committed yet (see the module docs). One `drain` runs at a time; a second one waits for
+/// the first.
+pub struct KLog<const N: usize> {
+    slots: [Slot; N],
+    /// Next sequence number to hand out: also the number of records logged so far.
+    tail: AtomicU64,
+    /// Next sequence number `drain` will read.
+    head: AtomicU64,
+    /// Records lost over all drains.
+    lost: AtomicU64,
+    /// The most verbose level still recorded, as `Level as u8`.
+    max_level: AtomicU8,
+    /// Held by the running `drain`.
+    reading: AtomicBool,
+}
+
+impl<const N: usize> KLog<N> {
+    /// An empty log recording `Level::Info` and more severe.
+    pub const fn new() -> Self {
+        assert!(N > 0, "a log needs at least one slot");
+        Self {
+            slots: [const { Slot::new() }; N],
+            tail: AtomicU64::new(0),
+            head: AtomicU64::new(0),
+            lost: AtomicU64::new(0),
+            max_level: AtomicU8::new(Level::Info as u8),
+            reading: AtomicBool::new(false),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Record `level` and everything more severe from now on.
+    pub fn set_level(&self, level: Level) {
+        self.max_level.store(level as u8, Ordering::Relaxed);
+    }
+
+    pub fn level(&self) -> Level {
+        Level::from_u8(self.max_level.load(Ordering::Relaxed)).unwrap()
+    }
+
+    /// Whether a record of `level` would be kept.
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= self.level()
+    }
+
+    /// Format `args` and append it as a record; returns its sequence number, or `None` if
+    /// `level` is filtered out (then nothing is formatted).
+    ///
+    /// 1. Check `enabled(level)`.
+    /// 2. Format into a `FixedBuf<MSG_MAX>` on the stack, before claiming anything, so the slot
+    ///    is held open only for the copy.
+    /// 3. `seq = tail.fetch_add(1)`; write the record into slot `seq % N`.
+    pub fn log(&self, level: Level, args: fmt::Arguments<'_>) -> Option<u64> {
+        if !self.enabled(level) {
+            return None;
+        }
+        let mut buf = FixedBuf::<MSG_MAX>::new();
+        let _ = buf.write_fmt(args);
+        let seq = self.tail.fetch_add(1, Ordering::Relaxed);
+        self.slots[(seq % N as u64) as usize].write(seq, level, buf.as_bytes());
+        Some(seq)
+    }
+
+    /// Hand every record not read yet to `f`, oldest first, and report how many were lost.
+    ///
+    /// Starting at `head`, loop while `head < tail`:
+    /// - If `tail - head > N`, the records before `tail - N` have been overwritten: count them
+    ///   as lost and jump `head` to `tail - N` (reading their slots one by one would find them
+    ///   overwritten too, just more slowly).
+    /// - Read slot `head % N`: `Ready` goes to `f`, `Overwritten` is one more lost record, and
+    ///   either way `head += 1`. `Pending` stops the drain: records come out in order, so a
+    ///   later drain picks up from there.
+    ///
+    /// Store `head` back and add the lost records to `lost()`.
+    pub fn drain(&self, mut f: impl FnMut(&Record)) -> Drained {
+        while self
+            .reading
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let mut out = Drained::default();
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            let tail = self.tail.load(Ordering::Relaxed);
+            if head >= tail {
+                break;
+            }
+            if tail - head > N as u64 {
+                out.lost += tail - N as u64 - head;
+                head = tail - N as u64;
+            }
+            match self.slots[(head % N as u64) as usize].read(head) {
+                Read::Ready(record) => {
+                    f(&record);
+                    out.records += 1;
+                }
+                Read::Overwritten => out.lost += 1,
+                Read::Pending => break,
+            }
+            head += 1;
+        }
+        self.head.store(head, Ordering::Relaxed);
+        self.lost.fetch_add(out.lost, Ordering::Relaxed);
+        self.reading.store(false, Ordering::Release);
+        out
+    }
+
+    /// Records logged so far (not counting filtered ones).
+    pub fn written(&self) -> u64 {
+        self.tail.load(Ordering::Relaxed)
+    }
+
+    /// Records lost over all drains so far.
+    pub fn lost(&self) -> u64 {
+        self.lost.load(Ordering::Relaxed)
+    }
+}
+
+impl<const N: usize> Default for KLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Slots in the global `KLOG`.
+pub const LOG_CAPACITY: usize = 256;
+
+/// The kernel log written by `log!` and friends.
+pub static KLOG: KLog<LOG_CAPACITY> = KLog::new();
+
+/// `log!(level, "fmt", args...)`: append a record to `KLOG`; evaluates to its sequence number,
+/// or `None` if `level` is filtered out.
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)+) => {
+        $crate::solution::KLOG.log($level, format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => { $crate::log!($crate::solution::Level::Error, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => { $crate::log!($crate::solution::Level::Warn, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::log!($crate::solution::Level::Info, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::log!($crate::solution::Level::Debug, $($arg)+) };
+}
@@ -66,20 +66,43 @@ impl AtomicCounter {
     }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, Hints};
     use std::sync::Arc;
     use std::thread;
 
+    const HINTS: Hints = &[
+        (
+            "test_basic_ops",
+            "increment and decrement return the value from before the change: that is what fetch_add / fetch_sub return",
+        ),
+        (
+            "test_cas_failure",
+            "compare_exchange returns Err(current value) when it does not match; pass that through",
+        ),
+        (
+            "test_fetch_multiply",
+            "there is no fetch_mul: loop on load + compare_exchange_weak(old, old * n) until it succeeds, and return old",
+        ),
+    ];
+
     #[test]
     fn test_basic_ops() {
         let c = AtomicCounter::new(0);
-        assert_eq!(c.increment(), 0);
-        assert_eq!(c.increment(), 1);
-        assert_eq!(c.get(), 2);
-        assert_eq!(c.decrement(), 2);
-        assert_eq!(c.get(), 1);
+        assert_eq_with_hint!(c.increment(), 0);
+        assert_eq_with_hint!(c.increment(), 1);
+        assert_eq_with_hint!(c.get(), 2);
+        assert_eq_with_hint!(c.decrement(), 2);
+        assert_eq_with_hint!(c.get(), 1);
     }
 
     #[test]
@@ -92,16 +115,16 @@ mod tests {
     #[test]
     fn test_cas_failure() {
         let c = AtomicCounter::new(10);
-        assert_eq!(c.compare_and_swap(5, 20), Err(10));
-        assert_eq!(c.get(), 10);
+        assert_eq_with_hint!(c.compare_and_swap(5, 20), Err(10));
+        assert_eq_with_hint!(c.get(), 10);
     }
 
     #[test]
     fn test_fetch_multiply() {
         let c = AtomicCounter::new(3);
         let old = c.fetch_multiply(4);
-        assert_eq!(old, 3);
-        assert_eq!(c.get(), 12);
+        assert_eq_with_hint!(old, 3);
+        assert_eq_with_hint!(c.get(), 12);
     }
 
     #[test]
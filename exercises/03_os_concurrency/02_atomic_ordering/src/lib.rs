@@ -12,8 +12,19 @@
 //! ## Release-Acquire Pairing
 //! When thread A writes with Release, and thread B reads the same location with Acquire,
 //! thread B will see all writes that thread A performed before the Release.
+//!
+//! `Mailbox` keeps the same pairing for a stream of messages through one slot: sequence
+//! numbers that wrap around tell a fresh message from a stale one, and a second Release-Acquire
+//! pair in the other direction tells the producer when the slot is free again.
+//!
+//! `OnceCell<T>` uses the same pairing to publish a value of any type: the initializing thread
+//! writes it through an `UnsafeCell`, then stores `READY` with Release; readers load the state
+//! with Acquire before touching the value.
 
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::cell::UnsafeCell;
+#[allow(unused_imports)]
+use std::mem::{self, MaybeUninit};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
 
 /// Use Release-Acquire semantics to safely pass data between two threads.
 ///
@@ -61,44 +72,236 @@ impl FlagChannel {
     }
 }
 
-/// A simple once-initializer using SeqCst.
-/// Guarantees `init` is executed only once, and all threads see the initialized value.
-pub struct OnceCell {
-    initialized: AtomicBool,
-    value: AtomicU32,
+/// `a` comes after `b` in a wrapping `u32` sequence: true while `a` is less than 2^31 steps
+/// ahead, so `seq_after(0, u32::MAX)` holds.
+pub fn seq_after(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+/// `FlagChannel` for more than one message: a single-slot mailbox between one producer thread
+/// and one consumer thread.
+///
+/// `sent` is the sequence number of the last message written into the slot, `received` that
+/// of the last one taken out. The slot is full while they differ. Both only ever move forward
+/// by one and wrap around at `u32::MAX`, so compare them with `==` or `seq_after`, never `<`.
+/// A consumer that sees `sent == received` knows the slot still holds the message it already
+/// read (a stale value) and must not return it again.
+///
+/// The pairing goes both ways: the producer publishes `data` with a Release store of `sent`,
+/// and the consumer hands the slot back with a Release store of `received`, so the producer
+/// never overwrites a message the consumer is still reading.
+#[allow(dead_code)]
+pub struct Mailbox {
+    data: AtomicU32,
+    sent: AtomicU32,
+    received: AtomicU32,
 }
 
-impl OnceCell {
+impl Default for Mailbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mailbox {
     pub const fn new() -> Self {
+        Self::starting_at(0)
+    }
+
+    /// An empty mailbox whose first message gets sequence number `seq + 1`.
+    pub const fn starting_at(seq: u32) -> Self {
         Self {
-            initialized: AtomicBool::new(false),
-            value: AtomicU32::new(0),
+            data: AtomicU32::new(0),
+            sent: AtomicU32::new(seq),
+            received: AtomicU32::new(seq),
         }
     }
 
+    /// Producer: put `value` in the slot if it is empty, and return the message's sequence
+    /// number. `None` if the consumer has not taken the previous message yet.
+    #[allow(unused_variables)]
+    pub fn try_send(&self, value: u32) -> Option<u32> {
+        // TODO: Only the producer writes `sent`, so read it with Relaxed
+        // TODO: Slot still full (received != sent, read with Acquire)? Return None
+        // TODO: Store data, then publish seq = sent.wrapping_add(1) with Release
+        todo!()
+    }
+
+    /// Producer: `try_send`, spinning until the slot is free. It yields between tries: on a
+    /// single CPU the consumer cannot empty the slot while the producer spins.
+    #[allow(unused_variables)]
+    pub fn send(&self, value: u32) -> u32 {
+        // TODO: Loop on try_send with std::hint::spin_loop() and std::thread::yield_now()
+        todo!()
+    }
+
+    /// Consumer: take the message in the slot as `(seq, value)`. `None` if there is no message
+    /// newer than the last one taken.
+    pub fn try_recv(&self) -> Option<(u32, u32)> {
+        // TODO: Load `received` (Relaxed) and `sent` (Acquire); equal means nothing new
+        // TODO: Read data, then hand the slot back by storing `received = sent` with Release
+        todo!()
+    }
+
+    /// Consumer: `try_recv`, spinning (and yielding) until a message arrives.
+    pub fn recv(&self) -> (u32, u32) {
+        // TODO: Loop on try_recv with std::hint::spin_loop() and std::thread::yield_now()
+        todo!()
+    }
+
+    /// Sequence number of the last message sent.
+    pub fn last_sent(&self) -> u32 {
+        self.sent.load(Ordering::Acquire)
+    }
+}
+
+/// Lazily initialized storage for a value of any type, set at most once.
+///
+/// `state` walks `UNINIT -> RUNNING -> READY`; the thread whose `compare_exchange` moves it out
+/// of `UNINIT` is the only one that ever writes `value`. Everyone else waits while it is
+/// `RUNNING`. If the initializer panics, the cell ends up `POISONED` for good: `get` keeps
+/// returning `None` and every later (or waiting) `get_or_init` panics too.
+pub struct OnceCell<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+const UNINIT: u8 = 0;
+#[allow(dead_code)]
+const RUNNING: u8 = 1;
+const READY: u8 = 2;
+const POISONED: u8 = 3;
+
+// The value is written once, before `READY` is published with Release, and only shared after.
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+unsafe impl<T: Send> Send for OnceCell<T> {}
+
+/// Armed while an initializer runs: dropping it during a panic poisons the cell.
+#[allow(dead_code)]
+struct PoisonOnUnwind<'a> {
+    state: &'a AtomicU8,
+}
+
+impl Drop for PoisonOnUnwind<'_> {
+    fn drop(&mut self) {
+        // TODO: Mark the cell POISONED (Release)
+        todo!()
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> OnceCell<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(UNINIT),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Get value. Returns Some if initialized, otherwise None (also while another thread is
+    /// still initializing, and once poisoned).
+    pub fn get(&self) -> Option<&T> {
+        // TODO: READY (Acquire) -> a reference to the value, else None
+        todo!()
+    }
+
     /// Attempt initialization. If not yet initialized, store value and return true.
-    /// If already initialized, return false.
+    /// If already initialized (or being initialized, or poisoned), return false.
     ///
     /// Hint: use `compare_exchange` to ensure only one thread succeeds.
-    pub fn init(&self, val: u32) -> bool {
-        // TODO: Use compare_exchange to ensure initialization only once
-        // Store value on success
+    #[allow(unused_variables)]
+    pub fn init(&self, val: T) -> bool {
+        // TODO: Use compare_exchange (UNINIT -> RUNNING) to ensure initialization only once
+        // Write the value on success, then publish READY with Release
         todo!()
     }
 
-    /// Get value. Returns Some if initialized, otherwise None.
-    pub fn get(&self) -> Option<u32> {
-        // TODO: Check initialized flag, then read value
+    /// The value, running `f` to create it first if the cell is empty. Concurrent callers wait
+    /// for the one running `f` and then share its value; `f` runs at most once.
+    ///
+    /// 1. `compare_exchange(UNINIT, RUNNING)`. Won: arm a `PoisonOnUnwind`, run `f`, write the
+    ///    value, disarm the guard (`mem::forget`), publish `READY` with Release.
+    /// 2. Lost: `READY` -> return the value; `RUNNING` -> spin (`spin_loop` / `yield_now`) and
+    ///    look again; `POISONED` -> panic with a message containing "poisoned".
+    #[allow(unused_variables)]
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        // TODO: Loop on compare_exchange(UNINIT, RUNNING): run f under a PoisonOnUnwind guard,
+        // write the value, mem::forget the guard, store READY; wait while RUNNING; panic if POISONED
         todo!()
     }
+
+    /// Whether an initializer panicked.
+    pub fn is_poisoned(&self) -> bool {
+        self.state.load(Ordering::Acquire) == POISONED
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == READY {
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
     use std::sync::Arc;
     use std::thread;
 
+    const HINTS: Hints = &[
+        (
+            "test_flag_channel",
+            "store the data before setting the flag with Release, and load the flag with Acquire before reading the data",
+        ),
+        (
+            "test_seq_after_wraps",
+            "compare wrapping_sub(a, b) as i32 against 0 instead of a > b",
+        ),
+        (
+            "test_mailbox_slot_is_single",
+            "try_send must refuse while received != sent, and try_recv must return None while they are equal",
+        ),
+        (
+            "test_mailbox_stress_10k",
+            "the consumer must store received with Release after reading data, and the producer load it with Acquire before overwriting data",
+        ),
+        (
+            "test_once_cell_init_once",
+            "claim the cell with compare_exchange on the state (UNINIT -> RUNNING) so only the first init wins",
+        ),
+        (
+            "test_get_or_init_runs_once",
+            "only the thread that wins compare_exchange(UNINIT, RUNNING) runs f; the others wait while the state is RUNNING",
+        ),
+        (
+            "test_get_or_init_poisons_on_panic",
+            "arm a guard before calling f whose Drop stores POISONED, and mem::forget it once f returned",
+        ),
+        (
+            "test_get_or_init_waiters_see_poison",
+            "a thread waiting on RUNNING must re-check the state and panic when it becomes POISONED, not spin forever",
+        ),
+        (
+            "test_once_cell_concurrent",
+            "exactly one init may return true: losers of the compare_exchange must return false without writing the value",
+        ),
+    ];
+
     #[test]
     fn test_flag_channel() {
         let ch = Arc::new(FlagChannel::new());
@@ -112,7 +315,7 @@ mod tests {
 
         producer.join().unwrap();
         let val = consumer.join().unwrap();
-        assert_eq!(val, 42);
+        assert_eq_with_hint!(val, 42);
     }
 
     #[test]
@@ -129,17 +332,83 @@ mod tests {
         assert_eq!(val, 0xDEAD_BEEF);
     }
 
+    #[test]
+    fn test_seq_after_wraps() {
+        assert!(seq_after(2, 1));
+        assert!(!seq_after(1, 2));
+        assert!(!seq_after(5, 5));
+        assert_with_hint!(seq_after(0, u32::MAX));
+        assert_with_hint!(seq_after(3, u32::MAX - 3));
+        assert!(!seq_after(u32::MAX, 0));
+    }
+
+    #[test]
+    fn test_mailbox_slot_is_single() {
+        let mb = Mailbox::new();
+        assert_eq_with_hint!(mb.try_recv(), None);
+        assert_eq!(mb.try_send(10), Some(1));
+        assert_eq_with_hint!(mb.try_send(11), None);
+        assert_eq!(mb.try_recv(), Some((1, 10)));
+        assert_eq_with_hint!(mb.try_recv(), None, "the same message came back twice");
+        assert_eq!(mb.send(12), 2);
+        assert_eq!(mb.recv(), (2, 12));
+        assert_eq!(mb.last_sent(), 2);
+    }
+
+    #[test]
+    fn test_mailbox_wraps_around() {
+        let mb = Mailbox::starting_at(u32::MAX - 1);
+        let mut seqs = Vec::new();
+        for v in 0..4 {
+            mb.send(v);
+            let (seq, got) = mb.recv();
+            assert_eq!(got, v);
+            seqs.push(seq);
+        }
+        assert_eq!(seqs, vec![u32::MAX, 0, 1, 2]);
+        assert_eq!(mb.try_recv(), None);
+    }
+
+    #[test]
+    fn test_mailbox_stress_10k() {
+        const N: u32 = 10_000;
+        // Start close to the end so the sequence numbers wrap halfway through.
+        let start = u32::MAX - N / 2;
+        let mb = Arc::new(Mailbox::starting_at(start));
+        let producer = {
+            let mb = Arc::clone(&mb);
+            thread::spawn(move || {
+                for i in 0..N {
+                    mb.send(i.wrapping_mul(2_654_435_761));
+                }
+            })
+        };
+        let mut last = start;
+        for i in 0..N {
+            let (seq, value) = mb.recv();
+            assert_with_hint!(
+                seq == last.wrapping_add(1),
+                "message {i}: seq {seq} after {last} (lost or duplicated)"
+            );
+            assert_eq_with_hint!(value, i.wrapping_mul(2_654_435_761));
+            last = seq;
+        }
+        producer.join().unwrap();
+        assert_eq!(mb.try_recv(), None);
+        assert_eq!(mb.last_sent(), start.wrapping_add(N));
+    }
+
     #[test]
     fn test_once_cell_init_once() {
         let cell = OnceCell::new();
-        assert!(cell.init(42));
-        assert!(!cell.init(100));
-        assert_eq!(cell.get(), Some(42));
+        assert_with_hint!(cell.init(42));
+        assert_with_hint!(!cell.init(100));
+        assert_eq_with_hint!(cell.get(), Some(&42));
     }
 
     #[test]
     fn test_once_cell_not_initialized() {
-        let cell = OnceCell::new();
+        let cell = OnceCell::<u32>::new();
         assert_eq!(cell.get(), None);
     }
 
@@ -155,7 +424,108 @@ mod tests {
 
         let results: Vec<bool> = handles.into_iter().map(|h| h.join().unwrap()).collect();
         // Exactly one thread initializes successfully
-        assert_eq!(results.iter().filter(|&&r| r).count(), 1);
-        assert!(cell.get().is_some());
+        assert_eq_with_hint!(results.iter().filter(|&&r| r).count(), 1);
+        assert_with_hint!(cell.get().is_some());
+    }
+
+    #[test]
+    fn test_get_or_init_runs_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let cell = Arc::new(OnceCell::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let (cell, calls) = (cell.clone(), calls.clone());
+                thread::spawn(move || {
+                    cell.get_or_init(|| {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        format!("made by {i}")
+                    })
+                    .clone()
+                })
+            })
+            .collect();
+        let values: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq_with_hint!(calls.load(Ordering::SeqCst), 1);
+        assert_with_hint!(values.iter().all(|v| v == &values[0]), "{values:?}");
+        assert_eq!(cell.get(), Some(&values[0]));
+        assert_eq!(cell.get_or_init(|| unreachable!()), &values[0]);
+    }
+
+    #[test]
+    fn test_init_then_get_or_init() {
+        let cell = OnceCell::new();
+        assert!(cell.init(vec![1, 2]));
+        assert_eq!(cell.get_or_init(|| vec![3]), &vec![1, 2]);
+        assert!(!cell.init(vec![4]));
+    }
+
+    #[test]
+    fn test_once_cell_drops_value() {
+        let marker = Arc::new(());
+        let cell = OnceCell::new();
+        cell.get_or_init(|| marker.clone());
+        assert_eq!(Arc::strong_count(&marker), 2);
+        drop(cell);
+        assert_eq!(Arc::strong_count(&marker), 1);
+        drop(OnceCell::<Arc<()>>::new());
+    }
+
+    #[test]
+    fn test_get_or_init_poisons_on_panic() {
+        use std::panic::AssertUnwindSafe;
+
+        let cell = OnceCell::<String>::new();
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            cell.get_or_init(|| panic!("init failed"));
+        }));
+        assert!(result.is_err());
+        assert_with_hint!(cell.is_poisoned());
+        assert_eq_with_hint!(cell.get(), None);
+        assert!(!cell.init("late".into()));
+        let again = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            cell.get_or_init(|| "ok".into()).clone()
+        }));
+        let payload = again.expect_err("a poisoned cell must panic");
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        assert_with_hint!(msg.contains("poisoned"), "panic message: {msg:?}");
+    }
+
+    #[test]
+    fn test_get_or_init_waiters_see_poison() {
+        use std::time::Duration;
+
+        let cell = Arc::new(OnceCell::<u32>::new());
+        let initializer = {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                cell.get_or_init(|| {
+                    thread::sleep(Duration::from_millis(50));
+                    panic!("slow failure")
+                });
+            })
+        };
+        thread::sleep(Duration::from_millis(10));
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let cell = cell.clone();
+                thread::spawn(move || *cell.get_or_init(|| 7))
+            })
+            .collect();
+        assert!(initializer.join().is_err());
+        for w in waiters {
+            assert_with_hint!(
+                w.join().is_err(),
+                "a waiter returned a value from a poisoned cell"
+            );
+        }
+        assert!(cell.is_poisoned());
     }
 }
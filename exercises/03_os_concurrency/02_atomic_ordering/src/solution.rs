@@ -0,0 +1,290 @@
+//! # Memory Ordering and Synchronization
+//!
+//! In this exercise, you will use correct memory ordering to implement thread synchronization primitives.
+//!
+//! ## Key Concepts
+//! - `Ordering::Relaxed`: No synchronization guarantees
+//! - `Ordering::Acquire`: Read operation, prevents subsequent reads/writes from being reordered before this operation
+//! - `Ordering::Release`: Write operation, prevents preceding reads/writes from being reordered after this operation
+//! - `Ordering::AcqRel`: Both Acquire and Release semantics
+//! - `Ordering::SeqCst`: Sequentially consistent (global ordering)
+//!
+//! ## Release-Acquire Pairing
+//! When thread A writes with Release, and thread B reads the same location with Acquire,
+//! thread B will see all writes that thread A performed before the Release.
+//!
+//! `Mailbox` keeps the same pairing for a stream of messages through one slot: sequence
+//! numbers that wrap around tell a fresh message from a stale one, and a second Release-Acquire
+//! pair in the other direction tells the producer when the slot is free again.
+//!
+//! `OnceCell<T>` uses the same pairing to publish a value of any type: the initializing thread
+//! writes it through an `UnsafeCell`, then stores `READY` with Release; readers load the state
+//! with Acquire before touching the value.
+
+use std::cell::UnsafeCell;
+use std::mem::{self, MaybeUninit};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+
+/// Use Release-Acquire semantics to safely pass data between two threads.
+///
+/// `produce` writes data first, then sets flag with Release;
+/// `consume` reads flag with Acquire, ensuring it sees the data.
+pub struct FlagChannel {
+    data: AtomicU32,
+    ready: AtomicBool,
+}
+
+impl FlagChannel {
+    pub const fn new() -> Self {
+        Self {
+            data: AtomicU32::new(0),
+            ready: AtomicBool::new(false),
+        }
+    }
+
+    /// Producer: store data first, then set ready flag.
+    ///
+    /// TODO: Choose correct Ordering
+    /// - What Ordering should be used for writing data?
+    /// - What Ordering should be used for writing ready? (ensuring data writes are visible to consumer)
+    pub fn produce(&self, value: u32) {
+        self.data.store(value, Ordering::Relaxed);
+        self.ready.store(true, Ordering::Release);
+    }
+
+    /// Consumer: spin-wait for ready flag, then read data.
+    ///
+    /// TODO: Choose correct Ordering
+    /// - What Ordering should be used for reading ready? (ensuring it sees data writes from produce)
+    /// - What Ordering should be used for reading data?
+    pub fn consume(&self) -> u32 {
+        while !self.ready.load(Ordering::Acquire) {
+            std::hint::spin_loop();
+        }
+        self.data.load(Ordering::Relaxed)
+    }
+
+    /// Reset channel state
+    pub fn reset(&self) {
+        self.ready.store(false, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+    }
+}
+
+/// `a` comes after `b` in a wrapping `u32` sequence: true while `a` is less than 2^31 steps
+/// ahead, so `seq_after(0, u32::MAX)` holds.
+pub fn seq_after(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+/// `FlagChannel` for more than one message: a single-slot mailbox between one producer thread
+/// and one consumer thread.
+///
+/// `sent` is the sequence number of the last message written into the slot, `received` that
+/// of the last one taken out. The slot is full while they differ. Both only ever move forward
+/// by one and wrap around at `u32::MAX`, so compare them with `==` or `seq_after`, never `<`.
+/// A consumer that sees `sent == received` knows the slot still holds the message it already
+/// read (a stale value) and must not return it again.
+///
+/// The pairing goes both ways: the producer publishes `data` with a Release store of `sent`,
+/// and the consumer hands the slot back with a Release store of `received`, so the producer
+/// never overwrites a message the consumer is still reading.
+pub struct Mailbox {
+    data: AtomicU32,
+    sent: AtomicU32,
+    received: AtomicU32,
+}
+
+impl Default for Mailbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mailbox {
+    pub const fn new() -> Self {
+        Self::starting_at(0)
+    }
+
+    /// An empty mailbox whose first message gets sequence number `seq + 1`.
+    pub const fn starting_at(seq: u32) -> Self {
+        Self {
+            data: AtomicU32::new(0),
+            sent: AtomicU32::new(seq),
+            received: AtomicU32::new(seq),
+        }
+    }
+
+    /// Producer: put `value` in the slot if it is empty, and return the message's sequence
+    /// number. `None` if the consumer has not taken the previous message yet.
+    pub fn try_send(&self, value: u32) -> Option<u32> {
+        let sent = self.sent.load(Ordering::Relaxed);
+        if self.received.load(Ordering::Acquire) != sent {
+            return None;
+        }
+        let seq = sent.wrapping_add(1);
+        self.data.store(value, Ordering::Relaxed);
+        self.sent.store(seq, Ordering::Release);
+        Some(seq)
+    }
+
+    /// Producer: `try_send`, spinning until the slot is free. It yields between tries: on a
+    /// single CPU the consumer cannot empty the slot while the producer spins.
+    pub fn send(&self, value: u32) -> u32 {
+        loop {
+            if let Some(seq) = self.try_send(value) {
+                return seq;
+            }
+            std::hint::spin_loop();
+            std::thread::yield_now();
+        }
+    }
+
+    /// Consumer: take the message in the slot as `(seq, value)`. `None` if there is no message
+    /// newer than the last one taken.
+    pub fn try_recv(&self) -> Option<(u32, u32)> {
+        let received = self.received.load(Ordering::Relaxed);
+        let sent = self.sent.load(Ordering::Acquire);
+        if sent == received {
+            return None;
+        }
+        let value = self.data.load(Ordering::Relaxed);
+        self.received.store(sent, Ordering::Release);
+        Some((sent, value))
+    }
+
+    /// Consumer: `try_recv`, spinning (and yielding) until a message arrives.
+    pub fn recv(&self) -> (u32, u32) {
+        loop {
+            if let Some(msg) = self.try_recv() {
+                return msg;
+            }
+            std::hint::spin_loop();
+            std::thread::yield_now();
+        }
+    }
+
+    /// Sequence number of the last message sent.
+    pub fn last_sent(&self) -> u32 {
+        self.sent.load(Ordering::Acquire)
+    }
+}
+
+/// Lazily initialized storage for a value of any type, set at most once.
+///
+/// `state` walks `UNINIT -> RUNNING -> READY`; the thread whose `compare_exchange` moves it out
+/// of `UNINIT` is the only one that ever writes `value`. Everyone else waits while it is
+/// `RUNNING`. If the initializer panics, the cell ends up `POISONED` for good: `get` keeps
+/// returning `None` and every later (or waiting) `get_or_init` panics too.
+pub struct OnceCell<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+const UNINIT: u8 = 0;
+const RUNNING: u8 = 1;
+const READY: u8 = 2;
+const POISONED: u8 = 3;
+
+// The value is written once, before `READY` is published with Release, and only shared after.
+unsafe impl<T: Send + Sync> Sync for OnceCell<T> {}
+unsafe impl<T: Send> Send for OnceCell<T> {}
+
+/// Armed while an initializer runs: dropping it during a panic poisons the cell.
+struct PoisonOnUnwind<'a> {
+    state: &'a AtomicU8,
+}
+
+impl Drop for PoisonOnUnwind<'_> {
+    fn drop(&mut self) {
+        self.state.store(POISONED, Ordering::Release);
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> OnceCell<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(UNINIT),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Get value. Returns Some if initialized, otherwise None (also while another thread is
+    /// still initializing, and once poisoned).
+    pub fn get(&self) -> Option<&T> {
+        if self.state.load(Ordering::Acquire) == READY {
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    /// Attempt initialization. If not yet initialized, store value and return true.
+    /// If already initialized (or being initialized, or poisoned), return false.
+    ///
+    /// Hint: use `compare_exchange` to ensure only one thread succeeds.
+    pub fn init(&self, val: T) -> bool {
+        if self
+            .state
+            .compare_exchange(UNINIT, RUNNING, Ordering::Acquire, Ordering::Acquire)
+            .is_err()
+        {
+            return false;
+        }
+        unsafe { (*self.value.get()).write(val) };
+        self.state.store(READY, Ordering::Release);
+        true
+    }
+
+    /// The value, running `f` to create it first if the cell is empty. Concurrent callers wait
+    /// for the one running `f` and then share its value; `f` runs at most once.
+    ///
+    /// 1. `compare_exchange(UNINIT, RUNNING)`. Won: arm a `PoisonOnUnwind`, run `f`, write the
+    ///    value, disarm the guard (`mem::forget`), publish `READY` with Release.
+    /// 2. Lost: `READY` -> return the value; `RUNNING` -> spin (`spin_loop` / `yield_now`) and
+    ///    look again; `POISONED` -> panic with a message containing "poisoned".
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> &T {
+        let mut f = Some(f);
+        loop {
+            match self
+                .state
+                .compare_exchange(UNINIT, RUNNING, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    let guard = PoisonOnUnwind { state: &self.state };
+                    let value = (f.take().unwrap())();
+                    unsafe { (*self.value.get()).write(value) };
+                    mem::forget(guard);
+                    self.state.store(READY, Ordering::Release);
+                }
+                Err(READY) => {}
+                Err(RUNNING) => {
+                    std::hint::spin_loop();
+                    std::thread::yield_now();
+                    continue;
+                }
+                Err(_) => panic!("OnceCell poisoned: its initializer panicked"),
+            }
+            return unsafe { (*self.value.get()).assume_init_ref() };
+        }
+    }
+
+    /// Whether an initializer panicked.
+    pub fn is_poisoned(&self) -> bool {
+        self.state.load(Ordering::Acquire) == POISONED
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == READY {
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
@@ -8,14 +8,43 @@
 //! - `AtomicBool`'s `compare_exchange` to implement lock acquisition
 //! - `core::hint::spin_loop` to reduce CPU power consumption
 //! - `UnsafeCell` provides interior mutability
+//!
+//! ## Contention statistics
+//! Each lock counts its acquisitions, how many of them had to wait (*contended*), and how many
+//! `spin_loop` rounds the waiting took. `stats()` reads them, so a test can show how much a lock
+//! is fought over. Building with the `no-stats` feature compiles the counters out, the way a
+//! kernel drops lock statistics from production builds.
 
 use std::cell::UnsafeCell;
+#[cfg(not(feature = "no-stats"))]
+use std::sync::atomic::AtomicU64;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+/// Counters of one `SpinLock`, as returned by `stats()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockStats {
+    /// Successful `lock` and `try_lock` calls.
+    pub acquisitions: u64,
+    /// `lock` calls whose first attempt found the lock already taken.
+    pub contended: u64,
+    /// `spin_loop` rounds spent waiting, over all `lock` calls.
+    pub spins: u64,
+}
+
+#[cfg(not(feature = "no-stats"))]
+#[derive(Default)]
+struct Counters {
+    acquisitions: AtomicU64,
+    contended: AtomicU64,
+    spins: AtomicU64,
+}
+
 /// Basic spin lock
 pub struct SpinLock<T> {
     locked: AtomicBool,
     data: UnsafeCell<T>,
+    #[cfg(not(feature = "no-stats"))]
+    counters: Counters,
 }
 
 unsafe impl<T: Send> Sync for SpinLock<T> {}
@@ -26,6 +55,8 @@ impl<T> SpinLock<T> {
         Self {
             locked: AtomicBool::new(false),
             data: UnsafeCell::new(data),
+            #[cfg(not(feature = "no-stats"))]
+            counters: Counters::default(),
         }
     }
 
@@ -35,7 +66,9 @@ impl<T> SpinLock<T> {
     /// 1. In a loop, try to change locked from false to true
     /// 2. Success uses Acquire ordering, failure uses Relaxed
     /// 3. On failure call `core::hint::spin_loop()` to hint CPU
-    /// 4. On success return `&mut *self.data.get()`
+    /// 4. Count the `spin_loop` rounds, and whether the first attempt failed; once the lock is
+    ///    yours, report them with `self.record(contended, spins)`
+    /// 5. On success return `&mut *self.data.get()`
     ///
     /// # Safety
     /// Caller must ensure `unlock` is called after using the data.
@@ -53,86 +86,222 @@ impl<T> SpinLock<T> {
     }
 
     /// Try to acquire lock without spinning.
-    /// Returns Some(&mut T) on success, None if lock is busy.
+    /// Returns Some(&mut T) on success, None if lock is busy. A success counts as an
+    /// uncontended acquisition: `self.record(false, 0)`.
     pub fn try_lock(&self) -> Option<&mut T> {
         // TODO: Single compare_exchange attempt
         todo!()
     }
+
+    /// Count one acquisition; `spins` is how many rounds it waited. Relaxed is enough: the
+    /// counters are statistics and order nothing.
+    #[cfg(not(feature = "no-stats"))]
+    #[allow(dead_code)]
+    fn record(&self, contended: bool, spins: u64) {
+        let c = &self.counters;
+        c.acquisitions.fetch_add(1, Ordering::Relaxed);
+        if contended {
+            c.contended.fetch_add(1, Ordering::Relaxed);
+            c.spins.fetch_add(spins, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(feature = "no-stats")]
+    #[inline(always)]
+    #[allow(dead_code)]
+    fn record(&self, _contended: bool, _spins: u64) {}
+
+    /// Counters since the lock was created. All zero when built with the `no-stats` feature.
+    pub fn stats(&self) -> LockStats {
+        #[cfg(not(feature = "no-stats"))]
+        {
+            let c = &self.counters;
+            LockStats {
+                acquisitions: c.acquisitions.load(Ordering::Relaxed),
+                contended: c.contended.load(Ordering::Relaxed),
+                spins: c.spins.load(Ordering::Relaxed),
+            }
+        }
+        #[cfg(feature = "no-stats")]
+        LockStats::default()
+    }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
     use std::sync::Arc;
     use std::thread;
+    use std::time::Duration;
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_try_lock",
+            "try_lock takes the lock with one compare_exchange(false, true) and returns None if it was already held",
+        ),
+        (
+            "test_concurrent_counter",
+            "lock with compare_exchange(false, true, Acquire, Relaxed) in a loop and unlock with store(false, Release)",
+        ),
+        (
+            "test_stats_count_acquisitions",
+            "call self.record once per successful lock or try_lock, and never for a failed try_lock",
+        ),
+        (
+            "test_stats_contended_lock",
+            "remember that the first compare_exchange failed and count every spin_loop round before calling record",
+        ),
+    ];
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(10);
 
     #[test]
     fn test_basic_lock_unlock() {
-        let lock = SpinLock::new(0u32);
-        {
+        run_with_timeout(TIMEOUT, || {
+            let lock = SpinLock::new(0u32);
+            {
+                let data = lock.lock();
+                *data = 42;
+                lock.unlock();
+            }
             let data = lock.lock();
-            *data = 42;
+            assert_eq!(*data, 42);
             lock.unlock();
-        }
-        let data = lock.lock();
-        assert_eq!(*data, 42);
-        lock.unlock();
+        });
     }
 
     #[test]
     fn test_try_lock() {
         let lock = SpinLock::new(0u32);
-        assert!(lock.try_lock().is_some());
+        assert_with_hint!(lock.try_lock().is_some());
         lock.unlock();
     }
 
     #[test]
     fn test_concurrent_counter() {
-        let lock = Arc::new(SpinLock::new(0u64));
-        let mut handles = vec![];
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(SpinLock::new(0u64));
+            let mut handles = vec![];
+
+            for _ in 0..10 {
+                let l = Arc::clone(&lock);
+                handles.push(thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let data = l.lock();
+                        *data += 1;
+                        l.unlock();
+                    }
+                }));
+            }
+
+            for h in handles {
+                h.join().unwrap();
+            }
 
-        for _ in 0..10 {
-            let l = Arc::clone(&lock);
-            handles.push(thread::spawn(move || {
-                for _ in 0..1000 {
+            #[cfg(not(feature = "no-stats"))]
+            {
+                let stats = lock.stats();
+                assert_eq_with_hint!(stats.acquisitions, 10_000);
+                assert!(stats.contended <= stats.acquisitions);
+                // One CPU only interleaves the threads at preemption, which rarely lands inside
+                // the critical section.
+                if thread::available_parallelism().map_or(1, |n| n.get()) >= 2 {
+                    assert_with_hint!(stats.contended > 0, "10 threads never collided: {stats:?}");
+                    assert!(stats.spins > 0, "{stats:?}");
+                }
+            }
+
+            let data = lock.lock();
+            assert_eq_with_hint!(*data, 10000);
+            lock.unlock();
+        });
+    }
+
+    #[test]
+    fn test_lock_protects_data() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(SpinLock::new(Vec::new()));
+            let mut handles = vec![];
+
+            for i in 0..5 {
+                let l = Arc::clone(&lock);
+                handles.push(thread::spawn(move || {
                     let data = l.lock();
-                    *data += 1;
+                    data.push(i);
                     l.unlock();
-                }
-            }));
-        }
+                }));
+            }
 
-        for h in handles {
-            h.join().unwrap();
-        }
+            for h in handles {
+                h.join().unwrap();
+            }
 
-        let data = lock.lock();
-        assert_eq!(*data, 10000);
-        lock.unlock();
+            let data = lock.lock();
+            let mut sorted = data.clone();
+            sorted.sort();
+            assert_eq!(sorted, vec![0, 1, 2, 3, 4]);
+            lock.unlock();
+        });
     }
 
     #[test]
-    fn test_lock_protects_data() {
-        let lock = Arc::new(SpinLock::new(Vec::new()));
-        let mut handles = vec![];
-
-        for i in 0..5 {
-            let l = Arc::clone(&lock);
-            handles.push(thread::spawn(move || {
-                let data = l.lock();
-                data.push(i);
-                l.unlock();
-            }));
+    #[cfg(not(feature = "no-stats"))]
+    fn test_stats_count_acquisitions() {
+        let lock = SpinLock::new(0u32);
+        assert_eq!(lock.stats(), LockStats::default());
+        for _ in 0..3 {
+            *lock.lock() += 1;
+            lock.unlock();
         }
+        assert!(lock.try_lock().is_some());
+        assert!(lock.try_lock().is_none());
+        lock.unlock();
+        let stats = lock.stats();
+        assert_eq_with_hint!(stats.acquisitions, 4);
+        assert_eq_with_hint!(stats.contended, 0);
+        assert_eq!(stats.spins, 0);
+    }
 
-        for h in handles {
-            h.join().unwrap();
-        }
+    #[test]
+    #[cfg(not(feature = "no-stats"))]
+    fn test_stats_contended_lock() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(SpinLock::new(0u32));
+            lock.lock();
+            let waiter = {
+                let l = Arc::clone(&lock);
+                thread::spawn(move || {
+                    *l.lock() += 1;
+                    l.unlock();
+                })
+            };
+            // Keep the lock long enough for the waiter to start spinning on it.
+            thread::sleep(Duration::from_millis(30));
+            lock.unlock();
+            waiter.join().unwrap();
+            let stats = lock.stats();
+            assert_eq_with_hint!(stats.acquisitions, 2);
+            assert_eq_with_hint!(stats.contended, 1);
+            assert_with_hint!(stats.spins > 0, "{stats:?}");
+        });
+    }
 
-        let data = lock.lock();
-        let mut sorted = data.clone();
-        sorted.sort();
-        assert_eq!(sorted, vec![0, 1, 2, 3, 4]);
+    #[test]
+    #[cfg(feature = "no-stats")]
+    fn test_no_stats_reports_zero() {
+        let lock = SpinLock::new(0u32);
+        lock.lock();
         lock.unlock();
+        assert_eq!(lock.stats(), LockStats::default());
     }
 }
@@ -0,0 +1,143 @@
+//! # Spin Lock
+//!
+//! In this exercise, you will implement a basic spin lock.
+//! Spin locks are one of the most fundamental synchronization primitives in OS kernels.
+//!
+//! ## Key Concepts
+//! - Spin locks use busy-waiting to acquire the lock
+//! - `AtomicBool`'s `compare_exchange` to implement lock acquisition
+//! - `core::hint::spin_loop` to reduce CPU power consumption
+//! - `UnsafeCell` provides interior mutability
+//!
+//! ## Contention statistics
+//! Each lock counts its acquisitions, how many of them had to wait (*contended*), and how many
+//! `spin_loop` rounds the waiting took. `stats()` reads them, so a test can show how much a lock
+//! is fought over. Building with the `no-stats` feature compiles the counters out, the way a
+//! kernel drops lock statistics from production builds.
+
+use std::cell::UnsafeCell;
+#[cfg(not(feature = "no-stats"))]
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Counters of one `SpinLock`, as returned by `stats()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockStats {
+    /// Successful `lock` and `try_lock` calls.
+    pub acquisitions: u64,
+    /// `lock` calls whose first attempt found the lock already taken.
+    pub contended: u64,
+    /// `spin_loop` rounds spent waiting, over all `lock` calls.
+    pub spins: u64,
+}
+
+#[cfg(not(feature = "no-stats"))]
+#[derive(Default)]
+struct Counters {
+    acquisitions: AtomicU64,
+    contended: AtomicU64,
+    spins: AtomicU64,
+}
+
+/// Basic spin lock
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+    #[cfg(not(feature = "no-stats"))]
+    counters: Counters,
+}
+
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+unsafe impl<T: Send> Send for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(data),
+            #[cfg(not(feature = "no-stats"))]
+            counters: Counters::default(),
+        }
+    }
+
+    /// Acquire lock, returning a mutable reference to inner data.
+    ///
+    /// TODO: Use compare_exchange to spin until lock is acquired
+    /// 1. In a loop, try to change locked from false to true
+    /// 2. Success uses Acquire ordering, failure uses Relaxed
+    /// 3. On failure call `core::hint::spin_loop()` to hint CPU
+    /// 4. Count the `spin_loop` rounds, and whether the first attempt failed; once the lock is
+    ///    yours, report them with `self.record(contended, spins)`
+    /// 5. On success return `&mut *self.data.get()`
+    ///
+    /// # Safety
+    /// Caller must ensure `unlock` is called after using the data.
+    pub fn lock(&self) -> &mut T {
+        let mut contended = false;
+        let mut spins = 0;
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            contended = true;
+            while self.locked.load(Ordering::Relaxed) {
+                spins += 1;
+                std::hint::spin_loop();
+            }
+        }
+        self.record(contended, spins);
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Release lock.
+    ///
+    /// TODO: Set locked to false (using Release ordering)
+    pub fn unlock(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+
+    /// Try to acquire lock without spinning.
+    /// Returns Some(&mut T) on success, None if lock is busy. A success counts as an
+    /// uncontended acquisition: `self.record(false, 0)`.
+    pub fn try_lock(&self) -> Option<&mut T> {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| {
+                self.record(false, 0);
+                unsafe { &mut *self.data.get() }
+            })
+    }
+
+    /// Count one acquisition; `spins` is how many rounds it waited. Relaxed is enough: the
+    /// counters are statistics and order nothing.
+    #[cfg(not(feature = "no-stats"))]
+    fn record(&self, contended: bool, spins: u64) {
+        let c = &self.counters;
+        c.acquisitions.fetch_add(1, Ordering::Relaxed);
+        if contended {
+            c.contended.fetch_add(1, Ordering::Relaxed);
+            c.spins.fetch_add(spins, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(feature = "no-stats")]
+    #[inline(always)]
+    fn record(&self, _contended: bool, _spins: u64) {}
+
+    /// Counters since the lock was created. All zero when built with the `no-stats` feature.
+    pub fn stats(&self) -> LockStats {
+        #[cfg(not(feature = "no-stats"))]
+        {
+            let c = &self.counters;
+            LockStats {
+                acquisitions: c.acquisitions.load(Ordering::Relaxed),
+                contended: c.contended.load(Ordering::Relaxed),
+                spins: c.spins.load(Ordering::Relaxed),
+            }
+        }
+        #[cfg(feature = "no-stats")]
+        LockStats::default()
+    }
+}
@@ -8,8 +8,13 @@
 //! - `Deref` / `DerefMut` traits for transparent access
 //! - `Drop` trait for automatic release
 //! - Why manual lock/unlock is unsafe (forgetting unlock, panic without release)
+//! - Mapping a guard (`SpinGuard::map`) to hand out access to one field while the whole lock
+//!   stays held, as `parking_lot`'s `MutexGuard::map` does
 
 use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+#[allow(unused_imports)]
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -71,23 +76,122 @@ impl<T> Drop for SpinGuard<'_, T> {
     }
 }
 
+/// A `SpinGuard` narrowed to a part of the locked data by `SpinGuard::map`. It still holds the
+/// whole lock, and releases it on drop.
+pub struct MappedSpinGuard<'a, U: ?Sized> {
+    locked: &'a AtomicBool,
+    data: *mut U,
+    /// Borrows the locked data mutably for `'a`, like the `&'a mut U` it was made from.
+    _marker: PhantomData<&'a mut U>,
+}
+
+unsafe impl<U: ?Sized + Sync> Sync for MappedSpinGuard<'_, U> {}
+
+impl<'a, T> SpinGuard<'a, T> {
+    /// Narrow the guard to the part of the data `f` picks, e.g. one field of a struct, without
+    /// unlocking in between. An associated function (`SpinGuard::map(guard, f)`) so it cannot
+    /// shadow a `map` method of `T`.
+    ///
+    /// If `f` panics, the lock is released.
+    #[allow(unused_variables)]
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&mut T) -> &mut U) -> MappedSpinGuard<'a, U> {
+        // TODO: Call f on the data while `this` still holds the lock (if f panics, `this`
+        // is dropped during unwinding and unlocks), then mem::forget(this) so the lock stays
+        // held, and build MappedSpinGuard { locked: &lock.locked, data, _marker: PhantomData }
+        todo!()
+    }
+}
+
+impl<'a, U: ?Sized> MappedSpinGuard<'a, U> {
+    /// Narrow a mapped guard further.
+    #[allow(unused_variables)]
+    pub fn map<V: ?Sized>(this: Self, f: impl FnOnce(&mut U) -> &mut V) -> MappedSpinGuard<'a, V> {
+        // TODO: Same as SpinGuard::map, starting from this.data
+        todo!()
+    }
+}
+
+impl<U: ?Sized> Deref for MappedSpinGuard<'_, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> DerefMut for MappedSpinGuard<'_, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedSpinGuard<'_, U> {
+    fn drop(&mut self) {
+        // TODO: Release the lock, as SpinGuard's Drop does
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Arc;
     use std::thread;
+    use std::time::Duration;
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_guard_auto_release",
+            "the guard's Drop must release the lock (store false with Release), or the next lock() spins forever",
+        ),
+        (
+            "test_guard_deref_mut",
+            "DerefMut hands out &mut to the data through the UnsafeCell: unsafe { &mut *self.lock.data.get() }",
+        ),
+        (
+            "test_panic_safety",
+            "a panic while holding the guard still runs its Drop, which has to unlock",
+        ),
+        (
+            "test_map_keeps_lock_held",
+            "mem::forget the original guard after mapping: its Drop would unlock while the mapped guard is still alive",
+        ),
+        (
+            "test_mapped_guard_releases_on_drop",
+            "the mapped guard's Drop must store false into the lock's flag, exactly like SpinGuard's Drop",
+        ),
+        (
+            "test_map_closure_panics",
+            "call f before forgetting the original guard, so a panic in f still drops (and unlocks) it",
+        ),
+    ];
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(10);
 
     #[test]
     fn test_guard_auto_release() {
-        let lock = SpinLock::new(0u32);
-        {
-            let mut guard = lock.lock();
-            *guard = 42;
-            // guard drops here, automatically releasing lock
-        }
-        // Should be able to acquire lock again
-        let guard = lock.lock();
-        assert_eq!(*guard, 42);
+        run_with_timeout(TIMEOUT, || {
+            let lock = SpinLock::new(0u32);
+            {
+                let mut guard = lock.lock();
+                *guard = 42;
+                // guard drops here, automatically releasing lock
+            }
+            // Should be able to acquire lock again
+            let guard = lock.lock();
+            assert_eq_with_hint!(*guard, 42);
+        });
     }
 
     #[test]
@@ -108,46 +212,136 @@ mod tests {
             guard.push(3);
         }
         let guard = lock.lock();
-        assert_eq!(&*guard, &[1, 2, 3]);
+        assert_eq_with_hint!(&*guard, &[1, 2, 3]);
     }
 
     #[test]
     fn test_concurrent_with_guard() {
-        let lock = Arc::new(SpinLock::new(0u64));
-        let mut handles = vec![];
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(SpinLock::new(0u64));
+            let mut handles = vec![];
 
-        for _ in 0..10 {
-            let l = Arc::clone(&lock);
-            handles.push(thread::spawn(move || {
-                for _ in 0..1000 {
-                    let mut guard = l.lock();
-                    *guard += 1;
-                    // guard automatically released
-                }
-            }));
-        }
+            for _ in 0..10 {
+                let l = Arc::clone(&lock);
+                handles.push(thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let mut guard = l.lock();
+                        *guard += 1;
+                        // guard automatically released
+                    }
+                }));
+            }
 
-        for h in handles {
-            h.join().unwrap();
-        }
+            for h in handles {
+                h.join().unwrap();
+            }
 
-        assert_eq!(*lock.lock(), 10000);
+            assert_eq!(*lock.lock(), 10000);
+        });
     }
 
     #[test]
     fn test_panic_safety() {
-        let lock = Arc::new(SpinLock::new(0u32));
-        let l = Arc::clone(&lock);
-
-        let result = thread::spawn(move || {
-            let mut guard = l.lock();
-            *guard = 42;
-            panic!("intentional panic");
-        })
-        .join();
-
-        assert!(result.is_err());
-        // Even if thread panics, guard's Drop should release lock
-        // Note: this test may have different results due to panic unwind behavior
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(SpinLock::new(0u32));
+            let l = Arc::clone(&lock);
+
+            let result = thread::spawn(move || {
+                let mut guard = l.lock();
+                *guard = 42;
+                panic!("intentional panic");
+            })
+            .join();
+
+            assert_with_hint!(result.is_err());
+            // Even if thread panics, guard's Drop should release lock
+            // Note: this test may have different results due to panic unwind behavior
+        });
+    }
+
+    #[derive(Default)]
+    struct Task {
+        id: u32,
+        log: Vec<&'static str>,
+    }
+
+    #[test]
+    fn test_map_to_field() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = SpinLock::new(Task::default());
+            {
+                let mut log = SpinGuard::map(lock.lock(), |t| &mut t.log);
+                log.push("start");
+                log.push("stop");
+            }
+            *SpinGuard::map(lock.lock(), |t| &mut t.id) = 7;
+            let t = lock.lock();
+            assert_eq!((t.id, t.log.as_slice()), (7, &["start", "stop"][..]));
+        });
+    }
+
+    #[test]
+    fn test_map_chained_and_unsized() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = SpinLock::new(vec![String::from("a"), String::from("b")]);
+            {
+                let all: MappedSpinGuard<'_, [String]> =
+                    SpinGuard::map(lock.lock(), |v| &mut v[..]);
+                assert_eq!(all.len(), 2);
+                let mut first = MappedSpinGuard::map(all, |s| &mut s[0]);
+                first.push('!');
+            }
+            assert_eq!(*lock.lock(), ["a!", "b"]);
+        });
+    }
+
+    #[test]
+    fn test_map_keeps_lock_held() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(SpinLock::new(Task::default()));
+            let released = Arc::new(AtomicBool::new(false));
+            let mut id = SpinGuard::map(lock.lock(), |t| &mut t.id);
+            let other = {
+                let (lock, released) = (Arc::clone(&lock), Arc::clone(&released));
+                thread::spawn(move || {
+                    let t = lock.lock();
+                    assert_with_hint!(
+                        released.load(Ordering::SeqCst),
+                        "another thread got the lock while a mapped guard was alive"
+                    );
+                    t.id
+                })
+            };
+            thread::sleep(Duration::from_millis(30));
+            *id = 5;
+            released.store(true, Ordering::SeqCst);
+            drop(id);
+            assert_eq!(other.join().unwrap(), 5);
+        });
+    }
+
+    #[test]
+    fn test_mapped_guard_releases_on_drop() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = SpinLock::new(Task::default());
+            for i in 0..3 {
+                let mut id = SpinGuard::map(lock.lock(), |t| &mut t.id);
+                *id += i;
+            }
+            // Hangs (and times out) if a mapped guard forgot to unlock.
+            assert_eq_with_hint!(lock.lock().id, 3);
+        });
+    }
+
+    #[test]
+    fn test_map_closure_panics() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = SpinLock::new(Task::default());
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                SpinGuard::map(lock.lock(), |_| -> &mut u32 { panic!("no such field") });
+            }));
+            assert!(result.is_err());
+            assert_eq_with_hint!(lock.lock().id, 0);
+        });
     }
 }
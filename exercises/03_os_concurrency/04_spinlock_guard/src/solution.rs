@@ -0,0 +1,146 @@
+//! # RAII Spin Lock Guard
+//!
+//! In this exercise, you will implement an RAII guard for a spin lock, causing the lock to be automatically released when leaving scope.
+//! This is a classic application of Rust's ownership system in systems programming.
+//!
+//! ## Key Points
+//! - RAII (Resource Acquisition Is Initialization) pattern
+//! - `Deref` / `DerefMut` traits for transparent access
+//! - `Drop` trait for automatic release
+//! - Why manual lock/unlock is unsafe (forgetting unlock, panic without release)
+//! - Mapping a guard (`SpinGuard::map`) to hand out access to one field while the whole lock
+//!   stays held, as `parking_lot`'s `MutexGuard::map` does
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+unsafe impl<T: Send> Send for SpinLock<T> {}
+
+/// Spin lock guard: RAII handle holding the lock.
+/// Automatically releases the lock when SpinGuard is dropped.
+pub struct SpinGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+impl<T> SpinLock<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Acquire lock, returning SpinGuard.
+    ///
+    /// TODO: Spin-wait to acquire lock (compare_exchange), return SpinGuard on success.
+    pub fn lock(&self) -> SpinGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            while self.locked.load(Ordering::Relaxed) {
+                std::hint::spin_loop();
+            }
+        }
+        SpinGuard { lock: self }
+    }
+}
+
+// TODO: Implement Deref trait for SpinGuard
+// Return &T, obtained via self.lock.data.get()
+impl<T> Deref for SpinGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+// TODO: Implement DerefMut trait for SpinGuard
+// Return &mut T
+impl<T> DerefMut for SpinGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+// TODO: Implement Drop trait for SpinGuard
+// Set lock.locked to false (Release ordering)
+impl<T> Drop for SpinGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// A `SpinGuard` narrowed to a part of the locked data by `SpinGuard::map`. It still holds the
+/// whole lock, and releases it on drop.
+pub struct MappedSpinGuard<'a, U: ?Sized> {
+    locked: &'a AtomicBool,
+    data: *mut U,
+    /// Borrows the locked data mutably for `'a`, like the `&'a mut U` it was made from.
+    _marker: PhantomData<&'a mut U>,
+}
+
+unsafe impl<U: ?Sized + Sync> Sync for MappedSpinGuard<'_, U> {}
+
+impl<'a, T> SpinGuard<'a, T> {
+    /// Narrow the guard to the part of the data `f` picks, e.g. one field of a struct, without
+    /// unlocking in between. An associated function (`SpinGuard::map(guard, f)`) so it cannot
+    /// shadow a `map` method of `T`.
+    ///
+    /// If `f` panics, the lock is released.
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&mut T) -> &mut U) -> MappedSpinGuard<'a, U> {
+        let lock = this.lock;
+        let data: *mut U = f(unsafe { &mut *lock.data.get() });
+        mem::forget(this);
+        MappedSpinGuard {
+            locked: &lock.locked,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, U: ?Sized> MappedSpinGuard<'a, U> {
+    /// Narrow a mapped guard further.
+    pub fn map<V: ?Sized>(this: Self, f: impl FnOnce(&mut U) -> &mut V) -> MappedSpinGuard<'a, V> {
+        let locked = this.locked;
+        let data: *mut V = f(unsafe { &mut *this.data });
+        mem::forget(this);
+        MappedSpinGuard {
+            locked,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized> Deref for MappedSpinGuard<'_, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> DerefMut for MappedSpinGuard<'_, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedSpinGuard<'_, U> {
+    fn drop(&mut self) {
+        self.locked.store(false, Ordering::Release);
+    }
+}
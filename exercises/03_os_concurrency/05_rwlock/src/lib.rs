@@ -25,10 +25,27 @@
 //! ## State (single atomic)
 //! We use one `AtomicU32`: low bits = reader count, two flags = writer holding / writer waiting.
 //! All logic is implemented with compare_exchange and load/store; no use of `std::sync::RwLock`.
+//!
+//! ## Not waiting forever
+//! `try_read` / `try_write` make a single attempt and return `None` instead of spinning.
+//! `read_timeout` / `write_timeout` retry until a deadline and then return `Err(TimedOut)`. A
+//! writer that times out must take back its `WRITER_WAITING` announcement, or readers would
+//! stay locked out by a writer that is no longer there.
+//!
+//! ## Mapped guards
+//! `RwLockReadGuard::map` / `RwLockWriteGuard::map` narrow a guard to one part of the data
+//! (a field, an element) while the lock stays held; dropping the mapped guard releases it.
 
 use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+#[allow(unused_imports)]
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicU32, Ordering};
+#[allow(unused_imports)]
+use std::thread;
+#[allow(unused_imports)]
+use std::time::{Duration, Instant};
 
 /// Maximum number of concurrent readers (fits in state bits).
 const READER_MASK: u32 = (1 << 30) - 1;
@@ -37,6 +54,10 @@ const WRITER_HOLDING: u32 = 1 << 30;
 /// Bit set when at least one writer is waiting (writer-priority: block new readers).
 const WRITER_WAITING: u32 = 1 << 31;
 
+/// `read_timeout` / `write_timeout` gave up before the lock became available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut;
+
 /// Writer-priority read-write lock. Implemented from scratch; does not use `std::sync::RwLock`.
 pub struct RwLock<T> {
     state: AtomicU32,
@@ -77,6 +98,60 @@ impl<T> RwLock<T> {
         // TODO
         todo!()
     }
+
+    /// Try to acquire a read lock without waiting. `None` if a writer holds the lock or is
+    /// waiting for it (writer-priority applies here too), or the reader count is full.
+    ///
+    /// TODO: Implement non-blocking read acquisition
+    /// 1. In a loop, load state (Acquire).
+    /// 2. If a writer bit is set or the reader count is full, return None right away.
+    /// 3. Try compare_exchange(s, s + 1, AcqRel, Acquire); on success return the guard. On
+    ///    failure only the reader count moved under us: go round again.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        // TODO
+        todo!()
+    }
+
+    /// Try to acquire the write lock without waiting. `None` if any reader or a writer holds it.
+    ///
+    /// TODO: Implement non-blocking write acquisition
+    /// 1. Load state (Acquire); if any reader or WRITER_HOLDING is set, return None.
+    /// 2. One compare_exchange(s, WRITER_HOLDING, AcqRel, Acquire); Some(guard) on success,
+    ///    None otherwise (someone else got there first). Do not set WRITER_WAITING: a caller
+    ///    that gives up must not hold readers back.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        // TODO
+        todo!()
+    }
+
+    /// `read`, giving up after `dur`.
+    ///
+    /// TODO: Implement timed read acquisition
+    /// 1. deadline = Instant::now() + dur.
+    /// 2. Loop on try_read; on success return Ok(guard).
+    /// 3. Past the deadline: Err(TimedOut). Otherwise spin_loop and `thread::yield_now()` (the
+    ///    writer we wait for may need this CPU to finish).
+    #[allow(unused_variables)]
+    pub fn read_timeout(&self, dur: Duration) -> Result<RwLockReadGuard<'_, T>, TimedOut> {
+        // TODO
+        todo!()
+    }
+
+    /// `write`, giving up after `dur`. Announces itself with WRITER_WAITING like `write` while it
+    /// waits, so new readers queue up behind it.
+    ///
+    /// TODO: Implement timed write acquisition
+    /// 1. deadline = Instant::now() + dur.
+    /// 2. In a loop: fetch_or(WRITER_WAITING, Release), then try to take the lock as `write` does;
+    ///    on success return Ok(guard).
+    /// 3. Past the deadline: clear WRITER_WAITING with fetch_and (readers must not stay blocked
+    ///    by a writer that left; any other waiting writer sets it again on its next round) and
+    ///    return Err(TimedOut). Otherwise spin_loop and `thread::yield_now()`.
+    #[allow(unused_variables)]
+    pub fn write_timeout(&self, dur: Duration) -> Result<RwLockWriteGuard<'_, T>, TimedOut> {
+        // TODO
+        todo!()
+    }
 }
 
 /// Guard for a read lock; releases the read lock on drop.
@@ -133,79 +208,440 @@ impl<T> Drop for RwLockWriteGuard<'_, T> {
     }
 }
 
+/// A read guard narrowed to a part of the data by `RwLockReadGuard::map`. It still holds the
+/// read lock, and releases it on drop.
+pub struct MappedRwLockReadGuard<'a, U: ?Sized> {
+    state: &'a AtomicU32,
+    data: *const U,
+    _marker: PhantomData<&'a U>,
+}
+
+unsafe impl<U: ?Sized + Sync> Sync for MappedRwLockReadGuard<'_, U> {}
+
+/// A write guard narrowed to a part of the data by `RwLockWriteGuard::map`. It still holds the
+/// write lock, and releases it on drop.
+pub struct MappedRwLockWriteGuard<'a, U: ?Sized> {
+    state: &'a AtomicU32,
+    data: *mut U,
+    _marker: PhantomData<&'a mut U>,
+}
+
+unsafe impl<U: ?Sized + Sync> Sync for MappedRwLockWriteGuard<'_, U> {}
+
+impl<'a, T> RwLockReadGuard<'a, T> {
+    /// Narrow the guard to the part of the data `f` picks, keeping the read lock. An associated
+    /// function (`RwLockReadGuard::map(guard, f)`) so it cannot shadow a `map` method of `T`.
+    ///
+    /// If `f` panics, the read lock is released.
+    #[allow(unused_variables)]
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&T) -> &U) -> MappedRwLockReadGuard<'a, U> {
+        // TODO: Call f while `this` still holds the read lock, then mem::forget(this) and build
+        // MappedRwLockReadGuard { state: &lock.state, data, _marker: PhantomData }
+        todo!()
+    }
+}
+
+impl<'a, T> RwLockWriteGuard<'a, T> {
+    /// Narrow the guard to the part of the data `f` picks, keeping the write lock.
+    ///
+    /// If `f` panics, the write lock is released.
+    #[allow(unused_variables)]
+    pub fn map<U: ?Sized>(
+        this: Self,
+        f: impl FnOnce(&mut T) -> &mut U,
+    ) -> MappedRwLockWriteGuard<'a, U> {
+        // TODO: Same as RwLockReadGuard::map, with &mut access
+        todo!()
+    }
+}
+
+impl<'a, U: ?Sized> MappedRwLockReadGuard<'a, U> {
+    /// Narrow a mapped guard further.
+    #[allow(unused_variables)]
+    pub fn map<V: ?Sized>(this: Self, f: impl FnOnce(&U) -> &V) -> MappedRwLockReadGuard<'a, V> {
+        // TODO: Same as RwLockReadGuard::map, starting from this.data
+        todo!()
+    }
+}
+
+impl<'a, U: ?Sized> MappedRwLockWriteGuard<'a, U> {
+    /// Narrow a mapped guard further.
+    #[allow(unused_variables)]
+    pub fn map<V: ?Sized>(
+        this: Self,
+        f: impl FnOnce(&mut U) -> &mut V,
+    ) -> MappedRwLockWriteGuard<'a, V> {
+        // TODO: Same as RwLockWriteGuard::map, starting from this.data
+        todo!()
+    }
+}
+
+impl<U: ?Sized> Deref for MappedRwLockReadGuard<'_, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedRwLockReadGuard<'_, U> {
+    fn drop(&mut self) {
+        // TODO: Release the read lock, as RwLockReadGuard's Drop does
+        todo!()
+    }
+}
+
+impl<U: ?Sized> Deref for MappedRwLockWriteGuard<'_, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> DerefMut for MappedRwLockWriteGuard<'_, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedRwLockWriteGuard<'_, U> {
+    fn drop(&mut self) {
+        // TODO: Release the write lock, as RwLockWriteGuard's Drop does
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::mpsc;
     use std::sync::Arc;
     use std::thread;
+    use std::time::{Duration, Instant};
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_multiple_readers",
+            "readers only block while a writer holds the lock: increment the reader count with a CAS that fails if the writer bit is set",
+        ),
+        (
+            "test_writer_excludes_readers",
+            "a writer may only take the lock when the state is 0: no readers and no writer",
+        ),
+        (
+            "test_concurrent_writes_serialized",
+            "dropping a write guard must clear the writer bit, or every later read/write spins forever",
+        ),
+        (
+            "test_try_read_fails_fast_under_writer",
+            "try_read must return None as soon as it sees a writer bit instead of spinning",
+        ),
+        (
+            "test_try_write_fails_with_readers",
+            "try_write needs a state with no readers and no writer; it must not set WRITER_WAITING",
+        ),
+        (
+            "test_read_timeout_expires",
+            "compare Instant::now() against the deadline on every round and return Err(TimedOut) once it passed",
+        ),
+        (
+            "test_write_timeout_withdraws",
+            "on timeout, clear WRITER_WAITING with fetch_and, or new readers keep waiting for a writer that gave up",
+        ),
+        (
+            "test_mapped_read_guard_releases",
+            "mem::forget the original guard after mapping, and have the mapped guard's Drop do the fetch_sub(1)",
+        ),
+        (
+            "test_mapped_write_guard_releases",
+            "the mapped write guard's Drop must clear both writer bits, like RwLockWriteGuard's Drop",
+        ),
+    ];
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(10);
 
     #[test]
     fn test_multiple_readers() {
-        let lock = Arc::new(RwLock::new(0u32));
-        let mut handles = vec![];
-        for _ in 0..10 {
-            let l = Arc::clone(&lock);
-            handles.push(thread::spawn(move || {
-                let g = l.read();
-                assert_eq!(*g, 0);
-            }));
-        }
-        for h in handles {
-            h.join().unwrap();
-        }
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(RwLock::new(0u32));
+            let mut handles = vec![];
+            for _ in 0..10 {
+                let l = Arc::clone(&lock);
+                handles.push(thread::spawn(move || {
+                    let g = l.read();
+                    assert_eq_with_hint!(*g, 0);
+                }));
+            }
+            for h in handles {
+                h.join().unwrap();
+            }
+        });
     }
 
     #[test]
     fn test_writer_excludes_readers() {
-        let lock = Arc::new(RwLock::new(0u32));
-        let lock_w = Arc::clone(&lock);
-        let writer = thread::spawn(move || {
-            let mut g = lock_w.write();
-            *g = 42;
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(RwLock::new(0u32));
+            let lock_w = Arc::clone(&lock);
+            let writer = thread::spawn(move || {
+                let mut g = lock_w.write();
+                *g = 42;
+            });
+            writer.join().unwrap();
+            let g = lock.read();
+            assert_eq_with_hint!(*g, 42);
         });
-        writer.join().unwrap();
-        let g = lock.read();
-        assert_eq!(*g, 42);
     }
 
     #[test]
     fn test_concurrent_reads_after_write() {
-        let lock = Arc::new(RwLock::new(Vec::<i32>::new()));
-        {
-            let mut g = lock.write();
-            g.push(1);
-            g.push(2);
-        }
-        let mut handles = vec![];
-        for _ in 0..5 {
-            let l = Arc::clone(&lock);
-            handles.push(thread::spawn(move || {
-                let g = l.read();
-                assert_eq!(g.len(), 2);
-                assert_eq!(&*g, &[1, 2]);
-            }));
-        }
-        for h in handles {
-            h.join().unwrap();
-        }
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(RwLock::new(Vec::<i32>::new()));
+            {
+                let mut g = lock.write();
+                g.push(1);
+                g.push(2);
+            }
+            let mut handles = vec![];
+            for _ in 0..5 {
+                let l = Arc::clone(&lock);
+                handles.push(thread::spawn(move || {
+                    let g = l.read();
+                    assert_eq!(g.len(), 2);
+                    assert_eq!(&*g, &[1, 2]);
+                }));
+            }
+            for h in handles {
+                h.join().unwrap();
+            }
+        });
     }
 
     #[test]
     fn test_concurrent_writes_serialized() {
-        let lock = Arc::new(RwLock::new(0u64));
-        let mut handles = vec![];
-        for _ in 0..10 {
-            let l = Arc::clone(&lock);
-            handles.push(thread::spawn(move || {
-                for _ in 0..100 {
-                    let mut g = l.write();
-                    *g += 1;
-                }
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(RwLock::new(0u64));
+            let mut handles = vec![];
+            for _ in 0..10 {
+                let l = Arc::clone(&lock);
+                handles.push(thread::spawn(move || {
+                    for _ in 0..100 {
+                        let mut g = l.write();
+                        *g += 1;
+                    }
+                }));
+            }
+            for h in handles {
+                h.join().unwrap();
+            }
+            assert_eq_with_hint!(*lock.read(), 1000);
+        });
+    }
+
+    /// Spawn a thread that takes the write lock, reports that it holds it, keeps it for `hold`,
+    /// then writes `value` and releases it.
+    fn hold_write(lock: &Arc<RwLock<u32>>, hold: Duration, value: u32) -> thread::JoinHandle<()> {
+        let lock = Arc::clone(lock);
+        let (tx, rx) = mpsc::channel();
+        let h = thread::spawn(move || {
+            let mut g = lock.write();
+            tx.send(()).unwrap();
+            thread::sleep(hold);
+            *g = value;
+        });
+        rx.recv().unwrap();
+        h
+    }
+
+    #[test]
+    fn test_try_read_fails_fast_under_writer() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(RwLock::new(0u32));
+            let writer = hold_write(&lock, Duration::from_millis(300), 7);
+            let start = Instant::now();
+            assert_with_hint!(lock.try_read().is_none());
+            assert!(lock.try_write().is_none());
+            assert_with_hint!(
+                start.elapsed() < Duration::from_millis(100),
+                "try_read/try_write took {:?} while a writer held the lock",
+                start.elapsed()
+            );
+            writer.join().unwrap();
+            assert_eq!(lock.try_read().map(|g| *g), Some(7));
+        });
+    }
+
+    #[test]
+    fn test_try_write_fails_with_readers() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = RwLock::new(1u32);
+            let r1 = lock.try_read().expect("free lock");
+            let r2 = lock.try_read().expect("readers share the lock");
+            assert_with_hint!(lock.try_write().is_none());
+            assert_with_hint!(
+                lock.try_read().is_some(),
+                "a failed try_write left readers locked out"
+            );
+            drop((r1, r2));
+            let mut w = lock.try_write().expect("no readers left");
+            *w = 2;
+            assert!(lock.try_read().is_none());
+            drop(w);
+            assert_eq!(*lock.read(), 2);
+        });
+    }
+
+    #[test]
+    fn test_read_timeout_expires() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = RwLock::new(0u32);
+            let w = lock.write();
+            let start = Instant::now();
+            let r = lock.read_timeout(Duration::from_millis(50));
+            let waited = start.elapsed();
+            assert_eq_with_hint!(r.err(), Some(TimedOut));
+            assert_with_hint!(
+                waited >= Duration::from_millis(50),
+                "gave up after {waited:?}"
+            );
+            assert!(lock.write_timeout(Duration::from_millis(10)).is_err());
+            drop(w);
+            assert!(lock.read_timeout(Duration::from_millis(10)).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_write_timeout_withdraws() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = RwLock::new(0u32);
+            let r = lock.read();
+            let start = Instant::now();
+            assert_eq_with_hint!(
+                lock.write_timeout(Duration::from_millis(30)).err(),
+                Some(TimedOut)
+            );
+            assert!(start.elapsed() >= Duration::from_millis(30));
+            assert_with_hint!(
+                lock.try_read().is_some(),
+                "readers are still blocked by a writer that timed out"
+            );
+            drop(r);
+            *lock.write_timeout(Duration::from_millis(10)).unwrap() = 5;
+            assert_eq!(*lock.read(), 5);
+        });
+    }
+
+    #[test]
+    fn test_timed_acquire_succeeds_after_release() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(RwLock::new(0u32));
+            let writer = hold_write(&lock, Duration::from_millis(30), 9);
+            let g = lock
+                .read_timeout(Duration::from_secs(5))
+                .expect("writer lets go");
+            assert_eq!(*g, 9);
+            drop(g);
+            writer.join().unwrap();
+            let writer = hold_write(&lock, Duration::from_millis(30), 11);
+            let mut g = lock
+                .write_timeout(Duration::from_secs(5))
+                .expect("writer lets go");
+            assert_eq!(*g, 11);
+            *g = 12;
+            drop(g);
+            writer.join().unwrap();
+            assert_eq!(*lock.read(), 12);
+        });
+    }
+
+    #[derive(Default)]
+    struct Config {
+        name: String,
+        ports: Vec<u16>,
+    }
+
+    #[test]
+    fn test_mapped_read_guard_releases() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = RwLock::new(Config {
+                name: "web".into(),
+                ports: vec![80, 443],
+            });
+            {
+                let name = RwLockReadGuard::map(lock.read(), |c| c.name.as_str());
+                assert_eq!(&*name, "web");
+                let port = RwLockReadGuard::map(lock.read(), |c| &c.ports);
+                let first = MappedRwLockReadGuard::map(port, |p| &p[0]);
+                assert_eq!(*first, 80);
+                assert_with_hint!(
+                    lock.try_write().is_none(),
+                    "mapped read guards must keep the read lock"
+                );
+            }
+            assert_with_hint!(
+                lock.try_write().is_some(),
+                "the read lock leaked after the mapped guards dropped"
+            );
+        });
+    }
+
+    #[test]
+    fn test_mapped_write_guard_releases() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = RwLock::new(Config::default());
+            {
+                let mut ports = RwLockWriteGuard::map(lock.write(), |c| &mut c.ports);
+                ports.push(8080);
+                assert_with_hint!(lock.try_read().is_none());
+                let mut slice = MappedRwLockWriteGuard::map(ports, |p| &mut p[..]);
+                slice[0] += 1;
+            }
+            assert_eq_with_hint!(lock.try_read().map(|c| c.ports.clone()), Some(vec![8081]));
+            *RwLockWriteGuard::map(lock.write(), |c| &mut c.name) = "db".into();
+            assert_eq!(lock.read().name, "db");
+        });
+    }
+
+    #[test]
+    fn test_mapped_write_guard_blocks_other_threads() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = Arc::new(RwLock::new(Config::default()));
+            let mut name = RwLockWriteGuard::map(lock.write(), |c| &mut c.name);
+            let reader = {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || lock.read().name.clone())
+            };
+            thread::sleep(Duration::from_millis(30));
+            name.push_str("ready");
+            drop(name);
+            assert_eq!(reader.join().unwrap(), "ready");
+        });
+    }
+
+    #[test]
+    fn test_map_closure_panics_releases() {
+        run_with_timeout(TIMEOUT, || {
+            let lock = RwLock::new(Config::default());
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                RwLockWriteGuard::map(lock.write(), |_| -> &mut u16 { panic!("no port") });
             }));
-        }
-        for h in handles {
-            h.join().unwrap();
-        }
-        assert_eq!(*lock.read(), 1000);
+            assert!(result.is_err());
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                RwLockReadGuard::map(lock.read(), |_| -> &u16 { panic!("no port") });
+            }));
+            assert!(result.is_err());
+            assert!(lock.try_write().is_some());
+        });
     }
 }
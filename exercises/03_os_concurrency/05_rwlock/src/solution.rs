@@ -0,0 +1,397 @@
+//! # Read-Write Lock (Writer-Priority)
+//!
+//! In this exercise, you will implement a **writer-priority** read-write lock from scratch using atomics.
+//! Multiple readers may hold the lock concurrently; a writer holds it exclusively.
+//!
+//! **Note:** Rust's standard library already provides [`std::sync::RwLock`]. This exercise implements
+//! a minimal version for learning the protocol and policy without using the standard one.
+//!
+//! ## Common policies for read-write locks
+//! Different implementations can give different **priority** when both readers and writers are waiting:
+//!
+//! - **Reader-priority (读者优先)**: New readers are allowed to enter while a writer is waiting, so writers
+//!   may be starved if readers keep arriving.
+//! - **Writer-priority (写者优先)**: Once a writer is waiting, no new readers are admitted until that writer
+//!   has run; this exercise implements this policy.
+//! - **Read-write fair (读写公平)**: Requests are served in a fair order (e.g. FIFO or round-robin), so
+//!   neither readers nor writers are systematically starved.
+//!
+//! ## Key Concepts
+//! - **Readers**: share access; many threads can hold a read lock at once.
+//! - **Writer**: exclusive access; only one writer, and no readers while the writer holds the lock.
+//! - **Writer-priority (this implementation)**: when at least one writer is waiting, new readers block
+//!   until the writer runs.
+//!
+//! ## State (single atomic)
+//! We use one `AtomicU32`: low bits = reader count, two flags = writer holding / writer waiting.
+//! All logic is implemented with compare_exchange and load/store; no use of `std::sync::RwLock`.
+//!
+//! ## Not waiting forever
+//! `try_read` / `try_write` make a single attempt and return `None` instead of spinning.
+//! `read_timeout` / `write_timeout` retry until a deadline and then return `Err(TimedOut)`. A
+//! writer that times out must take back its `WRITER_WAITING` announcement, or readers would
+//! stay locked out by a writer that is no longer there.
+//!
+//! ## Mapped guards
+//! `RwLockReadGuard::map` / `RwLockWriteGuard::map` narrow a guard to one part of the data
+//! (a field, an element) while the lock stays held; dropping the mapped guard releases it.
+
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Maximum number of concurrent readers (fits in state bits).
+const READER_MASK: u32 = (1 << 30) - 1;
+/// Bit set when a writer holds the lock.
+const WRITER_HOLDING: u32 = 1 << 30;
+/// Bit set when at least one writer is waiting (writer-priority: block new readers).
+const WRITER_WAITING: u32 = 1 << 31;
+
+/// `read_timeout` / `write_timeout` gave up before the lock became available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut;
+
+/// Writer-priority read-write lock. Implemented from scratch; does not use `std::sync::RwLock`.
+pub struct RwLock<T> {
+    state: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for RwLock<T> {}
+unsafe impl<T: Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicU32::new(0),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Acquire a read lock. Blocks (spins) until no writer holds and no writer is waiting (writer-priority).
+    ///
+    /// TODO: Implement read lock acquisition
+    /// 1. In a loop, load state (Acquire).
+    /// 2. If WRITER_HOLDING or WRITER_WAITING is set, spin_loop and continue (writer-priority: no new readers while writer waits).
+    /// 3. If reader count (state & READER_MASK) is already READER_MASK, spin and continue.
+    /// 4. Try compare_exchange(s, s + 1, AcqRel, Acquire); on success return RwLockReadGuard { lock: self }.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        loop {
+            let s = self.state.load(Ordering::Acquire);
+            if s & (WRITER_HOLDING | WRITER_WAITING) != 0 || s & READER_MASK == READER_MASK {
+                std::hint::spin_loop();
+                continue;
+            }
+            if self
+                .state
+                .compare_exchange(s, s + 1, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return RwLockReadGuard { lock: self };
+            }
+        }
+    }
+
+    /// Acquire the write lock. Blocks until no readers and no other writer.
+    ///
+    /// TODO: Implement write lock acquisition (writer-priority)
+    /// 1. Set WRITER_WAITING first: fetch_or(WRITER_WAITING, Release) so new readers will block.
+    /// 2. In a loop: load state; if any readers (READER_MASK) or WRITER_HOLDING, spin_loop and continue.
+    /// 3. Try compare_exchange(WRITER_WAITING, WRITER_HOLDING, ...) to take the lock; or compare_exchange(0, WRITER_HOLDING, ...) if a writer just released.
+    /// 4. On success return RwLockWriteGuard { lock: self }.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        loop {
+            // Re-announce every round: a releasing writer clears both writer bits.
+            self.state.fetch_or(WRITER_WAITING, Ordering::Release);
+            let s = self.state.load(Ordering::Acquire);
+            if s & (READER_MASK | WRITER_HOLDING) != 0 {
+                std::hint::spin_loop();
+                continue;
+            }
+            if self
+                .state
+                .compare_exchange(s, WRITER_HOLDING, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return RwLockWriteGuard { lock: self };
+            }
+        }
+    }
+
+    /// Try to acquire a read lock without waiting. `None` if a writer holds the lock or is
+    /// waiting for it (writer-priority applies here too), or the reader count is full.
+    ///
+    /// TODO: Implement non-blocking read acquisition
+    /// 1. In a loop, load state (Acquire).
+    /// 2. If a writer bit is set or the reader count is full, return None right away.
+    /// 3. Try compare_exchange(s, s + 1, AcqRel, Acquire); on success return the guard. On
+    ///    failure only the reader count moved under us: go round again.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        loop {
+            let s = self.state.load(Ordering::Acquire);
+            if s & (WRITER_HOLDING | WRITER_WAITING) != 0 || s & READER_MASK == READER_MASK {
+                return None;
+            }
+            if self
+                .state
+                .compare_exchange(s, s + 1, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return Some(RwLockReadGuard { lock: self });
+            }
+        }
+    }
+
+    /// Try to acquire the write lock without waiting. `None` if any reader or a writer holds it.
+    ///
+    /// TODO: Implement non-blocking write acquisition
+    /// 1. Load state (Acquire); if any reader or WRITER_HOLDING is set, return None.
+    /// 2. One compare_exchange(s, WRITER_HOLDING, AcqRel, Acquire); Some(guard) on success,
+    ///    None otherwise (someone else got there first). Do not set WRITER_WAITING: a caller
+    ///    that gives up must not hold readers back.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        let s = self.state.load(Ordering::Acquire);
+        if s & (READER_MASK | WRITER_HOLDING) != 0 {
+            return None;
+        }
+        self.state
+            .compare_exchange(s, WRITER_HOLDING, Ordering::AcqRel, Ordering::Acquire)
+            .ok()
+            .map(|_| RwLockWriteGuard { lock: self })
+    }
+
+    /// `read`, giving up after `dur`.
+    ///
+    /// TODO: Implement timed read acquisition
+    /// 1. deadline = Instant::now() + dur.
+    /// 2. Loop on try_read; on success return Ok(guard).
+    /// 3. Past the deadline: Err(TimedOut). Otherwise spin_loop and `thread::yield_now()` (the
+    ///    writer we wait for may need this CPU to finish).
+    pub fn read_timeout(&self, dur: Duration) -> Result<RwLockReadGuard<'_, T>, TimedOut> {
+        let deadline = Instant::now() + dur;
+        loop {
+            if let Some(guard) = self.try_read() {
+                return Ok(guard);
+            }
+            if Instant::now() >= deadline {
+                return Err(TimedOut);
+            }
+            std::hint::spin_loop();
+            thread::yield_now();
+        }
+    }
+
+    /// `write`, giving up after `dur`. Announces itself with WRITER_WAITING like `write` while it
+    /// waits, so new readers queue up behind it.
+    ///
+    /// TODO: Implement timed write acquisition
+    /// 1. deadline = Instant::now() + dur.
+    /// 2. In a loop: fetch_or(WRITER_WAITING, Release), then try to take the lock as `write` does;
+    ///    on success return Ok(guard).
+    /// 3. Past the deadline: clear WRITER_WAITING with fetch_and (readers must not stay blocked
+    ///    by a writer that left; any other waiting writer sets it again on its next round) and
+    ///    return Err(TimedOut). Otherwise spin_loop and `thread::yield_now()`.
+    pub fn write_timeout(&self, dur: Duration) -> Result<RwLockWriteGuard<'_, T>, TimedOut> {
+        let deadline = Instant::now() + dur;
+        loop {
+            self.state.fetch_or(WRITER_WAITING, Ordering::Release);
+            let s = self.state.load(Ordering::Acquire);
+            if s & (READER_MASK | WRITER_HOLDING) == 0
+                && self
+                    .state
+                    .compare_exchange(s, WRITER_HOLDING, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok()
+            {
+                return Ok(RwLockWriteGuard { lock: self });
+            }
+            if Instant::now() >= deadline {
+                self.state.fetch_and(!WRITER_WAITING, Ordering::Release);
+                return Err(TimedOut);
+            }
+            std::hint::spin_loop();
+            thread::yield_now();
+        }
+    }
+}
+
+/// Guard for a read lock; releases the read lock on drop.
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+// TODO: Implement Deref for RwLockReadGuard
+// Return shared reference to data: unsafe { &*self.lock.data.get() }
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+// TODO: Implement Drop for RwLockReadGuard
+// Decrement reader count: self.lock.state.fetch_sub(1, Ordering::Release)
+impl<T> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.state.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Guard for a write lock; releases the write lock on drop.
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+}
+
+// TODO: Implement Deref for RwLockWriteGuard
+// Return shared reference: unsafe { &*self.lock.data.get() }
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+// TODO: Implement DerefMut for RwLockWriteGuard
+// Return mutable reference: unsafe { &mut *self.lock.data.get() }
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+// TODO: Implement Drop for RwLockWriteGuard
+// Clear writer bits so lock is free: self.lock.state.fetch_and(!(WRITER_HOLDING | WRITER_WAITING), Ordering::Release)
+impl<T> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock
+            .state
+            .fetch_and(!(WRITER_HOLDING | WRITER_WAITING), Ordering::Release);
+    }
+}
+
+/// A read guard narrowed to a part of the data by `RwLockReadGuard::map`. It still holds the
+/// read lock, and releases it on drop.
+pub struct MappedRwLockReadGuard<'a, U: ?Sized> {
+    state: &'a AtomicU32,
+    data: *const U,
+    _marker: PhantomData<&'a U>,
+}
+
+unsafe impl<U: ?Sized + Sync> Sync for MappedRwLockReadGuard<'_, U> {}
+
+/// A write guard narrowed to a part of the data by `RwLockWriteGuard::map`. It still holds the
+/// write lock, and releases it on drop.
+pub struct MappedRwLockWriteGuard<'a, U: ?Sized> {
+    state: &'a AtomicU32,
+    data: *mut U,
+    _marker: PhantomData<&'a mut U>,
+}
+
+unsafe impl<U: ?Sized + Sync> Sync for MappedRwLockWriteGuard<'_, U> {}
+
+impl<'a, T> RwLockReadGuard<'a, T> {
+    /// Narrow the guard to the part of the data `f` picks, keeping the read lock. An associated
+    /// function (`RwLockReadGuard::map(guard, f)`) so it cannot shadow a `map` method of `T`.
+    ///
+    /// If `f` panics, the read lock is released.
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&T) -> &U) -> MappedRwLockReadGuard<'a, U> {
+        let lock = this.lock;
+        let data: *const U = f(unsafe { &*lock.data.get() });
+        mem::forget(this);
+        MappedRwLockReadGuard {
+            state: &lock.state,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> RwLockWriteGuard<'a, T> {
+    /// Narrow the guard to the part of the data `f` picks, keeping the write lock.
+    ///
+    /// If `f` panics, the write lock is released.
+    pub fn map<U: ?Sized>(
+        this: Self,
+        f: impl FnOnce(&mut T) -> &mut U,
+    ) -> MappedRwLockWriteGuard<'a, U> {
+        let lock = this.lock;
+        let data: *mut U = f(unsafe { &mut *lock.data.get() });
+        mem::forget(this);
+        MappedRwLockWriteGuard {
+            state: &lock.state,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, U: ?Sized> MappedRwLockReadGuard<'a, U> {
+    /// Narrow a mapped guard further.
+    pub fn map<V: ?Sized>(this: Self, f: impl FnOnce(&U) -> &V) -> MappedRwLockReadGuard<'a, V> {
+        let state = this.state;
+        let data: *const V = f(unsafe { &*this.data });
+        mem::forget(this);
+        MappedRwLockReadGuard {
+            state,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, U: ?Sized> MappedRwLockWriteGuard<'a, U> {
+    /// Narrow a mapped guard further.
+    pub fn map<V: ?Sized>(
+        this: Self,
+        f: impl FnOnce(&mut U) -> &mut V,
+    ) -> MappedRwLockWriteGuard<'a, V> {
+        let state = this.state;
+        let data: *mut V = f(unsafe { &mut *this.data });
+        mem::forget(this);
+        MappedRwLockWriteGuard {
+            state,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized> Deref for MappedRwLockReadGuard<'_, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedRwLockReadGuard<'_, U> {
+    fn drop(&mut self) {
+        self.state.fetch_sub(1, Ordering::Release);
+    }
+}
+
+impl<U: ?Sized> Deref for MappedRwLockWriteGuard<'_, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { &*self.data }
+    }
+}
+
+impl<U: ?Sized> DerefMut for MappedRwLockWriteGuard<'_, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedRwLockWriteGuard<'_, U> {
+    fn drop(&mut self) {
+        self.state
+            .fetch_and(!(WRITER_HOLDING | WRITER_WAITING), Ordering::Release);
+    }
+}
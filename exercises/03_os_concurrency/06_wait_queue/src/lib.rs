@@ -0,0 +1,443 @@
+//! # Wait Queue
+//!
+//! In this exercise, you implement the kernel's most basic blocking primitive: a **wait queue**.
+//! A task that cannot make progress (empty pipe, no message of the right type, child still
+//! running) sleeps on a queue; whoever changes the state it is waiting for wakes it up.
+//!
+//! ## Key Concepts
+//! - `wait_until(cond)`: sleep until `cond()` is true, re-checking after every wake-up (wake-ups
+//!   may be spurious, and another waiter may have consumed what we were woken for)
+//! - `wake_one` / `wake_all`: the waker changes the shared state *first*, then wakes
+//! - Lost wake-ups: checking `cond` and going to sleep must be atomic with respect to wakers
+//! - One interface, several backends: the same subsystem code runs on OS threads or green threads
+//!
+//! ## Backends
+//! - `CondvarWaitQueue`: OS threads. A `Mutex<usize>` counts sleepers and a `Condvar` parks them.
+//!   `cond` is evaluated while holding that mutex and wakers take it before notifying, so a
+//!   wake-up can never slip in between a false `cond()` and the call to `Condvar::wait`.
+//! - `GreenWaitQueue` (`--features green`): green threads of `04_context_switch/02_green_threads`.
+//!   Waiters record their thread id and `block_current()`; wakers `wake(id)` them. Scheduling is
+//!   cooperative, so nothing can run between checking `cond` and blocking.
+//!
+//! The state `cond` looks at belongs to the caller (an atomic, or data behind its own lock). Never
+//! call back into the same queue from inside `cond`.
+
+use std::sync::{Condvar, Mutex};
+
+/// A queue of sleeping tasks; see the crate docs for the protocol.
+pub trait WaitQueue {
+    /// Block the calling task until `cond()` returns `true`. Returns immediately if it already
+    /// does. `cond` may be called any number of times.
+    fn wait_until<F: FnMut() -> bool>(&self, cond: F);
+
+    /// Wake one sleeping task. Returns `false` if nobody was waiting.
+    fn wake_one(&self) -> bool;
+
+    /// Wake every sleeping task. Returns how many were waiting.
+    fn wake_all(&self) -> usize;
+
+    /// Number of tasks currently asleep on the queue.
+    fn waiters(&self) -> usize;
+}
+
+/// Wait queue for OS threads.
+pub struct CondvarWaitQueue {
+    /// Number of threads inside `Condvar::wait`. The mutex also orders `cond` checks against wakers.
+    sleepers: Mutex<usize>,
+    cv: Condvar,
+}
+
+impl CondvarWaitQueue {
+    pub const fn new() -> Self {
+        Self {
+            sleepers: Mutex::new(0),
+            cv: Condvar::new(),
+        }
+    }
+}
+
+impl Default for CondvarWaitQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WaitQueue for CondvarWaitQueue {
+    /// 1. Lock `sleepers`.
+    /// 2. While `cond()` is false: increment the count, `cv.wait` (which gives the lock back while
+    ///    asleep), decrement the count once woken.
+    fn wait_until<F: FnMut() -> bool>(&self, mut cond: F) {
+        // TODO
+        todo!()
+    }
+
+    /// Lock `sleepers`; if the count is 0 return `false`, otherwise `notify_one` and return `true`.
+    /// Taking the lock is what makes the wake-up safe: a waiter that saw `cond()` false still
+    /// holds it until it is inside `wait`.
+    fn wake_one(&self) -> bool {
+        // TODO
+        todo!()
+    }
+
+    /// Lock `sleepers`, `notify_all`, return the count.
+    fn wake_all(&self) -> usize {
+        // TODO
+        todo!()
+    }
+
+    fn waiters(&self) -> usize {
+        *self.sleepers.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "green")]
+pub use green::GreenWaitQueue;
+
+#[cfg(feature = "green")]
+mod green {
+    use super::WaitQueue;
+    use green_threads::{block_current, current_thread, wake};
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Wait queue for green threads. Only green threads (not the thread calling
+    /// `Scheduler::run`) may wait on it.
+    pub struct GreenWaitQueue {
+        /// Ids of blocked threads, in the order they went to sleep.
+        waiters: Mutex<VecDeque<usize>>,
+    }
+
+    impl GreenWaitQueue {
+        pub const fn new() -> Self {
+            Self {
+                waiters: Mutex::new(VecDeque::new()),
+            }
+        }
+    }
+
+    impl Default for GreenWaitQueue {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl WaitQueue for GreenWaitQueue {
+        /// Loop: return if `cond()` holds; otherwise push `current_thread()` onto `waiters`,
+        /// release the lock and `block_current()`. A woken thread is no longer in `waiters`, so
+        /// it re-enqueues itself if `cond()` is still false.
+        fn wait_until<F: FnMut() -> bool>(&self, mut cond: F) {
+            // TODO
+            todo!()
+        }
+
+        /// Pop the oldest waiter and `wake` it (FIFO).
+        fn wake_one(&self) -> bool {
+            // TODO
+            todo!()
+        }
+
+        /// Drain `waiters`, `wake` each, return how many there were.
+        fn wake_all(&self) -> usize {
+            // TODO
+            todo!()
+        }
+
+        fn waiters(&self) -> usize {
+            self.waiters.lock().unwrap().len()
+        }
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_condition_already_true",
+            "check the condition before sleeping: if it already holds, return without becoming a waiter",
+        ),
+        (
+            "test_wake_without_waiters",
+            "wake_one returns whether it woke anybody, and wake_all how many; with no waiters both do nothing",
+        ),
+        (
+            "test_wake_one_hands_out_tokens",
+            "wake_one must wake exactly one sleeper: hand out one token and notify_one, not notify_all",
+        ),
+        (
+            "test_ping_pong_no_lost_wakeups",
+            "re-check the condition while holding the queue lock before sleeping, so a wake between check and sleep is not lost",
+        ),
+    ];
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Spin (with short sleeps) until `n` threads are asleep on `wq`.
+    fn wait_for_sleepers(wq: &CondvarWaitQueue, n: usize) {
+        for _ in 0..2000 {
+            if wq.waiters() == n {
+                return;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("expected {n} sleepers, found {}", wq.waiters());
+    }
+
+    #[test]
+    fn test_condition_already_true() {
+        let wq = CondvarWaitQueue::new();
+        let mut calls = 0;
+        wq.wait_until(|| {
+            calls += 1;
+            true
+        });
+        assert_eq_with_hint!(calls, 1);
+        assert_eq_with_hint!(wq.waiters(), 0);
+    }
+
+    #[test]
+    fn test_wake_without_waiters() {
+        let wq = CondvarWaitQueue::new();
+        assert_with_hint!(!wq.wake_one());
+        assert_eq_with_hint!(wq.wake_all(), 0);
+    }
+
+    #[test]
+    fn test_waiter_sleeps_until_woken() {
+        run_with_timeout(TIMEOUT, || {
+            let wq = Arc::new(CondvarWaitQueue::new());
+            let flag = Arc::new(AtomicBool::new(false));
+            let done = Arc::new(AtomicBool::new(false));
+            let h = {
+                let (wq, flag, done) = (wq.clone(), flag.clone(), done.clone());
+                thread::spawn(move || {
+                    wq.wait_until(|| flag.load(Ordering::SeqCst));
+                    done.store(true, Ordering::SeqCst);
+                })
+            };
+            wait_for_sleepers(&wq, 1);
+            assert!(
+                !done.load(Ordering::SeqCst),
+                "must sleep while cond is false"
+            );
+
+            // A wake-up with the condition still false is spurious: the waiter goes back to sleep.
+            assert!(wq.wake_one());
+            wait_for_sleepers(&wq, 1);
+            assert!(!done.load(Ordering::SeqCst));
+
+            flag.store(true, Ordering::SeqCst);
+            assert!(wq.wake_one());
+            h.join().unwrap();
+            assert!(done.load(Ordering::SeqCst));
+            assert_eq!(wq.waiters(), 0);
+        });
+    }
+
+    #[test]
+    fn test_wake_all() {
+        run_with_timeout(TIMEOUT, || {
+            let wq = Arc::new(CondvarWaitQueue::new());
+            let flag = Arc::new(AtomicBool::new(false));
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let (wq, flag) = (wq.clone(), flag.clone());
+                    thread::spawn(move || wq.wait_until(|| flag.load(Ordering::SeqCst)))
+                })
+                .collect();
+            wait_for_sleepers(&wq, 4);
+            flag.store(true, Ordering::SeqCst);
+            assert_eq!(wq.wake_all(), 4);
+            for h in handles {
+                h.join().unwrap();
+            }
+            assert_eq!(wq.waiters(), 0);
+        });
+    }
+
+    #[test]
+    fn test_wake_one_hands_out_tokens() {
+        run_with_timeout(TIMEOUT, || {
+            // Each waiter consumes one token; with one token only one of them may leave.
+            let wq = Arc::new(CondvarWaitQueue::new());
+            let tokens = Arc::new(Mutex::new(0));
+            let passed = Arc::new(AtomicUsize::new(0));
+            let handles: Vec<_> = (0..3)
+                .map(|_| {
+                    let (wq, tokens, passed) = (wq.clone(), tokens.clone(), passed.clone());
+                    thread::spawn(move || {
+                        wq.wait_until(|| {
+                            let mut t = tokens.lock().unwrap();
+                            if *t > 0 {
+                                *t -= 1;
+                                true
+                            } else {
+                                false
+                            }
+                        });
+                        passed.fetch_add(1, Ordering::SeqCst);
+                    })
+                })
+                .collect();
+            wait_for_sleepers(&wq, 3);
+
+            *tokens.lock().unwrap() += 1;
+            assert_with_hint!(wq.wake_one());
+            wait_for_sleepers(&wq, 2);
+            assert_eq_with_hint!(passed.load(Ordering::SeqCst), 1);
+
+            *tokens.lock().unwrap() += 2;
+            wq.wake_all();
+            for h in handles {
+                h.join().unwrap();
+            }
+            assert_eq_with_hint!(passed.load(Ordering::SeqCst), 3);
+            assert_eq_with_hint!(*tokens.lock().unwrap(), 0);
+        });
+    }
+
+    #[test]
+    fn test_ping_pong_no_lost_wakeups() {
+        run_with_timeout(TIMEOUT, || {
+            // Two threads alternate through a shared counter; a single lost wake-up would hang.
+            const ROUNDS: usize = 2000;
+            let wq = Arc::new(CondvarWaitQueue::new());
+            let turn = Arc::new(AtomicUsize::new(0));
+            let player = |parity: usize| {
+                let (wq, turn) = (wq.clone(), turn.clone());
+                thread::spawn(move || {
+                    for i in 0..ROUNDS {
+                        let mine = 2 * i + parity;
+                        wq.wait_until(|| turn.load(Ordering::SeqCst) == mine);
+                        turn.store(mine + 1, Ordering::SeqCst);
+                        wq.wake_all();
+                    }
+                })
+            };
+            let (a, b) = (player(0), player(1));
+            a.join().unwrap();
+            b.join().unwrap();
+            assert_eq_with_hint!(turn.load(Ordering::SeqCst), 2 * ROUNDS);
+        });
+    }
+
+    #[cfg(feature = "green")]
+    mod green {
+        use super::*;
+        #[cfg(feature = "solutions")]
+        use green_threads::solution::{yield_now, Scheduler};
+        #[cfg(not(feature = "solutions"))]
+        use green_threads::{yield_now, Scheduler};
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::{Mutex, PoisonError};
+
+        /// The green scheduler is global: run these tests one at a time.
+        static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+        /// Run a test alone, failing it if it hangs. The lock is held out here rather than by
+        /// the watchdog's thread, which a hung test leaks with whatever it holds.
+        #[track_caller]
+        fn serial(test: impl FnOnce() + Send + 'static) {
+            let _guard = TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            run_with_timeout(TIMEOUT, test);
+        }
+        static LOG: Mutex<Vec<char>> = Mutex::new(Vec::new());
+        static WQ: GreenWaitQueue = GreenWaitQueue::new();
+        static FLAG: AtomicBool = AtomicBool::new(false);
+
+        fn log(c: char) {
+            LOG.lock().unwrap().push(c);
+        }
+
+        fn reset() {
+            LOG.lock().unwrap().clear();
+            FLAG.store(false, Ordering::SeqCst);
+        }
+
+        extern "C" fn waiter_a() {
+            log('a');
+            WQ.wait_until(|| FLAG.load(Ordering::SeqCst));
+            log('A');
+        }
+
+        extern "C" fn waiter_b() {
+            log('b');
+            WQ.wait_until(|| FLAG.load(Ordering::SeqCst));
+            log('B');
+        }
+
+        extern "C" fn setter_one() {
+            assert_eq!(WQ.waiters(), 2);
+            // Spurious wake-up: `a` runs, sees the flag still false and sleeps again.
+            assert!(WQ.wake_one());
+            yield_now();
+            log('s');
+            FLAG.store(true, Ordering::SeqCst);
+            assert!(WQ.wake_one());
+            yield_now();
+            assert_eq!(WQ.waiters(), 1, "only the oldest waiter was woken");
+            assert!(WQ.wake_one());
+            assert!(!WQ.wake_one());
+        }
+
+        #[test]
+        fn test_green_wake_one_fifo() {
+            serial(|| {
+                reset();
+                let mut sched = Scheduler::new();
+                sched.spawn(waiter_a);
+                sched.spawn(waiter_b);
+                sched.spawn(setter_one);
+                sched.run().unwrap();
+                // After the spurious wake-up `a` queued up behind `b`, so `b` is woken first.
+                assert_eq!(LOG.lock().unwrap().iter().collect::<String>(), "absBA");
+                assert_eq!(WQ.waiters(), 0);
+            });
+        }
+
+        extern "C" fn setter_all() {
+            log('s');
+            FLAG.store(true, Ordering::SeqCst);
+            assert_eq!(WQ.wake_all(), 2);
+        }
+
+        extern "C" fn worker() {
+            for _ in 0..3 {
+                log('w');
+                yield_now();
+            }
+        }
+
+        #[test]
+        fn test_green_wake_all_and_others_keep_running() {
+            serial(|| {
+                reset();
+                let mut sched = Scheduler::new();
+                sched.spawn(waiter_a);
+                sched.spawn(waiter_b);
+                sched.spawn(worker);
+                sched.spawn(setter_all);
+                sched.run().unwrap();
+                let log: String = LOG.lock().unwrap().iter().collect();
+                // Blocked waiters are skipped while `worker` keeps running.
+                assert_eq!(log, "abwsABww");
+                assert_eq!(WQ.waiters(), 0);
+            });
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! # Wait Queue
+//!
+//! In this exercise, you implement the kernel's most basic blocking primitive: a **wait queue**.
+//! A task that cannot make progress (empty pipe, no message of the right type, child still
+//! running) sleeps on a queue; whoever changes the state it is waiting for wakes it up.
+//!
+//! ## Key Concepts
+//! - `wait_until(cond)`: sleep until `cond()` is true, re-checking after every wake-up (wake-ups
+//!   may be spurious, and another waiter may have consumed what we were woken for)
+//! - `wake_one` / `wake_all`: the waker changes the shared state *first*, then wakes
+//! - Lost wake-ups: checking `cond` and going to sleep must be atomic with respect to wakers
+//! - One interface, several backends: the same subsystem code runs on OS threads or green threads
+//!
+//! ## Backends
+//! - `CondvarWaitQueue`: OS threads. A `Mutex<usize>` counts sleepers and a `Condvar` parks them.
+//!   `cond` is evaluated while holding that mutex and wakers take it before notifying, so a
+//!   wake-up can never slip in between a false `cond()` and the call to `Condvar::wait`.
+//! - `GreenWaitQueue` (`--features green`): green threads of `04_context_switch/02_green_threads`.
+//!   Waiters record their thread id and `block_current()`; wakers `wake(id)` them. Scheduling is
+//!   cooperative, so nothing can run between checking `cond` and blocking.
+//!
+//! The state `cond` looks at belongs to the caller (an atomic, or data behind its own lock). Never
+//! call back into the same queue from inside `cond`.
+
+pub(crate) use std::sync::{Condvar, Mutex};
+
+/// A queue of sleeping tasks; see the crate docs for the protocol.
+pub trait WaitQueue {
+    /// Block the calling task until `cond()` returns `true`. Returns immediately if it already
+    /// does. `cond` may be called any number of times.
+    fn wait_until<F: FnMut() -> bool>(&self, cond: F);
+
+    /// Wake one sleeping task. Returns `false` if nobody was waiting.
+    fn wake_one(&self) -> bool;
+
+    /// Wake every sleeping task. Returns how many were waiting.
+    fn wake_all(&self) -> usize;
+
+    /// Number of tasks currently asleep on the queue.
+    fn waiters(&self) -> usize;
+}
+
+/// Wait queue for OS threads.
+pub struct CondvarWaitQueue {
+    /// Number of threads inside `Condvar::wait`. The mutex also orders `cond` checks against wakers.
+    sleepers: Mutex<usize>,
+    cv: Condvar,
+}
+
+impl CondvarWaitQueue {
+    pub const fn new() -> Self {
+        Self {
+            sleepers: Mutex::new(0),
+            cv: Condvar::new(),
+        }
+    }
+}
+
+impl Default for CondvarWaitQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WaitQueue for CondvarWaitQueue {
+    /// 1. Lock `sleepers`.
+    /// 2. While `cond()` is false: increment the count, `cv.wait` (which gives the lock back while
+    ///    asleep), decrement the count once woken.
+    fn wait_until<F: FnMut() -> bool>(&self, mut cond: F) {
+        let mut sleepers = self.sleepers.lock().unwrap();
+        while !cond() {
+            *sleepers += 1;
+            sleepers = self.cv.wait(sleepers).unwrap();
+            *sleepers -= 1;
+        }
+    }
+
+    /// Lock `sleepers`; if the count is 0 return `false`, otherwise `notify_one` and return `true`.
+    /// Taking the lock is what makes the wake-up safe: a waiter that saw `cond()` false still
+    /// holds it until it is inside `wait`.
+    fn wake_one(&self) -> bool {
+        let sleepers = self.sleepers.lock().unwrap();
+        if *sleepers == 0 {
+            return false;
+        }
+        self.cv.notify_one();
+        true
+    }
+
+    /// Lock `sleepers`, `notify_all`, return the count.
+    fn wake_all(&self) -> usize {
+        let sleepers = self.sleepers.lock().unwrap();
+        self.cv.notify_all();
+        *sleepers
+    }
+
+    fn waiters(&self) -> usize {
+        *self.sleepers.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "green")]
+pub use green::GreenWaitQueue;
+
+#[cfg(feature = "green")]
+mod green {
+    use super::WaitQueue;
+    use green_threads::solution::{block_current, current_thread, wake};
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Wait queue for green threads. Only green threads (not the thread calling
+    /// `Scheduler::run`) may wait on it.
+    pub struct GreenWaitQueue {
+        /// Ids of blocked threads, in the order they went to sleep.
+        waiters: Mutex<VecDeque<usize>>,
+    }
+
+    impl GreenWaitQueue {
+        pub const fn new() -> Self {
+            Self {
+                waiters: Mutex::new(VecDeque::new()),
+            }
+        }
+    }
+
+    impl Default for GreenWaitQueue {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl WaitQueue for GreenWaitQueue {
+        /// Loop: return if `cond()` holds; otherwise push `current_thread()` onto `waiters`,
+        /// release the lock and `block_current()`. A woken thread is no longer in `waiters`, so
+        /// it re-enqueues itself if `cond()` is still false.
+        fn wait_until<F: FnMut() -> bool>(&self, mut cond: F) {
+            while !cond() {
+                self.waiters.lock().unwrap().push_back(current_thread());
+                block_current();
+            }
+        }
+
+        /// Pop the oldest waiter and `wake` it (FIFO).
+        fn wake_one(&self) -> bool {
+            let tid = self.waiters.lock().unwrap().pop_front();
+            match tid {
+                Some(tid) => {
+                    wake(tid);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Drain `waiters`, `wake` each, return how many there were.
+        fn wake_all(&self) -> usize {
+            let tids: Vec<usize> = self.waiters.lock().unwrap().drain(..).collect();
+            for &tid in &tids {
+                wake(tid);
+            }
+            tids.len()
+        }
+
+        fn waiters(&self) -> usize {
+            self.waiters.lock().unwrap().len()
+        }
+    }
+}
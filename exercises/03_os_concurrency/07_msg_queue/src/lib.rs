@@ -0,0 +1,363 @@
+//! # System V Message Queue
+//!
+//! In this exercise, you implement a `msgsnd` / `msgrcv` style message queue on top of the
+//! `WaitQueue` from `06_wait_queue` (finish that one first).
+//!
+//! ## Semantics
+//! - Every message has a type `mtype >= 1` and a byte payload.
+//! - The queue holds at most `max_bytes` payload bytes. `msgsnd` blocks while the new message
+//!   does not fit; with `nowait` it fails with `WouldBlock` instead (`EAGAIN`).
+//! - `msgrcv(msgtyp)` picks a message by type:
+//!   - `msgtyp == 0`: the oldest message
+//!   - `msgtyp > 0`: the oldest message of exactly that type
+//!   - `msgtyp < 0`: the oldest message of the *lowest* type `<= |msgtyp|` (a priority queue)
+//!
+//!   It blocks until such a message exists; with `nowait` it fails with `NoMessage` (`ENOMSG`).
+//! - `remove` (`IPC_RMID`) discards all messages and wakes every blocked sender and receiver,
+//!   which fail with `Removed` (`EIDRM`), as does every later call.
+//!
+//! ## Blocking without races
+//! Do the actual work *inside* the `wait_until` condition: the closure locks `state`, and if it
+//! can send (or finds a message) it does so right there and returns `true`. Checking first and
+//! acting after `wait_until` returns would let another thread take the slot or the message in
+//! between. Store the outcome in a variable outside the closure.
+//!
+//! Wake-ups go out *after* the `state` lock is released (`cond` takes `state` while the wait
+//! queue holds its own lock, so waking while holding `state` could deadlock). Because receivers
+//! filter by type, a new message wakes **all** receivers: `wake_one` might pick one waiting for a
+//! different type and the message would sit there while the right receiver sleeps.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use wait_queue::{CondvarWaitQueue, WaitQueue};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub mtype: i64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsgError {
+    /// `mtype < 1` on send (`EINVAL`).
+    InvalidType,
+    /// Payload larger than the whole queue could ever hold (`EINVAL`).
+    TooBig,
+    /// `nowait` send to a full queue (`EAGAIN`).
+    WouldBlock,
+    /// `nowait` receive with no matching message (`ENOMSG`).
+    NoMessage,
+    /// The queue was removed (`EIDRM`).
+    Removed,
+}
+
+struct QueueState {
+    msgs: VecDeque<Message>,
+    /// Sum of the payload sizes in `msgs`.
+    bytes: usize,
+    removed: bool,
+}
+
+/// A message queue; `W` is the wait queue backend (OS threads by default).
+pub struct MsgQueue<W: WaitQueue = CondvarWaitQueue> {
+    state: Mutex<QueueState>,
+    max_bytes: usize,
+    /// Senders waiting for space.
+    senders: W,
+    /// Receivers waiting for a message.
+    receivers: W,
+}
+
+/// Index of the message `msgrcv(msgtyp)` takes from `msgs`, if any (rules in the crate docs).
+pub fn select(msgs: &VecDeque<Message>, msgtyp: i64) -> Option<usize> {
+    // TODO
+    todo!()
+}
+
+impl<W: WaitQueue + Default> MsgQueue<W> {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                msgs: VecDeque::new(),
+                bytes: 0,
+                removed: false,
+            }),
+            max_bytes,
+            senders: W::default(),
+            receivers: W::default(),
+        }
+    }
+}
+
+impl<W: WaitQueue> MsgQueue<W> {
+    /// Append a message, blocking while it does not fit.
+    ///
+    /// 1. `mtype < 1` -> `InvalidType`; `data.len() > max_bytes` -> `TooBig`.
+    /// 2. An attempt (under the `state` lock): `Removed` if removed; if `bytes + len <= max_bytes`
+    ///    push the message and succeed; otherwise "not yet".
+    /// 3. With `nowait`, try once ("not yet" -> `WouldBlock`); otherwise retry the attempt as the
+    ///    `senders.wait_until` condition.
+    /// 4. After a successful send, `receivers.wake_all()`.
+    pub fn msgsnd(&self, mtype: i64, data: &[u8], nowait: bool) -> Result<(), MsgError> {
+        // TODO
+        todo!()
+    }
+
+    /// Take the message `select(msgs, msgtyp)` picks, blocking until there is one.
+    ///
+    /// Same structure as `msgsnd`: the attempt fails with `Removed` if removed, otherwise removes
+    /// the selected message (updating `bytes`); `nowait` + nothing found -> `NoMessage`. After a
+    /// successful receive, `senders.wake_all()` (the freed space may let several senders in).
+    pub fn msgrcv(&self, msgtyp: i64, nowait: bool) -> Result<Message, MsgError> {
+        // TODO
+        todo!()
+    }
+
+    /// `msgctl(IPC_RMID)`: mark removed, drop all messages, wake every sender and receiver.
+    pub fn remove(&self) {
+        // TODO
+        todo!()
+    }
+
+    /// Number of queued messages.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().msgs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Payload bytes currently queued.
+    pub fn bytes(&self) -> usize {
+        self.state.lock().unwrap().bytes
+    }
+
+    /// Number of (senders, receivers) currently blocked.
+    pub fn blocked(&self) -> (usize, usize) {
+        (self.senders.waiters(), self.receivers.waiters())
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_select_rules",
+            "msgtyp 0: the oldest message; > 0: the oldest of exactly that type; < 0: the oldest of the lowest type <= |msgtyp|",
+        ),
+        (
+            "test_fifo_and_byte_accounting",
+            "add the data length to bytes on msgsnd and subtract it on msgrcv",
+        ),
+        (
+            "test_nowait_and_invalid_arguments",
+            "validate mtype > 0 and the size limit before checking for space; with nowait, return WouldBlock/NoMessage instead of waiting",
+        ),
+        (
+            "test_remove_wakes_all_waiters",
+            "remove must mark the queue removed and wake every blocked sender and receiver, which then return Removed",
+        ),
+    ];
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    type Queue = MsgQueue<CondvarWaitQueue>;
+
+    fn msg(mtype: i64, data: &[u8]) -> Message {
+        Message {
+            mtype,
+            data: data.to_vec(),
+        }
+    }
+
+    /// Wait until `(senders, receivers)` are blocked on `q`.
+    fn wait_blocked(q: &Queue, expected: (usize, usize)) {
+        for _ in 0..2000 {
+            if q.blocked() == expected {
+                return;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("expected {expected:?} blocked, found {:?}", q.blocked());
+    }
+
+    #[test]
+    fn test_select_rules() {
+        let msgs: VecDeque<Message> = [msg(3, b"a"), msg(1, b"b"), msg(2, b"c"), msg(1, b"d")]
+            .into_iter()
+            .collect();
+        assert_eq_with_hint!(select(&msgs, 0), Some(0), "0: oldest");
+        assert_eq_with_hint!(select(&msgs, 1), Some(1), "exact type, oldest first");
+        assert_eq_with_hint!(select(&msgs, 2), Some(2));
+        assert_eq_with_hint!(select(&msgs, 7), None);
+        assert_eq_with_hint!(select(&msgs, -3), Some(1), "lowest type <= 3");
+        assert_eq_with_hint!(select(&msgs, -2), Some(1));
+        assert_eq_with_hint!(select(&msgs, -1), Some(1));
+        let high: VecDeque<Message> = [msg(5, b"x"), msg(4, b"y")].into_iter().collect();
+        assert_eq_with_hint!(select(&high, -4), Some(1));
+        assert_eq_with_hint!(select(&high, -3), None);
+        assert_eq_with_hint!(select(&VecDeque::new(), 0), None);
+    }
+
+    #[test]
+    fn test_fifo_and_byte_accounting() {
+        let q = Queue::new(100);
+        q.msgsnd(1, b"first", false).unwrap();
+        q.msgsnd(2, b"second", false).unwrap();
+        assert_eq_with_hint!((q.len(), q.bytes()), (2, 11));
+        assert_eq_with_hint!(q.msgrcv(0, false).unwrap(), msg(1, b"first"));
+        assert_eq_with_hint!(q.msgrcv(0, false).unwrap(), msg(2, b"second"));
+        assert_eq_with_hint!((q.len(), q.bytes()), (0, 0));
+    }
+
+    #[test]
+    fn test_receive_by_type() {
+        let q = Queue::new(100);
+        for (t, d) in [(2, "b1"), (1, "a1"), (2, "b2"), (3, "c1")] {
+            q.msgsnd(t, d.as_bytes(), false).unwrap();
+        }
+        assert_eq!(q.msgrcv(2, false).unwrap().data, b"b1");
+        assert_eq!(q.msgrcv(-3, false).unwrap().data, b"a1");
+        assert_eq!(q.msgrcv(2, false).unwrap().data, b"b2");
+        assert_eq!(q.msgrcv(1, true), Err(MsgError::NoMessage));
+        assert_eq!(q.msgrcv(0, false).unwrap().data, b"c1");
+    }
+
+    #[test]
+    fn test_nowait_and_invalid_arguments() {
+        let q = Queue::new(8);
+        assert_eq_with_hint!(q.msgrcv(0, true), Err(MsgError::NoMessage));
+        assert_eq_with_hint!(q.msgsnd(0, b"x", false), Err(MsgError::InvalidType));
+        assert_eq_with_hint!(q.msgsnd(1, &[0; 9], false), Err(MsgError::TooBig));
+        q.msgsnd(1, &[0; 6], true).unwrap();
+        assert_eq_with_hint!(q.msgsnd(1, &[0; 3], true), Err(MsgError::WouldBlock));
+        q.msgsnd(1, &[0; 2], true).unwrap();
+        assert_eq_with_hint!(q.bytes(), 8);
+        q.msgsnd(1, &[], true).unwrap();
+        assert_eq_with_hint!(q.len(), 3, "empty messages always fit");
+    }
+
+    #[test]
+    fn test_receiver_blocks_until_matching_type() {
+        run_with_timeout(TIMEOUT, || {
+            let q = Arc::new(Queue::new(100));
+            let h = {
+                let q = q.clone();
+                thread::spawn(move || q.msgrcv(5, false))
+            };
+            wait_blocked(&q, (0, 1));
+            q.msgsnd(4, b"not for you", false).unwrap();
+            thread::sleep(Duration::from_millis(20));
+            wait_blocked(&q, (0, 1));
+            assert!(
+                !h.is_finished(),
+                "a message of another type must not satisfy it"
+            );
+
+            q.msgsnd(5, b"yours", false).unwrap();
+            assert_eq!(h.join().unwrap().unwrap(), msg(5, b"yours"));
+            assert_eq!(q.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_sender_blocks_on_quota() {
+        run_with_timeout(TIMEOUT, || {
+            let q = Arc::new(Queue::new(10));
+            q.msgsnd(1, &[1; 8], false).unwrap();
+            let h = {
+                let q = q.clone();
+                thread::spawn(move || q.msgsnd(2, &[2; 5], false))
+            };
+            wait_blocked(&q, (1, 0));
+            assert_eq!(q.len(), 1);
+
+            assert_eq!(q.msgrcv(0, false).unwrap().mtype, 1);
+            h.join().unwrap().unwrap();
+            assert_eq!(q.msgrcv(0, false).unwrap(), msg(2, &[2; 5]));
+        });
+    }
+
+    #[test]
+    fn test_remove_wakes_all_waiters() {
+        run_with_timeout(TIMEOUT, || {
+            let q = Arc::new(Queue::new(4));
+            q.msgsnd(1, b"full", false).unwrap();
+            let receiver = {
+                let q = q.clone();
+                thread::spawn(move || q.msgrcv(9, false))
+            };
+            let senders: Vec<_> = (0..2)
+                .map(|_| {
+                    let q = q.clone();
+                    thread::spawn(move || q.msgsnd(1, b"more", false))
+                })
+                .collect();
+            wait_blocked(&q, (2, 1));
+
+            q.remove();
+            assert_eq_with_hint!(receiver.join().unwrap(), Err(MsgError::Removed));
+            for s in senders {
+                assert_eq_with_hint!(s.join().unwrap(), Err(MsgError::Removed));
+            }
+            assert_eq_with_hint!(q.len(), 0);
+            assert_eq_with_hint!(q.msgsnd(1, b"x", true), Err(MsgError::Removed));
+            assert_eq_with_hint!(q.msgrcv(0, true), Err(MsgError::Removed));
+        });
+    }
+
+    #[test]
+    fn test_typed_producers_and_consumers() {
+        run_with_timeout(TIMEOUT, || {
+            // Two consumers each want one type; producers interleave both through a small queue.
+            const N: usize = 200;
+            let q = Arc::new(Queue::new(16));
+            let consumer = |t: i64| {
+                let q = q.clone();
+                thread::spawn(move || {
+                    let mut sum = 0u64;
+                    for _ in 0..N {
+                        let m = q.msgrcv(t, false).unwrap();
+                        assert_eq!(m.mtype, t);
+                        sum += m.data[0] as u64;
+                    }
+                    sum
+                })
+            };
+            let (c1, c2) = (consumer(1), consumer(2));
+            let producers: Vec<_> = (0..2)
+                .map(|p| {
+                    let q = q.clone();
+                    thread::spawn(move || {
+                        for i in 0..N {
+                            let t = 1 + ((i + p) % 2) as i64;
+                            q.msgsnd(t, &[(i % 200) as u8; 4], false).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            for p in producers {
+                p.join().unwrap();
+            }
+            let total = c1.join().unwrap() + c2.join().unwrap();
+            assert_eq!(total, 2 * (0..N as u64).map(|i| i % 200).sum::<u64>());
+            assert!(q.is_empty());
+        });
+    }
+}
@@ -0,0 +1,209 @@
+//! # System V Message Queue
+//!
+//! In this exercise, you implement a `msgsnd` / `msgrcv` style message queue on top of the
+//! `WaitQueue` from `06_wait_queue` (finish that one first).
+//!
+//! ## Semantics
+//! - Every message has a type `mtype >= 1` and a byte payload.
+//! - The queue holds at most `max_bytes` payload bytes. `msgsnd` blocks while the new message
+//!   does not fit; with `nowait` it fails with `WouldBlock` instead (`EAGAIN`).
+//! - `msgrcv(msgtyp)` picks a message by type:
+//!   - `msgtyp == 0`: the oldest message
+//!   - `msgtyp > 0`: the oldest message of exactly that type
+//!   - `msgtyp < 0`: the oldest message of the *lowest* type `<= |msgtyp|` (a priority queue)
+//!
+//!   It blocks until such a message exists; with `nowait` it fails with `NoMessage` (`ENOMSG`).
+//! - `remove` (`IPC_RMID`) discards all messages and wakes every blocked sender and receiver,
+//!   which fail with `Removed` (`EIDRM`), as does every later call.
+//!
+//! ## Blocking without races
+//! Do the actual work *inside* the `wait_until` condition: the closure locks `state`, and if it
+//! can send (or finds a message) it does so right there and returns `true`. Checking first and
+//! acting after `wait_until` returns would let another thread take the slot or the message in
+//! between. Store the outcome in a variable outside the closure.
+//!
+//! Wake-ups go out *after* the `state` lock is released (`cond` takes `state` while the wait
+//! queue holds its own lock, so waking while holding `state` could deadlock). Because receivers
+//! filter by type, a new message wakes **all** receivers: `wake_one` might pick one waiting for a
+//! different type and the message would sit there while the right receiver sleeps.
+
+pub(crate) use std::collections::VecDeque;
+use std::sync::Mutex;
+pub(crate) use wait_queue::solution::{CondvarWaitQueue, WaitQueue};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub mtype: i64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MsgError {
+    /// `mtype < 1` on send (`EINVAL`).
+    InvalidType,
+    /// Payload larger than the whole queue could ever hold (`EINVAL`).
+    TooBig,
+    /// `nowait` send to a full queue (`EAGAIN`).
+    WouldBlock,
+    /// `nowait` receive with no matching message (`ENOMSG`).
+    NoMessage,
+    /// The queue was removed (`EIDRM`).
+    Removed,
+}
+
+struct QueueState {
+    msgs: VecDeque<Message>,
+    /// Sum of the payload sizes in `msgs`.
+    bytes: usize,
+    removed: bool,
+}
+
+/// A message queue; `W` is the wait queue backend (OS threads by default).
+pub struct MsgQueue<W: WaitQueue = CondvarWaitQueue> {
+    state: Mutex<QueueState>,
+    max_bytes: usize,
+    /// Senders waiting for space.
+    senders: W,
+    /// Receivers waiting for a message.
+    receivers: W,
+}
+
+/// Index of the message `msgrcv(msgtyp)` takes from `msgs`, if any (rules in the crate docs).
+pub fn select(msgs: &VecDeque<Message>, msgtyp: i64) -> Option<usize> {
+    match msgtyp {
+        0 => (!msgs.is_empty()).then_some(0),
+        t if t > 0 => msgs.iter().position(|m| m.mtype == t),
+        t => msgs
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m.mtype <= -t)
+            .min_by_key(|&(i, m)| (m.mtype, i))
+            .map(|(i, _)| i),
+    }
+}
+
+impl<W: WaitQueue + Default> MsgQueue<W> {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                msgs: VecDeque::new(),
+                bytes: 0,
+                removed: false,
+            }),
+            max_bytes,
+            senders: W::default(),
+            receivers: W::default(),
+        }
+    }
+}
+
+impl<W: WaitQueue> MsgQueue<W> {
+    /// Append a message, blocking while it does not fit.
+    ///
+    /// 1. `mtype < 1` -> `InvalidType`; `data.len() > max_bytes` -> `TooBig`.
+    /// 2. An attempt (under the `state` lock): `Removed` if removed; if `bytes + len <= max_bytes`
+    ///    push the message and succeed; otherwise "not yet".
+    /// 3. With `nowait`, try once ("not yet" -> `WouldBlock`); otherwise retry the attempt as the
+    ///    `senders.wait_until` condition.
+    /// 4. After a successful send, `receivers.wake_all()`.
+    pub fn msgsnd(&self, mtype: i64, data: &[u8], nowait: bool) -> Result<(), MsgError> {
+        if mtype < 1 {
+            return Err(MsgError::InvalidType);
+        }
+        if data.len() > self.max_bytes {
+            return Err(MsgError::TooBig);
+        }
+        let attempt = || {
+            let mut st = self.state.lock().unwrap();
+            if st.removed {
+                return Some(Err(MsgError::Removed));
+            }
+            if st.bytes + data.len() > self.max_bytes {
+                return None;
+            }
+            st.bytes += data.len();
+            st.msgs.push_back(Message {
+                mtype,
+                data: data.to_vec(),
+            });
+            Some(Ok(()))
+        };
+        let result = if nowait {
+            attempt().unwrap_or(Err(MsgError::WouldBlock))
+        } else {
+            let mut result = None;
+            self.senders.wait_until(|| {
+                result = attempt();
+                result.is_some()
+            });
+            result.unwrap()
+        };
+        if result.is_ok() {
+            self.receivers.wake_all();
+        }
+        result
+    }
+
+    /// Take the message `select(msgs, msgtyp)` picks, blocking until there is one.
+    ///
+    /// Same structure as `msgsnd`: the attempt fails with `Removed` if removed, otherwise removes
+    /// the selected message (updating `bytes`); `nowait` + nothing found -> `NoMessage`. After a
+    /// successful receive, `senders.wake_all()` (the freed space may let several senders in).
+    pub fn msgrcv(&self, msgtyp: i64, nowait: bool) -> Result<Message, MsgError> {
+        let attempt = || {
+            let mut st = self.state.lock().unwrap();
+            if st.removed {
+                return Some(Err(MsgError::Removed));
+            }
+            let i = select(&st.msgs, msgtyp)?;
+            let m = st.msgs.remove(i).unwrap();
+            st.bytes -= m.data.len();
+            Some(Ok(m))
+        };
+        let result = if nowait {
+            attempt().unwrap_or(Err(MsgError::NoMessage))
+        } else {
+            let mut result = None;
+            self.receivers.wait_until(|| {
+                result = attempt();
+                result.is_some()
+            });
+            result.unwrap()
+        };
+        if result.is_ok() {
+            self.senders.wake_all();
+        }
+        result
+    }
+
+    /// `msgctl(IPC_RMID)`: mark removed, drop all messages, wake every sender and receiver.
+    pub fn remove(&self) {
+        {
+            let mut st = self.state.lock().unwrap();
+            st.removed = true;
+            st.msgs.clear();
+            st.bytes = 0;
+        }
+        self.senders.wake_all();
+        self.receivers.wake_all();
+    }
+
+    /// Number of queued messages.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().msgs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Payload bytes currently queued.
+    pub fn bytes(&self) -> usize {
+        self.state.lock().unwrap().bytes
+    }
+
+    /// Number of (senders, receivers) currently blocked.
+    pub fn blocked(&self) -> (usize, usize) {
+        (self.senders.waiters(), self.receivers.waiters())
+    }
+}
@@ -0,0 +1,425 @@
+//! # Unix Pipe
+//!
+//! In this exercise, you implement a pipe as a kernel file object, with the blocking rules every
+//! shell pipeline relies on. Both ends implement the `File` trait from `02_no_std_dev/05_fd_table`
+//! (`read`/`write` return a byte count or a negative errno), so they can sit in an fd table.
+//! Blocking uses the `WaitQueue` from `06_wait_queue`; finish both of those first.
+//!
+//! ## Semantics
+//! - `read` blocks while the pipe is empty and a writer still exists. It returns whatever is
+//!   buffered (up to `buf.len()`), possibly fewer bytes than asked for. Empty pipe and no writers
+//!   left: `0`, end of file.
+//! - `write` blocks while the pipe is full and returns only once every byte is in (a write larger
+//!   than the capacity completes piece by piece as the reader drains it).
+//! - Writing when no reader is left fails with `-EPIPE` and raises `SIGPIPE` (here: increments
+//!   `sigpipes()`). If some bytes of this write already went in, return that count instead.
+//! - `O_NONBLOCK` (`set_nonblocking`): instead of blocking, `read` returns `-EAGAIN`; `write` puts
+//!   in what fits and returns that count, or `-EAGAIN` if nothing fit.
+//! - Ends are reference counted: `clone()` is another handle (as after `fork`/`dup`), and the
+//!   end is closed when its last handle is dropped. Closing the last writer wakes blocked readers
+//!   (they see EOF); closing the last reader wakes blocked writers (they get `-EPIPE`).
+//!
+//! ## Locking
+//! As in `07_msg_queue`: do the transfer inside the `wait_until` condition, and wake the other
+//! side only after the `state` lock is released and `wait_until` has returned.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use wait_queue::{CondvarWaitQueue, WaitQueue};
+
+pub use fd_table::File;
+
+pub const EBADF: isize = 9;
+pub const EAGAIN: isize = 11;
+pub const EPIPE: isize = 32;
+
+struct PipeState {
+    buf: VecDeque<u8>,
+    capacity: usize,
+    /// Open read / write handles.
+    readers: usize,
+    writers: usize,
+}
+
+struct PipeInner {
+    state: Mutex<PipeState>,
+    /// Readers waiting for data (or EOF).
+    readable: CondvarWaitQueue,
+    /// Writers waiting for space (or for the last reader to go away).
+    writable: CondvarWaitQueue,
+    /// Number of `SIGPIPE`s raised.
+    sigpipes: AtomicUsize,
+}
+
+/// Read end of a pipe.
+pub struct PipeReader {
+    inner: Arc<PipeInner>,
+    nonblocking: AtomicBool,
+}
+
+/// Write end of a pipe.
+pub struct PipeWriter {
+    inner: Arc<PipeInner>,
+    nonblocking: AtomicBool,
+}
+
+/// Create a pipe buffering at most `capacity` (> 0) bytes.
+pub fn pipe(capacity: usize) -> (PipeReader, PipeWriter) {
+    assert!(capacity > 0, "capacity must be non-zero");
+    let inner = Arc::new(PipeInner {
+        state: Mutex::new(PipeState {
+            buf: VecDeque::new(),
+            capacity,
+            readers: 1,
+            writers: 1,
+        }),
+        readable: CondvarWaitQueue::new(),
+        writable: CondvarWaitQueue::new(),
+        sigpipes: AtomicUsize::new(0),
+    });
+    (
+        PipeReader {
+            inner: inner.clone(),
+            nonblocking: AtomicBool::new(false),
+        },
+        PipeWriter {
+            inner,
+            nonblocking: AtomicBool::new(false),
+        },
+    )
+}
+
+impl PipeInner {
+    fn buffered(&self) -> usize {
+        self.state.lock().unwrap().buf.len()
+    }
+}
+
+impl PipeReader {
+    /// Set or clear `O_NONBLOCK` on this handle.
+    pub fn set_nonblocking(&self, on: bool) {
+        self.nonblocking.store(on, Ordering::SeqCst);
+    }
+
+    /// Bytes currently in the pipe.
+    pub fn buffered(&self) -> usize {
+        self.inner.buffered()
+    }
+}
+
+impl PipeWriter {
+    /// Set or clear `O_NONBLOCK` on this handle.
+    pub fn set_nonblocking(&self, on: bool) {
+        self.nonblocking.store(on, Ordering::SeqCst);
+    }
+
+    /// Bytes currently in the pipe.
+    pub fn buffered(&self) -> usize {
+        self.inner.buffered()
+    }
+
+    /// How many times writing to this pipe raised `SIGPIPE`.
+    pub fn sigpipes(&self) -> usize {
+        self.inner.sigpipes.load(Ordering::SeqCst)
+    }
+}
+
+impl Clone for PipeReader {
+    fn clone(&self) -> Self {
+        self.inner.state.lock().unwrap().readers += 1;
+        Self {
+            inner: self.inner.clone(),
+            nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+impl Clone for PipeWriter {
+    fn clone(&self) -> Self {
+        self.inner.state.lock().unwrap().writers += 1;
+        Self {
+            inner: self.inner.clone(),
+            nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+impl File for PipeReader {
+    /// 1. Empty `buf`: return 0.
+    /// 2. An attempt (under the lock): data buffered -> move up to `buf.len()` bytes out, done
+    ///    with that count; no data and `writers == 0` -> done with 0; otherwise "not yet".
+    /// 3. Non-blocking: try once, "not yet" -> `-EAGAIN`. Blocking: retry the attempt as the
+    ///    `readable.wait_until` condition.
+    /// 4. If bytes were taken, `writable.wake_all()`.
+    fn read(&self, buf: &mut [u8]) -> isize {
+        // TODO
+        todo!()
+    }
+
+    fn write(&self, _buf: &[u8]) -> isize {
+        -EBADF
+    }
+}
+
+impl File for PipeWriter {
+    fn read(&self, _buf: &mut [u8]) -> isize {
+        -EBADF
+    }
+
+    /// Loop until all of `buf` is written:
+    /// 1. An attempt (under the lock): `readers == 0` -> broken pipe; free space -> push as much
+    ///    of the rest as fits, progress; full -> "not yet".
+    /// 2. Non-blocking: try once; "not yet" ends the write (return the count so far, or `-EAGAIN`
+    ///    if it is 0). Blocking: retry as the `writable.wait_until` condition.
+    /// 3. Progress: `readable.wake_all()` and continue. Broken pipe: increment `sigpipes` and
+    ///    return the count so far, or `-EPIPE` if it is 0.
+    fn write(&self, buf: &[u8]) -> isize {
+        // TODO
+        todo!()
+    }
+}
+
+impl Drop for PipeReader {
+    /// Close this handle: decrement `readers`; if it reaches 0, wake every blocked writer.
+    /// (Checking `waiters()` first is safe: a waiter is counted before it releases the queue lock.)
+    fn drop(&mut self) {
+        let last = {
+            let mut st = self.inner.state.lock().unwrap();
+            st.readers -= 1;
+            st.readers == 0
+        };
+        if last && self.inner.writable.waiters() > 0 {
+            self.inner.writable.wake_all();
+        }
+    }
+}
+
+impl Drop for PipeWriter {
+    /// Close this handle: decrement `writers`; if it reaches 0, wake every blocked reader.
+    fn drop(&mut self) {
+        let last = {
+            let mut st = self.inner.state.lock().unwrap();
+            st.writers -= 1;
+            st.writers == 0
+        };
+        if last && self.inner.readable.waiters() > 0 {
+            self.inner.readable.wake_all();
+        }
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    #[cfg(feature = "solutions")]
+    use fd_table::solution::FdTable;
+    #[cfg(not(feature = "solutions"))]
+    use fd_table::FdTable;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::thread;
+    use std::time::Duration;
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_write_then_read",
+            "read takes min(buf.len(), buffered) bytes and returns at once: a short read is fine",
+        ),
+        (
+            "test_eof_after_last_writer_closes",
+            "an empty pipe is EOF (0) only once writers == 0; while a writer is left, read blocks",
+        ),
+        (
+            "test_write_without_reader_is_epipe",
+            "with readers == 0, count a SIGPIPE and return -EPIPE (or the bytes already written)",
+        ),
+        (
+            "test_nonblocking_write",
+            "in O_NONBLOCK mode write what fits and return that count; -EAGAIN only when nothing fit",
+        ),
+    ];
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn read_vec(r: &PipeReader, max: usize) -> Vec<u8> {
+        let mut buf = vec![0; max];
+        let n = r.read(&mut buf);
+        assert!(n >= 0, "read failed with {n}");
+        buf.truncate(n as usize);
+        buf
+    }
+
+    /// Give a spawned thread time to block.
+    fn settle() {
+        thread::sleep(Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_write_then_read() {
+        let (r, w) = pipe(16);
+        assert_eq_with_hint!(w.write(b"hello"), 5);
+        assert_eq_with_hint!(r.buffered(), 5);
+        assert_eq_with_hint!(read_vec(&r, 3), b"hel");
+        assert_eq_with_hint!(read_vec(&r, 10), b"lo", "short read: only what is buffered");
+        assert_eq_with_hint!(r.read(&mut []), 0);
+        assert_eq_with_hint!(w.write(&[]), 0);
+    }
+
+    #[test]
+    fn test_wrong_direction() {
+        let (r, w) = pipe(4);
+        assert_eq!(r.write(b"x"), -EBADF);
+        assert_eq!(w.read(&mut [0; 1]), -EBADF);
+    }
+
+    #[test]
+    fn test_read_blocks_until_data() {
+        run_with_timeout(TIMEOUT, || {
+            let (r, w) = pipe(16);
+            let h = thread::spawn(move || read_vec(&r, 16));
+            settle();
+            assert!(!h.is_finished(), "reader must block on an empty pipe");
+            assert_eq!(w.write(b"ping"), 4);
+            assert_eq!(h.join().unwrap(), b"ping");
+        });
+    }
+
+    #[test]
+    fn test_eof_after_last_writer_closes() {
+        run_with_timeout(TIMEOUT, || {
+            let (r, w) = pipe(16);
+            let w2 = w.clone();
+            w.write(b"tail");
+            let h = thread::spawn(move || {
+                let first = read_vec(&r, 16);
+                let second = read_vec(&r, 16);
+                (first, second)
+            });
+            settle();
+            drop(w);
+            settle();
+            assert_with_hint!(!h.is_finished(), "one writer is still open");
+            drop(w2);
+            let (first, second) = h.join().unwrap();
+            assert_eq_with_hint!(first, b"tail", "buffered data comes before EOF");
+            assert_with_hint!(second.is_empty(), "then EOF");
+        });
+    }
+
+    #[test]
+    fn test_write_without_reader_is_epipe() {
+        let (r, w) = pipe(16);
+        drop(r);
+        assert_eq_with_hint!(w.write(b"lost"), -EPIPE);
+        assert_eq_with_hint!(w.write(b"again"), -EPIPE);
+        assert_eq_with_hint!(w.sigpipes(), 2);
+        assert_eq_with_hint!(w.buffered(), 0);
+    }
+
+    #[test]
+    fn test_blocked_writer_gets_epipe_when_reader_closes() {
+        run_with_timeout(TIMEOUT, || {
+            let (r, w) = pipe(4);
+            assert_eq!(w.write(b"full"), 4);
+            let h = thread::spawn(move || {
+                let res = w.write(b"more");
+                (res, w.sigpipes())
+            });
+            settle();
+            assert!(!h.is_finished(), "writer must block on a full pipe");
+            drop(r);
+            assert_eq!(h.join().unwrap(), (-EPIPE, 1));
+        });
+    }
+
+    #[test]
+    fn test_large_write_streams_through() {
+        run_with_timeout(TIMEOUT, || {
+            let (r, w) = pipe(8);
+            let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+            let expected = data.clone();
+            let h = thread::spawn(move || w.write(&data));
+            let mut got = Vec::new();
+            loop {
+                let chunk = read_vec(&r, 5);
+                if chunk.is_empty() {
+                    break;
+                }
+                got.extend(chunk);
+            }
+            assert_eq!(h.join().unwrap(), 1000);
+            assert_eq!(got, expected);
+        });
+    }
+
+    #[test]
+    fn test_partial_write_then_epipe() {
+        run_with_timeout(TIMEOUT, || {
+            let (r, w) = pipe(4);
+            let h = thread::spawn(move || w.write(b"abcdefgh"));
+            settle();
+            assert_eq!(r.buffered(), 4);
+            drop(r);
+            assert_eq!(h.join().unwrap(), 4, "bytes already written are reported");
+        });
+    }
+
+    #[test]
+    fn test_nonblocking_read() {
+        let (r, w) = pipe(4);
+        r.set_nonblocking(true);
+        assert_eq!(r.read(&mut [0; 4]), -EAGAIN);
+        w.write(b"ab");
+        assert_eq!(read_vec(&r, 4), b"ab");
+        drop(w);
+        assert_eq!(r.read(&mut [0; 4]), 0, "no writers: EOF, not EAGAIN");
+    }
+
+    #[test]
+    fn test_nonblocking_write() {
+        let (r, w) = pipe(4);
+        w.set_nonblocking(true);
+        assert_eq_with_hint!(w.write(b"abc"), 3);
+        assert_eq_with_hint!(w.write(b"defg"), 1, "only what fits");
+        assert_eq_with_hint!(w.write(b"h"), -EAGAIN);
+        assert_eq_with_hint!(read_vec(&r, 8), b"abcd");
+        drop(r);
+        assert_eq_with_hint!(w.write(b"x"), -EPIPE);
+    }
+
+    #[test]
+    fn test_ends_in_fd_table() {
+        run_with_timeout(TIMEOUT, || {
+            let (r, w) = pipe(32);
+            let mut fds = FdTable::new();
+            let rfd = fds.alloc(Arc::new(r));
+            let wfd = fds.alloc(Arc::new(w));
+            let writer = fds.get(wfd).unwrap();
+            let h = thread::spawn(move || {
+                for word in [&b"one "[..], b"two ", b"three"] {
+                    assert_eq!(writer.write(word), word.len() as isize);
+                }
+            });
+            h.join().unwrap();
+            assert!(fds.close(wfd));
+            let reader = fds.get(rfd).unwrap();
+            let mut buf = [0; 32];
+            let n = reader.read(&mut buf);
+            assert_eq!(&buf[..n as usize], b"one two three");
+            assert_eq!(
+                reader.read(&mut buf),
+                0,
+                "closing the fd dropped the writer end"
+            );
+        });
+    }
+}
@@ -0,0 +1,287 @@
+//! # Unix Pipe
+//!
+//! In this exercise, you implement a pipe as a kernel file object, with the blocking rules every
+//! shell pipeline relies on. Both ends implement the `File` trait from `02_no_std_dev/05_fd_table`
+//! (`read`/`write` return a byte count or a negative errno), so they can sit in an fd table.
+//! Blocking uses the `WaitQueue` from `06_wait_queue`; finish both of those first.
+//!
+//! ## Semantics
+//! - `read` blocks while the pipe is empty and a writer still exists. It returns whatever is
+//!   buffered (up to `buf.len()`), possibly fewer bytes than asked for. Empty pipe and no writers
+//!   left: `0`, end of file.
+//! - `write` blocks while the pipe is full and returns only once every byte is in (a write larger
+//!   than the capacity completes piece by piece as the reader drains it).
+//! - Writing when no reader is left fails with `-EPIPE` and raises `SIGPIPE` (here: increments
+//!   `sigpipes()`). If some bytes of this write already went in, return that count instead.
+//! - `O_NONBLOCK` (`set_nonblocking`): instead of blocking, `read` returns `-EAGAIN`; `write` puts
+//!   in what fits and returns that count, or `-EAGAIN` if nothing fit.
+//! - Ends are reference counted: `clone()` is another handle (as after `fork`/`dup`), and the
+//!   end is closed when its last handle is dropped. Closing the last writer wakes blocked readers
+//!   (they see EOF); closing the last reader wakes blocked writers (they get `-EPIPE`).
+//!
+//! ## Locking
+//! As in `07_msg_queue`: do the transfer inside the `wait_until` condition, and wake the other
+//! side only after the `state` lock is released and `wait_until` has returned.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+pub(crate) use std::sync::{Arc, Mutex};
+use wait_queue::solution::{CondvarWaitQueue, WaitQueue};
+
+pub use fd_table::solution::File;
+
+pub const EBADF: isize = 9;
+pub const EAGAIN: isize = 11;
+pub const EPIPE: isize = 32;
+
+struct PipeState {
+    buf: VecDeque<u8>,
+    capacity: usize,
+    /// Open read / write handles.
+    readers: usize,
+    writers: usize,
+}
+
+struct PipeInner {
+    state: Mutex<PipeState>,
+    /// Readers waiting for data (or EOF).
+    readable: CondvarWaitQueue,
+    /// Writers waiting for space (or for the last reader to go away).
+    writable: CondvarWaitQueue,
+    /// Number of `SIGPIPE`s raised.
+    sigpipes: AtomicUsize,
+}
+
+/// Read end of a pipe.
+pub struct PipeReader {
+    inner: Arc<PipeInner>,
+    nonblocking: AtomicBool,
+}
+
+/// Write end of a pipe.
+pub struct PipeWriter {
+    inner: Arc<PipeInner>,
+    nonblocking: AtomicBool,
+}
+
+/// Create a pipe buffering at most `capacity` (> 0) bytes.
+pub fn pipe(capacity: usize) -> (PipeReader, PipeWriter) {
+    assert!(capacity > 0, "capacity must be non-zero");
+    let inner = Arc::new(PipeInner {
+        state: Mutex::new(PipeState {
+            buf: VecDeque::new(),
+            capacity,
+            readers: 1,
+            writers: 1,
+        }),
+        readable: CondvarWaitQueue::new(),
+        writable: CondvarWaitQueue::new(),
+        sigpipes: AtomicUsize::new(0),
+    });
+    (
+        PipeReader {
+            inner: inner.clone(),
+            nonblocking: AtomicBool::new(false),
+        },
+        PipeWriter {
+            inner,
+            nonblocking: AtomicBool::new(false),
+        },
+    )
+}
+
+impl PipeInner {
+    fn buffered(&self) -> usize {
+        self.state.lock().unwrap().buf.len()
+    }
+}
+
+impl PipeReader {
+    /// Set or clear `O_NONBLOCK` on this handle.
+    pub fn set_nonblocking(&self, on: bool) {
+        self.nonblocking.store(on, Ordering::SeqCst);
+    }
+
+    /// Bytes currently in the pipe.
+    pub fn buffered(&self) -> usize {
+        self.inner.buffered()
+    }
+}
+
+impl PipeWriter {
+    /// Set or clear `O_NONBLOCK` on this handle.
+    pub fn set_nonblocking(&self, on: bool) {
+        self.nonblocking.store(on, Ordering::SeqCst);
+    }
+
+    /// Bytes currently in the pipe.
+    pub fn buffered(&self) -> usize {
+        self.inner.buffered()
+    }
+
+    /// How many times writing to this pipe raised `SIGPIPE`.
+    pub fn sigpipes(&self) -> usize {
+        self.inner.sigpipes.load(Ordering::SeqCst)
+    }
+}
+
+impl Clone for PipeReader {
+    fn clone(&self) -> Self {
+        self.inner.state.lock().unwrap().readers += 1;
+        Self {
+            inner: self.inner.clone(),
+            nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+impl Clone for PipeWriter {
+    fn clone(&self) -> Self {
+        self.inner.state.lock().unwrap().writers += 1;
+        Self {
+            inner: self.inner.clone(),
+            nonblocking: AtomicBool::new(self.nonblocking.load(Ordering::SeqCst)),
+        }
+    }
+}
+
+impl File for PipeReader {
+    /// 1. Empty `buf`: return 0.
+    /// 2. An attempt (under the lock): data buffered -> move up to `buf.len()` bytes out, done
+    ///    with that count; no data and `writers == 0` -> done with 0; otherwise "not yet".
+    /// 3. Non-blocking: try once, "not yet" -> `-EAGAIN`. Blocking: retry the attempt as the
+    ///    `readable.wait_until` condition.
+    /// 4. If bytes were taken, `writable.wake_all()`.
+    fn read(&self, buf: &mut [u8]) -> isize {
+        if buf.is_empty() {
+            return 0;
+        }
+        let inner = &self.inner;
+        let mut attempt = || {
+            let mut st = inner.state.lock().unwrap();
+            if st.buf.is_empty() {
+                return (st.writers == 0).then_some(0);
+            }
+            let n = buf.len().min(st.buf.len());
+            for (dst, src) in buf.iter_mut().zip(st.buf.drain(..n)) {
+                *dst = src;
+            }
+            Some(n)
+        };
+        let n = if self.nonblocking.load(Ordering::SeqCst) {
+            match attempt() {
+                Some(n) => n,
+                None => return -EAGAIN,
+            }
+        } else {
+            let mut result = None;
+            inner.readable.wait_until(|| {
+                result = attempt();
+                result.is_some()
+            });
+            result.unwrap()
+        };
+        if n > 0 {
+            inner.writable.wake_all();
+        }
+        n as isize
+    }
+
+    fn write(&self, _buf: &[u8]) -> isize {
+        -EBADF
+    }
+}
+
+impl File for PipeWriter {
+    fn read(&self, _buf: &mut [u8]) -> isize {
+        -EBADF
+    }
+
+    /// Loop until all of `buf` is written:
+    /// 1. An attempt (under the lock): `readers == 0` -> broken pipe; free space -> push as much
+    ///    of the rest as fits, progress; full -> "not yet".
+    /// 2. Non-blocking: try once; "not yet" ends the write (return the count so far, or `-EAGAIN`
+    ///    if it is 0). Blocking: retry as the `writable.wait_until` condition.
+    /// 3. Progress: `readable.wake_all()` and continue. Broken pipe: increment `sigpipes` and
+    ///    return the count so far, or `-EPIPE` if it is 0.
+    fn write(&self, buf: &[u8]) -> isize {
+        let inner = &self.inner;
+        let mut written = 0;
+        while written < buf.len() {
+            let rest = &buf[written..];
+            let attempt = || {
+                let mut st = inner.state.lock().unwrap();
+                if st.readers == 0 {
+                    return Some(Err(()));
+                }
+                let n = (st.capacity - st.buf.len()).min(rest.len());
+                if n == 0 {
+                    return None;
+                }
+                st.buf.extend(&rest[..n]);
+                Some(Ok(n))
+            };
+            let outcome = if self.nonblocking.load(Ordering::SeqCst) {
+                attempt()
+            } else {
+                let mut result = None;
+                inner.writable.wait_until(|| {
+                    result = attempt();
+                    result.is_some()
+                });
+                result
+            };
+            match outcome {
+                Some(Ok(n)) => {
+                    written += n;
+                    inner.readable.wake_all();
+                }
+                Some(Err(())) => {
+                    inner.sigpipes.fetch_add(1, Ordering::SeqCst);
+                    return if written > 0 {
+                        written as isize
+                    } else {
+                        -EPIPE
+                    };
+                }
+                None => {
+                    return if written > 0 {
+                        written as isize
+                    } else {
+                        -EAGAIN
+                    }
+                }
+            }
+        }
+        written as isize
+    }
+}
+
+impl Drop for PipeReader {
+    /// Close this handle: decrement `readers`; if it reaches 0, wake every blocked writer.
+    /// (Checking `waiters()` first is safe: a waiter is counted before it releases the queue lock.)
+    fn drop(&mut self) {
+        let last = {
+            let mut st = self.inner.state.lock().unwrap();
+            st.readers -= 1;
+            st.readers == 0
+        };
+        if last && self.inner.writable.waiters() > 0 {
+            self.inner.writable.wake_all();
+        }
+    }
+}
+
+impl Drop for PipeWriter {
+    /// Close this handle: decrement `writers`; if it reaches 0, wake every blocked reader.
+    fn drop(&mut self) {
+        let last = {
+            let mut st = self.inner.state.lock().unwrap();
+            st.writers -= 1;
+            st.writers == 0
+        };
+        if last && self.inner.readable.waiters() > 0 {
+            self.inner.readable.wake_all();
+        }
+    }
+}
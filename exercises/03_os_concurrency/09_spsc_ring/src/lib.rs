@@ -0,0 +1,297 @@
+//! # Lock-free SPSC Ring Buffer
+//!
+//! In this exercise, you implement a fixed-capacity ring buffer for exactly **one producer and
+//! one consumer**, with no lock at all: just two atomic indices. This is the queue between an
+//! interrupt handler and a driver (the UART's RX/TX buffers), or between the two ends of a pipe.
+//!
+//! ## Concepts
+//! - Free-running indices: `tail` counts every push, `head` every pop; both only ever grow
+//!   (wrapping at `usize::MAX`). `tail - head` is the length, `index % N` the slot — `N` is a
+//!   power of two, so the wrap of the counters is harmless
+//! - Each index has a single writer: the producer owns `tail`, the consumer owns `head`. A side
+//!   may read its own index `Relaxed`
+//! - **Release / Acquire pairs**: the producer writes the slot, *then* publishes it with a
+//!   `Release` store of `tail`; the consumer's `Acquire` load of `tail` makes the slot's contents
+//!   visible. The same pair on `head` hands the emptied slot back to the producer
+//! - `split` hands out one `Producer` and one `Consumer`; Rust's borrow rules enforce "single"
+//!
+//! Only `core` is used, so the ring works in a `no_std` kernel (e.g. in a `static`).
+
+#![cfg_attr(not(test), no_std)]
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+pub struct SpscRing<T, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    /// Next slot to pop (written by the consumer only).
+    head: AtomicUsize,
+    /// Next slot to push (written by the producer only).
+    tail: AtomicUsize,
+}
+
+// The producer and the consumer may live on different threads; items move between them.
+unsafe impl<T: Send, const N: usize> Sync for SpscRing<T, N> {}
+
+impl<T, const N: usize> SpscRing<T, N> {
+    /// # Panics
+    /// If `N` is not a power of two.
+    pub fn new() -> Self {
+        Self::with_start_index(0)
+    }
+
+    /// A ring whose counters start at `start` instead of 0 — lets tests exercise the wrap of
+    /// the counters at `usize::MAX` without pushing 2^64 items.
+    pub fn with_start_index(start: usize) -> Self {
+        assert!(N.is_power_of_two(), "capacity must be a power of two");
+        Self {
+            slots: core::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+            head: AtomicUsize::new(start),
+            tail: AtomicUsize::new(start),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of items (a snapshot when the other side is active).
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The two ends of the ring. Holding `&mut self` guarantees there is only one of each.
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        (Producer { ring: self }, Consumer { ring: self })
+    }
+}
+
+impl<T, const N: usize> Default for SpscRing<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for SpscRing<T, N> {
+    fn drop(&mut self) {
+        // Drop the items still queued: slots head..tail are initialized.
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            unsafe { self.slots[head % N].get_mut().assume_init_drop() };
+            head = head.wrapping_add(1);
+        }
+    }
+}
+
+pub struct Producer<'a, T, const N: usize> {
+    ring: &'a SpscRing<T, N>,
+}
+
+pub struct Consumer<'a, T, const N: usize> {
+    ring: &'a SpscRing<T, N>,
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Append `item`, or hand it back if the ring is full.
+    ///
+    /// 1. `tail` = own index (`Relaxed`), `head` = the consumer's (`Acquire`: the slot it
+    ///    freed must really be free before we overwrite it)
+    /// 2. Full if `tail - head == N` (wrapping): `Err(item)`
+    /// 3. Write `item` into `slots[tail % N]` (`(*slot.get()).write(item)`)
+    /// 4. Publish: store `tail + 1` (wrapping) with `Release`
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        // TODO
+        todo!()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.ring.len() == N
+    }
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Remove the oldest item.
+    ///
+    /// 1. `head` = own index (`Relaxed`), `tail` = the producer's (`Acquire`: pairs with the
+    ///    `Release` in `push`, so the slot's contents are visible)
+    /// 2. Empty if `head == tail`
+    /// 3. Move the item out of `slots[head % N]` (`(*slot.get()).assume_init_read()`)
+    /// 4. Hand the slot back: store `head + 1` (wrapping) with `Release`
+    pub fn pop(&mut self) -> Option<T> {
+        // TODO
+        todo!()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::rc::Rc;
+    use std::time::Instant;
+
+    const HINTS: Hints = &[
+        (
+            "test_fifo_full_and_empty",
+            "the ring is full when tail - head == N and empty when head == tail; push returns Err(item) when full",
+        ),
+        (
+            "test_wrap_around_slots",
+            "the indices only ever grow: the slot is index % N, never store the index already reduced mod N",
+        ),
+        (
+            "test_wrap_around_counters",
+            "compute the length with tail.wrapping_sub(head) and advance with wrapping_add(1), the counters overflow usize",
+        ),
+        (
+            "test_cross_thread_throughput",
+            "push must store tail with Release after writing the slot, and pop must load tail with Acquire before reading it",
+        ),
+    ];
+
+    #[test]
+    fn test_fifo_full_and_empty() {
+        let mut ring: SpscRing<u32, 4> = SpscRing::new();
+        let (mut tx, mut rx) = ring.split();
+        assert_eq_with_hint!(rx.pop(), None);
+        for i in 0..4 {
+            assert_eq_with_hint!(tx.push(i), Ok(()));
+        }
+        assert_with_hint!(tx.is_full());
+        assert_eq_with_hint!(tx.push(99), Err(99), "full: the item comes back");
+        assert_eq_with_hint!(rx.pop(), Some(0));
+        assert_eq_with_hint!(tx.push(4), Ok(()));
+        let rest: Vec<u32> = std::iter::from_fn(|| rx.pop()).collect();
+        assert_eq_with_hint!(rest, [1, 2, 3, 4]);
+        assert_with_hint!(rx.is_empty());
+    }
+
+    #[test]
+    fn test_wrap_around_slots() {
+        let mut ring: SpscRing<usize, 4> = SpscRing::new();
+        let (mut tx, mut rx) = ring.split();
+        let mut next = 0;
+        for round in 0..10 {
+            for k in 0..3 {
+                tx.push(round * 3 + k).unwrap();
+            }
+            assert_eq_with_hint!(rx.len(), 3);
+            for _ in 0..3 {
+                assert_eq_with_hint!(rx.pop(), Some(next));
+                next += 1;
+            }
+        }
+        assert_eq_with_hint!(next, 30);
+    }
+
+    #[test]
+    fn test_wrap_around_counters() {
+        let mut ring: SpscRing<u8, 8> = SpscRing::with_start_index(usize::MAX - 3);
+        let (mut tx, mut rx) = ring.split();
+        for i in 0..8 {
+            tx.push(i).unwrap();
+        }
+        assert_with_hint!(tx.is_full(), "the length survives the counter wrap");
+        assert_eq_with_hint!(tx.push(8), Err(8));
+        for i in 0..8 {
+            assert_eq_with_hint!(rx.pop(), Some(i));
+        }
+        assert_eq_with_hint!(rx.pop(), None);
+    }
+
+    #[test]
+    fn test_drop_remaining_items() {
+        let marker = Rc::new(());
+        {
+            let mut ring: SpscRing<Rc<()>, 4> = SpscRing::new();
+            let (mut tx, mut rx) = ring.split();
+            for _ in 0..3 {
+                tx.push(marker.clone()).unwrap();
+            }
+            drop(rx.pop());
+            assert_eq!(Rc::strong_count(&marker), 3);
+        }
+        assert_eq!(
+            Rc::strong_count(&marker),
+            1,
+            "queued items dropped with the ring"
+        );
+    }
+
+    #[test]
+    fn test_non_copy_items() {
+        let mut ring: SpscRing<String, 2> = SpscRing::default();
+        let (mut tx, mut rx) = ring.split();
+        tx.push("hello".to_string()).unwrap();
+        tx.push("world".to_string()).unwrap();
+        assert_eq!(tx.push("!".to_string()), Err("!".to_string()));
+        assert_eq!(rx.pop().as_deref(), Some("hello"));
+        assert_eq!(rx.pop().as_deref(), Some("world"));
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn test_capacity_must_be_power_of_two() {
+        let _ring: SpscRing<u8, 6> = SpscRing::new();
+    }
+
+    #[test]
+    fn test_cross_thread_throughput() {
+        const COUNT: u64 = if cfg!(miri) { 500 } else { 200_000 };
+        let mut ring: SpscRing<u64, 256> = SpscRing::new();
+        let (mut tx, mut rx) = ring.split();
+        let start = Instant::now();
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..COUNT {
+                    let mut item = i;
+                    while let Err(back) = tx.push(item) {
+                        item = back;
+                        std::thread::yield_now();
+                    }
+                }
+            });
+            s.spawn(move || {
+                let mut expected = 0;
+                while expected < COUNT {
+                    match rx.pop() {
+                        Some(v) => {
+                            assert_eq_with_hint!(v, expected, "items arrive in order, none lost");
+                            expected += 1;
+                        }
+                        None => std::thread::yield_now(),
+                    }
+                }
+            });
+        });
+        let elapsed = start.elapsed();
+        eprintln!(
+            "{COUNT} items in {elapsed:?} ({:.1} M items/s)",
+            COUNT as f64 / elapsed.as_secs_f64() / 1e6
+        );
+        assert_with_hint!(ring.is_empty());
+    }
+}
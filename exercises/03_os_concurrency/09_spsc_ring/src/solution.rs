@@ -0,0 +1,154 @@
+//! # Lock-free SPSC Ring Buffer
+//!
+//! In this exercise, you implement a fixed-capacity ring buffer for exactly **one producer and
+//! one consumer**, with no lock at all: just two atomic indices. This is the queue between an
+//! interrupt handler and a driver (the UART's RX/TX buffers), or between the two ends of a pipe.
+//!
+//! ## Concepts
+//! - Free-running indices: `tail` counts every push, `head` every pop; both only ever grow
+//!   (wrapping at `usize::MAX`). `tail - head` is the length, `index % N` the slot — `N` is a
+//!   power of two, so the wrap of the counters is harmless
+//! - Each index has a single writer: the producer owns `tail`, the consumer owns `head`. A side
+//!   may read its own index `Relaxed`
+//! - **Release / Acquire pairs**: the producer writes the slot, *then* publishes it with a
+//!   `Release` store of `tail`; the consumer's `Acquire` load of `tail` makes the slot's contents
+//!   visible. The same pair on `head` hands the emptied slot back to the producer
+//! - `split` hands out one `Producer` and one `Consumer`; Rust's borrow rules enforce "single"
+//!
+//! Only `core` is used, so the ring works in a `no_std` kernel (e.g. in a `static`).
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+pub struct SpscRing<T, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    /// Next slot to pop (written by the consumer only).
+    head: AtomicUsize,
+    /// Next slot to push (written by the producer only).
+    tail: AtomicUsize,
+}
+
+// The producer and the consumer may live on different threads; items move between them.
+unsafe impl<T: Send, const N: usize> Sync for SpscRing<T, N> {}
+
+impl<T, const N: usize> SpscRing<T, N> {
+    /// # Panics
+    /// If `N` is not a power of two.
+    pub fn new() -> Self {
+        Self::with_start_index(0)
+    }
+
+    /// A ring whose counters start at `start` instead of 0 — lets tests exercise the wrap of
+    /// the counters at `usize::MAX` without pushing 2^64 items.
+    pub fn with_start_index(start: usize) -> Self {
+        assert!(N.is_power_of_two(), "capacity must be a power of two");
+        Self {
+            slots: core::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+            head: AtomicUsize::new(start),
+            tail: AtomicUsize::new(start),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of items (a snapshot when the other side is active).
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The two ends of the ring. Holding `&mut self` guarantees there is only one of each.
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        (Producer { ring: self }, Consumer { ring: self })
+    }
+}
+
+impl<T, const N: usize> Default for SpscRing<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for SpscRing<T, N> {
+    fn drop(&mut self) {
+        // Drop the items still queued: slots head..tail are initialized.
+        let tail = *self.tail.get_mut();
+        let mut head = *self.head.get_mut();
+        while head != tail {
+            unsafe { self.slots[head % N].get_mut().assume_init_drop() };
+            head = head.wrapping_add(1);
+        }
+    }
+}
+
+pub struct Producer<'a, T, const N: usize> {
+    ring: &'a SpscRing<T, N>,
+}
+
+pub struct Consumer<'a, T, const N: usize> {
+    ring: &'a SpscRing<T, N>,
+}
+
+impl<T, const N: usize> Producer<'_, T, N> {
+    /// Append `item`, or hand it back if the ring is full.
+    ///
+    /// 1. `tail` = own index (`Relaxed`), `head` = the consumer's (`Acquire`: the slot it
+    ///    freed must really be free before we overwrite it)
+    /// 2. Full if `tail - head == N` (wrapping): `Err(item)`
+    /// 3. Write `item` into `slots[tail % N]` (`(*slot.get()).write(item)`)
+    /// 4. Publish: store `tail + 1` (wrapping) with `Release`
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        let tail = self.ring.tail.load(Ordering::Relaxed);
+        let head = self.ring.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == N {
+            return Err(item);
+        }
+        unsafe { (*self.ring.slots[tail % N].get()).write(item) };
+        self.ring
+            .tail
+            .store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.ring.len() == N
+    }
+}
+
+impl<T, const N: usize> Consumer<'_, T, N> {
+    /// Remove the oldest item.
+    ///
+    /// 1. `head` = own index (`Relaxed`), `tail` = the producer's (`Acquire`: pairs with the
+    ///    `Release` in `push`, so the slot's contents are visible)
+    /// 2. Empty if `head == tail`
+    /// 3. Move the item out of `slots[head % N]` (`(*slot.get()).assume_init_read()`)
+    /// 4. Hand the slot back: store `head + 1` (wrapping) with `Release`
+    pub fn pop(&mut self) -> Option<T> {
+        let head = self.ring.head.load(Ordering::Relaxed);
+        let tail = self.ring.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let item = unsafe { (*self.ring.slots[head % N].get()).assume_init_read() };
+        self.ring
+            .head
+            .store(head.wrapping_add(1), Ordering::Release);
+        Some(item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
@@ -0,0 +1,290 @@
+//! # CPU Affinity
+//!
+//! In this exercise, you pin threads to CPUs through raw syscalls (`syscall6` from
+//! `02_no_std_dev/04_syscall_wrapper`, finish that first). Linux only.
+//!
+//! Every thread has an *affinity mask*: the set of CPUs the scheduler may run it on. By default
+//! it is every online CPU. Narrowing it to one CPU pins the thread: it stays on that CPU's caches
+//! and runqueue, which is what per-CPU data structures and cache-line experiments rely on.
+//!
+//! ## `cpu_set_t`
+//! A bitmap, CPU `n` is bit `n % 64` of word `n / 64`. glibc's `cpu_set_t` has room for 1024
+//! CPUs (128 bytes); `CpuSet` has the same layout.
+//!
+//! ## Calls (errors are `-errno`, as in the other syscall exercises)
+//! - `sched_setaffinity(pid, len, &mask)`: `pid == 0` is the calling *thread*. A mask with no
+//!   online CPU in it fails with `-EINVAL`.
+//! - `sched_getaffinity(pid, len, &mut mask)`: the raw syscall returns how many bytes of the mask
+//!   it wrote (not 0 like the libc wrapper), and leaves the rest of the buffer untouched: start
+//!   from an empty set.
+//! - `getcpu(&mut cpu, &mut node, NULL)`: the CPU the thread is running on right now. Unless the
+//!   thread is pinned, the answer may be stale by the time you read it.
+
+#[allow(unused_imports)]
+use syscall_wrapper::syscall6;
+
+#[cfg(target_arch = "x86_64")]
+mod nr {
+    pub const SCHED_SETAFFINITY: usize = 203;
+    pub const SCHED_GETAFFINITY: usize = 204;
+    pub const GETCPU: usize = 309;
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod nr {
+    pub const SCHED_SETAFFINITY: usize = 122;
+    pub const SCHED_GETAFFINITY: usize = 123;
+    pub const GETCPU: usize = 168;
+}
+
+pub use nr::*;
+
+pub const EINVAL: isize = 22;
+
+/// Largest CPU count a `CpuSet` can describe.
+pub const CPU_SETSIZE: usize = 1024;
+
+/// A set of CPUs, laid out like `cpu_set_t`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuSet {
+    bits: [u64; CPU_SETSIZE / 64],
+}
+
+impl Default for CpuSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuSet {
+    /// The empty set.
+    pub const fn new() -> Self {
+        Self {
+            bits: [0; CPU_SETSIZE / 64],
+        }
+    }
+
+    /// The set holding just `cpu`.
+    pub fn single(cpu: usize) -> Self {
+        let mut set = Self::new();
+        set.insert(cpu);
+        set
+    }
+
+    /// Add `cpu` (`CPU_SET`). Panics if `cpu >= CPU_SETSIZE`.
+    pub fn insert(&mut self, cpu: usize) {
+        // TODO: Set bit cpu % 64 of word cpu / 64
+        todo!()
+    }
+
+    /// Remove `cpu` (`CPU_CLR`); out of range CPUs are never in the set.
+    pub fn remove(&mut self, cpu: usize) {
+        // TODO: Clear the bit (ignore cpu >= CPU_SETSIZE)
+        todo!()
+    }
+
+    /// `CPU_ISSET`
+    pub fn contains(&self, cpu: usize) -> bool {
+        // TODO: Test the bit (false for cpu >= CPU_SETSIZE)
+        todo!()
+    }
+
+    /// `CPU_COUNT`
+    pub fn count(&self) -> usize {
+        // TODO: Sum count_ones() over the words
+        todo!()
+    }
+
+    /// The CPUs in the set, ascending.
+    pub fn cpus(&self) -> Vec<usize> {
+        (0..CPU_SETSIZE).filter(|&cpu| self.contains(cpu)).collect()
+    }
+}
+
+impl FromIterator<usize> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for cpu in iter {
+            set.insert(cpu);
+        }
+        set
+    }
+}
+
+/// Result of a raw syscall: negative values are `-errno`.
+#[allow(dead_code)]
+fn check(ret: isize) -> Result<usize, isize> {
+    if ret < 0 {
+        Err(ret)
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+/// The calling thread's affinity mask.
+pub fn get_affinity() -> Result<CpuSet, isize> {
+    // TODO: sched_getaffinity(0, size_of::<CpuSet>(), &mut set) into an empty set
+    todo!()
+}
+
+/// Restrict the calling thread to the CPUs in `set`.
+pub fn set_affinity(set: &CpuSet) -> Result<(), isize> {
+    // TODO: sched_setaffinity(0, size_of::<CpuSet>(), set)
+    todo!()
+}
+
+/// Pin the calling thread to CPU `cpu` alone.
+pub fn pin_to_cpu(cpu: usize) -> Result<(), isize> {
+    // TODO: set_affinity with a set holding only cpu
+    todo!()
+}
+
+/// The CPU the calling thread is running on (`getcpu`).
+pub fn current_cpu() -> Result<usize, isize> {
+    // TODO: getcpu(&mut cpu, &mut node, 0) with two u32 out-parameters
+    todo!()
+}
+
+/// Run `f` with the calling thread pinned to `cpu`, then restore the mask it had before. If
+/// pinning fails, `f` does not run.
+pub fn run_pinned<R>(cpu: usize, f: impl FnOnce() -> R) -> Result<R, isize> {
+    // TODO: Save get_affinity(), pin, run f, restore the saved mask
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_cpu_set_bits",
+            "CPU n is bit n % 64 of word n / 64; shift a u64 (1u64 << ...), not an i32",
+        ),
+        (
+            "test_cpu_set_layout",
+            "CpuSet must stay #[repr(C)] with exactly 128 bytes of bitmap, like cpu_set_t",
+        ),
+        (
+            "test_get_affinity_contains_current_cpu",
+            "sched_getaffinity(0, size_of::<CpuSet>(), &mut set): start from an empty set, and a positive return value is a byte count, not an error",
+        ),
+        (
+            "test_pinned_thread_stays_on_cpu",
+            "sched_setaffinity takes the mask *length in bytes* as the second argument and a pointer to the mask as the third",
+        ),
+        (
+            "test_pin_to_invalid_cpu_fails",
+            "return the negative syscall result as Err(-errno); do not unwrap or ignore it",
+        ),
+        (
+            "test_run_pinned_restores_mask",
+            "save get_affinity() before pinning and set_affinity(&saved) after f returns",
+        ),
+    ];
+
+    #[test]
+    fn test_cpu_set_bits() {
+        let mut set = CpuSet::new();
+        assert_eq!(set.count(), 0);
+        for cpu in [0, 1, 63, 64, 200, CPU_SETSIZE - 1] {
+            set.insert(cpu);
+            assert_with_hint!(set.contains(cpu), "cpu {cpu}");
+        }
+        assert_eq_with_hint!(set.count(), 6);
+        assert!(!set.contains(2) && !set.contains(65) && !set.contains(CPU_SETSIZE));
+        set.remove(64);
+        set.remove(CPU_SETSIZE + 5);
+        assert_eq_with_hint!(set.cpus(), vec![0, 1, 63, 200, CPU_SETSIZE - 1]);
+        assert_eq!([3, 5].into_iter().collect::<CpuSet>().cpus(), vec![3, 5]);
+    }
+
+    #[test]
+    fn test_cpu_set_layout() {
+        assert_eq_with_hint!(std::mem::size_of::<CpuSet>(), 128);
+        let set = CpuSet::single(65);
+        let words: [u64; 16] = unsafe { std::mem::transmute(set) };
+        assert_eq_with_hint!(words[1], 0b10);
+        assert!(words.iter().enumerate().all(|(i, &w)| i == 1 || w == 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cpu_set_insert_out_of_range() {
+        CpuSet::new().insert(CPU_SETSIZE);
+    }
+
+    #[cfg(all(target_os = "linux", not(miri)))]
+    mod linux_tests {
+        use super::*;
+        use std::thread;
+
+        #[test]
+        fn test_get_affinity_contains_current_cpu() {
+            let mask = get_affinity().unwrap();
+            assert_with_hint!(mask.count() >= 1, "empty affinity mask");
+            let cpu = current_cpu().unwrap();
+            assert_with_hint!(mask.contains(cpu), "running on cpu {cpu}, outside {mask:?}");
+        }
+
+        #[test]
+        fn test_pinned_thread_stays_on_cpu() {
+            // Each test runs on its own thread, so pinning here leaves the other tests alone.
+            for cpu in get_affinity().unwrap().cpus() {
+                pin_to_cpu(cpu).unwrap();
+                assert_eq_with_hint!(get_affinity().unwrap(), CpuSet::single(cpu));
+                for _ in 0..100 {
+                    thread::yield_now();
+                    assert_eq_with_hint!(current_cpu().unwrap(), cpu);
+                }
+            }
+        }
+
+        #[test]
+        fn test_pin_to_invalid_cpu_fails() {
+            let before = get_affinity().unwrap();
+            assert_eq_with_hint!(pin_to_cpu(CPU_SETSIZE - 1), Err(-EINVAL));
+            assert_eq_with_hint!(set_affinity(&CpuSet::new()), Err(-EINVAL));
+            assert_eq!(
+                get_affinity().unwrap(),
+                before,
+                "a failed call changed the mask"
+            );
+        }
+
+        #[test]
+        fn test_affinity_is_per_thread() {
+            let before = get_affinity().unwrap();
+            let cpu = before.cpus()[0];
+            let child = thread::spawn(move || {
+                pin_to_cpu(cpu).unwrap();
+                get_affinity().unwrap()
+            })
+            .join()
+            .unwrap();
+            assert_eq!(child, CpuSet::single(cpu));
+            assert_eq!(get_affinity().unwrap(), before);
+        }
+
+        #[test]
+        fn test_run_pinned_restores_mask() {
+            let before = get_affinity().unwrap();
+            let cpu = *before.cpus().last().unwrap();
+            let seen = run_pinned(cpu, || (get_affinity().unwrap(), current_cpu().unwrap()));
+            assert_eq!(seen, Ok((CpuSet::single(cpu), cpu)));
+            assert_eq_with_hint!(get_affinity().unwrap(), before);
+            let mut ran = false;
+            assert_eq!(run_pinned(CPU_SETSIZE - 1, || ran = true), Err(-EINVAL));
+            assert!(!ran);
+        }
+    }
+}
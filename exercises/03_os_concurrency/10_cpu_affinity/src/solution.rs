@@ -0,0 +1,194 @@
+//! # CPU Affinity
+//!
+//! In this exercise, you pin threads to CPUs through raw syscalls (`syscall6` from
+//! `02_no_std_dev/04_syscall_wrapper`, finish that first). Linux only.
+//!
+//! Every thread has an *affinity mask*: the set of CPUs the scheduler may run it on. By default
+//! it is every online CPU. Narrowing it to one CPU pins the thread: it stays on that CPU's caches
+//! and runqueue, which is what per-CPU data structures and cache-line experiments rely on.
+//!
+//! ## `cpu_set_t`
+//! A bitmap, CPU `n` is bit `n % 64` of word `n / 64`. glibc's `cpu_set_t` has room for 1024
+//! CPUs (128 bytes); `CpuSet` has the same layout.
+//!
+//! ## Calls (errors are `-errno`, as in the other syscall exercises)
+//! - `sched_setaffinity(pid, len, &mask)`: `pid == 0` is the calling *thread*. A mask with no
+//!   online CPU in it fails with `-EINVAL`.
+//! - `sched_getaffinity(pid, len, &mut mask)`: the raw syscall returns how many bytes of the mask
+//!   it wrote (not 0 like the libc wrapper), and leaves the rest of the buffer untouched: start
+//!   from an empty set.
+//! - `getcpu(&mut cpu, &mut node, NULL)`: the CPU the thread is running on right now. Unless the
+//!   thread is pinned, the answer may be stale by the time you read it.
+
+use syscall_wrapper::solution::syscall6;
+
+#[cfg(target_arch = "x86_64")]
+mod nr {
+    pub const SCHED_SETAFFINITY: usize = 203;
+    pub const SCHED_GETAFFINITY: usize = 204;
+    pub const GETCPU: usize = 309;
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod nr {
+    pub const SCHED_SETAFFINITY: usize = 122;
+    pub const SCHED_GETAFFINITY: usize = 123;
+    pub const GETCPU: usize = 168;
+}
+
+pub use nr::*;
+
+pub const EINVAL: isize = 22;
+
+/// Largest CPU count a `CpuSet` can describe.
+pub const CPU_SETSIZE: usize = 1024;
+
+/// A set of CPUs, laid out like `cpu_set_t`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuSet {
+    bits: [u64; CPU_SETSIZE / 64],
+}
+
+impl Default for CpuSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuSet {
+    /// The empty set.
+    pub const fn new() -> Self {
+        Self {
+            bits: [0; CPU_SETSIZE / 64],
+        }
+    }
+
+    /// The set holding just `cpu`.
+    pub fn single(cpu: usize) -> Self {
+        let mut set = Self::new();
+        set.insert(cpu);
+        set
+    }
+
+    /// Add `cpu` (`CPU_SET`). Panics if `cpu >= CPU_SETSIZE`.
+    pub fn insert(&mut self, cpu: usize) {
+        assert!(cpu < CPU_SETSIZE, "cpu {cpu} out of range");
+        self.bits[cpu / 64] |= 1 << (cpu % 64);
+    }
+
+    /// Remove `cpu` (`CPU_CLR`); out of range CPUs are never in the set.
+    pub fn remove(&mut self, cpu: usize) {
+        if cpu < CPU_SETSIZE {
+            self.bits[cpu / 64] &= !(1 << (cpu % 64));
+        }
+    }
+
+    /// `CPU_ISSET`
+    pub fn contains(&self, cpu: usize) -> bool {
+        cpu < CPU_SETSIZE && self.bits[cpu / 64] & (1 << (cpu % 64)) != 0
+    }
+
+    /// `CPU_COUNT`
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// The CPUs in the set, ascending.
+    pub fn cpus(&self) -> Vec<usize> {
+        (0..CPU_SETSIZE).filter(|&cpu| self.contains(cpu)).collect()
+    }
+}
+
+impl FromIterator<usize> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for cpu in iter {
+            set.insert(cpu);
+        }
+        set
+    }
+}
+
+/// Result of a raw syscall: negative values are `-errno`.
+fn check(ret: isize) -> Result<usize, isize> {
+    if ret < 0 {
+        Err(ret)
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+/// The calling thread's affinity mask.
+pub fn get_affinity() -> Result<CpuSet, isize> {
+    let mut set = CpuSet::new();
+    let ret = unsafe {
+        syscall6(
+            SCHED_GETAFFINITY,
+            [
+                0,
+                core::mem::size_of::<CpuSet>(),
+                &mut set as *mut CpuSet as usize,
+                0,
+                0,
+                0,
+            ],
+        )
+    };
+    check(ret)?;
+    Ok(set)
+}
+
+/// Restrict the calling thread to the CPUs in `set`.
+pub fn set_affinity(set: &CpuSet) -> Result<(), isize> {
+    let ret = unsafe {
+        syscall6(
+            SCHED_SETAFFINITY,
+            [
+                0,
+                core::mem::size_of::<CpuSet>(),
+                set as *const CpuSet as usize,
+                0,
+                0,
+                0,
+            ],
+        )
+    };
+    check(ret).map(drop)
+}
+
+/// Pin the calling thread to CPU `cpu` alone.
+pub fn pin_to_cpu(cpu: usize) -> Result<(), isize> {
+    set_affinity(&CpuSet::single(cpu))
+}
+
+/// The CPU the calling thread is running on (`getcpu`).
+pub fn current_cpu() -> Result<usize, isize> {
+    let mut cpu: u32 = 0;
+    let mut node: u32 = 0;
+    let ret = unsafe {
+        syscall6(
+            GETCPU,
+            [
+                &mut cpu as *mut u32 as usize,
+                &mut node as *mut u32 as usize,
+                0,
+                0,
+                0,
+                0,
+            ],
+        )
+    };
+    check(ret)?;
+    Ok(cpu as usize)
+}
+
+/// Run `f` with the calling thread pinned to `cpu`, then restore the mask it had before. If
+/// pinning fails, `f` does not run.
+pub fn run_pinned<R>(cpu: usize, f: impl FnOnce() -> R) -> Result<R, isize> {
+    let saved = get_affinity()?;
+    pin_to_cpu(cpu)?;
+    let result = f();
+    set_affinity(&saved)?;
+    Ok(result)
+}
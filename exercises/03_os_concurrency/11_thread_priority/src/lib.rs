@@ -0,0 +1,262 @@
+//! # Thread Priority and Niceness
+//!
+//! In this exercise, you change how the Linux scheduler treats a thread, through raw syscalls
+//! (`syscall6` from `02_no_std_dev/04_syscall_wrapper`), and measure the effect with two
+//! threads pinned to one CPU (`10_cpu_affinity`; finish both first). Linux only.
+//!
+//! ## Niceness
+//! Normal threads (`SCHED_OTHER`) have a nice value from -20 (greedy) to 19 (polite). The
+//! scheduler splits a contended CPU in proportion to weights derived from it: each nice step
+//! is about 1.25x, so nice 0 against nice 10 is roughly 9:1. On Linux the nice value belongs to
+//! the *thread*, and `PRIO_PROCESS` with `who == 0` means the calling thread.
+//!
+//! - `setpriority(PRIO_PROCESS, 0, nice)`: the kernel clamps `nice` into -20..=19. Raising it is
+//!   always allowed; lowering it needs `CAP_SYS_NICE` (or `RLIMIT_NICE`), else `-EACCES`.
+//! - `getpriority(PRIO_PROCESS, 0)`: the raw syscall returns `20 - nice` (1..=40), so a valid
+//!   result is never negative and cannot be mistaken for `-errno`. The libc wrapper undoes this.
+//!
+//! ## Real-time
+//! `sched_setscheduler(0, SCHED_FIFO, &sched_param { priority })` (priority 1..=99) puts the
+//! thread above every normal thread: it runs until it blocks or yields. Unprivileged callers get
+//! `-EPERM`, which is the expected outcome here and must come back as an error, not a panic.
+//! `SCHED_OTHER` with priority 0 goes back to normal scheduling.
+
+#[allow(unused_imports)]
+use cpu_affinity::{get_affinity, pin_to_cpu};
+#[allow(unused_imports)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[allow(unused_imports)]
+use std::sync::Arc;
+#[allow(unused_imports)]
+use std::thread;
+use std::time::Duration;
+use syscall_wrapper::syscall6;
+
+#[cfg(target_arch = "x86_64")]
+mod nr {
+    pub const GETPRIORITY: usize = 140;
+    pub const SETPRIORITY: usize = 141;
+    pub const SCHED_SETSCHEDULER: usize = 144;
+    pub const SCHED_GETSCHEDULER: usize = 145;
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod nr {
+    pub const SCHED_SETSCHEDULER: usize = 119;
+    pub const SCHED_GETSCHEDULER: usize = 120;
+    pub const SETPRIORITY: usize = 140;
+    pub const GETPRIORITY: usize = 141;
+}
+
+pub use nr::*;
+
+pub const PRIO_PROCESS: usize = 0;
+pub const SCHED_OTHER: usize = 0;
+pub const SCHED_FIFO: usize = 1;
+
+pub const EPERM: isize = 1;
+pub const EACCES: isize = 13;
+pub const EINVAL: isize = 22;
+
+/// `struct sched_param`
+#[repr(C)]
+#[allow(dead_code)]
+struct SchedParam {
+    sched_priority: i32,
+}
+
+/// Result of a raw syscall: negative values are `-errno`.
+fn check(ret: isize) -> Result<usize, isize> {
+    if ret < 0 {
+        Err(ret)
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+/// The calling thread's nice value.
+pub fn get_nice() -> Result<i32, isize> {
+    // TODO: getpriority(PRIO_PROCESS, 0) returns 20 - nice
+    todo!()
+}
+
+/// Set the calling thread's nice value.
+pub fn set_nice(nice: i32) -> Result<(), isize> {
+    // TODO: setpriority(PRIO_PROCESS, 0, nice)
+    todo!()
+}
+
+/// The calling thread's scheduling policy (`SCHED_OTHER`, `SCHED_FIFO`, ...).
+pub fn get_policy() -> Result<usize, isize> {
+    check(unsafe { syscall6(SCHED_GETSCHEDULER, [0; 6]) })
+}
+
+fn set_scheduler(policy: usize, priority: i32) -> Result<(), isize> {
+    // TODO: sched_setscheduler(0, policy, &SchedParam { sched_priority: priority })
+    todo!()
+}
+
+/// Try to make the calling thread `SCHED_FIFO` at `priority`. Without privileges this fails
+/// with `-EPERM`; the thread's scheduling is then unchanged.
+pub fn try_set_fifo(priority: i32) -> Result<(), isize> {
+    set_scheduler(SCHED_FIFO, priority)
+}
+
+/// Back to `SCHED_OTHER` (priority 0). Always allowed.
+pub fn set_normal() -> Result<(), isize> {
+    set_scheduler(SCHED_OTHER, 0)
+}
+
+/// Run two spinning threads on the same CPU for `duration`, one at `nice_a` and one at
+/// `nice_b`, and return how many loop iterations each managed.
+///
+/// 1. Pick the first CPU of the caller's affinity mask.
+/// 2. Each thread pins itself to it and sets its nice value, then counts until `stop` is set.
+///    It keeps going even if `pin_to_cpu` / `set_nice` failed, and returns that error at the end.
+/// 3. The caller sleeps `duration`, sets `stop`, and joins both.
+pub fn nice_race(nice_a: i32, nice_b: i32, duration: Duration) -> Result<(u64, u64), isize> {
+    // TODO: Two threads pinned to the first CPU of get_affinity(), at nice_a and nice_b,
+    // counting until a shared AtomicBool is set after `duration`
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(all(test, target_os = "linux", not(miri)))]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::thread;
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_nice_round_trip",
+            "the raw getpriority returns 20 - nice: convert it back instead of returning it as is",
+        ),
+        (
+            "test_nice_is_per_thread",
+            "pass who = 0 so setpriority changes the calling thread only",
+        ),
+        (
+            "test_lowering_nice_without_privilege",
+            "a negative setpriority result is -errno (-EACCES when not allowed): return it as Err",
+        ),
+        (
+            "test_fifo_degrades_gracefully",
+            "sched_setscheduler's third argument is a pointer to a #[repr(C)] sched_param, and -EPERM must come back as Err",
+        ),
+        (
+            "test_niceness_shifts_throughput",
+            "pin both spinners to the same CPU and call set_nice inside each spinning thread, not in the caller",
+        ),
+    ];
+
+    /// Run `f` on a fresh thread, so nice and policy changes die with it.
+    fn on_thread<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> R {
+        thread::spawn(f).join().unwrap()
+    }
+
+    #[test]
+    fn test_nice_round_trip() {
+        on_thread(|| {
+            let base = get_nice().unwrap();
+            assert_with_hint!((-20..=19).contains(&base), "nice {base} out of range");
+            let target = (base + 3).min(19);
+            set_nice(target).unwrap();
+            assert_eq_with_hint!(get_nice().unwrap(), target);
+        });
+    }
+
+    #[test]
+    fn test_nice_is_clamped() {
+        on_thread(|| {
+            set_nice(100).unwrap();
+            assert_eq!(get_nice().unwrap(), 19);
+        });
+    }
+
+    #[test]
+    fn test_nice_is_per_thread() {
+        let before = get_nice().unwrap();
+        let child = on_thread(move || {
+            set_nice((before + 5).min(19)).unwrap();
+            get_nice().unwrap()
+        });
+        assert_eq!(child, (before + 5).min(19));
+        assert_eq_with_hint!(get_nice().unwrap(), before);
+    }
+
+    #[test]
+    fn test_lowering_nice_without_privilege() {
+        on_thread(|| {
+            set_nice(19).unwrap();
+            match set_nice(0) {
+                // Privileged (CAP_SYS_NICE or a generous RLIMIT_NICE).
+                Ok(()) => assert_eq!(get_nice().unwrap(), 0),
+                Err(e) => {
+                    assert_eq_with_hint!(e, -EACCES);
+                    assert_eq!(get_nice().unwrap(), 19);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_fifo_degrades_gracefully() {
+        on_thread(|| {
+            assert_eq!(get_policy(), Ok(SCHED_OTHER));
+            match try_set_fifo(10) {
+                Ok(()) => {
+                    assert_eq_with_hint!(get_policy(), Ok(SCHED_FIFO));
+                    set_normal().unwrap();
+                    assert_eq!(get_policy(), Ok(SCHED_OTHER));
+                }
+                Err(e) => {
+                    assert_eq_with_hint!(e, -EPERM);
+                    assert_eq!(get_policy(), Ok(SCHED_OTHER));
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_fifo_rejects_bad_priority() {
+        // Checked before permissions, so every caller gets EINVAL.
+        on_thread(|| {
+            assert_eq!(try_set_fifo(0), Err(-EINVAL));
+            assert_eq!(try_set_fifo(100), Err(-EINVAL));
+        });
+    }
+
+    #[test]
+    fn test_niceness_shifts_throughput() {
+        let base = get_nice().unwrap();
+        if base > 9 {
+            eprintln!("skipping: already running at nice {base}");
+            return;
+        }
+        let (a, b) = nice_race(base, base + 10, Duration::from_millis(300)).unwrap();
+        // The weights are about 9:1; leave room for noise.
+        assert_with_hint!(
+            a > 3 * b,
+            "nice {base}: {a} iterations, nice {}: {b}",
+            base + 10
+        );
+    }
+
+    #[test]
+    fn test_equal_nice_is_fair() {
+        let base = get_nice().unwrap();
+        let (a, b) = nice_race(base, base, Duration::from_millis(300)).unwrap();
+        assert!(
+            a < 2 * b && b < 2 * a,
+            "{a} vs {b} iterations at equal nice"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! # Thread Priority and Niceness
+//!
+//! In this exercise, you change how the Linux scheduler treats a thread, through raw syscalls
+//! (`syscall6` from `02_no_std_dev/04_syscall_wrapper`), and measure the effect with two
+//! threads pinned to one CPU (`10_cpu_affinity`; finish both first). Linux only.
+//!
+//! ## Niceness
+//! Normal threads (`SCHED_OTHER`) have a nice value from -20 (greedy) to 19 (polite). The
+//! scheduler splits a contended CPU in proportion to weights derived from it: each nice step
+//! is about 1.25x, so nice 0 against nice 10 is roughly 9:1. On Linux the nice value belongs to
+//! the *thread*, and `PRIO_PROCESS` with `who == 0` means the calling thread.
+//!
+//! - `setpriority(PRIO_PROCESS, 0, nice)`: the kernel clamps `nice` into -20..=19. Raising it is
+//!   always allowed; lowering it needs `CAP_SYS_NICE` (or `RLIMIT_NICE`), else `-EACCES`.
+//! - `getpriority(PRIO_PROCESS, 0)`: the raw syscall returns `20 - nice` (1..=40), so a valid
+//!   result is never negative and cannot be mistaken for `-errno`. The libc wrapper undoes this.
+//!
+//! ## Real-time
+//! `sched_setscheduler(0, SCHED_FIFO, &sched_param { priority })` (priority 1..=99) puts the
+//! thread above every normal thread: it runs until it blocks or yields. Unprivileged callers get
+//! `-EPERM`, which is the expected outcome here and must come back as an error, not a panic.
+//! `SCHED_OTHER` with priority 0 goes back to normal scheduling.
+
+use cpu_affinity::solution::{get_affinity, pin_to_cpu};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use syscall_wrapper::solution::syscall6;
+
+#[cfg(target_arch = "x86_64")]
+mod nr {
+    pub const GETPRIORITY: usize = 140;
+    pub const SETPRIORITY: usize = 141;
+    pub const SCHED_SETSCHEDULER: usize = 144;
+    pub const SCHED_GETSCHEDULER: usize = 145;
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod nr {
+    pub const SCHED_SETSCHEDULER: usize = 119;
+    pub const SCHED_GETSCHEDULER: usize = 120;
+    pub const SETPRIORITY: usize = 140;
+    pub const GETPRIORITY: usize = 141;
+}
+
+pub use nr::*;
+
+pub const PRIO_PROCESS: usize = 0;
+pub const SCHED_OTHER: usize = 0;
+pub const SCHED_FIFO: usize = 1;
+
+pub const EPERM: isize = 1;
+pub const EACCES: isize = 13;
+pub const EINVAL: isize = 22;
+
+/// `struct sched_param`
+#[repr(C)]
+struct SchedParam {
+    sched_priority: i32,
+}
+
+/// Result of a raw syscall: negative values are `-errno`.
+fn check(ret: isize) -> Result<usize, isize> {
+    if ret < 0 {
+        Err(ret)
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+/// The calling thread's nice value.
+pub fn get_nice() -> Result<i32, isize> {
+    let ret = unsafe { syscall6(GETPRIORITY, [PRIO_PROCESS, 0, 0, 0, 0, 0]) };
+    Ok(20 - check(ret)? as i32)
+}
+
+/// Set the calling thread's nice value.
+pub fn set_nice(nice: i32) -> Result<(), isize> {
+    let ret = unsafe { syscall6(SETPRIORITY, [PRIO_PROCESS, 0, nice as usize, 0, 0, 0]) };
+    check(ret).map(drop)
+}
+
+/// The calling thread's scheduling policy (`SCHED_OTHER`, `SCHED_FIFO`, ...).
+pub fn get_policy() -> Result<usize, isize> {
+    check(unsafe { syscall6(SCHED_GETSCHEDULER, [0; 6]) })
+}
+
+fn set_scheduler(policy: usize, priority: i32) -> Result<(), isize> {
+    let param = SchedParam {
+        sched_priority: priority,
+    };
+    let ret = unsafe {
+        syscall6(
+            SCHED_SETSCHEDULER,
+            [0, policy, &param as *const SchedParam as usize, 0, 0, 0],
+        )
+    };
+    check(ret).map(drop)
+}
+
+/// Try to make the calling thread `SCHED_FIFO` at `priority`. Without privileges this fails
+/// with `-EPERM`; the thread's scheduling is then unchanged.
+pub fn try_set_fifo(priority: i32) -> Result<(), isize> {
+    set_scheduler(SCHED_FIFO, priority)
+}
+
+/// Back to `SCHED_OTHER` (priority 0). Always allowed.
+pub fn set_normal() -> Result<(), isize> {
+    set_scheduler(SCHED_OTHER, 0)
+}
+
+/// Run two spinning threads on the same CPU for `duration`, one at `nice_a` and one at
+/// `nice_b`, and return how many loop iterations each managed.
+///
+/// 1. Pick the first CPU of the caller's affinity mask.
+/// 2. Each thread pins itself to it and sets its nice value, then counts until `stop` is set.
+///    It keeps going even if `pin_to_cpu` / `set_nice` failed, and returns that error at the end.
+/// 3. The caller sleeps `duration`, sets `stop`, and joins both.
+pub fn nice_race(nice_a: i32, nice_b: i32, duration: Duration) -> Result<(u64, u64), isize> {
+    let cpu = get_affinity()?.cpus()[0];
+    let stop = Arc::new(AtomicBool::new(false));
+    let spawn = |nice: i32| {
+        let stop = stop.clone();
+        thread::spawn(move || -> Result<u64, isize> {
+            let setup = pin_to_cpu(cpu).and_then(|()| set_nice(nice));
+            let mut count = 0u64;
+            while !stop.load(Ordering::Relaxed) {
+                count += 1;
+            }
+            setup.map(|()| count)
+        })
+    };
+    let (a, b) = (spawn(nice_a), spawn(nice_b));
+    thread::sleep(duration);
+    stop.store(true, Ordering::Relaxed);
+    let (a, b) = (a.join().unwrap(), b.join().unwrap());
+    Ok((a?, b?))
+}
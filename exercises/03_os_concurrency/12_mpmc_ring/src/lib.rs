@@ -0,0 +1,480 @@
+//! # Lock-free MPMC Ring Buffer
+//!
+//! `09_spsc_ring` relied on each index having a single writer. With many producers and many
+//! consumers that no longer holds: two producers can read the same `tail` and both think they
+//! own the slot. In this exercise, you implement Dmitry Vyukov's bounded MPMC queue (the design
+//! behind crossbeam's `ArrayQueue`): still no lock, but every slot carries a *sequence number*
+//! saying whose turn it is.
+//!
+//! ## Concepts
+//! - Free-running `head` / `tail` counters as in the SPSC ring, slot `index % N`, `N` a power
+//!   of two (at least 2)
+//! - Slot `i` starts with `seq = i`. For the position `pos` of a slot:
+//!   - `seq == pos`: the slot is empty and waits for the producer of `pos`
+//!   - `seq == pos + 1`: it holds the item of `pos` and waits for its consumer
+//!   - after the pop, `seq = pos + N`: empty again, for the producer one lap later
+//! - Claiming a position is a `compare_exchange` on `tail` (or `head`); only the winner touches
+//!   the slot, and publishes it with a `Release` store of `seq`
+//! - Comparing `seq` with `pos` as a signed difference tells "my turn" (0), "someone got here
+//!   first, reload" (> 0) and "full" / "empty" (< 0) apart, even across the counters' wrap
+//! - `head` and `tail` sit on separate cache lines, so producers and consumers do not keep
+//!   stealing one line from each other (false sharing)
+//!
+//! Only `core` is used, so the ring works in a `no_std` kernel (e.g. in a `static`).
+
+#![cfg_attr(not(test), no_std)]
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A value alone on its 64-byte cache line.
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+struct Slot<T> {
+    /// Whose turn it is: `pos` (empty, for the producer of `pos`) or `pos + 1` (full, for the
+    /// consumer of `pos`).
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+pub struct MpmcRing<T, const N: usize> {
+    slots: [Slot<T>; N],
+    /// Next position to pop.
+    head: CachePadded<AtomicUsize>,
+    /// Next position to push.
+    tail: CachePadded<AtomicUsize>,
+}
+
+// Items move between threads; each slot is accessed by one thread at a time, as its `seq` says.
+unsafe impl<T: Send, const N: usize> Send for MpmcRing<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for MpmcRing<T, N> {}
+
+impl<T, const N: usize> MpmcRing<T, N> {
+    /// # Panics
+    /// If `N` is not a power of two, or is 1 (a full and an empty slot would look alike).
+    pub fn new() -> Self {
+        Self::with_start_index(0)
+    }
+
+    /// A ring whose counters start at `start` instead of 0 — lets tests exercise the wrap of
+    /// the counters at `usize::MAX` without pushing 2^64 items.
+    pub fn with_start_index(start: usize) -> Self {
+        // TODO: Check N, then build the slots and the counters
+        // Hints:
+        //   - assert!(N.is_power_of_two() && N >= 2, ...)
+        //   - slot i waits for the first position >= start that maps to it (pos % N == i):
+        //     seq = start + (i - start) % N, all wrapping
+        //   - head and tail both start at `start`
+        todo!()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Append `item`, or hand it back if the ring is full.
+    ///
+    /// Loop:
+    /// 1. `pos` = `tail` (`Relaxed`), `seq` = its slot's sequence number (`Acquire`: pairs with
+    ///    the consumer's `Release`, so the slot really is empty)
+    /// 2. `diff = seq - pos` (wrapping, as `isize`):
+    ///    - `0`: claim `pos` with `compare_exchange_weak(pos, pos + 1)` on `tail`. On success
+    ///      write the item and store `seq = pos + 1` with `Release`; on failure retry
+    ///    - `< 0`: the slot still holds the item of `pos - N`: full, `Err(item)`
+    ///    - `> 0`: another producer claimed `pos` already: retry
+    pub fn push(&self, item: T) -> Result<(), T> {
+        // TODO: Follow the steps above
+        todo!()
+    }
+
+    /// Remove the oldest item.
+    ///
+    /// Loop:
+    /// 1. `pos` = `head` (`Relaxed`), `seq` = its slot's sequence number (`Acquire`: pairs with
+    ///    the producer's `Release`, so the item is visible)
+    /// 2. `diff = seq - (pos + 1)` (wrapping, as `isize`):
+    ///    - `0`: claim `pos` on `head`. On success move the item out and store
+    ///      `seq = pos + N` with `Release`, handing the slot to the next lap's producer
+    ///    - `< 0`: the item of `pos` has not been pushed: empty, `None`
+    ///    - `> 0`: another consumer claimed `pos` already: retry
+    pub fn pop(&self) -> Option<T> {
+        // TODO: Follow the steps above
+        todo!()
+    }
+
+    /// Number of items (a snapshot while other threads are active).
+    pub fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.load(Ordering::SeqCst);
+            let head = self.head.load(Ordering::SeqCst);
+            // Only trust the pair if `tail` did not move while `head` was read.
+            if self.tail.load(Ordering::SeqCst) == tail {
+                return tail.wrapping_sub(head).min(N);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+}
+
+impl<T, const N: usize> Default for MpmcRing<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for MpmcRing<T, N> {
+    fn drop(&mut self) {
+        // No other thread can hold the ring any more: pop what is left.
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    #[cfg(feature = "solutions")]
+    use blocking_queue::solution::BlockingQueue;
+    #[cfg(not(feature = "solutions"))]
+    use blocking_queue::BlockingQueue;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_fifo_full_and_empty",
+            "slot i starts with seq = i; push is full when seq - pos < 0, pop is empty when seq - (pos + 1) < 0",
+        ),
+        (
+            "test_wrap_around_slots",
+            "after a pop store seq = pos + N, so the slot waits for the producer one lap later",
+        ),
+        (
+            "test_wrap_around_counters",
+            "compare seq and pos as seq.wrapping_sub(pos) as isize, never with < on the raw counters: they overflow usize",
+        ),
+        (
+            "test_racing_on_a_full_ring",
+            "claim a position with compare_exchange on tail before writing the slot; two producers must never both see diff == 0 and write",
+        ),
+        (
+            "test_racing_on_an_empty_ring",
+            "claim a position with compare_exchange on head before reading the slot, or two consumers take the same item",
+        ),
+        (
+            "test_mpmc_stress",
+            "publish the slot with a Release store of seq after writing (or reading) it, and load seq with Acquire",
+        ),
+        (
+            "test_throughput_vs_blocking_queue",
+            "push and pop must return Err / None at once when the ring is full / empty, not wait for a slot; only a lost compare_exchange is retried",
+        ),
+    ];
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Fewer items and rounds under Miri, which is orders of magnitude slower.
+    const fn scaled(n: usize) -> usize {
+        if cfg!(miri) {
+            n / 500
+        } else {
+            n
+        }
+    }
+
+    #[test]
+    fn test_fifo_full_and_empty() {
+        let ring: MpmcRing<u32, 4> = MpmcRing::new();
+        assert_eq_with_hint!(ring.pop(), None);
+        for i in 0..4 {
+            assert_eq_with_hint!(ring.push(i), Ok(()));
+        }
+        assert_with_hint!(ring.is_full());
+        assert_eq_with_hint!(ring.push(99), Err(99), "full: the item comes back");
+        assert_eq_with_hint!(ring.pop(), Some(0));
+        assert_eq_with_hint!(ring.push(4), Ok(()));
+        let rest: Vec<u32> = std::iter::from_fn(|| ring.pop()).collect();
+        assert_eq_with_hint!(rest, [1, 2, 3, 4]);
+        assert_with_hint!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_wrap_around_slots() {
+        let ring: MpmcRing<usize, 4> = MpmcRing::new();
+        let mut next = 0;
+        for round in 0..10 {
+            for k in 0..3 {
+                assert_eq_with_hint!(ring.push(round * 3 + k), Ok(()));
+            }
+            assert_eq_with_hint!(ring.len(), 3);
+            for _ in 0..3 {
+                assert_eq_with_hint!(ring.pop(), Some(next));
+                next += 1;
+            }
+        }
+        assert_eq_with_hint!(next, 30);
+    }
+
+    #[test]
+    fn test_wrap_around_counters() {
+        for start in [usize::MAX - 3, usize::MAX - 8, usize::MAX] {
+            let ring: MpmcRing<u8, 8> = MpmcRing::with_start_index(start);
+            for lap in 0..3 {
+                for i in 0..8 {
+                    assert_eq_with_hint!(ring.push(i), Ok(()), "start {start}, lap {lap}");
+                }
+                assert_with_hint!(ring.is_full(), "the length survives the counter wrap");
+                assert_eq_with_hint!(ring.push(8), Err(8));
+                for i in 0..8 {
+                    assert_eq_with_hint!(ring.pop(), Some(i), "start {start}, lap {lap}");
+                }
+                assert_eq_with_hint!(ring.pop(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_remaining_items() {
+        let marker = Rc::new(());
+        {
+            let ring: MpmcRing<Rc<()>, 4> = MpmcRing::new();
+            for _ in 0..3 {
+                ring.push(marker.clone()).unwrap();
+            }
+            drop(ring.pop());
+            assert_eq!(Rc::strong_count(&marker), 3);
+        }
+        assert_eq!(
+            Rc::strong_count(&marker),
+            1,
+            "queued items dropped with the ring"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn test_capacity_must_be_power_of_two() {
+        let _ring: MpmcRing<u8, 6> = MpmcRing::new();
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 2")]
+    fn test_capacity_one_is_rejected() {
+        // With one slot, "holds the item of pos" (seq = pos + 1) and "empty, for the producer of
+        // pos + 1" would be the same sequence number.
+        let _ring: MpmcRing<u8, 1> = MpmcRing::new();
+    }
+
+    /// `THREADS` threads each try one push into a ring with room for half of them, all at once:
+    /// exactly `N` must get in. Then they all try one pop: exactly `N` items come out, once each.
+    #[test]
+    fn test_racing_on_a_full_ring() {
+        run_with_timeout(TIMEOUT, || {
+            const THREADS: usize = 8;
+            for _ in 0..scaled(1000).max(2) {
+                let ring: MpmcRing<usize, 4> = MpmcRing::new();
+                let barrier = Barrier::new(THREADS);
+                let pushed = thread::scope(|s| {
+                    let handles: Vec<_> = (0..THREADS)
+                        .map(|t| {
+                            let (ring, barrier) = (&ring, &barrier);
+                            s.spawn(move || {
+                                barrier.wait();
+                                ring.push(t).is_ok()
+                            })
+                        })
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|h| h.join().unwrap())
+                        .filter(|&ok| ok)
+                        .count()
+                });
+                assert_eq_with_hint!(pushed, 4, "pushes that succeeded on a ring of 4");
+                assert_with_hint!(ring.is_full());
+            }
+        });
+    }
+
+    #[test]
+    fn test_racing_on_an_empty_ring() {
+        run_with_timeout(TIMEOUT, || {
+            const THREADS: usize = 8;
+            for _ in 0..scaled(1000).max(2) {
+                let ring: MpmcRing<usize, 4> = MpmcRing::new();
+                for i in 0..4 {
+                    ring.push(i).unwrap();
+                }
+                let barrier = Barrier::new(THREADS);
+                let mut popped: Vec<usize> = thread::scope(|s| {
+                    let handles: Vec<_> = (0..THREADS)
+                        .map(|_| {
+                            let (ring, barrier) = (&ring, &barrier);
+                            s.spawn(move || {
+                                barrier.wait();
+                                ring.pop()
+                            })
+                        })
+                        .collect();
+                    handles
+                        .into_iter()
+                        .filter_map(|h| h.join().unwrap())
+                        .collect()
+                });
+                popped.sort();
+                assert_eq_with_hint!(popped, [0, 1, 2, 3], "each item popped exactly once");
+                assert_with_hint!(ring.is_empty());
+            }
+        });
+    }
+
+    /// Run `PRODUCERS` threads pushing `(producer, seq)` and `CONSUMERS` threads popping through
+    /// `push` / `pop`, which retry on full / empty. Every item must arrive exactly once, and each
+    /// consumer must see each producer's items in order.
+    fn stress<P, Q>(count: usize, push: P, pop: Q) -> Duration
+    where
+        P: Fn((usize, usize)) + Sync,
+        Q: Fn() -> Option<(usize, usize)> + Sync,
+    {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        assert_eq!(count % PRODUCERS, 0, "count must split evenly");
+        let per_producer = count / PRODUCERS;
+        let start = Instant::now();
+        let received: Vec<Vec<(usize, usize)>> = thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                let push = &push;
+                s.spawn(move || (0..per_producer).for_each(|i| push((p, i))));
+            }
+            let consumers: Vec<_> = (0..CONSUMERS)
+                .map(|_| s.spawn(|| std::iter::from_fn(&pop).collect::<Vec<_>>()))
+                .collect();
+            consumers.into_iter().map(|c| c.join().unwrap()).collect()
+        });
+        let elapsed = start.elapsed();
+
+        for items in &received {
+            for p in 0..PRODUCERS {
+                let seqs: Vec<usize> = items.iter().filter(|it| it.0 == p).map(|it| it.1).collect();
+                assert!(
+                    seqs.windows(2).all(|w| w[0] < w[1]),
+                    "a consumer saw producer {p}'s items out of order"
+                );
+            }
+        }
+        let mut all: Vec<(usize, usize)> = received.into_iter().flatten().collect();
+        all.sort();
+        let expected: Vec<(usize, usize)> = (0..PRODUCERS)
+            .flat_map(|p| (0..per_producer).map(move |i| (p, i)))
+            .collect();
+        assert!(all == expected, "items lost or duplicated");
+        elapsed
+    }
+
+    /// Stress the ring: producers spin while it is full. Consumers stop once they have seen
+    /// `count` items between them.
+    fn stress_ring(count: usize) -> Duration {
+        let ring: MpmcRing<(usize, usize), 64> = MpmcRing::new();
+        let taken = AtomicUsize::new(0);
+        stress(
+            count,
+            |mut item| {
+                while let Err(back) = ring.push(item) {
+                    item = back;
+                    thread::yield_now();
+                }
+            },
+            || {
+                // Reserve an item before waiting for it, so the consumers stop at `count`.
+                if taken.fetch_add(1, Ordering::Relaxed) >= count {
+                    return None;
+                }
+                loop {
+                    match ring.pop() {
+                        Some(item) => return Some(item),
+                        None => thread::yield_now(),
+                    }
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn test_mpmc_stress() {
+        run_with_timeout(TIMEOUT, || {
+            stress_ring(scaled(200_000));
+        });
+    }
+
+    /// The same workload through the Mutex + Condvar `BlockingQueue` of
+    /// `01_concurrency_sync/06_blocking_queue`, for comparison.
+    fn stress_blocking_queue(count: usize) -> Duration {
+        let queue = BlockingQueue::new(64);
+        let done = AtomicUsize::new(0);
+        stress(
+            count,
+            |item| {
+                queue.push(item).unwrap();
+                // The last producer closes the queue, which ends the consumers after the drain.
+                if done.fetch_add(1, Ordering::AcqRel) + 1 == count {
+                    queue.close();
+                }
+            },
+            || queue.pop(),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "timings under Miri say nothing about the real machine")]
+    fn test_throughput_vs_blocking_queue() {
+        run_with_timeout(TIMEOUT, || {
+            let count = 200_000;
+            // Take the best of a few runs to be robust against a noisy machine.
+            let best = |f: fn(usize) -> Duration| (0..3).map(|_| f(count)).min().unwrap();
+            let ring = best(stress_ring);
+            let blocking = best(stress_blocking_queue);
+
+            let rate = |d: Duration| count as f64 / d.as_secs_f64() / 1e6;
+            eprintln!(
+                "{count} items, 4 producers / 4 consumers: MpmcRing {ring:?} ({:.1} M items/s), \
+                 BlockingQueue {blocking:?} ({:.1} M items/s)",
+                rate(ring),
+                rate(blocking)
+            );
+            // The ring is typically several times faster; allow 2x the other way for a busy machine.
+            assert_with_hint!(
+                ring <= 2 * blocking,
+                "MpmcRing ({ring:?}) should not be slower than BlockingQueue ({blocking:?})"
+            );
+        });
+    }
+}
@@ -0,0 +1,199 @@
+//! # Lock-free MPMC Ring Buffer
+//!
+//! `09_spsc_ring` relied on each index having a single writer. With many producers and many
+//! consumers that no longer holds: two producers can read the same `tail` and both think they
+//! own the slot. In this exercise, you implement Dmitry Vyukov's bounded MPMC queue (the design
+//! behind crossbeam's `ArrayQueue`): still no lock, but every slot carries a *sequence number*
+//! saying whose turn it is.
+//!
+//! ## Concepts
+//! - Free-running `head` / `tail` counters as in the SPSC ring, slot `index % N`, `N` a power
+//!   of two (at least 2)
+//! - Slot `i` starts with `seq = i`. For the position `pos` of a slot:
+//!   - `seq == pos`: the slot is empty and waits for the producer of `pos`
+//!   - `seq == pos + 1`: it holds the item of `pos` and waits for its consumer
+//!   - after the pop, `seq = pos + N`: empty again, for the producer one lap later
+//! - Claiming a position is a `compare_exchange` on `tail` (or `head`); only the winner touches
+//!   the slot, and publishes it with a `Release` store of `seq`
+//! - Comparing `seq` with `pos` as a signed difference tells "my turn" (0), "someone got here
+//!   first, reload" (> 0) and "full" / "empty" (< 0) apart, even across the counters' wrap
+//! - `head` and `tail` sit on separate cache lines, so producers and consumers do not keep
+//!   stealing one line from each other (false sharing)
+//!
+//! Only `core` is used, so the ring works in a `no_std` kernel (e.g. in a `static`).
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A value alone on its 64-byte cache line.
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+struct Slot<T> {
+    /// Whose turn it is: `pos` (empty, for the producer of `pos`) or `pos + 1` (full, for the
+    /// consumer of `pos`).
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+pub struct MpmcRing<T, const N: usize> {
+    slots: [Slot<T>; N],
+    /// Next position to pop.
+    head: CachePadded<AtomicUsize>,
+    /// Next position to push.
+    tail: CachePadded<AtomicUsize>,
+}
+
+// Items move between threads; each slot is accessed by one thread at a time, as its `seq` says.
+unsafe impl<T: Send, const N: usize> Send for MpmcRing<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for MpmcRing<T, N> {}
+
+impl<T, const N: usize> MpmcRing<T, N> {
+    /// # Panics
+    /// If `N` is not a power of two, or is 1 (a full and an empty slot would look alike).
+    pub fn new() -> Self {
+        Self::with_start_index(0)
+    }
+
+    /// A ring whose counters start at `start` instead of 0 — lets tests exercise the wrap of
+    /// the counters at `usize::MAX` without pushing 2^64 items.
+    pub fn with_start_index(start: usize) -> Self {
+        assert!(
+            N.is_power_of_two() && N >= 2,
+            "capacity must be a power of two, at least 2"
+        );
+        Self {
+            // The slot of position `pos` is `pos % N`: give each slot the first position at or
+            // after `start` that maps to it.
+            slots: core::array::from_fn(|i| Slot {
+                seq: AtomicUsize::new(start.wrapping_add(i.wrapping_sub(start) % N)),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            }),
+            head: CachePadded(AtomicUsize::new(start)),
+            tail: CachePadded(AtomicUsize::new(start)),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Append `item`, or hand it back if the ring is full.
+    ///
+    /// Loop:
+    /// 1. `pos` = `tail` (`Relaxed`), `seq` = its slot's sequence number (`Acquire`: pairs with
+    ///    the consumer's `Release`, so the slot really is empty)
+    /// 2. `diff = seq - pos` (wrapping, as `isize`):
+    ///    - `0`: claim `pos` with `compare_exchange_weak(pos, pos + 1)` on `tail`. On success
+    ///      write the item and store `seq = pos + 1` with `Release`; on failure retry
+    ///    - `< 0`: the slot still holds the item of `pos - N`: full, `Err(item)`
+    ///    - `> 0`: another producer claimed `pos` already: retry
+    pub fn push(&self, item: T) -> Result<(), T> {
+        loop {
+            let pos = self.tail.load(Ordering::Relaxed);
+            let slot = &self.slots[pos % N];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos) as isize;
+            if diff == 0 {
+                if self
+                    .tail
+                    .compare_exchange_weak(
+                        pos,
+                        pos.wrapping_add(1),
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+                {
+                    unsafe { (*slot.value.get()).write(item) };
+                    slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                    return Ok(());
+                }
+            } else if diff < 0 {
+                return Err(item);
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Remove the oldest item.
+    ///
+    /// Loop:
+    /// 1. `pos` = `head` (`Relaxed`), `seq` = its slot's sequence number (`Acquire`: pairs with
+    ///    the producer's `Release`, so the item is visible)
+    /// 2. `diff = seq - (pos + 1)` (wrapping, as `isize`):
+    ///    - `0`: claim `pos` on `head`. On success move the item out and store
+    ///      `seq = pos + N` with `Release`, handing the slot to the next lap's producer
+    ///    - `< 0`: the item of `pos` has not been pushed: empty, `None`
+    ///    - `> 0`: another consumer claimed `pos` already: retry
+    pub fn pop(&self) -> Option<T> {
+        loop {
+            let pos = self.head.load(Ordering::Relaxed);
+            let slot = &self.slots[pos % N];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos.wrapping_add(1)) as isize;
+            if diff == 0 {
+                if self
+                    .head
+                    .compare_exchange_weak(
+                        pos,
+                        pos.wrapping_add(1),
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+                {
+                    let item = unsafe { (*slot.value.get()).assume_init_read() };
+                    slot.seq.store(pos.wrapping_add(N), Ordering::Release);
+                    return Some(item);
+                }
+            } else if diff < 0 {
+                return None;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Number of items (a snapshot while other threads are active).
+    pub fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.load(Ordering::SeqCst);
+            let head = self.head.load(Ordering::SeqCst);
+            // Only trust the pair if `tail` did not move while `head` was read.
+            if self.tail.load(Ordering::SeqCst) == tail {
+                return tail.wrapping_sub(head).min(N);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+}
+
+impl<T, const N: usize> Default for MpmcRing<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for MpmcRing<T, N> {
+    fn drop(&mut self) {
+        // No other thread can hold the ring any more: pop what is left.
+        while self.pop().is_some() {}
+    }
+}
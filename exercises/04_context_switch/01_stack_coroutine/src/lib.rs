@@ -3,6 +3,8 @@
 //! In this exercise, you implement the minimal context switch using inline assembly,
 //! which is the core mechanism of OS thread scheduling. This crate is **riscv64 only**;
 //! run `cargo test` on riscv64 Linux, or use the repo's normal flow (`./check.sh` / `oscamp`) on x86 with QEMU.
+//! The QEMU route needs a Linux host: on macOS or Windows, `cargo test` stops with a `compile_error!`
+//! pointing to a Linux container or the Codespace.
 //!
 //! ## Key Concepts
 //! - **Callee-saved registers**: Save and restore them on switch so the switched-away task can resume correctly later.
@@ -12,9 +14,18 @@
 //! ## riscv64 ABI (for this exercise)
 //! - Callee-saved: `sp`, `ra`, `s0`–`s11`. The `ret` instruction is `jalr zero, 0(ra)`.
 //! - First and second arguments: `a0` (old context), `a1` (new context).
+//!
+//! ## Passing an argument to the entry
+//! `switch_context` zeroes `a0` before `ret`, so a fresh context cannot start with its argument
+//! already in `a0`. `TaskContext::init_with_arg` parks `arg` in `s0` and `entry` in `s1` (callee-saved,
+//! so the switch restores them) and points `ra` at `entry_trampoline`, which moves `s0` into `a0` and
+//! calls `s1`. Entries get their state through `arg` (typically a pointer) instead of global statics.
+//! On x86_64 the same trick moves a callee-saved register into `rdi`.
 
 #![cfg(target_arch = "riscv64")]
 
+use core::arch::naked_asm;
+
 /// Saved register state for one task (riscv64). Layout must match the offsets used in the asm below: for one task (riscv64). Layout must match the offsets used in the asm below:
 /// `sp` at 0, `ra` at 8, then `s0`–`s11` at 16, 24, … 104.
 #[repr(C)]
@@ -64,6 +75,17 @@ impl TaskContext {
     pub fn init(&mut self, stack_top: usize, entry: usize) {
         todo!("set ra = entry, sp = stack_top (16-byte aligned)")
     }
+
+    /// Initialize this context so that the first switch to it calls `entry(arg)`, with `entry` an
+    /// `extern "C" fn(usize)`.
+    ///
+    /// - `init(stack_top, entry_trampoline)`: the first `ret` lands in the trampoline.
+    /// - `s0 = arg`, `s1 = entry`: the trampoline finds them there.
+    ///
+    /// `entry` must not return: switch away for good instead.
+    pub fn init_with_arg(&mut self, stack_top: usize, entry: usize, arg: usize) {
+        todo!("init(stack_top, entry_trampoline), then s0 = arg, s1 = entry")
+    }
 }
 
 /// Switch from `old` to `new` context: save current callee-saved regs into `old`, load from `new`, then `ret` (jumps to `new.ra`).
@@ -75,6 +97,13 @@ pub unsafe fn switch_context(old: &mut TaskContext, new: &TaskContext) {
     todo!("save callee-saved regs to old, load from new, then ret; use #[unsafe(naked)] + naked_asm!, see module doc for riscv64 ABI and layout")
 }
 
+/// First code run by a context set up with [`TaskContext::init_with_arg`]: call `s1` with `a0 = s0`.
+/// If the entry ever returns, `unimp` stops the program with an illegal instruction.
+#[unsafe(naked)]
+unsafe extern "C" fn entry_trampoline() {
+    naked_asm!("mv a0, s0", "jalr s1", "unimp");
+}
+
 const STACK_SIZE: usize = 1024 * 64;
 
 /// Allocate a stack for a coroutine. Returns `(buffer, stack_top)` where `stack_top` is the high address
@@ -83,10 +112,41 @@ pub fn alloc_stack() -> (Vec<u8>, usize) {
     todo!("allocate stack buffer, return (buffer, stack_top) with stack_top 16-byte aligned")
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
-    use std::sync::atomic::{AtomicU32, Ordering};
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+    const HINTS: Hints = &[
+        (
+            "test_alloc_stack",
+            "the stack grows down: stack_top is the end of the buffer (ptr + STACK_SIZE), rounded down to 16 bytes",
+        ),
+        (
+            "test_context_init",
+            "init sets ra = entry (where the first switch returns to) and sp = stack_top",
+        ),
+        (
+            "test_switch_to_task",
+            "switch_context must store sp, ra and s0-s11 of old at the offsets the TaskContext layout gives, load them from new, then ret",
+        ),
+        (
+            "test_init_with_arg",
+            "init_with_arg = init(stack_top, entry_trampoline) plus s0 = arg and s1 = entry; the trampoline moves s0 into a0",
+        ),
+        (
+            "test_switch_passes_arg",
+            "switch_context restores s0/s1 from the new context, so the trampoline sees the arg and entry init_with_arg stored there",
+        ),
+    ];
 
     static COUNTER: AtomicU32 = AtomicU32::new(0);
 
@@ -100,8 +160,8 @@ mod tests {
     #[test]
     fn test_alloc_stack() {
         let (buf, top) = alloc_stack();
-        assert_eq!(top, buf.as_ptr() as usize + STACK_SIZE);
-        assert!(top % 16 == 0);
+        assert_eq_with_hint!(top, buf.as_ptr() as usize + STACK_SIZE);
+        assert_with_hint!(top % 16 == 0);
     }
 
     #[test]
@@ -111,8 +171,8 @@ mod tests {
         let mut ctx = TaskContext::empty();
         let entry = task_entry as *const () as usize;
         ctx.init(top, entry);
-        assert_eq!(ctx.ra, entry as u64);
-        assert!(ctx.sp != 0);
+        assert_eq_with_hint!(ctx.ra, entry as u64);
+        assert_with_hint!(ctx.sp != 0);
     }
 
     #[test]
@@ -140,6 +200,52 @@ mod tests {
             switch_context(&mut main_ctx, &task_ctx);
         }
 
-        assert_eq!(COUNTER.load(Ordering::SeqCst), 99);
+        assert_eq_with_hint!(COUNTER.load(Ordering::SeqCst), 99);
+    }
+
+    #[test]
+    fn test_init_with_arg() {
+        let (_stack_buf, top) = alloc_stack();
+        let mut ctx = TaskContext::empty();
+        ctx.init_with_arg(top, task_entry as *const () as usize, 0xfeed);
+        assert_eq_with_hint!(ctx.sp, (top & !15) as u64);
+        // The first switch goes through the trampoline, not straight to the entry.
+        assert_with_hint!(ctx.ra != 0 && ctx.ra != task_entry as *const () as u64);
+        assert_eq_with_hint!(ctx.s0, 0xfeed);
+        assert_eq_with_hint!(ctx.s1, task_entry as *const () as u64);
+    }
+
+    /// Everything a task needs, reached through its argument instead of statics.
+    struct ArgTask {
+        main: TaskContext,
+        task: TaskContext,
+        seen: AtomicUsize,
+    }
+
+    extern "C" fn arg_entry(arg: usize) {
+        let t = arg as *mut ArgTask;
+        unsafe {
+            (*t).seen.store(arg, Ordering::SeqCst);
+            switch_context(&mut (*t).task, &(*t).main);
+        }
+    }
+
+    #[test]
+    fn test_switch_passes_arg() {
+        for _ in 0..2 {
+            let (_stack_buf, stack_top) = alloc_stack();
+            let t = Box::into_raw(Box::new(ArgTask {
+                main: TaskContext::empty(),
+                task: TaskContext::empty(),
+                seen: AtomicUsize::new(0),
+            }));
+            unsafe {
+                (*t).task
+                    .init_with_arg(stack_top, arg_entry as *const () as usize, t as usize);
+                switch_context(&mut (*t).main, &(*t).task);
+                assert_eq_with_hint!((*t).seen.load(Ordering::SeqCst), t as usize);
+                drop(Box::from_raw(t));
+            }
+        }
     }
 }
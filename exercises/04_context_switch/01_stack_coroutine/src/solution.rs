@@ -0,0 +1,155 @@
+//! # Stackful Coroutine and Context Switch (riscv64)
+//!
+//! In this exercise, you implement the minimal context switch using inline assembly,
+//! which is the core mechanism of OS thread scheduling. This crate is **riscv64 only**;
+//! run `cargo test` on riscv64 Linux, or use the repo's normal flow (`./check.sh` / `oscamp`) on x86 with QEMU.
+//! The QEMU route needs a Linux host: on macOS or Windows, `cargo test` stops with a `compile_error!`
+//! pointing to a Linux container or the Codespace.
+//!
+//! ## Key Concepts
+//! - **Callee-saved registers**: Save and restore them on switch so the switched-away task can resume correctly later.
+//! - **Stack pointer `sp`** and **return address `ra`**: Restore them in the new context; the first time we switch to a task, `ret` jumps to `ra` (the entry point).
+//! - Inline assembly: `core::arch::asm!`
+//!
+//! ## riscv64 ABI (for this exercise)
+//! - Callee-saved: `sp`, `ra`, `s0`–`s11`. The `ret` instruction is `jalr zero, 0(ra)`.
+//! - First and second arguments: `a0` (old context), `a1` (new context).
+//!
+//! ## Passing an argument to the entry
+//! `switch_context` zeroes `a0` before `ret`, so a fresh context cannot start with its argument
+//! already in `a0`. `TaskContext::init_with_arg` parks `arg` in `s0` and `entry` in `s1` (callee-saved,
+//! so the switch restores them) and points `ra` at `entry_trampoline`, which moves `s0` into `a0` and
+//! calls `s1`. Entries get their state through `arg` (typically a pointer) instead of global statics.
+//! On x86_64 the same trick moves a callee-saved register into `rdi`.
+
+#![cfg(target_arch = "riscv64")]
+
+use core::arch::naked_asm;
+
+/// Saved register state for one task (riscv64). Layout must match the offsets used in the asm below: for one task (riscv64). Layout must match the offsets used in the asm below:
+/// `sp` at 0, `ra` at 8, then `s0`–`s11` at 16, 24, … 104.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TaskContext {
+    pub sp: u64,
+    pub ra: u64,
+    pub s0: u64,
+    pub s1: u64,
+    pub s2: u64,
+    pub s3: u64,
+    pub s4: u64,
+    pub s5: u64,
+    pub s6: u64,
+    pub s7: u64,
+    pub s8: u64,
+    pub s9: u64,
+    pub s10: u64,
+    pub s11: u64,
+}
+
+impl TaskContext {
+    pub const fn empty() -> Self {
+        Self {
+            sp: 0,
+            ra: 0,
+            s0: 0,
+            s1: 0,
+            s2: 0,
+            s3: 0,
+            s4: 0,
+            s5: 0,
+            s6: 0,
+            s7: 0,
+            s8: 0,
+            s9: 0,
+            s10: 0,
+            s11: 0,
+        }
+    }
+
+    /// Initialize this context so that when we switch to it, execution starts at `entry`.
+    ///
+    /// - Set `ra = entry` so that the first `ret` in the new context jumps to `entry`.
+    /// - Set `sp = stack_top` with 16-byte alignment (RISC-V ABI requires 16-byte aligned stack at function entry).
+    /// - Leave `s0`–`s11` zero; they will be loaded on switch.
+    pub fn init(&mut self, stack_top: usize, entry: usize) {
+        self.ra = entry as u64;
+        self.sp = (stack_top & !15) as u64;
+    }
+
+    /// Initialize this context so that the first switch to it calls `entry(arg)`, with `entry` an
+    /// `extern "C" fn(usize)`.
+    ///
+    /// - `init(stack_top, entry_trampoline)`: the first `ret` lands in the trampoline.
+    /// - `s0 = arg`, `s1 = entry`: the trampoline finds them there.
+    ///
+    /// `entry` must not return: switch away for good instead.
+    pub fn init_with_arg(&mut self, stack_top: usize, entry: usize, arg: usize) {
+        self.init(stack_top, entry_trampoline as *const () as usize);
+        self.s0 = arg as u64;
+        self.s1 = entry as u64;
+    }
+}
+
+/// Switch from `old` to `new` context: save current callee-saved regs into `old`, load from `new`, then `ret` (jumps to `new.ra`).
+///
+/// In asm: store `sp`, `ra`, `s0`–`s11` to `[a0]` (old), load from `[a1]` (new), zero `a0`/`a1` so we do not leak pointers into the new context, then `ret`.
+///
+/// Must be `#[unsafe(naked)]` to prevent the compiler from generating a prologue/epilogue.
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or set up by [`TaskContext::init`],
+/// whose stack is still alive.
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "sd sp, 0(a0)",
+        "sd ra, 8(a0)",
+        "sd s0, 16(a0)",
+        "sd s1, 24(a0)",
+        "sd s2, 32(a0)",
+        "sd s3, 40(a0)",
+        "sd s4, 48(a0)",
+        "sd s5, 56(a0)",
+        "sd s6, 64(a0)",
+        "sd s7, 72(a0)",
+        "sd s8, 80(a0)",
+        "sd s9, 88(a0)",
+        "sd s10, 96(a0)",
+        "sd s11, 104(a0)",
+        "ld sp, 0(a1)",
+        "ld ra, 8(a1)",
+        "ld s0, 16(a1)",
+        "ld s1, 24(a1)",
+        "ld s2, 32(a1)",
+        "ld s3, 40(a1)",
+        "ld s4, 48(a1)",
+        "ld s5, 56(a1)",
+        "ld s6, 64(a1)",
+        "ld s7, 72(a1)",
+        "ld s8, 80(a1)",
+        "ld s9, 88(a1)",
+        "ld s10, 96(a1)",
+        "ld s11, 104(a1)",
+        "li a0, 0",
+        "li a1, 0",
+        "ret",
+    );
+}
+
+/// First code run by a context set up with [`TaskContext::init_with_arg`]: call `s1` with `a0 = s0`.
+/// If the entry ever returns, `unimp` stops the program with an illegal instruction.
+#[unsafe(naked)]
+unsafe extern "C" fn entry_trampoline() {
+    naked_asm!("mv a0, s0", "jalr s1", "unimp");
+}
+
+pub(crate) const STACK_SIZE: usize = 1024 * 64;
+
+/// Allocate a stack for a coroutine. Returns `(buffer, stack_top)` where `stack_top` is the high address
+/// (stack grows down). The buffer must be kept alive for the lifetime of the context using this stack.
+pub fn alloc_stack() -> (Vec<u8>, usize) {
+    let buf = vec![0u8; STACK_SIZE];
+    let top = buf.as_ptr() as usize + STACK_SIZE;
+    (buf, top)
+}
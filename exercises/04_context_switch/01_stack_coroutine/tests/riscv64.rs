@@ -0,0 +1,18 @@
+//! The exercise only builds for riscv64; on other hosts, run its tests there under QEMU.
+#![cfg(not(target_arch = "riscv64"))]
+
+#[cfg(not(target_os = "linux"))]
+compile_error!(
+    "stack_coroutine only builds for riscv64, and other hosts run its tests under QEMU user-mode \
+     emulation, which needs Linux. Use a Linux container or the Codespace."
+);
+
+#[test]
+fn riscv64_tests_under_qemu() {
+    let features: &[&str] = if cfg!(feature = "solutions") {
+        &["solutions"]
+    } else {
+        &[]
+    };
+    qemu_runner::check_package(env!("CARGO_PKG_NAME"), features);
+}
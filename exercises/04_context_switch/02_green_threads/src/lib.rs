@@ -1,27 +1,334 @@
-//! # Green Thread Scheduler (riscv64)
+//! # Green Thread Scheduler (riscv64, x86_64, aarch64)
 //!
 //! In this exercise, you build a simple cooperative (green) thread scheduler on top of context switching.
-//! This crate is **riscv64 only**; run with the repo's normal flow (`./check.sh` / `oscamp`) or natively on riscv64.
+//! The exercise targets **riscv64**; run with the repo's normal flow (`./check.sh` / `oscamp`) or natively on riscv64.
+//!
+//! ## Backends
+//! - **riscv64**: the hand-written `switch_context` below (same as `01_stack_coroutine`).
+//! - **x86_64** (Linux, macOS, Windows) and **aarch64** (Linux, macOS): the same idea in those
+//!   instruction sets, so `cargo test -p green_threads` also runs without a riscv64 toolchain.
+//!   Their `switch_context` pushes the callee-saved registers onto the stack it leaves and pops
+//!   them off the one it resumes, so `TaskContext` is just the saved stack pointer.
+//!
+//! Other targets stop the build with a `compile_error!`. On Windows, stacks come from
+//! `VirtualAlloc` instead of `mmap`, and waiting on file descriptors (`block_on_fd`) is not
+//! available.
 //!
 //! ## Key Concepts
 //! - Cooperative vs preemptive scheduling
-//! - Thread state: `Ready`, `Running`, `Finished`
+//! - Thread state: `Ready`, `Running`, `Blocked`, `Finished`
 //! - `yield_now()`: current thread voluntarily gives up the CPU
 //! - Scheduler loop: pick next ready thread and switch to it
+//! - Scheduling policies: round-robin, strict priority, weighted fair (stride) scheduling
+//! - Guard pages: an `mmap`ed stack with a `PROT_NONE` page below it turns overflow into a fault
+//! - Blocking primitives: a thread that cannot make progress parks itself (`Blocked`) and is woken by another thread
 //!
 //! ## Design
 //! Each green thread has its own stack and `TaskContext`. Threads call `yield_now()` to yield.
-//! The scheduler round-robins among ready threads. User entry is wrapped by `thread_wrapper`, which
-//! calls the entry then marks the thread `Finished` and switches back.
+//! Which ready thread runs next is decided by a `SchedPolicy` chosen when the scheduler is built
+//! (`Scheduler::new()` uses `RoundRobin`; `Scheduler::with_policy` takes any policy). Every thread has
+//! a priority (`spawn_with_priority`; higher number = more important, the main thread has priority 0).
+//! User entry is wrapped by `thread_wrapper`, which calls the entry then marks the thread `Finished`
+//! and switches back. `yield_now()` and the other free functions find the scheduler through a
+//! thread-local slot that `run` fills for its duration, so schedulers on different OS threads, or
+//! run one after another, never share state.
+//!
+//! Every context switch is counted in `SchedStats` and appended to a bounded trace of `SwitchEvent`s
+//! (`Scheduler::stats` / `Scheduler::trace`), so scheduling behaviour can be inspected after a run.
+//! Each event says why the old thread gave up the CPU (`SwitchReason`). To watch switches as they
+//! happen, `Scheduler::set_hook` installs a callback that gets every event, e.g. to print a timeline
+//! (`SwitchEvent` displays as `1 -> 2 (blocked)`).
+//!
+//! Stacks come from `mmap` rather than `Vec`: below the usable region sits one `PROT_NONE` guard page,
+//! so a thread that overflows its stack dies with `SIGSEGV` instead of scribbling over neighbouring
+//! memory. Fresh stacks are filled with `STACK_CANARY`; `Scheduler::stack_watermark` reports how deep a
+//! thread's stack ever got by finding the lowest byte that no longer holds the canary.
+//!
+//! Mapping a fresh stack for every `spawn` is expensive, so stacks of finished threads go back into a
+//! `StackPool` and are handed out again. Once every green thread has finished (i.e. between two `run`
+//! calls), the next `spawn` recycles all their stacks and thread ids start again from 1.
+//!
+//! `GreenChannel<T>` is a bounded channel whose `send`/`recv` block the calling green thread instead of
+//! spinning: the thread records its id in the channel's wait queue, calls `block_current()`, and is made
+//! `Ready` again by `wake(id)` when the other side frees a slot or pushes a value.
+//!
+//! ## Waiting for file descriptors
+//! A green thread must not block the OS thread in `read`/`write`: that would stop every other green
+//! thread too. Instead it puts the fd in `O_NONBLOCK` mode, and on `EAGAIN` calls
+//! `block_on_fd(fd, interest)`, which registers it with the scheduler's `Poller` (a tiny epoll on
+//! top of `poll(2)`) and blocks it. Before every scheduling decision the scheduler checks the
+//! registered fds without waiting and makes the threads whose fd became ready `Ready` again.
+//!
+//! ## Idle and deadlock
+//! When no green thread is `Ready`, `run` calls `idle()`: it runs the hook set with
+//! `Scheduler::set_idle_hook` (which may `wake` threads), then waits in `poll(2)` for as long as it
+//! takes if some thread waits on an fd, then, with the `timer` feature, skips virtual time ahead to
+//! the next sleeper. If after that still nothing is `Ready`, every live thread is `Blocked` with
+//! nobody left to wake it, and `run` returns `DeadlockDetected` listing them instead of spinning
+//! forever.
+//!
+//! ## Optional: timer-driven sleep (`--features timer`)
+//! With the `timer` feature the scheduler owns a `TimerDriver` from `07_trap_interrupt/02_timer_tick`
+//! and `sleep_ticks(n)` parks the current thread in the timer's wait list. Time is virtual: it only
+//! moves when no green thread is `Ready`, and then `idle()` skips straight to the next wake-up tick,
+//! so a test with long sleeps still finishes instantly. Run `cargo test -p green_threads --features timer`.
 
-#![cfg(target_arch = "riscv64")]
+#[cfg(not(any(
+    target_arch = "riscv64",
+    target_arch = "x86_64",
+    all(target_arch = "aarch64", not(windows))
+)))]
+compile_error!(
+    "green_threads has no context-switch backend for this target: it needs riscv64, x86_64, or \
+     aarch64 (not Windows)."
+);
 
 use core::arch::naked_asm;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+#[cfg(unix)]
+use std::os::fd::RawFd;
+use std::ptr::NonNull;
+use std::sync::Mutex;
 
 /// Per-thread stack size. Slightly larger to avoid overflow under QEMU / test harness.
 const STACK_SIZE: usize = 1024 * 128;
 
+/// Simulated CLINT cycles per timer tick (the `timer` feature counts ticks, so any value works).
+#[cfg(feature = "timer")]
+const TICK_CYCLES: u64 = 10_000;
+
+/// Byte written over every fresh stack; `Stack::watermark` looks for the first byte that changed.
+pub const STACK_CANARY: u8 = 0xC5;
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+/// Windows on x86_64 always uses 4 KiB pages.
+#[cfg(windows)]
+fn page_size() -> usize {
+    4096
+}
+
+/// The few `kernel32` functions behind Windows stacks (what `mmap` / `mprotect` / `munmap` do on
+/// unix).
+#[cfg(windows)]
+mod kernel32 {
+    use std::ffi::c_void;
+
+    pub const MEM_COMMIT: u32 = 0x1000;
+    pub const MEM_RESERVE: u32 = 0x2000;
+    pub const MEM_RELEASE: u32 = 0x8000;
+    pub const PAGE_NOACCESS: u32 = 0x01;
+    pub const PAGE_READWRITE: u32 = 0x04;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn VirtualAlloc(addr: *mut c_void, size: usize, kind: u32, protect: u32)
+            -> *mut c_void;
+        pub fn VirtualProtect(addr: *mut c_void, size: usize, protect: u32, old: *mut u32) -> i32;
+        pub fn VirtualFree(addr: *mut c_void, size: usize, kind: u32) -> i32;
+    }
+}
+
+/// A green thread stack: one `mmap` region (`VirtualAlloc` on Windows) laid out as
+///
+/// ```text
+/// base             base + page                                base + len
+/// | guard (PROT_NONE) | usable stack (RW, grows down) ...... | <- top()
+/// ```
+///
+/// Touching the guard page raises `SIGSEGV` (an access violation on Windows), so overflow is
+/// caught instead of corrupting memory.
+pub struct Stack {
+    /// Start of the mapping (the guard page).
+    base: *mut u8,
+    /// Length of the whole mapping, guard page included.
+    len: usize,
+}
+
+#[cfg(unix)]
+impl Stack {
+    /// Map a stack with at least `size` usable bytes plus a guard page.
+    ///
+    /// 1. Round `size` up to a multiple of `page_size()`; the mapping is one page longer.
+    /// 2. `libc::mmap` it `PROT_READ | PROT_WRITE`, `MAP_PRIVATE | MAP_ANONYMOUS`; on `MAP_FAILED`
+    ///    return `std::io::Error::last_os_error()`.
+    /// 3. `libc::mprotect` the lowest page to `PROT_NONE` (unmap and return the error on failure).
+    /// 4. Fill the usable part with `STACK_CANARY` so `watermark` can measure use later.
+    pub fn new(size: usize) -> std::io::Result<Self> {
+        todo!("mmap size rounded up to pages + one guard page, mprotect the lowest page PROT_NONE, fill the rest with STACK_CANARY")
+    }
+}
+
+#[cfg(windows)]
+impl Stack {
+    /// Same as the unix version, with `VirtualAlloc` for `mmap` (`MEM_RESERVE | MEM_COMMIT`,
+    /// `PAGE_READWRITE`) and `VirtualProtect(.., PAGE_NOACCESS, ..)` for `mprotect`.
+    pub fn new(size: usize) -> std::io::Result<Self> {
+        use kernel32::*;
+        let page = page_size();
+        let len = size.div_ceil(page) * page + page;
+        let base = unsafe {
+            VirtualAlloc(
+                std::ptr::null_mut(),
+                len,
+                MEM_RESERVE | MEM_COMMIT,
+                PAGE_READWRITE,
+            )
+        };
+        if base.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut old = 0;
+        if unsafe { VirtualProtect(base, page, PAGE_NOACCESS, &mut old) } == 0 {
+            let err = std::io::Error::last_os_error();
+            unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+            return Err(err);
+        }
+        let stack = Self {
+            base: base as *mut u8,
+            len,
+        };
+        unsafe {
+            std::ptr::write_bytes(stack.bottom() as *mut u8, STACK_CANARY, stack.size());
+        }
+        Ok(stack)
+    }
+}
+
+impl Stack {
+    /// Lowest usable address (just above the guard page).
+    pub fn bottom(&self) -> usize {
+        self.base as usize + page_size()
+    }
+
+    /// Highest address (exclusive); the initial `sp` is derived from it.
+    pub fn top(&self) -> usize {
+        self.base as usize + self.len
+    }
+
+    /// Number of usable bytes.
+    pub fn size(&self) -> usize {
+        self.top() - self.bottom()
+    }
+
+    /// Peak stack use in bytes: scan up from `bottom()` while bytes still equal `STACK_CANARY`;
+    /// everything from the first changed byte up to `top()` has been used at some point.
+    ///
+    /// This runs every time a stack is recycled, so skip whole chunks at once (compare
+    /// `chunks(4096)` against a canary-filled array) and only go byte by byte in the first chunk
+    /// that differs.
+    pub fn watermark(&self) -> usize {
+        todo!("count untouched STACK_CANARY bytes from bottom(), return size() - untouched")
+    }
+
+    /// Re-fill the used part (the top `watermark()` bytes) with `STACK_CANARY`, so a recycled
+    /// stack reports a watermark of 0 again without rewriting the whole stack.
+    pub fn clear_watermark(&self) {
+        let used = self.watermark();
+        unsafe {
+            std::ptr::write_bytes((self.top() - used) as *mut u8, STACK_CANARY, used);
+        }
+    }
+}
+
+/// Recycles stacks of finished threads so `spawn` does not `mmap` a new one every time.
+///
+/// At most `max_idle` unused stacks are kept; extra ones are unmapped (dropped) on `put`.
+pub struct StackPool {
+    free: Vec<Stack>,
+    max_idle: usize,
+    /// Stacks mapped with `Stack::new` so far.
+    allocated: usize,
+    /// `get` calls served from `free`.
+    reused: usize,
+    /// Stacks currently handed out.
+    in_use: usize,
+    /// Largest `in_use` ever seen.
+    high_water: usize,
+}
+
+impl StackPool {
+    /// Default for `max_idle`.
+    pub const DEFAULT_MAX_IDLE: usize = 64;
+
+    pub fn new(max_idle: usize) -> Self {
+        Self {
+            free: Vec::new(),
+            max_idle,
+            allocated: 0,
+            reused: 0,
+            in_use: 0,
+            high_water: 0,
+        }
+    }
+
+    /// Hand out a stack of `STACK_SIZE` bytes.
+    ///
+    /// 1. Pop one from `free` (count it in `reused`), or else `Stack::new(STACK_SIZE)` (count it in `allocated`).
+    /// 2. Increment `in_use` and raise `high_water` if needed.
+    pub fn get(&mut self) -> std::io::Result<Stack> {
+        todo!("pop from free (reused += 1) or Stack::new(STACK_SIZE) (allocated += 1), then in_use += 1 and update high_water")
+    }
+
+    /// Take back a stack that is no longer used by any thread.
+    ///
+    /// Decrement `in_use`. If fewer than `max_idle` stacks are idle, `clear_watermark()` it and push
+    /// it onto `free`; otherwise just drop it (which unmaps it).
+    pub fn put(&mut self, stack: Stack) {
+        todo!("in_use -= 1; keep the stack (after clear_watermark) if free.len() < max_idle, else drop it")
+    }
+
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+
+    pub fn in_use(&self) -> usize {
+        self.in_use
+    }
+
+    pub fn high_water(&self) -> usize {
+        self.high_water
+    }
+
+    /// Stacks waiting in the pool.
+    pub fn idle(&self) -> usize {
+        self.free.len()
+    }
+}
+
+impl Default for StackPool {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_IDLE)
+    }
+}
+
+impl Drop for Stack {
+    #[cfg(unix)]
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.base as *mut libc::c_void, self.len);
+        }
+    }
+
+    #[cfg(windows)]
+    fn drop(&mut self) {
+        unsafe {
+            kernel32::VirtualFree(self.base.cast(), 0, kernel32::MEM_RELEASE);
+        }
+    }
+}
+
 /// Task context (riscv64); layout must match `01_stack_coroutine::TaskContext` and the asm below.
+#[cfg(target_arch = "riscv64")]
 #[repr(C)]
 #[derive(Debug, Default, Clone)]
 pub struct TaskContext {
@@ -41,29 +348,41 @@ pub struct TaskContext {
     s11: u64,
 }
 
+/// Task context of the x86_64 / aarch64 backends: only the saved stack pointer. The registers
+/// live in a frame on the task's own stack (see `switch_context`), so the context may move.
+#[cfg(not(target_arch = "riscv64"))]
+#[repr(C)]
+#[derive(Debug, Default, Clone)]
+pub struct TaskContext {
+    sp: usize,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ThreadState {
     Ready,
     Running,
+    /// Waiting for an event (e.g. channel data); skipped by the scheduler until `wake` makes it `Ready`.
+    Blocked,
     Finished,
 }
 
+/// Default priority of threads created with `Scheduler::spawn`.
+pub const DEFAULT_PRIORITY: u8 = 1;
+
 struct GreenThread {
     ctx: TaskContext,
     state: ThreadState,
-    _stack: Option<Vec<u8>>,
-    /// User entry; taken once when the thread is first scheduled and passed to `thread_wrapper`.
+    priority: u8,
+    /// `None` for the main thread, which runs on the process stack.
+    stack: Option<Stack>,
+    /// User entry; taken by `thread_wrapper` when the thread runs for the first time.
     entry: Option<extern "C" fn()>,
 }
 
-/// Set by the scheduler before switching to a new thread; `thread_wrapper` reads and calls it once.
-static mut CURRENT_THREAD_ENTRY: Option<extern "C" fn()> = None;
-
-/// Wrapper run as the initial `ra` for each green thread: call the user entry (from `CURRENT_THREAD_ENTRY`), then mark Finished and switch back.
+/// Wrapper run as the initial `ra` for each green thread: take the thread's entry from the
+/// scheduler, call it, then mark the thread Finished and switch away.
 extern "C" fn thread_wrapper() {
-    let entry = unsafe { core::ptr::read(&raw const CURRENT_THREAD_ENTRY) };
-    if let Some(f) = entry {
-        unsafe { CURRENT_THREAD_ENTRY = None };
+    if let Some(f) = with_scheduler(|s| s.threads[s.current].entry.take()).flatten() {
         f();
     }
     thread_finished();
@@ -73,8 +392,13 @@ extern "C" fn thread_wrapper() {
 /// Zero `a0`/`a1` before `ret` so we don't leak pointers into the new context.
 ///
 /// Must be `#[unsafe(naked)]` to prevent the compiler from generating a prologue/epilogue.
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(target_arch = "riscv64")]
 #[unsafe(naked)]
-unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
     naked_asm!(
         "sd sp, 0(a0)",
         "sd ra, 8(a0)",
@@ -110,141 +434,1842 @@ unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext)
     );
 }
 
+#[cfg(target_arch = "riscv64")]
+impl TaskContext {
+    /// Context that starts executing `entry` on `stack` the first time it is switched to.
+    ///
+    /// Set `ra = entry` so the first `ret` in `switch_context` jumps to it; `sp` must be 16-byte
+    /// aligned (e.g. `(stack.top() - 16) & !15` to leave headroom). The `s` registers stay zero.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        todo!("ra = entry, sp = (stack.top() - 16) & !15, everything else zero")
+    }
+}
+
+/// `mxcsr` of a fresh x86_64 thread: every SSE exception masked, round to nearest.
+#[cfg(target_arch = "x86_64")]
+const MXCSR_DEFAULT: u32 = 0x1F80;
+
+/// x87 control word of a fresh x86_64 thread: every exception masked, 64-bit precision.
+#[cfg(target_arch = "x86_64")]
+const FPUCW_DEFAULT: u16 = 0x037F;
+
+/// x86_64 System V (Linux, macOS): push the callee-saved `rbp`, `rbx`, `r12`-`r15` and the
+/// `mxcsr` / x87 control words, save `rsp` into `old`, load `rsp` from `new`, pop the same frame
+/// and `ret` into `new`. The frame, from the saved `rsp` up:
+///
+/// ```text
+/// +0 mxcsr, +4 x87 cw | +8 r15 | r14 | r13 | r12 | rbx | +48 rbp | +56 return address
+/// ```
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "push rbp",
+        "push rbx",
+        "push r12",
+        "push r13",
+        "push r14",
+        "push r15",
+        "sub rsp, 8",
+        "stmxcsr [rsp]",
+        "fnstcw [rsp + 4]",
+        "mov [rdi], rsp",
+        "mov rsp, [rsi]",
+        "ldmxcsr [rsp]",
+        "fldcw [rsp + 4]",
+        "add rsp, 8",
+        "pop r15",
+        "pop r14",
+        "pop r13",
+        "pop r12",
+        "pop rbx",
+        "pop rbp",
+        "ret",
+    );
+}
+
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+impl TaskContext {
+    /// Context that starts executing `entry` on `stack` the first time it is switched to: a
+    /// `switch_context` frame with zeroed registers, default control words and `entry` as the
+    /// return address. The return address sits on a 16-byte boundary, so `entry` starts with
+    /// `rsp` 8 past one, as after a `call`; the fake return address above it is 0.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        let ret = (stack.top() - 64) & !15;
+        let sp = ret - 56;
+        unsafe {
+            std::ptr::write_bytes(sp as *mut u8, 0, ret + 16 - sp);
+            (sp as *mut u32).write(MXCSR_DEFAULT);
+            ((sp + 4) as *mut u16).write(FPUCW_DEFAULT);
+            (ret as *mut usize).write(entry as *const () as usize);
+        }
+        Self { sp }
+    }
+}
+
+/// x86_64 Windows: as on System V, plus the registers Windows also treats as callee-saved (`rdi`,
+/// `rsi`, `xmm6`-`xmm15`) and the stack bounds in the TEB (`gs:[0x08]` stack base, `gs:[0x10]`
+/// stack limit, `gs:[0x1478]` deallocation stack), which unwinding and stack probes check against
+/// `rsp`. Arguments come in `rcx` / `rdx`. The frame, from the saved `rsp` up:
+///
+/// ```text
+/// +0 xmm6 .. +144 xmm15 | +160 mxcsr, +164 x87 cw | +168 stack base | limit | dealloc
+/// | +192 r15 | r14 | r13 | r12 | rsi | rdi | rbx | +248 rbp | +256 return address
+/// ```
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(all(target_arch = "x86_64", windows))]
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "push rbp",
+        "push rbx",
+        "push rdi",
+        "push rsi",
+        "push r12",
+        "push r13",
+        "push r14",
+        "push r15",
+        "push qword ptr gs:[0x1478]",
+        "push qword ptr gs:[0x10]",
+        "push qword ptr gs:[0x08]",
+        "sub rsp, 168",
+        "movups [rsp], xmm6",
+        "movups [rsp + 16], xmm7",
+        "movups [rsp + 32], xmm8",
+        "movups [rsp + 48], xmm9",
+        "movups [rsp + 64], xmm10",
+        "movups [rsp + 80], xmm11",
+        "movups [rsp + 96], xmm12",
+        "movups [rsp + 112], xmm13",
+        "movups [rsp + 128], xmm14",
+        "movups [rsp + 144], xmm15",
+        "stmxcsr [rsp + 160]",
+        "fnstcw [rsp + 164]",
+        "mov [rcx], rsp",
+        "mov rsp, [rdx]",
+        "movups xmm6, [rsp]",
+        "movups xmm7, [rsp + 16]",
+        "movups xmm8, [rsp + 32]",
+        "movups xmm9, [rsp + 48]",
+        "movups xmm10, [rsp + 64]",
+        "movups xmm11, [rsp + 80]",
+        "movups xmm12, [rsp + 96]",
+        "movups xmm13, [rsp + 112]",
+        "movups xmm14, [rsp + 128]",
+        "movups xmm15, [rsp + 144]",
+        "ldmxcsr [rsp + 160]",
+        "fldcw [rsp + 164]",
+        "add rsp, 168",
+        "pop qword ptr gs:[0x08]",
+        "pop qword ptr gs:[0x10]",
+        "pop qword ptr gs:[0x1478]",
+        "pop r15",
+        "pop r14",
+        "pop r13",
+        "pop r12",
+        "pop rsi",
+        "pop rdi",
+        "pop rbx",
+        "pop rbp",
+        "ret",
+    );
+}
+
+#[cfg(all(target_arch = "x86_64", windows))]
+impl TaskContext {
+    /// Same as on System V with the larger Windows frame; the TEB slots get `stack`'s bounds. The
+    /// 64 bytes left free at the top cover the 32-byte shadow space `entry` may write above its
+    /// return address.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        let ret = (stack.top() - 64) & !15;
+        let sp = ret - 256;
+        unsafe {
+            std::ptr::write_bytes(sp as *mut u8, 0, ret + 16 - sp);
+            ((sp + 160) as *mut u32).write(MXCSR_DEFAULT);
+            ((sp + 164) as *mut u16).write(FPUCW_DEFAULT);
+            ((sp + 168) as *mut usize).write(stack.top());
+            ((sp + 176) as *mut usize).write(stack.bottom());
+            ((sp + 184) as *mut usize).write(stack.base as usize);
+            (ret as *mut usize).write(entry as *const () as usize);
+        }
+        Self { sp }
+    }
+}
+
+/// aarch64 (Linux, macOS): store the callee-saved `x19`-`x28`, `x29` (frame pointer), `x30`
+/// (return address) and `d8`-`d15` in a 0xa0-byte frame below `sp`, save `sp` into `old`, load
+/// `sp` from `new`, reload the same frame and `ret` to the `x30` found there. `x18` is left alone:
+/// macOS reserves it. The frame, from the saved `sp` up:
+///
+/// ```text
+/// +0x00 x19, x20 | .. | +0x40 x27, x28 | +0x50 x29, x30 | +0x60 d8, d9 | .. | +0x90 d14, d15
+/// ```
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(target_arch = "aarch64")]
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "sub sp, sp, #0xa0",
+        "stp x19, x20, [sp, #0x00]",
+        "stp x21, x22, [sp, #0x10]",
+        "stp x23, x24, [sp, #0x20]",
+        "stp x25, x26, [sp, #0x30]",
+        "stp x27, x28, [sp, #0x40]",
+        "stp x29, x30, [sp, #0x50]",
+        "stp d8, d9, [sp, #0x60]",
+        "stp d10, d11, [sp, #0x70]",
+        "stp d12, d13, [sp, #0x80]",
+        "stp d14, d15, [sp, #0x90]",
+        "mov x9, sp",
+        "str x9, [x0]",
+        "ldr x9, [x1]",
+        "mov sp, x9",
+        "ldp x19, x20, [sp, #0x00]",
+        "ldp x21, x22, [sp, #0x10]",
+        "ldp x23, x24, [sp, #0x20]",
+        "ldp x25, x26, [sp, #0x30]",
+        "ldp x27, x28, [sp, #0x40]",
+        "ldp x29, x30, [sp, #0x50]",
+        "ldp d8, d9, [sp, #0x60]",
+        "ldp d10, d11, [sp, #0x70]",
+        "ldp d12, d13, [sp, #0x80]",
+        "ldp d14, d15, [sp, #0x90]",
+        "add sp, sp, #0xa0",
+        "ret",
+    );
+}
+
+#[cfg(target_arch = "aarch64")]
+impl TaskContext {
+    /// Context that starts executing `entry` on `stack` the first time it is switched to: a
+    /// zeroed `switch_context` frame whose `x30` is `entry`, placed so that `sp` is 16-byte
+    /// aligned both before and after the frame is popped.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        let sp = ((stack.top() - 16) & !15) - 0xa0;
+        unsafe {
+            std::ptr::write_bytes(sp as *mut u8, 0, 0xa0);
+            ((sp + 0x58) as *mut usize).write(entry as *const () as usize);
+        }
+        Self { sp }
+    }
+}
+
+/// What a `SchedPolicy` sees of each thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThreadInfo {
+    pub state: ThreadState,
+    pub priority: u8,
+}
+
+/// Decides which thread runs next.
+///
+/// `pick_next` is called from `schedule_next` with `threads[i]` describing thread `i`. By then the
+/// current thread, if it was still running, has already been marked `Ready`, so returning `current`
+/// means "keep running it". Only `Ready` threads may be returned; `None` means nothing is ready.
+pub trait SchedPolicy {
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize>;
+
+    /// Called when the scheduler drops its finished threads and ids restart from 1; forget any
+    /// per-thread state.
+    fn reset(&mut self) {}
+}
+
+/// Ignore priorities: the first `Ready` thread after `current`, wrapping around (`current` itself last).
+pub struct RoundRobin;
+
+impl SchedPolicy for RoundRobin {
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize> {
+        todo!("scan (current + 1) % n, (current + 2) % n, ..., current and return the first Ready index")
+    }
+}
+
+/// Always run a `Ready` thread with the highest priority; among equals, round-robin starting after
+/// `current`. Lower-priority threads only run when no higher-priority thread is ready.
+pub struct StrictPriority;
+
+impl SchedPolicy for StrictPriority {
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize> {
+        todo!("find the max priority among Ready threads, then the first Ready thread with it in round-robin order after current")
+    }
+}
+
+/// Stride scheduling: each thread gets CPU turns in proportion to `priority + 1`, and no ready
+/// thread starves, however low its priority.
+///
+/// Every thread has a virtual time `pass`. Pick the `Ready` thread with the smallest `pass` (ties
+/// round-robin after `current`) and advance its `pass` by `STRIDE / (priority + 1)`: the higher the
+/// priority, the slower its pass grows and the more often it is picked, but a thread that keeps
+/// being passed over ends up with the smallest `pass` and must be chosen.
+pub struct WeightedFair {
+    /// `pass[i]` is the virtual time of thread `i`.
+    pass: Vec<u64>,
+}
+
+impl WeightedFair {
+    /// Pass increment of a priority-0 thread; large so integer division keeps the ratios accurate.
+    pub const STRIDE: u64 = 1 << 20;
+
+    pub fn new() -> Self {
+        Self { pass: Vec::new() }
+    }
+}
+
+impl Default for WeightedFair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedPolicy for WeightedFair {
+    /// 1. Threads without a `pass` yet (newly spawned) start at the smallest `pass` of the known
+    ///    `Ready` threads (0 if none), so they neither jump ahead of nor lag behind everyone else.
+    /// 2. Pick the `Ready` thread with minimal `pass`, ties in round-robin order after `current`.
+    /// 3. Add `STRIDE / (priority + 1)` to the picked thread's `pass`.
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize> {
+        todo!("extend pass for new threads, pick the Ready thread with minimal pass, advance its pass by STRIDE / (priority + 1)")
+    }
+
+    fn reset(&mut self) {
+        self.pass.clear();
+    }
+}
+
+/// Number of most recent switches kept by `Scheduler::trace`.
+pub const TRACE_CAPACITY: usize = 256;
+
+/// Why the thread switched away from gave up the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchReason {
+    /// It yielded (`yield_now`, or the main thread's `run` loop) and is still `Ready`.
+    Yield,
+    /// It blocked (`block_current`: a channel, `sleep_ticks`, `block_on_fd`, ...).
+    Blocked,
+    /// Its entry returned.
+    Finished,
+}
+
+/// One context switch from thread `from` to thread `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwitchEvent {
+    pub from: usize,
+    pub to: usize,
+    /// Threads left `Ready` (waiting for the CPU) at the moment of the switch.
+    pub ready: usize,
+    pub reason: SwitchReason,
+}
+
+impl fmt::Display for SwitchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            SwitchReason::Yield => "yield",
+            SwitchReason::Blocked => "blocked",
+            SwitchReason::Finished => "finished",
+        };
+        write!(f, "{} -> {} ({reason})", self.from, self.to)
+    }
+}
+
+/// Counters updated on every context switch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedStats {
+    /// Context switches performed (a policy keeping the current thread running is not a switch).
+    pub switches: u64,
+    /// `run_count[tid]`: how many times thread `tid` was switched to.
+    pub run_count: Vec<u64>,
+    /// Sum of the ready queue length sampled at each switch; see `avg_ready`.
+    pub ready_total: u64,
+    /// Longest ready queue seen at a switch.
+    pub ready_max: usize,
+}
+
+impl SchedStats {
+    /// Average ready queue length over all switches (0.0 before the first one).
+    pub fn avg_ready(&self) -> f64 {
+        if self.switches == 0 {
+            0.0
+        } else {
+            self.ready_total as f64 / self.switches as f64
+        }
+    }
+}
+
 pub struct Scheduler {
     threads: Vec<GreenThread>,
     current: usize,
+    policy: Box<dyn SchedPolicy>,
+    stacks: StackPool,
+    stats: SchedStats,
+    /// Ring buffer of the last `TRACE_CAPACITY` switches, oldest first.
+    trace: VecDeque<SwitchEvent>,
+    /// Clock and wait list behind `sleep_ticks`.
+    #[cfg(feature = "timer")]
+    timer: timer_tick::TimerDriver,
+    /// Called by `idle` whenever no green thread is `Ready`.
+    idle_hook: Option<Box<dyn FnMut()>>,
+    /// Threads blocked in `block_on_fd`.
+    #[cfg(unix)]
+    poller: Poller,
+    /// Called with every `SwitchEvent` as it is recorded.
+    switch_hook: Option<Box<dyn FnMut(SwitchEvent)>>,
+}
+
+/// `Scheduler::run` stopped because no green thread can ever run again: none is `Ready`, none
+/// waits on an fd, and neither the idle hook nor the timer woke one.
+///
+/// The blocked threads stay suspended where they blocked; dropping the scheduler frees their
+/// stacks without unwinding them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadlockDetected {
+    /// Ids of the threads left `Blocked`, ascending.
+    pub blocked: Vec<usize>,
 }
 
 impl Scheduler {
+    /// Scheduler with the `RoundRobin` policy.
     pub fn new() -> Self {
+        Self::with_policy(Box::new(RoundRobin))
+    }
+
+    /// Scheduler that asks `policy` which thread to run next.
+    pub fn with_policy(policy: Box<dyn SchedPolicy>) -> Self {
         let main_thread = GreenThread {
             ctx: TaskContext::default(),
             state: ThreadState::Running,
-            _stack: None,
+            priority: 0,
+            stack: None,
             entry: None,
         };
 
         Self {
             threads: vec![main_thread],
             current: 0,
+            policy,
+            stacks: StackPool::default(),
+            stats: SchedStats::default(),
+            trace: VecDeque::with_capacity(TRACE_CAPACITY),
+            #[cfg(feature = "timer")]
+            timer: timer_tick::TimerDriver::new(TICK_CYCLES, 1),
+            idle_hook: None,
+            #[cfg(unix)]
+            poller: Poller::default(),
+            switch_hook: None,
         }
     }
 
+    /// Register a new green thread with `DEFAULT_PRIORITY`.
+    pub fn spawn(&mut self, entry: extern "C" fn()) {
+        self.spawn_with_priority(entry, DEFAULT_PRIORITY);
+    }
+
     /// Register a new green thread that will run `entry` when first scheduled.
     ///
-    /// 1. Allocate a stack of `STACK_SIZE` bytes; compute `stack_top` (high address).
-    /// 2. Set up the context: `ra = thread_wrapper` so the first switch jumps to the wrapper;
-    ///    `sp` must be 16-byte aligned (e.g. `(stack_top - 16) & !15` to leave headroom).
-    /// 3. Push a `GreenThread` with this context, state `Ready`, and `entry` stored for the wrapper to call.
-    pub fn spawn(&mut self, entry: extern "C" fn()) {
-        todo!("alloc stack, init ctx with ra=thread_wrapper and aligned sp, push GreenThread(Ready, entry)")
+    /// 1. Call `self.reclaim()`, then take a guarded `Stack` from `self.stacks.get()`.
+    /// 2. Set up the context with `TaskContext::new_task(&stack, thread_wrapper)` so the first switch
+    ///    enters the wrapper on the new stack.
+    /// 3. Push a `GreenThread` with this context, state `Ready`, `priority`, and `entry` stored for the wrapper to call.
+    pub fn spawn_with_priority(&mut self, entry: extern "C" fn(), priority: u8) {
+        todo!("reclaim(), stacks.get(), TaskContext::new_task(&stack, thread_wrapper), push GreenThread(Ready, priority, stack, entry)")
     }
 
-    /// Run the scheduler until all threads (except the main one) are `Finished`.
+    /// Run the scheduler until all threads (except the main one) are `Finished`, or until the
+    /// ones left are all `Blocked` for good.
     ///
-    /// 1. Set the global `SCHEDULER` pointer to `self` so that `yield_now` and `thread_finished` can call back.
-    /// 2. Loop: if all threads in `threads[1..]` are `Finished`, break; otherwise call `schedule_next()` (which may switch away and later return).
-    /// 3. Clear `SCHEDULER` when done.
-    pub fn run(&mut self) {
-        todo!("set SCHEDULER to self, loop until threads[1..] all Finished, call schedule_next, then clear SCHEDULER")
+    /// 1. Make `self` this OS thread's scheduler (`let _current = SchedulerHandle::install(self)`) so
+    ///    that `yield_now` and `thread_finished` can call back.
+    /// 2. Loop: if all threads in `threads[1..]` are `Finished`, return `Ok(())`. If none of them is
+    ///    `Ready` (`self.has_ready()`), call `self.idle()` first (idle hook, optional timer); if that
+    ///    did not make one `Ready` either, return `DeadlockDetected` with the ids of the `Blocked`
+    ///    threads. Then call `schedule_next()` (which may switch away and later return).
+    /// 3. When done, drop the guard: it puts back whatever scheduler was current before.
+    pub fn run(&mut self) -> Result<(), DeadlockDetected> {
+        todo!("SchedulerHandle::install(self), loop until threads[1..] all Finished (idle, or DeadlockDetected, when none is Ready), call schedule_next")
     }
 
-    /// Find the next ready thread (starting from `current + 1` round-robin), mark current as `Ready` (if not `Finished`), mark next as `Running`, set `CURRENT_THREAD_ENTRY` if the next thread has an entry, then switch to it.
+    /// Pick the next thread with the policy and switch to it.
+    ///
+    /// 0. If any thread waits on an fd (`!self.poller.is_empty()`), `self.poll_fds(0)` first so the
+    ///    ones whose fd became ready can be picked.
+    /// 1. Mark current as `Ready` if it is still `Running` (`Blocked` and `Finished` threads keep their state).
+    /// 2. Ask `self.policy.pick_next(self.current, &self.thread_infos())`. On `None`, return.
+    /// 3. If the pick is the current thread, mark it `Running` again and return without switching.
+    /// 4. Otherwise mark next as `Running`, `self.record_switch(current, next)`, then switch to it.
+    ///    (A thread that has never run starts in `thread_wrapper`, which takes its entry itself.)
     fn schedule_next(&mut self) {
-        todo!("round-robin find next Ready, set current Ready (if not Finished), next Running, CURRENT_THREAD_ENTRY, then switch_context")
+        todo!("poll_fds(0) if the poller is not empty, set current Ready (if still Running), policy.pick_next, next Running, record_switch, then switch_context unless next == current")
     }
-}
 
-impl TaskContext {
-    fn as_mut_ptr(&mut self) -> *mut TaskContext {
-        self as *mut TaskContext
+    /// Peak stack use of thread `tid` in bytes (see `Stack::watermark`); `None` for the main thread
+    /// or an unknown id. Stacks are kept after a thread finishes until the next `spawn` recycles
+    /// them, so this also works right after `run`.
+    pub fn stack_watermark(&self, tid: usize) -> Option<usize> {
+        self.threads.get(tid)?.stack.as_ref().map(Stack::watermark)
     }
-    fn as_ptr(&self) -> *const TaskContext {
-        self as *const TaskContext
+
+    /// Update `stats` and `trace` for a switch from `from` to `to` (call after marking `to` Running),
+    /// and pass the event to the switch hook. The reason follows from the state `from` was left in.
+    fn record_switch(&mut self, from: usize, to: usize) {
+        let ready = self
+            .threads
+            .iter()
+            .filter(|t| t.state == ThreadState::Ready)
+            .count();
+        let stats = &mut self.stats;
+        stats.switches += 1;
+        if stats.run_count.len() <= to {
+            stats.run_count.resize(to + 1, 0);
+        }
+        stats.run_count[to] += 1;
+        stats.ready_total += ready as u64;
+        stats.ready_max = stats.ready_max.max(ready);
+        if self.trace.len() == TRACE_CAPACITY {
+            self.trace.pop_front();
+        }
+        let reason = match self.threads[from].state {
+            ThreadState::Blocked => SwitchReason::Blocked,
+            ThreadState::Finished => SwitchReason::Finished,
+            _ => SwitchReason::Yield,
+        };
+        let event = SwitchEvent {
+            from,
+            to,
+            ready,
+            reason,
+        };
+        self.trace.push_back(event);
+        if let Some(mut hook) = self.switch_hook.take() {
+            hook(event);
+            self.switch_hook = Some(hook);
+        }
     }
-}
 
-static mut SCHEDULER: *mut Scheduler = std::ptr::null_mut();
+    /// Call `hook` with every context switch from now on, right before it happens (it runs on the
+    /// thread being switched away from). Replaces any previous hook.
+    pub fn set_hook(&mut self, hook: Box<dyn FnMut(SwitchEvent)>) {
+        self.switch_hook = Some(hook);
+    }
 
-/// Current thread voluntarily yields; the scheduler will pick the next ready thread.
-pub fn yield_now() {
-    unsafe {
-        if !SCHEDULER.is_null() {
-            (*SCHEDULER).schedule_next();
+    /// Call `hook` whenever `run` finds no green thread `Ready`. It runs on the main thread and
+    /// may call `wake`; if it wakes nobody (and no timer sleeper is due), `run` reports a deadlock.
+    pub fn set_idle_hook(&mut self, hook: impl FnMut() + 'static) {
+        self.idle_hook = Some(Box::new(hook));
+    }
+
+    /// Whether any green thread (the main thread aside) is `Ready`.
+    fn has_ready(&self) -> bool {
+        self.threads[1..]
+            .iter()
+            .any(|t| t.state == ThreadState::Ready)
+    }
+
+    /// Nothing is ready to run. Call the idle hook first. If it woke nobody and some thread waits
+    /// on an fd, wait in `poll(2)` until one is ready (only check, if a timer sleeper is due: virtual
+    /// time must get to move). Finally, with the `timer` feature and still nothing ready, advance
+    /// virtual time to the next wake-up tick and make the sleepers it wakes `Ready`.
+    fn idle(&mut self) {
+        // Taken out while it runs: its `wake` calls reach this scheduler through the handle.
+        if let Some(mut hook) = self.idle_hook.take() {
+            hook();
+            self.idle_hook = Some(hook);
+        }
+        #[cfg(unix)]
+        if !self.has_ready() && !self.poller.is_empty() {
+            #[cfg(feature = "timer")]
+            let timeout = if self.timer.next_wakeup().is_some() {
+                0
+            } else {
+                -1
+            };
+            #[cfg(not(feature = "timer"))]
+            let timeout = -1;
+            self.poll_fds(timeout);
+        }
+        #[cfg(feature = "timer")]
+        if !self.has_ready() {
+            for tid in self.timer.advance_to_next_wakeup() {
+                self.unblock(tid);
+            }
         }
     }
-}
 
-/// Mark current thread as `Finished` and switch to the next (called by `thread_wrapper` after the user entry returns).
-fn thread_finished() {
-    unsafe {
-        if !SCHEDULER.is_null() {
-            let sched = &mut *SCHEDULER;
-            sched.threads[sched.current].state = ThreadState::Finished;
-            sched.schedule_next();
+    /// Poll the fds threads are blocked on (`timeout_ms` as for `poll(2)`) and make the threads
+    /// whose fd is ready `Ready`.
+    #[cfg(unix)]
+    fn poll_fds(&mut self, timeout_ms: i32) {
+        for tid in self.poller.poll(timeout_ms) {
+            self.unblock(tid);
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::atomic::{AtomicU32, Ordering};
-    use std::sync::Mutex;
+    /// Make thread `tid` `Ready` if it is `Blocked`.
+    fn unblock(&mut self, tid: usize) {
+        if let Some(t) = self.threads.get_mut(tid) {
+            if t.state == ThreadState::Blocked {
+                t.state = ThreadState::Ready;
+            }
+        }
+    }
 
-    /// Tests must run serially: the scheduler uses global state (SCHEDULER, CURRENT_THREAD_ENTRY).
-    static TEST_LOCK: Mutex<()> = Mutex::new(());
+    /// Threads currently blocked in `block_on_fd`.
+    #[cfg(unix)]
+    pub fn poller(&self) -> &Poller {
+        &self.poller
+    }
 
-    static EXEC_ORDER: AtomicU32 = AtomicU32::new(0);
+    /// The timer behind `sleep_ticks`; `jiffies()` is the current virtual time in ticks.
+    #[cfg(feature = "timer")]
+    pub fn timer(&self) -> &timer_tick::TimerDriver {
+        &self.timer
+    }
 
-    extern "C" fn task_a() {
-        EXEC_ORDER.fetch_add(1, Ordering::SeqCst);
-        yield_now();
-        EXEC_ORDER.fetch_add(10, Ordering::SeqCst);
-        yield_now();
-        EXEC_ORDER.fetch_add(100, Ordering::SeqCst);
+    /// Switch counters accumulated over the scheduler's lifetime. `run_count` is cleared when
+    /// thread ids restart (see `reclaim`).
+    pub fn stats(&self) -> &SchedStats {
+        &self.stats
     }
 
-    extern "C" fn task_b() {
-        EXEC_ORDER.fetch_add(1, Ordering::SeqCst);
-        yield_now();
-        EXEC_ORDER.fetch_add(10, Ordering::SeqCst);
+    /// The last `TRACE_CAPACITY` switches, oldest first.
+    pub fn trace(&self) -> Vec<SwitchEvent> {
+        self.trace.iter().copied().collect()
     }
 
-    #[test]
-    fn test_scheduler_runs_all() {
-        let _guard = TEST_LOCK.lock().unwrap();
-        EXEC_ORDER.store(0, Ordering::SeqCst);
-
-        let mut sched = Scheduler::new();
-        sched.spawn(task_a);
-        sched.spawn(task_b);
-        sched.run();
-
-        let got = EXEC_ORDER.load(Ordering::SeqCst);
-        if got != 122 {
-            panic!(
-                "EXEC_ORDER: expected 122, got {} (run with --nocapture to see stderr)",
-                got
-            );
-        }
+    /// The pool `spawn` takes stacks from, for its statistics.
+    pub fn stack_pool(&self) -> &StackPool {
+        &self.stacks
     }
 
-    static SIMPLE_FLAG: AtomicU32 = AtomicU32::new(0);
+    /// If every green thread has finished, return their stacks to the pool, drop them, and
+    /// `reset` the policy so ids start again from 1. Does nothing while any thread is alive: a
+    /// running thread's id must stay valid, and a finished thread's stack is only safe to reuse
+    /// once nothing runs on it any more.
+    fn reclaim(&mut self) {
+        if self.threads.len() == 1
+            || !self.threads[1..]
+                .iter()
+                .all(|t| t.state == ThreadState::Finished)
+        {
+            return;
+        }
+        for t in self.threads.drain(1..) {
+            if let Some(stack) = t.stack {
+                self.stacks.put(stack);
+            }
+        }
+        self.policy.reset();
+        self.stats.run_count.clear();
+    }
 
-    extern "C" fn simple_task() {
-        SIMPLE_FLAG.store(42, Ordering::SeqCst);
+    /// Snapshot of every thread's state and priority, indexed by thread id, for the policy.
+    fn thread_infos(&self) -> Vec<ThreadInfo> {
+        self.threads
+            .iter()
+            .map(|t| ThreadInfo {
+                state: t.state,
+                priority: t.priority,
+            })
+            .collect()
     }
+}
 
-    #[test]
-    fn test_single_thread() {
-        let _guard = TEST_LOCK.lock().unwrap();
-        SIMPLE_FLAG.store(0, Ordering::SeqCst);
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let mut sched = Scheduler::new();
-        sched.spawn(simple_task);
-        sched.run();
+impl TaskContext {
+    fn as_mut_ptr(&mut self) -> *mut TaskContext {
+        self as *mut TaskContext
+    }
+    fn as_ptr(&self) -> *const TaskContext {
+        self as *const TaskContext
+    }
+}
+
+/// The scheduler whose `run` is active on an OS thread.
+///
+/// Green threads call back into it (`yield_now`, `block_current`, ...) while `run` is suspended
+/// inside `schedule_next`, so it is reached through a pointer rather than a borrow.
+#[derive(Clone, Copy)]
+struct SchedulerHandle(NonNull<Scheduler>);
+
+thread_local! {
+    /// This OS thread's scheduler, while its `run` is active. Each OS thread has its own slot,
+    /// so schedulers on different threads never see each other.
+    static CURRENT_SCHEDULER: RefCell<Option<SchedulerHandle>> = const { RefCell::new(None) };
+}
+
+/// Keeps a scheduler installed until dropped, then restores the one it replaced.
+struct Installed {
+    previous: Option<SchedulerHandle>,
+}
+
+impl SchedulerHandle {
+    /// Make `sched` this OS thread's scheduler for as long as the returned guard lives.
+    fn install(sched: &mut Scheduler) -> Installed {
+        let handle = SchedulerHandle(NonNull::from(sched));
+        Installed {
+            previous: CURRENT_SCHEDULER.with(|c| c.replace(Some(handle))),
+        }
+    }
+}
+
+impl Drop for Installed {
+    fn drop(&mut self) {
+        CURRENT_SCHEDULER.with(|c| *c.borrow_mut() = self.previous);
+    }
+}
+
+/// Run `f` on this OS thread's scheduler; `None` outside `Scheduler::run`.
+///
+/// The `RefCell` is borrowed only to copy the handle out: `f` may switch to another green
+/// thread, which calls `with_scheduler` again before `f` returns.
+fn with_scheduler<R>(f: impl FnOnce(&mut Scheduler) -> R) -> Option<R> {
+    let handle = CURRENT_SCHEDULER.with(|c| *c.borrow())?;
+    // SAFETY: `run` installs the pointer from its `&mut self` and uninstalls it before it
+    // returns, and every green thread of that scheduler runs on this OS thread.
+    Some(f(unsafe { &mut *handle.0.as_ptr() }))
+}
+
+/// Current thread voluntarily yields; the scheduler will pick the next ready thread.
+pub fn yield_now() {
+    with_scheduler(Scheduler::schedule_next);
+}
+
+/// Mark current thread as `Finished` and switch to the next (called by `thread_wrapper` after the user entry returns).
+fn thread_finished() {
+    with_scheduler(|sched| {
+        sched.threads[sched.current].state = ThreadState::Finished;
+        sched.schedule_next();
+    });
+}
+
+/// Id of the running green thread (its index in the scheduler; the main thread is 0).
+pub fn current_thread() -> usize {
+    with_scheduler(|sched| sched.current).unwrap_or(0)
+}
+
+/// Mark the current thread `Blocked` and switch away. Returns once another thread has called
+/// `wake` on it and the scheduler picks it again.
+pub fn block_current() {
+    with_scheduler(|sched| {
+        sched.threads[sched.current].state = ThreadState::Blocked;
+        sched.schedule_next();
+    });
+}
+
+/// Make a `Blocked` thread `Ready` again. Threads in any other state are left untouched.
+pub fn wake(tid: usize) {
+    with_scheduler(|sched| sched.unblock(tid));
+}
+
+/// Block the current green thread for `ticks` timer ticks. The main thread (or a call outside
+/// `run`) does not sleep.
+#[cfg(feature = "timer")]
+pub fn sleep_ticks(ticks: u64) {
+    let queued = with_scheduler(|sched| {
+        if sched.current == 0 {
+            return false;
+        }
+        sched.timer.sleep_for(sched.current, ticks);
+        true
+    });
+    if queued == Some(true) {
+        block_current();
+    }
+}
+
+/// What a thread blocked on an fd waits for.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interest {
+    /// Data to read, or end of file (`POLLIN`).
+    Readable,
+    /// Room to write (`POLLOUT`).
+    Writable,
+}
+
+#[cfg(unix)]
+impl Interest {
+    fn events(self) -> libc::c_short {
+        match self {
+            Interest::Readable => libc::POLLIN,
+            Interest::Writable => libc::POLLOUT,
+        }
+    }
+}
+
+/// A minimal epoll: the (fd, interest) pairs green threads are blocked on, checked all at once
+/// with one `poll(2)`. Each registration is one-shot: it is removed when reported ready.
+#[cfg(unix)]
+#[derive(Debug, Default)]
+pub struct Poller {
+    waiters: Vec<FdWaiter>,
+}
+
+#[cfg(unix)]
+#[derive(Debug)]
+struct FdWaiter {
+    fd: RawFd,
+    interest: Interest,
+    tid: usize,
+}
+
+#[cfg(unix)]
+impl Poller {
+    /// Wake thread `tid` once `fd` is ready for `interest`.
+    pub fn register(&mut self, fd: RawFd, interest: Interest, tid: usize) {
+        self.waiters.push(FdWaiter { fd, interest, tid });
+    }
+
+    /// Number of registrations still waiting.
+    pub fn len(&self) -> usize {
+        self.waiters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiters.is_empty()
+    }
+
+    /// `poll(2)` every registered fd, waiting at most `timeout_ms` (0: just check, -1: until one
+    /// is ready), and remove and return the thread ids whose fd is ready.
+    ///
+    /// 1. Build one `libc::pollfd` per waiter, `events` from its interest.
+    /// 2. Call `libc::poll`. On a timeout or an error (`EINTR`), nobody is ready: return empty.
+    /// 3. A waiter is ready if its `revents` is non-zero. That includes `POLLHUP` / `POLLERR`: the
+    ///    thread's next `read`/`write` then reports end of file or the error.
+    pub fn poll(&mut self, timeout_ms: i32) -> Vec<usize> {
+        todo!("one pollfd per waiter (events from interest), libc::poll, then remove and return the waiters whose revents != 0")
+    }
+}
+
+/// Block the current green thread until `fd` is ready for `interest`, letting the others run.
+///
+/// Meant for fds in `O_NONBLOCK` mode, after a `read`/`write` returned `EAGAIN`; retry it after
+/// this returns. A green thread registers `(fd, interest, current_thread())` with the scheduler's
+/// `Poller` and calls `block_current()`. The main thread (or a call outside `run`) has nobody to
+/// switch to, and waits with a blocking `poll(2)` on `fd` instead.
+#[cfg(unix)]
+pub fn block_on_fd(fd: RawFd, interest: Interest) {
+    todo!("register (fd, interest, current) with the scheduler's poller and block_current(); the main thread polls the fd itself")
+}
+
+/// Bounded channel between green threads of one scheduler.
+///
+/// A full channel blocks `send`, an empty channel blocks `recv`; blocked threads wait in FIFO
+/// order in `senders` / `receivers`. Never hold the inner lock across `block_current()`: the
+/// thread that wakes us needs it.
+pub struct GreenChannel<T> {
+    inner: Mutex<ChannelInner<T>>,
+}
+
+struct ChannelInner<T> {
+    buf: VecDeque<T>,
+    capacity: usize,
+    /// Threads blocked in `send`, waiting for a free slot.
+    senders: VecDeque<usize>,
+    /// Threads blocked in `recv`, waiting for a value.
+    receivers: VecDeque<usize>,
+}
+
+impl<T> GreenChannel<T> {
+    /// Create a channel holding at most `capacity` (> 0) values.
+    pub const fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        Self {
+            inner: Mutex::new(ChannelInner {
+                buf: VecDeque::new(),
+                capacity,
+                senders: VecDeque::new(),
+                receivers: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Send `value`, blocking the current green thread while the channel is full.
+    ///
+    /// Loop:
+    /// 1. Lock `inner`. If `buf.len() < capacity`: push the value, `wake` the first waiting receiver (if any), return.
+    /// 2. Otherwise push `current_thread()` onto `senders`, release the lock, and call `block_current()`; retry after waking.
+    pub fn send(&self, value: T) {
+        todo!("push if there is room and wake one receiver; otherwise enqueue current_thread() in senders, unlock, block_current(), retry")
+    }
+
+    /// Receive the oldest value, blocking the current green thread while the channel is empty.
+    ///
+    /// Loop:
+    /// 1. Lock `inner`. If `buf` has a value: pop it, `wake` the first waiting sender (if any), return it.
+    /// 2. Otherwise push `current_thread()` onto `receivers`, release the lock, and call `block_current()`; retry after waking.
+    pub fn recv(&self) -> T {
+        todo!("pop if non-empty and wake one sender; otherwise enqueue current_thread() in receivers, unlock, block_current(), retry")
+    }
+
+    /// Number of values currently buffered.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::cell::{Cell, RefCell};
+    #[cfg(unix)]
+    use std::os::unix::process::ExitStatusExt;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Mutex, PoisonError};
+    use std::time::Duration;
+    #[cfg(unix)]
+    use subprocess::in_child;
+    use watchdog::run_with_timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_single_thread",
+            "run() calls schedule_next until every spawned thread is Finished; thread_wrapper marks its thread Finished after the entry returns",
+        ),
+        (
+            "test_two_schedulers_sequentially",
+            "run installs self with SchedulerHandle::install and keeps the guard until its loop ends, so the next scheduler starts from a clean slate",
+        ),
+        (
+            "test_stats_for_known_yield_pattern",
+            "schedule_next marks the current thread Ready again before pick_next, and calls record_switch only after marking the next one Running",
+        ),
+        (
+            "test_switch_hook_sees_every_switch",
+            "record_switch derives the reason from the state the old thread was left in, so a blocked or finished thread must not be marked Ready",
+        ),
+        (
+            "test_channel_recv_blocks_until_send",
+            "recv on an empty channel enqueues current_thread() in receivers and blocks; send wakes one waiting receiver",
+        ),
+        (
+            "test_stack_layout",
+            "mmap the size rounded up to pages plus one guard page, and mprotect the lowest page PROT_NONE: bottom() is just above it",
+        ),
+        (
+            "test_stack_pool_reuse",
+            "get() pops from free before calling Stack::new; release() clears the watermark and keeps the stack while free.len() < max_idle",
+        ),
+        (
+            "test_channel_cycle_is_deadlock",
+            "when no thread is Ready, run calls idle(); if still none is Ready, return Err(DeadlockDetected) with every Blocked thread id instead of looping",
+        ),
+        (
+            "test_idle_hook_wakes_parked_thread",
+            "only report a deadlock after idle() has run the hook and the hook woke nobody",
+        ),
+        (
+            "test_pipe_reader_and_writer_block_on_readiness",
+            "schedule_next calls poll_fds(0) while the poller is not empty; Poller::poll removes and returns the waiters whose revents != 0",
+        ),
+        (
+            "test_idle_waits_for_fd",
+            "with only fd waiters left, idle() waits in poll(2) with timeout -1 instead of reporting a deadlock",
+        ),
+        (
+            "test_strict_priority_runs_highest_first",
+            "StrictPriority picks the highest priority among Ready threads, and the first such thread after current in round-robin order",
+        ),
+    ];
+
+    /// Longest a test may take before it counts as hung.
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Tests must run serially: they share static counters and logs.
+    static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Run a scheduler test alone, failing it if it hangs. The lock is held out here rather
+    /// than by the watchdog's thread, which a hung test leaks with whatever it holds.
+    #[track_caller]
+    fn serial(test: impl FnOnce() + Send + 'static) {
+        let _guard = TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        run_with_timeout(TIMEOUT, test);
+    }
+
+    static EXEC_ORDER: AtomicU32 = AtomicU32::new(0);
+
+    extern "C" fn task_a() {
+        EXEC_ORDER.fetch_add(1, Ordering::SeqCst);
+        yield_now();
+        EXEC_ORDER.fetch_add(10, Ordering::SeqCst);
+        yield_now();
+        EXEC_ORDER.fetch_add(100, Ordering::SeqCst);
+    }
+
+    extern "C" fn task_b() {
+        EXEC_ORDER.fetch_add(1, Ordering::SeqCst);
+        yield_now();
+        EXEC_ORDER.fetch_add(10, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_scheduler_runs_all() {
+        serial(|| {
+            EXEC_ORDER.store(0, Ordering::SeqCst);
+
+            let mut sched = Scheduler::new();
+            sched.spawn(task_a);
+            sched.spawn(task_b);
+            sched.run().unwrap();
+
+            let got = EXEC_ORDER.load(Ordering::SeqCst);
+            if got != 122 {
+                panic!(
+                    "EXEC_ORDER: expected 122, got {} (run with --nocapture to see stderr)",
+                    got
+                );
+            }
+        });
+    }
+
+    static SIMPLE_FLAG: AtomicU32 = AtomicU32::new(0);
+
+    extern "C" fn simple_task() {
+        SIMPLE_FLAG.store(42, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_single_thread() {
+        serial(|| {
+            SIMPLE_FLAG.store(0, Ordering::SeqCst);
+
+            let mut sched = Scheduler::new();
+            sched.spawn(simple_task);
+            sched.run().unwrap();
+
+            assert_eq_with_hint!(SIMPLE_FLAG.load(Ordering::SeqCst), 42);
+        });
+    }
+
+    static SEQ_LOG: Mutex<Vec<(char, usize)>> = Mutex::new(Vec::new());
+
+    extern "C" fn seq_first() {
+        SEQ_LOG.lock().unwrap().push(('1', current_thread()));
+        yield_now();
+        SEQ_LOG.lock().unwrap().push(('1', current_thread()));
+    }
+
+    extern "C" fn seq_second() {
+        SEQ_LOG.lock().unwrap().push(('2', current_thread()));
+    }
+
+    #[test]
+    fn test_two_schedulers_sequentially() {
+        serial(|| {
+            SEQ_LOG.lock().unwrap().clear();
+
+            let mut first = Scheduler::new();
+            first.spawn(seq_first);
+            first.spawn(seq_first);
+            first.run().unwrap();
+            // Nothing is installed outside `run`: the main thread yields to nobody.
+            yield_now();
+            assert_eq_with_hint!(current_thread(), 0);
+
+            let mut second = Scheduler::new();
+            second.spawn(seq_second);
+            second.run().unwrap();
+
+            assert_eq_with_hint!(
+                *SEQ_LOG.lock().unwrap(),
+                vec![('1', 1), ('1', 2), ('1', 1), ('1', 2), ('2', 1)]
+            );
+            assert_eq!(first.stats().run_count.len(), 3);
+            assert_eq!(second.stats().run_count.len(), 2, "stats are per scheduler");
+        });
+    }
+
+    // ---- Statistics and trace ----
+
+    #[test]
+    fn test_stats_for_known_yield_pattern() {
+        serial(|| {
+            let mut sched = Scheduler::new();
+            sched.spawn(task_a); // yields twice
+            sched.spawn(task_b); // yields once
+            sched.run().unwrap();
+
+            use SwitchReason::{Finished, Yield};
+            let ev = |from, to, ready, reason| SwitchEvent {
+                from,
+                to,
+                ready,
+                reason,
+            };
+            assert_eq_with_hint!(
+                sched.trace(),
+                vec![
+                    ev(0, 1, 2, Yield),
+                    ev(1, 2, 2, Yield),
+                    ev(2, 0, 2, Yield),
+                    ev(0, 1, 2, Yield),
+                    ev(1, 2, 2, Yield),
+                    ev(2, 0, 1, Finished),
+                    ev(0, 1, 1, Yield),
+                    ev(1, 0, 0, Finished),
+                ]
+            );
+            let stats = sched.stats();
+            assert_eq_with_hint!(stats.switches, 8);
+            assert_eq_with_hint!(stats.run_count, vec![3, 3, 2]);
+            assert_eq_with_hint!(stats.ready_max, 2);
+            assert_eq_with_hint!(stats.ready_total, 12);
+            assert_with_hint!((stats.avg_ready() - 1.5).abs() < 1e-9);
+        });
+    }
+
+    extern "C" fn yield_300() {
+        for _ in 0..300 {
+            yield_now();
+        }
+    }
+
+    #[test]
+    fn test_trace_keeps_last_events() {
+        serial(|| {
+            let mut sched = Scheduler::new();
+            sched.spawn(yield_300);
+            sched.run().unwrap();
+
+            // Every yield bounces through the main thread: 2 switches per yield, plus the first
+            // switch in and the final switch out.
+            assert_eq!(sched.stats().switches, 2 * 300 + 2);
+            let trace = sched.trace();
+            assert_eq!(
+                trace.len(),
+                TRACE_CAPACITY,
+                "trace is a bounded ring buffer"
+            );
+            assert_eq!(
+                trace.last(),
+                Some(&SwitchEvent {
+                    from: 1,
+                    to: 0,
+                    ready: 0,
+                    reason: SwitchReason::Finished,
+                })
+            );
+            assert!(trace.windows(2).all(|w| w[0].to == w[1].from));
+        });
+    }
+
+    static HOOK_CHAN: GreenChannel<u32> = GreenChannel::new(1);
+
+    extern "C" fn hook_receiver() {
+        HOOK_CHAN.recv();
+    }
+
+    extern "C" fn hook_sender() {
+        HOOK_CHAN.send(1);
+    }
+
+    #[test]
+    fn test_switch_hook_sees_every_switch() {
+        serial(|| {
+            let timeline = Rc::new(RefCell::new(Vec::new()));
+            let mut sched = Scheduler::new();
+            let log = timeline.clone();
+            sched.set_hook(Box::new(move |ev: SwitchEvent| {
+                log.borrow_mut().push(ev.to_string())
+            }));
+            sched.spawn(hook_receiver);
+            sched.spawn(hook_sender);
+            sched.run().unwrap();
+
+            assert_eq_with_hint!(
+                *timeline.borrow(),
+                vec![
+                    "0 -> 1 (yield)",
+                    "1 -> 2 (blocked)", // the channel is empty
+                    "2 -> 0 (finished)",
+                    "0 -> 1 (yield)",
+                    "1 -> 0 (finished)",
+                ]
+            );
+            // The hook and the trace see the same events.
+            let traced: Vec<String> = sched.trace().iter().map(|ev| ev.to_string()).collect();
+            assert_eq!(*timeline.borrow(), traced);
+        });
+    }
+
+    // ---- GreenChannel ----
+
+    /// Event log shared by the channel tests: `(who, value)`, 'S' = sent, 'R' = received.
+    static CHAN_LOG: Mutex<Vec<(char, u32)>> = Mutex::new(Vec::new());
+    static PC_CHAN: GreenChannel<u32> = GreenChannel::new(2);
+
+    extern "C" fn pc_producer() {
+        for i in 0..5 {
+            PC_CHAN.send(i);
+            CHAN_LOG.lock().unwrap().push(('S', i));
+        }
+    }
+
+    extern "C" fn pc_consumer() {
+        for _ in 0..5 {
+            let v = PC_CHAN.recv();
+            CHAN_LOG.lock().unwrap().push(('R', v));
+        }
+    }
+
+    #[test]
+    fn test_channel_producer_consumer_order() {
+        serial(|| {
+            CHAN_LOG.lock().unwrap().clear();
+
+            let mut sched = Scheduler::new();
+            sched.spawn(pc_producer);
+            sched.spawn(pc_consumer);
+            sched.run().unwrap();
+
+            let log = CHAN_LOG.lock().unwrap().clone();
+            let received: Vec<u32> = log.iter().filter(|e| e.0 == 'R').map(|e| e.1).collect();
+            assert_eq!(
+                received,
+                vec![0, 1, 2, 3, 4],
+                "values must arrive in send order"
+            );
+            // Capacity 2: the producer fills the channel, blocks on the third send, and only
+            // resumes after the consumer has drained it and blocked in turn.
+            assert_eq!(
+                log,
+                vec![
+                    ('S', 0),
+                    ('S', 1),
+                    ('R', 0),
+                    ('R', 1),
+                    ('S', 2),
+                    ('S', 3),
+                    ('R', 2),
+                    ('R', 3),
+                    ('S', 4),
+                    ('R', 4),
+                ]
+            );
+            assert!(PC_CHAN.is_empty());
+        });
+    }
+
+    static EMPTY_CHAN: GreenChannel<u32> = GreenChannel::new(1);
+
+    extern "C" fn early_receiver() {
+        let v = EMPTY_CHAN.recv();
+        CHAN_LOG.lock().unwrap().push(('R', v));
+    }
+
+    extern "C" fn late_sender() {
+        CHAN_LOG.lock().unwrap().push(('S', 7));
+        EMPTY_CHAN.send(7);
+    }
+
+    #[test]
+    fn test_channel_recv_blocks_until_send() {
+        serial(|| {
+            CHAN_LOG.lock().unwrap().clear();
+
+            let mut sched = Scheduler::new();
+            sched.spawn(early_receiver);
+            sched.spawn(late_sender);
+            sched.run().unwrap();
+
+            assert_eq_with_hint!(*CHAN_LOG.lock().unwrap(), vec![('S', 7), ('R', 7)]);
+        });
+    }
+
+    static MULTI_CHAN: GreenChannel<u32> = GreenChannel::new(1);
+    static MULTI_SUM: AtomicU32 = AtomicU32::new(0);
+
+    extern "C" fn multi_sender_a() {
+        for i in 1..=3 {
+            MULTI_CHAN.send(i);
+        }
+    }
+
+    extern "C" fn multi_sender_b() {
+        for i in 10..=12 {
+            MULTI_CHAN.send(i);
+        }
+    }
+
+    extern "C" fn multi_receiver() {
+        for _ in 0..6 {
+            MULTI_SUM.fetch_add(MULTI_CHAN.recv(), Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_channel_multiple_senders() {
+        serial(|| {
+            MULTI_SUM.store(0, Ordering::SeqCst);
+
+            let mut sched = Scheduler::new();
+            sched.spawn(multi_sender_a);
+            sched.spawn(multi_sender_b);
+            sched.spawn(multi_receiver);
+            sched.run().unwrap();
+
+            assert_eq!(MULTI_SUM.load(Ordering::SeqCst), 1 + 2 + 3 + 10 + 11 + 12);
+            assert!(MULTI_CHAN.is_empty());
+        });
+    }
+
+    // ---- Guarded stacks ----
+
+    #[test]
+    fn test_stack_layout() {
+        let stack = Stack::new(10_000).unwrap();
+        let page = page_size();
+        assert_with_hint!(stack.size() >= 10_000);
+        assert_eq_with_hint!(stack.size() % page, 0, "usable size is whole pages");
+        assert_eq_with_hint!(stack.bottom() % page, 0);
+        assert_eq_with_hint!(stack.top() - stack.bottom(), stack.size());
+        assert_eq_with_hint!(stack.watermark(), 0, "a fresh stack is all canary");
+        let bottom = stack.bottom() as *const u8;
+        assert_eq_with_hint!(unsafe { *bottom }, STACK_CANARY);
+    }
+
+    extern "C" fn deep_frame_task() {
+        let buf = std::hint::black_box([1u8; 16 * 1024]);
+        std::hint::black_box(&buf);
+    }
+
+    extern "C" fn shallow_task() {}
+
+    #[test]
+    fn test_stack_watermark() {
+        serial(|| {
+            let mut sched = Scheduler::new();
+            sched.spawn(deep_frame_task);
+            sched.spawn(shallow_task);
+            sched.run().unwrap();
+
+            let deep = sched.stack_watermark(1).unwrap();
+            let shallow = sched.stack_watermark(2).unwrap();
+            assert!(
+                deep >= 16 * 1024,
+                "16 KiB local array not seen, watermark {deep}"
+            );
+            assert!(deep < STACK_SIZE);
+            assert!(shallow < deep, "shallow {shallow} >= deep {deep}");
+            assert_eq!(
+                sched.stack_watermark(0),
+                None,
+                "main thread has no green stack"
+            );
+            assert_eq!(sched.stack_watermark(99), None);
+        });
+    }
+
+    fn recurse(depth: u64) -> u64 {
+        let buf = std::hint::black_box([depth as u8; 512]);
+        if depth == 0 {
+            return 0;
+        }
+        recurse(depth - 1) + buf[0] as u64
+    }
+
+    extern "C" fn overflow_task() {
+        std::hint::black_box(recurse(1 << 20));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stack_overflow_hits_guard_page() {
+        serial(|| {
+            // Overflow in a child process: it must be killed by SIGSEGV (SIGBUS on macOS) on the
+            // guard page rather than running on into whatever memory lies below the stack.
+            let out = in_child(|| {
+                let mut sched = Scheduler::new();
+                sched.spawn(overflow_task);
+                sched.run().unwrap();
+            });
+            assert!(
+                matches!(out.status.signal(), Some(libc::SIGSEGV | libc::SIGBUS)),
+                "child should die from SIGSEGV or SIGBUS, exit status {:?}",
+                out.status
+            );
+        });
+    }
+
+    // ---- Stack pool ----
+
+    #[test]
+    fn test_stack_pool_reuse() {
+        let mut pool = StackPool::new(4);
+        let stacks: Vec<Stack> = (0..6).map(|_| pool.get().unwrap()).collect();
+        assert_eq_with_hint!(pool.allocated(), 6);
+        assert_eq_with_hint!(pool.in_use(), 6);
+        assert_eq_with_hint!(pool.high_water(), 6);
+
+        // Dirty one stack near its top, as a thread would.
+        let dirty = &stacks[0];
+        unsafe { std::ptr::write_bytes((dirty.top() - 100) as *mut u8, 0, 100) };
+        assert_eq_with_hint!(dirty.watermark(), 100);
+
+        for stack in stacks {
+            pool.put(stack);
+        }
+        assert_eq_with_hint!(pool.in_use(), 0);
+        assert_eq_with_hint!(pool.idle(), 4, "only max_idle stacks are kept");
+
+        for _ in 0..4 {
+            let stack = pool.get().unwrap();
+            assert_eq_with_hint!(stack.watermark(), 0, "recycled stacks come back clean");
+            pool.put(stack);
+        }
+        assert_eq_with_hint!(
+            pool.allocated(),
+            6,
+            "idle stacks are reused before mapping new ones"
+        );
+        assert_eq_with_hint!(pool.reused(), 4);
+        assert_eq_with_hint!(pool.high_water(), 6);
+    }
+
+    static TINY_RUNS: AtomicU32 = AtomicU32::new(0);
+
+    extern "C" fn tiny_task() {
+        TINY_RUNS.fetch_add(1, Ordering::SeqCst);
+        yield_now();
+    }
+
+    #[test]
+    fn test_spawn_many_short_lived_threads() {
+        serial(|| {
+            TINY_RUNS.store(0, Ordering::SeqCst);
+
+            let mut sched = Scheduler::new();
+            for _ in 0..200 {
+                for _ in 0..50 {
+                    sched.spawn(tiny_task);
+                }
+                sched.run().unwrap();
+            }
+
+            assert_eq!(TINY_RUNS.load(Ordering::SeqCst), 10_000);
+            let pool = sched.stack_pool();
+            assert_eq!(
+                pool.allocated(),
+                50,
+                "10k spawns should map only one batch of stacks"
+            );
+            assert_eq!(pool.reused(), 10_000 - 50);
+            assert_eq!(pool.high_water(), 50);
+        });
+    }
+
+    // ---- Scheduling policies ----
+
+    /// One letter per time slice: 'H' / 'M' / 'L' for high / medium / low priority tasks.
+    static PRIO_LOG: Mutex<Vec<char>> = Mutex::new(Vec::new());
+
+    fn log_and_yield(c: char, times: usize) {
+        for _ in 0..times {
+            PRIO_LOG.lock().unwrap().push(c);
+            yield_now();
+        }
+    }
+
+    extern "C" fn prio_high() {
+        log_and_yield('H', 3);
+    }
+
+    extern "C" fn prio_mid() {
+        log_and_yield('M', 3);
+    }
+
+    extern "C" fn prio_low() {
+        log_and_yield('L', 3);
+    }
+
+    fn run_three(policy: Box<dyn SchedPolicy>) -> String {
+        PRIO_LOG.lock().unwrap().clear();
+        let mut sched = Scheduler::with_policy(policy);
+        sched.spawn_with_priority(prio_low, 1);
+        sched.spawn_with_priority(prio_high, 5);
+        sched.spawn_with_priority(prio_mid, 3);
+        sched.run().unwrap();
+        let log = PRIO_LOG.lock().unwrap();
+        log.iter().collect()
+    }
 
-        assert_eq!(SIMPLE_FLAG.load(Ordering::SeqCst), 42);
+    #[test]
+    fn test_round_robin_ignores_priority() {
+        serial(|| {
+            assert_eq!(run_three(Box::new(RoundRobin)), "LHMLHMLHM");
+        });
+    }
+
+    #[test]
+    fn test_strict_priority_runs_highest_first() {
+        serial(|| {
+            // Yielding does not give the CPU away while the yielder is the highest-priority ready thread.
+            assert_eq_with_hint!(run_three(Box::new(StrictPriority)), "HHHMMMLLL");
+        });
+    }
+
+    extern "C" fn prio_same_a() {
+        log_and_yield('A', 2);
+    }
+
+    extern "C" fn prio_same_b() {
+        log_and_yield('B', 2);
+    }
+
+    #[test]
+    fn test_strict_priority_round_robin_among_equals() {
+        serial(|| {
+            PRIO_LOG.lock().unwrap().clear();
+            let mut sched = Scheduler::with_policy(Box::new(StrictPriority));
+            sched.spawn_with_priority(prio_same_a, 4);
+            sched.spawn_with_priority(prio_same_b, 4);
+            sched.run().unwrap();
+            assert_eq!(*PRIO_LOG.lock().unwrap(), vec!['A', 'B', 'A', 'B']);
+        });
+    }
+
+    extern "C" fn hog_high() {
+        log_and_yield('H', 12);
+    }
+
+    extern "C" fn hog_low() {
+        log_and_yield('L', 4);
+    }
+
+    #[test]
+    fn test_weighted_fair_no_starvation() {
+        serial(|| {
+            PRIO_LOG.lock().unwrap().clear();
+            let mut sched = Scheduler::with_policy(Box::new(WeightedFair::new()));
+            sched.spawn_with_priority(hog_high, 3);
+            sched.spawn_with_priority(hog_low, 0);
+            sched.run().unwrap();
+
+            let log = PRIO_LOG.lock().unwrap().clone();
+            assert_eq!(log.iter().filter(|&&c| c == 'H').count(), 12);
+            assert_eq!(log.iter().filter(|&&c| c == 'L').count(), 4);
+            // The low-priority thread must get turns while the high-priority one is still runnable...
+            let first_low = log.iter().position(|&c| c == 'L').unwrap();
+            let last_high = log.iter().rposition(|&c| c == 'H').unwrap();
+            assert!(
+                first_low < last_high,
+                "low-priority thread starved: {log:?}"
+            );
+            // ...but only about one for every four high-priority turns (weights 4 : 1).
+            let first_half_lows = log[..8].iter().filter(|&&c| c == 'L').count();
+            assert!(
+                (1..=3).contains(&first_half_lows),
+                "expected roughly a 4:1 share, got {log:?}"
+            );
+        });
+    }
+
+    // ---- Idle and deadlock ----
+
+    static CYCLE_A: GreenChannel<u32> = GreenChannel::new(1);
+    static CYCLE_B: GreenChannel<u32> = GreenChannel::new(1);
+
+    extern "C" fn cycle_left() {
+        let v = CYCLE_A.recv();
+        CYCLE_B.send(v);
+    }
+
+    extern "C" fn cycle_right() {
+        let v = CYCLE_B.recv();
+        CYCLE_A.send(v);
+    }
+
+    #[test]
+    fn test_channel_cycle_is_deadlock() {
+        serial(|| {
+            let mut sched = Scheduler::new();
+            sched.spawn(cycle_left);
+            sched.spawn(simple_task);
+            sched.spawn(cycle_right);
+            // Each side waits for the other to send first; `simple_task` finishes normally.
+            assert_eq_with_hint!(
+                sched.run(),
+                Err(DeadlockDetected {
+                    blocked: vec![1, 3]
+                })
+            );
+        });
+    }
+
+    /// Thread id parked in `parker`, for the idle hook to wake (0: none).
+    static PARKED: AtomicU32 = AtomicU32::new(0);
+
+    extern "C" fn parker() {
+        for _ in 0..2 {
+            PRIO_LOG.lock().unwrap().push('p');
+            PARKED.store(current_thread() as u32, Ordering::SeqCst);
+            block_current();
+        }
+        PRIO_LOG.lock().unwrap().push('P');
+    }
+
+    extern "C" fn idle_worker() {
+        log_and_yield('w', 2);
+    }
+
+    #[test]
+    fn test_idle_hook_wakes_parked_thread() {
+        serial(|| {
+            PRIO_LOG.lock().unwrap().clear();
+            PARKED.store(0, Ordering::SeqCst);
+            let idle_calls = Rc::new(Cell::new(0));
+
+            let mut sched = Scheduler::new();
+            let calls = idle_calls.clone();
+            sched.set_idle_hook(move || {
+                calls.set(calls.get() + 1);
+                match PARKED.swap(0, Ordering::SeqCst) {
+                    0 => {}
+                    tid => wake(tid as usize),
+                }
+            });
+            sched.spawn(parker);
+            sched.spawn(idle_worker);
+            assert_eq_with_hint!(sched.run(), Ok(()));
+
+            // The hook only runs once `idle_worker` is done and `parker` is the only one left.
+            assert_eq!(PRIO_LOG.lock().unwrap().iter().collect::<String>(), "pwwpP");
+            assert_eq_with_hint!(idle_calls.get(), 2);
+        });
+    }
+
+    extern "C" fn park_forever() {
+        block_current();
+        unreachable!("nobody wakes this thread");
+    }
+
+    #[test]
+    fn test_idle_hook_waking_nobody_is_deadlock() {
+        serial(|| {
+            let idle_calls = Rc::new(Cell::new(0));
+            let mut sched = Scheduler::new();
+            let calls = idle_calls.clone();
+            sched.set_idle_hook(move || calls.set(calls.get() + 1));
+            sched.spawn(park_forever);
+            assert_eq!(sched.run(), Err(DeadlockDetected { blocked: vec![1] }));
+            assert_eq!(idle_calls.get(), 1);
+        });
+    }
+
+    // ---- Blocking on file descriptors ----
+
+    /// `pipe2` and `F_SETPIPE_SZ` are Linux-only.
+    #[cfg(target_os = "linux")]
+    mod fds {
+        use super::*;
+        use std::sync::atomic::AtomicI32;
+
+        static PIPE_R: AtomicI32 = AtomicI32::new(-1);
+        static PIPE_W: AtomicI32 = AtomicI32::new(-1);
+        static READ_BLOCKS: AtomicU32 = AtomicU32::new(0);
+        static WRITE_BLOCKS: AtomicU32 = AtomicU32::new(0);
+        static RECEIVED: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+        /// More than the pipe holds, so the writer has to wait for the reader.
+        const PIPE_BYTES: usize = 64 * 1024;
+
+        fn pattern() -> Vec<u8> {
+            (0..PIPE_BYTES).map(|i| (i % 251) as u8).collect()
+        }
+
+        fn would_block() -> bool {
+            std::io::Error::last_os_error().raw_os_error() == Some(libc::EAGAIN)
+        }
+
+        /// An `O_NONBLOCK` pipe shrunk to one page, stored in `PIPE_R` / `PIPE_W`.
+        fn nonblocking_pipe() {
+            let mut fds = [0; 2];
+            assert_eq!(
+                unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) },
+                0
+            );
+            unsafe { libc::fcntl(fds[1], libc::F_SETPIPE_SZ, 4096) };
+            PIPE_R.store(fds[0], Ordering::SeqCst);
+            PIPE_W.store(fds[1], Ordering::SeqCst);
+            READ_BLOCKS.store(0, Ordering::SeqCst);
+            WRITE_BLOCKS.store(0, Ordering::SeqCst);
+            RECEIVED.lock().unwrap().clear();
+        }
+
+        extern "C" fn pipe_writer() {
+            let fd = PIPE_W.load(Ordering::SeqCst);
+            let data = pattern();
+            let mut off = 0;
+            while off < data.len() {
+                let n = unsafe { libc::write(fd, data[off..].as_ptr().cast(), data.len() - off) };
+                if n >= 0 {
+                    off += n as usize;
+                } else {
+                    assert!(would_block());
+                    WRITE_BLOCKS.fetch_add(1, Ordering::SeqCst);
+                    block_on_fd(fd, Interest::Writable);
+                }
+            }
+            unsafe { libc::close(fd) };
+        }
+
+        extern "C" fn pipe_reader() {
+            let fd = PIPE_R.load(Ordering::SeqCst);
+            let mut buf = [0u8; 1024];
+            loop {
+                let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+                match n {
+                    0 => break,
+                    n if n > 0 => RECEIVED
+                        .lock()
+                        .unwrap()
+                        .extend_from_slice(&buf[..n as usize]),
+                    _ => {
+                        assert!(would_block());
+                        READ_BLOCKS.fetch_add(1, Ordering::SeqCst);
+                        block_on_fd(fd, Interest::Readable);
+                    }
+                }
+            }
+            unsafe { libc::close(fd) };
+        }
+
+        #[test]
+        fn test_pipe_reader_and_writer_block_on_readiness() {
+            serial(|| {
+                nonblocking_pipe();
+                let mut sched = Scheduler::new();
+                sched.spawn(pipe_reader);
+                sched.spawn(pipe_writer);
+                assert_eq_with_hint!(sched.run(), Ok(()));
+
+                assert_with_hint!(*RECEIVED.lock().unwrap() == pattern());
+                // The reader found the pipe empty at least once, the writer found it full.
+                assert_with_hint!(READ_BLOCKS.load(Ordering::SeqCst) > 0);
+                assert_with_hint!(WRITE_BLOCKS.load(Ordering::SeqCst) > 0);
+                assert!(sched.poller().is_empty());
+            });
+        }
+
+        #[test]
+        fn test_idle_waits_for_fd() {
+            serial(|| {
+                nonblocking_pipe();
+                // The only writer is an OS thread the scheduler knows nothing about.
+                let writer = std::thread::spawn(|| {
+                    std::thread::sleep(Duration::from_millis(50));
+                    let fd = PIPE_W.load(Ordering::SeqCst);
+                    unsafe {
+                        libc::write(fd, b"ping".as_ptr().cast(), 4);
+                        libc::close(fd);
+                    }
+                });
+                let mut sched = Scheduler::new();
+                sched.spawn(pipe_reader);
+                assert_eq_with_hint!(sched.run(), Ok(()));
+                writer.join().unwrap();
+
+                assert_eq_with_hint!(*RECEIVED.lock().unwrap(), b"ping");
+                assert!(READ_BLOCKS.load(Ordering::SeqCst) > 0);
+            });
+        }
+    }
+
+    #[cfg(feature = "timer")]
+    mod timer {
+        use super::*;
+
+        extern "C" fn sleeper_3() {
+            sleep_ticks(3);
+            PRIO_LOG.lock().unwrap().push('3');
+        }
+
+        extern "C" fn sleeper_1() {
+            sleep_ticks(1);
+            PRIO_LOG.lock().unwrap().push('1');
+        }
+
+        extern "C" fn sleeper_2() {
+            sleep_ticks(2);
+            PRIO_LOG.lock().unwrap().push('2');
+        }
+
+        #[test]
+        fn test_sleepers_wake_in_deadline_order() {
+            serial(|| {
+                PRIO_LOG.lock().unwrap().clear();
+                let mut sched = Scheduler::new();
+                sched.spawn(sleeper_3);
+                sched.spawn(sleeper_1);
+                sched.spawn(sleeper_2);
+                sched.run().unwrap();
+                assert_eq!(PRIO_LOG.lock().unwrap().iter().collect::<String>(), "123");
+                assert_eq!(
+                    sched.timer().jiffies(),
+                    3,
+                    "virtual time skips to each wake-up"
+                );
+            });
+        }
+
+        extern "C" fn long_sleeper() {
+            sleep_ticks(1000);
+            PRIO_LOG.lock().unwrap().push('S');
+        }
+
+        extern "C" fn busy_worker() {
+            log_and_yield('w', 3);
+        }
+
+        #[test]
+        fn test_sleeper_does_not_block_others() {
+            serial(|| {
+                PRIO_LOG.lock().unwrap().clear();
+                let mut sched = Scheduler::new();
+                sched.spawn(long_sleeper);
+                sched.spawn(busy_worker);
+                sched.run().unwrap();
+                assert_eq!(PRIO_LOG.lock().unwrap().iter().collect::<String>(), "wwwS");
+                assert_eq!(sched.timer().jiffies(), 1000);
+            });
+        }
+
+        #[test]
+        fn test_sleeper_is_not_a_deadlock() {
+            serial(|| {
+                PRIO_LOG.lock().unwrap().clear();
+                let mut sched = Scheduler::new();
+                sched.spawn(park_forever);
+                sched.spawn(long_sleeper);
+                // Both are blocked at first, but the sleeper has a wake-up tick coming.
+                assert_eq!(sched.run(), Err(DeadlockDetected { blocked: vec![1] }));
+                assert_eq!(PRIO_LOG.lock().unwrap().iter().collect::<String>(), "S");
+                assert_eq!(sched.timer().jiffies(), 1000);
+            });
+        }
     }
 }
@@ -0,0 +1,1521 @@
+//! # Green Thread Scheduler (riscv64, x86_64, aarch64)
+//!
+//! In this exercise, you build a simple cooperative (green) thread scheduler on top of context switching.
+//! The exercise targets **riscv64**; run with the repo's normal flow (`./check.sh` / `oscamp`) or natively on riscv64.
+//!
+//! ## Backends
+//! - **riscv64**: the hand-written `switch_context` below (same as `01_stack_coroutine`).
+//! - **x86_64** (Linux, macOS, Windows) and **aarch64** (Linux, macOS): the same idea in those
+//!   instruction sets, so `cargo test -p green_threads` also runs without a riscv64 toolchain.
+//!   Their `switch_context` pushes the callee-saved registers onto the stack it leaves and pops
+//!   them off the one it resumes, so `TaskContext` is just the saved stack pointer.
+//!
+//! Other targets stop the build with a `compile_error!`. On Windows, stacks come from
+//! `VirtualAlloc` instead of `mmap`, and waiting on file descriptors (`block_on_fd`) is not
+//! available.
+//!
+//! ## Key Concepts
+//! - Cooperative vs preemptive scheduling
+//! - Thread state: `Ready`, `Running`, `Blocked`, `Finished`
+//! - `yield_now()`: current thread voluntarily gives up the CPU
+//! - Scheduler loop: pick next ready thread and switch to it
+//! - Scheduling policies: round-robin, strict priority, weighted fair (stride) scheduling
+//! - Guard pages: an `mmap`ed stack with a `PROT_NONE` page below it turns overflow into a fault
+//! - Blocking primitives: a thread that cannot make progress parks itself (`Blocked`) and is woken by another thread
+//!
+//! ## Design
+//! Each green thread has its own stack and `TaskContext`. Threads call `yield_now()` to yield.
+//! Which ready thread runs next is decided by a `SchedPolicy` chosen when the scheduler is built
+//! (`Scheduler::new()` uses `RoundRobin`; `Scheduler::with_policy` takes any policy). Every thread has
+//! a priority (`spawn_with_priority`; higher number = more important, the main thread has priority 0).
+//! User entry is wrapped by `thread_wrapper`, which calls the entry then marks the thread `Finished`
+//! and switches back. `yield_now()` and the other free functions find the scheduler through a
+//! thread-local slot that `run` fills for its duration, so schedulers on different OS threads, or
+//! run one after another, never share state.
+//!
+//! Every context switch is counted in `SchedStats` and appended to a bounded trace of `SwitchEvent`s
+//! (`Scheduler::stats` / `Scheduler::trace`), so scheduling behaviour can be inspected after a run.
+//! Each event says why the old thread gave up the CPU (`SwitchReason`). To watch switches as they
+//! happen, `Scheduler::set_hook` installs a callback that gets every event, e.g. to print a timeline
+//! (`SwitchEvent` displays as `1 -> 2 (blocked)`).
+//!
+//! Stacks come from `mmap` rather than `Vec`: below the usable region sits one `PROT_NONE` guard page,
+//! so a thread that overflows its stack dies with `SIGSEGV` instead of scribbling over neighbouring
+//! memory. Fresh stacks are filled with `STACK_CANARY`; `Scheduler::stack_watermark` reports how deep a
+//! thread's stack ever got by finding the lowest byte that no longer holds the canary.
+//!
+//! Mapping a fresh stack for every `spawn` is expensive, so stacks of finished threads go back into a
+//! `StackPool` and are handed out again. Once every green thread has finished (i.e. between two `run`
+//! calls), the next `spawn` recycles all their stacks and thread ids start again from 1.
+//!
+//! `GreenChannel<T>` is a bounded channel whose `send`/`recv` block the calling green thread instead of
+//! spinning: the thread records its id in the channel's wait queue, calls `block_current()`, and is made
+//! `Ready` again by `wake(id)` when the other side frees a slot or pushes a value.
+//!
+//! ## Waiting for file descriptors
+//! A green thread must not block the OS thread in `read`/`write`: that would stop every other green
+//! thread too. Instead it puts the fd in `O_NONBLOCK` mode, and on `EAGAIN` calls
+//! `block_on_fd(fd, interest)`, which registers it with the scheduler's `Poller` (a tiny epoll on
+//! top of `poll(2)`) and blocks it. Before every scheduling decision the scheduler checks the
+//! registered fds without waiting and makes the threads whose fd became ready `Ready` again.
+//!
+//! ## Idle and deadlock
+//! When no green thread is `Ready`, `run` calls `idle()`: it runs the hook set with
+//! `Scheduler::set_idle_hook` (which may `wake` threads), then waits in `poll(2)` for as long as it
+//! takes if some thread waits on an fd, then, with the `timer` feature, skips virtual time ahead to
+//! the next sleeper. If after that still nothing is `Ready`, every live thread is `Blocked` with
+//! nobody left to wake it, and `run` returns `DeadlockDetected` listing them instead of spinning
+//! forever.
+//!
+//! ## Optional: timer-driven sleep (`--features timer`)
+//! With the `timer` feature the scheduler owns a `TimerDriver` from `07_trap_interrupt/02_timer_tick`
+//! and `sleep_ticks(n)` parks the current thread in the timer's wait list. Time is virtual: it only
+//! moves when no green thread is `Ready`, and then `idle()` skips straight to the next wake-up tick,
+//! so a test with long sleeps still finishes instantly. Run `cargo test -p green_threads --features timer`.
+
+#![cfg(any(
+    target_arch = "riscv64",
+    target_arch = "x86_64",
+    all(target_arch = "aarch64", not(windows))
+))]
+
+use core::arch::naked_asm;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+#[cfg(unix)]
+use std::os::fd::RawFd;
+use std::ptr::NonNull;
+use std::sync::Mutex;
+
+/// Per-thread stack size. Slightly larger to avoid overflow under QEMU / test harness.
+pub(crate) const STACK_SIZE: usize = 1024 * 128;
+
+/// Simulated CLINT cycles per timer tick (the `timer` feature counts ticks, so any value works).
+#[cfg(feature = "timer")]
+const TICK_CYCLES: u64 = 10_000;
+
+/// Byte written over every fresh stack; `Stack::watermark` looks for the first byte that changed.
+pub const STACK_CANARY: u8 = 0xC5;
+
+#[cfg(unix)]
+pub(crate) fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+/// Windows on x86_64 always uses 4 KiB pages.
+#[cfg(windows)]
+pub(crate) fn page_size() -> usize {
+    4096
+}
+
+/// The few `kernel32` functions behind Windows stacks (what `mmap` / `mprotect` / `munmap` do on
+/// unix).
+#[cfg(windows)]
+mod kernel32 {
+    use std::ffi::c_void;
+
+    pub const MEM_COMMIT: u32 = 0x1000;
+    pub const MEM_RESERVE: u32 = 0x2000;
+    pub const MEM_RELEASE: u32 = 0x8000;
+    pub const PAGE_NOACCESS: u32 = 0x01;
+    pub const PAGE_READWRITE: u32 = 0x04;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn VirtualAlloc(addr: *mut c_void, size: usize, kind: u32, protect: u32)
+            -> *mut c_void;
+        pub fn VirtualProtect(addr: *mut c_void, size: usize, protect: u32, old: *mut u32) -> i32;
+        pub fn VirtualFree(addr: *mut c_void, size: usize, kind: u32) -> i32;
+    }
+}
+
+/// A green thread stack: one `mmap` region (`VirtualAlloc` on Windows) laid out as
+///
+/// ```text
+/// base             base + page                                base + len
+/// | guard (PROT_NONE) | usable stack (RW, grows down) ...... | <- top()
+/// ```
+///
+/// Touching the guard page raises `SIGSEGV` (an access violation on Windows), so overflow is
+/// caught instead of corrupting memory.
+pub struct Stack {
+    /// Start of the mapping (the guard page).
+    base: *mut u8,
+    /// Length of the whole mapping, guard page included.
+    len: usize,
+}
+
+#[cfg(unix)]
+impl Stack {
+    /// Map a stack with at least `size` usable bytes plus a guard page.
+    ///
+    /// 1. Round `size` up to a multiple of `page_size()`; the mapping is one page longer.
+    /// 2. `libc::mmap` it `PROT_READ | PROT_WRITE`, `MAP_PRIVATE | MAP_ANONYMOUS`; on `MAP_FAILED`
+    ///    return `std::io::Error::last_os_error()`.
+    /// 3. `libc::mprotect` the lowest page to `PROT_NONE` (unmap and return the error on failure).
+    /// 4. Fill the usable part with `STACK_CANARY` so `watermark` can measure use later.
+    pub fn new(size: usize) -> std::io::Result<Self> {
+        let page = page_size();
+        let len = size.div_ceil(page) * page + page;
+        let base = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        if unsafe { libc::mprotect(base, page, libc::PROT_NONE) } != 0 {
+            let err = std::io::Error::last_os_error();
+            unsafe { libc::munmap(base, len) };
+            return Err(err);
+        }
+        let stack = Self {
+            base: base as *mut u8,
+            len,
+        };
+        unsafe {
+            std::ptr::write_bytes(stack.bottom() as *mut u8, STACK_CANARY, stack.size());
+        }
+        Ok(stack)
+    }
+}
+
+#[cfg(windows)]
+impl Stack {
+    /// Same as the unix version, with `VirtualAlloc` for `mmap` (`MEM_RESERVE | MEM_COMMIT`,
+    /// `PAGE_READWRITE`) and `VirtualProtect(.., PAGE_NOACCESS, ..)` for `mprotect`.
+    pub fn new(size: usize) -> std::io::Result<Self> {
+        use kernel32::*;
+        let page = page_size();
+        let len = size.div_ceil(page) * page + page;
+        let base = unsafe {
+            VirtualAlloc(
+                std::ptr::null_mut(),
+                len,
+                MEM_RESERVE | MEM_COMMIT,
+                PAGE_READWRITE,
+            )
+        };
+        if base.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut old = 0;
+        if unsafe { VirtualProtect(base, page, PAGE_NOACCESS, &mut old) } == 0 {
+            let err = std::io::Error::last_os_error();
+            unsafe { VirtualFree(base, 0, MEM_RELEASE) };
+            return Err(err);
+        }
+        let stack = Self {
+            base: base as *mut u8,
+            len,
+        };
+        unsafe {
+            std::ptr::write_bytes(stack.bottom() as *mut u8, STACK_CANARY, stack.size());
+        }
+        Ok(stack)
+    }
+}
+
+impl Stack {
+    /// Lowest usable address (just above the guard page).
+    pub fn bottom(&self) -> usize {
+        self.base as usize + page_size()
+    }
+
+    /// Highest address (exclusive); the initial `sp` is derived from it.
+    pub fn top(&self) -> usize {
+        self.base as usize + self.len
+    }
+
+    /// Number of usable bytes.
+    pub fn size(&self) -> usize {
+        self.top() - self.bottom()
+    }
+
+    /// Peak stack use in bytes: scan up from `bottom()` while bytes still equal `STACK_CANARY`;
+    /// everything from the first changed byte up to `top()` has been used at some point.
+    ///
+    /// This runs every time a stack is recycled, so skip whole chunks at once (compare
+    /// `chunks(4096)` against a canary-filled array) and only go byte by byte in the first chunk
+    /// that differs.
+    pub fn watermark(&self) -> usize {
+        const CANARY_CHUNK: [u8; 4096] = [STACK_CANARY; 4096];
+        let usable = unsafe { std::slice::from_raw_parts(self.bottom() as *const u8, self.size()) };
+        let mut untouched = 0;
+        for chunk in usable.chunks(CANARY_CHUNK.len()) {
+            if chunk == &CANARY_CHUNK[..chunk.len()] {
+                untouched += chunk.len();
+            } else {
+                untouched += chunk.iter().take_while(|&&b| b == STACK_CANARY).count();
+                break;
+            }
+        }
+        self.size() - untouched
+    }
+
+    /// Re-fill the used part (the top `watermark()` bytes) with `STACK_CANARY`, so a recycled
+    /// stack reports a watermark of 0 again without rewriting the whole stack.
+    pub fn clear_watermark(&self) {
+        let used = self.watermark();
+        unsafe {
+            std::ptr::write_bytes((self.top() - used) as *mut u8, STACK_CANARY, used);
+        }
+    }
+}
+
+/// Recycles stacks of finished threads so `spawn` does not `mmap` a new one every time.
+///
+/// At most `max_idle` unused stacks are kept; extra ones are unmapped (dropped) on `put`.
+pub struct StackPool {
+    free: Vec<Stack>,
+    max_idle: usize,
+    /// Stacks mapped with `Stack::new` so far.
+    allocated: usize,
+    /// `get` calls served from `free`.
+    reused: usize,
+    /// Stacks currently handed out.
+    in_use: usize,
+    /// Largest `in_use` ever seen.
+    high_water: usize,
+}
+
+impl StackPool {
+    /// Default for `max_idle`.
+    pub const DEFAULT_MAX_IDLE: usize = 64;
+
+    pub fn new(max_idle: usize) -> Self {
+        Self {
+            free: Vec::new(),
+            max_idle,
+            allocated: 0,
+            reused: 0,
+            in_use: 0,
+            high_water: 0,
+        }
+    }
+
+    /// Hand out a stack of `STACK_SIZE` bytes.
+    ///
+    /// 1. Pop one from `free` (count it in `reused`), or else `Stack::new(STACK_SIZE)` (count it in `allocated`).
+    /// 2. Increment `in_use` and raise `high_water` if needed.
+    pub fn get(&mut self) -> std::io::Result<Stack> {
+        let stack = match self.free.pop() {
+            Some(stack) => {
+                self.reused += 1;
+                stack
+            }
+            None => {
+                let stack = Stack::new(STACK_SIZE)?;
+                self.allocated += 1;
+                stack
+            }
+        };
+        self.in_use += 1;
+        self.high_water = self.high_water.max(self.in_use);
+        Ok(stack)
+    }
+
+    /// Take back a stack that is no longer used by any thread.
+    ///
+    /// Decrement `in_use`. If fewer than `max_idle` stacks are idle, `clear_watermark()` it and push
+    /// it onto `free`; otherwise just drop it (which unmaps it).
+    pub fn put(&mut self, stack: Stack) {
+        self.in_use -= 1;
+        if self.free.len() < self.max_idle {
+            stack.clear_watermark();
+            self.free.push(stack);
+        }
+    }
+
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+
+    pub fn in_use(&self) -> usize {
+        self.in_use
+    }
+
+    pub fn high_water(&self) -> usize {
+        self.high_water
+    }
+
+    /// Stacks waiting in the pool.
+    pub fn idle(&self) -> usize {
+        self.free.len()
+    }
+}
+
+impl Default for StackPool {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_IDLE)
+    }
+}
+
+impl Drop for Stack {
+    #[cfg(unix)]
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.base as *mut libc::c_void, self.len);
+        }
+    }
+
+    #[cfg(windows)]
+    fn drop(&mut self) {
+        unsafe {
+            kernel32::VirtualFree(self.base.cast(), 0, kernel32::MEM_RELEASE);
+        }
+    }
+}
+
+/// Task context (riscv64); layout must match `01_stack_coroutine::TaskContext` and the asm below.
+#[cfg(target_arch = "riscv64")]
+#[repr(C)]
+#[derive(Debug, Default, Clone)]
+pub struct TaskContext {
+    sp: u64,
+    ra: u64,
+    s0: u64,
+    s1: u64,
+    s2: u64,
+    s3: u64,
+    s4: u64,
+    s5: u64,
+    s6: u64,
+    s7: u64,
+    s8: u64,
+    s9: u64,
+    s10: u64,
+    s11: u64,
+}
+
+/// Task context of the x86_64 / aarch64 backends: only the saved stack pointer. The registers
+/// live in a frame on the task's own stack (see `switch_context`), so the context may move.
+#[cfg(not(target_arch = "riscv64"))]
+#[repr(C)]
+#[derive(Debug, Default, Clone)]
+pub struct TaskContext {
+    sp: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThreadState {
+    Ready,
+    Running,
+    /// Waiting for an event (e.g. channel data); skipped by the scheduler until `wake` makes it `Ready`.
+    Blocked,
+    Finished,
+}
+
+/// Default priority of threads created with `Scheduler::spawn`.
+pub const DEFAULT_PRIORITY: u8 = 1;
+
+struct GreenThread {
+    ctx: TaskContext,
+    state: ThreadState,
+    priority: u8,
+    /// `None` for the main thread, which runs on the process stack.
+    stack: Option<Stack>,
+    /// User entry; taken by `thread_wrapper` when the thread runs for the first time.
+    entry: Option<extern "C" fn()>,
+}
+
+/// Wrapper run as the initial `ra` for each green thread: take the thread's entry from the
+/// scheduler, call it, then mark the thread Finished and switch away.
+extern "C" fn thread_wrapper() {
+    if let Some(f) = with_scheduler(|s| s.threads[s.current].entry.take()).flatten() {
+        f();
+    }
+    thread_finished();
+}
+
+/// Save current callee-saved regs into `old`, load from `new`, then `ret` to `new.ra`.
+/// Zero `a0`/`a1` before `ret` so we don't leak pointers into the new context.
+///
+/// Must be `#[unsafe(naked)]` to prevent the compiler from generating a prologue/epilogue.
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(target_arch = "riscv64")]
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "sd sp, 0(a0)",
+        "sd ra, 8(a0)",
+        "sd s0, 16(a0)",
+        "sd s1, 24(a0)",
+        "sd s2, 32(a0)",
+        "sd s3, 40(a0)",
+        "sd s4, 48(a0)",
+        "sd s5, 56(a0)",
+        "sd s6, 64(a0)",
+        "sd s7, 72(a0)",
+        "sd s8, 80(a0)",
+        "sd s9, 88(a0)",
+        "sd s10, 96(a0)",
+        "sd s11, 104(a0)",
+        "ld sp, 0(a1)",
+        "ld ra, 8(a1)",
+        "ld s0, 16(a1)",
+        "ld s1, 24(a1)",
+        "ld s2, 32(a1)",
+        "ld s3, 40(a1)",
+        "ld s4, 48(a1)",
+        "ld s5, 56(a1)",
+        "ld s6, 64(a1)",
+        "ld s7, 72(a1)",
+        "ld s8, 80(a1)",
+        "ld s9, 88(a1)",
+        "ld s10, 96(a1)",
+        "ld s11, 104(a1)",
+        "li a0, 0",
+        "li a1, 0",
+        "ret",
+    );
+}
+
+#[cfg(target_arch = "riscv64")]
+impl TaskContext {
+    /// Context that starts executing `entry` on `stack` the first time it is switched to.
+    ///
+    /// Set `ra = entry` so the first `ret` in `switch_context` jumps to it; `sp` must be 16-byte
+    /// aligned (e.g. `(stack.top() - 16) & !15` to leave headroom). The `s` registers stay zero.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        Self {
+            ra: entry as *const () as usize as u64,
+            sp: ((stack.top() - 16) & !15) as u64,
+            ..Self::default()
+        }
+    }
+}
+
+/// `mxcsr` of a fresh x86_64 thread: every SSE exception masked, round to nearest.
+#[cfg(target_arch = "x86_64")]
+const MXCSR_DEFAULT: u32 = 0x1F80;
+
+/// x87 control word of a fresh x86_64 thread: every exception masked, 64-bit precision.
+#[cfg(target_arch = "x86_64")]
+const FPUCW_DEFAULT: u16 = 0x037F;
+
+/// x86_64 System V (Linux, macOS): push the callee-saved `rbp`, `rbx`, `r12`-`r15` and the
+/// `mxcsr` / x87 control words, save `rsp` into `old`, load `rsp` from `new`, pop the same frame
+/// and `ret` into `new`. The frame, from the saved `rsp` up:
+///
+/// ```text
+/// +0 mxcsr, +4 x87 cw | +8 r15 | r14 | r13 | r12 | rbx | +48 rbp | +56 return address
+/// ```
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "push rbp",
+        "push rbx",
+        "push r12",
+        "push r13",
+        "push r14",
+        "push r15",
+        "sub rsp, 8",
+        "stmxcsr [rsp]",
+        "fnstcw [rsp + 4]",
+        "mov [rdi], rsp",
+        "mov rsp, [rsi]",
+        "ldmxcsr [rsp]",
+        "fldcw [rsp + 4]",
+        "add rsp, 8",
+        "pop r15",
+        "pop r14",
+        "pop r13",
+        "pop r12",
+        "pop rbx",
+        "pop rbp",
+        "ret",
+    );
+}
+
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+impl TaskContext {
+    /// Context that starts executing `entry` on `stack` the first time it is switched to: a
+    /// `switch_context` frame with zeroed registers, default control words and `entry` as the
+    /// return address. The return address sits on a 16-byte boundary, so `entry` starts with
+    /// `rsp` 8 past one, as after a `call`; the fake return address above it is 0.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        let ret = (stack.top() - 64) & !15;
+        let sp = ret - 56;
+        unsafe {
+            std::ptr::write_bytes(sp as *mut u8, 0, ret + 16 - sp);
+            (sp as *mut u32).write(MXCSR_DEFAULT);
+            ((sp + 4) as *mut u16).write(FPUCW_DEFAULT);
+            (ret as *mut usize).write(entry as *const () as usize);
+        }
+        Self { sp }
+    }
+}
+
+/// x86_64 Windows: as on System V, plus the registers Windows also treats as callee-saved (`rdi`,
+/// `rsi`, `xmm6`-`xmm15`) and the stack bounds in the TEB (`gs:[0x08]` stack base, `gs:[0x10]`
+/// stack limit, `gs:[0x1478]` deallocation stack), which unwinding and stack probes check against
+/// `rsp`. Arguments come in `rcx` / `rdx`. The frame, from the saved `rsp` up:
+///
+/// ```text
+/// +0 xmm6 .. +144 xmm15 | +160 mxcsr, +164 x87 cw | +168 stack base | limit | dealloc
+/// | +192 r15 | r14 | r13 | r12 | rsi | rdi | rbx | +248 rbp | +256 return address
+/// ```
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(all(target_arch = "x86_64", windows))]
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "push rbp",
+        "push rbx",
+        "push rdi",
+        "push rsi",
+        "push r12",
+        "push r13",
+        "push r14",
+        "push r15",
+        "push qword ptr gs:[0x1478]",
+        "push qword ptr gs:[0x10]",
+        "push qword ptr gs:[0x08]",
+        "sub rsp, 168",
+        "movups [rsp], xmm6",
+        "movups [rsp + 16], xmm7",
+        "movups [rsp + 32], xmm8",
+        "movups [rsp + 48], xmm9",
+        "movups [rsp + 64], xmm10",
+        "movups [rsp + 80], xmm11",
+        "movups [rsp + 96], xmm12",
+        "movups [rsp + 112], xmm13",
+        "movups [rsp + 128], xmm14",
+        "movups [rsp + 144], xmm15",
+        "stmxcsr [rsp + 160]",
+        "fnstcw [rsp + 164]",
+        "mov [rcx], rsp",
+        "mov rsp, [rdx]",
+        "movups xmm6, [rsp]",
+        "movups xmm7, [rsp + 16]",
+        "movups xmm8, [rsp + 32]",
+        "movups xmm9, [rsp + 48]",
+        "movups xmm10, [rsp + 64]",
+        "movups xmm11, [rsp + 80]",
+        "movups xmm12, [rsp + 96]",
+        "movups xmm13, [rsp + 112]",
+        "movups xmm14, [rsp + 128]",
+        "movups xmm15, [rsp + 144]",
+        "ldmxcsr [rsp + 160]",
+        "fldcw [rsp + 164]",
+        "add rsp, 168",
+        "pop qword ptr gs:[0x08]",
+        "pop qword ptr gs:[0x10]",
+        "pop qword ptr gs:[0x1478]",
+        "pop r15",
+        "pop r14",
+        "pop r13",
+        "pop r12",
+        "pop rsi",
+        "pop rdi",
+        "pop rbx",
+        "pop rbp",
+        "ret",
+    );
+}
+
+#[cfg(all(target_arch = "x86_64", windows))]
+impl TaskContext {
+    /// Same as on System V with the larger Windows frame; the TEB slots get `stack`'s bounds. The
+    /// 64 bytes left free at the top cover the 32-byte shadow space `entry` may write above its
+    /// return address.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        let ret = (stack.top() - 64) & !15;
+        let sp = ret - 256;
+        unsafe {
+            std::ptr::write_bytes(sp as *mut u8, 0, ret + 16 - sp);
+            ((sp + 160) as *mut u32).write(MXCSR_DEFAULT);
+            ((sp + 164) as *mut u16).write(FPUCW_DEFAULT);
+            ((sp + 168) as *mut usize).write(stack.top());
+            ((sp + 176) as *mut usize).write(stack.bottom());
+            ((sp + 184) as *mut usize).write(stack.base as usize);
+            (ret as *mut usize).write(entry as *const () as usize);
+        }
+        Self { sp }
+    }
+}
+
+/// aarch64 (Linux, macOS): store the callee-saved `x19`-`x28`, `x29` (frame pointer), `x30`
+/// (return address) and `d8`-`d15` in a 0xa0-byte frame below `sp`, save `sp` into `old`, load
+/// `sp` from `new`, reload the same frame and `ret` to the `x30` found there. `x18` is left alone:
+/// macOS reserves it. The frame, from the saved `sp` up:
+///
+/// ```text
+/// +0x00 x19, x20 | .. | +0x40 x27, x28 | +0x50 x29, x30 | +0x60 d8, d9 | .. | +0x90 d14, d15
+/// ```
+///
+/// # Safety
+/// `new` must hold a context saved by `switch_context` or built by `TaskContext::new_task`, whose
+/// stack is still alive.
+#[cfg(target_arch = "aarch64")]
+#[unsafe(naked)]
+pub unsafe extern "C" fn switch_context(_old: &mut TaskContext, _new: &TaskContext) {
+    naked_asm!(
+        "sub sp, sp, #0xa0",
+        "stp x19, x20, [sp, #0x00]",
+        "stp x21, x22, [sp, #0x10]",
+        "stp x23, x24, [sp, #0x20]",
+        "stp x25, x26, [sp, #0x30]",
+        "stp x27, x28, [sp, #0x40]",
+        "stp x29, x30, [sp, #0x50]",
+        "stp d8, d9, [sp, #0x60]",
+        "stp d10, d11, [sp, #0x70]",
+        "stp d12, d13, [sp, #0x80]",
+        "stp d14, d15, [sp, #0x90]",
+        "mov x9, sp",
+        "str x9, [x0]",
+        "ldr x9, [x1]",
+        "mov sp, x9",
+        "ldp x19, x20, [sp, #0x00]",
+        "ldp x21, x22, [sp, #0x10]",
+        "ldp x23, x24, [sp, #0x20]",
+        "ldp x25, x26, [sp, #0x30]",
+        "ldp x27, x28, [sp, #0x40]",
+        "ldp x29, x30, [sp, #0x50]",
+        "ldp d8, d9, [sp, #0x60]",
+        "ldp d10, d11, [sp, #0x70]",
+        "ldp d12, d13, [sp, #0x80]",
+        "ldp d14, d15, [sp, #0x90]",
+        "add sp, sp, #0xa0",
+        "ret",
+    );
+}
+
+#[cfg(target_arch = "aarch64")]
+impl TaskContext {
+    /// Context that starts executing `entry` on `stack` the first time it is switched to: a
+    /// zeroed `switch_context` frame whose `x30` is `entry`, placed so that `sp` is 16-byte
+    /// aligned both before and after the frame is popped.
+    pub fn new_task(stack: &Stack, entry: extern "C" fn()) -> Self {
+        let sp = ((stack.top() - 16) & !15) - 0xa0;
+        unsafe {
+            std::ptr::write_bytes(sp as *mut u8, 0, 0xa0);
+            ((sp + 0x58) as *mut usize).write(entry as *const () as usize);
+        }
+        Self { sp }
+    }
+}
+
+/// What a `SchedPolicy` sees of each thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThreadInfo {
+    pub state: ThreadState,
+    pub priority: u8,
+}
+
+/// Decides which thread runs next.
+///
+/// `pick_next` is called from `schedule_next` with `threads[i]` describing thread `i`. By then the
+/// current thread, if it was still running, has already been marked `Ready`, so returning `current`
+/// means "keep running it". Only `Ready` threads may be returned; `None` means nothing is ready.
+pub trait SchedPolicy {
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize>;
+
+    /// Called when the scheduler drops its finished threads and ids restart from 1; forget any
+    /// per-thread state.
+    fn reset(&mut self) {}
+}
+
+/// Ignore priorities: the first `Ready` thread after `current`, wrapping around (`current` itself last).
+pub struct RoundRobin;
+
+impl SchedPolicy for RoundRobin {
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize> {
+        let n = threads.len();
+        (1..=n)
+            .map(|i| (current + i) % n)
+            .find(|&i| threads[i].state == ThreadState::Ready)
+    }
+}
+
+/// Always run a `Ready` thread with the highest priority; among equals, round-robin starting after
+/// `current`. Lower-priority threads only run when no higher-priority thread is ready.
+pub struct StrictPriority;
+
+impl SchedPolicy for StrictPriority {
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize> {
+        let n = threads.len();
+        let best = threads
+            .iter()
+            .filter(|t| t.state == ThreadState::Ready)
+            .map(|t| t.priority)
+            .max()?;
+        (1..=n)
+            .map(|i| (current + i) % n)
+            .find(|&i| threads[i].state == ThreadState::Ready && threads[i].priority == best)
+    }
+}
+
+/// Stride scheduling: each thread gets CPU turns in proportion to `priority + 1`, and no ready
+/// thread starves, however low its priority.
+///
+/// Every thread has a virtual time `pass`. Pick the `Ready` thread with the smallest `pass` (ties
+/// round-robin after `current`) and advance its `pass` by `STRIDE / (priority + 1)`: the higher the
+/// priority, the slower its pass grows and the more often it is picked, but a thread that keeps
+/// being passed over ends up with the smallest `pass` and must be chosen.
+pub struct WeightedFair {
+    /// `pass[i]` is the virtual time of thread `i`.
+    pass: Vec<u64>,
+}
+
+impl WeightedFair {
+    /// Pass increment of a priority-0 thread; large so integer division keeps the ratios accurate.
+    pub const STRIDE: u64 = 1 << 20;
+
+    pub fn new() -> Self {
+        Self { pass: Vec::new() }
+    }
+}
+
+impl Default for WeightedFair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedPolicy for WeightedFair {
+    /// 1. Threads without a `pass` yet (newly spawned) start at the smallest `pass` of the known
+    ///    `Ready` threads (0 if none), so they neither jump ahead of nor lag behind everyone else.
+    /// 2. Pick the `Ready` thread with minimal `pass`, ties in round-robin order after `current`.
+    /// 3. Add `STRIDE / (priority + 1)` to the picked thread's `pass`.
+    fn pick_next(&mut self, current: usize, threads: &[ThreadInfo]) -> Option<usize> {
+        let start = threads[..self.pass.len().min(threads.len())]
+            .iter()
+            .zip(&self.pass)
+            .filter(|(t, _)| t.state == ThreadState::Ready)
+            .map(|(_, &p)| p)
+            .min()
+            .unwrap_or(0);
+        self.pass.resize(threads.len(), start);
+        let n = threads.len();
+        let next = (1..=n)
+            .map(|i| (current + i) % n)
+            .filter(|&i| threads[i].state == ThreadState::Ready)
+            .min_by_key(|&i| self.pass[i])?;
+        self.pass[next] += Self::STRIDE / (threads[next].priority as u64 + 1);
+        Some(next)
+    }
+
+    fn reset(&mut self) {
+        self.pass.clear();
+    }
+}
+
+/// Number of most recent switches kept by `Scheduler::trace`.
+pub const TRACE_CAPACITY: usize = 256;
+
+/// Why the thread switched away from gave up the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchReason {
+    /// It yielded (`yield_now`, or the main thread's `run` loop) and is still `Ready`.
+    Yield,
+    /// It blocked (`block_current`: a channel, `sleep_ticks`, `block_on_fd`, ...).
+    Blocked,
+    /// Its entry returned.
+    Finished,
+}
+
+/// One context switch from thread `from` to thread `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwitchEvent {
+    pub from: usize,
+    pub to: usize,
+    /// Threads left `Ready` (waiting for the CPU) at the moment of the switch.
+    pub ready: usize,
+    pub reason: SwitchReason,
+}
+
+impl fmt::Display for SwitchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.reason {
+            SwitchReason::Yield => "yield",
+            SwitchReason::Blocked => "blocked",
+            SwitchReason::Finished => "finished",
+        };
+        write!(f, "{} -> {} ({reason})", self.from, self.to)
+    }
+}
+
+/// Counters updated on every context switch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedStats {
+    /// Context switches performed (a policy keeping the current thread running is not a switch).
+    pub switches: u64,
+    /// `run_count[tid]`: how many times thread `tid` was switched to.
+    pub run_count: Vec<u64>,
+    /// Sum of the ready queue length sampled at each switch; see `avg_ready`.
+    pub ready_total: u64,
+    /// Longest ready queue seen at a switch.
+    pub ready_max: usize,
+}
+
+impl SchedStats {
+    /// Average ready queue length over all switches (0.0 before the first one).
+    pub fn avg_ready(&self) -> f64 {
+        if self.switches == 0 {
+            0.0
+        } else {
+            self.ready_total as f64 / self.switches as f64
+        }
+    }
+}
+
+pub struct Scheduler {
+    threads: Vec<GreenThread>,
+    current: usize,
+    policy: Box<dyn SchedPolicy>,
+    stacks: StackPool,
+    stats: SchedStats,
+    /// Ring buffer of the last `TRACE_CAPACITY` switches, oldest first.
+    trace: VecDeque<SwitchEvent>,
+    /// Clock and wait list behind `sleep_ticks`.
+    #[cfg(feature = "timer")]
+    timer: timer_tick::solution::TimerDriver,
+    /// Called by `idle` whenever no green thread is `Ready`.
+    idle_hook: Option<Box<dyn FnMut()>>,
+    /// Threads blocked in `block_on_fd`.
+    #[cfg(unix)]
+    poller: Poller,
+    /// Called with every `SwitchEvent` as it is recorded.
+    switch_hook: Option<Box<dyn FnMut(SwitchEvent)>>,
+}
+
+/// `Scheduler::run` stopped because no green thread can ever run again: none is `Ready`, none
+/// waits on an fd, and neither the idle hook nor the timer woke one.
+///
+/// The blocked threads stay suspended where they blocked; dropping the scheduler frees their
+/// stacks without unwinding them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadlockDetected {
+    /// Ids of the threads left `Blocked`, ascending.
+    pub blocked: Vec<usize>,
+}
+
+impl Scheduler {
+    /// Scheduler with the `RoundRobin` policy.
+    pub fn new() -> Self {
+        Self::with_policy(Box::new(RoundRobin))
+    }
+
+    /// Scheduler that asks `policy` which thread to run next.
+    pub fn with_policy(policy: Box<dyn SchedPolicy>) -> Self {
+        let main_thread = GreenThread {
+            ctx: TaskContext::default(),
+            state: ThreadState::Running,
+            priority: 0,
+            stack: None,
+            entry: None,
+        };
+
+        Self {
+            threads: vec![main_thread],
+            current: 0,
+            policy,
+            stacks: StackPool::default(),
+            stats: SchedStats::default(),
+            trace: VecDeque::with_capacity(TRACE_CAPACITY),
+            #[cfg(feature = "timer")]
+            timer: timer_tick::solution::TimerDriver::new(TICK_CYCLES, 1),
+            idle_hook: None,
+            #[cfg(unix)]
+            poller: Poller::default(),
+            switch_hook: None,
+        }
+    }
+
+    /// Register a new green thread with `DEFAULT_PRIORITY`.
+    pub fn spawn(&mut self, entry: extern "C" fn()) {
+        self.spawn_with_priority(entry, DEFAULT_PRIORITY);
+    }
+
+    /// Register a new green thread that will run `entry` when first scheduled.
+    ///
+    /// 1. Call `self.reclaim()`, then take a guarded `Stack` from `self.stacks.get()`.
+    /// 2. Set up the context with `TaskContext::new_task(&stack, thread_wrapper)` so the first switch
+    ///    enters the wrapper on the new stack.
+    /// 3. Push a `GreenThread` with this context, state `Ready`, `priority`, and `entry` stored for the wrapper to call.
+    pub fn spawn_with_priority(&mut self, entry: extern "C" fn(), priority: u8) {
+        self.reclaim();
+        let stack = self
+            .stacks
+            .get()
+            .expect("failed to allocate green thread stack");
+        let ctx = TaskContext::new_task(&stack, thread_wrapper);
+        self.threads.push(GreenThread {
+            ctx,
+            state: ThreadState::Ready,
+            priority,
+            stack: Some(stack),
+            entry: Some(entry),
+        });
+    }
+
+    /// Run the scheduler until all threads (except the main one) are `Finished`, or until the
+    /// ones left are all `Blocked` for good.
+    ///
+    /// 1. Make `self` this OS thread's scheduler (`let _current = SchedulerHandle::install(self)`) so
+    ///    that `yield_now` and `thread_finished` can call back.
+    /// 2. Loop: if all threads in `threads[1..]` are `Finished`, return `Ok(())`. If none of them is
+    ///    `Ready` (`self.has_ready()`), call `self.idle()` first (idle hook, optional timer); if that
+    ///    did not make one `Ready` either, return `DeadlockDetected` with the ids of the `Blocked`
+    ///    threads. Then call `schedule_next()` (which may switch away and later return).
+    /// 3. When done, drop the guard: it puts back whatever scheduler was current before.
+    pub fn run(&mut self) -> Result<(), DeadlockDetected> {
+        let _current = SchedulerHandle::install(self);
+        loop {
+            if self.threads[1..]
+                .iter()
+                .all(|t| t.state == ThreadState::Finished)
+            {
+                return Ok(());
+            }
+            if !self.has_ready() {
+                self.idle();
+                if !self.has_ready() {
+                    let blocked = (1..self.threads.len())
+                        .filter(|&tid| self.threads[tid].state == ThreadState::Blocked)
+                        .collect();
+                    return Err(DeadlockDetected { blocked });
+                }
+            }
+            self.schedule_next();
+        }
+    }
+
+    /// Pick the next thread with the policy and switch to it.
+    ///
+    /// 0. If any thread waits on an fd (`!self.poller.is_empty()`), `self.poll_fds(0)` first so the
+    ///    ones whose fd became ready can be picked.
+    /// 1. Mark current as `Ready` if it is still `Running` (`Blocked` and `Finished` threads keep their state).
+    /// 2. Ask `self.policy.pick_next(self.current, &self.thread_infos())`. On `None`, return.
+    /// 3. If the pick is the current thread, mark it `Running` again and return without switching.
+    /// 4. Otherwise mark next as `Running`, `self.record_switch(current, next)`, then switch to it.
+    ///    (A thread that has never run starts in `thread_wrapper`, which takes its entry itself.)
+    fn schedule_next(&mut self) {
+        #[cfg(unix)]
+        if !self.poller.is_empty() {
+            self.poll_fds(0);
+        }
+        let cur = self.current;
+        if self.threads[cur].state == ThreadState::Running {
+            self.threads[cur].state = ThreadState::Ready;
+        }
+        let infos = self.thread_infos();
+        let Some(next) = self.policy.pick_next(cur, &infos) else {
+            return;
+        };
+        self.threads[next].state = ThreadState::Running;
+        if next == cur {
+            return;
+        }
+        self.record_switch(cur, next);
+        self.current = next;
+        let old = self.threads[cur].ctx.as_mut_ptr();
+        let new = self.threads[next].ctx.as_ptr();
+        unsafe { switch_context(&mut *old, &*new) };
+    }
+
+    /// Peak stack use of thread `tid` in bytes (see `Stack::watermark`); `None` for the main thread
+    /// or an unknown id. Stacks are kept after a thread finishes until the next `spawn` recycles
+    /// them, so this also works right after `run`.
+    pub fn stack_watermark(&self, tid: usize) -> Option<usize> {
+        self.threads.get(tid)?.stack.as_ref().map(Stack::watermark)
+    }
+
+    /// Update `stats` and `trace` for a switch from `from` to `to` (call after marking `to` Running),
+    /// and pass the event to the switch hook. The reason follows from the state `from` was left in.
+    fn record_switch(&mut self, from: usize, to: usize) {
+        let ready = self
+            .threads
+            .iter()
+            .filter(|t| t.state == ThreadState::Ready)
+            .count();
+        let stats = &mut self.stats;
+        stats.switches += 1;
+        if stats.run_count.len() <= to {
+            stats.run_count.resize(to + 1, 0);
+        }
+        stats.run_count[to] += 1;
+        stats.ready_total += ready as u64;
+        stats.ready_max = stats.ready_max.max(ready);
+        if self.trace.len() == TRACE_CAPACITY {
+            self.trace.pop_front();
+        }
+        let reason = match self.threads[from].state {
+            ThreadState::Blocked => SwitchReason::Blocked,
+            ThreadState::Finished => SwitchReason::Finished,
+            _ => SwitchReason::Yield,
+        };
+        let event = SwitchEvent {
+            from,
+            to,
+            ready,
+            reason,
+        };
+        self.trace.push_back(event);
+        if let Some(mut hook) = self.switch_hook.take() {
+            hook(event);
+            self.switch_hook = Some(hook);
+        }
+    }
+
+    /// Call `hook` with every context switch from now on, right before it happens (it runs on the
+    /// thread being switched away from). Replaces any previous hook.
+    pub fn set_hook(&mut self, hook: Box<dyn FnMut(SwitchEvent)>) {
+        self.switch_hook = Some(hook);
+    }
+
+    /// Call `hook` whenever `run` finds no green thread `Ready`. It runs on the main thread and
+    /// may call `wake`; if it wakes nobody (and no timer sleeper is due), `run` reports a deadlock.
+    pub fn set_idle_hook(&mut self, hook: impl FnMut() + 'static) {
+        self.idle_hook = Some(Box::new(hook));
+    }
+
+    /// Whether any green thread (the main thread aside) is `Ready`.
+    fn has_ready(&self) -> bool {
+        self.threads[1..]
+            .iter()
+            .any(|t| t.state == ThreadState::Ready)
+    }
+
+    /// Nothing is ready to run. Call the idle hook first. If it woke nobody and some thread waits
+    /// on an fd, wait in `poll(2)` until one is ready (only check, if a timer sleeper is due: virtual
+    /// time must get to move). Finally, with the `timer` feature and still nothing ready, advance
+    /// virtual time to the next wake-up tick and make the sleepers it wakes `Ready`.
+    fn idle(&mut self) {
+        // Taken out while it runs: its `wake` calls reach this scheduler through the handle.
+        if let Some(mut hook) = self.idle_hook.take() {
+            hook();
+            self.idle_hook = Some(hook);
+        }
+        #[cfg(unix)]
+        if !self.has_ready() && !self.poller.is_empty() {
+            #[cfg(feature = "timer")]
+            let timeout = if self.timer.next_wakeup().is_some() {
+                0
+            } else {
+                -1
+            };
+            #[cfg(not(feature = "timer"))]
+            let timeout = -1;
+            self.poll_fds(timeout);
+        }
+        #[cfg(feature = "timer")]
+        if !self.has_ready() {
+            for tid in self.timer.advance_to_next_wakeup() {
+                self.unblock(tid);
+            }
+        }
+    }
+
+    /// Poll the fds threads are blocked on (`timeout_ms` as for `poll(2)`) and make the threads
+    /// whose fd is ready `Ready`.
+    #[cfg(unix)]
+    fn poll_fds(&mut self, timeout_ms: i32) {
+        for tid in self.poller.poll(timeout_ms) {
+            self.unblock(tid);
+        }
+    }
+
+    /// Make thread `tid` `Ready` if it is `Blocked`.
+    fn unblock(&mut self, tid: usize) {
+        if let Some(t) = self.threads.get_mut(tid) {
+            if t.state == ThreadState::Blocked {
+                t.state = ThreadState::Ready;
+            }
+        }
+    }
+
+    /// Threads currently blocked in `block_on_fd`.
+    #[cfg(unix)]
+    pub fn poller(&self) -> &Poller {
+        &self.poller
+    }
+
+    /// The timer behind `sleep_ticks`; `jiffies()` is the current virtual time in ticks.
+    #[cfg(feature = "timer")]
+    pub fn timer(&self) -> &timer_tick::solution::TimerDriver {
+        &self.timer
+    }
+
+    /// Switch counters accumulated over the scheduler's lifetime. `run_count` is cleared when
+    /// thread ids restart (see `reclaim`).
+    pub fn stats(&self) -> &SchedStats {
+        &self.stats
+    }
+
+    /// The last `TRACE_CAPACITY` switches, oldest first.
+    pub fn trace(&self) -> Vec<SwitchEvent> {
+        self.trace.iter().copied().collect()
+    }
+
+    /// The pool `spawn` takes stacks from, for its statistics.
+    pub fn stack_pool(&self) -> &StackPool {
+        &self.stacks
+    }
+
+    /// If every green thread has finished, return their stacks to the pool, drop them, and
+    /// `reset` the policy so ids start again from 1. Does nothing while any thread is alive: a
+    /// running thread's id must stay valid, and a finished thread's stack is only safe to reuse
+    /// once nothing runs on it any more.
+    fn reclaim(&mut self) {
+        if self.threads.len() == 1
+            || !self.threads[1..]
+                .iter()
+                .all(|t| t.state == ThreadState::Finished)
+        {
+            return;
+        }
+        for t in self.threads.drain(1..) {
+            if let Some(stack) = t.stack {
+                self.stacks.put(stack);
+            }
+        }
+        self.policy.reset();
+        self.stats.run_count.clear();
+    }
+
+    /// Snapshot of every thread's state and priority, indexed by thread id, for the policy.
+    fn thread_infos(&self) -> Vec<ThreadInfo> {
+        self.threads
+            .iter()
+            .map(|t| ThreadInfo {
+                state: t.state,
+                priority: t.priority,
+            })
+            .collect()
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskContext {
+    fn as_mut_ptr(&mut self) -> *mut TaskContext {
+        self as *mut TaskContext
+    }
+    fn as_ptr(&self) -> *const TaskContext {
+        self as *const TaskContext
+    }
+}
+
+/// The scheduler whose `run` is active on an OS thread.
+///
+/// Green threads call back into it (`yield_now`, `block_current`, ...) while `run` is suspended
+/// inside `schedule_next`, so it is reached through a pointer rather than a borrow.
+#[derive(Clone, Copy)]
+struct SchedulerHandle(NonNull<Scheduler>);
+
+thread_local! {
+    /// This OS thread's scheduler, while its `run` is active. Each OS thread has its own slot,
+    /// so schedulers on different threads never see each other.
+    static CURRENT_SCHEDULER: RefCell<Option<SchedulerHandle>> = const { RefCell::new(None) };
+}
+
+/// Keeps a scheduler installed until dropped, then restores the one it replaced.
+struct Installed {
+    previous: Option<SchedulerHandle>,
+}
+
+impl SchedulerHandle {
+    /// Make `sched` this OS thread's scheduler for as long as the returned guard lives.
+    fn install(sched: &mut Scheduler) -> Installed {
+        let handle = SchedulerHandle(NonNull::from(sched));
+        Installed {
+            previous: CURRENT_SCHEDULER.with(|c| c.replace(Some(handle))),
+        }
+    }
+}
+
+impl Drop for Installed {
+    fn drop(&mut self) {
+        CURRENT_SCHEDULER.with(|c| *c.borrow_mut() = self.previous);
+    }
+}
+
+/// Run `f` on this OS thread's scheduler; `None` outside `Scheduler::run`.
+///
+/// The `RefCell` is borrowed only to copy the handle out: `f` may switch to another green
+/// thread, which calls `with_scheduler` again before `f` returns.
+fn with_scheduler<R>(f: impl FnOnce(&mut Scheduler) -> R) -> Option<R> {
+    let handle = CURRENT_SCHEDULER.with(|c| *c.borrow())?;
+    // SAFETY: `run` installs the pointer from its `&mut self` and uninstalls it before it
+    // returns, and every green thread of that scheduler runs on this OS thread.
+    Some(f(unsafe { &mut *handle.0.as_ptr() }))
+}
+
+/// Current thread voluntarily yields; the scheduler will pick the next ready thread.
+pub fn yield_now() {
+    with_scheduler(Scheduler::schedule_next);
+}
+
+/// Mark current thread as `Finished` and switch to the next (called by `thread_wrapper` after the user entry returns).
+fn thread_finished() {
+    with_scheduler(|sched| {
+        sched.threads[sched.current].state = ThreadState::Finished;
+        sched.schedule_next();
+    });
+}
+
+/// Id of the running green thread (its index in the scheduler; the main thread is 0).
+pub fn current_thread() -> usize {
+    with_scheduler(|sched| sched.current).unwrap_or(0)
+}
+
+/// Mark the current thread `Blocked` and switch away. Returns once another thread has called
+/// `wake` on it and the scheduler picks it again.
+pub fn block_current() {
+    with_scheduler(|sched| {
+        sched.threads[sched.current].state = ThreadState::Blocked;
+        sched.schedule_next();
+    });
+}
+
+/// Make a `Blocked` thread `Ready` again. Threads in any other state are left untouched.
+pub fn wake(tid: usize) {
+    with_scheduler(|sched| sched.unblock(tid));
+}
+
+/// Block the current green thread for `ticks` timer ticks. The main thread (or a call outside
+/// `run`) does not sleep.
+#[cfg(feature = "timer")]
+pub fn sleep_ticks(ticks: u64) {
+    let queued = with_scheduler(|sched| {
+        if sched.current == 0 {
+            return false;
+        }
+        sched.timer.sleep_for(sched.current, ticks);
+        true
+    });
+    if queued == Some(true) {
+        block_current();
+    }
+}
+
+/// What a thread blocked on an fd waits for.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interest {
+    /// Data to read, or end of file (`POLLIN`).
+    Readable,
+    /// Room to write (`POLLOUT`).
+    Writable,
+}
+
+#[cfg(unix)]
+impl Interest {
+    fn events(self) -> libc::c_short {
+        match self {
+            Interest::Readable => libc::POLLIN,
+            Interest::Writable => libc::POLLOUT,
+        }
+    }
+}
+
+/// A minimal epoll: the (fd, interest) pairs green threads are blocked on, checked all at once
+/// with one `poll(2)`. Each registration is one-shot: it is removed when reported ready.
+#[cfg(unix)]
+#[derive(Debug, Default)]
+pub struct Poller {
+    waiters: Vec<FdWaiter>,
+}
+
+#[cfg(unix)]
+#[derive(Debug)]
+struct FdWaiter {
+    fd: RawFd,
+    interest: Interest,
+    tid: usize,
+}
+
+#[cfg(unix)]
+impl Poller {
+    /// Wake thread `tid` once `fd` is ready for `interest`.
+    pub fn register(&mut self, fd: RawFd, interest: Interest, tid: usize) {
+        self.waiters.push(FdWaiter { fd, interest, tid });
+    }
+
+    /// Number of registrations still waiting.
+    pub fn len(&self) -> usize {
+        self.waiters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waiters.is_empty()
+    }
+
+    /// `poll(2)` every registered fd, waiting at most `timeout_ms` (0: just check, -1: until one
+    /// is ready), and remove and return the thread ids whose fd is ready.
+    ///
+    /// 1. Build one `libc::pollfd` per waiter, `events` from its interest.
+    /// 2. Call `libc::poll`. On a timeout or an error (`EINTR`), nobody is ready: return empty.
+    /// 3. A waiter is ready if its `revents` is non-zero. That includes `POLLHUP` / `POLLERR`: the
+    ///    thread's next `read`/`write` then reports end of file or the error.
+    pub fn poll(&mut self, timeout_ms: i32) -> Vec<usize> {
+        if self.waiters.is_empty() {
+            return Vec::new();
+        }
+        let mut fds: Vec<libc::pollfd> = self
+            .waiters
+            .iter()
+            .map(|w| libc::pollfd {
+                fd: w.fd,
+                events: w.interest.events(),
+                revents: 0,
+            })
+            .collect();
+        let n = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout_ms) };
+        if n <= 0 {
+            // Timed out, or interrupted (`EINTR`): nobody is ready yet, the caller polls again.
+            return Vec::new();
+        }
+        let mut revents = fds.iter().map(|p| p.revents);
+        let mut woken = Vec::new();
+        self.waiters.retain(|w| {
+            if revents.next() == Some(0) {
+                return true;
+            }
+            woken.push(w.tid);
+            false
+        });
+        woken
+    }
+}
+
+/// Block the current green thread until `fd` is ready for `interest`, letting the others run.
+///
+/// Meant for fds in `O_NONBLOCK` mode, after a `read`/`write` returned `EAGAIN`; retry it after
+/// this returns. A green thread registers `(fd, interest, current_thread())` with the scheduler's
+/// `Poller` and calls `block_current()`. The main thread (or a call outside `run`) has nobody to
+/// switch to, and waits with a blocking `poll(2)` on `fd` instead.
+#[cfg(unix)]
+pub fn block_on_fd(fd: RawFd, interest: Interest) {
+    let queued = with_scheduler(|sched| {
+        if sched.current == 0 {
+            return false;
+        }
+        sched.poller.register(fd, interest, sched.current);
+        true
+    });
+    if queued == Some(true) {
+        block_current();
+    } else {
+        let mut poller = Poller::default();
+        poller.register(fd, interest, 0);
+        poller.poll(-1);
+    }
+}
+
+/// Bounded channel between green threads of one scheduler.
+///
+/// A full channel blocks `send`, an empty channel blocks `recv`; blocked threads wait in FIFO
+/// order in `senders` / `receivers`. Never hold the inner lock across `block_current()`: the
+/// thread that wakes us needs it.
+pub struct GreenChannel<T> {
+    inner: Mutex<ChannelInner<T>>,
+}
+
+struct ChannelInner<T> {
+    buf: VecDeque<T>,
+    capacity: usize,
+    /// Threads blocked in `send`, waiting for a free slot.
+    senders: VecDeque<usize>,
+    /// Threads blocked in `recv`, waiting for a value.
+    receivers: VecDeque<usize>,
+}
+
+impl<T> GreenChannel<T> {
+    /// Create a channel holding at most `capacity` (> 0) values.
+    pub const fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        Self {
+            inner: Mutex::new(ChannelInner {
+                buf: VecDeque::new(),
+                capacity,
+                senders: VecDeque::new(),
+                receivers: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Send `value`, blocking the current green thread while the channel is full.
+    ///
+    /// Loop:
+    /// 1. Lock `inner`. If `buf.len() < capacity`: push the value, `wake` the first waiting receiver (if any), return.
+    /// 2. Otherwise push `current_thread()` onto `senders`, release the lock, and call `block_current()`; retry after waking.
+    pub fn send(&self, value: T) {
+        let mut value = Some(value);
+        loop {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.buf.len() < inner.capacity {
+                inner.buf.push_back(value.take().unwrap());
+                let r = inner.receivers.pop_front();
+                drop(inner);
+                if let Some(r) = r {
+                    wake(r);
+                }
+                return;
+            }
+            inner.senders.push_back(current_thread());
+            drop(inner);
+            block_current();
+        }
+    }
+
+    /// Receive the oldest value, blocking the current green thread while the channel is empty.
+    ///
+    /// Loop:
+    /// 1. Lock `inner`. If `buf` has a value: pop it, `wake` the first waiting sender (if any), return it.
+    /// 2. Otherwise push `current_thread()` onto `receivers`, release the lock, and call `block_current()`; retry after waking.
+    pub fn recv(&self) -> T {
+        loop {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(v) = inner.buf.pop_front() {
+                let s = inner.senders.pop_front();
+                drop(inner);
+                if let Some(s) = s {
+                    wake(s);
+                }
+                return v;
+            }
+            inner.receivers.push_back(current_thread());
+            drop(inner);
+            block_current();
+        }
+    }
+
+    /// Number of values currently buffered.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
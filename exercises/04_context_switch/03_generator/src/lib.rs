@@ -0,0 +1,261 @@
+//! # Generators on Stackful Coroutines (riscv64, x86_64, aarch64)
+//!
+//! In this exercise, you turn a bare context switch into a generator: a function running on its
+//! own stack that hands values back to its caller one at a time.
+//! The exercise targets **riscv64**; run with the repo's normal flow (`./check.sh` / `oscamp`) or natively on riscv64.
+//! Stacks, initial contexts and `switch_context` come from `02_green_threads` (finish that one
+//! first), so the crate also runs on the x86_64 / aarch64 hosts that one supports.
+//!
+//! ## Key Concepts
+//! - Asymmetric coroutines: `resume()` switches *into* the generator, `yield_value(v)` switches *back*
+//! - Passing data across a context switch through shared memory (the generator's `slot`)
+//! - Each generator owns a stack and two saved contexts (its own and its caller's)
+//!
+//! ## Design
+//! ```text
+//! caller                          generator stack
+//! ------                          ---------------
+//! resume() --switch_context-->    body runs ...
+//!                                 y.yield_value(v): slot = Some(v)
+//!          <--switch_context--    (suspended here)
+//! returns slot.take() = Some(v)
+//! resume() --switch_context-->    ... continues after yield_value
+//!                                 body returns: state = Done
+//!          <--switch_context--
+//! returns None (and forever after)
+//! ```
+//!
+//! All state lives in a heap-allocated `Inner<Y>` so its address stays fixed while contexts point
+//! into it. The first switch lands in `trampoline::<Y>`, which fetches the `Inner` pointer from
+//! `STARTING`, runs the body, marks the generator `Done` and switches back for the last time.
+//! Generators nest: a body may create and resume other generators.
+//!
+//! The body must not panic (unwinding cannot cross the hand-made stack), and a generator dropped
+//! before it is `Done` never finishes its body, so values captured by the body are leaked.
+
+use std::cell::Cell;
+
+use green_threads::{switch_context, Stack, TaskContext};
+
+/// Per-generator stack size.
+const STACK_SIZE: usize = 1024 * 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenState {
+    /// Created, body not started yet.
+    Created,
+    /// Stopped inside `yield_value`, waiting for the next `resume`.
+    Suspended,
+    /// Currently executing (we are inside `resume`).
+    Running,
+    /// Body returned; `resume` yields `None` from now on.
+    Done,
+}
+
+type Body<Y> = Box<dyn FnOnce(&Yielder<Y>)>;
+
+struct Inner<Y> {
+    /// Saved registers of the generator while it is suspended.
+    gen_ctx: TaskContext,
+    /// Saved registers of whoever called `resume`, while the generator runs.
+    caller_ctx: TaskContext,
+    state: GenState,
+    /// Value handed from `yield_value` to `resume`.
+    slot: Option<Y>,
+    /// Taken once by `trampoline` on the first `resume`.
+    body: Option<Body<Y>>,
+    _stack: Stack,
+}
+
+thread_local! {
+    /// `Inner<Y>` of the generator being started; set by `resume` right before the first switch
+    /// and taken by `trampoline`. (It cannot take arguments: `switch_context` enters it by
+    /// returning into it, not by calling it.)
+    static STARTING: Cell<*mut ()> = const { Cell::new(std::ptr::null_mut()) };
+}
+
+/// First code run on a generator's stack (the entry of its initial context): run the body, mark `Done`, switch
+/// back to the caller for good.
+extern "C" fn trampoline<Y>() {
+    let inner = STARTING.with(|s| s.replace(std::ptr::null_mut())) as *mut Inner<Y>;
+    unsafe {
+        let body = (*inner).body.take().expect("generator started twice");
+        body(&Yielder { inner });
+        (*inner).state = GenState::Done;
+        switch_context(&mut (*inner).gen_ctx, &(*inner).caller_ctx);
+    }
+    unreachable!("a finished generator was resumed");
+}
+
+/// Handle passed to the generator body for yielding values back to `resume`.
+pub struct Yielder<Y> {
+    inner: *mut Inner<Y>,
+}
+
+impl<Y> Yielder<Y> {
+    /// Hand `value` to the caller of `resume` and suspend until the next `resume`.
+    ///
+    /// 1. Store `value` in `slot` and set the state to `Suspended`.
+    /// 2. `switch_context(&mut gen_ctx, &caller_ctx)`; execution continues here on the next `resume`.
+    pub fn yield_value(&self, value: Y) {
+        todo!("slot = Some(value), state = Suspended, switch from gen_ctx to caller_ctx")
+    }
+}
+
+/// A generator producing values of type `Y`.
+pub struct Generator<Y> {
+    inner: Box<Inner<Y>>,
+}
+
+impl<Y: 'static> Generator<Y> {
+    /// Create a generator that will run `body` on its own stack when first resumed.
+    ///
+    /// 1. Map a guarded `Stack` of `STACK_SIZE` bytes.
+    /// 2. `gen_ctx = TaskContext::new_task(&stack, trampoline::<Y>)`, so the first switch to it
+    ///    enters `trampoline` on the new stack.
+    /// 3. Box an `Inner` in state `Created` holding the boxed body and the stack.
+    pub fn new(body: impl FnOnce(&Yielder<Y>) + 'static) -> Self {
+        todo!("Stack::new(STACK_SIZE), gen_ctx = TaskContext::new_task(&stack, trampoline::<Y>), Box<Inner> in state Created")
+    }
+}
+
+impl<Y> Generator<Y> {
+    /// Run the generator until it yields (`Some(value)`) or finishes (`None`).
+    ///
+    /// 1. If `Done`, return `None` without switching.
+    /// 2. If `Created`, store the `Inner` pointer in `STARTING` for `trampoline`.
+    /// 3. Set state `Running`, `switch_context(&mut caller_ctx, &gen_ctx)`.
+    /// 4. Back here after a `yield_value` or completion: return `slot.take()`.
+    pub fn resume(&mut self) -> Option<Y> {
+        todo!("None if Done; set STARTING if Created; Running; switch caller_ctx -> gen_ctx; return slot.take()")
+    }
+
+    pub fn state(&self) -> GenState {
+        self.inner.state
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.inner.state == GenState::Done
+    }
+}
+
+impl<Y> Iterator for Generator<Y> {
+    type Item = Y;
+
+    fn next(&mut self) -> Option<Y> {
+        self.resume()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const HINTS: Hints = &[
+        (
+            "test_fibonacci",
+            "resume switches into the generator and, once yield_value switches back, takes the value from the slot",
+        ),
+        (
+            "test_empty_generator",
+            "a body that returns without yielding: trampoline marks Done and switches back, so resume returns None",
+        ),
+        (
+            "test_body_runs_lazily_between_resumes",
+            "new() must not run the body: it only prepares gen_ctx so the first resume enters trampoline",
+        ),
+        (
+            "test_nested_generators",
+            "save the caller's registers in caller_ctx of this generator, so a generator resumed inside another returns to the right place",
+        ),
+    ];
+
+    fn fibonacci(n: usize) -> Generator<u64> {
+        Generator::new(move |y| {
+            let (mut a, mut b) = (0u64, 1u64);
+            for _ in 0..n {
+                y.yield_value(a);
+                (a, b) = (b, a + b);
+            }
+        })
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let mut fib = fibonacci(10);
+        assert_eq_with_hint!(fib.state(), GenState::Created);
+        assert_eq_with_hint!(fib.resume(), Some(0));
+        assert_eq_with_hint!(fib.state(), GenState::Suspended);
+        let rest: Vec<u64> = fib.by_ref().collect();
+        assert_eq_with_hint!(rest, vec![1, 1, 2, 3, 5, 8, 13, 21, 34]);
+        assert_with_hint!(fib.is_done());
+        assert_eq_with_hint!(fib.resume(), None, "a finished generator stays finished");
+    }
+
+    #[test]
+    fn test_empty_generator() {
+        let mut g: Generator<u32> = Generator::new(|_| {});
+        assert_eq_with_hint!(g.resume(), None);
+        assert_with_hint!(g.is_done());
+    }
+
+    #[test]
+    fn test_body_runs_lazily_between_resumes() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let inner_log = log.clone();
+        let mut g = Generator::new(move |y| {
+            inner_log.borrow_mut().push("start");
+            y.yield_value(1);
+            inner_log.borrow_mut().push("after 1");
+            y.yield_value(2);
+            inner_log.borrow_mut().push("end");
+        });
+        assert_with_hint!(
+            log.borrow().is_empty(),
+            "nothing runs before the first resume"
+        );
+        assert_eq_with_hint!(g.resume(), Some(1));
+        assert_eq_with_hint!(*log.borrow(), vec!["start"]);
+        assert_eq_with_hint!(g.resume(), Some(2));
+        assert_eq_with_hint!(*log.borrow(), vec!["start", "after 1"]);
+        assert_eq_with_hint!(g.resume(), None);
+        assert_eq_with_hint!(*log.borrow(), vec!["start", "after 1", "end"]);
+    }
+
+    #[test]
+    fn test_nested_generators() {
+        // The outer generator drives an inner one from its own stack and re-yields its values.
+        let mut outer = Generator::new(|y| {
+            let inner = fibonacci(5);
+            for v in inner {
+                y.yield_value(v * 10);
+            }
+            y.yield_value(999);
+        });
+        let got: Vec<u64> = outer.by_ref().collect();
+        assert_eq_with_hint!(got, vec![0, 10, 10, 20, 30, 999]);
+        assert_with_hint!(outer.is_done());
+    }
+
+    #[test]
+    fn test_interleaved_generators() {
+        let mut evens = Generator::new(|y| (0..5).for_each(|i| y.yield_value(i * 2)));
+        let mut odds = Generator::new(|y| (0..5).for_each(|i| y.yield_value(i * 2 + 1)));
+        let mut merged = Vec::new();
+        while let (Some(a), Some(b)) = (evens.resume(), odds.resume()) {
+            merged.push(a);
+            merged.push(b);
+        }
+        assert_eq!(merged, (0..10).collect::<Vec<i32>>());
+    }
+}
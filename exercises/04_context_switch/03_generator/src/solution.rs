@@ -0,0 +1,174 @@
+//! # Generators on Stackful Coroutines (riscv64, x86_64, aarch64)
+//!
+//! In this exercise, you turn a bare context switch into a generator: a function running on its
+//! own stack that hands values back to its caller one at a time.
+//! The exercise targets **riscv64**; run with the repo's normal flow (`./check.sh` / `oscamp`) or natively on riscv64.
+//! Stacks, initial contexts and `switch_context` come from `02_green_threads` (finish that one
+//! first), so the crate also runs on the x86_64 / aarch64 hosts that one supports.
+//!
+//! ## Key Concepts
+//! - Asymmetric coroutines: `resume()` switches *into* the generator, `yield_value(v)` switches *back*
+//! - Passing data across a context switch through shared memory (the generator's `slot`)
+//! - Each generator owns a stack and two saved contexts (its own and its caller's)
+//!
+//! ## Design
+//! ```text
+//! caller                          generator stack
+//! ------                          ---------------
+//! resume() --switch_context-->    body runs ...
+//!                                 y.yield_value(v): slot = Some(v)
+//!          <--switch_context--    (suspended here)
+//! returns slot.take() = Some(v)
+//! resume() --switch_context-->    ... continues after yield_value
+//!                                 body returns: state = Done
+//!          <--switch_context--
+//! returns None (and forever after)
+//! ```
+//!
+//! All state lives in a heap-allocated `Inner<Y>` so its address stays fixed while contexts point
+//! into it. The first switch lands in `trampoline::<Y>`, which fetches the `Inner` pointer from
+//! `STARTING`, runs the body, marks the generator `Done` and switches back for the last time.
+//! Generators nest: a body may create and resume other generators.
+//!
+//! The body must not panic (unwinding cannot cross the hand-made stack), and a generator dropped
+//! before it is `Done` never finishes its body, so values captured by the body are leaked.
+
+use std::cell::Cell;
+
+use green_threads::solution::{switch_context, Stack, TaskContext};
+
+/// Per-generator stack size.
+const STACK_SIZE: usize = 1024 * 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenState {
+    /// Created, body not started yet.
+    Created,
+    /// Stopped inside `yield_value`, waiting for the next `resume`.
+    Suspended,
+    /// Currently executing (we are inside `resume`).
+    Running,
+    /// Body returned; `resume` yields `None` from now on.
+    Done,
+}
+
+type Body<Y> = Box<dyn FnOnce(&Yielder<Y>)>;
+
+struct Inner<Y> {
+    /// Saved registers of the generator while it is suspended.
+    gen_ctx: TaskContext,
+    /// Saved registers of whoever called `resume`, while the generator runs.
+    caller_ctx: TaskContext,
+    state: GenState,
+    /// Value handed from `yield_value` to `resume`.
+    slot: Option<Y>,
+    /// Taken once by `trampoline` on the first `resume`.
+    body: Option<Body<Y>>,
+    _stack: Stack,
+}
+
+thread_local! {
+    /// `Inner<Y>` of the generator being started; set by `resume` right before the first switch
+    /// and taken by `trampoline`. (It cannot take arguments: `switch_context` enters it by
+    /// returning into it, not by calling it.)
+    static STARTING: Cell<*mut ()> = const { Cell::new(std::ptr::null_mut()) };
+}
+
+/// First code run on a generator's stack (the entry of its initial context): run the body, mark `Done`, switch
+/// back to the caller for good.
+extern "C" fn trampoline<Y>() {
+    let inner = STARTING.with(|s| s.replace(std::ptr::null_mut())) as *mut Inner<Y>;
+    unsafe {
+        let body = (*inner).body.take().expect("generator started twice");
+        body(&Yielder { inner });
+        (*inner).state = GenState::Done;
+        switch_context(&mut (*inner).gen_ctx, &(*inner).caller_ctx);
+    }
+    unreachable!("a finished generator was resumed");
+}
+
+/// Handle passed to the generator body for yielding values back to `resume`.
+pub struct Yielder<Y> {
+    inner: *mut Inner<Y>,
+}
+
+impl<Y> Yielder<Y> {
+    /// Hand `value` to the caller of `resume` and suspend until the next `resume`.
+    ///
+    /// 1. Store `value` in `slot` and set the state to `Suspended`.
+    /// 2. `switch_context(&mut gen_ctx, &caller_ctx)`; execution continues here on the next `resume`.
+    pub fn yield_value(&self, value: Y) {
+        unsafe {
+            let inner = self.inner;
+            (*inner).slot = Some(value);
+            (*inner).state = GenState::Suspended;
+            switch_context(&mut (*inner).gen_ctx, &(*inner).caller_ctx);
+        }
+    }
+}
+
+/// A generator producing values of type `Y`.
+pub struct Generator<Y> {
+    inner: Box<Inner<Y>>,
+}
+
+impl<Y: 'static> Generator<Y> {
+    /// Create a generator that will run `body` on its own stack when first resumed.
+    ///
+    /// 1. Map a guarded `Stack` of `STACK_SIZE` bytes.
+    /// 2. `gen_ctx = TaskContext::new_task(&stack, trampoline::<Y>)`, so the first switch to it
+    ///    enters `trampoline` on the new stack.
+    /// 3. Box an `Inner` in state `Created` holding the boxed body and the stack.
+    pub fn new(body: impl FnOnce(&Yielder<Y>) + 'static) -> Self {
+        let stack = Stack::new(STACK_SIZE).expect("mmap of the generator stack failed");
+        let gen_ctx = TaskContext::new_task(&stack, trampoline::<Y>);
+        Self {
+            inner: Box::new(Inner {
+                gen_ctx,
+                caller_ctx: TaskContext::default(),
+                state: GenState::Created,
+                slot: None,
+                body: Some(Box::new(body)),
+                _stack: stack,
+            }),
+        }
+    }
+}
+
+impl<Y> Generator<Y> {
+    /// Run the generator until it yields (`Some(value)`) or finishes (`None`).
+    ///
+    /// 1. If `Done`, return `None` without switching.
+    /// 2. If `Created`, store the `Inner` pointer in `STARTING` for `trampoline`.
+    /// 3. Set state `Running`, `switch_context(&mut caller_ctx, &gen_ctx)`.
+    /// 4. Back here after a `yield_value` or completion: return `slot.take()`.
+    pub fn resume(&mut self) -> Option<Y> {
+        let inner: *mut Inner<Y> = &mut *self.inner;
+        unsafe {
+            match (*inner).state {
+                GenState::Done => return None,
+                GenState::Created => STARTING.with(|s| s.set(inner as *mut ())),
+                _ => {}
+            }
+            (*inner).state = GenState::Running;
+            switch_context(&mut (*inner).caller_ctx, &(*inner).gen_ctx);
+            (*inner).slot.take()
+        }
+    }
+
+    pub fn state(&self) -> GenState {
+        self.inner.state
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.inner.state == GenState::Done
+    }
+}
+
+impl<Y> Iterator for Generator<Y> {
+    type Item = Y;
+
+    fn next(&mut self) -> Option<Y> {
+        self.resume()
+    }
+}
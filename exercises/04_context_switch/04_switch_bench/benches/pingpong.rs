@@ -0,0 +1,12 @@
+//! `cargo bench -p switch_bench`: ping-pong the three ways and print a comparison table.
+
+use switch_bench::*;
+
+fn main() {
+    let results = [
+        bench_coroutines(1_000_000),
+        bench_os_threads(50_000),
+        bench_tokio_tasks(200_000),
+    ];
+    print!("{}", format_table(&results));
+}
@@ -0,0 +1,211 @@
+//! # Context Switch Microbenchmark (riscv64, x86_64, aarch64)
+//!
+//! In this exercise, you measure what a switch between two execution contexts costs in three
+//! models, to see in numbers why user-level threads exist:
+//!
+//! - two **coroutines** switching with `switch_context` (no kernel involved)
+//! - two **OS threads** handing a token back and forth over `std::sync::mpsc` channels
+//! - two **tokio tasks** doing the same over `tokio::sync::mpsc` on a current-thread runtime
+//!
+//! Each benchmark plays `rounds` round trips of ping-pong; one round trip is two switches.
+//! `cargo bench -p switch_bench` runs all three and prints a table (see `benches/pingpong.rs`).
+//!
+//! ## Backends
+//! The coroutines switch with `switch_context` of `02_green_threads` (finish that one first): the
+//! hand-written one for **riscv64**, or its x86_64 / aarch64 counterpart on other hosts. Either
+//! way a switch is a few loads and stores, with no system call.
+//!
+//! ## Key Concepts
+//! - Cost of a user-level switch (save/restore a few registers) vs. a kernel thread switch
+//!   (syscall, scheduler, cache effects) vs. an async task switch (poll + waker + queue)
+//! - Measuring with `std::time::Instant` and reporting switches per second
+
+use std::cell::Cell;
+use std::time::Duration;
+
+pub use green_threads::{switch_context, Stack, TaskContext};
+
+/// Stack size of the benchmark coroutine.
+const STACK_SIZE: usize = 1024 * 64;
+
+thread_local! {
+    /// `(main, coroutine)` contexts of the ping-pong running on this OS thread, so that `pong`
+    /// (which cannot take arguments) knows where to switch back to.
+    static PING_PONG: Cell<(*mut TaskContext, *mut TaskContext)> =
+        const { Cell::new((std::ptr::null_mut(), std::ptr::null_mut())) };
+    /// Times `pong` has been entered or resumed on this OS thread.
+    static PONGS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// One benchmark run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub name: &'static str,
+    /// Context switches performed (two per round trip).
+    pub switches: u64,
+    pub elapsed: Duration,
+}
+
+impl BenchResult {
+    pub fn switches_per_sec(&self) -> f64 {
+        self.switches as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+
+    pub fn ns_per_switch(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.switches.max(1) as f64
+    }
+}
+
+/// Coroutine side of the ping-pong: forever, count one pong in `PONGS` and switch back to main.
+///
+/// Read both context pointers from `PING_PONG`, then `loop { PONGS += 1; switch_context(co, main) }`.
+extern "C" fn pong() {
+    todo!("loop forever: PONGS += 1, then switch_context(coroutine ctx, main ctx) (pointers from PING_PONG)")
+}
+
+/// Ping-pong `rounds` times between the caller and a coroutine running `pong`.
+///
+/// 1. Map a `STACK_SIZE` `Stack`, build `co = TaskContext::new_task(&stack, pong)` and a
+///    default `main` context; store both pointers in `PING_PONG` and reset `PONGS` to 0.
+/// 2. Start an `Instant`, then `rounds` times `switch_context(&mut main, &co)`.
+/// 3. Check `PONGS == rounds` and return `name: "coroutine"`, `switches: 2 * rounds`. (The coroutine is left suspended
+///    in its loop; its stack is freed when this function returns, which is fine since it is never
+///    resumed again.)
+pub fn bench_coroutines(rounds: u64) -> BenchResult {
+    todo!("build main/coroutine contexts, set PING_PONG, time `rounds` switch_context(main, co) calls")
+}
+
+/// Ping-pong `rounds` times between two OS threads over a pair of `std::sync::mpsc` channels.
+///
+/// Spawn a thread that `rounds` times receives on `ping` and replies on `pong`; on the calling
+/// thread time `rounds` iterations of send-ping / receive-pong, then join. `name: "os_thread"`, `switches: 2 * rounds`.
+pub fn bench_os_threads(rounds: u64) -> BenchResult {
+    todo!("two std::sync::mpsc channels, spawned echo thread, time `rounds` send/recv round trips")
+}
+
+/// Ping-pong `rounds` times between two tokio tasks on a current-thread runtime.
+///
+/// Build a `tokio::runtime::Builder::new_current_thread()` runtime; inside `block_on`, spawn an
+/// echo task (`recv` on ping, `send` on pong, `rounds` times, over `tokio::sync::mpsc::channel(1)`)
+/// and time `rounds` send/recv round trips from the main task.
+/// `name: "tokio_task"`, `switches: 2 * rounds`.
+pub fn bench_tokio_tasks(rounds: u64) -> BenchResult {
+    todo!("current-thread runtime, two tokio mpsc channels, spawned echo task, time `rounds` round trips")
+}
+
+/// Render results as a table, fastest first, with each row's slowdown relative to the fastest.
+pub fn format_table(results: &[BenchResult]) -> String {
+    let mut sorted: Vec<&BenchResult> = results.iter().collect();
+    sorted.sort_by(|a, b| a.ns_per_switch().total_cmp(&b.ns_per_switch()));
+    let best = sorted.first().map_or(1.0, |r| r.ns_per_switch());
+
+    let mut out = format!(
+        "{:<12} {:>12} {:>14} {:>12} {:>9}\n",
+        "model", "switches", "switches/s", "ns/switch", "relative"
+    );
+    for r in sorted {
+        out += &format!(
+            "{:<12} {:>12} {:>14.0} {:>12.1} {:>8.1}x\n",
+            r.name,
+            r.switches,
+            r.switches_per_sec(),
+            r.ns_per_switch(),
+            r.ns_per_switch() / best
+        );
+    }
+    out
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_coroutine_switch_count",
+            "pong must switch back to the main context after every PONGS += 1, and the result reports switches: 2 * rounds",
+        ),
+        (
+            "test_os_thread_switch_count",
+            "one round trip is a ping and a pong, so report switches: 2 * rounds",
+        ),
+        (
+            "test_tokio_switch_count",
+            "spawn the echo task on the same current-thread runtime and report switches: 2 * rounds",
+        ),
+        (
+            "test_coroutine_at_least_as_fast_as_os_threads",
+            "build the coroutine context and stack once, outside the timed loop; each round trip is then just two switch_context calls",
+        ),
+    ];
+
+    #[test]
+    fn test_coroutine_switch_count() {
+        let r = bench_coroutines(1000);
+        assert_eq_with_hint!(r.name, "coroutine");
+        assert_eq_with_hint!(r.switches, 2000);
+        // Running it again on the same OS thread starts from a clean slate.
+        assert_eq_with_hint!(bench_coroutines(10).switches, 20);
+    }
+
+    #[test]
+    fn test_os_thread_switch_count() {
+        let r = bench_os_threads(100);
+        assert_eq_with_hint!(r.name, "os_thread");
+        assert_eq_with_hint!(r.switches, 200);
+    }
+
+    #[test]
+    fn test_tokio_switch_count() {
+        let r = bench_tokio_tasks(100);
+        assert_eq_with_hint!(r.name, "tokio_task");
+        assert_eq_with_hint!(r.switches, 200);
+    }
+
+    #[test]
+    fn test_coroutine_at_least_as_fast_as_os_threads() {
+        // Take the best of a few runs to be robust against a noisy machine.
+        let best = |f: fn(u64) -> BenchResult, rounds| {
+            (0..5)
+                .map(|_| f(rounds).ns_per_switch())
+                .fold(f64::INFINITY, f64::min)
+        };
+        let co = best(bench_coroutines, 20_000);
+        let os = best(bench_os_threads, 2_000);
+        // A coroutine switch is typically 100x cheaper; allow 2x the other way for a busy machine.
+        assert_with_hint!(
+            co <= 2.0 * os,
+            "coroutine switch ({co:.0} ns) should not be slower than an OS thread switch ({os:.0} ns)"
+        );
+    }
+
+    #[test]
+    fn test_format_table() {
+        let ms = Duration::from_millis;
+        let table = format_table(&[
+            BenchResult {
+                name: "slow",
+                switches: 1000,
+                elapsed: ms(10),
+            },
+            BenchResult {
+                name: "fast",
+                switches: 1000,
+                elapsed: ms(1),
+            },
+        ]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("model"));
+        assert!(lines[1].starts_with("fast") && lines[1].ends_with("1.0x"));
+        assert!(lines[2].starts_with("slow") && lines[2].ends_with("10.0x"));
+    }
+}
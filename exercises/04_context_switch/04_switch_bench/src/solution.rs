@@ -0,0 +1,180 @@
+//! # Context Switch Microbenchmark (riscv64, x86_64, aarch64)
+//!
+//! In this exercise, you measure what a switch between two execution contexts costs in three
+//! models, to see in numbers why user-level threads exist:
+//!
+//! - two **coroutines** switching with `switch_context` (no kernel involved)
+//! - two **OS threads** handing a token back and forth over `std::sync::mpsc` channels
+//! - two **tokio tasks** doing the same over `tokio::sync::mpsc` on a current-thread runtime
+//!
+//! Each benchmark plays `rounds` round trips of ping-pong; one round trip is two switches.
+//! `cargo bench -p switch_bench` runs all three and prints a table (see `benches/pingpong.rs`).
+//!
+//! ## Backends
+//! The coroutines switch with `switch_context` of `02_green_threads` (finish that one first): the
+//! hand-written one for **riscv64**, or its x86_64 / aarch64 counterpart on other hosts. Either
+//! way a switch is a few loads and stores, with no system call.
+//!
+//! ## Key Concepts
+//! - Cost of a user-level switch (save/restore a few registers) vs. a kernel thread switch
+//!   (syscall, scheduler, cache effects) vs. an async task switch (poll + waker + queue)
+//! - Measuring with `std::time::Instant` and reporting switches per second
+
+use std::cell::Cell;
+pub(crate) use std::time::Duration;
+
+pub use green_threads::solution::{switch_context, Stack, TaskContext};
+
+/// Stack size of the benchmark coroutine.
+const STACK_SIZE: usize = 1024 * 64;
+
+thread_local! {
+    /// `(main, coroutine)` contexts of the ping-pong running on this OS thread, so that `pong`
+    /// (which cannot take arguments) knows where to switch back to.
+    static PING_PONG: Cell<(*mut TaskContext, *mut TaskContext)> =
+        const { Cell::new((std::ptr::null_mut(), std::ptr::null_mut())) };
+    /// Times `pong` has been entered or resumed on this OS thread.
+    static PONGS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// One benchmark run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub name: &'static str,
+    /// Context switches performed (two per round trip).
+    pub switches: u64,
+    pub elapsed: Duration,
+}
+
+impl BenchResult {
+    pub fn switches_per_sec(&self) -> f64 {
+        self.switches as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+
+    pub fn ns_per_switch(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.switches.max(1) as f64
+    }
+}
+
+/// Coroutine side of the ping-pong: forever, count one pong in `PONGS` and switch back to main.
+///
+/// Read both context pointers from `PING_PONG`, then `loop { PONGS += 1; switch_context(co, main) }`.
+extern "C" fn pong() {
+    let (main, co) = PING_PONG.with(Cell::get);
+    loop {
+        PONGS.with(|p| p.set(p.get() + 1));
+        unsafe { switch_context(&mut *co, &*main) };
+    }
+}
+
+/// Ping-pong `rounds` times between the caller and a coroutine running `pong`.
+///
+/// 1. Map a `STACK_SIZE` `Stack`, build `co = TaskContext::new_task(&stack, pong)` and a
+///    default `main` context; store both pointers in `PING_PONG` and reset `PONGS` to 0.
+/// 2. Start an `Instant`, then `rounds` times `switch_context(&mut main, &co)`.
+/// 3. Check `PONGS == rounds` and return `name: "coroutine"`, `switches: 2 * rounds`. (The coroutine is left suspended
+///    in its loop; its stack is freed when this function returns, which is fine since it is never
+///    resumed again.)
+pub fn bench_coroutines(rounds: u64) -> BenchResult {
+    let stack = Stack::new(STACK_SIZE).expect("mmap of the coroutine stack failed");
+    let mut co = TaskContext::new_task(&stack, pong);
+    let mut main = TaskContext::default();
+    PING_PONG.with(|p| p.set((&mut main, &mut co)));
+    PONGS.with(|p| p.set(0));
+    let start = std::time::Instant::now();
+    for _ in 0..rounds {
+        unsafe { switch_context(&mut main, &co) };
+    }
+    let elapsed = start.elapsed();
+    assert_eq!(PONGS.with(Cell::get), rounds);
+    BenchResult {
+        name: "coroutine",
+        switches: 2 * rounds,
+        elapsed,
+    }
+}
+
+/// Ping-pong `rounds` times between two OS threads over a pair of `std::sync::mpsc` channels.
+///
+/// Spawn a thread that `rounds` times receives on `ping` and replies on `pong`; on the calling
+/// thread time `rounds` iterations of send-ping / receive-pong, then join. `name: "os_thread"`, `switches: 2 * rounds`.
+pub fn bench_os_threads(rounds: u64) -> BenchResult {
+    let (ping_tx, ping_rx) = std::sync::mpsc::channel::<u64>();
+    let (pong_tx, pong_rx) = std::sync::mpsc::channel::<u64>();
+    let echo = std::thread::spawn(move || {
+        for _ in 0..rounds {
+            let v = ping_rx.recv().unwrap();
+            pong_tx.send(v).unwrap();
+        }
+    });
+    let start = std::time::Instant::now();
+    for i in 0..rounds {
+        ping_tx.send(i).unwrap();
+        pong_rx.recv().unwrap();
+    }
+    let elapsed = start.elapsed();
+    echo.join().unwrap();
+    BenchResult {
+        name: "os_thread",
+        switches: 2 * rounds,
+        elapsed,
+    }
+}
+
+/// Ping-pong `rounds` times between two tokio tasks on a current-thread runtime.
+///
+/// Build a `tokio::runtime::Builder::new_current_thread()` runtime; inside `block_on`, spawn an
+/// echo task (`recv` on ping, `send` on pong, `rounds` times, over `tokio::sync::mpsc::channel(1)`)
+/// and time `rounds` send/recv round trips from the main task.
+/// `name: "tokio_task"`, `switches: 2 * rounds`.
+pub fn bench_tokio_tasks(rounds: u64) -> BenchResult {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let elapsed = rt.block_on(async move {
+        let (ping_tx, mut ping_rx) = tokio::sync::mpsc::channel::<u64>(1);
+        let (pong_tx, mut pong_rx) = tokio::sync::mpsc::channel::<u64>(1);
+        let echo = tokio::spawn(async move {
+            for _ in 0..rounds {
+                let v = ping_rx.recv().await.unwrap();
+                pong_tx.send(v).await.unwrap();
+            }
+        });
+        let start = std::time::Instant::now();
+        for i in 0..rounds {
+            ping_tx.send(i).await.unwrap();
+            pong_rx.recv().await.unwrap();
+        }
+        let elapsed = start.elapsed();
+        echo.await.unwrap();
+        elapsed
+    });
+    BenchResult {
+        name: "tokio_task",
+        switches: 2 * rounds,
+        elapsed,
+    }
+}
+
+/// Render results as a table, fastest first, with each row's slowdown relative to the fastest.
+pub fn format_table(results: &[BenchResult]) -> String {
+    let mut sorted: Vec<&BenchResult> = results.iter().collect();
+    sorted.sort_by(|a, b| a.ns_per_switch().total_cmp(&b.ns_per_switch()));
+    let best = sorted.first().map_or(1.0, |r| r.ns_per_switch());
+
+    let mut out = format!(
+        "{:<12} {:>12} {:>14} {:>12} {:>9}\n",
+        "model", "switches", "switches/s", "ns/switch", "relative"
+    );
+    for r in sorted {
+        out += &format!(
+            "{:<12} {:>12} {:>14.0} {:>12.1} {:>8.1}x\n",
+            r.name,
+            r.switches,
+            r.switches_per_sec(),
+            r.ns_per_switch(),
+            r.ns_per_switch() / best
+        );
+    }
+    out
+}
@@ -49,6 +49,12 @@ impl YieldOnce {
     }
 }
 
+impl Default for YieldOnce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // TODO: Implement Future trait for YieldOnce
 // - Output type is ()
 // - First poll: set yielded = true, wake waker, return Pending
@@ -61,20 +67,39 @@ impl Future for YieldOnce {
     }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_countdown_zero",
+            "a CountDown that starts at 0 is Ready(\"liftoff!\") on its first poll",
+        ),
+        (
+            "test_countdown_three",
+            "before returning Pending, call cx.waker().wake_by_ref(): otherwise nobody polls the future again and the test hangs",
+        ),
+    ];
 
     #[tokio::test]
     async fn test_countdown_zero() {
         let result = CountDown::new(0).await;
-        assert_eq!(result, "liftoff!");
+        assert_eq_with_hint!(result, "liftoff!");
     }
 
     #[tokio::test]
     async fn test_countdown_three() {
         let result = CountDown::new(3).await;
-        assert_eq!(result, "liftoff!");
+        assert_eq_with_hint!(result, "liftoff!");
     }
 
     #[tokio::test]
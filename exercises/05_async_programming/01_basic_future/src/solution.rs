@@ -0,0 +1,80 @@
+//! # Manual Future Implementation
+//!
+//! In this exercise, you will manually implement the `Future` trait for custom types to understand the core mechanism of asynchronous runtime.
+//!
+//! ## Concepts
+//! - `std::future::Future` trait
+//! - `Poll::Ready` and `Poll::Pending`
+//! - The role of `Waker`: notifying the runtime to poll again
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Countdown Future: decrements count by 1 each time it's polled,
+/// returns `"liftoff!"` when count reaches 0.
+pub struct CountDown {
+    pub count: u32,
+}
+
+impl CountDown {
+    pub fn new(count: u32) -> Self {
+        Self { count }
+    }
+}
+
+// TODO: Implement Future trait for CountDown
+// - Output type is &'static str
+// - Each poll: if count == 0, return Poll::Ready("liftoff!")
+// - Otherwise count -= 1, call cx.waker().wake_by_ref(), return Poll::Pending
+//
+// Hint: Use `self.get_mut()` to get `&mut Self` (since self is Pin<&mut Self>)
+impl Future for CountDown {
+    type Output = &'static str;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.count == 0 {
+            return Poll::Ready("liftoff!");
+        }
+        this.count -= 1;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Yield-only-once Future: first poll returns Pending, second returns Ready(()).
+/// This is the minimal example of an asynchronous state machine.
+pub struct YieldOnce {
+    yielded: bool,
+}
+
+impl YieldOnce {
+    pub fn new() -> Self {
+        Self { yielded: false }
+    }
+}
+
+impl Default for YieldOnce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// TODO: Implement Future trait for YieldOnce
+// - Output type is ()
+// - First poll: set yielded = true, wake waker, return Pending
+// - Second poll: return Ready(())
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.yielded {
+            return Poll::Ready(());
+        }
+        this.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
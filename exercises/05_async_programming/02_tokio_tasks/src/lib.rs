@@ -6,7 +6,12 @@
 //! - `tokio::spawn` creates asynchronous tasks
 //! - `JoinHandle` waits for task completion
 //! - Concurrent execution between asynchronous tasks
+//! - Bounding concurrency with a `Semaphore`: one task per item is fine for five items, not for
+//!   five million — each task holds memory, and whatever it talks to sees all of them at once
 
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 
@@ -31,21 +36,85 @@ pub async fn parallel_sleep_tasks(n: usize, duration_ms: u64) -> Vec<usize> {
     todo!()
 }
 
+/// Run `job(i)` for every `i` in `0..n` as spawned tasks, with at most `max_in_flight` of them
+/// alive at once, and return the outputs in order of `i`.
+///
+/// Acquire an owned permit from a `Semaphore` *before* spawning (`acquire_owned`), and drop it
+/// when the job is done: the loop then stops spawning while `max_in_flight` tasks exist, instead
+/// of creating all `n` and letting them wait.
+///
+/// Panics if `max_in_flight == 0`.
+pub async fn map_limited<F, Fut>(n: usize, max_in_flight: usize, job: F) -> Vec<Fut::Output>
+where
+    F: Fn(usize) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    // TODO: Create an Arc<Semaphore> with max_in_flight permits
+    // TODO: For each i: acquire an owned permit, then spawn job(i) with the permit moved in
+    // TODO: Await the JoinHandles in order
+    todo!()
+}
+
+/// `concurrent_squares` with at most `max_in_flight` tasks at once.
+pub async fn concurrent_squares_limited(n: usize, max_in_flight: usize) -> Vec<usize> {
+    // TODO: map_limited with a job computing i * i
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
-    use tokio::time::Instant;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::task::yield_now;
+    use tokio::time::{advance, sleep, Duration, Instant};
+
+    const HINTS: Hints = &[
+        (
+            "test_squares_basic",
+            "await the JoinHandles in the order they were spawned, so the results come back in order",
+        ),
+        (
+            "test_squares_zero",
+            "n == 0 spawns nothing and returns an empty Vec",
+        ),
+        (
+            "test_parallel_sleep",
+            "spawn every task first and only then await them; awaiting each right after spawning runs them one by one",
+        ),
+        (
+            "test_parallel_sleep_manual_clock",
+            "every task sleeps duration_ms from the moment it is spawned, all at the same time: none may be done a millisecond early",
+        ),
+        (
+            "test_map_limited_caps_in_flight",
+            "acquire the permit before tokio::spawn and move it into the task, so it is released only when the job is done",
+        ),
+        (
+            "test_map_limited_limit_above_count",
+            "the semaphore only caps concurrency: with more permits than jobs, all of them run at once",
+        ),
+    ];
 
     #[tokio::test]
     async fn test_squares_basic() {
         let result = concurrent_squares(5).await;
-        assert_eq!(result, vec![0, 1, 4, 9, 16]);
+        assert_eq_with_hint!(result, vec![0, 1, 4, 9, 16]);
     }
 
     #[tokio::test]
     async fn test_squares_zero() {
         let result = concurrent_squares(0).await;
-        assert!(result.is_empty());
+        assert_with_hint!(result.is_empty());
     }
 
     #[tokio::test]
@@ -54,18 +123,96 @@ mod tests {
         assert_eq!(result, vec![0]);
     }
 
-    #[tokio::test]
+    // The clock of a `start_paused` test only moves when every task is waiting on a timer, and
+    // then jumps straight to the next deadline: elapsed times are exact, however slow the machine.
+
+    #[tokio::test(start_paused = true)]
     async fn test_parallel_sleep() {
         let start = Instant::now();
         let result = parallel_sleep_tasks(5, 100).await;
-        let elapsed = start.elapsed();
-
-        assert_eq!(result, vec![0, 1, 2, 3, 4]);
-        // Concurrent execution, total time should be much less than 5 * 100ms
-        assert!(
-            elapsed.as_millis() < 400,
-            "Tasks should run concurrently, took {}ms",
-            elapsed.as_millis()
-        );
+
+        assert_eq_with_hint!(result, vec![0, 1, 2, 3, 4]);
+        // Concurrent: one sleep, not five in a row.
+        assert_eq_with_hint!(start.elapsed(), Duration::from_millis(100));
+    }
+
+    /// Let the other tasks run until they wait on something, without moving the paused clock.
+    async fn settle() {
+        for _ in 0..10 {
+            yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_parallel_sleep_manual_clock() {
+        // Move the clock by hand instead of letting it jump to the next deadline.
+        let tasks = tokio::spawn(parallel_sleep_tasks(3, 100));
+        settle().await;
+        advance(Duration::from_millis(99)).await;
+        settle().await;
+        assert_with_hint!(!tasks.is_finished(), "done before the sleep was over");
+
+        advance(Duration::from_millis(1)).await;
+        settle().await;
+        assert_with_hint!(tasks.is_finished(), "still sleeping after duration_ms");
+        assert_eq!(tasks.await.unwrap(), vec![0, 1, 2]);
+    }
+
+    /// Jobs that record how many of them run at the same time.
+    #[derive(Default)]
+    struct InFlight {
+        now: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    impl InFlight {
+        async fn job(self: Arc<Self>, i: usize) -> usize {
+            let now = self.now.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(now, Ordering::SeqCst);
+            sleep(Duration::from_millis(10)).await;
+            self.now.fetch_sub(1, Ordering::SeqCst);
+            i
+        }
+    }
+
+    #[tokio::test]
+    async fn test_squares_limited() {
+        let want: Vec<usize> = (0..20).map(|i| i * i).collect();
+        assert_eq!(concurrent_squares_limited(20, 3).await, want);
+        assert!(concurrent_squares_limited(0, 3).await.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_map_limited_caps_in_flight() {
+        let f = Arc::new(InFlight::default());
+        let start = Instant::now();
+        let out = map_limited(12, 3, |i| f.clone().job(i)).await;
+        assert_eq_with_hint!(out, (0..12).collect::<Vec<_>>());
+        assert_eq_with_hint!(f.max.load(Ordering::SeqCst), 3, "at most 3 jobs at once");
+        assert_eq!(f.now.load(Ordering::SeqCst), 0);
+        // Four rounds of three 10ms jobs.
+        assert_eq_with_hint!(start.elapsed(), Duration::from_millis(40));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_map_limited_caps_in_flight_multi_thread() {
+        let f = Arc::new(InFlight::default());
+        map_limited(40, 2, |i| f.clone().job(i)).await;
+        assert_eq!(f.max.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_map_limited_limit_above_count() {
+        let f = Arc::new(InFlight::default());
+        let start = Instant::now();
+        map_limited(4, 100, |i| f.clone().job(i)).await;
+        assert_eq_with_hint!(f.max.load(Ordering::SeqCst), 4);
+        assert_eq_with_hint!(start.elapsed(), Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "max_in_flight")]
+    async fn test_map_limited_zero_panics() {
+        map_limited(1, 0, |i| async move { i }).await;
     }
 }
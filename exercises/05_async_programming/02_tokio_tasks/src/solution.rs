@@ -0,0 +1,88 @@
+//! # Tokio Async Tasks
+//!
+//! In this exercise, you will use `tokio::spawn` to create concurrent asynchronous tasks.
+//!
+//! ## Concepts
+//! - `tokio::spawn` creates asynchronous tasks
+//! - `JoinHandle` waits for task completion
+//! - Concurrent execution between asynchronous tasks
+//! - Bounding concurrency with a `Semaphore`: one task per item is fine for five items, not for
+//!   five million — each task holds memory, and whatever it talks to sees all of them at once
+
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+
+/// Concurrently compute the square of each number in 0..n, collect results and return in order.
+///
+/// Hint: Create `tokio::spawn` task for each i, collect JoinHandle, await them sequentially.
+pub async fn concurrent_squares(n: usize) -> Vec<usize> {
+    let handles: Vec<JoinHandle<usize>> =
+        (0..n).map(|i| tokio::spawn(async move { i * i })).collect();
+    let mut out = Vec::with_capacity(n);
+    for h in handles {
+        out.push(h.await.unwrap());
+    }
+    out
+}
+
+/// Concurrently execute multiple "time-consuming" tasks (simulated with sleep), return all results.
+/// Each task sleeps `duration_ms` milliseconds and then returns its `task_id`.
+///
+/// Key: All tasks should execute concurrently, total duration should be close to single task duration, not sum of all tasks.
+pub async fn parallel_sleep_tasks(n: usize, duration_ms: u64) -> Vec<usize> {
+    let handles: Vec<JoinHandle<usize>> = (0..n)
+        .map(|id| {
+            tokio::spawn(async move {
+                sleep(Duration::from_millis(duration_ms)).await;
+                id
+            })
+        })
+        .collect();
+    let mut out = Vec::with_capacity(n);
+    for h in handles {
+        out.push(h.await.unwrap());
+    }
+    out.sort();
+    out
+}
+
+/// Run `job(i)` for every `i` in `0..n` as spawned tasks, with at most `max_in_flight` of them
+/// alive at once, and return the outputs in order of `i`.
+///
+/// Acquire an owned permit from a `Semaphore` *before* spawning (`acquire_owned`), and drop it
+/// when the job is done: the loop then stops spawning while `max_in_flight` tasks exist, instead
+/// of creating all `n` and letting them wait.
+///
+/// Panics if `max_in_flight == 0`.
+pub async fn map_limited<F, Fut>(n: usize, max_in_flight: usize, job: F) -> Vec<Fut::Output>
+where
+    F: Fn(usize) -> Fut,
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    assert!(max_in_flight > 0, "max_in_flight must be at least 1");
+    let limit = Arc::new(Semaphore::new(max_in_flight));
+    let mut handles: Vec<JoinHandle<Fut::Output>> = Vec::with_capacity(n);
+    for i in 0..n {
+        let permit = limit.clone().acquire_owned().await.unwrap();
+        let fut = job(i);
+        handles.push(tokio::spawn(async move {
+            let out = fut.await;
+            drop(permit);
+            out
+        }));
+    }
+    let mut out = Vec::with_capacity(n);
+    for h in handles {
+        out.push(h.await.unwrap());
+    }
+    out
+}
+
+/// `concurrent_squares` with at most `max_in_flight` tasks at once.
+pub async fn concurrent_squares_limited(n: usize, max_in_flight: usize) -> Vec<usize> {
+    map_limited(n, max_in_flight, |i| async move { i * i }).await
+}
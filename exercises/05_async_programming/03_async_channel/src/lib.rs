@@ -6,8 +6,13 @@
 //! - `tokio::sync::mpsc::channel` creates bounded async channels
 //! - Async `send` and `recv`
 //! - Channel closing mechanism (receiver returns None after all senders are dropped)
+//! - Fan-out: several workers sharing one receiver (`Arc<Mutex<Receiver>>`) to split the work
+//! - Batching: coalescing items by count or by deadline (`tokio::time::timeout_at`)
 
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout_at, Duration, Instant};
 
 /// Async producer-consumer:
 /// - Create a producer task that sends each element from items sequentially
@@ -34,15 +39,106 @@ pub async fn fan_in(n_producers: usize) -> Vec<String> {
     todo!()
 }
 
+/// Fan-out pattern: one producer, `n_workers` consumers sharing its receiver.
+///
+/// A producer task sends every item; each worker takes the next item from the shared receiver,
+/// "processes" it (`sleep(per_item)`) and keeps it. Returns, for each worker in order, the items
+/// it processed. Every item is processed by exactly one worker, and since a busy worker does not
+/// ask for more, the work spreads evenly.
+///
+/// Hold the receiver's lock only for the `recv`, not while processing: otherwise one worker at
+/// a time does all the work.
+///
+/// Panics if `n_workers == 0`.
+pub async fn fan_out<T: Send + 'static>(
+    items: Vec<T>,
+    n_workers: usize,
+    per_item: Duration,
+) -> Vec<Vec<T>> {
+    // TODO: Spawn a producer sending every item into an mpsc channel (capacity n_workers)
+    // TODO: Put the receiver into an Arc<Mutex<..>> and spawn n_workers workers
+    //       Each worker: lock, recv, unlock; None -> stop; else sleep(per_item) and keep the item
+    // TODO: Await the workers in order and return what each one processed
+    todo!()
+}
+
+/// Receive one batch: wait (without limit) for the first item, then keep collecting until the
+/// batch holds `max_items` items, `max_delay` has passed since the first item arrived, or the
+/// channel is closed — whichever comes first.
+///
+/// Returns `None` only if the channel is closed and empty before the first item arrives, so a
+/// returned batch is never empty.
+///
+/// Hint: after the first item, compute `deadline = Instant::now() + max_delay` and use
+/// `timeout_at(deadline, rx.recv())` for the following items.
+pub async fn batch_recv<T>(
+    rx: &mut mpsc::Receiver<T>,
+    max_items: usize,
+    max_delay: Duration,
+) -> Option<Vec<T>> {
+    // TODO: Await the first item (None -> return None)
+    // TODO: Collect more until max_items, the deadline, or channel close
+    todo!()
+}
+
+/// Split everything received until the channel closes into batches (see `batch_recv`).
+pub async fn collect_batches<T>(
+    mut rx: mpsc::Receiver<T>,
+    max_items: usize,
+    max_delay: Duration,
+) -> Vec<Vec<T>> {
+    // TODO: Call batch_recv until it returns None
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_producer_consumer",
+            "the consumer loops on rx.recv() until it returns None, which happens once the producer's sender is dropped",
+        ),
+        (
+            "test_fan_in",
+            "drop the original sender after cloning it for the producers, otherwise recv() never returns None",
+        ),
+        (
+            "test_fan_out_processes_each_item_once",
+            "every worker loops on the shared receiver until recv() returns None; the producer's sender drops after the last item",
+        ),
+        (
+            "test_fan_out_is_balanced_and_concurrent",
+            "release the receiver's lock right after recv() (bind the item in its own statement), before processing the item",
+        ),
+        (
+            "test_batch_size_triggered",
+            "stop collecting as soon as the batch holds max_items, without waiting for the deadline",
+        ),
+        (
+            "test_batch_deadline_triggered",
+            "the deadline is max_delay after the first item arrived: compute it once, then use timeout_at for the following items",
+        ),
+        (
+            "test_batch_waits_for_first_item",
+            "wait for the first item without a timeout; None only when the channel is closed and empty",
+        ),
+    ];
 
     #[tokio::test]
     async fn test_producer_consumer() {
         let items = vec!["hello".into(), "async".into(), "world".into()];
         let result = producer_consumer(items.clone()).await;
-        assert_eq!(result, items);
+        assert_eq_with_hint!(result, items);
     }
 
     #[tokio::test]
@@ -54,7 +150,7 @@ mod tests {
     #[tokio::test]
     async fn test_fan_in() {
         let result = fan_in(3).await;
-        assert_eq!(
+        assert_eq_with_hint!(
             result,
             vec![
                 "producer 0: message",
@@ -69,4 +165,130 @@ mod tests {
         let result = fan_in(1).await;
         assert_eq!(result, vec!["producer 0: message"]);
     }
+
+    const WORK: Duration = Duration::from_millis(1);
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fan_out_processes_each_item_once() {
+        let per_worker = fan_out((0..100).collect(), 4, WORK).await;
+        assert_eq!(per_worker.len(), 4);
+        let mut all: Vec<i32> = per_worker.iter().flatten().copied().collect();
+        all.sort();
+        assert_eq_with_hint!(all, (0..100).collect::<Vec<_>>());
+        for items in &per_worker {
+            assert!(
+                items.windows(2).all(|w| w[0] < w[1]),
+                "taken in channel order"
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fan_out_is_balanced_and_concurrent() {
+        let start = Instant::now();
+        let per_worker = fan_out((0..100).collect::<Vec<i32>>(), 4, WORK).await;
+        let counts: Vec<usize> = per_worker.iter().map(Vec::len).collect();
+        assert_with_hint!(
+            counts.iter().all(|&c| (20..=30).contains(&c)),
+            "unbalanced: {counts:?}"
+        );
+        assert_with_hint!(
+            start.elapsed() <= WORK * 30,
+            "workers did not overlap: {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "n_workers")]
+    async fn test_fan_out_zero_workers_panics() {
+        fan_out(vec![1], 0, WORK).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fan_out_more_workers_than_items() {
+        let per_worker = fan_out(vec!["a", "b"], 5, WORK).await;
+        assert_eq!(per_worker.len(), 5);
+        assert_eq!(per_worker.iter().map(Vec::len).sum::<usize>(), 2);
+        assert!(fan_out(Vec::<u8>::new(), 3, WORK)
+            .await
+            .iter()
+            .all(Vec::is_empty));
+    }
+
+    const MAX_DELAY: Duration = Duration::from_millis(20);
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_size_triggered() {
+        let (tx, mut rx) = mpsc::channel(16);
+        for i in 0..10 {
+            tx.send(i).await.unwrap();
+        }
+        let start = Instant::now();
+        assert_eq_with_hint!(
+            batch_recv(&mut rx, 4, MAX_DELAY).await,
+            Some(vec![0, 1, 2, 3])
+        );
+        assert_eq_with_hint!(
+            batch_recv(&mut rx, 4, MAX_DELAY).await,
+            Some(vec![4, 5, 6, 7])
+        );
+        assert_eq_with_hint!(start.elapsed(), Duration::ZERO, "full batches don't wait");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_deadline_triggered() {
+        let (tx, mut rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            tx.send("a").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            tx.send("b").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            tx.send("c").await.unwrap();
+            // Keep the channel open.
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        let start = Instant::now();
+        assert_eq_with_hint!(
+            batch_recv(&mut rx, 10, MAX_DELAY).await,
+            Some(vec!["a", "b"])
+        );
+        assert_eq_with_hint!(
+            start.elapsed(),
+            MAX_DELAY,
+            "deadline counts from the first item"
+        );
+        assert_eq_with_hint!(batch_recv(&mut rx, 10, MAX_DELAY).await, Some(vec!["c"]));
+        assert_eq_with_hint!(start.elapsed(), Duration::from_millis(35) + MAX_DELAY);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_waits_for_first_item() {
+        let (tx, mut rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            tx.send(1).await.unwrap();
+        });
+        // The sender is dropped right after sending: the batch ends when the channel closes.
+        assert_eq_with_hint!(batch_recv(&mut rx, 3, MAX_DELAY).await, Some(vec![1]));
+        assert_eq_with_hint!(batch_recv(&mut rx, 3, MAX_DELAY).await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_collect_batches() {
+        let (tx, rx) = mpsc::channel(16);
+        tokio::spawn(async move {
+            for burst in [3, 5, 1] {
+                for i in 0..burst {
+                    tx.send(i).await.unwrap();
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        });
+        let batches = collect_batches(rx, 4, MAX_DELAY).await;
+        assert_eq!(
+            batches,
+            vec![vec![0, 1, 2], vec![0, 1, 2, 3], vec![4], vec![0]]
+        );
+    }
 }
@@ -0,0 +1,141 @@
+//! # Async Channel
+//!
+//! In this exercise, you will use `tokio::sync::mpsc` async channels to implement producer-consumer pattern.
+//!
+//! ## Concepts
+//! - `tokio::sync::mpsc::channel` creates bounded async channels
+//! - Async `send` and `recv`
+//! - Channel closing mechanism (receiver returns None after all senders are dropped)
+//! - Fan-out: several workers sharing one receiver (`Arc<Mutex<Receiver>>`) to split the work
+//! - Batching: coalescing items by count or by deadline (`tokio::time::timeout_at`)
+
+use std::sync::Arc;
+pub(crate) use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+pub(crate) use tokio::time::{sleep, timeout_at, Duration, Instant};
+
+/// Async producer-consumer:
+/// - Create a producer task that sends each element from items sequentially
+/// - Create a consumer task that receives all elements and collects them into Vec for return
+///
+/// Hint: Set channel capacity to items.len().max(1)
+pub async fn producer_consumer(items: Vec<String>) -> Vec<String> {
+    let (tx, mut rx) = mpsc::channel(items.len().max(1));
+    tokio::spawn(async move {
+        for item in items {
+            tx.send(item).await.unwrap();
+        }
+    });
+    let mut result = Vec::new();
+    while let Some(item) = rx.recv().await {
+        result.push(item);
+    }
+    result
+}
+
+/// Fan‑in pattern: multiple producers, one consumer.
+/// Create `n_producers` producers, each sending `"producer {id}: message"`.
+/// Consumer collects all messages, sorts them, and returns.
+pub async fn fan_in(n_producers: usize) -> Vec<String> {
+    let (tx, mut rx) = mpsc::channel(n_producers.max(1));
+    for id in 0..n_producers {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            tx.send(format!("producer {id}: message")).await.unwrap();
+        });
+    }
+    drop(tx);
+    let mut result = Vec::new();
+    while let Some(m) = rx.recv().await {
+        result.push(m);
+    }
+    result.sort();
+    result
+}
+
+/// Fan-out pattern: one producer, `n_workers` consumers sharing its receiver.
+///
+/// A producer task sends every item; each worker takes the next item from the shared receiver,
+/// "processes" it (`sleep(per_item)`) and keeps it. Returns, for each worker in order, the items
+/// it processed. Every item is processed by exactly one worker, and since a busy worker does not
+/// ask for more, the work spreads evenly.
+///
+/// Hold the receiver's lock only for the `recv`, not while processing: otherwise one worker at
+/// a time does all the work.
+///
+/// Panics if `n_workers == 0`.
+pub async fn fan_out<T: Send + 'static>(
+    items: Vec<T>,
+    n_workers: usize,
+    per_item: Duration,
+) -> Vec<Vec<T>> {
+    assert!(n_workers > 0, "n_workers must be at least 1");
+    let (tx, rx) = mpsc::channel(n_workers);
+    tokio::spawn(async move {
+        for item in items {
+            if tx.send(item).await.is_err() {
+                break;
+            }
+        }
+    });
+    let rx = Arc::new(Mutex::new(rx));
+    let workers: Vec<_> = (0..n_workers)
+        .map(|_| {
+            let rx = rx.clone();
+            tokio::spawn(async move {
+                let mut done = Vec::new();
+                loop {
+                    let item = rx.lock().await.recv().await;
+                    let Some(item) = item else { break };
+                    sleep(per_item).await;
+                    done.push(item);
+                }
+                done
+            })
+        })
+        .collect();
+    let mut result = Vec::with_capacity(n_workers);
+    for w in workers {
+        result.push(w.await.unwrap());
+    }
+    result
+}
+
+/// Receive one batch: wait (without limit) for the first item, then keep collecting until the
+/// batch holds `max_items` items, `max_delay` has passed since the first item arrived, or the
+/// channel is closed — whichever comes first.
+///
+/// Returns `None` only if the channel is closed and empty before the first item arrives, so a
+/// returned batch is never empty.
+///
+/// Hint: after the first item, compute `deadline = Instant::now() + max_delay` and use
+/// `timeout_at(deadline, rx.recv())` for the following items.
+pub async fn batch_recv<T>(
+    rx: &mut mpsc::Receiver<T>,
+    max_items: usize,
+    max_delay: Duration,
+) -> Option<Vec<T>> {
+    let first = rx.recv().await?;
+    let deadline = Instant::now() + max_delay;
+    let mut batch = vec![first];
+    while batch.len() < max_items {
+        match timeout_at(deadline, rx.recv()).await {
+            Ok(Some(item)) => batch.push(item),
+            Ok(None) | Err(_) => break,
+        }
+    }
+    Some(batch)
+}
+
+/// Split everything received until the channel closes into batches (see `batch_recv`).
+pub async fn collect_batches<T>(
+    mut rx: mpsc::Receiver<T>,
+    max_items: usize,
+    max_delay: Duration,
+) -> Vec<Vec<T>> {
+    let mut batches = Vec::new();
+    while let Some(b) = batch_recv(&mut rx, max_items, max_delay).await {
+        batches.push(b);
+    }
+    batches
+}
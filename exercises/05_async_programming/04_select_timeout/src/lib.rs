@@ -6,6 +6,8 @@
 //! - `tokio::select!` waits for multiple async operations simultaneously
 //! - `tokio::time::timeout` timeout control
 //! - The first completed branch is executed, others are cancelled
+//! - `biased;` polls the branches in order instead of randomly
+//! - Retrying with exponential backoff, and composing it with a per-attempt timeout
 
 use std::future::Future;
 use tokio::time::{sleep, Duration};
@@ -37,18 +39,160 @@ where
     todo!()
 }
 
+/// Like `race`, but when both futures are ready `f1` always wins.
+///
+/// Plain `select!` polls its branches in a random order on each wakeup, so two ready branches
+/// win about half the time each. With `biased;` it polls them top to bottom instead.
+pub async fn race_biased<F1, F2, T>(f1: F1, f2: F2) -> T
+where
+    F1: Future<Output = T>,
+    F2: Future<Output = T>,
+{
+    // TODO: Same as race, but start the select! with `biased;`
+    todo!()
+}
+
+/// Run `op` until it returns `Ok`, at most `max_attempts` times. Returns the first `Ok`, or
+/// the last `Err` if every attempt failed.
+///
+/// Between attempts sleep with exponential backoff: `base_delay` after the 1st failure,
+/// `2 * base_delay` after the 2nd, `4 * base_delay` after the 3rd, ... No sleep after the last
+/// attempt.
+///
+/// Panics if `max_attempts == 0`.
+pub async fn retry_with_backoff<F, Fut, T, E>(
+    mut op: F,
+    max_attempts: u32,
+    base_delay: Duration,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    assert!(max_attempts > 0, "max_attempts must be positive");
+    // TODO: Loop over attempts 1..=max_attempts calling op().await
+    // TODO: Return Ok at once; on Err, keep it and sleep base_delay * 2^(attempt - 1)
+    //       unless this was the last attempt
+    todo!()
+}
+
+/// Why a single attempt of `retry_with_timeout` failed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AttemptError<E> {
+    /// The attempt did not finish within the per-attempt timeout.
+    TimedOut,
+    Failed(E),
+}
+
+/// `retry_with_backoff` where every attempt is also limited by `with_timeout(.., timeout_ms)`;
+/// an attempt that times out counts as a failed attempt.
+///
+/// Hint: wrap `op` in a closure that returns `with_timeout(op(), timeout_ms)` mapped to
+/// `Result<T, AttemptError<E>>`, and pass that closure to `retry_with_backoff`.
+pub async fn retry_with_timeout<F, Fut, T, E>(
+    mut op: F,
+    max_attempts: u32,
+    base_delay: Duration,
+    timeout_ms: u64,
+) -> Result<T, AttemptError<E>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    // TODO: Compose retry_with_backoff and with_timeout
+    todo!()
+}
+
+/// Run a fresh `fut_factory()` future, giving each attempt `per_try_timeout` to finish. An
+/// attempt that runs out of time is dropped (cancelled) and the next one starts right away.
+/// Returns the first result, or `None` if all `retries + 1` attempts timed out.
+///
+/// Hint: `tokio::select!` the attempt against `sleep(per_try_timeout)` in a loop.
+pub async fn with_deadline_retry<F, Fut, T>(
+    mut fut_factory: F,
+    per_try_timeout: Duration,
+    retries: u32,
+) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    // TODO: Up to retries + 1 times: build a fresh future with fut_factory() and
+    // select! it against sleep(per_try_timeout); return Some(v) if the future wins
+    // Return None once every attempt timed out
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use tokio::time::Instant;
 
-    #[tokio::test]
+    const HINTS: Hints = &[
+        (
+            "test_timeout_expired",
+            "race the future against sleep(timeout_ms) and return None when the sleep wins",
+        ),
+        (
+            "test_timeout_manual_clock",
+            "the timeout sleep starts with the call: after exactly timeout_ms the sleep branch must win",
+        ),
+        (
+            "test_race_second_wins",
+            "tokio::select! returns whichever branch finishes first, whatever the order of the branches",
+        ),
+        (
+            "test_retry_flaky_then_ok",
+            "sleep base_delay * 2^(attempt - 1) after each failed attempt: BASE, 2 * BASE, 4 * BASE",
+        ),
+        (
+            "test_retry_returns_last_error",
+            "keep the last Err and do not sleep after the final attempt",
+        ),
+        (
+            "test_retry_with_timeout_recovers",
+            "an attempt that times out is a failed attempt: map with_timeout's None to AttemptError::TimedOut and let the retry continue",
+        ),
+        (
+            "test_race_biased_prefers_first",
+            "start the select! with `biased;` so the branches are polled in order, f1 first",
+        ),
+        (
+            "test_deadline_retry_recovers",
+            "select! each attempt against sleep(per_try_timeout); when the sleep wins, drop the attempt and call fut_factory() again",
+        ),
+        (
+            "test_deadline_retry_gives_up",
+            "make retries + 1 attempts in total, then return None",
+        ),
+    ];
+
+    const BASE: Duration = Duration::from_millis(10);
+
+    // Timing tests run with `start_paused = true`: the clock stands still while any task can run
+    // and then jumps to the next timer, so elapsed times are exact and the tests never wait.
+
+    #[tokio::test(start_paused = true)]
     async fn test_timeout_success() {
+        let start = Instant::now();
         let result = with_timeout(async { 42 }, 100).await;
         assert_eq!(result, Some(42));
+        assert_eq!(start.elapsed(), Duration::ZERO);
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
     async fn test_timeout_expired() {
+        let start = Instant::now();
         let result = with_timeout(
             async {
                 sleep(Duration::from_millis(200)).await;
@@ -57,10 +201,33 @@ mod tests {
             50,
         )
         .await;
-        assert_eq!(result, None);
+        assert_eq_with_hint!(result, None);
+        assert_eq_with_hint!(start.elapsed(), Duration::from_millis(50));
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout_manual_clock() {
+        // Move the clock by hand instead of letting it jump to the next deadline.
+        let op = tokio::spawn(with_timeout(sleep(Duration::from_secs(60)), 50));
+        settle().await;
+        tokio::time::advance(Duration::from_millis(49)).await;
+        settle().await;
+        assert!(!op.is_finished(), "timed out early");
+
+        tokio::time::advance(Duration::from_millis(1)).await;
+        settle().await;
+        assert_with_hint!(op.is_finished(), "still waiting after timeout_ms");
+        assert_eq!(op.await.unwrap(), None);
+    }
+
+    /// Let the other tasks run until they wait on something, without moving the paused clock.
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
     async fn test_race_first_wins() {
         let result = race(
             async {
@@ -76,8 +243,9 @@ mod tests {
         assert_eq!(result, "fast");
     }
 
-    #[tokio::test]
+    #[tokio::test(start_paused = true)]
     async fn test_race_second_wins() {
+        let start = Instant::now();
         let result = race(
             async {
                 sleep(Duration::from_millis(200)).await;
@@ -89,6 +257,164 @@ mod tests {
             },
         )
         .await;
+        assert_eq_with_hint!(result, "fast");
+        assert_eq!(start.elapsed(), Duration::from_millis(10));
+    }
+
+    /// Fails the first `failures` calls (with the attempt number), then succeeds.
+    fn flaky(
+        failures: u32,
+    ) -> (
+        Arc<AtomicU32>,
+        impl FnMut() -> std::future::Ready<Result<u32, String>>,
+    ) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let c = calls.clone();
+        let op = move || {
+            let n = c.fetch_add(1, Ordering::SeqCst) + 1;
+            std::future::ready(if n <= failures {
+                Err(format!("fail {n}"))
+            } else {
+                Ok(n)
+            })
+        };
+        (calls, op)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_first_try() {
+        let (calls, op) = flaky(0);
+        let start = Instant::now();
+        assert_eq!(retry_with_backoff(op, 5, BASE).await, Ok(1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_flaky_then_ok() {
+        let (calls, op) = flaky(3);
+        let start = Instant::now();
+        assert_eq_with_hint!(retry_with_backoff(op, 5, BASE).await, Ok(4));
+        assert_eq_with_hint!(calls.load(Ordering::SeqCst), 4);
+        // Backoff 1 + 2 + 4 times the base delay.
+        assert_eq_with_hint!(start.elapsed(), BASE * 7);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_returns_last_error() {
+        let (calls, op) = flaky(u32::MAX);
+        let start = Instant::now();
+        assert_eq_with_hint!(
+            retry_with_backoff(op, 3, BASE).await,
+            Err("fail 3".to_string())
+        );
+        assert_eq_with_hint!(calls.load(Ordering::SeqCst), 3);
+        assert_eq_with_hint!(start.elapsed(), BASE * 3, "no sleep after the last attempt");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_with_timeout_recovers() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let op = || {
+            let n = calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if n == 0 {
+                    // The first attempt hangs.
+                    sleep(Duration::from_secs(60)).await;
+                }
+                Ok::<_, ()>(n)
+            }
+        };
+        let start = Instant::now();
+        assert_eq_with_hint!(retry_with_timeout(op, 3, BASE, 100).await, Ok(1));
+        assert_eq_with_hint!(start.elapsed(), Duration::from_millis(100) + BASE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_with_timeout_gives_up() {
+        let op = || async {
+            sleep(Duration::from_secs(60)).await;
+            Ok::<u8, ()>(0)
+        };
+        let start = Instant::now();
+        assert_eq!(
+            retry_with_timeout(op, 2, BASE, 50).await,
+            Err(AttemptError::TimedOut)
+        );
+        assert_eq!(start.elapsed(), Duration::from_millis(100) + BASE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_with_timeout_keeps_error() {
+        let (_, op) = flaky(u32::MAX);
+        assert_eq!(
+            retry_with_timeout(op, 2, BASE, 50).await,
+            Err(AttemptError::Failed("fail 2".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_race_biased_prefers_first() {
+        for _ in 0..100 {
+            assert_eq_with_hint!(
+                race_biased(async { "first" }, async { "second" }).await,
+                "first"
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_race_biased_still_races() {
+        let result = race_biased(
+            async {
+                sleep(Duration::from_millis(200)).await;
+                "slow"
+            },
+            async { "fast" },
+        )
+        .await;
         assert_eq!(result, "fast");
     }
+
+    type Attempt = std::pin::Pin<Box<dyn std::future::Future<Output = u32>>>;
+
+    /// Attempt `i` (from 0) takes `durations[i]` milliseconds, then returns `i`.
+    fn slow_attempts(durations: &'static [u64]) -> (Arc<AtomicU32>, impl FnMut() -> Attempt) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let factory = move || {
+            let i = counter.fetch_add(1, Ordering::SeqCst);
+            let d = durations[i as usize];
+            Box::pin(async move {
+                sleep(Duration::from_millis(d)).await;
+                i
+            }) as Attempt
+        };
+        (calls, factory)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_retry_first_try() {
+        let (calls, factory) = slow_attempts(&[10]);
+        assert_eq!(with_deadline_retry(factory, BASE * 5, 3).await, Some(0));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_retry_recovers() {
+        let (calls, factory) = slow_attempts(&[1000, 1000, 20]);
+        let start = Instant::now();
+        assert_eq_with_hint!(with_deadline_retry(factory, BASE * 5, 3).await, Some(2));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(start.elapsed(), Duration::from_millis(50 + 50 + 20));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_retry_gives_up() {
+        let (calls, factory) = slow_attempts(&[1000; 4]);
+        let start = Instant::now();
+        assert_eq!(with_deadline_retry(factory, BASE * 5, 2).await, None);
+        assert_eq_with_hint!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(start.elapsed(), Duration::from_millis(150));
+    }
 }
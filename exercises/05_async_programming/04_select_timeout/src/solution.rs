@@ -0,0 +1,153 @@
+//! # Select and Timeout
+//!
+//! In this exercise, you will use `tokio::select!` macro to implement race selection and timeout control.
+//!
+//! ## Concepts
+//! - `tokio::select!` waits for multiple async operations simultaneously
+//! - `tokio::time::timeout` timeout control
+//! - The first completed branch is executed, others are cancelled
+//! - `biased;` polls the branches in order instead of randomly
+//! - Retrying with exponential backoff, and composing it with a per-attempt timeout
+
+use std::future::Future;
+pub(crate) use tokio::time::{sleep, Duration};
+
+/// Async operation with timeout.
+/// If `future` completes within `timeout_ms` milliseconds, returns Some(result).
+/// Otherwise returns None.
+///
+/// Hint: Use `tokio::select!` or `tokio::time::timeout`.
+pub async fn with_timeout<F, T>(future: F, timeout_ms: u64) -> Option<T>
+where
+    F: Future<Output = T>,
+{
+    tokio::time::timeout(Duration::from_millis(timeout_ms), future)
+        .await
+        .ok()
+}
+
+/// Race two async tasks, return the result of whichever finishes first.
+///
+/// Hint: Use `tokio::select!` macro.
+pub async fn race<F1, F2, T>(f1: F1, f2: F2) -> T
+where
+    F1: Future<Output = T>,
+    F2: Future<Output = T>,
+{
+    tokio::select! {
+        v = f1 => v,
+        v = f2 => v,
+    }
+}
+
+/// Like `race`, but when both futures are ready `f1` always wins.
+///
+/// Plain `select!` polls its branches in a random order on each wakeup, so two ready branches
+/// win about half the time each. With `biased;` it polls them top to bottom instead.
+pub async fn race_biased<F1, F2, T>(f1: F1, f2: F2) -> T
+where
+    F1: Future<Output = T>,
+    F2: Future<Output = T>,
+{
+    tokio::select! {
+        biased;
+        v = f1 => v,
+        v = f2 => v,
+    }
+}
+
+/// Run `op` until it returns `Ok`, at most `max_attempts` times. Returns the first `Ok`, or
+/// the last `Err` if every attempt failed.
+///
+/// Between attempts sleep with exponential backoff: `base_delay` after the 1st failure,
+/// `2 * base_delay` after the 2nd, `4 * base_delay` after the 3rd, ... No sleep after the last
+/// attempt.
+///
+/// Panics if `max_attempts == 0`.
+pub async fn retry_with_backoff<F, Fut, T, E>(
+    mut op: F,
+    max_attempts: u32,
+    base_delay: Duration,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    assert!(max_attempts > 0, "max_attempts must be positive");
+    let mut delay = base_delay;
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt == max_attempts => return Err(e),
+            Err(_) => {}
+        }
+        sleep(delay).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+/// Why a single attempt of `retry_with_timeout` failed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AttemptError<E> {
+    /// The attempt did not finish within the per-attempt timeout.
+    TimedOut,
+    Failed(E),
+}
+
+/// `retry_with_backoff` where every attempt is also limited by `with_timeout(.., timeout_ms)`;
+/// an attempt that times out counts as a failed attempt.
+///
+/// Hint: wrap `op` in a closure that returns `with_timeout(op(), timeout_ms)` mapped to
+/// `Result<T, AttemptError<E>>`, and pass that closure to `retry_with_backoff`.
+pub async fn retry_with_timeout<F, Fut, T, E>(
+    mut op: F,
+    max_attempts: u32,
+    base_delay: Duration,
+    timeout_ms: u64,
+) -> Result<T, AttemptError<E>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    retry_with_backoff(
+        || {
+            let fut = op();
+            async move {
+                match with_timeout(fut, timeout_ms).await {
+                    Some(Ok(v)) => Ok(v),
+                    Some(Err(e)) => Err(AttemptError::Failed(e)),
+                    None => Err(AttemptError::TimedOut),
+                }
+            }
+        },
+        max_attempts,
+        base_delay,
+    )
+    .await
+}
+
+/// Run a fresh `fut_factory()` future, giving each attempt `per_try_timeout` to finish. An
+/// attempt that runs out of time is dropped (cancelled) and the next one starts right away.
+/// Returns the first result, or `None` if all `retries + 1` attempts timed out.
+///
+/// Hint: `tokio::select!` the attempt against `sleep(per_try_timeout)` in a loop.
+pub async fn with_deadline_retry<F, Fut, T>(
+    mut fut_factory: F,
+    per_try_timeout: Duration,
+    retries: u32,
+) -> Option<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    for _ in 0..=retries {
+        let fut = fut_factory();
+        tokio::select! {
+            v = fut => return Some(v),
+            _ = sleep(per_try_timeout) => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,400 @@
+//! # A Mini Executor
+//!
+//! In this exercise, you write the piece between "implement `Future`" (`01_basic_future`) and
+//! "use tokio" (`02_tokio_tasks`): the code that actually polls futures.
+//!
+//! ## Concepts
+//! - `block_on`: poll one future on the current thread; while it is `Pending`, park the thread
+//!   until its `Waker` unparks it
+//! - A task = a boxed future + a way to put itself back on the run queue
+//! - `Wake` for `Arc<Task>` ("ArcWake"): `waker.wake()` sends the task to the executor's queue
+//! - Coalescing: a task that is woken five times before it runs is polled once, not five times
+//! - `JoinHandle<T>`: a future that completes with the spawned task's output
+//!
+//! ## Design
+//! The run queue is an `mpsc` channel of `Arc<Task>`. Every task owns a `Sender`, so a waker
+//! can be fired from any thread. `Executor::run` receives tasks and polls them until every
+//! spawned task has finished; when the queue is empty it simply blocks in `recv()`, waiting for
+//! a wake-up from another thread.
+//!
+//! The tests run `CountDown` and `YieldOnce` from `01_basic_future` (finish that one first).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+/// Waker for `block_on`: wakes the thread that is blocked.
+struct ThreadWaker {
+    thread: Thread,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.thread.unpark();
+    }
+}
+
+/// Run `future` to completion on the current thread.
+///
+/// 1. Pin the future (`std::pin::pin!` or `Box::pin`).
+/// 2. Build a `Waker` from `Arc<ThreadWaker>` for the current thread and a `Context` from it.
+/// 3. Loop: `poll`; `Ready(v)` -> return `v`; `Pending` -> `thread::park()`.
+///
+/// (`park` may return spuriously, and an `unpark` that comes before `park` is not lost; both
+/// just mean one more `poll`.)
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    // TODO
+    todo!()
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A spawned future plus what it needs to reschedule itself.
+struct Task {
+    /// `None` once the future has completed.
+    future: Mutex<Option<BoxFuture>>,
+    queue: Sender<Arc<Task>>,
+    /// Already in the run queue; further wakes are no-ops until it is polled.
+    queued: AtomicBool,
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    /// Put the task on the run queue unless it is already there: set `queued` (`swap`), and
+    /// only if it was `false`, send a clone of `self` into `queue`.
+    fn wake_by_ref(self: &Arc<Self>) {
+        // TODO
+        todo!()
+    }
+}
+
+struct JoinState<T> {
+    result: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Completes with the output of a spawned task.
+pub struct JoinHandle<T> {
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().result.is_some()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut st = self.state.lock().unwrap();
+        match st.result.take() {
+            Some(v) => Poll::Ready(v),
+            None => {
+                st.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Cloneable handle for spawning onto an `Executor`, also from inside its tasks.
+#[derive(Clone)]
+pub struct Spawner {
+    queue: Sender<Arc<Task>>,
+    /// Spawned tasks that have not completed yet.
+    live: Arc<AtomicUsize>,
+}
+
+impl Spawner {
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(JoinState {
+            result: None,
+            waker: None,
+        }));
+        let handle = JoinHandle {
+            state: state.clone(),
+        };
+        let wrapped = async move {
+            let out = future.await;
+            let waker = {
+                let mut st = state.lock().unwrap();
+                st.result = Some(out);
+                st.waker.take()
+            };
+            if let Some(w) = waker {
+                w.wake();
+            }
+        };
+        self.live.fetch_add(1, Ordering::SeqCst);
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(Box::pin(wrapped))),
+            queue: self.queue.clone(),
+            queued: AtomicBool::new(false),
+        });
+        task.wake_by_ref();
+        handle
+    }
+}
+
+/// Single-threaded executor.
+pub struct Executor {
+    spawner: Spawner,
+    queue: Receiver<Arc<Task>>,
+    polls: usize,
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            spawner: Spawner {
+                queue: tx,
+                live: Arc::new(AtomicUsize::new(0)),
+            },
+            queue: rx,
+            polls: 0,
+        }
+    }
+
+    pub fn spawner(&self) -> Spawner {
+        self.spawner.clone()
+    }
+
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawner.spawn(future)
+    }
+
+    /// Poll tasks until every spawned task (including ones spawned while running) has completed.
+    ///
+    /// While `live > 0`:
+    /// 1. `recv()` the next task (blocks until some waker sends one).
+    /// 2. Clear its `queued` flag *before* polling, so a wake during the poll re-queues it.
+    /// 3. Take the future out of `task.future`; if it is already `None` skip the task.
+    /// 4. Poll it with a `Waker::from(task.clone())`, counting the poll in `polls`.
+    /// 5. `Pending` -> put the future back; `Ready` -> decrement `live`.
+    pub fn run(&mut self) {
+        // TODO
+        todo!()
+    }
+
+    /// Total number of `poll` calls made by `run`.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    #[cfg(feature = "solutions")]
+    use basic_future::solution::{CountDown, YieldOnce};
+    #[cfg(not(feature = "solutions"))]
+    use basic_future::{CountDown, YieldOnce};
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_block_on_ready",
+            "poll once; Ready(v) returns v straight away",
+        ),
+        (
+            "test_block_on_woken_by_other_thread",
+            "on Pending, thread::park(); ThreadWaker::wake unparks the thread that called block_on",
+        ),
+        (
+            "test_executor_runs_tasks",
+            "run() keeps receiving tasks while live > 0 and decrements live when a task's poll returns Ready",
+        ),
+        (
+            "test_duplicate_wakes_coalesce",
+            "wake_by_ref sends the task only if queued.swap(true) returned false; run clears queued before polling",
+        ),
+        (
+            "test_executor_waits_for_cross_thread_wake",
+            "when the queue is empty, block in recv() instead of returning: a waker on another thread will send the task",
+        ),
+    ];
+
+    /// Pending until another thread wakes it after `ms` milliseconds.
+    struct WakeFromThread {
+        ms: u64,
+        fired: Arc<AtomicBool>,
+        started: bool,
+    }
+
+    fn wake_from_thread(ms: u64) -> WakeFromThread {
+        WakeFromThread {
+            ms,
+            fired: Arc::new(AtomicBool::new(false)),
+            started: false,
+        }
+    }
+
+    impl Future for WakeFromThread {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.fired.load(Ordering::SeqCst) {
+                return Poll::Ready(());
+            }
+            if !self.started {
+                self.started = true;
+                let (ms, fired, waker) = (self.ms, self.fired.clone(), cx.waker().clone());
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(ms));
+                    fired.store(true, Ordering::SeqCst);
+                    waker.wake();
+                });
+            }
+            Poll::Pending
+        }
+    }
+
+    /// Wakes itself `n` times per poll, `pending` times, then completes.
+    struct NoisyWaker {
+        n: usize,
+        pending: usize,
+    }
+
+    impl Future for NoisyWaker {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.pending == 0 {
+                return Poll::Ready(());
+            }
+            self.pending -= 1;
+            for _ in 0..self.n {
+                cx.waker().wake_by_ref();
+            }
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_block_on_ready() {
+        assert_eq_with_hint!(block_on(async { 6 * 7 }), 42);
+    }
+
+    #[test]
+    fn test_block_on_basic_futures() {
+        assert_eq!(block_on(CountDown::new(5)), "liftoff!");
+        block_on(YieldOnce::new());
+        let out = block_on(async {
+            YieldOnce::new().await;
+            CountDown::new(2).await
+        });
+        assert_eq!(out, "liftoff!");
+    }
+
+    #[test]
+    fn test_block_on_woken_by_other_thread() {
+        let start = std::time::Instant::now();
+        block_on(wake_from_thread(30));
+        assert_with_hint!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_executor_runs_tasks() {
+        let mut ex = Executor::new();
+        let a = ex.spawn(CountDown::new(3));
+        let b = ex.spawn(async { 1 + 1 });
+        ex.run();
+        assert_with_hint!(a.is_finished() && b.is_finished());
+        assert_eq_with_hint!(block_on(a), "liftoff!");
+        assert_eq_with_hint!(block_on(b), 2);
+        // CountDown(3): three Pending polls + one Ready; the async block: one.
+        assert_eq_with_hint!(ex.polls(), 5);
+    }
+
+    #[test]
+    fn test_tasks_interleave() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut ex = Executor::new();
+        for name in ["a", "b"] {
+            let log = log.clone();
+            ex.spawn(async move {
+                for i in 1..=3 {
+                    log.lock().unwrap().push(format!("{name}{i}"));
+                    YieldOnce::new().await;
+                }
+            });
+        }
+        ex.run();
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["a1", "b1", "a2", "b2", "a3", "b3"],
+            "a yielding task goes to the back of the queue"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_wakes_coalesce() {
+        let mut ex = Executor::new();
+        ex.spawn(NoisyWaker { n: 5, pending: 3 });
+        ex.run();
+        assert_eq_with_hint!(ex.polls(), 4, "5 wakes per poll still mean one poll");
+    }
+
+    #[test]
+    fn test_await_join_handle_and_spawn_from_task() {
+        let mut ex = Executor::new();
+        let spawner = ex.spawner();
+        let outer = ex.spawn(async move {
+            let inner = spawner.spawn(async {
+                CountDown::new(2).await;
+                10
+            });
+            inner.await + 1
+        });
+        ex.run();
+        assert_eq!(block_on(outer), 11);
+    }
+
+    #[test]
+    fn test_executor_waits_for_cross_thread_wake() {
+        let mut ex = Executor::new();
+        let done = Arc::new(AtomicUsize::new(0));
+        for ms in [40, 10, 25] {
+            let done = done.clone();
+            ex.spawn(async move {
+                wake_from_thread(ms).await;
+                done.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        ex.run();
+        assert_eq_with_hint!(done.load(Ordering::SeqCst), 3);
+        assert_eq_with_hint!(ex.polls(), 6, "each task: one Pending poll, one Ready poll");
+    }
+}
@@ -0,0 +1,232 @@
+//! # A Mini Executor
+//!
+//! In this exercise, you write the piece between "implement `Future`" (`01_basic_future`) and
+//! "use tokio" (`02_tokio_tasks`): the code that actually polls futures.
+//!
+//! ## Concepts
+//! - `block_on`: poll one future on the current thread; while it is `Pending`, park the thread
+//!   until its `Waker` unparks it
+//! - A task = a boxed future + a way to put itself back on the run queue
+//! - `Wake` for `Arc<Task>` ("ArcWake"): `waker.wake()` sends the task to the executor's queue
+//! - Coalescing: a task that is woken five times before it runs is polled once, not five times
+//! - `JoinHandle<T>`: a future that completes with the spawned task's output
+//!
+//! ## Design
+//! The run queue is an `mpsc` channel of `Arc<Task>`. Every task owns a `Sender`, so a waker
+//! can be fired from any thread. `Executor::run` receives tasks and polls them until every
+//! spawned task has finished; when the queue is empty it simply blocks in `recv()`, waiting for
+//! a wake-up from another thread.
+//!
+//! The tests run `CountDown` and `YieldOnce` from `01_basic_future` (finish that one first).
+
+pub(crate) use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+pub(crate) use std::sync::{Arc, Mutex};
+pub(crate) use std::task::{Context, Poll, Wake, Waker};
+pub(crate) use std::thread::{self, Thread};
+
+/// Waker for `block_on`: wakes the thread that is blocked.
+struct ThreadWaker {
+    thread: Thread,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.thread.unpark();
+    }
+}
+
+/// Run `future` to completion on the current thread.
+///
+/// 1. Pin the future (`std::pin::pin!` or `Box::pin`).
+/// 2. Build a `Waker` from `Arc<ThreadWaker>` for the current thread and a `Context` from it.
+/// 3. Loop: `poll`; `Ready(v)` -> return `v`; `Pending` -> `thread::park()`.
+///
+/// (`park` may return spuriously, and an `unpark` that comes before `park` is not lost; both
+/// just mean one more `poll`.)
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker {
+        thread: thread::current(),
+    }));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(v) => return v,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A spawned future plus what it needs to reschedule itself.
+struct Task {
+    /// `None` once the future has completed.
+    future: Mutex<Option<BoxFuture>>,
+    queue: Sender<Arc<Task>>,
+    /// Already in the run queue; further wakes are no-ops until it is polled.
+    queued: AtomicBool,
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    /// Put the task on the run queue unless it is already there: set `queued` (`swap`), and
+    /// only if it was `false`, send a clone of `self` into `queue`.
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.queued.swap(true, Ordering::SeqCst) {
+            let _ = self.queue.send(self.clone());
+        }
+    }
+}
+
+struct JoinState<T> {
+    result: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Completes with the output of a spawned task.
+pub struct JoinHandle<T> {
+    state: Arc<Mutex<JoinState<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().result.is_some()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut st = self.state.lock().unwrap();
+        match st.result.take() {
+            Some(v) => Poll::Ready(v),
+            None => {
+                st.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Cloneable handle for spawning onto an `Executor`, also from inside its tasks.
+#[derive(Clone)]
+pub struct Spawner {
+    queue: Sender<Arc<Task>>,
+    /// Spawned tasks that have not completed yet.
+    live: Arc<AtomicUsize>,
+}
+
+impl Spawner {
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(JoinState {
+            result: None,
+            waker: None,
+        }));
+        let handle = JoinHandle {
+            state: state.clone(),
+        };
+        let wrapped = async move {
+            let out = future.await;
+            let waker = {
+                let mut st = state.lock().unwrap();
+                st.result = Some(out);
+                st.waker.take()
+            };
+            if let Some(w) = waker {
+                w.wake();
+            }
+        };
+        self.live.fetch_add(1, Ordering::SeqCst);
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(Box::pin(wrapped))),
+            queue: self.queue.clone(),
+            queued: AtomicBool::new(false),
+        });
+        task.wake_by_ref();
+        handle
+    }
+}
+
+/// Single-threaded executor.
+pub struct Executor {
+    spawner: Spawner,
+    queue: Receiver<Arc<Task>>,
+    polls: usize,
+}
+
+impl Executor {
+    pub fn new() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            spawner: Spawner {
+                queue: tx,
+                live: Arc::new(AtomicUsize::new(0)),
+            },
+            queue: rx,
+            polls: 0,
+        }
+    }
+
+    pub fn spawner(&self) -> Spawner {
+        self.spawner.clone()
+    }
+
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawner.spawn(future)
+    }
+
+    /// Poll tasks until every spawned task (including ones spawned while running) has completed.
+    ///
+    /// While `live > 0`:
+    /// 1. `recv()` the next task (blocks until some waker sends one).
+    /// 2. Clear its `queued` flag *before* polling, so a wake during the poll re-queues it.
+    /// 3. Take the future out of `task.future`; if it is already `None` skip the task.
+    /// 4. Poll it with a `Waker::from(task.clone())`, counting the poll in `polls`.
+    /// 5. `Pending` -> put the future back; `Ready` -> decrement `live`.
+    pub fn run(&mut self) {
+        while self.spawner.live.load(Ordering::SeqCst) > 0 {
+            let task = self.queue.recv().expect("executor holds a sender");
+            task.queued.store(false, Ordering::SeqCst);
+            let mut slot = task.future.lock().unwrap();
+            let Some(mut future) = slot.take() else {
+                continue;
+            };
+            let waker = Waker::from(task.clone());
+            let mut cx = Context::from_waker(&waker);
+            self.polls += 1;
+            match future.as_mut().poll(&mut cx) {
+                Poll::Pending => *slot = Some(future),
+                Poll::Ready(()) => {
+                    self.spawner.live.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    /// Total number of `poll` calls made by `run`.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,347 @@
+//! # Building a Waker by Hand
+//!
+//! In this exercise, you build a `std::task::Waker` from its raw parts: a data pointer and a
+//! `RawWakerVTable` of four functions. This is what `impl Wake for Task` (used in
+//! `05_mini_executor`) generates for you.
+//!
+//! ## Concepts
+//! - `RawWaker { data: *const (), vtable: &'static RawWakerVTable }`
+//! - The data pointer here is an `Arc<Task>` turned into a raw pointer (`Arc::into_raw`); every
+//!   `Waker` owns **one strong reference**
+//! - The four vtable entries and their ownership rules:
+//!   - `clone`: make a second waker -> one more reference (`Arc::increment_strong_count`)
+//!   - `wake`: consumes the waker -> schedule the task, then give the reference back
+//!   - `wake_by_ref`: waker stays alive -> schedule the task, reference count unchanged
+//!   - `drop`: the waker goes away -> give the reference back
+//! - Getting any of these wrong either leaks the task or frees it while a waker still points at
+//!   it; the tests watch `Arc::strong_count`
+//!
+//! ## Scheduling
+//! `Task::schedule` pushes the task id onto its `RunQueue` unless it is already queued, so however
+//! often a task is woken before the executor gets to it, it is polled once. The executor calls
+//! `Task::mark_polled` right before polling, after which the next wake queues it again.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{RawWaker, RawWakerVTable, Waker};
+
+/// Ids of tasks waiting to be polled.
+#[derive(Default)]
+pub struct RunQueue {
+    ids: Mutex<VecDeque<usize>>,
+}
+
+impl RunQueue {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn pop(&self) -> Option<usize> {
+        self.ids.lock().unwrap().pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct Task {
+    pub id: usize,
+    queue: Arc<RunQueue>,
+    queued: AtomicBool,
+    /// Times the task was actually put on the queue.
+    enqueued: AtomicUsize,
+}
+
+impl Task {
+    pub fn new(id: usize, queue: Arc<RunQueue>) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            queue,
+            queued: AtomicBool::new(false),
+            enqueued: AtomicUsize::new(0),
+        })
+    }
+
+    /// Put the task on the run queue unless it is already there.
+    pub fn schedule(&self) {
+        if !self.queued.swap(true, Ordering::SeqCst) {
+            self.enqueued.fetch_add(1, Ordering::SeqCst);
+            self.queue.ids.lock().unwrap().push_back(self.id);
+        }
+    }
+
+    /// The executor is about to poll the task: later wakes must queue it again.
+    pub fn mark_polled(&self) {
+        self.queued.store(false, Ordering::SeqCst);
+    }
+
+    pub fn enqueued(&self) -> usize {
+        self.enqueued.load(Ordering::SeqCst)
+    }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone_raw, wake_raw, wake_by_ref_raw, drop_raw);
+
+/// Turn a task handle into a `Waker`. The `Arc` is moved into the waker (no count change).
+///
+/// `Arc::into_raw` gives the data pointer (cast to `*const ()`); wrap it with `&VTABLE` in a
+/// `RawWaker` and call `Waker::from_raw` (unsafe: you promise the vtable follows the contract).
+pub fn task_waker(task: Arc<Task>) -> Waker {
+    // TODO
+    todo!()
+}
+
+/// `data` came from `Arc::<Task>::into_raw`. Add one strong reference for the new waker and
+/// return a `RawWaker` with the same data pointer and `&VTABLE`.
+unsafe fn clone_raw(data: *const ()) -> RawWaker {
+    // TODO
+    todo!()
+}
+
+/// Consume the waker: take its reference back with `Arc::from_raw`, schedule the task; the
+/// `Arc` drops at the end.
+unsafe fn wake_raw(data: *const ()) {
+    // TODO
+    todo!()
+}
+
+/// Schedule the task without touching the reference count (borrow through the pointer, or
+/// wrap `Arc::from_raw` in `ManuallyDrop`).
+unsafe fn wake_by_ref_raw(data: *const ()) {
+    // TODO
+    todo!()
+}
+
+/// Give the waker's reference back.
+unsafe fn drop_raw(data: *const ()) {
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::thread;
+
+    const HINTS: Hints = &[
+        (
+            "test_waker_owns_one_reference",
+            "task_waker moves the Arc in with Arc::into_raw, and the drop entry gives it back with Arc::from_raw",
+        ),
+        (
+            "test_clone_and_drop",
+            "clone adds one reference with Arc::increment_strong_count and returns a RawWaker with the same pointer and &VTABLE",
+        ),
+        (
+            "test_wake_by_ref_keeps_reference",
+            "wake_by_ref must not change the count: wrap Arc::from_raw in ManuallyDrop, or borrow through the pointer",
+        ),
+        (
+            "test_wake_consumes_reference",
+            "wake takes the reference back with Arc::from_raw, schedules the task and lets the Arc drop",
+        ),
+        (
+            "test_poll_then_wake_enqueues_exactly_once",
+            "schedule pushes the id only if the task is not queued yet; nothing is queued by creating or polling",
+        ),
+    ];
+
+    fn setup() -> (Arc<RunQueue>, Arc<Task>) {
+        let q = RunQueue::new();
+        let t = Task::new(7, q.clone());
+        (q, t)
+    }
+
+    /// Pending until `ready` is set; remembers the last waker, like a real I/O future.
+    struct Flag {
+        ready: Arc<AtomicBool>,
+        waker: Arc<Mutex<Option<Waker>>>,
+    }
+
+    impl Future for Flag {
+        type Output = &'static str;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<&'static str> {
+            if self.ready.load(Ordering::SeqCst) {
+                return Poll::Ready("done");
+            }
+            *self.waker.lock().unwrap() = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_waker_owns_one_reference() {
+        let (q, t) = setup();
+        let w = task_waker(t.clone());
+        assert_eq_with_hint!(Arc::strong_count(&t), 2);
+        drop(w);
+        assert_eq_with_hint!(Arc::strong_count(&t), 1, "dropping the waker releases it");
+        assert_with_hint!(q.is_empty());
+    }
+
+    #[test]
+    fn test_clone_and_drop() {
+        let (_q, t) = setup();
+        let w = task_waker(t.clone());
+        let w2 = w.clone();
+        let w3 = w2.clone();
+        assert_eq_with_hint!(Arc::strong_count(&t), 4);
+        assert_with_hint!(w.will_wake(&w3), "clones share data pointer and vtable");
+        drop(w2);
+        drop(w);
+        assert_eq_with_hint!(Arc::strong_count(&t), 2);
+        drop(w3);
+        assert_eq_with_hint!(Arc::strong_count(&t), 1);
+    }
+
+    #[test]
+    fn test_wake_by_ref_keeps_reference() {
+        let (q, t) = setup();
+        let w = task_waker(t.clone());
+        w.wake_by_ref();
+        assert_eq_with_hint!(q.pop(), Some(7));
+        assert_eq_with_hint!(Arc::strong_count(&t), 2);
+        drop(w);
+        assert_eq_with_hint!(Arc::strong_count(&t), 1);
+    }
+
+    #[test]
+    fn test_wake_consumes_reference() {
+        let (q, t) = setup();
+        let w = task_waker(t.clone());
+        w.wake();
+        assert_eq_with_hint!(q.pop(), Some(7));
+        assert_eq_with_hint!(Arc::strong_count(&t), 1);
+    }
+
+    #[test]
+    fn test_poll_then_wake_enqueues_exactly_once() {
+        let (q, t) = setup();
+        let ready = Arc::new(AtomicBool::new(false));
+        let slot = Arc::new(Mutex::new(None));
+        let mut fut = Flag {
+            ready: ready.clone(),
+            waker: slot.clone(),
+        };
+        let waker = task_waker(t.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        t.mark_polled();
+        assert_eq_with_hint!(Pin::new(&mut fut).poll(&mut cx), Poll::Pending);
+        assert_with_hint!(q.is_empty(), "polling alone does not schedule");
+
+        // The event source fires, several times, before the executor runs again.
+        ready.store(true, Ordering::SeqCst);
+        let stored = slot.lock().unwrap().take().unwrap();
+        stored.wake_by_ref();
+        stored.wake_by_ref();
+        stored.wake();
+        assert_eq_with_hint!(q.len(), 1, "re-enqueued exactly once");
+        assert_eq_with_hint!(t.enqueued(), 1);
+
+        assert_eq_with_hint!(q.pop(), Some(7));
+        t.mark_polled();
+        assert_eq_with_hint!(Pin::new(&mut fut).poll(&mut cx), Poll::Ready("done"));
+        drop(waker);
+        assert_eq_with_hint!(Arc::strong_count(&t), 1, "no waker leaked");
+    }
+
+    #[test]
+    fn test_woken_again_after_poll() {
+        let (q, t) = setup();
+        let w = task_waker(t.clone());
+        for round in 1..=3 {
+            w.wake_by_ref();
+            w.wake_by_ref();
+            assert_eq!(q.pop(), Some(7));
+            assert!(q.is_empty());
+            t.mark_polled();
+            assert_eq!(t.enqueued(), round);
+        }
+    }
+
+    #[test]
+    fn test_wake_from_many_threads() {
+        let (q, t) = setup();
+        let w = task_waker(t.clone());
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let w = w.clone();
+                thread::spawn(move || {
+                    if i % 2 == 0 {
+                        w.wake();
+                    } else {
+                        w.wake_by_ref();
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(q.len(), 1);
+        drop(w);
+        assert_eq!(Arc::strong_count(&t), 1);
+    }
+
+    #[test]
+    fn test_drive_future_with_run_queue() {
+        // A tiny executor loop: poll whenever the task id comes off the queue.
+        let (q, t) = setup();
+        let ready = Arc::new(AtomicBool::new(false));
+        let slot = Arc::new(Mutex::new(None));
+        let mut fut = Box::pin(Flag {
+            ready: ready.clone(),
+            waker: slot.clone(),
+        });
+        let waker = task_waker(t.clone());
+        t.schedule();
+
+        let setter = {
+            let (ready, slot) = (ready.clone(), slot.clone());
+            thread::spawn(move || {
+                while slot.lock().unwrap().is_none() {
+                    thread::yield_now();
+                }
+                ready.store(true, Ordering::SeqCst);
+                slot.lock().unwrap().take().unwrap().wake();
+            })
+        };
+        let mut polls = 0;
+        let out = loop {
+            let Some(id) = q.pop() else {
+                thread::yield_now();
+                continue;
+            };
+            assert_eq!(id, 7);
+            t.mark_polled();
+            polls += 1;
+            if let Poll::Ready(v) = fut.as_mut().poll(&mut Context::from_waker(&waker)) {
+                break v;
+            }
+        };
+        setter.join().unwrap();
+        assert_eq!((out, polls), ("done", 2));
+        drop(waker);
+        drop(fut);
+        assert_eq!(Arc::strong_count(&t), 1);
+    }
+}
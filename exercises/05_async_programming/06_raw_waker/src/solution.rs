@@ -0,0 +1,124 @@
+//! # Building a Waker by Hand
+//!
+//! In this exercise, you build a `std::task::Waker` from its raw parts: a data pointer and a
+//! `RawWakerVTable` of four functions. This is what `impl Wake for Task` (used in
+//! `05_mini_executor`) generates for you.
+//!
+//! ## Concepts
+//! - `RawWaker { data: *const (), vtable: &'static RawWakerVTable }`
+//! - The data pointer here is an `Arc<Task>` turned into a raw pointer (`Arc::into_raw`); every
+//!   `Waker` owns **one strong reference**
+//! - The four vtable entries and their ownership rules:
+//!   - `clone`: make a second waker -> one more reference (`Arc::increment_strong_count`)
+//!   - `wake`: consumes the waker -> schedule the task, then give the reference back
+//!   - `wake_by_ref`: waker stays alive -> schedule the task, reference count unchanged
+//!   - `drop`: the waker goes away -> give the reference back
+//! - Getting any of these wrong either leaks the task or frees it while a waker still points at
+//!   it; the tests watch `Arc::strong_count`
+//!
+//! ## Scheduling
+//! `Task::schedule` pushes the task id onto its `RunQueue` unless it is already queued, so however
+//! often a task is woken before the executor gets to it, it is polled once. The executor calls
+//! `Task::mark_polled` right before polling, after which the next wake queues it again.
+
+use std::collections::VecDeque;
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+pub(crate) use std::sync::{Arc, Mutex};
+pub(crate) use std::task::{RawWaker, RawWakerVTable, Waker};
+
+/// Ids of tasks waiting to be polled.
+#[derive(Default)]
+pub struct RunQueue {
+    ids: Mutex<VecDeque<usize>>,
+}
+
+impl RunQueue {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn pop(&self) -> Option<usize> {
+        self.ids.lock().unwrap().pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct Task {
+    pub id: usize,
+    queue: Arc<RunQueue>,
+    queued: AtomicBool,
+    /// Times the task was actually put on the queue.
+    enqueued: AtomicUsize,
+}
+
+impl Task {
+    pub fn new(id: usize, queue: Arc<RunQueue>) -> Arc<Self> {
+        Arc::new(Self {
+            id,
+            queue,
+            queued: AtomicBool::new(false),
+            enqueued: AtomicUsize::new(0),
+        })
+    }
+
+    /// Put the task on the run queue unless it is already there.
+    pub fn schedule(&self) {
+        if !self.queued.swap(true, Ordering::SeqCst) {
+            self.enqueued.fetch_add(1, Ordering::SeqCst);
+            self.queue.ids.lock().unwrap().push_back(self.id);
+        }
+    }
+
+    /// The executor is about to poll the task: later wakes must queue it again.
+    pub fn mark_polled(&self) {
+        self.queued.store(false, Ordering::SeqCst);
+    }
+
+    pub fn enqueued(&self) -> usize {
+        self.enqueued.load(Ordering::SeqCst)
+    }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone_raw, wake_raw, wake_by_ref_raw, drop_raw);
+
+/// Turn a task handle into a `Waker`. The `Arc` is moved into the waker (no count change).
+///
+/// `Arc::into_raw` gives the data pointer (cast to `*const ()`); wrap it with `&VTABLE` in a
+/// `RawWaker` and call `Waker::from_raw` (unsafe: you promise the vtable follows the contract).
+pub fn task_waker(task: Arc<Task>) -> Waker {
+    let data = Arc::into_raw(task) as *const ();
+    unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) }
+}
+
+/// `data` came from `Arc::<Task>::into_raw`. Add one strong reference for the new waker and
+/// return a `RawWaker` with the same data pointer and `&VTABLE`.
+unsafe fn clone_raw(data: *const ()) -> RawWaker {
+    Arc::increment_strong_count(data as *const Task);
+    RawWaker::new(data, &VTABLE)
+}
+
+/// Consume the waker: take its reference back with `Arc::from_raw`, schedule the task; the
+/// `Arc` drops at the end.
+unsafe fn wake_raw(data: *const ()) {
+    let task = Arc::from_raw(data as *const Task);
+    task.schedule();
+}
+
+/// Schedule the task without touching the reference count (borrow through the pointer, or
+/// wrap `Arc::from_raw` in `ManuallyDrop`).
+unsafe fn wake_by_ref_raw(data: *const ()) {
+    let task = &*(data as *const Task);
+    task.schedule();
+}
+
+/// Give the waker's reference back.
+unsafe fn drop_raw(data: *const ()) {
+    drop(Arc::from_raw(data as *const Task));
+}
@@ -0,0 +1,440 @@
+//! # Delay Future and Hashed Timer Wheel
+//!
+//! In this exercise, you implement what sits behind `tokio::time::sleep`: a `Delay` future whose
+//! waker is parked in a **timer wheel**, and the wheel that wakes it when time has come.
+//!
+//! ## Hashed timer wheel
+//! ```text
+//!   slot:   0     1     2     3     4     5     6     7        (WHEEL_SLOTS = 8)
+//!         [   ] [d=9] [   ] [d=3] [   ] [d=5] [   ] [   ]
+//!                [d=17]      [d=11]
+//!                  ^ now = 1: tick() moves to slot 2 ...
+//! ```
+//! Time is counted in ticks. A timer with deadline `d` lives in slot `d % WHEEL_SLOTS`. Each
+//! `tick()` advances `now` by one and looks at the single slot `now % WHEEL_SLOTS` only; entries
+//! there whose deadline has arrived fire, the others (deadline one or more rounds later) stay.
+//! Inserting and cancelling are O(1) plus one bucket; a tick never scans the whole wheel.
+//!
+//! ## Delay
+//! - `poll`: if `now >= deadline` -> `Ready`. Otherwise register the waker (first poll) or
+//!   update it (later polls may come with a different waker) and return `Pending`.
+//! - Dropping a pending `Delay` cancels its timer, so it never fires.
+//!
+//! Wakers are woken *after* the wheel's lock is released: a woken task may poll immediately (on
+//! another thread) and would otherwise block on the lock.
+//!
+//! Ticks come from explicit `tick()` calls (tests) or from a background thread (`spawn_driver`).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub const WHEEL_SLOTS: usize = 8;
+
+pub type TimerId = u64;
+
+struct Entry {
+    id: TimerId,
+    deadline: u64,
+    waker: Waker,
+}
+
+struct WheelInner {
+    now: u64,
+    slots: Vec<Vec<Entry>>,
+    next_id: TimerId,
+    /// Entries examined by all ticks so far.
+    scanned: usize,
+}
+
+impl WheelInner {
+    fn slot(deadline: u64) -> usize {
+        (deadline % WHEEL_SLOTS as u64) as usize
+    }
+
+    /// Add a timer firing at `deadline` (> `now`): push an entry with a fresh id (`next_id`,
+    /// then increment) into `slots[slot(deadline)]` and return the id.
+    fn insert(&mut self, deadline: u64, waker: Waker) -> TimerId {
+        // TODO
+        todo!()
+    }
+
+    /// Replace the waker of timer `id` unless the stored one `will_wake` the same task.
+    fn update(&mut self, id: TimerId, deadline: u64, waker: &Waker) {
+        if let Some(e) = self.slots[Self::slot(deadline)]
+            .iter_mut()
+            .find(|e| e.id == id)
+        {
+            if !e.waker.will_wake(waker) {
+                e.waker = waker.clone();
+            }
+        }
+    }
+
+    /// Remove timer `id` (which has deadline `deadline`) from its slot. Returns whether it was
+    /// still there (`false` if it already fired).
+    fn remove(&mut self, id: TimerId, deadline: u64) -> bool {
+        // TODO
+        todo!()
+    }
+
+    /// One tick: increment `now`, then go through `slots[slot(now)]` only, counting every
+    /// entry in `scanned`. Entries with `deadline <= now` are removed and their wakers
+    /// returned, in the order they were inserted; the rest stay.
+    fn expire(&mut self) -> Vec<Waker> {
+        // TODO
+        todo!()
+    }
+}
+
+/// Handle to a timer wheel; clones share the same wheel.
+#[derive(Clone)]
+pub struct TimerWheel {
+    inner: Arc<Mutex<WheelInner>>,
+}
+
+impl TimerWheel {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(WheelInner {
+                now: 0,
+                slots: (0..WHEEL_SLOTS).map(|_| Vec::new()).collect(),
+                next_id: 0,
+                scanned: 0,
+            })),
+        }
+    }
+
+    /// Current time in ticks.
+    pub fn now(&self) -> u64 {
+        self.inner.lock().unwrap().now
+    }
+
+    /// A future that completes `ticks` ticks from now.
+    pub fn delay(&self, ticks: u64) -> Delay {
+        Delay {
+            wheel: self.clone(),
+            deadline: self.now() + ticks,
+            id: None,
+        }
+    }
+
+    /// A future that completes when the wheel reaches tick `deadline` (at once if it already has).
+    pub fn delay_until(&self, deadline: u64) -> Delay {
+        Delay {
+            wheel: self.clone(),
+            deadline,
+            id: None,
+        }
+    }
+
+    /// Advance by one tick and wake the expired timers. Returns how many fired.
+    pub fn tick(&self) -> usize {
+        let wakers = self.inner.lock().unwrap().expire();
+        let n = wakers.len();
+        for w in wakers {
+            w.wake();
+        }
+        n
+    }
+
+    /// `n` ticks; returns how many timers fired in total.
+    pub fn advance(&self, n: u64) -> usize {
+        (0..n).map(|_| self.tick()).sum()
+    }
+
+    /// Timers registered and not yet fired or cancelled.
+    pub fn pending(&self) -> usize {
+        self.inner.lock().unwrap().slots.iter().map(Vec::len).sum()
+    }
+
+    /// Entries examined by all ticks so far.
+    pub fn scanned(&self) -> usize {
+        self.inner.lock().unwrap().scanned
+    }
+
+    /// Tick every `period` on a background thread until the returned `Driver` is dropped.
+    pub fn spawn_driver(&self, period: Duration) -> Driver {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (wheel, stop) = (self.clone(), stop.clone());
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    thread::sleep(period);
+                    wheel.tick();
+                }
+            })
+        };
+        Driver {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Background ticking thread; stopped and joined on drop.
+pub struct Driver {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Driver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+/// Completes once the wheel reaches `deadline`.
+pub struct Delay {
+    wheel: TimerWheel,
+    deadline: u64,
+    /// Set once the waker is registered.
+    id: Option<TimerId>,
+}
+
+impl Delay {
+    pub fn deadline(&self) -> u64 {
+        self.deadline
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    /// Under one lock of the wheel: `now >= deadline` -> `Ready`; no `id` yet -> `insert` and
+    /// store the id; otherwise `update` the waker. Then `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // TODO
+        todo!()
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.wheel.inner.lock().unwrap().remove(id, self.deadline);
+        }
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::AtomicUsize;
+    use std::task::Wake;
+    use std::time::Instant;
+
+    const HINTS: Hints = &[
+        (
+            "test_delay_fires_on_its_tick",
+            "poll registers the timer only on the first poll (id is None); later polls just update the waker",
+        ),
+        (
+            "test_fire_in_deadline_order",
+            "a slot also holds timers of later rounds: only fire entries with deadline <= now, keep the others",
+        ),
+        (
+            "test_dropped_delay_is_cancelled",
+            "Drop for Delay must cancel its timer (remove it from its slot) if it was registered",
+        ),
+        (
+            "test_latest_waker_is_woken",
+            "update must replace the stored waker unless it will_wake the same task",
+        ),
+        (
+            "test_tick_scans_one_slot",
+            "tick looks at slots[slot(now)] only and counts every entry it examines in scanned",
+        ),
+    ];
+
+    /// Waker that counts its wake-ups.
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counter() -> (Arc<Counter>, Waker) {
+        let c = Arc::new(Counter(AtomicUsize::new(0)));
+        (c.clone(), Waker::from(c))
+    }
+
+    fn poll(d: &mut Delay, w: &Waker) -> Poll<()> {
+        Pin::new(d).poll(&mut Context::from_waker(w))
+    }
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let mut f = std::pin::pin!(f);
+        let w = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        loop {
+            if let Poll::Ready(v) = f.as_mut().poll(&mut Context::from_waker(&w)) {
+                return v;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn test_zero_delay_is_ready() {
+        let wheel = TimerWheel::new();
+        let (c, w) = counter();
+        assert_eq!(poll(&mut wheel.delay(0), &w), Poll::Ready(()));
+        assert_eq!(wheel.pending(), 0, "nothing registered");
+        assert_eq!(c.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_delay_fires_on_its_tick() {
+        let wheel = TimerWheel::new();
+        let (c, w) = counter();
+        let mut d = wheel.delay(3);
+        assert_eq_with_hint!(poll(&mut d, &w), Poll::Pending);
+        assert_eq_with_hint!(
+            poll(&mut d, &w),
+            Poll::Pending,
+            "re-poll does not re-register"
+        );
+        assert_eq_with_hint!(wheel.pending(), 1);
+        assert_eq_with_hint!(wheel.advance(2), 0);
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 0);
+        assert_eq_with_hint!(wheel.tick(), 1);
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 1);
+        assert_eq_with_hint!(poll(&mut d, &w), Poll::Ready(()));
+        assert_eq_with_hint!(wheel.pending(), 0);
+    }
+
+    #[test]
+    fn test_fire_in_deadline_order() {
+        let wheel = TimerWheel::new();
+        // 11 and 19 share slot 3 with 3 (WHEEL_SLOTS = 8) but fire rounds later.
+        let ticks = [11, 5, 3, 19, 1, 8];
+        let mut delays: Vec<_> = ticks.iter().map(|&t| wheel.delay(t)).collect();
+        let counters: Vec<_> = ticks.iter().map(|_| counter()).collect();
+        for (d, (_, w)) in delays.iter_mut().zip(&counters) {
+            assert_eq_with_hint!(poll(d, w), Poll::Pending);
+        }
+        let mut order = Vec::new();
+        for _ in 0..20 {
+            wheel.tick();
+            for (i, (c, _)) in counters.iter().enumerate() {
+                if c.0.load(Ordering::SeqCst) == 1 && !order.contains(&ticks[i]) {
+                    assert_eq_with_hint!(wheel.now(), ticks[i], "fired exactly at its deadline");
+                    order.push(ticks[i]);
+                }
+            }
+        }
+        assert_eq_with_hint!(order, [1, 3, 5, 8, 11, 19]);
+        assert_eq_with_hint!(wheel.pending(), 0);
+    }
+
+    #[test]
+    fn test_same_deadline_fires_together() {
+        let wheel = TimerWheel::new();
+        let (c, w) = counter();
+        let mut a = wheel.delay(2);
+        let mut b = wheel.delay(2);
+        assert!(poll(&mut a, &w).is_pending() && poll(&mut b, &w).is_pending());
+        wheel.tick();
+        assert_eq!(wheel.tick(), 2);
+        assert_eq!(c.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_dropped_delay_is_cancelled() {
+        let wheel = TimerWheel::new();
+        let (c1, w1) = counter();
+        let (c2, w2) = counter();
+        let mut keep = wheel.delay(4);
+        let mut cancel = wheel.delay(4);
+        assert_with_hint!(poll(&mut keep, &w1).is_pending());
+        assert_with_hint!(poll(&mut cancel, &w2).is_pending());
+        drop(cancel);
+        assert_eq_with_hint!(wheel.pending(), 1);
+        assert_eq_with_hint!(wheel.advance(10), 1);
+        assert_eq_with_hint!(c1.0.load(Ordering::SeqCst), 1);
+        assert_eq_with_hint!(
+            c2.0.load(Ordering::SeqCst),
+            0,
+            "cancelled timer must not fire"
+        );
+        // Dropping after it fired is harmless.
+        drop(keep);
+        assert_eq_with_hint!(wheel.pending(), 0);
+    }
+
+    #[test]
+    fn test_latest_waker_is_woken() {
+        let wheel = TimerWheel::new();
+        let (old, w_old) = counter();
+        let (new, w_new) = counter();
+        let mut d = wheel.delay(1);
+        assert_with_hint!(poll(&mut d, &w_old).is_pending());
+        assert_with_hint!(poll(&mut d, &w_new).is_pending());
+        wheel.tick();
+        assert_eq_with_hint!(old.0.load(Ordering::SeqCst), 0);
+        assert_eq_with_hint!(new.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_tick_scans_one_slot() {
+        let wheel = TimerWheel::new();
+        let (_c, w) = counter();
+        // 64 timers spread over all slots: 8 per slot.
+        let mut delays: Vec<_> = (1..=64).map(|t| wheel.delay(t)).collect();
+        for d in &mut delays {
+            assert_with_hint!(poll(d, &w).is_pending());
+        }
+        wheel.tick();
+        assert_eq_with_hint!(wheel.scanned(), 8, "only slot 1 is examined");
+        assert_eq_with_hint!(wheel.pending(), 63);
+        assert_eq_with_hint!(wheel.advance(63), 63);
+        // Every slot is visited 8 times and loses one entry per visit: 8 * (8 + 7 + ... + 1).
+        assert_eq_with_hint!(wheel.scanned(), 8 * 36);
+    }
+
+    #[test]
+    fn test_background_driver() {
+        let wheel = TimerWheel::new();
+        let _driver = wheel.spawn_driver(Duration::from_millis(2));
+        let start = Instant::now();
+        block_on(wheel.delay(10));
+        assert!(wheel.now() >= 10);
+        assert!(start.elapsed() >= Duration::from_millis(18));
+        block_on(async {
+            wheel.delay(2).await;
+            wheel.delay(3).await;
+        });
+        assert!(wheel.now() >= 15);
+    }
+}
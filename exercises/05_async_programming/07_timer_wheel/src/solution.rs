@@ -0,0 +1,257 @@
+//! # Delay Future and Hashed Timer Wheel
+//!
+//! In this exercise, you implement what sits behind `tokio::time::sleep`: a `Delay` future whose
+//! waker is parked in a **timer wheel**, and the wheel that wakes it when time has come.
+//!
+//! ## Hashed timer wheel
+//! ```text
+//!   slot:   0     1     2     3     4     5     6     7        (WHEEL_SLOTS = 8)
+//!         [   ] [d=9] [   ] [d=3] [   ] [d=5] [   ] [   ]
+//!                [d=17]      [d=11]
+//!                  ^ now = 1: tick() moves to slot 2 ...
+//! ```
+//! Time is counted in ticks. A timer with deadline `d` lives in slot `d % WHEEL_SLOTS`. Each
+//! `tick()` advances `now` by one and looks at the single slot `now % WHEEL_SLOTS` only; entries
+//! there whose deadline has arrived fire, the others (deadline one or more rounds later) stay.
+//! Inserting and cancelling are O(1) plus one bucket; a tick never scans the whole wheel.
+//!
+//! ## Delay
+//! - `poll`: if `now >= deadline` -> `Ready`. Otherwise register the waker (first poll) or
+//!   update it (later polls may come with a different waker) and return `Pending`.
+//! - Dropping a pending `Delay` cancels its timer, so it never fires.
+//!
+//! Wakers are woken *after* the wheel's lock is released: a woken task may poll immediately (on
+//! another thread) and would otherwise block on the lock.
+//!
+//! Ticks come from explicit `tick()` calls (tests) or from a background thread (`spawn_driver`).
+
+pub(crate) use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::sync::atomic::{AtomicBool, Ordering};
+pub(crate) use std::sync::{Arc, Mutex};
+pub(crate) use std::task::{Context, Poll, Waker};
+pub(crate) use std::thread::{self, JoinHandle};
+pub(crate) use std::time::Duration;
+
+pub const WHEEL_SLOTS: usize = 8;
+
+pub type TimerId = u64;
+
+struct Entry {
+    id: TimerId,
+    deadline: u64,
+    waker: Waker,
+}
+
+struct WheelInner {
+    now: u64,
+    slots: Vec<Vec<Entry>>,
+    next_id: TimerId,
+    /// Entries examined by all ticks so far.
+    scanned: usize,
+}
+
+impl WheelInner {
+    fn slot(deadline: u64) -> usize {
+        (deadline % WHEEL_SLOTS as u64) as usize
+    }
+
+    /// Add a timer firing at `deadline` (> `now`): push an entry with a fresh id (`next_id`,
+    /// then increment) into `slots[slot(deadline)]` and return the id.
+    fn insert(&mut self, deadline: u64, waker: Waker) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.slots[Self::slot(deadline)].push(Entry {
+            id,
+            deadline,
+            waker,
+        });
+        id
+    }
+
+    /// Replace the waker of timer `id` unless the stored one `will_wake` the same task.
+    fn update(&mut self, id: TimerId, deadline: u64, waker: &Waker) {
+        if let Some(e) = self.slots[Self::slot(deadline)]
+            .iter_mut()
+            .find(|e| e.id == id)
+        {
+            if !e.waker.will_wake(waker) {
+                e.waker = waker.clone();
+            }
+        }
+    }
+
+    /// Remove timer `id` (which has deadline `deadline`) from its slot. Returns whether it was
+    /// still there (`false` if it already fired).
+    fn remove(&mut self, id: TimerId, deadline: u64) -> bool {
+        let slot = &mut self.slots[Self::slot(deadline)];
+        match slot.iter().position(|e| e.id == id) {
+            Some(i) => {
+                slot.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// One tick: increment `now`, then go through `slots[slot(now)]` only, counting every
+    /// entry in `scanned`. Entries with `deadline <= now` are removed and their wakers
+    /// returned, in the order they were inserted; the rest stay.
+    fn expire(&mut self) -> Vec<Waker> {
+        self.now += 1;
+        let now = self.now;
+        let slot = &mut self.slots[Self::slot(now)];
+        self.scanned += slot.len();
+        let (due, later): (Vec<Entry>, Vec<Entry>) =
+            slot.drain(..).partition(|e| e.deadline <= now);
+        *slot = later;
+        due.into_iter().map(|e| e.waker).collect()
+    }
+}
+
+/// Handle to a timer wheel; clones share the same wheel.
+#[derive(Clone)]
+pub struct TimerWheel {
+    inner: Arc<Mutex<WheelInner>>,
+}
+
+impl TimerWheel {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(WheelInner {
+                now: 0,
+                slots: (0..WHEEL_SLOTS).map(|_| Vec::new()).collect(),
+                next_id: 0,
+                scanned: 0,
+            })),
+        }
+    }
+
+    /// Current time in ticks.
+    pub fn now(&self) -> u64 {
+        self.inner.lock().unwrap().now
+    }
+
+    /// A future that completes `ticks` ticks from now.
+    pub fn delay(&self, ticks: u64) -> Delay {
+        Delay {
+            wheel: self.clone(),
+            deadline: self.now() + ticks,
+            id: None,
+        }
+    }
+
+    /// A future that completes when the wheel reaches tick `deadline` (at once if it already has).
+    pub fn delay_until(&self, deadline: u64) -> Delay {
+        Delay {
+            wheel: self.clone(),
+            deadline,
+            id: None,
+        }
+    }
+
+    /// Advance by one tick and wake the expired timers. Returns how many fired.
+    pub fn tick(&self) -> usize {
+        let wakers = self.inner.lock().unwrap().expire();
+        let n = wakers.len();
+        for w in wakers {
+            w.wake();
+        }
+        n
+    }
+
+    /// `n` ticks; returns how many timers fired in total.
+    pub fn advance(&self, n: u64) -> usize {
+        (0..n).map(|_| self.tick()).sum()
+    }
+
+    /// Timers registered and not yet fired or cancelled.
+    pub fn pending(&self) -> usize {
+        self.inner.lock().unwrap().slots.iter().map(Vec::len).sum()
+    }
+
+    /// Entries examined by all ticks so far.
+    pub fn scanned(&self) -> usize {
+        self.inner.lock().unwrap().scanned
+    }
+
+    /// Tick every `period` on a background thread until the returned `Driver` is dropped.
+    pub fn spawn_driver(&self, period: Duration) -> Driver {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (wheel, stop) = (self.clone(), stop.clone());
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    thread::sleep(period);
+                    wheel.tick();
+                }
+            })
+        };
+        Driver {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Background ticking thread; stopped and joined on drop.
+pub struct Driver {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Driver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+/// Completes once the wheel reaches `deadline`.
+pub struct Delay {
+    wheel: TimerWheel,
+    deadline: u64,
+    /// Set once the waker is registered.
+    id: Option<TimerId>,
+}
+
+impl Delay {
+    pub fn deadline(&self) -> u64 {
+        self.deadline
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    /// Under one lock of the wheel: `now >= deadline` -> `Ready`; no `id` yet -> `insert` and
+    /// store the id; otherwise `update` the waker. Then `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        let mut inner = this.wheel.inner.lock().unwrap();
+        if inner.now >= this.deadline {
+            return Poll::Ready(());
+        }
+        match this.id {
+            None => this.id = Some(inner.insert(this.deadline, cx.waker().clone())),
+            Some(id) => inner.update(id, this.deadline, cx.waker()),
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.wheel.inner.lock().unwrap().remove(id, self.deadline);
+        }
+    }
+}
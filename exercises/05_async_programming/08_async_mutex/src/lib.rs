@@ -0,0 +1,387 @@
+//! # Async Mutex with a FIFO Wait Queue
+//!
+//! In this exercise, you implement an `AsyncMutex<T>` whose `lock()` is a future. Compare with
+//! `03_os_concurrency/03_spinlock`: a contended spinlock burns the CPU, a contended `std::sync::Mutex`
+//! blocks the whole thread — and with it every other task of a single-threaded executor, possibly
+//! including the one holding the lock. A contended `AsyncMutex` just returns `Pending`; the
+//! executor runs other tasks until the holder unlocks and wakes the next waiter.
+//!
+//! ## Design
+//! - `State { locked, waiters }` behind a short `std::sync::Mutex` (never held across `.await`).
+//! - `lock()` returns a `LockFuture`. Its first poll takes the lock if it is free **and nobody is
+//!   waiting**; otherwise it joins the back of `waiters` with its waker.
+//! - Unlocking (dropping the guard) does not just clear `locked`: if someone waits, the lock is
+//!   **handed over** to the front waiter (`granted = true`, `locked` stays set) and that waiter
+//!   is woken. Nobody can barge in between, so waiters get the lock in FIFO order.
+//! - A `LockFuture` dropped while waiting removes itself; if the lock had already been handed to
+//!   it, it passes the lock on (given, see `Drop for LockFuture`).
+
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+struct Waiter {
+    id: u64,
+    waker: Waker,
+    /// The lock has been handed to this waiter; its next poll returns the guard.
+    granted: bool,
+}
+
+struct State {
+    locked: bool,
+    waiters: VecDeque<Waiter>,
+    next_id: u64,
+}
+
+impl State {
+    /// The holder gives the lock up. If there is a waiter, grant the lock to the front one and
+    /// return its waker (to be woken after the state lock is dropped); `locked` stays `true`.
+    /// Otherwise set `locked = false` and return `None`.
+    fn release(&mut self) -> Option<Waker> {
+        // TODO
+        todo!()
+    }
+}
+
+pub struct AsyncMutex<T> {
+    state: Mutex<State>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for AsyncMutex<T> {}
+unsafe impl<T: Send> Sync for AsyncMutex<T> {}
+
+impl<T> AsyncMutex<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            state: Mutex::new(State {
+                locked: false,
+                waiters: VecDeque::new(),
+                next_id: 0,
+            }),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn lock(&self) -> LockFuture<'_, T> {
+        LockFuture {
+            mutex: self,
+            id: None,
+        }
+    }
+
+    /// Take the lock if it is free and nobody is queued for it.
+    pub fn try_lock(&self) -> Option<AsyncMutexGuard<'_, T>> {
+        let mut st = self.state.lock().unwrap();
+        if st.locked || !st.waiters.is_empty() {
+            return None;
+        }
+        st.locked = true;
+        Some(AsyncMutexGuard { mutex: self })
+    }
+
+    /// Number of tasks queued for the lock.
+    pub fn waiters(&self) -> usize {
+        self.state.lock().unwrap().waiters.len()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+/// Future returned by `AsyncMutex::lock`.
+pub struct LockFuture<'a, T> {
+    mutex: &'a AsyncMutex<T>,
+    /// Our entry in `waiters`, once queued.
+    id: Option<u64>,
+}
+
+impl<'a, T> Future for LockFuture<'a, T> {
+    type Output = AsyncMutexGuard<'a, T>;
+
+    /// Lock `state`, then:
+    /// - not queued yet (`id` is `None`): if `!locked` and `waiters` is empty, set `locked` and
+    ///   return the guard. Otherwise push a `Waiter` with a fresh id (`next_id`) and the current
+    ///   waker, remember the id, return `Pending`.
+    /// - queued: find our entry. If `granted`, remove it, clear `self.id` and return the guard
+    ///   (the lock is already ours). Otherwise refresh its waker and return `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // TODO
+        todo!()
+    }
+}
+
+impl<T> Drop for LockFuture<'_, T> {
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+        let wake = {
+            let mut st = self.mutex.state.lock().unwrap();
+            let pos = st.waiters.iter().position(|w| w.id == id);
+            match pos.and_then(|p| st.waiters.remove(p)) {
+                // Handed the lock but never took it: pass it on.
+                Some(w) if w.granted => st.release(),
+                _ => None,
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+pub struct AsyncMutexGuard<'a, T> {
+    mutex: &'a AsyncMutex<T>,
+}
+
+impl<T> Deref for AsyncMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for AsyncMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for AsyncMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let wake = self.mutex.state.lock().unwrap().release();
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::Wake;
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_uncontended_lock",
+            "take the lock at once when it is free and nobody is queued; dropping the guard calls release",
+        ),
+        (
+            "test_contended_lock_is_pending_then_handed_over",
+            "release grants the lock to the front waiter and returns its waker, which is woken after the state lock is dropped",
+        ),
+        (
+            "test_fifo_order_with_manual_polls",
+            "queue waiters in arrival order and always grant the front one; a newcomer must not jump the queue while waiters exist",
+        ),
+        (
+            "test_cancelled_waiter_passes_lock_on",
+            "a LockFuture dropped while queued removes its entry; if it had already been granted the lock, release it to the next waiter",
+        ),
+    ];
+
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counter() -> (Arc<Counter>, Waker) {
+        let c = Arc::new(Counter(AtomicUsize::new(0)));
+        (c.clone(), Waker::from(c))
+    }
+
+    fn poll<F: Future + Unpin>(f: &mut F, w: &Waker) -> Poll<F::Output> {
+        Pin::new(f).poll(&mut Context::from_waker(w))
+    }
+
+    #[test]
+    fn test_uncontended_lock() {
+        let m = AsyncMutex::new(5);
+        let (_, w) = counter();
+        let Poll::Ready(mut g) = poll(&mut m.lock(), &w) else {
+            panic!("free lock must be ready")
+        };
+        *g += 1;
+        assert_with_hint!(m.try_lock().is_none());
+        drop(g);
+        assert_eq_with_hint!(*m.try_lock().unwrap(), 6);
+        assert_eq_with_hint!(m.into_inner(), 6);
+    }
+
+    #[test]
+    fn test_contended_lock_is_pending_then_handed_over() {
+        let m = AsyncMutex::new(());
+        let (c, w) = counter();
+        let guard = m.try_lock().unwrap();
+        let mut f = m.lock();
+        assert_with_hint!(poll(&mut f, &w).is_pending());
+        assert_eq_with_hint!(m.waiters(), 1);
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 0);
+
+        drop(guard);
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 1, "unlock wakes the waiter");
+        // Handed over: nobody can barge in before the waiter runs.
+        assert_with_hint!(m.try_lock().is_none());
+        let mut barger = m.lock();
+        assert_with_hint!(poll(&mut barger, &w).is_pending());
+
+        let Poll::Ready(g) = poll(&mut f, &w) else {
+            panic!("granted waiter must get the lock")
+        };
+        assert_eq_with_hint!(m.waiters(), 1, "only the barger still waits");
+        drop(g);
+        assert_with_hint!(poll(&mut barger, &w).is_ready());
+    }
+
+    #[test]
+    fn test_fifo_order_with_manual_polls() {
+        let m = AsyncMutex::new(Vec::new());
+        let (_, w) = counter();
+        let mut guard = m.try_lock().unwrap();
+        let mut futs: Vec<_> = (0..4).map(|_| m.lock()).collect();
+        for f in &mut futs {
+            assert_with_hint!(poll(f, &w).is_pending());
+        }
+        guard.push(99);
+        drop(guard);
+        // Poll in reverse order: only the front waiter can succeed each round.
+        let mut order = Vec::new();
+        while order.len() < 4 {
+            for (i, f) in futs.iter_mut().enumerate().rev() {
+                if order.contains(&i) {
+                    continue;
+                }
+                if let Poll::Ready(mut g) = poll(f, &w) {
+                    g.push(i);
+                    order.push(i);
+                }
+            }
+        }
+        assert_eq_with_hint!(order, [0, 1, 2, 3]);
+        assert_eq_with_hint!(*m.try_lock().unwrap(), [99, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_cancelled_waiter_passes_lock_on() {
+        let m = AsyncMutex::new(());
+        let (c, w) = counter();
+        let guard = m.try_lock().unwrap();
+        let mut a = m.lock();
+        let mut b = m.lock();
+        assert_with_hint!(poll(&mut a, &w).is_pending());
+        assert_with_hint!(poll(&mut b, &w).is_pending());
+
+        drop(guard); // granted to `a`
+        drop(a); // ... which gives up: `b` must get it
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 2);
+        assert_with_hint!(poll(&mut b, &w).is_ready());
+
+        // b's guard was dropped right away; the queue is empty and the lock is free again.
+        assert_eq_with_hint!(m.waiters(), 0);
+        assert_with_hint!(m.try_lock().is_some());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_waiting_does_not_block_executor() {
+        let m = Arc::new(AsyncMutex::new(0));
+        let released = Arc::new(AtomicBool::new(false));
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let guard = m.lock().await;
+
+        let waiter = {
+            let m = m.clone();
+            tokio::spawn(async move {
+                *m.lock().await += 1;
+            })
+        };
+        let ticker = {
+            let (released, ticks) = (released.clone(), ticks.clone());
+            tokio::spawn(async move {
+                while !released.load(Ordering::SeqCst) {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(m.waiters(), 1);
+        assert!(
+            ticks.load(Ordering::SeqCst) > 0,
+            "other tasks must keep running on the only thread"
+        );
+        released.store(true, Ordering::SeqCst);
+        drop(guard);
+        waiter.await.unwrap();
+        ticker.await.unwrap();
+        assert_eq!(*m.lock().await, 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_fifo_under_contention() {
+        let m = Arc::new(AsyncMutex::new(Vec::new()));
+        let guard = m.lock().await;
+        let handles: Vec<_> = (0..6)
+            .map(|i| {
+                let m = m.clone();
+                tokio::spawn(async move {
+                    let mut g = m.lock().await;
+                    g.push(i);
+                    // Hold the lock across an await point.
+                    tokio::task::yield_now().await;
+                })
+            })
+            .collect();
+        // Let every task queue up, in spawn order.
+        while m.waiters() < 6 {
+            tokio::task::yield_now().await;
+        }
+        drop(guard);
+        for h in handles {
+            h.await.unwrap();
+        }
+        assert_eq!(*m.lock().await, [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_mutual_exclusion_multi_thread() {
+        let m = Arc::new(AsyncMutex::new(0u64));
+        let inside = Arc::new(AtomicBool::new(false));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (m, inside) = (m.clone(), inside.clone());
+                tokio::spawn(async move {
+                    for _ in 0..100 {
+                        let mut g = m.lock().await;
+                        assert!(!inside.swap(true, Ordering::SeqCst), "two holders at once");
+                        *g += 1;
+                        tokio::task::yield_now().await;
+                        inside.store(false, Ordering::SeqCst);
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.await.unwrap();
+        }
+        assert_eq!(*m.lock().await, 800);
+    }
+}
@@ -0,0 +1,205 @@
+//! # Async Mutex with a FIFO Wait Queue
+//!
+//! In this exercise, you implement an `AsyncMutex<T>` whose `lock()` is a future. Compare with
+//! `03_os_concurrency/03_spinlock`: a contended spinlock burns the CPU, a contended `std::sync::Mutex`
+//! blocks the whole thread — and with it every other task of a single-threaded executor, possibly
+//! including the one holding the lock. A contended `AsyncMutex` just returns `Pending`; the
+//! executor runs other tasks until the holder unlocks and wakes the next waiter.
+//!
+//! ## Design
+//! - `State { locked, waiters }` behind a short `std::sync::Mutex` (never held across `.await`).
+//! - `lock()` returns a `LockFuture`. Its first poll takes the lock if it is free **and nobody is
+//!   waiting**; otherwise it joins the back of `waiters` with its waker.
+//! - Unlocking (dropping the guard) does not just clear `locked`: if someone waits, the lock is
+//!   **handed over** to the front waiter (`granted = true`, `locked` stays set) and that waiter
+//!   is woken. Nobody can barge in between, so waiters get the lock in FIFO order.
+//! - A `LockFuture` dropped while waiting removes itself; if the lock had already been handed to
+//!   it, it passes the lock on (given, see `Drop for LockFuture`).
+
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+pub(crate) use std::future::Future;
+use std::ops::{Deref, DerefMut};
+pub(crate) use std::pin::Pin;
+use std::sync::Mutex;
+pub(crate) use std::task::{Context, Poll, Waker};
+
+struct Waiter {
+    id: u64,
+    waker: Waker,
+    /// The lock has been handed to this waiter; its next poll returns the guard.
+    granted: bool,
+}
+
+struct State {
+    locked: bool,
+    waiters: VecDeque<Waiter>,
+    next_id: u64,
+}
+
+impl State {
+    /// The holder gives the lock up. If there is a waiter, grant the lock to the front one and
+    /// return its waker (to be woken after the state lock is dropped); `locked` stays `true`.
+    /// Otherwise set `locked = false` and return `None`.
+    fn release(&mut self) -> Option<Waker> {
+        match self.waiters.front_mut() {
+            Some(w) => {
+                w.granted = true;
+                Some(w.waker.clone())
+            }
+            None => {
+                self.locked = false;
+                None
+            }
+        }
+    }
+}
+
+pub struct AsyncMutex<T> {
+    state: Mutex<State>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for AsyncMutex<T> {}
+unsafe impl<T: Send> Sync for AsyncMutex<T> {}
+
+impl<T> AsyncMutex<T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            state: Mutex::new(State {
+                locked: false,
+                waiters: VecDeque::new(),
+                next_id: 0,
+            }),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub fn lock(&self) -> LockFuture<'_, T> {
+        LockFuture {
+            mutex: self,
+            id: None,
+        }
+    }
+
+    /// Take the lock if it is free and nobody is queued for it.
+    pub fn try_lock(&self) -> Option<AsyncMutexGuard<'_, T>> {
+        let mut st = self.state.lock().unwrap();
+        if st.locked || !st.waiters.is_empty() {
+            return None;
+        }
+        st.locked = true;
+        Some(AsyncMutexGuard { mutex: self })
+    }
+
+    /// Number of tasks queued for the lock.
+    pub fn waiters(&self) -> usize {
+        self.state.lock().unwrap().waiters.len()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+/// Future returned by `AsyncMutex::lock`.
+pub struct LockFuture<'a, T> {
+    mutex: &'a AsyncMutex<T>,
+    /// Our entry in `waiters`, once queued.
+    id: Option<u64>,
+}
+
+impl<'a, T> Future for LockFuture<'a, T> {
+    type Output = AsyncMutexGuard<'a, T>;
+
+    /// Lock `state`, then:
+    /// - not queued yet (`id` is `None`): if `!locked` and `waiters` is empty, set `locked` and
+    ///   return the guard. Otherwise push a `Waiter` with a fresh id (`next_id`) and the current
+    ///   waker, remember the id, return `Pending`.
+    /// - queued: find our entry. If `granted`, remove it, clear `self.id` and return the guard
+    ///   (the lock is already ours). Otherwise refresh its waker and return `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mutex = this.mutex;
+        let mut st = mutex.state.lock().unwrap();
+        match this.id {
+            None => {
+                if !st.locked && st.waiters.is_empty() {
+                    st.locked = true;
+                    return Poll::Ready(AsyncMutexGuard { mutex });
+                }
+                let id = st.next_id;
+                st.next_id += 1;
+                st.waiters.push_back(Waiter {
+                    id,
+                    waker: cx.waker().clone(),
+                    granted: false,
+                });
+                this.id = Some(id);
+                Poll::Pending
+            }
+            Some(id) => {
+                let pos = st
+                    .waiters
+                    .iter()
+                    .position(|w| w.id == id)
+                    .expect("queued waiter disappeared");
+                if st.waiters[pos].granted {
+                    st.waiters.remove(pos);
+                    this.id = None;
+                    return Poll::Ready(AsyncMutexGuard { mutex });
+                }
+                let w = &mut st.waiters[pos];
+                if !w.waker.will_wake(cx.waker()) {
+                    w.waker = cx.waker().clone();
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for LockFuture<'_, T> {
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+        let wake = {
+            let mut st = self.mutex.state.lock().unwrap();
+            let pos = st.waiters.iter().position(|w| w.id == id);
+            match pos.and_then(|p| st.waiters.remove(p)) {
+                // Handed the lock but never took it: pass it on.
+                Some(w) if w.granted => st.release(),
+                _ => None,
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+pub struct AsyncMutexGuard<'a, T> {
+    mutex: &'a AsyncMutex<T>,
+}
+
+impl<T> Deref for AsyncMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for AsyncMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for AsyncMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let wake = self.mutex.state.lock().unwrap().release();
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! # Async Semaphore and a Concurrency-Limited Runner
+//!
+//! In this exercise, you implement an `AsyncSemaphore` with `n` permits and use it to run a batch
+//! of futures with at most `max_concurrent` of them in flight at once.
+//!
+//! ## Design
+//! The structure follows `08_async_mutex` (a mutex is a semaphore with one permit):
+//! - `State { permits, waiters, granted }` behind a short `std::sync::Mutex`.
+//! - `acquire()` takes a permit right away only if one is free **and nobody is queued**;
+//!   otherwise the `Acquire` future queues `(id, waker)` at the back of `waiters`.
+//! - Releasing a permit hands it directly to the front waiter (its id moves into `granted`) and
+//!   wakes it, instead of incrementing `permits`. Waiters are therefore served in FIFO order and a
+//!   newcomer can never overtake them.
+//! - `AsyncSemaphore` is a cheap `Clone` handle (`Arc` inside), so permits can move into
+//!   `tokio::spawn`ed tasks.
+
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+struct State {
+    /// Free permits.
+    permits: usize,
+    /// Queued acquirers, front = first to be served.
+    waiters: VecDeque<(u64, Waker)>,
+    /// Acquirers that have been handed a permit but have not been polled since.
+    granted: HashSet<u64>,
+    next_id: u64,
+}
+
+impl State {
+    /// A permit is given back. If someone waits, pop the front waiter, record its id in `granted`
+    /// and return its waker (woken by the caller after the lock is released). Otherwise put the
+    /// permit back into `permits` and return `None`.
+    fn release(&mut self) -> Option<Waker> {
+        // TODO
+        todo!()
+    }
+}
+
+#[derive(Clone)]
+pub struct AsyncSemaphore {
+    state: Arc<Mutex<State>>,
+}
+
+impl AsyncSemaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                permits,
+                waiters: VecDeque::new(),
+                granted: HashSet::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    pub fn acquire(&self) -> Acquire {
+        Acquire {
+            sem: self.clone(),
+            id: None,
+        }
+    }
+
+    /// Take a permit if one is free and nobody is queued for one.
+    pub fn try_acquire(&self) -> Option<Permit> {
+        let mut st = self.state.lock().unwrap();
+        if st.permits == 0 || !st.waiters.is_empty() {
+            return None;
+        }
+        st.permits -= 1;
+        Some(Permit { sem: self.clone() })
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.state.lock().unwrap().permits
+    }
+
+    /// Number of acquirers still queued (not yet granted).
+    pub fn waiters(&self) -> usize {
+        self.state.lock().unwrap().waiters.len()
+    }
+
+    fn release(&self) {
+        let wake = self.state.lock().unwrap().release();
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+/// Future returned by `AsyncSemaphore::acquire`.
+pub struct Acquire {
+    sem: AsyncSemaphore,
+    /// Our queue id, once queued.
+    id: Option<u64>,
+}
+
+impl Future for Acquire {
+    type Output = Permit;
+
+    /// Lock the state, then:
+    /// - not queued yet: if `permits > 0` and `waiters` is empty, take one and return a `Permit`.
+    ///   Otherwise push `(next_id, waker)` to the back of `waiters`, remember the id, `Pending`.
+    /// - queued: if our id can be removed from `granted`, clear `self.id` and return the `Permit`
+    ///   (the permit was handed to us). Otherwise refresh our waker in `waiters`, `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit> {
+        // TODO
+        todo!()
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+        let wake = {
+            let mut st = self.sem.state.lock().unwrap();
+            if st.granted.remove(&id) {
+                // Handed a permit but never took it: pass it on.
+                st.release()
+            } else {
+                st.waiters.retain(|(w, _)| *w != id);
+                None
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+/// One permit; given back on drop.
+pub struct Permit {
+    sem: AsyncSemaphore,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.sem.release();
+    }
+}
+
+/// Run all `futures` with at most `max_concurrent` of them in flight, returning their outputs
+/// in input order.
+///
+/// Create an `AsyncSemaphore::new(max_concurrent)`. For each future, `tokio::spawn` a task that
+/// first acquires a permit and only then awaits the future, holding the permit until it is done.
+/// Finally await the `JoinHandle`s in order.
+///
+/// Panics if `max_concurrent == 0`.
+pub async fn run_limited<F>(futures: Vec<F>, max_concurrent: usize) -> Vec<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    assert!(max_concurrent > 0, "max_concurrent must be positive");
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_try_acquire_counts_permits",
+            "try_acquire takes a permit only when permits > 0; dropping the Permit gives it back",
+        ),
+        (
+            "test_acquire_pending_then_handed_over",
+            "release hands the permit to the front waiter (its id goes into granted) instead of incrementing permits",
+        ),
+        (
+            "test_fifo_order",
+            "serve waiters in queue order: a new acquire with waiters queued goes to the back even if permits > 0",
+        ),
+        (
+            "test_cancelled_acquire",
+            "an Acquire dropped while queued leaves waiters; one dropped after being granted releases its permit again",
+        ),
+        (
+            "test_run_limited_high_water_mark",
+            "acquire the permit inside the spawned task before awaiting the job, and keep it until the job is done",
+        ),
+    ];
+
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counter() -> (Arc<Counter>, Waker) {
+        let c = Arc::new(Counter(AtomicUsize::new(0)));
+        (c.clone(), Waker::from(c))
+    }
+
+    fn poll<F: Future + Unpin>(f: &mut F, w: &Waker) -> Poll<F::Output> {
+        Pin::new(f).poll(&mut Context::from_waker(w))
+    }
+
+    /// Tracks how many jobs run at once and the high-water mark.
+    #[derive(Clone, Default)]
+    struct InFlight {
+        now: Arc<AtomicUsize>,
+        max: Arc<AtomicUsize>,
+    }
+
+    impl InFlight {
+        fn job(&self, i: usize, ms: u64) -> impl Future<Output = usize> + Send + 'static {
+            let this = self.clone();
+            async move {
+                let n = this.now.fetch_add(1, Ordering::SeqCst) + 1;
+                this.max.fetch_max(n, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(ms)).await;
+                this.now.fetch_sub(1, Ordering::SeqCst);
+                i * 10
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_acquire_counts_permits() {
+        let sem = AsyncSemaphore::new(2);
+        let a = sem.try_acquire().unwrap();
+        let b = sem.try_acquire().unwrap();
+        assert_eq_with_hint!(sem.available_permits(), 0);
+        assert_with_hint!(sem.try_acquire().is_none());
+        drop(a);
+        assert_eq_with_hint!(sem.available_permits(), 1);
+        drop(b);
+        assert_eq_with_hint!(sem.available_permits(), 2);
+    }
+
+    #[test]
+    fn test_acquire_pending_then_handed_over() {
+        let sem = AsyncSemaphore::new(1);
+        let (c, w) = counter();
+        let held = sem.try_acquire().unwrap();
+        let mut acq = sem.acquire();
+        assert_with_hint!(poll(&mut acq, &w).is_pending());
+        assert_eq_with_hint!(sem.waiters(), 1);
+
+        drop(held);
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 1, "release wakes the waiter");
+        assert_eq_with_hint!(sem.available_permits(), 0, "permit handed over, not freed");
+        assert_with_hint!(sem.try_acquire().is_none());
+
+        let Poll::Ready(p) = poll(&mut acq, &w) else {
+            panic!("granted acquirer must get the permit")
+        };
+        drop(p);
+        assert_eq_with_hint!(sem.available_permits(), 1);
+    }
+
+    #[test]
+    fn test_fifo_order() {
+        let sem = AsyncSemaphore::new(1);
+        let (_, w) = counter();
+        let held = sem.try_acquire().unwrap();
+        let mut acqs: Vec<_> = (0..4).map(|_| sem.acquire()).collect();
+        for a in &mut acqs {
+            assert_with_hint!(poll(a, &w).is_pending());
+        }
+        drop(held);
+        let mut order = Vec::new();
+        while order.len() < 4 {
+            for (i, a) in acqs.iter_mut().enumerate().rev() {
+                if order.contains(&i) {
+                    continue;
+                }
+                if poll(a, &w).is_ready() {
+                    // The permit is dropped at once, handing it to the next in line.
+                    order.push(i);
+                }
+            }
+        }
+        assert_eq_with_hint!(order, [0, 1, 2, 3]);
+        assert_eq_with_hint!(sem.available_permits(), 1);
+    }
+
+    #[test]
+    fn test_cancelled_acquire() {
+        let sem = AsyncSemaphore::new(1);
+        let (_, w) = counter();
+        let held = sem.try_acquire().unwrap();
+        let mut a = sem.acquire();
+        let mut b = sem.acquire();
+        let mut c = sem.acquire();
+        assert_with_hint!(poll(&mut a, &w).is_pending());
+        assert_with_hint!(poll(&mut b, &w).is_pending());
+        assert_with_hint!(poll(&mut c, &w).is_pending());
+
+        drop(b); // not granted yet: just leaves the queue
+        assert_eq_with_hint!(sem.waiters(), 2);
+        drop(held); // granted to `a`
+        drop(a); // ... which gives up: passed on to `c`
+        assert_eq_with_hint!(sem.waiters(), 0);
+        assert_with_hint!(poll(&mut c, &w).is_ready());
+        assert_eq_with_hint!(sem.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_limited_high_water_mark() {
+        let f = InFlight::default();
+        let jobs: Vec<_> = (0..10).map(|i| f.job(i, 20)).collect();
+        let out = run_limited(jobs, 3).await;
+        assert_eq_with_hint!(out, (0..10).map(|i| i * 10).collect::<Vec<_>>());
+        assert_eq_with_hint!(f.max.load(Ordering::SeqCst), 3);
+        assert_eq_with_hint!(f.now.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_limited_one_at_a_time() {
+        let f = InFlight::default();
+        let jobs: Vec<_> = (0..5).map(|i| f.job(i, 5)).collect();
+        assert_eq!(run_limited(jobs, 1).await, [0, 10, 20, 30, 40]);
+        assert_eq!(f.max.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_limited_limit_above_count() {
+        let f = InFlight::default();
+        let jobs: Vec<_> = (0..4).map(|i| f.job(i, 50)).collect();
+        let start = tokio::time::Instant::now();
+        assert_eq!(run_limited(jobs, 100).await.len(), 4);
+        assert_eq!(f.max.load(Ordering::SeqCst), 4);
+        assert!(
+            start.elapsed() < Duration::from_millis(150),
+            "all jobs should run concurrently"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_limited_empty() {
+        let jobs: Vec<std::future::Ready<u8>> = Vec::new();
+        assert!(run_limited(jobs, 2).await.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_permits_never_exceeded_multi_thread() {
+        let sem = AsyncSemaphore::new(2);
+        let f = InFlight::default();
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let (sem, f) = (sem.clone(), f.clone());
+                tokio::spawn(async move {
+                    let _p = sem.acquire().await;
+                    f.job(i, 2).await
+                })
+            })
+            .collect();
+        for h in handles {
+            h.await.unwrap();
+        }
+        assert_eq!(f.max.load(Ordering::SeqCst), 2);
+        assert_eq!(sem.available_permits(), 2);
+    }
+}
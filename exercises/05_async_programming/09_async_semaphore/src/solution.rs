@@ -0,0 +1,212 @@
+//! # Async Semaphore and a Concurrency-Limited Runner
+//!
+//! In this exercise, you implement an `AsyncSemaphore` with `n` permits and use it to run a batch
+//! of futures with at most `max_concurrent` of them in flight at once.
+//!
+//! ## Design
+//! The structure follows `08_async_mutex` (a mutex is a semaphore with one permit):
+//! - `State { permits, waiters, granted }` behind a short `std::sync::Mutex`.
+//! - `acquire()` takes a permit right away only if one is free **and nobody is queued**;
+//!   otherwise the `Acquire` future queues `(id, waker)` at the back of `waiters`.
+//! - Releasing a permit hands it directly to the front waiter (its id moves into `granted`) and
+//!   wakes it, instead of incrementing `permits`. Waiters are therefore served in FIFO order and a
+//!   newcomer can never overtake them.
+//! - `AsyncSemaphore` is a cheap `Clone` handle (`Arc` inside), so permits can move into
+//!   `tokio::spawn`ed tasks.
+
+use std::collections::{HashSet, VecDeque};
+pub(crate) use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::sync::{Arc, Mutex};
+pub(crate) use std::task::{Context, Poll, Waker};
+
+struct State {
+    /// Free permits.
+    permits: usize,
+    /// Queued acquirers, front = first to be served.
+    waiters: VecDeque<(u64, Waker)>,
+    /// Acquirers that have been handed a permit but have not been polled since.
+    granted: HashSet<u64>,
+    next_id: u64,
+}
+
+impl State {
+    /// A permit is given back. If someone waits, pop the front waiter, record its id in `granted`
+    /// and return its waker (woken by the caller after the lock is released). Otherwise put the
+    /// permit back into `permits` and return `None`.
+    fn release(&mut self) -> Option<Waker> {
+        match self.waiters.pop_front() {
+            Some((id, waker)) => {
+                self.granted.insert(id);
+                Some(waker)
+            }
+            None => {
+                self.permits += 1;
+                None
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AsyncSemaphore {
+    state: Arc<Mutex<State>>,
+}
+
+impl AsyncSemaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                permits,
+                waiters: VecDeque::new(),
+                granted: HashSet::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    pub fn acquire(&self) -> Acquire {
+        Acquire {
+            sem: self.clone(),
+            id: None,
+        }
+    }
+
+    /// Take a permit if one is free and nobody is queued for one.
+    pub fn try_acquire(&self) -> Option<Permit> {
+        let mut st = self.state.lock().unwrap();
+        if st.permits == 0 || !st.waiters.is_empty() {
+            return None;
+        }
+        st.permits -= 1;
+        Some(Permit { sem: self.clone() })
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.state.lock().unwrap().permits
+    }
+
+    /// Number of acquirers still queued (not yet granted).
+    pub fn waiters(&self) -> usize {
+        self.state.lock().unwrap().waiters.len()
+    }
+
+    fn release(&self) {
+        let wake = self.state.lock().unwrap().release();
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+/// Future returned by `AsyncSemaphore::acquire`.
+pub struct Acquire {
+    sem: AsyncSemaphore,
+    /// Our queue id, once queued.
+    id: Option<u64>,
+}
+
+impl Future for Acquire {
+    type Output = Permit;
+
+    /// Lock the state, then:
+    /// - not queued yet: if `permits > 0` and `waiters` is empty, take one and return a `Permit`.
+    ///   Otherwise push `(next_id, waker)` to the back of `waiters`, remember the id, `Pending`.
+    /// - queued: if our id can be removed from `granted`, clear `self.id` and return the `Permit`
+    ///   (the permit was handed to us). Otherwise refresh our waker in `waiters`, `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Permit> {
+        let this = self.get_mut();
+        let mut st = this.sem.state.lock().unwrap();
+        match this.id {
+            None => {
+                if st.permits > 0 && st.waiters.is_empty() {
+                    st.permits -= 1;
+                    return Poll::Ready(Permit {
+                        sem: this.sem.clone(),
+                    });
+                }
+                let id = st.next_id;
+                st.next_id += 1;
+                st.waiters.push_back((id, cx.waker().clone()));
+                this.id = Some(id);
+                Poll::Pending
+            }
+            Some(id) => {
+                if st.granted.remove(&id) {
+                    this.id = None;
+                    return Poll::Ready(Permit {
+                        sem: this.sem.clone(),
+                    });
+                }
+                if let Some((_, w)) = st.waiters.iter_mut().find(|(w, _)| *w == id) {
+                    if !w.will_wake(cx.waker()) {
+                        *w = cx.waker().clone();
+                    }
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        let Some(id) = self.id else { return };
+        let wake = {
+            let mut st = self.sem.state.lock().unwrap();
+            if st.granted.remove(&id) {
+                // Handed a permit but never took it: pass it on.
+                st.release()
+            } else {
+                st.waiters.retain(|(w, _)| *w != id);
+                None
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+/// One permit; given back on drop.
+pub struct Permit {
+    sem: AsyncSemaphore,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.sem.release();
+    }
+}
+
+/// Run all `futures` with at most `max_concurrent` of them in flight, returning their outputs
+/// in input order.
+///
+/// Create an `AsyncSemaphore::new(max_concurrent)`. For each future, `tokio::spawn` a task that
+/// first acquires a permit and only then awaits the future, holding the permit until it is done.
+/// Finally await the `JoinHandle`s in order.
+///
+/// Panics if `max_concurrent == 0`.
+pub async fn run_limited<F>(futures: Vec<F>, max_concurrent: usize) -> Vec<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    assert!(max_concurrent > 0, "max_concurrent must be positive");
+    let sem = AsyncSemaphore::new(max_concurrent);
+    let handles: Vec<_> = futures
+        .into_iter()
+        .map(|f| {
+            let sem = sem.clone();
+            tokio::spawn(async move {
+                let _permit = sem.acquire().await;
+                f.await
+            })
+        })
+        .collect();
+    let mut out = Vec::with_capacity(handles.len());
+    for h in handles {
+        out.push(h.await.unwrap());
+    }
+    out
+}
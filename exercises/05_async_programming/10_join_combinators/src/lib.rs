@@ -0,0 +1,356 @@
+//! # Hand-Written Join Combinators
+//!
+//! In this exercise, you implement `join2`, `try_join2` and `join_all` as plain `Future` types,
+//! without `futures-util` or `tokio::join!`. `04_select_timeout` races futures and keeps the first
+//! result; joining waits for **all** of them, polling them concurrently within a single task.
+//!
+//! ## Concepts
+//! - Every `poll` of the combinator polls each sub-future that has not finished yet
+//! - A finished sub-future must not be polled again: its output is stored until all are done
+//! - `try_join2` resolves with the first `Err` immediately, dropping the other future unfinished
+//!
+//! ## Building block
+//! `MaybeDone<F>` is one slot: a running future, its stored output, or `Gone` once the output has
+//! been taken. The future is kept in a `Pin<Box<F>>`, so every type here is `Unpin` and can be
+//! polled through `Pin::get_mut` without unsafe code.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+pub enum MaybeDone<F: Future> {
+    Future(Pin<Box<F>>),
+    Done(F::Output),
+    Gone,
+}
+
+// The future is boxed and the output is never pinned, so moving a `MaybeDone` is fine.
+impl<F: Future> Unpin for MaybeDone<F> {}
+
+impl<F: Future> MaybeDone<F> {
+    pub fn new(f: F) -> Self {
+        MaybeDone::Future(Box::pin(f))
+    }
+
+    /// Drive the slot; returns `true` once an output is stored (or was already taken).
+    ///
+    /// If the slot holds a future, poll it; on `Ready(v)` replace the slot with `Done(v)`.
+    /// A `Done` or `Gone` slot is not polled again.
+    pub fn poll_done(&mut self, cx: &mut Context<'_>) -> bool {
+        // TODO
+        todo!()
+    }
+
+    /// The stored output, if any.
+    pub fn output(&self) -> Option<&F::Output> {
+        match self {
+            MaybeDone::Done(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Take the stored output, leaving `Gone` behind.
+    pub fn take(&mut self) -> Option<F::Output> {
+        match std::mem::replace(self, MaybeDone::Gone) {
+            MaybeDone::Done(v) => Some(v),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+}
+
+pub struct Join2<A: Future, B: Future> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+/// Wait for both futures; resolves with both outputs.
+pub fn join2<A: Future, B: Future>(a: A, b: B) -> Join2<A, B> {
+    Join2 {
+        a: MaybeDone::new(a),
+        b: MaybeDone::new(b),
+    }
+}
+
+impl<A: Future, B: Future> Future for Join2<A, B> {
+    type Output = (A::Output, B::Output);
+
+    /// Poll **both** slots (do not stop after the first one is pending — both must make
+    /// progress). When both are done, take both outputs.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // TODO
+        todo!()
+    }
+}
+
+pub struct TryJoin2<A: Future, B: Future> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+/// Wait for two fallible futures; resolves with the first error as soon as either fails.
+pub fn try_join2<A, B, T, U, E>(a: A, b: B) -> TryJoin2<A, B>
+where
+    A: Future<Output = Result<T, E>>,
+    B: Future<Output = Result<U, E>>,
+{
+    TryJoin2 {
+        a: MaybeDone::new(a),
+        b: MaybeDone::new(b),
+    }
+}
+
+impl<A, B, T, U, E> Future for TryJoin2<A, B>
+where
+    A: Future<Output = Result<T, E>>,
+    B: Future<Output = Result<U, E>>,
+{
+    type Output = Result<(T, U), E>;
+
+    /// Poll both slots. If either holds an `Err`, return it right away (the other future is
+    /// simply dropped together with `self` later). When both hold `Ok`, return both values.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // TODO
+        todo!()
+    }
+}
+
+pub struct JoinAll<F: Future> {
+    slots: Vec<MaybeDone<F>>,
+}
+
+/// Wait for every future; outputs are returned in input order, whatever order they finish in.
+pub fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> JoinAll<F> {
+    JoinAll {
+        slots: futures.into_iter().map(MaybeDone::new).collect(),
+    }
+}
+
+impl<F: Future> Future for JoinAll<F> {
+    type Output = Vec<F::Output>;
+
+    /// Poll every slot. When all are done, take the outputs in order.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Wake, Waker};
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_join2_both_ready",
+            "poll both slots in the same poll, and return Ready once both hold an output",
+        ),
+        (
+            "test_join2_a_finishes_first",
+            "a slot that is already Done is not polled again; keep polling only the unfinished one",
+        ),
+        (
+            "test_try_join2_error_is_early",
+            "return an Err as soon as one side produced it, without waiting for the other side",
+        ),
+        (
+            "test_join_all_keeps_input_order",
+            "take the outputs in slot order once every slot is done, whatever order they finished in",
+        ),
+        (
+            "test_join_all_empty",
+            "join_all of nothing is Ready(vec![]) on the first poll",
+        ),
+    ];
+
+    struct NoopWake;
+
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    type Log = Arc<Mutex<Vec<String>>>;
+
+    /// Pending for `pending` polls, then ready with `value`. Logs every poll, panics if polled
+    /// after completion, and sets `dropped` when dropped.
+    struct Steps<T> {
+        name: &'static str,
+        pending: usize,
+        value: Option<T>,
+        log: Log,
+        dropped: Arc<AtomicBool>,
+    }
+
+    fn steps<T>(name: &'static str, pending: usize, value: T, log: &Log) -> Steps<T> {
+        Steps {
+            name,
+            pending,
+            value: Some(value),
+            log: log.clone(),
+            dropped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    impl<T> Unpin for Steps<T> {}
+
+    impl<T> Future for Steps<T> {
+        type Output = T;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+            self.log.lock().unwrap().push(self.name.to_string());
+            if self.pending == 0 {
+                return Poll::Ready(self.value.take().expect("polled after completion"));
+            }
+            self.pending -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    impl<T> Drop for Steps<T> {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Poll `f` until ready; returns the output and the number of polls.
+    fn drive<F: Future + Unpin>(mut f: F) -> (F::Output, usize) {
+        let waker = Waker::from(Arc::new(NoopWake));
+        let mut cx = Context::from_waker(&waker);
+        for n in 1..100 {
+            if let Poll::Ready(v) = Pin::new(&mut f).poll(&mut cx) {
+                return (v, n);
+            }
+        }
+        panic!("future never completed");
+    }
+
+    fn new_log() -> Log {
+        Arc::new(Mutex::new(Vec::new()))
+    }
+
+    fn joined(log: &Log) -> String {
+        log.lock().unwrap().concat()
+    }
+
+    #[test]
+    fn test_join2_both_ready() {
+        let log = new_log();
+        let (out, polls) = drive(join2(steps("a", 0, 1, &log), steps("b", 0, "x", &log)));
+        assert_eq_with_hint!(out, (1, "x"));
+        assert_eq_with_hint!(polls, 1);
+        assert_eq_with_hint!(joined(&log), "ab");
+    }
+
+    #[test]
+    fn test_join2_a_finishes_first() {
+        let log = new_log();
+        let (out, polls) = drive(join2(steps("a", 1, 'a', &log), steps("b", 3, 'b', &log)));
+        assert_eq_with_hint!(out, ('a', 'b'));
+        assert_eq_with_hint!(polls, 4);
+        // `a` is not polled again after it completed.
+        assert_eq_with_hint!(joined(&log), "ababbb");
+    }
+
+    #[test]
+    fn test_join2_b_finishes_first() {
+        let log = new_log();
+        let (out, _) = drive(join2(steps("a", 2, 10, &log), steps("b", 0, 20, &log)));
+        assert_eq!(out, (10, 20));
+        assert_eq!(joined(&log), "abaa");
+    }
+
+    #[test]
+    fn test_try_join2_ok() {
+        let log = new_log();
+        let a = steps("a", 2, Ok::<_, String>(1), &log);
+        let b = steps("b", 1, Ok::<_, String>(2), &log);
+        assert_eq!(drive(try_join2(a, b)).0, Ok((1, 2)));
+    }
+
+    #[test]
+    fn test_try_join2_error_is_early() {
+        let log = new_log();
+        let a = steps("a", 10, Ok::<u8, &str>(1), &log);
+        let a_dropped = a.dropped.clone();
+        let b = steps("b", 1, Err::<u8, &str>("boom"), &log);
+        let (out, polls) = drive(try_join2(a, b));
+        assert_eq_with_hint!(out, Err("boom"));
+        assert_eq_with_hint!(polls, 2, "must not wait for the slow Ok side");
+        assert_eq_with_hint!(joined(&log), "abab");
+        assert_with_hint!(
+            a_dropped.load(Ordering::SeqCst),
+            "unfinished future is dropped"
+        );
+    }
+
+    #[test]
+    fn test_try_join2_error_after_ok() {
+        let log = new_log();
+        let a = steps("a", 0, Err::<u8, u8>(7), &log);
+        let b = steps("b", 3, Ok::<u8, u8>(1), &log);
+        assert_eq!(drive(try_join2(a, b)).0, Err(7));
+    }
+
+    #[test]
+    fn test_join_all_keeps_input_order() {
+        let log = new_log();
+        let futs = vec![
+            steps("0", 3, 'a', &log),
+            steps("1", 0, 'b', &log),
+            steps("2", 1, 'c', &log),
+        ];
+        let (out, polls) = drive(join_all(futs));
+        assert_eq_with_hint!(out, ['a', 'b', 'c']);
+        assert_eq_with_hint!(polls, 4);
+        assert_eq_with_hint!(joined(&log), "0120200");
+    }
+
+    #[test]
+    fn test_join_all_empty() {
+        let futs: Vec<Steps<u8>> = Vec::new();
+        let (out, polls) = drive(join_all(futs));
+        assert_with_hint!(out.is_empty());
+        assert_eq_with_hint!(polls, 1);
+    }
+
+    // Paused clock: it jumps straight to the next timer, so the elapsed time below is exact.
+    #[tokio::test(start_paused = true)]
+    async fn test_join_runs_concurrently() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let sleeper = |ms: u64, v: u64| {
+            let polls = polls.clone();
+            async move {
+                polls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(ms)).await;
+                v
+            }
+        };
+        let start = tokio::time::Instant::now();
+        let (a, b) = join2(sleeper(80, 1), sleeper(80, 2)).await;
+        let all = join_all((0..5).map(|i| sleeper(80, i))).await;
+        assert_eq!((a, b), (1, 2));
+        assert_eq!(all, [0, 1, 2, 3, 4]);
+        assert_eq!(polls.load(Ordering::SeqCst), 7);
+        // One 80ms sleep per join, not one per future.
+        assert_eq!(
+            start.elapsed(),
+            Duration::from_millis(160),
+            "joined futures should wait concurrently"
+        );
+    }
+}
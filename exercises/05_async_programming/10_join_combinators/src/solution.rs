@@ -0,0 +1,172 @@
+//! # Hand-Written Join Combinators
+//!
+//! In this exercise, you implement `join2`, `try_join2` and `join_all` as plain `Future` types,
+//! without `futures-util` or `tokio::join!`. `04_select_timeout` races futures and keeps the first
+//! result; joining waits for **all** of them, polling them concurrently within a single task.
+//!
+//! ## Concepts
+//! - Every `poll` of the combinator polls each sub-future that has not finished yet
+//! - A finished sub-future must not be polled again: its output is stored until all are done
+//! - `try_join2` resolves with the first `Err` immediately, dropping the other future unfinished
+//!
+//! ## Building block
+//! `MaybeDone<F>` is one slot: a running future, its stored output, or `Gone` once the output has
+//! been taken. The future is kept in a `Pin<Box<F>>`, so every type here is `Unpin` and can be
+//! polled through `Pin::get_mut` without unsafe code.
+
+pub(crate) use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::task::{Context, Poll};
+
+pub enum MaybeDone<F: Future> {
+    Future(Pin<Box<F>>),
+    Done(F::Output),
+    Gone,
+}
+
+// The future is boxed and the output is never pinned, so moving a `MaybeDone` is fine.
+impl<F: Future> Unpin for MaybeDone<F> {}
+
+impl<F: Future> MaybeDone<F> {
+    pub fn new(f: F) -> Self {
+        MaybeDone::Future(Box::pin(f))
+    }
+
+    /// Drive the slot; returns `true` once an output is stored (or was already taken).
+    ///
+    /// If the slot holds a future, poll it; on `Ready(v)` replace the slot with `Done(v)`.
+    /// A `Done` or `Gone` slot is not polled again.
+    pub fn poll_done(&mut self, cx: &mut Context<'_>) -> bool {
+        if let MaybeDone::Future(f) = self {
+            match f.as_mut().poll(cx) {
+                Poll::Ready(v) => *self = MaybeDone::Done(v),
+                Poll::Pending => return false,
+            }
+        }
+        true
+    }
+
+    /// The stored output, if any.
+    pub fn output(&self) -> Option<&F::Output> {
+        match self {
+            MaybeDone::Done(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Take the stored output, leaving `Gone` behind.
+    pub fn take(&mut self) -> Option<F::Output> {
+        match std::mem::replace(self, MaybeDone::Gone) {
+            MaybeDone::Done(v) => Some(v),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+}
+
+pub struct Join2<A: Future, B: Future> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+/// Wait for both futures; resolves with both outputs.
+pub fn join2<A: Future, B: Future>(a: A, b: B) -> Join2<A, B> {
+    Join2 {
+        a: MaybeDone::new(a),
+        b: MaybeDone::new(b),
+    }
+}
+
+impl<A: Future, B: Future> Future for Join2<A, B> {
+    type Output = (A::Output, B::Output);
+
+    /// Poll **both** slots (do not stop after the first one is pending — both must make
+    /// progress). When both are done, take both outputs.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let a_done = this.a.poll_done(cx);
+        let b_done = this.b.poll_done(cx);
+        if a_done && b_done {
+            Poll::Ready((this.a.take().unwrap(), this.b.take().unwrap()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+pub struct TryJoin2<A: Future, B: Future> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+/// Wait for two fallible futures; resolves with the first error as soon as either fails.
+pub fn try_join2<A, B, T, U, E>(a: A, b: B) -> TryJoin2<A, B>
+where
+    A: Future<Output = Result<T, E>>,
+    B: Future<Output = Result<U, E>>,
+{
+    TryJoin2 {
+        a: MaybeDone::new(a),
+        b: MaybeDone::new(b),
+    }
+}
+
+impl<A, B, T, U, E> Future for TryJoin2<A, B>
+where
+    A: Future<Output = Result<T, E>>,
+    B: Future<Output = Result<U, E>>,
+{
+    type Output = Result<(T, U), E>;
+
+    /// Poll both slots. If either holds an `Err`, return it right away (the other future is
+    /// simply dropped together with `self` later). When both hold `Ok`, return both values.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let a_done = this.a.poll_done(cx);
+        if let Some(Err(_)) = this.a.output() {
+            return Poll::Ready(Err(this.a.take().unwrap().err().unwrap()));
+        }
+        let b_done = this.b.poll_done(cx);
+        if let Some(Err(_)) = this.b.output() {
+            return Poll::Ready(Err(this.b.take().unwrap().err().unwrap()));
+        }
+        if a_done && b_done {
+            let a = this.a.take().unwrap().ok().unwrap();
+            let b = this.b.take().unwrap().ok().unwrap();
+            Poll::Ready(Ok((a, b)))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+pub struct JoinAll<F: Future> {
+    slots: Vec<MaybeDone<F>>,
+}
+
+/// Wait for every future; outputs are returned in input order, whatever order they finish in.
+pub fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> JoinAll<F> {
+    JoinAll {
+        slots: futures.into_iter().map(MaybeDone::new).collect(),
+    }
+}
+
+impl<F: Future> Future for JoinAll<F> {
+    type Output = Vec<F::Output>;
+
+    /// Poll every slot. When all are done, take the outputs in order.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut all_done = true;
+        for slot in &mut this.slots {
+            all_done &= slot.poll_done(cx);
+        }
+        if all_done {
+            Poll::Ready(this.slots.iter_mut().map(|s| s.take().unwrap()).collect())
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! # Hand-Written Select
+//!
+//! In this exercise, you implement `Select2<A, B>`: a future that polls two futures and resolves
+//! with whichever finishes first, dropping the other one. This is what
+//! `tokio::select! { biased; a = fa => ..., b = fb => ... }` (see `04_select_timeout`) boils down to.
+//!
+//! ## Concepts
+//! - Sub-futures are stored **inline**, not boxed, so they may be `!Unpin` (e.g. `async` blocks).
+//!   To poll them we need `Pin<&mut A>` from `Pin<&mut Select2<A, B>>`: a *pin projection*.
+//! - A pinned value must not be moved until it is dropped. `Pin::set(slot, None)` drops the
+//!   loser in place, which is allowed.
+//! - Fairness: this select is **biased** — `a` is polled first, so if both are ready in the same
+//!   poll, `a` wins. (`tokio::select!` picks a random branch first unless told `biased;`.)
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Which side finished first.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+pub struct Select2<A, B> {
+    /// `None` once the future has finished or been dropped as the loser.
+    a: Option<A>,
+    b: Option<B>,
+}
+
+pub fn select2<A: Future, B: Future>(a: A, b: B) -> Select2<A, B> {
+    Select2 {
+        a: Some(a),
+        b: Some(b),
+    }
+}
+
+impl<A, B> Select2<A, B> {
+    /// Pin projection: turn `Pin<&mut Self>` into pinned references to both slots.
+    ///
+    /// Use `Pin::get_unchecked_mut` and `Pin::new_unchecked` (both `unsafe`). It is sound here
+    /// because we never move `a` or `b` out of a pinned `Select2`, `Select2` has no `Drop` impl
+    /// that could move them, and it does not implement `Unpin` unless both fields are `Unpin`.
+    fn project(self: Pin<&mut Self>) -> (Pin<&mut Option<A>>, Pin<&mut Option<B>>) {
+        // TODO
+        todo!()
+    }
+}
+
+impl<A: Future, B: Future> Future for Select2<A, B> {
+    type Output = Either<A::Output, B::Output>;
+
+    /// 1. `project()` the slots.
+    /// 2. If `a` is still there (`Pin::as_pin_mut`), poll it. On `Ready(v)`: drop both futures in
+    ///    place (`Pin::set(slot, None)`) and return `Left(v)`.
+    /// 3. Same for `b`, returning `Right(v)`.
+    /// 4. If both slots were already empty, the select was polled after completion: panic with
+    ///    `"Select2 polled after completion"`. Otherwise `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::marker::PhantomPinned;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_right_wins",
+            "poll a and then b in every poll; a Pending a must not stop b from being polled",
+        ),
+        (
+            "test_simultaneous_ready_is_biased_to_left",
+            "poll a first: when both are ready, Left wins",
+        ),
+        (
+            "test_loser_dropped_on_completion",
+            "on Ready, set both slots to None with Pin::set so both futures are dropped at once",
+        ),
+        (
+            "test_nothing_dropped_while_pending",
+            "only clear the slots when one future completes, not while both are pending",
+        ),
+    ];
+
+    struct NoopWake;
+
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Ready with `value` after `pending` polls; `!Unpin`; records its drop.
+    struct Steps {
+        pending: usize,
+        value: u32,
+        dropped: Arc<AtomicBool>,
+        _pin: PhantomPinned,
+    }
+
+    fn steps(pending: usize, value: u32) -> (Steps, Arc<AtomicBool>) {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let s = Steps {
+            pending,
+            value,
+            dropped: dropped.clone(),
+            _pin: PhantomPinned,
+        };
+        (s, dropped)
+    }
+
+    impl Future for Steps {
+        type Output = u32;
+
+        fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<u32> {
+            let this = unsafe { self.get_unchecked_mut() };
+            if this.pending == 0 {
+                return Poll::Ready(this.value);
+            }
+            this.pending -= 1;
+            Poll::Pending
+        }
+    }
+
+    impl Drop for Steps {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn poll_n<F: Future>(mut f: Pin<&mut F>, n: usize) -> Option<F::Output> {
+        let waker = Waker::from(Arc::new(NoopWake));
+        let mut cx = Context::from_waker(&waker);
+        (0..n).find_map(|_| match f.as_mut().poll(&mut cx) {
+            Poll::Ready(v) => Some(v),
+            Poll::Pending => None,
+        })
+    }
+
+    #[test]
+    fn test_left_wins() {
+        let (a, _) = steps(1, 1);
+        let (b, _) = steps(5, 2);
+        let s = pin!(select2(a, b));
+        assert_eq!(poll_n(s, 10), Some(Either::Left(1)));
+    }
+
+    #[test]
+    fn test_right_wins() {
+        let (a, _) = steps(5, 1);
+        let (b, _) = steps(2, 2);
+        let s = pin!(select2(a, b));
+        assert_eq_with_hint!(poll_n(s, 10), Some(Either::Right(2)));
+    }
+
+    #[test]
+    fn test_pending_until_one_is_ready() {
+        let (a, _) = steps(3, 1);
+        let (b, _) = steps(3, 2);
+        let mut s = pin!(select2(a, b));
+        assert_eq!(poll_n(s.as_mut(), 3), None);
+        assert_eq!(poll_n(s, 1), Some(Either::Left(1)));
+    }
+
+    #[test]
+    fn test_simultaneous_ready_is_biased_to_left() {
+        let (a, _) = steps(2, 1);
+        let (b, _) = steps(2, 2);
+        let s = pin!(select2(a, b));
+        assert_eq_with_hint!(poll_n(s, 10), Some(Either::Left(1)));
+    }
+
+    #[test]
+    fn test_loser_dropped_on_completion() {
+        let (a, a_dropped) = steps(4, 1);
+        let (b, b_dropped) = steps(0, 2);
+        let mut s = pin!(select2(a, b));
+        assert_eq_with_hint!(poll_n(s.as_mut(), 1), Some(Either::Right(2)));
+        // The select itself is still alive, but the loser is gone already.
+        assert_with_hint!(a_dropped.load(Ordering::SeqCst));
+        assert_with_hint!(b_dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_nothing_dropped_while_pending() {
+        let (a, a_dropped) = steps(4, 1);
+        let (b, b_dropped) = steps(4, 2);
+        let s = pin!(select2(a, b));
+        assert_eq_with_hint!(poll_n(s, 2), None);
+        assert_with_hint!(!a_dropped.load(Ordering::SeqCst));
+        assert_with_hint!(!b_dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    #[should_panic(expected = "polled after completion")]
+    fn test_poll_after_completion_panics() {
+        let (a, _) = steps(0, 1);
+        let (b, _) = steps(0, 2);
+        let mut s = pin!(select2(a, b));
+        assert!(poll_n(s.as_mut(), 1).is_some());
+        poll_n(s, 1);
+    }
+
+    // Paused clock: it jumps straight to the next timer, so the elapsed time below is exact.
+    #[tokio::test(start_paused = true)]
+    async fn test_select_with_async_blocks() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            "slow"
+        };
+        let fast = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            7
+        };
+        let start = tokio::time::Instant::now();
+        assert_eq!(select2(slow, fast).await, Either::Right(7));
+        assert_eq!(start.elapsed(), Duration::from_millis(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_select_as_timeout() {
+        let work = std::future::pending::<()>();
+        let timeout = tokio::time::sleep(Duration::from_millis(20));
+        let start = tokio::time::Instant::now();
+        assert_eq!(select2(work, timeout).await, Either::Right(()));
+        assert_eq!(start.elapsed(), Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,88 @@
+//! # Hand-Written Select
+//!
+//! In this exercise, you implement `Select2<A, B>`: a future that polls two futures and resolves
+//! with whichever finishes first, dropping the other one. This is what
+//! `tokio::select! { biased; a = fa => ..., b = fb => ... }` (see `04_select_timeout`) boils down to.
+//!
+//! ## Concepts
+//! - Sub-futures are stored **inline**, not boxed, so they may be `!Unpin` (e.g. `async` blocks).
+//!   To poll them we need `Pin<&mut A>` from `Pin<&mut Select2<A, B>>`: a *pin projection*.
+//! - A pinned value must not be moved until it is dropped. `Pin::set(slot, None)` drops the
+//!   loser in place, which is allowed.
+//! - Fairness: this select is **biased** — `a` is polled first, so if both are ready in the same
+//!   poll, `a` wins. (`tokio::select!` picks a random branch first unless told `biased;`.)
+
+pub(crate) use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::task::{Context, Poll};
+
+/// Which side finished first.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+pub struct Select2<A, B> {
+    /// `None` once the future has finished or been dropped as the loser.
+    a: Option<A>,
+    b: Option<B>,
+}
+
+pub fn select2<A: Future, B: Future>(a: A, b: B) -> Select2<A, B> {
+    Select2 {
+        a: Some(a),
+        b: Some(b),
+    }
+}
+
+impl<A, B> Select2<A, B> {
+    /// Pin projection: turn `Pin<&mut Self>` into pinned references to both slots.
+    ///
+    /// Use `Pin::get_unchecked_mut` and `Pin::new_unchecked` (both `unsafe`). It is sound here
+    /// because we never move `a` or `b` out of a pinned `Select2`, `Select2` has no `Drop` impl
+    /// that could move them, and it does not implement `Unpin` unless both fields are `Unpin`.
+    fn project(self: Pin<&mut Self>) -> (Pin<&mut Option<A>>, Pin<&mut Option<B>>) {
+        // SAFETY: see above; the fields are only ever accessed through pinned references.
+        unsafe {
+            let this = self.get_unchecked_mut();
+            (
+                Pin::new_unchecked(&mut this.a),
+                Pin::new_unchecked(&mut this.b),
+            )
+        }
+    }
+}
+
+impl<A: Future, B: Future> Future for Select2<A, B> {
+    type Output = Either<A::Output, B::Output>;
+
+    /// 1. `project()` the slots.
+    /// 2. If `a` is still there (`Pin::as_pin_mut`), poll it. On `Ready(v)`: drop both futures in
+    ///    place (`Pin::set(slot, None)`) and return `Left(v)`.
+    /// 3. Same for `b`, returning `Right(v)`.
+    /// 4. If both slots were already empty, the select was polled after completion: panic with
+    ///    `"Select2 polled after completion"`. Otherwise `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let (mut a, mut b) = self.project();
+        let mut any = false;
+        if let Some(fa) = a.as_mut().as_pin_mut() {
+            any = true;
+            if let Poll::Ready(v) = fa.poll(cx) {
+                a.set(None);
+                b.set(None);
+                return Poll::Ready(Either::Left(v));
+            }
+        }
+        if let Some(fb) = b.as_mut().as_pin_mut() {
+            any = true;
+            if let Poll::Ready(v) = fb.poll(cx) {
+                a.set(None);
+                b.set(None);
+                return Poll::Ready(Either::Right(v));
+            }
+        }
+        assert!(any, "Select2 polled after completion");
+        Poll::Pending
+    }
+}
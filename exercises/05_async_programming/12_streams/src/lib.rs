@@ -0,0 +1,310 @@
+//! # Streams: Counter, Interval, Map and Collect
+//!
+//! In this exercise, you implement a minimal `Stream` trait — the async version of `Iterator` —
+//! and a few streams on top of it, then drive them on the executor from `05_mini_executor`.
+//!
+//! ## Concepts
+//! - `poll_next` returns `Ready(Some(item))`, `Ready(None)` (stream finished) or `Pending`
+//!   (no item yet; the waker is registered with whatever the stream is waiting on)
+//! - `Interval` is a stream of `Delay`s from `07_timer_wheel`: each time the current delay
+//!   fires, yield an item and arm the next delay
+//! - Adapters (`Map`) wrap a stream and forward `Pending` untouched
+//! - Consumers (`collect_n`) are futures that poll a stream repeatedly
+//!
+//! Needs `05_mini_executor` (tests) and `07_timer_wheel` finished first.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use timer_wheel::{Delay, TimerWheel};
+
+pub trait Stream {
+    type Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+/// Convenience methods, implemented for every stream.
+pub trait StreamExt: Stream {
+    /// Apply `f` to every item.
+    fn map<T, F>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> T,
+    {
+        Map { stream: self, f }
+    }
+
+    /// Future resolving to the next item (`None` when the stream is finished).
+    fn next(&mut self) -> Next<'_, Self>
+    where
+        Self: Unpin,
+    {
+        Next { stream: self }
+    }
+
+    /// Future resolving to the next `n` items, or fewer if the stream finishes first.
+    fn collect_n(self, n: usize) -> CollectN<Self>
+    where
+        Self: Sized + Unpin,
+    {
+        CollectN {
+            stream: self,
+            n,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl<S: Stream + ?Sized> StreamExt for S {}
+
+/// Yields `start, start + 1, ..., end - 1`, then ends. Always ready.
+pub struct Counter {
+    next: u64,
+    end: u64,
+}
+
+impl Counter {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self { next: start, end }
+    }
+}
+
+impl Stream for Counter {
+    type Item = u64;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<u64>> {
+        // TODO: yield `next` and advance it while `next < end`; then `Ready(None)`
+        todo!()
+    }
+}
+
+/// Yields the scheduled tick `period, 2 * period, ...` (relative to creation), forever.
+///
+/// Like tokio's default "burst" behaviour, the schedule does not drift: the next deadline is
+/// the previous *deadline* plus `period`, not "now" plus `period`. If the consumer falls behind,
+/// missed ticks are yielded back-to-back until the stream has caught up.
+pub struct Interval {
+    wheel: TimerWheel,
+    period: u64,
+    delay: Delay,
+}
+
+impl Interval {
+    pub fn new(wheel: &TimerWheel, period: u64) -> Self {
+        assert!(period > 0, "period must be positive");
+        Self {
+            wheel: wheel.clone(),
+            period,
+            delay: wheel.delay(period),
+        }
+    }
+}
+
+impl Stream for Interval {
+    type Item = u64;
+
+    /// Poll `self.delay`. `Pending` -> `Pending` (the delay has registered our waker).
+    /// `Ready` -> remember `deadline()`, replace the delay with
+    /// `wheel.delay_until(deadline + period)` and yield `Some(deadline)`.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u64>> {
+        // TODO
+        todo!()
+    }
+}
+
+pub struct Map<S, F> {
+    stream: S,
+    f: F,
+}
+
+impl<S, F, T> Stream for Map<S, F>
+where
+    S: Stream + Unpin,
+    F: FnMut(S::Item) -> T + Unpin,
+{
+    type Item = T;
+
+    /// Poll the inner stream; map `Some(item)` through `f`, pass `None` and `Pending` on.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        // TODO
+        todo!()
+    }
+}
+
+pub struct Next<'a, S: ?Sized> {
+    stream: &'a mut S,
+}
+
+impl<S: Stream + Unpin + ?Sized> Future for Next<'_, S> {
+    type Output = Option<S::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
+}
+
+pub struct CollectN<S: Stream> {
+    stream: S,
+    n: usize,
+    items: Vec<S::Item>,
+}
+
+impl<S: Stream + Unpin> Future for CollectN<S>
+where
+    S::Item: Unpin,
+{
+    type Output = Vec<S::Item>;
+
+    /// Keep polling the stream while it is ready, pushing items into `self.items`. Return the
+    /// items (`std::mem::take`) once there are `n` of them or the stream ends; return `Pending`
+    /// as soon as the stream is pending (it will wake us).
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    #[cfg(feature = "solutions")]
+    use mini_executor::solution::{block_on, Executor};
+    #[cfg(not(feature = "solutions"))]
+    use mini_executor::{block_on, Executor};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_counter",
+            "yield next and advance it while next < end; after that keep returning Ready(None)",
+        ),
+        (
+            "test_map",
+            "map Some(item) through f and pass Ready(None) and Pending on unchanged",
+        ),
+        (
+            "test_collect_n_stops_early",
+            "return the items as soon as there are n of them, without polling the stream again",
+        ),
+        (
+            "test_interval_manual_ticks",
+            "Pending from the delay means Pending: the delay has already registered the waker with the wheel",
+        ),
+        (
+            "test_interval_catches_up_without_drift",
+            "schedule the next delay at deadline + period, not now + period, so late ticks catch up",
+        ),
+    ];
+
+    struct NoopWake;
+
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn poll_once<S: Stream + Unpin>(s: &mut S) -> Poll<Option<S::Item>> {
+        let waker = Waker::from(Arc::new(NoopWake));
+        Pin::new(s).poll_next(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn test_counter() {
+        let mut c = Counter::new(3, 6);
+        let items = block_on(async {
+            let mut v = Vec::new();
+            while let Some(x) = c.next().await {
+                v.push(x);
+            }
+            v
+        });
+        assert_eq_with_hint!(items, [3, 4, 5]);
+        assert_eq_with_hint!(poll_once(&mut c), Poll::Ready(None), "stays finished");
+    }
+
+    #[test]
+    fn test_counter_empty() {
+        assert_eq!(poll_once(&mut Counter::new(5, 5)), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_map() {
+        let s = Counter::new(0, 4).map(|x| x * x).map(|x| format!("<{x}>"));
+        assert_eq_with_hint!(block_on(s.collect_n(10)), ["<0>", "<1>", "<4>", "<9>"]);
+    }
+
+    #[test]
+    fn test_collect_n_stops_early() {
+        let s = Counter::new(0, u64::MAX);
+        assert_eq_with_hint!(block_on(s.collect_n(3)), [0, 1, 2]);
+        assert_with_hint!(block_on(Counter::new(0, 9).collect_n(0)).is_empty());
+    }
+
+    #[test]
+    fn test_interval_manual_ticks() {
+        let wheel = TimerWheel::new();
+        let mut iv = Interval::new(&wheel, 3);
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Pending);
+        assert_eq_with_hint!(wheel.pending(), 1, "waiting on one delay");
+        wheel.advance(2);
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Pending);
+        wheel.advance(1);
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Ready(Some(3)));
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Pending);
+        wheel.advance(3);
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Ready(Some(6)));
+    }
+
+    #[test]
+    fn test_interval_catches_up_without_drift() {
+        let wheel = TimerWheel::new();
+        let mut iv = Interval::new(&wheel, 4);
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Pending);
+        wheel.advance(13);
+        // Missed ticks come back-to-back, on the original schedule.
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Ready(Some(4)));
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Ready(Some(8)));
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Ready(Some(12)));
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Pending);
+        wheel.advance(3);
+        assert_eq_with_hint!(poll_once(&mut iv), Poll::Ready(Some(16)));
+    }
+
+    #[test]
+    fn test_interval_block_on_with_driver() {
+        let wheel = TimerWheel::new();
+        let _driver = wheel.spawn_driver(Duration::from_millis(1));
+        let ticks = block_on(Interval::new(&wheel, 5).collect_n(3));
+        assert_eq!(ticks, [5, 10, 15]);
+        assert!(wheel.now() >= 15);
+    }
+
+    #[test]
+    fn test_streams_on_executor() {
+        let wheel = TimerWheel::new();
+        let _driver = wheel.spawn_driver(Duration::from_millis(1));
+        let mut ex = Executor::new();
+        let slow = ex.spawn(Interval::new(&wheel, 4).map(|t| t / 4).collect_n(3));
+        let fast = ex.spawn(Interval::new(&wheel, 1).collect_n(6));
+        let counted = ex.spawn(Counter::new(10, 13).collect_n(5));
+        ex.run();
+        assert_eq!(block_on(slow), [1, 2, 3]);
+        assert_eq!(block_on(fast), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(block_on(counted), [10, 11, 12]);
+        assert_eq!(
+            wheel.pending(),
+            0,
+            "finished intervals leave no timers behind"
+        );
+    }
+}
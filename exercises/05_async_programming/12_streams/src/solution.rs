@@ -0,0 +1,186 @@
+//! # Streams: Counter, Interval, Map and Collect
+//!
+//! In this exercise, you implement a minimal `Stream` trait — the async version of `Iterator` —
+//! and a few streams on top of it, then drive them on the executor from `05_mini_executor`.
+//!
+//! ## Concepts
+//! - `poll_next` returns `Ready(Some(item))`, `Ready(None)` (stream finished) or `Pending`
+//!   (no item yet; the waker is registered with whatever the stream is waiting on)
+//! - `Interval` is a stream of `Delay`s from `07_timer_wheel`: each time the current delay
+//!   fires, yield an item and arm the next delay
+//! - Adapters (`Map`) wrap a stream and forward `Pending` untouched
+//! - Consumers (`collect_n`) are futures that poll a stream repeatedly
+//!
+//! Needs `05_mini_executor` (tests) and `07_timer_wheel` finished first.
+
+use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::task::{Context, Poll};
+pub(crate) use timer_wheel::solution::{Delay, TimerWheel};
+
+pub trait Stream {
+    type Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+/// Convenience methods, implemented for every stream.
+pub trait StreamExt: Stream {
+    /// Apply `f` to every item.
+    fn map<T, F>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> T,
+    {
+        Map { stream: self, f }
+    }
+
+    /// Future resolving to the next item (`None` when the stream is finished).
+    fn next(&mut self) -> Next<'_, Self>
+    where
+        Self: Unpin,
+    {
+        Next { stream: self }
+    }
+
+    /// Future resolving to the next `n` items, or fewer if the stream finishes first.
+    fn collect_n(self, n: usize) -> CollectN<Self>
+    where
+        Self: Sized + Unpin,
+    {
+        CollectN {
+            stream: self,
+            n,
+            items: Vec::new(),
+        }
+    }
+}
+
+impl<S: Stream + ?Sized> StreamExt for S {}
+
+/// Yields `start, start + 1, ..., end - 1`, then ends. Always ready.
+pub struct Counter {
+    next: u64,
+    end: u64,
+}
+
+impl Counter {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self { next: start, end }
+    }
+}
+
+impl Stream for Counter {
+    type Item = u64;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<u64>> {
+        let this = self.get_mut();
+        if this.next < this.end {
+            this.next += 1;
+            Poll::Ready(Some(this.next - 1))
+        } else {
+            Poll::Ready(None)
+        }
+    }
+}
+
+/// Yields the scheduled tick `period, 2 * period, ...` (relative to creation), forever.
+///
+/// Like tokio's default "burst" behaviour, the schedule does not drift: the next deadline is
+/// the previous *deadline* plus `period`, not "now" plus `period`. If the consumer falls behind,
+/// missed ticks are yielded back-to-back until the stream has caught up.
+pub struct Interval {
+    wheel: TimerWheel,
+    period: u64,
+    delay: Delay,
+}
+
+impl Interval {
+    pub fn new(wheel: &TimerWheel, period: u64) -> Self {
+        assert!(period > 0, "period must be positive");
+        Self {
+            wheel: wheel.clone(),
+            period,
+            delay: wheel.delay(period),
+        }
+    }
+}
+
+impl Stream for Interval {
+    type Item = u64;
+
+    /// Poll `self.delay`. `Pending` -> `Pending` (the delay has registered our waker).
+    /// `Ready` -> remember `deadline()`, replace the delay with
+    /// `wheel.delay_until(deadline + period)` and yield `Some(deadline)`.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<u64>> {
+        if Pin::new(&mut self.delay).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let deadline = self.delay.deadline();
+        self.delay = self.wheel.delay_until(deadline + self.period);
+        Poll::Ready(Some(deadline))
+    }
+}
+
+pub struct Map<S, F> {
+    stream: S,
+    f: F,
+}
+
+impl<S, F, T> Stream for Map<S, F>
+where
+    S: Stream + Unpin,
+    F: FnMut(S::Item) -> T + Unpin,
+{
+    type Item = T;
+
+    /// Poll the inner stream; map `Some(item)` through `f`, pass `None` and `Pending` on.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = &mut *self;
+        match Pin::new(&mut this.stream).poll_next(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(Some((this.f)(item))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pub struct Next<'a, S: ?Sized> {
+    stream: &'a mut S,
+}
+
+impl<S: Stream + Unpin + ?Sized> Future for Next<'_, S> {
+    type Output = Option<S::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
+}
+
+pub struct CollectN<S: Stream> {
+    stream: S,
+    n: usize,
+    items: Vec<S::Item>,
+}
+
+impl<S: Stream + Unpin> Future for CollectN<S>
+where
+    S::Item: Unpin,
+{
+    type Output = Vec<S::Item>;
+
+    /// Keep polling the stream while it is ready, pushing items into `self.items`. Return the
+    /// items (`std::mem::take`) once there are `n` of them or the stream ends; return `Pending`
+    /// as soon as the stream is pending (it will wake us).
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        while this.items.len() < this.n {
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => this.items.push(item),
+                Poll::Ready(None) => break,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(std::mem::take(&mut this.items))
+    }
+}
@@ -0,0 +1,464 @@
+//! # Async MPSC Channel from Scratch
+//!
+//! In this exercise, you implement a bounded multi-producer single-consumer channel without
+//! `tokio::sync` — what `03_async_channel` uses from the library, built by hand.
+//!
+//! ## Design
+//! ```text
+//!   Sender ─┐                 State (behind a std Mutex)
+//!   Sender ─┼─ send().await ─▶ buf: VecDeque<T> (len <= cap) ─▶ recv().await ─ Receiver
+//!   Sender ─┘                 send_waiters: wakers of senders waiting for space
+//!                             rx_waker:     waker of the receiver waiting for an item
+//! ```
+//! - `send` on a full buffer parks its waker in `send_waiters` and returns `Pending`; every item
+//!   taken by the receiver wakes one waiting sender.
+//! - `recv` on an empty buffer parks its waker in `rx_waker`; every item pushed wakes it.
+//! - Closing: when the last `Sender` is dropped, `recv` drains the buffer and then returns `None`.
+//!   When the `Receiver` is dropped, every `send` fails and gives the value back.
+//!
+//! As in the other exercises, wakers are woken after the state lock has been released.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// The receiver is gone; the value is handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Closed(T),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No item right now, but senders are alive.
+    Empty,
+    /// No item and no sender left.
+    Disconnected,
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+struct State<T> {
+    buf: VecDeque<T>,
+    cap: usize,
+    senders: usize,
+    rx_alive: bool,
+    rx_waker: Option<Waker>,
+    send_waiters: VecDeque<Waker>,
+}
+
+type Shared<T> = Arc<Mutex<State<T>>>;
+
+/// Create a channel holding at most `cap` items. Panics if `cap == 0`.
+pub fn channel<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(cap > 0, "capacity must be positive");
+    let shared = Arc::new(Mutex::new(State {
+        buf: VecDeque::with_capacity(cap),
+        cap,
+        senders: 1,
+        rx_alive: true,
+        rx_waker: None,
+        send_waiters: VecDeque::new(),
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+pub struct Sender<T> {
+    shared: Shared<T>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, value: T) -> Send<'_, T> {
+        Send {
+            sender: self,
+            value: Some(value),
+            parked: false,
+        }
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let wake = {
+            let mut st = self.shared.lock().unwrap();
+            if !st.rx_alive {
+                return Err(TrySendError::Closed(value));
+            }
+            if st.buf.len() == st.cap {
+                return Err(TrySendError::Full(value));
+            }
+            st.buf.push_back(value);
+            st.rx_waker.take()
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.shared.lock().unwrap().rx_alive
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wake = {
+            let mut st = self.shared.lock().unwrap();
+            st.senders -= 1;
+            if st.senders == 0 {
+                st.rx_waker.take()
+            } else {
+                None
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+/// Future returned by `Sender::send`.
+pub struct Send<'a, T> {
+    sender: &'a Sender<T>,
+    /// `None` once sent (or handed back in the error).
+    value: Option<T>,
+    /// Our waker has been queued in `send_waiters` at least once.
+    parked: bool,
+}
+
+impl<T> Unpin for Send<'_, T> {}
+
+impl<T> Future for Send<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    /// Under the lock:
+    /// - receiver gone -> `Ready(Err(SendError(value)))`
+    /// - room in `buf` -> push the value, take `rx_waker`; after unlocking wake it; `Ready(Ok(()))`
+    /// - full -> push the current waker to `send_waiters`, set `parked`, `Pending`
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // TODO
+        todo!()
+    }
+}
+
+impl<T> Drop for Send<'_, T> {
+    fn drop(&mut self) {
+        // Cancelled while parked: we may have been woken for a free slot we will never use, so
+        // pass the wake-up on to the next waiting sender.
+        if self.parked && self.value.is_some() {
+            let wake = {
+                let mut st = self.sender.shared.lock().unwrap();
+                if st.buf.len() < st.cap {
+                    st.send_waiters.pop_front()
+                } else {
+                    None
+                }
+            };
+            if let Some(w) = wake {
+                w.wake();
+            }
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Shared<T>,
+}
+
+impl<T> Receiver<T> {
+    /// `None` once all senders are gone and the buffer is drained.
+    pub fn recv(&mut self) -> Recv<'_, T> {
+        Recv { receiver: self }
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let (item, wake) = {
+            let mut st = self.shared.lock().unwrap();
+            match st.buf.pop_front() {
+                Some(v) => (v, st.send_waiters.pop_front()),
+                None if st.senders == 0 => return Err(TryRecvError::Disconnected),
+                None => return Err(TryRecvError::Empty),
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+        Ok(item)
+    }
+
+    /// Items currently buffered.
+    pub fn len(&self) -> usize {
+        self.shared.lock().unwrap().buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut st = self.shared.lock().unwrap();
+            st.rx_alive = false;
+            std::mem::take(&mut st.send_waiters)
+        };
+        for w in wakers {
+            w.wake();
+        }
+    }
+}
+
+/// Future returned by `Receiver::recv`.
+pub struct Recv<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for Recv<'_, T> {
+    type Output = Option<T>;
+
+    /// Under the lock:
+    /// - an item is buffered -> pop it and one waiting sender (wake it after unlocking), `Ready(Some)`
+    /// - empty and `senders == 0` -> `Ready(None)`
+    /// - empty -> store the current waker in `rx_waker`, `Pending`
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_recv_pending_until_send",
+            "an empty recv stores cx.waker() in rx_waker; send takes it and wakes it after unlocking",
+        ),
+        (
+            "test_send_blocks_when_full",
+            "a full buffer parks the sender: push its waker to send_waiters and return Pending",
+        ),
+        (
+            "test_close_when_senders_dropped",
+            "recv returns Ready(None) only when the buffer is empty and senders == 0",
+        ),
+        (
+            "test_send_fails_after_receiver_dropped",
+            "dropping the Receiver wakes every parked sender, whose next poll returns Err(SendError(value))",
+        ),
+        (
+            "test_cancelled_sender_passes_wakeup_on",
+            "a Send future dropped while parked after being woken must wake the next waiting sender",
+        ),
+    ];
+
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counter() -> (Arc<Counter>, Waker) {
+        let c = Arc::new(Counter(AtomicUsize::new(0)));
+        (c.clone(), Waker::from(c))
+    }
+
+    fn poll<F: Future + Unpin>(f: &mut F, w: &Waker) -> Poll<F::Output> {
+        Pin::new(f).poll(&mut Context::from_waker(w))
+    }
+
+    #[tokio::test]
+    async fn test_producer_consumer() {
+        let items: Vec<String> = vec!["hello".into(), "async".into(), "world".into()];
+        let (tx, mut rx) = channel(items.len());
+        let producer = {
+            let items = items.clone();
+            tokio::spawn(async move {
+                for it in items {
+                    tx.send(it).await.unwrap();
+                }
+            })
+        };
+        let mut got = Vec::new();
+        while let Some(v) = rx.recv().await {
+            got.push(v);
+        }
+        producer.await.unwrap();
+        assert_eq!(got, items);
+    }
+
+    #[tokio::test]
+    async fn test_fan_in() {
+        let (tx, mut rx) = channel(1);
+        for id in 0..3 {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                tx.send(format!("producer {id}: message")).await.unwrap();
+            });
+        }
+        drop(tx);
+        let mut got = Vec::new();
+        while let Some(v) = rx.recv().await {
+            got.push(v);
+        }
+        got.sort();
+        assert_eq!(
+            got,
+            [
+                "producer 0: message",
+                "producer 1: message",
+                "producer 2: message"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recv_pending_until_send() {
+        let (tx, mut rx) = channel::<u8>(2);
+        let (c, w) = counter();
+        let mut r = rx.recv();
+        assert_with_hint!(poll(&mut r, &w).is_pending());
+        tx.try_send(7).unwrap();
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 1, "send wakes the receiver");
+        assert_eq_with_hint!(poll(&mut r, &w), Poll::Ready(Some(7)));
+    }
+
+    #[test]
+    fn test_send_blocks_when_full() {
+        let (tx, mut rx) = channel(1);
+        let (c, w) = counter();
+        assert_with_hint!(poll(&mut tx.send(1), &w).is_ready());
+        assert_eq_with_hint!(tx.try_send(2), Err(TrySendError::Full(2)));
+        let mut s = tx.send(2);
+        assert_with_hint!(poll(&mut s, &w).is_pending());
+        assert_eq_with_hint!(poll(&mut rx.recv(), &w), Poll::Ready(Some(1)));
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 1, "recv wakes a waiting sender");
+        assert_eq_with_hint!(poll(&mut s, &w), Poll::Ready(Ok(())));
+        assert_eq_with_hint!(rx.try_recv(), Ok(2));
+        assert_eq_with_hint!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn test_close_when_senders_dropped() {
+        let (tx, mut rx) = channel(4);
+        let tx2 = tx.clone();
+        let (c, w) = counter();
+        tx.try_send("a").unwrap();
+        drop(tx);
+        let mut r = rx.recv();
+        assert_eq_with_hint!(poll(&mut r, &w), Poll::Ready(Some("a")));
+        let mut r = rx.recv();
+        assert_with_hint!(poll(&mut r, &w).is_pending(), "one sender is still alive");
+        drop(tx2);
+        assert_eq_with_hint!(
+            c.0.load(Ordering::SeqCst),
+            1,
+            "last sender wakes the receiver"
+        );
+        assert_eq_with_hint!(poll(&mut r, &w), Poll::Ready(None));
+        assert_eq_with_hint!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_send_fails_after_receiver_dropped() {
+        let (tx, rx) = channel(1);
+        let (c, w) = counter();
+        tx.try_send(1).unwrap();
+        let mut s = tx.send(2);
+        assert_with_hint!(poll(&mut s, &w).is_pending());
+        drop(rx);
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), 1, "parked sender is woken");
+        assert_eq_with_hint!(poll(&mut s, &w), Poll::Ready(Err(SendError(2))));
+        assert_with_hint!(tx.is_closed());
+        assert_eq_with_hint!(tx.try_send(3), Err(TrySendError::Closed(3)));
+    }
+
+    #[test]
+    fn test_cancelled_sender_passes_wakeup_on() {
+        let (tx, mut rx) = channel(1);
+        let (ca, wa) = counter();
+        let (cb, wb) = counter();
+        tx.try_send(0).unwrap();
+        let mut a = tx.send(1);
+        let mut b = tx.send(2);
+        assert_with_hint!(poll(&mut a, &wa).is_pending());
+        assert_with_hint!(poll(&mut b, &wb).is_pending());
+        assert_eq_with_hint!(rx.try_recv(), Ok(0));
+        assert_eq_with_hint!(ca.0.load(Ordering::SeqCst), 1);
+        drop(a);
+        assert_eq_with_hint!(cb.0.load(Ordering::SeqCst), 1, "b must not starve");
+        assert_eq_with_hint!(poll(&mut b, &wb), Poll::Ready(Ok(())));
+        assert_eq_with_hint!(rx.try_recv(), Ok(2));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_many_producers_small_buffer() {
+        let (tx, mut rx) = channel(2);
+        let handles: Vec<_> = (0..8u64)
+            .map(|p| {
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    for i in 0..200 {
+                        tx.send(p * 1000 + i).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        drop(tx);
+        let mut per_producer = vec![Vec::new(); 8];
+        let recv = async {
+            while let Some(v) = rx.recv().await {
+                assert!(rx.len() <= 2);
+                per_producer[(v / 1000) as usize].push(v % 1000);
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), recv)
+            .await
+            .expect("channel stalled");
+        for h in handles {
+            h.await.unwrap();
+        }
+        for seq in per_producer {
+            assert_eq!(seq, (0..200).collect::<Vec<_>>(), "per-sender FIFO order");
+        }
+    }
+}
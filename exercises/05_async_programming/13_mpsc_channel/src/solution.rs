@@ -0,0 +1,281 @@
+//! # Async MPSC Channel from Scratch
+//!
+//! In this exercise, you implement a bounded multi-producer single-consumer channel without
+//! `tokio::sync` — what `03_async_channel` uses from the library, built by hand.
+//!
+//! ## Design
+//! ```text
+//!   Sender ─┐                 State (behind a std Mutex)
+//!   Sender ─┼─ send().await ─▶ buf: VecDeque<T> (len <= cap) ─▶ recv().await ─ Receiver
+//!   Sender ─┘                 send_waiters: wakers of senders waiting for space
+//!                             rx_waker:     waker of the receiver waiting for an item
+//! ```
+//! - `send` on a full buffer parks its waker in `send_waiters` and returns `Pending`; every item
+//!   taken by the receiver wakes one waiting sender.
+//! - `recv` on an empty buffer parks its waker in `rx_waker`; every item pushed wakes it.
+//! - Closing: when the last `Sender` is dropped, `recv` drains the buffer and then returns `None`.
+//!   When the `Receiver` is dropped, every `send` fails and gives the value back.
+//!
+//! As in the other exercises, wakers are woken after the state lock has been released.
+
+use std::collections::VecDeque;
+use std::fmt;
+pub(crate) use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::sync::{Arc, Mutex};
+pub(crate) use std::task::{Context, Poll, Waker};
+
+/// The receiver is gone; the value is handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Closed(T),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No item right now, but senders are alive.
+    Empty,
+    /// No item and no sender left.
+    Disconnected,
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+struct State<T> {
+    buf: VecDeque<T>,
+    cap: usize,
+    senders: usize,
+    rx_alive: bool,
+    rx_waker: Option<Waker>,
+    send_waiters: VecDeque<Waker>,
+}
+
+type Shared<T> = Arc<Mutex<State<T>>>;
+
+/// Create a channel holding at most `cap` items. Panics if `cap == 0`.
+pub fn channel<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(cap > 0, "capacity must be positive");
+    let shared = Arc::new(Mutex::new(State {
+        buf: VecDeque::with_capacity(cap),
+        cap,
+        senders: 1,
+        rx_alive: true,
+        rx_waker: None,
+        send_waiters: VecDeque::new(),
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+pub struct Sender<T> {
+    shared: Shared<T>,
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, value: T) -> Send<'_, T> {
+        Send {
+            sender: self,
+            value: Some(value),
+            parked: false,
+        }
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let wake = {
+            let mut st = self.shared.lock().unwrap();
+            if !st.rx_alive {
+                return Err(TrySendError::Closed(value));
+            }
+            if st.buf.len() == st.cap {
+                return Err(TrySendError::Full(value));
+            }
+            st.buf.push_back(value);
+            st.rx_waker.take()
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.shared.lock().unwrap().rx_alive
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wake = {
+            let mut st = self.shared.lock().unwrap();
+            st.senders -= 1;
+            if st.senders == 0 {
+                st.rx_waker.take()
+            } else {
+                None
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+    }
+}
+
+/// Future returned by `Sender::send`.
+pub struct Send<'a, T> {
+    sender: &'a Sender<T>,
+    /// `None` once sent (or handed back in the error).
+    value: Option<T>,
+    /// Our waker has been queued in `send_waiters` at least once.
+    parked: bool,
+}
+
+impl<T> Unpin for Send<'_, T> {}
+
+impl<T> Future for Send<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    /// Under the lock:
+    /// - receiver gone -> `Ready(Err(SendError(value)))`
+    /// - room in `buf` -> push the value, take `rx_waker`; after unlocking wake it; `Ready(Ok(()))`
+    /// - full -> push the current waker to `send_waiters`, set `parked`, `Pending`
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let wake = {
+            let mut st = this.sender.shared.lock().unwrap();
+            if !st.rx_alive {
+                return Poll::Ready(Err(SendError(this.value.take().unwrap())));
+            }
+            if st.buf.len() == st.cap {
+                st.send_waiters.push_back(cx.waker().clone());
+                this.parked = true;
+                return Poll::Pending;
+            }
+            st.buf.push_back(this.value.take().unwrap());
+            st.rx_waker.take()
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Drop for Send<'_, T> {
+    fn drop(&mut self) {
+        // Cancelled while parked: we may have been woken for a free slot we will never use, so
+        // pass the wake-up on to the next waiting sender.
+        if self.parked && self.value.is_some() {
+            let wake = {
+                let mut st = self.sender.shared.lock().unwrap();
+                if st.buf.len() < st.cap {
+                    st.send_waiters.pop_front()
+                } else {
+                    None
+                }
+            };
+            if let Some(w) = wake {
+                w.wake();
+            }
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Shared<T>,
+}
+
+impl<T> Receiver<T> {
+    /// `None` once all senders are gone and the buffer is drained.
+    pub fn recv(&mut self) -> Recv<'_, T> {
+        Recv { receiver: self }
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let (item, wake) = {
+            let mut st = self.shared.lock().unwrap();
+            match st.buf.pop_front() {
+                Some(v) => (v, st.send_waiters.pop_front()),
+                None if st.senders == 0 => return Err(TryRecvError::Disconnected),
+                None => return Err(TryRecvError::Empty),
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+        Ok(item)
+    }
+
+    /// Items currently buffered.
+    pub fn len(&self) -> usize {
+        self.shared.lock().unwrap().buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut st = self.shared.lock().unwrap();
+            st.rx_alive = false;
+            std::mem::take(&mut st.send_waiters)
+        };
+        for w in wakers {
+            w.wake();
+        }
+    }
+}
+
+/// Future returned by `Receiver::recv`.
+pub struct Recv<'a, T> {
+    receiver: &'a mut Receiver<T>,
+}
+
+impl<T> Future for Recv<'_, T> {
+    type Output = Option<T>;
+
+    /// Under the lock:
+    /// - an item is buffered -> pop it and one waiting sender (wake it after unlocking), `Ready(Some)`
+    /// - empty and `senders == 0` -> `Ready(None)`
+    /// - empty -> store the current waker in `rx_waker`, `Pending`
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let (item, wake) = {
+            let mut st = self.receiver.shared.lock().unwrap();
+            match st.buf.pop_front() {
+                Some(v) => (v, st.send_waiters.pop_front()),
+                None if st.senders == 0 => return Poll::Ready(None),
+                None => {
+                    st.rx_waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        };
+        if let Some(w) = wake {
+            w.wake();
+        }
+        Poll::Ready(Some(item))
+    }
+}
@@ -0,0 +1,224 @@
+//! # oneshot, watch and broadcast
+//!
+//! In this exercise, you will use the other three channel flavours of `tokio::sync`
+//! (`03_async_channel` covered `mpsc`).
+//!
+//! ## Concepts
+//! - `oneshot`: exactly one value, one sender, one receiver — the reply half of a request
+//! - `watch`: a single "current value" observed by many receivers; receivers only ever see the
+//!   **latest** value, intermediate updates may be skipped
+//! - `broadcast`: every receiver gets every message, unless it falls more than `capacity`
+//!   messages behind — then `recv` returns `RecvError::Lagged(n)` and skips the `n` oldest
+
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+
+/// A request to the squaring service: the number and where to send the answer.
+pub struct Request {
+    pub n: u64,
+    pub reply: oneshot::Sender<u64>,
+}
+
+/// Spawn a service task that answers every `Request` with `n * n` until all request senders are
+/// dropped. Returns the sender for requests and the service's `JoinHandle`.
+///
+/// Hint: `mpsc::channel(16)`; in the task `while let Some(req) = rx.recv().await`, then
+/// `req.reply.send(..)` (ignore the error if the requester has given up).
+pub fn spawn_squarer() -> (mpsc::Sender<Request>, JoinHandle<()>) {
+    // TODO
+    todo!()
+}
+
+/// Ask the service to square `n`. Returns `None` if the service is gone.
+///
+/// Hint: create a `oneshot::channel()`, send a `Request` carrying its sender, await the receiver.
+pub async fn request_square(service: &mpsc::Sender<Request>, n: u64) -> Option<u64> {
+    // TODO
+    todo!()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub level: u32,
+    pub name: String,
+}
+
+/// Spawn `n` observers of a configuration. Each observer records `level` of the current config,
+/// then again after every change, until the `watch::Sender` is dropped; it returns what it saw.
+///
+/// Hint: clone `rx` per task; record `rx.borrow_and_update().level` first, then loop on
+/// `rx.changed().await` (an `Err` means the sender is gone).
+pub fn spawn_observers(rx: &watch::Receiver<Config>, n: usize) -> Vec<JoinHandle<Vec<u32>>> {
+    // TODO
+    todo!()
+}
+
+/// What one broadcast subscriber got.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Received {
+    pub messages: Vec<u32>,
+    /// Total number of messages skipped because this receiver lagged behind.
+    pub lagged: u64,
+}
+
+/// Receive until the channel is closed, counting lagged (skipped) messages instead of failing.
+///
+/// Hint: match on `rx.recv().await`: `Ok(m)`, `Err(RecvError::Lagged(n))`, `Err(RecvError::Closed)`.
+pub async fn drain(mut rx: broadcast::Receiver<u32>) -> Received {
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use tokio::sync::broadcast::error::RecvError;
+    use tokio::time::{sleep, Duration};
+
+    const HINTS: Hints = &[
+        (
+            "test_request_response",
+            "send a Request carrying a oneshot sender, then await the oneshot receiver",
+        ),
+        (
+            "test_request_to_dead_service",
+            "if the send fails or the oneshot sender is dropped, return None instead of panicking",
+        ),
+        (
+            "test_watch_skips_intermediate_values",
+            "watch keeps only the latest value: read it with borrow_and_update so has_changed is reset",
+        ),
+        (
+            "test_broadcast_lagged_receiver",
+            "on Err(RecvError::Lagged(n)) add n to lagged and keep receiving; stop only on Closed",
+        ),
+    ];
+
+    #[tokio::test]
+    async fn test_request_response() {
+        let (service, handle) = spawn_squarer();
+        assert_eq_with_hint!(request_square(&service, 7).await, Some(49));
+        assert_eq_with_hint!(request_square(&service, 0).await, Some(0));
+        drop(service);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests() {
+        let (service, _handle) = spawn_squarer();
+        let tasks: Vec<_> = (0..20u64)
+            .map(|i| {
+                let service = service.clone();
+                tokio::spawn(async move { request_square(&service, i).await })
+            })
+            .collect();
+        for (i, t) in tasks.into_iter().enumerate() {
+            assert_eq!(t.await.unwrap(), Some((i * i) as u64));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_to_dead_service() {
+        let (service, handle) = spawn_squarer();
+        handle.abort();
+        let _ = handle.await;
+        assert_eq_with_hint!(request_square(&service, 3).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_watch_observers_see_latest() {
+        let cfg = |level| Config {
+            level,
+            name: "kernel".into(),
+        };
+        let (tx, rx) = watch::channel(cfg(0));
+        let observers = spawn_observers(&rx, 3);
+        // Let the observers start and read the initial value.
+        sleep(Duration::from_millis(5)).await;
+        for level in 1..=50 {
+            tx.send(cfg(level)).unwrap();
+            if level % 10 == 0 {
+                sleep(Duration::from_millis(5)).await;
+            }
+        }
+        drop(tx);
+        for h in observers {
+            let seen = h.await.unwrap();
+            assert_eq!(seen.first(), Some(&0));
+            assert_eq!(
+                seen.last(),
+                Some(&50),
+                "every observer ends on the latest value"
+            );
+            assert!(
+                seen.windows(2).all(|w| w[0] < w[1]),
+                "values only move forward: {seen:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_skips_intermediate_values() {
+        let (tx, mut rx) = watch::channel(1);
+        tx.send(2).unwrap();
+        tx.send(3).unwrap();
+        rx.changed().await.unwrap();
+        assert_eq_with_hint!(*rx.borrow_and_update(), 3, "only the latest value is kept");
+        assert_with_hint!(!rx.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_everyone_gets_everything() {
+        let (tx, rx1) = broadcast::channel(16);
+        let rx2 = tx.subscribe();
+        let a = tokio::spawn(drain(rx1));
+        let b = tokio::spawn(drain(rx2));
+        for m in 0..10 {
+            tx.send(m).unwrap();
+        }
+        drop(tx);
+        let want = Received {
+            messages: (0..10).collect(),
+            lagged: 0,
+        };
+        assert_eq!(a.await.unwrap(), want);
+        assert_eq!(b.await.unwrap(), want);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_lagged_receiver() {
+        let (tx, mut rx) = broadcast::channel(4);
+        for m in 0..10 {
+            tx.send(m).unwrap();
+        }
+        assert_eq_with_hint!(rx.recv().await, Err(RecvError::Lagged(6)));
+        assert_eq_with_hint!(rx.recv().await, Ok(6));
+
+        let slow = tx.subscribe();
+        for m in 10..20 {
+            tx.send(m).unwrap();
+        }
+        drop(tx);
+        let got = drain(slow).await;
+        assert_eq_with_hint!(got.lagged, 6);
+        assert_eq_with_hint!(got.messages, [16, 17, 18, 19]);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_late_subscriber_misses_old_messages() {
+        let (tx, _keep) = broadcast::channel(8);
+        tx.send(1).unwrap();
+        let late = tx.subscribe();
+        tx.send(2).unwrap();
+        drop(tx);
+        assert_eq!(drain(late).await.messages, [2]);
+    }
+}
@@ -0,0 +1,93 @@
+//! # oneshot, watch and broadcast
+//!
+//! In this exercise, you will use the other three channel flavours of `tokio::sync`
+//! (`03_async_channel` covered `mpsc`).
+//!
+//! ## Concepts
+//! - `oneshot`: exactly one value, one sender, one receiver — the reply half of a request
+//! - `watch`: a single "current value" observed by many receivers; receivers only ever see the
+//!   **latest** value, intermediate updates may be skipped
+//! - `broadcast`: every receiver gets every message, unless it falls more than `capacity`
+//!   messages behind — then `recv` returns `RecvError::Lagged(n)` and skips the `n` oldest
+
+pub(crate) use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+
+/// A request to the squaring service: the number and where to send the answer.
+pub struct Request {
+    pub n: u64,
+    pub reply: oneshot::Sender<u64>,
+}
+
+/// Spawn a service task that answers every `Request` with `n * n` until all request senders are
+/// dropped. Returns the sender for requests and the service's `JoinHandle`.
+///
+/// Hint: `mpsc::channel(16)`; in the task `while let Some(req) = rx.recv().await`, then
+/// `req.reply.send(..)` (ignore the error if the requester has given up).
+pub fn spawn_squarer() -> (mpsc::Sender<Request>, JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel::<Request>(16);
+    let handle = tokio::spawn(async move {
+        while let Some(req) = rx.recv().await {
+            let _ = req.reply.send(req.n * req.n);
+        }
+    });
+    (tx, handle)
+}
+
+/// Ask the service to square `n`. Returns `None` if the service is gone.
+///
+/// Hint: create a `oneshot::channel()`, send a `Request` carrying its sender, await the receiver.
+pub async fn request_square(service: &mpsc::Sender<Request>, n: u64) -> Option<u64> {
+    let (reply, rx) = oneshot::channel();
+    service.send(Request { n, reply }).await.ok()?;
+    rx.await.ok()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub level: u32,
+    pub name: String,
+}
+
+/// Spawn `n` observers of a configuration. Each observer records `level` of the current config,
+/// then again after every change, until the `watch::Sender` is dropped; it returns what it saw.
+///
+/// Hint: clone `rx` per task; record `rx.borrow_and_update().level` first, then loop on
+/// `rx.changed().await` (an `Err` means the sender is gone).
+pub fn spawn_observers(rx: &watch::Receiver<Config>, n: usize) -> Vec<JoinHandle<Vec<u32>>> {
+    (0..n)
+        .map(|_| {
+            let mut rx = rx.clone();
+            tokio::spawn(async move {
+                let mut seen = vec![rx.borrow_and_update().level];
+                while rx.changed().await.is_ok() {
+                    seen.push(rx.borrow_and_update().level);
+                }
+                seen
+            })
+        })
+        .collect()
+}
+
+/// What one broadcast subscriber got.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Received {
+    pub messages: Vec<u32>,
+    /// Total number of messages skipped because this receiver lagged behind.
+    pub lagged: u64,
+}
+
+/// Receive until the channel is closed, counting lagged (skipped) messages instead of failing.
+///
+/// Hint: match on `rx.recv().await`: `Ok(m)`, `Err(RecvError::Lagged(n))`, `Err(RecvError::Closed)`.
+pub async fn drain(mut rx: broadcast::Receiver<u32>) -> Received {
+    use broadcast::error::RecvError;
+    let mut got = Received::default();
+    loop {
+        match rx.recv().await {
+            Ok(m) => got.messages.push(m),
+            Err(RecvError::Lagged(n)) => got.lagged += n,
+            Err(RecvError::Closed) => return got,
+        }
+    }
+}
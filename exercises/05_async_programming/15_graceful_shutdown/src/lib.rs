@@ -0,0 +1,289 @@
+//! # Graceful Shutdown with a Cancellation Token
+//!
+//! In this exercise, you will build a small shutdown controller: a `CancellationToken` (the
+//! pattern of `tokio_util::sync::CancellationToken`, hand-rolled on a `watch` channel), worker
+//! tasks that stop when it is cancelled, and an orchestrator that waits for them to drain.
+//!
+//! ## Concepts
+//! - Cancellation is cooperative: a worker checks the token only at points where stopping is
+//!   safe (between jobs), so a job already in flight always runs to completion
+//! - `watch::Receiver::wait_for` turns "flag became true" into a future
+//! - Bounded shutdown: waiting for the workers is wrapped in `tokio::time::timeout`; tasks that
+//!   do not stop in time are aborted so nothing outlives the shutdown
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+
+/// Cheaply cloneable; cancelling any clone cancels all of them.
+#[derive(Clone)]
+pub struct CancellationToken {
+    tx: Arc<watch::Sender<bool>>,
+    rx: watch::Receiver<bool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        let (tx, rx) = watch::channel(false);
+        Self {
+            tx: Arc::new(tx),
+            rx,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Cancel the token. Idempotent.
+    ///
+    /// Hint: `send_replace(true)` sets the value even when no receiver is waiting.
+    pub fn cancel(&self) {
+        // TODO
+        todo!()
+    }
+
+    /// Resolves once the token is cancelled (immediately if it already is).
+    ///
+    /// Hint: clone `rx` (the future must not borrow `self` mutably) and `wait_for(|c| *c)`.
+    /// The sender lives as long as `self`, so `wait_for` cannot fail here.
+    pub async fn cancelled(&self) {
+        // TODO
+        todo!()
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shared counters for all workers.
+#[derive(Default)]
+pub struct Stats {
+    pub started: AtomicUsize,
+    pub completed: AtomicUsize,
+    pub in_flight: AtomicUsize,
+}
+
+/// One worker: repeatedly wait `idle` for the next job, then run a job lasting `job`.
+/// Returns the number of jobs it completed.
+///
+/// - The idle wait is cancellable: `tokio::select!` between `token.cancelled()` and
+///   `sleep(idle)`; on cancellation break out of the loop.
+/// - A job is not: bump `started` and `in_flight`, `sleep(job)`, then bump `completed` and
+///   drop `in_flight` — without looking at the token in between.
+pub async fn run_worker(
+    token: CancellationToken,
+    stats: Arc<Stats>,
+    idle: Duration,
+    job: Duration,
+) -> usize {
+    // TODO
+    todo!()
+}
+
+/// Spawn `n` workers sharing `token` and `stats`.
+pub fn spawn_workers(
+    n: usize,
+    token: &CancellationToken,
+    stats: &Arc<Stats>,
+    idle: Duration,
+    job: Duration,
+) -> Vec<JoinHandle<usize>> {
+    (0..n)
+        .map(|_| tokio::spawn(run_worker(token.clone(), stats.clone(), idle, job)))
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShutdownError {
+    /// `unfinished` tasks did not stop within the grace period and were aborted.
+    Timeout { unfinished: usize },
+}
+
+/// Cancel `token` and wait up to `grace` for every handle to finish.
+///
+/// Returns the sum of the workers' results. If the grace period runs out, abort every task
+/// that has not finished yet (`JoinHandle::abort`) and return `Timeout` with their number.
+///
+/// Hint: await the handles inside `timeout(grace, async { ... })`, borrowing them mutably
+/// (`&mut JoinHandle` is a future too) so they are still available for aborting afterwards.
+pub async fn shutdown(
+    token: &CancellationToken,
+    handles: Vec<JoinHandle<usize>>,
+    grace: Duration,
+) -> Result<usize, ShutdownError> {
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use tokio::time::Instant;
+
+    const HINTS: Hints = &[
+        (
+            "test_cancel_is_shared_and_idempotent",
+            "clones share one watch channel; cancel() sets it with send_replace(true), which works without receivers and can be repeated",
+        ),
+        (
+            "test_in_flight_work_completes",
+            "a started job is not cancellable: sleep the whole job duration without looking at the token",
+        ),
+        (
+            "test_no_new_jobs_after_cancel",
+            "select! between token.cancelled() and the idle sleep, and leave the loop on cancellation before starting a job",
+        ),
+        (
+            "test_shutdown_timeout_aborts_stragglers",
+            "await &mut handles inside timeout(grace, ..) so that afterwards you can abort() the ones still running and count them",
+        ),
+        (
+            "test_shutdown_with_no_workers",
+            "shutdown cancels the token first, even when there is nothing to wait for",
+        ),
+    ];
+
+    const MS: Duration = Duration::from_millis(1);
+
+    // All tests run on a paused clock: it stands still while any task can run and then jumps to
+    // the next timer, so the schedules below play out exactly, however slow the machine.
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_is_shared_and_idempotent() {
+        let a = CancellationToken::new();
+        let b = a.clone();
+        assert_with_hint!(!a.is_cancelled());
+        b.cancel();
+        b.cancel();
+        assert_with_hint!(a.is_cancelled() && b.is_cancelled());
+        // Already cancelled: resolves at once.
+        timeout(Duration::from_millis(50), a.cancelled())
+            .await
+            .expect("cancelled() should be ready");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancelled_wakes_waiters() {
+        let token = CancellationToken::new();
+        let waiters: Vec<_> = (0..4)
+            .map(|_| {
+                let t = token.clone();
+                tokio::spawn(async move { t.cancelled().await })
+            })
+            .collect();
+        sleep(10 * MS).await;
+        assert!(waiters.iter().all(|w| !w.is_finished()));
+        token.cancel();
+        for w in waiters {
+            timeout(Duration::from_millis(100), w)
+                .await
+                .expect("waiter not woken")
+                .unwrap();
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_workers_do_work_until_shutdown() {
+        let token = CancellationToken::new();
+        let stats = Arc::new(Stats::default());
+        let handles = spawn_workers(4, &token, &stats, MS, 5 * MS);
+        sleep(40 * MS).await;
+        let start = Instant::now();
+        let done = shutdown(&token, handles, Duration::from_secs(1))
+            .await
+            .unwrap();
+        // 6ms per job: six done by 40ms, the seventh started at 37ms and ends at 42ms.
+        assert_eq!(done, 4 * 7);
+        assert_eq!(done, stats.completed.load(Ordering::SeqCst));
+        assert_eq!(start.elapsed(), 2 * MS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_in_flight_work_completes() {
+        let token = CancellationToken::new();
+        let stats = Arc::new(Stats::default());
+        // Long jobs: shutdown certainly arrives while jobs are running.
+        let handles = spawn_workers(3, &token, &stats, MS, 60 * MS);
+        sleep(20 * MS).await;
+        assert_eq_with_hint!(stats.in_flight.load(Ordering::SeqCst), 3);
+        let start = Instant::now();
+        let done = shutdown(&token, handles, Duration::from_secs(1))
+            .await
+            .unwrap();
+        // The jobs started at 1ms and end at 61ms.
+        assert_eq_with_hint!(start.elapsed(), 41 * MS, "shutdown must wait for jobs");
+        assert_eq_with_hint!(done, 3);
+        assert_eq_with_hint!(stats.in_flight.load(Ordering::SeqCst), 0);
+        assert_eq_with_hint!(
+            stats.started.load(Ordering::SeqCst),
+            stats.completed.load(Ordering::SeqCst),
+            "no job was cut off"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_no_new_jobs_after_cancel() {
+        let token = CancellationToken::new();
+        let stats = Arc::new(Stats::default());
+        let handles = spawn_workers(2, &token, &stats, 30 * MS, MS);
+        sleep(5 * MS).await;
+        let start = Instant::now();
+        let done = shutdown(&token, handles, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq_with_hint!(done, 0, "idle workers stop without starting a job");
+        assert_eq_with_hint!(stats.started.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            start.elapsed(),
+            Duration::ZERO,
+            "cancellation cuts the idle wait short"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_timeout_aborts_stragglers() {
+        let token = CancellationToken::new();
+        let stats = Arc::new(Stats::default());
+        let mut handles = spawn_workers(2, &token, &stats, MS, 2 * MS);
+        // A task that ignores the token.
+        let stubborn = tokio::spawn(async {
+            sleep(Duration::from_secs(30)).await;
+            1
+        });
+        let probe = stubborn.abort_handle();
+        handles.push(stubborn);
+        sleep(10 * MS).await;
+        let start = Instant::now();
+        let res = shutdown(&token, handles, 50 * MS).await;
+        assert_eq_with_hint!(res, Err(ShutdownError::Timeout { unfinished: 1 }));
+        assert_eq_with_hint!(
+            start.elapsed(),
+            50 * MS,
+            "give up when the grace period is over"
+        );
+        sleep(MS).await;
+        assert_with_hint!(probe.is_finished(), "straggler must not outlive shutdown");
+        assert_eq_with_hint!(stats.in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_with_no_workers() {
+        let token = CancellationToken::new();
+        assert_eq_with_hint!(shutdown(&token, Vec::new(), MS).await, Ok(0));
+        assert_with_hint!(token.is_cancelled());
+    }
+}
@@ -0,0 +1,154 @@
+//! # Graceful Shutdown with a Cancellation Token
+//!
+//! In this exercise, you will build a small shutdown controller: a `CancellationToken` (the
+//! pattern of `tokio_util::sync::CancellationToken`, hand-rolled on a `watch` channel), worker
+//! tasks that stop when it is cancelled, and an orchestrator that waits for them to drain.
+//!
+//! ## Concepts
+//! - Cancellation is cooperative: a worker checks the token only at points where stopping is
+//!   safe (between jobs), so a job already in flight always runs to completion
+//! - `watch::Receiver::wait_for` turns "flag became true" into a future
+//! - Bounded shutdown: waiting for the workers is wrapped in `tokio::time::timeout`; tasks that
+//!   do not stop in time are aborted so nothing outlives the shutdown
+
+pub(crate) use std::sync::atomic::{AtomicUsize, Ordering};
+pub(crate) use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+pub(crate) use tokio::time::{sleep, timeout, Duration};
+
+/// Cheaply cloneable; cancelling any clone cancels all of them.
+#[derive(Clone)]
+pub struct CancellationToken {
+    tx: Arc<watch::Sender<bool>>,
+    rx: watch::Receiver<bool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        let (tx, rx) = watch::channel(false);
+        Self {
+            tx: Arc::new(tx),
+            rx,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Cancel the token. Idempotent.
+    ///
+    /// Hint: `send_replace(true)` sets the value even when no receiver is waiting.
+    pub fn cancel(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// Resolves once the token is cancelled (immediately if it already is).
+    ///
+    /// Hint: clone `rx` (the future must not borrow `self` mutably) and `wait_for(|c| *c)`.
+    /// The sender lives as long as `self`, so `wait_for` cannot fail here.
+    pub async fn cancelled(&self) {
+        let mut rx = self.rx.clone();
+        let _ = rx.wait_for(|c| *c).await;
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shared counters for all workers.
+#[derive(Default)]
+pub struct Stats {
+    pub started: AtomicUsize,
+    pub completed: AtomicUsize,
+    pub in_flight: AtomicUsize,
+}
+
+/// One worker: repeatedly wait `idle` for the next job, then run a job lasting `job`.
+/// Returns the number of jobs it completed.
+///
+/// - The idle wait is cancellable: `tokio::select!` between `token.cancelled()` and
+///   `sleep(idle)`; on cancellation break out of the loop.
+/// - A job is not: bump `started` and `in_flight`, `sleep(job)`, then bump `completed` and
+///   drop `in_flight` — without looking at the token in between.
+pub async fn run_worker(
+    token: CancellationToken,
+    stats: Arc<Stats>,
+    idle: Duration,
+    job: Duration,
+) -> usize {
+    let mut done = 0;
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = sleep(idle) => {}
+        }
+        stats.started.fetch_add(1, Ordering::SeqCst);
+        stats.in_flight.fetch_add(1, Ordering::SeqCst);
+        sleep(job).await;
+        stats.completed.fetch_add(1, Ordering::SeqCst);
+        stats.in_flight.fetch_sub(1, Ordering::SeqCst);
+        done += 1;
+    }
+    done
+}
+
+/// Spawn `n` workers sharing `token` and `stats`.
+pub fn spawn_workers(
+    n: usize,
+    token: &CancellationToken,
+    stats: &Arc<Stats>,
+    idle: Duration,
+    job: Duration,
+) -> Vec<JoinHandle<usize>> {
+    (0..n)
+        .map(|_| tokio::spawn(run_worker(token.clone(), stats.clone(), idle, job)))
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShutdownError {
+    /// `unfinished` tasks did not stop within the grace period and were aborted.
+    Timeout { unfinished: usize },
+}
+
+/// Cancel `token` and wait up to `grace` for every handle to finish.
+///
+/// Returns the sum of the workers' results. If the grace period runs out, abort every task
+/// that has not finished yet (`JoinHandle::abort`) and return `Timeout` with their number.
+///
+/// Hint: await the handles inside `timeout(grace, async { ... })`, borrowing them mutably
+/// (`&mut JoinHandle` is a future too) so they are still available for aborting afterwards.
+pub async fn shutdown(
+    token: &CancellationToken,
+    handles: Vec<JoinHandle<usize>>,
+    grace: Duration,
+) -> Result<usize, ShutdownError> {
+    token.cancel();
+    let mut handles = handles;
+    let joined = timeout(grace, async {
+        let mut total = 0;
+        for h in handles.iter_mut() {
+            total += h.await.unwrap_or(0);
+        }
+        total
+    })
+    .await;
+    match joined {
+        Ok(total) => Ok(total),
+        Err(_) => {
+            let mut unfinished = 0;
+            for h in &handles {
+                if !h.is_finished() {
+                    h.abort();
+                    unfinished += 1;
+                }
+            }
+            Err(ShutdownError::Timeout { unfinished })
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! # Async TCP Echo Server
+//!
+//! In this exercise, you will do real I/O with `tokio::net`: an echo server that handles many
+//! connections at once, and a client helper.
+//!
+//! ## Concepts
+//! - `TcpListener::accept` in a loop, one spawned task per connection — a slow client only
+//!   blocks its own task
+//! - `AsyncReadExt` / `AsyncWriteExt`: `read`, `write_all`, `read_to_end`, `shutdown`
+//! - Half-close: the client shuts down its write side to signal "no more data" (the server sees
+//!   EOF) while still reading the echo
+//! - Graceful shutdown: `select!` between `accept()` and the shutdown future; after shutdown no
+//!   new connections are accepted, but open connections are served to the end
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+
+/// Echo everything read from `stream` back to it until the peer closes its write side.
+/// Returns the number of bytes echoed.
+///
+/// Loop: `read` into a buffer; `0` means EOF -> done; otherwise `write_all` what was read.
+pub async fn handle_conn(mut stream: TcpStream) -> io::Result<u64> {
+    // TODO
+    todo!()
+}
+
+/// Serve echo connections on `listener` until `shutdown` resolves, then stop accepting and wait
+/// for the open connections to finish. Returns how many connections were accepted.
+///
+/// - Pin `shutdown` once (`tokio::pin!` / `std::pin::pin!`) so it can be polled in every loop
+///   iteration of `tokio::select!`.
+/// - Spawn `handle_conn` for each accepted stream into a `JoinSet`; errors of single
+///   connections are ignored (a client may reset its connection).
+/// - After shutdown, drop the listener and drain the `JoinSet` (`join_next`).
+pub async fn serve<S>(listener: TcpListener, shutdown: S) -> io::Result<usize>
+where
+    S: Future<Output = ()>,
+{
+    // TODO
+    todo!()
+}
+
+/// Connect to `addr`, send `msg`, half-close, and return everything echoed back.
+///
+/// Write and read **concurrently** (`stream.split()` + `tokio::try_join!`): for a large
+/// message the server starts echoing before we finished writing, and if nobody reads, both
+/// sides' buffers fill up and the connection deadlocks.
+pub async fn echo_once(addr: SocketAddr, msg: &[u8]) -> io::Result<Vec<u8>> {
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use tokio::sync::oneshot;
+    use tokio::task::JoinHandle;
+    use tokio::time::{sleep, timeout, Duration};
+
+    const HINTS: Hints = &[
+        (
+            "test_single_echo",
+            "echo_once must shut down its write side after sending, or the server never sees EOF and never finishes",
+        ),
+        (
+            "test_large_payload",
+            "write and read concurrently (stream.split() + try_join!): with a large message both socket buffers fill up otherwise",
+        ),
+        (
+            "test_idle_client_does_not_block_others",
+            "spawn handle_conn for every accepted stream instead of awaiting it in the accept loop",
+        ),
+        (
+            "test_shutdown_stops_accepting",
+            "pin the shutdown future once and select! between it and accept(); on shutdown drop the listener",
+        ),
+        (
+            "test_shutdown_drains_open_connections",
+            "after shutdown, drain the JoinSet with join_next so open connections finish first",
+        ),
+    ];
+
+    /// Start a server on an ephemeral port; returns its address, the shutdown trigger and the
+    /// server task.
+    async fn start() -> (
+        SocketAddr,
+        oneshot::Sender<()>,
+        JoinHandle<io::Result<usize>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, async {
+            let _ = rx.await;
+        }));
+        (addr, tx, server)
+    }
+
+    #[tokio::test]
+    async fn test_single_echo() {
+        let (addr, stop, server) = start().await;
+        assert_eq_with_hint!(echo_once(addr, b"hello").await.unwrap(), b"hello");
+        stop.send(()).unwrap();
+        assert_eq_with_hint!(server.await.unwrap().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_empty_message() {
+        let (addr, _stop, _server) = start().await;
+        assert!(echo_once(addr, b"").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_large_payload() {
+        let (addr, _stop, _server) = start().await;
+        let msg: Vec<u8> = (0..4 << 20).map(|i| (i % 251) as u8).collect();
+        let got = timeout(Duration::from_secs(20), echo_once(addr, &msg))
+            .await
+            .expect("echo of a large message stalled")
+            .unwrap();
+        assert_with_hint!(got == msg);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_many_concurrent_clients() {
+        let (addr, stop, server) = start().await;
+        let clients: Vec<_> = (0..50)
+            .map(|i| {
+                tokio::spawn(async move {
+                    let msg = format!("client {i} says hi").repeat(i + 1);
+                    let got = echo_once(addr, msg.as_bytes()).await.unwrap();
+                    assert_eq!(got, msg.as_bytes());
+                })
+            })
+            .collect();
+        for c in clients {
+            c.await.unwrap();
+        }
+        stop.send(()).unwrap();
+        assert_eq!(server.await.unwrap().unwrap(), 50);
+    }
+
+    #[tokio::test]
+    async fn test_idle_client_does_not_block_others() {
+        let (addr, _stop, _server) = start().await;
+        let mut idle = TcpStream::connect(addr).await.unwrap();
+        idle.write_all(b"partial").await.unwrap();
+        // The idle connection stays open; another client is still served.
+        let got = timeout(Duration::from_secs(2), echo_once(addr, b"next"))
+            .await
+            .expect("server is stuck on the first connection")
+            .unwrap();
+        assert_eq_with_hint!(got, b"next");
+        let mut buf = [0u8; 7];
+        idle.read_exact(&mut buf).await.unwrap();
+        assert_eq_with_hint!(&buf, b"partial");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_accepting() {
+        let (addr, stop, server) = start().await;
+        assert_eq_with_hint!(echo_once(addr, b"x").await.unwrap(), b"x");
+        stop.send(()).unwrap();
+        let served = timeout(Duration::from_secs(2), server)
+            .await
+            .expect("server did not stop")
+            .unwrap()
+            .unwrap();
+        assert_eq_with_hint!(served, 1);
+        assert_with_hint!(
+            TcpStream::connect(addr).await.is_err(),
+            "listener must be closed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_open_connections() {
+        let (addr, stop, server) = start().await;
+        let mut conn = TcpStream::connect(addr).await.unwrap();
+        conn.write_all(b"before").await.unwrap();
+        let mut buf = [0u8; 6];
+        conn.read_exact(&mut buf).await.unwrap();
+
+        stop.send(()).unwrap();
+        sleep(Duration::from_millis(30)).await;
+        assert_with_hint!(
+            !server.is_finished(),
+            "open connection must be served to the end"
+        );
+
+        conn.write_all(b"after").await.unwrap();
+        let mut buf = [0u8; 5];
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq_with_hint!(&buf, b"after");
+        drop(conn);
+        let served = timeout(Duration::from_secs(2), server)
+            .await
+            .expect("server did not finish after the last client left")
+            .unwrap()
+            .unwrap();
+        assert_eq_with_hint!(served, 1);
+    }
+}
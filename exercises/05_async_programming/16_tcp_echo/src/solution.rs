@@ -0,0 +1,86 @@
+//! # Async TCP Echo Server
+//!
+//! In this exercise, you will do real I/O with `tokio::net`: an echo server that handles many
+//! connections at once, and a client helper.
+//!
+//! ## Concepts
+//! - `TcpListener::accept` in a loop, one spawned task per connection — a slow client only
+//!   blocks its own task
+//! - `AsyncReadExt` / `AsyncWriteExt`: `read`, `write_all`, `read_to_end`, `shutdown`
+//! - Half-close: the client shuts down its write side to signal "no more data" (the server sees
+//!   EOF) while still reading the echo
+//! - Graceful shutdown: `select!` between `accept()` and the shutdown future; after shutdown no
+//!   new connections are accepted, but open connections are served to the end
+
+use std::future::Future;
+pub(crate) use std::io;
+pub(crate) use std::net::SocketAddr;
+pub(crate) use tokio::io::{AsyncReadExt, AsyncWriteExt};
+pub(crate) use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+
+/// Echo everything read from `stream` back to it until the peer closes its write side.
+/// Returns the number of bytes echoed.
+///
+/// Loop: `read` into a buffer; `0` means EOF -> done; otherwise `write_all` what was read.
+pub async fn handle_conn(mut stream: TcpStream) -> io::Result<u64> {
+    let mut buf = vec![0u8; 4096];
+    let mut total = 0;
+    loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(total);
+        }
+        stream.write_all(&buf[..n]).await?;
+        total += n as u64;
+    }
+}
+
+/// Serve echo connections on `listener` until `shutdown` resolves, then stop accepting and wait
+/// for the open connections to finish. Returns how many connections were accepted.
+///
+/// - Pin `shutdown` once (`tokio::pin!` / `std::pin::pin!`) so it can be polled in every loop
+///   iteration of `tokio::select!`.
+/// - Spawn `handle_conn` for each accepted stream into a `JoinSet`; errors of single
+///   connections are ignored (a client may reset its connection).
+/// - After shutdown, drop the listener and drain the `JoinSet` (`join_next`).
+pub async fn serve<S>(listener: TcpListener, shutdown: S) -> io::Result<usize>
+where
+    S: Future<Output = ()>,
+{
+    tokio::pin!(shutdown);
+    let mut conns = JoinSet::new();
+    let mut accepted = 0;
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            res = listener.accept() => {
+                let (stream, _) = res?;
+                accepted += 1;
+                conns.spawn(handle_conn(stream));
+            }
+        }
+    }
+    drop(listener);
+    while conns.join_next().await.is_some() {}
+    Ok(accepted)
+}
+
+/// Connect to `addr`, send `msg`, half-close, and return everything echoed back.
+///
+/// Write and read **concurrently** (`stream.split()` + `tokio::try_join!`): for a large
+/// message the server starts echoing before we finished writing, and if nobody reads, both
+/// sides' buffers fill up and the connection deadlocks.
+pub async fn echo_once(addr: SocketAddr, msg: &[u8]) -> io::Result<Vec<u8>> {
+    let mut stream = TcpStream::connect(addr).await?;
+    let (mut rd, mut wr) = stream.split();
+    let mut out = Vec::with_capacity(msg.len());
+    tokio::try_join!(
+        async {
+            wr.write_all(msg).await?;
+            wr.shutdown().await
+        },
+        rd.read_to_end(&mut out),
+    )?;
+    Ok(out)
+}
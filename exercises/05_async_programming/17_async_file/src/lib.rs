@@ -0,0 +1,569 @@
+//! # AsyncFile: Kernel Files in the Async Track
+//!
+//! In this exercise, you bring the `File` trait of `02_no_std_dev/05_fd_table` (as implemented by
+//! the pipe of `03_os_concurrency/08_pipe`) into async code. A blocking `File::read` would stall
+//! the executor thread and every task on it; an `AsyncFile` returns a future instead.
+//!
+//! ## The trait
+//! ```text
+//! fn read<'a>(&'a self, buf: &'a mut [u8]) -> FileFuture<'a, isize>
+//! ```
+//! Results keep the kernel convention (byte count or negative errno). The methods return boxed
+//! futures rather than being `async fn`s, so the trait stays object-safe and an fd table can hold
+//! `Arc<dyn AsyncFile>` just like the synchronous one holds `Arc<dyn File>`.
+//!
+//! ## Three implementations
+//! - `Blocking<F>`: run the blocking `File` call on tokio's blocking thread pool
+//!   (`spawn_blocking`) and await its `JoinHandle`; the task is woken when the call returns. A
+//!   read cannot be called back once it runs, so one whose future was dropped is not lost: the
+//!   next read picks up its bytes.
+//! - `NonBlocking<F>`: for a `File` in `O_NONBLOCK` mode. `-EAGAIN` means "not ready": return
+//!   `Pending` with the task's waker in a `PollQueue`, which the other end of the file wakes when
+//!   it moves data or closes — the wait queue `epoll` sleeps on, instead of retrying on a timer.
+//! - `TokioFile<T>`: an async file backed by a tokio I/O object (`AsyncRead + AsyncWrite`, e.g.
+//!   a `tokio::io::duplex` pipe), mapping `io::Error`s back to errnos.
+//!
+//! `File` comes from `02_no_std_dev/05_fd_table`; the pipe tests need `03_os_concurrency/08_pipe`
+//! (and thus `06_wait_queue`) finished too.
+
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::{Poll, Waker};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::task::JoinHandle;
+
+pub use fd_table::File;
+pub use pipe::{EAGAIN, EBADF, EPIPE};
+
+pub const EIO: isize = 5;
+
+pub type FileFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+pub trait AsyncFile: Send + Sync {
+    fn read<'a>(&'a self, buf: &'a mut [u8]) -> FileFuture<'a, isize>;
+    fn write<'a>(&'a self, buf: &'a [u8]) -> FileFuture<'a, isize>;
+}
+
+/// Write all of `buf`, calling `write` as often as needed. Returns `buf.len()`, or the first
+/// error if nothing was written yet (otherwise the count written before it).
+pub async fn write_all(file: &dyn AsyncFile, buf: &[u8]) -> isize {
+    let mut done = 0;
+    while done < buf.len() {
+        let n = file.write(&buf[done..]).await;
+        if n <= 0 {
+            return if done == 0 { n } else { done as isize };
+        }
+        done += n as usize;
+    }
+    done as isize
+}
+
+/// Adapter: a blocking `File` whose calls run on the blocking thread pool.
+pub struct Blocking<F> {
+    file: Arc<F>,
+    reading: tokio::sync::Mutex<ReadState>,
+}
+
+/// What a `Blocking` file has read but not handed to a caller yet.
+#[derive(Default)]
+struct ReadState {
+    /// A read still running on the blocking pool. A `read` future dropped while waiting for it
+    /// leaves it here, and the next `read` collects its result instead of starting another.
+    running: Option<JoinHandle<(Vec<u8>, isize)>>,
+    /// Bytes of a finished read that did not fit its caller's buffer.
+    leftover: VecDeque<u8>,
+}
+
+impl<F: File + 'static> Blocking<F> {
+    pub fn new(file: F) -> Self {
+        Self {
+            file: Arc::new(file),
+            reading: Default::default(),
+        }
+    }
+}
+
+impl<F: File + 'static> AsyncFile for Blocking<F> {
+    /// Cancelling the future cannot stop a read already on the blocking pool, and the bytes it
+    /// takes out of the file are gone from there. So reads run one at a time under `reading`,
+    /// which keeps whatever has not reached a caller for the next call.
+    fn read<'a>(&'a self, buf: &'a mut [u8]) -> FileFuture<'a, isize> {
+        Box::pin(async move {
+            let mut st = self.reading.lock().await;
+            if st.leftover.is_empty() {
+                // The closure must be `'static`: it gets its own buffer, copied out afterwards.
+                let running = st.running.get_or_insert_with(|| {
+                    let file = self.file.clone();
+                    let len = buf.len();
+                    tokio::task::spawn_blocking(move || {
+                        let mut tmp = vec![0u8; len];
+                        let n = file.read(&mut tmp);
+                        (tmp, n)
+                    })
+                });
+                let (mut tmp, n) = running.await.expect("blocking read panicked");
+                st.running = None;
+                if n <= 0 {
+                    return n;
+                }
+                tmp.truncate(n as usize);
+                st.leftover = tmp.into();
+            }
+            let n = st.leftover.len().min(buf.len());
+            for (dst, src) in buf.iter_mut().zip(st.leftover.drain(..n)) {
+                *dst = src;
+            }
+            n as isize
+        })
+    }
+
+    /// Copy `buf` into an owned `Vec`, call `file.write` inside `spawn_blocking` and return its
+    /// result. (A cancelled write still completes: the data has already been handed over.)
+    fn write<'a>(&'a self, buf: &'a [u8]) -> FileFuture<'a, isize> {
+        // TODO
+        todo!()
+    }
+}
+
+/// The wakers of tasks waiting for a file to become ready: what `poll`/`epoll` register on in a
+/// kernel. All async handles of one file share one queue.
+#[derive(Default)]
+pub struct PollQueue {
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl PollQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wake `waker` at the next `wake_all`.
+    pub fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap();
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    /// Wake every registered waker, and forget them.
+    pub fn wake_all(&self) {
+        let wakers = std::mem::take(&mut *self.wakers.lock().unwrap());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Adapter: a `File` in non-blocking mode. `-EAGAIN` means "not ready": the future returns
+/// `Pending` and another handle of the file wakes it through their `PollQueue` once it moves
+/// data or closes. Nothing blocks, and nothing polls the file on a timer either.
+pub struct NonBlocking<F> {
+    /// `None` only while dropping.
+    file: Option<F>,
+    queue: Arc<PollQueue>,
+}
+
+impl<F: File> NonBlocking<F> {
+    /// The caller must have put `file` into `O_NONBLOCK` mode, and every other handle that can
+    /// make it ready must be a `NonBlocking` on the same `queue`.
+    pub fn new(file: F, queue: Arc<PollQueue>) -> Self {
+        Self {
+            file: Some(file),
+            queue,
+        }
+    }
+
+    fn file(&self) -> &F {
+        self.file.as_ref().unwrap()
+    }
+}
+
+impl<F> Drop for NonBlocking<F> {
+    /// Close the file first, then wake the waiters: they must find EOF or `-EPIPE`.
+    fn drop(&mut self) {
+        drop(self.file.take());
+        self.queue.wake_all();
+    }
+}
+
+impl<F: File> AsyncFile for NonBlocking<F> {
+    /// `Box::pin(poll_fn(move |cx| ..))`, and on every poll:
+    /// 1. `queue.register(cx.waker())`, *before* trying: data arriving right after the attempt
+    ///    still wakes this task.
+    /// 2. `self.file().read(buf)`: `-EAGAIN` -> `Poll::Pending`.
+    /// 3. Otherwise `Poll::Ready(n)`, after `queue.wake_all()` if bytes moved (`n > 0`): the read
+    ///    made room for blocked writers.
+    fn read<'a>(&'a self, buf: &'a mut [u8]) -> FileFuture<'a, isize> {
+        // TODO
+        todo!()
+    }
+
+    /// Same around `self.file().write(buf)` (a partial count is a valid result; new data wakes
+    /// blocked readers).
+    fn write<'a>(&'a self, buf: &'a [u8]) -> FileFuture<'a, isize> {
+        // TODO
+        todo!()
+    }
+}
+
+/// An `AsyncFile` backed by a tokio I/O object. The object is behind an async mutex because
+/// tokio's I/O traits need `&mut`, while `AsyncFile` (like `File`) takes `&self`.
+pub struct TokioFile<T> {
+    io: tokio::sync::Mutex<T>,
+}
+
+impl<T> TokioFile<T> {
+    pub fn new(io: T) -> Self {
+        Self {
+            io: tokio::sync::Mutex::new(io),
+        }
+    }
+}
+
+/// `BrokenPipe` -> `-EPIPE`, `WouldBlock` -> `-EAGAIN`, anything else -> `-EIO`.
+pub fn errno(e: &io::Error) -> isize {
+    match e.kind() {
+        io::ErrorKind::BrokenPipe => -EPIPE,
+        io::ErrorKind::WouldBlock => -EAGAIN,
+        _ => -EIO,
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AsyncFile for TokioFile<T> {
+    /// Lock `io` (`.lock().await`), `read(buf).await`, map the result: `Ok(n)` -> `n`,
+    /// `Err(e)` -> `errno(&e)`.
+    fn read<'a>(&'a self, buf: &'a mut [u8]) -> FileFuture<'a, isize> {
+        // TODO
+        todo!()
+    }
+
+    /// Same with `write(buf).await`.
+    fn write<'a>(&'a self, buf: &'a [u8]) -> FileFuture<'a, isize> {
+        // TODO
+        todo!()
+    }
+}
+
+/// The fd table of `05_fd_table`, holding async files.
+#[derive(Default)]
+pub struct AsyncFdTable {
+    files: Vec<Option<Arc<dyn AsyncFile>>>,
+}
+
+impl AsyncFdTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocate the smallest free fd.
+    pub fn alloc(&mut self, file: Arc<dyn AsyncFile>) -> usize {
+        match self.files.iter().position(Option::is_none) {
+            Some(fd) => {
+                self.files[fd] = Some(file);
+                fd
+            }
+            None => {
+                self.files.push(Some(file));
+                self.files.len() - 1
+            }
+        }
+    }
+
+    pub fn get(&self, fd: usize) -> Option<Arc<dyn AsyncFile>> {
+        self.files.get(fd).cloned().flatten()
+    }
+
+    pub fn close(&mut self, fd: usize) -> bool {
+        matches!(self.files.get_mut(fd).map(Option::take), Some(Some(_)))
+    }
+}
+
+/// `read(fd, buf)`: `-EBADF` for an fd that is not open.
+pub async fn sys_read(table: &AsyncFdTable, fd: usize, buf: &mut [u8]) -> isize {
+    match table.get(fd) {
+        Some(f) => f.read(buf).await,
+        None => -EBADF,
+    }
+}
+
+/// `write(fd, buf)`: `-EBADF` for an fd that is not open.
+pub async fn sys_write(table: &AsyncFdTable, fd: usize, buf: &[u8]) -> isize {
+    match table.get(fd) {
+        Some(f) => f.write(buf).await,
+        None => -EBADF,
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    #[cfg(feature = "solutions")]
+    use pipe::solution as pipe;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio::time::{sleep, timeout};
+
+    const HINTS: Hints = &[
+        (
+            "test_nonblocking_pending_until_woken",
+            "register the waker before trying the read, and return Pending on -EAGAIN without waking yourself: the writer's wake_all retries the read",
+        ),
+        (
+            "test_write_larger_than_capacity",
+            "a partial count is a valid write result; after bytes moved, wake_all so the other side can make progress",
+        ),
+        (
+            "test_tokio_file_duplex",
+            "TokioFile locks io, awaits read/write and maps an io::Error through errno(): a broken pipe becomes -EPIPE",
+        ),
+        (
+            "test_fd_table_mixes_implementations",
+            "Blocking::write copies buf into an owned Vec and calls file.write inside spawn_blocking",
+        ),
+    ];
+
+    const SHORT: Duration = Duration::from_millis(30);
+
+    /// Read and write end.
+    type Ends = (Arc<dyn AsyncFile>, Arc<dyn AsyncFile>);
+
+    async fn read_vec(f: &dyn AsyncFile, max: usize) -> Vec<u8> {
+        let mut buf = vec![0; max];
+        let n = f.read(&mut buf).await;
+        assert!(n >= 0, "read failed with {n}");
+        buf.truncate(n as usize);
+        buf
+    }
+
+    /// Both pipe ends wrapped by an adapter.
+    fn blocking_pipe(cap: usize) -> Ends {
+        let (r, w) = pipe::pipe(cap);
+        (Arc::new(Blocking::new(r)), Arc::new(Blocking::new(w)))
+    }
+
+    fn nonblocking_pipe(cap: usize) -> Ends {
+        let (r, w) = pipe::pipe(cap);
+        r.set_nonblocking(true);
+        w.set_nonblocking(true);
+        let queue = Arc::new(PollQueue::new());
+        (
+            Arc::new(NonBlocking::new(r, queue.clone())),
+            Arc::new(NonBlocking::new(w, queue)),
+        )
+    }
+
+    /// A `File` that counts the reads reaching it.
+    struct Counted<F> {
+        file: F,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl<F: File> File for Counted<F> {
+        fn read(&self, buf: &mut [u8]) -> isize {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.file.read(buf)
+        }
+
+        fn write(&self, buf: &[u8]) -> isize {
+            self.file.write(buf)
+        }
+    }
+
+    /// A pending read must not block the executor: another task keeps running, and the read
+    /// completes once a writer task fills the pipe.
+    async fn check_pending_read_then_wake(r: Arc<dyn AsyncFile>, w: Arc<dyn AsyncFile>) {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let reader = tokio::spawn(async move { read_vec(&*r, 16).await });
+        let ticker = {
+            let ticks = ticks.clone();
+            tokio::spawn(async move {
+                for _ in 0..5 {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    sleep(Duration::from_millis(2)).await;
+                }
+            })
+        };
+        sleep(SHORT).await;
+        assert!(
+            !reader.is_finished(),
+            "read on an empty pipe must be pending"
+        );
+        ticker.await.unwrap();
+        assert_eq!(
+            ticks.load(Ordering::SeqCst),
+            5,
+            "executor must not be blocked"
+        );
+        assert_eq!(w.write(b"wake").await, 4);
+        let got = timeout(Duration::from_secs(2), reader)
+            .await
+            .expect("reader not woken")
+            .unwrap();
+        assert_eq!(got, b"wake");
+    }
+
+    async fn check_eof_and_epipe(make: fn(usize) -> Ends) {
+        let (r, w) = make(8);
+        assert_eq!(w.write(b"bye").await, 3);
+        drop(w);
+        assert_eq!(read_vec(&*r, 8).await, b"bye");
+        assert_eq!(r.read(&mut [0; 8]).await, 0, "EOF after the writer is gone");
+
+        let (r, w) = make(8);
+        drop(r);
+        assert_eq!(w.write(b"x").await, -EPIPE);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_blocking_adapter_pending_and_wake() {
+        let (r, w) = blocking_pipe(16);
+        check_pending_read_then_wake(r, w).await;
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_blocking_read_cancelled_keeps_data() {
+        let (r, w) = blocking_pipe(16);
+        let mut buf = [0; 8];
+        assert!(
+            timeout(SHORT, r.read(&mut buf)).await.is_err(),
+            "nothing to read yet"
+        );
+        // The cancelled read is still waiting on the pipe and takes these bytes.
+        assert_eq!(w.write(b"kept!").await, 5);
+        let next = timeout(Duration::from_secs(2), read_vec(&*r, 3))
+            .await
+            .expect("the cancelled read's bytes were lost");
+        assert_eq!(next, b"kep");
+        assert_eq!(read_vec(&*r, 8).await, b"t!", "the rest is kept too");
+    }
+
+    #[tokio::test]
+    async fn test_blocking_adapter_eof_and_epipe() {
+        check_eof_and_epipe(blocking_pipe).await;
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_nonblocking_adapter_pending_and_wake() {
+        let (r, w) = nonblocking_pipe(16);
+        check_pending_read_then_wake(r, w).await;
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_nonblocking_pending_until_woken() {
+        let (r, w) = pipe::pipe(16);
+        r.set_nonblocking(true);
+        w.set_nonblocking(true);
+        let reads = Arc::new(AtomicUsize::new(0));
+        let queue = Arc::new(PollQueue::new());
+        let r = Counted {
+            file: r,
+            reads: reads.clone(),
+        };
+        let r = Arc::new(NonBlocking::new(r, queue.clone()));
+        let w = NonBlocking::new(w, queue);
+        let reader = {
+            let r = r.clone();
+            tokio::spawn(async move { read_vec(&*r, 16).await })
+        };
+        sleep(SHORT).await;
+        assert_with_hint!(!reader.is_finished());
+        assert_eq_with_hint!(
+            reads.load(Ordering::SeqCst),
+            1,
+            "a pending read is retried when woken, not on a timer"
+        );
+        assert_eq_with_hint!(w.write(b"now").await, 3);
+        assert_eq_with_hint!(reader.await.unwrap(), b"now");
+        assert_eq_with_hint!(reads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_nonblocking_adapter_eof_and_epipe() {
+        check_eof_and_epipe(nonblocking_pipe).await;
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_write_larger_than_capacity() {
+        for (r, w) in [blocking_pipe(4), nonblocking_pipe(4)] {
+            let msg: Vec<u8> = (0..64).collect();
+            let writer = {
+                let msg = msg.clone();
+                tokio::spawn(async move { write_all(&*w, &msg).await })
+            };
+            let mut got = Vec::new();
+            while got.len() < msg.len() {
+                got.extend(read_vec(&*r, 5).await);
+            }
+            assert_eq_with_hint!(writer.await.unwrap(), 64);
+            assert_eq_with_hint!(got, msg);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tokio_file_duplex() {
+        let (a, b) = tokio::io::duplex(8);
+        let (a, b) = (TokioFile::new(a), TokioFile::new(b));
+        assert_eq_with_hint!(a.write(b"ping").await, 4);
+        assert_eq_with_hint!(read_vec(&b, 16).await, b"ping");
+
+        let reader = tokio::spawn(async move {
+            let got = read_vec(&b, 16).await;
+            (b, got)
+        });
+        sleep(SHORT).await;
+        assert_with_hint!(!reader.is_finished(), "nothing to read yet");
+        assert_eq_with_hint!(a.write(b"pong").await, 4);
+        let (b, got) = reader.await.unwrap();
+        assert_eq_with_hint!(got, b"pong");
+
+        drop(a);
+        assert_eq_with_hint!(b.read(&mut [0; 4]).await, 0, "EOF after the peer is gone");
+        assert_eq_with_hint!(b.write(b"x").await, -EPIPE);
+    }
+
+    #[tokio::test]
+    async fn test_fd_table_mixes_implementations() {
+        let mut table = AsyncFdTable::new();
+        let (r, w) = blocking_pipe(16);
+        let (a, b) = tokio::io::duplex(16);
+        let rfd = table.alloc(r);
+        let wfd = table.alloc(w);
+        let afd = table.alloc(Arc::new(TokioFile::new(a)));
+        let bfd = table.alloc(Arc::new(TokioFile::new(b)));
+        assert_eq_with_hint!((rfd, wfd, afd, bfd), (0, 1, 2, 3));
+
+        assert_eq_with_hint!(sys_write(&table, wfd, b"pipe").await, 4);
+        assert_eq_with_hint!(sys_write(&table, afd, b"duplex").await, 6);
+        let mut buf = [0u8; 16];
+        assert_eq_with_hint!(sys_read(&table, rfd, &mut buf).await, 4);
+        assert_eq_with_hint!(&buf[..4], b"pipe");
+        assert_eq_with_hint!(sys_read(&table, bfd, &mut buf).await, 6);
+        assert_eq_with_hint!(&buf[..6], b"duplex");
+
+        assert_with_hint!(table.close(wfd));
+        assert_with_hint!(!table.close(wfd));
+        assert_eq_with_hint!(sys_write(&table, wfd, b"x").await, -EBADF);
+        assert_eq_with_hint!(sys_read(&table, 99, &mut buf).await, -EBADF);
+        assert_eq_with_hint!(
+            sys_read(&table, rfd, &mut buf).await,
+            0,
+            "writer closed: EOF"
+        );
+        assert_eq_with_hint!(
+            table.alloc(Arc::new(TokioFile::new(tokio::io::empty()))),
+            wfd
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! # FuturesUnordered: a Completion-Order Task Set
+//!
+//! In this exercise, you implement a simplified `FuturesUnordered`: push many futures into one
+//! set, and get their outputs back in the order they **complete**.
+//!
+//! ## Why not just poll all of them?
+//! `join_all` (`10_join_combinators`) polls every unfinished future whenever the combined future
+//! is woken. With 10 000 futures of which one became ready, that is 10 000 polls for one result.
+//! Here each future gets its **own waker**: waking it records the future's slot index in a
+//! shared ready queue and then wakes the task that owns the set. `poll_next` polls only the
+//! slots in the ready queue, so the cost per wake is O(1), however many futures are pending.
+//!
+//! ```text
+//!   slots: [ fut0 | fut1 | (free) | fut3 ]      ready queue: [3, 0]
+//!             ^ SlotWaker { idx: 0 } --wake--> push 0, wake parent
+//! ```
+//!
+//! ## Details
+//! - A slot is queued at most once at a time (`queued` set), however often it is woken.
+//! - A newly pushed future is queued at once: it has never been polled.
+//! - Budget: one `poll_next` call polls at most as many futures as the set holds. A future that
+//!   wakes itself every time (like `yield_now`) goes back to the queue; without the budget the
+//!   loop would never end. Out of budget: wake our own waker and return `Pending`.
+//! - Slot indices are reused; a late wake for a freed index just causes one spurious poll.
+
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+#[derive(Default)]
+struct ReadyState {
+    queue: VecDeque<usize>,
+    queued: HashSet<usize>,
+}
+
+/// Shared between the set and all slot wakers.
+#[derive(Default)]
+struct ReadyQueue {
+    state: Mutex<ReadyState>,
+    /// Waker of the task polling the set.
+    parent: Mutex<Option<Waker>>,
+}
+
+impl ReadyQueue {
+    fn pop(&self) -> Option<usize> {
+        let mut st = self.state.lock().unwrap();
+        let idx = st.queue.pop_front()?;
+        st.queued.remove(&idx);
+        Some(idx)
+    }
+}
+
+/// Waker handed to the future in slot `idx`.
+struct SlotWaker {
+    idx: usize,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for SlotWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    /// Queue `idx` unless it is already queued, then wake the parent waker (if any; clone it out
+    /// of the lock before waking).
+    fn wake_by_ref(self: &Arc<Self>) {
+        // TODO
+        todo!()
+    }
+}
+
+pub struct FuturesUnordered<F> {
+    slots: Vec<Option<Pin<Box<F>>>>,
+    /// Free slot indices, reused by `push`.
+    free: Vec<usize>,
+    len: usize,
+    ready: Arc<ReadyQueue>,
+    polls: usize,
+}
+
+impl<F: Future> FuturesUnordered<F> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            ready: Arc::new(ReadyQueue::default()),
+            polls: 0,
+        }
+    }
+
+    /// Futures not yet completed.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Total number of polls of individual futures.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+
+    /// Add a future: store it (boxed and pinned) in a free slot or a new one, count it in `len`,
+    /// and queue its index as ready, so the next `poll_next` polls it for the first time. Use
+    /// the same path as a wake-up (`SlotWaker { idx, ready }.wake_by_ref()`), so that a task
+    /// already waiting on the set is woken too.
+    pub fn push(&mut self, future: F) {
+        // TODO
+        todo!()
+    }
+
+    /// 1. Store `cx.waker()` as the parent waker (futures woken later must wake us).
+    /// 2. Up to `len` times: pop a ready index; skip it if its slot is empty (stale wake-up);
+    ///    poll the future with a `Waker` from `Arc<SlotWaker>` for that index (count in `polls`).
+    ///    `Ready(v)` -> free the slot, `len -= 1`, return `Ready(Some(v))`.
+    /// 3. Queue empty: `Ready(None)` if `len == 0`, else `Pending`.
+    ///    Budget used up: `cx.waker().wake_by_ref()` and `Pending`.
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<F::Output>> {
+        // TODO
+        todo!()
+    }
+
+    /// Future resolving to the next completed output (`None` once the set is empty).
+    pub fn next_completed(&mut self) -> Next<'_, F> {
+        Next { set: self }
+    }
+}
+
+impl<F: Future> Default for FuturesUnordered<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Next<'a, F> {
+    set: &'a mut FuturesUnordered<F>,
+}
+
+impl<F: Future> Future for Next<'_, F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.set.poll_next(cx)
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const HINTS: Hints = &[
+        (
+            "test_empty_set",
+            "poll_next on an empty set is Ready(None), not Pending",
+        ),
+        (
+            "test_completion_order",
+            "push queues the new index as ready, so each new future is polled once on the next poll_next",
+        ),
+        (
+            "test_child_wake_wakes_parent",
+            "SlotWaker::wake_by_ref queues its index and then wakes the stored parent waker",
+        ),
+        (
+            "test_cost_per_wake_is_constant",
+            "poll only the indices popped from the ready queue, never every slot",
+        ),
+        (
+            "test_multiple_wakes_queue_once",
+            "queue an index only if it is not queued yet",
+        ),
+        (
+            "test_self_waking_futures_respect_budget",
+            "poll at most len futures per poll_next; when the budget is used up, wake_by_ref the parent and return Pending",
+        ),
+    ];
+
+    struct Counter(AtomicUsize);
+
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counter() -> (Arc<Counter>, Waker) {
+        let c = Arc::new(Counter(AtomicUsize::new(0)));
+        (c.clone(), Waker::from(c))
+    }
+
+    /// A future completed from outside: `open()` makes it ready and wakes it.
+    #[derive(Clone, Default)]
+    struct Gate(Arc<Mutex<(bool, Option<Waker>)>>);
+
+    impl Gate {
+        fn open(&self) {
+            let w = {
+                let mut g = self.0.lock().unwrap();
+                g.0 = true;
+                g.1.take()
+            };
+            if let Some(w) = w {
+                w.wake();
+            }
+        }
+
+        fn wait(&self, v: usize) -> GateFuture {
+            GateFuture(self.clone(), v)
+        }
+    }
+
+    struct GateFuture(Gate, usize);
+
+    impl Future for GateFuture {
+        type Output = usize;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+            let mut g = (self.0).0.lock().unwrap();
+            if g.0 {
+                return Poll::Ready(self.1);
+            }
+            g.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn gates(n: usize) -> (Vec<Gate>, FuturesUnordered<GateFuture>) {
+        let gates: Vec<Gate> = (0..n).map(|_| Gate::default()).collect();
+        let mut set = FuturesUnordered::new();
+        for (i, g) in gates.iter().enumerate() {
+            set.push(g.wait(i));
+        }
+        (gates, set)
+    }
+
+    #[test]
+    fn test_empty_set() {
+        let (_, w) = counter();
+        let mut set = FuturesUnordered::<GateFuture>::new();
+        assert_eq_with_hint!(
+            set.poll_next(&mut Context::from_waker(&w)),
+            Poll::Ready(None)
+        );
+    }
+
+    #[test]
+    fn test_completion_order() {
+        let (_, w) = counter();
+        let mut cx = Context::from_waker(&w);
+        let (gates, mut set) = gates(4);
+        assert_eq_with_hint!(set.poll_next(&mut cx), Poll::Pending);
+        assert_eq_with_hint!(set.polls(), 4, "each new future is polled once");
+        let mut out = Vec::new();
+        for i in [2, 0, 3, 1] {
+            gates[i].open();
+            match set.poll_next(&mut cx) {
+                Poll::Ready(Some(v)) => out.push(v),
+                other => panic!("expected an output, got {other:?}"),
+            }
+        }
+        assert_eq_with_hint!(out, [2, 0, 3, 1]);
+        assert_with_hint!(set.is_empty());
+        assert_eq_with_hint!(set.poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_child_wake_wakes_parent() {
+        let (c, w) = counter();
+        let (gates, mut set) = gates(3);
+        assert_eq_with_hint!(set.poll_next(&mut Context::from_waker(&w)), Poll::Pending);
+        let before = c.0.load(Ordering::SeqCst);
+        gates[1].open();
+        assert_eq_with_hint!(c.0.load(Ordering::SeqCst), before + 1);
+    }
+
+    #[test]
+    fn test_cost_per_wake_is_constant() {
+        let (_, w) = counter();
+        let mut cx = Context::from_waker(&w);
+        let (gates, mut set) = gates(1000);
+        assert_eq_with_hint!(set.poll_next(&mut cx), Poll::Pending);
+        assert_eq_with_hint!(set.polls(), 1000);
+        for (round, i) in [500, 7, 999].into_iter().enumerate() {
+            gates[i].open();
+            assert_eq_with_hint!(set.poll_next(&mut cx), Poll::Ready(Some(i)));
+            assert_eq_with_hint!(
+                set.polls(),
+                1000 + round + 1,
+                "only the woken future is polled"
+            );
+        }
+        // Nothing woken: no polling at all.
+        assert_eq_with_hint!(set.poll_next(&mut cx), Poll::Pending);
+        assert_eq_with_hint!(set.polls(), 1003);
+    }
+
+    #[test]
+    fn test_multiple_wakes_queue_once() {
+        let (_, w) = counter();
+        let mut cx = Context::from_waker(&w);
+        let gate = Gate::default();
+        let mut set = FuturesUnordered::new();
+        set.push(gate.wait(1));
+        set.push(Gate::default().wait(2));
+        assert_eq_with_hint!(set.poll_next(&mut cx), Poll::Pending);
+        let child = gate.0.lock().unwrap().1.clone().unwrap();
+        child.wake_by_ref();
+        child.wake_by_ref();
+        child.wake();
+        assert_eq_with_hint!(set.poll_next(&mut cx), Poll::Pending);
+        assert_eq_with_hint!(set.polls(), 3, "three wakes, one extra poll");
+    }
+
+    #[test]
+    fn test_push_after_start_and_slot_reuse() {
+        let (_, w) = counter();
+        let mut cx = Context::from_waker(&w);
+        let (gates, mut set) = gates(2);
+        gates[0].open();
+        assert_eq!(set.poll_next(&mut cx), Poll::Ready(Some(0)));
+        let late = Gate::default();
+        late.open();
+        set.push(late.wait(10));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.poll_next(&mut cx), Poll::Ready(Some(10)));
+        gates[1].open();
+        assert_eq!(set.poll_next(&mut cx), Poll::Ready(Some(1)));
+        assert_eq!(set.slots.len(), 2, "freed slot was reused");
+    }
+
+    /// Yields `n` times (waking itself), then completes.
+    struct YieldN(usize, usize);
+
+    impl Future for YieldN {
+        type Output = usize;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+            if self.0 == 0 {
+                return Poll::Ready(self.1);
+            }
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_self_waking_futures_respect_budget() {
+        let (c, w) = counter();
+        let mut cx = Context::from_waker(&w);
+        let mut set = FuturesUnordered::new();
+        set.push(YieldN(usize::MAX, 0));
+        set.push(YieldN(5, 1));
+        assert_eq_with_hint!(
+            set.poll_next(&mut cx),
+            Poll::Pending,
+            "must not spin forever"
+        );
+        assert_with_hint!(
+            c.0.load(Ordering::SeqCst) >= 1,
+            "out of budget: wake ourselves"
+        );
+        let mut got = None;
+        for _ in 0..20 {
+            if let Poll::Ready(v) = set.poll_next(&mut cx) {
+                got = v;
+                break;
+            }
+        }
+        assert_eq_with_hint!(
+            got,
+            Some(1),
+            "the finite future completes alongside the busy one"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_tokio_timers() {
+        let mut set = FuturesUnordered::new();
+        for (v, ms) in [(0, 60), (1, 10), (2, 40), (3, 20)] {
+            set.push(async move {
+                tokio::time::sleep(Duration::from_millis(ms)).await;
+                v
+            });
+        }
+        let mut out = Vec::new();
+        while let Some(v) = set.next_completed().await {
+            out.push(v);
+        }
+        assert_eq!(out, [1, 3, 2, 0]);
+        assert_eq!(
+            set.polls(),
+            8,
+            "each sleep: one poll to start, one to finish"
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! # FuturesUnordered: a Completion-Order Task Set
+//!
+//! In this exercise, you implement a simplified `FuturesUnordered`: push many futures into one
+//! set, and get their outputs back in the order they **complete**.
+//!
+//! ## Why not just poll all of them?
+//! `join_all` (`10_join_combinators`) polls every unfinished future whenever the combined future
+//! is woken. With 10 000 futures of which one became ready, that is 10 000 polls for one result.
+//! Here each future gets its **own waker**: waking it records the future's slot index in a
+//! shared ready queue and then wakes the task that owns the set. `poll_next` polls only the
+//! slots in the ready queue, so the cost per wake is O(1), however many futures are pending.
+//!
+//! ```text
+//!   slots: [ fut0 | fut1 | (free) | fut3 ]      ready queue: [3, 0]
+//!             ^ SlotWaker { idx: 0 } --wake--> push 0, wake parent
+//! ```
+//!
+//! ## Details
+//! - A slot is queued at most once at a time (`queued` set), however often it is woken.
+//! - A newly pushed future is queued at once: it has never been polled.
+//! - Budget: one `poll_next` call polls at most as many futures as the set holds. A future that
+//!   wakes itself every time (like `yield_now`) goes back to the queue; without the budget the
+//!   loop would never end. Out of budget: wake our own waker and return `Pending`.
+//! - Slot indices are reused; a late wake for a freed index just causes one spurious poll.
+
+use std::collections::{HashSet, VecDeque};
+pub(crate) use std::future::Future;
+pub(crate) use std::pin::Pin;
+pub(crate) use std::sync::{Arc, Mutex};
+pub(crate) use std::task::{Context, Poll, Wake, Waker};
+
+#[derive(Default)]
+struct ReadyState {
+    queue: VecDeque<usize>,
+    queued: HashSet<usize>,
+}
+
+/// Shared between the set and all slot wakers.
+#[derive(Default)]
+struct ReadyQueue {
+    state: Mutex<ReadyState>,
+    /// Waker of the task polling the set.
+    parent: Mutex<Option<Waker>>,
+}
+
+impl ReadyQueue {
+    fn pop(&self) -> Option<usize> {
+        let mut st = self.state.lock().unwrap();
+        let idx = st.queue.pop_front()?;
+        st.queued.remove(&idx);
+        Some(idx)
+    }
+}
+
+/// Waker handed to the future in slot `idx`.
+struct SlotWaker {
+    idx: usize,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for SlotWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    /// Queue `idx` unless it is already queued, then wake the parent waker (if any; clone it out
+    /// of the lock before waking).
+    fn wake_by_ref(self: &Arc<Self>) {
+        {
+            let mut st = self.ready.state.lock().unwrap();
+            if !st.queued.insert(self.idx) {
+                return;
+            }
+            st.queue.push_back(self.idx);
+        }
+        let parent = self.ready.parent.lock().unwrap().clone();
+        if let Some(w) = parent {
+            w.wake();
+        }
+    }
+}
+
+pub struct FuturesUnordered<F> {
+    pub(crate) slots: Vec<Option<Pin<Box<F>>>>,
+    /// Free slot indices, reused by `push`.
+    free: Vec<usize>,
+    len: usize,
+    ready: Arc<ReadyQueue>,
+    polls: usize,
+}
+
+impl<F: Future> FuturesUnordered<F> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            ready: Arc::new(ReadyQueue::default()),
+            polls: 0,
+        }
+    }
+
+    /// Futures not yet completed.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Total number of polls of individual futures.
+    pub fn polls(&self) -> usize {
+        self.polls
+    }
+
+    /// Add a future: store it (boxed and pinned) in a free slot or a new one, count it in `len`,
+    /// and queue its index as ready, so the next `poll_next` polls it for the first time. Use
+    /// the same path as a wake-up (`SlotWaker { idx, ready }.wake_by_ref()`), so that a task
+    /// already waiting on the set is woken too.
+    pub fn push(&mut self, future: F) {
+        let fut = Some(Box::pin(future));
+        let idx = match self.free.pop() {
+            Some(idx) => {
+                self.slots[idx] = fut;
+                idx
+            }
+            None => {
+                self.slots.push(fut);
+                self.slots.len() - 1
+            }
+        };
+        self.len += 1;
+        Arc::new(SlotWaker {
+            idx,
+            ready: self.ready.clone(),
+        })
+        .wake_by_ref();
+    }
+
+    /// 1. Store `cx.waker()` as the parent waker (futures woken later must wake us).
+    /// 2. Up to `len` times: pop a ready index; skip it if its slot is empty (stale wake-up);
+    ///    poll the future with a `Waker` from `Arc<SlotWaker>` for that index (count in `polls`).
+    ///    `Ready(v)` -> free the slot, `len -= 1`, return `Ready(Some(v))`.
+    /// 3. Queue empty: `Ready(None)` if `len == 0`, else `Pending`.
+    ///    Budget used up: `cx.waker().wake_by_ref()` and `Pending`.
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<F::Output>> {
+        *self.ready.parent.lock().unwrap() = Some(cx.waker().clone());
+        for _ in 0..self.len {
+            let Some(idx) = self.ready.pop() else {
+                return Poll::Pending;
+            };
+            let Some(fut) = self.slots[idx].as_mut() else {
+                continue;
+            };
+            let waker = Waker::from(Arc::new(SlotWaker {
+                idx,
+                ready: self.ready.clone(),
+            }));
+            self.polls += 1;
+            if let Poll::Ready(v) = fut.as_mut().poll(&mut Context::from_waker(&waker)) {
+                self.slots[idx] = None;
+                self.free.push(idx);
+                self.len -= 1;
+                return Poll::Ready(Some(v));
+            }
+        }
+        if self.len == 0 {
+            return Poll::Ready(None);
+        }
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+
+    /// Future resolving to the next completed output (`None` once the set is empty).
+    pub fn next_completed(&mut self) -> Next<'_, F> {
+        Next { set: self }
+    }
+}
+
+impl<F: Future> Default for FuturesUnordered<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Next<'a, F> {
+    set: &'a mut FuturesUnordered<F>,
+}
+
+impl<F: Future> Future for Next<'_, F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.set.poll_next(cx)
+    }
+}
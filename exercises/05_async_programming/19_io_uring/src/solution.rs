@@ -0,0 +1,378 @@
+//! # io_uring from Scratch
+//!
+//! In this exercise, you set up a Linux `io_uring` with raw syscalls (`syscall6` from
+//! `02_no_std_dev/04_syscall_wrapper`, finish that first), submit a read and reap its completion.
+//! No uring crate, no libc: just two syscalls, three `mmap`s and some atomics.
+//!
+//! ## How it works
+//! ```text
+//!   user space                                   kernel
+//!   ──────────                                   ──────
+//!   SQ ring: head tail mask array[]  ──────────▶ consumes entries between head and tail
+//!   SQE array: [Sqe; sq_entries]                 (the request: opcode, fd, buf, len, offset)
+//!   CQ ring: head tail mask cqes[]   ◀────────── appends a Cqe { user_data, res } per request
+//! ```
+//! 1. `io_uring_setup(entries, &mut params)` creates the ring and returns its fd; the kernel
+//!    fills `params` with the ring sizes and the **offsets** of each field inside the rings.
+//! 2. `mmap` the SQ ring, the CQ ring and the SQE array from that fd (at the fixed offsets
+//!    `IORING_OFF_*`), shared with the kernel.
+//! 3. Submit: write an `Sqe` into a free slot, put the slot index into the SQ `array`, then
+//!    publish it by advancing the SQ `tail` with a **Release** store (the kernel must see the
+//!    entry before the new tail).
+//! 4. `io_uring_enter(fd, to_submit, min_complete, IORING_ENTER_GETEVENTS)` hands the new
+//!    entries to the kernel and waits for `min_complete` completions.
+//! 5. Reap: if the CQ `head != tail` (tail loaded with **Acquire**), read the `Cqe` at
+//!    `head & mask`, then advance `head` with a Release store so the kernel may reuse the slot.
+//!
+//! The SQ side is produced by us and consumed by the kernel; the CQ side the other way around.
+//! Like a future, a submitted read completes *later*: the buffer must stay alive and untouched
+//! until its completion has been reaped.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use syscall_wrapper::solution::syscall6;
+
+pub const SYS_IO_URING_SETUP: usize = 425;
+pub const SYS_IO_URING_ENTER: usize = 426;
+
+#[cfg(target_arch = "x86_64")]
+const SYS_MMAP: usize = 9;
+#[cfg(target_arch = "x86_64")]
+const SYS_MUNMAP: usize = 11;
+#[cfg(target_arch = "x86_64")]
+const SYS_CLOSE: usize = 3;
+
+#[cfg(not(target_arch = "x86_64"))]
+const SYS_MMAP: usize = 222;
+#[cfg(not(target_arch = "x86_64"))]
+const SYS_MUNMAP: usize = 215;
+#[cfg(not(target_arch = "x86_64"))]
+const SYS_CLOSE: usize = 57;
+
+const PROT_READ: usize = 1;
+const PROT_WRITE: usize = 2;
+const MAP_SHARED: usize = 1;
+const MAP_POPULATE: usize = 0x8000;
+
+pub const IORING_OFF_SQ_RING: usize = 0;
+pub const IORING_OFF_CQ_RING: usize = 0x800_0000;
+pub const IORING_OFF_SQES: usize = 0x1000_0000;
+
+pub const IORING_OP_READ: u8 = 22;
+pub const IORING_ENTER_GETEVENTS: usize = 1;
+
+pub const EBADF: i32 = 9;
+
+/// `struct io_sqring_offsets`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SqRingOffsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub flags: u32,
+    pub dropped: u32,
+    pub array: u32,
+    pub resv1: u32,
+    pub user_addr: u64,
+}
+
+/// `struct io_cqring_offsets`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CqRingOffsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub overflow: u32,
+    pub cqes: u32,
+    pub flags: u32,
+    pub resv1: u32,
+    pub user_addr: u64,
+}
+
+/// `struct io_uring_params`: zeroed by us, filled in by `io_uring_setup`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IoUringParams {
+    pub sq_entries: u32,
+    pub cq_entries: u32,
+    pub flags: u32,
+    pub sq_thread_cpu: u32,
+    pub sq_thread_idle: u32,
+    pub features: u32,
+    pub wq_fd: u32,
+    pub resv: [u32; 3],
+    pub sq_off: SqRingOffsets,
+    pub cq_off: CqRingOffsets,
+}
+
+/// Submission queue entry (`struct io_uring_sqe`), 64 bytes.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sqe {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    pub off: u64,
+    pub addr: u64,
+    pub len: u32,
+    pub rw_flags: u32,
+    pub user_data: u64,
+    pub buf_index: u16,
+    pub personality: u16,
+    pub splice_fd_in: i32,
+    pub addr3: u64,
+    pub pad2: u64,
+}
+
+/// Completion queue entry (`struct io_uring_cqe`), 16 bytes.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Cqe {
+    pub user_data: u64,
+    /// Result of the operation: bytes read, or a negative errno.
+    pub res: i32,
+    pub flags: u32,
+}
+
+/// A shared mapping of the ring fd, unmapped on drop.
+struct Mmap {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mmap {
+    fn map(fd: usize, len: usize, offset: usize) -> Result<Self, isize> {
+        let ret = unsafe {
+            syscall6(
+                SYS_MMAP,
+                [
+                    0,
+                    len,
+                    PROT_READ | PROT_WRITE,
+                    MAP_SHARED | MAP_POPULATE,
+                    fd,
+                    offset,
+                ],
+            )
+        };
+        // mmap returns an address, or -errno (addresses never fall in the top 4095 bytes).
+        if (-4095..0).contains(&ret) {
+            return Err(ret);
+        }
+        Ok(Self {
+            ptr: ret as *mut u8,
+            len,
+        })
+    }
+
+    /// The `u32` at byte offset `off`, accessed atomically (shared with the kernel).
+    fn u32_at(&self, off: u32) -> &AtomicU32 {
+        assert!(off as usize + 4 <= self.len);
+        unsafe { &*(self.ptr.add(off as usize) as *const AtomicU32) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe { syscall6(SYS_MUNMAP, [self.ptr as usize, self.len, 0, 0, 0, 0]) };
+    }
+}
+
+pub struct IoUring {
+    fd: usize,
+    params: IoUringParams,
+    sq_ring: Mmap,
+    cq_ring: Mmap,
+    sqes: Mmap,
+}
+
+impl IoUring {
+    /// Create a ring with (at least) `entries` submission slots.
+    ///
+    /// 1. `syscall6(SYS_IO_URING_SETUP, [entries, &mut params as *mut _ as usize, 0, 0, 0, 0])`
+    ///    with zeroed `params`; a negative result is the error.
+    /// 2. Map the SQ ring: length `sq_off.array + sq_entries * 4`, offset `IORING_OFF_SQ_RING`.
+    /// 3. Map the CQ ring: length `cq_off.cqes + cq_entries * size_of::<Cqe>()`, offset
+    ///    `IORING_OFF_CQ_RING`.
+    /// 4. Map the SQE array: length `sq_entries * size_of::<Sqe>()`, offset `IORING_OFF_SQES`.
+    ///
+    /// If a mapping fails, close the fd (`SYS_CLOSE`) before returning the error; mappings made
+    /// so far are unmapped when their `Mmap` is dropped.
+    pub fn new(entries: u32) -> Result<Self, isize> {
+        let mut params = IoUringParams::default();
+        let fd = unsafe {
+            syscall6(
+                SYS_IO_URING_SETUP,
+                [entries as usize, &mut params as *mut _ as usize, 0, 0, 0, 0],
+            )
+        };
+        if fd < 0 {
+            return Err(fd);
+        }
+        let fd = fd as usize;
+        let maps = (|| {
+            let sq_len = params.sq_off.array as usize + params.sq_entries as usize * 4;
+            let cq_len = params.cq_off.cqes as usize
+                + params.cq_entries as usize * std::mem::size_of::<Cqe>();
+            let sqe_len = params.sq_entries as usize * std::mem::size_of::<Sqe>();
+            Ok((
+                Mmap::map(fd, sq_len, IORING_OFF_SQ_RING)?,
+                Mmap::map(fd, cq_len, IORING_OFF_CQ_RING)?,
+                Mmap::map(fd, sqe_len, IORING_OFF_SQES)?,
+            ))
+        })();
+        match maps {
+            Ok((sq_ring, cq_ring, sqes)) => Ok(Self {
+                fd,
+                params,
+                sq_ring,
+                cq_ring,
+                sqes,
+            }),
+            Err(e) => {
+                unsafe { syscall6(SYS_CLOSE, [fd, 0, 0, 0, 0, 0]) };
+                Err(e)
+            }
+        }
+    }
+
+    /// Submission slots, as rounded up by the kernel (a power of two).
+    pub fn sq_entries(&self) -> u32 {
+        self.params.sq_entries
+    }
+
+    /// Completion slots (twice `sq_entries` by default).
+    pub fn cq_entries(&self) -> u32 {
+        self.params.cq_entries
+    }
+
+    /// Queue (but don't submit yet) a read of `len` bytes at `offset` of `fd` into `buf`.
+    /// Returns `false` if the submission queue is full.
+    ///
+    /// - `tail` (SQ ring, `sq_off.tail`): only we write it, a `Relaxed` load is enough;
+    ///   `head` (`sq_off.head`) is written by the kernel: load it with `Acquire`.
+    /// - Full if `tail - head == sq_entries` (use `wrapping_sub`: the counters wrap around).
+    /// - `idx = tail & ring_mask` (mask at `sq_off.ring_mask`). Write the `Sqe` (opcode
+    ///   `IORING_OP_READ`, `fd`, `off`, `addr`, `len`, `user_data`, the rest zero) to
+    ///   `sqes[idx]` and store `idx` into the SQ `array[idx]` (`u32`s at `sq_off.array`).
+    /// - Publish: store `tail + 1` with `Release`.
+    ///
+    /// # Safety
+    /// `buf` must stay valid for `len` bytes, and unused by anyone else, until the completion
+    /// with this `user_data` has been reaped.
+    pub unsafe fn prep_read(
+        &mut self,
+        fd: i32,
+        buf: *mut u8,
+        len: u32,
+        offset: u64,
+        user_data: u64,
+    ) -> bool {
+        let off = self.params.sq_off;
+        let tail = self.sq_ring.u32_at(off.tail).load(Ordering::Relaxed);
+        let head = self.sq_ring.u32_at(off.head).load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == self.params.sq_entries {
+            return false;
+        }
+        let mask = self.sq_ring.u32_at(off.ring_mask).load(Ordering::Relaxed);
+        let idx = tail & mask;
+        let sqe = Sqe {
+            opcode: IORING_OP_READ,
+            fd,
+            off: offset,
+            addr: buf as u64,
+            len,
+            user_data,
+            ..Sqe::default()
+        };
+        (self.sqes.ptr as *mut Sqe).add(idx as usize).write(sqe);
+        self.sq_ring
+            .u32_at(off.array + idx * 4)
+            .store(idx, Ordering::Relaxed);
+        self.sq_ring
+            .u32_at(off.tail)
+            .store(tail.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// `io_uring_enter(fd, to_submit, min_complete, flags, NULL, 0)`, with
+    /// `IORING_ENTER_GETEVENTS` in `flags` when `min_complete > 0`. Returns the number of
+    /// entries submitted, or a negative errno.
+    pub fn submit_and_wait(&mut self, to_submit: u32, min_complete: u32) -> isize {
+        let flags = if min_complete > 0 {
+            IORING_ENTER_GETEVENTS
+        } else {
+            0
+        };
+        unsafe {
+            syscall6(
+                SYS_IO_URING_ENTER,
+                [
+                    self.fd,
+                    to_submit as usize,
+                    min_complete as usize,
+                    flags,
+                    0,
+                    0,
+                ],
+            )
+        }
+    }
+
+    /// Take the next completion, if any.
+    ///
+    /// `head` (CQ ring, `cq_off.head`) is ours: `Relaxed`; `tail` is the kernel's: `Acquire`.
+    /// Empty if they are equal. Otherwise copy the `Cqe` at `cqes[head & mask]` (byte offset
+    /// `cq_off.cqes`), then store `head + 1` with `Release`.
+    pub fn reap(&mut self) -> Option<Cqe> {
+        let off = self.params.cq_off;
+        let head = self.cq_ring.u32_at(off.head).load(Ordering::Relaxed);
+        let tail = self.cq_ring.u32_at(off.tail).load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let mask = self.cq_ring.u32_at(off.ring_mask).load(Ordering::Relaxed);
+        let cqe = unsafe {
+            (self.cq_ring.ptr.add(off.cqes as usize) as *const Cqe)
+                .add((head & mask) as usize)
+                .read()
+        };
+        self.cq_ring
+            .u32_at(off.head)
+            .store(head.wrapping_add(1), Ordering::Release);
+        Some(cqe)
+    }
+
+    /// Read into `buf` at `offset` of `fd` and wait for the result: bytes read, or `-errno`.
+    pub fn read_at(&mut self, fd: i32, buf: &mut [u8], offset: u64) -> Result<usize, i32> {
+        const USER_DATA: u64 = u64::MAX;
+        let len = buf.len().min(u32::MAX as usize) as u32;
+        // SAFETY: `buf` stays borrowed until the completion below has been reaped.
+        let queued = unsafe { self.prep_read(fd, buf.as_mut_ptr(), len, offset, USER_DATA) };
+        assert!(queued, "submission queue full");
+        let ret = self.submit_and_wait(1, 1);
+        if ret < 0 {
+            // The entry was not consumed; this ring is unusable from here on.
+            panic!("io_uring_enter failed: {ret}");
+        }
+        let cqe = self
+            .reap()
+            .expect("io_uring_enter returned without a completion");
+        assert_eq!(cqe.user_data, USER_DATA, "unexpected completion");
+        if cqe.res < 0 {
+            Err(cqe.res)
+        } else {
+            Ok(cqe.res as usize)
+        }
+    }
+}
+
+impl Drop for IoUring {
+    fn drop(&mut self) {
+        unsafe { syscall6(SYS_CLOSE, [self.fd, 0, 0, 0, 0, 0]) };
+    }
+}
@@ -0,0 +1,341 @@
+//! # Hierarchical Timer Wheel
+//!
+//! The hashed wheel of `07_timer_wheel` has one level: a timer far in the future sits in its
+//! slot and is looked at (and skipped) once per revolution. A **hierarchical** wheel — the
+//! classic Linux `tv1..tv5` design — stacks wheels of growing granularity like the hands of a
+//! clock, so every timer is touched only a few times before it fires, however far away it is.
+//!
+//! ## Levels
+//! ```text
+//! level 0: 16 slots of 1 tick      covers now .. now + 16
+//! level 1: 16 slots of 16 ticks    covers    .. now + 256
+//! level 2: 16 slots of 256 ticks   covers    .. now + 4096
+//! level 3: 16 slots of 4096 ticks  covers    .. now + 65536
+//! ```
+//! - A timer with `delta = deadline - now` goes to the lowest level `l` with
+//!   `delta < SLOTS^(l+1)`, in slot `(deadline >> (SLOT_BITS * l)) % SLOTS` — slots index
+//!   absolute time, like the bits of a page offset index a page table
+//! - Each tick fires level 0's slot `now % SLOTS`: everything in it expires exactly now
+//! - When the lower `SLOT_BITS * l` bits of `now` roll over to zero, level `l`'s current slot is
+//!   **cascaded**: its timers are re-inserted, and being closer now, they land on lower levels
+//! - Timers beyond the top level are parked at the farthest reachable slot and re-placed by the
+//!   cascade that reaches them
+//!
+//! The wheel stores plain `callback_id`s and `advance` returns what fired, so both an async
+//! `Delay` (callback = waker slot) and a scheduler's `sleep` (callback = thread id) can use it.
+
+use std::collections::HashMap;
+
+pub const SLOT_BITS: u32 = 4;
+pub const SLOTS: usize = 1 << SLOT_BITS;
+pub const LEVELS: usize = 4;
+/// Largest `deadline - now` that fits in the wheel.
+pub const MAX_DELTA: u64 = (1 << (SLOT_BITS * LEVELS as u32)) - 1;
+
+pub type TimerId = u64;
+
+/// A timer that expired during `advance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fired {
+    pub id: TimerId,
+    pub callback_id: u64,
+    /// The tick it fired at.
+    pub deadline: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    id: TimerId,
+    callback_id: u64,
+    deadline: u64,
+}
+
+pub struct TimerWheel {
+    now: u64,
+    levels: [[Vec<Entry>; SLOTS]; LEVELS],
+    /// `(level, slot)` of every pending timer, for `cancel`.
+    locations: HashMap<TimerId, (usize, usize)>,
+    next_id: TimerId,
+    /// Entries moved by cascades so far.
+    cascaded: usize,
+}
+
+impl TimerWheel {
+    pub fn new() -> Self {
+        Self {
+            now: 0,
+            levels: std::array::from_fn(|_| std::array::from_fn(|_| Vec::new())),
+            locations: HashMap::new(),
+            next_id: 0,
+            cascaded: 0,
+        }
+    }
+
+    /// Current time in ticks.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Timers scheduled and not yet fired or cancelled.
+    pub fn pending(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// The level timer `id` currently sits on.
+    pub fn level_of(&self, id: TimerId) -> Option<usize> {
+        self.locations.get(&id).map(|&(level, _)| level)
+    }
+
+    /// Entries moved by cascades so far.
+    pub fn cascaded(&self) -> usize {
+        self.cascaded
+    }
+
+    /// `(level, slot)` for a timer expiring at `deadline` (`> now`).
+    ///
+    /// 1. `delta = deadline - now`; beyond `MAX_DELTA`, place as if `deadline = now + MAX_DELTA`
+    /// 2. `level` = the lowest `l` with `delta >> (SLOT_BITS * (l + 1)) == 0`
+    /// 3. `slot = (deadline >> (SLOT_BITS * level)) % SLOTS` (with the clamped deadline)
+    fn place(&self, deadline: u64) -> (usize, usize) {
+        // TODO
+        todo!()
+    }
+
+    /// Push `entry` at the end of its `place`'s slot and record the location.
+    fn insert(&mut self, entry: Entry) {
+        // TODO
+        todo!()
+    }
+
+    /// Schedule `callback_id` to fire `after_ticks` from now (0 is treated as 1: the next tick).
+    /// Ids are handed out from `next_id`, starting at 0.
+    pub fn schedule(&mut self, after_ticks: u64, callback_id: u64) -> TimerId {
+        // TODO
+        todo!()
+    }
+
+    /// Remove a pending timer. Returns `false` if it already fired, was cancelled, or never
+    /// existed. Keep the other entries of the slot in order (`Vec::remove`, not `swap_remove`).
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        // TODO
+        todo!()
+    }
+
+    /// One tick.
+    ///
+    /// 1. `now += 1`
+    /// 2. Cascade, from the **highest** level down to 1: when `now % SLOTS^l == 0`, take all of
+    ///    `levels[l][(now >> (SLOT_BITS * l)) % SLOTS]` and `insert` them again (counting them
+    ///    in `cascaded`). Going top-down lets a timer fall through several levels at once.
+    /// 3. Take level 0's slot `now % SLOTS`; every entry in it expires now. Forget their
+    ///    locations and return them as `Fired`, ordered by id.
+    fn tick(&mut self) -> Vec<Fired> {
+        // TODO
+        todo!()
+    }
+
+    /// Advance `ticks` ticks; returns the timers that fired, in firing order.
+    pub fn advance(&mut self, ticks: u64) -> Vec<Fired> {
+        let mut fired = Vec::new();
+        for _ in 0..ticks {
+            fired.extend(self.tick());
+        }
+        fired
+    }
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_fires_on_its_tick",
+            "level 0 slot now % SLOTS is taken on each tick; a timer fires exactly on its deadline",
+        ),
+        (
+            "test_zero_ticks_fires_next_tick",
+            "schedule treats 0 ticks as 1: the deadline is now + 1",
+        ),
+        (
+            "test_cascade_from_level_1",
+            "level = the lowest l with delta >> (SLOT_BITS * (l + 1)) == 0, and slot = (deadline >> (SLOT_BITS * level)) % SLOTS",
+        ),
+        (
+            "test_cascade_through_every_level",
+            "cascade from the highest level down to 1, so a timer can fall through several levels in the same tick",
+        ),
+        (
+            "test_beyond_the_top_level",
+            "clamp delta to MAX_DELTA when placing, but keep the real deadline; it is re-placed by later cascades",
+        ),
+        (
+            "test_cancel",
+            "cancel looks the timer up by its recorded location, removes it with Vec::remove and forgets the location",
+        ),
+        (
+            "test_same_tick_ordered_by_id",
+            "sort the fired entries by id before returning them",
+        ),
+    ];
+
+    fn ids(fired: &[Fired]) -> Vec<TimerId> {
+        fired.iter().map(|f| f.id).collect()
+    }
+
+    #[test]
+    fn test_fires_on_its_tick() {
+        let mut w = TimerWheel::new();
+        let id = w.schedule(5, 42);
+        assert_eq_with_hint!(w.level_of(id), Some(0));
+        assert_with_hint!(w.advance(4).is_empty());
+        assert_eq_with_hint!(
+            w.advance(1),
+            [Fired {
+                id,
+                callback_id: 42,
+                deadline: 5
+            }]
+        );
+        assert_eq_with_hint!(w.pending(), 0);
+        assert_with_hint!(w.advance(100).is_empty());
+    }
+
+    #[test]
+    fn test_zero_ticks_fires_next_tick() {
+        let mut w = TimerWheel::new();
+        w.advance(7);
+        let id = w.schedule(0, 1);
+        let fired = w.advance(1);
+        assert_eq_with_hint!((ids(&fired), fired[0].deadline), (vec![id], 8));
+    }
+
+    #[test]
+    fn test_cascade_from_level_1() {
+        let mut w = TimerWheel::new();
+        let id = w.schedule(20, 0);
+        assert_eq_with_hint!(w.level_of(id), Some(1));
+        assert_with_hint!(w.advance(15).is_empty());
+        assert_eq_with_hint!(w.level_of(id), Some(1));
+        assert_with_hint!(w.advance(1).is_empty());
+        assert_eq_with_hint!(w.level_of(id), Some(0), "cascaded at tick 16");
+        assert_eq_with_hint!(w.cascaded(), 1);
+        assert_eq_with_hint!(w.advance(4)[0].deadline, 20);
+    }
+
+    #[test]
+    fn test_cascade_through_every_level() {
+        let mut w = TimerWheel::new();
+        let id = w.schedule(1000, 0);
+        assert_eq_with_hint!(w.level_of(id), Some(2));
+        assert_with_hint!(w.advance(767).is_empty());
+        assert_eq_with_hint!(w.level_of(id), Some(2));
+        w.advance(1); // 768 = 3 * 256: level 2 slot 3 cascades, 232 ticks left
+        assert_eq_with_hint!(w.level_of(id), Some(1));
+        w.advance(992 - 768); // 992 = 62 * 16: 8 ticks left
+        assert_eq_with_hint!(w.level_of(id), Some(0));
+        assert_with_hint!(w.advance(7).is_empty());
+        assert_eq_with_hint!(w.advance(1)[0].deadline, 1000);
+        assert_eq_with_hint!(w.cascaded(), 2);
+
+        // Top-down cascading: from level 3 straight to level 0 on one tick.
+        let mut w = TimerWheel::new();
+        w.advance(4000);
+        let id = w.schedule(8192 + 5 - 4000, 0);
+        assert_eq_with_hint!(w.level_of(id), Some(3));
+        w.advance(8191 - 4000);
+        assert_eq_with_hint!(w.level_of(id), Some(3));
+        w.advance(1);
+        assert_eq_with_hint!(w.level_of(id), Some(0));
+        assert_eq_with_hint!(w.advance(5)[0].deadline, 8197);
+    }
+
+    #[test]
+    fn test_beyond_the_top_level() {
+        let mut w = TimerWheel::new();
+        let id = w.schedule(100_000, 9);
+        assert_eq_with_hint!(w.level_of(id), Some(LEVELS - 1));
+        assert_with_hint!(w.advance(99_999).is_empty());
+        let fired = w.advance(1);
+        assert_eq_with_hint!((fired[0].id, fired[0].deadline), (id, 100_000));
+        assert_with_hint!(w.cascaded() <= 2 * LEVELS, "touched a few times only");
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut w = TimerWheel::new();
+        let a = w.schedule(30, 1);
+        let b = w.schedule(30, 2);
+        let c = w.schedule(30, 3);
+        w.advance(16); // all three cascaded to level 0
+        assert_with_hint!(w.cancel(b));
+        assert_with_hint!(!w.cancel(b), "already cancelled");
+        assert_with_hint!(!w.cancel(99), "never existed");
+        assert_eq_with_hint!(w.pending(), 2);
+        let fired = w.advance(14);
+        assert_eq_with_hint!(ids(&fired), [a, c]);
+        assert_with_hint!(!w.cancel(a), "already fired");
+    }
+
+    #[test]
+    fn test_same_tick_ordered_by_id() {
+        let mut w = TimerWheel::new();
+        let early = w.schedule(40, 0); // level 1, cascades at 32
+        w.advance(33);
+        let late = w.schedule(7, 0); // level 0 directly, same deadline
+        let fired = w.advance(7);
+        assert_eq_with_hint!(ids(&fired), [early, late]);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut w = TimerWheel::new();
+        // (deadline, id) of every pending timer.
+        let mut model: Vec<(u64, TimerId)> = Vec::new();
+        let mut x: u64 = 0x9E37_79B9_7F4A_7C15;
+        for _ in 0..400 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            match x & 7 {
+                0 if !model.is_empty() => {
+                    let (_, id) = model.remove((x >> 8) as usize % model.len());
+                    assert!(w.cancel(id));
+                }
+                1 | 2 => {
+                    let step = (x >> 16) % 300;
+                    for _ in 0..step {
+                        let now = w.now() + 1;
+                        let mut want: Vec<TimerId> =
+                            model.iter().filter(|t| t.0 == now).map(|t| t.1).collect();
+                        want.sort_unstable();
+                        model.retain(|t| t.0 != now);
+                        let fired = w.advance(1);
+                        assert!(fired.iter().all(|f| f.deadline == now));
+                        assert_eq!(ids(&fired), want, "tick {now}");
+                    }
+                }
+                _ => {
+                    let after = 1 + (x >> 20) % [20, 300, 5000, 80_000][(x >> 4) as usize & 3];
+                    let id = w.schedule(after, after);
+                    model.push((w.now() + after, id));
+                }
+            }
+            assert_eq!(w.pending(), model.len());
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! # Hierarchical Timer Wheel
+//!
+//! The hashed wheel of `07_timer_wheel` has one level: a timer far in the future sits in its
+//! slot and is looked at (and skipped) once per revolution. A **hierarchical** wheel — the
+//! classic Linux `tv1..tv5` design — stacks wheels of growing granularity like the hands of a
+//! clock, so every timer is touched only a few times before it fires, however far away it is.
+//!
+//! ## Levels
+//! ```text
+//! level 0: 16 slots of 1 tick      covers now .. now + 16
+//! level 1: 16 slots of 16 ticks    covers    .. now + 256
+//! level 2: 16 slots of 256 ticks   covers    .. now + 4096
+//! level 3: 16 slots of 4096 ticks  covers    .. now + 65536
+//! ```
+//! - A timer with `delta = deadline - now` goes to the lowest level `l` with
+//!   `delta < SLOTS^(l+1)`, in slot `(deadline >> (SLOT_BITS * l)) % SLOTS` — slots index
+//!   absolute time, like the bits of a page offset index a page table
+//! - Each tick fires level 0's slot `now % SLOTS`: everything in it expires exactly now
+//! - When the lower `SLOT_BITS * l` bits of `now` roll over to zero, level `l`'s current slot is
+//!   **cascaded**: its timers are re-inserted, and being closer now, they land on lower levels
+//! - Timers beyond the top level are parked at the farthest reachable slot and re-placed by the
+//!   cascade that reaches them
+//!
+//! The wheel stores plain `callback_id`s and `advance` returns what fired, so both an async
+//! `Delay` (callback = waker slot) and a scheduler's `sleep` (callback = thread id) can use it.
+
+use std::collections::HashMap;
+
+pub const SLOT_BITS: u32 = 4;
+pub const SLOTS: usize = 1 << SLOT_BITS;
+pub const LEVELS: usize = 4;
+/// Largest `deadline - now` that fits in the wheel.
+pub const MAX_DELTA: u64 = (1 << (SLOT_BITS * LEVELS as u32)) - 1;
+
+pub type TimerId = u64;
+
+/// A timer that expired during `advance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fired {
+    pub id: TimerId,
+    pub callback_id: u64,
+    /// The tick it fired at.
+    pub deadline: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    id: TimerId,
+    callback_id: u64,
+    deadline: u64,
+}
+
+pub struct TimerWheel {
+    now: u64,
+    levels: [[Vec<Entry>; SLOTS]; LEVELS],
+    /// `(level, slot)` of every pending timer, for `cancel`.
+    locations: HashMap<TimerId, (usize, usize)>,
+    next_id: TimerId,
+    /// Entries moved by cascades so far.
+    cascaded: usize,
+}
+
+impl TimerWheel {
+    pub fn new() -> Self {
+        Self {
+            now: 0,
+            levels: std::array::from_fn(|_| std::array::from_fn(|_| Vec::new())),
+            locations: HashMap::new(),
+            next_id: 0,
+            cascaded: 0,
+        }
+    }
+
+    /// Current time in ticks.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Timers scheduled and not yet fired or cancelled.
+    pub fn pending(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// The level timer `id` currently sits on.
+    pub fn level_of(&self, id: TimerId) -> Option<usize> {
+        self.locations.get(&id).map(|&(level, _)| level)
+    }
+
+    /// Entries moved by cascades so far.
+    pub fn cascaded(&self) -> usize {
+        self.cascaded
+    }
+
+    /// `(level, slot)` for a timer expiring at `deadline` (`> now`).
+    ///
+    /// 1. `delta = deadline - now`; beyond `MAX_DELTA`, place as if `deadline = now + MAX_DELTA`
+    /// 2. `level` = the lowest `l` with `delta >> (SLOT_BITS * (l + 1)) == 0`
+    /// 3. `slot = (deadline >> (SLOT_BITS * level)) % SLOTS` (with the clamped deadline)
+    fn place(&self, deadline: u64) -> (usize, usize) {
+        let delta = (deadline - self.now).min(MAX_DELTA);
+        let deadline = self.now + delta;
+        let level = (0..LEVELS)
+            .find(|&l| delta >> (SLOT_BITS * (l as u32 + 1)) == 0)
+            .unwrap();
+        let slot = (deadline >> (SLOT_BITS * level as u32)) as usize & (SLOTS - 1);
+        (level, slot)
+    }
+
+    /// Push `entry` at the end of its `place`'s slot and record the location.
+    fn insert(&mut self, entry: Entry) {
+        let (level, slot) = self.place(entry.deadline);
+        self.levels[level][slot].push(entry);
+        self.locations.insert(entry.id, (level, slot));
+    }
+
+    /// Schedule `callback_id` to fire `after_ticks` from now (0 is treated as 1: the next tick).
+    /// Ids are handed out from `next_id`, starting at 0.
+    pub fn schedule(&mut self, after_ticks: u64, callback_id: u64) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.insert(Entry {
+            id,
+            callback_id,
+            deadline: self.now + after_ticks.max(1),
+        });
+        id
+    }
+
+    /// Remove a pending timer. Returns `false` if it already fired, was cancelled, or never
+    /// existed. Keep the other entries of the slot in order (`Vec::remove`, not `swap_remove`).
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let Some((level, slot)) = self.locations.remove(&id) else {
+            return false;
+        };
+        let bucket = &mut self.levels[level][slot];
+        let pos = bucket.iter().position(|e| e.id == id).unwrap();
+        bucket.remove(pos);
+        true
+    }
+
+    /// One tick.
+    ///
+    /// 1. `now += 1`
+    /// 2. Cascade, from the **highest** level down to 1: when `now % SLOTS^l == 0`, take all of
+    ///    `levels[l][(now >> (SLOT_BITS * l)) % SLOTS]` and `insert` them again (counting them
+    ///    in `cascaded`). Going top-down lets a timer fall through several levels at once.
+    /// 3. Take level 0's slot `now % SLOTS`; every entry in it expires now. Forget their
+    ///    locations and return them as `Fired`, ordered by id.
+    fn tick(&mut self) -> Vec<Fired> {
+        self.now += 1;
+        for level in (1..LEVELS).rev() {
+            let shift = SLOT_BITS * level as u32;
+            if self.now & ((1 << shift) - 1) == 0 {
+                let slot = (self.now >> shift) as usize & (SLOTS - 1);
+                let entries = std::mem::take(&mut self.levels[level][slot]);
+                self.cascaded += entries.len();
+                for e in entries {
+                    self.insert(e);
+                }
+            }
+        }
+        let slot = self.now as usize & (SLOTS - 1);
+        let mut fired: Vec<Fired> = std::mem::take(&mut self.levels[0][slot])
+            .into_iter()
+            .map(|e| {
+                debug_assert_eq!(e.deadline, self.now);
+                self.locations.remove(&e.id);
+                Fired {
+                    id: e.id,
+                    callback_id: e.callback_id,
+                    deadline: e.deadline,
+                }
+            })
+            .collect();
+        fired.sort_unstable_by_key(|f| f.id);
+        fired
+    }
+
+    /// Advance `ticks` ticks; returns the timers that fired, in firing order.
+    pub fn advance(&mut self, ticks: u64) -> Vec<Fired> {
+        let mut fired = Vec::new();
+        for _ in 0..ticks {
+            fired.extend(self.tick());
+        }
+        fired
+    }
+}
+
+impl Default for TimerWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,393 @@
+//! # Job Outcomes Across Backends
+//!
+//! `01_thread_spawn`'s `handle_panic` turned one thread's panic into `Err(())`. A real job
+//! runner needs more: it runs a whole batch, and for every job reports *why* it did not produce
+//! a value. In this exercise, you run the same batch of jobs on three backends and classify each
+//! outcome the same way:
+//!
+//! - **OS threads**: `thread::spawn`; `join()` hands back the panic payload.
+//! - **Green threads** (`04_context_switch/02_green_threads`; finish it first): a panic must not
+//!   unwind out of the green thread's `extern "C"` entry (that aborts the process), so every job
+//!   runs inside `catch_unwind`.
+//! - **Tokio tasks**: `JoinError` says whether the task panicked (`into_panic()` gives the
+//!   payload) or was cancelled by `abort()`.
+//!
+//! ## Outcomes
+//! - `Ok(value)`: the job returned `Ok`.
+//! - `JobError::Failed(msg)`: the job returned `Err(msg)`.
+//! - `JobError::Panicked(msg)`: the job panicked. `panic!("literal")` carries a `&'static str`,
+//!   `panic!("{x}")` a `String`; anything else (`panic_any(42)`) is reported as
+//!   `NON_STRING_PANIC`.
+//! - `JobError::Cancelled`: the batch's `CancelToken` was cancelled, and either the job had not
+//!   started yet, or it stopped at a `checkpoint()`.
+//!
+//! ## Cooperative cancellation
+//! `CancelToken::checkpoint` unwinds the job with a private `Cancelled` payload through
+//! `resume_unwind` (which, unlike `panic!`, does not run the panic hook, so nothing is printed).
+//! The runner's `catch_unwind` then sees that payload and reports `Cancelled` rather than a
+//! panic. A job that never calls `checkpoint` runs to completion.
+
+#![allow(unused_imports, unused_variables)]
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use green_threads::Scheduler;
+
+/// Reported for a panic whose payload is neither `&str` nor `String`.
+pub const NON_STRING_PANIC: &str = "<non-string panic payload>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    /// The job panicked with this message.
+    Panicked(String),
+    /// The job returned `Err` with this message.
+    Failed(String),
+    /// The job was cancelled before or while running.
+    Cancelled,
+}
+
+/// A unit of work. It gets the batch's token so it can `checkpoint()` along the way.
+pub type Job<T> = Box<dyn FnOnce(&CancelToken) -> Result<T, String> + Send>;
+
+/// Payload `CancelToken::checkpoint` unwinds with.
+#[allow(dead_code)]
+struct Cancelled;
+
+/// Shared cancellation flag of a batch; clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Stop the calling job here if the batch was cancelled.
+    pub fn checkpoint(&self) {
+        // TODO: If cancelled, panic::resume_unwind(Box::new(Cancelled))
+        todo!()
+    }
+}
+
+/// The message in a panic payload: the `&str` or `String` it holds, else `NON_STRING_PANIC`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    // TODO: downcast_ref::<&str>(), then downcast_ref::<String>(), else NON_STRING_PANIC
+    todo!()
+}
+
+/// Classify what running a job under `catch_unwind` (or `join`) produced.
+///
+/// A `Cancelled` payload means `Cancelled`; any other payload `Panicked(panic_message(..))`;
+/// otherwise the job's own `Ok` / `Err(msg)` (as `Failed(msg)`).
+pub fn classify<T>(outcome: thread::Result<Result<T, String>>) -> Result<T, JobError> {
+    // TODO: Ok(Ok(v)) -> Ok(v), Ok(Err(msg)) -> Failed(msg), a Cancelled payload (payload.is::<Cancelled>())
+    // -> Cancelled, any other payload -> Panicked(panic_message(&*payload))
+    todo!()
+}
+
+/// Run `job` on the current thread: `Cancelled` without calling it if `token` is already
+/// cancelled, else the classified `catch_unwind` outcome.
+pub fn run_job<T>(job: Job<T>, token: &CancelToken) -> Result<T, JobError> {
+    // TODO: Cancelled if the token is already cancelled, else classify(catch_unwind(AssertUnwindSafe(..)))
+    todo!()
+}
+
+/// One OS thread per job, all started before any is joined; results in job order.
+///
+/// Each thread checks `token` before calling its job (as `run_job` does), but calls the job
+/// directly, without `catch_unwind`: `join()` returns the panic payload for `classify`.
+pub fn run_on_threads<T: Send + 'static>(
+    jobs: Vec<Job<T>>,
+    token: &CancelToken,
+) -> Vec<Result<T, JobError>> {
+    // TODO: Spawn every thread first (each checks the token, then classify(Ok(job(&token)))),
+    // then join in order; a join error is classify(Err(payload))
+    todo!()
+}
+
+type GreenTask = Box<dyn FnOnce()>;
+
+thread_local! {
+    /// Tasks waiting for a green thread, in spawn order. Green threads only take an
+    /// `extern "C" fn()`, so this is how `green_entry` finds its work.
+    static GREEN_TASKS: RefCell<VecDeque<GreenTask>> = const { RefCell::new(VecDeque::new()) };
+}
+
+extern "C" fn green_entry() {
+    if let Some(task) = GREEN_TASKS.with(|q| q.borrow_mut().pop_front()) {
+        task();
+    }
+}
+
+/// One green thread per job on a fresh `Scheduler` on the calling OS thread; results in job
+/// order. Green threads run in spawn order, and a job runs to completion unless it yields, so
+/// when job `i` cancels the token, every job after it reports `Cancelled`.
+///
+/// Queue one task per job in `GREEN_TASKS` that stores `run_job(job, &token)` into its slot of
+/// a shared results vector, spawn one `green_entry` thread per job, then `run()`.
+pub fn run_on_green_threads<T: 'static>(
+    jobs: Vec<Job<T>>,
+    token: &CancelToken,
+) -> Vec<Result<T, JobError>> {
+    // TODO: Shared Rc<RefCell<Vec<Option<..>>>> results, one GREEN_TASKS entry + sched.spawn(green_entry)
+    // per job, sched.run(), then unwrap the results
+    todo!()
+}
+
+/// One tokio task per job; results in job order.
+///
+/// Each task calls `token.checkpoint()` before its job. Spawn all of them, then, if `token` is
+/// already cancelled, `abort()` every handle (a task that already started stops at its
+/// checkpoint instead). Await the handles in order: a `JoinError` that `is_cancelled()` is
+/// `Cancelled`, one that `is_panic()` goes through `classify(Err(e.into_panic()))`.
+pub async fn run_on_tokio<T: Send + 'static>(
+    jobs: Vec<Job<T>>,
+    token: &CancelToken,
+) -> Vec<Result<T, JobError>> {
+    // TODO: tokio::spawn each job behind token.checkpoint(), abort all if already cancelled,
+    // await in order and map JoinError: is_cancelled() -> Cancelled, else classify(Err(e.into_panic()))
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const HINTS: Hints = &[
+        (
+            "test_panic_message",
+            "panic!(\"literal\") carries a &'static str, panic!(\"{x}\") a String: try downcast_ref for both",
+        ),
+        (
+            "test_classify",
+            "check for the Cancelled payload with payload.is::<Cancelled>() before treating it as a panic",
+        ),
+        (
+            "test_threads_mixed_batch",
+            "join() returns Err(payload) for a panicked thread: feed it to classify(Err(payload))",
+        ),
+        (
+            "test_green_mixed_batch",
+            "wrap every job in catch_unwind inside the green thread: a panic must not unwind out of the extern \"C\" entry",
+        ),
+        (
+            "test_green_cancel_stops_later_jobs",
+            "run_job must check the token before calling the job, so jobs after the cancelling one report Cancelled",
+        ),
+        (
+            "test_tokio_mixed_batch",
+            "a JoinError that is_panic() holds the payload: classify(Err(e.into_panic()))",
+        ),
+        (
+            "test_tokio_pre_cancelled",
+            "abort() every handle when the token is already cancelled: the JoinError is_cancelled()",
+        ),
+    ];
+
+    fn mixed_batch() -> Vec<Job<u32>> {
+        vec![
+            Box::new(|_| Ok(1)),
+            Box::new(|_| Err("bad input".to_string())),
+            Box::new(|_| panic!("boom")),
+            Box::new(|_| panic!("value was {}", 7)),
+            Box::new(|_| std::panic::panic_any(42u8)),
+        ]
+    }
+
+    fn mixed_expected() -> Vec<Result<u32, JobError>> {
+        vec![
+            Ok(1),
+            Err(JobError::Failed("bad input".into())),
+            Err(JobError::Panicked("boom".into())),
+            Err(JobError::Panicked("value was 7".into())),
+            Err(JobError::Panicked(NON_STRING_PANIC.into())),
+        ]
+    }
+
+    /// `n` jobs that count how many of them ran.
+    fn counting_batch(n: usize) -> (Arc<AtomicUsize>, Vec<Job<usize>>) {
+        let ran = Arc::new(AtomicUsize::new(0));
+        let jobs = (0..n)
+            .map(|i| {
+                let ran = ran.clone();
+                Box::new(move |_: &CancelToken| {
+                    ran.fetch_add(1, Ordering::SeqCst);
+                    Ok(i)
+                }) as Job<usize>
+            })
+            .collect();
+        (ran, jobs)
+    }
+
+    fn cancelled() -> CancelToken {
+        let token = CancelToken::new();
+        token.cancel();
+        token
+    }
+
+    /// Jobs 0 and 2 succeed; job 1 cancels the batch and stops at its checkpoint.
+    fn cancelling_batch() -> Vec<Job<u32>> {
+        vec![
+            Box::new(|_| Ok(0)),
+            Box::new(|token| {
+                token.cancel();
+                token.checkpoint();
+                Ok(1)
+            }),
+            Box::new(|_| Ok(2)),
+        ]
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let literal = std::panic::catch_unwind(|| panic!("literal")).unwrap_err();
+        let formatted = std::panic::catch_unwind(|| panic!("n = {}", 3)).unwrap_err();
+        let other = std::panic::catch_unwind(|| std::panic::panic_any(1.5f64)).unwrap_err();
+        assert_eq_with_hint!(panic_message(&*literal), "literal");
+        assert_eq_with_hint!(panic_message(&*formatted), "n = 3");
+        assert_eq!(panic_message(&*other), NON_STRING_PANIC);
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify::<u8>(Ok(Ok(3))), Ok(3));
+        assert_eq!(
+            classify::<u8>(Ok(Err("e".into()))),
+            Err(JobError::Failed("e".into()))
+        );
+        let token = cancelled();
+        let stopped = std::panic::catch_unwind(|| token.checkpoint()).unwrap_err();
+        assert_eq_with_hint!(classify::<u8>(Err(stopped)), Err(JobError::Cancelled));
+        let panicked = std::panic::catch_unwind(|| panic!("x")).unwrap_err();
+        assert_eq!(
+            classify::<u8>(Err(panicked)),
+            Err(JobError::Panicked("x".into()))
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_passes_when_not_cancelled() {
+        let token = CancelToken::new();
+        token.checkpoint();
+        assert_eq!(
+            run_job(
+                Box::new(|t| {
+                    t.checkpoint();
+                    Ok(1)
+                }),
+                &token
+            ),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_threads_mixed_batch() {
+        let results = run_on_threads(mixed_batch(), &CancelToken::new());
+        assert_eq_with_hint!(results, mixed_expected());
+    }
+
+    #[test]
+    fn test_threads_pre_cancelled() {
+        let (ran, jobs) = counting_batch(4);
+        let results = run_on_threads(jobs, &cancelled());
+        assert_eq!(results, vec![Err(JobError::Cancelled); 4]);
+        assert_eq!(ran.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_threads_checkpoint() {
+        let job: Job<u32> = Box::new(|token| {
+            token.cancel();
+            token.checkpoint();
+            Ok(1)
+        });
+        assert_eq!(
+            run_on_threads(vec![job], &CancelToken::new()),
+            vec![Err(JobError::Cancelled)]
+        );
+    }
+
+    #[test]
+    fn test_green_mixed_batch() {
+        let results = run_on_green_threads(mixed_batch(), &CancelToken::new());
+        assert_eq_with_hint!(results, mixed_expected());
+    }
+
+    #[test]
+    fn test_green_pre_cancelled() {
+        let (ran, jobs) = counting_batch(3);
+        let results = run_on_green_threads(jobs, &cancelled());
+        assert_eq!(results, vec![Err(JobError::Cancelled); 3]);
+        assert_eq!(ran.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_green_cancel_stops_later_jobs() {
+        let results = run_on_green_threads(cancelling_batch(), &CancelToken::new());
+        assert_eq_with_hint!(
+            results,
+            vec![Ok(0), Err(JobError::Cancelled), Err(JobError::Cancelled)]
+        );
+    }
+
+    #[test]
+    fn test_green_empty_batch() {
+        assert!(run_on_green_threads::<u8>(vec![], &CancelToken::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tokio_mixed_batch() {
+        let results = run_on_tokio(mixed_batch(), &CancelToken::new()).await;
+        assert_eq_with_hint!(results, mixed_expected());
+    }
+
+    #[tokio::test]
+    async fn test_tokio_pre_cancelled() {
+        let (ran, jobs) = counting_batch(3);
+        let results = run_on_tokio(jobs, &cancelled()).await;
+        assert_eq_with_hint!(results, vec![Err(JobError::Cancelled); 3]);
+        assert_eq!(ran.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tokio_cancel_stops_later_jobs() {
+        // The current-thread runtime runs the tasks one at a time, in spawn order.
+        let results = run_on_tokio(cancelling_batch(), &CancelToken::new()).await;
+        assert_eq!(
+            results,
+            vec![Ok(0), Err(JobError::Cancelled), Err(JobError::Cancelled)]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_tokio_mixed_batch_multi_thread() {
+        let results = run_on_tokio(mixed_batch(), &CancelToken::new()).await;
+        assert_eq!(results, mixed_expected());
+    }
+}
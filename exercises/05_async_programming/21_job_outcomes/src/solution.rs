@@ -0,0 +1,225 @@
+//! # Job Outcomes Across Backends
+//!
+//! `01_thread_spawn`'s `handle_panic` turned one thread's panic into `Err(())`. A real job
+//! runner needs more: it runs a whole batch, and for every job reports *why* it did not produce
+//! a value. In this exercise, you run the same batch of jobs on three backends and classify each
+//! outcome the same way:
+//!
+//! - **OS threads**: `thread::spawn`; `join()` hands back the panic payload.
+//! - **Green threads** (`04_context_switch/02_green_threads`; finish it first): a panic must not
+//!   unwind out of the green thread's `extern "C"` entry (that aborts the process), so every job
+//!   runs inside `catch_unwind`.
+//! - **Tokio tasks**: `JoinError` says whether the task panicked (`into_panic()` gives the
+//!   payload) or was cancelled by `abort()`.
+//!
+//! ## Outcomes
+//! - `Ok(value)`: the job returned `Ok`.
+//! - `JobError::Failed(msg)`: the job returned `Err(msg)`.
+//! - `JobError::Panicked(msg)`: the job panicked. `panic!("literal")` carries a `&'static str`,
+//!   `panic!("{x}")` a `String`; anything else (`panic_any(42)`) is reported as
+//!   `NON_STRING_PANIC`.
+//! - `JobError::Cancelled`: the batch's `CancelToken` was cancelled, and either the job had not
+//!   started yet, or it stopped at a `checkpoint()`.
+//!
+//! ## Cooperative cancellation
+//! `CancelToken::checkpoint` unwinds the job with a private `Cancelled` payload through
+//! `resume_unwind` (which, unlike `panic!`, does not run the panic hook, so nothing is printed).
+//! The runner's `catch_unwind` then sees that payload and reports `Cancelled` rather than a
+//! panic. A job that never calls `checkpoint` runs to completion.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use green_threads::solution::Scheduler;
+
+/// Reported for a panic whose payload is neither `&str` nor `String`.
+pub const NON_STRING_PANIC: &str = "<non-string panic payload>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    /// The job panicked with this message.
+    Panicked(String),
+    /// The job returned `Err` with this message.
+    Failed(String),
+    /// The job was cancelled before or while running.
+    Cancelled,
+}
+
+/// A unit of work. It gets the batch's token so it can `checkpoint()` along the way.
+pub type Job<T> = Box<dyn FnOnce(&CancelToken) -> Result<T, String> + Send>;
+
+/// Payload `CancelToken::checkpoint` unwinds with.
+struct Cancelled;
+
+/// Shared cancellation flag of a batch; clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Stop the calling job here if the batch was cancelled.
+    pub fn checkpoint(&self) {
+        if self.is_cancelled() {
+            panic::resume_unwind(Box::new(Cancelled));
+        }
+    }
+}
+
+/// The message in a panic payload: the `&str` or `String` it holds, else `NON_STRING_PANIC`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        NON_STRING_PANIC.to_string()
+    }
+}
+
+/// Classify what running a job under `catch_unwind` (or `join`) produced.
+///
+/// A `Cancelled` payload means `Cancelled`; any other payload `Panicked(panic_message(..))`;
+/// otherwise the job's own `Ok` / `Err(msg)` (as `Failed(msg)`).
+pub fn classify<T>(outcome: thread::Result<Result<T, String>>) -> Result<T, JobError> {
+    match outcome {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(msg)) => Err(JobError::Failed(msg)),
+        Err(payload) if payload.is::<Cancelled>() => Err(JobError::Cancelled),
+        Err(payload) => Err(JobError::Panicked(panic_message(&*payload))),
+    }
+}
+
+/// Run `job` on the current thread: `Cancelled` without calling it if `token` is already
+/// cancelled, else the classified `catch_unwind` outcome.
+pub fn run_job<T>(job: Job<T>, token: &CancelToken) -> Result<T, JobError> {
+    if token.is_cancelled() {
+        return Err(JobError::Cancelled);
+    }
+    classify(panic::catch_unwind(AssertUnwindSafe(|| job(token))))
+}
+
+/// One OS thread per job, all started before any is joined; results in job order.
+///
+/// Each thread checks `token` before calling its job (as `run_job` does), but calls the job
+/// directly, without `catch_unwind`: `join()` returns the panic payload for `classify`.
+pub fn run_on_threads<T: Send + 'static>(
+    jobs: Vec<Job<T>>,
+    token: &CancelToken,
+) -> Vec<Result<T, JobError>> {
+    let handles: Vec<_> = jobs
+        .into_iter()
+        .map(|job| {
+            let token = token.clone();
+            thread::spawn(move || {
+                if token.is_cancelled() {
+                    return Err(JobError::Cancelled);
+                }
+                classify(Ok(job(&token)))
+            })
+        })
+        .collect();
+    handles
+        .into_iter()
+        .map(|h| h.join().unwrap_or_else(|payload| classify(Err(payload))))
+        .collect()
+}
+
+type GreenTask = Box<dyn FnOnce()>;
+
+thread_local! {
+    /// Tasks waiting for a green thread, in spawn order. Green threads only take an
+    /// `extern "C" fn()`, so this is how `green_entry` finds its work.
+    static GREEN_TASKS: RefCell<VecDeque<GreenTask>> = const { RefCell::new(VecDeque::new()) };
+}
+
+extern "C" fn green_entry() {
+    if let Some(task) = GREEN_TASKS.with(|q| q.borrow_mut().pop_front()) {
+        task();
+    }
+}
+
+/// One green thread per job on a fresh `Scheduler` on the calling OS thread; results in job
+/// order. Green threads run in spawn order, and a job runs to completion unless it yields, so
+/// when job `i` cancels the token, every job after it reports `Cancelled`.
+///
+/// Queue one task per job in `GREEN_TASKS` that stores `run_job(job, &token)` into its slot of
+/// a shared results vector, spawn one `green_entry` thread per job, then `run()`.
+pub fn run_on_green_threads<T: 'static>(
+    jobs: Vec<Job<T>>,
+    token: &CancelToken,
+) -> Vec<Result<T, JobError>> {
+    let n = jobs.len();
+    let results: Rc<RefCell<Vec<Option<Result<T, JobError>>>>> =
+        Rc::new(RefCell::new((0..n).map(|_| None).collect()));
+    let mut sched = Scheduler::new();
+    for (i, job) in jobs.into_iter().enumerate() {
+        let (results, token) = (results.clone(), token.clone());
+        GREEN_TASKS.with(|q| {
+            q.borrow_mut().push_back(Box::new(move || {
+                let outcome = run_job(job, &token);
+                results.borrow_mut()[i] = Some(outcome);
+            }))
+        });
+        sched.spawn(green_entry);
+    }
+    sched.run().expect("jobs never block each other");
+    let results = Rc::try_unwrap(results).ok().expect("every task finished");
+    results
+        .into_inner()
+        .into_iter()
+        .map(|r| r.expect("every job ran"))
+        .collect()
+}
+
+/// One tokio task per job; results in job order.
+///
+/// Each task calls `token.checkpoint()` before its job. Spawn all of them, then, if `token` is
+/// already cancelled, `abort()` every handle (a task that already started stops at its
+/// checkpoint instead). Await the handles in order: a `JoinError` that `is_cancelled()` is
+/// `Cancelled`, one that `is_panic()` goes through `classify(Err(e.into_panic()))`.
+pub async fn run_on_tokio<T: Send + 'static>(
+    jobs: Vec<Job<T>>,
+    token: &CancelToken,
+) -> Vec<Result<T, JobError>> {
+    let handles: Vec<_> = jobs
+        .into_iter()
+        .map(|job| {
+            let token = token.clone();
+            tokio::spawn(async move {
+                token.checkpoint();
+                job(&token)
+            })
+        })
+        .collect();
+    if token.is_cancelled() {
+        for h in &handles {
+            h.abort();
+        }
+    }
+    let mut results = Vec::with_capacity(handles.len());
+    for h in handles {
+        results.push(match h.await {
+            Ok(outcome) => classify(Ok(outcome)),
+            Err(e) if e.is_cancelled() => Err(JobError::Cancelled),
+            Err(e) => classify(Err(e.into_panic())),
+        });
+    }
+    results
+}
@@ -0,0 +1,317 @@
+//! # Executor Fairness
+//!
+//! An async executor is cooperative: it can only switch tasks when the running task returns
+//! `Pending` from `poll`. A task that computes for a long time without reaching an `.await` that
+//! actually suspends keeps the whole thread to itself. On a current-thread runtime that means
+//! every other task, timers included, waits until it is done.
+//!
+//! In this exercise, you run a CPU-bound *hot* task next to periodic tasks that want to wake up
+//! every `period`, measure how late their ticks are, and fix the starvation by having the hot
+//! task give the executor a chance to run the others.
+//!
+//! ## Concepts
+//! - A tick scheduled for `start + k * period` that runs more than `budget` after that instant
+//!   is a *missed deadline*
+//! - `tokio::task::yield_now()` returns `Pending` once, so the executor runs the other ready
+//!   tasks (and fires due timers) before polling the hot task again
+//! - Yielding every N units of work vs. yielding once a time slice is used up (budgeting)
+//! - Deadlines are measured from a fixed `start`, not from when a task first got polled: a task
+//!   that only starts once the hot task is done would not notice it was starved
+
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// How the hot task shares the executor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Never yield: run all the work in one poll.
+    Greedy,
+    /// `yield_now()` after every `n`th unit of work. `n` must be positive.
+    YieldEvery(u32),
+    /// `yield_now()` after the unit of work that used up the slice: at least `slice` since the
+    /// task started or last yielded.
+    TimeSlice(Duration),
+}
+
+/// The hot task's CPU-bound work: `units` units of `unit` busy time each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotWork {
+    pub units: u32,
+    pub unit: Duration,
+}
+
+/// A periodic task: `ticks` ticks, tick `k` (from 1) due at `start + k * period`. A tick that
+/// runs more than `budget` after it was due misses its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Periodic {
+    pub period: Duration,
+    pub ticks: u32,
+    pub budget: Duration,
+}
+
+/// What one periodic task saw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeadlineReport {
+    pub ticks: u32,
+    /// Ticks that ran more than `budget` late.
+    pub missed: u32,
+    /// Lateness of the latest tick.
+    pub max_lateness: Duration,
+}
+
+/// Outcome of `measure`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairnessReport {
+    /// One report per periodic task, in the order they were given.
+    pub periodic: Vec<DeadlineReport>,
+    /// How many times the hot task yielded.
+    pub yields: u32,
+}
+
+impl FairnessReport {
+    /// Missed deadlines over all periodic tasks.
+    pub fn missed(&self) -> u32 {
+        // TODO: Sum `missed` over `self.periodic`
+        todo!()
+    }
+
+    /// The worst lateness over all periodic tasks.
+    pub fn max_lateness(&self) -> Duration {
+        // TODO: The largest `max_lateness` in `self.periodic` (zero if there is none)
+        todo!()
+    }
+}
+
+/// Busy-wait for `d` without ever giving up the thread, like a real computation would.
+pub fn spin_for(d: Duration) {
+    let start = std::time::Instant::now();
+    while start.elapsed() < d {
+        std::hint::spin_loop();
+    }
+}
+
+/// Do `work` (`spin_for(work.unit)` per unit), sharing the executor as `strategy` says.
+/// Returns how many times it yielded.
+pub async fn hot_task(work: HotWork, strategy: Strategy) -> u32 {
+    // TODO: Panic if strategy is YieldEvery(0)
+    // TODO: For i in 1..=work.units: spin_for(work.unit), then decide whether to yield:
+    //       Greedy never; YieldEvery(n) when i is a multiple of n;
+    //       TimeSlice(slice) when at least `slice` passed since the start / the last yield
+    // TODO: To yield: tokio::task::yield_now().await, count it, restart the slice
+    todo!()
+}
+
+/// Run the ticks of `cfg` against the schedule that began at `start`: for each tick, sleep
+/// until it is due, then record how late it actually ran.
+pub async fn periodic_task(cfg: Periodic, start: Instant) -> DeadlineReport {
+    // TODO: For k in 1..=cfg.ticks: due = start + cfg.period * k; sleep_until(due).await
+    // TODO: lateness = Instant::now() - due; count the tick, count it as missed if
+    //       lateness > cfg.budget, and keep the largest lateness
+    todo!()
+}
+
+/// Run `work` as a hot task with `strategy` alongside one task per entry of `periodic`, on a
+/// fresh current-thread runtime, and report what the periodic tasks saw.
+///
+/// All periodic schedules start at the same `Instant`, taken before anything is spawned. The
+/// hot task is spawned first.
+pub fn measure(strategy: Strategy, work: HotWork, periodic: &[Periodic]) -> FairnessReport {
+    // TODO: Build a current-thread runtime with the time driver enabled
+    // TODO: In block_on: take start = Instant::now(), spawn hot_task(work, strategy), then one
+    //       periodic_task(cfg, start) per entry; await the periodic handles in order, then the
+    //       hot one
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::{Mutex, PoisonError};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    const HINTS: Hints = &[
+        (
+            "test_hot_task_yield_every",
+            "yield after unit i when i % n == 0, and return how many times you yielded",
+        ),
+        (
+            "test_periodic_task_lateness",
+            "tick k is due at start + period * k; it is late by Instant::now() - due, and missed only if that is more than the budget",
+        ),
+        (
+            "test_greedy_starves_periodic",
+            "spawn the hot task first and measure every deadline from the start taken before spawning: the periodic tasks only get polled once the hot task is done",
+        ),
+        (
+            "test_yield_every_meets_deadlines",
+            "tokio::task::yield_now().await lets the runtime fire due timers and run the periodic tasks before the hot task continues",
+        ),
+        (
+            "test_time_slice_meets_deadlines",
+            "restart the slice after every yield, or the hot task yields after every unit once the first slice is used up",
+        ),
+    ];
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// 300ms of work in 1ms units.
+    const WORK: HotWork = HotWork {
+        units: 300,
+        unit: MS,
+    };
+
+    /// A fast and a slow ticker, both done after 200ms and allowed to run up to 15ms late.
+    const TICKERS: [Periodic; 2] = [
+        Periodic {
+            period: Duration::from_millis(20),
+            ticks: 10,
+            budget: Duration::from_millis(15),
+        },
+        Periodic {
+            period: Duration::from_millis(50),
+            ticks: 4,
+            budget: Duration::from_millis(15),
+        },
+    ];
+
+    /// Tests that measure real time must not share the CPU with each other.
+    static TIMING_LOCK: Mutex<()> = Mutex::new(());
+
+    fn alone<R>(test: impl FnOnce() -> R) -> R {
+        let _guard = TIMING_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        test()
+    }
+
+    #[tokio::test]
+    async fn test_hot_task_greedy() {
+        let work = HotWork {
+            units: 10,
+            unit: Duration::ZERO,
+        };
+        assert_eq!(hot_task(work, Strategy::Greedy).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_hot_task_yield_every() {
+        let work = HotWork {
+            units: 10,
+            unit: Duration::ZERO,
+        };
+        assert_eq_with_hint!(hot_task(work, Strategy::YieldEvery(4)).await, 2);
+        assert_eq_with_hint!(hot_task(work, Strategy::YieldEvery(1)).await, 10);
+    }
+
+    #[tokio::test]
+    #[should_panic]
+    async fn test_hot_task_yield_every_zero() {
+        let work = HotWork {
+            units: 1,
+            unit: Duration::ZERO,
+        };
+        hot_task(work, Strategy::YieldEvery(0)).await;
+    }
+
+    #[test]
+    fn test_hot_task_time_slice() {
+        // A 25ms slice is used up by the 3rd 10ms unit.
+        let work = HotWork {
+            units: 9,
+            unit: MS * 10,
+        };
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let yields = alone(|| rt.block_on(hot_task(work, Strategy::TimeSlice(MS * 25))));
+        assert_eq!(yields, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_periodic_task_lateness() {
+        // The schedule began 25ms ago: ticks due at -15ms and -5ms run late right away.
+        let start = Instant::now() - MS * 25;
+        let cfg = Periodic {
+            period: MS * 10,
+            ticks: 5,
+            budget: MS * 5,
+        };
+        let report = periodic_task(cfg, start).await;
+        assert_eq_with_hint!(
+            report,
+            DeadlineReport {
+                ticks: 5,
+                missed: 1,
+                max_lateness: MS * 15,
+            }
+        );
+        assert_eq!(Instant::now() - start, MS * 50);
+    }
+
+    #[test]
+    fn test_report_totals() {
+        let report = FairnessReport {
+            periodic: vec![
+                DeadlineReport {
+                    ticks: 3,
+                    missed: 1,
+                    max_lateness: MS * 7,
+                },
+                DeadlineReport {
+                    ticks: 2,
+                    missed: 2,
+                    max_lateness: MS * 9,
+                },
+            ],
+            yields: 0,
+        };
+        assert_eq!(report.missed(), 3);
+        assert_eq!(report.max_lateness(), MS * 9);
+        let empty = FairnessReport {
+            periodic: vec![],
+            yields: 0,
+        };
+        assert_eq!(empty.max_lateness(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_periodic_alone_meets_deadlines() {
+        let idle = HotWork { units: 0, unit: MS };
+        let report = alone(|| measure(Strategy::Greedy, idle, &TICKERS));
+        assert_eq!(report.periodic.len(), 2);
+        assert_eq!(report.periodic[0].ticks, 10);
+        assert_eq!(report.periodic[1].ticks, 4);
+        assert_eq!(report.missed(), 0);
+    }
+
+    #[test]
+    fn test_greedy_starves_periodic() {
+        let report = alone(|| measure(Strategy::Greedy, WORK, &TICKERS));
+        assert_eq!(report.yields, 0);
+        // Every tick was due before the hot task finished.
+        assert_eq_with_hint!(report.missed(), 14);
+        assert_with_hint!(report.max_lateness() >= MS * 250);
+    }
+
+    #[test]
+    fn test_yield_every_meets_deadlines() {
+        let report = alone(|| measure(Strategy::YieldEvery(2), WORK, &TICKERS));
+        assert_eq_with_hint!(report.yields, 150);
+        assert_eq_with_hint!(report.missed(), 0);
+    }
+
+    #[test]
+    fn test_time_slice_meets_deadlines() {
+        let report = alone(|| measure(Strategy::TimeSlice(MS * 2), WORK, &TICKERS));
+        assert_with_hint!(report.yields > 0 && report.yields <= WORK.units);
+        assert_eq_with_hint!(report.missed(), 0);
+    }
+}
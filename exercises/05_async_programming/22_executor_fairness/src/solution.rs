@@ -0,0 +1,162 @@
+//! # Executor Fairness
+//!
+//! An async executor is cooperative: it can only switch tasks when the running task returns
+//! `Pending` from `poll`. A task that computes for a long time without reaching an `.await` that
+//! actually suspends keeps the whole thread to itself. On a current-thread runtime that means
+//! every other task, timers included, waits until it is done.
+//!
+//! In this exercise, you run a CPU-bound *hot* task next to periodic tasks that want to wake up
+//! every `period`, measure how late their ticks are, and fix the starvation by having the hot
+//! task give the executor a chance to run the others.
+//!
+//! ## Concepts
+//! - A tick scheduled for `start + k * period` that runs more than `budget` after that instant
+//!   is a *missed deadline*
+//! - `tokio::task::yield_now()` returns `Pending` once, so the executor runs the other ready
+//!   tasks (and fires due timers) before polling the hot task again
+//! - Yielding every N units of work vs. yielding once a time slice is used up (budgeting)
+//! - Deadlines are measured from a fixed `start`, not from when a task first got polled: a task
+//!   that only starts once the hot task is done would not notice it was starved
+
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// How the hot task shares the executor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Never yield: run all the work in one poll.
+    Greedy,
+    /// `yield_now()` after every `n`th unit of work. `n` must be positive.
+    YieldEvery(u32),
+    /// `yield_now()` after the unit of work that used up the slice: at least `slice` since the
+    /// task started or last yielded.
+    TimeSlice(Duration),
+}
+
+/// The hot task's CPU-bound work: `units` units of `unit` busy time each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotWork {
+    pub units: u32,
+    pub unit: Duration,
+}
+
+/// A periodic task: `ticks` ticks, tick `k` (from 1) due at `start + k * period`. A tick that
+/// runs more than `budget` after it was due misses its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Periodic {
+    pub period: Duration,
+    pub ticks: u32,
+    pub budget: Duration,
+}
+
+/// What one periodic task saw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeadlineReport {
+    pub ticks: u32,
+    /// Ticks that ran more than `budget` late.
+    pub missed: u32,
+    /// Lateness of the latest tick.
+    pub max_lateness: Duration,
+}
+
+/// Outcome of `measure`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairnessReport {
+    /// One report per periodic task, in the order they were given.
+    pub periodic: Vec<DeadlineReport>,
+    /// How many times the hot task yielded.
+    pub yields: u32,
+}
+
+impl FairnessReport {
+    /// Missed deadlines over all periodic tasks.
+    pub fn missed(&self) -> u32 {
+        self.periodic.iter().map(|r| r.missed).sum()
+    }
+
+    /// The worst lateness over all periodic tasks.
+    pub fn max_lateness(&self) -> Duration {
+        self.periodic
+            .iter()
+            .map(|r| r.max_lateness)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Busy-wait for `d` without ever giving up the thread, like a real computation would.
+pub fn spin_for(d: Duration) {
+    let start = std::time::Instant::now();
+    while start.elapsed() < d {
+        std::hint::spin_loop();
+    }
+}
+
+/// Do `work` (`spin_for(work.unit)` per unit), sharing the executor as `strategy` says.
+/// Returns how many times it yielded.
+pub async fn hot_task(work: HotWork, strategy: Strategy) -> u32 {
+    if let Strategy::YieldEvery(n) = strategy {
+        assert!(n > 0, "YieldEvery needs a positive unit count");
+    }
+    let mut yields = 0;
+    let mut slice_start = Instant::now();
+    for i in 1..=work.units {
+        spin_for(work.unit);
+        let yield_now = match strategy {
+            Strategy::Greedy => false,
+            Strategy::YieldEvery(n) => i.is_multiple_of(n),
+            Strategy::TimeSlice(slice) => slice_start.elapsed() >= slice,
+        };
+        if yield_now {
+            tokio::task::yield_now().await;
+            yields += 1;
+            slice_start = Instant::now();
+        }
+    }
+    yields
+}
+
+/// Run the ticks of `cfg` against the schedule that began at `start`: for each tick, sleep
+/// until it is due, then record how late it actually ran.
+pub async fn periodic_task(cfg: Periodic, start: Instant) -> DeadlineReport {
+    let mut report = DeadlineReport::default();
+    for k in 1..=cfg.ticks {
+        let due = start + cfg.period * k;
+        sleep_until(due).await;
+        let lateness = Instant::now() - due;
+        report.ticks += 1;
+        if lateness > cfg.budget {
+            report.missed += 1;
+        }
+        report.max_lateness = report.max_lateness.max(lateness);
+    }
+    report
+}
+
+/// Run `work` as a hot task with `strategy` alongside one task per entry of `periodic`, on a
+/// fresh current-thread runtime, and report what the periodic tasks saw.
+///
+/// All periodic schedules start at the same `Instant`, taken before anything is spawned. The
+/// hot task is spawned first.
+pub fn measure(strategy: Strategy, work: HotWork, periodic: &[Periodic]) -> FairnessReport {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let start = Instant::now();
+        let hot = tokio::spawn(hot_task(work, strategy));
+        let tasks: Vec<_> = periodic
+            .iter()
+            .map(|&cfg| tokio::spawn(periodic_task(cfg, start)))
+            .collect();
+        let mut reports = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            reports.push(task.await.unwrap());
+        }
+        FairnessReport {
+            periodic: reports,
+            yields: hot.await.unwrap(),
+        }
+    })
+}
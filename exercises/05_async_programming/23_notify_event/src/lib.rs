@@ -0,0 +1,364 @@
+//! # Notify: Events and Condvar-Style Queues
+//!
+//! `tokio::sync::Notify` is the wakeup primitive under many of tokio's own sync types: it holds
+//! no data, it only wakes tasks. In this exercise, you build two things on it: a one-shot
+//! `Event` (a latch) and a bounded queue that waits the way a `Condvar` would.
+//!
+//! ## Notify semantics
+//! - `notify_waiters()` wakes every task waiting *right now*, and stores nothing: a task that
+//!   starts waiting afterwards waits for the next call.
+//! - `notify_one()` wakes one waiting task; if none is waiting it stores a single permit, and
+//!   the next `notified().await` completes at once. Permits do not add up.
+//! - A `Notified` future counts as waiting for `notify_waiters()` from the moment it is
+//!   *created*, even before its first poll. Create it before checking the condition and
+//!   nothing can slip in between the check and the `.await`.
+//!
+//! ## Condvar-style waiting
+//! As with a `Condvar`, a wakeup only means "go and look again": the state lives behind a
+//! mutex, the waiter re-checks its condition in a loop, and never holds the lock across the
+//! `.await`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// A one-shot latch: once `set`, every current and future `wait` returns at once.
+#[derive(Debug, Default)]
+pub struct Event {
+    set: AtomicBool,
+    notify: Notify,
+}
+
+impl Event {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_set(&self) -> bool {
+        // TODO: Load the flag
+        todo!()
+    }
+
+    /// Set the latch and wake every waiter. Setting it again does nothing.
+    pub fn set(&self) {
+        // TODO: Store the flag first, then notify_waiters() so every waiter re-checks it
+        todo!()
+    }
+
+    /// Wait until the latch is set; returns at once if it already is.
+    pub async fn wait(&self) {
+        // TODO: Loop: create `self.notify.notified()` *before* checking is_set(); return if set,
+        //       else await it. notify_waiters() stores no permit, so a Notified created after the
+        //       check could miss a set() that happened in between
+        todo!()
+    }
+}
+
+/// A bounded FIFO queue for many producers and many consumers: `push` waits while it is full,
+/// `pop` while it is empty.
+#[derive(Debug)]
+pub struct BoundedQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    capacity: usize,
+    /// Signalled once per pushed item.
+    not_empty: Notify,
+    /// Signalled once per popped item.
+    not_full: Notify,
+}
+
+impl<T> BoundedQueue<T> {
+    /// Panics if `capacity == 0`.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            not_empty: Notify::new(),
+            not_full: Notify::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Push `value` if there is room, else hand it back.
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        // TODO: Under the lock: Err(value) if full, else push_back
+        // TODO: Release the lock, then not_empty.notify_one()
+        todo!()
+    }
+
+    /// Pop the oldest item, if any.
+    pub fn try_pop(&self) -> Option<T> {
+        // TODO: pop_front under the lock; if you got an item, not_full.notify_one()
+        todo!()
+    }
+
+    /// Push `value`, waiting for room.
+    pub async fn push(&self, mut value: T) {
+        // TODO: Loop: try_push; on Err take the value back and await not_full.notified()
+        // (notify_one stores a permit, so a pop between try_push and the await is not lost)
+        todo!()
+    }
+
+    /// Pop the oldest item, waiting for one.
+    pub async fn pop(&self) -> T {
+        // TODO: Loop: try_pop, else await not_empty.notified()
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::task::yield_now;
+    use tokio::time::timeout;
+
+    const HINTS: Hints = &[
+        (
+            "test_event_set_before_wait",
+            "notify_waiters() stores no permit: wait() must check the flag, and only await when it is not set yet",
+        ),
+        (
+            "test_event_wakes_all_waiters",
+            "set() must use notify_waiters(): notify_one() would wake only one of them",
+        ),
+        (
+            "test_event_no_lost_wakeup",
+            "create the Notified future before checking is_set(): a set() between the check and notified() would be missed",
+        ),
+        (
+            "test_pop_waits_for_push",
+            "pop on an empty queue awaits not_empty.notified(), and try_push signals not_empty after pushing",
+        ),
+        (
+            "test_push_waits_for_room",
+            "push on a full queue awaits not_full.notified(), and try_pop signals not_full after popping",
+        ),
+        (
+            "test_many_producers_and_consumers",
+            "use notify_one() for the queue: its permit covers a push or pop that happens between the failed try_* and the await",
+        ),
+        (
+            "test_cancelled_pop_passes_wakeup_on",
+            "wait on notified() directly: a dropped Notified that was picked by notify_one() hands the wakeup to the next waiter",
+        ),
+    ];
+
+    /// Longest any wait in these tests may take before it counts as hung.
+    const HANG: Duration = Duration::from_secs(10);
+
+    /// Let the other tasks of a current-thread runtime run until they block.
+    async fn settle() {
+        for _ in 0..10 {
+            yield_now().await;
+        }
+    }
+
+    // ---- Event ----
+
+    #[tokio::test]
+    async fn test_event_set_before_wait() {
+        let ev = Event::new();
+        assert!(!ev.is_set());
+        ev.set();
+        assert!(ev.is_set());
+        assert_with_hint!(timeout(HANG, ev.wait()).await.is_ok());
+        // Still set: waiting again returns at once, too.
+        assert_with_hint!(timeout(HANG, ev.wait()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_event_wakes_all_waiters() {
+        let ev = Arc::new(Event::new());
+        let woken = Arc::new(AtomicUsize::new(0));
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let (ev, woken) = (ev.clone(), woken.clone());
+                tokio::spawn(async move {
+                    ev.wait().await;
+                    woken.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        settle().await;
+        assert_eq!(woken.load(Ordering::SeqCst), 0, "waiters ran before set()");
+
+        ev.set();
+        let all_done = async {
+            for w in waiters {
+                w.await.unwrap();
+            }
+        };
+        let _ = timeout(HANG, all_done).await;
+        assert_eq_with_hint!(woken.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_event_no_lost_wakeup() {
+        for _ in 0..500 {
+            let ev = Arc::new(Event::new());
+            let setter = {
+                let ev = ev.clone();
+                tokio::spawn(async move { ev.set() })
+            };
+            assert_with_hint!(timeout(HANG, ev.wait()).await.is_ok());
+            setter.await.unwrap();
+        }
+    }
+
+    // ---- BoundedQueue ----
+
+    #[test]
+    #[should_panic]
+    fn test_zero_capacity_panics() {
+        let _ = BoundedQueue::<u8>::new(0);
+    }
+
+    #[test]
+    fn test_try_push_try_pop() {
+        let q = BoundedQueue::new(2);
+        assert_eq!(q.capacity(), 2);
+        assert!(q.is_empty());
+        assert_eq!(q.try_push(1), Ok(()));
+        assert_eq!(q.try_push(2), Ok(()));
+        assert_eq!(q.try_push(3), Err(3));
+        assert_eq!(q.len(), 2);
+        assert_eq!(q.try_pop(), Some(1));
+        assert_eq!(q.try_push(3), Ok(()));
+        assert_eq!(q.try_pop(), Some(2));
+        assert_eq!(q.try_pop(), Some(3));
+        assert_eq!(q.try_pop(), None);
+    }
+
+    #[tokio::test]
+    async fn test_pop_waits_for_push() {
+        let q = Arc::new(BoundedQueue::new(1));
+        let consumer = {
+            let q = q.clone();
+            tokio::spawn(async move { q.pop().await })
+        };
+        settle().await;
+        assert!(!consumer.is_finished(), "pop returned from an empty queue");
+
+        q.push(7).await;
+        let got = timeout(HANG, consumer).await.expect("pop hung").unwrap();
+        assert_eq_with_hint!(got, 7);
+    }
+
+    #[tokio::test]
+    async fn test_push_waits_for_room() {
+        let q = Arc::new(BoundedQueue::new(1));
+        q.push(1).await;
+        let producer = {
+            let q = q.clone();
+            tokio::spawn(async move { q.push(2).await })
+        };
+        settle().await;
+        assert!(!producer.is_finished(), "push returned on a full queue");
+        assert_eq!(q.len(), 1);
+
+        assert_eq!(q.pop().await, 1);
+        timeout(HANG, producer).await.expect("push hung").unwrap();
+        assert_eq_with_hint!(q.try_pop(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_fifo_order() {
+        let q = Arc::new(BoundedQueue::new(3));
+        let producer = {
+            let q = q.clone();
+            tokio::spawn(async move {
+                for i in 0..100 {
+                    q.push(i).await;
+                }
+            })
+        };
+        let mut got = Vec::new();
+        for _ in 0..100 {
+            got.push(timeout(HANG, q.pop()).await.expect("pop hung"));
+        }
+        producer.await.unwrap();
+        assert_eq!(got, (0..100).collect::<Vec<_>>());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_many_producers_and_consumers() {
+        let q = Arc::new(BoundedQueue::new(4));
+        let producers: Vec<_> = (0..4)
+            .map(|p| {
+                let q = q.clone();
+                tokio::spawn(async move {
+                    for i in 0..250 {
+                        q.push(p * 250 + i).await;
+                    }
+                })
+            })
+            .collect();
+        let consumers: Vec<_> = [334, 333, 333]
+            .into_iter()
+            .map(|n| {
+                let q = q.clone();
+                tokio::spawn(async move {
+                    let mut got = Vec::new();
+                    for _ in 0..n {
+                        got.push(q.pop().await);
+                    }
+                    got
+                })
+            })
+            .collect();
+
+        let mut all = Vec::new();
+        for c in consumers {
+            all.extend(timeout(HANG, c).await.expect("consumer hung").unwrap());
+        }
+        for p in producers {
+            timeout(HANG, p).await.expect("producer hung").unwrap();
+        }
+        all.sort_unstable();
+        assert_eq_with_hint!(all, (0..1000).collect::<Vec<_>>());
+        assert!(q.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_pop_passes_wakeup_on() {
+        let q = Arc::new(BoundedQueue::new(1));
+        let spawn_pop = || {
+            let q = q.clone();
+            tokio::spawn(async move { q.pop().await })
+        };
+        let (first, second) = (spawn_pop(), spawn_pop());
+        settle().await;
+
+        // The push wakes `first`, which is cancelled before it gets to run.
+        q.try_push(5).unwrap();
+        first.abort();
+        assert!(first.await.unwrap_err().is_cancelled());
+
+        let got = timeout(HANG, second).await;
+        assert_with_hint!(got.is_ok(), "the item is stuck in the queue");
+        assert_eq!(got.unwrap().unwrap(), 5);
+    }
+}
@@ -0,0 +1,135 @@
+//! # Notify: Events and Condvar-Style Queues
+//!
+//! `tokio::sync::Notify` is the wakeup primitive under many of tokio's own sync types: it holds
+//! no data, it only wakes tasks. In this exercise, you build two things on it: a one-shot
+//! `Event` (a latch) and a bounded queue that waits the way a `Condvar` would.
+//!
+//! ## Notify semantics
+//! - `notify_waiters()` wakes every task waiting *right now*, and stores nothing: a task that
+//!   starts waiting afterwards waits for the next call.
+//! - `notify_one()` wakes one waiting task; if none is waiting it stores a single permit, and
+//!   the next `notified().await` completes at once. Permits do not add up.
+//! - A `Notified` future counts as waiting for `notify_waiters()` from the moment it is
+//!   *created*, even before its first poll. Create it before checking the condition and
+//!   nothing can slip in between the check and the `.await`.
+//!
+//! ## Condvar-style waiting
+//! As with a `Condvar`, a wakeup only means "go and look again": the state lives behind a
+//! mutex, the waiter re-checks its condition in a loop, and never holds the lock across the
+//! `.await`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// A one-shot latch: once `set`, every current and future `wait` returns at once.
+#[derive(Debug, Default)]
+pub struct Event {
+    set: AtomicBool,
+    notify: Notify,
+}
+
+impl Event {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.set.load(Ordering::SeqCst)
+    }
+
+    /// Set the latch and wake every waiter. Setting it again does nothing.
+    pub fn set(&self) {
+        self.set.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Wait until the latch is set; returns at once if it already is.
+    pub async fn wait(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_set() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// A bounded FIFO queue for many producers and many consumers: `push` waits while it is full,
+/// `pop` while it is empty.
+#[derive(Debug)]
+pub struct BoundedQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    capacity: usize,
+    /// Signalled once per pushed item.
+    not_empty: Notify,
+    /// Signalled once per popped item.
+    not_full: Notify,
+}
+
+impl<T> BoundedQueue<T> {
+    /// Panics if `capacity == 0`.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            not_empty: Notify::new(),
+            not_full: Notify::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Push `value` if there is room, else hand it back.
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let mut items = self.items.lock().unwrap();
+        if items.len() == self.capacity {
+            return Err(value);
+        }
+        items.push_back(value);
+        drop(items);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Pop the oldest item, if any.
+    pub fn try_pop(&self) -> Option<T> {
+        let value = self.items.lock().unwrap().pop_front()?;
+        self.not_full.notify_one();
+        Some(value)
+    }
+
+    /// Push `value`, waiting for room.
+    pub async fn push(&self, mut value: T) {
+        loop {
+            match self.try_push(value) {
+                Ok(()) => return,
+                Err(v) => value = v,
+            }
+            self.not_full.notified().await;
+        }
+    }
+
+    /// Pop the oldest item, waiting for one.
+    pub async fn pop(&self) -> T {
+        loop {
+            if let Some(value) = self.try_pop() {
+                return value;
+            }
+            self.not_empty.notified().await;
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! # Bounded Worker Pool
+//!
+//! `02_tokio_tasks` bounded concurrency with a semaphore, still one task per job. A worker pool
+//! turns it around: a fixed set of long-lived worker tasks pull jobs from a shared queue, so at
+//! most `n_workers` jobs run at once however many are submitted.
+//!
+//! ## Design
+//! ```text
+//!   submit(job) ──▶ mpsc queue ──▶ worker 0 ─┐
+//!        │                    ├──▶ worker 1 ─┼─▶ job.await, result into the job's oneshot
+//!        ▼                    └──▶ worker 2 ─┘
+//!   oneshot::Receiver<Result<T, PoolError>>
+//! ```
+//! - The workers share the queue's receiver behind a `tokio::sync::Mutex`: the worker holding
+//!   the lock waits in `recv()`, the others wait for the lock.
+//! - Every job carries the `oneshot::Sender` for its result, so the queue only moves
+//!   type-erased `Task`s.
+//! - Shutdown drops the queue's sender. The workers still drain every job already queued, and
+//!   only then does `recv()` return `None` and they exit. Jobs submitted after that are
+//!   rejected with `PoolError::ShutDown` instead of being queued.
+//!
+//! Jobs must not panic: a panic ends the worker task running it.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    /// The job was submitted after `shutdown` and never ran.
+    ShutDown,
+}
+
+/// A job with its result channel, type-erased.
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+pub struct WorkerPool {
+    /// `None` once shut down.
+    queue: Mutex<Option<mpsc::UnboundedSender<Task>>>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl WorkerPool {
+    /// Start `n_workers` worker tasks on the current runtime. Panics if `n_workers == 0`.
+    pub fn new(n_workers: usize) -> Self {
+        // TODO: Panic if n_workers == 0
+        // TODO: Create an unbounded mpsc channel of Tasks; wrap the receiver in
+        //       Arc<tokio::sync::Mutex<..>> and spawn n_workers `worker` tasks sharing it
+        todo!()
+    }
+
+    /// Queue `job`; its output arrives on the returned receiver. After `shutdown` the job is
+    /// dropped unrun and the receiver gets `Err(PoolError::ShutDown)`.
+    pub fn submit<F, T>(&self, job: F) -> oneshot::Receiver<Result<T, PoolError>>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        // TODO: Create a oneshot channel for the result
+        // TODO: If the pool is running, queue a Task (Box::pin of an async block) that awaits
+        //       `job` and sends Ok(output); otherwise send Err(PoolError::ShutDown) right away
+        todo!()
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.queue.lock().unwrap().is_none()
+    }
+
+    /// Stop accepting jobs, then wait until the workers have run every queued job and exited.
+    /// Calling it again returns at once.
+    pub async fn shutdown(&self) {
+        // TODO: Take the sender out of `queue` (dropping it closes the channel once it is drained)
+        // TODO: Take the worker handles and await each of them
+        todo!()
+    }
+}
+
+/// One worker: run queued tasks one at a time until the queue is closed and empty.
+async fn worker(rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<Task>>>) {
+    // TODO: Loop: lock the receiver and recv() the next task; release the lock, then run it
+    // TODO: Return once recv() gives None
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::time::{sleep, Duration, Instant};
+
+    const HINTS: Hints = &[
+        (
+            "test_results_come_back",
+            "each Task sends Ok(job.await) into the job's own oneshot::Sender",
+        ),
+        (
+            "test_at_most_n_jobs_at_once",
+            "a worker must finish its job before taking the next one: release the receiver lock, then await the task in the worker itself",
+        ),
+        (
+            "test_shutdown_drains_queue",
+            "shutdown only drops the sender: the workers keep taking queued jobs until recv() returns None, and shutdown awaits them",
+        ),
+        (
+            "test_submit_after_shutdown_is_rejected",
+            "once the sender was taken, submit answers Err(PoolError::ShutDown) on the oneshot instead of queueing",
+        ),
+        (
+            "test_idle_workers_do_not_block_each_other",
+            "do not hold the receiver lock while running a task, or only one worker ever runs",
+        ),
+    ];
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// Jobs that record how many of them run at the same time.
+    #[derive(Default)]
+    struct InFlight {
+        now: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    impl InFlight {
+        async fn job(self: Arc<Self>, i: usize) -> usize {
+            let now = self.now.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(now, Ordering::SeqCst);
+            sleep(10 * MS).await;
+            self.now.fetch_sub(1, Ordering::SeqCst);
+            i * i
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_workers_panics() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            WorkerPool::new(0);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_results_come_back() {
+        let pool = WorkerPool::new(2);
+        let answer = pool.submit(async { 6 * 7 });
+        let text = pool.submit(async { "done".to_string() });
+        assert_eq_with_hint!(answer.await.unwrap(), Ok(42));
+        assert_eq_with_hint!(text.await.unwrap(), Ok("done".to_string()));
+        pool.shutdown().await;
+    }
+
+    // The clock is paused: it only moves when every task waits on a timer, so elapsed times
+    // below are exact.
+
+    #[tokio::test(start_paused = true)]
+    async fn test_at_most_n_jobs_at_once() {
+        let pool = WorkerPool::new(3);
+        let f = Arc::new(InFlight::default());
+        let start = Instant::now();
+        let results: Vec<_> = (0..10).map(|i| pool.submit(f.clone().job(i))).collect();
+        for (i, rx) in results.into_iter().enumerate() {
+            assert_eq_with_hint!(rx.await.unwrap(), Ok(i * i));
+        }
+        assert_eq_with_hint!(f.max.load(Ordering::SeqCst), 3, "at most 3 jobs at once");
+        // Rounds of 3, 3, 3 and 1 jobs.
+        assert_eq_with_hint!(start.elapsed(), 40 * MS);
+        pool.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_single_worker_runs_jobs_in_order() {
+        let pool = WorkerPool::new(1);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let results: Vec<_> = (0..5)
+            .map(|i| {
+                let log = log.clone();
+                pool.submit(async move {
+                    // Later jobs are shorter: only the queue keeps them in order.
+                    sleep((5 - i) * MS).await;
+                    log.lock().unwrap().push(i);
+                })
+            })
+            .collect();
+        for rx in results {
+            rx.await.unwrap().unwrap();
+        }
+        assert_eq!(*log.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+        pool.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_drains_queue() {
+        let pool = WorkerPool::new(2);
+        let done = Arc::new(AtomicUsize::new(0));
+        let results: Vec<_> = (0..6)
+            .map(|i| {
+                let done = done.clone();
+                pool.submit(async move {
+                    sleep(10 * MS).await;
+                    done.fetch_add(1, Ordering::SeqCst);
+                    i
+                })
+            })
+            .collect();
+        let start = Instant::now();
+        pool.shutdown().await;
+        assert_with_hint!(pool.is_shut_down());
+        assert_eq_with_hint!(done.load(Ordering::SeqCst), 6, "queued jobs still run");
+        assert_eq_with_hint!(start.elapsed(), 30 * MS);
+        for (i, rx) in results.into_iter().enumerate() {
+            assert_eq!(rx.await.unwrap(), Ok(i));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_waits_for_running_job() {
+        let pool = WorkerPool::new(1);
+        let rx = pool.submit(async {
+            sleep(Duration::from_secs(5)).await;
+            "slow"
+        });
+        sleep(MS).await;
+        let start = Instant::now();
+        pool.shutdown().await;
+        assert_eq!(start.elapsed(), Duration::from_secs(5) - MS);
+        assert_eq!(rx.await.unwrap(), Ok("slow"));
+    }
+
+    #[tokio::test]
+    async fn test_submit_after_shutdown_is_rejected() {
+        let pool = WorkerPool::new(2);
+        assert!(!pool.is_shut_down());
+        pool.shutdown().await;
+        let ran = Arc::new(AtomicUsize::new(0));
+        let r = ran.clone();
+        let rx = pool.submit(async move {
+            r.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq_with_hint!(rx.await.unwrap(), Err(PoolError::ShutDown));
+        assert_eq!(ran.load(Ordering::SeqCst), 0, "a rejected job must not run");
+        // A second shutdown has nothing left to do.
+        pool.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_workers_do_not_block_each_other() {
+        let pool = WorkerPool::new(2);
+        // One long job; a second worker must still serve the short ones.
+        let long = pool.submit(sleep(Duration::from_secs(60)));
+        let start = Instant::now();
+        for i in 0..3 {
+            let rx = pool.submit(async move {
+                sleep(MS).await;
+                i
+            });
+            assert_eq!(rx.await.unwrap(), Ok(i));
+        }
+        assert_eq_with_hint!(start.elapsed(), 3 * MS);
+        assert_with_hint!(!pool.is_shut_down());
+        drop(long);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_many_jobs_multi_thread() {
+        let pool = Arc::new(WorkerPool::new(4));
+        let results: Vec<_> = (0..200u64)
+            .map(|i| pool.submit(async move { i * 2 }))
+            .collect();
+        let mut sum = 0;
+        for rx in results {
+            sum += rx.await.unwrap().unwrap();
+        }
+        assert_eq!(sum, (0..200u64).map(|i| i * 2).sum());
+        pool.shutdown().await;
+    }
+}
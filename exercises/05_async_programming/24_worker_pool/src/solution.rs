@@ -0,0 +1,107 @@
+//! # Bounded Worker Pool
+//!
+//! `02_tokio_tasks` bounded concurrency with a semaphore, still one task per job. A worker pool
+//! turns it around: a fixed set of long-lived worker tasks pull jobs from a shared queue, so at
+//! most `n_workers` jobs run at once however many are submitted.
+//!
+//! ## Design
+//! ```text
+//!   submit(job) ──▶ mpsc queue ──▶ worker 0 ─┐
+//!        │                    ├──▶ worker 1 ─┼─▶ job.await, result into the job's oneshot
+//!        ▼                    └──▶ worker 2 ─┘
+//!   oneshot::Receiver<Result<T, PoolError>>
+//! ```
+//! - The workers share the queue's receiver behind a `tokio::sync::Mutex`: the worker holding
+//!   the lock waits in `recv()`, the others wait for the lock.
+//! - Every job carries the `oneshot::Sender` for its result, so the queue only moves
+//!   type-erased `Task`s.
+//! - Shutdown drops the queue's sender. The workers still drain every job already queued, and
+//!   only then does `recv()` return `None` and they exit. Jobs submitted after that are
+//!   rejected with `PoolError::ShutDown` instead of being queued.
+//!
+//! Jobs must not panic: a panic ends the worker task running it.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolError {
+    /// The job was submitted after `shutdown` and never ran.
+    ShutDown,
+}
+
+/// A job with its result channel, type-erased.
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+pub struct WorkerPool {
+    /// `None` once shut down.
+    queue: Mutex<Option<mpsc::UnboundedSender<Task>>>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl WorkerPool {
+    /// Start `n_workers` worker tasks on the current runtime. Panics if `n_workers == 0`.
+    pub fn new(n_workers: usize) -> Self {
+        assert!(n_workers > 0, "a pool needs at least one worker");
+        let (tx, rx) = mpsc::unbounded_channel();
+        let rx = Arc::new(tokio::sync::Mutex::new(rx));
+        let workers = (0..n_workers)
+            .map(|_| tokio::spawn(worker(rx.clone())))
+            .collect();
+        Self {
+            queue: Mutex::new(Some(tx)),
+            workers: Mutex::new(workers),
+        }
+    }
+
+    /// Queue `job`; its output arrives on the returned receiver. After `shutdown` the job is
+    /// dropped unrun and the receiver gets `Err(PoolError::ShutDown)`.
+    pub fn submit<F, T>(&self, job: F) -> oneshot::Receiver<Result<T, PoolError>>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        match self.queue.lock().unwrap().as_ref() {
+            Some(queue) => {
+                let task: Task = Box::pin(async move {
+                    let _ = tx.send(Ok(job.await));
+                });
+                // The workers only stop once the sender is gone, so this cannot fail.
+                let _ = queue.send(task);
+            }
+            None => {
+                let _ = tx.send(Err(PoolError::ShutDown));
+            }
+        }
+        rx
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.queue.lock().unwrap().is_none()
+    }
+
+    /// Stop accepting jobs, then wait until the workers have run every queued job and exited.
+    /// Calling it again returns at once.
+    pub async fn shutdown(&self) {
+        self.queue.lock().unwrap().take();
+        let workers = std::mem::take(&mut *self.workers.lock().unwrap());
+        for w in workers {
+            let _ = w.await;
+        }
+    }
+}
+
+/// One worker: run queued tasks one at a time until the queue is closed and empty.
+async fn worker(rx: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<Task>>>) {
+    loop {
+        let task = rx.lock().await.recv().await;
+        match task {
+            Some(task) => task.await,
+            None => break,
+        }
+    }
+}
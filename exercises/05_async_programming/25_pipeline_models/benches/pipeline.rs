@@ -0,0 +1,24 @@
+//! `cargo bench -p pipeline_models`: run the same pipeline on each model and print a comparison
+//! table.
+
+#[cfg(feature = "solutions")]
+use pipeline_models::solution::*;
+#[cfg(not(feature = "solutions"))]
+use pipeline_models::*;
+
+fn stage(x: u64) -> u64 {
+    x.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 7
+}
+
+fn main() {
+    let input: Vec<u64> = (0..100_000).collect();
+    for capacity in [1, 64] {
+        let models: [&dyn Pipeline; 3] = [&OsThreads, &GreenThreads, &AsyncTasks];
+        let reports: Vec<Report> = models
+            .iter()
+            .map(|&m| measure(m, &input, stage, capacity))
+            .collect();
+        println!("capacity {capacity}");
+        print!("{}", format_table(&reports));
+    }
+}
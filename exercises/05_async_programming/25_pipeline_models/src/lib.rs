@@ -0,0 +1,396 @@
+//! # One Pipeline, Three Concurrency Models
+//!
+//! A capstone for modules 3 to 5: the same three-stage pipeline built on OS threads, on green
+//! threads (`04_context_switch/02_green_threads`; finish it first) and on tokio tasks, behind one
+//! `Pipeline` trait, so that their outputs can be checked against each other and their costs
+//! compared side by side.
+//!
+//! ```text
+//!   producer ──chan(capacity)──▶ mapper: stage(x) ──chan(capacity)──▶ sink: collect
+//! ```
+//!
+//! - `OsThreads`: one `thread::Builder` thread per stage, `std::sync::mpsc::sync_channel`s; a
+//!   stage's input ends when the previous stage drops its sender.
+//! - `GreenThreads`: one green thread per stage, `GreenChannel<Option<u64>>`s; a green channel
+//!   cannot be closed, so `None` marks the end of the input.
+//! - `AsyncTasks`: one `tokio::spawn`ed task per stage on a current-thread runtime,
+//!   `tokio::sync::mpsc::channel`s; ends like the OS thread version.
+//!
+//! ## What is measured
+//! - `switches`: for green threads, the scheduler's own count (`stats().switches`). Threads and
+//!   tasks are switched by someone else, so there it is the number of times a stage found its
+//!   channel full (send) or empty (recv) and had to block: each such wait is a switch away.
+//! - `stage_bytes`: memory one stage needs for its execution context. An OS thread reserves a
+//!   whole stack (`OS_STACK_SIZE`, mapped lazily by the kernel); a green thread is reported by
+//!   its peak stack use (`Scheduler::stack_watermark`); an async task keeps everything it needs
+//!   across `.await`s in its future, so it is `size_of_val` of the future.
+//!
+//! `cargo bench -p pipeline_models` runs the three and prints a table (see `benches/pipeline.rs`).
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use green_threads::{GreenChannel, Scheduler};
+
+/// Stack reserved for each OS thread stage (what `thread::spawn` uses by default).
+pub const OS_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// What one run of a pipeline produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineRun {
+    /// `stage(x)` for every input `x`, in input order.
+    pub output: Vec<u64>,
+    pub switches: u64,
+    /// The largest `stage_bytes` of the three stages.
+    pub stage_bytes: usize,
+}
+
+/// A producer → mapper → sink pipeline with bounded channels between the stages.
+pub trait Pipeline {
+    fn name(&self) -> &'static str;
+
+    /// Push every item of `input` through `stage`, with `capacity` (> 0) slots in each
+    /// channel. Panics if `capacity == 0`.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun;
+}
+
+pub struct OsThreads;
+pub struct GreenThreads;
+pub struct AsyncTasks;
+
+/// `send` that counts a switch when the channel is full.
+fn send_counting(tx: &mpsc::SyncSender<u64>, value: u64, switches: &AtomicU64) {
+    match tx.try_send(value) {
+        Ok(()) => {}
+        Err(mpsc::TrySendError::Full(value)) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            tx.send(value).expect("the next stage hung up");
+        }
+        Err(mpsc::TrySendError::Disconnected(_)) => panic!("the next stage hung up"),
+    }
+}
+
+/// `recv` that counts a switch when the channel is empty; `None` once it is closed.
+fn recv_counting(rx: &mpsc::Receiver<u64>, switches: &AtomicU64) -> Option<u64> {
+    match rx.try_recv() {
+        Ok(value) => Some(value),
+        Err(mpsc::TryRecvError::Empty) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            rx.recv().ok()
+        }
+        Err(mpsc::TryRecvError::Disconnected) => None,
+    }
+}
+
+impl Pipeline for OsThreads {
+    fn name(&self) -> &'static str {
+        "os_thread"
+    }
+
+    /// One `thread::Builder` thread with `OS_STACK_SIZE` per stage, `sync_channel(capacity)`
+    /// between them; dropping a sender ends the next stage's loop.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun {
+        // TODO: Assert capacity > 0; two sync_channel(capacity)s and a shared AtomicU64 switch count
+        // TODO: Spawn producer, mapper and sink threads with thread::Builder::stack_size(OS_STACK_SIZE);
+        //       move each sender into its stage so dropping it ends the next stage, and use
+        //       send_counting / recv_counting
+        // TODO: Join all three; the sink returns the output. stage_bytes is OS_STACK_SIZE
+        todo!()
+    }
+}
+
+type GreenStage = Box<dyn FnOnce()>;
+
+thread_local! {
+    /// Stages waiting for a green thread, in spawn order (see `21_job_outcomes`).
+    static GREEN_STAGES: RefCell<VecDeque<GreenStage>> = const { RefCell::new(VecDeque::new()) };
+}
+
+extern "C" fn green_stage() {
+    if let Some(stage) = GREEN_STAGES.with(|q| q.borrow_mut().pop_front()) {
+        stage();
+    }
+}
+
+impl Pipeline for GreenThreads {
+    fn name(&self) -> &'static str {
+        "green_thread"
+    }
+
+    /// Three green threads on a fresh `Scheduler` on the calling OS thread, connected by
+    /// `GreenChannel<Option<u64>>`s; the producer and mapper send `None` when they are done.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun {
+        // TODO: Assert capacity > 0; two Rc<GreenChannel<Option<u64>>> and an Rc<RefCell<Vec<u64>>>
+        // TODO: Push producer (sends Some(x) per item, then None), mapper (Some(stage(x)) until None,
+        //       then None) and sink closures to GREEN_STAGES, spawn green_stage three times, run()
+        // TODO: switches from sched.stats(), stage_bytes = max stack_watermark of threads 1..=3
+        todo!()
+    }
+}
+
+/// Tokio `send` that counts a switch when the channel is full.
+async fn send_counting_async(
+    tx: &tokio::sync::mpsc::Sender<u64>,
+    value: u64,
+    switches: &AtomicU64,
+) {
+    use tokio::sync::mpsc::error::TrySendError;
+    match tx.try_send(value) {
+        Ok(()) => {}
+        Err(TrySendError::Full(value)) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            tx.send(value).await.expect("the next stage hung up");
+        }
+        Err(TrySendError::Closed(_)) => panic!("the next stage hung up"),
+    }
+}
+
+/// Tokio `recv` that counts a switch when the channel is empty; `None` once it is closed.
+async fn recv_counting_async(
+    rx: &mut tokio::sync::mpsc::Receiver<u64>,
+    switches: &AtomicU64,
+) -> Option<u64> {
+    use tokio::sync::mpsc::error::TryRecvError;
+    match rx.try_recv() {
+        Ok(value) => Some(value),
+        Err(TryRecvError::Empty) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            rx.recv().await
+        }
+        Err(TryRecvError::Disconnected) => None,
+    }
+}
+
+impl Pipeline for AsyncTasks {
+    fn name(&self) -> &'static str {
+        "tokio_task"
+    }
+
+    /// Three `tokio::spawn`ed tasks on a current-thread runtime, `mpsc::channel(capacity)`
+    /// between them. Measure each future with `size_of_val` before spawning it.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun {
+        // TODO: Assert capacity > 0; build a current-thread runtime and block_on:
+        // TODO: Two mpsc::channel(capacity)s; write the three stages as async blocks using
+        //       send_counting_async / recv_counting_async
+        // TODO: stage_bytes = largest size_of_val of the three futures, taken before tokio::spawn;
+        //       the sink's JoinHandle gives the output
+        todo!()
+    }
+}
+
+/// One timed run of a pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub name: &'static str,
+    pub items: usize,
+    pub elapsed: Duration,
+    pub switches: u64,
+    pub stage_bytes: usize,
+}
+
+impl Report {
+    pub fn ns_per_item(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.items.max(1) as f64
+    }
+}
+
+/// Run `pipeline` once and time it.
+pub fn measure(
+    pipeline: &dyn Pipeline,
+    input: &[u64],
+    stage: fn(u64) -> u64,
+    capacity: usize,
+) -> Report {
+    let start = Instant::now();
+    let run = pipeline.run(input, stage, capacity);
+    let elapsed = start.elapsed();
+    Report {
+        name: pipeline.name(),
+        items: run.output.len(),
+        elapsed,
+        switches: run.switches,
+        stage_bytes: run.stage_bytes,
+    }
+}
+
+/// Render reports as a table in the given order: one row per model with its time per item,
+/// switch count and memory per stage.
+pub fn format_table(reports: &[Report]) -> String {
+    let mut out = format!(
+        "{:<14} {:>10} {:>12} {:>12} {:>14}\n",
+        "model", "items", "ns/item", "switches", "bytes/stage"
+    );
+    for r in reports {
+        out += &format!(
+            "{:<14} {:>10} {:>12.1} {:>12} {:>14}\n",
+            r.name,
+            r.items,
+            r.ns_per_item(),
+            r.switches,
+            r.stage_bytes
+        );
+    }
+    out
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::panic::{self, AssertUnwindSafe};
+
+    const HINTS: Hints = &[
+        (
+            "test_os_threads_pipeline",
+            "move each sender into the stage that sends on it: the next stage's loop only ends once every sender is dropped",
+        ),
+        (
+            "test_green_threads_pipeline",
+            "a GreenChannel cannot be closed: the producer and the mapper each send a final None, and the next stage stops on it",
+        ),
+        (
+            "test_async_tasks_pipeline",
+            "spawn all three stages before awaiting any of them; with bounded channels, a stage awaited alone waits forever",
+        ),
+        (
+            "test_all_models_agree",
+            "each stage handles items in the order it receives them, so every model keeps the input order at any capacity",
+        ),
+        (
+            "test_stage_bytes",
+            "OS threads report OS_STACK_SIZE, green threads their largest stack_watermark, tokio tasks the largest size_of_val of the three futures before spawning",
+        ),
+        (
+            "test_switches_are_counted",
+            "green threads take stats().switches from the scheduler; tokio stages count with send_counting_async / recv_counting_async",
+        ),
+    ];
+
+    fn square(x: u64) -> u64 {
+        x * x
+    }
+
+    fn scramble(x: u64) -> u64 {
+        x.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 7
+    }
+
+    fn models() -> [&'static dyn Pipeline; 3] {
+        [&OsThreads, &GreenThreads, &AsyncTasks]
+    }
+
+    fn expected(input: &[u64], stage: fn(u64) -> u64) -> Vec<u64> {
+        input.iter().map(|&x| stage(x)).collect()
+    }
+
+    #[test]
+    fn test_os_threads_pipeline() {
+        let input: Vec<u64> = (0..200).collect();
+        let run = OsThreads.run(&input, square, 4);
+        assert_eq_with_hint!(run.output, expected(&input, square));
+    }
+
+    #[test]
+    fn test_green_threads_pipeline() {
+        let input: Vec<u64> = (0..200).collect();
+        let run = GreenThreads.run(&input, square, 4);
+        assert_eq_with_hint!(run.output, expected(&input, square));
+    }
+
+    #[test]
+    fn test_async_tasks_pipeline() {
+        let input: Vec<u64> = (0..200).collect();
+        let run = AsyncTasks.run(&input, square, 4);
+        assert_eq_with_hint!(run.output, expected(&input, square));
+    }
+
+    #[test]
+    fn test_all_models_agree() {
+        let inputs: [Vec<u64>; 3] = [vec![], vec![7], (0..1000).rev().collect()];
+        for input in &inputs {
+            for capacity in [1, 2, 64] {
+                let want = expected(input, scramble);
+                for model in models() {
+                    let run = model.run(input, scramble, capacity);
+                    assert_eq_with_hint!(
+                        run.output,
+                        want,
+                        "{} with {} items, capacity {capacity}",
+                        model.name(),
+                        input.len()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_capacity_panics() {
+        for model in models() {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| model.run(&[1], square, 0)));
+            assert!(result.is_err(), "{} accepted capacity 0", model.name());
+        }
+    }
+
+    #[test]
+    fn test_switches_are_counted() {
+        let input: Vec<u64> = (0..100).collect();
+        // With one slot per channel, every item is handed from thread to thread.
+        let green = GreenThreads.run(&input, square, 1);
+        assert_with_hint!(green.switches >= input.len() as u64);
+        // The producer task runs first and finds the channel full on its second item.
+        let tasks = AsyncTasks.run(&input, square, 1);
+        assert_with_hint!(tasks.switches > 0);
+    }
+
+    #[test]
+    fn test_stage_bytes() {
+        let input: Vec<u64> = (0..100).collect();
+        let threads = OsThreads.run(&input, square, 4);
+        let green = GreenThreads.run(&input, square, 4);
+        let tasks = AsyncTasks.run(&input, square, 4);
+        assert_eq_with_hint!(threads.stage_bytes, OS_STACK_SIZE);
+        assert_with_hint!(
+            green.stage_bytes > 0 && green.stage_bytes < OS_STACK_SIZE,
+            "green stack use: {}",
+            green.stage_bytes
+        );
+        assert_with_hint!(
+            tasks.stage_bytes > 0 && tasks.stage_bytes < green.stage_bytes,
+            "future size {} vs green stack use {}",
+            tasks.stage_bytes,
+            green.stage_bytes
+        );
+    }
+
+    #[test]
+    fn test_measure_and_table() {
+        let input: Vec<u64> = (0..50).collect();
+        let reports: Vec<Report> = models()
+            .iter()
+            .map(|&m| measure(m, &input, square, 8))
+            .collect();
+        assert!(reports.iter().all(|r| r.items == 50));
+        let table = format_table(&reports);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("model"));
+        for (line, name) in lines[1..]
+            .iter()
+            .zip(["os_thread", "green_thread", "tokio_task"])
+        {
+            assert!(line.starts_with(name), "{line}");
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! # One Pipeline, Three Concurrency Models
+//!
+//! A capstone for modules 3 to 5: the same three-stage pipeline built on OS threads, on green
+//! threads (`04_context_switch/02_green_threads`; finish it first) and on tokio tasks, behind one
+//! `Pipeline` trait, so that their outputs can be checked against each other and their costs
+//! compared side by side.
+//!
+//! ```text
+//!   producer ──chan(capacity)──▶ mapper: stage(x) ──chan(capacity)──▶ sink: collect
+//! ```
+//!
+//! - `OsThreads`: one `thread::Builder` thread per stage, `std::sync::mpsc::sync_channel`s; a
+//!   stage's input ends when the previous stage drops its sender.
+//! - `GreenThreads`: one green thread per stage, `GreenChannel<Option<u64>>`s; a green channel
+//!   cannot be closed, so `None` marks the end of the input.
+//! - `AsyncTasks`: one `tokio::spawn`ed task per stage on a current-thread runtime,
+//!   `tokio::sync::mpsc::channel`s; ends like the OS thread version.
+//!
+//! ## What is measured
+//! - `switches`: for green threads, the scheduler's own count (`stats().switches`). Threads and
+//!   tasks are switched by someone else, so there it is the number of times a stage found its
+//!   channel full (send) or empty (recv) and had to block: each such wait is a switch away.
+//! - `stage_bytes`: memory one stage needs for its execution context. An OS thread reserves a
+//!   whole stack (`OS_STACK_SIZE`, mapped lazily by the kernel); a green thread is reported by
+//!   its peak stack use (`Scheduler::stack_watermark`); an async task keeps everything it needs
+//!   across `.await`s in its future, so it is `size_of_val` of the future.
+//!
+//! `cargo bench -p pipeline_models` runs the three and prints a table (see `benches/pipeline.rs`).
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use green_threads::solution::{GreenChannel, Scheduler};
+
+/// Stack reserved for each OS thread stage (what `thread::spawn` uses by default).
+pub const OS_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// What one run of a pipeline produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineRun {
+    /// `stage(x)` for every input `x`, in input order.
+    pub output: Vec<u64>,
+    pub switches: u64,
+    /// The largest `stage_bytes` of the three stages.
+    pub stage_bytes: usize,
+}
+
+/// A producer → mapper → sink pipeline with bounded channels between the stages.
+pub trait Pipeline {
+    fn name(&self) -> &'static str;
+
+    /// Push every item of `input` through `stage`, with `capacity` (> 0) slots in each
+    /// channel. Panics if `capacity == 0`.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun;
+}
+
+pub struct OsThreads;
+pub struct GreenThreads;
+pub struct AsyncTasks;
+
+/// `send` that counts a switch when the channel is full.
+fn send_counting(tx: &mpsc::SyncSender<u64>, value: u64, switches: &AtomicU64) {
+    match tx.try_send(value) {
+        Ok(()) => {}
+        Err(mpsc::TrySendError::Full(value)) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            tx.send(value).expect("the next stage hung up");
+        }
+        Err(mpsc::TrySendError::Disconnected(_)) => panic!("the next stage hung up"),
+    }
+}
+
+/// `recv` that counts a switch when the channel is empty; `None` once it is closed.
+fn recv_counting(rx: &mpsc::Receiver<u64>, switches: &AtomicU64) -> Option<u64> {
+    match rx.try_recv() {
+        Ok(value) => Some(value),
+        Err(mpsc::TryRecvError::Empty) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            rx.recv().ok()
+        }
+        Err(mpsc::TryRecvError::Disconnected) => None,
+    }
+}
+
+impl Pipeline for OsThreads {
+    fn name(&self) -> &'static str {
+        "os_thread"
+    }
+
+    /// One `thread::Builder` thread with `OS_STACK_SIZE` per stage, `sync_channel(capacity)`
+    /// between them; dropping a sender ends the next stage's loop.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun {
+        assert!(capacity > 0, "capacity must be positive");
+        let switches = Arc::new(AtomicU64::new(0));
+        let (raw_tx, raw_rx) = mpsc::sync_channel(capacity);
+        let (mapped_tx, mapped_rx) = mpsc::sync_channel(capacity);
+        let spawn = |f: Box<dyn FnOnce() -> Vec<u64> + Send>| {
+            thread::Builder::new()
+                .stack_size(OS_STACK_SIZE)
+                .spawn(f)
+                .unwrap()
+        };
+
+        let input = input.to_vec();
+        let sw = switches.clone();
+        let producer = spawn(Box::new(move || {
+            for x in input {
+                send_counting(&raw_tx, x, &sw);
+            }
+            Vec::new()
+        }));
+        let sw = switches.clone();
+        let mapper = spawn(Box::new(move || {
+            while let Some(x) = recv_counting(&raw_rx, &sw) {
+                send_counting(&mapped_tx, stage(x), &sw);
+            }
+            Vec::new()
+        }));
+        let sw = switches.clone();
+        let sink = spawn(Box::new(move || {
+            let mut out = Vec::new();
+            while let Some(y) = recv_counting(&mapped_rx, &sw) {
+                out.push(y);
+            }
+            out
+        }));
+
+        producer.join().unwrap();
+        mapper.join().unwrap();
+        let output = sink.join().unwrap();
+        PipelineRun {
+            output,
+            switches: switches.load(Ordering::Relaxed),
+            stage_bytes: OS_STACK_SIZE,
+        }
+    }
+}
+
+type GreenStage = Box<dyn FnOnce()>;
+
+thread_local! {
+    /// Stages waiting for a green thread, in spawn order (see `21_job_outcomes`).
+    static GREEN_STAGES: RefCell<VecDeque<GreenStage>> = const { RefCell::new(VecDeque::new()) };
+}
+
+extern "C" fn green_stage() {
+    if let Some(stage) = GREEN_STAGES.with(|q| q.borrow_mut().pop_front()) {
+        stage();
+    }
+}
+
+impl Pipeline for GreenThreads {
+    fn name(&self) -> &'static str {
+        "green_thread"
+    }
+
+    /// Three green threads on a fresh `Scheduler` on the calling OS thread, connected by
+    /// `GreenChannel<Option<u64>>`s; the producer and mapper send `None` when they are done.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun {
+        assert!(capacity > 0, "capacity must be positive");
+        let raw = Rc::new(GreenChannel::<Option<u64>>::new(capacity));
+        let mapped = Rc::new(GreenChannel::<Option<u64>>::new(capacity));
+        let output = Rc::new(RefCell::new(Vec::with_capacity(input.len())));
+
+        let input = input.to_vec();
+        let tx = raw.clone();
+        let producer = move || {
+            for x in input {
+                tx.send(Some(x));
+            }
+            tx.send(None);
+        };
+        let (rx, tx) = (raw, mapped.clone());
+        let mapper = move || {
+            while let Some(x) = rx.recv() {
+                tx.send(Some(stage(x)));
+            }
+            tx.send(None);
+        };
+        let (rx, out) = (mapped, output.clone());
+        let sink = move || {
+            while let Some(y) = rx.recv() {
+                out.borrow_mut().push(y);
+            }
+        };
+
+        let mut sched = Scheduler::new();
+        GREEN_STAGES.with(|q| {
+            let mut q = q.borrow_mut();
+            q.push_back(Box::new(producer));
+            q.push_back(Box::new(mapper));
+            q.push_back(Box::new(sink));
+        });
+        for _ in 0..3 {
+            sched.spawn(green_stage);
+        }
+        sched.run().expect("pipeline stages never deadlock");
+
+        let stage_bytes = (1..=3)
+            .filter_map(|tid| sched.stack_watermark(tid))
+            .max()
+            .unwrap_or(0);
+        let output = output.take();
+        PipelineRun {
+            output,
+            switches: sched.stats().switches,
+            stage_bytes,
+        }
+    }
+}
+
+/// Tokio `send` that counts a switch when the channel is full.
+async fn send_counting_async(
+    tx: &tokio::sync::mpsc::Sender<u64>,
+    value: u64,
+    switches: &AtomicU64,
+) {
+    use tokio::sync::mpsc::error::TrySendError;
+    match tx.try_send(value) {
+        Ok(()) => {}
+        Err(TrySendError::Full(value)) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            tx.send(value).await.expect("the next stage hung up");
+        }
+        Err(TrySendError::Closed(_)) => panic!("the next stage hung up"),
+    }
+}
+
+/// Tokio `recv` that counts a switch when the channel is empty; `None` once it is closed.
+async fn recv_counting_async(
+    rx: &mut tokio::sync::mpsc::Receiver<u64>,
+    switches: &AtomicU64,
+) -> Option<u64> {
+    use tokio::sync::mpsc::error::TryRecvError;
+    match rx.try_recv() {
+        Ok(value) => Some(value),
+        Err(TryRecvError::Empty) => {
+            switches.fetch_add(1, Ordering::Relaxed);
+            rx.recv().await
+        }
+        Err(TryRecvError::Disconnected) => None,
+    }
+}
+
+impl Pipeline for AsyncTasks {
+    fn name(&self) -> &'static str {
+        "tokio_task"
+    }
+
+    /// Three `tokio::spawn`ed tasks on a current-thread runtime, `mpsc::channel(capacity)`
+    /// between them. Measure each future with `size_of_val` before spawning it.
+    fn run(&self, input: &[u64], stage: fn(u64) -> u64, capacity: usize) -> PipelineRun {
+        assert!(capacity > 0, "capacity must be positive");
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let input = input.to_vec();
+        rt.block_on(async move {
+            let switches = Arc::new(AtomicU64::new(0));
+            let (raw_tx, mut raw_rx) = tokio::sync::mpsc::channel(capacity);
+            let (mapped_tx, mut mapped_rx) = tokio::sync::mpsc::channel(capacity);
+
+            let sw = switches.clone();
+            let producer = async move {
+                for x in input {
+                    send_counting_async(&raw_tx, x, &sw).await;
+                }
+            };
+            let sw = switches.clone();
+            let mapper = async move {
+                while let Some(x) = recv_counting_async(&mut raw_rx, &sw).await {
+                    send_counting_async(&mapped_tx, stage(x), &sw).await;
+                }
+            };
+            let sw = switches.clone();
+            let sink = async move {
+                let mut out = Vec::new();
+                while let Some(y) = recv_counting_async(&mut mapped_rx, &sw).await {
+                    out.push(y);
+                }
+                out
+            };
+            let stage_bytes = std::mem::size_of_val(&producer)
+                .max(std::mem::size_of_val(&mapper))
+                .max(std::mem::size_of_val(&sink));
+
+            let producer = tokio::spawn(producer);
+            let mapper = tokio::spawn(mapper);
+            let output = tokio::spawn(sink).await.unwrap();
+            producer.await.unwrap();
+            mapper.await.unwrap();
+            PipelineRun {
+                output,
+                switches: switches.load(Ordering::Relaxed),
+                stage_bytes,
+            }
+        })
+    }
+}
+
+/// One timed run of a pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub name: &'static str,
+    pub items: usize,
+    pub elapsed: Duration,
+    pub switches: u64,
+    pub stage_bytes: usize,
+}
+
+impl Report {
+    pub fn ns_per_item(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.items.max(1) as f64
+    }
+}
+
+/// Run `pipeline` once and time it.
+pub fn measure(
+    pipeline: &dyn Pipeline,
+    input: &[u64],
+    stage: fn(u64) -> u64,
+    capacity: usize,
+) -> Report {
+    let start = Instant::now();
+    let run = pipeline.run(input, stage, capacity);
+    let elapsed = start.elapsed();
+    Report {
+        name: pipeline.name(),
+        items: run.output.len(),
+        elapsed,
+        switches: run.switches,
+        stage_bytes: run.stage_bytes,
+    }
+}
+
+/// Render reports as a table in the given order: one row per model with its time per item,
+/// switch count and memory per stage.
+pub fn format_table(reports: &[Report]) -> String {
+    let mut out = format!(
+        "{:<14} {:>10} {:>12} {:>12} {:>14}\n",
+        "model", "items", "ns/item", "switches", "bytes/stage"
+    );
+    for r in reports {
+        out += &format!(
+            "{:<14} {:>10} {:>12.1} {:>12} {:>14}\n",
+            r.name,
+            r.items,
+            r.ns_per_item(),
+            r.switches,
+            r.stage_bytes
+        );
+    }
+    out
+}
@@ -7,6 +7,7 @@
 //! - RISC-V SV39 page table entry 64-bit layout
 //! - Bit operations to construct/extract fields
 //! - Meaning of PTE flags (V/R/W/X/U/G/A/D)
+//! - The RSW field, a flag pretty-printer and a PTE builder, debugging aids for later exercises
 //!
 //! ## SV39 PTE Layout (64-bit)
 //! ```text
@@ -48,6 +49,13 @@ pub const PTE_D: u64 = 1 << 7; // Dirty
 const PPN_SHIFT: u32 = 10;
 const PPN_MASK: u64 = (1u64 << 44) - 1; // 44-bit PPN
 
+/// RSW field offset and mask in PTE
+const RSW_SHIFT: u32 = 8;
+const RSW_MASK: u64 = 0b11; // 2-bit RSW
+
+/// Flag letters of `flags_to_string`, from bit 7 (D) down to bit 0 (V).
+const FLAG_LETTERS: &[u8; 8] = b"DAGUXWRV";
+
 /// Construct a page table entry from physical page number (PPN) and flags.
 ///
 /// PPN occupies bits [53:10], flags occupy bits [7:0].
@@ -102,15 +110,155 @@ pub fn check_permission(pte: u64, read: bool, write: bool, execute: bool) -> boo
     todo!()
 }
 
+/// Replace the 2-bit RSW field (bits [9:8]) of `pte` with `rsw`, keeping every other bit.
+///
+/// The hardware ignores RSW; kernels use it for things like marking copy-on-write pages.
+/// Panics if `rsw` does not fit in 2 bits.
+pub fn set_rsw(pte: u64, rsw: u64) -> u64 {
+    // TODO: Check that rsw fits in RSW_MASK, clear bits [9:8], then OR in rsw << RSW_SHIFT
+    todo!()
+}
+
+/// Extract the 2-bit RSW field (bits [9:8]) from page table entry.
+pub fn get_rsw(pte: u64) -> u64 {
+    // TODO: Extract RSW from pte
+    todo!()
+}
+
+/// Render the flags of `pte` as 8 letters, `DAGUXWRV` from bit 7 down to bit 0,
+/// with `-` for each cleared bit.
+///
+/// Example: PTE_V|PTE_R|PTE_W gives `"-----WRV"`, a user code page with A set gives `"-A-UX-RV"`.
+pub fn flags_to_string(pte: u64) -> String {
+    // TODO: FLAG_LETTERS[i] stands for bit 7 - i
+    todo!()
+}
+
+/// Fluent builder for page table entries.
+///
+/// ```ignore
+/// let pte = PteBuilder::new(0x80200).valid().readable().executable().build();
+/// assert_eq!(pte, make_pte(0x80200, PTE_V | PTE_R | PTE_X));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PteBuilder {
+    ppn: u64,
+    flags: u64,
+    rsw: u64,
+}
+
+impl PteBuilder {
+    /// Start a PTE pointing at `ppn`, with no flags set and RSW = 0.
+    pub fn new(ppn: u64) -> Self {
+        Self {
+            ppn,
+            ..Self::default()
+        }
+    }
+
+    /// Set the given flag bits (any combination of `PTE_*`).
+    pub fn flag(mut self, flag: u64) -> Self {
+        // TODO: OR flag into self.flags
+        todo!()
+    }
+
+    pub fn valid(self) -> Self {
+        self.flag(PTE_V)
+    }
+
+    pub fn readable(self) -> Self {
+        self.flag(PTE_R)
+    }
+
+    pub fn writable(self) -> Self {
+        self.flag(PTE_W)
+    }
+
+    pub fn executable(self) -> Self {
+        self.flag(PTE_X)
+    }
+
+    pub fn user(self) -> Self {
+        self.flag(PTE_U)
+    }
+
+    pub fn global(self) -> Self {
+        self.flag(PTE_G)
+    }
+
+    pub fn accessed(self) -> Self {
+        self.flag(PTE_A)
+    }
+
+    pub fn dirty(self) -> Self {
+        self.flag(PTE_D)
+    }
+
+    /// Set the RSW field. Panics if `rsw` does not fit in 2 bits.
+    pub fn rsw(mut self, rsw: u64) -> Self {
+        // TODO: Same check as set_rsw, then store rsw
+        todo!()
+    }
+
+    /// The finished PTE: `make_pte(ppn, flags)` with the RSW field set.
+    pub fn build(self) -> u64 {
+        // TODO: Combine make_pte and set_rsw
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_make_pte_basic",
+            "shift the PPN left by PPN_SHIFT (10) and OR in the flags",
+        ),
+        (
+            "test_make_pte_large_ppn",
+            "extract_ppn must mask with PPN_MASK after shifting right, so bits above 53 are dropped",
+        ),
+        (
+            "test_is_leaf",
+            "a PTE is a leaf if any of R, W, X is set; V alone points to the next level",
+        ),
+        (
+            "test_check_permission_rw",
+            "every requested permission must be present; permissions that are not requested do not matter",
+        ),
+        (
+            "test_check_permission_invalid",
+            "an entry without PTE_V grants nothing, whatever R/W/X say",
+        ),
+        (
+            "test_rsw_round_trip",
+            "RSW lives in bits [9:8]: (pte >> 8) & 0b11; set_rsw clears those two bits before OR-ing in rsw << 8",
+        ),
+        (
+            "test_flags_to_string",
+            "walk DAGUXWRV left to right: letter i is bit 7 - i, '-' when that bit is clear",
+        ),
+        (
+            "test_builder_matches_make_pte",
+            "each builder method ORs one PTE_* bit into flags; build() is make_pte(ppn, flags) plus set_rsw",
+        ),
+    ];
 
     #[test]
     fn test_make_pte_basic() {
         let pte = make_pte(0x12345, PTE_V | PTE_R | PTE_W);
-        assert_eq!(extract_ppn(pte), 0x12345);
-        assert_eq!(extract_flags(pte), PTE_V | PTE_R | PTE_W);
+        assert_eq_with_hint!(extract_ppn(pte), 0x12345);
+        assert_eq_with_hint!(extract_flags(pte), PTE_V | PTE_R | PTE_W);
     }
 
     #[test]
@@ -133,7 +281,7 @@ mod tests {
     fn test_make_pte_large_ppn() {
         let ppn = (1u64 << 44) - 1; // maximum PPN
         let pte = make_pte(ppn, PTE_V);
-        assert_eq!(extract_ppn(pte), ppn);
+        assert_eq_with_hint!(extract_ppn(pte), ppn);
     }
 
     #[test]
@@ -145,12 +293,12 @@ mod tests {
 
     #[test]
     fn test_is_leaf() {
-        assert!(is_leaf(make_pte(1, PTE_V | PTE_R)));
-        assert!(is_leaf(make_pte(1, PTE_V | PTE_X)));
-        assert!(is_leaf(make_pte(1, PTE_V | PTE_R | PTE_W | PTE_X)));
+        assert_with_hint!(is_leaf(make_pte(1, PTE_V | PTE_R)));
+        assert_with_hint!(is_leaf(make_pte(1, PTE_V | PTE_X)));
+        assert_with_hint!(is_leaf(make_pte(1, PTE_V | PTE_R | PTE_W | PTE_X)));
         // Non-leaf: only V set, R/W/X all cleared
-        assert!(!is_leaf(make_pte(1, PTE_V)));
-        assert!(!is_leaf(make_pte(1, PTE_V | PTE_A | PTE_D)));
+        assert_with_hint!(!is_leaf(make_pte(1, PTE_V)));
+        assert_with_hint!(!is_leaf(make_pte(1, PTE_V | PTE_A | PTE_D)));
     }
 
     #[test]
@@ -164,8 +312,8 @@ mod tests {
     #[test]
     fn test_check_permission_rw() {
         let pte = make_pte(1, PTE_V | PTE_R | PTE_W);
-        assert!(check_permission(pte, true, true, false));
-        assert!(!check_permission(pte, true, true, true));
+        assert_with_hint!(check_permission(pte, true, true, false));
+        assert_with_hint!(!check_permission(pte, true, true, true));
     }
 
     #[test]
@@ -180,6 +328,95 @@ mod tests {
     fn test_check_permission_invalid() {
         // V not set, should return false even if R/W/X flags present
         let pte = make_pte(1, PTE_R | PTE_W | PTE_X);
-        assert!(!check_permission(pte, true, false, false));
+        assert_with_hint!(!check_permission(pte, true, false, false));
+    }
+
+    #[test]
+    fn test_rsw_round_trip() {
+        let pte = make_pte(0x12345, PTE_V | PTE_R | PTE_W | PTE_A | PTE_D);
+        assert_eq!(get_rsw(pte), 0);
+        for rsw in 0..=3 {
+            let marked = set_rsw(pte, rsw);
+            assert_eq_with_hint!(get_rsw(marked), rsw);
+            // PPN and flags are untouched
+            assert_eq_with_hint!(extract_ppn(marked), 0x12345);
+            assert_eq_with_hint!(extract_flags(marked), extract_flags(pte));
+            // Overwriting, not OR-ing
+            assert_eq_with_hint!(get_rsw(set_rsw(set_rsw(pte, 0b11), rsw)), rsw);
+        }
+        assert_eq!(set_rsw(set_rsw(pte, 2), 0), pte);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rsw_too_wide() {
+        set_rsw(0, 4);
+    }
+
+    #[test]
+    fn test_flags_to_string() {
+        assert_eq_with_hint!(flags_to_string(0), "--------");
+        assert_eq_with_hint!(
+            flags_to_string(make_pte(0x12345, PTE_V | PTE_R | PTE_W)),
+            "-----WRV"
+        );
+        assert_eq_with_hint!(flags_to_string(make_pte(0x80000, PTE_V)), "-------V");
+        assert_eq_with_hint!(
+            flags_to_string(make_pte(1, PTE_V | PTE_R | PTE_X | PTE_U | PTE_A)),
+            "-A-UX-RV"
+        );
+        assert_eq_with_hint!(
+            flags_to_string(make_pte(
+                (1u64 << 44) - 1,
+                PTE_V | PTE_R | PTE_W | PTE_X | PTE_U | PTE_G | PTE_A | PTE_D
+            )),
+            "DAGUXWRV"
+        );
+        // PPN and RSW bits do not show up
+        assert_eq!(
+            flags_to_string(set_rsw(make_pte(0x3ff, PTE_G), 3)),
+            "--G-----"
+        );
+    }
+
+    #[test]
+    fn test_builder_matches_make_pte() {
+        let pte = PteBuilder::new(0x80200)
+            .valid()
+            .readable()
+            .executable()
+            .build();
+        assert_eq_with_hint!(pte, make_pte(0x80200, PTE_V | PTE_R | PTE_X));
+
+        let all = PteBuilder::new(0xABC)
+            .valid()
+            .readable()
+            .writable()
+            .executable()
+            .user()
+            .global()
+            .accessed()
+            .dirty()
+            .build();
+        assert_eq_with_hint!(
+            all,
+            make_pte(
+                0xABC,
+                PTE_V | PTE_R | PTE_W | PTE_X | PTE_U | PTE_G | PTE_A | PTE_D
+            )
+        );
+        assert_eq!(PteBuilder::new(7).build(), make_pte(7, 0));
+        assert_eq!(
+            PteBuilder::new(7).flag(PTE_V | PTE_W).build(),
+            PteBuilder::new(7).writable().valid().build()
+        );
+    }
+
+    #[test]
+    fn test_builder_rsw() {
+        let pte = PteBuilder::new(0x42).valid().readable().rsw(2).build();
+        assert_eq!(get_rsw(pte), 2);
+        assert_eq!(pte, set_rsw(make_pte(0x42, PTE_V | PTE_R), 2));
+        assert_eq!(flags_to_string(pte), "------RV");
     }
 }
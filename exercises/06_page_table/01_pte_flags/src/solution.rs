@@ -0,0 +1,217 @@
+//! # Page Table Entry Flags
+//!
+//! In this exercise, you will learn the structure of RISC-V SV39 Page Table Entry (PTE),
+//! and construct/parse page table entries through bit operations.
+//!
+//! ## Concepts
+//! - RISC-V SV39 page table entry 64-bit layout
+//! - Bit operations to construct/extract fields
+//! - Meaning of PTE flags (V/R/W/X/U/G/A/D)
+//! - The RSW field, a flag pretty-printer and a PTE builder, debugging aids for later exercises
+//!
+//! ## SV39 PTE Layout (64-bit)
+//! ```text
+//! 63    54 53        10 9  8 7 6 5 4 3 2 1 0
+//! ┌───────┬────────────┬────┬─┬─┬─┬─┬─┬─┬─┬─┐
+//! │ Rsvd  │  PPN[2:0]  │ RSW│D│A│G│U│X│W│R│V│
+//! │ 10bit │  44 bits   │ 2b │ │ │ │ │ │ │ │ │
+//! └───────┴────────────┴────┴─┴─┴─┴─┴─┴─┴─┴─┘
+//! ```
+//! - V (Valid): Valid bit indicating whether the page table entry is valid.
+//!
+//! - R/W/X (Read/Write/Execute): Permission bits for read, write, and execute access respectively.
+//!
+//! - U (User): User-accessible bit, allowing access from user mode.
+//!
+//! - G (Global): Global mapping bit (typically used for kernel space to avoid TLB flushes).
+//!
+//! - A (Accessed): Accessed bit, set by hardware when the page is accessed.
+//!
+//! - D (Dirty): Dirty bit, set by hardware when the page is written to.
+//!
+//! - RSW (Reserved for Supervisor Software): Two bits reserved for operating system software use.
+//!
+//! - PPN (Physical Page Number): Physical page number occupying 44 bits (bits [53:10]), specifying the base address of the physical page frame.
+//! - PPN[2:0] (Physical Page Number): In the RISC-V SV39 paging mechanism, the Physical Page Number (PPN) is divided into three parts, which are referred to as PPN[2], PPN[1], and PPN[0]. This division is designed to support the indexing of multi-level page tables.
+//! - Rsvd (Reserved): Reserved bits, typically set to 0.
+
+/// PTE flag constants
+pub const PTE_V: u64 = 1 << 0; // Valid
+pub const PTE_R: u64 = 1 << 1; // Readable
+pub const PTE_W: u64 = 1 << 2; // Writable
+pub const PTE_X: u64 = 1 << 3; // Executable
+pub const PTE_U: u64 = 1 << 4; // User accessible
+pub const PTE_G: u64 = 1 << 5; // Global
+pub const PTE_A: u64 = 1 << 6; // Accessed
+pub const PTE_D: u64 = 1 << 7; // Dirty
+
+/// PPN field offset and mask in PTE
+const PPN_SHIFT: u32 = 10;
+const PPN_MASK: u64 = (1u64 << 44) - 1; // 44-bit PPN
+
+/// RSW field offset and mask in PTE
+const RSW_SHIFT: u32 = 8;
+const RSW_MASK: u64 = 0b11; // 2-bit RSW
+
+/// Flag letters of `flags_to_string`, from bit 7 (D) down to bit 0 (V).
+const FLAG_LETTERS: &[u8; 8] = b"DAGUXWRV";
+
+/// Construct a page table entry from physical page number (PPN) and flags.
+///
+/// PPN occupies bits [53:10], flags occupy bits [7:0].
+///
+/// Example: ppn=0x12345, flags=PTE_V|PTE_R|PTE_W
+/// Result should be: (0x12345 << 10) | 0b111 = 0x48D14007
+///
+/// Hint: Shift PPN left by PPN_SHIFT bits, then OR with flags.
+pub fn make_pte(ppn: u64, flags: u64) -> u64 {
+    ((ppn & PPN_MASK) << PPN_SHIFT) | (flags & 0xff)
+}
+
+/// Extract physical page number (PPN) from page table entry.
+///
+/// Hint: Right shift by PPN_SHIFT bits, then AND with PPN_MASK.
+pub fn extract_ppn(pte: u64) -> u64 {
+    (pte >> PPN_SHIFT) & PPN_MASK
+}
+
+/// Extract flags (lower 8 bits) from page table entry.
+pub fn extract_flags(pte: u64) -> u64 {
+    pte & 0xff
+}
+
+/// Check whether page table entry is valid (V bit set).
+pub fn is_valid(pte: u64) -> bool {
+    pte & PTE_V != 0
+}
+
+/// Determine whether page table entry is a leaf PTE.
+///
+/// In SV39, if any of R, W, X bits is set, the PTE is a leaf,
+/// pointing to the final physical page. Otherwise it points to next-level page table.
+pub fn is_leaf(pte: u64) -> bool {
+    pte & (PTE_R | PTE_W | PTE_X) != 0
+}
+
+/// Check whether page table entry permits the requested access based on given permissions.
+///
+/// - `read`: requires read permission
+/// - `write`: requires write permission
+/// - `execute`: requires execute permission
+///
+/// Returns true iff: PTE is valid, and each requested permission is satisfied.
+pub fn check_permission(pte: u64, read: bool, write: bool, execute: bool) -> bool {
+    is_valid(pte)
+        && (!read || pte & PTE_R != 0)
+        && (!write || pte & PTE_W != 0)
+        && (!execute || pte & PTE_X != 0)
+}
+
+/// Replace the 2-bit RSW field (bits [9:8]) of `pte` with `rsw`, keeping every other bit.
+///
+/// The hardware ignores RSW; kernels use it for things like marking copy-on-write pages.
+/// Panics if `rsw` does not fit in 2 bits.
+pub fn set_rsw(pte: u64, rsw: u64) -> u64 {
+    assert!(rsw <= RSW_MASK, "RSW is 2 bits wide, got {rsw:#x}");
+    (pte & !(RSW_MASK << RSW_SHIFT)) | (rsw << RSW_SHIFT)
+}
+
+/// Extract the 2-bit RSW field (bits [9:8]) from page table entry.
+pub fn get_rsw(pte: u64) -> u64 {
+    (pte >> RSW_SHIFT) & RSW_MASK
+}
+
+/// Render the flags of `pte` as 8 letters, `DAGUXWRV` from bit 7 down to bit 0,
+/// with `-` for each cleared bit.
+///
+/// Example: PTE_V|PTE_R|PTE_W gives `"-----WRV"`, a user code page with A set gives `"-A-UX-RV"`.
+pub fn flags_to_string(pte: u64) -> String {
+    FLAG_LETTERS
+        .iter()
+        .enumerate()
+        .map(|(i, &letter)| {
+            if pte & (1 << (7 - i)) != 0 {
+                {
+                    letter as char
+                }
+            } else {
+                {
+                    '-'
+                }
+            }
+        })
+        .collect()
+}
+
+/// Fluent builder for page table entries.
+///
+/// ```ignore
+/// let pte = PteBuilder::new(0x80200).valid().readable().executable().build();
+/// assert_eq!(pte, make_pte(0x80200, PTE_V | PTE_R | PTE_X));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PteBuilder {
+    ppn: u64,
+    flags: u64,
+    rsw: u64,
+}
+
+impl PteBuilder {
+    /// Start a PTE pointing at `ppn`, with no flags set and RSW = 0.
+    pub fn new(ppn: u64) -> Self {
+        Self {
+            ppn,
+            ..Self::default()
+        }
+    }
+
+    /// Set the given flag bits (any combination of `PTE_*`).
+    pub fn flag(mut self, flag: u64) -> Self {
+        self.flags |= flag;
+        self
+    }
+
+    pub fn valid(self) -> Self {
+        self.flag(PTE_V)
+    }
+
+    pub fn readable(self) -> Self {
+        self.flag(PTE_R)
+    }
+
+    pub fn writable(self) -> Self {
+        self.flag(PTE_W)
+    }
+
+    pub fn executable(self) -> Self {
+        self.flag(PTE_X)
+    }
+
+    pub fn user(self) -> Self {
+        self.flag(PTE_U)
+    }
+
+    pub fn global(self) -> Self {
+        self.flag(PTE_G)
+    }
+
+    pub fn accessed(self) -> Self {
+        self.flag(PTE_A)
+    }
+
+    pub fn dirty(self) -> Self {
+        self.flag(PTE_D)
+    }
+
+    /// Set the RSW field. Panics if `rsw` does not fit in 2 bits.
+    pub fn rsw(mut self, rsw: u64) -> Self {
+        assert!(rsw <= RSW_MASK, "RSW is 2 bits wide, got {rsw:#x}");
+        self.rsw = rsw;
+        self
+    }
+
+    /// The finished PTE: `make_pte(ppn, flags)` with the RSW field set.
+    pub fn build(self) -> u64 {
+        set_rsw(make_pte(self.ppn, self.flags), self.rsw)
+    }
+}
@@ -7,6 +7,9 @@
 //! - Page table: VPN → PPN mapping table
 //! - Address translation: Physical address = PPN × PAGE_SIZE + offset
 //! - Page fault: accessing an unmapped virtual page
+//! - Permissions: reads need R, writes W, instruction fetches X; `protect` changes them later
+//! - Accessed/dirty bits: a successful access sets A, a successful write also sets D, which is
+//!   how an OS finds the pages it has to write back (`dirty_pages`)
 //!
 //! ## Address Format (Simplified Model)
 //! ```text
@@ -28,6 +31,13 @@ pub const PAGE_OFFSET_BITS: u32 = 12;
 pub const PTE_VALID: u8 = 1 << 0;
 pub const PTE_READ: u8 = 1 << 1;
 pub const PTE_WRITE: u8 = 1 << 2;
+pub const PTE_EXEC: u8 = 1 << 3;
+/// 访问位：页被成功访问（读、写或取指）过，由 `translate` / `access` 置位
+pub const PTE_ACCESSED: u8 = 1 << 6;
+/// 脏位：页被成功写入过，由 `translate` / `access` 置位
+pub const PTE_DIRTY: u8 = 1 << 7;
+/// `protect` 可以修改的权限位
+pub const PTE_PERM_MASK: u8 = PTE_READ | PTE_WRITE | PTE_EXEC;
 
 /// 页表项
 #[derive(Clone, Copy, Debug)]
@@ -36,6 +46,14 @@ pub struct PageTableEntry {
     pub flags: u8,
 }
 
+/// 访问类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
 /// 翻译结果
 #[derive(Debug, PartialEq)]
 pub enum TranslateResult {
@@ -43,7 +61,7 @@ pub enum TranslateResult {
     Ok(u32),
     /// 缺页：虚拟页未映射
     PageFault,
-    /// 权限错误：尝试写入只读页
+    /// 权限错误：访问类型不被页的权限位允许（如写入只读页）
     PermissionDenied,
 }
 
@@ -80,19 +98,48 @@ impl SingleLevelPageTable {
         todo!()
     }
 
-    /// 将虚拟地址翻译为物理地址。
+    /// 将虚拟地址翻译为物理地址：`access(va, Access::Write)`（`is_write`）或
+    /// `access(va, Access::Read)`。
+    pub fn translate(&mut self, va: u32, is_write: bool) -> TranslateResult {
+        let kind = if is_write {
+            Access::Write
+        } else {
+            Access::Read
+        };
+        self.access(va, kind)
+    }
+
+    /// 以 `kind` 方式访问虚拟地址 `va`，成功时返回物理地址，并像硬件一样更新页表项。
     ///
     /// 步骤：
     /// 1. 从虚拟地址中提取 VPN（高 20 位）和 offset（低 12 位）
     /// 2. 用 VPN 查页表，如果未映射返回 PageFault
     /// 3. 检查 PTE_VALID 标志，未置位返回 PageFault
-    /// 4. 如果 `is_write` 为 true，检查 PTE_WRITE 标志
-    /// 5. 计算物理地址 = ppn * PAGE_SIZE + offset
-    pub fn translate(&self, va: u32, is_write: bool) -> TranslateResult {
+    /// 4. 检查访问类型需要的权限位（读 PTE_READ、写 PTE_WRITE、取指 PTE_EXEC），
+    ///    缺少则返回 PermissionDenied
+    /// 5. 成功：置 PTE_ACCESSED，写访问再置 PTE_DIRTY；失败的访问不改动页表项
+    /// 6. 计算物理地址 = ppn * PAGE_SIZE + offset
+    #[allow(unused_variables)]
+    pub fn access(&mut self, va: u32, kind: Access) -> TranslateResult {
         // TODO: 实现虚拟地址到物理地址的翻译
         // 提示：
         //   let vpn = (va >> PAGE_OFFSET_BITS) as usize;
         //   let offset = va & ((1 << PAGE_OFFSET_BITS) - 1);
+        //   需要修改页表项时用 self.entries[vpn].as_mut()
+        todo!()
+    }
+
+    /// 修改虚拟页 `vpn` 的权限：把 R/W/X 三位换成 `new_flags` 中的对应位，
+    /// 其余位（VALID、ACCESSED、DIRTY）保持不变。`vpn` 未映射时返回 false。
+    #[allow(unused_variables)]
+    pub fn protect(&mut self, vpn: usize, new_flags: u8) -> bool {
+        // TODO: flags = (flags & !PTE_PERM_MASK) | (new_flags & PTE_PERM_MASK)
+        todo!()
+    }
+
+    /// 所有 PTE_DIRTY 置位的虚拟页号，升序。
+    pub fn dirty_pages(&self) -> Vec<usize> {
+        // TODO: 遍历 entries，收集带 PTE_DIRTY 的下标
         todo!()
     }
 }
@@ -119,16 +166,67 @@ pub fn make_pa(ppn: u32, offset: u32) -> u32 {
     todo!()
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_va_decompose",
+            "VPN = va >> PAGE_OFFSET_BITS, offset = va & ((1 << PAGE_OFFSET_BITS) - 1)",
+        ),
+        (
+            "test_make_pa",
+            "physical address = ppn * PAGE_SIZE + offset, i.e. (ppn << 12) | offset",
+        ),
+        (
+            "test_unmap",
+            "unmap sets entries[vpn] to None, so lookup returns None afterwards",
+        ),
+        (
+            "test_translate_page_fault",
+            "an unmapped VPN is a PageFault",
+        ),
+        (
+            "test_translate_write_permission",
+            "for a write, also check PTE_WRITE and fault if it is missing",
+        ),
+        (
+            "test_translate_invalid_entry",
+            "an entry that is present but lacks PTE_VALID is a PageFault too",
+        ),
+        (
+            "test_exec_permission",
+            "Access::Execute needs PTE_EXEC; a readable page is not executable, and PTE_EXEC alone is not readable",
+        ),
+        (
+            "test_accessed_dirty_bits",
+            "set PTE_ACCESSED on every successful access and PTE_DIRTY on a successful write; a denied access changes nothing",
+        ),
+        (
+            "test_protect_read_only",
+            "protect replaces only the R/W/X bits: (flags & !PTE_PERM_MASK) | (new_flags & PTE_PERM_MASK)",
+        ),
+        (
+            "test_dirty_pages",
+            "collect the VPNs whose entry has PTE_DIRTY, in ascending order",
+        ),
+    ];
 
     #[test]
     fn test_va_decompose() {
         // 虚拟地址 0x12345678
         // VPN = 0x12345, offset = 0x678
-        assert_eq!(va_to_vpn(0x12345678), 0x12345);
-        assert_eq!(va_to_offset(0x12345678), 0x678);
+        assert_eq_with_hint!(va_to_vpn(0x12345678), 0x12345);
+        assert_eq_with_hint!(va_to_offset(0x12345678), 0x678);
     }
 
     #[test]
@@ -146,9 +244,9 @@ mod tests {
 
     #[test]
     fn test_make_pa() {
-        assert_eq!(make_pa(0x80, 0x100), 0x80 * 4096 + 0x100);
-        assert_eq!(make_pa(0, 0), 0);
-        assert_eq!(make_pa(1, 0), 4096);
+        assert_eq_with_hint!(make_pa(0x80, 0x100), 0x80 * 4096 + 0x100);
+        assert_eq_with_hint!(make_pa(0, 0), 0);
+        assert_eq_with_hint!(make_pa(1, 0), 4096);
     }
 
     #[test]
@@ -171,10 +269,10 @@ mod tests {
     fn test_unmap() {
         let mut pt = SingleLevelPageTable::new(1024);
         pt.map(10, 200, PTE_VALID | PTE_READ);
-        assert!(pt.lookup(10).is_some());
+        assert_with_hint!(pt.lookup(10).is_some());
 
         pt.unmap(10);
-        assert!(pt.lookup(10).is_none());
+        assert_with_hint!(pt.lookup(10).is_none());
     }
 
     #[test]
@@ -191,8 +289,8 @@ mod tests {
 
     #[test]
     fn test_translate_page_fault() {
-        let pt = SingleLevelPageTable::new(1024);
-        assert_eq!(pt.translate(0x5000, false), TranslateResult::PageFault);
+        let mut pt = SingleLevelPageTable::new(1024);
+        assert_eq_with_hint!(pt.translate(0x5000, false), TranslateResult::PageFault);
     }
 
     #[test]
@@ -202,12 +300,12 @@ mod tests {
         pt.map(2, 0x90, PTE_VALID | PTE_READ);
 
         // 读取应成功
-        assert_eq!(
+        assert_eq_with_hint!(
             pt.translate(0x2000, false),
             TranslateResult::Ok(0x90 * PAGE_SIZE as u32)
         );
         // 写入应拒绝
-        assert_eq!(
+        assert_eq_with_hint!(
             pt.translate(0x2000, true),
             TranslateResult::PermissionDenied
         );
@@ -230,7 +328,7 @@ mod tests {
         let mut pt = SingleLevelPageTable::new(1024);
         // 映射了但 VALID 未置位
         pt.map(4, 0x50, PTE_READ);
-        assert_eq!(pt.translate(0x4000, false), TranslateResult::PageFault);
+        assert_eq_with_hint!(pt.translate(0x4000, false), TranslateResult::PageFault);
     }
 
     #[test]
@@ -244,4 +342,104 @@ mod tests {
         assert_eq!(pt.translate(0x1000, true), TranslateResult::Ok(0x20000));
         assert_eq!(pt.translate(0x2800, false), TranslateResult::Ok(0x30800));
     }
+
+    #[test]
+    fn test_exec_permission() {
+        let mut pt = SingleLevelPageTable::new(1024);
+        pt.map(1, 0x10, PTE_VALID | PTE_READ | PTE_EXEC);
+        pt.map(2, 0x20, PTE_VALID | PTE_READ | PTE_WRITE);
+        pt.map(3, 0x30, PTE_VALID | PTE_EXEC);
+
+        assert_eq!(
+            pt.access(0x1004, Access::Execute),
+            TranslateResult::Ok(0x10004)
+        );
+        assert_eq_with_hint!(
+            pt.access(0x2000, Access::Execute),
+            TranslateResult::PermissionDenied
+        );
+        assert_eq_with_hint!(
+            pt.access(0x3000, Access::Read),
+            TranslateResult::PermissionDenied
+        );
+        assert_eq!(
+            pt.access(0x3000, Access::Execute),
+            TranslateResult::Ok(0x30000)
+        );
+        assert_eq!(
+            pt.access(0x9000, Access::Execute),
+            TranslateResult::PageFault
+        );
+    }
+
+    #[test]
+    fn test_accessed_dirty_bits() {
+        let mut pt = SingleLevelPageTable::new(1024);
+        pt.map(1, 0x10, PTE_VALID | PTE_READ | PTE_WRITE);
+        pt.map(2, 0x20, PTE_VALID | PTE_READ);
+        let flags = |pt: &SingleLevelPageTable, vpn| pt.lookup(vpn).unwrap().flags;
+
+        assert_eq!(flags(&pt, 1) & (PTE_ACCESSED | PTE_DIRTY), 0);
+        pt.translate(0x1000, false);
+        assert_eq_with_hint!(flags(&pt, 1) & (PTE_ACCESSED | PTE_DIRTY), PTE_ACCESSED);
+        pt.translate(0x1000, true);
+        assert_eq_with_hint!(
+            flags(&pt, 1) & (PTE_ACCESSED | PTE_DIRTY),
+            PTE_ACCESSED | PTE_DIRTY
+        );
+
+        // 被拒绝的写不留痕迹
+        assert_eq!(
+            pt.translate(0x2000, true),
+            TranslateResult::PermissionDenied
+        );
+        assert_eq_with_hint!(flags(&pt, 2) & (PTE_ACCESSED | PTE_DIRTY), 0);
+    }
+
+    #[test]
+    fn test_protect_read_only() {
+        let mut pt = SingleLevelPageTable::new(1024);
+        pt.map(5, 0x50, PTE_VALID | PTE_READ | PTE_WRITE);
+        assert_eq!(pt.translate(0x5010, true), TranslateResult::Ok(0x50010));
+
+        assert_with_hint!(pt.protect(5, PTE_READ));
+        assert_eq_with_hint!(
+            pt.translate(0x5010, true),
+            TranslateResult::PermissionDenied
+        );
+        assert_eq!(pt.translate(0x5010, false), TranslateResult::Ok(0x50010));
+        // VALID 与之前写入留下的 DIRTY 都保留
+        assert_eq_with_hint!(
+            pt.lookup(5).unwrap().flags,
+            PTE_VALID | PTE_READ | PTE_ACCESSED | PTE_DIRTY
+        );
+
+        // 再升级回可写
+        assert!(pt.protect(5, PTE_READ | PTE_WRITE));
+        assert_eq!(pt.translate(0x5010, true), TranslateResult::Ok(0x50010));
+
+        assert_with_hint!(!pt.protect(6, PTE_READ));
+        assert!(pt.lookup(6).is_none());
+    }
+
+    #[test]
+    fn test_dirty_pages() {
+        let mut pt = SingleLevelPageTable::new(1024);
+        for vpn in 0..8 {
+            pt.map(vpn, 0x100 + vpn as u32, PTE_VALID | PTE_READ | PTE_WRITE);
+        }
+        assert_eq!(pt.dirty_pages(), Vec::<usize>::new());
+
+        for va in [0x6000, 0x2abc, 0x6ff0] {
+            assert!(matches!(pt.translate(va, true), TranslateResult::Ok(_)));
+        }
+        for va in [0x1000, 0x3000] {
+            assert!(matches!(pt.translate(va, false), TranslateResult::Ok(_)));
+        }
+        assert_eq_with_hint!(pt.dirty_pages(), vec![2, 6]);
+
+        // 写回后重新映射，脏位清零
+        pt.map(6, 0x106, PTE_VALID | PTE_READ | PTE_WRITE);
+        assert_eq!(pt.dirty_pages(), vec![2]);
+    }
 }
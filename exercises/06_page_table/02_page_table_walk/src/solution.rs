@@ -0,0 +1,181 @@
+//! # Single‑Level Page Table Address Translation
+//!
+//! This exercise simulates a simple single‑level page table to help you understand the process of virtual‑to‑physical address translation.
+//!
+//! ## Concepts
+//! - Virtual address = Virtual Page Number (VPN) + Page Offset (offset)
+//! - Page table: VPN → PPN mapping table
+//! - Address translation: Physical address = PPN × PAGE_SIZE + offset
+//! - Page fault: accessing an unmapped virtual page
+//! - Permissions: reads need R, writes W, instruction fetches X; `protect` changes them later
+//! - Accessed/dirty bits: a successful access sets A, a successful write also sets D, which is
+//!   how an OS finds the pages it has to write back (`dirty_pages`)
+//!
+//! ## Address Format (Simplified Model)
+//! ```text
+//! Virtual address (32‑bit):
+//! 31          12 11          0
+//! ┌──────────────┬────────────┐
+//! │   VPN (20 bits)  │ offset (12 bits) │
+//! └──────────────┴────────────┘
+//!
+//! Page size: 4KB (2^12 = 4096 bytes)
+//! ```
+
+/// 页大小 4KB
+pub const PAGE_SIZE: usize = 4096;
+/// 页内偏移位数
+pub const PAGE_OFFSET_BITS: u32 = 12;
+
+/// 页表项标志
+pub const PTE_VALID: u8 = 1 << 0;
+pub const PTE_READ: u8 = 1 << 1;
+pub const PTE_WRITE: u8 = 1 << 2;
+pub const PTE_EXEC: u8 = 1 << 3;
+/// 访问位：页被成功访问（读、写或取指）过，由 `translate` / `access` 置位
+pub const PTE_ACCESSED: u8 = 1 << 6;
+/// 脏位：页被成功写入过，由 `translate` / `access` 置位
+pub const PTE_DIRTY: u8 = 1 << 7;
+/// `protect` 可以修改的权限位
+pub const PTE_PERM_MASK: u8 = PTE_READ | PTE_WRITE | PTE_EXEC;
+
+/// 页表项
+#[derive(Clone, Copy, Debug)]
+pub struct PageTableEntry {
+    pub ppn: u32,
+    pub flags: u8,
+}
+
+/// 访问类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+/// 翻译结果
+#[derive(Debug, PartialEq)]
+pub enum TranslateResult {
+    /// 翻译成功，得到物理地址
+    Ok(u32),
+    /// 缺页：虚拟页未映射
+    PageFault,
+    /// 权限错误：访问类型不被页的权限位允许（如写入只读页）
+    PermissionDenied,
+}
+
+/// 单级页表，最多支持 `MAX_PAGES` 个虚拟页。
+pub struct SingleLevelPageTable {
+    entries: Vec<Option<PageTableEntry>>,
+}
+
+impl SingleLevelPageTable {
+    /// 创建一个空页表，支持 `max_pages` 个虚拟页。
+    pub fn new(max_pages: usize) -> Self {
+        Self {
+            entries: vec![None; max_pages],
+        }
+    }
+
+    /// 将虚拟页号 `vpn` 映射到物理页号 `ppn`，并设置标志位 `flags`。
+    ///
+    /// 提示：在 `entries[vpn]` 处存放一个 `PageTableEntry`。
+    pub fn map(&mut self, vpn: usize, ppn: u32, flags: u8) {
+        self.entries[vpn] = Some(PageTableEntry { ppn, flags });
+    }
+
+    /// 取消虚拟页号 `vpn` 的映射。
+    pub fn unmap(&mut self, vpn: usize) {
+        self.entries[vpn] = None;
+    }
+
+    /// 查询虚拟页号 `vpn` 对应的页表项。
+    pub fn lookup(&self, vpn: usize) -> Option<&PageTableEntry> {
+        self.entries.get(vpn)?.as_ref()
+    }
+
+    /// 将虚拟地址翻译为物理地址：`access(va, Access::Write)`（`is_write`）或
+    /// `access(va, Access::Read)`。
+    pub fn translate(&mut self, va: u32, is_write: bool) -> TranslateResult {
+        let kind = if is_write {
+            Access::Write
+        } else {
+            Access::Read
+        };
+        self.access(va, kind)
+    }
+
+    /// 以 `kind` 方式访问虚拟地址 `va`，成功时返回物理地址，并像硬件一样更新页表项。
+    ///
+    /// 步骤：
+    /// 1. 从虚拟地址中提取 VPN（高 20 位）和 offset（低 12 位）
+    /// 2. 用 VPN 查页表，如果未映射返回 PageFault
+    /// 3. 检查 PTE_VALID 标志，未置位返回 PageFault
+    /// 4. 检查访问类型需要的权限位（读 PTE_READ、写 PTE_WRITE、取指 PTE_EXEC），
+    ///    缺少则返回 PermissionDenied
+    /// 5. 成功：置 PTE_ACCESSED，写访问再置 PTE_DIRTY；失败的访问不改动页表项
+    /// 6. 计算物理地址 = ppn * PAGE_SIZE + offset
+    pub fn access(&mut self, va: u32, kind: Access) -> TranslateResult {
+        let Some(pte) = self.entries.get_mut(va_to_vpn(va)).and_then(Option::as_mut) else {
+            return TranslateResult::PageFault;
+        };
+        if pte.flags & PTE_VALID == 0 {
+            return TranslateResult::PageFault;
+        }
+        let needed = match kind {
+            Access::Read => PTE_READ,
+            Access::Write => PTE_WRITE,
+            Access::Execute => PTE_EXEC,
+        };
+        if pte.flags & needed == 0 {
+            return TranslateResult::PermissionDenied;
+        }
+        pte.flags |= PTE_ACCESSED;
+        if kind == Access::Write {
+            pte.flags |= PTE_DIRTY;
+        }
+        TranslateResult::Ok(make_pa(pte.ppn, va_to_offset(va)))
+    }
+
+    /// 修改虚拟页 `vpn` 的权限：把 R/W/X 三位换成 `new_flags` 中的对应位，
+    /// 其余位（VALID、ACCESSED、DIRTY）保持不变。`vpn` 未映射时返回 false。
+    pub fn protect(&mut self, vpn: usize, new_flags: u8) -> bool {
+        match self.entries.get_mut(vpn).and_then(Option::as_mut) {
+            Some(pte) => {
+                pte.flags = (pte.flags & !PTE_PERM_MASK) | (new_flags & PTE_PERM_MASK);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 所有 PTE_DIRTY 置位的虚拟页号，升序。
+    pub fn dirty_pages(&self) -> Vec<usize> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.is_some_and(|pte| pte.flags & PTE_DIRTY != 0))
+            .map(|(vpn, _)| vpn)
+            .collect()
+    }
+}
+
+/// 从虚拟地址中提取虚拟页号。
+///
+/// 提示：右移 PAGE_OFFSET_BITS 位。
+pub fn va_to_vpn(va: u32) -> usize {
+    (va >> PAGE_OFFSET_BITS) as usize
+}
+
+/// 从虚拟地址中提取页内偏移。
+///
+/// 提示：用掩码提取低 PAGE_OFFSET_BITS 位。
+pub fn va_to_offset(va: u32) -> u32 {
+    va & ((1 << PAGE_OFFSET_BITS) - 1)
+}
+
+/// 由物理页号和偏移量拼出物理地址。
+pub fn make_pa(ppn: u32, offset: u32) -> u32 {
+    (ppn << PAGE_OFFSET_BITS) | offset
+}
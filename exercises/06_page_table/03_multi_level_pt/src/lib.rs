@@ -9,6 +9,9 @@
 //! - VPN 拆分：VPN[2] (9bit) | VPN[1] (9bit) | VPN[0] (9bit)
 //! - 页表遍历（page table walk）逐级查找
 //! - 大页（2MB superpage）映射
+//! - 区域映射：按对齐和剩余长度贪心地选 1GB / 2MB / 4KB 页
+//! - 深拷贝页表（fork 时立即复制，不共享任何页表节点）
+//! - 按虚拟地址顺序遍历所有叶子（`for_each_leaf`），检查页表的结构
 //!
 //! ## SV39 虚拟地址布局
 //! ```text
@@ -20,6 +23,7 @@
 //! ```
 
 use std::collections::HashMap;
+use std::ops::Range;
 
 /// 页大小 4KB
 pub const PAGE_SIZE: usize = 4096;
@@ -31,6 +35,7 @@ pub const PTE_V: u64 = 1 << 0;
 pub const PTE_R: u64 = 1 << 1;
 pub const PTE_W: u64 = 1 << 2;
 pub const PTE_X: u64 = 1 << 3;
+pub const PTE_U: u64 = 1 << 4;
 
 /// PPN 在 PTE 中的偏移
 const PPN_SHIFT: u32 = 10;
@@ -68,6 +73,45 @@ pub struct Sv39PageTable {
     next_ppn: u64,
 }
 
+/// 页的大小，也决定叶子 PTE 所在的级别。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PageSize {
+    /// 4KB，叶子在 level 0
+    Size4K,
+    /// 2MB 大页，叶子在 level 1
+    Size2M,
+    /// 1GB 大页，叶子在 level 2（根页表）
+    Size1G,
+}
+
+impl PageSize {
+    /// 页的字节数。
+    pub const fn bytes(self) -> u64 {
+        match self {
+            PageSize::Size4K => 1 << 12,
+            PageSize::Size2M => 1 << 21,
+            PageSize::Size1G => 1 << 30,
+        }
+    }
+
+    /// 叶子 PTE 所在的级别。
+    pub const fn level(self) -> usize {
+        match self {
+            PageSize::Size4K => 0,
+            PageSize::Size2M => 1,
+            PageSize::Size1G => 2,
+        }
+    }
+}
+
+/// `map_region` 建立的一个映射：从 `va` 开始的一页（大小为 `size`）映射到 `pa`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    pub va: u64,
+    pub pa: u64,
+    pub size: PageSize,
+}
+
 /// 翻译结果
 #[derive(Debug, PartialEq)]
 pub enum TranslateResult {
@@ -144,6 +188,19 @@ impl Sv39PageTable {
         todo!()
     }
 
+    /// 遍历页表，返回 `va` 所在页的叶子 PTE（4KB 页在 level 0，2MB 大页在 level 1，1GB 大页在 level 2），
+    /// 未映射时返回 `None`。
+    ///
+    /// 与 `translate` 走同样的路径，但返回 PTE 本身：调用者据此读取标志位
+    /// （PTE_U / PTE_R / PTE_W / PTE_X）做权限检查，或用 `pte >> 10` 取得物理页号。
+    pub fn leaf(&self, va: u64) -> Option<u64> {
+        // TODO: 与 translate 相同的逐级遍历，但返回叶子 PTE 本身
+        //
+        // 提示：遇到无效 PTE 返回 None；遇到叶节点（R、W、X 有任一置位）返回 Some(pte)；
+        // level 0 的 PTE 仍不是叶节点时同样返回 None。
+        todo!()
+    }
+
     /// 建立大页映射（2MB superpage，在 level 1 设叶子 PTE）。
     ///
     /// 2MB = 512 × 4KB，对齐要求：va 和 pa 都必须 2MB 对齐。
@@ -162,6 +219,92 @@ impl Sv39PageTable {
         // 但翻译时 offset 包含虚拟地址的低 21 位（VPN[0] 部分 + 12 位页内偏移）。
         todo!()
     }
+
+    /// 建立 1GB 大页映射（在 level 2，也就是根页表中设叶子 PTE）。
+    ///
+    /// 对齐要求：va 和 pa 都必须 1GB 对齐。
+    pub fn map_gigapage(&mut self, va: u64, pa: u64, flags: u64) {
+        let giga_size = PageSize::Size1G.bytes();
+        assert_eq!(va % giga_size, 0, "va must be 1GB-aligned");
+        assert_eq!(pa % giga_size, 0, "pa must be 1GB-aligned");
+
+        // TODO: 叶子就在根页表里：不需要分配任何中间节点，
+        // 直接写根页表中 VPN[2] 对应的条目
+        todo!()
+    }
+
+    /// 把 `[va, va + len)` 映射到 `[pa, pa + len)`，自动选择页大小，返回按地址顺序建立的映射。
+    ///
+    /// 贪心：每一步在剩余区域的开头选能用的最大页——va 和 pa 都按该页大小对齐、
+    /// 且剩余长度至少一页——1GB 优先，其次 2MB，最后 4KB。
+    /// 例如 2MB 对齐的 5MB 区域是两个 2MB 大页加 256 个 4KB 页。
+    ///
+    /// `va`、`pa`、`len` 都必须 4KB 对齐。
+    #[allow(unused_variables)]
+    pub fn map_region(&mut self, va: u64, pa: u64, len: u64, flags: u64) -> Vec<Mapping> {
+        let page = PAGE_SIZE as u64;
+        assert!(
+            va.is_multiple_of(page) && pa.is_multiple_of(page) && len.is_multiple_of(page),
+            "va, pa and len must be 4KB-aligned"
+        );
+
+        // TODO: 从 offset = 0 开始，直到 offset == len：
+        // 1. 在 [Size1G, Size2M, Size4K] 中找第一个满足
+        //    (va + offset) 和 (pa + offset) 都按 size.bytes() 对齐、且 len - offset >= size.bytes() 的大小
+        // 2. 按大小调用 map_gigapage / map_superpage / map_page，并记录一个 Mapping
+        // 3. offset += size.bytes()
+        todo!()
+    }
+
+    /// 页表节点（包括根）的数量。
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 所有页表节点的 PPN，升序。
+    pub fn node_ppns(&self) -> Vec<u64> {
+        let mut ppns: Vec<u64> = self.nodes.keys().copied().collect();
+        ppns.sort_unstable();
+        ppns
+    }
+
+    /// 深拷贝整个页表，相当于不做写时复制、立即复制一切的 fork。
+    ///
+    /// 副本的每个页表节点（包括根）都是新分配的，PPN 从原页表的 `next_ppn` 开始往后取，
+    /// 所以拷贝完成时两棵树没有共用的节点：之后修改其中一个的映射不会影响另一个。
+    /// 叶子 PTE 原样复制（本模拟里没有数据页；真正的 fork 还要复制叶子指向的物理页）。
+    pub fn clone_full(&self) -> Self {
+        // TODO: 新建一个空页表：root_ppn = self.next_ppn，next_ppn = root_ppn + 1，
+        // 并插入一个空的根节点
+        //
+        // TODO: 从两个根开始递归复制（level 2 → 0）：对源节点的每个条目，
+        // - 有效的非叶子条目（R/W/X 都没置位）：在副本中 alloc_node 一个新节点，
+        //   先递归复制子树，再写入 (新 PPN << 10) | 原来的标志位
+        // - 其他条目（无效或叶子）：原样复制
+        todo!()
+    }
+
+    /// 按虚拟地址从低到高遍历每个叶子 PTE，对每个叶子调用
+    /// `f(va_range, ppn, flags, level)`：
+    /// - `va_range`：该叶子映射的虚拟地址范围，长度为 4KB / 2MB / 1GB（level 0 / 1 / 2）
+    /// - `ppn`：叶子 PTE 中的物理页号（`pte >> 10`）
+    /// - `flags`：PTE 的低 10 位标志位
+    /// - `level`：叶子所在的级别
+    ///
+    /// 虚拟地址只取低 39 位（与 `extract_vpn` 一致，不做高位符号扩展）。
+    #[allow(unused_variables)]
+    pub fn for_each_leaf(&self, mut f: impl FnMut(Range<u64>, u64, u64, usize)) {
+        // TODO: 从根节点（level 2，起始 va = 0）开始深度优先遍历，条目下标从 0 到 511：
+        // - 条目 i 覆盖的 va 起点是 base + (i << (12 + 9 * level))
+        // - 无效条目跳过；叶子（R/W/X 有任一置位）调用 f；否则进入下一级节点
+        todo!()
+    }
+
+    /// 每一级叶子的数量：`[level 0 (4KB), level 1 (2MB), level 2 (1GB)]`。
+    pub fn count_pages_by_level(&self) -> [usize; 3] {
+        // TODO: 用 for_each_leaf 按 level 计数
+        todo!()
+    }
 }
 
 impl Default for Sv39PageTable {
@@ -170,9 +313,76 @@ impl Default for Sv39PageTable {
     }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_extract_vpn",
+            "each VPN field is 9 bits wide: (va >> (12 + 9 * level)) & 0x1FF",
+        ),
+        (
+            "test_extract_vpn_level2",
+            "VPN[2] starts at bit 30 = 12 + 9 * 2",
+        ),
+        (
+            "test_translate_with_offset",
+            "the page offset (va & 0xFFF) is added to the physical page address unchanged",
+        ),
+        (
+            "test_multiple_mappings",
+            "a non-leaf PTE points to the next table: ppn = pte >> 10; create missing tables instead of reusing the root",
+        ),
+        (
+            "test_map_overwrite",
+            "mapping an already mapped page overwrites its leaf PTE",
+        ),
+        (
+            "test_leaf_pte",
+            "leaf returns the whole PTE (PPN << 10 | flags) of the level where the walk stops, which is level 1 for a superpage",
+        ),
+        (
+            "test_superpage_mapping",
+            "a superpage is a leaf at level 1: the offset is the low 21 bits (va & 0x1F_FFFF), not 12",
+        ),
+        (
+            "test_gigapage_mapping",
+            "a 1GB page is a leaf in the root table itself: the offset is the low 30 bits",
+        ),
+        (
+            "test_map_region_5mb",
+            "at each step take the largest page size that both va + offset and pa + offset are aligned to and that still fits in len - offset",
+        ),
+        (
+            "test_map_region_misaligned_pa",
+            "a superpage needs pa aligned as well as va; otherwise fall back to 4KB pages",
+        ),
+        (
+            "test_clone_full_is_independent",
+            "allocate a new node for every non-leaf PTE and point the copied PTE at it; copying the PTE as-is shares the child table",
+        ),
+        (
+            "test_clone_full_fresh_ppns",
+            "start the copy's allocator at the original's next_ppn, so no node PPN of the copy is already used by the original",
+        ),
+        (
+            "test_for_each_leaf_va_order",
+            "visit entries 0..512 in order and recurse into a child before moving on to the next entry; entry i of a level-l node starts at base + (i << (12 + 9 * l))",
+        ),
+        (
+            "test_count_pages_by_level",
+            "a superpage is one leaf at level 1, not 512 leaves at level 0: stop descending at the first leaf",
+        ),
+    ];
 
     #[test]
     fn test_extract_vpn() {
@@ -181,9 +391,9 @@ mod tests {
         // VPN[1] = 0x1FF (bits 29:21)
         // VPN[0] = 0x1FF (bits 20:12)
         let va: u64 = 0x7FFFFFF000;
-        assert_eq!(Sv39PageTable::extract_vpn(va, 2), 0x1FF);
-        assert_eq!(Sv39PageTable::extract_vpn(va, 1), 0x1FF);
-        assert_eq!(Sv39PageTable::extract_vpn(va, 0), 0x1FF);
+        assert_eq_with_hint!(Sv39PageTable::extract_vpn(va, 2), 0x1FF);
+        assert_eq_with_hint!(Sv39PageTable::extract_vpn(va, 1), 0x1FF);
+        assert_eq_with_hint!(Sv39PageTable::extract_vpn(va, 0), 0x1FF);
     }
 
     #[test]
@@ -200,9 +410,9 @@ mod tests {
     fn test_extract_vpn_level2() {
         // VPN[2] = 1 means bit 30 set -> VA >= 0x40000000
         let va: u64 = 0x40000000;
-        assert_eq!(Sv39PageTable::extract_vpn(va, 2), 1);
-        assert_eq!(Sv39PageTable::extract_vpn(va, 1), 0);
-        assert_eq!(Sv39PageTable::extract_vpn(va, 0), 0);
+        assert_eq_with_hint!(Sv39PageTable::extract_vpn(va, 2), 1);
+        assert_eq_with_hint!(Sv39PageTable::extract_vpn(va, 1), 0);
+        assert_eq_with_hint!(Sv39PageTable::extract_vpn(va, 0), 0);
     }
 
     #[test]
@@ -222,7 +432,7 @@ mod tests {
 
         // 访问 VA 0x2ABC -> PA 应为 0x90000ABC
         let result = pt.translate(0x2ABC);
-        assert_eq!(result, TranslateResult::Ok(0x90000ABC));
+        assert_eq_with_hint!(result, TranslateResult::Ok(0x90000ABC));
     }
 
     #[test]
@@ -238,19 +448,19 @@ mod tests {
         pt.map_page(0x0000_2000, 0x8000_5000, PTE_V | PTE_R | PTE_W);
         pt.map_page(0x0040_0000, 0x9000_0000, PTE_V | PTE_R);
 
-        assert_eq!(pt.translate(0x1234), TranslateResult::Ok(0x80001234));
-        assert_eq!(pt.translate(0x2000), TranslateResult::Ok(0x80005000));
-        assert_eq!(pt.translate(0x400100), TranslateResult::Ok(0x90000100));
+        assert_eq_with_hint!(pt.translate(0x1234), TranslateResult::Ok(0x80001234));
+        assert_eq_with_hint!(pt.translate(0x2000), TranslateResult::Ok(0x80005000));
+        assert_eq_with_hint!(pt.translate(0x400100), TranslateResult::Ok(0x90000100));
     }
 
     #[test]
     fn test_map_overwrite() {
         let mut pt = Sv39PageTable::new();
         pt.map_page(0x1000, 0x80001000, PTE_V | PTE_R);
-        assert_eq!(pt.translate(0x1000), TranslateResult::Ok(0x80001000));
+        assert_eq_with_hint!(pt.translate(0x1000), TranslateResult::Ok(0x80001000));
 
         pt.map_page(0x1000, 0x90002000, PTE_V | PTE_R);
-        assert_eq!(pt.translate(0x1000), TranslateResult::Ok(0x90002000));
+        assert_eq_with_hint!(pt.translate(0x1000), TranslateResult::Ok(0x90002000));
     }
 
     #[test]
@@ -260,9 +470,27 @@ mod tests {
         pt.map_superpage(0x200000, 0x80200000, PTE_V | PTE_R | PTE_W);
 
         // 大页内不同偏移都应命中
-        assert_eq!(pt.translate(0x200000), TranslateResult::Ok(0x80200000));
-        assert_eq!(pt.translate(0x200ABC), TranslateResult::Ok(0x80200ABC));
-        assert_eq!(pt.translate(0x2FF000), TranslateResult::Ok(0x802FF000));
+        assert_eq_with_hint!(pt.translate(0x200000), TranslateResult::Ok(0x80200000));
+        assert_eq_with_hint!(pt.translate(0x200ABC), TranslateResult::Ok(0x80200ABC));
+        assert_eq_with_hint!(pt.translate(0x2FF000), TranslateResult::Ok(0x802FF000));
+    }
+
+    #[test]
+    fn test_leaf_pte() {
+        let mut pt = Sv39PageTable::new();
+        pt.map_page(0x1000, 0x8000_3000, PTE_V | PTE_R | PTE_U);
+        pt.map_superpage(0x4000_0000, 0x8020_0000, PTE_V | PTE_R | PTE_W);
+
+        assert_eq_with_hint!(
+            pt.leaf(0x1abc),
+            Some((0x80003 << 10) | PTE_V | PTE_R | PTE_U)
+        );
+        assert_eq_with_hint!(
+            pt.leaf(0x4012_3456),
+            Some((0x80200 << 10) | PTE_V | PTE_R | PTE_W)
+        );
+        assert_eq!(pt.leaf(0x2000), None, "same level-0 table, empty entry");
+        assert_eq!(pt.leaf(0x8000_0000), None, "no level-1 table at all");
     }
 
     #[test]
@@ -276,4 +504,216 @@ mod tests {
         assert_eq!(pt.translate(0x100), TranslateResult::Ok(0x80000100));
         assert_eq!(pt.translate(0x40000000), TranslateResult::Ok(0x90001000));
     }
+
+    /// 每隔 `step` 字节（以及区域末尾）检查一次翻译结果。
+    fn assert_translates(pt: &Sv39PageTable, va: u64, pa: u64, len: u64, step: u64) {
+        let offsets = (0..len).step_by(step as usize).chain([len - 1]);
+        for off in offsets {
+            assert_eq!(
+                pt.translate(va + off),
+                TranslateResult::Ok(pa + off),
+                "va {:#x}",
+                va + off
+            );
+        }
+    }
+
+    #[test]
+    fn test_gigapage_mapping() {
+        let mut pt = Sv39PageTable::new();
+        pt.map_gigapage(0x4000_0000, 0x8000_0000, PTE_V | PTE_R | PTE_W);
+        assert_eq_with_hint!(pt.translate(0x4000_0000), TranslateResult::Ok(0x8000_0000));
+        assert_eq_with_hint!(pt.translate(0x7FFF_FABC), TranslateResult::Ok(0xBFFF_FABC));
+        assert_eq!(pt.translate(0x8000_0000), TranslateResult::PageFault);
+        assert_eq!(
+            pt.leaf(0x4123_4567),
+            Some((0x80000 << 10) | PTE_V | PTE_R | PTE_W)
+        );
+    }
+
+    #[test]
+    fn test_map_region_5mb() {
+        let mut pt = Sv39PageTable::new();
+        let (va, pa, len) = (0x20_0000, 0x8040_0000, 5 << 20);
+        let maps = pt.map_region(va, pa, len, PTE_V | PTE_R | PTE_W);
+
+        let supers: Vec<_> = maps.iter().filter(|m| m.size == PageSize::Size2M).collect();
+        assert_eq_with_hint!(supers.len(), 2);
+        assert_eq!((supers[0].va, supers[1].va), (0x20_0000, 0x40_0000));
+        assert_eq_with_hint!(maps.len(), 2 + 256);
+        assert!(maps[2..].iter().all(|m| m.size == PageSize::Size4K));
+        assert_eq!(maps.last().unwrap().va, va + len - 0x1000);
+        assert!(maps.iter().all(|m| m.pa - pa == m.va - va));
+        assert_translates(&pt, va, pa, len, 0x800);
+        assert_eq!(pt.translate(va + len), TranslateResult::PageFault);
+    }
+
+    #[test]
+    fn test_map_region_uses_all_sizes() {
+        use PageSize::*;
+        let mut pt = Sv39PageTable::new();
+        // 4KB 开头，然后对齐到 2MB，再到 1GB，最后剩 2MB + 4KB
+        let va = 0x3FDF_F000;
+        let pa = 0x7FDF_F000;
+        let len = 0x1000 + 0x20_0000 + (1 << 30) + 0x20_0000 + 0x1000;
+        let maps = pt.map_region(va, pa, len, PTE_V | PTE_R);
+        let sizes: Vec<PageSize> = maps.iter().map(|m| m.size).collect();
+        assert_eq!(sizes, [Size4K, Size2M, Size1G, Size2M, Size4K]);
+        assert_eq!(maps[2].va, 0x4000_0000);
+        assert_translates(&pt, va, pa, len, 0x10_0000);
+    }
+
+    #[test]
+    fn test_map_region_misaligned_pa() {
+        let mut pt = Sv39PageTable::new();
+        let maps = pt.map_region(0x20_0000, 0x8000_1000, 0x40_0000, PTE_V | PTE_R);
+        assert_eq_with_hint!(maps.len(), 1024);
+        assert!(maps.iter().all(|m| m.size == PageSize::Size4K));
+        assert_translates(&pt, 0x20_0000, 0x8000_1000, 0x40_0000, 0x1000);
+    }
+
+    #[test]
+    fn test_map_region_empty() {
+        let mut pt = Sv39PageTable::new();
+        assert!(pt
+            .map_region(0x1000, 0x8000_1000, 0, PTE_V | PTE_R)
+            .is_empty());
+        assert_eq!(pt.translate(0x1000), TranslateResult::PageFault);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_map_region_unaligned_len() {
+        Sv39PageTable::new().map_region(0x1000, 0x8000_1000, 0x1800, PTE_V | PTE_R);
+    }
+
+    #[test]
+    fn test_clone_full_same_translations() {
+        let mut pt = Sv39PageTable::new();
+        pt.map_page(0x1000, 0x8000_1000, PTE_V | PTE_R | PTE_U);
+        pt.map_superpage(0x4000_0000, 0x8020_0000, PTE_V | PTE_R | PTE_W);
+        pt.map_gigapage(0x8000_0000, 0xC000_0000, PTE_V | PTE_R | PTE_X);
+        let copy = pt.clone_full();
+        for va in [0x1abc, 0x4012_3456, 0x9234_5678, 0x2000, 0x4020_0000] {
+            assert_eq!(copy.translate(va), pt.translate(va), "va {va:#x}");
+            assert_eq!(copy.leaf(va), pt.leaf(va), "va {va:#x}");
+        }
+        // 根 + 两个 level-1 节点 + 一个 level-0 节点
+        assert_eq!(pt.node_count(), 4);
+        assert_eq!(copy.node_count(), 4);
+    }
+
+    #[test]
+    fn test_clone_full_fresh_ppns() {
+        let mut pt = Sv39PageTable::new();
+        pt.map_region(0x20_0000, 0x8040_0000, 5 << 20, PTE_V | PTE_R);
+        pt.map_page(0x40_0000_0000 - 0x1000, 0x9000_0000, PTE_V | PTE_R);
+        let copy = pt.clone_full();
+        assert_ne!(copy.root_ppn, pt.root_ppn);
+        let (a, b) = (pt.node_ppns(), copy.node_ppns());
+        assert_eq!(a.len(), b.len());
+        assert_with_hint!(
+            a.iter().all(|ppn| !b.contains(ppn)),
+            "original {a:x?} and copy {b:x?} share page table nodes"
+        );
+        assert!(b.contains(&copy.root_ppn));
+    }
+
+    #[test]
+    fn test_clone_full_is_independent() {
+        let mut pt = Sv39PageTable::new();
+        pt.map_page(0x1000, 0x8000_1000, PTE_V | PTE_R | PTE_W);
+        pt.map_page(0x2000, 0x8000_2000, PTE_V | PTE_R | PTE_W);
+        let mut child = pt.clone_full();
+
+        // 子进程改写已有映射、并在同一个 level-0 节点里新增映射
+        child.map_page(0x1000, 0x9000_0000, PTE_V | PTE_R);
+        child.map_page(0x3000, 0x9000_1000, PTE_V | PTE_R);
+        assert_eq_with_hint!(pt.translate(0x1000), TranslateResult::Ok(0x8000_1000));
+        assert_eq_with_hint!(pt.translate(0x3000), TranslateResult::PageFault);
+        assert_eq!(child.translate(0x1000), TranslateResult::Ok(0x9000_0000));
+
+        // 父进程的修改同样不影响子进程
+        pt.map_page(0x2000, 0xA000_0000, PTE_V | PTE_R);
+        pt.map_page(0x4000_0000, 0xA000_1000, PTE_V | PTE_R);
+        assert_eq!(child.translate(0x2000), TranslateResult::Ok(0x8000_2000));
+        assert_eq!(child.translate(0x4000_0000), TranslateResult::PageFault);
+        assert_eq!(pt.node_count(), 5);
+        assert_eq!(child.node_count(), 3);
+    }
+
+    #[test]
+    fn test_clone_full_empty_and_twice() {
+        let pt = Sv39PageTable::new();
+        let copy = pt.clone_full();
+        assert_eq!(copy.node_count(), 1);
+        assert_eq!(copy.translate(0x1000), TranslateResult::PageFault);
+
+        let mut pt = Sv39PageTable::new();
+        pt.map_page(0x5000, 0x8000_5000, PTE_V | PTE_R);
+        let grandchild = pt.clone_full().clone_full();
+        assert_eq!(
+            grandchild.translate(0x5123),
+            TranslateResult::Ok(0x8000_5123)
+        );
+        assert_eq!(grandchild.node_count(), 3);
+    }
+
+    /// `for_each_leaf` 的结果：(va 范围, ppn, flags, level)。
+    fn leaves(pt: &Sv39PageTable) -> Vec<(std::ops::Range<u64>, u64, u64, usize)> {
+        let mut out = Vec::new();
+        pt.for_each_leaf(|range, ppn, flags, level| out.push((range, ppn, flags, level)));
+        out
+    }
+
+    #[test]
+    fn test_for_each_leaf_va_order() {
+        let mut pt = Sv39PageTable::new();
+        // 故意乱序建立映射
+        pt.map_gigapage(0x8000_0000, 0xC000_0000, PTE_V | PTE_R | PTE_X);
+        pt.map_page(0x3000, 0x8000_3000, PTE_V | PTE_R | PTE_U);
+        pt.map_superpage(0x20_0000, 0x8020_0000, PTE_V | PTE_R | PTE_W);
+        pt.map_page(0x1000, 0x8000_1000, PTE_V | PTE_R);
+
+        assert_eq_with_hint!(
+            leaves(&pt),
+            vec![
+                (0x1000..0x2000, 0x80001, PTE_V | PTE_R, 0),
+                (0x3000..0x4000, 0x80003, PTE_V | PTE_R | PTE_U, 0),
+                (0x20_0000..0x40_0000, 0x80200, PTE_V | PTE_R | PTE_W, 1),
+                (0x8000_0000..0xC000_0000, 0xC0000, PTE_V | PTE_R | PTE_X, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_for_each_leaf_matches_map_region() {
+        let mut pt = Sv39PageTable::new();
+        let maps = pt.map_region(
+            0x3FDF_F000,
+            0x7FDF_F000,
+            0x1000 + 0x20_0000 + (1 << 30),
+            PTE_V | PTE_R,
+        );
+        let seen: Vec<(u64, u64, usize)> = leaves(&pt)
+            .into_iter()
+            .map(|(range, ppn, _, level)| (range.start, ppn << 12, level))
+            .collect();
+        let expected: Vec<(u64, u64, usize)> =
+            maps.iter().map(|m| (m.va, m.pa, m.size.level())).collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn test_count_pages_by_level() {
+        let mut pt = Sv39PageTable::new();
+        assert_eq!(pt.count_pages_by_level(), [0, 0, 0]);
+        pt.map_region(0x20_0000, 0x8040_0000, 5 << 20, PTE_V | PTE_R);
+        assert_eq_with_hint!(pt.count_pages_by_level(), [256, 2, 0]);
+        pt.map_superpage(0x4000_0000, 0x8000_0000, PTE_V | PTE_R);
+        pt.map_gigapage(0x8000_0000, 0x8000_0000, PTE_V | PTE_R);
+        assert_eq_with_hint!(pt.count_pages_by_level(), [256, 3, 1]);
+        // 深拷贝后结构不变
+        assert_eq!(pt.clone_full().count_pages_by_level(), [256, 3, 1]);
+    }
 }
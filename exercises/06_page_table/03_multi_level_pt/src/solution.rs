@@ -0,0 +1,396 @@
+//! # SV39 三级页表
+//!
+//! 本练习模拟 RISC-V SV39 三级页表的构造和地址翻译。
+//! 注意，实际上的三级页表实现并非如本练习中使用 HashMap 模拟，本练习仅作为模拟帮助学习。
+//! 你需要实现页表的创建、映射和地址翻译（页表遍历）。
+//!
+//! ## 知识点
+//! - SV39：39 位虚拟地址，三级页表
+//! - VPN 拆分：VPN[2] (9bit) | VPN[1] (9bit) | VPN[0] (9bit)
+//! - 页表遍历（page table walk）逐级查找
+//! - 大页（2MB superpage）映射
+//! - 区域映射：按对齐和剩余长度贪心地选 1GB / 2MB / 4KB 页
+//! - 深拷贝页表（fork 时立即复制，不共享任何页表节点）
+//! - 按虚拟地址顺序遍历所有叶子（`for_each_leaf`），检查页表的结构
+//!
+//! ## SV39 虚拟地址布局
+//! ```text
+//! 38        30 29       21 20       12 11        0
+//! ┌──────────┬───────────┬───────────┬───────────┐
+//! │ VPN[2]   │  VPN[1]   │  VPN[0]   │  offset   │
+//! │  9 bits  │  9 bits   │  9 bits   │  12 bits  │
+//! └──────────┴───────────┴───────────┴───────────┘
+//! ```
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// 页大小 4KB
+pub const PAGE_SIZE: usize = 4096;
+/// 每级页表有 512 个条目 (2^9)
+pub const PT_ENTRIES: usize = 512;
+
+/// PTE 标志位
+pub const PTE_V: u64 = 1 << 0;
+pub const PTE_R: u64 = 1 << 1;
+pub const PTE_W: u64 = 1 << 2;
+pub const PTE_X: u64 = 1 << 3;
+pub const PTE_U: u64 = 1 << 4;
+
+/// PPN 在 PTE 中的偏移
+const PPN_SHIFT: u32 = 10;
+
+/// 页表节点：一个包含 512 个条目的数组
+#[derive(Clone)]
+pub struct PageTableNode {
+    pub entries: [u64; PT_ENTRIES],
+}
+
+impl PageTableNode {
+    pub fn new() -> Self {
+        Self {
+            entries: [0; PT_ENTRIES],
+        }
+    }
+}
+
+impl Default for PageTableNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 模拟的三级页表。
+///
+/// 使用 HashMap<u64, PageTableNode> 模拟物理内存中的页表页。
+/// `root_ppn` 是根页表所在的物理页号。
+pub struct Sv39PageTable {
+    /// 物理页号 -> 页表节点
+    nodes: HashMap<u64, PageTableNode>,
+    /// 根页表的物理页号
+    pub root_ppn: u64,
+    /// 下一个可分配的物理页号（简易分配器）
+    next_ppn: u64,
+}
+
+/// 页的大小，也决定叶子 PTE 所在的级别。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PageSize {
+    /// 4KB，叶子在 level 0
+    Size4K,
+    /// 2MB 大页，叶子在 level 1
+    Size2M,
+    /// 1GB 大页，叶子在 level 2（根页表）
+    Size1G,
+}
+
+impl PageSize {
+    /// 页的字节数。
+    pub const fn bytes(self) -> u64 {
+        match self {
+            PageSize::Size4K => 1 << 12,
+            PageSize::Size2M => 1 << 21,
+            PageSize::Size1G => 1 << 30,
+        }
+    }
+
+    /// 叶子 PTE 所在的级别。
+    pub const fn level(self) -> usize {
+        match self {
+            PageSize::Size4K => 0,
+            PageSize::Size2M => 1,
+            PageSize::Size1G => 2,
+        }
+    }
+}
+
+/// `map_region` 建立的一个映射：从 `va` 开始的一页（大小为 `size`）映射到 `pa`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    pub va: u64,
+    pub pa: u64,
+    pub size: PageSize,
+}
+
+/// 翻译结果
+#[derive(Debug, PartialEq)]
+pub enum TranslateResult {
+    Ok(u64),
+    PageFault,
+}
+
+impl Sv39PageTable {
+    pub fn new() -> Self {
+        let mut pt = Self {
+            nodes: HashMap::new(),
+            root_ppn: 0x80000,
+            next_ppn: 0x80001,
+        };
+        pt.nodes.insert(pt.root_ppn, PageTableNode::new());
+        pt
+    }
+
+    /// 分配一个新的物理页并初始化为空页表节点，返回其 PPN。
+    fn alloc_node(&mut self) -> u64 {
+        let ppn = self.next_ppn;
+        self.next_ppn += 1;
+        self.nodes.insert(ppn, PageTableNode::new());
+        ppn
+    }
+
+    /// 从 39 位虚拟地址中提取第 `level` 级的 VPN。
+    ///
+    /// - level=2: 取 bits [38:30]
+    /// - level=1: 取 bits [29:21]
+    /// - level=0: 取 bits [20:12]
+    ///
+    /// 提示：右移 (12 + level * 9) 位，然后与 0x1FF 做掩码。
+    pub fn extract_vpn(va: u64, level: usize) -> usize {
+        ((va >> (12 + level * 9)) & 0x1ff) as usize
+    }
+
+    /// 建立从虚拟页到物理页的映射（4KB 页）。
+    ///
+    /// 参数：
+    /// - `va`: 虚拟地址（会自动对齐到页边界）
+    /// - `pa`: 物理地址（会自动对齐到页边界）
+    /// - `flags`: 标志位（如 PTE_V | PTE_R | PTE_W）
+    pub fn map_page(&mut self, va: u64, pa: u64, flags: u64) {
+        let node = self.walk_create(va, 0);
+        let idx = Self::extract_vpn(va, 0);
+        self.nodes.get_mut(&node).unwrap().entries[idx] = ((pa >> 12) << PPN_SHIFT) | flags;
+    }
+
+    /// 遍历三级页表，将虚拟地址翻译为物理地址。
+    ///
+    /// 步骤：
+    /// 1. 从根页表（root_ppn）开始
+    /// 2. 对每一级（2, 1, 0）：
+    ///    a. 用 VPN[level] 索引当前页表节点
+    ///    b. 如果 PTE 无效（!PTE_V），返回 PageFault
+    ///    c. 如果 PTE 是叶节点（R|W|X 有任一置位），提取 PPN 计算物理地址
+    ///    d. 否则用 PTE 中的 PPN 进入下一级页表
+    /// 3. level 0 的 PTE 必须是叶节点
+    pub fn translate(&self, va: u64) -> TranslateResult {
+        let mut ppn = self.root_ppn;
+        for level in (0..3).rev() {
+            let pte = self.nodes[&ppn].entries[Self::extract_vpn(va, level)];
+            if pte & PTE_V == 0 {
+                return TranslateResult::PageFault;
+            }
+            let next = pte >> PPN_SHIFT;
+            if pte & (PTE_R | PTE_W | PTE_X) != 0 {
+                let offset_bits = 12 + level * 9;
+                let offset = va & ((1u64 << offset_bits) - 1);
+                return TranslateResult::Ok((next << 12) + offset);
+            }
+            if level == 0 {
+                return TranslateResult::PageFault;
+            }
+            ppn = next;
+        }
+        TranslateResult::PageFault
+    }
+
+    /// 遍历页表，返回 `va` 所在页的叶子 PTE（4KB 页在 level 0，2MB 大页在 level 1，1GB 大页在 level 2），
+    /// 未映射时返回 `None`。
+    ///
+    /// 与 `translate` 走同样的路径，但返回 PTE 本身：调用者据此读取标志位
+    /// （PTE_U / PTE_R / PTE_W / PTE_X）做权限检查，或用 `pte >> 10` 取得物理页号。
+    pub fn leaf(&self, va: u64) -> Option<u64> {
+        let mut ppn = self.root_ppn;
+        for level in (0..3).rev() {
+            let pte = self.nodes[&ppn].entries[Self::extract_vpn(va, level)];
+            if pte & PTE_V == 0 {
+                return None;
+            }
+            if pte & (PTE_R | PTE_W | PTE_X) != 0 {
+                return Some(pte);
+            }
+            ppn = pte >> PPN_SHIFT;
+        }
+        None
+    }
+
+    /// 建立大页映射（2MB superpage，在 level 1 设叶子 PTE）。
+    ///
+    /// 2MB = 512 × 4KB，对齐要求：va 和 pa 都必须 2MB 对齐。
+    ///
+    /// 与 map_page 类似，但只遍历到 level 1 就写入叶子 PTE。
+    pub fn map_superpage(&mut self, va: u64, pa: u64, flags: u64) {
+        let mega_size: u64 = (PAGE_SIZE * PT_ENTRIES) as u64; // 2MB
+        assert_eq!(va % mega_size, 0, "va must be 2MB-aligned");
+        assert_eq!(pa % mega_size, 0, "pa must be 2MB-aligned");
+
+        let node = self.walk_create(va, 1);
+        let idx = Self::extract_vpn(va, 1);
+        self.nodes.get_mut(&node).unwrap().entries[idx] = ((pa >> 12) << PPN_SHIFT) | flags;
+    }
+
+    /// 建立 1GB 大页映射（在 level 2，也就是根页表中设叶子 PTE）。
+    ///
+    /// 对齐要求：va 和 pa 都必须 1GB 对齐。
+    pub fn map_gigapage(&mut self, va: u64, pa: u64, flags: u64) {
+        let giga_size = PageSize::Size1G.bytes();
+        assert_eq!(va % giga_size, 0, "va must be 1GB-aligned");
+        assert_eq!(pa % giga_size, 0, "pa must be 1GB-aligned");
+
+        let node = self.walk_create(va, 2);
+        let idx = Self::extract_vpn(va, 2);
+        self.nodes.get_mut(&node).unwrap().entries[idx] = ((pa >> 12) << PPN_SHIFT) | flags;
+    }
+
+    /// 把 `[va, va + len)` 映射到 `[pa, pa + len)`，自动选择页大小，返回按地址顺序建立的映射。
+    ///
+    /// 贪心：每一步在剩余区域的开头选能用的最大页——va 和 pa 都按该页大小对齐、
+    /// 且剩余长度至少一页——1GB 优先，其次 2MB，最后 4KB。
+    /// 例如 2MB 对齐的 5MB 区域是两个 2MB 大页加 256 个 4KB 页。
+    ///
+    /// `va`、`pa`、`len` 都必须 4KB 对齐。
+    pub fn map_region(&mut self, va: u64, pa: u64, len: u64, flags: u64) -> Vec<Mapping> {
+        let page = PAGE_SIZE as u64;
+        assert!(
+            va.is_multiple_of(page) && pa.is_multiple_of(page) && len.is_multiple_of(page),
+            "va, pa and len must be 4KB-aligned"
+        );
+
+        let mut mappings = Vec::new();
+        let mut offset = 0;
+        while offset < len {
+            let (v, p) = (va + offset, pa + offset);
+            let size = [PageSize::Size1G, PageSize::Size2M, PageSize::Size4K]
+                .into_iter()
+                .find(|s| {
+                    let bytes = s.bytes();
+                    v.is_multiple_of(bytes) && p.is_multiple_of(bytes) && len - offset >= bytes
+                })
+                .unwrap();
+            match size {
+                PageSize::Size1G => self.map_gigapage(v, p, flags),
+                PageSize::Size2M => self.map_superpage(v, p, flags),
+                PageSize::Size4K => self.map_page(v, p, flags),
+            }
+            mappings.push(Mapping { va: v, pa: p, size });
+            offset += size.bytes();
+        }
+        mappings
+    }
+
+    /// 页表节点（包括根）的数量。
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 所有页表节点的 PPN，升序。
+    pub fn node_ppns(&self) -> Vec<u64> {
+        let mut ppns: Vec<u64> = self.nodes.keys().copied().collect();
+        ppns.sort_unstable();
+        ppns
+    }
+
+    /// 深拷贝整个页表，相当于不做写时复制、立即复制一切的 fork。
+    ///
+    /// 副本的每个页表节点（包括根）都是新分配的，PPN 从原页表的 `next_ppn` 开始往后取，
+    /// 所以拷贝完成时两棵树没有共用的节点：之后修改其中一个的映射不会影响另一个。
+    /// 叶子 PTE 原样复制（本模拟里没有数据页；真正的 fork 还要复制叶子指向的物理页）。
+    pub fn clone_full(&self) -> Self {
+        let mut copy = Self {
+            nodes: HashMap::new(),
+            root_ppn: self.next_ppn,
+            next_ppn: self.next_ppn + 1,
+        };
+        copy.nodes.insert(copy.root_ppn, PageTableNode::new());
+        let root = copy.root_ppn;
+        self.copy_node(self.root_ppn, &mut copy, root);
+        copy
+    }
+
+    /// 按虚拟地址从低到高遍历每个叶子 PTE，对每个叶子调用
+    /// `f(va_range, ppn, flags, level)`：
+    /// - `va_range`：该叶子映射的虚拟地址范围，长度为 4KB / 2MB / 1GB（level 0 / 1 / 2）
+    /// - `ppn`：叶子 PTE 中的物理页号（`pte >> 10`）
+    /// - `flags`：PTE 的低 10 位标志位
+    /// - `level`：叶子所在的级别
+    ///
+    /// 虚拟地址只取低 39 位（与 `extract_vpn` 一致，不做高位符号扩展）。
+    pub fn for_each_leaf(&self, mut f: impl FnMut(Range<u64>, u64, u64, usize)) {
+        self.visit_leaves(self.root_ppn, 2, 0, &mut f);
+    }
+
+    /// 每一级叶子的数量：`[level 0 (4KB), level 1 (2MB), level 2 (1GB)]`。
+    pub fn count_pages_by_level(&self) -> [usize; 3] {
+        let mut counts = [0; 3];
+        self.for_each_leaf(|_, _, _, level| counts[level] += 1);
+        counts
+    }
+}
+
+impl Sv39PageTable {
+    /// Walk from the root down to the node holding the level-`leaf_level` PTE of `va`,
+    /// allocating missing intermediate nodes. Returns that node's PPN.
+    fn walk_create(&mut self, va: u64, leaf_level: usize) -> u64 {
+        let mut ppn = self.root_ppn;
+        for level in (leaf_level + 1..3).rev() {
+            let idx = Self::extract_vpn(va, level);
+            let pte = self.nodes[&ppn].entries[idx];
+            ppn = if pte & PTE_V != 0 {
+                pte >> PPN_SHIFT
+            } else {
+                let child = self.alloc_node();
+                self.nodes.get_mut(&ppn).unwrap().entries[idx] = (child << PPN_SHIFT) | PTE_V;
+                child
+            };
+        }
+        ppn
+    }
+
+    /// Copy node `src` of `self` into node `dst` of `copy`, giving every child table a fresh
+    /// node in `copy`.
+    fn copy_node(&self, src: u64, copy: &mut Self, dst: u64) {
+        let flag_mask = (1 << PPN_SHIFT) - 1;
+        for (idx, &pte) in self.nodes[&src].entries.iter().enumerate() {
+            let new_pte = if pte & PTE_V != 0 && pte & (PTE_R | PTE_W | PTE_X) == 0 {
+                let child = copy.alloc_node();
+                self.copy_node(pte >> PPN_SHIFT, copy, child);
+                (child << PPN_SHIFT) | (pte & flag_mask)
+            } else {
+                pte
+            };
+            copy.nodes.get_mut(&dst).unwrap().entries[idx] = new_pte;
+        }
+    }
+
+    /// Call `f` for every leaf below node `ppn`, which sits at `level` and covers the VAs from
+    /// `base`.
+    fn visit_leaves(
+        &self,
+        ppn: u64,
+        level: usize,
+        base: u64,
+        f: &mut impl FnMut(Range<u64>, u64, u64, usize),
+    ) {
+        let span = 1u64 << (12 + 9 * level);
+        for (idx, &pte) in self.nodes[&ppn].entries.iter().enumerate() {
+            if pte & PTE_V == 0 {
+                continue;
+            }
+            let va = base + idx as u64 * span;
+            if pte & (PTE_R | PTE_W | PTE_X) != 0 {
+                f(
+                    va..va + span,
+                    pte >> PPN_SHIFT,
+                    pte & ((1 << PPN_SHIFT) - 1),
+                    level,
+                );
+            } else if level > 0 {
+                self.visit_leaves(pte >> PPN_SHIFT, level - 1, va, f);
+            }
+        }
+    }
+}
+
+impl Default for Sv39PageTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
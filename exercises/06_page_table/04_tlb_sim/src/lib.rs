@@ -5,11 +5,12 @@
 //!
 //! ## 知识点
 //! - TLB 是页表的硬件缓存，加速虚拟地址翻译
-//! - TLB 命中/未命中（hit/miss）
+//! - TLB 命中/未命中（hit/miss），按 ASID 与虚拟页拆分统计，找出颠簸（thrashing）的进程和区域
 //! - TLB 替换策略（本练习使用 FIFO）
 //! - TLB 刷新：全部刷新、按虚拟页刷新、按 ASID 刷新
 //! - ASID（Address Space Identifier）区分不同进程的地址空间
 //! - MMU 工作流程：先查 TLB，miss 则走页表，再回填 TLB
+//! - 带数据的访存：翻译后检查权限、置 A/D 位，再读写模拟的物理内存
 //!
 //! ## TLB 条目结构
 //! ```text
@@ -18,6 +19,20 @@
 //! └───────┴──────┴──────┴───────┴───────┘
 //! ```
 
+use std::collections::HashMap;
+
+/// 页大小
+pub const PAGE_SIZE: usize = 4096;
+pub const PAGE_SHIFT: u32 = 12;
+
+/// 页表项标志位（与 SV39 相同的位置）
+pub const PTE_V: u64 = 1 << 0;
+pub const PTE_R: u64 = 1 << 1;
+pub const PTE_W: u64 = 1 << 2;
+pub const PTE_X: u64 = 1 << 3;
+pub const PTE_A: u64 = 1 << 6;
+pub const PTE_D: u64 = 1 << 7;
+
 /// TLB 条目
 #[derive(Clone, Debug)]
 pub struct TlbEntry {
@@ -40,21 +55,68 @@ impl TlbEntry {
     }
 }
 
-/// TLB 统计信息
+/// 单个地址空间（ASID）的命中/未命中次数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AsidStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// TLB 统计信息：总数，以及按 ASID、按虚拟页的拆分。
 #[derive(Debug, Default)]
 pub struct TlbStats {
     pub hits: u64,
     pub misses: u64,
+    per_asid: HashMap<u16, AsidStats>,
+    /// vpn -> 未命中次数（不区分 ASID）
+    vpn_misses: HashMap<u64, u64>,
+}
+
+fn hit_rate(hits: u64, misses: u64) -> f64 {
+    let total = hits + misses;
+    if total == 0 {
+        0.0
+    } else {
+        hits as f64 / total as f64
+    }
+}
+
+impl AsidStats {
+    pub fn hit_rate(&self) -> f64 {
+        hit_rate(self.hits, self.misses)
+    }
 }
 
 impl TlbStats {
     pub fn hit_rate(&self) -> f64 {
-        let total = self.hits + self.misses;
-        if total == 0 {
-            0.0
-        } else {
-            self.hits as f64 / total as f64
-        }
+        hit_rate(self.hits, self.misses)
+    }
+
+    /// 记录一次以 (`vpn`, `asid`) 进行的 TLB 查找：更新总数与该 ASID 的计数，
+    /// 未命中时还要给 `vpn` 的未命中次数加一。
+    #[allow(unused_variables)]
+    pub fn record(&mut self, asid: u16, vpn: u64, hit: bool) {
+        // TODO: 更新总数、per_asid[asid]，未命中时再给 vpn_misses[vpn] 加一
+        todo!()
+    }
+
+    /// `asid` 的命中/未命中次数；从未查找过的 ASID 为全零。
+    pub fn asid(&self, asid: u16) -> AsidStats {
+        self.per_asid.get(&asid).copied().unwrap_or_default()
+    }
+
+    /// 所有出现过的 ASID 及其计数，按 ASID 升序。各项之和等于 `hits` / `misses`。
+    pub fn per_asid(&self) -> Vec<(u16, AsidStats)> {
+        let mut v: Vec<_> = self.per_asid.iter().map(|(&a, &s)| (a, s)).collect();
+        v.sort_by_key(|&(a, _)| a);
+        v
+    }
+
+    /// 未命中最多的 `n` 个虚拟页：`(vpn, misses)`，按未命中次数降序，次数相同按 vpn 升序。
+    #[allow(unused_variables)]
+    pub fn top_missing_vpns(&self, n: usize) -> Vec<(u64, u64)> {
+        // TODO: 收集 vpn_misses，按未命中次数降序、vpn 升序排序，取前 n 个
+        todo!()
     }
 }
 
@@ -84,7 +146,7 @@ impl Tlb {
     /// - 遍历所有条目
     /// - 条目必须 `valid == true`
     /// - 条目的 `vpn` 和 `asid` 都必须匹配
-    /// - 命中时增加 `stats.hits`，未命中增加 `stats.misses`
+    /// - 用 `stats.record(asid, vpn, hit)` 记录这次查找（命中与否都要记录）
     ///
     /// 返回匹配条目的 `ppn`，未命中返回 None。
     pub fn lookup(&mut self, vpn: u64, asid: u16) -> Option<u64> {
@@ -149,7 +211,16 @@ pub struct PageMapping {
     pub flags: u64,
 }
 
-/// 模拟的 MMU：包含 TLB 和一个简单的页表。
+/// 带数据访存的错误，携带出错的虚拟地址。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemFault {
+    /// 当前 ASID 下该页未映射，或映射的 PTE_V 未置位
+    PageFault(u64),
+    /// 读缺 PTE_R 或写缺 PTE_W
+    PermissionDenied(u64),
+}
+
+/// 模拟的 MMU：包含 TLB、一个简单的页表和模拟的物理内存。
 ///
 /// MMU 翻译流程：
 /// 1. 先查 TLB（lookup）
@@ -162,6 +233,8 @@ pub struct Mmu {
     /// 简化的页表：(vpn, asid) -> PageMapping
     page_table: Vec<(u16, PageMapping)>,
     pub current_asid: u16,
+    /// 模拟的物理内存：ppn -> 页帧内容，第一次写入时按全零创建
+    memory: HashMap<u64, [u8; PAGE_SIZE]>,
 }
 
 impl Mmu {
@@ -170,6 +243,7 @@ impl Mmu {
             tlb: Tlb::new(tlb_capacity),
             page_table: Vec::new(),
             current_asid: 0,
+            memory: HashMap::new(),
         }
     }
 
@@ -196,11 +270,116 @@ impl Mmu {
         // TODO: 实现 TLB + 页表的二级查找
         todo!()
     }
+
+    /// 以读（`is_write == false`）或写方式访问虚拟地址 `va`，返回物理地址。
+    ///
+    /// 流程：
+    /// 1. `translate(va >> PAGE_SHIFT)` 得到 ppn（经过 TLB，计入命中率），缺页返回 `PageFault`
+    /// 2. 找到页表中 (current_asid, vpn) 的映射：PTE_V 未置位返回 `PageFault`，
+    ///    读缺 PTE_R、写缺 PTE_W 返回 `PermissionDenied`
+    /// 3. 访问成功才更新标志：置 PTE_A，写访问再置 PTE_D，并用 `tlb.insert`
+    ///    把新标志同步到 TLB 中的副本（真实硬件在 TLB 命中时检查缓存的权限，
+    ///    遇到 D 未置位的写再回页表置位；这里简化为始终以页表为准）
+    /// 4. 物理地址 = (ppn << PAGE_SHIFT) | 页内偏移
+    #[allow(unused_variables)]
+    pub fn access(&mut self, va: u64, is_write: bool) -> Result<u64, MemFault> {
+        // TODO: translate 得到 ppn，再在页表中找到 (current_asid, vpn) 的映射，
+        //       检查 V 与 R/W，置 A（写还要置 D），用 tlb.insert 刷新 TLB 中的副本
+        todo!()
+    }
+
+    /// 读取当前地址空间中 `[va, va + len)` 的数据，可以跨页。
+    ///
+    /// 每一页单独 `access`，任何一页出错就返回该页第一个字节的地址。
+    /// 从未写过的物理页读出全零。
+    #[allow(unused_variables)]
+    pub fn read(&mut self, va: u64, len: usize) -> Result<Vec<u8>, MemFault> {
+        // TODO: 按页切分 [va, va + len)，每一段用 access 得到物理地址再拷贝
+        todo!()
+    }
+
+    /// 把 `bytes` 写到当前地址空间的 `va` 处，可以跨页。
+    ///
+    /// 与 `read` 一样逐页处理：出错时前面的页已经写入（和一条条执行的 store 一样）。
+    #[allow(unused_variables)]
+    pub fn write(&mut self, va: u64, bytes: &[u8]) -> Result<(), MemFault> {
+        // TODO: 与 read 相同的切分；页帧不存在时用 [0; PAGE_SIZE] 创建
+        todo!()
+    }
+
+    /// 页表中 (asid, vpn) 映射当前的标志位（包括 A/D），未映射返回 None。
+    #[allow(unused_variables)]
+    pub fn mapping_flags(&self, asid: u16, vpn: u64) -> Option<u64> {
+        // TODO: 在页表中查找 (asid, vpn)
+        todo!()
+    }
+
+    /// 物理页 `ppn` 的内容；从未写过的页返回 None。
+    #[allow(unused_variables)]
+    pub fn frame(&self, ppn: u64) -> Option<&[u8; PAGE_SIZE]> {
+        // TODO: 返回 memory 中的页帧
+        todo!()
+    }
 }
 
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
     use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_tlb_miss_wrong_asid",
+            "an entry only matches if it is valid AND both vpn and asid are equal",
+        ),
+        (
+            "test_tlb_fifo_eviction",
+            "write the new entry at fifo_ptr, then advance it: fifo_ptr = (fifo_ptr + 1) % capacity",
+        ),
+        (
+            "test_tlb_update_existing",
+            "look for a valid entry with the same (vpn, asid) first and update it in place, without touching fifo_ptr",
+        ),
+        (
+            "test_flush_by_vpn",
+            "sfence.vma vaddr flushes the page in every address space: compare only the vpn, not the asid",
+        ),
+        (
+            "test_mmu_basic_translate",
+            "on a TLB miss, walk page_table for (current_asid, vpn) and insert the result into the TLB so the next access hits",
+        ),
+        (
+            "test_mmu_shared_frame_across_asids",
+            "read/write go through access(), which turns the VA into (ppn << 12) | offset; two ASIDs mapping the same ppn share one frame in memory",
+        ),
+        (
+            "test_mmu_write_read_only",
+            "a write needs PTE_W; on PermissionDenied neither memory nor the A/D bits may change",
+        ),
+        (
+            "test_mmu_accessed_dirty",
+            "a successful access sets PTE_A in the page table mapping, a successful write also PTE_D",
+        ),
+        (
+            "test_mmu_cross_page",
+            "split [va, va + len) at page boundaries and access each page separately",
+        ),
+        (
+            "test_stats_per_asid_sums",
+            "lookup must call stats.record for every lookup; record bumps the totals and per_asid[asid] together, so they always add up",
+        ),
+        (
+            "test_top_missing_vpns",
+            "count misses per vpn in vpn_misses, then sort by misses descending, vpn ascending, and keep the first n",
+        ),
+    ];
 
     // ──────── TLB 基础测试 ────────
 
@@ -237,8 +416,8 @@ mod tests {
         tlb.insert(0x100, 0x200, 1, 0x7);
 
         // ASID 不匹配应该 miss
-        assert_eq!(tlb.lookup(0x100, 99), None);
-        assert_eq!(tlb.stats.misses, 1);
+        assert_eq_with_hint!(tlb.lookup(0x100, 99), None);
+        assert_eq_with_hint!(tlb.stats.misses, 1);
     }
 
     #[test]
@@ -250,10 +429,10 @@ mod tests {
         tlb.insert(0x50, 0x60, 0, 0x7);
 
         // 0x10 应该被淘汰
-        assert_eq!(tlb.lookup(0x10, 0), None);
+        assert_eq_with_hint!(tlb.lookup(0x10, 0), None);
         // 0x30 和 0x50 应该还在
-        assert_eq!(tlb.lookup(0x30, 0), Some(0x40));
-        assert_eq!(tlb.lookup(0x50, 0), Some(0x60));
+        assert_eq_with_hint!(tlb.lookup(0x30, 0), Some(0x40));
+        assert_eq_with_hint!(tlb.lookup(0x50, 0), Some(0x60));
     }
 
     #[test]
@@ -262,8 +441,8 @@ mod tests {
         tlb.insert(0x100, 0x200, 1, 0x3);
         tlb.insert(0x100, 0x999, 1, 0x7); // 更新同一条目
 
-        assert_eq!(tlb.lookup(0x100, 1), Some(0x999));
-        assert_eq!(tlb.valid_count(), 1); // 不应该多出一条
+        assert_eq_with_hint!(tlb.lookup(0x100, 1), Some(0x999));
+        assert_eq_with_hint!(tlb.valid_count(), 1); // 不应该多出一条
     }
 
     #[test]
@@ -303,10 +482,10 @@ mod tests {
         tlb.flush_by_vpn(0x100);
 
         // VPN=0x100 的两条都应被刷掉
-        assert_eq!(tlb.lookup(0x100, 1), None);
-        assert_eq!(tlb.lookup(0x100, 2), None);
+        assert_eq_with_hint!(tlb.lookup(0x100, 1), None);
+        assert_eq_with_hint!(tlb.lookup(0x100, 2), None);
         // VPN=0x999 不受影响
-        assert_eq!(tlb.lookup(0x999, 1), Some(0x400));
+        assert_eq_with_hint!(tlb.lookup(0x999, 1), Some(0x400));
     }
 
     #[test]
@@ -347,14 +526,14 @@ mod tests {
 
         // 第一次：TLB miss，走页表
         let ppn = mmu.translate(0x100);
-        assert_eq!(ppn, Some(0x200));
-        assert_eq!(mmu.tlb.stats.misses, 1);
-        assert_eq!(mmu.tlb.stats.hits, 0);
+        assert_eq_with_hint!(ppn, Some(0x200));
+        assert_eq_with_hint!(mmu.tlb.stats.misses, 1);
+        assert_eq_with_hint!(mmu.tlb.stats.hits, 0);
 
         // 第二次：TLB hit
         let ppn = mmu.translate(0x100);
-        assert_eq!(ppn, Some(0x200));
-        assert_eq!(mmu.tlb.stats.hits, 1);
+        assert_eq_with_hint!(ppn, Some(0x200));
+        assert_eq_with_hint!(mmu.tlb.stats.hits, 1);
     }
 
     #[test]
@@ -438,4 +617,171 @@ mod tests {
         assert_eq!(mmu.tlb.stats.misses, 6);
         assert_eq!(mmu.tlb.stats.hits, 0);
     }
+
+    // ──────── 带数据的访存 ────────
+
+    const RW: u64 = PTE_V | PTE_R | PTE_W;
+
+    #[test]
+    fn test_mmu_shared_frame_across_asids() {
+        let mut mmu = Mmu::new(4);
+        // 两个地址空间把不同的虚拟页映射到同一个物理页 0x80
+        mmu.add_mapping(1, 0x10, 0x80, RW);
+        mmu.add_mapping(2, 0x20, 0x80, PTE_V | PTE_R);
+
+        mmu.switch_asid(1);
+        assert_eq_with_hint!(mmu.write(0x10_123, b"hello, shm"), Ok(()));
+
+        mmu.switch_asid(2);
+        assert_eq_with_hint!(mmu.read(0x20_123, 10).as_deref(), Ok(&b"hello, shm"[..]));
+        assert_eq!(&mmu.frame(0x80).unwrap()[0x123..0x12d], b"hello, shm");
+        // 物理地址与 ASID 无关
+        assert_eq!(mmu.access(0x20_123, false), Ok(0x80_123));
+    }
+
+    #[test]
+    fn test_mmu_write_read_only() {
+        let mut mmu = Mmu::new(4);
+        mmu.add_mapping(0, 0x1, 0x10, PTE_V | PTE_R);
+
+        assert_eq_with_hint!(
+            mmu.write(0x1008, &[1, 2, 3]),
+            Err(MemFault::PermissionDenied(0x1008))
+        );
+        assert_eq_with_hint!(mmu.frame(0x10), None);
+        assert_eq_with_hint!(mmu.mapping_flags(0, 0x1), Some(PTE_V | PTE_R));
+        // 读是允许的，未写过的页读出全零
+        assert_eq!(mmu.read(0x1008, 3), Ok(vec![0, 0, 0]));
+
+        // 缺页与 V 未置位
+        mmu.add_mapping(0, 0x2, 0x20, PTE_R | PTE_W);
+        assert_eq!(mmu.read(0x5000, 1), Err(MemFault::PageFault(0x5000)));
+        assert_eq!(mmu.write(0x2000, &[1]), Err(MemFault::PageFault(0x2000)));
+    }
+
+    #[test]
+    fn test_mmu_accessed_dirty() {
+        let mut mmu = Mmu::new(4);
+        mmu.add_mapping(0, 0x1, 0x10, RW);
+        mmu.add_mapping(0, 0x2, 0x20, RW);
+
+        mmu.read(0x1000, 4).unwrap();
+        assert_eq_with_hint!(mmu.mapping_flags(0, 0x1), Some(RW | PTE_A));
+        assert_eq_with_hint!(mmu.mapping_flags(0, 0x2), Some(RW));
+
+        // 第二次访问命中 TLB，也要置 D
+        mmu.write(0x1000, &[0xff]).unwrap();
+        assert_eq_with_hint!(mmu.mapping_flags(0, 0x1), Some(RW | PTE_A | PTE_D));
+        assert_eq!(mmu.tlb.stats.hits, 1);
+        assert_eq!(mmu.mapping_flags(0, 0x2), Some(RW));
+        assert_eq!(mmu.mapping_flags(0, 0x3), None);
+    }
+
+    #[test]
+    fn test_mmu_cross_page() {
+        let mut mmu = Mmu::new(4);
+        mmu.add_mapping(0, 0x1, 0x31, RW);
+        mmu.add_mapping(0, 0x2, 0x17, RW);
+
+        let data: Vec<u8> = (0..=255).collect();
+        // 跨越 0x1000 / 0x2000 页边界，两页的物理页不相邻
+        assert_eq_with_hint!(mmu.write(0x1f80, &data), Ok(()));
+        assert_eq_with_hint!(mmu.read(0x1f80, 256), Ok(data.clone()));
+        assert_eq!(&mmu.frame(0x31).unwrap()[0xf80..], &data[..128]);
+        assert_eq!(&mmu.frame(0x17).unwrap()[..128], &data[128..]);
+
+        // 第二页未映射：报告第二页的起始地址，第一页已写入
+        mmu.add_mapping(0, 0x5, 0x50, RW);
+        assert_eq!(mmu.write(0x5ffe, &[7; 4]), Err(MemFault::PageFault(0x6000)));
+        assert_eq!(mmu.read(0x5ffe, 2), Ok(vec![7, 7]));
+        assert_eq!(mmu.read(0x5000, 0), Ok(vec![]));
+    }
+
+    // ──────── 分项统计 ────────
+
+    #[test]
+    fn test_stats_per_asid_sums() {
+        let mut mmu = Mmu::new(4);
+        // ASID 1：每轮连续访问同一个页 3 次
+        mmu.add_mapping(1, 0x0, 0x100, RW);
+        // ASID 2：5 个页循环访问，超过 TLB 容量
+        for vpn in 0x40..0x45 {
+            mmu.add_mapping(2, vpn, 0x200 + vpn, RW);
+        }
+        for _ in 0..6 {
+            mmu.switch_asid(1);
+            for _ in 0..3 {
+                mmu.translate(0x0);
+            }
+            mmu.switch_asid(2);
+            for vpn in 0x40..0x45 {
+                mmu.translate(vpn);
+            }
+        }
+
+        let stats = &mmu.tlb.stats;
+        let per_asid = stats.per_asid();
+        assert_eq_with_hint!(
+            per_asid.iter().map(|(a, _)| *a).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq_with_hint!(
+            per_asid.iter().map(|(_, s)| s.hits).sum::<u64>(),
+            stats.hits
+        );
+        assert_eq_with_hint!(
+            per_asid.iter().map(|(_, s)| s.misses).sum::<u64>(),
+            stats.misses
+        );
+        assert_eq!(stats.hits + stats.misses, 48);
+
+        // ASID 2 在颠簸：一次都没命中，还把 ASID 1 的页挤了出去
+        assert_eq_with_hint!(
+            stats.asid(2),
+            AsidStats {
+                hits: 0,
+                misses: 30
+            }
+        );
+        assert_eq_with_hint!(
+            stats.asid(1),
+            AsidStats {
+                hits: 12,
+                misses: 6
+            }
+        );
+        assert_eq!(stats.asid(7), AsidStats::default());
+    }
+
+    #[test]
+    fn test_top_missing_vpns() {
+        let mut tlb = Tlb::new(2);
+        assert_eq!(tlb.stats.top_missing_vpns(3), vec![]);
+
+        // 两个 ASID 的同一个 vpn 合并计数
+        tlb.lookup(0x10, 1);
+        tlb.lookup(0x10, 2);
+        tlb.lookup(0x10, 2);
+        tlb.lookup(0x30, 1);
+        tlb.lookup(0x20, 1);
+        tlb.lookup(0x20, 1);
+        tlb.insert(0x99, 0x1, 1, 0x7);
+        for _ in 0..5 {
+            tlb.lookup(0x99, 1); // 命中不算
+        }
+
+        assert_eq_with_hint!(tlb.stats.top_missing_vpns(2), vec![(0x10, 3), (0x20, 2)]);
+        assert_eq_with_hint!(
+            tlb.stats.top_missing_vpns(10),
+            vec![(0x10, 3), (0x20, 2), (0x30, 1)]
+        );
+        let total: u64 = tlb
+            .stats
+            .top_missing_vpns(usize::MAX)
+            .iter()
+            .map(|(_, m)| m)
+            .sum();
+        assert_eq!(total, tlb.stats.misses);
+        assert_eq!(tlb.stats.asid(1), AsidStats { hits: 5, misses: 4 });
+    }
 }
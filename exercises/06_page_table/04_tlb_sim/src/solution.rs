@@ -0,0 +1,409 @@
+//! # TLB 模拟与刷新
+//!
+//! 本练习模拟 TLB（Translation Lookaside Buffer，地址翻译后备缓冲区），
+//! 帮助你理解 TLB 的查找、插入、替换和刷新机制。
+//!
+//! ## 知识点
+//! - TLB 是页表的硬件缓存，加速虚拟地址翻译
+//! - TLB 命中/未命中（hit/miss），按 ASID 与虚拟页拆分统计，找出颠簸（thrashing）的进程和区域
+//! - TLB 替换策略（本练习使用 FIFO）
+//! - TLB 刷新：全部刷新、按虚拟页刷新、按 ASID 刷新
+//! - ASID（Address Space Identifier）区分不同进程的地址空间
+//! - MMU 工作流程：先查 TLB，miss 则走页表，再回填 TLB
+//! - 带数据的访存：翻译后检查权限、置 A/D 位，再读写模拟的物理内存
+//!
+//! ## TLB 条目结构
+//! ```text
+//! ┌───────┬──────┬──────┬───────┬───────┐
+//! │ valid │ asid │ vpn  │  ppn  │ flags │
+//! └───────┴──────┴──────┴───────┴───────┘
+//! ```
+
+use std::collections::HashMap;
+
+/// 页大小
+pub const PAGE_SIZE: usize = 4096;
+pub const PAGE_SHIFT: u32 = 12;
+
+/// 页表项标志位（与 SV39 相同的位置）
+pub const PTE_V: u64 = 1 << 0;
+pub const PTE_R: u64 = 1 << 1;
+pub const PTE_W: u64 = 1 << 2;
+pub const PTE_X: u64 = 1 << 3;
+pub const PTE_A: u64 = 1 << 6;
+pub const PTE_D: u64 = 1 << 7;
+
+/// TLB 条目
+#[derive(Clone, Debug)]
+pub struct TlbEntry {
+    pub valid: bool,
+    pub asid: u16,
+    pub vpn: u64,
+    pub ppn: u64,
+    pub flags: u64,
+}
+
+impl TlbEntry {
+    pub fn empty() -> Self {
+        Self {
+            valid: false,
+            asid: 0,
+            vpn: 0,
+            ppn: 0,
+            flags: 0,
+        }
+    }
+}
+
+/// 单个地址空间（ASID）的命中/未命中次数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AsidStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// TLB 统计信息：总数，以及按 ASID、按虚拟页的拆分。
+#[derive(Debug, Default)]
+pub struct TlbStats {
+    pub hits: u64,
+    pub misses: u64,
+    per_asid: HashMap<u16, AsidStats>,
+    /// vpn -> 未命中次数（不区分 ASID）
+    vpn_misses: HashMap<u64, u64>,
+}
+
+fn hit_rate(hits: u64, misses: u64) -> f64 {
+    let total = hits + misses;
+    if total == 0 {
+        0.0
+    } else {
+        hits as f64 / total as f64
+    }
+}
+
+impl AsidStats {
+    pub fn hit_rate(&self) -> f64 {
+        hit_rate(self.hits, self.misses)
+    }
+}
+
+impl TlbStats {
+    pub fn hit_rate(&self) -> f64 {
+        hit_rate(self.hits, self.misses)
+    }
+
+    /// 记录一次以 (`vpn`, `asid`) 进行的 TLB 查找：更新总数与该 ASID 的计数，
+    /// 未命中时还要给 `vpn` 的未命中次数加一。
+    pub fn record(&mut self, asid: u16, vpn: u64, hit: bool) {
+        let per_asid = self.per_asid.entry(asid).or_default();
+        if hit {
+            self.hits += 1;
+            per_asid.hits += 1;
+        } else {
+            self.misses += 1;
+            per_asid.misses += 1;
+            *self.vpn_misses.entry(vpn).or_default() += 1;
+        }
+    }
+
+    /// `asid` 的命中/未命中次数；从未查找过的 ASID 为全零。
+    pub fn asid(&self, asid: u16) -> AsidStats {
+        self.per_asid.get(&asid).copied().unwrap_or_default()
+    }
+
+    /// 所有出现过的 ASID 及其计数，按 ASID 升序。各项之和等于 `hits` / `misses`。
+    pub fn per_asid(&self) -> Vec<(u16, AsidStats)> {
+        let mut v: Vec<_> = self.per_asid.iter().map(|(&a, &s)| (a, s)).collect();
+        v.sort_by_key(|&(a, _)| a);
+        v
+    }
+
+    /// 未命中最多的 `n` 个虚拟页：`(vpn, misses)`，按未命中次数降序，次数相同按 vpn 升序。
+    pub fn top_missing_vpns(&self, n: usize) -> Vec<(u64, u64)> {
+        let mut v: Vec<(u64, u64)> = self.vpn_misses.iter().map(|(&vpn, &m)| (vpn, m)).collect();
+        v.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        v.truncate(n);
+        v
+    }
+}
+
+/// 模拟 TLB，固定大小，使用 FIFO 替换策略。
+pub struct Tlb {
+    entries: Vec<TlbEntry>,
+    capacity: usize,
+    /// FIFO 指针：下次替换的位置
+    fifo_ptr: usize,
+    pub stats: TlbStats,
+}
+
+impl Tlb {
+    /// 创建一个容量为 `capacity` 的 TLB。
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: vec![TlbEntry::empty(); capacity],
+            capacity,
+            fifo_ptr: 0,
+            stats: TlbStats::default(),
+        }
+    }
+
+    /// 在 TLB 中查找匹配 `vpn` 和 `asid` 的条目。
+    ///
+    /// 查找规则：
+    /// - 遍历所有条目
+    /// - 条目必须 `valid == true`
+    /// - 条目的 `vpn` 和 `asid` 都必须匹配
+    /// - 用 `stats.record(asid, vpn, hit)` 记录这次查找（命中与否都要记录）
+    ///
+    /// 返回匹配条目的 `ppn`，未命中返回 None。
+    pub fn lookup(&mut self, vpn: u64, asid: u16) -> Option<u64> {
+        match self
+            .entries
+            .iter()
+            .find(|e| e.valid && e.vpn == vpn && e.asid == asid)
+        {
+            Some(e) => {
+                let ppn = e.ppn;
+                self.stats.record(asid, vpn, true);
+                Some(ppn)
+            }
+            None => {
+                self.stats.record(asid, vpn, false);
+                None
+            }
+        }
+    }
+
+    /// 将一条新映射插入 TLB。
+    ///
+    /// 使用 FIFO 替换策略：
+    /// 1. 先检查是否已存在相同 (vpn, asid) 的有效条目，如果有则更新它
+    /// 2. 否则，写入 `fifo_ptr` 指向的位置
+    /// 3. 将 `fifo_ptr` 前进到下一个位置（循环：`(fifo_ptr + 1) % capacity`）
+    pub fn insert(&mut self, vpn: u64, ppn: u64, asid: u16, flags: u64) {
+        if let Some(e) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.valid && e.vpn == vpn && e.asid == asid)
+        {
+            e.ppn = ppn;
+            e.flags = flags;
+            return;
+        }
+        self.entries[self.fifo_ptr] = TlbEntry {
+            valid: true,
+            asid,
+            vpn,
+            ppn,
+            flags,
+        };
+        self.fifo_ptr = (self.fifo_ptr + 1) % self.capacity;
+    }
+
+    /// 刷新整个 TLB（将所有条目标记为无效）。
+    ///
+    /// 这对应于 RISC-V 的 `sfence.vma`（不带参数）操作。
+    pub fn flush_all(&mut self) {
+        for e in &mut self.entries {
+            e.valid = false;
+        }
+    }
+
+    /// 刷新指定虚拟页的 TLB 条目。
+    ///
+    /// 对应 `sfence.vma vaddr`：只刷新匹配 `vpn` 的条目（任意 ASID）。
+    pub fn flush_by_vpn(&mut self, vpn: u64) {
+        for e in self.entries.iter_mut().filter(|e| e.vpn == vpn) {
+            e.valid = false;
+        }
+    }
+
+    /// 刷新指定地址空间（ASID）的所有 TLB 条目。
+    ///
+    /// 对应 `sfence.vma zero, asid`：刷新该 ASID 的所有条目。
+    pub fn flush_by_asid(&mut self, asid: u16) {
+        for e in self.entries.iter_mut().filter(|e| e.asid == asid) {
+            e.valid = false;
+        }
+    }
+
+    /// 返回当前有效条目的数量。
+    pub fn valid_count(&self) -> usize {
+        self.entries.iter().filter(|e| e.valid).count()
+    }
+}
+
+/// 页表项（简化版，用于 MMU 模拟）
+pub struct PageMapping {
+    pub vpn: u64,
+    pub ppn: u64,
+    pub flags: u64,
+}
+
+/// 带数据访存的错误，携带出错的虚拟地址。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemFault {
+    /// 当前 ASID 下该页未映射，或映射的 PTE_V 未置位
+    PageFault(u64),
+    /// 读缺 PTE_R 或写缺 PTE_W
+    PermissionDenied(u64),
+}
+
+/// 模拟的 MMU：包含 TLB、一个简单的页表和模拟的物理内存。
+///
+/// MMU 翻译流程：
+/// 1. 先查 TLB（lookup）
+/// 2. TLB 命中 → 直接返回物理页号
+/// 3. TLB 未命中 → 遍历页表查找（walk page table）
+/// 4. 页表命中 → 将结果回填到 TLB（insert），然后返回
+/// 5. 页表也未命中 → 缺页（None）
+pub struct Mmu {
+    pub tlb: Tlb,
+    /// 简化的页表：(vpn, asid) -> PageMapping
+    page_table: Vec<(u16, PageMapping)>,
+    pub current_asid: u16,
+    /// 模拟的物理内存：ppn -> 页帧内容，第一次写入时按全零创建
+    memory: HashMap<u64, [u8; PAGE_SIZE]>,
+}
+
+impl Mmu {
+    pub fn new(tlb_capacity: usize) -> Self {
+        Self {
+            tlb: Tlb::new(tlb_capacity),
+            page_table: Vec::new(),
+            current_asid: 0,
+            memory: HashMap::new(),
+        }
+    }
+
+    /// 在页表中添加一条映射。
+    pub fn add_mapping(&mut self, asid: u16, vpn: u64, ppn: u64, flags: u64) {
+        self.page_table
+            .push((asid, PageMapping { vpn, ppn, flags }));
+    }
+
+    /// 切换当前地址空间（ASID）。
+    pub fn switch_asid(&mut self, new_asid: u16) {
+        self.current_asid = new_asid;
+    }
+
+    /// 模拟 MMU 地址翻译。
+    ///
+    /// 流程：
+    /// 1. 使用 `self.current_asid` 和 `vpn` 查找 TLB
+    /// 2. TLB 命中 → 返回 Some(ppn)
+    /// 3. TLB 未命中 → 在 `self.page_table` 中查找匹配 (current_asid, vpn) 的条目
+    /// 4. 页表命中 → 回填 TLB（insert），返回 Some(ppn)
+    /// 5. 页表未命中 → 返回 None（缺页）
+    pub fn translate(&mut self, vpn: u64) -> Option<u64> {
+        let asid = self.current_asid;
+        if let Some(ppn) = self.tlb.lookup(vpn, asid) {
+            return Some(ppn);
+        }
+        let (_, m) = self
+            .page_table
+            .iter()
+            .find(|(a, m)| *a == asid && m.vpn == vpn)?;
+        let (ppn, flags) = (m.ppn, m.flags);
+        self.tlb.insert(vpn, ppn, asid, flags);
+        Some(ppn)
+    }
+
+    /// 以读（`is_write == false`）或写方式访问虚拟地址 `va`，返回物理地址。
+    ///
+    /// 流程：
+    /// 1. `translate(va >> PAGE_SHIFT)` 得到 ppn（经过 TLB，计入命中率），缺页返回 `PageFault`
+    /// 2. 找到页表中 (current_asid, vpn) 的映射：PTE_V 未置位返回 `PageFault`，
+    ///    读缺 PTE_R、写缺 PTE_W 返回 `PermissionDenied`
+    /// 3. 访问成功才更新标志：置 PTE_A，写访问再置 PTE_D，并用 `tlb.insert`
+    ///    把新标志同步到 TLB 中的副本（真实硬件在 TLB 命中时检查缓存的权限，
+    ///    遇到 D 未置位的写再回页表置位；这里简化为始终以页表为准）
+    /// 4. 物理地址 = (ppn << PAGE_SHIFT) | 页内偏移
+    pub fn access(&mut self, va: u64, is_write: bool) -> Result<u64, MemFault> {
+        let vpn = va >> PAGE_SHIFT;
+        let ppn = self.translate(vpn).ok_or(MemFault::PageFault(va))?;
+        let asid = self.current_asid;
+        let mapping = self
+            .page_table
+            .iter_mut()
+            .find(|(a, m)| *a == asid && m.vpn == vpn)
+            .map(|(_, m)| m)
+            .ok_or(MemFault::PageFault(va))?;
+        if mapping.flags & PTE_V == 0 {
+            return Err(MemFault::PageFault(va));
+        }
+        let needed = if is_write { PTE_W } else { PTE_R };
+        if mapping.flags & needed == 0 {
+            return Err(MemFault::PermissionDenied(va));
+        }
+        mapping.flags |= if is_write { PTE_A | PTE_D } else { PTE_A };
+        let flags = mapping.flags;
+        self.tlb.insert(vpn, ppn, asid, flags);
+        Ok((ppn << PAGE_SHIFT) | (va & (PAGE_SIZE as u64 - 1)))
+    }
+
+    /// 读取当前地址空间中 `[va, va + len)` 的数据，可以跨页。
+    ///
+    /// 每一页单独 `access`，任何一页出错就返回该页第一个字节的地址。
+    /// 从未写过的物理页读出全零。
+    pub fn read(&mut self, va: u64, len: usize) -> Result<Vec<u8>, MemFault> {
+        let mut out = Vec::with_capacity(len);
+        for (va, n) in page_chunks(va, len) {
+            let pa = self.access(va, false)?;
+            let (ppn, off) = split_pa(pa);
+            match self.memory.get(&ppn) {
+                Some(frame) => out.extend_from_slice(&frame[off..off + n]),
+                None => out.resize(out.len() + n, 0),
+            }
+        }
+        Ok(out)
+    }
+
+    /// 把 `bytes` 写到当前地址空间的 `va` 处，可以跨页。
+    ///
+    /// 与 `read` 一样逐页处理：出错时前面的页已经写入（和一条条执行的 store 一样）。
+    pub fn write(&mut self, va: u64, bytes: &[u8]) -> Result<(), MemFault> {
+        let mut done = 0;
+        for (va, n) in page_chunks(va, bytes.len()) {
+            let pa = self.access(va, true)?;
+            let (ppn, off) = split_pa(pa);
+            let frame = self.memory.entry(ppn).or_insert([0; PAGE_SIZE]);
+            frame[off..off + n].copy_from_slice(&bytes[done..done + n]);
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// 页表中 (asid, vpn) 映射当前的标志位（包括 A/D），未映射返回 None。
+    pub fn mapping_flags(&self, asid: u16, vpn: u64) -> Option<u64> {
+        self.page_table
+            .iter()
+            .find(|(a, m)| *a == asid && m.vpn == vpn)
+            .map(|(_, m)| m.flags)
+    }
+
+    /// 物理页 `ppn` 的内容；从未写过的页返回 None。
+    pub fn frame(&self, ppn: u64) -> Option<&[u8; PAGE_SIZE]> {
+        self.memory.get(&ppn)
+    }
+}
+
+/// Split `[va, va + len)` at page boundaries into `(start, len)` pieces.
+fn page_chunks(va: u64, len: usize) -> impl Iterator<Item = (u64, usize)> {
+    let end = va + len as u64;
+    let mut cur = va;
+    std::iter::from_fn(move || {
+        if cur >= end {
+            return None;
+        }
+        let page_end = (cur | (PAGE_SIZE as u64 - 1)) + 1;
+        let n = (page_end.min(end) - cur) as usize;
+        let chunk = (cur, n);
+        cur += n as u64;
+        Some(chunk)
+    })
+}
+
+fn split_pa(pa: u64) -> (u64, usize) {
+    (pa >> PAGE_SHIFT, (pa as usize) & (PAGE_SIZE - 1))
+}
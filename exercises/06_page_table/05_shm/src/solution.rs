@@ -0,0 +1,402 @@
+//! # Shared Memory Segments (System V `shm*`)
+//!
+//! In this exercise, you implement System V shared memory on top of simulated page tables: two
+//! processes attach the same segment, each at an address of its own choosing, and see each other's
+//! writes because both page tables point at the **same physical frames**.
+//!
+//! ## Concepts
+//! - `shmget(key, size)`: find the segment registered under `key`, or create it (frames are
+//!   allocated once, zeroed). `IPC_PRIVATE` always creates a new, unnamed segment
+//! - `shmat(id, proc)`: map the segment's frames into the process's address space (user, R/W) at
+//!   the lowest free address of the attach window `[SHM_BASE, SHM_END)`
+//! - `shmdt(proc, va)`: unmap it again; the frames stay with the segment
+//! - `IPC_RMID` (`remove`): the key disappears at once, but the segment (and its memory) lives
+//!   until the last process has detached — the same reference-counting as an unlinked file
+//! - Process exit detaches everything the process still has attached
+//!
+//! ## Given
+//! `PhysMem` (frames, zeroed on allocation, with `frames_in_use` to spot leaks) and
+//! `AddressSpace`, which keeps the areas of one process in a `MemorySet` from `09_memory_set`,
+//! maps them in an `Sv39PageTable` from `03_multi_level_pt`, and reads/writes through it. Finish
+//! those two first (`--features solutions` uses their reference solutions instead).
+
+use std::collections::HashMap;
+
+pub use memory_set::solution::{Area, AreaKind, Layout, MemorySet};
+pub use multi_level_pt::solution::{Sv39PageTable, PAGE_SIZE, PTE_R, PTE_U, PTE_V, PTE_W, PTE_X};
+
+const PPN_SHIFT: u32 = 10;
+
+/// Attach window: `shmat` picks addresses in `[SHM_BASE, SHM_END)`.
+pub const SHM_BASE: u64 = 0x2000_0000;
+pub const SHM_END: u64 = SHM_BASE + 64 * PAGE_SIZE as u64;
+
+/// Key that never names an existing segment: every `shmget(IPC_PRIVATE, ..)` creates a new one.
+pub const IPC_PRIVATE: ShmKey = 0;
+
+pub type ShmKey = u32;
+pub type ShmId = usize;
+pub type Pid = usize;
+
+pub fn pages_for(size: usize) -> usize {
+    size.div_ceil(PAGE_SIZE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShmError {
+    /// Size 0, or larger than the existing segment registered under the key.
+    InvalidSize,
+    /// No such segment (never created, or removed).
+    InvalidId,
+    /// No attachment starts at this address.
+    InvalidAddress,
+    /// The attach window has no free range large enough.
+    NoSpace,
+}
+
+// ============================================================
+// Physical memory and address space (given)
+// ============================================================
+
+/// Simulated RAM shared by all processes.
+pub struct PhysMem {
+    frames: HashMap<u64, Box<[u8; PAGE_SIZE]>>,
+    next_ppn: u64,
+}
+
+impl PhysMem {
+    pub fn new() -> Self {
+        Self {
+            frames: HashMap::new(),
+            next_ppn: 0x90000,
+        }
+    }
+
+    /// Allocate a zeroed frame and return its PPN.
+    pub fn alloc_frame(&mut self) -> u64 {
+        let ppn = self.next_ppn;
+        self.next_ppn += 1;
+        self.frames.insert(ppn, Box::new([0; PAGE_SIZE]));
+        ppn
+    }
+
+    pub fn free_frame(&mut self, ppn: u64) {
+        self.frames
+            .remove(&ppn)
+            .expect("freeing a frame that is not allocated");
+    }
+
+    pub fn frames_in_use(&self) -> usize {
+        self.frames.len()
+    }
+
+    fn frame_mut(&mut self, pa: u64) -> &mut [u8; PAGE_SIZE] {
+        self.frames
+            .get_mut(&(pa >> 12))
+            .expect("access to unallocated physical memory")
+    }
+
+    fn frame(&self, pa: u64) -> &[u8; PAGE_SIZE] {
+        self.frames
+            .get(&(pa >> 12))
+            .expect("access to unallocated physical memory")
+    }
+}
+
+impl Default for PhysMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The address space of one process: its areas (`09_memory_set`) and the page table behind
+/// them (`03_multi_level_pt`).
+pub struct AddressSpace {
+    pub areas: MemorySet,
+    pub pt: Sv39PageTable,
+}
+
+impl AddressSpace {
+    pub fn new() -> Self {
+        Self {
+            areas: MemorySet::new(Layout::fixed(PAGE_SIZE as u64)),
+            pt: Sv39PageTable::new(),
+        }
+    }
+
+    /// Map `frames` (PPNs) at consecutive pages from `va` with `flags` (`PTE_V` is added) and
+    /// record them as an `Mmap` area. The range must be free.
+    pub fn map_frames(&mut self, va: u64, frames: &[u64], flags: u64) {
+        let end = va + (frames.len() * PAGE_SIZE) as u64;
+        let perm = ((flags >> 1) & 0b111) as u8;
+        self.areas
+            .insert(Area::new(va, end, perm, AreaKind::Mmap))
+            .unwrap_or_else(|_| panic!("mapping over an existing area at {va:#x}"));
+        for (i, &ppn) in frames.iter().enumerate() {
+            self.pt
+                .map_page(va + (i * PAGE_SIZE) as u64, ppn << 12, flags | PTE_V);
+        }
+    }
+
+    /// Unmap the area that starts at `va`; returns its frames (which are *not* freed).
+    pub fn unmap_region(&mut self, va: u64) -> Option<Vec<u64>> {
+        let area = self.areas.remove(va)?;
+        Some(
+            (area.start..area.end)
+                .step_by(PAGE_SIZE)
+                .map(|page| {
+                    let ppn = self.pt.leaf(page).unwrap() >> PPN_SHIFT;
+                    self.pt.map_page(page, 0, 0);
+                    ppn
+                })
+                .collect(),
+        )
+    }
+
+    /// Lowest page-aligned address in `[from, to)` where `pages` pages fit.
+    pub fn find_free(&self, from: u64, to: u64, pages: usize) -> Option<u64> {
+        let len = (pages * PAGE_SIZE) as u64;
+        let mut va = from;
+        // `overlapping` yields the area reaching highest first: skip past it and try again.
+        while let Some(area) = self.areas.overlapping(va, va + len).first() {
+            va = area.end;
+        }
+        (va + len <= to).then_some(va)
+    }
+
+    /// Physical address of `va`, if mapped.
+    pub fn translate(&self, va: u64) -> Option<u64> {
+        let pte = self.pt.leaf(va)?;
+        Some(((pte >> PPN_SHIFT) << 12) | (va & (PAGE_SIZE as u64 - 1)))
+    }
+
+    pub fn write(&self, mem: &mut PhysMem, va: u64, bytes: &[u8]) {
+        for (i, &b) in bytes.iter().enumerate() {
+            let pa = self.pa(va + i as u64);
+            mem.frame_mut(pa)[(pa as usize) % PAGE_SIZE] = b;
+        }
+    }
+
+    pub fn read(&self, mem: &PhysMem, va: u64, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| {
+                let pa = self.pa(va + i as u64);
+                mem.frame(pa)[(pa as usize) % PAGE_SIZE]
+            })
+            .collect()
+    }
+
+    fn pa(&self, va: u64) -> u64 {
+        self.translate(va)
+            .unwrap_or_else(|| panic!("page fault at {va:#x}"))
+    }
+}
+
+impl Default for AddressSpace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A simulated process.
+pub struct Process {
+    pub pid: Pid,
+    pub space: AddressSpace,
+}
+
+impl Process {
+    pub fn new(pid: Pid) -> Self {
+        Self {
+            pid,
+            space: AddressSpace::new(),
+        }
+    }
+}
+
+// ============================================================
+// Shared memory manager
+// ============================================================
+
+struct Segment {
+    key: ShmKey,
+    /// Size requested at creation (the mapping covers whole pages).
+    size: usize,
+    frames: Vec<u64>,
+    /// Current attachments: (process, start address).
+    attaches: Vec<(Pid, u64)>,
+    /// `IPC_RMID` was called: destroy once `attaches` is empty.
+    removed: bool,
+}
+
+/// Information about a segment, as `shmctl(IPC_STAT)` would report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShmStat {
+    pub size: usize,
+    pub nattch: usize,
+    pub removed: bool,
+}
+
+/// All shared memory segments of the system.
+pub struct ShmManager {
+    segments: HashMap<ShmId, Segment>,
+    /// Keys of live, not-yet-removed segments.
+    keys: HashMap<ShmKey, ShmId>,
+    next_id: ShmId,
+}
+
+impl ShmManager {
+    pub fn new() -> Self {
+        Self {
+            segments: HashMap::new(),
+            keys: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Get or create a segment.
+    ///
+    /// - `size == 0` -> `InvalidSize`.
+    /// - If `key != IPC_PRIVATE` and `keys` has it: return that id, or `InvalidSize` if `size` is
+    ///   larger than the existing segment.
+    /// - Otherwise create a segment with `pages_for(size)` fresh frames under `next_id` (then
+    ///   increment it), and register the key unless it is `IPC_PRIVATE`.
+    pub fn shmget(
+        &mut self,
+        mem: &mut PhysMem,
+        key: ShmKey,
+        size: usize,
+    ) -> Result<ShmId, ShmError> {
+        if size == 0 {
+            return Err(ShmError::InvalidSize);
+        }
+        if key != IPC_PRIVATE {
+            if let Some(&id) = self.keys.get(&key) {
+                if size > self.segments[&id].size {
+                    return Err(ShmError::InvalidSize);
+                }
+                return Ok(id);
+            }
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let frames = (0..pages_for(size)).map(|_| mem.alloc_frame()).collect();
+        self.segments.insert(
+            id,
+            Segment {
+                key,
+                size,
+                frames,
+                attaches: Vec::new(),
+                removed: false,
+            },
+        );
+        if key != IPC_PRIVATE {
+            self.keys.insert(key, id);
+        }
+        Ok(id)
+    }
+
+    /// Attach segment `id` to `proc`: map its frames with `PTE_U | PTE_R | PTE_W` at the lowest
+    /// free address in `[SHM_BASE, SHM_END)` (`AddressSpace::find_free`), record `(pid, va)` and
+    /// return `va`. `InvalidId` for unknown *or removed* segments, `NoSpace` if nothing fits.
+    pub fn shmat(&mut self, id: ShmId, proc: &mut Process) -> Result<u64, ShmError> {
+        let seg = self
+            .segments
+            .get_mut(&id)
+            .filter(|s| !s.removed)
+            .ok_or(ShmError::InvalidId)?;
+        let va = proc
+            .space
+            .find_free(SHM_BASE, SHM_END, seg.frames.len())
+            .ok_or(ShmError::NoSpace)?;
+        proc.space
+            .map_frames(va, &seg.frames, PTE_U | PTE_R | PTE_W);
+        seg.attaches.push((proc.pid, va));
+        Ok(va)
+    }
+
+    /// Detach the attachment of `proc` that starts at `va`.
+    ///
+    /// Find the segment with `(proc.pid, va)` in its `attaches` (`InvalidAddress` if none), remove
+    /// that entry and `unmap_region(va)`. Then `destroy_if_unused`: a removed segment dies with its
+    /// last attachment.
+    pub fn shmdt(
+        &mut self,
+        mem: &mut PhysMem,
+        proc: &mut Process,
+        va: u64,
+    ) -> Result<(), ShmError> {
+        let (&id, seg) = self
+            .segments
+            .iter_mut()
+            .find(|(_, s)| s.attaches.contains(&(proc.pid, va)))
+            .ok_or(ShmError::InvalidAddress)?;
+        seg.attaches.retain(|&a| a != (proc.pid, va));
+        proc.space.unmap_region(va);
+        self.destroy_if_unused(mem, id);
+        Ok(())
+    }
+
+    /// `shmctl(id, IPC_RMID)`: unregister the key right away and mark the segment `removed`;
+    /// `destroy_if_unused` it. `InvalidId` if unknown or already removed.
+    pub fn remove(&mut self, mem: &mut PhysMem, id: ShmId) -> Result<(), ShmError> {
+        let seg = self
+            .segments
+            .get_mut(&id)
+            .filter(|s| !s.removed)
+            .ok_or(ShmError::InvalidId)?;
+        seg.removed = true;
+        if seg.key != IPC_PRIVATE {
+            self.keys.remove(&seg.key);
+        }
+        self.destroy_if_unused(mem, id);
+        Ok(())
+    }
+
+    /// Process exit: detach every attachment `proc` still has (in any segment).
+    pub fn exit(&mut self, mem: &mut PhysMem, proc: &mut Process) {
+        let mine: Vec<u64> = self
+            .segments
+            .values()
+            .flat_map(|s| s.attaches.iter())
+            .filter(|&&(pid, _)| pid == proc.pid)
+            .map(|&(_, va)| va)
+            .collect();
+        for va in mine {
+            self.shmdt(mem, proc, va).unwrap();
+        }
+    }
+
+    /// `shmctl(id, IPC_STAT)`; `None` once the segment is destroyed.
+    pub fn stat(&self, id: ShmId) -> Option<ShmStat> {
+        self.segments.get(&id).map(|s| ShmStat {
+            size: s.size,
+            nattch: s.attaches.len(),
+            removed: s.removed,
+        })
+    }
+
+    /// Number of segments that still exist (including removed ones with attachments).
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Destroy segment `id` if it is `removed` and nobody is attached: free its frames and drop it.
+    fn destroy_if_unused(&mut self, mem: &mut PhysMem, id: ShmId) {
+        if self
+            .segments
+            .get(&id)
+            .is_some_and(|s| s.removed && s.attaches.is_empty())
+        {
+            for ppn in self.segments.remove(&id).unwrap().frames {
+                mem.free_frame(ppn);
+            }
+        }
+    }
+}
+
+impl Default for ShmManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,335 @@
+//! # Cache Simulator (direct-mapped and set-associative)
+//!
+//! The TLB caches translations; this exercise looks at the other cache on the memory path: the
+//! data cache. You implement a simulator configurable by line size, number of sets and ways, replay
+//! address traces through it and count hits, misses and evictions.
+//!
+//! ## Concepts
+//! - A cache is split into **lines** (`line_size` bytes, the unit moved from memory) grouped into
+//!   **sets**; each set holds `ways` lines
+//! - An address is split into `tag | set index | offset`:
+//!   ```text
+//!   63                 offset_bits + index_bits     offset_bits        0
+//!   ┌──────────────────────────┬──────────────────────┬─────────────────┐
+//!   │           tag            │      set index       │     offset      │
+//!   └──────────────────────────┴──────────────────────┴─────────────────┘
+//!   ```
+//!   The index picks the set, the tag tells which memory line currently sits in a way
+//! - **Direct-mapped** (`ways == 1`): every line has exactly one place to go, so two hot lines
+//!   with the same index keep kicking each other out (**conflict misses**) even if the rest of
+//!   the cache is empty
+//! - **Set-associative** (`ways > 1`): a line may use any way of its set; on a miss in a full set
+//!   the **least recently used** way is evicted. `sets == 1` is fully associative
+//! - Miss kinds: cold (first touch), conflict (too many hot lines in one set) and capacity (the
+//!   working set is larger than the whole cache)
+
+/// Geometry of a cache. All three parameters must be powers of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    pub line_size: usize,
+    pub sets: usize,
+    pub ways: usize,
+}
+
+impl CacheConfig {
+    /// # Panics
+    /// If a parameter is zero or not a power of two.
+    pub fn new(line_size: usize, sets: usize, ways: usize) -> Self {
+        for (name, v) in [("line_size", line_size), ("sets", sets), ("ways", ways)] {
+            assert!(
+                v.is_power_of_two(),
+                "{name} must be a power of two, got {v}"
+            );
+        }
+        Self {
+            line_size,
+            sets,
+            ways,
+        }
+    }
+
+    /// Total size in bytes.
+    pub fn capacity(&self) -> usize {
+        self.line_size * self.sets * self.ways
+    }
+
+    /// Number of low address bits selecting the byte inside a line.
+    pub fn offset_bits(&self) -> u32 {
+        self.line_size.trailing_zeros()
+    }
+
+    /// Number of address bits selecting the set.
+    pub fn index_bits(&self) -> u32 {
+        self.sets.trailing_zeros()
+    }
+}
+
+/// One way of a set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheLine {
+    pub valid: bool,
+    pub tag: u64,
+    /// Value of the access counter the last time this line was used (for LRU).
+    pub last_used: u64,
+}
+
+/// Outcome of one access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Hit,
+    /// The line was loaded; `evicted` is the address of the first byte of the line it replaced,
+    /// if the chosen way was valid.
+    Miss {
+        evicted: Option<u64>,
+    },
+}
+
+/// Hit/miss/eviction counters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+pub struct CacheSim {
+    config: CacheConfig,
+    /// `sets[index][way]`
+    sets: Vec<Vec<CacheLine>>,
+    /// Incremented on every access; stored into `last_used`.
+    clock: u64,
+    pub stats: CacheStats,
+}
+
+impl CacheSim {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            sets: vec![vec![CacheLine::default(); config.ways]; config.sets],
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> CacheConfig {
+        self.config
+    }
+
+    /// Split `addr` into `(tag, set index, offset)`.
+    ///
+    /// - `offset` = the low `offset_bits` bits
+    /// - `index` = the next `index_bits` bits
+    /// - `tag` = everything above
+    pub fn decompose(&self, addr: u64) -> (u64, usize, usize) {
+        // TODO: shifts and masks (`(1 << bits) - 1`); index_bits may be 0 (one set)
+        todo!()
+    }
+
+    /// Is the line holding `addr` in the cache? Does not count as an access.
+    pub fn contains(&self, addr: u64) -> bool {
+        // TODO: decompose, then look for a valid way of that set with the same tag
+        todo!()
+    }
+
+    /// Access `addr`, updating `stats`.
+    ///
+    /// 1. Advance `clock`; decompose the address
+    /// 2. Hit: a valid way of the set has the tag → refresh its `last_used`, `hits += 1`
+    /// 3. Miss: `misses += 1`; pick the victim way: the first invalid way, otherwise the way with
+    ///    the smallest `last_used` (LRU)
+    /// 4. If the victim was valid: `evictions += 1` and report its address, rebuilt from its tag
+    ///    and the set index (offset 0)
+    /// 5. Fill the victim with the new tag, `valid = true`, `last_used = clock`
+    pub fn access(&mut self, addr: u64) -> Access {
+        // TODO
+        todo!()
+    }
+
+    /// Replay a trace of addresses and return the counters afterwards.
+    pub fn replay(&mut self, trace: impl IntoIterator<Item = u64>) -> CacheStats {
+        for addr in trace {
+            self.access(addr);
+        }
+        self.stats
+    }
+
+    /// Invalidate every line (statistics are kept).
+    pub fn flush(&mut self) {
+        for line in self.sets.iter_mut().flatten() {
+            line.valid = false;
+        }
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_decompose",
+            "offset = low offset_bits bits, index = the next index_bits bits, tag = everything above",
+        ),
+        (
+            "test_spatial_locality",
+            "addresses in the same line share tag and index: only the first access misses",
+        ),
+        (
+            "test_direct_mapped_conflict_misses",
+            "with one way per set, a miss always evicts the line in that set",
+        ),
+        (
+            "test_lru_victim_and_evicted_address",
+            "evict the valid way with the smallest last_used and rebuild its address from tag and set index, offset 0",
+        ),
+        (
+            "test_flush",
+            "flush invalidates every way, so the next access to any address misses",
+        ),
+    ];
+
+    /// `n` rounds of alternating accesses to `a` and `b`.
+    fn ping_pong(a: u64, b: u64, n: usize) -> Vec<u64> {
+        (0..n).flat_map(|_| [a, b]).collect()
+    }
+
+    #[test]
+    fn test_config_geometry() {
+        let cfg = CacheConfig::new(64, 8, 2);
+        assert_eq!(cfg.capacity(), 1024);
+        assert_eq!(cfg.offset_bits(), 6);
+        assert_eq!(cfg.index_bits(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn test_config_rejects_non_power_of_two() {
+        CacheConfig::new(48, 4, 1);
+    }
+
+    #[test]
+    fn test_decompose() {
+        let c = CacheSim::new(CacheConfig::new(16, 4, 1));
+        // 0x1234 = tag 0x48 | index 3 | offset 4
+        assert_eq_with_hint!(c.decompose(0x1234), (0x48, 3, 4));
+        assert_eq_with_hint!(c.decompose(0x0f), (0, 0, 15));
+        // Fully associative: no index bits
+        let c = CacheSim::new(CacheConfig::new(16, 1, 4));
+        assert_eq_with_hint!(c.decompose(0x1234), (0x123, 0, 4));
+    }
+
+    #[test]
+    fn test_spatial_locality() {
+        let mut c = CacheSim::new(CacheConfig::new(16, 4, 1));
+        assert_eq_with_hint!(c.access(0x100), Access::Miss { evicted: None });
+        for addr in 0x101..0x110 {
+            assert_eq_with_hint!(c.access(addr), Access::Hit, "same line as 0x100");
+        }
+        assert_with_hint!(c.contains(0x10f));
+        assert_with_hint!(!c.contains(0x110));
+        assert_eq_with_hint!(
+            c.stats,
+            CacheStats {
+                hits: 15,
+                misses: 1,
+                evictions: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_direct_mapped_conflict_misses() {
+        // 4 sets of 16 bytes: 0x000 and 0x040 both map to set 0.
+        let mut c = CacheSim::new(CacheConfig::new(16, 4, 1));
+        let stats = c.replay(ping_pong(0x000, 0x040, 4));
+        assert_eq_with_hint!(stats.hits, 0, "each access evicts the other line");
+        assert_eq_with_hint!(stats.misses, 8);
+        assert_eq_with_hint!(stats.evictions, 7);
+        // Three of the four sets were never used.
+        assert_with_hint!(!c.contains(0x010) && !c.contains(0x020) && !c.contains(0x030));
+    }
+
+    #[test]
+    fn test_two_way_removes_conflict() {
+        // Same capacity (64 bytes) as the direct-mapped cache above, organised as 2 sets x 2 ways.
+        let mut c = CacheSim::new(CacheConfig::new(16, 2, 2));
+        let stats = c.replay(ping_pong(0x000, 0x040, 4));
+        assert_eq!(
+            stats,
+            CacheStats {
+                hits: 6,
+                misses: 2,
+                evictions: 0
+            }
+        );
+        assert_eq!(stats.hit_rate(), 0.75);
+    }
+
+    #[test]
+    fn test_lru_victim_and_evicted_address() {
+        let mut c = CacheSim::new(CacheConfig::new(16, 2, 2));
+        // 0x000, 0x020, 0x040 all map to set 0.
+        c.access(0x000);
+        c.access(0x020);
+        c.access(0x004); // touch 0x000 again: 0x020 is now least recently used
+        assert_eq_with_hint!(
+            c.access(0x04c),
+            Access::Miss {
+                evicted: Some(0x020)
+            }
+        );
+        assert_with_hint!(c.contains(0x000));
+        assert_with_hint!(!c.contains(0x020));
+        assert_eq_with_hint!(c.access(0x000), Access::Hit);
+        assert_eq_with_hint!(
+            c.access(0x024),
+            Access::Miss {
+                evicted: Some(0x040)
+            }
+        );
+    }
+
+    #[test]
+    fn test_capacity_misses_fully_associative() {
+        // 4 lines, fully associative: a cyclic working set of 5 lines always misses under LRU,
+        // while 4 lines fit.
+        let mut c = CacheSim::new(CacheConfig::new(16, 1, 4));
+        let five = (0..5).map(|i| i * 16);
+        let stats = c.replay(five.cycle().take(20));
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (0, 20, 16));
+
+        let mut c = CacheSim::new(CacheConfig::new(16, 1, 4));
+        let four = (0..4).map(|i| i * 16);
+        let stats = c.replay(four.cycle().take(20));
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (16, 4, 0));
+    }
+
+    #[test]
+    fn test_flush() {
+        let mut c = CacheSim::new(CacheConfig::new(16, 4, 2));
+        c.replay([0x0, 0x10, 0x20]);
+        c.flush();
+        assert_with_hint!(!c.contains(0x0));
+        assert_eq_with_hint!(c.access(0x10), Access::Miss { evicted: None });
+        assert_eq_with_hint!(c.stats.misses, 4);
+    }
+}
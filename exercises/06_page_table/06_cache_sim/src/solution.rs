@@ -0,0 +1,200 @@
+//! # Cache Simulator (direct-mapped and set-associative)
+//!
+//! The TLB caches translations; this exercise looks at the other cache on the memory path: the
+//! data cache. You implement a simulator configurable by line size, number of sets and ways, replay
+//! address traces through it and count hits, misses and evictions.
+//!
+//! ## Concepts
+//! - A cache is split into **lines** (`line_size` bytes, the unit moved from memory) grouped into
+//!   **sets**; each set holds `ways` lines
+//! - An address is split into `tag | set index | offset`:
+//!   ```text
+//!   63                 offset_bits + index_bits     offset_bits        0
+//!   ┌──────────────────────────┬──────────────────────┬─────────────────┐
+//!   │           tag            │      set index       │     offset      │
+//!   └──────────────────────────┴──────────────────────┴─────────────────┘
+//!   ```
+//!   The index picks the set, the tag tells which memory line currently sits in a way
+//! - **Direct-mapped** (`ways == 1`): every line has exactly one place to go, so two hot lines
+//!   with the same index keep kicking each other out (**conflict misses**) even if the rest of
+//!   the cache is empty
+//! - **Set-associative** (`ways > 1`): a line may use any way of its set; on a miss in a full set
+//!   the **least recently used** way is evicted. `sets == 1` is fully associative
+//! - Miss kinds: cold (first touch), conflict (too many hot lines in one set) and capacity (the
+//!   working set is larger than the whole cache)
+
+/// Geometry of a cache. All three parameters must be powers of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    pub line_size: usize,
+    pub sets: usize,
+    pub ways: usize,
+}
+
+impl CacheConfig {
+    /// # Panics
+    /// If a parameter is zero or not a power of two.
+    pub fn new(line_size: usize, sets: usize, ways: usize) -> Self {
+        for (name, v) in [("line_size", line_size), ("sets", sets), ("ways", ways)] {
+            assert!(
+                v.is_power_of_two(),
+                "{name} must be a power of two, got {v}"
+            );
+        }
+        Self {
+            line_size,
+            sets,
+            ways,
+        }
+    }
+
+    /// Total size in bytes.
+    pub fn capacity(&self) -> usize {
+        self.line_size * self.sets * self.ways
+    }
+
+    /// Number of low address bits selecting the byte inside a line.
+    pub fn offset_bits(&self) -> u32 {
+        self.line_size.trailing_zeros()
+    }
+
+    /// Number of address bits selecting the set.
+    pub fn index_bits(&self) -> u32 {
+        self.sets.trailing_zeros()
+    }
+}
+
+/// One way of a set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheLine {
+    pub valid: bool,
+    pub tag: u64,
+    /// Value of the access counter the last time this line was used (for LRU).
+    pub last_used: u64,
+}
+
+/// Outcome of one access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Hit,
+    /// The line was loaded; `evicted` is the address of the first byte of the line it replaced,
+    /// if the chosen way was valid.
+    Miss {
+        evicted: Option<u64>,
+    },
+}
+
+/// Hit/miss/eviction counters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+pub struct CacheSim {
+    config: CacheConfig,
+    /// `sets[index][way]`
+    sets: Vec<Vec<CacheLine>>,
+    /// Incremented on every access; stored into `last_used`.
+    clock: u64,
+    pub stats: CacheStats,
+}
+
+impl CacheSim {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            sets: vec![vec![CacheLine::default(); config.ways]; config.sets],
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> CacheConfig {
+        self.config
+    }
+
+    /// Split `addr` into `(tag, set index, offset)`.
+    ///
+    /// - `offset` = the low `offset_bits` bits
+    /// - `index` = the next `index_bits` bits
+    /// - `tag` = everything above
+    pub fn decompose(&self, addr: u64) -> (u64, usize, usize) {
+        let off_bits = self.config.offset_bits();
+        let idx_bits = self.config.index_bits();
+        let offset = (addr & ((1u64 << off_bits) - 1)) as usize;
+        let index = ((addr >> off_bits) & ((1u64 << idx_bits) - 1)) as usize;
+        let tag = addr >> (off_bits + idx_bits);
+        (tag, index, offset)
+    }
+
+    /// Is the line holding `addr` in the cache? Does not count as an access.
+    pub fn contains(&self, addr: u64) -> bool {
+        let (tag, index, _) = self.decompose(addr);
+        self.sets[index].iter().any(|l| l.valid && l.tag == tag)
+    }
+
+    /// Access `addr`, updating `stats`.
+    ///
+    /// 1. Advance `clock`; decompose the address
+    /// 2. Hit: a valid way of the set has the tag → refresh its `last_used`, `hits += 1`
+    /// 3. Miss: `misses += 1`; pick the victim way: the first invalid way, otherwise the way with
+    ///    the smallest `last_used` (LRU)
+    /// 4. If the victim was valid: `evictions += 1` and report its address, rebuilt from its tag
+    ///    and the set index (offset 0)
+    /// 5. Fill the victim with the new tag, `valid = true`, `last_used = clock`
+    pub fn access(&mut self, addr: u64) -> Access {
+        self.clock += 1;
+        let (tag, index, _) = self.decompose(addr);
+        let set = &mut self.sets[index];
+        if let Some(line) = set.iter_mut().find(|l| l.valid && l.tag == tag) {
+            line.last_used = self.clock;
+            self.stats.hits += 1;
+            return Access::Hit;
+        }
+        self.stats.misses += 1;
+        let victim = match set.iter().position(|l| !l.valid) {
+            Some(w) => w,
+            None => (0..set.len()).min_by_key(|&w| set[w].last_used).unwrap(),
+        };
+        let old = set[victim];
+        let evicted = old.valid.then(|| {
+            self.stats.evictions += 1;
+            let shift = self.config.offset_bits() + self.config.index_bits();
+            (old.tag << shift) | ((index as u64) << self.config.offset_bits())
+        });
+        set[victim] = CacheLine {
+            valid: true,
+            tag,
+            last_used: self.clock,
+        };
+        Access::Miss { evicted }
+    }
+
+    /// Replay a trace of addresses and return the counters afterwards.
+    pub fn replay(&mut self, trace: impl IntoIterator<Item = u64>) -> CacheStats {
+        for addr in trace {
+            self.access(addr);
+        }
+        self.stats
+    }
+
+    /// Invalidate every line (statistics are kept).
+    pub fn flush(&mut self) {
+        for line in self.sets.iter_mut().flatten() {
+            line.valid = false;
+        }
+    }
+}
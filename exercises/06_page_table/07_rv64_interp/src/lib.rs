@@ -0,0 +1,884 @@
+//! # A Tiny RV64 Interpreter over Sv39
+//!
+//! In this exercise, you build a minimal user-mode RISC-V CPU: 32 integer registers and a `pc`,
+//! executing arithmetic, load/store and branch instructions. Every instruction fetch and every
+//! data access goes through an Sv39 page table **with permission checks**, so a program can page
+//! fault; faults are handed to a callback, the way a kernel's trap handler would see them.
+//!
+//! ## Concepts
+//! - Fetch → decode → execute: `pc` selects the instruction, `decode` (given) turns the 32-bit
+//!   word into an [`Inst`], executing it updates registers, memory and `pc`
+//! - `x0` always reads as zero; writes to it are dropped
+//! - The MMU checks each access against the leaf PTE: `V`, `U` (the CPU runs in user mode) and
+//!   `X` for fetches, `R` for loads, `W` for stores. A failed check is an instruction /
+//!   load / store **page fault** (`scause` 12 / 13 / 15) with the faulting address (`stval`)
+//! - Faults are **precise**: the faulting instruction has no effect at all (no register written,
+//!   no memory changed, `pc` still points at it), so after the handler fixed the mapping the
+//!   instruction can simply be executed again — that is how demand paging works
+//!
+//! ## Given
+//! `PhysMem`, `Memory` with `map` and kernel-side `write_bytes`/`read_bytes` (no permission
+//! checks), `load`/`store` built on your `translate`, and `decode` for the RV64I subset below.
+//! The page table is your `Sv39PageTable` from `03_multi_level_pt`, so finish that first: its
+//! `leaf` is where `translate` starts.
+//!
+//! | Group     | Instructions                                                        |
+//! |-----------|---------------------------------------------------------------------|
+//! | Upper     | `lui`, `auipc`                                                      |
+//! | Jumps     | `jal`, `jalr`                                                       |
+//! | Branches  | `beq`, `bne`, `blt`, `bge`, `bltu`, `bgeu`                          |
+//! | Loads     | `lb`, `lh`, `lw`, `ld`, `lbu`, `lhu`, `lwu`                         |
+//! | Stores    | `sb`, `sh`, `sw`, `sd`                                              |
+//! | ALU       | `add(i)`, `sub`, `sll(i)`, `slt(i)`, `slt(i)u`, `xor(i)`, `srl(i)`, `sra(i)`, `or(i)`, `and(i)` |
+//! | System    | `ecall`, `ebreak`                                                   |
+
+use std::collections::HashMap;
+
+pub use multi_level_pt::{Sv39PageTable, PAGE_SIZE, PTE_R, PTE_U, PTE_V, PTE_W, PTE_X};
+
+const PPN_SHIFT: u32 = 10;
+
+// ============================================================
+// Physical memory (given)
+// ============================================================
+
+/// Simulated RAM: zeroed 4 KiB frames by PPN.
+pub struct PhysMem {
+    frames: HashMap<u64, Box<[u8; PAGE_SIZE]>>,
+    next_ppn: u64,
+}
+
+impl PhysMem {
+    pub fn new() -> Self {
+        Self {
+            frames: HashMap::new(),
+            next_ppn: 0x90000,
+        }
+    }
+
+    pub fn alloc_frame(&mut self) -> u64 {
+        let ppn = self.next_ppn;
+        self.next_ppn += 1;
+        self.frames.insert(ppn, Box::new([0; PAGE_SIZE]));
+        ppn
+    }
+
+    fn byte(&self, pa: u64) -> u8 {
+        self.frames
+            .get(&(pa >> 12))
+            .expect("access to unallocated physical memory")[(pa as usize) % PAGE_SIZE]
+    }
+
+    fn byte_mut(&mut self, pa: u64) -> &mut u8 {
+        &mut self
+            .frames
+            .get_mut(&(pa >> 12))
+            .expect("access to unallocated physical memory")[(pa as usize) % PAGE_SIZE]
+    }
+}
+
+impl Default for PhysMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// Memory access through the MMU
+// ============================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessType {
+    Fetch,
+    Load,
+    Store,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageFault {
+    /// Faulting virtual address (what `stval` would hold).
+    pub addr: u64,
+    pub access: AccessType,
+}
+
+impl PageFault {
+    /// The `scause` exception code of this fault.
+    pub fn scause(&self) -> u64 {
+        match self.access {
+            AccessType::Fetch => 12,
+            AccessType::Load => 13,
+            AccessType::Store => 15,
+        }
+    }
+}
+
+/// The address space of the running program.
+pub struct Memory {
+    pub pt: Sv39PageTable,
+    pub phys: PhysMem,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self {
+            pt: Sv39PageTable::new(),
+            phys: PhysMem::new(),
+        }
+    }
+
+    /// Map a fresh zeroed frame at the page containing `va`; returns its PPN.
+    pub fn map(&mut self, va: u64, flags: u64) -> u64 {
+        let ppn = self.phys.alloc_frame();
+        self.pt.map_page(va, ppn << 12, flags | PTE_V);
+        ppn
+    }
+
+    /// Translate `va` for a user-mode access of kind `access`.
+    ///
+    /// Fault (`Err(PageFault { addr: va, access })`) if:
+    /// - `va` is not canonical: bits 63..=39 must all equal bit 38
+    ///   (hint: `((va as i64) << 25 >> 25) as u64 == va`)
+    /// - there is no valid leaf PTE (`self.pt.leaf(va)`)
+    /// - the PTE lacks `PTE_U`
+    /// - the PTE lacks the permission for `access`: `PTE_X` (fetch), `PTE_R` (load),
+    ///   `PTE_W` (store)
+    ///
+    /// Otherwise the physical address is `(ppn << 12) | (va & 0xfff)`, `ppn = pte >> 10`.
+    pub fn translate(&self, va: u64, access: AccessType) -> Result<u64, PageFault> {
+        // TODO
+        todo!()
+    }
+
+    /// Little-endian read of `size` (1, 2, 4 or 8) bytes at `va`, zero-extended.
+    /// Misaligned accesses are not modelled and panic.
+    pub fn load(&self, va: u64, size: usize, access: AccessType) -> Result<u64, PageFault> {
+        assert_eq!(va % size as u64, 0, "misaligned access at {va:#x}");
+        let pa = self.translate(va, access)?;
+        Ok((0..size).rev().fold(0, |acc, i| {
+            (acc << 8) | self.phys.byte(pa + i as u64) as u64
+        }))
+    }
+
+    /// Little-endian write of the low `size` bytes of `value` at `va`.
+    pub fn store(&mut self, va: u64, size: usize, value: u64) -> Result<(), PageFault> {
+        assert_eq!(va % size as u64, 0, "misaligned access at {va:#x}");
+        let pa = self.translate(va, AccessType::Store)?;
+        for i in 0..size {
+            *self.phys.byte_mut(pa + i as u64) = (value >> (8 * i)) as u8;
+        }
+        Ok(())
+    }
+
+    /// Kernel-side write (no permission checks); panics if a page is not mapped.
+    pub fn write_bytes(&mut self, va: u64, bytes: &[u8]) {
+        for (i, &b) in bytes.iter().enumerate() {
+            let pa = self.kernel_translate(va + i as u64);
+            *self.phys.byte_mut(pa) = b;
+        }
+    }
+
+    /// Kernel-side read (no permission checks); panics if a page is not mapped.
+    pub fn read_bytes(&self, va: u64, len: usize) -> Vec<u8> {
+        (0..len as u64)
+            .map(|i| self.phys.byte(self.kernel_translate(va + i)))
+            .collect()
+    }
+
+    /// Write `words` as consecutive little-endian instructions from `va`.
+    pub fn write_program(&mut self, va: u64, words: &[u32]) {
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        self.write_bytes(va, &bytes);
+    }
+
+    fn kernel_translate(&self, va: u64) -> u64 {
+        let pte = self
+            .pt
+            .leaf(va)
+            .unwrap_or_else(|| panic!("kernel access to unmapped {va:#x}"));
+        ((pte >> PPN_SHIFT) << 12) | (va & 0xfff)
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// Decoder (given)
+// ============================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AluOp {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchCond {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+/// Decoded instruction. Register fields are indices `0..32`; immediates are sign-extended
+/// (shift amounts of `OpImm` are in `imm` as well).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inst {
+    Lui {
+        rd: usize,
+        imm: i64,
+    },
+    Auipc {
+        rd: usize,
+        imm: i64,
+    },
+    Jal {
+        rd: usize,
+        imm: i64,
+    },
+    Jalr {
+        rd: usize,
+        rs1: usize,
+        imm: i64,
+    },
+    Branch {
+        cond: BranchCond,
+        rs1: usize,
+        rs2: usize,
+        imm: i64,
+    },
+    /// `size` bytes; `signed` selects sign- or zero-extension.
+    Load {
+        size: usize,
+        signed: bool,
+        rd: usize,
+        rs1: usize,
+        imm: i64,
+    },
+    Store {
+        size: usize,
+        rs1: usize,
+        rs2: usize,
+        imm: i64,
+    },
+    OpImm {
+        op: AluOp,
+        rd: usize,
+        rs1: usize,
+        imm: i64,
+    },
+    Op {
+        op: AluOp,
+        rd: usize,
+        rs1: usize,
+        rs2: usize,
+    },
+    Ecall,
+    Ebreak,
+}
+
+/// Decode a 32-bit instruction word; `None` if it is not in the supported subset.
+pub fn decode(raw: u32) -> Option<Inst> {
+    let rd = ((raw >> 7) & 0x1f) as usize;
+    let rs1 = ((raw >> 15) & 0x1f) as usize;
+    let rs2 = ((raw >> 20) & 0x1f) as usize;
+    let funct3 = (raw >> 12) & 0x7;
+    let funct7 = raw >> 25;
+    let imm_i = (raw as i32 >> 20) as i64;
+    let imm_s = (((raw as i32 >> 25) << 5) | ((raw >> 7) & 0x1f) as i32) as i64;
+    let imm_b = (((raw as i32 >> 31) << 12)
+        | (((raw >> 7) & 0x1) << 11) as i32
+        | (((raw >> 25) & 0x3f) << 5) as i32
+        | (((raw >> 8) & 0xf) << 1) as i32) as i64;
+    let imm_u = (raw & 0xffff_f000) as i32 as i64;
+    let imm_j = (((raw as i32 >> 31) << 20)
+        | (raw & 0x000f_f000) as i32
+        | (((raw >> 20) & 0x1) << 11) as i32
+        | (((raw >> 21) & 0x3ff) << 1) as i32) as i64;
+
+    let inst = match raw & 0x7f {
+        0x37 => Inst::Lui { rd, imm: imm_u },
+        0x17 => Inst::Auipc { rd, imm: imm_u },
+        0x6f => Inst::Jal { rd, imm: imm_j },
+        0x67 if funct3 == 0 => Inst::Jalr {
+            rd,
+            rs1,
+            imm: imm_i,
+        },
+        0x63 => {
+            let cond = match funct3 {
+                0 => BranchCond::Eq,
+                1 => BranchCond::Ne,
+                4 => BranchCond::Lt,
+                5 => BranchCond::Ge,
+                6 => BranchCond::Ltu,
+                7 => BranchCond::Geu,
+                _ => return None,
+            };
+            Inst::Branch {
+                cond,
+                rs1,
+                rs2,
+                imm: imm_b,
+            }
+        }
+        0x03 => {
+            let (size, signed) = match funct3 {
+                0 => (1, true),
+                1 => (2, true),
+                2 => (4, true),
+                3 => (8, true),
+                4 => (1, false),
+                5 => (2, false),
+                6 => (4, false),
+                _ => return None,
+            };
+            Inst::Load {
+                size,
+                signed,
+                rd,
+                rs1,
+                imm: imm_i,
+            }
+        }
+        0x23 if funct3 < 4 => Inst::Store {
+            size: 1 << funct3,
+            rs1,
+            rs2,
+            imm: imm_s,
+        },
+        0x13 => {
+            let shamt = ((raw >> 20) & 0x3f) as i64;
+            let (op, imm) = match (funct3, raw >> 26) {
+                (0, _) => (AluOp::Add, imm_i),
+                (1, 0) => (AluOp::Sll, shamt),
+                (2, _) => (AluOp::Slt, imm_i),
+                (3, _) => (AluOp::Sltu, imm_i),
+                (4, _) => (AluOp::Xor, imm_i),
+                (5, 0) => (AluOp::Srl, shamt),
+                (5, 0x10) => (AluOp::Sra, shamt),
+                (6, _) => (AluOp::Or, imm_i),
+                (7, _) => (AluOp::And, imm_i),
+                _ => return None,
+            };
+            Inst::OpImm { op, rd, rs1, imm }
+        }
+        0x33 => {
+            let op = match (funct3, funct7) {
+                (0, 0) => AluOp::Add,
+                (0, 0x20) => AluOp::Sub,
+                (1, 0) => AluOp::Sll,
+                (2, 0) => AluOp::Slt,
+                (3, 0) => AluOp::Sltu,
+                (4, 0) => AluOp::Xor,
+                (5, 0) => AluOp::Srl,
+                (5, 0x20) => AluOp::Sra,
+                (6, 0) => AluOp::Or,
+                (7, 0) => AluOp::And,
+                _ => return None,
+            };
+            Inst::Op { op, rd, rs1, rs2 }
+        }
+        0x73 if raw == 0x0000_0073 => Inst::Ecall,
+        0x73 if raw == 0x0010_0073 => Inst::Ebreak,
+        _ => return None,
+    };
+    Some(inst)
+}
+
+// ============================================================
+// CPU
+// ============================================================
+
+/// Compute `a op b` on 64-bit registers.
+///
+/// - `Add`/`Sub` wrap around
+/// - `Slt`: 1 if `a < b` as signed, else 0; `Sltu` the same, unsigned
+/// - Shifts use only the low 6 bits of `b`; `Srl` is logical, `Sra` arithmetic
+pub fn alu(op: AluOp, a: u64, b: u64) -> u64 {
+    // TODO
+    todo!()
+}
+
+/// Why `step` did not retire an instruction. `pc` still points at the instruction concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    PageFault(PageFault),
+    /// The raw word that failed to decode.
+    IllegalInstruction(u32),
+    Ecall,
+    Ebreak,
+}
+
+/// What the page-fault callback of [`Cpu::run`] wants to happen next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    /// The handler fixed the mapping: execute the faulting instruction again.
+    Retry,
+    /// Give up: `run` returns the fault.
+    Kill,
+}
+
+/// Why [`Cpu::run`] returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Trap(Trap),
+    StepLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cpu {
+    x: [u64; 32],
+    pub pc: u64,
+}
+
+impl Cpu {
+    pub fn new(pc: u64) -> Self {
+        Self { x: [0; 32], pc }
+    }
+
+    pub fn reg(&self, i: usize) -> u64 {
+        self.x[i]
+    }
+
+    /// Write register `i`; writes to `x0` are ignored.
+    pub fn set_reg(&mut self, i: usize, value: u64) {
+        if i != 0 {
+            self.x[i] = value;
+        }
+    }
+
+    /// Execute one instruction.
+    ///
+    /// 1. Fetch: `mem.load(pc, 4, AccessType::Fetch)`; `decode` it, or
+    ///    `Trap::IllegalInstruction(raw)`
+    /// 2. Execute (`next = pc + 4` unless the instruction jumps):
+    ///    - `Lui`: `rd = imm`; `Auipc`: `rd = pc + imm`
+    ///    - `Jal`: `rd = pc + 4`, `next = pc + imm`; `Jalr`: `next = (rs1 + imm) & !1`, then
+    ///      `rd = pc + 4` (read `rs1` *before* writing `rd`: they may be the same register)
+    ///    - `Branch`: compare `rs1` with `rs2` (signed for `Lt`/`Ge`); if taken, `next = pc + imm`
+    ///    - `Load`: address `rs1 + imm`, `mem.load(addr, size, Load)`, sign-extend if `signed`
+    ///      (hint: shift left by `64 - 8 * size`, then arithmetic shift right as `i64`)
+    ///    - `Store`: `mem.store(rs1 + imm, size, rs2)`
+    ///    - `OpImm` / `Op`: `rd = alu(op, rs1, imm or rs2)`
+    ///    - `Ecall` / `Ebreak`: return `Trap::Ecall` / `Trap::Ebreak`
+    /// 3. Set `pc = next`.
+    ///
+    /// Any trap must leave the CPU untouched: return before writing a register or `pc`
+    /// (`?` on the memory access does exactly that). Use wrapping arithmetic for addresses.
+    pub fn step(&mut self, mem: &mut Memory) -> Result<(), Trap> {
+        // TODO
+        todo!()
+    }
+
+    /// Run for at most `max_steps` calls to `step`.
+    ///
+    /// - Page fault: call `on_fault(mem, fault)`; on `Retry` keep going (the retried
+    ///   instruction is executed by the next step), on `Kill` return `Exit::Trap` of the fault
+    /// - Any other trap: return `Exit::Trap(trap)` (e.g. stop at `ecall` so the caller can
+    ///   handle the syscall, advance `pc` by 4 and call `run` again)
+    /// - Out of steps: `Exit::StepLimit`
+    pub fn run(
+        &mut self,
+        mem: &mut Memory,
+        max_steps: usize,
+        mut on_fault: impl FnMut(&mut Memory, PageFault) -> FaultAction,
+    ) -> Exit {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_alu",
+            "Add/Sub wrap, Slt compares as i64 and Sltu as u64, shifts use only the low 6 bits of b, Sra shifts as i64",
+        ),
+        (
+            "test_translate_permissions",
+            "fault on a non-canonical va, a missing leaf, a missing PTE_U, or a missing R/W/X for the access",
+        ),
+        (
+            "test_loads_sign_and_zero_extend",
+            "signed loads: shift left by 64 - 8 * size, then arithmetic shift right as i64; unsigned loads keep the zero bits",
+        ),
+        (
+            "test_signed_vs_unsigned_branch",
+            "BranchCond::Lt/Ge compare rs1 and rs2 as i64, Ltu/Geu as u64",
+        ),
+        (
+            "test_x0_is_hardwired",
+            "write rd through set_reg, which drops writes to x0, instead of indexing the register array",
+        ),
+        (
+            "test_store_fault_is_precise",
+            "a trap must leave the registers and pc untouched: return before writing anything",
+        ),
+        (
+            "test_ecall_resume_and_step_limit",
+            "run stops with Exit::Trap(Ecall) without advancing pc, and with StepLimit once max_steps are used",
+        ),
+    ];
+
+    /// A hand assembler for the subset used by the test programs.
+    mod asm {
+        fn i_type(imm: i32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+            ((imm as u32 & 0xfff) << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode
+        }
+        fn r_type(funct7: u32, rs2: u32, rs1: u32, funct3: u32, rd: u32) -> u32 {
+            (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | 0x33
+        }
+        fn s_type(imm: i32, rs2: u32, rs1: u32, funct3: u32) -> u32 {
+            let imm = imm as u32;
+            ((imm >> 5 & 0x7f) << 25)
+                | (rs2 << 20)
+                | (rs1 << 15)
+                | (funct3 << 12)
+                | ((imm & 0x1f) << 7)
+                | 0x23
+        }
+        fn b_type(imm: i32, rs2: u32, rs1: u32, funct3: u32) -> u32 {
+            let imm = imm as u32;
+            ((imm >> 12 & 1) << 31)
+                | ((imm >> 5 & 0x3f) << 25)
+                | (rs2 << 20)
+                | (rs1 << 15)
+                | (funct3 << 12)
+                | ((imm >> 1 & 0xf) << 8)
+                | ((imm >> 11 & 1) << 7)
+                | 0x63
+        }
+
+        pub fn addi(rd: u32, rs1: u32, imm: i32) -> u32 {
+            i_type(imm, rs1, 0, rd, 0x13)
+        }
+        pub fn slli(rd: u32, rs1: u32, shamt: i32) -> u32 {
+            i_type(shamt, rs1, 1, rd, 0x13)
+        }
+        pub fn add(rd: u32, rs1: u32, rs2: u32) -> u32 {
+            r_type(0, rs2, rs1, 0, rd)
+        }
+        pub fn sub(rd: u32, rs1: u32, rs2: u32) -> u32 {
+            r_type(0x20, rs2, rs1, 0, rd)
+        }
+        pub fn lui(rd: u32, imm20: u32) -> u32 {
+            (imm20 << 12) | (rd << 7) | 0x37
+        }
+        pub fn auipc(rd: u32, imm20: u32) -> u32 {
+            (imm20 << 12) | (rd << 7) | 0x17
+        }
+        pub fn jal(rd: u32, imm: i32) -> u32 {
+            let imm = imm as u32;
+            ((imm >> 20 & 1) << 31)
+                | ((imm >> 1 & 0x3ff) << 21)
+                | ((imm >> 11 & 1) << 20)
+                | (imm & 0xff000)
+                | (rd << 7)
+                | 0x6f
+        }
+        pub fn jalr(rd: u32, rs1: u32, imm: i32) -> u32 {
+            i_type(imm, rs1, 0, rd, 0x67)
+        }
+        pub fn beq(rs1: u32, rs2: u32, imm: i32) -> u32 {
+            b_type(imm, rs2, rs1, 0)
+        }
+        pub fn bne(rs1: u32, rs2: u32, imm: i32) -> u32 {
+            b_type(imm, rs2, rs1, 1)
+        }
+        pub fn blt(rs1: u32, rs2: u32, imm: i32) -> u32 {
+            b_type(imm, rs2, rs1, 4)
+        }
+        pub fn bltu(rs1: u32, rs2: u32, imm: i32) -> u32 {
+            b_type(imm, rs2, rs1, 6)
+        }
+        pub fn lb(rd: u32, rs1: u32, imm: i32) -> u32 {
+            i_type(imm, rs1, 0, rd, 0x03)
+        }
+        pub fn lbu(rd: u32, rs1: u32, imm: i32) -> u32 {
+            i_type(imm, rs1, 4, rd, 0x03)
+        }
+        pub fn ld(rd: u32, rs1: u32, imm: i32) -> u32 {
+            i_type(imm, rs1, 3, rd, 0x03)
+        }
+        pub fn sb(rs2: u32, rs1: u32, imm: i32) -> u32 {
+            s_type(imm, rs2, rs1, 0)
+        }
+        pub fn sd(rs2: u32, rs1: u32, imm: i32) -> u32 {
+            s_type(imm, rs2, rs1, 3)
+        }
+        pub const ECALL: u32 = 0x0000_0073;
+        pub const EBREAK: u32 = 0x0010_0073;
+    }
+    use asm::*;
+
+    const CODE: u64 = 0x1000;
+    const DATA: u64 = 0x2000;
+    const RODATA: u64 = 0x3000;
+
+    /// Code (U|R|X) at `CODE`, data (U|R|W) at `DATA`, read-only data (U|R) at `RODATA`.
+    fn setup(program: &[u32]) -> (Cpu, Memory) {
+        let mut mem = Memory::new();
+        mem.map(CODE, PTE_U | PTE_R | PTE_X);
+        mem.map(DATA, PTE_U | PTE_R | PTE_W);
+        mem.map(RODATA, PTE_U | PTE_R);
+        mem.write_program(CODE, program);
+        (Cpu::new(CODE), mem)
+    }
+
+    fn no_faults(_: &mut Memory, f: PageFault) -> FaultAction {
+        panic!("unexpected {f:?}")
+    }
+
+    #[test]
+    fn test_alu() {
+        assert_eq_with_hint!(alu(AluOp::Add, u64::MAX, 2), 1);
+        assert_eq_with_hint!(alu(AluOp::Sub, 1, 2), u64::MAX);
+        assert_eq_with_hint!(alu(AluOp::Slt, -1i64 as u64, 0), 1);
+        assert_eq_with_hint!(alu(AluOp::Sltu, -1i64 as u64, 0), 0);
+        assert_eq_with_hint!(alu(AluOp::Sll, 1, 65), 2, "only the low 6 bits shift");
+        assert_eq_with_hint!(alu(AluOp::Srl, 1 << 63, 63), 1);
+        assert_eq_with_hint!(alu(AluOp::Sra, 1 << 63, 63), u64::MAX);
+        assert_eq_with_hint!(alu(AluOp::Xor, 0b1100, 0b1010), 0b0110);
+        assert_eq_with_hint!(alu(AluOp::Or, 0b1100, 0b1010), 0b1110);
+        assert_eq_with_hint!(alu(AluOp::And, 0b1100, 0b1010), 0b1000);
+    }
+
+    #[test]
+    fn test_translate_permissions() {
+        let (_, mut mem) = setup(&[]);
+        mem.map(0x4000, PTE_R | PTE_W); // kernel page: no PTE_U
+        assert_eq_with_hint!(
+            mem.translate(DATA + 8, AccessType::Store)
+                .map(|pa| pa & 0xfff),
+            Ok(8)
+        );
+        assert_with_hint!(mem.translate(CODE, AccessType::Fetch).is_ok());
+        assert_with_hint!(mem.translate(RODATA, AccessType::Load).is_ok());
+
+        let fault = |addr, access| Err(PageFault { addr, access });
+        assert_eq_with_hint!(
+            mem.translate(RODATA + 4, AccessType::Store),
+            fault(RODATA + 4, AccessType::Store)
+        );
+        assert_eq_with_hint!(
+            mem.translate(DATA, AccessType::Fetch),
+            fault(DATA, AccessType::Fetch)
+        );
+        assert_eq_with_hint!(
+            mem.translate(0x4000, AccessType::Load),
+            fault(0x4000, AccessType::Load)
+        );
+        assert_eq_with_hint!(
+            mem.translate(0x5000, AccessType::Load),
+            fault(0x5000, AccessType::Load)
+        );
+        // Same low 39 bits as DATA, but not canonical.
+        let bad = DATA | (1 << 50);
+        assert_eq_with_hint!(
+            mem.translate(bad, AccessType::Load),
+            fault(bad, AccessType::Load)
+        );
+
+        let f = PageFault {
+            addr: 0,
+            access: AccessType::Store,
+        };
+        assert_eq_with_hint!(f.scause(), 15);
+    }
+
+    #[test]
+    fn test_sum_loop_stores_result() {
+        // sum = 1 + 2 + ... + 10, stored at DATA; then ecall.
+        let program = [
+            addi(10, 0, 0),  // a0 = 0 (sum)
+            addi(11, 0, 1),  // a1 = 1 (i)
+            addi(12, 0, 11), // a2 = 11
+            add(10, 10, 11), // loop: sum += i
+            addi(11, 11, 1), //       i += 1
+            blt(11, 12, -8), //       if i < 11 goto loop
+            lui(5, 0x2),     // t0 = DATA
+            sd(10, 5, 0),    // *t0 = sum
+            ECALL,
+        ];
+        let (mut cpu, mut mem) = setup(&program);
+        assert_eq!(cpu.run(&mut mem, 1000, no_faults), Exit::Trap(Trap::Ecall));
+        assert_eq!(cpu.reg(10), 55);
+        assert_eq!(cpu.pc, CODE + 8 * 4, "pc stays on the ecall");
+        assert_eq!(mem.read_bytes(DATA, 8), 55u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_loads_sign_and_zero_extend() {
+        let (mut cpu, mut mem) = setup(&[
+            lui(5, 0x3),   // t0 = RODATA
+            lb(6, 5, 0),   // t1 = sext(0xff)
+            lbu(7, 5, 0),  // t2 = zext(0xff)
+            ld(28, 5, 8),  // t3 = 64-bit word
+            sub(29, 0, 7), // t4 = -255
+            EBREAK,
+        ]);
+        mem.write_bytes(RODATA, &[0xff]);
+        mem.write_bytes(RODATA + 8, &0x1122_3344_5566_7788u64.to_le_bytes());
+        assert_eq_with_hint!(cpu.run(&mut mem, 100, no_faults), Exit::Trap(Trap::Ebreak));
+        assert_eq_with_hint!(cpu.reg(6), u64::MAX);
+        assert_eq_with_hint!(cpu.reg(7), 0xff);
+        assert_eq_with_hint!(cpu.reg(28), 0x1122_3344_5566_7788);
+        assert_eq_with_hint!(cpu.reg(29) as i64, -255);
+    }
+
+    #[test]
+    fn test_signed_vs_unsigned_branch() {
+        let (mut cpu, mut mem) = setup(&[
+            addi(5, 0, -1),
+            addi(6, 0, 1),
+            blt(5, 6, 8),   // -1 < 1: taken
+            addi(10, 0, 1), // skipped
+            bltu(5, 6, 8),  // u64::MAX < 1: not taken
+            addi(11, 0, 1),
+            EBREAK,
+        ]);
+        assert_eq_with_hint!(cpu.run(&mut mem, 10, no_faults), Exit::Trap(Trap::Ebreak));
+        assert_eq_with_hint!((cpu.reg(10), cpu.reg(11)), (0, 1));
+    }
+
+    #[test]
+    fn test_call_and_return() {
+        // main: a0 = 20; call double; a0 += 1; ebreak
+        // double (at CODE + 0x20): a0 = a0 << 1; ret
+        let mut program = vec![
+            addi(10, 0, 20),
+            auipc(1, 0),      // ra = CODE + 4
+            jal(1, 0x20 - 8), // call double (at CODE + 0x20)
+            addi(10, 10, 1),
+            EBREAK,
+        ];
+        program.resize(8, 0);
+        program.extend([slli(10, 10, 1), jalr(0, 1, 0)]);
+        let (mut cpu, mut mem) = setup(&program);
+        assert_eq!(cpu.run(&mut mem, 100, no_faults), Exit::Trap(Trap::Ebreak));
+        assert_eq!(cpu.reg(10), 41);
+        assert_eq!(
+            cpu.reg(1),
+            CODE + 12,
+            "jal wrote the return address over auipc's value"
+        );
+    }
+
+    #[test]
+    fn test_x0_is_hardwired() {
+        let (mut cpu, mut mem) = setup(&[addi(0, 0, 5), add(6, 0, 0), EBREAK]);
+        cpu.run(&mut mem, 10, no_faults);
+        assert_eq_with_hint!(cpu.reg(0), 0);
+        assert_eq_with_hint!(cpu.reg(6), 0);
+    }
+
+    #[test]
+    fn test_store_fault_is_precise() {
+        let (mut cpu, mut mem) = setup(&[
+            addi(6, 0, 7),
+            lui(5, 0x3), // t0 = RODATA
+            sb(6, 5, 3), // faults: RODATA is read-only
+            addi(6, 0, 9),
+        ]);
+        let mut faults = vec![];
+        let exit = cpu.run(&mut mem, 100, |_, f| {
+            faults.push(f);
+            FaultAction::Kill
+        });
+        let fault = PageFault {
+            addr: RODATA + 3,
+            access: AccessType::Store,
+        };
+        assert_eq_with_hint!(exit, Exit::Trap(Trap::PageFault(fault)));
+        assert_eq_with_hint!(faults, [fault]);
+        assert_eq_with_hint!(cpu.pc, CODE + 8, "pc points at the faulting store");
+        assert_eq_with_hint!(cpu.reg(6), 7);
+        assert_eq_with_hint!(mem.read_bytes(RODATA, 4), [0; 4]);
+    }
+
+    #[test]
+    fn test_demand_paging_retries() {
+        // Fill 3 pages from 0x10_0000 with one byte each; none is mapped yet.
+        let (mut cpu, mut mem) = setup(&[
+            lui(5, 0x100), // t0 = 0x10_0000
+            addi(6, 0, 3), // t1 = 3 pages left
+            lui(7, 0x1),   // t2 = PAGE_SIZE
+            sb(6, 5, 0),   // loop: *t0 = t1
+            add(5, 5, 7),
+            addi(6, 6, -1),
+            bne(6, 0, -12),
+            ECALL,
+        ]);
+        let mut faults = vec![];
+        let exit = cpu.run(&mut mem, 100, |mem, f| {
+            faults.push(f);
+            mem.map(f.addr, PTE_U | PTE_R | PTE_W);
+            FaultAction::Retry
+        });
+        assert_eq!(exit, Exit::Trap(Trap::Ecall));
+        let addrs: Vec<u64> = faults.iter().map(|f| f.addr).collect();
+        assert_eq!(addrs, [0x10_0000, 0x10_1000, 0x10_2000]);
+        assert!(faults.iter().all(|f| f.access == AccessType::Store));
+        assert_eq!(mem.read_bytes(0x10_0000, 1), [3]);
+        assert_eq!(mem.read_bytes(0x10_2000, 1), [1]);
+    }
+
+    #[test]
+    fn test_fetch_fault_and_illegal_instruction() {
+        // Jump into the data page: not executable.
+        let (mut cpu, mut mem) = setup(&[lui(5, 0x2), jalr(1, 5, 0)]);
+        let exit = cpu.run(&mut mem, 10, |_, _| FaultAction::Kill);
+        let fault = PageFault {
+            addr: DATA,
+            access: AccessType::Fetch,
+        };
+        assert_eq!(exit, Exit::Trap(Trap::PageFault(fault)));
+        assert_eq!((cpu.pc, cpu.reg(1)), (DATA, CODE + 8));
+
+        let (mut cpu, mut mem) = setup(&[addi(5, 0, 1), 0xffff_ffff]);
+        assert_eq!(
+            cpu.run(&mut mem, 10, no_faults),
+            Exit::Trap(Trap::IllegalInstruction(0xffff_ffff))
+        );
+        assert_eq!(cpu.pc, CODE + 4);
+    }
+
+    #[test]
+    fn test_ecall_resume_and_step_limit() {
+        // a7 = 1; ecall; a7 = 2; ecall; loop forever
+        let (mut cpu, mut mem) =
+            setup(&[addi(17, 0, 1), ECALL, addi(17, 0, 2), ECALL, beq(0, 0, 0)]);
+        let mut syscalls = vec![];
+        while let Exit::Trap(Trap::Ecall) = cpu.run(&mut mem, 100, no_faults) {
+            syscalls.push(cpu.reg(17));
+            cpu.pc += 4;
+        }
+        assert_eq_with_hint!(syscalls, [1, 2]);
+        assert_eq_with_hint!(cpu.pc, CODE + 16);
+    }
+}
@@ -0,0 +1,608 @@
+//! # A Tiny RV64 Interpreter over Sv39
+//!
+//! In this exercise, you build a minimal user-mode RISC-V CPU: 32 integer registers and a `pc`,
+//! executing arithmetic, load/store and branch instructions. Every instruction fetch and every
+//! data access goes through an Sv39 page table **with permission checks**, so a program can page
+//! fault; faults are handed to a callback, the way a kernel's trap handler would see them.
+//!
+//! ## Concepts
+//! - Fetch → decode → execute: `pc` selects the instruction, `decode` (given) turns the 32-bit
+//!   word into an [`Inst`], executing it updates registers, memory and `pc`
+//! - `x0` always reads as zero; writes to it are dropped
+//! - The MMU checks each access against the leaf PTE: `V`, `U` (the CPU runs in user mode) and
+//!   `X` for fetches, `R` for loads, `W` for stores. A failed check is an instruction /
+//!   load / store **page fault** (`scause` 12 / 13 / 15) with the faulting address (`stval`)
+//! - Faults are **precise**: the faulting instruction has no effect at all (no register written,
+//!   no memory changed, `pc` still points at it), so after the handler fixed the mapping the
+//!   instruction can simply be executed again — that is how demand paging works
+//!
+//! ## Given
+//! `PhysMem`, `Memory` with `map` and kernel-side `write_bytes`/`read_bytes` (no permission
+//! checks), `load`/`store` built on your `translate`, and `decode` for the RV64I subset below.
+//! The page table is your `Sv39PageTable` from `03_multi_level_pt`, so finish that first: its
+//! `leaf` is where `translate` starts.
+//!
+//! | Group     | Instructions                                                        |
+//! |-----------|---------------------------------------------------------------------|
+//! | Upper     | `lui`, `auipc`                                                      |
+//! | Jumps     | `jal`, `jalr`                                                       |
+//! | Branches  | `beq`, `bne`, `blt`, `bge`, `bltu`, `bgeu`                          |
+//! | Loads     | `lb`, `lh`, `lw`, `ld`, `lbu`, `lhu`, `lwu`                         |
+//! | Stores    | `sb`, `sh`, `sw`, `sd`                                              |
+//! | ALU       | `add(i)`, `sub`, `sll(i)`, `slt(i)`, `slt(i)u`, `xor(i)`, `srl(i)`, `sra(i)`, `or(i)`, `and(i)` |
+//! | System    | `ecall`, `ebreak`                                                   |
+
+use std::collections::HashMap;
+
+pub use multi_level_pt::solution::{Sv39PageTable, PAGE_SIZE, PTE_R, PTE_U, PTE_V, PTE_W, PTE_X};
+
+const PPN_SHIFT: u32 = 10;
+
+// ============================================================
+// Physical memory (given)
+// ============================================================
+
+/// Simulated RAM: zeroed 4 KiB frames by PPN.
+pub struct PhysMem {
+    frames: HashMap<u64, Box<[u8; PAGE_SIZE]>>,
+    next_ppn: u64,
+}
+
+impl PhysMem {
+    pub fn new() -> Self {
+        Self {
+            frames: HashMap::new(),
+            next_ppn: 0x90000,
+        }
+    }
+
+    pub fn alloc_frame(&mut self) -> u64 {
+        let ppn = self.next_ppn;
+        self.next_ppn += 1;
+        self.frames.insert(ppn, Box::new([0; PAGE_SIZE]));
+        ppn
+    }
+
+    fn byte(&self, pa: u64) -> u8 {
+        self.frames
+            .get(&(pa >> 12))
+            .expect("access to unallocated physical memory")[(pa as usize) % PAGE_SIZE]
+    }
+
+    fn byte_mut(&mut self, pa: u64) -> &mut u8 {
+        &mut self
+            .frames
+            .get_mut(&(pa >> 12))
+            .expect("access to unallocated physical memory")[(pa as usize) % PAGE_SIZE]
+    }
+}
+
+impl Default for PhysMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// Memory access through the MMU
+// ============================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessType {
+    Fetch,
+    Load,
+    Store,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageFault {
+    /// Faulting virtual address (what `stval` would hold).
+    pub addr: u64,
+    pub access: AccessType,
+}
+
+impl PageFault {
+    /// The `scause` exception code of this fault.
+    pub fn scause(&self) -> u64 {
+        match self.access {
+            AccessType::Fetch => 12,
+            AccessType::Load => 13,
+            AccessType::Store => 15,
+        }
+    }
+}
+
+/// The address space of the running program.
+pub struct Memory {
+    pub pt: Sv39PageTable,
+    pub phys: PhysMem,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self {
+            pt: Sv39PageTable::new(),
+            phys: PhysMem::new(),
+        }
+    }
+
+    /// Map a fresh zeroed frame at the page containing `va`; returns its PPN.
+    pub fn map(&mut self, va: u64, flags: u64) -> u64 {
+        let ppn = self.phys.alloc_frame();
+        self.pt.map_page(va, ppn << 12, flags | PTE_V);
+        ppn
+    }
+
+    /// Translate `va` for a user-mode access of kind `access`.
+    ///
+    /// Fault (`Err(PageFault { addr: va, access })`) if:
+    /// - `va` is not canonical: bits 63..=39 must all equal bit 38
+    ///   (hint: `((va as i64) << 25 >> 25) as u64 == va`)
+    /// - there is no valid leaf PTE (`self.pt.leaf(va)`)
+    /// - the PTE lacks `PTE_U`
+    /// - the PTE lacks the permission for `access`: `PTE_X` (fetch), `PTE_R` (load),
+    ///   `PTE_W` (store)
+    ///
+    /// Otherwise the physical address is `(ppn << 12) | (va & 0xfff)`, `ppn = pte >> 10`.
+    pub fn translate(&self, va: u64, access: AccessType) -> Result<u64, PageFault> {
+        let fault = PageFault { addr: va, access };
+        if ((va as i64) << 25 >> 25) as u64 != va {
+            return Err(fault);
+        }
+        let pte = self.pt.leaf(va).ok_or(fault)?;
+        let needed = match access {
+            AccessType::Fetch => PTE_X,
+            AccessType::Load => PTE_R,
+            AccessType::Store => PTE_W,
+        };
+        if pte & PTE_U == 0 || pte & needed == 0 {
+            return Err(fault);
+        }
+        Ok(((pte >> PPN_SHIFT) << 12) | (va & 0xfff))
+    }
+
+    /// Little-endian read of `size` (1, 2, 4 or 8) bytes at `va`, zero-extended.
+    /// Misaligned accesses are not modelled and panic.
+    pub fn load(&self, va: u64, size: usize, access: AccessType) -> Result<u64, PageFault> {
+        assert_eq!(va % size as u64, 0, "misaligned access at {va:#x}");
+        let pa = self.translate(va, access)?;
+        Ok((0..size).rev().fold(0, |acc, i| {
+            (acc << 8) | self.phys.byte(pa + i as u64) as u64
+        }))
+    }
+
+    /// Little-endian write of the low `size` bytes of `value` at `va`.
+    pub fn store(&mut self, va: u64, size: usize, value: u64) -> Result<(), PageFault> {
+        assert_eq!(va % size as u64, 0, "misaligned access at {va:#x}");
+        let pa = self.translate(va, AccessType::Store)?;
+        for i in 0..size {
+            *self.phys.byte_mut(pa + i as u64) = (value >> (8 * i)) as u8;
+        }
+        Ok(())
+    }
+
+    /// Kernel-side write (no permission checks); panics if a page is not mapped.
+    pub fn write_bytes(&mut self, va: u64, bytes: &[u8]) {
+        for (i, &b) in bytes.iter().enumerate() {
+            let pa = self.kernel_translate(va + i as u64);
+            *self.phys.byte_mut(pa) = b;
+        }
+    }
+
+    /// Kernel-side read (no permission checks); panics if a page is not mapped.
+    pub fn read_bytes(&self, va: u64, len: usize) -> Vec<u8> {
+        (0..len as u64)
+            .map(|i| self.phys.byte(self.kernel_translate(va + i)))
+            .collect()
+    }
+
+    /// Write `words` as consecutive little-endian instructions from `va`.
+    pub fn write_program(&mut self, va: u64, words: &[u32]) {
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        self.write_bytes(va, &bytes);
+    }
+
+    fn kernel_translate(&self, va: u64) -> u64 {
+        let pte = self
+            .pt
+            .leaf(va)
+            .unwrap_or_else(|| panic!("kernel access to unmapped {va:#x}"));
+        ((pte >> PPN_SHIFT) << 12) | (va & 0xfff)
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// Decoder (given)
+// ============================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AluOp {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchCond {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Ltu,
+    Geu,
+}
+
+/// Decoded instruction. Register fields are indices `0..32`; immediates are sign-extended
+/// (shift amounts of `OpImm` are in `imm` as well).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inst {
+    Lui {
+        rd: usize,
+        imm: i64,
+    },
+    Auipc {
+        rd: usize,
+        imm: i64,
+    },
+    Jal {
+        rd: usize,
+        imm: i64,
+    },
+    Jalr {
+        rd: usize,
+        rs1: usize,
+        imm: i64,
+    },
+    Branch {
+        cond: BranchCond,
+        rs1: usize,
+        rs2: usize,
+        imm: i64,
+    },
+    /// `size` bytes; `signed` selects sign- or zero-extension.
+    Load {
+        size: usize,
+        signed: bool,
+        rd: usize,
+        rs1: usize,
+        imm: i64,
+    },
+    Store {
+        size: usize,
+        rs1: usize,
+        rs2: usize,
+        imm: i64,
+    },
+    OpImm {
+        op: AluOp,
+        rd: usize,
+        rs1: usize,
+        imm: i64,
+    },
+    Op {
+        op: AluOp,
+        rd: usize,
+        rs1: usize,
+        rs2: usize,
+    },
+    Ecall,
+    Ebreak,
+}
+
+/// Decode a 32-bit instruction word; `None` if it is not in the supported subset.
+pub fn decode(raw: u32) -> Option<Inst> {
+    let rd = ((raw >> 7) & 0x1f) as usize;
+    let rs1 = ((raw >> 15) & 0x1f) as usize;
+    let rs2 = ((raw >> 20) & 0x1f) as usize;
+    let funct3 = (raw >> 12) & 0x7;
+    let funct7 = raw >> 25;
+    let imm_i = (raw as i32 >> 20) as i64;
+    let imm_s = (((raw as i32 >> 25) << 5) | ((raw >> 7) & 0x1f) as i32) as i64;
+    let imm_b = (((raw as i32 >> 31) << 12)
+        | (((raw >> 7) & 0x1) << 11) as i32
+        | (((raw >> 25) & 0x3f) << 5) as i32
+        | (((raw >> 8) & 0xf) << 1) as i32) as i64;
+    let imm_u = (raw & 0xffff_f000) as i32 as i64;
+    let imm_j = (((raw as i32 >> 31) << 20)
+        | (raw & 0x000f_f000) as i32
+        | (((raw >> 20) & 0x1) << 11) as i32
+        | (((raw >> 21) & 0x3ff) << 1) as i32) as i64;
+
+    let inst = match raw & 0x7f {
+        0x37 => Inst::Lui { rd, imm: imm_u },
+        0x17 => Inst::Auipc { rd, imm: imm_u },
+        0x6f => Inst::Jal { rd, imm: imm_j },
+        0x67 if funct3 == 0 => Inst::Jalr {
+            rd,
+            rs1,
+            imm: imm_i,
+        },
+        0x63 => {
+            let cond = match funct3 {
+                0 => BranchCond::Eq,
+                1 => BranchCond::Ne,
+                4 => BranchCond::Lt,
+                5 => BranchCond::Ge,
+                6 => BranchCond::Ltu,
+                7 => BranchCond::Geu,
+                _ => return None,
+            };
+            Inst::Branch {
+                cond,
+                rs1,
+                rs2,
+                imm: imm_b,
+            }
+        }
+        0x03 => {
+            let (size, signed) = match funct3 {
+                0 => (1, true),
+                1 => (2, true),
+                2 => (4, true),
+                3 => (8, true),
+                4 => (1, false),
+                5 => (2, false),
+                6 => (4, false),
+                _ => return None,
+            };
+            Inst::Load {
+                size,
+                signed,
+                rd,
+                rs1,
+                imm: imm_i,
+            }
+        }
+        0x23 if funct3 < 4 => Inst::Store {
+            size: 1 << funct3,
+            rs1,
+            rs2,
+            imm: imm_s,
+        },
+        0x13 => {
+            let shamt = ((raw >> 20) & 0x3f) as i64;
+            let (op, imm) = match (funct3, raw >> 26) {
+                (0, _) => (AluOp::Add, imm_i),
+                (1, 0) => (AluOp::Sll, shamt),
+                (2, _) => (AluOp::Slt, imm_i),
+                (3, _) => (AluOp::Sltu, imm_i),
+                (4, _) => (AluOp::Xor, imm_i),
+                (5, 0) => (AluOp::Srl, shamt),
+                (5, 0x10) => (AluOp::Sra, shamt),
+                (6, _) => (AluOp::Or, imm_i),
+                (7, _) => (AluOp::And, imm_i),
+                _ => return None,
+            };
+            Inst::OpImm { op, rd, rs1, imm }
+        }
+        0x33 => {
+            let op = match (funct3, funct7) {
+                (0, 0) => AluOp::Add,
+                (0, 0x20) => AluOp::Sub,
+                (1, 0) => AluOp::Sll,
+                (2, 0) => AluOp::Slt,
+                (3, 0) => AluOp::Sltu,
+                (4, 0) => AluOp::Xor,
+                (5, 0) => AluOp::Srl,
+                (5, 0x20) => AluOp::Sra,
+                (6, 0) => AluOp::Or,
+                (7, 0) => AluOp::And,
+                _ => return None,
+            };
+            Inst::Op { op, rd, rs1, rs2 }
+        }
+        0x73 if raw == 0x0000_0073 => Inst::Ecall,
+        0x73 if raw == 0x0010_0073 => Inst::Ebreak,
+        _ => return None,
+    };
+    Some(inst)
+}
+
+// ============================================================
+// CPU
+// ============================================================
+
+/// Compute `a op b` on 64-bit registers.
+///
+/// - `Add`/`Sub` wrap around
+/// - `Slt`: 1 if `a < b` as signed, else 0; `Sltu` the same, unsigned
+/// - Shifts use only the low 6 bits of `b`; `Srl` is logical, `Sra` arithmetic
+pub fn alu(op: AluOp, a: u64, b: u64) -> u64 {
+    let shamt = (b & 63) as u32;
+    match op {
+        AluOp::Add => a.wrapping_add(b),
+        AluOp::Sub => a.wrapping_sub(b),
+        AluOp::Sll => a << shamt,
+        AluOp::Slt => ((a as i64) < (b as i64)) as u64,
+        AluOp::Sltu => (a < b) as u64,
+        AluOp::Xor => a ^ b,
+        AluOp::Srl => a >> shamt,
+        AluOp::Sra => ((a as i64) >> shamt) as u64,
+        AluOp::Or => a | b,
+        AluOp::And => a & b,
+    }
+}
+
+/// Why `step` did not retire an instruction. `pc` still points at the instruction concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    PageFault(PageFault),
+    /// The raw word that failed to decode.
+    IllegalInstruction(u32),
+    Ecall,
+    Ebreak,
+}
+
+/// What the page-fault callback of [`Cpu::run`] wants to happen next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultAction {
+    /// The handler fixed the mapping: execute the faulting instruction again.
+    Retry,
+    /// Give up: `run` returns the fault.
+    Kill,
+}
+
+/// Why [`Cpu::run`] returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Trap(Trap),
+    StepLimit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cpu {
+    x: [u64; 32],
+    pub pc: u64,
+}
+
+impl Cpu {
+    pub fn new(pc: u64) -> Self {
+        Self { x: [0; 32], pc }
+    }
+
+    pub fn reg(&self, i: usize) -> u64 {
+        self.x[i]
+    }
+
+    /// Write register `i`; writes to `x0` are ignored.
+    pub fn set_reg(&mut self, i: usize, value: u64) {
+        if i != 0 {
+            self.x[i] = value;
+        }
+    }
+
+    /// Execute one instruction.
+    ///
+    /// 1. Fetch: `mem.load(pc, 4, AccessType::Fetch)`; `decode` it, or
+    ///    `Trap::IllegalInstruction(raw)`
+    /// 2. Execute (`next = pc + 4` unless the instruction jumps):
+    ///    - `Lui`: `rd = imm`; `Auipc`: `rd = pc + imm`
+    ///    - `Jal`: `rd = pc + 4`, `next = pc + imm`; `Jalr`: `next = (rs1 + imm) & !1`, then
+    ///      `rd = pc + 4` (read `rs1` *before* writing `rd`: they may be the same register)
+    ///    - `Branch`: compare `rs1` with `rs2` (signed for `Lt`/`Ge`); if taken, `next = pc + imm`
+    ///    - `Load`: address `rs1 + imm`, `mem.load(addr, size, Load)`, sign-extend if `signed`
+    ///      (hint: shift left by `64 - 8 * size`, then arithmetic shift right as `i64`)
+    ///    - `Store`: `mem.store(rs1 + imm, size, rs2)`
+    ///    - `OpImm` / `Op`: `rd = alu(op, rs1, imm or rs2)`
+    ///    - `Ecall` / `Ebreak`: return `Trap::Ecall` / `Trap::Ebreak`
+    /// 3. Set `pc = next`.
+    ///
+    /// Any trap must leave the CPU untouched: return before writing a register or `pc`
+    /// (`?` on the memory access does exactly that). Use wrapping arithmetic for addresses.
+    pub fn step(&mut self, mem: &mut Memory) -> Result<(), Trap> {
+        let raw = mem
+            .load(self.pc, 4, AccessType::Fetch)
+            .map_err(Trap::PageFault)? as u32;
+        let inst = decode(raw).ok_or(Trap::IllegalInstruction(raw))?;
+        let pc = self.pc;
+        let mut next = pc.wrapping_add(4);
+        match inst {
+            Inst::Lui { rd, imm } => self.set_reg(rd, imm as u64),
+            Inst::Auipc { rd, imm } => self.set_reg(rd, pc.wrapping_add(imm as u64)),
+            Inst::Jal { rd, imm } => {
+                self.set_reg(rd, pc.wrapping_add(4));
+                next = pc.wrapping_add(imm as u64);
+            }
+            Inst::Jalr { rd, rs1, imm } => {
+                next = self.reg(rs1).wrapping_add(imm as u64) & !1;
+                self.set_reg(rd, pc.wrapping_add(4));
+            }
+            Inst::Branch {
+                cond,
+                rs1,
+                rs2,
+                imm,
+            } => {
+                let (a, b) = (self.reg(rs1), self.reg(rs2));
+                let taken = match cond {
+                    BranchCond::Eq => a == b,
+                    BranchCond::Ne => a != b,
+                    BranchCond::Lt => (a as i64) < (b as i64),
+                    BranchCond::Ge => (a as i64) >= (b as i64),
+                    BranchCond::Ltu => a < b,
+                    BranchCond::Geu => a >= b,
+                };
+                if taken {
+                    next = pc.wrapping_add(imm as u64);
+                }
+            }
+            Inst::Load {
+                size,
+                signed,
+                rd,
+                rs1,
+                imm,
+            } => {
+                let addr = self.reg(rs1).wrapping_add(imm as u64);
+                let mut v = mem
+                    .load(addr, size, AccessType::Load)
+                    .map_err(Trap::PageFault)?;
+                if signed {
+                    let shift = 64 - 8 * size as u32;
+                    v = (((v << shift) as i64) >> shift) as u64;
+                }
+                self.set_reg(rd, v);
+            }
+            Inst::Store {
+                size,
+                rs1,
+                rs2,
+                imm,
+            } => {
+                let addr = self.reg(rs1).wrapping_add(imm as u64);
+                mem.store(addr, size, self.reg(rs2))
+                    .map_err(Trap::PageFault)?;
+            }
+            Inst::OpImm { op, rd, rs1, imm } => {
+                self.set_reg(rd, alu(op, self.reg(rs1), imm as u64))
+            }
+            Inst::Op { op, rd, rs1, rs2 } => {
+                self.set_reg(rd, alu(op, self.reg(rs1), self.reg(rs2)))
+            }
+            Inst::Ecall => return Err(Trap::Ecall),
+            Inst::Ebreak => return Err(Trap::Ebreak),
+        }
+        self.pc = next;
+        Ok(())
+    }
+
+    /// Run for at most `max_steps` calls to `step`.
+    ///
+    /// - Page fault: call `on_fault(mem, fault)`; on `Retry` keep going (the retried
+    ///   instruction is executed by the next step), on `Kill` return `Exit::Trap` of the fault
+    /// - Any other trap: return `Exit::Trap(trap)` (e.g. stop at `ecall` so the caller can
+    ///   handle the syscall, advance `pc` by 4 and call `run` again)
+    /// - Out of steps: `Exit::StepLimit`
+    pub fn run(
+        &mut self,
+        mem: &mut Memory,
+        max_steps: usize,
+        mut on_fault: impl FnMut(&mut Memory, PageFault) -> FaultAction,
+    ) -> Exit {
+        for _ in 0..max_steps {
+            match self.step(mem) {
+                Ok(()) => {}
+                Err(Trap::PageFault(f)) => {
+                    if on_fault(mem, f) == FaultAction::Kill {
+                        return Exit::Trap(Trap::PageFault(f));
+                    }
+                }
+                Err(trap) => return Exit::Trap(trap),
+            }
+        }
+        Exit::StepLimit
+    }
+}
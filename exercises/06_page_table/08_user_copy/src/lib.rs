@@ -0,0 +1,341 @@
+//! # `copy_from_user` / `copy_to_user`
+//!
+//! In this exercise, you implement the kernel's safe access to user memory. A system call gets
+//! raw user pointers (`write(fd, buf, len)`); before touching them the kernel must check that the
+//! **whole** range is mapped in the process's page table, accessible from user mode, and readable
+//! (or writable) — otherwise the call fails with `EFAULT` instead of crashing the kernel or
+//! leaking kernel memory.
+//!
+//! ## Concepts
+//! - A user buffer is a virtual range `[va, va + len)`; it may **straddle pages** whose frames
+//!   are anywhere in physical memory, so the copy goes page by page, translating each one
+//! - Validation covers every page of the range: a single unmapped page in the middle (a hole),
+//!   a kernel page (no `PTE_U`) or a missing `R`/`W` permission makes the whole call fail
+//! - Arithmetic on user pointers must not overflow, and must stay below `USER_END`
+//! - Validate first, then copy: a failed `copy_to_user` leaves user memory untouched
+//! - Strings (paths, ...) have no length: `strncpy_from_user` validates page by page while
+//!   scanning for the NUL, and never touches the page after the terminator
+//!
+//! ## Given
+//! `PhysMem`, and an `AddressSpace` with `map` to set up a process and `translate` to find the
+//! frame behind a page. The page table is your `Sv39PageTable` from `03_multi_level_pt` (finish
+//! it first): its `leaf` gives the PTE whose flags `check_range` inspects.
+
+use std::collections::HashMap;
+
+pub use multi_level_pt::{Sv39PageTable, PAGE_SIZE, PTE_R, PTE_U, PTE_V, PTE_W, PTE_X};
+
+const PPN_SHIFT: u32 = 10;
+
+/// User addresses are below this bound (the lower half of Sv39).
+pub const USER_END: u64 = 1 << 38;
+
+pub const EFAULT: isize = 14;
+pub const ENAMETOOLONG: isize = 36;
+
+/// Start of the page containing `va`.
+pub fn page_floor(va: u64) -> u64 {
+    va & !(PAGE_SIZE as u64 - 1)
+}
+
+// ============================================================
+// Physical memory (given)
+// ============================================================
+
+/// Simulated RAM: zeroed 4 KiB frames by PPN.
+pub struct PhysMem {
+    frames: HashMap<u64, Box<[u8; PAGE_SIZE]>>,
+    next_ppn: u64,
+}
+
+impl PhysMem {
+    pub fn new() -> Self {
+        Self {
+            frames: HashMap::new(),
+            next_ppn: 0x90000,
+        }
+    }
+
+    pub fn alloc_frame(&mut self) -> u64 {
+        let ppn = self.next_ppn;
+        self.next_ppn += 1;
+        self.frames.insert(ppn, Box::new([0; PAGE_SIZE]));
+        ppn
+    }
+
+    pub fn frame(&self, ppn: u64) -> &[u8; PAGE_SIZE] {
+        self.frames
+            .get(&ppn)
+            .expect("access to unallocated physical memory")
+    }
+
+    pub fn frame_mut(&mut self, ppn: u64) -> &mut [u8; PAGE_SIZE] {
+        self.frames
+            .get_mut(&ppn)
+            .expect("access to unallocated physical memory")
+    }
+}
+
+impl Default for PhysMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// User memory access
+// ============================================================
+
+/// The address space of the process making the system call.
+pub struct AddressSpace {
+    pub pt: Sv39PageTable,
+    pub mem: PhysMem,
+}
+
+impl AddressSpace {
+    pub fn new() -> Self {
+        Self {
+            pt: Sv39PageTable::new(),
+            mem: PhysMem::new(),
+        }
+    }
+
+    /// Map the page containing `va` to a fresh zeroed frame with `flags`.
+    pub fn map(&mut self, va: u64, flags: u64) {
+        let ppn = self.mem.alloc_frame();
+        self.pt.map_page(va, ppn << 12, flags | PTE_V);
+    }
+
+    /// Frame number backing the page containing `va`, if mapped (no permission checks).
+    pub fn translate(&self, va: u64) -> Option<u64> {
+        self.pt.leaf(va).map(|pte| pte >> PPN_SHIFT)
+    }
+
+    /// Check that `[va, va + len)` is user memory with all of the permissions in `need`
+    /// (`PTE_R` and/or `PTE_W`). An empty range is always valid.
+    ///
+    /// `Err(-EFAULT)` if:
+    /// - `va + len` overflows, or the range ends above `USER_END`
+    /// - some page of the range has no valid leaf PTE, lacks `PTE_U`, or lacks a bit of `need`
+    ///
+    /// Walk the pages from `page_floor(va)` up to (excluding) `va + len` in `PAGE_SIZE` steps.
+    pub fn check_range(&self, va: u64, len: usize, need: u64) -> Result<(), isize> {
+        // TODO
+        todo!()
+    }
+
+    /// Copy `dst.len()` bytes from user address `src` into the kernel buffer `dst`.
+    ///
+    /// 1. `check_range(src, dst.len(), PTE_R)?`
+    /// 2. Copy chunk by chunk: each chunk runs from the current address to the end of its page
+    ///    (or of the buffer), and is read from the frame `self.translate(addr)` at offset
+    ///    `addr % PAGE_SIZE`
+    pub fn copy_from_user(&self, dst: &mut [u8], src: u64) -> Result<(), isize> {
+        // TODO
+        todo!()
+    }
+
+    /// Copy the kernel buffer `src` to user address `dst`. Like `copy_from_user`, with `PTE_W`;
+    /// nothing is written unless the whole range is valid.
+    pub fn copy_to_user(&mut self, dst: u64, src: &[u8]) -> Result<(), isize> {
+        // TODO
+        todo!()
+    }
+
+    /// Read the NUL-terminated string at user address `src`, at most `max` bytes (without the
+    /// NUL).
+    ///
+    /// Scan byte by byte; whenever the scan enters a new page (first byte, or `addr` is
+    /// page-aligned), `check_range(addr, 1, PTE_R)?` that page. Stop at the NUL and return the
+    /// bytes before it — the page after the terminator is never looked at. `Err(-ENAMETOOLONG)`
+    /// if there is no NUL within the first `max + 1` bytes.
+    pub fn strncpy_from_user(&self, src: u64, max: usize) -> Result<Vec<u8>, isize> {
+        // TODO
+        todo!()
+    }
+}
+
+impl Default for AddressSpace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `write(1, buf, len)` for a console that collects its output in `console`: returns `len`, or
+/// `-EFAULT` without writing anything.
+pub fn sys_write(space: &AddressSpace, console: &mut Vec<u8>, buf: u64, len: usize) -> isize {
+    let mut kbuf = vec![0; len];
+    match space.copy_from_user(&mut kbuf, buf) {
+        Ok(()) => {
+            console.extend_from_slice(&kbuf);
+            len as isize
+        }
+        Err(e) => e,
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_check_range",
+            "fail with -EFAULT if va + len overflows or ends above USER_END, or if any page lacks a leaf PTE, PTE_U or a needed bit",
+        ),
+        (
+            "test_copy_straddling_pages",
+            "copy chunk by chunk: each chunk ends at its page boundary and is read from that page's own frame",
+        ),
+        (
+            "test_unmapped_hole",
+            "check the whole range before copying: a hole in the middle is -EFAULT",
+        ),
+        (
+            "test_copy_to_read_only_writes_nothing",
+            "copy_to_user checks the whole range for PTE_W first, and writes nothing if that fails",
+        ),
+        (
+            "test_strncpy_stops_at_unmapped_page",
+            "check a page only when the scan enters it; stop at the NUL without touching the next page",
+        ),
+    ];
+
+    const URW: u64 = PTE_U | PTE_R | PTE_W;
+    const UR: u64 = PTE_U | PTE_R;
+
+    /// Pages mapped at `0x1000` and `0x2000`, with frames in reverse physical order so that a
+    /// straddling copy cannot just run on in physical memory.
+    fn two_pages(second_flags: u64) -> AddressSpace {
+        let mut s = AddressSpace::new();
+        s.map(0x2000, second_flags);
+        s.map(0x1000, URW);
+        s
+    }
+
+    #[test]
+    fn test_check_range() {
+        let mut s = two_pages(UR);
+        s.map(0x5000, PTE_R | PTE_W); // kernel-only page
+        assert_eq_with_hint!(s.check_range(0x1000, PAGE_SIZE * 2, PTE_R), Ok(()));
+        assert_eq_with_hint!(
+            s.check_range(0x1ff0, 0x10, PTE_W),
+            Ok(()),
+            "ends exactly at the page end"
+        );
+        assert_eq_with_hint!(
+            s.check_range(0x1ff0, 0x11, PTE_W),
+            Err(-EFAULT),
+            "last byte read-only"
+        );
+        assert_eq_with_hint!(s.check_range(0x5000, 1, PTE_R), Err(-EFAULT), "no PTE_U");
+        assert_eq_with_hint!(s.check_range(0x3000, 1, PTE_R), Err(-EFAULT), "unmapped");
+        assert_eq_with_hint!(s.check_range(0, 0, PTE_R), Ok(()), "empty range");
+        assert_eq_with_hint!(
+            s.check_range(u64::MAX - 1, 4, PTE_R),
+            Err(-EFAULT),
+            "overflow"
+        );
+        assert_eq_with_hint!(s.check_range(USER_END - 1, 2, PTE_R), Err(-EFAULT));
+    }
+
+    #[test]
+    fn test_copy_from_user_one_page() {
+        let mut s = two_pages(URW);
+        s.copy_to_user(0x1010, b"hello").unwrap();
+        let mut buf = [0u8; 5];
+        assert_eq!(s.copy_from_user(&mut buf, 0x1010), Ok(()));
+        assert_eq!(&buf, b"hello");
+    }
+
+    #[test]
+    fn test_copy_straddling_pages() {
+        let mut s = two_pages(URW);
+        let data: Vec<u8> = (0..32).collect();
+        assert_eq_with_hint!(s.copy_to_user(0x1ff0, &data), Ok(()));
+        assert_eq_with_hint!(
+            s.mem.frame(s.translate(0x1000).unwrap())[0xff0..],
+            data[..16]
+        );
+        assert_eq_with_hint!(s.mem.frame(s.translate(0x2000).unwrap())[..16], data[16..]);
+
+        let mut back = [0u8; 32];
+        assert_eq_with_hint!(s.copy_from_user(&mut back, 0x1ff0), Ok(()));
+        assert_eq_with_hint!(back[..], data[..]);
+    }
+
+    #[test]
+    fn test_unmapped_hole() {
+        let mut s = AddressSpace::new();
+        s.map(0x10000, URW);
+        s.map(0x12000, URW); // 0x11000 is a hole
+        let mut buf = [0xaa; 0x1020];
+        assert_eq_with_hint!(s.copy_from_user(&mut buf, 0x10ff0), Err(-EFAULT));
+        assert_with_hint!(buf.iter().all(|&b| b == 0xaa), "kernel buffer untouched");
+        assert_eq_with_hint!(s.copy_to_user(0x10ff0, &buf), Err(-EFAULT));
+        assert_eq_with_hint!(
+            s.copy_from_user(&mut buf[..0x10], 0x10ff0),
+            Ok(()),
+            "before the hole"
+        );
+    }
+
+    #[test]
+    fn test_copy_to_read_only_writes_nothing() {
+        let mut s = two_pages(UR);
+        assert_eq_with_hint!(s.copy_to_user(0x1ffe, b"abcd"), Err(-EFAULT));
+        let mut buf = [0xff; 2];
+        s.copy_from_user(&mut buf, 0x1ffe).unwrap();
+        assert_eq_with_hint!(buf, [0, 0], "the writable half was not written either");
+    }
+
+    #[test]
+    fn test_strncpy_from_user() {
+        let mut s = two_pages(URW);
+        s.copy_to_user(0x1100, b"/bin/sh\0").unwrap();
+        assert_eq!(s.strncpy_from_user(0x1100, 64), Ok(b"/bin/sh".to_vec()));
+        assert_eq!(s.strncpy_from_user(0x1100, 7), Ok(b"/bin/sh".to_vec()));
+        assert_eq!(s.strncpy_from_user(0x1100, 6), Err(-ENAMETOOLONG));
+        assert_eq!(s.strncpy_from_user(0x1107, 6), Ok(vec![]));
+        // Straddling the page boundary.
+        s.copy_to_user(0x1ffc, b"straddle\0").unwrap();
+        assert_eq!(s.strncpy_from_user(0x1ffc, 64), Ok(b"straddle".to_vec()));
+    }
+
+    #[test]
+    fn test_strncpy_stops_at_unmapped_page() {
+        let mut s = AddressSpace::new();
+        s.map(0x1000, URW); // 0x2000 is not mapped
+        s.copy_to_user(0x1ffd, b"ab\0").unwrap();
+        assert_eq_with_hint!(
+            s.strncpy_from_user(0x1ffd, 64),
+            Ok(b"ab".to_vec()),
+            "NUL on the last byte"
+        );
+        s.copy_to_user(0x1ffd, b"abc").unwrap();
+        assert_eq_with_hint!(
+            s.strncpy_from_user(0x1ffd, 64),
+            Err(-EFAULT),
+            "runs into the hole"
+        );
+        assert_eq_with_hint!(s.strncpy_from_user(0x5000, 64), Err(-EFAULT));
+    }
+
+    #[test]
+    fn test_sys_write() {
+        let mut s = two_pages(URW);
+        s.copy_to_user(0x1ffa, b"hi there\n").unwrap();
+        let mut console = vec![];
+        assert_eq!(sys_write(&s, &mut console, 0x1ffa, 9), 9);
+        assert_eq!(sys_write(&s, &mut console, 0x2ffa, 9), -EFAULT);
+        assert_eq!(console, b"hi there\n");
+    }
+}
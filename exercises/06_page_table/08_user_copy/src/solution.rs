@@ -0,0 +1,219 @@
+//! # `copy_from_user` / `copy_to_user`
+//!
+//! In this exercise, you implement the kernel's safe access to user memory. A system call gets
+//! raw user pointers (`write(fd, buf, len)`); before touching them the kernel must check that the
+//! **whole** range is mapped in the process's page table, accessible from user mode, and readable
+//! (or writable) — otherwise the call fails with `EFAULT` instead of crashing the kernel or
+//! leaking kernel memory.
+//!
+//! ## Concepts
+//! - A user buffer is a virtual range `[va, va + len)`; it may **straddle pages** whose frames
+//!   are anywhere in physical memory, so the copy goes page by page, translating each one
+//! - Validation covers every page of the range: a single unmapped page in the middle (a hole),
+//!   a kernel page (no `PTE_U`) or a missing `R`/`W` permission makes the whole call fail
+//! - Arithmetic on user pointers must not overflow, and must stay below `USER_END`
+//! - Validate first, then copy: a failed `copy_to_user` leaves user memory untouched
+//! - Strings (paths, ...) have no length: `strncpy_from_user` validates page by page while
+//!   scanning for the NUL, and never touches the page after the terminator
+//!
+//! ## Given
+//! `PhysMem`, and an `AddressSpace` with `map` to set up a process and `translate` to find the
+//! frame behind a page. The page table is your `Sv39PageTable` from `03_multi_level_pt` (finish
+//! it first): its `leaf` gives the PTE whose flags `check_range` inspects.
+
+use std::collections::HashMap;
+
+pub use multi_level_pt::solution::{Sv39PageTable, PAGE_SIZE, PTE_R, PTE_U, PTE_V, PTE_W, PTE_X};
+
+const PPN_SHIFT: u32 = 10;
+
+/// User addresses are below this bound (the lower half of Sv39).
+pub const USER_END: u64 = 1 << 38;
+
+pub const EFAULT: isize = 14;
+pub const ENAMETOOLONG: isize = 36;
+
+/// Start of the page containing `va`.
+pub fn page_floor(va: u64) -> u64 {
+    va & !(PAGE_SIZE as u64 - 1)
+}
+
+// ============================================================
+// Physical memory (given)
+// ============================================================
+
+/// Simulated RAM: zeroed 4 KiB frames by PPN.
+pub struct PhysMem {
+    frames: HashMap<u64, Box<[u8; PAGE_SIZE]>>,
+    next_ppn: u64,
+}
+
+impl PhysMem {
+    pub fn new() -> Self {
+        Self {
+            frames: HashMap::new(),
+            next_ppn: 0x90000,
+        }
+    }
+
+    pub fn alloc_frame(&mut self) -> u64 {
+        let ppn = self.next_ppn;
+        self.next_ppn += 1;
+        self.frames.insert(ppn, Box::new([0; PAGE_SIZE]));
+        ppn
+    }
+
+    pub fn frame(&self, ppn: u64) -> &[u8; PAGE_SIZE] {
+        self.frames
+            .get(&ppn)
+            .expect("access to unallocated physical memory")
+    }
+
+    pub fn frame_mut(&mut self, ppn: u64) -> &mut [u8; PAGE_SIZE] {
+        self.frames
+            .get_mut(&ppn)
+            .expect("access to unallocated physical memory")
+    }
+}
+
+impl Default for PhysMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// User memory access
+// ============================================================
+
+/// The address space of the process making the system call.
+pub struct AddressSpace {
+    pub pt: Sv39PageTable,
+    pub mem: PhysMem,
+}
+
+impl AddressSpace {
+    pub fn new() -> Self {
+        Self {
+            pt: Sv39PageTable::new(),
+            mem: PhysMem::new(),
+        }
+    }
+
+    /// Map the page containing `va` to a fresh zeroed frame with `flags`.
+    pub fn map(&mut self, va: u64, flags: u64) {
+        let ppn = self.mem.alloc_frame();
+        self.pt.map_page(va, ppn << 12, flags | PTE_V);
+    }
+
+    /// Frame number backing the page containing `va`, if mapped (no permission checks).
+    pub fn translate(&self, va: u64) -> Option<u64> {
+        self.pt.leaf(va).map(|pte| pte >> PPN_SHIFT)
+    }
+
+    /// Check that `[va, va + len)` is user memory with all of the permissions in `need`
+    /// (`PTE_R` and/or `PTE_W`). An empty range is always valid.
+    ///
+    /// `Err(-EFAULT)` if:
+    /// - `va + len` overflows, or the range ends above `USER_END`
+    /// - some page of the range has no valid leaf PTE, lacks `PTE_U`, or lacks a bit of `need`
+    ///
+    /// Walk the pages from `page_floor(va)` up to (excluding) `va + len` in `PAGE_SIZE` steps.
+    pub fn check_range(&self, va: u64, len: usize, need: u64) -> Result<(), isize> {
+        if len == 0 {
+            return Ok(());
+        }
+        let end = va.checked_add(len as u64).ok_or(-EFAULT)?;
+        if end > USER_END {
+            return Err(-EFAULT);
+        }
+        let mut page = page_floor(va);
+        while page < end {
+            let pte = self.pt.leaf(page).ok_or(-EFAULT)?;
+            if pte & PTE_U == 0 || pte & need != need {
+                return Err(-EFAULT);
+            }
+            page += PAGE_SIZE as u64;
+        }
+        Ok(())
+    }
+
+    /// Copy `dst.len()` bytes from user address `src` into the kernel buffer `dst`.
+    ///
+    /// 1. `check_range(src, dst.len(), PTE_R)?`
+    /// 2. Copy chunk by chunk: each chunk runs from the current address to the end of its page
+    ///    (or of the buffer), and is read from the frame `self.translate(addr)` at offset
+    ///    `addr % PAGE_SIZE`
+    pub fn copy_from_user(&self, dst: &mut [u8], src: u64) -> Result<(), isize> {
+        self.check_range(src, dst.len(), PTE_R)?;
+        let mut done = 0;
+        while done < dst.len() {
+            let addr = src + done as u64;
+            let off = addr as usize % PAGE_SIZE;
+            let n = (PAGE_SIZE - off).min(dst.len() - done);
+            let frame = self.mem.frame(self.translate(addr).unwrap());
+            dst[done..done + n].copy_from_slice(&frame[off..off + n]);
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Copy the kernel buffer `src` to user address `dst`. Like `copy_from_user`, with `PTE_W`;
+    /// nothing is written unless the whole range is valid.
+    pub fn copy_to_user(&mut self, dst: u64, src: &[u8]) -> Result<(), isize> {
+        self.check_range(dst, src.len(), PTE_W)?;
+        let mut done = 0;
+        while done < src.len() {
+            let addr = dst + done as u64;
+            let off = addr as usize % PAGE_SIZE;
+            let n = (PAGE_SIZE - off).min(src.len() - done);
+            let ppn = self.translate(addr).unwrap();
+            self.mem.frame_mut(ppn)[off..off + n].copy_from_slice(&src[done..done + n]);
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Read the NUL-terminated string at user address `src`, at most `max` bytes (without the
+    /// NUL).
+    ///
+    /// Scan byte by byte; whenever the scan enters a new page (first byte, or `addr` is
+    /// page-aligned), `check_range(addr, 1, PTE_R)?` that page. Stop at the NUL and return the
+    /// bytes before it — the page after the terminator is never looked at. `Err(-ENAMETOOLONG)`
+    /// if there is no NUL within the first `max + 1` bytes.
+    pub fn strncpy_from_user(&self, src: u64, max: usize) -> Result<Vec<u8>, isize> {
+        let mut out = Vec::new();
+        for i in 0..=max as u64 {
+            let addr = src.checked_add(i).ok_or(-EFAULT)?;
+            if i == 0 || addr % PAGE_SIZE as u64 == 0 {
+                self.check_range(addr, 1, PTE_R)?;
+            }
+            let ppn = self.translate(addr).unwrap();
+            let b = self.mem.frame(ppn)[addr as usize % PAGE_SIZE];
+            if b == 0 {
+                return Ok(out);
+            }
+            out.push(b);
+        }
+        Err(-ENAMETOOLONG)
+    }
+}
+
+impl Default for AddressSpace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `write(1, buf, len)` for a console that collects its output in `console`: returns `len`, or
+/// `-EFAULT` without writing anything.
+pub fn sys_write(space: &AddressSpace, console: &mut Vec<u8>, buf: u64, len: usize) -> isize {
+    let mut kbuf = vec![0; len];
+    match space.copy_from_user(&mut kbuf, buf) {
+        Ok(()) => {
+            console.extend_from_slice(&kbuf);
+            len as isize
+        }
+        Err(e) => e,
+    }
+}
@@ -0,0 +1,565 @@
+//! # Memory Regions and ASLR
+//!
+//! In this exercise, you manage the virtual memory areas (VMAs) of a process — code, heap, stack
+//! and anonymous `mmap` regions — and randomize where they start (**ASLR**, address space layout
+//! randomization), so that an attacker cannot hard-code the address of the stack or a library.
+//!
+//! ## Concepts
+//! - A `MemorySet` is a set of non-overlapping, page-aligned areas `[start, end)`
+//! - `find_vma(va)`: which area (if any) contains an address — what a page fault handler asks
+//! - Areas are kept in a `BTreeMap` keyed by start address. Since they never overlap, sorted by
+//!   start they are also sorted by end, so `find_vma` and overlap checks are `O(log n)` range
+//!   queries instead of linear scans (Linux uses a maple tree, formerly an augmented rbtree)
+//! - `mmap` without an address picks the **highest** free range below `mmap_base` (top-down)
+//! - ASLR moves `stack_top`, `mmap_base` and `heap_base` by a random number of pages, inside
+//!   windows chosen so the regions can never collide, and always below `USER_TOP` (the upper end
+//!   of the canonical lower half of Sv39)
+//!
+//! ## Layout
+//! ```text
+//! USER_TOP ─┬───────────────────────────────  ┐
+//!           │ random gap (< STACK_RAND_PAGES)  │
+//! stack_top ┼───────────────────────────────  │ stack window: STACK_MAX, grows down
+//!           │ stack                            │
+//!           ├─── stack_top - STACK_MAX ──────  ┘
+//!           │ guard page + random gap (< MMAP_RAND_PAGES)
+//! mmap_base ┼───────────────────────────────  ┐
+//!           │ mmap areas, top-down             │ mmap window: MMAP_MAX
+//!           ├─── mmap_base - MMAP_MAX ───────  ┘
+//!           │ ...
+//!           ├─── heap_base + HEAP_MAX ───────  ┐
+//!           │ heap, grows up                   │ heap window: HEAP_MAX
+//! heap_base ┼───────────────────────────────  ┘
+//!           │ random gap (< HEAP_RAND_PAGES)
+//!  code_end ┼───────────────────────────────
+//!           │ code (fixed: not position independent)
+//! CODE_BASE ┴───────────────────────────────
+//! ```
+
+use std::collections::BTreeMap;
+
+pub const PAGE_SIZE: u64 = 4096;
+/// End of the user half of the Sv39 address space (exclusive).
+pub const USER_TOP: u64 = 1 << 38;
+pub const CODE_BASE: u64 = 0x1_0000;
+
+/// Pages of stack mapped at start.
+pub const STACK_PAGES: u64 = 16;
+/// Room reserved for the stack below `stack_top`.
+pub const STACK_MAX: u64 = 8 << 20;
+pub const MMAP_MAX: u64 = 16 << 30;
+pub const HEAP_MAX: u64 = 1 << 30;
+
+/// Randomization ranges, in pages.
+pub const STACK_RAND_PAGES: u64 = 1 << 16;
+pub const MMAP_RAND_PAGES: u64 = 1 << 20;
+pub const HEAP_RAND_PAGES: u64 = 1 << 13;
+
+pub const PERM_R: u8 = 1 << 0;
+pub const PERM_W: u8 = 1 << 1;
+pub const PERM_X: u8 = 1 << 2;
+
+/// Round `x` up to a multiple of `PAGE_SIZE`.
+pub fn page_ceil(x: u64) -> u64 {
+    x.div_ceil(PAGE_SIZE) * PAGE_SIZE
+}
+
+/// splitmix64: a small deterministic generator, good enough to pick offsets in a simulation.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A value in `0..n` (`n > 0`).
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// Where the regions of a process start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub code_start: u64,
+    pub code_end: u64,
+    /// The heap grows up from here.
+    pub heap_base: u64,
+    /// `mmap` places areas below this address.
+    pub mmap_base: u64,
+    /// The stack grows down from here.
+    pub stack_top: u64,
+}
+
+impl Layout {
+    /// The layout without randomization: every random offset is 0.
+    pub fn fixed(code_len: u64) -> Self {
+        let code_end = CODE_BASE + page_ceil(code_len);
+        Self {
+            code_start: CODE_BASE,
+            code_end,
+            heap_base: code_end,
+            mmap_base: USER_TOP - STACK_MAX - PAGE_SIZE,
+            stack_top: USER_TOP,
+        }
+    }
+
+    /// The layout with ASLR. With `rng = Rng::new(seed)`, draw in this order:
+    /// 1. `stack_top = USER_TOP - rng.below(STACK_RAND_PAGES) * PAGE_SIZE`
+    /// 2. `mmap_base = stack_top - STACK_MAX - PAGE_SIZE - rng.below(MMAP_RAND_PAGES) * PAGE_SIZE`
+    ///    (the stack window, a guard page, then the random gap)
+    /// 3. `heap_base = code_end + rng.below(HEAP_RAND_PAGES) * PAGE_SIZE`
+    ///
+    /// The code stays where `fixed` puts it.
+    pub fn randomize(code_len: u64, seed: u64) -> Self {
+        // TODO
+        todo!()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaKind {
+    Code,
+    Heap,
+    Stack,
+    Mmap,
+}
+
+/// A virtual memory area `[start, end)`, page-aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub start: u64,
+    pub end: u64,
+    pub perm: u8,
+    pub kind: AreaKind,
+}
+
+impl Area {
+    pub fn new(start: u64, end: u64, perm: u8, kind: AreaKind) -> Self {
+        assert!(start < end, "empty area {start:#x}..{end:#x}");
+        assert!(
+            (start | end) & (PAGE_SIZE - 1) == 0,
+            "unaligned area {start:#x}..{end:#x}"
+        );
+        Self {
+            start,
+            end,
+            perm,
+            kind,
+        }
+    }
+
+    pub fn contains(&self, va: u64) -> bool {
+        self.start <= va && va < self.end
+    }
+
+    pub fn overlaps(&self, other: &Area) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapError {
+    /// The new area overlaps an existing one.
+    Overlap,
+    /// `mmap` of 0 bytes.
+    InvalidLength,
+    /// No free range in the mmap window.
+    NoSpace,
+}
+
+pub struct MemorySet {
+    layout: Layout,
+    /// Areas by start address.
+    areas: BTreeMap<u64, Area>,
+}
+
+impl MemorySet {
+    /// A fresh process: code (R|X), one page of heap (R|W) and `STACK_PAGES` of stack (R|W)
+    /// at the places `layout` gives.
+    pub fn new(layout: Layout) -> Self {
+        let mut ms = Self {
+            layout,
+            areas: BTreeMap::new(),
+        };
+        let initial = [
+            Area::new(
+                layout.code_start,
+                layout.code_end,
+                PERM_R | PERM_X,
+                AreaKind::Code,
+            ),
+            Area::new(
+                layout.heap_base,
+                layout.heap_base + PAGE_SIZE,
+                PERM_R | PERM_W,
+                AreaKind::Heap,
+            ),
+            Area::new(
+                layout.stack_top - STACK_PAGES * PAGE_SIZE,
+                layout.stack_top,
+                PERM_R | PERM_W,
+                AreaKind::Stack,
+            ),
+        ];
+        for area in initial {
+            ms.insert(area).expect("initial areas overlap");
+        }
+        ms
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// All areas, by increasing start address.
+    pub fn areas(&self) -> impl Iterator<Item = &Area> {
+        self.areas.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.areas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.areas.is_empty()
+    }
+
+    /// The areas overlapping `[start, end)`, from the highest start down.
+    ///
+    /// Only areas starting below `end` can overlap, and among those (walking
+    /// `self.areas.range(..end)` backwards) the ends decrease too, so stop at the first area
+    /// with `end <= start`: nothing before it can reach the range.
+    pub fn overlapping(&self, start: u64, end: u64) -> Vec<&Area> {
+        // TODO
+        todo!()
+    }
+
+    /// Add `area`, unless it overlaps an existing area (`Err(MapError::Overlap)`).
+    /// Areas that merely touch (`a.end == b.start`) do not overlap.
+    pub fn insert(&mut self, area: Area) -> Result<(), MapError> {
+        // TODO
+        todo!()
+    }
+
+    /// Remove the area starting at `start` (`munmap` of a whole area) and return it.
+    pub fn remove(&mut self, start: u64) -> Option<Area> {
+        self.areas.remove(&start)
+    }
+
+    /// The area containing `va`: the area with the greatest `start <= va`, if it also
+    /// contains `va`.
+    pub fn find_vma(&self, va: u64) -> Option<&Area> {
+        // TODO
+        todo!()
+    }
+
+    /// Map an anonymous area of `len` bytes (rounded up to pages) with `perm`, top-down in the
+    /// mmap window `[mmap_base - MMAP_MAX, mmap_base)`; returns its start.
+    ///
+    /// Start with `end = mmap_base` and try `[end - len, end)`: if an area overlaps it, move
+    /// `end` down to the lowest `start` among the overlapping areas (the last one `overlapping`
+    /// yields) and try again. Fail with
+    /// `NoSpace` once the candidate would start below the window, `InvalidLength` for `len == 0`.
+    pub fn mmap(&mut self, len: u64, perm: u8) -> Result<u64, MapError> {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_randomized_invariants_hold_for_1000_seeds",
+            "draw in the documented order (stack, mmap, heap) and subtract the stack window and guard page before the mmap gap",
+        ),
+        (
+            "test_insert_rejects_overlap",
+            "areas that merely touch (a.end == b.start) do not overlap",
+        ),
+        (
+            "test_find_vma",
+            "take the area with the greatest start <= va, and return it only if it also contains va",
+        ),
+        (
+            "test_mmap_top_down",
+            "the first mmap ends at mmap_base; each later one goes right below the previous",
+        ),
+        (
+            "test_mmap_skips_occupied_ranges",
+            "on overlap move end down to the lowest start among the overlapping areas and try again",
+        ),
+        (
+            "test_mmap_window_exhausted",
+            "return NoSpace once the candidate would start below mmap_base - MMAP_MAX",
+        ),
+    ];
+
+    const CODE_LEN: u64 = 0x5432;
+
+    /// Every invariant a layout must satisfy.
+    fn check_layout(l: &Layout) {
+        for addr in [
+            l.code_start,
+            l.code_end,
+            l.heap_base,
+            l.mmap_base,
+            l.stack_top,
+        ] {
+            assert_eq!(addr % PAGE_SIZE, 0, "{addr:#x} not aligned in {l:x?}");
+            assert!(addr <= USER_TOP, "{addr:#x} not canonical in {l:x?}");
+        }
+        assert_eq!((l.code_start, l.code_end), (CODE_BASE, CODE_BASE + 0x6000));
+        assert!(l.code_end <= l.heap_base);
+        assert!(l.heap_base + HEAP_MAX <= l.mmap_base - MMAP_MAX, "{l:x?}");
+        assert!(l.mmap_base + PAGE_SIZE <= l.stack_top - STACK_MAX, "{l:x?}");
+    }
+
+    #[test]
+    fn test_fixed_layout() {
+        let l = Layout::fixed(CODE_LEN);
+        check_layout(&l);
+        assert_eq!(l.heap_base, 0x1_6000);
+        assert_eq!(l.stack_top, USER_TOP);
+        assert_eq!(l.mmap_base, USER_TOP - STACK_MAX - PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_randomized_invariants_hold_for_1000_seeds() {
+        for seed in 0..1000 {
+            let l = Layout::randomize(CODE_LEN, seed);
+            check_layout(&l);
+            let ms = MemorySet::new(l);
+            let areas: Vec<&Area> = ms.areas().collect();
+            for (i, a) in areas.iter().enumerate() {
+                assert_with_hint!(a.end <= USER_TOP);
+                for b in areas[i + 1..].iter() {
+                    assert_with_hint!(!a.overlaps(b), "seed {seed}: {a:x?} overlaps {b:x?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_layouts_differ_per_seed() {
+        let a = Layout::randomize(CODE_LEN, 1);
+        let b = Layout::randomize(CODE_LEN, 2);
+        assert_ne!(a.stack_top, b.stack_top);
+        assert_ne!(a.mmap_base, b.mmap_base);
+        assert_ne!(a.heap_base, b.heap_base);
+        assert_eq!(Layout::randomize(CODE_LEN, 1), a, "same seed, same layout");
+
+        let mut tops: Vec<u64> = (0..1000)
+            .map(|s| Layout::randomize(CODE_LEN, s).stack_top)
+            .collect();
+        tops.sort_unstable();
+        tops.dedup();
+        assert!(tops.len() > 990, "only {} distinct stack tops", tops.len());
+    }
+
+    #[test]
+    fn test_insert_rejects_overlap() {
+        let mut ms = MemorySet::new(Layout::fixed(CODE_LEN));
+        let a = Area::new(0x100_0000, 0x100_3000, PERM_R, AreaKind::Mmap);
+        assert_eq_with_hint!(ms.insert(a), Ok(()));
+        let overlapping = Area::new(0x100_2000, 0x100_4000, PERM_R, AreaKind::Mmap);
+        assert_eq_with_hint!(ms.insert(overlapping), Err(MapError::Overlap));
+        let inside = Area::new(0x100_1000, 0x100_2000, PERM_R, AreaKind::Mmap);
+        assert_eq_with_hint!(ms.insert(inside), Err(MapError::Overlap));
+        let adjacent = Area::new(0x100_3000, 0x100_4000, PERM_R, AreaKind::Mmap);
+        assert_eq_with_hint!(ms.insert(adjacent), Ok(()));
+        assert_eq_with_hint!(ms.len(), 5);
+        let starts: Vec<u64> = ms.areas().map(|a| a.start).collect();
+        assert_with_hint!(starts.windows(2).all(|w| w[0] < w[1]), "sorted by start");
+    }
+
+    #[test]
+    fn test_find_vma() {
+        let l = Layout::randomize(CODE_LEN, 42);
+        let ms = MemorySet::new(l);
+        assert_eq_with_hint!(ms.find_vma(l.code_start).unwrap().kind, AreaKind::Code);
+        assert_eq_with_hint!(ms.find_vma(l.code_end - 1).unwrap().kind, AreaKind::Code);
+        assert_eq_with_hint!(ms.find_vma(l.heap_base + 8).unwrap().kind, AreaKind::Heap);
+        assert_eq_with_hint!(ms.find_vma(l.stack_top - 8).unwrap().kind, AreaKind::Stack);
+        assert_with_hint!(ms.find_vma(l.stack_top).is_none(), "end is exclusive");
+        assert_with_hint!(ms.find_vma(l.heap_base + PAGE_SIZE).is_none());
+        assert_with_hint!(ms.find_vma(0).is_none());
+    }
+
+    #[test]
+    fn test_mmap_top_down() {
+        let l = Layout::randomize(CODE_LEN, 7);
+        let mut ms = MemorySet::new(l);
+        let a = ms.mmap(PAGE_SIZE, PERM_R | PERM_W).unwrap();
+        assert_eq_with_hint!(a, l.mmap_base - PAGE_SIZE);
+        let b = ms.mmap(100, PERM_R).unwrap();
+        assert_eq_with_hint!(b, a - PAGE_SIZE, "rounded up to a page, right below");
+        let area = ms.find_vma(b).unwrap();
+        assert_eq_with_hint!(
+            (area.end, area.kind, area.perm),
+            (a, AreaKind::Mmap, PERM_R)
+        );
+        assert_eq_with_hint!(ms.mmap(0, PERM_R), Err(MapError::InvalidLength));
+    }
+
+    #[test]
+    fn test_mmap_skips_occupied_ranges() {
+        let l = Layout::fixed(CODE_LEN);
+        let mut ms = MemorySet::new(l);
+        // Occupy [base - 3 pages, base - 1 page): one free page on top, then a hole below.
+        let top = l.mmap_base;
+        let fixed = Area::new(top - 3 * PAGE_SIZE, top - PAGE_SIZE, PERM_R, AreaKind::Mmap);
+        ms.insert(fixed).unwrap();
+        assert_eq_with_hint!(
+            ms.mmap(2 * PAGE_SIZE, PERM_R),
+            Ok(top - 5 * PAGE_SIZE),
+            "too big for the top gap"
+        );
+        assert_eq_with_hint!(
+            ms.mmap(PAGE_SIZE, PERM_R),
+            Ok(top - PAGE_SIZE),
+            "fits in the top gap"
+        );
+    }
+
+    #[test]
+    fn test_mmap_window_exhausted() {
+        let mut ms = MemorySet::new(Layout::randomize(CODE_LEN, 3));
+        assert_with_hint!(ms.mmap(MMAP_MAX, PERM_R).is_ok());
+        assert_eq_with_hint!(ms.mmap(PAGE_SIZE, PERM_R), Err(MapError::NoSpace));
+        assert_eq_with_hint!(
+            MemorySet::new(Layout::fixed(CODE_LEN)).mmap(MMAP_MAX + 1, PERM_R),
+            Err(MapError::NoSpace)
+        );
+    }
+
+    /// Brute-force reference: the same operations as linear scans over a `Vec`.
+    #[derive(Default)]
+    struct Reference {
+        areas: Vec<Area>,
+    }
+
+    impl Reference {
+        fn insert(&mut self, area: Area) -> Result<(), MapError> {
+            if self.areas.iter().any(|a| a.overlaps(&area)) {
+                return Err(MapError::Overlap);
+            }
+            self.areas.push(area);
+            Ok(())
+        }
+
+        fn find_vma(&self, va: u64) -> Option<&Area> {
+            self.areas.iter().find(|a| a.contains(va))
+        }
+
+        fn mmap(&mut self, layout: &Layout, len: u64, perm: u8) -> Result<u64, MapError> {
+            let floor = layout.mmap_base - MMAP_MAX;
+            let mut end = layout.mmap_base;
+            loop {
+                let start = match end.checked_sub(page_ceil(len)) {
+                    Some(s) if s >= floor => s,
+                    _ => return Err(MapError::NoSpace),
+                };
+                let candidate = Area::new(start, end, perm, AreaKind::Mmap);
+                match self
+                    .areas
+                    .iter()
+                    .filter(|a| a.overlaps(&candidate))
+                    .map(|a| a.start)
+                    .min()
+                {
+                    Some(lowest) => end = lowest,
+                    None => {
+                        self.areas.push(candidate);
+                        return Ok(start);
+                    }
+                }
+            }
+        }
+    }
+
+    /// A random area of 1..=8 pages somewhere in `[base, base + span_pages pages)`.
+    fn random_area(rng: &mut Rng, base: u64, span_pages: u64) -> Area {
+        let start = base + rng.below(span_pages) * PAGE_SIZE;
+        let end = start + (1 + rng.below(8)) * PAGE_SIZE;
+        Area::new(start, end, PERM_R, AreaKind::Mmap)
+    }
+
+    #[test]
+    fn test_matches_brute_force_on_random_sets() {
+        for seed in 0..50 {
+            let mut rng = Rng::new(seed);
+            let layout = Layout::fixed(CODE_LEN);
+            let mut ms = MemorySet::new(layout);
+            let mut reference = Reference::default();
+            for area in ms.areas() {
+                reference.insert(*area).unwrap();
+            }
+            let base = 0x1000_0000;
+            for _ in 0..300 {
+                let area = random_area(&mut rng, base, 512);
+                assert_eq!(ms.insert(area), reference.insert(area), "seed {seed}");
+            }
+            assert_eq!(ms.len(), reference.areas.len());
+
+            for _ in 0..500 {
+                let va = base - PAGE_SIZE + rng.below(520 * PAGE_SIZE);
+                assert_eq!(
+                    ms.find_vma(va),
+                    reference.find_vma(va),
+                    "seed {seed} va {va:#x}"
+                );
+                let q = random_area(&mut rng, base - 8 * PAGE_SIZE, 528);
+                let mut want: Vec<&Area> =
+                    reference.areas.iter().filter(|a| a.overlaps(&q)).collect();
+                want.sort_by_key(|a| std::cmp::Reverse(a.start));
+                let got = ms.overlapping(q.start, q.end);
+                assert_eq!(got, want, "seed {seed} query {q:x?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_mmap_matches_brute_force() {
+        for seed in 0..50 {
+            let mut rng = Rng::new(seed);
+            let layout = Layout::randomize(CODE_LEN, seed);
+            let mut ms = MemorySet::new(layout);
+            let mut reference = Reference::default();
+            for area in ms.areas() {
+                reference.insert(*area).unwrap();
+            }
+            for _ in 0..200 {
+                if rng.below(3) == 0 {
+                    // Fixed mappings near the top punch holes for mmap to skip.
+                    let area = random_area(&mut rng, layout.mmap_base - 1024 * PAGE_SIZE, 1024);
+                    assert_eq!(ms.insert(area), reference.insert(area), "seed {seed}");
+                } else {
+                    let len = 1 + rng.below(6 * PAGE_SIZE);
+                    let got = ms.mmap(len, PERM_R | PERM_W);
+                    let want = reference.mmap(&layout, len, PERM_R | PERM_W);
+                    assert_eq!(got, want, "seed {seed} len {len:#x}");
+                }
+            }
+        }
+    }
+}
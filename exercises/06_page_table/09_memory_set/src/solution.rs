@@ -0,0 +1,313 @@
+//! # Memory Regions and ASLR
+//!
+//! In this exercise, you manage the virtual memory areas (VMAs) of a process — code, heap, stack
+//! and anonymous `mmap` regions — and randomize where they start (**ASLR**, address space layout
+//! randomization), so that an attacker cannot hard-code the address of the stack or a library.
+//!
+//! ## Concepts
+//! - A `MemorySet` is a set of non-overlapping, page-aligned areas `[start, end)`
+//! - `find_vma(va)`: which area (if any) contains an address — what a page fault handler asks
+//! - Areas are kept in a `BTreeMap` keyed by start address. Since they never overlap, sorted by
+//!   start they are also sorted by end, so `find_vma` and overlap checks are `O(log n)` range
+//!   queries instead of linear scans (Linux uses a maple tree, formerly an augmented rbtree)
+//! - `mmap` without an address picks the **highest** free range below `mmap_base` (top-down)
+//! - ASLR moves `stack_top`, `mmap_base` and `heap_base` by a random number of pages, inside
+//!   windows chosen so the regions can never collide, and always below `USER_TOP` (the upper end
+//!   of the canonical lower half of Sv39)
+//!
+//! ## Layout
+//! ```text
+//! USER_TOP ─┬───────────────────────────────  ┐
+//!           │ random gap (< STACK_RAND_PAGES)  │
+//! stack_top ┼───────────────────────────────  │ stack window: STACK_MAX, grows down
+//!           │ stack                            │
+//!           ├─── stack_top - STACK_MAX ──────  ┘
+//!           │ guard page + random gap (< MMAP_RAND_PAGES)
+//! mmap_base ┼───────────────────────────────  ┐
+//!           │ mmap areas, top-down             │ mmap window: MMAP_MAX
+//!           ├─── mmap_base - MMAP_MAX ───────  ┘
+//!           │ ...
+//!           ├─── heap_base + HEAP_MAX ───────  ┐
+//!           │ heap, grows up                   │ heap window: HEAP_MAX
+//! heap_base ┼───────────────────────────────  ┘
+//!           │ random gap (< HEAP_RAND_PAGES)
+//!  code_end ┼───────────────────────────────
+//!           │ code (fixed: not position independent)
+//! CODE_BASE ┴───────────────────────────────
+//! ```
+
+use std::collections::BTreeMap;
+
+pub const PAGE_SIZE: u64 = 4096;
+/// End of the user half of the Sv39 address space (exclusive).
+pub const USER_TOP: u64 = 1 << 38;
+pub const CODE_BASE: u64 = 0x1_0000;
+
+/// Pages of stack mapped at start.
+pub const STACK_PAGES: u64 = 16;
+/// Room reserved for the stack below `stack_top`.
+pub const STACK_MAX: u64 = 8 << 20;
+pub const MMAP_MAX: u64 = 16 << 30;
+pub const HEAP_MAX: u64 = 1 << 30;
+
+/// Randomization ranges, in pages.
+pub const STACK_RAND_PAGES: u64 = 1 << 16;
+pub const MMAP_RAND_PAGES: u64 = 1 << 20;
+pub const HEAP_RAND_PAGES: u64 = 1 << 13;
+
+pub const PERM_R: u8 = 1 << 0;
+pub const PERM_W: u8 = 1 << 1;
+pub const PERM_X: u8 = 1 << 2;
+
+/// Round `x` up to a multiple of `PAGE_SIZE`.
+pub fn page_ceil(x: u64) -> u64 {
+    x.div_ceil(PAGE_SIZE) * PAGE_SIZE
+}
+
+/// splitmix64: a small deterministic generator, good enough to pick offsets in a simulation.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A value in `0..n` (`n > 0`).
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// Where the regions of a process start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub code_start: u64,
+    pub code_end: u64,
+    /// The heap grows up from here.
+    pub heap_base: u64,
+    /// `mmap` places areas below this address.
+    pub mmap_base: u64,
+    /// The stack grows down from here.
+    pub stack_top: u64,
+}
+
+impl Layout {
+    /// The layout without randomization: every random offset is 0.
+    pub fn fixed(code_len: u64) -> Self {
+        let code_end = CODE_BASE + page_ceil(code_len);
+        Self {
+            code_start: CODE_BASE,
+            code_end,
+            heap_base: code_end,
+            mmap_base: USER_TOP - STACK_MAX - PAGE_SIZE,
+            stack_top: USER_TOP,
+        }
+    }
+
+    /// The layout with ASLR. With `rng = Rng::new(seed)`, draw in this order:
+    /// 1. `stack_top = USER_TOP - rng.below(STACK_RAND_PAGES) * PAGE_SIZE`
+    /// 2. `mmap_base = stack_top - STACK_MAX - PAGE_SIZE - rng.below(MMAP_RAND_PAGES) * PAGE_SIZE`
+    ///    (the stack window, a guard page, then the random gap)
+    /// 3. `heap_base = code_end + rng.below(HEAP_RAND_PAGES) * PAGE_SIZE`
+    ///
+    /// The code stays where `fixed` puts it.
+    pub fn randomize(code_len: u64, seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let fixed = Self::fixed(code_len);
+        let stack_top = USER_TOP - rng.below(STACK_RAND_PAGES) * PAGE_SIZE;
+        let mmap_base = stack_top - STACK_MAX - PAGE_SIZE - rng.below(MMAP_RAND_PAGES) * PAGE_SIZE;
+        let heap_base = fixed.code_end + rng.below(HEAP_RAND_PAGES) * PAGE_SIZE;
+        Self {
+            heap_base,
+            mmap_base,
+            stack_top,
+            ..fixed
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaKind {
+    Code,
+    Heap,
+    Stack,
+    Mmap,
+}
+
+/// A virtual memory area `[start, end)`, page-aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub start: u64,
+    pub end: u64,
+    pub perm: u8,
+    pub kind: AreaKind,
+}
+
+impl Area {
+    pub fn new(start: u64, end: u64, perm: u8, kind: AreaKind) -> Self {
+        assert!(start < end, "empty area {start:#x}..{end:#x}");
+        assert!(
+            (start | end) & (PAGE_SIZE - 1) == 0,
+            "unaligned area {start:#x}..{end:#x}"
+        );
+        Self {
+            start,
+            end,
+            perm,
+            kind,
+        }
+    }
+
+    pub fn contains(&self, va: u64) -> bool {
+        self.start <= va && va < self.end
+    }
+
+    pub fn overlaps(&self, other: &Area) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapError {
+    /// The new area overlaps an existing one.
+    Overlap,
+    /// `mmap` of 0 bytes.
+    InvalidLength,
+    /// No free range in the mmap window.
+    NoSpace,
+}
+
+pub struct MemorySet {
+    layout: Layout,
+    /// Areas by start address.
+    areas: BTreeMap<u64, Area>,
+}
+
+impl MemorySet {
+    /// A fresh process: code (R|X), one page of heap (R|W) and `STACK_PAGES` of stack (R|W)
+    /// at the places `layout` gives.
+    pub fn new(layout: Layout) -> Self {
+        let mut ms = Self {
+            layout,
+            areas: BTreeMap::new(),
+        };
+        let initial = [
+            Area::new(
+                layout.code_start,
+                layout.code_end,
+                PERM_R | PERM_X,
+                AreaKind::Code,
+            ),
+            Area::new(
+                layout.heap_base,
+                layout.heap_base + PAGE_SIZE,
+                PERM_R | PERM_W,
+                AreaKind::Heap,
+            ),
+            Area::new(
+                layout.stack_top - STACK_PAGES * PAGE_SIZE,
+                layout.stack_top,
+                PERM_R | PERM_W,
+                AreaKind::Stack,
+            ),
+        ];
+        for area in initial {
+            ms.insert(area).expect("initial areas overlap");
+        }
+        ms
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// All areas, by increasing start address.
+    pub fn areas(&self) -> impl Iterator<Item = &Area> {
+        self.areas.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.areas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.areas.is_empty()
+    }
+
+    /// The areas overlapping `[start, end)`, from the highest start down.
+    ///
+    /// Only areas starting below `end` can overlap, and among those (walking
+    /// `self.areas.range(..end)` backwards) the ends decrease too, so stop at the first area
+    /// with `end <= start`: nothing before it can reach the range.
+    pub fn overlapping(&self, start: u64, end: u64) -> Vec<&Area> {
+        self.areas
+            .range(..end)
+            .rev()
+            .map(|(_, a)| a)
+            .take_while(|a| a.end > start)
+            .collect()
+    }
+
+    /// Add `area`, unless it overlaps an existing area (`Err(MapError::Overlap)`).
+    /// Areas that merely touch (`a.end == b.start`) do not overlap.
+    pub fn insert(&mut self, area: Area) -> Result<(), MapError> {
+        if !self.overlapping(area.start, area.end).is_empty() {
+            return Err(MapError::Overlap);
+        }
+        self.areas.insert(area.start, area);
+        Ok(())
+    }
+
+    /// Remove the area starting at `start` (`munmap` of a whole area) and return it.
+    pub fn remove(&mut self, start: u64) -> Option<Area> {
+        self.areas.remove(&start)
+    }
+
+    /// The area containing `va`: the area with the greatest `start <= va`, if it also
+    /// contains `va`.
+    pub fn find_vma(&self, va: u64) -> Option<&Area> {
+        self.areas
+            .range(..=va)
+            .next_back()
+            .map(|(_, a)| a)
+            .filter(|a| a.contains(va))
+    }
+
+    /// Map an anonymous area of `len` bytes (rounded up to pages) with `perm`, top-down in the
+    /// mmap window `[mmap_base - MMAP_MAX, mmap_base)`; returns its start.
+    ///
+    /// Start with `end = mmap_base` and try `[end - len, end)`: if an area overlaps it, move
+    /// `end` down to the lowest `start` among the overlapping areas (the last one `overlapping`
+    /// yields) and try again. Fail with
+    /// `NoSpace` once the candidate would start below the window, `InvalidLength` for `len == 0`.
+    pub fn mmap(&mut self, len: u64, perm: u8) -> Result<u64, MapError> {
+        if len == 0 {
+            return Err(MapError::InvalidLength);
+        }
+        let len = page_ceil(len);
+        let floor = self.layout.mmap_base - MMAP_MAX;
+        let mut end = self.layout.mmap_base;
+        loop {
+            let start = match end.checked_sub(len) {
+                Some(s) if s >= floor => s,
+                _ => return Err(MapError::NoSpace),
+            };
+            let candidate = Area::new(start, end, perm, AreaKind::Mmap);
+            match self.overlapping(start, end).last() {
+                Some(lowest) => end = lowest.start,
+                None => {
+                    self.areas.insert(start, candidate);
+                    return Ok(start);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! # Radix Tree Page-Cache Index
+//!
+//! The page cache must find "page 7 of this file" fast, for files that are mostly holes and can
+//! be terabytes long. Linux indexes it with a **radix tree** (today the XArray): a trie over the
+//! bits of the page offset, 6 bits per level — the same idea as a multi-level page table, but
+//! only as tall as the largest key requires.
+//!
+//! ## Concepts
+//! - Level `l` (0 = the leaves) indexes its 64 slots with bits `6l .. 6l+6` of the key
+//! - A tree of height `h` holds keys `< 2^(6h)`. Inserting a larger key **grows** the tree: a new
+//!   root is put on top, with the old root in its slot 0 (keys below the old limit start with
+//!   zero bits)
+//! - Sparse keys cost one path each: storing offset `2^40` allocates 7 nodes, not a 2^40 array
+//! - Removing a page **prunes** nodes that became empty, so memory follows the contents
+//! - Gang lookup (`lookup_range`) walks slots in order and skips empty subtrees entirely
+//!
+//! ```text
+//! height 2, key 0x85 = 0b10_000101:
+//!   root (level 1): slot 2 ──► node (level 0): slot 5 ──► Arc<Page>
+//! ```
+
+use std::sync::Arc;
+
+pub const RADIX_BITS: u32 = 6;
+pub const RADIX_SLOTS: usize = 1 << RADIX_BITS;
+const MAX_HEIGHT: u32 = u64::BITS.div_ceil(RADIX_BITS);
+
+/// A cached page of a file.
+#[derive(Debug)]
+pub struct Page {
+    /// Page offset in the file.
+    pub index: u64,
+    pub data: Vec<u8>,
+}
+
+impl Page {
+    pub fn new(index: u64) -> Arc<Self> {
+        Arc::new(Self {
+            index,
+            data: Vec::new(),
+        })
+    }
+}
+
+enum Child {
+    Node(Box<Node>),
+    Page(Arc<Page>),
+}
+
+struct Node {
+    slots: [Option<Child>; RADIX_SLOTS],
+    /// Number of occupied slots.
+    count: usize,
+}
+
+impl Node {
+    fn new() -> Box<Self> {
+        Box::new(Self {
+            slots: std::array::from_fn(|_| None),
+            count: 0,
+        })
+    }
+}
+
+/// Slot of `key` in a node at `level`.
+fn slot_index(key: u64, level: u32) -> usize {
+    ((key >> (level * RADIX_BITS)) as usize) & (RADIX_SLOTS - 1)
+}
+
+/// Largest key a tree of `height` levels can hold.
+fn max_key(height: u32) -> u64 {
+    if height >= MAX_HEIGHT {
+        u64::MAX
+    } else {
+        (1u64 << (height * RADIX_BITS)) - 1
+    }
+}
+
+pub struct RadixTree {
+    /// `None` with `height == 0` when the tree is empty.
+    root: Option<Box<Node>>,
+    /// Number of levels; the root is at level `height - 1`.
+    height: u32,
+    len: usize,
+}
+
+impl RadixTree {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            height: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of allocated nodes.
+    pub fn node_count(&self) -> usize {
+        fn count(node: &Node) -> usize {
+            1 + node
+                .slots
+                .iter()
+                .map(|s| match s {
+                    Some(Child::Node(n)) => count(n),
+                    _ => 0,
+                })
+                .sum::<usize>()
+        }
+        self.root.as_deref().map_or(0, count)
+    }
+
+    /// Store `page` at `key`; returns the page previously stored there.
+    ///
+    /// 1. Empty tree: a root of height 1
+    /// 2. While `key > max_key(height)`: grow — a new root whose slot 0 holds the old root
+    ///    (`count = 1`), `height += 1`
+    /// 3. Walk from the root (level `height - 1`) down to level 1, creating missing nodes
+    ///    (and counting them in their parent's `count`)
+    /// 4. At level 0, put the page in its slot (`count += 1` and `len += 1` only if the slot
+    ///    was empty)
+    pub fn insert(&mut self, key: u64, page: Arc<Page>) -> Option<Arc<Page>> {
+        // TODO
+        todo!()
+    }
+
+    /// The page at `key`.
+    ///
+    /// Keys above `max_key(height)` are not in the tree. Otherwise walk down by `slot_index`
+    /// from level `height - 1`; an empty slot on the way means "not cached".
+    pub fn lookup(&self, key: u64) -> Option<Arc<Page>> {
+        // TODO
+        todo!()
+    }
+
+    /// Remove the page at `key` and return it.
+    ///
+    /// Afterwards, every node left without occupied slots is freed and its slot in the parent
+    /// cleared (recursion makes this natural: remove in the child, then check `child.count`).
+    /// If the root becomes empty, the tree is reset (`root = None`, `height = 0`); otherwise
+    /// the height stays.
+    pub fn remove(&mut self, key: u64) -> Option<Arc<Page>> {
+        // TODO
+        todo!()
+    }
+
+    /// Gang lookup: up to `max_items` pages with keys `>= start`, in increasing key order.
+    ///
+    /// Depth-first over the slots in order. In a node, start at `slot_index(start, level)` only
+    /// while still on the path of `start`; after moving to a later slot, every key below it is
+    /// `> start`, so the subtree is walked from slot 0. Stop as soon as `max_items` are found.
+    /// Track the key prefix of each subtree to report each page's key.
+    pub fn lookup_range(&self, start: u64, max_items: usize) -> Vec<(u64, Arc<Page>)> {
+        // TODO
+        todo!()
+    }
+}
+
+impl Default for RadixTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use std::collections::BTreeMap;
+
+    const HINTS: Hints = &[
+        (
+            "test_insert_lookup_replace",
+            "insert returns the page it replaced; len only grows when the slot was empty",
+        ),
+        (
+            "test_grow_keeps_existing_keys",
+            "grow by putting the old root in slot 0 of a new root (count = 1), one level at a time",
+        ),
+        (
+            "test_sparse_keys_up_to_2_pow_40",
+            "lookup of a key above max_key(height) is None; below that, walk down by slot_index from level height - 1",
+        ),
+        (
+            "test_remove_prunes_empty_nodes",
+            "after removing in a child, free it if its count reached 0; an empty root resets the tree",
+        ),
+        (
+            "test_lookup_range",
+            "only the path of start begins at slot_index(start, level); later subtrees are walked from slot 0",
+        ),
+    ];
+
+    fn keys(found: &[(u64, Arc<Page>)]) -> Vec<u64> {
+        found.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn test_empty() {
+        let t = RadixTree::new();
+        assert!(t.is_empty());
+        assert!(t.lookup(0).is_none());
+        assert!(t.lookup_range(0, 10).is_empty());
+        assert_eq!((t.height(), t.node_count()), (0, 0));
+    }
+
+    #[test]
+    fn test_insert_lookup_replace() {
+        let mut t = RadixTree::new();
+        assert_with_hint!(t.insert(5, Page::new(5)).is_none());
+        assert_eq_with_hint!(t.height(), 1);
+        assert_eq_with_hint!(t.lookup(5).unwrap().index, 5);
+        assert_with_hint!(t.lookup(4).is_none());
+        assert_with_hint!(t.lookup(64).is_none(), "above the current height");
+
+        let newer = Page::new(5);
+        let old = t.insert(5, newer.clone()).unwrap();
+        assert_eq_with_hint!(old.index, 5);
+        assert_with_hint!(Arc::ptr_eq(&t.lookup(5).unwrap(), &newer));
+        assert_eq_with_hint!(t.len(), 1);
+    }
+
+    #[test]
+    fn test_grow_keeps_existing_keys() {
+        let mut t = RadixTree::new();
+        t.insert(5, Page::new(5));
+        t.insert(64, Page::new(64));
+        assert_eq_with_hint!(t.height(), 2);
+        t.insert(1 << 30, Page::new(1 << 30));
+        assert_eq_with_hint!(t.height(), 6);
+        for k in [5, 64, 1 << 30] {
+            assert_eq_with_hint!(t.lookup(k).unwrap().index, k);
+        }
+        assert_with_hint!(t.lookup(6).is_none());
+    }
+
+    #[test]
+    fn test_sparse_keys_up_to_2_pow_40() {
+        let mut t = RadixTree::new();
+        let ks = [0, 63, 64, 4095, 1 << 20, (1 << 40) - 1, 1 << 40];
+        for k in ks {
+            t.insert(k, Page::new(k));
+        }
+        assert_eq_with_hint!(t.height(), 7, "41 bits need 7 levels of 6");
+        for k in ks {
+            assert_eq_with_hint!(t.lookup(k).unwrap().index, k, "key {k:#x}");
+            assert_with_hint!(t.lookup(k + 2).is_none());
+        }
+        assert_eq_with_hint!(t.len(), ks.len());
+        assert_with_hint!(t.node_count() < 40, "one path per key, not an array");
+    }
+
+    #[test]
+    fn test_remove_prunes_empty_nodes() {
+        let mut t = RadixTree::new();
+        t.insert(1, Page::new(1));
+        t.insert(1 << 40, Page::new(1 << 40));
+        assert_eq_with_hint!(t.node_count(), 13, "root + two paths of 6");
+        assert_eq_with_hint!(t.remove(1 << 40).unwrap().index, 1 << 40);
+        assert_eq_with_hint!(t.node_count(), 7);
+        assert_with_hint!(t.remove(1 << 40).is_none());
+        assert_with_hint!(t.remove(2).is_none());
+        assert_eq_with_hint!(t.lookup(1).unwrap().index, 1);
+        assert_with_hint!(t.remove(1).is_some());
+        assert_eq_with_hint!((t.len(), t.height(), t.node_count()), (0, 0, 0));
+        t.insert(3, Page::new(3));
+        assert_eq_with_hint!(t.height(), 1, "usable again after emptying");
+    }
+
+    #[test]
+    fn test_lookup_range() {
+        let mut t = RadixTree::new();
+        for k in [10, 20, 64 * 64 + 1, 1 << 40] {
+            t.insert(k, Page::new(k));
+        }
+        assert_eq_with_hint!(keys(&t.lookup_range(0, 2)), [10, 20]);
+        assert_eq_with_hint!(keys(&t.lookup_range(15, 10)), [20, 4097, 1 << 40]);
+        assert_eq_with_hint!(keys(&t.lookup_range(20, 1)), [20]);
+        assert_eq_with_hint!(keys(&t.lookup_range(21, 10)), [4097, 1 << 40]);
+        assert_with_hint!(t.lookup_range((1 << 40) + 1, 10).is_empty());
+        assert_with_hint!(t.lookup_range(u64::MAX, 10).is_empty());
+        assert_with_hint!(t.lookup_range(0, 0).is_empty());
+        let found = t.lookup_range(4097, 1);
+        assert_eq_with_hint!(found[0].1.index, 4097);
+    }
+
+    #[test]
+    fn test_pages_are_shared() {
+        let mut t = RadixTree::new();
+        let page = Page::new(9);
+        t.insert(9, page.clone());
+        let a = t.lookup(9).unwrap();
+        assert!(Arc::ptr_eq(&a, &page));
+        assert_eq!(
+            Arc::strong_count(&page),
+            3,
+            "caller, tree and lookup result"
+        );
+        drop(t);
+        assert_eq!(Arc::strong_count(&page), 2);
+    }
+
+    #[test]
+    fn test_matches_btreemap() {
+        let mut t = RadixTree::new();
+        let mut model: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut x: u64 = 0x2545_f491_4f6c_dd1d;
+        for step in 0..3000 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            // Mostly small keys (collisions), some large sparse ones.
+            let key = if x & 3 == 0 { x >> 24 } else { (x >> 8) % 512 };
+            match x % 3 {
+                0 | 1 => {
+                    let old = t.insert(key, Page::new(step));
+                    assert_eq!(old.map(|p| p.index), model.insert(key, step));
+                }
+                _ => {
+                    let old = t.remove(key);
+                    assert_eq!(old.map(|p| p.index), model.remove(&key));
+                }
+            }
+            if step & 127 == 0 {
+                let got: Vec<(u64, u64)> = t
+                    .lookup_range(key / 2, 20)
+                    .into_iter()
+                    .map(|(k, p)| (k, p.index))
+                    .collect();
+                let want: Vec<(u64, u64)> = model
+                    .range(key / 2..)
+                    .take(20)
+                    .map(|(&k, &v)| (k, v))
+                    .collect();
+                assert_eq!(got, want, "step {step}");
+            }
+        }
+        assert_eq!(t.len(), model.len());
+        for (&k, &v) in &model {
+            assert_eq!(t.lookup(k).unwrap().index, v);
+        }
+    }
+}
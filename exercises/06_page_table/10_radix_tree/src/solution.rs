@@ -0,0 +1,274 @@
+//! # Radix Tree Page-Cache Index
+//!
+//! The page cache must find "page 7 of this file" fast, for files that are mostly holes and can
+//! be terabytes long. Linux indexes it with a **radix tree** (today the XArray): a trie over the
+//! bits of the page offset, 6 bits per level — the same idea as a multi-level page table, but
+//! only as tall as the largest key requires.
+//!
+//! ## Concepts
+//! - Level `l` (0 = the leaves) indexes its 64 slots with bits `6l .. 6l+6` of the key
+//! - A tree of height `h` holds keys `< 2^(6h)`. Inserting a larger key **grows** the tree: a new
+//!   root is put on top, with the old root in its slot 0 (keys below the old limit start with
+//!   zero bits)
+//! - Sparse keys cost one path each: storing offset `2^40` allocates 7 nodes, not a 2^40 array
+//! - Removing a page **prunes** nodes that became empty, so memory follows the contents
+//! - Gang lookup (`lookup_range`) walks slots in order and skips empty subtrees entirely
+//!
+//! ```text
+//! height 2, key 0x85 = 0b10_000101:
+//!   root (level 1): slot 2 ──► node (level 0): slot 5 ──► Arc<Page>
+//! ```
+
+pub(crate) use std::sync::Arc;
+
+pub const RADIX_BITS: u32 = 6;
+pub const RADIX_SLOTS: usize = 1 << RADIX_BITS;
+const MAX_HEIGHT: u32 = u64::BITS.div_ceil(RADIX_BITS);
+
+/// A cached page of a file.
+#[derive(Debug)]
+pub struct Page {
+    /// Page offset in the file.
+    pub index: u64,
+    pub data: Vec<u8>,
+}
+
+impl Page {
+    pub fn new(index: u64) -> Arc<Self> {
+        Arc::new(Self {
+            index,
+            data: Vec::new(),
+        })
+    }
+}
+
+enum Child {
+    Node(Box<Node>),
+    Page(Arc<Page>),
+}
+
+struct Node {
+    slots: [Option<Child>; RADIX_SLOTS],
+    /// Number of occupied slots.
+    count: usize,
+}
+
+impl Node {
+    fn new() -> Box<Self> {
+        Box::new(Self {
+            slots: std::array::from_fn(|_| None),
+            count: 0,
+        })
+    }
+}
+
+/// Slot of `key` in a node at `level`.
+fn slot_index(key: u64, level: u32) -> usize {
+    ((key >> (level * RADIX_BITS)) as usize) & (RADIX_SLOTS - 1)
+}
+
+/// Largest key a tree of `height` levels can hold.
+fn max_key(height: u32) -> u64 {
+    if height >= MAX_HEIGHT {
+        u64::MAX
+    } else {
+        (1u64 << (height * RADIX_BITS)) - 1
+    }
+}
+
+pub struct RadixTree {
+    /// `None` with `height == 0` when the tree is empty.
+    root: Option<Box<Node>>,
+    /// Number of levels; the root is at level `height - 1`.
+    height: u32,
+    len: usize,
+}
+
+impl RadixTree {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            height: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Number of allocated nodes.
+    pub fn node_count(&self) -> usize {
+        fn count(node: &Node) -> usize {
+            1 + node
+                .slots
+                .iter()
+                .map(|s| match s {
+                    Some(Child::Node(n)) => count(n),
+                    _ => 0,
+                })
+                .sum::<usize>()
+        }
+        self.root.as_deref().map_or(0, count)
+    }
+
+    /// Store `page` at `key`; returns the page previously stored there.
+    ///
+    /// 1. Empty tree: a root of height 1
+    /// 2. While `key > max_key(height)`: grow — a new root whose slot 0 holds the old root
+    ///    (`count = 1`), `height += 1`
+    /// 3. Walk from the root (level `height - 1`) down to level 1, creating missing nodes
+    ///    (and counting them in their parent's `count`)
+    /// 4. At level 0, put the page in its slot (`count += 1` and `len += 1` only if the slot
+    ///    was empty)
+    pub fn insert(&mut self, key: u64, page: Arc<Page>) -> Option<Arc<Page>> {
+        if self.root.is_none() {
+            self.root = Some(Node::new());
+            self.height = 1;
+        }
+        while key > max_key(self.height) {
+            let mut new_root = Node::new();
+            new_root.slots[0] = Some(Child::Node(self.root.take().unwrap()));
+            new_root.count = 1;
+            self.root = Some(new_root);
+            self.height += 1;
+        }
+        let mut node = self.root.as_mut().unwrap();
+        for level in (1..self.height).rev() {
+            let idx = slot_index(key, level);
+            if node.slots[idx].is_none() {
+                node.slots[idx] = Some(Child::Node(Node::new()));
+                node.count += 1;
+            }
+            node = match node.slots[idx].as_mut() {
+                Some(Child::Node(n)) => n,
+                _ => unreachable!("page above level 0"),
+            };
+        }
+        let idx = slot_index(key, 0);
+        let old = match node.slots[idx].replace(Child::Page(page)) {
+            Some(Child::Page(p)) => Some(p),
+            Some(Child::Node(_)) => unreachable!("node at level 0"),
+            None => None,
+        };
+        if old.is_none() {
+            node.count += 1;
+            self.len += 1;
+        }
+        old
+    }
+
+    /// The page at `key`.
+    ///
+    /// Keys above `max_key(height)` are not in the tree. Otherwise walk down by `slot_index`
+    /// from level `height - 1`; an empty slot on the way means "not cached".
+    pub fn lookup(&self, key: u64) -> Option<Arc<Page>> {
+        if key > max_key(self.height) {
+            return None;
+        }
+        let mut node = self.root.as_deref()?;
+        for level in (1..self.height).rev() {
+            node = match node.slots[slot_index(key, level)].as_ref()? {
+                Child::Node(n) => n,
+                Child::Page(_) => unreachable!(),
+            };
+        }
+        match node.slots[slot_index(key, 0)].as_ref()? {
+            Child::Page(p) => Some(p.clone()),
+            Child::Node(_) => unreachable!(),
+        }
+    }
+
+    /// Remove the page at `key` and return it.
+    ///
+    /// Afterwards, every node left without occupied slots is freed and its slot in the parent
+    /// cleared (recursion makes this natural: remove in the child, then check `child.count`).
+    /// If the root becomes empty, the tree is reset (`root = None`, `height = 0`); otherwise
+    /// the height stays.
+    pub fn remove(&mut self, key: u64) -> Option<Arc<Page>> {
+        fn remove_in(node: &mut Node, key: u64, level: u32) -> Option<Arc<Page>> {
+            let idx = slot_index(key, level);
+            if level == 0 {
+                let page = match node.slots[idx].take()? {
+                    Child::Page(p) => p,
+                    Child::Node(_) => unreachable!(),
+                };
+                node.count -= 1;
+                return Some(page);
+            }
+            let Some(Child::Node(child)) = node.slots[idx].as_mut() else {
+                return None;
+            };
+            let page = remove_in(child, key, level - 1)?;
+            if child.count == 0 {
+                node.slots[idx] = None;
+                node.count -= 1;
+            }
+            Some(page)
+        }
+        if key > max_key(self.height) {
+            return None;
+        }
+        let root = self.root.as_mut()?;
+        let page = remove_in(root, key, self.height - 1)?;
+        self.len -= 1;
+        if root.count == 0 {
+            self.root = None;
+            self.height = 0;
+        }
+        Some(page)
+    }
+
+    /// Gang lookup: up to `max_items` pages with keys `>= start`, in increasing key order.
+    ///
+    /// Depth-first over the slots in order. In a node, start at `slot_index(start, level)` only
+    /// while still on the path of `start`; after moving to a later slot, every key below it is
+    /// `> start`, so the subtree is walked from slot 0. Stop as soon as `max_items` are found.
+    /// Track the key prefix of each subtree to report each page's key.
+    pub fn lookup_range(&self, start: u64, max_items: usize) -> Vec<(u64, Arc<Page>)> {
+        fn walk(
+            node: &Node,
+            level: u32,
+            prefix: u64,
+            start: Option<u64>,
+            max: usize,
+            out: &mut Vec<(u64, Arc<Page>)>,
+        ) {
+            let first = start.map_or(0, |s| slot_index(s, level));
+            for idx in first..RADIX_SLOTS {
+                if out.len() >= max {
+                    return;
+                }
+                let key = prefix | ((idx as u64) << (level * RADIX_BITS));
+                // Still on the path of `start` only in its own slot.
+                let bound = start.filter(|_| idx == first);
+                match &node.slots[idx] {
+                    None => {}
+                    Some(Child::Page(p)) => out.push((key, p.clone())),
+                    Some(Child::Node(n)) => walk(n, level - 1, key, bound, max, out),
+                }
+            }
+        }
+        let mut out = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            if start <= max_key(self.height) {
+                walk(root, self.height - 1, 0, Some(start), max_items, &mut out);
+            }
+        }
+        out
+    }
+}
+
+impl Default for RadixTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,334 @@
+//! # Decoding `scause` / `stval`
+//!
+//! In this exercise, you turn the raw trap CSRs of RISC-V supervisor mode into typed values, so that
+//! a trap handler can `match` on the cause instead of comparing magic numbers.
+//!
+//! ## Concepts
+//! - `scause` layout: bit 63 = Interrupt flag, bits 62..0 = exception / interrupt code
+//! - Standard supervisor interrupt and exception codes
+//! - What `stval` holds for each kind of trap
+//!
+//! ## `scause` Layout (RV64)
+//! ```text
+//! 63  62                                   0
+//! ┌───┬─────────────────────────────────────┐
+//! │ I │           Exception Code            │
+//! └───┴─────────────────────────────────────┘
+//! ```
+//!
+//! | I | Code | Meaning                          | `stval`                 |
+//! |---|------|----------------------------------|-------------------------|
+//! | 1 | 1    | Supervisor software interrupt    | 0                       |
+//! | 1 | 5    | Supervisor timer interrupt       | 0                       |
+//! | 1 | 9    | Supervisor external interrupt    | 0                       |
+//! | 0 | 0    | Instruction address misaligned   | faulting address        |
+//! | 0 | 1    | Instruction access fault         | faulting address        |
+//! | 0 | 2    | Illegal instruction              | instruction bits (or 0) |
+//! | 0 | 3    | Breakpoint                       | address of `ebreak`     |
+//! | 0 | 4    | Load address misaligned          | faulting address        |
+//! | 0 | 5    | Load access fault                | faulting address        |
+//! | 0 | 6    | Store/AMO address misaligned     | faulting address        |
+//! | 0 | 7    | Store/AMO access fault           | faulting address        |
+//! | 0 | 8    | Environment call from U-mode     | 0                       |
+//! | 0 | 9    | Environment call from S-mode     | 0                       |
+//! | 0 | 12   | Instruction page fault           | faulting address        |
+//! | 0 | 13   | Load page fault                  | faulting address        |
+//! | 0 | 15   | Store/AMO page fault             | faulting address        |
+//!
+//! Every other code is reserved or platform specific and decodes to `Unknown(code)`.
+
+/// Bit 63 of `scause`: set for interrupts, clear for exceptions.
+pub const INTERRUPT_BIT: u64 = 1 << 63;
+
+/// Supervisor-level interrupts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    SupervisorSoft,
+    SupervisorTimer,
+    SupervisorExternal,
+    /// Any other interrupt code.
+    Unknown(u64),
+}
+
+/// Synchronous exceptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exception {
+    InstructionMisaligned,
+    InstructionFault,
+    IllegalInstruction,
+    Breakpoint,
+    LoadMisaligned,
+    LoadFault,
+    StoreMisaligned,
+    StoreFault,
+    UserEnvCall,
+    SupervisorEnvCall,
+    InstructionPageFault,
+    LoadPageFault,
+    StorePageFault,
+    /// Any other exception code.
+    Unknown(u64),
+}
+
+/// A decoded trap cause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    Interrupt(Interrupt),
+    Exception(Exception),
+}
+
+/// Kind of memory access that caused a fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessType {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Meaning of `stval` for a given trap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stval {
+    /// Faulting virtual address.
+    Addr(u64),
+    /// Bits of the offending instruction (may be 0 if the hardware does not report them).
+    Instruction(u64),
+    /// `stval` carries no information for this trap.
+    None,
+}
+
+/// Decode a raw `scause` value.
+///
+/// Test `INTERRUPT_BIT`, take the code from the remaining bits, and map it according to the table
+/// in the module docs; unlisted codes become `Unknown(code)`.
+pub fn decode_scause(scause: u64) -> Trap {
+    // TODO: split into interrupt bit + code, then match the code
+    todo!()
+}
+
+/// Inverse of `decode_scause`: build the raw `scause` value for `trap`.
+///
+/// `Unknown(code)` encodes back to `code` (plus `INTERRUPT_BIT` for interrupts).
+pub fn encode_scause(trap: Trap) -> u64 {
+    // TODO: map each variant back to its code, set INTERRUPT_BIT for interrupts
+    todo!()
+}
+
+impl Exception {
+    /// For faults caused by a memory access (misaligned, access fault, page fault), the kind of
+    /// access: instruction fetch = `Execute`, load = `Read`, store/AMO = `Write`. `None` otherwise.
+    pub fn access_type(&self) -> Option<AccessType> {
+        // TODO
+        todo!()
+    }
+
+    /// Whether this is one of the three page faults (codes 12, 13, 15).
+    pub fn is_page_fault(&self) -> bool {
+        // TODO
+        todo!()
+    }
+}
+
+/// Interpret `stval` for `trap` (see the table in the module docs).
+///
+/// Memory faults and breakpoints report an address, illegal instructions the instruction bits;
+/// environment calls, interrupts and unknown causes carry nothing (`Stval::None`).
+pub fn decode_stval(trap: Trap, stval: u64) -> Stval {
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_same_code_differs_by_interrupt_bit",
+            "test INTERRUPT_BIT first, then decode the remaining code as an interrupt or an exception",
+        ),
+        (
+            "test_unknown_codes",
+            "codes without a variant decode to Unknown(code), with INTERRUPT_BIT already cleared",
+        ),
+        (
+            "test_encode_decode_roundtrip_exhaustive",
+            "encode_scause sets INTERRUPT_BIT for interrupts and turns Unknown(code) back into code",
+        ),
+        (
+            "test_decode_stval",
+            "stval is the faulting address for misaligned/access/page faults and breakpoints, the instruction for illegal instructions, otherwise nothing",
+        ),
+    ];
+
+    const INTERRUPTS: [(u64, Interrupt); 3] = [
+        (1, Interrupt::SupervisorSoft),
+        (5, Interrupt::SupervisorTimer),
+        (9, Interrupt::SupervisorExternal),
+    ];
+
+    const EXCEPTIONS: [(u64, Exception); 13] = [
+        (0, Exception::InstructionMisaligned),
+        (1, Exception::InstructionFault),
+        (2, Exception::IllegalInstruction),
+        (3, Exception::Breakpoint),
+        (4, Exception::LoadMisaligned),
+        (5, Exception::LoadFault),
+        (6, Exception::StoreMisaligned),
+        (7, Exception::StoreFault),
+        (8, Exception::UserEnvCall),
+        (9, Exception::SupervisorEnvCall),
+        (12, Exception::InstructionPageFault),
+        (13, Exception::LoadPageFault),
+        (15, Exception::StorePageFault),
+    ];
+
+    #[test]
+    fn test_decode_standard_exceptions() {
+        for (code, e) in EXCEPTIONS {
+            assert_eq!(
+                decode_scause(code),
+                Trap::Exception(e),
+                "exception code {code}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_standard_interrupts() {
+        for (code, i) in INTERRUPTS {
+            assert_eq!(
+                decode_scause(INTERRUPT_BIT | code),
+                Trap::Interrupt(i),
+                "interrupt code {code}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_same_code_differs_by_interrupt_bit() {
+        assert_eq_with_hint!(decode_scause(5), Trap::Exception(Exception::LoadFault));
+        assert_eq_with_hint!(
+            decode_scause(INTERRUPT_BIT | 5),
+            Trap::Interrupt(Interrupt::SupervisorTimer)
+        );
+    }
+
+    #[test]
+    fn test_unknown_codes() {
+        for code in [10, 11, 14, 16, 24, 63, 1 << 40] {
+            assert_eq_with_hint!(
+                decode_scause(code),
+                Trap::Exception(Exception::Unknown(code))
+            );
+        }
+        for code in [0, 2, 3, 4, 7, 11, 13, 100] {
+            assert_eq_with_hint!(
+                decode_scause(INTERRUPT_BIT | code),
+                Trap::Interrupt(Interrupt::Unknown(code))
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip_exhaustive() {
+        for code in 0..64u64 {
+            for raw in [code, INTERRUPT_BIT | code] {
+                assert_eq_with_hint!(encode_scause(decode_scause(raw)), raw, "scause {raw:#x}");
+            }
+        }
+        for (code, e) in EXCEPTIONS {
+            assert_eq_with_hint!(encode_scause(Trap::Exception(e)), code);
+        }
+        for (code, i) in INTERRUPTS {
+            assert_eq_with_hint!(encode_scause(Trap::Interrupt(i)), INTERRUPT_BIT | code);
+        }
+    }
+
+    #[test]
+    fn test_access_type() {
+        use AccessType::*;
+        let expected = [
+            (Exception::InstructionMisaligned, Some(Execute)),
+            (Exception::InstructionFault, Some(Execute)),
+            (Exception::InstructionPageFault, Some(Execute)),
+            (Exception::LoadMisaligned, Some(Read)),
+            (Exception::LoadFault, Some(Read)),
+            (Exception::LoadPageFault, Some(Read)),
+            (Exception::StoreMisaligned, Some(Write)),
+            (Exception::StoreFault, Some(Write)),
+            (Exception::StorePageFault, Some(Write)),
+            (Exception::IllegalInstruction, None),
+            (Exception::Breakpoint, None),
+            (Exception::UserEnvCall, None),
+            (Exception::SupervisorEnvCall, None),
+            (Exception::Unknown(11), None),
+        ];
+        for (e, access) in expected {
+            assert_eq!(e.access_type(), access, "{e:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_page_fault() {
+        for (code, e) in EXCEPTIONS {
+            assert_eq!(e.is_page_fault(), matches!(code, 12 | 13 | 15), "{e:?}");
+        }
+        assert!(!Exception::Unknown(14).is_page_fault());
+    }
+
+    #[test]
+    fn test_decode_stval() {
+        let addr = 0xdead_b000;
+        assert_eq_with_hint!(
+            decode_stval(Trap::Exception(Exception::StorePageFault), addr),
+            Stval::Addr(addr)
+        );
+        assert_eq_with_hint!(
+            decode_stval(Trap::Exception(Exception::LoadMisaligned), 0x1001),
+            Stval::Addr(0x1001)
+        );
+        assert_eq_with_hint!(
+            decode_stval(Trap::Exception(Exception::Breakpoint), 0x8020_0000),
+            Stval::Addr(0x8020_0000)
+        );
+        assert_eq_with_hint!(
+            decode_stval(Trap::Exception(Exception::IllegalInstruction), 0xffff_ffff),
+            Stval::Instruction(0xffff_ffff)
+        );
+        assert_eq_with_hint!(
+            decode_stval(Trap::Exception(Exception::UserEnvCall), 0),
+            Stval::None
+        );
+        assert_eq_with_hint!(
+            decode_stval(Trap::Interrupt(Interrupt::SupervisorTimer), 0),
+            Stval::None
+        );
+        assert_eq_with_hint!(
+            decode_stval(Trap::Exception(Exception::Unknown(24)), 7),
+            Stval::None
+        );
+    }
+
+    #[test]
+    fn test_every_memory_fault_reports_an_address() {
+        for (code, e) in EXCEPTIONS {
+            if e == Exception::Breakpoint {
+                continue; // reports an address without being a memory access
+            }
+            let stval = decode_stval(Trap::Exception(e), 0x4000);
+            assert_eq!(
+                e.access_type().is_some(),
+                stval == Stval::Addr(0x4000),
+                "code {code}: {stval:?}"
+            );
+        }
+    }
+}
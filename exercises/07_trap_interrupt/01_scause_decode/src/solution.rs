@@ -0,0 +1,203 @@
+//! # Decoding `scause` / `stval`
+//!
+//! In this exercise, you turn the raw trap CSRs of RISC-V supervisor mode into typed values, so that
+//! a trap handler can `match` on the cause instead of comparing magic numbers.
+//!
+//! ## Concepts
+//! - `scause` layout: bit 63 = Interrupt flag, bits 62..0 = exception / interrupt code
+//! - Standard supervisor interrupt and exception codes
+//! - What `stval` holds for each kind of trap
+//!
+//! ## `scause` Layout (RV64)
+//! ```text
+//! 63  62                                   0
+//! ┌───┬─────────────────────────────────────┐
+//! │ I │           Exception Code            │
+//! └───┴─────────────────────────────────────┘
+//! ```
+//!
+//! | I | Code | Meaning                          | `stval`                 |
+//! |---|------|----------------------------------|-------------------------|
+//! | 1 | 1    | Supervisor software interrupt    | 0                       |
+//! | 1 | 5    | Supervisor timer interrupt       | 0                       |
+//! | 1 | 9    | Supervisor external interrupt    | 0                       |
+//! | 0 | 0    | Instruction address misaligned   | faulting address        |
+//! | 0 | 1    | Instruction access fault         | faulting address        |
+//! | 0 | 2    | Illegal instruction              | instruction bits (or 0) |
+//! | 0 | 3    | Breakpoint                       | address of `ebreak`     |
+//! | 0 | 4    | Load address misaligned          | faulting address        |
+//! | 0 | 5    | Load access fault                | faulting address        |
+//! | 0 | 6    | Store/AMO address misaligned     | faulting address        |
+//! | 0 | 7    | Store/AMO access fault           | faulting address        |
+//! | 0 | 8    | Environment call from U-mode     | 0                       |
+//! | 0 | 9    | Environment call from S-mode     | 0                       |
+//! | 0 | 12   | Instruction page fault           | faulting address        |
+//! | 0 | 13   | Load page fault                  | faulting address        |
+//! | 0 | 15   | Store/AMO page fault             | faulting address        |
+//!
+//! Every other code is reserved or platform specific and decodes to `Unknown(code)`.
+
+/// Bit 63 of `scause`: set for interrupts, clear for exceptions.
+pub const INTERRUPT_BIT: u64 = 1 << 63;
+
+/// Supervisor-level interrupts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    SupervisorSoft,
+    SupervisorTimer,
+    SupervisorExternal,
+    /// Any other interrupt code.
+    Unknown(u64),
+}
+
+/// Synchronous exceptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exception {
+    InstructionMisaligned,
+    InstructionFault,
+    IllegalInstruction,
+    Breakpoint,
+    LoadMisaligned,
+    LoadFault,
+    StoreMisaligned,
+    StoreFault,
+    UserEnvCall,
+    SupervisorEnvCall,
+    InstructionPageFault,
+    LoadPageFault,
+    StorePageFault,
+    /// Any other exception code.
+    Unknown(u64),
+}
+
+/// A decoded trap cause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trap {
+    Interrupt(Interrupt),
+    Exception(Exception),
+}
+
+/// Kind of memory access that caused a fault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessType {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Meaning of `stval` for a given trap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stval {
+    /// Faulting virtual address.
+    Addr(u64),
+    /// Bits of the offending instruction (may be 0 if the hardware does not report them).
+    Instruction(u64),
+    /// `stval` carries no information for this trap.
+    None,
+}
+
+/// Decode a raw `scause` value.
+///
+/// Test `INTERRUPT_BIT`, take the code from the remaining bits, and map it according to the table
+/// in the module docs; unlisted codes become `Unknown(code)`.
+pub fn decode_scause(scause: u64) -> Trap {
+    let code = scause & !INTERRUPT_BIT;
+    if scause & INTERRUPT_BIT != 0 {
+        Trap::Interrupt(match code {
+            1 => Interrupt::SupervisorSoft,
+            5 => Interrupt::SupervisorTimer,
+            9 => Interrupt::SupervisorExternal,
+            c => Interrupt::Unknown(c),
+        })
+    } else {
+        Trap::Exception(match code {
+            0 => Exception::InstructionMisaligned,
+            1 => Exception::InstructionFault,
+            2 => Exception::IllegalInstruction,
+            3 => Exception::Breakpoint,
+            4 => Exception::LoadMisaligned,
+            5 => Exception::LoadFault,
+            6 => Exception::StoreMisaligned,
+            7 => Exception::StoreFault,
+            8 => Exception::UserEnvCall,
+            9 => Exception::SupervisorEnvCall,
+            12 => Exception::InstructionPageFault,
+            13 => Exception::LoadPageFault,
+            15 => Exception::StorePageFault,
+            c => Exception::Unknown(c),
+        })
+    }
+}
+
+/// Inverse of `decode_scause`: build the raw `scause` value for `trap`.
+///
+/// `Unknown(code)` encodes back to `code` (plus `INTERRUPT_BIT` for interrupts).
+pub fn encode_scause(trap: Trap) -> u64 {
+    match trap {
+        Trap::Interrupt(i) => {
+            INTERRUPT_BIT
+                | match i {
+                    Interrupt::SupervisorSoft => 1,
+                    Interrupt::SupervisorTimer => 5,
+                    Interrupt::SupervisorExternal => 9,
+                    Interrupt::Unknown(c) => c,
+                }
+        }
+        Trap::Exception(e) => match e {
+            Exception::InstructionMisaligned => 0,
+            Exception::InstructionFault => 1,
+            Exception::IllegalInstruction => 2,
+            Exception::Breakpoint => 3,
+            Exception::LoadMisaligned => 4,
+            Exception::LoadFault => 5,
+            Exception::StoreMisaligned => 6,
+            Exception::StoreFault => 7,
+            Exception::UserEnvCall => 8,
+            Exception::SupervisorEnvCall => 9,
+            Exception::InstructionPageFault => 12,
+            Exception::LoadPageFault => 13,
+            Exception::StorePageFault => 15,
+            Exception::Unknown(c) => c,
+        },
+    }
+}
+
+impl Exception {
+    /// For faults caused by a memory access (misaligned, access fault, page fault), the kind of
+    /// access: instruction fetch = `Execute`, load = `Read`, store/AMO = `Write`. `None` otherwise.
+    pub fn access_type(&self) -> Option<AccessType> {
+        match self {
+            Exception::InstructionMisaligned
+            | Exception::InstructionFault
+            | Exception::InstructionPageFault => Some(AccessType::Execute),
+            Exception::LoadMisaligned | Exception::LoadFault | Exception::LoadPageFault => {
+                Some(AccessType::Read)
+            }
+            Exception::StoreMisaligned | Exception::StoreFault | Exception::StorePageFault => {
+                Some(AccessType::Write)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this is one of the three page faults (codes 12, 13, 15).
+    pub fn is_page_fault(&self) -> bool {
+        matches!(
+            self,
+            Exception::InstructionPageFault | Exception::LoadPageFault | Exception::StorePageFault
+        )
+    }
+}
+
+/// Interpret `stval` for `trap` (see the table in the module docs).
+///
+/// Memory faults and breakpoints report an address, illegal instructions the instruction bits;
+/// environment calls, interrupts and unknown causes carry nothing (`Stval::None`).
+pub fn decode_stval(trap: Trap, stval: u64) -> Stval {
+    match trap {
+        Trap::Exception(Exception::IllegalInstruction) => Stval::Instruction(stval),
+        Trap::Exception(Exception::Breakpoint) => Stval::Addr(stval),
+        Trap::Exception(e) if e.access_type().is_some() => Stval::Addr(stval),
+        _ => Stval::None,
+    }
+}
@@ -0,0 +1,340 @@
+//! # Timer Interrupt and Tick Bookkeeping
+//!
+//! In this exercise, you simulate the timer path of a kernel: a CLINT-style timer raises an
+//! interrupt when its counter passes the programmed deadline, and the tick handler does the
+//! periodic bookkeeping — count jiffies, wake sleeping threads, ask for a reschedule.
+//!
+//! ## Concepts
+//! - CLINT: free-running `mtime`, per-hart `mtimecmp`; interrupt pending while `mtime >= mtimecmp`
+//! - `set_timer(next)` (SBI TIME extension) programs `mtimecmp`, which also clears the pending interrupt
+//! - Periodic ticks: re-arm relative to the previous deadline so late handling does not drift
+//! - Jiffies, a sleeper wait list ordered by wake-up tick, time slices and the reschedule flag
+//!
+//! ## Flow
+//! ```text
+//! clint.advance(cycles)      (time passes; in tests we drive the clock by hand)
+//!   while clint.pending():
+//!     on_tick():  jiffies += 1
+//!                 set_timer(old deadline + interval)
+//!                 wake sleepers with wake_at <= jiffies
+//!                 time slice used up or someone woke up -> need_resched = true
+//! ```
+//!
+//! The wait list stores plain `usize` ids, so any scheduler can use `TimerDriver` as its clock:
+//! `02_green_threads` does with its optional `timer` feature (`sleep_ticks`).
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Simulated core-local interruptor (single hart).
+#[derive(Debug)]
+pub struct Clint {
+    /// Free-running cycle counter.
+    mtime: u64,
+    /// Deadline: the timer interrupt is pending while `mtime >= mtimecmp`.
+    mtimecmp: u64,
+}
+
+impl Clint {
+    /// Clock at 0, no timer programmed (`mtimecmp = u64::MAX`).
+    pub fn new() -> Self {
+        Self {
+            mtime: 0,
+            mtimecmp: u64::MAX,
+        }
+    }
+
+    pub fn mtime(&self) -> u64 {
+        self.mtime
+    }
+
+    pub fn mtimecmp(&self) -> u64 {
+        self.mtimecmp
+    }
+
+    /// Let `cycles` of simulated time pass.
+    pub fn advance(&mut self, cycles: u64) {
+        self.mtime += cycles;
+    }
+
+    /// Program the next timer interrupt for time `next` (what SBI `set_timer` does).
+    /// A deadline in the future also clears a pending interrupt.
+    pub fn set_timer(&mut self, next: u64) {
+        // TODO
+        todo!()
+    }
+
+    /// Whether the timer interrupt is pending (`mtime >= mtimecmp`).
+    pub fn pending(&self) -> bool {
+        // TODO
+        todo!()
+    }
+}
+
+impl Default for Clint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Periodic tick on top of a `Clint`, with a sleeper wait list and a reschedule flag.
+#[derive(Debug)]
+pub struct TimerDriver {
+    clint: Clint,
+    /// Cycles between two ticks.
+    interval: u64,
+    /// Ticks per time slice.
+    slice: u64,
+    /// Ticks left in the running thread's time slice.
+    slice_left: u64,
+    /// Ticks handled since boot.
+    jiffies: u64,
+    /// `(wake_at, id)`, earliest first; ties are woken in id order.
+    sleepers: BinaryHeap<Reverse<(u64, usize)>>,
+    need_resched: bool,
+}
+
+impl TimerDriver {
+    /// A tick every `interval` cycles and a reschedule every `slice` ticks; arms the first tick
+    /// at `interval`.
+    pub fn new(interval: u64, slice: u64) -> Self {
+        assert!(interval > 0 && slice > 0);
+        let mut clint = Clint::new();
+        clint.set_timer(interval);
+        Self {
+            clint,
+            interval,
+            slice,
+            slice_left: slice,
+            jiffies: 0,
+            sleepers: BinaryHeap::new(),
+            need_resched: false,
+        }
+    }
+
+    pub fn clint(&self) -> &Clint {
+        &self.clint
+    }
+
+    pub fn jiffies(&self) -> u64 {
+        self.jiffies
+    }
+
+    /// Number of ids in the wait list.
+    pub fn sleeping(&self) -> usize {
+        self.sleepers.len()
+    }
+
+    /// Put `id` to sleep until tick `wake_at`. A tick that has already passed wakes `id` on the
+    /// next tick.
+    pub fn sleep_until(&mut self, id: usize, wake_at: u64) {
+        // TODO: push onto the wait list
+        todo!()
+    }
+
+    /// Put `id` to sleep for `ticks` ticks from now.
+    pub fn sleep_for(&mut self, id: usize, ticks: u64) {
+        self.sleep_until(id, self.jiffies + ticks);
+    }
+
+    /// Tick at which the earliest sleeper wakes up, if any.
+    pub fn next_wakeup(&self) -> Option<u64> {
+        // TODO: peek at the wait list
+        todo!()
+    }
+
+    /// Timer interrupt handler; returns the ids woken by this tick, in wake-up order.
+    ///
+    /// 1. `jiffies += 1`.
+    /// 2. Re-arm: `set_timer(mtimecmp + interval)` — relative to the previous deadline, not to
+    ///    `mtime`, so handling a tick late does not shift all later ticks.
+    /// 3. Pop every sleeper with `wake_at <= jiffies`.
+    /// 4. Count down `slice_left`; when it reaches 0, refill it and set `need_resched`. Also set
+    ///    `need_resched` if anyone woke up.
+    pub fn on_tick(&mut self) -> Vec<usize> {
+        // TODO
+        todo!()
+    }
+
+    /// Let `cycles` pass and run `on_tick` once for every tick that became due; returns all
+    /// woken ids.
+    pub fn advance(&mut self, cycles: u64) -> Vec<usize> {
+        self.clint.advance(cycles);
+        let mut woken = Vec::new();
+        while self.clint.pending() {
+            woken.extend(self.on_tick());
+        }
+        woken
+    }
+
+    /// Idle CPU: skip straight to the tick that wakes the earliest sleeper (nothing to do in
+    /// between) and return the woken ids. Does nothing without sleepers.
+    pub fn advance_to_next_wakeup(&mut self) -> Vec<usize> {
+        let Some(wake_at) = self.next_wakeup() else {
+            return Vec::new();
+        };
+        let ticks = wake_at.max(self.jiffies + 1) - self.jiffies;
+        let target = self.clint.mtimecmp() + (ticks - 1) * self.interval;
+        self.advance(target - self.clint.mtime())
+    }
+
+    /// Return and clear the reschedule flag.
+    pub fn take_resched(&mut self) -> bool {
+        std::mem::take(&mut self.need_resched)
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_clint_pending",
+            "the timer interrupt is pending while mtime >= mtimecmp; setting a future deadline clears it",
+        ),
+        (
+            "test_late_handling_does_not_drift",
+            "re-arm with set_timer(mtimecmp + interval), relative to the previous deadline, not to mtime",
+        ),
+        (
+            "test_sleepers_wake_in_order",
+            "keep the wait list ordered by wake_at (a min-heap) and pop every sleeper with wake_at <= jiffies",
+        ),
+        (
+            "test_sleep_until_past_tick_wakes_next_tick",
+            "a wake_at that already passed still wakes on the next tick: <= jiffies, not ==",
+        ),
+        (
+            "test_resched_on_slice_boundary",
+            "count slice_left down on each tick; at 0 refill it and set need_resched",
+        ),
+        (
+            "test_resched_on_wakeup",
+            "set need_resched whenever a tick woke somebody, too",
+        ),
+    ];
+
+    #[test]
+    fn test_clint_pending() {
+        let mut clint = Clint::new();
+        clint.advance(1_000_000);
+        assert_with_hint!(!clint.pending(), "no timer programmed");
+        clint.set_timer(1_000_100);
+        assert_with_hint!(!clint.pending());
+        clint.advance(99);
+        assert_with_hint!(!clint.pending());
+        clint.advance(1);
+        assert_with_hint!(clint.pending(), "pending once mtime reaches mtimecmp");
+        clint.advance(50);
+        assert_with_hint!(clint.pending(), "stays pending until re-armed");
+        clint.set_timer(clint.mtime() + 10);
+        assert_with_hint!(!clint.pending(), "a future deadline clears the interrupt");
+    }
+
+    #[test]
+    fn test_ticks_count_and_rearm() {
+        let mut t = TimerDriver::new(100, 1000);
+        assert_eq!(t.clint().mtimecmp(), 100);
+        t.advance(99);
+        assert_eq!(t.jiffies(), 0);
+        t.advance(1);
+        assert_eq!(t.jiffies(), 1);
+        assert_eq!(t.clint().mtimecmp(), 200);
+        t.advance(250); // mtime = 350: ticks at 200 and 300
+        assert_eq!(t.jiffies(), 3);
+        assert_eq!(t.clint().mtimecmp(), 400);
+    }
+
+    #[test]
+    fn test_late_handling_does_not_drift() {
+        let mut t = TimerDriver::new(100, 1000);
+        t.advance(130); // tick handled 30 cycles late
+        assert_eq_with_hint!(
+            t.clint().mtimecmp(),
+            200,
+            "re-arm from the deadline, not from mtime"
+        );
+        t.advance(1000); // mtime = 1130: ticks 200..=1100
+        assert_eq_with_hint!(t.jiffies(), 11);
+        assert_eq_with_hint!(t.clint().mtimecmp(), 1200);
+    }
+
+    #[test]
+    fn test_sleepers_wake_in_order() {
+        let mut t = TimerDriver::new(10, 1000);
+        t.sleep_for(7, 3);
+        t.sleep_for(2, 1);
+        t.sleep_for(5, 3);
+        t.sleep_for(1, 5);
+        assert_eq_with_hint!(t.sleeping(), 4);
+        assert_eq_with_hint!(t.next_wakeup(), Some(1));
+
+        assert_eq_with_hint!(t.advance(10), vec![2]);
+        assert_eq_with_hint!(t.advance(10), Vec::<usize>::new());
+        assert_eq_with_hint!(t.advance(10), vec![5, 7], "same tick: woken in id order");
+        assert_eq_with_hint!(t.next_wakeup(), Some(5));
+        assert_eq_with_hint!(t.advance(100), vec![1]);
+        assert_eq_with_hint!(t.sleeping(), 0);
+        assert_eq_with_hint!(t.next_wakeup(), None);
+    }
+
+    #[test]
+    fn test_sleep_until_past_tick_wakes_next_tick() {
+        let mut t = TimerDriver::new(10, 1000);
+        t.advance(50);
+        t.sleep_until(3, 2);
+        assert_eq_with_hint!(t.advance(10), vec![3]);
+    }
+
+    #[test]
+    fn test_resched_on_slice_boundary() {
+        let mut t = TimerDriver::new(10, 3);
+        let mut resched_at = Vec::new();
+        for _ in 0..9 {
+            t.advance(10);
+            if t.take_resched() {
+                resched_at.push(t.jiffies());
+            }
+        }
+        assert_eq_with_hint!(resched_at, vec![3, 6, 9]);
+        assert_with_hint!(!t.take_resched(), "take_resched clears the flag");
+    }
+
+    #[test]
+    fn test_resched_on_wakeup() {
+        let mut t = TimerDriver::new(10, 1000);
+        t.sleep_for(4, 2);
+        t.advance(10);
+        assert_with_hint!(!t.take_resched());
+        t.advance(10);
+        assert_with_hint!(
+            t.take_resched(),
+            "a woken sleeper should get a chance to run"
+        );
+    }
+
+    #[test]
+    fn test_advance_to_next_wakeup() {
+        let mut t = TimerDriver::new(100, 1000);
+        assert!(t.advance_to_next_wakeup().is_empty());
+        assert_eq!(t.jiffies(), 0, "no sleepers, no time travel");
+
+        t.advance(30);
+        t.sleep_for(9, 4);
+        t.sleep_for(8, 6);
+        assert_eq!(t.advance_to_next_wakeup(), vec![9]);
+        assert_eq!(t.jiffies(), 4);
+        assert_eq!(t.clint().mtime(), 400);
+        assert_eq!(t.advance_to_next_wakeup(), vec![8]);
+        assert_eq!(t.jiffies(), 6);
+    }
+}
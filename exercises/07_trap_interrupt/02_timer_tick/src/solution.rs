@@ -0,0 +1,199 @@
+//! # Timer Interrupt and Tick Bookkeeping
+//!
+//! In this exercise, you simulate the timer path of a kernel: a CLINT-style timer raises an
+//! interrupt when its counter passes the programmed deadline, and the tick handler does the
+//! periodic bookkeeping — count jiffies, wake sleeping threads, ask for a reschedule.
+//!
+//! ## Concepts
+//! - CLINT: free-running `mtime`, per-hart `mtimecmp`; interrupt pending while `mtime >= mtimecmp`
+//! - `set_timer(next)` (SBI TIME extension) programs `mtimecmp`, which also clears the pending interrupt
+//! - Periodic ticks: re-arm relative to the previous deadline so late handling does not drift
+//! - Jiffies, a sleeper wait list ordered by wake-up tick, time slices and the reschedule flag
+//!
+//! ## Flow
+//! ```text
+//! clint.advance(cycles)      (time passes; in tests we drive the clock by hand)
+//!   while clint.pending():
+//!     on_tick():  jiffies += 1
+//!                 set_timer(old deadline + interval)
+//!                 wake sleepers with wake_at <= jiffies
+//!                 time slice used up or someone woke up -> need_resched = true
+//! ```
+//!
+//! The wait list stores plain `usize` ids, so any scheduler can use `TimerDriver` as its clock:
+//! `02_green_threads` does with its optional `timer` feature (`sleep_ticks`).
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Simulated core-local interruptor (single hart).
+#[derive(Debug)]
+pub struct Clint {
+    /// Free-running cycle counter.
+    mtime: u64,
+    /// Deadline: the timer interrupt is pending while `mtime >= mtimecmp`.
+    mtimecmp: u64,
+}
+
+impl Clint {
+    /// Clock at 0, no timer programmed (`mtimecmp = u64::MAX`).
+    pub fn new() -> Self {
+        Self {
+            mtime: 0,
+            mtimecmp: u64::MAX,
+        }
+    }
+
+    pub fn mtime(&self) -> u64 {
+        self.mtime
+    }
+
+    pub fn mtimecmp(&self) -> u64 {
+        self.mtimecmp
+    }
+
+    /// Let `cycles` of simulated time pass.
+    pub fn advance(&mut self, cycles: u64) {
+        self.mtime += cycles;
+    }
+
+    /// Program the next timer interrupt for time `next` (what SBI `set_timer` does).
+    /// A deadline in the future also clears a pending interrupt.
+    pub fn set_timer(&mut self, next: u64) {
+        self.mtimecmp = next;
+    }
+
+    /// Whether the timer interrupt is pending (`mtime >= mtimecmp`).
+    pub fn pending(&self) -> bool {
+        self.mtime >= self.mtimecmp
+    }
+}
+
+impl Default for Clint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Periodic tick on top of a `Clint`, with a sleeper wait list and a reschedule flag.
+#[derive(Debug)]
+pub struct TimerDriver {
+    clint: Clint,
+    /// Cycles between two ticks.
+    interval: u64,
+    /// Ticks per time slice.
+    slice: u64,
+    /// Ticks left in the running thread's time slice.
+    slice_left: u64,
+    /// Ticks handled since boot.
+    jiffies: u64,
+    /// `(wake_at, id)`, earliest first; ties are woken in id order.
+    sleepers: BinaryHeap<Reverse<(u64, usize)>>,
+    need_resched: bool,
+}
+
+impl TimerDriver {
+    /// A tick every `interval` cycles and a reschedule every `slice` ticks; arms the first tick
+    /// at `interval`.
+    pub fn new(interval: u64, slice: u64) -> Self {
+        assert!(interval > 0 && slice > 0);
+        let mut clint = Clint::new();
+        clint.set_timer(interval);
+        Self {
+            clint,
+            interval,
+            slice,
+            slice_left: slice,
+            jiffies: 0,
+            sleepers: BinaryHeap::new(),
+            need_resched: false,
+        }
+    }
+
+    pub fn clint(&self) -> &Clint {
+        &self.clint
+    }
+
+    pub fn jiffies(&self) -> u64 {
+        self.jiffies
+    }
+
+    /// Number of ids in the wait list.
+    pub fn sleeping(&self) -> usize {
+        self.sleepers.len()
+    }
+
+    /// Put `id` to sleep until tick `wake_at`. A tick that has already passed wakes `id` on the
+    /// next tick.
+    pub fn sleep_until(&mut self, id: usize, wake_at: u64) {
+        self.sleepers.push(Reverse((wake_at, id)));
+    }
+
+    /// Put `id` to sleep for `ticks` ticks from now.
+    pub fn sleep_for(&mut self, id: usize, ticks: u64) {
+        self.sleep_until(id, self.jiffies + ticks);
+    }
+
+    /// Tick at which the earliest sleeper wakes up, if any.
+    pub fn next_wakeup(&self) -> Option<u64> {
+        self.sleepers.peek().map(|Reverse((t, _))| *t)
+    }
+
+    /// Timer interrupt handler; returns the ids woken by this tick, in wake-up order.
+    ///
+    /// 1. `jiffies += 1`.
+    /// 2. Re-arm: `set_timer(mtimecmp + interval)` — relative to the previous deadline, not to
+    ///    `mtime`, so handling a tick late does not shift all later ticks.
+    /// 3. Pop every sleeper with `wake_at <= jiffies`.
+    /// 4. Count down `slice_left`; when it reaches 0, refill it and set `need_resched`. Also set
+    ///    `need_resched` if anyone woke up.
+    pub fn on_tick(&mut self) -> Vec<usize> {
+        self.jiffies += 1;
+        let next = self.clint.mtimecmp() + self.interval;
+        self.clint.set_timer(next);
+        let mut woken = Vec::new();
+        while let Some(&Reverse((wake_at, id))) = self.sleepers.peek() {
+            if wake_at > self.jiffies {
+                break;
+            }
+            self.sleepers.pop();
+            woken.push(id);
+        }
+        self.slice_left -= 1;
+        if self.slice_left == 0 {
+            self.slice_left = self.slice;
+            self.need_resched = true;
+        }
+        if !woken.is_empty() {
+            self.need_resched = true;
+        }
+        woken
+    }
+
+    /// Let `cycles` pass and run `on_tick` once for every tick that became due; returns all
+    /// woken ids.
+    pub fn advance(&mut self, cycles: u64) -> Vec<usize> {
+        self.clint.advance(cycles);
+        let mut woken = Vec::new();
+        while self.clint.pending() {
+            woken.extend(self.on_tick());
+        }
+        woken
+    }
+
+    /// Idle CPU: skip straight to the tick that wakes the earliest sleeper (nothing to do in
+    /// between) and return the woken ids. Does nothing without sleepers.
+    pub fn advance_to_next_wakeup(&mut self) -> Vec<usize> {
+        let Some(wake_at) = self.next_wakeup() else {
+            return Vec::new();
+        };
+        let ticks = wake_at.max(self.jiffies + 1) - self.jiffies;
+        let target = self.clint.mtimecmp() + (ticks - 1) * self.interval;
+        self.advance(target - self.clint.mtime())
+    }
+
+    /// Return and clear the reschedule flag.
+    pub fn take_resched(&mut self) -> bool {
+        std::mem::take(&mut self.need_resched)
+    }
+}
@@ -0,0 +1,293 @@
+//! # PLIC External-Interrupt Controller
+//!
+//! In this exercise, you simulate the RISC-V Platform-Level Interrupt Controller: the chip that
+//! collects interrupt lines from devices (UART, virtio, ...) and decides which hart gets which one.
+//!
+//! ## Concepts
+//! - Interrupt sources `1..num_sources` (source 0 does not exist: `claim` returning 0 means "none")
+//! - Per-source priority; priority 0 means "never interrupt"
+//! - Per-hart (per-context) enable bits and priority threshold: a hart only sees enabled sources
+//!   whose priority is strictly greater than its threshold
+//! - claim / complete: the handler claims the best pending source (clearing its pending bit),
+//!   talks to the device, then completes it
+//! - Gateway: a claimed source cannot become pending again until it is completed; if the device
+//!   still holds its (level-triggered) line high at that point, it is pending again right away
+//!
+//! ## Handler flow
+//! ```text
+//! trap: scause = SupervisorExternal
+//!   while let Some(src) = plic.claim(hart) {
+//!       handle device `src` (which lowers its line once serviced)
+//!       plic.complete(hart, src)
+//!   }
+//! ```
+
+/// Simulated PLIC with `num_sources` sources (including the unused source 0) and `num_harts` contexts.
+#[derive(Debug)]
+pub struct Plic {
+    /// Priority of each source; 0 = disabled.
+    priority: Vec<u32>,
+    /// Device interrupt line is high.
+    line: Vec<bool>,
+    /// Pending bit: forwarded by the gateway, not yet claimed.
+    pending: Vec<bool>,
+    /// Claimed by some hart, not yet completed.
+    in_flight: Vec<bool>,
+    /// `enable[hart][src]`.
+    enable: Vec<Vec<bool>>,
+    /// Per-hart priority threshold.
+    threshold: Vec<u32>,
+}
+
+impl Plic {
+    /// All priorities, enable bits and thresholds start at 0, so nothing interrupts yet.
+    pub fn new(num_sources: usize, num_harts: usize) -> Self {
+        Self {
+            priority: vec![0; num_sources],
+            line: vec![false; num_sources],
+            pending: vec![false; num_sources],
+            in_flight: vec![false; num_sources],
+            enable: vec![vec![false; num_sources]; num_harts],
+            threshold: vec![0; num_harts],
+        }
+    }
+
+    pub fn set_priority(&mut self, src: u32, priority: u32) {
+        self.priority[src as usize] = priority;
+    }
+
+    pub fn set_enable(&mut self, hart: usize, src: u32, enabled: bool) {
+        self.enable[hart][src as usize] = enabled;
+    }
+
+    pub fn set_threshold(&mut self, hart: usize, threshold: u32) {
+        self.threshold[hart] = threshold;
+    }
+
+    pub fn is_pending(&self, src: u32) -> bool {
+        self.pending[src as usize]
+    }
+
+    /// Device drives its interrupt line high.
+    ///
+    /// Set the line; the gateway forwards it (sets the pending bit) unless the source is
+    /// currently claimed. Source 0 does not exist: ignore it.
+    pub fn raise(&mut self, src: u32) {
+        // TODO
+        todo!()
+    }
+
+    /// Device drops its interrupt line (e.g. the driver drained its FIFO). An already latched
+    /// pending bit stays set.
+    pub fn lower(&mut self, src: u32) {
+        self.line[src as usize] = false;
+    }
+
+    /// The source `hart` would get from `claim` right now, without claiming it.
+    ///
+    /// Among sources that are pending, enabled for `hart` and have
+    /// `priority > threshold[hart]`, return the one with the highest priority; on a tie the
+    /// lowest source id wins.
+    pub fn best_pending(&self, hart: usize) -> Option<u32> {
+        // TODO
+        todo!()
+    }
+
+    /// Whether the external-interrupt line into `hart` (`sip.SEIP`) is asserted.
+    pub fn eip(&self, hart: usize) -> bool {
+        self.best_pending(hart).is_some()
+    }
+
+    /// Claim the best pending source for `hart`: clear its pending bit and mark it in flight.
+    /// `None` (claim register reads 0) if there is nothing to claim.
+    pub fn claim(&mut self, hart: usize) -> Option<u32> {
+        // TODO
+        todo!()
+    }
+
+    /// Signal that `hart` finished handling `src`.
+    ///
+    /// Like the real PLIC, a completion for a source that is not enabled for `hart` is silently
+    /// ignored. Otherwise the source leaves flight, and if its line is still high the gateway
+    /// forwards it again (pending).
+    pub fn complete(&mut self, hart: usize, src: u32) {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_priority_arbitration",
+            "among pending, enabled sources with priority > threshold, pick the highest priority",
+        ),
+        (
+            "test_equal_priority_lowest_id_wins",
+            "on a priority tie the lowest source id wins: scan ids upwards and replace only on a strictly higher priority",
+        ),
+        (
+            "test_threshold_masks_low_priorities",
+            "a source is eligible only if its priority is strictly greater than the hart's threshold",
+        ),
+        (
+            "test_source_zero_is_ignored",
+            "source 0 does not exist: raise(0) does nothing",
+        ),
+        (
+            "test_gateway_holds_claimed_source_until_complete",
+            "while a source is in flight the gateway does not set its pending bit again; complete re-forwards a line that is still high",
+        ),
+        (
+            "test_complete_from_wrong_hart_is_ignored",
+            "complete does nothing if the source is not enabled for that hart",
+        ),
+    ];
+
+    /// Three sources with priorities 1, 3, 2, all enabled for hart 0 (threshold 0).
+    fn plic3() -> Plic {
+        let mut plic = Plic::new(4, 2);
+        for (src, prio) in [(1, 1), (2, 3), (3, 2)] {
+            plic.set_priority(src, prio);
+            plic.set_enable(0, src, true);
+        }
+        plic
+    }
+
+    /// Claim until empty, completing each source after lowering its line.
+    fn drain(plic: &mut Plic, hart: usize) -> Vec<u32> {
+        let mut order = Vec::new();
+        while let Some(src) = plic.claim(hart) {
+            plic.lower(src);
+            plic.complete(hart, src);
+            order.push(src);
+        }
+        order
+    }
+
+    #[test]
+    fn test_nothing_pending() {
+        let mut plic = plic3();
+        assert!(!plic.eip(0));
+        assert_eq!(plic.claim(0), None);
+    }
+
+    #[test]
+    fn test_priority_arbitration() {
+        let mut plic = plic3();
+        plic.raise(1);
+        plic.raise(2);
+        plic.raise(3);
+        assert_with_hint!(plic.eip(0));
+        assert_eq_with_hint!(plic.best_pending(0), Some(2));
+        assert_eq_with_hint!(drain(&mut plic, 0), vec![2, 3, 1]);
+        assert_with_hint!(!plic.eip(0));
+    }
+
+    #[test]
+    fn test_equal_priority_lowest_id_wins() {
+        let mut plic = plic3();
+        plic.set_priority(1, 5);
+        plic.set_priority(3, 5);
+        plic.raise(3);
+        plic.raise(1);
+        assert_eq_with_hint!(drain(&mut plic, 0), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_disabled_source_is_masked_but_stays_pending() {
+        let mut plic = plic3();
+        plic.set_enable(0, 2, false);
+        plic.raise(2);
+        assert!(!plic.eip(0));
+        assert_eq!(plic.claim(0), None);
+        assert!(plic.is_pending(2), "masking does not drop the interrupt");
+        plic.set_enable(0, 2, true);
+        assert_eq!(plic.claim(0), Some(2));
+    }
+
+    #[test]
+    fn test_threshold_masks_low_priorities() {
+        let mut plic = plic3();
+        plic.raise(1);
+        plic.raise(3);
+        plic.set_threshold(0, 2);
+        assert_eq_with_hint!(
+            plic.claim(0),
+            None,
+            "priority must be strictly above the threshold"
+        );
+        plic.set_threshold(0, 1);
+        assert_eq_with_hint!(drain(&mut plic, 0), vec![3]);
+        plic.set_threshold(0, 0);
+        assert_eq_with_hint!(drain(&mut plic, 0), vec![1]);
+    }
+
+    #[test]
+    fn test_priority_zero_never_interrupts() {
+        let mut plic = plic3();
+        plic.set_priority(2, 0);
+        plic.raise(2);
+        assert_eq!(plic.claim(0), None);
+    }
+
+    #[test]
+    fn test_source_zero_is_ignored() {
+        let mut plic = plic3();
+        plic.set_enable(0, 0, true);
+        plic.set_priority(0, 7);
+        plic.raise(0);
+        assert_eq_with_hint!(plic.claim(0), None);
+    }
+
+    #[test]
+    fn test_gateway_holds_claimed_source_until_complete() {
+        let mut plic = plic3();
+        plic.raise(1);
+        assert_eq_with_hint!(plic.claim(0), Some(1));
+        // Device fires again while the handler is still running: not forwarded yet.
+        plic.raise(1);
+        assert_with_hint!(!plic.is_pending(1));
+        assert_eq_with_hint!(plic.claim(0), None);
+        // Line still high at completion: pending again.
+        plic.complete(0, 1);
+        assert_eq_with_hint!(plic.claim(0), Some(1));
+        // This time the driver services the device first.
+        plic.lower(1);
+        plic.complete(0, 1);
+        assert_eq_with_hint!(plic.claim(0), None);
+    }
+
+    #[test]
+    fn test_claim_is_exclusive_between_harts() {
+        let mut plic = plic3();
+        plic.set_enable(1, 2, true);
+        plic.raise(2);
+        assert!(plic.eip(0) && plic.eip(1), "both harts see the interrupt");
+        assert_eq!(plic.claim(1), Some(2));
+        assert_eq!(plic.claim(0), None, "only one hart gets to handle it");
+        assert!(!plic.eip(0));
+    }
+
+    #[test]
+    fn test_complete_from_wrong_hart_is_ignored() {
+        let mut plic = plic3();
+        plic.raise(3);
+        assert_eq_with_hint!(plic.claim(0), Some(3));
+        plic.complete(1, 3); // source 3 is not enabled for hart 1
+        assert_eq_with_hint!(plic.claim(0), None, "still in flight");
+        plic.complete(0, 3);
+        assert_eq_with_hint!(plic.claim(0), Some(3), "line still high");
+    }
+}
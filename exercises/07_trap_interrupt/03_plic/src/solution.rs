@@ -0,0 +1,134 @@
+//! # PLIC External-Interrupt Controller
+//!
+//! In this exercise, you simulate the RISC-V Platform-Level Interrupt Controller: the chip that
+//! collects interrupt lines from devices (UART, virtio, ...) and decides which hart gets which one.
+//!
+//! ## Concepts
+//! - Interrupt sources `1..num_sources` (source 0 does not exist: `claim` returning 0 means "none")
+//! - Per-source priority; priority 0 means "never interrupt"
+//! - Per-hart (per-context) enable bits and priority threshold: a hart only sees enabled sources
+//!   whose priority is strictly greater than its threshold
+//! - claim / complete: the handler claims the best pending source (clearing its pending bit),
+//!   talks to the device, then completes it
+//! - Gateway: a claimed source cannot become pending again until it is completed; if the device
+//!   still holds its (level-triggered) line high at that point, it is pending again right away
+//!
+//! ## Handler flow
+//! ```text
+//! trap: scause = SupervisorExternal
+//!   while let Some(src) = plic.claim(hart) {
+//!       handle device `src` (which lowers its line once serviced)
+//!       plic.complete(hart, src)
+//!   }
+//! ```
+
+/// Simulated PLIC with `num_sources` sources (including the unused source 0) and `num_harts` contexts.
+#[derive(Debug)]
+pub struct Plic {
+    /// Priority of each source; 0 = disabled.
+    priority: Vec<u32>,
+    /// Device interrupt line is high.
+    line: Vec<bool>,
+    /// Pending bit: forwarded by the gateway, not yet claimed.
+    pending: Vec<bool>,
+    /// Claimed by some hart, not yet completed.
+    in_flight: Vec<bool>,
+    /// `enable[hart][src]`.
+    enable: Vec<Vec<bool>>,
+    /// Per-hart priority threshold.
+    threshold: Vec<u32>,
+}
+
+impl Plic {
+    /// All priorities, enable bits and thresholds start at 0, so nothing interrupts yet.
+    pub fn new(num_sources: usize, num_harts: usize) -> Self {
+        Self {
+            priority: vec![0; num_sources],
+            line: vec![false; num_sources],
+            pending: vec![false; num_sources],
+            in_flight: vec![false; num_sources],
+            enable: vec![vec![false; num_sources]; num_harts],
+            threshold: vec![0; num_harts],
+        }
+    }
+
+    pub fn set_priority(&mut self, src: u32, priority: u32) {
+        self.priority[src as usize] = priority;
+    }
+
+    pub fn set_enable(&mut self, hart: usize, src: u32, enabled: bool) {
+        self.enable[hart][src as usize] = enabled;
+    }
+
+    pub fn set_threshold(&mut self, hart: usize, threshold: u32) {
+        self.threshold[hart] = threshold;
+    }
+
+    pub fn is_pending(&self, src: u32) -> bool {
+        self.pending[src as usize]
+    }
+
+    /// Device drives its interrupt line high.
+    ///
+    /// Set the line; the gateway forwards it (sets the pending bit) unless the source is
+    /// currently claimed. Source 0 does not exist: ignore it.
+    pub fn raise(&mut self, src: u32) {
+        if src == 0 {
+            return;
+        }
+        let src = src as usize;
+        self.line[src] = true;
+        if !self.in_flight[src] {
+            self.pending[src] = true;
+        }
+    }
+
+    /// Device drops its interrupt line (e.g. the driver drained its FIFO). An already latched
+    /// pending bit stays set.
+    pub fn lower(&mut self, src: u32) {
+        self.line[src as usize] = false;
+    }
+
+    /// The source `hart` would get from `claim` right now, without claiming it.
+    ///
+    /// Among sources that are pending, enabled for `hart` and have
+    /// `priority > threshold[hart]`, return the one with the highest priority; on a tie the
+    /// lowest source id wins.
+    pub fn best_pending(&self, hart: usize) -> Option<u32> {
+        let threshold = self.threshold[hart];
+        (1..self.priority.len())
+            .filter(|&s| self.pending[s] && self.enable[hart][s] && self.priority[s] > threshold)
+            .min_by_key(|&s| (std::cmp::Reverse(self.priority[s]), s))
+            .map(|s| s as u32)
+    }
+
+    /// Whether the external-interrupt line into `hart` (`sip.SEIP`) is asserted.
+    pub fn eip(&self, hart: usize) -> bool {
+        self.best_pending(hart).is_some()
+    }
+
+    /// Claim the best pending source for `hart`: clear its pending bit and mark it in flight.
+    /// `None` (claim register reads 0) if there is nothing to claim.
+    pub fn claim(&mut self, hart: usize) -> Option<u32> {
+        let src = self.best_pending(hart)?;
+        self.pending[src as usize] = false;
+        self.in_flight[src as usize] = true;
+        Some(src)
+    }
+
+    /// Signal that `hart` finished handling `src`.
+    ///
+    /// Like the real PLIC, a completion for a source that is not enabled for `hart` is silently
+    /// ignored. Otherwise the source leaves flight, and if its line is still high the gateway
+    /// forwards it again (pending).
+    pub fn complete(&mut self, hart: usize, src: u32) {
+        let src = src as usize;
+        if !self.enable[hart][src] {
+            return;
+        }
+        self.in_flight[src] = false;
+        if self.line[src] {
+            self.pending[src] = true;
+        }
+    }
+}
@@ -0,0 +1,566 @@
+//! # UART 16550 Driver
+//!
+//! In this exercise, you write two drivers for a simulated 16550 UART (the serial port QEMU's
+//! `virt` machine puts at `0x1000_0000`): a polling one, and an interrupt-driven one that buffers
+//! data in TX/RX ring buffers so the CPU never waits for the (slow) serial line.
+//!
+//! ## Registers (byte offsets)
+//! ```text
+//! 0  RBR (read)  receive buffer: pop one byte from the RX FIFO
+//! 0  THR (write) transmit holding: push one byte into the TX FIFO
+//! 1  IER         interrupt enable: bit 0 = RX data available, bit 1 = THR empty
+//! 2  IIR (read)  interrupt identification: 0x04 RX data, 0x02 THR empty, 0x01 none (| 0xC0 FIFOs on)
+//! 2  FCR (write) FIFO control: bit 1 clears the RX FIFO, bit 2 clears the TX FIFO
+//! 3  LCR, 4 MCR  line / modem control: stored, no effect in the model
+//! 5  LSR (read)  line status: bit 0 DR (data ready), bit 1 OE (overrun, clears on read),
+//!                bit 5 THRE (TX FIFO empty), bit 6 TEMT (transmitter idle)
+//! ```
+//!
+//! ## Model
+//! - Both FIFOs hold `FIFO_SIZE` bytes. A THR write into a full TX FIFO is lost; a byte arriving
+//!   at a full RX FIFO is lost and sets OE.
+//! - Time: every register access takes one cycle and `idle(n)` lets `n` cycles pass. Every
+//!   `CYCLES_PER_BYTE` cycles the line moves one byte out of the TX FIFO (onto `output()`) and one
+//!   byte fed with `feed()` into the RX FIFO.
+//! - Interrupts: `irq()` is the level of the UART's interrupt line (what a PLIC source would see).
+//!   RX data keeps it high while the RX FIFO is non-empty; THR empty is latched when the TX FIFO
+//!   runs empty (or when THR-empty interrupts are enabled while it already is) and cleared by
+//!   reading IIR or writing THR.
+
+use std::collections::VecDeque;
+
+pub const RBR: usize = 0;
+pub const THR: usize = 0;
+pub const IER: usize = 1;
+pub const IIR: usize = 2;
+pub const FCR: usize = 2;
+pub const LCR: usize = 3;
+pub const MCR: usize = 4;
+pub const LSR: usize = 5;
+
+pub const IER_RX_AVAILABLE: u8 = 1 << 0;
+pub const IER_THR_EMPTY: u8 = 1 << 1;
+
+pub const IIR_NO_INTERRUPT: u8 = 0x01;
+pub const IIR_THR_EMPTY: u8 = 0x02;
+pub const IIR_RX_AVAILABLE: u8 = 0x04;
+/// Set in every IIR read: FIFOs are enabled.
+pub const IIR_FIFO_ENABLED: u8 = 0xC0;
+/// Mask for the interrupt id in an IIR value.
+pub const IIR_ID_MASK: u8 = 0x0F;
+
+pub const FCR_ENABLE_FIFO: u8 = 1 << 0;
+pub const FCR_CLEAR_RX: u8 = 1 << 1;
+pub const FCR_CLEAR_TX: u8 = 1 << 2;
+
+pub const LSR_DATA_READY: u8 = 1 << 0;
+pub const LSR_OVERRUN: u8 = 1 << 1;
+pub const LSR_THR_EMPTY: u8 = 1 << 5;
+pub const LSR_TX_IDLE: u8 = 1 << 6;
+
+/// 8 data bits, no parity, 1 stop bit.
+pub const LCR_8N1: u8 = 0x03;
+
+pub const FIFO_SIZE: usize = 16;
+pub const CYCLES_PER_BYTE: u64 = 8;
+
+/// Simulated 16550 with the far end of the serial line attached.
+#[derive(Debug, Default)]
+pub struct Uart16550 {
+    ier: u8,
+    lcr: u8,
+    mcr: u8,
+    rx_fifo: VecDeque<u8>,
+    tx_fifo: VecDeque<u8>,
+    overrun: bool,
+    thre_pending: bool,
+    /// Bytes the far end has sent that have not reached the RX FIFO yet.
+    wire_in: VecDeque<u8>,
+    /// Bytes that went out over the line.
+    wire_out: Vec<u8>,
+    /// Cycles since the line last moved a byte.
+    phase: u64,
+    lost_rx: usize,
+    lost_tx: usize,
+}
+
+impl Uart16550 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register read at `offset` (one cycle).
+    pub fn read(&mut self, offset: usize) -> u8 {
+        self.idle(1);
+        match offset {
+            RBR => self.rx_fifo.pop_front().unwrap_or(0),
+            IER => self.ier,
+            IIR => {
+                let id = if self.ier & IER_RX_AVAILABLE != 0 && !self.rx_fifo.is_empty() {
+                    IIR_RX_AVAILABLE
+                } else if self.ier & IER_THR_EMPTY != 0 && self.thre_pending {
+                    self.thre_pending = false;
+                    IIR_THR_EMPTY
+                } else {
+                    IIR_NO_INTERRUPT
+                };
+                id | IIR_FIFO_ENABLED
+            }
+            LCR => self.lcr,
+            MCR => self.mcr,
+            LSR => {
+                let mut lsr = 0;
+                if !self.rx_fifo.is_empty() {
+                    lsr |= LSR_DATA_READY;
+                }
+                if std::mem::take(&mut self.overrun) {
+                    lsr |= LSR_OVERRUN;
+                }
+                if self.tx_fifo.is_empty() {
+                    lsr |= LSR_THR_EMPTY | LSR_TX_IDLE;
+                }
+                lsr
+            }
+            _ => 0,
+        }
+    }
+
+    /// Register write at `offset` (one cycle).
+    pub fn write(&mut self, offset: usize, value: u8) {
+        self.idle(1);
+        match offset {
+            THR => {
+                self.thre_pending = false;
+                if self.tx_fifo.len() < FIFO_SIZE {
+                    self.tx_fifo.push_back(value);
+                } else {
+                    self.lost_tx += 1;
+                }
+            }
+            IER => {
+                let newly_enabled = value & !self.ier;
+                self.ier = value & (IER_RX_AVAILABLE | IER_THR_EMPTY);
+                if newly_enabled & IER_THR_EMPTY != 0 && self.tx_fifo.is_empty() {
+                    self.thre_pending = true;
+                }
+            }
+            FCR => {
+                if value & FCR_CLEAR_RX != 0 {
+                    self.rx_fifo.clear();
+                }
+                if value & FCR_CLEAR_TX != 0 {
+                    self.tx_fifo.clear();
+                }
+            }
+            LCR => self.lcr = value,
+            MCR => self.mcr = value,
+            _ => {}
+        }
+    }
+
+    /// Let `cycles` cycles pass on the serial line.
+    pub fn idle(&mut self, cycles: u64) {
+        for _ in 0..cycles {
+            self.phase += 1;
+            if self.phase < CYCLES_PER_BYTE {
+                continue;
+            }
+            self.phase = 0;
+            if let Some(b) = self.tx_fifo.pop_front() {
+                self.wire_out.push(b);
+                if self.tx_fifo.is_empty() {
+                    self.thre_pending = true;
+                }
+            }
+            if let Some(b) = self.wire_in.pop_front() {
+                if self.rx_fifo.len() < FIFO_SIZE {
+                    self.rx_fifo.push_back(b);
+                } else {
+                    self.overrun = true;
+                    self.lost_rx += 1;
+                }
+            }
+        }
+    }
+
+    /// The far end sends `bytes`; they arrive at line speed.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.wire_in.extend(bytes);
+    }
+
+    /// Everything transmitted so far.
+    pub fn output(&self) -> &[u8] {
+        &self.wire_out
+    }
+
+    /// Level of the interrupt line.
+    pub fn irq(&self) -> bool {
+        (self.ier & IER_RX_AVAILABLE != 0 && !self.rx_fifo.is_empty())
+            || (self.ier & IER_THR_EMPTY != 0 && self.thre_pending)
+    }
+
+    /// Nothing left to send or receive on the line.
+    pub fn line_idle(&self) -> bool {
+        self.tx_fifo.is_empty() && self.wire_in.is_empty()
+    }
+
+    /// Received bytes dropped because the RX FIFO was full.
+    pub fn lost_rx(&self) -> usize {
+        self.lost_rx
+    }
+
+    /// THR writes dropped because the TX FIFO was full.
+    pub fn lost_tx(&self) -> usize {
+        self.lost_tx
+    }
+}
+
+/// Fixed-size byte queue; `push` fails instead of overwriting when full.
+pub struct RingBuffer<const N: usize> {
+    buf: [u8; N],
+    /// Index of the oldest byte.
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> RingBuffer<N> {
+    pub fn new() -> Self {
+        Self {
+            buf: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Append `byte`; `false` (and nothing stored) if the buffer is full.
+    /// The free slot is at `(head + len) % N`.
+    pub fn push(&mut self, byte: u8) -> bool {
+        // TODO
+        todo!()
+    }
+
+    /// Remove and return the oldest byte.
+    pub fn pop(&mut self) -> Option<u8> {
+        // TODO
+        todo!()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+}
+
+impl<const N: usize> Default for RingBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Busy-waiting driver: no interrupts, the CPU spins on LSR.
+pub struct PollingDriver {
+    uart: Uart16550,
+}
+
+impl PollingDriver {
+    /// 8N1, FIFOs enabled and cleared, all interrupts off.
+    pub fn new(mut uart: Uart16550) -> Self {
+        uart.write(LCR, LCR_8N1);
+        uart.write(FCR, FCR_ENABLE_FIFO | FCR_CLEAR_RX | FCR_CLEAR_TX);
+        uart.write(IER, 0);
+        Self { uart }
+    }
+
+    pub fn uart(&mut self) -> &mut Uart16550 {
+        &mut self.uart
+    }
+
+    /// Spin until LSR says THR is empty, then write `byte` to THR.
+    pub fn putc(&mut self, byte: u8) {
+        // TODO
+        todo!()
+    }
+
+    /// One received byte if LSR has data ready (read it from RBR), `None` otherwise. Does not wait.
+    pub fn getc(&mut self) -> Option<u8> {
+        // TODO
+        todo!()
+    }
+
+    pub fn write_bytes(&mut self, data: &[u8]) {
+        for &b in data {
+            self.putc(b);
+        }
+    }
+
+    /// Spin until the transmitter is idle (LSR.TEMT).
+    pub fn flush(&mut self) {
+        while self.uart.read(LSR) & LSR_TX_IDLE == 0 {}
+    }
+}
+
+pub const RING_SIZE: usize = 64;
+
+/// Interrupt-driven driver: `write` / `read` only touch the ring buffers; `handle_interrupt`
+/// moves bytes between the rings and the FIFOs.
+pub struct InterruptDriver {
+    uart: Uart16550,
+    tx: RingBuffer<RING_SIZE>,
+    rx: RingBuffer<RING_SIZE>,
+    irq_count: usize,
+    rx_dropped: usize,
+}
+
+impl InterruptDriver {
+    /// 8N1, FIFOs enabled and cleared, RX interrupts on. THR-empty interrupts stay off until
+    /// there is something to send.
+    pub fn new(mut uart: Uart16550) -> Self {
+        uart.write(LCR, LCR_8N1);
+        uart.write(FCR, FCR_ENABLE_FIFO | FCR_CLEAR_RX | FCR_CLEAR_TX);
+        uart.write(IER, IER_RX_AVAILABLE);
+        Self {
+            uart,
+            tx: RingBuffer::new(),
+            rx: RingBuffer::new(),
+            irq_count: 0,
+            rx_dropped: 0,
+        }
+    }
+
+    pub fn uart(&mut self) -> &mut Uart16550 {
+        &mut self.uart
+    }
+
+    /// Queue as much of `data` as fits in the TX ring and return how many bytes were taken.
+    /// If anything was queued, make sure THR-empty interrupts are enabled (set `IER_THR_EMPTY`,
+    /// keeping the other IER bits): the handler will then feed the FIFO.
+    pub fn write(&mut self, data: &[u8]) -> usize {
+        // TODO
+        todo!()
+    }
+
+    /// Move up to `buf.len()` received bytes out of the RX ring; returns how many.
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        // TODO
+        todo!()
+    }
+
+    /// Interrupt handler. Count the call, then read IIR until it reports no interrupt:
+    ///
+    /// - `IIR_RX_AVAILABLE`: while LSR has data ready, read RBR into the RX ring (count a byte
+    ///   that does not fit in `rx_dropped`).
+    /// - `IIR_THR_EMPTY`: the TX FIFO is empty, so write up to `FIFO_SIZE` bytes from the TX ring
+    ///   to THR. If the TX ring is now empty, clear `IER_THR_EMPTY` so an idle transmitter does
+    ///   not keep interrupting.
+    pub fn handle_interrupt(&mut self) {
+        // TODO
+        todo!()
+    }
+
+    /// How many times `handle_interrupt` ran.
+    pub fn irq_count(&self) -> usize {
+        self.irq_count
+    }
+
+    pub fn rx_dropped(&self) -> usize {
+        self.rx_dropped
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_ring_buffer_fifo_and_full",
+            "push writes at (head + len) % N and fails when len == N; pop takes from head",
+        ),
+        (
+            "test_polling_getc_does_not_block",
+            "getc checks LSR data-ready once and returns None instead of waiting",
+        ),
+        (
+            "test_polling_long_write_loses_nothing",
+            "putc spins until LSR reports THR empty before every write to THR",
+        ),
+        (
+            "test_interrupt_write_returns_immediately",
+            "write only queues into the TX ring and sets IER_THR_EMPTY; the interrupt handler feeds THR",
+        ),
+        (
+            "test_interrupt_write_partial_when_ring_full",
+            "take only what fits in the TX ring and return that count",
+        ),
+        (
+            "test_interrupt_rx_ring_overflow_is_counted",
+            "a received byte that does not fit in the RX ring is counted in rx_dropped",
+        ),
+    ];
+
+    /// Let time pass one cycle at a time, taking the interrupt whenever the line is high.
+    fn run(drv: &mut InterruptDriver, cycles: u64) {
+        for _ in 0..cycles {
+            drv.uart().idle(1);
+            if drv.uart().irq() {
+                drv.handle_interrupt();
+            }
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_fifo_and_full() {
+        let mut ring = RingBuffer::<4>::new();
+        assert_with_hint!(ring.is_empty());
+        for b in 1..=4 {
+            assert_with_hint!(ring.push(b));
+        }
+        assert_with_hint!(ring.is_full());
+        assert_with_hint!(!ring.push(5), "full ring rejects");
+        assert_eq_with_hint!(ring.pop(), Some(1));
+        assert_eq_with_hint!(ring.pop(), Some(2));
+        // Wrap around the end of the array.
+        assert_with_hint!(ring.push(6));
+        assert_with_hint!(ring.push(7));
+        let mut out = Vec::new();
+        while let Some(b) = ring.pop() {
+            out.push(b);
+        }
+        assert_eq_with_hint!(out, vec![3, 4, 6, 7]);
+        assert_eq_with_hint!(ring.pop(), None);
+    }
+
+    #[test]
+    fn test_uart_model_registers() {
+        let mut uart = Uart16550::new();
+        assert_eq!(
+            uart.read(LSR) & (LSR_THR_EMPTY | LSR_DATA_READY),
+            LSR_THR_EMPTY
+        );
+        uart.write(THR, b'x');
+        assert_eq!(uart.read(LSR) & LSR_THR_EMPTY, 0);
+        uart.idle(CYCLES_PER_BYTE);
+        assert_eq!(uart.output(), b"x");
+        uart.feed(b"y");
+        uart.idle(CYCLES_PER_BYTE);
+        assert_ne!(uart.read(LSR) & LSR_DATA_READY, 0);
+        assert_eq!(uart.read(RBR), b'y');
+    }
+
+    #[test]
+    fn test_polling_echo() {
+        let mut drv = PollingDriver::new(Uart16550::new());
+        drv.uart().feed(b"ping");
+        let mut got = Vec::new();
+        while got.len() < 4 {
+            if let Some(b) = drv.getc() {
+                got.push(b);
+            }
+        }
+        assert_eq!(got, b"ping");
+        drv.write_bytes(&got);
+        drv.flush();
+        assert_eq!(drv.uart().output(), b"ping");
+    }
+
+    #[test]
+    fn test_polling_getc_does_not_block() {
+        let mut drv = PollingDriver::new(Uart16550::new());
+        assert_eq_with_hint!(drv.getc(), None);
+    }
+
+    #[test]
+    fn test_polling_long_write_loses_nothing() {
+        let mut drv = PollingDriver::new(Uart16550::new());
+        let msg: Vec<u8> = (0..100).collect();
+        drv.write_bytes(&msg);
+        drv.flush();
+        assert_eq_with_hint!(drv.uart().output(), &msg[..]);
+        assert_eq_with_hint!(drv.uart().lost_tx(), 0);
+    }
+
+    #[test]
+    fn test_interrupt_write_returns_immediately() {
+        let mut drv = InterruptDriver::new(Uart16550::new());
+        let msg = b"hello, interrupt-driven world";
+        assert_eq_with_hint!(drv.write(msg), msg.len());
+        assert_with_hint!(drv.uart().output().is_empty(), "write only fills the ring");
+        run(&mut drv, 1000);
+        assert_eq_with_hint!(drv.uart().output(), msg);
+        assert_eq_with_hint!(drv.uart().lost_tx(), 0);
+        // 29 bytes go out in two FIFO-sized batches, one THR-empty interrupt each; the handler
+        // that sends the last batch also turns THR-empty interrupts off.
+        assert_eq_with_hint!(drv.irq_count(), 2);
+        assert_with_hint!(!drv.uart().irq());
+    }
+
+    #[test]
+    fn test_interrupt_write_partial_when_ring_full() {
+        let mut drv = InterruptDriver::new(Uart16550::new());
+        let msg = [b'a'; RING_SIZE + 10];
+        assert_eq_with_hint!(drv.write(&msg), RING_SIZE);
+        assert_eq_with_hint!(drv.write(b"b"), 0);
+        run(&mut drv, 100);
+        assert_with_hint!(drv.write(b"b") > 0, "the handler made room");
+    }
+
+    #[test]
+    fn test_interrupt_echo() {
+        let mut drv = InterruptDriver::new(Uart16550::new());
+        drv.uart().feed(b"echo me");
+        let mut echoed = Vec::new();
+        for _ in 0..200 {
+            run(&mut drv, 1);
+            let mut buf = [0u8; 8];
+            let n = drv.read(&mut buf);
+            assert_eq!(drv.write(&buf[..n]), n);
+            echoed.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(echoed, b"echo me");
+        assert_eq!(drv.uart().output(), b"echo me");
+        assert!(drv.uart().line_idle());
+    }
+
+    #[test]
+    fn test_interrupt_rx_one_irq_per_byte_at_line_speed() {
+        let mut drv = InterruptDriver::new(Uart16550::new());
+        drv.uart().feed(b"abcde");
+        run(&mut drv, 5 * CYCLES_PER_BYTE + 1);
+        let mut buf = [0u8; 8];
+        assert_eq!(drv.read(&mut buf), 5);
+        assert_eq!(&buf[..5], b"abcde");
+        // The handler drains each byte as it arrives.
+        assert_eq!(drv.irq_count(), 5);
+        assert_eq!(drv.uart().lost_rx(), 0);
+    }
+
+    #[test]
+    fn test_interrupt_rx_ring_overflow_is_counted() {
+        let mut drv = InterruptDriver::new(Uart16550::new());
+        drv.uart().feed(&[b'z'; RING_SIZE + 5]);
+        run(&mut drv, (RING_SIZE as u64 + 5) * CYCLES_PER_BYTE + 1);
+        assert_eq_with_hint!(
+            drv.rx_dropped(),
+            5,
+            "nobody called read: the ring overflows"
+        );
+        assert_eq_with_hint!(drv.uart().lost_rx(), 0, "but the FIFO never does");
+        let mut buf = [0u8; RING_SIZE + 5];
+        assert_eq_with_hint!(drv.read(&mut buf), RING_SIZE);
+    }
+}
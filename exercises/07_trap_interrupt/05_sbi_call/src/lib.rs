@@ -0,0 +1,424 @@
+//! # RISC-V SBI Calling Convention and Wrapper
+//!
+//! A supervisor-mode kernel asks the machine-mode firmware (OpenSBI, RustSBI) for services —
+//! printing a character, programming the timer, starting another hart — through the Supervisor
+//! Binary Interface. Like a syscall it is an `ecall`, only one privilege level further down.
+//! Describe the calling convention, encode calls into registers, decode the result, and write the
+//! real `ecall` for riscv64.
+//!
+//! ## Background
+//!
+//! | Register | Role                                                        |
+//! |----------|-------------------------------------------------------------|
+//! | a7       | extension id (EID)                                          |
+//! | a6       | function id (FID) within the extension                      |
+//! | a0..a5   | arguments                                                   |
+//! | a0, a1   | returned `struct sbiret { long error; long value; }`        |
+//!
+//! Legacy extensions (EID 0x00..=0x0F, e.g. `console_putchar` = 0x01) predate this scheme:
+//! they ignore a6 and return a single value in a0.
+//!
+//! Newer extension ids are ASCII names: TIME = `0x54494D45` ("TIME"), HSM = `0x48534D` ("HSM").
+//!
+//! ## Task
+//!
+//! 1. Implement `sbi_abi()` — describe the calling convention
+//! 2. Implement `SbiCall::encode` and `SbiCall::decode` — what goes into a7/a6/a0..a5, what comes back
+//! 3. Implement `SbiError::from_code`
+//! 4. (riscv64 only) Implement `sbi_call` with inline assembly
+//!
+//! `sbi_call` only works in S-mode (a kernel under QEMU); in a riscv64 Linux user program the
+//! `ecall` goes to Linux instead. The tests therefore check everything except the `ecall` itself
+//! and run on any platform.
+
+#![cfg_attr(not(test), no_std)]
+
+/// Describes the SBI calling convention.
+pub struct SbiABI {
+    /// Instruction that traps into the firmware
+    pub instruction: &'static str,
+    /// Register holding the extension id
+    pub eid_reg: &'static str,
+    /// Register holding the function id
+    pub fid_reg: &'static str,
+    /// Argument registers (in order)
+    pub arg_regs: &'static [&'static str],
+    /// Register holding `sbiret.error`
+    pub error_reg: &'static str,
+    /// Register holding `sbiret.value`
+    pub value_reg: &'static str,
+    /// Register holding the return value of a legacy call
+    pub legacy_ret_reg: &'static str,
+}
+
+/// Return the SBI calling convention description
+pub fn sbi_abi() -> SbiABI {
+    // TODO: Fill in the SBI calling convention (see the table above)
+    todo!()
+}
+
+/// Legacy `sbi_console_putchar`.
+pub const EID_CONSOLE_PUTCHAR: usize = 0x01;
+/// Legacy `sbi_console_getchar`.
+pub const EID_CONSOLE_GETCHAR: usize = 0x02;
+/// Base extension.
+pub const EID_BASE: usize = 0x10;
+/// Timer extension ("TIME").
+pub const EID_TIME: usize = 0x5449_4D45;
+/// Hart state management extension ("HSM").
+pub const EID_HSM: usize = 0x48_534D;
+
+/// Highest legacy extension id.
+pub const LEGACY_EID_MAX: usize = 0x0F;
+
+pub const FID_BASE_GET_SPEC_VERSION: usize = 0;
+pub const FID_BASE_PROBE_EXTENSION: usize = 3;
+pub const FID_TIME_SET_TIMER: usize = 0;
+pub const FID_HSM_HART_START: usize = 0;
+pub const FID_HSM_HART_STOP: usize = 1;
+pub const FID_HSM_HART_GET_STATUS: usize = 2;
+
+/// An SBI call with its arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SbiCall {
+    /// Legacy: print one byte on the debug console.
+    ConsolePutchar(u8),
+    /// Legacy: read one byte from the debug console (`-1` if none).
+    ConsoleGetchar,
+    /// BASE: spec version, major in bits 24..31, minor in bits 0..24.
+    GetSpecVersion,
+    /// BASE: whether an extension is available (0 = no).
+    ProbeExtension(usize),
+    /// TIME: raise the next supervisor timer interrupt when `time >= stime_value`.
+    SetTimer(u64),
+    /// HSM: start `hartid` at `start_addr` in S-mode with `a1 = opaque`.
+    HartStart {
+        hartid: usize,
+        start_addr: usize,
+        opaque: usize,
+    },
+    /// HSM: stop the calling hart (does not return on success).
+    HartStop,
+    /// HSM: current `HartState` of `hartid`.
+    HartGetStatus(usize),
+}
+
+/// Register values for one `ecall`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SbiRegs {
+    /// a7
+    pub eid: usize,
+    /// a6 (0 for legacy calls)
+    pub fid: usize,
+    /// a0..a5 (unused arguments are 0)
+    pub args: [usize; 6],
+}
+
+/// Standard SBI error codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SbiError {
+    Failed,
+    NotSupported,
+    InvalidParam,
+    Denied,
+    InvalidAddress,
+    AlreadyAvailable,
+    AlreadyStarted,
+    AlreadyStopped,
+    /// Any other negative code.
+    Unknown(isize),
+}
+
+impl SbiError {
+    /// Map a non-zero `sbiret.error` to an error:
+    /// -1 Failed, -2 NotSupported, -3 InvalidParam, -4 Denied, -5 InvalidAddress,
+    /// -6 AlreadyAvailable, -7 AlreadyStarted, -8 AlreadyStopped, anything else Unknown.
+    pub fn from_code(code: isize) -> Self {
+        // TODO
+        todo!()
+    }
+}
+
+impl SbiCall {
+    /// Registers for this call: `eid`/`fid` from the constants above, arguments in order from a0.
+    /// Legacy calls use fid 0.
+    pub fn encode(&self) -> SbiRegs {
+        // TODO
+        todo!()
+    }
+
+    /// Interpret the returned a0/a1.
+    ///
+    /// - Legacy call (eid <= `LEGACY_EID_MAX`): always `Ok(a0)`; there is no error register.
+    /// - Otherwise a0 is `sbiret.error` (as `isize`): 0 => `Ok(a1)`, else `Err(SbiError::from_code)`.
+    pub fn decode(&self, a0: usize, a1: usize) -> Result<usize, SbiError> {
+        // TODO
+        todo!()
+    }
+}
+
+/// HSM hart states (`HartGetStatus` result).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HartState {
+    Started,
+    Stopped,
+    StartPending,
+    StopPending,
+    Suspended,
+    SuspendPending,
+    ResumePending,
+}
+
+impl HartState {
+    pub fn from_raw(raw: usize) -> Option<Self> {
+        Some(match raw {
+            0 => Self::Started,
+            1 => Self::Stopped,
+            2 => Self::StartPending,
+            3 => Self::StopPending,
+            4 => Self::Suspended,
+            5 => Self::SuspendPending,
+            6 => Self::ResumePending,
+            _ => return None,
+        })
+    }
+}
+
+// ============================================================
+// Real SBI call (riscv64 S-mode only)
+// ============================================================
+
+/// Issue an `ecall` to the SBI firmware and return `(a0, a1)`.
+///
+/// # Safety
+/// Only meaningful in S-mode; the call itself may have arbitrary effects (e.g. stopping the hart).
+#[cfg(target_arch = "riscv64")]
+pub unsafe fn sbi_call(regs: SbiRegs) -> (usize, usize) {
+    // TODO: Implement with core::arch::asm!
+    // Hints:
+    //   - "ecall" instruction
+    //   - in("a7") regs.eid, in("a6") regs.fid
+    //   - inlateout("a0") regs.args[0] => a0, inlateout("a1") regs.args[1] => a1
+    //   - in("a2") .. in("a5") for the remaining arguments
+    todo!()
+}
+
+// Other architectures: provide a stub so the code compiles
+/// Always panics: there is no SBI outside riscv64.
+///
+/// # Safety
+/// Same contract as the riscv64 version.
+#[cfg(not(target_arch = "riscv64"))]
+pub unsafe fn sbi_call(_regs: SbiRegs) -> (usize, usize) {
+    panic!("sbi_call is only available on riscv64 in S-mode")
+}
+
+/// Encode, `ecall`, decode.
+pub fn call(c: SbiCall) -> Result<usize, SbiError> {
+    let (a0, a1) = unsafe { sbi_call(c.encode()) };
+    c.decode(a0, a1)
+}
+
+pub fn console_putchar(ch: u8) {
+    let _ = call(SbiCall::ConsolePutchar(ch));
+}
+
+/// `None` if no byte is waiting.
+pub fn console_getchar() -> Option<u8> {
+    match call(SbiCall::ConsoleGetchar) {
+        Ok(c) if c as isize >= 0 => Some(c as u8),
+        _ => None,
+    }
+}
+
+pub fn set_timer(stime_value: u64) -> Result<(), SbiError> {
+    call(SbiCall::SetTimer(stime_value)).map(|_| ())
+}
+
+pub fn hart_start(hartid: usize, start_addr: usize, opaque: usize) -> Result<(), SbiError> {
+    call(SbiCall::HartStart {
+        hartid,
+        start_addr,
+        opaque,
+    })
+    .map(|_| ())
+}
+
+pub fn hart_get_status(hartid: usize) -> Result<HartState, SbiError> {
+    let raw = call(SbiCall::HartGetStatus(hartid))?;
+    HartState::from_raw(raw).ok_or(SbiError::Failed)
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+// ============================================================
+// Tests
+// ============================================================
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_abi_call_registers",
+            "the extension id goes in a7, the function id in a6, arguments in a0..a5",
+        ),
+        (
+            "test_abi_return_registers",
+            "sbiret.error comes back in a0 and sbiret.value in a1; a legacy call returns in a0",
+        ),
+        (
+            "test_encode_legacy_console",
+            "legacy calls use their own eid and fid 0",
+        ),
+        (
+            "test_error_codes",
+            "-1 Failed, -2 NotSupported, -3 InvalidParam, -4 Denied, -5 InvalidAddress, -6..-8 Already*, anything else Unknown",
+        ),
+        (
+            "test_decode_sbiret",
+            "read a0 as isize: 0 means Ok(a1), anything else Err(SbiError::from_code(a0))",
+        ),
+        (
+            "test_decode_legacy_has_no_error_register",
+            "for eid <= LEGACY_EID_MAX, decode always returns Ok(a0)",
+        ),
+    ];
+
+    #[test]
+    fn test_abi_call_registers() {
+        let abi = sbi_abi();
+        assert_eq_with_hint!(abi.instruction, "ecall");
+        assert_eq_with_hint!(abi.eid_reg, "a7");
+        assert_eq_with_hint!(abi.fid_reg, "a6");
+        assert_eq_with_hint!(
+            abi.arg_regs,
+            &["a0", "a1", "a2", "a3", "a4", "a5"],
+            "SBI argument register order is incorrect"
+        );
+    }
+
+    #[test]
+    fn test_abi_return_registers() {
+        let abi = sbi_abi();
+        assert_eq_with_hint!(abi.error_reg, "a0");
+        assert_eq_with_hint!(abi.value_reg, "a1");
+        assert_eq_with_hint!(abi.legacy_ret_reg, "a0");
+    }
+
+    #[test]
+    fn test_encode_legacy_console() {
+        assert_eq_with_hint!(
+            SbiCall::ConsolePutchar(b'A').encode(),
+            SbiRegs {
+                eid: 0x01,
+                fid: 0,
+                args: [b'A' as usize, 0, 0, 0, 0, 0]
+            }
+        );
+        assert_eq_with_hint!(
+            SbiCall::ConsoleGetchar.encode(),
+            SbiRegs {
+                eid: 0x02,
+                fid: 0,
+                args: [0; 6]
+            }
+        );
+    }
+
+    #[test]
+    fn test_encode_base() {
+        let r = SbiCall::GetSpecVersion.encode();
+        assert_eq!((r.eid, r.fid), (0x10, 0));
+        let r = SbiCall::ProbeExtension(EID_HSM).encode();
+        assert_eq!((r.eid, r.fid, r.args[0]), (0x10, 3, EID_HSM));
+    }
+
+    #[test]
+    fn test_encode_set_timer() {
+        let r = SbiCall::SetTimer(0x1234_5678_9abc).encode();
+        assert_eq!(
+            r.eid,
+            usize::from_be_bytes([0, 0, 0, 0, b'T', b'I', b'M', b'E'])
+        );
+        assert_eq!(r.fid, 0);
+        assert_eq!(
+            r.args,
+            [0x1234_5678_9abc, 0, 0, 0, 0, 0],
+            "on rv64 the whole stime_value fits in a0"
+        );
+    }
+
+    #[test]
+    fn test_encode_hsm() {
+        let r = SbiCall::HartStart {
+            hartid: 2,
+            start_addr: 0x8020_0000,
+            opaque: 0xdead,
+        }
+        .encode();
+        assert_eq!(
+            r.eid,
+            usize::from_be_bytes([0, 0, 0, 0, 0, b'H', b'S', b'M'])
+        );
+        assert_eq!(r.fid, 0);
+        assert_eq!(r.args, [2, 0x8020_0000, 0xdead, 0, 0, 0]);
+        assert_eq!(
+            SbiCall::HartStop.encode(),
+            SbiRegs {
+                eid: EID_HSM,
+                fid: 1,
+                args: [0; 6]
+            }
+        );
+        let r = SbiCall::HartGetStatus(3).encode();
+        assert_eq!((r.eid, r.fid, r.args[0]), (EID_HSM, 2, 3));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq_with_hint!(SbiError::from_code(-1), SbiError::Failed);
+        assert_eq_with_hint!(SbiError::from_code(-2), SbiError::NotSupported);
+        assert_eq_with_hint!(SbiError::from_code(-3), SbiError::InvalidParam);
+        assert_eq_with_hint!(SbiError::from_code(-4), SbiError::Denied);
+        assert_eq_with_hint!(SbiError::from_code(-5), SbiError::InvalidAddress);
+        assert_eq_with_hint!(SbiError::from_code(-6), SbiError::AlreadyAvailable);
+        assert_eq_with_hint!(SbiError::from_code(-7), SbiError::AlreadyStarted);
+        assert_eq_with_hint!(SbiError::from_code(-8), SbiError::AlreadyStopped);
+        assert_eq_with_hint!(SbiError::from_code(-42), SbiError::Unknown(-42));
+    }
+
+    #[test]
+    fn test_decode_sbiret() {
+        let c = SbiCall::HartGetStatus(1);
+        assert_eq_with_hint!(c.decode(0, 1), Ok(1));
+        assert_eq_with_hint!(c.decode(-3isize as usize, 99), Err(SbiError::InvalidParam));
+        let c = SbiCall::HartStart {
+            hartid: 1,
+            start_addr: 0,
+            opaque: 0,
+        };
+        assert_eq_with_hint!(c.decode(-7isize as usize, 0), Err(SbiError::AlreadyStarted));
+    }
+
+    #[test]
+    fn test_decode_legacy_has_no_error_register() {
+        // console_getchar returns the byte (or -1) in a0; a1 is garbage.
+        let c = SbiCall::ConsoleGetchar;
+        assert_eq_with_hint!(c.decode(b'x' as usize, 12345), Ok(b'x' as usize));
+        assert_eq_with_hint!(c.decode(usize::MAX, 0), Ok(usize::MAX));
+    }
+
+    #[test]
+    fn test_hart_state_from_raw() {
+        assert_eq!(HartState::from_raw(0), Some(HartState::Started));
+        assert_eq!(HartState::from_raw(1), Some(HartState::Stopped));
+        assert_eq!(HartState::from_raw(7), None);
+    }
+}
@@ -0,0 +1,298 @@
+//! # RISC-V SBI Calling Convention and Wrapper
+//!
+//! A supervisor-mode kernel asks the machine-mode firmware (OpenSBI, RustSBI) for services —
+//! printing a character, programming the timer, starting another hart — through the Supervisor
+//! Binary Interface. Like a syscall it is an `ecall`, only one privilege level further down.
+//! Describe the calling convention, encode calls into registers, decode the result, and write the
+//! real `ecall` for riscv64.
+//!
+//! ## Background
+//!
+//! | Register | Role                                                        |
+//! |----------|-------------------------------------------------------------|
+//! | a7       | extension id (EID)                                          |
+//! | a6       | function id (FID) within the extension                      |
+//! | a0..a5   | arguments                                                   |
+//! | a0, a1   | returned `struct sbiret { long error; long value; }`        |
+//!
+//! Legacy extensions (EID 0x00..=0x0F, e.g. `console_putchar` = 0x01) predate this scheme:
+//! they ignore a6 and return a single value in a0.
+//!
+//! Newer extension ids are ASCII names: TIME = `0x54494D45` ("TIME"), HSM = `0x48534D` ("HSM").
+//!
+//! ## Task
+//!
+//! 1. Implement `sbi_abi()` — describe the calling convention
+//! 2. Implement `SbiCall::encode` and `SbiCall::decode` — what goes into a7/a6/a0..a5, what comes back
+//! 3. Implement `SbiError::from_code`
+//! 4. (riscv64 only) Implement `sbi_call` with inline assembly
+//!
+//! `sbi_call` only works in S-mode (a kernel under QEMU); in a riscv64 Linux user program the
+//! `ecall` goes to Linux instead. The tests therefore check everything except the `ecall` itself
+//! and run on any platform.
+
+/// Describes the SBI calling convention.
+pub struct SbiABI {
+    /// Instruction that traps into the firmware
+    pub instruction: &'static str,
+    /// Register holding the extension id
+    pub eid_reg: &'static str,
+    /// Register holding the function id
+    pub fid_reg: &'static str,
+    /// Argument registers (in order)
+    pub arg_regs: &'static [&'static str],
+    /// Register holding `sbiret.error`
+    pub error_reg: &'static str,
+    /// Register holding `sbiret.value`
+    pub value_reg: &'static str,
+    /// Register holding the return value of a legacy call
+    pub legacy_ret_reg: &'static str,
+}
+
+/// Return the SBI calling convention description
+pub fn sbi_abi() -> SbiABI {
+    SbiABI {
+        instruction: "ecall",
+        eid_reg: "a7",
+        fid_reg: "a6",
+        arg_regs: &["a0", "a1", "a2", "a3", "a4", "a5"],
+        error_reg: "a0",
+        value_reg: "a1",
+        legacy_ret_reg: "a0",
+    }
+}
+
+/// Legacy `sbi_console_putchar`.
+pub const EID_CONSOLE_PUTCHAR: usize = 0x01;
+/// Legacy `sbi_console_getchar`.
+pub const EID_CONSOLE_GETCHAR: usize = 0x02;
+/// Base extension.
+pub const EID_BASE: usize = 0x10;
+/// Timer extension ("TIME").
+pub const EID_TIME: usize = 0x5449_4D45;
+/// Hart state management extension ("HSM").
+pub const EID_HSM: usize = 0x48_534D;
+
+/// Highest legacy extension id.
+pub const LEGACY_EID_MAX: usize = 0x0F;
+
+pub const FID_BASE_GET_SPEC_VERSION: usize = 0;
+pub const FID_BASE_PROBE_EXTENSION: usize = 3;
+pub const FID_TIME_SET_TIMER: usize = 0;
+pub const FID_HSM_HART_START: usize = 0;
+pub const FID_HSM_HART_STOP: usize = 1;
+pub const FID_HSM_HART_GET_STATUS: usize = 2;
+
+/// An SBI call with its arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SbiCall {
+    /// Legacy: print one byte on the debug console.
+    ConsolePutchar(u8),
+    /// Legacy: read one byte from the debug console (`-1` if none).
+    ConsoleGetchar,
+    /// BASE: spec version, major in bits 24..31, minor in bits 0..24.
+    GetSpecVersion,
+    /// BASE: whether an extension is available (0 = no).
+    ProbeExtension(usize),
+    /// TIME: raise the next supervisor timer interrupt when `time >= stime_value`.
+    SetTimer(u64),
+    /// HSM: start `hartid` at `start_addr` in S-mode with `a1 = opaque`.
+    HartStart {
+        hartid: usize,
+        start_addr: usize,
+        opaque: usize,
+    },
+    /// HSM: stop the calling hart (does not return on success).
+    HartStop,
+    /// HSM: current `HartState` of `hartid`.
+    HartGetStatus(usize),
+}
+
+/// Register values for one `ecall`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SbiRegs {
+    /// a7
+    pub eid: usize,
+    /// a6 (0 for legacy calls)
+    pub fid: usize,
+    /// a0..a5 (unused arguments are 0)
+    pub args: [usize; 6],
+}
+
+/// Standard SBI error codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SbiError {
+    Failed,
+    NotSupported,
+    InvalidParam,
+    Denied,
+    InvalidAddress,
+    AlreadyAvailable,
+    AlreadyStarted,
+    AlreadyStopped,
+    /// Any other negative code.
+    Unknown(isize),
+}
+
+impl SbiError {
+    /// Map a non-zero `sbiret.error` to an error:
+    /// -1 Failed, -2 NotSupported, -3 InvalidParam, -4 Denied, -5 InvalidAddress,
+    /// -6 AlreadyAvailable, -7 AlreadyStarted, -8 AlreadyStopped, anything else Unknown.
+    pub fn from_code(code: isize) -> Self {
+        match code {
+            -1 => Self::Failed,
+            -2 => Self::NotSupported,
+            -3 => Self::InvalidParam,
+            -4 => Self::Denied,
+            -5 => Self::InvalidAddress,
+            -6 => Self::AlreadyAvailable,
+            -7 => Self::AlreadyStarted,
+            -8 => Self::AlreadyStopped,
+            c => Self::Unknown(c),
+        }
+    }
+}
+
+impl SbiCall {
+    /// Registers for this call: `eid`/`fid` from the constants above, arguments in order from a0.
+    /// Legacy calls use fid 0.
+    pub fn encode(&self) -> SbiRegs {
+        let (eid, fid, a) = match *self {
+            Self::ConsolePutchar(c) => (EID_CONSOLE_PUTCHAR, 0, [c as usize, 0, 0]),
+            Self::ConsoleGetchar => (EID_CONSOLE_GETCHAR, 0, [0; 3]),
+            Self::GetSpecVersion => (EID_BASE, FID_BASE_GET_SPEC_VERSION, [0; 3]),
+            Self::ProbeExtension(e) => (EID_BASE, FID_BASE_PROBE_EXTENSION, [e, 0, 0]),
+            Self::SetTimer(t) => (EID_TIME, FID_TIME_SET_TIMER, [t as usize, 0, 0]),
+            Self::HartStart {
+                hartid,
+                start_addr,
+                opaque,
+            } => (EID_HSM, FID_HSM_HART_START, [hartid, start_addr, opaque]),
+            Self::HartStop => (EID_HSM, FID_HSM_HART_STOP, [0; 3]),
+            Self::HartGetStatus(h) => (EID_HSM, FID_HSM_HART_GET_STATUS, [h, 0, 0]),
+        };
+        SbiRegs {
+            eid,
+            fid,
+            args: [a[0], a[1], a[2], 0, 0, 0],
+        }
+    }
+
+    /// Interpret the returned a0/a1.
+    ///
+    /// - Legacy call (eid <= `LEGACY_EID_MAX`): always `Ok(a0)`; there is no error register.
+    /// - Otherwise a0 is `sbiret.error` (as `isize`): 0 => `Ok(a1)`, else `Err(SbiError::from_code)`.
+    pub fn decode(&self, a0: usize, a1: usize) -> Result<usize, SbiError> {
+        if self.encode().eid <= LEGACY_EID_MAX {
+            return Ok(a0);
+        }
+        match a0 as isize {
+            0 => Ok(a1),
+            e => Err(SbiError::from_code(e)),
+        }
+    }
+}
+
+/// HSM hart states (`HartGetStatus` result).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HartState {
+    Started,
+    Stopped,
+    StartPending,
+    StopPending,
+    Suspended,
+    SuspendPending,
+    ResumePending,
+}
+
+impl HartState {
+    pub fn from_raw(raw: usize) -> Option<Self> {
+        Some(match raw {
+            0 => Self::Started,
+            1 => Self::Stopped,
+            2 => Self::StartPending,
+            3 => Self::StopPending,
+            4 => Self::Suspended,
+            5 => Self::SuspendPending,
+            6 => Self::ResumePending,
+            _ => return None,
+        })
+    }
+}
+
+// ============================================================
+// Real SBI call (riscv64 S-mode only)
+// ============================================================
+
+/// Issue an `ecall` to the SBI firmware and return `(a0, a1)`.
+///
+/// # Safety
+/// Only meaningful in S-mode; the call itself may have arbitrary effects (e.g. stopping the hart).
+#[cfg(target_arch = "riscv64")]
+pub unsafe fn sbi_call(regs: SbiRegs) -> (usize, usize) {
+    // TODO: Implement with core::arch::asm!
+    // Hints:
+    //   - "ecall" instruction
+    //   - in("a7") regs.eid, in("a6") regs.fid
+    //   - inlateout("a0") regs.args[0] => a0, inlateout("a1") regs.args[1] => a1
+    //   - in("a2") .. in("a5") for the remaining arguments
+    let (a0, a1);
+    core::arch::asm!(
+        "ecall",
+        in("a7") regs.eid,
+        in("a6") regs.fid,
+        inlateout("a0") regs.args[0] => a0,
+        inlateout("a1") regs.args[1] => a1,
+        in("a2") regs.args[2],
+        in("a3") regs.args[3],
+        in("a4") regs.args[4],
+        in("a5") regs.args[5],
+    );
+    (a0, a1)
+}
+
+// Other architectures: provide a stub so the code compiles
+/// Always panics: there is no SBI outside riscv64.
+///
+/// # Safety
+/// Same contract as the riscv64 version.
+#[cfg(not(target_arch = "riscv64"))]
+pub unsafe fn sbi_call(_regs: SbiRegs) -> (usize, usize) {
+    panic!("sbi_call is only available on riscv64 in S-mode")
+}
+
+/// Encode, `ecall`, decode.
+pub fn call(c: SbiCall) -> Result<usize, SbiError> {
+    let (a0, a1) = unsafe { sbi_call(c.encode()) };
+    c.decode(a0, a1)
+}
+
+pub fn console_putchar(ch: u8) {
+    let _ = call(SbiCall::ConsolePutchar(ch));
+}
+
+/// `None` if no byte is waiting.
+pub fn console_getchar() -> Option<u8> {
+    match call(SbiCall::ConsoleGetchar) {
+        Ok(c) if c as isize >= 0 => Some(c as u8),
+        _ => None,
+    }
+}
+
+pub fn set_timer(stime_value: u64) -> Result<(), SbiError> {
+    call(SbiCall::SetTimer(stime_value)).map(|_| ())
+}
+
+pub fn hart_start(hartid: usize, start_addr: usize, opaque: usize) -> Result<(), SbiError> {
+    call(SbiCall::HartStart {
+        hartid,
+        start_addr,
+        opaque,
+    })
+    .map(|_| ())
+}
+
+pub fn hart_get_status(hartid: usize) -> Result<HartState, SbiError> {
+    let raw = call(SbiCall::HartGetStatus(hartid))?;
+    HartState::from_raw(raw).ok_or(SbiError::Failed)
+}
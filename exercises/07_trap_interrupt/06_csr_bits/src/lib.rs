@@ -0,0 +1,373 @@
+//! # `sstatus` / `sie` / `sip` Bitfields
+//!
+//! In this exercise, you write typed accessors for the supervisor status and interrupt CSRs, and
+//! use them to express what trap entry, `sret` and "return to user mode for the first time" do to
+//! `sstatus` — instead of or-ing magic constants together.
+//!
+//! ## Concepts
+//! - Read-modify-write of a single bit: set with `|= mask`, clear with `&= !mask`; every other
+//!   bit (FS, XS, UXL, ...) must survive untouched
+//! - `sstatus.SIE` enables interrupts *while in S-mode*; in U-mode supervisor interrupts are
+//!   always taken. `SPIE` / `SPP` save `SIE` and the previous privilege across a trap
+//! - `SUM` lets S-mode access user pages, `MXR` makes executable pages readable
+//! - `sie` (enabled) and `sip` (pending) share one layout: bit `code` for the interrupt with
+//!   `scause` code `code`
+//!
+//! ## Layouts (RV64)
+//! ```text
+//! sstatus:  ... │ 19  │ 18  │ ... │  8  │ ... │  5   │ ... │  1  │  0
+//!               │ MXR │ SUM │     │ SPP │     │ SPIE │     │ SIE │
+//!
+//! sie/sip:  ... │   9    │ ... │   5    │ ... │   1    │  0
+//!               │ SEIE/P │     │ STIE/P │     │ SSIE/P │
+//! ```
+//!
+//! ## What the hardware does
+//! ```text
+//! trap into S-mode:  SPIE = SIE; SIE = 0; SPP = previous mode
+//! sret:              SIE = SPIE; SPIE = 1; SPP = U; return to the mode SPP held
+//! ```
+
+pub const SSTATUS_SIE: u64 = 1 << 1;
+pub const SSTATUS_SPIE: u64 = 1 << 5;
+pub const SSTATUS_SPP: u64 = 1 << 8;
+pub const SSTATUS_SUM: u64 = 1 << 18;
+pub const SSTATUS_MXR: u64 = 1 << 19;
+
+/// Privilege mode a trap came from / `sret` returns to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivilegeMode {
+    User,
+    Supervisor,
+}
+
+/// Supervisor-level interrupts, by `scause` code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Code 1 (`SSIE` / `SSIP`).
+    Software,
+    /// Code 5 (`STIE` / `STIP`).
+    Timer,
+    /// Code 9 (`SEIE` / `SEIP`).
+    External,
+}
+
+impl Interrupt {
+    /// In decreasing priority, as the hardware picks among several pending interrupts.
+    pub const BY_PRIORITY: [Interrupt; 3] =
+        [Interrupt::External, Interrupt::Software, Interrupt::Timer];
+
+    /// The `scause` code of this interrupt.
+    pub fn code(self) -> u64 {
+        match self {
+            Interrupt::Software => 1,
+            Interrupt::Timer => 5,
+            Interrupt::External => 9,
+        }
+    }
+
+    /// Its bit in `sie` / `sip`.
+    pub fn mask(self) -> u64 {
+        // TODO: one bit, at position `code`
+        todo!()
+    }
+}
+
+/// A raw `sstatus` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sstatus(pub u64);
+
+impl Sstatus {
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Whether any bit of `mask` is set.
+    pub fn get(self, mask: u64) -> bool {
+        // TODO
+        todo!()
+    }
+
+    /// Set (`on`) or clear the bits of `mask`, leaving all others unchanged.
+    pub fn set(&mut self, mask: u64, on: bool) {
+        // TODO
+        todo!()
+    }
+
+    pub fn sie(self) -> bool {
+        self.get(SSTATUS_SIE)
+    }
+
+    pub fn set_sie(&mut self, on: bool) {
+        self.set(SSTATUS_SIE, on)
+    }
+
+    pub fn spie(self) -> bool {
+        self.get(SSTATUS_SPIE)
+    }
+
+    pub fn set_spie(&mut self, on: bool) {
+        self.set(SSTATUS_SPIE, on)
+    }
+
+    pub fn sum(self) -> bool {
+        self.get(SSTATUS_SUM)
+    }
+
+    pub fn set_sum(&mut self, on: bool) {
+        self.set(SSTATUS_SUM, on)
+    }
+
+    pub fn mxr(self) -> bool {
+        self.get(SSTATUS_MXR)
+    }
+
+    pub fn set_mxr(&mut self, on: bool) {
+        self.set(SSTATUS_MXR, on)
+    }
+
+    /// `SPP`: 1 = the trap came from S-mode, 0 = from U-mode.
+    pub fn spp(self) -> PrivilegeMode {
+        // TODO
+        todo!()
+    }
+
+    pub fn set_spp(&mut self, mode: PrivilegeMode) {
+        // TODO
+        todo!()
+    }
+
+    /// Prepare `sstatus` for the first `sret` into a user program:
+    /// - `SPP = User`, so `sret` drops to U-mode
+    /// - `SPIE = 1`, so interrupts are enabled once the program runs
+    /// - `SIE = 0`: no interrupt may arrive between writing `sstatus` and the `sret`
+    ///
+    /// Everything else (`SUM`, `MXR`, FS, ...) is kept.
+    pub fn prepare_sret_to_user(&mut self) {
+        // TODO
+        todo!()
+    }
+
+    /// What the hardware does to `sstatus` when a trap from `from` enters S-mode.
+    pub fn trap_entry(&mut self, from: PrivilegeMode) {
+        // TODO: SPIE = SIE, SIE = 0, SPP = from
+        todo!()
+    }
+
+    /// What `sret` does to `sstatus`; returns the mode it returns to.
+    pub fn sret(&mut self) -> PrivilegeMode {
+        // TODO: remember SPP, then SIE = SPIE, SPIE = 1, SPP = User
+        todo!()
+    }
+}
+
+/// A raw `sie` or `sip` value: one bit per [`Interrupt`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IrqBits(pub u64);
+
+impl IrqBits {
+    pub fn contains(self, irq: Interrupt) -> bool {
+        self.0 & irq.mask() != 0
+    }
+
+    pub fn insert(&mut self, irq: Interrupt) {
+        self.0 |= irq.mask();
+    }
+
+    pub fn remove(&mut self, irq: Interrupt) {
+        self.0 &= !irq.mask();
+    }
+}
+
+impl FromIterator<Interrupt> for IrqBits {
+    fn from_iter<I: IntoIterator<Item = Interrupt>>(iter: I) -> Self {
+        let mut bits = IrqBits::default();
+        for irq in iter {
+            bits.insert(irq);
+        }
+        bits
+    }
+}
+
+/// The interrupt the hart would take right now, if any.
+///
+/// An interrupt is taken if it is pending (`sip`) **and** enabled (`sie`) **and** supervisor
+/// interrupts are globally on: always in U-mode, only with `sstatus.SIE` in S-mode. Among several
+/// candidates, the first in `Interrupt::BY_PRIORITY` wins.
+pub fn pending_interrupt(
+    sstatus: Sstatus,
+    sie: IrqBits,
+    sip: IrqBits,
+    mode: PrivilegeMode,
+) -> Option<Interrupt> {
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+    use Interrupt::*;
+    use PrivilegeMode::*;
+
+    const HINTS: Hints = &[
+        (
+            "test_bit_positions",
+            "an interrupt's bit in sie/sip is 1 << code",
+        ),
+        (
+            "test_set_and_clear_keep_other_bits",
+            "set ORs the mask in, clear ANDs with !mask: other bits stay as they were",
+        ),
+        (
+            "test_prepare_sret_to_user",
+            "SPP = User, SPIE = 1, SIE = 0, and every other bit kept",
+        ),
+        (
+            "test_trap_from_user_and_back",
+            "trap entry: SPIE = SIE, SIE = 0, SPP = from; sret: SIE = SPIE, SPIE = 1, SPP = User, return the old SPP",
+        ),
+        (
+            "test_pending_interrupt_gating",
+            "taken only if pending and enabled, and in S-mode only with sstatus.SIE; U-mode always",
+        ),
+        (
+            "test_pending_interrupt_priority",
+            "check the candidates in Interrupt::BY_PRIORITY order and return the first one",
+        ),
+    ];
+
+    /// FS = Dirty (bits 13-14) and UXL = 64 (bits 32-33): must survive every helper.
+    const OTHER_BITS: u64 = (3 << 13) | (2 << 32);
+
+    #[test]
+    fn test_bit_positions() {
+        let mut s = Sstatus::default();
+        s.set_sie(true);
+        assert_eq_with_hint!(s.bits(), 0x2);
+        let mut s = Sstatus::default();
+        s.set_spie(true);
+        assert_eq_with_hint!(s.bits(), 0x20);
+        let mut s = Sstatus::default();
+        s.set_spp(Supervisor);
+        assert_eq_with_hint!(s.bits(), 0x100);
+        let mut s = Sstatus::default();
+        s.set_sum(true);
+        s.set_mxr(true);
+        assert_eq_with_hint!(s.bits(), 0xc_0000);
+    }
+
+    #[test]
+    fn test_set_and_clear_keep_other_bits() {
+        let mut s = Sstatus(OTHER_BITS | SSTATUS_SIE | SSTATUS_SUM);
+        assert_with_hint!(s.sie() && s.sum() && !s.mxr() && !s.spie());
+        s.set_sie(false);
+        assert_eq_with_hint!(s.bits(), OTHER_BITS | SSTATUS_SUM);
+        s.set_sum(true);
+        assert_eq_with_hint!(
+            s.bits(),
+            OTHER_BITS | SSTATUS_SUM,
+            "setting a set bit is a no-op"
+        );
+        s.set_sum(false);
+        assert_eq_with_hint!(s.bits(), OTHER_BITS);
+    }
+
+    #[test]
+    fn test_spp_round_trip() {
+        let mut s = Sstatus(OTHER_BITS);
+        assert_eq!(s.spp(), User);
+        s.set_spp(Supervisor);
+        assert_eq!(s.spp(), Supervisor);
+        s.set_spp(User);
+        assert_eq!(s, Sstatus(OTHER_BITS));
+    }
+
+    #[test]
+    fn test_prepare_sret_to_user() {
+        // A kernel running with interrupts on, having come from S-mode, with SUM set.
+        let mut s = Sstatus(OTHER_BITS | SSTATUS_SIE | SSTATUS_SPP | SSTATUS_SUM);
+        s.prepare_sret_to_user();
+        assert_eq_with_hint!(s.bits(), OTHER_BITS | SSTATUS_SPIE | SSTATUS_SUM);
+        assert_eq_with_hint!(s.sret(), User);
+        assert_with_hint!(s.sie(), "interrupts are on in the user program");
+    }
+
+    #[test]
+    fn test_trap_from_user_and_back() {
+        let mut s = Sstatus(SSTATUS_SIE);
+        s.trap_entry(User);
+        assert_eq_with_hint!(s.bits(), SSTATUS_SPIE, "SIE saved to SPIE, SPP = U");
+        assert_eq_with_hint!(s.sret(), User);
+        assert_eq_with_hint!(s.bits(), SSTATUS_SIE | SSTATUS_SPIE);
+    }
+
+    #[test]
+    fn test_trap_from_kernel_with_interrupts_off() {
+        let mut s = Sstatus(OTHER_BITS);
+        s.trap_entry(Supervisor);
+        assert_eq!(s.bits(), OTHER_BITS | SSTATUS_SPP);
+        assert_eq!(s.sret(), Supervisor);
+        assert!(!s.sie(), "interrupts stay off after returning");
+        assert_eq!(s.bits(), OTHER_BITS | SSTATUS_SPIE);
+    }
+
+    #[test]
+    fn test_interrupt_masks() {
+        assert_eq!(Software.mask(), 1 << 1);
+        assert_eq!(Timer.mask(), 1 << 5);
+        assert_eq!(External.mask(), 1 << 9);
+        let mut sie: IrqBits = [Software, Timer, External].into_iter().collect();
+        assert_eq!(sie, IrqBits(0x222));
+        sie.remove(Timer);
+        assert_eq!(sie, IrqBits(0x202));
+        assert!(sie.contains(External) && !sie.contains(Timer));
+    }
+
+    #[test]
+    fn test_pending_interrupt_gating() {
+        let on = Sstatus(SSTATUS_SIE);
+        let off = Sstatus(0);
+        let timer = IrqBits(Timer.mask());
+        assert_eq_with_hint!(pending_interrupt(on, timer, timer, Supervisor), Some(Timer));
+        assert_eq_with_hint!(
+            pending_interrupt(off, timer, timer, Supervisor),
+            None,
+            "SIE off"
+        );
+        assert_eq_with_hint!(
+            pending_interrupt(off, timer, timer, User),
+            Some(Timer),
+            "U-mode ignores SIE"
+        );
+        assert_eq_with_hint!(
+            pending_interrupt(on, IrqBits(0), timer, User),
+            None,
+            "not enabled"
+        );
+        assert_eq_with_hint!(
+            pending_interrupt(on, timer, IrqBits(0), User),
+            None,
+            "not pending"
+        );
+    }
+
+    #[test]
+    fn test_pending_interrupt_priority() {
+        let on = Sstatus(SSTATUS_SIE);
+        let all: IrqBits = [Software, Timer, External].into_iter().collect();
+        assert_eq_with_hint!(pending_interrupt(on, all, all, Supervisor), Some(External));
+        let sip: IrqBits = [Software, Timer].into_iter().collect();
+        assert_eq_with_hint!(pending_interrupt(on, all, sip, Supervisor), Some(Software));
+        let sie: IrqBits = [Timer, External].into_iter().collect();
+        assert_eq_with_hint!(pending_interrupt(on, sie, sip, Supervisor), Some(Timer));
+    }
+}
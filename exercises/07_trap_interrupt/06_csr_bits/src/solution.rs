@@ -0,0 +1,217 @@
+//! # `sstatus` / `sie` / `sip` Bitfields
+//!
+//! In this exercise, you write typed accessors for the supervisor status and interrupt CSRs, and
+//! use them to express what trap entry, `sret` and "return to user mode for the first time" do to
+//! `sstatus` — instead of or-ing magic constants together.
+//!
+//! ## Concepts
+//! - Read-modify-write of a single bit: set with `|= mask`, clear with `&= !mask`; every other
+//!   bit (FS, XS, UXL, ...) must survive untouched
+//! - `sstatus.SIE` enables interrupts *while in S-mode*; in U-mode supervisor interrupts are
+//!   always taken. `SPIE` / `SPP` save `SIE` and the previous privilege across a trap
+//! - `SUM` lets S-mode access user pages, `MXR` makes executable pages readable
+//! - `sie` (enabled) and `sip` (pending) share one layout: bit `code` for the interrupt with
+//!   `scause` code `code`
+//!
+//! ## Layouts (RV64)
+//! ```text
+//! sstatus:  ... │ 19  │ 18  │ ... │  8  │ ... │  5   │ ... │  1  │  0
+//!               │ MXR │ SUM │     │ SPP │     │ SPIE │     │ SIE │
+//!
+//! sie/sip:  ... │   9    │ ... │   5    │ ... │   1    │  0
+//!               │ SEIE/P │     │ STIE/P │     │ SSIE/P │
+//! ```
+//!
+//! ## What the hardware does
+//! ```text
+//! trap into S-mode:  SPIE = SIE; SIE = 0; SPP = previous mode
+//! sret:              SIE = SPIE; SPIE = 1; SPP = U; return to the mode SPP held
+//! ```
+
+pub const SSTATUS_SIE: u64 = 1 << 1;
+pub const SSTATUS_SPIE: u64 = 1 << 5;
+pub const SSTATUS_SPP: u64 = 1 << 8;
+pub const SSTATUS_SUM: u64 = 1 << 18;
+pub const SSTATUS_MXR: u64 = 1 << 19;
+
+/// Privilege mode a trap came from / `sret` returns to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivilegeMode {
+    User,
+    Supervisor,
+}
+
+/// Supervisor-level interrupts, by `scause` code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Code 1 (`SSIE` / `SSIP`).
+    Software,
+    /// Code 5 (`STIE` / `STIP`).
+    Timer,
+    /// Code 9 (`SEIE` / `SEIP`).
+    External,
+}
+
+impl Interrupt {
+    /// In decreasing priority, as the hardware picks among several pending interrupts.
+    pub const BY_PRIORITY: [Interrupt; 3] =
+        [Interrupt::External, Interrupt::Software, Interrupt::Timer];
+
+    /// The `scause` code of this interrupt.
+    pub fn code(self) -> u64 {
+        match self {
+            Interrupt::Software => 1,
+            Interrupt::Timer => 5,
+            Interrupt::External => 9,
+        }
+    }
+
+    /// Its bit in `sie` / `sip`.
+    pub fn mask(self) -> u64 {
+        1 << self.code()
+    }
+}
+
+/// A raw `sstatus` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sstatus(pub u64);
+
+impl Sstatus {
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Whether any bit of `mask` is set.
+    pub fn get(self, mask: u64) -> bool {
+        self.0 & mask != 0
+    }
+
+    /// Set (`on`) or clear the bits of `mask`, leaving all others unchanged.
+    pub fn set(&mut self, mask: u64, on: bool) {
+        if on {
+            self.0 |= mask;
+        } else {
+            self.0 &= !mask;
+        }
+    }
+
+    pub fn sie(self) -> bool {
+        self.get(SSTATUS_SIE)
+    }
+
+    pub fn set_sie(&mut self, on: bool) {
+        self.set(SSTATUS_SIE, on)
+    }
+
+    pub fn spie(self) -> bool {
+        self.get(SSTATUS_SPIE)
+    }
+
+    pub fn set_spie(&mut self, on: bool) {
+        self.set(SSTATUS_SPIE, on)
+    }
+
+    pub fn sum(self) -> bool {
+        self.get(SSTATUS_SUM)
+    }
+
+    pub fn set_sum(&mut self, on: bool) {
+        self.set(SSTATUS_SUM, on)
+    }
+
+    pub fn mxr(self) -> bool {
+        self.get(SSTATUS_MXR)
+    }
+
+    pub fn set_mxr(&mut self, on: bool) {
+        self.set(SSTATUS_MXR, on)
+    }
+
+    /// `SPP`: 1 = the trap came from S-mode, 0 = from U-mode.
+    pub fn spp(self) -> PrivilegeMode {
+        if self.get(SSTATUS_SPP) {
+            PrivilegeMode::Supervisor
+        } else {
+            PrivilegeMode::User
+        }
+    }
+
+    pub fn set_spp(&mut self, mode: PrivilegeMode) {
+        self.set(SSTATUS_SPP, mode == PrivilegeMode::Supervisor)
+    }
+
+    /// Prepare `sstatus` for the first `sret` into a user program:
+    /// - `SPP = User`, so `sret` drops to U-mode
+    /// - `SPIE = 1`, so interrupts are enabled once the program runs
+    /// - `SIE = 0`: no interrupt may arrive between writing `sstatus` and the `sret`
+    ///
+    /// Everything else (`SUM`, `MXR`, FS, ...) is kept.
+    pub fn prepare_sret_to_user(&mut self) {
+        self.set_spp(PrivilegeMode::User);
+        self.set_spie(true);
+        self.set_sie(false);
+    }
+
+    /// What the hardware does to `sstatus` when a trap from `from` enters S-mode.
+    pub fn trap_entry(&mut self, from: PrivilegeMode) {
+        self.set_spie(self.sie());
+        self.set_sie(false);
+        self.set_spp(from);
+    }
+
+    /// What `sret` does to `sstatus`; returns the mode it returns to.
+    pub fn sret(&mut self) -> PrivilegeMode {
+        let mode = self.spp();
+        self.set_sie(self.spie());
+        self.set_spie(true);
+        self.set_spp(PrivilegeMode::User);
+        mode
+    }
+}
+
+/// A raw `sie` or `sip` value: one bit per [`Interrupt`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IrqBits(pub u64);
+
+impl IrqBits {
+    pub fn contains(self, irq: Interrupt) -> bool {
+        self.0 & irq.mask() != 0
+    }
+
+    pub fn insert(&mut self, irq: Interrupt) {
+        self.0 |= irq.mask();
+    }
+
+    pub fn remove(&mut self, irq: Interrupt) {
+        self.0 &= !irq.mask();
+    }
+}
+
+impl FromIterator<Interrupt> for IrqBits {
+    fn from_iter<I: IntoIterator<Item = Interrupt>>(iter: I) -> Self {
+        let mut bits = IrqBits::default();
+        for irq in iter {
+            bits.insert(irq);
+        }
+        bits
+    }
+}
+
+/// The interrupt the hart would take right now, if any.
+///
+/// An interrupt is taken if it is pending (`sip`) **and** enabled (`sie`) **and** supervisor
+/// interrupts are globally on: always in U-mode, only with `sstatus.SIE` in S-mode. Among several
+/// candidates, the first in `Interrupt::BY_PRIORITY` wins.
+pub fn pending_interrupt(
+    sstatus: Sstatus,
+    sie: IrqBits,
+    sip: IrqBits,
+    mode: PrivilegeMode,
+) -> Option<Interrupt> {
+    if mode == PrivilegeMode::Supervisor && !sstatus.sie() {
+        return None;
+    }
+    Interrupt::BY_PRIORITY
+        .into_iter()
+        .find(|&irq| sie.contains(irq) && sip.contains(irq))
+}
@@ -0,0 +1,718 @@
+//! # Timer-Driven Preemption: MLFQ and CFS
+//!
+//! `02_timer_tick` stops at setting a reschedule flag. In this exercise, you connect that tick to a
+//! simulated process scheduler: every timer interrupt charges the running task one tick of its
+//! quantum, and a task whose quantum is used up is switched out on the way back from the
+//! interrupt, whether it wants to give up the CPU or not.
+//!
+//! ## Concepts
+//! - Quantum accounting in the tick handler: `on_tick` counts down `slice_left` and requests
+//!   preemption when it reaches 0
+//! - `need_resched` is only acted on at `schedule()`, the "return from interrupt" point
+//! - Multi-level feedback queue (`Mlfq`): a task that uses up its whole quantum drops a level,
+//!   where quanta are longer; a task that blocks before its quantum runs out keeps its level, so
+//!   CPU-bound tasks sink and interactive tasks stay on top
+//! - Simplified CFS (`Cfs`): run the task with the least virtual runtime; the quantum is the
+//!   scheduling latency split among the runnable tasks, but never below a minimum granularity
+//! - Wake-up preemption: a task that wakes up at a higher MLFQ level takes the CPU at once. The
+//!   preempted task keeps the rest of its quantum; restarting it instead would let a CPU-bound
+//!   task that is interrupted often enough never use up a quantum, and never sink
+//!
+//! ## Flow of one `Machine::step`
+//! ```text
+//! sched.schedule()              need_resched or idle -> put current back, pick_next, refill its slice
+//! timer.advance(TICK_CYCLES)    the timer interrupt; returns the sleepers due now
+//!   sched.on_tick()             charge current; slice_left -= 1; at 0 -> policy.expired, need_resched
+//!   burst done?                 sched.block_current(); timer.sleep_for(tid, sleep)
+//!   sched.wake(id)              for every woken sleeper; may request preemption too
+//! ```
+//!
+//! Both policies are simplified: every task has the same weight (nice 0), and `Mlfq` has no
+//! periodic priority boost, so tasks that sank to the bottom can starve behind interactive ones.
+
+use std::collections::{BTreeSet, VecDeque};
+use timer_tick::TimerDriver;
+
+/// Task id: tasks are numbered from 0 in spawn order.
+pub type Tid = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    /// On the policy's run queue.
+    Ready,
+    Running,
+    /// Waiting for `wake`.
+    Blocked,
+}
+
+/// Decides which task runs next and for how many ticks.
+///
+/// The scheduler calls `enqueue` whenever a task becomes runnable (spawned, woken, or switched
+/// out while still runnable) and `pick_next` to take the next one off the run queue. The running
+/// task is never on the run queue.
+pub trait Policy {
+    /// Put `tid` on the run queue.
+    fn enqueue(&mut self, tid: Tid);
+
+    /// Take the task to run next off the run queue.
+    fn pick_next(&mut self) -> Option<Tid>;
+
+    /// Quantum in ticks for `tid`, which `pick_next` just returned; must be positive.
+    fn quantum(&self, tid: Tid) -> u64;
+
+    /// The running task `tid` ran for one more tick.
+    fn charge(&mut self, _tid: Tid) {}
+
+    /// The running task `tid` used up its whole quantum; it is switched out at the next
+    /// `schedule`.
+    fn expired(&mut self, _tid: Tid) {}
+
+    /// Whether `woken`, just made runnable, should take the CPU from the running `current` right
+    /// away instead of waiting for `current`'s quantum to end.
+    fn should_preempt(&self, _current: Tid, _woken: Tid) -> bool {
+        false
+    }
+}
+
+/// Multi-level feedback queue: level 0 is the top; a `Ready` task on a higher level always runs
+/// before any task on a lower one, and tasks on the same level take turns.
+///
+/// - New tasks start at level 0.
+/// - A task that uses up its quantum drops one level (it stays on the bottom level once there).
+/// - A task that gives up the CPU before its quantum ends (blocks) keeps its level.
+pub struct Mlfq {
+    /// `quanta[l]`: quantum of a task on level `l`.
+    quanta: Vec<u64>,
+    /// `queues[l]`: ready tasks on level `l`, in FIFO order.
+    queues: Vec<VecDeque<Tid>>,
+    /// `level[tid]`; tasks never enqueued have no entry yet.
+    level: Vec<usize>,
+}
+
+impl Mlfq {
+    /// One level per entry of `quanta`, top level first. Panics if `quanta` is empty or holds a 0.
+    pub fn new(quanta: Vec<u64>) -> Self {
+        assert!(!quanta.is_empty(), "an MLFQ needs at least one level");
+        assert!(quanta.iter().all(|&q| q > 0), "quanta must be positive");
+        let queues = quanta.iter().map(|_| VecDeque::new()).collect();
+        Self {
+            quanta,
+            queues,
+            level: Vec::new(),
+        }
+    }
+
+    pub fn levels(&self) -> usize {
+        self.quanta.len()
+    }
+
+    /// Current level of `tid` (0 = top). Panics if `tid` was never enqueued.
+    pub fn level(&self, tid: Tid) -> usize {
+        self.level[tid]
+    }
+}
+
+impl Policy for Mlfq {
+    /// A task seen for the first time starts at level 0. Append it to its level's queue.
+    fn enqueue(&mut self, tid: Tid) {
+        // TODO
+        todo!()
+    }
+
+    /// Front of the highest non-empty queue.
+    fn pick_next(&mut self) -> Option<Tid> {
+        // TODO
+        todo!()
+    }
+
+    fn quantum(&self, tid: Tid) -> u64 {
+        self.quanta[self.level[tid]]
+    }
+
+    /// Drop `tid` one level, unless it is already on the bottom one.
+    fn expired(&mut self, tid: Tid) {
+        // TODO
+        todo!()
+    }
+
+    /// Preempt if `woken` sits on a higher level (smaller number) than `current`.
+    fn should_preempt(&self, current: Tid, woken: Tid) -> bool {
+        // TODO
+        todo!()
+    }
+}
+
+/// Simplified CFS: every task has a virtual runtime (`vruntime`, in ticks, all weights equal) and
+/// the runnable task with the smallest one runs next, ties by lower id.
+///
+/// A task that was not runnable for a while (new, or back from sleep) must not win every pick
+/// until it has caught up with the others, so on `enqueue` its `vruntime` is raised to at least
+/// `min_vruntime`, which follows the smallest `vruntime` of the runnable tasks (the running one
+/// included) but never goes back.
+pub struct Cfs {
+    /// Target time in ticks within which every runnable task should run once.
+    latency: u64,
+    /// Smallest quantum in ticks, however many tasks are runnable.
+    min_granularity: u64,
+    /// `vruntime[tid]`; tasks never enqueued have no entry yet.
+    vruntime: Vec<u64>,
+    /// Runnable tasks as `(vruntime, tid)`, smallest first.
+    queue: BTreeSet<(u64, Tid)>,
+    /// Never decreases.
+    min_vruntime: u64,
+}
+
+impl Cfs {
+    /// Panics if `latency` or `min_granularity` is 0.
+    pub fn new(latency: u64, min_granularity: u64) -> Self {
+        assert!(
+            latency > 0 && min_granularity > 0,
+            "latency and granularity must be positive"
+        );
+        Self {
+            latency,
+            min_granularity,
+            vruntime: Vec::new(),
+            queue: BTreeSet::new(),
+            min_vruntime: 0,
+        }
+    }
+
+    /// Virtual runtime of `tid`. Panics if `tid` was never enqueued.
+    pub fn vruntime(&self, tid: Tid) -> u64 {
+        self.vruntime[tid]
+    }
+
+    pub fn min_vruntime(&self) -> u64 {
+        self.min_vruntime
+    }
+}
+
+impl Policy for Cfs {
+    /// Raise `vruntime[tid]` to at least `min_vruntime` (a new task starts there), then insert
+    /// `(vruntime, tid)` into the queue.
+    fn enqueue(&mut self, tid: Tid) {
+        // TODO
+        todo!()
+    }
+
+    /// Pop the smallest entry and advance `min_vruntime` to its `vruntime` if that is larger.
+    fn pick_next(&mut self) -> Option<Tid> {
+        // TODO
+        todo!()
+    }
+
+    /// `latency` divided among the runnable tasks (the queue plus `tid` itself), at least
+    /// `min_granularity`.
+    fn quantum(&self, _tid: Tid) -> u64 {
+        // TODO
+        todo!()
+    }
+
+    /// Add 1 to `vruntime[tid]`, then advance `min_vruntime` to the smaller of `vruntime[tid]`
+    /// and the queue's smallest `vruntime`, if that is larger.
+    fn charge(&mut self, tid: Tid) {
+        // TODO
+        todo!()
+    }
+}
+
+/// A uniprocessor scheduler that preempts on timer ticks; `P` picks the tasks.
+pub struct Scheduler<P> {
+    policy: P,
+    /// `state[tid]`.
+    state: Vec<TaskState>,
+    current: Option<Tid>,
+    /// `slice[tid]`: ticks left in `tid`'s quantum; 0 means it gets a fresh quantum when it is
+    /// picked next.
+    slice: Vec<u64>,
+    /// Switch at the next `schedule`, even though `current` could keep running.
+    need_resched: bool,
+}
+
+impl<P: Policy> Scheduler<P> {
+    pub fn new(policy: P) -> Self {
+        Self {
+            policy,
+            state: Vec::new(),
+            current: None,
+            slice: Vec::new(),
+            need_resched: false,
+        }
+    }
+
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Add a `Ready` task; it first runs at a later `schedule`.
+    pub fn spawn(&mut self) -> Tid {
+        let tid = self.state.len();
+        self.state.push(TaskState::Ready);
+        self.slice.push(0);
+        self.policy.enqueue(tid);
+        tid
+    }
+
+    pub fn current(&self) -> Option<Tid> {
+        self.current
+    }
+
+    pub fn state(&self, tid: Tid) -> TaskState {
+        self.state[tid]
+    }
+
+    /// Ticks left in the running task's quantum, 0 when idle.
+    pub fn slice_left(&self) -> u64 {
+        self.current.map_or(0, |cur| self.slice[cur])
+    }
+
+    pub fn need_resched(&self) -> bool {
+        self.need_resched
+    }
+
+    /// Timer interrupt: charge the running task one tick and count down its quantum. Returns
+    /// `true` if this tick used the quantum up, i.e. the task is to be preempted.
+    ///
+    /// With no task running (idle), do nothing and return `false`. Otherwise:
+    /// 1. `policy.charge(current)`.
+    /// 2. `slice[current] -= 1`.
+    /// 3. At 0: `policy.expired(current)`, set `need_resched`, return `true`.
+    pub fn on_tick(&mut self) -> bool {
+        // TODO
+        todo!()
+    }
+
+    /// The running task blocks (waits for I/O, sleeps, ...) and gives up the CPU before its
+    /// quantum is over. The rest of the quantum is dropped: it gets a fresh one when it runs
+    /// again. Panics if no task is running.
+    pub fn block_current(&mut self) {
+        let cur = self.current.take().expect("no task is running");
+        self.state[cur] = TaskState::Blocked;
+        self.slice[cur] = 0;
+        self.need_resched = true;
+    }
+
+    /// Make the `Blocked` task `tid` `Ready` again and enqueue it. If a task is running and
+    /// `policy.should_preempt(current, tid)`, set `need_resched`. Panics if `tid` is not blocked.
+    pub fn wake(&mut self, tid: Tid) {
+        // TODO
+        todo!()
+    }
+
+    /// The return-from-interrupt path: switch tasks if needed and return the task that runs now.
+    ///
+    /// 1. If a task is running and `need_resched` is not set, keep running it.
+    /// 2. Clear `need_resched`. Put the running task, if any, back: `Ready` and `enqueue`.
+    /// 3. `pick_next`; if it returns a task, make it `Running`. If its `slice` is 0 (it used up
+    ///    its quantum, blocked, or never ran), give it a fresh `quantum`; otherwise it was
+    ///    preempted by a wake-up and resumes the rest. The task put back in step 2 may be picked
+    ///    again.
+    pub fn schedule(&mut self) -> Option<Tid> {
+        // TODO
+        todo!()
+    }
+}
+
+/// Cycles between two timer interrupts of a `Machine`.
+pub const TICK_CYCLES: u64 = 10_000;
+
+/// What a simulated task does with the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behavior {
+    /// Computes forever and never blocks.
+    CpuBound,
+    /// Runs for `burst` ticks, then sleeps for `sleep` ticks (waiting for a key press, say), and
+    /// so on. Both must be positive.
+    Interactive { burst: u64, sleep: u64 },
+}
+
+/// A `Scheduler` driven by the simulated CLINT timer of `02_timer_tick`, running tasks that
+/// behave as their `Behavior` says.
+pub struct Machine<P> {
+    /// Clock and sleeper wait list. Its own time slice is unused: quanta come from the policy.
+    timer: TimerDriver,
+    sched: Scheduler<P>,
+    behavior: Vec<Behavior>,
+    /// `burst_ran[tid]`: ticks run since `tid` last woke up (or was spawned).
+    burst_ran: Vec<u64>,
+    /// `timeline[k]`: the task that ran during tick `k + 1`, `None` if the CPU was idle.
+    timeline: Vec<Option<Tid>>,
+    /// `(tick, tid)`: `tid` used up its quantum at tick `tick`.
+    expirations: Vec<(u64, Tid)>,
+}
+
+impl<P: Policy> Machine<P> {
+    pub fn new(policy: P) -> Self {
+        Self {
+            timer: TimerDriver::new(TICK_CYCLES, 1),
+            sched: Scheduler::new(policy),
+            behavior: Vec::new(),
+            burst_ran: Vec::new(),
+            timeline: Vec::new(),
+            expirations: Vec::new(),
+        }
+    }
+
+    pub fn spawn(&mut self, behavior: Behavior) -> Tid {
+        if let Behavior::Interactive { burst, sleep } = behavior {
+            assert!(burst > 0 && sleep > 0, "burst and sleep must be positive");
+        }
+        self.behavior.push(behavior);
+        self.burst_ran.push(0);
+        self.sched.spawn()
+    }
+
+    /// Run one tick; see the module docs for the order of events.
+    pub fn step(&mut self) {
+        let ran = self.sched.schedule();
+        let woken = self.timer.advance(TICK_CYCLES);
+        let now = self.timer.jiffies();
+        if let Some(tid) = ran {
+            if self.sched.on_tick() {
+                self.expirations.push((now, tid));
+            }
+            if let Behavior::Interactive { burst, sleep } = self.behavior[tid] {
+                self.burst_ran[tid] += 1;
+                if self.burst_ran[tid] == burst {
+                    self.burst_ran[tid] = 0;
+                    self.sched.block_current();
+                    self.timer.sleep_for(tid, sleep);
+                }
+            }
+        }
+        for tid in woken {
+            self.sched.wake(tid);
+        }
+        self.timeline.push(ran);
+    }
+
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.step();
+        }
+    }
+
+    /// Ticks run so far.
+    pub fn now(&self) -> u64 {
+        self.timer.jiffies()
+    }
+
+    pub fn scheduler(&self) -> &Scheduler<P> {
+        &self.sched
+    }
+
+    pub fn timeline(&self) -> &[Option<Tid>] {
+        &self.timeline
+    }
+
+    pub fn expirations(&self) -> &[(u64, Tid)] {
+        &self.expirations
+    }
+
+    /// Ticks `tid` has run so far.
+    pub fn ran(&self, tid: Tid) -> u64 {
+        self.timeline.iter().filter(|&&t| t == Some(tid)).count() as u64
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_on_tick_counts_down_quantum",
+            "on_tick: charge, slice_left -= 1, and only at 0 call expired, set need_resched and return true",
+        ),
+        (
+            "test_schedule_keeps_current_until_resched",
+            "schedule keeps the running task unless need_resched is set; then it re-enqueues it before pick_next",
+        ),
+        (
+            "test_cpu_bound_preempted_at_quantum_boundaries",
+            "schedule must refill slice_left from policy.quantum(next) on every switch",
+        ),
+        (
+            "test_mlfq_cpu_bound_sinks_one_level_per_quantum",
+            "expired drops the task one level, but never below the bottom level",
+        ),
+        (
+            "test_mlfq_interactive_keeps_level",
+            "only expired() demotes: a task that blocks before its quantum ends keeps its level",
+        ),
+        (
+            "test_mlfq_wakeup_preempts_lower_level",
+            "wake: if should_preempt(current, woken) set need_resched; should_preempt compares levels",
+        ),
+        (
+            "test_cfs_quantum_splits_latency",
+            "quantum = max(latency / (queued + 1), min_granularity)",
+        ),
+        (
+            "test_cfs_new_task_starts_at_min_vruntime",
+            "enqueue raises vruntime to at least min_vruntime; pick_next advances min_vruntime",
+        ),
+        (
+            "test_cfs_sleeper_does_not_bank_vruntime",
+            "enqueue raises a woken task's vruntime too, and charge keeps min_vruntime up with the running task",
+        ),
+    ];
+
+    /// Lengths of the runs of consecutive ticks `tid` got, in order.
+    fn runs(timeline: &[Option<Tid>], tid: Tid) -> Vec<u64> {
+        let mut runs = Vec::new();
+        let mut len = 0;
+        for &t in timeline {
+            if t == Some(tid) {
+                len += 1;
+            } else if len > 0 {
+                runs.push(len);
+                len = 0;
+            }
+        }
+        if len > 0 {
+            runs.push(len);
+        }
+        runs
+    }
+
+    #[test]
+    fn test_on_tick_counts_down_quantum() {
+        let mut s = Scheduler::new(Mlfq::new(vec![3, 6]));
+        assert_with_hint!(!s.on_tick(), "nothing running: nothing to charge");
+        let a = s.spawn();
+        assert_eq!(s.schedule(), Some(a));
+        assert_eq!(s.state(a), TaskState::Running);
+        assert_eq!(s.slice_left(), 3);
+        assert_with_hint!(!s.on_tick());
+        assert_with_hint!(!s.on_tick());
+        assert_eq_with_hint!(s.slice_left(), 1);
+        assert_with_hint!(!s.need_resched());
+        assert_with_hint!(s.on_tick(), "the third tick uses up a quantum of 3");
+        assert_with_hint!(s.need_resched());
+        assert_eq_with_hint!(s.policy().level(a), 1, "expired moves it down a level");
+    }
+
+    #[test]
+    fn test_schedule_keeps_current_until_resched() {
+        let mut s = Scheduler::new(Mlfq::new(vec![2]));
+        let a = s.spawn();
+        let b = s.spawn();
+        assert_eq!(s.schedule(), Some(a));
+        s.on_tick();
+        assert_eq_with_hint!(s.schedule(), Some(a), "quantum not used up yet");
+        s.on_tick();
+        assert_eq_with_hint!(s.schedule(), Some(b));
+        assert_eq_with_hint!(s.state(a), TaskState::Ready);
+        assert_with_hint!(!s.need_resched(), "schedule clears need_resched");
+        assert_eq_with_hint!(s.slice_left(), 2);
+    }
+
+    #[test]
+    fn test_block_and_wake() {
+        let mut s = Scheduler::new(Mlfq::new(vec![4]));
+        let a = s.spawn();
+        assert_eq!(s.schedule(), Some(a));
+        s.block_current();
+        assert_eq!(s.state(a), TaskState::Blocked);
+        assert_eq!(s.current(), None);
+        assert_eq!(s.schedule(), None, "nothing ready: idle");
+        assert!(!s.on_tick());
+        s.wake(a);
+        assert_eq!(s.state(a), TaskState::Ready);
+        assert_eq!(s.schedule(), Some(a), "an idle CPU picks up a woken task");
+        assert_eq!(s.slice_left(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wake_ready_task_panics() {
+        let mut s = Scheduler::new(Mlfq::new(vec![4]));
+        let a = s.spawn();
+        s.wake(a);
+    }
+
+    #[test]
+    fn test_cpu_bound_preempted_at_quantum_boundaries() {
+        let mut m = Machine::new(Mlfq::new(vec![3]));
+        let a = m.spawn(Behavior::CpuBound);
+        let b = m.spawn(Behavior::CpuBound);
+        m.run(12);
+        assert_eq_with_hint!(
+            m.timeline().to_vec(),
+            [a, a, a, b, b, b, a, a, a, b, b, b].map(Some).to_vec()
+        );
+        assert_eq_with_hint!(
+            m.expirations().to_vec(),
+            vec![(3, a), (6, b), (9, a), (12, b)]
+        );
+    }
+
+    #[test]
+    fn test_lone_task_keeps_running_across_quanta() {
+        let mut m = Machine::new(Mlfq::new(vec![2]));
+        let a = m.spawn(Behavior::CpuBound);
+        m.run(6);
+        assert_eq!(m.timeline().to_vec(), vec![Some(a); 6]);
+        assert_eq!(m.expirations().to_vec(), vec![(2, a), (4, a), (6, a)]);
+    }
+
+    #[test]
+    fn test_mlfq_cpu_bound_sinks_one_level_per_quantum() {
+        let mut m = Machine::new(Mlfq::new(vec![2, 4, 8]));
+        let a = m.spawn(Behavior::CpuBound);
+        m.run(30);
+        assert_eq_with_hint!(
+            m.expirations().to_vec(),
+            vec![(2, a), (6, a), (14, a), (22, a), (30, a)],
+            "quanta 2, 4, then 8 on the bottom level for good"
+        );
+        assert_eq_with_hint!(m.scheduler().policy().level(a), 2);
+    }
+
+    #[test]
+    fn test_mlfq_interactive_keeps_level() {
+        let mut m = Machine::new(Mlfq::new(vec![2, 4, 8]));
+        let hog = m.spawn(Behavior::CpuBound);
+        let ui = m.spawn(Behavior::Interactive { burst: 1, sleep: 3 });
+        m.run(100);
+        assert_eq_with_hint!(m.scheduler().policy().level(ui), 0);
+        assert_eq_with_hint!(m.scheduler().policy().level(hog), 2);
+        assert_with_hint!(
+            m.expirations().iter().all(|&(_, t)| t == hog),
+            "the interactive task never uses up a quantum"
+        );
+        assert_eq_with_hint!(
+            runs(m.timeline(), ui),
+            vec![1; 25],
+            "it runs one tick every 4: its burst, then 3 ticks asleep"
+        );
+        assert_eq!(m.ran(hog), 75);
+    }
+
+    #[test]
+    fn test_mlfq_wakeup_preempts_lower_level() {
+        let mut m = Machine::new(Mlfq::new(vec![2, 100]));
+        let ui = m.spawn(Behavior::Interactive { burst: 1, sleep: 5 });
+        let hog = m.spawn(Behavior::CpuBound);
+        m.run(20);
+        // ui runs tick 1 and sleeps until tick 6; hog gets 2 ticks on level 0, then level 1
+        // with a quantum of 100, but ui takes the CPU right after each wake-up.
+        let ui_ticks: Vec<usize> = m
+            .timeline()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &t)| t == Some(ui))
+            .map(|(k, _)| k + 1)
+            .collect();
+        assert_eq_with_hint!(
+            ui_ticks,
+            vec![1, 7, 13, 19],
+            "a woken level-0 task must not wait for the level-1 quantum to end"
+        );
+        assert_eq_with_hint!(m.scheduler().policy().level(hog), 1);
+        assert_eq_with_hint!(m.expirations().to_vec(), vec![(3, hog)]);
+    }
+
+    #[test]
+    fn test_cfs_quantum_splits_latency() {
+        for (tasks, quantum) in [(1, 12), (2, 6), (3, 4), (8, 2)] {
+            let mut m = Machine::new(Cfs::new(12, 2));
+            for _ in 0..tasks {
+                m.spawn(Behavior::CpuBound);
+            }
+            m.run(48);
+            let boundaries: Vec<u64> = m.expirations().iter().map(|&(t, _)| t).collect();
+            let expected: Vec<u64> = (1..=48 / quantum).map(|k| k * quantum).collect();
+            assert_eq_with_hint!(boundaries, expected, "{tasks} tasks");
+        }
+    }
+
+    #[test]
+    fn test_cfs_shares_cpu_evenly() {
+        let mut m = Machine::new(Cfs::new(12, 2));
+        let tids: Vec<Tid> = (0..3).map(|_| m.spawn(Behavior::CpuBound)).collect();
+        m.run(120);
+        for &t in &tids {
+            assert_eq!(m.ran(t), 40);
+            assert_eq!(m.scheduler().policy().vruntime(t), 40);
+            assert!(runs(m.timeline(), t).iter().all(|&r| r == 4));
+        }
+    }
+
+    #[test]
+    fn test_cfs_new_task_starts_at_min_vruntime() {
+        let mut m = Machine::new(Cfs::new(12, 2));
+        let old = m.spawn(Behavior::CpuBound);
+        m.run(100);
+        let late = m.spawn(Behavior::CpuBound);
+        assert_with_hint!(
+            m.scheduler().policy().vruntime(late) >= 90,
+            "a new task starts near min_vruntime, not at 0"
+        );
+        m.run(60);
+        let late_runs = runs(&m.timeline()[100..], late);
+        assert_with_hint!(
+            late_runs.iter().all(|&r| r <= 12),
+            "the newcomer must not monopolize the CPU to catch up: runs {late_runs:?}"
+        );
+        assert_with_hint!(m.ran(old) >= 100 + 24 && m.ran(late) >= 24);
+    }
+
+    #[test]
+    fn test_cfs_sleeper_runs_at_next_boundary() {
+        let mut m = Machine::new(Cfs::new(12, 2));
+        m.spawn(Behavior::CpuBound);
+        m.spawn(Behavior::CpuBound);
+        let ui = m.spawn(Behavior::Interactive {
+            burst: 1,
+            sleep: 10,
+        });
+        m.run(200);
+        let ui_ticks: Vec<u64> = (1..=200)
+            .filter(|&k| m.timeline()[k as usize - 1] == Some(ui))
+            .collect();
+        // Asleep for ticks t+1..=t+10, Ready from t+11, picked no later than the end of the
+        // running hog's quantum (at most 6 ticks with 2 runnable tasks).
+        for w in ui_ticks.windows(2) {
+            assert!((11..=16).contains(&(w[1] - w[0])), "{ui_ticks:?}");
+        }
+    }
+
+    #[test]
+    fn test_cfs_sleeper_does_not_bank_vruntime() {
+        let mut m = Machine::new(Cfs::new(12, 2));
+        m.spawn(Behavior::CpuBound);
+        let ui = m.spawn(Behavior::Interactive {
+            burst: 20,
+            sleep: 100,
+        });
+        m.run(300);
+        let ui_runs = runs(m.timeline(), ui);
+        assert_with_hint!(
+            ui_runs.iter().all(|&r| r <= 12),
+            "a woken task starts at min_vruntime instead of making up for its sleep: runs {ui_runs:?}"
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mlfq_zero_quantum_panics() {
+        Mlfq::new(vec![2, 0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cfs_zero_latency_panics() {
+        Cfs::new(0, 1);
+    }
+}
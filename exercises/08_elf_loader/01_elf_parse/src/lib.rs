@@ -0,0 +1,388 @@
+//! # ELF64 Header and Program-Header Parser
+//!
+//! In this exercise, you parse just enough of an ELF64 executable to load it: the file header
+//! and the program headers, then pick out the `PT_LOAD` segments a loader has to map.
+//!
+//! ## Layout
+//! ```text
+//! offset 0       Elf64_Ehdr (64 bytes)
+//!   0x00  e_ident[16]   7f 'E' 'L' 'F', class (2 = 64-bit), data (1 = little endian), ...
+//!   0x10  e_type u16    2 = EXEC, 3 = DYN (PIE / shared object)
+//!   0x12  e_machine u16 243 = RISC-V
+//!   0x18  e_entry u64
+//!   0x20  e_phoff u64   where the program header table starts
+//!   0x36  e_phentsize u16, 0x38 e_phnum u16
+//! e_phoff        e_phnum x Elf64_Phdr (56 bytes each)
+//!   0x00 p_type u32, 0x04 p_flags u32, 0x08 p_offset, 0x10 p_vaddr, 0x18 p_paddr,
+//!   0x20 p_filesz, 0x28 p_memsz, 0x30 p_align (all u64)
+//! ```
+//!
+//! A `PT_LOAD` segment says: map `p_memsz` bytes at `p_vaddr` with permissions `p_flags`, fill the
+//! first `p_filesz` of them from the file at `p_offset`, zero the rest (that is `.bss`).
+//!
+//! The tests parse `../fixtures/hello.elf`, a tiny statically linked riscv64 program built from
+//! `../fixtures/hello.s` (see `../fixtures/build.sh`).
+//!
+//! ## Task
+//! 1. `Elf64Header::parse` — validate the identification bytes and machine, read the fields
+//! 2. `ProgramHeader::parse` — read one program header
+//! 3. `ElfFile::program_headers` / `ElfFile::load_segments` — iterate without allocating (`no_std`)
+
+#![cfg_attr(not(test), no_std)]
+
+pub const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+pub const ELFCLASS64: u8 = 2;
+pub const ELFDATA2LSB: u8 = 1;
+pub const EM_RISCV: u16 = 243;
+
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+
+pub const PT_LOAD: u32 = 1;
+pub const PT_PHDR: u32 = 6;
+
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+pub const EHDR_SIZE: usize = 64;
+pub const PHDR_SIZE: usize = 56;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfError {
+    /// The data ends before a structure it should contain.
+    Truncated,
+    BadMagic,
+    /// `e_ident[EI_CLASS]` is not `ELFCLASS64`.
+    NotElf64,
+    /// `e_ident[EI_DATA]` is not `ELFDATA2LSB`.
+    NotLittleEndian,
+    WrongMachine(u16),
+    /// `e_phentsize` is not `PHDR_SIZE`, or the table does not fit in the file.
+    BadProgramHeaders,
+}
+
+/// Little-endian `u16` at `off`.
+pub fn read_u16(data: &[u8], off: usize) -> Result<u16, ElfError> {
+    let b = data.get(off..off + 2).ok_or(ElfError::Truncated)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+/// Little-endian `u32` at `off`.
+pub fn read_u32(data: &[u8], off: usize) -> Result<u32, ElfError> {
+    let b = data.get(off..off + 4).ok_or(ElfError::Truncated)?;
+    Ok(u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// Little-endian `u64` at `off`.
+pub fn read_u64(data: &[u8], off: usize) -> Result<u64, ElfError> {
+    let b = data.get(off..off + 8).ok_or(ElfError::Truncated)?;
+    Ok(u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// The fields of `Elf64_Ehdr` a loader needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elf64Header {
+    pub e_type: u16,
+    pub e_machine: u16,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
+    pub e_phentsize: u16,
+    pub e_phnum: u16,
+    pub e_shentsize: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+impl Elf64Header {
+    /// Parse and validate the file header.
+    ///
+    /// Check, in this order: at least `EHDR_SIZE` bytes (`Truncated`), magic (`BadMagic`),
+    /// class (`NotElf64`), data encoding (`NotLittleEndian`), `e_machine == EM_RISCV`
+    /// (`WrongMachine`). Then read the fields at the offsets in the table above
+    /// (`e_shoff` 0x28, `e_shentsize` 0x3A, `e_shnum` 0x3C, `e_shstrndx` 0x3E).
+    pub fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        // TODO
+        todo!()
+    }
+}
+
+/// One `Elf64_Phdr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl ProgramHeader {
+    /// Parse the program header starting at `data[off]`.
+    pub fn parse(data: &[u8], off: usize) -> Result<Self, ElfError> {
+        // TODO
+        todo!()
+    }
+}
+
+/// Segment permissions decoded from `p_flags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentFlags {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl SegmentFlags {
+    pub fn from_bits(p_flags: u32) -> Self {
+        Self {
+            read: p_flags & PF_R != 0,
+            write: p_flags & PF_W != 0,
+            execute: p_flags & PF_X != 0,
+        }
+    }
+}
+
+/// A `PT_LOAD` segment: what to map where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadSegment {
+    pub vaddr: u64,
+    pub memsz: u64,
+    pub filesz: u64,
+    /// File offset of the first `filesz` bytes.
+    pub offset: u64,
+    pub flags: SegmentFlags,
+}
+
+/// A parsed ELF file borrowing its bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct ElfFile<'a> {
+    data: &'a [u8],
+    header: Elf64Header,
+}
+
+impl<'a> ElfFile<'a> {
+    /// Parse the header and check that the whole program header table is present, so that the
+    /// iterators below cannot fail.
+    pub fn parse(data: &'a [u8]) -> Result<Self, ElfError> {
+        let header = Elf64Header::parse(data)?;
+        if header.e_phnum > 0 && header.e_phentsize as usize != PHDR_SIZE {
+            return Err(ElfError::BadProgramHeaders);
+        }
+        let end = (header.e_phoff as usize)
+            .checked_add(header.e_phnum as usize * PHDR_SIZE)
+            .ok_or(ElfError::BadProgramHeaders)?;
+        if end > data.len() {
+            return Err(ElfError::BadProgramHeaders);
+        }
+        Ok(Self { data, header })
+    }
+
+    pub fn header(&self) -> &Elf64Header {
+        &self.header
+    }
+
+    pub fn entry(&self) -> u64 {
+        self.header.e_entry
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// All program headers, in file order (`e_phnum` entries starting at `e_phoff`).
+    pub fn program_headers(&self) -> impl Iterator<Item = ProgramHeader> + 'a {
+        // TODO: map each index to ProgramHeader::parse (bounds were checked in `parse`)
+        todo!();
+        #[allow(unreachable_code)]
+        core::iter::empty()
+    }
+
+    /// The `PT_LOAD` program headers as `LoadSegment`s, in file order.
+    pub fn load_segments(&self) -> impl Iterator<Item = LoadSegment> + 'a {
+        // TODO: filter program_headers() by p_type and convert
+        todo!();
+        #[allow(unreachable_code)]
+        core::iter::empty()
+    }
+
+    /// The `filesz` bytes of `seg` in the file.
+    pub fn segment_data(&self, seg: &LoadSegment) -> Result<&'a [u8], ElfError> {
+        let start = seg.offset as usize;
+        self.data
+            .get(start..start + seg.filesz as usize)
+            .ok_or(ElfError::Truncated)
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_header_fields",
+            "ELF64 header fields are little-endian at fixed offsets: e_entry at 0x18, e_phoff at 0x20, e_phnum at 0x38",
+        ),
+        (
+            "test_program_headers",
+            "program header i starts at e_phoff + i * e_phentsize; p_offset comes before p_vaddr, and p_flags right after p_type",
+        ),
+        (
+            "test_load_segments_layout",
+            "keep only PT_LOAD headers; p_flags bit 0 is X, bit 1 W and bit 2 R",
+        ),
+        (
+            "test_segment_data",
+            "a segment's bytes are data[p_offset..p_offset + p_filesz]; the rest of memsz is .bss",
+        ),
+        (
+            "test_reject_elf32_and_big_endian",
+            "e_ident[EI_CLASS] (byte 4) must be 2 for ELF64 and e_ident[EI_DATA] (byte 5) 1 for little-endian",
+        ),
+        (
+            "test_reject_truncated",
+            "check data.len() before reading the header, and that the program header table fits in the file",
+        ),
+    ];
+
+    static HELLO: &[u8] = include_bytes!("../../fixtures/hello.elf");
+
+    fn rwx(read: bool, write: bool, execute: bool) -> SegmentFlags {
+        SegmentFlags {
+            read,
+            write,
+            execute,
+        }
+    }
+
+    #[test]
+    fn test_header_fields() {
+        let h = Elf64Header::parse(HELLO).unwrap();
+        assert_eq_with_hint!(h.e_type, ET_EXEC);
+        assert_eq_with_hint!(h.e_machine, EM_RISCV);
+        assert_eq_with_hint!(h.e_entry, 0x11168);
+        assert_eq_with_hint!(h.e_phoff, 64);
+        assert_eq_with_hint!(h.e_phentsize as usize, PHDR_SIZE);
+        assert_eq_with_hint!(h.e_phnum, 5);
+        assert_eq_with_hint!(h.e_shoff, 856);
+        assert_eq_with_hint!(h.e_shnum, 9);
+        assert_eq_with_hint!(h.e_shstrndx, 7);
+    }
+
+    #[test]
+    fn test_program_headers() {
+        let elf = ElfFile::parse(HELLO).unwrap();
+        let phdrs: Vec<_> = elf.program_headers().collect();
+        assert_eq_with_hint!(phdrs.len(), 5);
+        assert_eq_with_hint!(phdrs[0].p_type, PT_PHDR);
+        assert_eq_with_hint!(phdrs[0].p_vaddr, 0x10040);
+        assert_eq_with_hint!(phdrs[0].p_filesz, 5 * PHDR_SIZE as u64);
+        assert_eq_with_hint!(phdrs[4].p_type, 0x6474_e551, "PT_GNU_STACK");
+        assert_eq_with_hint!(phdrs[1].p_align, 0x1000);
+    }
+
+    #[test]
+    fn test_load_segments_layout() {
+        let elf = ElfFile::parse(HELLO).unwrap();
+        let loads: Vec<_> = elf.load_segments().collect();
+        assert_eq_with_hint!(
+            loads,
+            vec![
+                // ELF + program headers and .rodata
+                LoadSegment {
+                    vaddr: 0x10000,
+                    memsz: 0x166,
+                    filesz: 0x166,
+                    offset: 0,
+                    flags: rwx(true, false, false),
+                },
+                // .text
+                LoadSegment {
+                    vaddr: 0x11168,
+                    memsz: 0x38,
+                    filesz: 0x38,
+                    offset: 0x168,
+                    flags: rwx(true, false, true),
+                },
+                // .data + 8 KiB .bss
+                LoadSegment {
+                    vaddr: 0x121a0,
+                    memsz: 0x2018,
+                    filesz: 0x15,
+                    offset: 0x1a0,
+                    flags: rwx(true, true, false),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entry_is_in_executable_segment() {
+        let elf = ElfFile::parse(HELLO).unwrap();
+        let seg = elf
+            .load_segments()
+            .find(|s| (s.vaddr..s.vaddr + s.memsz).contains(&elf.entry()))
+            .expect("entry not inside any PT_LOAD");
+        assert!(seg.flags.execute && !seg.flags.write);
+    }
+
+    #[test]
+    fn test_segment_data() {
+        let elf = ElfFile::parse(HELLO).unwrap();
+        let rodata = elf.load_segments().next().unwrap();
+        let bytes = elf.segment_data(&rodata).unwrap();
+        assert_eq_with_hint!(&bytes[..4], &ELF_MAGIC, "first segment maps the ELF header");
+        assert_eq_with_hint!(&bytes[0x158..0x166], b"Hello, world!\n");
+        let data = elf.load_segments().nth(2).unwrap();
+        let bytes = elf.segment_data(&data).unwrap();
+        assert_eq_with_hint!(&bytes[..8], &0x1122334455667788u64.to_le_bytes());
+        assert_eq_with_hint!(&bytes[8..20], b"data segment");
+    }
+
+    #[test]
+    fn test_reject_bad_magic() {
+        let mut bad = HELLO.to_vec();
+        bad[1] = b'X';
+        assert_eq!(Elf64Header::parse(&bad), Err(ElfError::BadMagic));
+    }
+
+    #[test]
+    fn test_reject_elf32_and_big_endian() {
+        let mut bad = HELLO.to_vec();
+        bad[4] = 1;
+        assert_eq_with_hint!(Elf64Header::parse(&bad), Err(ElfError::NotElf64));
+        let mut bad = HELLO.to_vec();
+        bad[5] = 2;
+        assert_eq_with_hint!(Elf64Header::parse(&bad), Err(ElfError::NotLittleEndian));
+    }
+
+    #[test]
+    fn test_reject_wrong_machine() {
+        let mut bad = HELLO.to_vec();
+        bad[0x12..0x14].copy_from_slice(&62u16.to_le_bytes()); // EM_X86_64
+        assert_eq!(Elf64Header::parse(&bad), Err(ElfError::WrongMachine(62)));
+    }
+
+    #[test]
+    fn test_reject_truncated() {
+        assert_eq_with_hint!(Elf64Header::parse(&HELLO[..40]), Err(ElfError::Truncated));
+        assert_eq_with_hint!(Elf64Header::parse(&[]), Err(ElfError::Truncated));
+        // Header intact, program header table cut off.
+        assert_eq_with_hint!(
+            ElfFile::parse(&HELLO[..200]).err(),
+            Some(ElfError::BadProgramHeaders)
+        );
+    }
+}
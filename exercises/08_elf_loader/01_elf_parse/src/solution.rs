@@ -0,0 +1,255 @@
+//! # ELF64 Header and Program-Header Parser
+//!
+//! In this exercise, you parse just enough of an ELF64 executable to load it: the file header
+//! and the program headers, then pick out the `PT_LOAD` segments a loader has to map.
+//!
+//! ## Layout
+//! ```text
+//! offset 0       Elf64_Ehdr (64 bytes)
+//!   0x00  e_ident[16]   7f 'E' 'L' 'F', class (2 = 64-bit), data (1 = little endian), ...
+//!   0x10  e_type u16    2 = EXEC, 3 = DYN (PIE / shared object)
+//!   0x12  e_machine u16 243 = RISC-V
+//!   0x18  e_entry u64
+//!   0x20  e_phoff u64   where the program header table starts
+//!   0x36  e_phentsize u16, 0x38 e_phnum u16
+//! e_phoff        e_phnum x Elf64_Phdr (56 bytes each)
+//!   0x00 p_type u32, 0x04 p_flags u32, 0x08 p_offset, 0x10 p_vaddr, 0x18 p_paddr,
+//!   0x20 p_filesz, 0x28 p_memsz, 0x30 p_align (all u64)
+//! ```
+//!
+//! A `PT_LOAD` segment says: map `p_memsz` bytes at `p_vaddr` with permissions `p_flags`, fill the
+//! first `p_filesz` of them from the file at `p_offset`, zero the rest (that is `.bss`).
+//!
+//! The tests parse `../fixtures/hello.elf`, a tiny statically linked riscv64 program built from
+//! `../fixtures/hello.s` (see `../fixtures/build.sh`).
+//!
+//! ## Task
+//! 1. `Elf64Header::parse` — validate the identification bytes and machine, read the fields
+//! 2. `ProgramHeader::parse` — read one program header
+//! 3. `ElfFile::program_headers` / `ElfFile::load_segments` — iterate without allocating (`no_std`)
+
+pub const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+pub const ELFCLASS64: u8 = 2;
+pub const ELFDATA2LSB: u8 = 1;
+pub const EM_RISCV: u16 = 243;
+
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+
+pub const PT_LOAD: u32 = 1;
+pub const PT_PHDR: u32 = 6;
+
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+pub const EHDR_SIZE: usize = 64;
+pub const PHDR_SIZE: usize = 56;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfError {
+    /// The data ends before a structure it should contain.
+    Truncated,
+    BadMagic,
+    /// `e_ident[EI_CLASS]` is not `ELFCLASS64`.
+    NotElf64,
+    /// `e_ident[EI_DATA]` is not `ELFDATA2LSB`.
+    NotLittleEndian,
+    WrongMachine(u16),
+    /// `e_phentsize` is not `PHDR_SIZE`, or the table does not fit in the file.
+    BadProgramHeaders,
+}
+
+/// Little-endian `u16` at `off`.
+pub fn read_u16(data: &[u8], off: usize) -> Result<u16, ElfError> {
+    let b = data.get(off..off + 2).ok_or(ElfError::Truncated)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+/// Little-endian `u32` at `off`.
+pub fn read_u32(data: &[u8], off: usize) -> Result<u32, ElfError> {
+    let b = data.get(off..off + 4).ok_or(ElfError::Truncated)?;
+    Ok(u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// Little-endian `u64` at `off`.
+pub fn read_u64(data: &[u8], off: usize) -> Result<u64, ElfError> {
+    let b = data.get(off..off + 8).ok_or(ElfError::Truncated)?;
+    Ok(u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// The fields of `Elf64_Ehdr` a loader needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elf64Header {
+    pub e_type: u16,
+    pub e_machine: u16,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
+    pub e_phentsize: u16,
+    pub e_phnum: u16,
+    pub e_shentsize: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+impl Elf64Header {
+    /// Parse and validate the file header.
+    ///
+    /// Check, in this order: at least `EHDR_SIZE` bytes (`Truncated`), magic (`BadMagic`),
+    /// class (`NotElf64`), data encoding (`NotLittleEndian`), `e_machine == EM_RISCV`
+    /// (`WrongMachine`). Then read the fields at the offsets in the table above
+    /// (`e_shoff` 0x28, `e_shentsize` 0x3A, `e_shnum` 0x3C, `e_shstrndx` 0x3E).
+    pub fn parse(data: &[u8]) -> Result<Self, ElfError> {
+        if data.len() < EHDR_SIZE {
+            return Err(ElfError::Truncated);
+        }
+        if data[..4] != ELF_MAGIC {
+            return Err(ElfError::BadMagic);
+        }
+        if data[4] != ELFCLASS64 {
+            return Err(ElfError::NotElf64);
+        }
+        if data[5] != ELFDATA2LSB {
+            return Err(ElfError::NotLittleEndian);
+        }
+        let e_machine = read_u16(data, 0x12)?;
+        if e_machine != EM_RISCV {
+            return Err(ElfError::WrongMachine(e_machine));
+        }
+        Ok(Self {
+            e_type: read_u16(data, 0x10)?,
+            e_machine,
+            e_entry: read_u64(data, 0x18)?,
+            e_phoff: read_u64(data, 0x20)?,
+            e_shoff: read_u64(data, 0x28)?,
+            e_phentsize: read_u16(data, 0x36)?,
+            e_phnum: read_u16(data, 0x38)?,
+            e_shentsize: read_u16(data, 0x3A)?,
+            e_shnum: read_u16(data, 0x3C)?,
+            e_shstrndx: read_u16(data, 0x3E)?,
+        })
+    }
+}
+
+/// One `Elf64_Phdr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl ProgramHeader {
+    /// Parse the program header starting at `data[off]`.
+    pub fn parse(data: &[u8], off: usize) -> Result<Self, ElfError> {
+        Ok(Self {
+            p_type: read_u32(data, off)?,
+            p_flags: read_u32(data, off + 0x04)?,
+            p_offset: read_u64(data, off + 0x08)?,
+            p_vaddr: read_u64(data, off + 0x10)?,
+            p_filesz: read_u64(data, off + 0x20)?,
+            p_memsz: read_u64(data, off + 0x28)?,
+            p_align: read_u64(data, off + 0x30)?,
+        })
+    }
+}
+
+/// Segment permissions decoded from `p_flags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentFlags {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl SegmentFlags {
+    pub fn from_bits(p_flags: u32) -> Self {
+        Self {
+            read: p_flags & PF_R != 0,
+            write: p_flags & PF_W != 0,
+            execute: p_flags & PF_X != 0,
+        }
+    }
+}
+
+/// A `PT_LOAD` segment: what to map where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadSegment {
+    pub vaddr: u64,
+    pub memsz: u64,
+    pub filesz: u64,
+    /// File offset of the first `filesz` bytes.
+    pub offset: u64,
+    pub flags: SegmentFlags,
+}
+
+/// A parsed ELF file borrowing its bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct ElfFile<'a> {
+    data: &'a [u8],
+    header: Elf64Header,
+}
+
+impl<'a> ElfFile<'a> {
+    /// Parse the header and check that the whole program header table is present, so that the
+    /// iterators below cannot fail.
+    pub fn parse(data: &'a [u8]) -> Result<Self, ElfError> {
+        let header = Elf64Header::parse(data)?;
+        if header.e_phnum > 0 && header.e_phentsize as usize != PHDR_SIZE {
+            return Err(ElfError::BadProgramHeaders);
+        }
+        let end = (header.e_phoff as usize)
+            .checked_add(header.e_phnum as usize * PHDR_SIZE)
+            .ok_or(ElfError::BadProgramHeaders)?;
+        if end > data.len() {
+            return Err(ElfError::BadProgramHeaders);
+        }
+        Ok(Self { data, header })
+    }
+
+    pub fn header(&self) -> &Elf64Header {
+        &self.header
+    }
+
+    pub fn entry(&self) -> u64 {
+        self.header.e_entry
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// All program headers, in file order (`e_phnum` entries starting at `e_phoff`).
+    pub fn program_headers(&self) -> impl Iterator<Item = ProgramHeader> + 'a {
+        let data = self.data;
+        let phoff = self.header.e_phoff as usize;
+        (0..self.header.e_phnum as usize)
+            .map(move |i| ProgramHeader::parse(data, phoff + i * PHDR_SIZE).unwrap())
+    }
+
+    /// The `PT_LOAD` program headers as `LoadSegment`s, in file order.
+    pub fn load_segments(&self) -> impl Iterator<Item = LoadSegment> + 'a {
+        self.program_headers()
+            .filter(|ph| ph.p_type == PT_LOAD)
+            .map(|ph| LoadSegment {
+                vaddr: ph.p_vaddr,
+                memsz: ph.p_memsz,
+                filesz: ph.p_filesz,
+                offset: ph.p_offset,
+                flags: SegmentFlags::from_bits(ph.p_flags),
+            })
+    }
+
+    /// The `filesz` bytes of `seg` in the file.
+    pub fn segment_data(&self, seg: &LoadSegment) -> Result<&'a [u8], ElfError> {
+        let start = seg.offset as usize;
+        self.data
+            .get(start..start + seg.filesz as usize)
+            .ok_or(ElfError::Truncated)
+    }
+}
@@ -0,0 +1,284 @@
+//! # Loading a User Program into an Sv39 Address Space
+//!
+//! In this exercise, you do what `execve` does after parsing the ELF file: map every `PT_LOAD`
+//! segment into a fresh Sv39 page table backed by simulated physical memory, copy the file bytes,
+//! zero the `.bss`, and map a user stack.
+//!
+//! ## Concepts
+//! - Segment permissions become PTE flags: `PF_R/W/X` -> `PTE_R/W/X`, plus `PTE_U` (user page) and `PTE_V`
+//! - A segment covers the pages `[page_down(vaddr), page_up(vaddr + memsz))`; `vaddr` itself need
+//!   not be page aligned
+//! - `filesz` bytes come from the file, the remaining `memsz - filesz` bytes must be zeroed: fresh
+//!   physical frames hold whatever was there before (`PhysMem` fills them with `JUNK`)
+//! - The initial program break is the page after the highest segment
+//!
+//! The ELF parser is `01_elf_parse` and the page table `06_page_table/03_multi_level_pt`: finish
+//! those first (`--features solutions` uses their reference solutions instead). The tests load
+//! `../fixtures/hello.elf`.
+//!
+//! ## Layout after `load_elf(hello.elf)`
+//! ```text
+//! 0x10000 .. 0x11000   headers + .rodata   U R
+//! 0x11000 .. 0x12000   .text               U R X   (entry 0x11168)
+//! 0x12000 .. 0x15000   .data + .bss        U R W   (brk = 0x15000)
+//! USER_STACK_TOP - USER_STACK_SIZE .. USER_STACK_TOP   stack   U R W
+//! ```
+
+use std::collections::HashMap;
+
+pub use elf_parse::solution::{ElfError, ElfFile, LoadSegment, SegmentFlags, ET_EXEC};
+pub use multi_level_pt::solution::{Sv39PageTable, PAGE_SIZE, PTE_R, PTE_U, PTE_V, PTE_W, PTE_X};
+
+const PPN_SHIFT: u32 = 10;
+
+/// Byte value of a freshly allocated physical frame.
+pub const JUNK: u8 = 0xAA;
+
+/// Top of the user stack (exclusive); the initial `sp`.
+pub const USER_STACK_TOP: u64 = 0x4000_0000;
+pub const USER_STACK_SIZE: usize = 4 * PAGE_SIZE;
+
+pub fn page_down(addr: u64) -> u64 {
+    addr & !(PAGE_SIZE as u64 - 1)
+}
+
+pub fn page_up(addr: u64) -> u64 {
+    page_down(addr + PAGE_SIZE as u64 - 1)
+}
+
+// ============================================================
+// Physical memory (given)
+// ============================================================
+
+/// Simulated RAM: 4 KiB frames allocated on demand.
+pub struct PhysMem {
+    frames: HashMap<u64, Box<[u8; PAGE_SIZE]>>,
+    next_ppn: u64,
+}
+
+impl PhysMem {
+    pub fn new() -> Self {
+        Self {
+            frames: HashMap::new(),
+            next_ppn: 0x88000,
+        }
+    }
+
+    /// Allocate a frame and return its PPN. The frame is *not* zeroed: it is full of `JUNK`.
+    pub fn alloc_frame(&mut self) -> u64 {
+        let ppn = self.next_ppn;
+        self.next_ppn += 1;
+        self.frames.insert(ppn, Box::new([JUNK; PAGE_SIZE]));
+        ppn
+    }
+
+    pub fn frames_allocated(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The frame containing physical address `pa` (panics on unallocated memory).
+    pub fn frame_mut(&mut self, pa: u64) -> &mut [u8; PAGE_SIZE] {
+        self.frames
+            .get_mut(&(pa >> 12))
+            .expect("access to unallocated physical memory")
+    }
+
+    pub fn frame(&self, pa: u64) -> &[u8; PAGE_SIZE] {
+        self.frames
+            .get(&(pa >> 12))
+            .expect("access to unallocated physical memory")
+    }
+}
+
+impl Default for PhysMem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ============================================================
+// Loader
+// ============================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    Elf(ElfError),
+    /// Only statically linked `ET_EXEC` files are supported.
+    NotExecutable,
+    /// A page is mapped twice (overlapping segments, or loading into a used address space).
+    Overlap(u64),
+    /// A virtual address that is not mapped.
+    Unmapped(u64),
+    /// A segment's file bytes lie outside the file.
+    BadSegment,
+}
+
+impl From<ElfError> for LoadError {
+    fn from(e: ElfError) -> Self {
+        LoadError::Elf(e)
+    }
+}
+
+/// Result of `load_elf`: where to start the new program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadedProgram {
+    pub entry: u64,
+    /// Initial stack pointer (`USER_STACK_TOP`).
+    pub stack_top: u64,
+    /// Initial program break: `page_up` of the highest segment end.
+    pub brk: u64,
+}
+
+/// A user address space: page table plus the physical memory behind it.
+pub struct AddressSpace {
+    pub pt: Sv39PageTable,
+    pub mem: PhysMem,
+}
+
+impl AddressSpace {
+    pub fn new() -> Self {
+        Self {
+            pt: Sv39PageTable::new(),
+            mem: PhysMem::new(),
+        }
+    }
+
+    /// Physical address of `va`, if mapped.
+    pub fn translate(&self, va: u64) -> Option<u64> {
+        let pte = self.pt.leaf(va)?;
+        Some(((pte >> PPN_SHIFT) << 12) | (va & (PAGE_SIZE as u64 - 1)))
+    }
+
+    /// Flags (low 10 bits) of the page containing `va`, if mapped.
+    pub fn flags(&self, va: u64) -> Option<u64> {
+        self.pt.leaf(va).map(|pte| pte & ((1 << PPN_SHIFT) - 1))
+    }
+
+    /// Copy `bytes` to user virtual address `va` through the page table (may cross pages).
+    pub fn write_va(&mut self, va: u64, bytes: &[u8]) -> Result<(), LoadError> {
+        let mut done = 0;
+        while done < bytes.len() {
+            let cur = va + done as u64;
+            let pa = self.translate(cur).ok_or(LoadError::Unmapped(cur))?;
+            let off = (pa as usize) % PAGE_SIZE;
+            let n = (PAGE_SIZE - off).min(bytes.len() - done);
+            self.mem.frame_mut(pa)[off..off + n].copy_from_slice(&bytes[done..done + n]);
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Set `len` bytes at `va` to `byte`.
+    pub fn fill_va(&mut self, va: u64, len: usize, byte: u8) -> Result<(), LoadError> {
+        let mut done = 0;
+        while done < len {
+            let cur = va + done as u64;
+            let pa = self.translate(cur).ok_or(LoadError::Unmapped(cur))?;
+            let off = (pa as usize) % PAGE_SIZE;
+            let n = (PAGE_SIZE - off).min(len - done);
+            self.mem.frame_mut(pa)[off..off + n].fill(byte);
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Read `buf.len()` bytes from `va`.
+    pub fn read_va(&self, va: u64, buf: &mut [u8]) -> Result<(), LoadError> {
+        let mut done = 0;
+        while done < buf.len() {
+            let cur = va + done as u64;
+            let pa = self.translate(cur).ok_or(LoadError::Unmapped(cur))?;
+            let off = (pa as usize) % PAGE_SIZE;
+            let n = (PAGE_SIZE - off).min(buf.len() - done);
+            buf[done..done + n].copy_from_slice(&self.mem.frame(pa)[off..off + n]);
+            done += n;
+        }
+        Ok(())
+    }
+
+    /// Map `[va, va + len)` (rounded out to whole pages) to fresh frames with `flags`.
+    /// Fails with `Overlap(page)` if any of the pages is already mapped, before mapping anything.
+    pub fn map_fresh(&mut self, va: u64, len: u64, flags: u64) -> Result<(), LoadError> {
+        let (start, end) = (page_down(va), page_up(va + len));
+        if let Some(page) = (start..end)
+            .step_by(PAGE_SIZE)
+            .find(|&p| self.pt.leaf(p).is_some())
+        {
+            return Err(LoadError::Overlap(page));
+        }
+        for page in (start..end).step_by(PAGE_SIZE) {
+            let ppn = self.mem.alloc_frame();
+            self.pt.map_page(page, ppn << 12, flags | PTE_V);
+        }
+        Ok(())
+    }
+
+    /// Load one segment.
+    ///
+    /// 1. `map_fresh` its pages with `pte_flags(seg.flags)`.
+    /// 2. Copy the `filesz` file bytes at `seg.offset` to `seg.vaddr` (`BadSegment` if they are
+    ///    not inside `file`).
+    /// 3. Zero the `memsz - filesz` bytes after them.
+    pub fn load_segment(&mut self, file: &[u8], seg: &LoadSegment) -> Result<(), LoadError> {
+        let start = seg.offset as usize;
+        let bytes = file
+            .get(start..start + seg.filesz as usize)
+            .ok_or(LoadError::BadSegment)?;
+        self.map_fresh(seg.vaddr, seg.memsz, pte_flags(seg.flags))?;
+        self.write_va(seg.vaddr, bytes)?;
+        self.fill_va(seg.vaddr + seg.filesz, (seg.memsz - seg.filesz) as usize, 0)
+    }
+
+    /// Map `USER_STACK_SIZE` bytes of zeroed stack right below `USER_STACK_TOP` (user, R/W) and
+    /// return the initial `sp` (`USER_STACK_TOP`).
+    pub fn map_user_stack(&mut self) -> Result<u64, LoadError> {
+        let bottom = USER_STACK_TOP - USER_STACK_SIZE as u64;
+        self.map_fresh(bottom, USER_STACK_SIZE as u64, PTE_U | PTE_R | PTE_W)?;
+        self.fill_va(bottom, USER_STACK_SIZE, 0)?;
+        Ok(USER_STACK_TOP)
+    }
+}
+
+impl Default for AddressSpace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// PTE flags for a user segment with permissions `flags`: always `PTE_U`, plus `PTE_R` /
+/// `PTE_W` / `PTE_X` as requested.
+pub fn pte_flags(flags: SegmentFlags) -> u64 {
+    let mut pte = PTE_U;
+    if flags.read {
+        pte |= PTE_R;
+    }
+    if flags.write {
+        pte |= PTE_W;
+    }
+    if flags.execute {
+        pte |= PTE_X;
+    }
+    pte
+}
+
+/// Load the program in `file` into `space`.
+///
+/// Parse it with `ElfFile::parse`; reject anything but `ET_EXEC` with `NotExecutable`. Load every
+/// segment, map the user stack, and return the entry point, the stack top and the initial break.
+pub fn load_elf(space: &mut AddressSpace, file: &[u8]) -> Result<LoadedProgram, LoadError> {
+    let elf = ElfFile::parse(file)?;
+    if elf.header().e_type != ET_EXEC {
+        return Err(LoadError::NotExecutable);
+    }
+    let mut end = 0;
+    for seg in elf.load_segments() {
+        space.load_segment(file, &seg)?;
+        end = end.max(seg.vaddr + seg.memsz);
+    }
+    let stack_top = space.map_user_stack()?;
+    Ok(LoadedProgram {
+        entry: elf.entry(),
+        stack_top,
+        brk: page_up(end),
+    })
+}
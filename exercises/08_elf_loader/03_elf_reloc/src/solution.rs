@@ -0,0 +1,394 @@
+//! # Symbols and Dynamic Relocations
+//!
+//! In this exercise, you load a position-independent executable (PIE) at a base address of your
+//! choice and fix up the absolute pointers inside it, then look up symbols by name.
+//!
+//! ## Concepts
+//! - A PIE is linked at address 0 (`ET_DYN`); loaded at `base`, every link-time address `v`
+//!   becomes `base + v` (`base` is the *load bias*)
+//! - Pointers stored in data cannot be computed by PC-relative code, so the linker leaves
+//!   dynamic relocations in `.rela.dyn` for the loader:
+//!   - `R_RISCV_RELATIVE`: `*(base + r_offset) = base + r_addend`
+//!   - `R_RISCV_64`: `*(base + r_offset) = S + r_addend`, where `S` is the address of symbol
+//!     `r_sym` in `.dynsym` — `base + st_value` if the image defines it, otherwise it comes from
+//!     outside (another library, or here the `imports` callback); an undefined *weak* symbol
+//!     without a definition resolves to 0
+//! - `.symtab` (with names in `.strtab`) lists every symbol; only `GLOBAL` / `WEAK` symbols that
+//!   are defined (`st_shndx != SHN_UNDEF`) can be looked up from outside
+//!
+//! ## Structures
+//! ```text
+//! Elf64_Sym  (24 bytes): st_name u32 | st_info u8 | st_other u8 | st_shndx u16 | st_value u64 | st_size u64
+//!                        st_info = bind << 4 | type
+//! Elf64_Rela (24 bytes): r_offset u64 | r_info u64 | r_addend i64
+//!                        r_info = sym << 32 | type
+//! ```
+//!
+//! The tests load `../fixtures/pie.elf` (built from `../fixtures/pie.s`): its `table` holds three
+//! `R_RISCV_RELATIVE` pointers and one `R_RISCV_64` pointer to the weak import `host_hook`.
+
+pub const ET_DYN: u16 = 3;
+pub const EM_RISCV: u16 = 243;
+pub const PT_LOAD: u32 = 1;
+
+pub const SHT_SYMTAB: u32 = 2;
+pub const SHT_STRTAB: u32 = 3;
+pub const SHT_RELA: u32 = 4;
+pub const SHT_DYNSYM: u32 = 11;
+
+pub const SHN_UNDEF: u16 = 0;
+
+pub const STB_LOCAL: u8 = 0;
+pub const STB_GLOBAL: u8 = 1;
+pub const STB_WEAK: u8 = 2;
+
+pub const R_RISCV_NONE: u32 = 0;
+pub const R_RISCV_64: u32 = 2;
+pub const R_RISCV_RELATIVE: u32 = 3;
+
+pub const SYM_SIZE: usize = 24;
+pub const RELA_SIZE: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelocError {
+    Truncated,
+    BadMagic,
+    WrongMachine(u16),
+    /// Only `ET_DYN` images can be loaded at an arbitrary base.
+    NotPie,
+    MissingSection(&'static str),
+    /// `R_RISCV_64` against a non-weak undefined symbol that `imports` does not know.
+    UndefinedSymbol(String),
+    UnsupportedRelocation(u32),
+    /// A relocation target outside the loaded image.
+    OutOfImage(u64),
+}
+
+pub fn read_u16(data: &[u8], off: usize) -> Result<u16, RelocError> {
+    let b = data.get(off..off + 2).ok_or(RelocError::Truncated)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+pub fn read_u32(data: &[u8], off: usize) -> Result<u32, RelocError> {
+    let b = data.get(off..off + 4).ok_or(RelocError::Truncated)?;
+    Ok(u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+pub fn read_u64(data: &[u8], off: usize) -> Result<u64, RelocError> {
+    let b = data.get(off..off + 8).ok_or(RelocError::Truncated)?;
+    Ok(u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// NUL-terminated string at `off` (lossy for non-UTF-8 names).
+pub fn read_cstr(data: &[u8], off: usize) -> Result<String, RelocError> {
+    let rest = data.get(off..).ok_or(RelocError::Truncated)?;
+    let len = rest
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or(RelocError::Truncated)?;
+    Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+}
+
+// ============================================================
+// Section headers and loading (given)
+// ============================================================
+
+/// One `Elf64_Shdr`, with its name resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionHeader {
+    pub name: String,
+    pub sh_type: u32,
+    pub addr: u64,
+    pub offset: u64,
+    pub size: u64,
+    /// For symbol tables: index of the string table; for `SHT_RELA`: index of the symbol table.
+    pub link: u32,
+    pub entsize: u64,
+}
+
+/// All section headers of `file`.
+pub fn section_headers(file: &[u8]) -> Result<Vec<SectionHeader>, RelocError> {
+    let shoff = read_u64(file, 0x28)? as usize;
+    let shnum = read_u16(file, 0x3C)? as usize;
+    let shstrndx = read_u16(file, 0x3E)? as usize;
+    let raw = |i: usize| -> Result<(u32, SectionHeader), RelocError> {
+        let off = shoff + i * 64;
+        Ok((
+            read_u32(file, off)?,
+            SectionHeader {
+                name: String::new(),
+                sh_type: read_u32(file, off + 0x04)?,
+                addr: read_u64(file, off + 0x10)?,
+                offset: read_u64(file, off + 0x18)?,
+                size: read_u64(file, off + 0x20)?,
+                link: read_u32(file, off + 0x28)?,
+                entsize: read_u64(file, off + 0x38)?,
+            },
+        ))
+    };
+    let shstrtab = raw(shstrndx)?.1.offset as usize;
+    (0..shnum)
+        .map(|i| {
+            let (name_off, mut sh) = raw(i)?;
+            sh.name = read_cstr(file, shstrtab + name_off as usize)?;
+            Ok(sh)
+        })
+        .collect()
+}
+
+/// A PIE image copied into a flat buffer that stands for `[base, base + mem.len())`.
+#[derive(Debug, Clone)]
+pub struct LoadedImage {
+    pub base: u64,
+    pub mem: Vec<u8>,
+}
+
+impl LoadedImage {
+    /// Check the header and copy every `PT_LOAD` segment to `base + p_vaddr` (BSS stays zero).
+    pub fn load(file: &[u8], base: u64) -> Result<Self, RelocError> {
+        assert_eq!(base % 4096, 0, "load bias must be page aligned");
+        if file.len() < 64 || file[..4] != [0x7f, b'E', b'L', b'F'] {
+            return Err(RelocError::BadMagic);
+        }
+        let machine = read_u16(file, 0x12)?;
+        if machine != EM_RISCV {
+            return Err(RelocError::WrongMachine(machine));
+        }
+        if read_u16(file, 0x10)? != ET_DYN {
+            return Err(RelocError::NotPie);
+        }
+        let phoff = read_u64(file, 0x20)? as usize;
+        let phnum = read_u16(file, 0x38)? as usize;
+        let mut mem = Vec::new();
+        for i in 0..phnum {
+            let off = phoff + i * 56;
+            if read_u32(file, off)? != PT_LOAD {
+                continue;
+            }
+            let p_offset = read_u64(file, off + 0x08)? as usize;
+            let vaddr = read_u64(file, off + 0x10)? as usize;
+            let filesz = read_u64(file, off + 0x20)? as usize;
+            let memsz = read_u64(file, off + 0x28)? as usize;
+            if mem.len() < vaddr + memsz {
+                mem.resize(vaddr + memsz, 0);
+            }
+            let bytes = file
+                .get(p_offset..p_offset + filesz)
+                .ok_or(RelocError::Truncated)?;
+            mem[vaddr..vaddr + filesz].copy_from_slice(bytes);
+        }
+        Ok(Self { base, mem })
+    }
+
+    /// Whether `addr` (absolute) lies inside the image.
+    pub fn contains(&self, addr: u64) -> bool {
+        (self.base..self.base + self.mem.len() as u64).contains(&addr)
+    }
+
+    /// `u64` at absolute address `addr`.
+    pub fn read_u64(&self, addr: u64) -> Result<u64, RelocError> {
+        let off = addr
+            .checked_sub(self.base)
+            .ok_or(RelocError::OutOfImage(addr))?;
+        read_u64(&self.mem, off as usize).map_err(|_| RelocError::OutOfImage(addr))
+    }
+
+    /// Store `value` at absolute address `addr`.
+    pub fn write_u64(&mut self, addr: u64, value: u64) -> Result<(), RelocError> {
+        let off = addr
+            .checked_sub(self.base)
+            .ok_or(RelocError::OutOfImage(addr))? as usize;
+        let slot = self
+            .mem
+            .get_mut(off..off + 8)
+            .ok_or(RelocError::OutOfImage(addr))?;
+        slot.copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    /// NUL-terminated string at absolute address `addr`.
+    pub fn read_cstr(&self, addr: u64) -> Result<String, RelocError> {
+        let off = addr
+            .checked_sub(self.base)
+            .ok_or(RelocError::OutOfImage(addr))?;
+        read_cstr(&self.mem, off as usize)
+    }
+}
+
+// ============================================================
+// Symbols and relocations
+// ============================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    /// Link-time address (add the load bias for the runtime address).
+    pub value: u64,
+    pub size: u64,
+    /// `STB_*`
+    pub bind: u8,
+    /// `STT_*`
+    pub sym_type: u8,
+    /// Section index; `SHN_UNDEF` for symbols defined elsewhere.
+    pub shndx: u16,
+}
+
+impl Symbol {
+    /// Parse the `Elf64_Sym` at `file[off]`; its name is at `strtab_off + st_name`.
+    pub fn parse(file: &[u8], off: usize, strtab_off: usize) -> Result<Self, RelocError> {
+        let st_name = read_u32(file, off)? as usize;
+        let st_info = *file.get(off + 4).ok_or(RelocError::Truncated)?;
+        Ok(Self {
+            name: read_cstr(file, strtab_off + st_name)?,
+            value: read_u64(file, off + 8)?,
+            size: read_u64(file, off + 16)?,
+            bind: st_info >> 4,
+            sym_type: st_info & 0xf,
+            shndx: read_u16(file, off + 6)?,
+        })
+    }
+
+    pub fn is_defined(&self) -> bool {
+        self.shndx != SHN_UNDEF
+    }
+}
+
+/// Every symbol in the symbol table section `symtab` (`.symtab` or `.dynsym`), including the
+/// null symbol at index 0, so that relocation `r_sym` values index straight into the result.
+pub fn read_symbols(
+    file: &[u8],
+    sections: &[SectionHeader],
+    symtab: &SectionHeader,
+) -> Result<Vec<Symbol>, RelocError> {
+    let strtab = sections
+        .get(symtab.link as usize)
+        .ok_or(RelocError::MissingSection("strtab"))?;
+    (0..symtab.size as usize / SYM_SIZE)
+        .map(|i| {
+            Symbol::parse(
+                file,
+                symtab.offset as usize + i * SYM_SIZE,
+                strtab.offset as usize,
+            )
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rela {
+    pub offset: u64,
+    pub sym: u32,
+    pub rel_type: u32,
+    pub addend: i64,
+}
+
+impl Rela {
+    /// Parse the `Elf64_Rela` at `file[off]`.
+    pub fn parse(file: &[u8], off: usize) -> Result<Self, RelocError> {
+        let info = read_u64(file, off + 8)?;
+        Ok(Self {
+            offset: read_u64(file, off)?,
+            sym: (info >> 32) as u32,
+            rel_type: info as u32,
+            addend: read_u64(file, off + 16)? as i64,
+        })
+    }
+}
+
+/// Apply every relocation in `.rela.dyn` to `image` and return how many were applied.
+///
+/// `dynsym` is the symbol table `.rela.dyn` refers to (see `read_symbols`). For each entry:
+/// - `R_RISCV_NONE`: skip (not counted)
+/// - `R_RISCV_RELATIVE`: write `base + addend` at `base + offset`
+/// - `R_RISCV_64`: `S + addend`, with `S = base + value` for a defined symbol, else
+///   `imports(name)`, else 0 for a weak symbol, else `UndefinedSymbol(name)`
+/// - anything else: `UnsupportedRelocation(type)`
+///
+/// Use `wrapping_add` for `base + addend` (the addend is signed).
+pub fn apply_relocations(
+    image: &mut LoadedImage,
+    relas: &[Rela],
+    dynsym: &[Symbol],
+    imports: &dyn Fn(&str) -> Option<u64>,
+) -> Result<usize, RelocError> {
+    let base = image.base;
+    let mut applied = 0;
+    for r in relas {
+        let value = match r.rel_type {
+            R_RISCV_NONE => continue,
+            R_RISCV_RELATIVE => base.wrapping_add(r.addend as u64),
+            R_RISCV_64 => {
+                let sym = dynsym.get(r.sym as usize).ok_or(RelocError::Truncated)?;
+                let s = if sym.is_defined() {
+                    base + sym.value
+                } else if let Some(addr) = imports(&sym.name) {
+                    addr
+                } else if sym.bind == STB_WEAK {
+                    0
+                } else {
+                    return Err(RelocError::UndefinedSymbol(sym.name.clone()));
+                };
+                s.wrapping_add(r.addend as u64)
+            }
+            t => return Err(RelocError::UnsupportedRelocation(t)),
+        };
+        image.write_u64(base + r.offset, value)?;
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+/// A loaded and relocated PIE.
+pub struct Pie {
+    pub image: LoadedImage,
+    /// Entry point at its runtime address.
+    pub entry: u64,
+    /// `.symtab`, for `resolve`.
+    symbols: Vec<Symbol>,
+}
+
+impl Pie {
+    /// Load `file` at `base`, apply `.rela.dyn` (importing undefined symbols from `imports`) and
+    /// keep `.symtab` for lookups.
+    pub fn load(
+        file: &[u8],
+        base: u64,
+        imports: &dyn Fn(&str) -> Option<u64>,
+    ) -> Result<Self, RelocError> {
+        let mut image = LoadedImage::load(file, base)?;
+        let sections = section_headers(file)?;
+        let find = |name: &'static str| {
+            sections
+                .iter()
+                .find(|s| s.name == name)
+                .ok_or(RelocError::MissingSection(name))
+        };
+        let rela_dyn = find(".rela.dyn")?;
+        let dynsym = read_symbols(file, &sections, &sections[rela_dyn.link as usize])?;
+        let relas = (0..rela_dyn.size as usize / RELA_SIZE)
+            .map(|i| Rela::parse(file, rela_dyn.offset as usize + i * RELA_SIZE))
+            .collect::<Result<Vec<_>, _>>()?;
+        apply_relocations(&mut image, &relas, &dynsym, imports)?;
+        let symbols = read_symbols(file, &sections, find(".symtab")?)?;
+        Ok(Self {
+            entry: base + read_u64(file, 0x18)?,
+            image,
+            symbols,
+        })
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Runtime address of the exported symbol `name`: a defined `GLOBAL` or `WEAK` symbol
+    /// (local symbols and undefined imports give `None`).
+    pub fn resolve(&self, name: &str) -> Option<u64> {
+        self.symbols
+            .iter()
+            .find(|s| {
+                s.name == name && s.is_defined() && (s.bind == STB_GLOBAL || s.bind == STB_WEAK)
+            })
+            .map(|s| self.image.base + s.value)
+    }
+}
@@ -0,0 +1,428 @@
+//! # Auxiliary Vector and Initial User Stack
+//!
+//! In this exercise, you build the stack a new program finds when `execve` jumps to its entry
+//! point: `argc`, the `argv` and `envp` pointer arrays, the auxiliary vector, and the strings they
+//! point to — laid out exactly as the System V ABI (and every libc's `_start`) expects.
+//!
+//! ## Layout (addresses grow upwards)
+//! ```text
+//!            +---------------------------+ <- top
+//!            | argv / envp strings, NUL- |
+//!            | terminated; 16 random     |
+//!            | bytes for AT_RANDOM       |
+//!            +---------------------------+
+//!            | padding (sp ends up 16-aligned)
+//!            +---------------------------+
+//!            | auxv: (type, value) pairs |
+//!            |       ..., AT_NULL, 0     |
+//!            | envp[0] .. envp[m-1], NULL|
+//!            | argv[0] .. argv[n-1], NULL|
+//! sp ----->  | argc                      |
+//!            +---------------------------+
+//! ```
+//! All slots below the strings are 8-byte words; `sp` must be a multiple of 16.
+//!
+//! ## Auxiliary vector
+//! The kernel tells the program (or its dynamic linker) about itself: where its program headers
+//! were loaded (`AT_PHDR`, `AT_PHENT`, `AT_PHNUM`), the page size (`AT_PAGESZ`), its entry point
+//! (`AT_ENTRY`), and a pointer to 16 random bytes (`AT_RANDOM`, used for stack canaries and
+//! pointer mangling).
+//!
+//! `auxv_from_elf` reads these from `../fixtures/hello.elf`.
+
+pub const AT_NULL: u64 = 0;
+pub const AT_PHDR: u64 = 3;
+pub const AT_PHENT: u64 = 4;
+pub const AT_PHNUM: u64 = 5;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64 = 9;
+pub const AT_RANDOM: u64 = 25;
+
+pub const PAGE_SIZE: u64 = 4096;
+pub const PT_LOAD: u32 = 1;
+pub const PT_PHDR: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+    /// The image does not fit into the stack.
+    Overflow,
+    /// Malformed ELF file passed to `auxv_from_elf`.
+    BadElf,
+}
+
+/// Writes a stack image downwards from `top` into a buffer standing for `[top - size, top)`.
+pub struct StackWriter {
+    top: u64,
+    sp: u64,
+    buf: Vec<u8>,
+}
+
+impl StackWriter {
+    pub fn new(top: u64, size: usize) -> Self {
+        Self {
+            top,
+            sp: top,
+            buf: vec![0; size],
+        }
+    }
+
+    /// Lowest address of the stack area.
+    pub fn bottom(&self) -> u64 {
+        self.top - self.buf.len() as u64
+    }
+
+    pub fn sp(&self) -> u64 {
+        self.sp
+    }
+
+    /// Move `sp` down by `bytes.len()`, copy `bytes` to the new `sp`, and return it.
+    /// `Overflow` (and nothing changed) if that would go below `bottom()`.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<u64, StackError> {
+        // TODO
+        todo!()
+    }
+
+    pub fn push_u64(&mut self, value: u64) -> Result<u64, StackError> {
+        self.push_bytes(&value.to_le_bytes())
+    }
+
+    /// Move `sp` down to a multiple of `align` (a power of two). `Overflow` if that would go
+    /// below `bottom()`.
+    pub fn align_down(&mut self, align: u64) -> Result<(), StackError> {
+        // TODO
+        todo!()
+    }
+
+    /// The finished image.
+    pub fn finish(self) -> StackImage {
+        StackImage {
+            top: self.top,
+            sp: self.sp,
+            mem: self.buf,
+        }
+    }
+}
+
+/// A user stack: `mem` holds `[top - mem.len(), top)`, the program starts with `sp`.
+#[derive(Debug, Clone)]
+pub struct StackImage {
+    pub top: u64,
+    pub sp: u64,
+    pub mem: Vec<u8>,
+}
+
+impl StackImage {
+    fn offset(&self, addr: u64) -> usize {
+        let bottom = self.top - self.mem.len() as u64;
+        assert!(
+            (bottom..self.top).contains(&addr),
+            "address {addr:#x} outside the stack"
+        );
+        (addr - bottom) as usize
+    }
+
+    pub fn read_u64(&self, addr: u64) -> u64 {
+        let off = self.offset(addr);
+        u64::from_le_bytes(self.mem[off..off + 8].try_into().unwrap())
+    }
+
+    pub fn read_bytes(&self, addr: u64, len: usize) -> &[u8] {
+        let off = self.offset(addr);
+        &self.mem[off..off + len]
+    }
+
+    /// NUL-terminated string at `addr`.
+    pub fn read_cstr(&self, addr: u64) -> String {
+        let off = self.offset(addr);
+        let len = self.mem[off..]
+            .iter()
+            .position(|&b| b == 0)
+            .expect("unterminated string");
+        String::from_utf8_lossy(&self.mem[off..off + len]).into_owned()
+    }
+}
+
+/// Build the initial stack for `argv` / `envp` / `auxv` below `top` (the stack is `size`
+/// bytes). `AT_RANDOM` and the terminating `AT_NULL` are appended to `auxv` here.
+///
+/// 1. Push every argv and envp string with its NUL, remembering each address.
+/// 2. Push the 16 `random` bytes; remember their address for `AT_RANDOM`.
+/// 3. `align_down(16)`. Count the words still to come: `1 + (argc + 1) + (envc + 1) +
+///    2 * (auxv.len() + 2)`; if the count is odd, push one 0 word so `sp` ends up 16-aligned.
+/// 4. Push from the top of the table down: `AT_NULL, 0`, then `AT_RANDOM, addr`, then `auxv` in
+///    reverse (value first, then type, so each pair reads `type, value` upwards); a 0 and the
+///    envp pointers in reverse; a 0 and the argv pointers in reverse; finally `argc`.
+pub fn build_initial_stack(
+    top: u64,
+    size: usize,
+    argv: &[&str],
+    envp: &[&str],
+    auxv: &[(u64, u64)],
+    random: [u8; 16],
+) -> Result<StackImage, StackError> {
+    // TODO
+    todo!()
+}
+
+/// What `_start` sees: the inverse of `build_initial_stack`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitialStack {
+    pub argv: Vec<String>,
+    pub envp: Vec<String>,
+    /// In stack order, including `AT_NULL`.
+    pub auxv: Vec<(u64, u64)>,
+}
+
+/// Walk the image from `sp` like a libc `_start` does.
+pub fn parse_initial_stack(image: &StackImage) -> InitialStack {
+    let mut p = image.sp;
+    let mut next = || {
+        let v = image.read_u64(p);
+        p += 8;
+        v
+    };
+    let argc = next();
+    let argv = (0..argc).map(|_| image.read_cstr(next())).collect();
+    assert_eq!(next(), 0, "argv not NULL-terminated");
+    let mut envp = Vec::new();
+    loop {
+        match next() {
+            0 => break,
+            ptr => envp.push(image.read_cstr(ptr)),
+        }
+    }
+    let mut auxv = Vec::new();
+    loop {
+        let (ty, val) = (next(), next());
+        auxv.push((ty, val));
+        if ty == AT_NULL {
+            break;
+        }
+    }
+    InitialStack { argv, envp, auxv }
+}
+
+fn read_u16(data: &[u8], off: usize) -> Result<u16, StackError> {
+    let b = data.get(off..off + 2).ok_or(StackError::BadElf)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], off: usize) -> Result<u32, StackError> {
+    let b = data.get(off..off + 4).ok_or(StackError::BadElf)?;
+    Ok(u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], off: usize) -> Result<u64, StackError> {
+    let b = data.get(off..off + 8).ok_or(StackError::BadElf)?;
+    Ok(u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// The auxv entries a loader derives from a (statically linked) ELF file, in this order:
+/// `AT_PHDR`, `AT_PHENT`, `AT_PHNUM`, `AT_PAGESZ`, `AT_ENTRY`.
+///
+/// `AT_PHDR` is the *virtual* address of the program header table: the `p_vaddr` of the
+/// `PT_PHDR` entry if there is one, otherwise `p_vaddr - p_offset + e_phoff` of the `PT_LOAD`
+/// segment whose file range contains `e_phoff`. `BadElf` if neither exists (or on truncation).
+/// Offsets: `e_entry` 0x18, `e_phoff` 0x20, `e_phentsize` 0x36, `e_phnum` 0x38; in each program
+/// header `p_type` 0x00, `p_offset` 0x08, `p_vaddr` 0x10, `p_filesz` 0x20.
+pub fn auxv_from_elf(file: &[u8]) -> Result<Vec<(u64, u64)>, StackError> {
+    // TODO
+    todo!()
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_writer_push_and_align",
+            "push_bytes moves sp down by the length first and copies to the new sp; align_down rounds sp down to a multiple of align",
+        ),
+        (
+            "test_writer_overflow",
+            "check against bottom() before changing anything: a failed push leaves sp as it was",
+        ),
+        (
+            "test_sp_alignment_for_all_shapes",
+            "the final sp (at argc) must be 16-byte aligned: add a padding word when the number of words is odd",
+        ),
+        (
+            "test_table_layout",
+            "from the top: argc, argv pointers, 0, envp pointers, 0, auxv pairs ending with AT_NULL",
+        ),
+        (
+            "test_auxv_phdr_without_pt_phdr",
+            "without PT_PHDR, AT_PHDR is p_vaddr - p_offset + e_phoff of the PT_LOAD segment containing e_phoff",
+        ),
+    ];
+
+    static HELLO: &[u8] = include_bytes!("../../fixtures/hello.elf");
+
+    const TOP: u64 = 0x4000_0000;
+    const SIZE: usize = 16 * 1024;
+    const RANDOM: [u8; 16] = *b"0123456789abcdef";
+
+    fn build(argv: &[&str], envp: &[&str], auxv: &[(u64, u64)]) -> StackImage {
+        build_initial_stack(TOP, SIZE, argv, envp, auxv, RANDOM).unwrap()
+    }
+
+    #[test]
+    fn test_writer_push_and_align() {
+        let mut w = StackWriter::new(TOP, 64);
+        assert_eq_with_hint!(w.push_bytes(b"abc").unwrap(), TOP - 3);
+        assert_eq_with_hint!(w.sp(), TOP - 3);
+        w.align_down(16).unwrap();
+        assert_eq_with_hint!(w.sp(), TOP - 16);
+        w.align_down(16).unwrap();
+        assert_eq_with_hint!(w.sp(), TOP - 16, "already aligned");
+        assert_eq_with_hint!(w.push_u64(0x1122).unwrap(), TOP - 24);
+        let img = w.finish();
+        assert_eq_with_hint!(img.read_bytes(TOP - 3, 3), b"abc");
+        assert_eq_with_hint!(img.read_u64(TOP - 24), 0x1122);
+    }
+
+    #[test]
+    fn test_writer_overflow() {
+        let mut w = StackWriter::new(TOP, 16);
+        w.push_bytes(&[1; 12]).unwrap();
+        assert_eq_with_hint!(w.push_bytes(&[2; 8]), Err(StackError::Overflow));
+        assert_eq_with_hint!(w.sp(), TOP - 12, "failed push changes nothing");
+        w.push_bytes(&[3; 4]).unwrap();
+        assert_eq_with_hint!(w.sp(), w.bottom());
+        assert_eq_with_hint!(
+            build_initial_stack(TOP, 64, &["x"; 8], &[], &[], RANDOM).err(),
+            Some(StackError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let auxv = [(AT_PAGESZ, 4096), (AT_ENTRY, 0x11168)];
+        let img = build(
+            &["/bin/hello", "-v", "world"],
+            &["HOME=/root", "TERM=vt100"],
+            &auxv,
+        );
+        let parsed = parse_initial_stack(&img);
+        assert_eq!(parsed.argv, ["/bin/hello", "-v", "world"]);
+        assert_eq!(parsed.envp, ["HOME=/root", "TERM=vt100"]);
+        assert_eq!(parsed.auxv.len(), 4);
+        assert_eq!(&parsed.auxv[..2], &auxv);
+        assert_eq!(parsed.auxv[2].0, AT_RANDOM);
+        assert_eq!(parsed.auxv[3], (AT_NULL, 0));
+    }
+
+    #[test]
+    fn test_at_random_points_to_random_bytes() {
+        let img = build(&["a"], &[], &[]);
+        let parsed = parse_initial_stack(&img);
+        let (_, addr) = parsed
+            .auxv
+            .iter()
+            .find(|(t, _)| *t == AT_RANDOM)
+            .copied()
+            .unwrap();
+        assert_eq!(img.read_bytes(addr, 16), &RANDOM);
+    }
+
+    #[test]
+    fn test_sp_alignment_for_all_shapes() {
+        for argc in 0..4 {
+            for envc in 0..4 {
+                for auxc in 0..3 {
+                    let argv: Vec<&str> = ["prog", "a", "bb", "ccc"][..argc].to_vec();
+                    let envp: Vec<&str> = ["X=1", "YY=2", "ZZZ=3", "W"][..envc].to_vec();
+                    let auxv: Vec<(u64, u64)> =
+                        [(AT_PAGESZ, 4096), (AT_ENTRY, 0x1000)][..auxc].to_vec();
+                    let img = build(&argv, &envp, &auxv);
+                    assert_eq_with_hint!(
+                        img.sp % 16,
+                        0,
+                        "argc={argc} envc={envc} auxc={auxc}: sp={:#x}",
+                        img.sp
+                    );
+                    let parsed = parse_initial_stack(&img);
+                    assert_eq_with_hint!(parsed.argv, argv);
+                    assert_eq_with_hint!(parsed.envp, envp);
+                    assert_eq_with_hint!(parsed.auxv.len(), auxc + 2);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_table_layout() {
+        let img = build(&["prog", "x"], &["A=b"], &[(AT_PAGESZ, 4096)]);
+        let sp = img.sp;
+        assert_eq_with_hint!(img.read_u64(sp), 2, "argc");
+        assert_eq_with_hint!(img.read_cstr(img.read_u64(sp + 8)), "prog");
+        assert_eq_with_hint!(img.read_cstr(img.read_u64(sp + 16)), "x");
+        assert_eq_with_hint!(img.read_u64(sp + 24), 0, "argv terminator");
+        assert_eq_with_hint!(img.read_cstr(img.read_u64(sp + 32)), "A=b");
+        assert_eq_with_hint!(img.read_u64(sp + 40), 0, "envp terminator");
+        assert_eq_with_hint!(
+            (img.read_u64(sp + 48), img.read_u64(sp + 56)),
+            (AT_PAGESZ, 4096)
+        );
+        assert_eq_with_hint!(img.read_u64(sp + 64), AT_RANDOM);
+        assert_eq_with_hint!((img.read_u64(sp + 80), img.read_u64(sp + 88)), (AT_NULL, 0));
+        // The strings live above the tables.
+        for i in 0..2 {
+            assert_with_hint!(img.read_u64(sp + 8 + 8 * i) > sp + 88);
+        }
+    }
+
+    #[test]
+    fn test_empty_argv_and_envp() {
+        let img = build(&[], &[], &[]);
+        assert_eq!(img.read_u64(img.sp), 0);
+        let parsed = parse_initial_stack(&img);
+        assert!(parsed.argv.is_empty() && parsed.envp.is_empty());
+        assert_eq!(parsed.auxv.len(), 2);
+    }
+
+    #[test]
+    fn test_auxv_from_elf() {
+        assert_eq!(
+            auxv_from_elf(HELLO).unwrap(),
+            vec![
+                (AT_PHDR, 0x10040),
+                (AT_PHENT, 56),
+                (AT_PHNUM, 5),
+                (AT_PAGESZ, PAGE_SIZE),
+                (AT_ENTRY, 0x11168),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_auxv_phdr_without_pt_phdr() {
+        // Turn PT_PHDR (program header 0) into PT_NULL: AT_PHDR must come from the PT_LOAD that
+        // maps the start of the file.
+        let mut elf = HELLO.to_vec();
+        elf[64..68].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq_with_hint!(auxv_from_elf(&elf).unwrap()[0], (AT_PHDR, 0x10040));
+        assert_eq_with_hint!(auxv_from_elf(&HELLO[..100]), Err(StackError::BadElf));
+    }
+
+    #[test]
+    fn test_full_exec_stack() {
+        let mut auxv = auxv_from_elf(HELLO).unwrap();
+        let img = build(&["hello"], &["PATH=/bin"], &auxv);
+        auxv.push((AT_RANDOM, 0));
+        auxv.push((AT_NULL, 0));
+        let mut parsed = parse_initial_stack(&img);
+        parsed.auxv[5].1 = 0; // AT_RANDOM address varies
+        assert_eq!(parsed.auxv, auxv);
+        assert!(img.sp >= TOP - SIZE as u64 && img.sp < TOP);
+    }
+}
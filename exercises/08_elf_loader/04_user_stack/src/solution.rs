@@ -0,0 +1,304 @@
+//! # Auxiliary Vector and Initial User Stack
+//!
+//! In this exercise, you build the stack a new program finds when `execve` jumps to its entry
+//! point: `argc`, the `argv` and `envp` pointer arrays, the auxiliary vector, and the strings they
+//! point to — laid out exactly as the System V ABI (and every libc's `_start`) expects.
+//!
+//! ## Layout (addresses grow upwards)
+//! ```text
+//!            +---------------------------+ <- top
+//!            | argv / envp strings, NUL- |
+//!            | terminated; 16 random     |
+//!            | bytes for AT_RANDOM       |
+//!            +---------------------------+
+//!            | padding (sp ends up 16-aligned)
+//!            +---------------------------+
+//!            | auxv: (type, value) pairs |
+//!            |       ..., AT_NULL, 0     |
+//!            | envp[0] .. envp[m-1], NULL|
+//!            | argv[0] .. argv[n-1], NULL|
+//! sp ----->  | argc                      |
+//!            +---------------------------+
+//! ```
+//! All slots below the strings are 8-byte words; `sp` must be a multiple of 16.
+//!
+//! ## Auxiliary vector
+//! The kernel tells the program (or its dynamic linker) about itself: where its program headers
+//! were loaded (`AT_PHDR`, `AT_PHENT`, `AT_PHNUM`), the page size (`AT_PAGESZ`), its entry point
+//! (`AT_ENTRY`), and a pointer to 16 random bytes (`AT_RANDOM`, used for stack canaries and
+//! pointer mangling).
+//!
+//! `auxv_from_elf` reads these from `../fixtures/hello.elf`.
+
+pub const AT_NULL: u64 = 0;
+pub const AT_PHDR: u64 = 3;
+pub const AT_PHENT: u64 = 4;
+pub const AT_PHNUM: u64 = 5;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_ENTRY: u64 = 9;
+pub const AT_RANDOM: u64 = 25;
+
+pub const PAGE_SIZE: u64 = 4096;
+pub const PT_LOAD: u32 = 1;
+pub const PT_PHDR: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+    /// The image does not fit into the stack.
+    Overflow,
+    /// Malformed ELF file passed to `auxv_from_elf`.
+    BadElf,
+}
+
+/// Writes a stack image downwards from `top` into a buffer standing for `[top - size, top)`.
+pub struct StackWriter {
+    top: u64,
+    sp: u64,
+    buf: Vec<u8>,
+}
+
+impl StackWriter {
+    pub fn new(top: u64, size: usize) -> Self {
+        Self {
+            top,
+            sp: top,
+            buf: vec![0; size],
+        }
+    }
+
+    /// Lowest address of the stack area.
+    pub fn bottom(&self) -> u64 {
+        self.top - self.buf.len() as u64
+    }
+
+    pub fn sp(&self) -> u64 {
+        self.sp
+    }
+
+    /// Move `sp` down by `bytes.len()`, copy `bytes` to the new `sp`, and return it.
+    /// `Overflow` (and nothing changed) if that would go below `bottom()`.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<u64, StackError> {
+        let sp = self
+            .sp
+            .checked_sub(bytes.len() as u64)
+            .filter(|&sp| sp >= self.bottom())
+            .ok_or(StackError::Overflow)?;
+        let off = (sp - self.bottom()) as usize;
+        self.buf[off..off + bytes.len()].copy_from_slice(bytes);
+        self.sp = sp;
+        Ok(sp)
+    }
+
+    pub fn push_u64(&mut self, value: u64) -> Result<u64, StackError> {
+        self.push_bytes(&value.to_le_bytes())
+    }
+
+    /// Move `sp` down to a multiple of `align` (a power of two). `Overflow` if that would go
+    /// below `bottom()`.
+    pub fn align_down(&mut self, align: u64) -> Result<(), StackError> {
+        let sp = self.sp & !(align - 1);
+        if sp < self.bottom() {
+            return Err(StackError::Overflow);
+        }
+        self.sp = sp;
+        Ok(())
+    }
+
+    /// The finished image.
+    pub fn finish(self) -> StackImage {
+        StackImage {
+            top: self.top,
+            sp: self.sp,
+            mem: self.buf,
+        }
+    }
+}
+
+/// A user stack: `mem` holds `[top - mem.len(), top)`, the program starts with `sp`.
+#[derive(Debug, Clone)]
+pub struct StackImage {
+    pub top: u64,
+    pub sp: u64,
+    pub mem: Vec<u8>,
+}
+
+impl StackImage {
+    fn offset(&self, addr: u64) -> usize {
+        let bottom = self.top - self.mem.len() as u64;
+        assert!(
+            (bottom..self.top).contains(&addr),
+            "address {addr:#x} outside the stack"
+        );
+        (addr - bottom) as usize
+    }
+
+    pub fn read_u64(&self, addr: u64) -> u64 {
+        let off = self.offset(addr);
+        u64::from_le_bytes(self.mem[off..off + 8].try_into().unwrap())
+    }
+
+    pub fn read_bytes(&self, addr: u64, len: usize) -> &[u8] {
+        let off = self.offset(addr);
+        &self.mem[off..off + len]
+    }
+
+    /// NUL-terminated string at `addr`.
+    pub fn read_cstr(&self, addr: u64) -> String {
+        let off = self.offset(addr);
+        let len = self.mem[off..]
+            .iter()
+            .position(|&b| b == 0)
+            .expect("unterminated string");
+        String::from_utf8_lossy(&self.mem[off..off + len]).into_owned()
+    }
+}
+
+/// Build the initial stack for `argv` / `envp` / `auxv` below `top` (the stack is `size`
+/// bytes). `AT_RANDOM` and the terminating `AT_NULL` are appended to `auxv` here.
+///
+/// 1. Push every argv and envp string with its NUL, remembering each address.
+/// 2. Push the 16 `random` bytes; remember their address for `AT_RANDOM`.
+/// 3. `align_down(16)`. Count the words still to come: `1 + (argc + 1) + (envc + 1) +
+///    2 * (auxv.len() + 2)`; if the count is odd, push one 0 word so `sp` ends up 16-aligned.
+/// 4. Push from the top of the table down: `AT_NULL, 0`, then `AT_RANDOM, addr`, then `auxv` in
+///    reverse (value first, then type, so each pair reads `type, value` upwards); a 0 and the
+///    envp pointers in reverse; a 0 and the argv pointers in reverse; finally `argc`.
+pub fn build_initial_stack(
+    top: u64,
+    size: usize,
+    argv: &[&str],
+    envp: &[&str],
+    auxv: &[(u64, u64)],
+    random: [u8; 16],
+) -> Result<StackImage, StackError> {
+    let mut w = StackWriter::new(top, size);
+    let push_str = |w: &mut StackWriter, s: &str| -> Result<u64, StackError> {
+        w.push_bytes(&[0])?;
+        w.push_bytes(s.as_bytes())
+    };
+    let argv_ptrs = argv
+        .iter()
+        .map(|s| push_str(&mut w, s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let envp_ptrs = envp
+        .iter()
+        .map(|s| push_str(&mut w, s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let random_addr = w.push_bytes(&random)?;
+    w.align_down(16)?;
+    let words = 1 + (argv.len() + 1) + (envp.len() + 1) + 2 * (auxv.len() + 2);
+    if words % 2 == 1 {
+        w.push_u64(0)?;
+    }
+    w.push_u64(0)?;
+    w.push_u64(AT_NULL)?;
+    w.push_u64(random_addr)?;
+    w.push_u64(AT_RANDOM)?;
+    for &(ty, val) in auxv.iter().rev() {
+        w.push_u64(val)?;
+        w.push_u64(ty)?;
+    }
+    w.push_u64(0)?;
+    for &p in envp_ptrs.iter().rev() {
+        w.push_u64(p)?;
+    }
+    w.push_u64(0)?;
+    for &p in argv_ptrs.iter().rev() {
+        w.push_u64(p)?;
+    }
+    w.push_u64(argv.len() as u64)?;
+    Ok(w.finish())
+}
+
+/// What `_start` sees: the inverse of `build_initial_stack`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitialStack {
+    pub argv: Vec<String>,
+    pub envp: Vec<String>,
+    /// In stack order, including `AT_NULL`.
+    pub auxv: Vec<(u64, u64)>,
+}
+
+/// Walk the image from `sp` like a libc `_start` does.
+pub fn parse_initial_stack(image: &StackImage) -> InitialStack {
+    let mut p = image.sp;
+    let mut next = || {
+        let v = image.read_u64(p);
+        p += 8;
+        v
+    };
+    let argc = next();
+    let argv = (0..argc).map(|_| image.read_cstr(next())).collect();
+    assert_eq!(next(), 0, "argv not NULL-terminated");
+    let mut envp = Vec::new();
+    loop {
+        match next() {
+            0 => break,
+            ptr => envp.push(image.read_cstr(ptr)),
+        }
+    }
+    let mut auxv = Vec::new();
+    loop {
+        let (ty, val) = (next(), next());
+        auxv.push((ty, val));
+        if ty == AT_NULL {
+            break;
+        }
+    }
+    InitialStack { argv, envp, auxv }
+}
+
+fn read_u16(data: &[u8], off: usize) -> Result<u16, StackError> {
+    let b = data.get(off..off + 2).ok_or(StackError::BadElf)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], off: usize) -> Result<u32, StackError> {
+    let b = data.get(off..off + 4).ok_or(StackError::BadElf)?;
+    Ok(u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], off: usize) -> Result<u64, StackError> {
+    let b = data.get(off..off + 8).ok_or(StackError::BadElf)?;
+    Ok(u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+/// The auxv entries a loader derives from a (statically linked) ELF file, in this order:
+/// `AT_PHDR`, `AT_PHENT`, `AT_PHNUM`, `AT_PAGESZ`, `AT_ENTRY`.
+///
+/// `AT_PHDR` is the *virtual* address of the program header table: the `p_vaddr` of the
+/// `PT_PHDR` entry if there is one, otherwise `p_vaddr - p_offset + e_phoff` of the `PT_LOAD`
+/// segment whose file range contains `e_phoff`. `BadElf` if neither exists (or on truncation).
+/// Offsets: `e_entry` 0x18, `e_phoff` 0x20, `e_phentsize` 0x36, `e_phnum` 0x38; in each program
+/// header `p_type` 0x00, `p_offset` 0x08, `p_vaddr` 0x10, `p_filesz` 0x20.
+pub fn auxv_from_elf(file: &[u8]) -> Result<Vec<(u64, u64)>, StackError> {
+    let entry = read_u64(file, 0x18)?;
+    let phoff = read_u64(file, 0x20)?;
+    let phent = read_u16(file, 0x36)? as u64;
+    let phnum = read_u16(file, 0x38)? as u64;
+    let mut phdr = None;
+    let mut from_load = None;
+    for i in 0..phnum {
+        let off = (phoff + i * phent) as usize;
+        let p_type = read_u32(file, off)?;
+        let p_offset = read_u64(file, off + 0x08)?;
+        let p_vaddr = read_u64(file, off + 0x10)?;
+        let p_filesz = read_u64(file, off + 0x20)?;
+        if p_type == PT_PHDR {
+            phdr = Some(p_vaddr);
+        } else if p_type == PT_LOAD
+            && from_load.is_none()
+            && (p_offset..p_offset + p_filesz).contains(&phoff)
+        {
+            from_load = Some(p_vaddr - p_offset + phoff);
+        }
+    }
+    let phdr = phdr.or(from_load).ok_or(StackError::BadElf)?;
+    Ok(vec![
+        (AT_PHDR, phdr),
+        (AT_PHENT, phent),
+        (AT_PHNUM, phnum),
+        (AT_PAGESZ, PAGE_SIZE),
+        (AT_ENTRY, entry),
+    ])
+}
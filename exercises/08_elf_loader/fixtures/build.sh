@@ -0,0 +1,14 @@
+#!/bin/sh
+# Rebuild the ELF fixtures from source. Needs llvm-mc and an ld.lld with riscv64 support;
+# rustup's bundled linker works too: LD="rust-lld -flavor gnu" ./build.sh
+set -e
+cd "$(dirname "$0")"
+LD=${LD:-ld.lld}
+
+llvm-mc -triple=riscv64 -filetype=obj hello.s -o hello.o
+$LD hello.o -o hello.elf
+rm hello.o
+
+llvm-mc -triple=riscv64 -filetype=obj pie.s -o pie.o
+$LD -pie -z dynamic-undefined-weak pie.o -o pie.elf
+rm pie.o
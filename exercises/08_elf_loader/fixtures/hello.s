@@ -0,0 +1,37 @@
+# Statically linked riscv64 Linux program: write(1, msg, 14); counter += 1; exit(0).
+# Layout after linking: headers + .rodata (R), .text (R+X), .data + .bss (R+W, memsz > filesz).
+
+    .section .text
+    .globl _start
+_start:
+    li      a0, 1
+    la      a1, msg
+    li      a2, 14
+    li      a7, 64              # write
+    ecall
+    la      t0, counter
+    ld      t1, 0(t0)
+    addi    t1, t1, 1
+    sd      t1, 0(t0)
+    li      a0, 0
+    li      a7, 93              # exit
+    ecall
+
+    .section .rodata
+msg:
+    .ascii  "Hello, world!\n"
+
+    .data
+    .balign 8
+    .globl  counter
+counter:
+    .dword  0x1122334455667788
+    .globl  greeting
+greeting:
+    .asciz  "data segment"
+
+    .bss
+    .balign 8
+    .globl  scratch
+scratch:
+    .zero   8192
@@ -0,0 +1,30 @@
+# Position-independent riscv64 executable whose .data holds absolute pointers, so loading it at
+# any base needs dynamic relocations:
+#   table[0..3] -> R_RISCV_RELATIVE (pointers into the image itself)
+#   table[3]    -> R_RISCV_64 against `host_hook`, an undefined weak symbol the loader provides
+
+    .weak   host_hook
+
+    .text
+    .globl  _start
+_start:
+    ret
+    .globl  greet
+greet:
+    ret
+
+    .data
+    .balign 8
+    .globl  counter
+counter:
+    .dword  7
+    .globl  table
+table:
+    .dword  greet
+    .dword  counter
+    .dword  msg
+    .dword  host_hook + 16
+
+    .section .rodata
+msg:
+    .asciz  "relocated!"
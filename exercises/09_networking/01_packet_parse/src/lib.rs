@@ -0,0 +1,551 @@
+//! # Ethernet / IPv4 / UDP Packets
+//!
+//! In this exercise, you parse and build the three headers of a UDP datagram on an Ethernet
+//! link — the first thing a network stack does with every frame the NIC hands it. Parsing
+//! borrows the input and building writes into a caller-provided buffer: no allocation, so the
+//! code runs in a `no_std` kernel.
+//!
+//! ## Layout (all fields big endian, "network byte order")
+//! ```text
+//! Ethernet (14 bytes)
+//!   0  dst MAC [6]   6  src MAC [6]   12 ethertype u16 (0x0800 = IPv4, 0x0806 = ARP)
+//! IPv4 (20 bytes + options, IHL * 4 in total)
+//!   0  version:4 IHL:4   1 TOS   2 total length u16 (header + payload)
+//!   4  identification u16   6 flags:3 (DF = 0x4000, MF = 0x2000) fragment offset:13
+//!   8  TTL   9 protocol (17 = UDP)   10 header checksum u16
+//!   12 src addr [4]   16 dst addr [4]   20 options (IHL > 5), padded to 4 bytes
+//! UDP (8 bytes)
+//!   0  src port u16   2 dst port u16   4 length u16 (header + data)   6 checksum u16
+//! ```
+//!
+//! ## Internet checksum (RFC 1071)
+//! The one's-complement of the one's-complement sum of the data as big-endian 16-bit words
+//! (an odd trailing byte is padded with a zero). Summing a header *including* its correct
+//! checksum therefore gives `0xffff`, whose complement is 0: that is how a receiver verifies.
+//! UDP's checksum also covers a **pseudo header** (src, dst, 0, protocol, UDP length); a
+//! transmitted checksum of 0 means "none", so a computed 0 is sent as `0xffff`.
+//!
+//! ## Robustness
+//! Every length field comes from the wire and may lie: check it against the buffer before
+//! slicing, and return an error — never panic — on malformed input. Frames may carry padding
+//! after the IPv4 packet (Ethernet's 60-byte minimum), so the IPv4 payload ends at
+//! `total length`, not at the end of the buffer.
+
+#![cfg_attr(not(test), no_std)]
+
+pub use core::net::Ipv4Addr;
+
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+pub const IPPROTO_UDP: u8 = 17;
+
+pub const ETH_HDR_LEN: usize = 14;
+pub const IPV4_MIN_HDR_LEN: usize = 20;
+pub const IPV4_MAX_OPTIONS: usize = 40;
+pub const UDP_HDR_LEN: usize = 8;
+
+const IPV4_DF: u16 = 0x4000;
+const IPV4_MF: u16 = 0x2000;
+
+/// IPv4 option kinds.
+pub const IPOPT_EOL: u8 = 0;
+pub const IPOPT_NOP: u8 = 1;
+pub const IPOPT_RA: u8 = 148;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    /// The buffer ends before the header (or a length it announces).
+    Truncated,
+    /// IPv4 version field is not 4.
+    BadVersion,
+    /// IHL below 5 words.
+    BadHeaderLen,
+    /// IPv4 total length shorter than the header, or over 65535 when building.
+    BadTotalLen,
+    BadChecksum,
+    /// Malformed option list, or options not a multiple of 4 bytes / over 40 when building.
+    BadOptions,
+    /// UDP length field below 8 or beyond the IPv4 payload.
+    BadUdpLen,
+    /// The output buffer is too small.
+    BufferTooSmall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    pub const BROADCAST: Self = Self([0xff; 6]);
+}
+
+/// Big-endian `u16` at `off` (the caller has checked the length).
+pub fn read_be16(buf: &[u8], off: usize) -> u16 {
+    u16::from_be_bytes([buf[off], buf[off + 1]])
+}
+
+pub fn write_be16(buf: &mut [u8], off: usize, v: u16) {
+    buf[off..off + 2].copy_from_slice(&v.to_be_bytes());
+}
+
+/// Add `data` as big-endian 16-bit words to `sum`, without folding the carries. An odd last
+/// byte counts as the high byte of a word whose low byte is 0.
+pub fn sum16(data: &[u8], sum: u32) -> u32 {
+    // TODO
+    todo!()
+}
+
+/// Fold the carries of a `sum16` result back into 16 bits (repeat
+/// `sum = (sum & 0xffff) + (sum >> 16)` until it fits) and return its complement.
+pub fn fold(sum: u32) -> u16 {
+    // TODO
+    todo!()
+}
+
+/// The Internet checksum of `data`.
+pub fn checksum(data: &[u8]) -> u16 {
+    fold(sum16(data, 0))
+}
+
+/// `sum16` of the IPv4 pseudo header that UDP and TCP checksums cover.
+pub fn pseudo_header_sum(src: Ipv4Addr, dst: Ipv4Addr, protocol: u8, len: u16) -> u32 {
+    let mut ph = [0u8; 12];
+    ph[0..4].copy_from_slice(&src.octets());
+    ph[4..8].copy_from_slice(&dst.octets());
+    ph[9] = protocol;
+    write_be16(&mut ph, 10, len);
+    sum16(&ph, 0)
+}
+
+// ---------------------------------------------------------------------------
+// Ethernet
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthernetHeader {
+    pub dst: MacAddr,
+    pub src: MacAddr,
+    pub ethertype: u16,
+}
+
+impl EthernetHeader {
+    /// Split a frame into its header and payload (`Truncated` below 14 bytes).
+    pub fn parse(buf: &[u8]) -> Result<(Self, &[u8]), PacketError> {
+        // TODO
+        todo!()
+    }
+
+    /// Write the header to the start of `buf`; returns its length.
+    pub fn emit(&self, buf: &mut [u8]) -> Result<usize, PacketError> {
+        if buf.len() < ETH_HDR_LEN {
+            return Err(PacketError::BufferTooSmall);
+        }
+        buf[0..6].copy_from_slice(&self.dst.0);
+        buf[6..12].copy_from_slice(&self.src.0);
+        write_be16(buf, 12, self.ethertype);
+        Ok(ETH_HDR_LEN)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// IPv4
+// ---------------------------------------------------------------------------
+
+/// The IPv4 header fields, except those derived when building (version, IHL, total length,
+/// checksum).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Header {
+    pub tos: u8,
+    pub ident: u16,
+    pub dont_frag: bool,
+    pub more_frags: bool,
+    /// In units of 8 bytes.
+    pub frag_offset: u16,
+    pub ttl: u8,
+    pub protocol: u8,
+    pub src: Ipv4Addr,
+    pub dst: Ipv4Addr,
+}
+
+/// A parsed IPv4 packet, borrowing the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Packet<'a> {
+    pub header: Ipv4Header,
+    /// Raw option bytes (`IHL * 4 - 20` of them).
+    pub options: &'a [u8],
+    /// `total length - IHL * 4` bytes; trailing link-layer padding is not included.
+    pub payload: &'a [u8],
+}
+
+impl<'a> Ipv4Packet<'a> {
+    pub fn options(&self) -> OptionsIter<'a> {
+        OptionsIter { rest: self.options }
+    }
+}
+
+impl Ipv4Header {
+    /// Parse and validate an IPv4 packet, checking in this order:
+    /// 1. at least 20 bytes, else `Truncated`
+    /// 2. version 4, else `BadVersion`
+    /// 3. `IHL * 4 >= 20`, else `BadHeaderLen`; the header fits in `buf`, else `Truncated`
+    /// 4. `total length >= IHL * 4`, else `BadTotalLen`; it fits in `buf`, else `Truncated`
+    /// 5. `checksum` of the header (options included) is 0, else `BadChecksum`
+    pub fn parse(buf: &[u8]) -> Result<Ipv4Packet<'_>, PacketError> {
+        // TODO
+        todo!()
+    }
+
+    /// Write this header followed by `options` to the start of `buf`, for a payload of
+    /// `payload_len` bytes that the caller writes after it. Returns the header length.
+    ///
+    /// - `options`: a multiple of 4 bytes, at most 40, else `BadOptions`
+    /// - total length over 65535: `BadTotalLen`; `buf` shorter than the total: `BufferTooSmall`
+    /// - version 4, IHL from the options, flags from `dont_frag` / `more_frags`; compute the
+    ///   checksum over the header with the checksum field at 0, then store it
+    pub fn emit(
+        &self,
+        options: &[u8],
+        payload_len: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, PacketError> {
+        // TODO
+        todo!()
+    }
+}
+
+/// One IPv4 option (`kind`, then for everything but NOP a length byte covering kind and
+/// length, then data).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Option<'a> {
+    pub kind: u8,
+    pub data: &'a [u8],
+}
+
+/// Walks the options of an IPv4 header.
+pub struct OptionsIter<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for OptionsIter<'a> {
+    type Item = Result<Ipv4Option<'a>, PacketError>;
+
+    /// - nothing left, or `IPOPT_EOL`: the list ends (`None`)
+    /// - `IPOPT_NOP`: one byte, empty `data`
+    /// - otherwise `kind, len, data[len - 2]`: a missing length byte, `len < 2` or a length
+    ///   past the end is `Err(BadOptions)`, and ends the iteration (empty `rest`)
+    fn next(&mut self) -> Option<Self::Item> {
+        // TODO
+        todo!()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// UDP
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UdpHeader {
+    pub src_port: u16,
+    pub dst_port: u16,
+}
+
+impl UdpHeader {
+    /// Parse the UDP datagram in an IPv4 payload sent from `src` to `dst`; returns the header
+    /// and the data.
+    ///
+    /// Below 8 bytes: `Truncated`. A length field below 8 or past the buffer: `BadUdpLen`.
+    /// A non-zero checksum must verify: `fold(pseudo_header_sum + sum16(datagram)) == 0`.
+    pub fn parse(buf: &[u8], src: Ipv4Addr, dst: Ipv4Addr) -> Result<(Self, &[u8]), PacketError> {
+        // TODO
+        todo!()
+    }
+
+    /// Write header and `payload` to the start of `buf`; returns the datagram length.
+    ///
+    /// Over 65535 bytes: `BadUdpLen`; `buf` too short: `BufferTooSmall`. The checksum is
+    /// computed with the field at 0; a result of 0 is sent as `0xffff`.
+    pub fn emit(
+        &self,
+        payload: &[u8],
+        src: Ipv4Addr,
+        dst: Ipv4Addr,
+        buf: &mut [u8],
+    ) -> Result<usize, PacketError> {
+        // TODO
+        todo!()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_checksum",
+            "sum16 adds big-endian 16-bit words (an odd last byte is the high half); fold adds the carries back in until the sum fits in 16 bits",
+        ),
+        (
+            "test_malformed_ipv4",
+            "check the length before every read: 20 bytes, version 4, IHL >= 5, IHL * 4 bytes, total length, and the checksum last",
+        ),
+        (
+            "test_padding_after_ip_packet_is_ignored",
+            "the IP payload ends at total length, not at the end of the frame: short Ethernet frames are padded",
+        ),
+        (
+            "test_udp_checksum_and_length",
+            "the UDP checksum also covers the pseudo header (src, dst, protocol, UDP length); a checksum of 0 means none",
+        ),
+        (
+            "test_options_iterator",
+            "NOP is a single byte and EOL ends the list; other options are kind, length (>= 2, counting both bytes), data",
+        ),
+    ];
+
+    const MAC_A: MacAddr = MacAddr([0x02, 0, 0, 0, 0, 0x0a]);
+    const MAC_B: MacAddr = MacAddr([0x02, 0, 0, 0, 0, 0x0b]);
+    const IP_A: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const IP_B: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+
+    /// The IPv4 header example from Wikipedia: 192.168.0.1 -> 192.168.0.199, UDP.
+    const WIKI_HDR: [u8; 20] = [
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00,
+        0x01, 0xc0, 0xa8, 0x00, 0xc7,
+    ];
+
+    fn ip_header() -> Ipv4Header {
+        Ipv4Header {
+            tos: 0,
+            ident: 0x1234,
+            dont_frag: true,
+            more_frags: false,
+            frag_offset: 0,
+            ttl: 64,
+            protocol: IPPROTO_UDP,
+            src: IP_A,
+            dst: IP_B,
+        }
+    }
+
+    /// Build Ethernet + IPv4 (with `options`) + UDP carrying `data` into `buf`; returns the
+    /// frame length.
+    fn build_frame(buf: &mut [u8], options: &[u8], data: &[u8]) -> usize {
+        let eth = EthernetHeader {
+            dst: MAC_B,
+            src: MAC_A,
+            ethertype: ETHERTYPE_IPV4,
+        };
+        let mut off = eth.emit(buf).unwrap();
+        let udp_len = UDP_HDR_LEN + data.len();
+        off += ip_header().emit(options, udp_len, &mut buf[off..]).unwrap();
+        let udp = UdpHeader {
+            src_port: 5000,
+            dst_port: 53,
+        };
+        off + udp.emit(data, IP_A, IP_B, &mut buf[off..]).unwrap()
+    }
+
+    #[test]
+    fn test_checksum() {
+        // RFC 1071 section 3 example: the words sum to 0xddf2.
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq_with_hint!(sum16(&data, 0), 0x2_ddf0);
+        assert_eq_with_hint!(checksum(&data), !0xddf2);
+        assert_eq_with_hint!(sum16(&[0xab], 0), 0xab00, "odd byte is the high half");
+        assert_eq_with_hint!(fold(0xffff), 0);
+        assert_eq_with_hint!(fold(0x1_fffe), 0, "carry folds back in");
+
+        let mut hdr = WIKI_HDR;
+        assert_eq_with_hint!(checksum(&hdr), 0, "a correct header sums to 0xffff");
+        hdr[10..12].fill(0);
+        assert_eq_with_hint!(checksum(&hdr), 0xb861);
+    }
+
+    #[test]
+    fn test_parse_ethernet() {
+        let mut frame = [0u8; 20];
+        frame[..6].copy_from_slice(&MacAddr::BROADCAST.0);
+        frame[6..12].copy_from_slice(&MAC_A.0);
+        frame[12..14].copy_from_slice(&[0x08, 0x06]);
+        let (eth, payload) = EthernetHeader::parse(&frame).unwrap();
+        assert_eq!(
+            eth,
+            EthernetHeader {
+                dst: MacAddr::BROADCAST,
+                src: MAC_A,
+                ethertype: ETHERTYPE_ARP
+            }
+        );
+        assert_eq!(payload.len(), 6);
+        assert_eq!(
+            EthernetHeader::parse(&frame[..13]),
+            Err(PacketError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_parse_ipv4_header() {
+        let mut pkt = [0u8; 0x73];
+        pkt[..20].copy_from_slice(&WIKI_HDR);
+        let ip = Ipv4Header::parse(&pkt).unwrap();
+        assert_eq!(ip.header.src, Ipv4Addr::new(192, 168, 0, 1));
+        assert_eq!(ip.header.dst, Ipv4Addr::new(192, 168, 0, 199));
+        assert_eq!((ip.header.ttl, ip.header.protocol), (64, IPPROTO_UDP));
+        assert!(ip.header.dont_frag && !ip.header.more_frags);
+        assert_eq!(ip.payload.len(), 0x73 - 20);
+        assert!(ip.options.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_ipv4() {
+        let mut pkt = [0u8; 0x73];
+        pkt[..20].copy_from_slice(&WIKI_HDR);
+        let with = |i: usize, v: u8| {
+            let mut p = pkt;
+            p[i] = v;
+            p
+        };
+        use PacketError::*;
+        assert_eq_with_hint!(Ipv4Header::parse(&pkt[..19]), Err(Truncated));
+        assert_eq_with_hint!(Ipv4Header::parse(&with(0, 0x65)), Err(BadVersion));
+        assert_eq_with_hint!(Ipv4Header::parse(&with(0, 0x44)), Err(BadHeaderLen));
+        assert_eq_with_hint!(Ipv4Header::parse(&pkt[..20]), Err(Truncated), "total 0x73");
+        assert_eq_with_hint!(Ipv4Header::parse(&with(3, 19)), Err(BadTotalLen));
+        assert_eq_with_hint!(Ipv4Header::parse(&with(8, 63)), Err(BadChecksum));
+        // IHL = 15 (60 bytes) in a 40-byte buffer: must not panic.
+        let long = with(0, 0x4f);
+        assert_eq_with_hint!(Ipv4Header::parse(&long[..40]), Err(Truncated));
+    }
+
+    #[test]
+    fn test_frame_round_trip_with_options() {
+        let mut buf = [0u8; 128];
+        let options = [
+            IPOPT_RA, 4, 0, 0, IPOPT_NOP, IPOPT_NOP, IPOPT_NOP, IPOPT_EOL,
+        ];
+        let len = build_frame(&mut buf, &options, b"hello");
+        assert_eq!(len, 14 + 28 + 8 + 5);
+
+        let (eth, l3) = EthernetHeader::parse(&buf[..len]).unwrap();
+        assert_eq!(
+            (eth.src, eth.dst, eth.ethertype),
+            (MAC_A, MAC_B, ETHERTYPE_IPV4)
+        );
+        let ip = Ipv4Header::parse(l3).unwrap();
+        assert_eq!(ip.header, ip_header());
+        assert_eq!(ip.options, &options);
+        let (udp, data) = UdpHeader::parse(ip.payload, ip.header.src, ip.header.dst).unwrap();
+        assert_eq!((udp.src_port, udp.dst_port), (5000, 53));
+        assert_eq!(data, b"hello");
+    }
+
+    #[test]
+    fn test_padding_after_ip_packet_is_ignored() {
+        let mut buf = [0u8; 60]; // minimum Ethernet frame
+        let len = build_frame(&mut buf, &[], b"hi");
+        assert_with_hint!(len < 60);
+        buf[len..].fill(0xee);
+        let (_, l3) = EthernetHeader::parse(&buf).unwrap();
+        let ip = Ipv4Header::parse(l3).unwrap();
+        assert_eq_with_hint!(ip.payload.len(), 8 + 2);
+        let (_, data) = UdpHeader::parse(ip.payload, IP_A, IP_B).unwrap();
+        assert_eq_with_hint!(data, b"hi");
+    }
+
+    #[test]
+    fn test_udp_checksum_and_length() {
+        let mut buf = [0u8; 32];
+        let udp = UdpHeader {
+            src_port: 1,
+            dst_port: 2,
+        };
+        let n = udp.emit(b"abc", IP_A, IP_B, &mut buf).unwrap();
+        assert_eq_with_hint!(n, 11);
+        assert_ne!(read_be16(&buf, 6), 0);
+        let dgram = &mut buf[..n];
+        use PacketError::*;
+        assert_eq_with_hint!(
+            UdpHeader::parse(dgram, IP_B, IP_A).map(|r| r.1),
+            Ok(&b"abc"[..]),
+            "pseudo header sum does not depend on the direction"
+        );
+        assert_eq_with_hint!(
+            UdpHeader::parse(dgram, IP_A, Ipv4Addr::new(10, 0, 0, 3)),
+            Err(BadChecksum),
+            "wrong pseudo header"
+        );
+        dgram[8] ^= 1;
+        assert_eq_with_hint!(UdpHeader::parse(dgram, IP_A, IP_B), Err(BadChecksum));
+        write_be16(dgram, 6, 0);
+        assert_with_hint!(
+            UdpHeader::parse(dgram, IP_A, IP_B).is_ok(),
+            "0 means no checksum"
+        );
+        write_be16(dgram, 4, 7);
+        assert_eq_with_hint!(UdpHeader::parse(dgram, IP_A, IP_B), Err(BadUdpLen));
+        write_be16(dgram, 4, 12);
+        assert_eq_with_hint!(UdpHeader::parse(dgram, IP_A, IP_B), Err(BadUdpLen));
+        assert_eq_with_hint!(UdpHeader::parse(&dgram[..7], IP_A, IP_B), Err(Truncated));
+    }
+
+    #[test]
+    fn test_options_iterator() {
+        let opts = [IPOPT_NOP, IPOPT_RA, 4, 0xaa, 0xbb, IPOPT_EOL, 0x99, 0x99];
+        let mut it = OptionsIter { rest: &opts };
+        assert_eq_with_hint!(
+            it.next(),
+            Some(Ok(Ipv4Option {
+                kind: IPOPT_NOP,
+                data: &[]
+            }))
+        );
+        assert_eq_with_hint!(
+            it.next(),
+            Some(Ok(Ipv4Option {
+                kind: IPOPT_RA,
+                data: &[0xaa, 0xbb]
+            }))
+        );
+        assert_eq_with_hint!(it.next(), None, "EOL ends the list");
+
+        for bad in [&[IPOPT_RA][..], &[IPOPT_RA, 1, 0, 0], &[IPOPT_RA, 9, 0, 0]] {
+            let mut it = OptionsIter { rest: bad };
+            assert_eq_with_hint!(it.next(), Some(Err(PacketError::BadOptions)), "{bad:?}");
+            assert_eq_with_hint!(it.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_emit_errors() {
+        let mut buf = [0u8; 64];
+        let hdr = ip_header();
+        use PacketError::*;
+        assert_eq!(hdr.emit(&[1, 1, 1], 0, &mut buf), Err(BadOptions));
+        assert_eq!(hdr.emit(&[1; 44], 0, &mut buf), Err(BadOptions));
+        assert_eq!(hdr.emit(&[], 45, &mut buf), Err(BufferTooSmall));
+        assert_eq!(hdr.emit(&[], 65_516, &mut buf), Err(BadTotalLen));
+        assert_eq!(hdr.emit(&[], 44, &mut buf), Ok(20));
+        let udp = UdpHeader {
+            src_port: 1,
+            dst_port: 2,
+        };
+        assert_eq!(
+            udp.emit(&[0; 60], IP_A, IP_B, &mut buf),
+            Err(BufferTooSmall)
+        );
+        let eth = EthernetHeader {
+            dst: MAC_A,
+            src: MAC_B,
+            ethertype: 0,
+        };
+        assert_eq!(eth.emit(&mut buf[..10]), Err(BufferTooSmall));
+    }
+}
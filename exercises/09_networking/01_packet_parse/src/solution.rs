@@ -0,0 +1,415 @@
+//! # Ethernet / IPv4 / UDP Packets
+//!
+//! In this exercise, you parse and build the three headers of a UDP datagram on an Ethernet
+//! link — the first thing a network stack does with every frame the NIC hands it. Parsing
+//! borrows the input and building writes into a caller-provided buffer: no allocation, so the
+//! code runs in a `no_std` kernel.
+//!
+//! ## Layout (all fields big endian, "network byte order")
+//! ```text
+//! Ethernet (14 bytes)
+//!   0  dst MAC [6]   6  src MAC [6]   12 ethertype u16 (0x0800 = IPv4, 0x0806 = ARP)
+//! IPv4 (20 bytes + options, IHL * 4 in total)
+//!   0  version:4 IHL:4   1 TOS   2 total length u16 (header + payload)
+//!   4  identification u16   6 flags:3 (DF = 0x4000, MF = 0x2000) fragment offset:13
+//!   8  TTL   9 protocol (17 = UDP)   10 header checksum u16
+//!   12 src addr [4]   16 dst addr [4]   20 options (IHL > 5), padded to 4 bytes
+//! UDP (8 bytes)
+//!   0  src port u16   2 dst port u16   4 length u16 (header + data)   6 checksum u16
+//! ```
+//!
+//! ## Internet checksum (RFC 1071)
+//! The one's-complement of the one's-complement sum of the data as big-endian 16-bit words
+//! (an odd trailing byte is padded with a zero). Summing a header *including* its correct
+//! checksum therefore gives `0xffff`, whose complement is 0: that is how a receiver verifies.
+//! UDP's checksum also covers a **pseudo header** (src, dst, 0, protocol, UDP length); a
+//! transmitted checksum of 0 means "none", so a computed 0 is sent as `0xffff`.
+//!
+//! ## Robustness
+//! Every length field comes from the wire and may lie: check it against the buffer before
+//! slicing, and return an error — never panic — on malformed input. Frames may carry padding
+//! after the IPv4 packet (Ethernet's 60-byte minimum), so the IPv4 payload ends at
+//! `total length`, not at the end of the buffer.
+
+pub use core::net::Ipv4Addr;
+
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+pub const IPPROTO_UDP: u8 = 17;
+
+pub const ETH_HDR_LEN: usize = 14;
+pub const IPV4_MIN_HDR_LEN: usize = 20;
+pub const IPV4_MAX_OPTIONS: usize = 40;
+pub const UDP_HDR_LEN: usize = 8;
+
+const IPV4_DF: u16 = 0x4000;
+const IPV4_MF: u16 = 0x2000;
+
+/// IPv4 option kinds.
+pub const IPOPT_EOL: u8 = 0;
+pub const IPOPT_NOP: u8 = 1;
+pub const IPOPT_RA: u8 = 148;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    /// The buffer ends before the header (or a length it announces).
+    Truncated,
+    /// IPv4 version field is not 4.
+    BadVersion,
+    /// IHL below 5 words.
+    BadHeaderLen,
+    /// IPv4 total length shorter than the header, or over 65535 when building.
+    BadTotalLen,
+    BadChecksum,
+    /// Malformed option list, or options not a multiple of 4 bytes / over 40 when building.
+    BadOptions,
+    /// UDP length field below 8 or beyond the IPv4 payload.
+    BadUdpLen,
+    /// The output buffer is too small.
+    BufferTooSmall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    pub const BROADCAST: Self = Self([0xff; 6]);
+}
+
+/// Big-endian `u16` at `off` (the caller has checked the length).
+pub fn read_be16(buf: &[u8], off: usize) -> u16 {
+    u16::from_be_bytes([buf[off], buf[off + 1]])
+}
+
+pub fn write_be16(buf: &mut [u8], off: usize, v: u16) {
+    buf[off..off + 2].copy_from_slice(&v.to_be_bytes());
+}
+
+/// Add `data` as big-endian 16-bit words to `sum`, without folding the carries. An odd last
+/// byte counts as the high byte of a word whose low byte is 0.
+pub fn sum16(data: &[u8], sum: u32) -> u32 {
+    let mut chunks = data.chunks_exact(2);
+    let mut sum = chunks
+        .by_ref()
+        .fold(sum, |s, w| s + u16::from_be_bytes([w[0], w[1]]) as u32);
+    if let [last] = chunks.remainder() {
+        sum += (*last as u32) << 8;
+    }
+    sum
+}
+
+/// Fold the carries of a `sum16` result back into 16 bits (repeat
+/// `sum = (sum & 0xffff) + (sum >> 16)` until it fits) and return its complement.
+pub fn fold(sum: u32) -> u16 {
+    let mut sum = sum;
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// The Internet checksum of `data`.
+pub fn checksum(data: &[u8]) -> u16 {
+    fold(sum16(data, 0))
+}
+
+/// `sum16` of the IPv4 pseudo header that UDP and TCP checksums cover.
+pub fn pseudo_header_sum(src: Ipv4Addr, dst: Ipv4Addr, protocol: u8, len: u16) -> u32 {
+    let mut ph = [0u8; 12];
+    ph[0..4].copy_from_slice(&src.octets());
+    ph[4..8].copy_from_slice(&dst.octets());
+    ph[9] = protocol;
+    write_be16(&mut ph, 10, len);
+    sum16(&ph, 0)
+}
+
+// ---------------------------------------------------------------------------
+// Ethernet
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthernetHeader {
+    pub dst: MacAddr,
+    pub src: MacAddr,
+    pub ethertype: u16,
+}
+
+impl EthernetHeader {
+    /// Split a frame into its header and payload (`Truncated` below 14 bytes).
+    pub fn parse(buf: &[u8]) -> Result<(Self, &[u8]), PacketError> {
+        if buf.len() < ETH_HDR_LEN {
+            return Err(PacketError::Truncated);
+        }
+        let hdr = Self {
+            dst: MacAddr(buf[0..6].try_into().unwrap()),
+            src: MacAddr(buf[6..12].try_into().unwrap()),
+            ethertype: read_be16(buf, 12),
+        };
+        Ok((hdr, &buf[ETH_HDR_LEN..]))
+    }
+
+    /// Write the header to the start of `buf`; returns its length.
+    pub fn emit(&self, buf: &mut [u8]) -> Result<usize, PacketError> {
+        if buf.len() < ETH_HDR_LEN {
+            return Err(PacketError::BufferTooSmall);
+        }
+        buf[0..6].copy_from_slice(&self.dst.0);
+        buf[6..12].copy_from_slice(&self.src.0);
+        write_be16(buf, 12, self.ethertype);
+        Ok(ETH_HDR_LEN)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// IPv4
+// ---------------------------------------------------------------------------
+
+/// The IPv4 header fields, except those derived when building (version, IHL, total length,
+/// checksum).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Header {
+    pub tos: u8,
+    pub ident: u16,
+    pub dont_frag: bool,
+    pub more_frags: bool,
+    /// In units of 8 bytes.
+    pub frag_offset: u16,
+    pub ttl: u8,
+    pub protocol: u8,
+    pub src: Ipv4Addr,
+    pub dst: Ipv4Addr,
+}
+
+/// A parsed IPv4 packet, borrowing the buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Packet<'a> {
+    pub header: Ipv4Header,
+    /// Raw option bytes (`IHL * 4 - 20` of them).
+    pub options: &'a [u8],
+    /// `total length - IHL * 4` bytes; trailing link-layer padding is not included.
+    pub payload: &'a [u8],
+}
+
+impl<'a> Ipv4Packet<'a> {
+    pub fn options(&self) -> OptionsIter<'a> {
+        OptionsIter { rest: self.options }
+    }
+}
+
+impl Ipv4Header {
+    /// Parse and validate an IPv4 packet, checking in this order:
+    /// 1. at least 20 bytes, else `Truncated`
+    /// 2. version 4, else `BadVersion`
+    /// 3. `IHL * 4 >= 20`, else `BadHeaderLen`; the header fits in `buf`, else `Truncated`
+    /// 4. `total length >= IHL * 4`, else `BadTotalLen`; it fits in `buf`, else `Truncated`
+    /// 5. `checksum` of the header (options included) is 0, else `BadChecksum`
+    pub fn parse(buf: &[u8]) -> Result<Ipv4Packet<'_>, PacketError> {
+        if buf.len() < IPV4_MIN_HDR_LEN {
+            return Err(PacketError::Truncated);
+        }
+        if buf[0] >> 4 != 4 {
+            return Err(PacketError::BadVersion);
+        }
+        let ihl = (buf[0] & 0xf) as usize * 4;
+        if ihl < IPV4_MIN_HDR_LEN {
+            return Err(PacketError::BadHeaderLen);
+        }
+        if ihl > buf.len() {
+            return Err(PacketError::Truncated);
+        }
+        let total = read_be16(buf, 2) as usize;
+        if total < ihl {
+            return Err(PacketError::BadTotalLen);
+        }
+        if total > buf.len() {
+            return Err(PacketError::Truncated);
+        }
+        if checksum(&buf[..ihl]) != 0 {
+            return Err(PacketError::BadChecksum);
+        }
+        let flags = read_be16(buf, 6);
+        let header = Ipv4Header {
+            tos: buf[1],
+            ident: read_be16(buf, 4),
+            dont_frag: flags & IPV4_DF != 0,
+            more_frags: flags & IPV4_MF != 0,
+            frag_offset: flags & 0x1fff,
+            ttl: buf[8],
+            protocol: buf[9],
+            src: Ipv4Addr::new(buf[12], buf[13], buf[14], buf[15]),
+            dst: Ipv4Addr::new(buf[16], buf[17], buf[18], buf[19]),
+        };
+        Ok(Ipv4Packet {
+            header,
+            options: &buf[IPV4_MIN_HDR_LEN..ihl],
+            payload: &buf[ihl..total],
+        })
+    }
+
+    /// Write this header followed by `options` to the start of `buf`, for a payload of
+    /// `payload_len` bytes that the caller writes after it. Returns the header length.
+    ///
+    /// - `options`: a multiple of 4 bytes, at most 40, else `BadOptions`
+    /// - total length over 65535: `BadTotalLen`; `buf` shorter than the total: `BufferTooSmall`
+    /// - version 4, IHL from the options, flags from `dont_frag` / `more_frags`; compute the
+    ///   checksum over the header with the checksum field at 0, then store it
+    pub fn emit(
+        &self,
+        options: &[u8],
+        payload_len: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, PacketError> {
+        if options.len() & 3 != 0 || options.len() > IPV4_MAX_OPTIONS {
+            return Err(PacketError::BadOptions);
+        }
+        let ihl = IPV4_MIN_HDR_LEN + options.len();
+        let total = ihl + payload_len;
+        if total > u16::MAX as usize {
+            return Err(PacketError::BadTotalLen);
+        }
+        if buf.len() < total {
+            return Err(PacketError::BufferTooSmall);
+        }
+        let mut flags = self.frag_offset & 0x1fff;
+        if self.dont_frag {
+            flags |= IPV4_DF;
+        }
+        if self.more_frags {
+            flags |= IPV4_MF;
+        }
+        buf[0] = 0x40 | (ihl / 4) as u8;
+        buf[1] = self.tos;
+        write_be16(buf, 2, total as u16);
+        write_be16(buf, 4, self.ident);
+        write_be16(buf, 6, flags);
+        buf[8] = self.ttl;
+        buf[9] = self.protocol;
+        write_be16(buf, 10, 0);
+        buf[12..16].copy_from_slice(&self.src.octets());
+        buf[16..20].copy_from_slice(&self.dst.octets());
+        buf[IPV4_MIN_HDR_LEN..ihl].copy_from_slice(options);
+        let cks = checksum(&buf[..ihl]);
+        write_be16(buf, 10, cks);
+        Ok(ihl)
+    }
+}
+
+/// One IPv4 option (`kind`, then for everything but NOP a length byte covering kind and
+/// length, then data).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Option<'a> {
+    pub kind: u8,
+    pub data: &'a [u8],
+}
+
+/// Walks the options of an IPv4 header.
+pub struct OptionsIter<'a> {
+    pub(crate) rest: &'a [u8],
+}
+
+impl<'a> Iterator for OptionsIter<'a> {
+    type Item = Result<Ipv4Option<'a>, PacketError>;
+
+    /// - nothing left, or `IPOPT_EOL`: the list ends (`None`)
+    /// - `IPOPT_NOP`: one byte, empty `data`
+    /// - otherwise `kind, len, data[len - 2]`: a missing length byte, `len < 2` or a length
+    ///   past the end is `Err(BadOptions)`, and ends the iteration (empty `rest`)
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest;
+        match *rest.first()? {
+            IPOPT_EOL => {
+                self.rest = &[];
+                None
+            }
+            IPOPT_NOP => {
+                self.rest = &rest[1..];
+                Some(Ok(Ipv4Option {
+                    kind: IPOPT_NOP,
+                    data: &[],
+                }))
+            }
+            kind => {
+                let len = rest.get(1).map_or(0, |&l| l as usize);
+                if len < 2 || len > rest.len() {
+                    self.rest = &[];
+                    return Some(Err(PacketError::BadOptions));
+                }
+                self.rest = &rest[len..];
+                Some(Ok(Ipv4Option {
+                    kind,
+                    data: &rest[2..len],
+                }))
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// UDP
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UdpHeader {
+    pub src_port: u16,
+    pub dst_port: u16,
+}
+
+impl UdpHeader {
+    /// Parse the UDP datagram in an IPv4 payload sent from `src` to `dst`; returns the header
+    /// and the data.
+    ///
+    /// Below 8 bytes: `Truncated`. A length field below 8 or past the buffer: `BadUdpLen`.
+    /// A non-zero checksum must verify: `fold(pseudo_header_sum + sum16(datagram)) == 0`.
+    pub fn parse(buf: &[u8], src: Ipv4Addr, dst: Ipv4Addr) -> Result<(Self, &[u8]), PacketError> {
+        if buf.len() < UDP_HDR_LEN {
+            return Err(PacketError::Truncated);
+        }
+        let len = read_be16(buf, 4) as usize;
+        if len < UDP_HDR_LEN || len > buf.len() {
+            return Err(PacketError::BadUdpLen);
+        }
+        if read_be16(buf, 6) != 0 {
+            let sum = pseudo_header_sum(src, dst, IPPROTO_UDP, len as u16);
+            if fold(sum16(&buf[..len], sum)) != 0 {
+                return Err(PacketError::BadChecksum);
+            }
+        }
+        let hdr = Self {
+            src_port: read_be16(buf, 0),
+            dst_port: read_be16(buf, 2),
+        };
+        Ok((hdr, &buf[UDP_HDR_LEN..len]))
+    }
+
+    /// Write header and `payload` to the start of `buf`; returns the datagram length.
+    ///
+    /// Over 65535 bytes: `BadUdpLen`; `buf` too short: `BufferTooSmall`. The checksum is
+    /// computed with the field at 0; a result of 0 is sent as `0xffff`.
+    pub fn emit(
+        &self,
+        payload: &[u8],
+        src: Ipv4Addr,
+        dst: Ipv4Addr,
+        buf: &mut [u8],
+    ) -> Result<usize, PacketError> {
+        let len = UDP_HDR_LEN + payload.len();
+        if len > u16::MAX as usize {
+            return Err(PacketError::BadUdpLen);
+        }
+        if buf.len() < len {
+            return Err(PacketError::BufferTooSmall);
+        }
+        write_be16(buf, 0, self.src_port);
+        write_be16(buf, 2, self.dst_port);
+        write_be16(buf, 4, len as u16);
+        write_be16(buf, 6, 0);
+        buf[UDP_HDR_LEN..len].copy_from_slice(payload);
+        let sum = pseudo_header_sum(src, dst, IPPROTO_UDP, len as u16);
+        let cks = match fold(sum16(&buf[..len], sum)) {
+            0 => 0xffff,
+            c => c,
+        };
+        write_be16(buf, 6, cks);
+        Ok(len)
+    }
+}
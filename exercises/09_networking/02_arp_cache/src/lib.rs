@@ -0,0 +1,348 @@
+//! # ARP Cache with Aging
+//!
+//! Before a host can send an IPv4 packet on Ethernet it needs the MAC address of the next hop.
+//! ARP asks ("who has 10.0.0.2? tell 10.0.0.1") and the answer goes into the **ARP cache**,
+//! which every outgoing packet consults. This exercise builds that cache on top of the
+//! `packet_parse` types from the previous exercise.
+//!
+//! ## Concepts
+//! - Fixed capacity (`N` slots, no allocation): when full, the **least recently used** entry is
+//!   evicted
+//! - Entries **age**: each one expires `ttl` ticks after it was last confirmed, so a host that
+//!   changed its MAC (or left) is not used forever. `tick()` advances the clock and drops them
+//! - A lookup that hits counts as a use and refreshes the entry's lifetime
+//! - Learning from ARP traffic follows RFC 826: the sender of *any* ARP packet refreshes an
+//!   entry that already exists, but a new entry is only created when the packet is addressed
+//!   to us — otherwise every broadcast request on the LAN would fill the cache
+//!
+//! ## ARP packet (Ethernet / IPv4, 28 bytes, big endian)
+//! ```text
+//! 0  htype u16 = 1   2 ptype u16 = 0x0800   4 hlen = 6   5 plen = 4   6 oper u16 (1 request, 2 reply)
+//! 8  sender MAC [6]  14 sender IP [4]       18 target MAC [6]         24 target IP [4]
+//! ```
+
+#![cfg_attr(not(test), no_std)]
+
+use packet_parse::{read_be16, ETHERTYPE_IPV4};
+pub use packet_parse::{Ipv4Addr, MacAddr};
+
+pub const ARP_LEN: usize = 28;
+pub const ARP_HTYPE_ETHERNET: u16 = 1;
+pub const ARP_REQUEST: u16 = 1;
+pub const ARP_REPLY: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArpError {
+    Truncated,
+    /// Not Ethernet / IPv4 address sizes, or an unknown operation.
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpPacket {
+    pub oper: u16,
+    pub sender_mac: MacAddr,
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: MacAddr,
+    pub target_ip: Ipv4Addr,
+}
+
+impl ArpPacket {
+    pub fn parse(buf: &[u8]) -> Result<Self, ArpError> {
+        if buf.len() < ARP_LEN {
+            return Err(ArpError::Truncated);
+        }
+        let oper = read_be16(buf, 6);
+        if read_be16(buf, 0) != ARP_HTYPE_ETHERNET
+            || read_be16(buf, 2) != ETHERTYPE_IPV4
+            || buf[4] != 6
+            || buf[5] != 4
+            || !matches!(oper, ARP_REQUEST | ARP_REPLY)
+        {
+            return Err(ArpError::Unsupported);
+        }
+        let ip = |o: usize| Ipv4Addr::new(buf[o], buf[o + 1], buf[o + 2], buf[o + 3]);
+        Ok(Self {
+            oper,
+            sender_mac: MacAddr(buf[8..14].try_into().unwrap()),
+            sender_ip: ip(14),
+            target_mac: MacAddr(buf[18..24].try_into().unwrap()),
+            target_ip: ip(24),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpEntry {
+    pub ip: Ipv4Addr,
+    pub mac: MacAddr,
+    /// Tick at which the entry is dropped (`now >= expires`).
+    pub expires: u64,
+    /// Use stamp for LRU: larger is more recent.
+    pub last_used: u64,
+}
+
+pub struct ArpCache<const N: usize> {
+    entries: [Option<ArpEntry>; N],
+    /// Our own address: only packets targeting it create entries.
+    our_ip: Ipv4Addr,
+    ttl: u64,
+    now: u64,
+    /// Source of `last_used` stamps; bumped on every insert and hit.
+    stamp: u64,
+}
+
+impl<const N: usize> ArpCache<N> {
+    pub fn new(our_ip: Ipv4Addr, ttl: u64) -> Self {
+        assert!(N > 0 && ttl > 0);
+        Self {
+            entries: [None; N],
+            our_ip,
+            ttl,
+            now: 0,
+            stamp: 0,
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The entry for `ip`, without counting as a use.
+    pub fn peek(&self, ip: Ipv4Addr) -> Option<&ArpEntry> {
+        self.entries.iter().flatten().find(|e| e.ip == ip)
+    }
+
+    fn next_stamp(&mut self) -> u64 {
+        self.stamp += 1;
+        self.stamp
+    }
+
+    /// Map `ip` to `mac`. Returns the IP address of the entry evicted to make room, if any.
+    ///
+    /// 1. `ip` already present: update its MAC and refresh it (new `expires = now + ttl`,
+    ///    new `last_used` stamp); nothing is evicted
+    /// 2. Otherwise take a free slot, or evict the entry with the smallest `last_used`
+    /// 3. The new entry expires at `now + ttl` and gets a fresh stamp
+    pub fn insert(&mut self, ip: Ipv4Addr, mac: MacAddr) -> Option<Ipv4Addr> {
+        // TODO
+        todo!()
+    }
+
+    /// The MAC address of `ip`. A hit is a use: refresh `expires` and `last_used`.
+    pub fn lookup(&mut self, ip: Ipv4Addr) -> Option<MacAddr> {
+        // TODO
+        todo!()
+    }
+
+    /// Advance the clock by one tick and drop every entry with `expires <= now`. Returns how
+    /// many were dropped.
+    pub fn tick(&mut self) -> usize {
+        // TODO
+        todo!()
+    }
+
+    /// Learn from a received ARP packet (RFC 826 "merge" logic).
+    ///
+    /// If the sender's IP is cached, update it to the sender's MAC (as `insert` does). Else, if
+    /// the packet's target IP is `our_ip`, `insert` the sender. Packets between other hosts
+    /// teach nothing new. Returns the evicted IP, if an insert evicted one.
+    pub fn learn(&mut self, pkt: &ArpPacket) -> Option<Ipv4Addr> {
+        // TODO
+        todo!()
+    }
+
+    pub fn remove(&mut self, ip: Ipv4Addr) -> Option<ArpEntry> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.is_some_and(|e| e.ip == ip))?
+            .take()
+    }
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, assert_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_update_existing_entry",
+            "an ip that is already cached is updated in place and refreshed; nothing is evicted",
+        ),
+        (
+            "test_entries_expire",
+            "tick advances the clock and drops every entry with expires <= now",
+        ),
+        (
+            "test_hit_refreshes_lifetime",
+            "a lookup hit sets expires = now + ttl and a new last_used stamp",
+        ),
+        (
+            "test_lru_eviction",
+            "with no free slot, evict the entry with the smallest last_used and return its ip",
+        ),
+        (
+            "test_learn_from_arp_traffic",
+            "update the sender if it is cached; insert it only if the packet targets our_ip",
+        ),
+    ];
+
+    const OUR_IP: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+
+    fn ip(n: u8) -> Ipv4Addr {
+        Ipv4Addr::new(10, 0, 0, n)
+    }
+
+    fn mac(n: u8) -> MacAddr {
+        MacAddr([2, 0, 0, 0, 0, n])
+    }
+
+    fn arp_bytes(oper: u16, sender: u8, target: u8) -> [u8; ARP_LEN] {
+        let mut b = [0u8; ARP_LEN];
+        b[0..8].copy_from_slice(&[0, 1, 0x08, 0x00, 6, 4, 0, oper as u8]);
+        b[8..14].copy_from_slice(&mac(sender).0);
+        b[14..18].copy_from_slice(&ip(sender).octets());
+        b[24..28].copy_from_slice(&ip(target).octets());
+        b
+    }
+
+    #[test]
+    fn test_insert_and_lookup() {
+        let mut c = ArpCache::<4>::new(OUR_IP, 10);
+        assert!(c.is_empty());
+        assert_eq!(c.insert(ip(2), mac(2)), None);
+        assert_eq!(c.insert(ip(3), mac(3)), None);
+        assert_eq!(c.lookup(ip(2)), Some(mac(2)));
+        assert_eq!(c.lookup(ip(3)), Some(mac(3)));
+        assert_eq!(c.lookup(ip(4)), None);
+        assert_eq!(c.len(), 2);
+    }
+
+    #[test]
+    fn test_update_existing_entry() {
+        let mut c = ArpCache::<2>::new(OUR_IP, 10);
+        c.insert(ip(2), mac(2));
+        c.insert(ip(3), mac(3));
+        assert_eq_with_hint!(c.insert(ip(2), mac(9)), None, "no eviction for an update");
+        assert_eq_with_hint!(c.lookup(ip(2)), Some(mac(9)));
+        assert_eq_with_hint!(c.len(), 2);
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let mut c = ArpCache::<4>::new(OUR_IP, 3);
+        c.insert(ip(2), mac(2));
+        c.tick();
+        c.insert(ip(3), mac(3));
+        assert_eq_with_hint!(c.peek(ip(2)).unwrap().expires, 3);
+        assert_eq_with_hint!(c.tick(), 0);
+        assert_eq_with_hint!(c.tick(), 1, "ip 2 expires at tick 3");
+        assert_with_hint!(c.peek(ip(2)).is_none());
+        assert_eq_with_hint!(c.lookup(ip(2)), None);
+        assert_eq_with_hint!(c.tick(), 1, "ip 3 one tick later");
+        assert_with_hint!(c.is_empty());
+    }
+
+    #[test]
+    fn test_hit_refreshes_lifetime() {
+        let mut c = ArpCache::<4>::new(OUR_IP, 3);
+        c.insert(ip(2), mac(2));
+        c.insert(ip(3), mac(3));
+        c.tick();
+        c.tick();
+        assert_eq_with_hint!(c.lookup(ip(2)), Some(mac(2)));
+        assert_eq_with_hint!(c.peek(ip(2)).unwrap().expires, 5);
+        assert_eq_with_hint!(c.tick(), 1, "only ip 3 expires");
+        c.tick();
+        assert_eq_with_hint!(c.lookup(ip(2)), Some(mac(2)));
+        // Without further hits it is gone 3 ticks after the last one.
+        assert_eq_with_hint!(c.tick() + c.tick(), 0);
+        assert_eq_with_hint!(c.tick(), 1);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut c = ArpCache::<3>::new(OUR_IP, 100);
+        c.insert(ip(2), mac(2));
+        c.insert(ip(3), mac(3));
+        c.insert(ip(4), mac(4));
+        assert_with_hint!(c.lookup(ip(2)).is_some());
+        assert_eq_with_hint!(c.insert(ip(5), mac(5)), Some(ip(3)), "3 is least recent");
+        assert_eq_with_hint!(c.insert(ip(6), mac(6)), Some(ip(4)));
+        assert_eq_with_hint!(c.insert(ip(7), mac(7)), Some(ip(2)));
+        assert_eq_with_hint!(c.len(), 3);
+        assert_with_hint!(c.peek(ip(2)).is_none());
+        assert_eq_with_hint!(c.lookup(ip(5)), Some(mac(5)));
+    }
+
+    #[test]
+    fn test_expired_slots_are_reused_without_eviction() {
+        let mut c = ArpCache::<2>::new(OUR_IP, 2);
+        c.insert(ip(2), mac(2));
+        c.tick();
+        c.insert(ip(3), mac(3));
+        c.tick();
+        assert_eq!(
+            c.insert(ip(4), mac(4)),
+            None,
+            "ip 2 expired, its slot is free"
+        );
+        assert!(c.peek(ip(3)).is_some());
+    }
+
+    #[test]
+    fn test_parse_arp() {
+        let b = arp_bytes(ARP_REPLY, 2, 1);
+        let p = ArpPacket::parse(&b).unwrap();
+        assert_eq!(p.oper, ARP_REPLY);
+        assert_eq!(
+            (p.sender_ip, p.sender_mac, p.target_ip),
+            (ip(2), mac(2), OUR_IP)
+        );
+        assert_eq!(ArpPacket::parse(&b[..27]), Err(ArpError::Truncated));
+        let mut bad = b;
+        bad[5] = 16; // IPv6-sized protocol addresses
+        assert_eq!(ArpPacket::parse(&bad), Err(ArpError::Unsupported));
+        let mut bad = b;
+        bad[7] = 3;
+        assert_eq!(ArpPacket::parse(&bad), Err(ArpError::Unsupported));
+    }
+
+    #[test]
+    fn test_learn_from_arp_traffic() {
+        let mut c = ArpCache::<4>::new(OUR_IP, 10);
+        // A request between two other hosts: nothing learnt.
+        let other = ArpPacket::parse(&arp_bytes(ARP_REQUEST, 3, 4)).unwrap();
+        assert_eq_with_hint!(c.learn(&other), None);
+        assert_with_hint!(c.is_empty());
+        // A request for us: learn the asker (we will answer it anyway).
+        let for_us = ArpPacket::parse(&arp_bytes(ARP_REQUEST, 3, 1)).unwrap();
+        c.learn(&for_us);
+        assert_eq_with_hint!(c.lookup(ip(3)), Some(mac(3)));
+
+        // Now that 3 is known, even traffic not for us updates it.
+        c.tick();
+        let mut moved = arp_bytes(ARP_REQUEST, 3, 4);
+        moved[8..14].copy_from_slice(&mac(33).0);
+        c.learn(&ArpPacket::parse(&moved).unwrap());
+        assert_eq_with_hint!(c.peek(ip(3)).unwrap().mac, mac(33));
+        assert_eq_with_hint!(c.peek(ip(3)).unwrap().expires, 11, "refreshed");
+        assert_eq_with_hint!(c.len(), 1);
+    }
+}
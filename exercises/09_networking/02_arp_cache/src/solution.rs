@@ -0,0 +1,205 @@
+//! # ARP Cache with Aging
+//!
+//! Before a host can send an IPv4 packet on Ethernet it needs the MAC address of the next hop.
+//! ARP asks ("who has 10.0.0.2? tell 10.0.0.1") and the answer goes into the **ARP cache**,
+//! which every outgoing packet consults. This exercise builds that cache on top of the
+//! `packet_parse` types from the previous exercise.
+//!
+//! ## Concepts
+//! - Fixed capacity (`N` slots, no allocation): when full, the **least recently used** entry is
+//!   evicted
+//! - Entries **age**: each one expires `ttl` ticks after it was last confirmed, so a host that
+//!   changed its MAC (or left) is not used forever. `tick()` advances the clock and drops them
+//! - A lookup that hits counts as a use and refreshes the entry's lifetime
+//! - Learning from ARP traffic follows RFC 826: the sender of *any* ARP packet refreshes an
+//!   entry that already exists, but a new entry is only created when the packet is addressed
+//!   to us — otherwise every broadcast request on the LAN would fill the cache
+//!
+//! ## ARP packet (Ethernet / IPv4, 28 bytes, big endian)
+//! ```text
+//! 0  htype u16 = 1   2 ptype u16 = 0x0800   4 hlen = 6   5 plen = 4   6 oper u16 (1 request, 2 reply)
+//! 8  sender MAC [6]  14 sender IP [4]       18 target MAC [6]         24 target IP [4]
+//! ```
+
+use packet_parse::solution::{read_be16, ETHERTYPE_IPV4};
+pub use packet_parse::solution::{Ipv4Addr, MacAddr};
+
+pub const ARP_LEN: usize = 28;
+pub const ARP_HTYPE_ETHERNET: u16 = 1;
+pub const ARP_REQUEST: u16 = 1;
+pub const ARP_REPLY: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArpError {
+    Truncated,
+    /// Not Ethernet / IPv4 address sizes, or an unknown operation.
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpPacket {
+    pub oper: u16,
+    pub sender_mac: MacAddr,
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: MacAddr,
+    pub target_ip: Ipv4Addr,
+}
+
+impl ArpPacket {
+    pub fn parse(buf: &[u8]) -> Result<Self, ArpError> {
+        if buf.len() < ARP_LEN {
+            return Err(ArpError::Truncated);
+        }
+        let oper = read_be16(buf, 6);
+        if read_be16(buf, 0) != ARP_HTYPE_ETHERNET
+            || read_be16(buf, 2) != ETHERTYPE_IPV4
+            || buf[4] != 6
+            || buf[5] != 4
+            || !matches!(oper, ARP_REQUEST | ARP_REPLY)
+        {
+            return Err(ArpError::Unsupported);
+        }
+        let ip = |o: usize| Ipv4Addr::new(buf[o], buf[o + 1], buf[o + 2], buf[o + 3]);
+        Ok(Self {
+            oper,
+            sender_mac: MacAddr(buf[8..14].try_into().unwrap()),
+            sender_ip: ip(14),
+            target_mac: MacAddr(buf[18..24].try_into().unwrap()),
+            target_ip: ip(24),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpEntry {
+    pub ip: Ipv4Addr,
+    pub mac: MacAddr,
+    /// Tick at which the entry is dropped (`now >= expires`).
+    pub expires: u64,
+    /// Use stamp for LRU: larger is more recent.
+    pub last_used: u64,
+}
+
+pub struct ArpCache<const N: usize> {
+    entries: [Option<ArpEntry>; N],
+    /// Our own address: only packets targeting it create entries.
+    our_ip: Ipv4Addr,
+    ttl: u64,
+    now: u64,
+    /// Source of `last_used` stamps; bumped on every insert and hit.
+    stamp: u64,
+}
+
+impl<const N: usize> ArpCache<N> {
+    pub fn new(our_ip: Ipv4Addr, ttl: u64) -> Self {
+        assert!(N > 0 && ttl > 0);
+        Self {
+            entries: [None; N],
+            our_ip,
+            ttl,
+            now: 0,
+            stamp: 0,
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The entry for `ip`, without counting as a use.
+    pub fn peek(&self, ip: Ipv4Addr) -> Option<&ArpEntry> {
+        self.entries.iter().flatten().find(|e| e.ip == ip)
+    }
+
+    fn next_stamp(&mut self) -> u64 {
+        self.stamp += 1;
+        self.stamp
+    }
+
+    /// Map `ip` to `mac`. Returns the IP address of the entry evicted to make room, if any.
+    ///
+    /// 1. `ip` already present: update its MAC and refresh it (new `expires = now + ttl`,
+    ///    new `last_used` stamp); nothing is evicted
+    /// 2. Otherwise take a free slot, or evict the entry with the smallest `last_used`
+    /// 3. The new entry expires at `now + ttl` and gets a fresh stamp
+    pub fn insert(&mut self, ip: Ipv4Addr, mac: MacAddr) -> Option<Ipv4Addr> {
+        let entry = ArpEntry {
+            ip,
+            mac,
+            expires: self.now + self.ttl,
+            last_used: self.next_stamp(),
+        };
+        if let Some(slot) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.is_some_and(|e| e.ip == ip))
+        {
+            *slot = Some(entry);
+            return None;
+        }
+        if let Some(slot) = self.entries.iter_mut().find(|e| e.is_none()) {
+            *slot = Some(entry);
+            return None;
+        }
+        let victim = self
+            .entries
+            .iter_mut()
+            .min_by_key(|e| e.map_or(0, |e| e.last_used))
+            .unwrap();
+        let evicted = victim.map(|e| e.ip);
+        *victim = Some(entry);
+        evicted
+    }
+
+    /// The MAC address of `ip`. A hit is a use: refresh `expires` and `last_used`.
+    pub fn lookup(&mut self, ip: Ipv4Addr) -> Option<MacAddr> {
+        let (now, ttl) = (self.now, self.ttl);
+        let stamp = self.next_stamp();
+        let e = self.entries.iter_mut().flatten().find(|e| e.ip == ip)?;
+        e.expires = now + ttl;
+        e.last_used = stamp;
+        Some(e.mac)
+    }
+
+    /// Advance the clock by one tick and drop every entry with `expires <= now`. Returns how
+    /// many were dropped.
+    pub fn tick(&mut self) -> usize {
+        self.now += 1;
+        let mut dropped = 0;
+        for slot in &mut self.entries {
+            if slot.is_some_and(|e| e.expires <= self.now) {
+                *slot = None;
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+
+    /// Learn from a received ARP packet (RFC 826 "merge" logic).
+    ///
+    /// If the sender's IP is cached, update it to the sender's MAC (as `insert` does). Else, if
+    /// the packet's target IP is `our_ip`, `insert` the sender. Packets between other hosts
+    /// teach nothing new. Returns the evicted IP, if an insert evicted one.
+    pub fn learn(&mut self, pkt: &ArpPacket) -> Option<Ipv4Addr> {
+        if self.peek(pkt.sender_ip).is_some() || pkt.target_ip == self.our_ip {
+            self.insert(pkt.sender_ip, pkt.sender_mac)
+        } else {
+            None
+        }
+    }
+
+    pub fn remove(&mut self, ip: Ipv4Addr) -> Option<ArpEntry> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.is_some_and(|e| e.ip == ip))?
+            .take()
+    }
+}
@@ -0,0 +1,342 @@
+//! # UDP Socket from Raw Syscalls
+//!
+//! In this exercise, you open a UDP socket without `std::net` or libc: `socket`, `bind`,
+//! `sendto` and `recvfrom` through `syscall6` (from `02_no_std_dev/04_syscall_wrapper`, finish
+//! that first), with the socket address built by hand.
+//!
+//! A socket is a file descriptor like any other — `close` releases it — but it is not a byte
+//! stream at an offset: every `sendto` is one datagram with a destination, every `recvfrom`
+//! returns one datagram and tells who sent it.
+//!
+//! ## `struct sockaddr_in` (16 bytes)
+//! ```text
+//! 0 sin_family u16   host byte order (AF_INET = 2)
+//! 2 sin_port   u16   NETWORK byte order (big endian): port 0x1234 is stored as 12 34
+//! 4 sin_addr   u32   network byte order: 127.0.0.1 is stored as 7f 00 00 01
+//! 8 sin_zero   [u8; 8]
+//! ```
+//! Mixing up the byte orders is the classic bug: on a little-endian machine, port 80 stored
+//! without `to_be` is port 20480 on the wire.
+//!
+//! ## Calls (errors are `-errno`, as in the other syscall exercises)
+//! - `socket(AF_INET, SOCK_DGRAM | SOCK_CLOEXEC, 0)` -> fd
+//! - `bind(fd, &addr, 16)`; port 0 lets the kernel pick a free port (`getsockname` tells which)
+//! - `sendto(fd, buf, len, flags, &addr, 16)` -> bytes sent
+//! - `recvfrom(fd, buf, len, flags, &mut addr, &mut addrlen)` -> bytes received; `addrlen` is
+//!   in/out: our buffer size in, the size of the sender address out. A datagram longer than
+//!   `buf` is truncated, and the rest is lost.
+
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::Duration;
+use syscall_wrapper::syscall6;
+
+#[cfg(target_arch = "x86_64")]
+mod nr {
+    pub const SOCKET: usize = 41;
+    pub const SENDTO: usize = 44;
+    pub const RECVFROM: usize = 45;
+    pub const BIND: usize = 49;
+    pub const GETSOCKNAME: usize = 51;
+    pub const SETSOCKOPT: usize = 54;
+    pub const CLOSE: usize = 3;
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod nr {
+    pub const SOCKET: usize = 198;
+    pub const BIND: usize = 200;
+    pub const GETSOCKNAME: usize = 204;
+    pub const SENDTO: usize = 206;
+    pub const RECVFROM: usize = 207;
+    pub const SETSOCKOPT: usize = 208;
+    pub const CLOSE: usize = 57;
+}
+
+pub use nr::*;
+
+pub const AF_INET: u16 = 2;
+pub const SOCK_DGRAM: usize = 2;
+pub const SOCK_CLOEXEC: usize = 0o2000000;
+const SOL_SOCKET: usize = 1;
+const SO_RCVTIMEO: usize = 20;
+
+pub const EAGAIN: isize = 11;
+pub const EADDRINUSE: isize = 98;
+
+/// `struct sockaddr_in`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SockAddrIn {
+    pub sin_family: u16,
+    /// Big endian.
+    pub sin_port: u16,
+    /// The four octets in order, i.e. big endian.
+    pub sin_addr: u32,
+    pub sin_zero: [u8; 8],
+}
+
+pub const SOCKADDR_IN_LEN: usize = std::mem::size_of::<SockAddrIn>();
+
+impl SockAddrIn {
+    /// The `sockaddr_in` for `addr`: family `AF_INET`, port with `to_be()`, and the octets
+    /// stored in memory order (`u32::from_ne_bytes(ip.octets())`).
+    pub fn new(addr: SocketAddrV4) -> Self {
+        // TODO
+        todo!()
+    }
+
+    /// Back to a `SocketAddrV4` (undo both byte-order conversions).
+    pub fn to_socket_addr(&self) -> SocketAddrV4 {
+        // TODO
+        todo!()
+    }
+}
+
+/// `struct timeval`
+#[repr(C)]
+struct Timeval {
+    tv_sec: i64,
+    tv_usec: i64,
+}
+
+/// Result of a raw syscall: negative values are `-errno`.
+fn check(ret: isize) -> Result<usize, isize> {
+    if ret < 0 {
+        Err(ret)
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+pub struct UdpSocket {
+    fd: usize,
+}
+
+impl UdpSocket {
+    /// Create an `AF_INET` datagram socket (close-on-exec) and bind it to `addr`.
+    /// If `bind` fails, close the new fd before returning the error.
+    pub fn bind(addr: SocketAddrV4) -> Result<Self, isize> {
+        // TODO
+        todo!()
+    }
+
+    pub fn fd(&self) -> usize {
+        self.fd
+    }
+
+    /// The address the socket is bound to (`getsockname`): with port 0 in `bind`, this is how
+    /// to learn the port the kernel picked.
+    pub fn local_addr(&self) -> Result<SocketAddrV4, isize> {
+        let mut sa = SockAddrIn::default();
+        let mut len = SOCKADDR_IN_LEN as u32;
+        let ret = unsafe {
+            syscall6(
+                GETSOCKNAME,
+                [
+                    self.fd,
+                    &mut sa as *mut SockAddrIn as usize,
+                    &mut len as *mut u32 as usize,
+                    0,
+                    0,
+                    0,
+                ],
+            )
+        };
+        check(ret)?;
+        Ok(sa.to_socket_addr())
+    }
+
+    /// Send `buf` as one datagram to `addr`; returns the bytes sent.
+    pub fn send_to(&self, buf: &[u8], addr: SocketAddrV4) -> Result<usize, isize> {
+        // TODO
+        todo!()
+    }
+
+    /// Receive one datagram into `buf`; returns its length (at most `buf.len()`) and sender.
+    /// Pass `addrlen` as a `u32` initialised to `SOCKADDR_IN_LEN`.
+    pub fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), isize> {
+        // TODO
+        todo!()
+    }
+
+    /// Make `recv_from` fail with `-EAGAIN` after waiting `timeout` (`SO_RCVTIMEO`).
+    pub fn set_read_timeout(&self, timeout: Duration) -> Result<(), isize> {
+        let tv = Timeval {
+            tv_sec: timeout.as_secs() as i64,
+            tv_usec: timeout.subsec_micros() as i64,
+        };
+        let ret = unsafe {
+            syscall6(
+                SETSOCKOPT,
+                [
+                    self.fd,
+                    SOL_SOCKET,
+                    SO_RCVTIMEO,
+                    &tv as *const Timeval as usize,
+                    std::mem::size_of::<Timeval>(),
+                    0,
+                ],
+            )
+        };
+        check(ret).map(drop)
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        unsafe { syscall6(CLOSE, [self.fd, 0, 0, 0, 0, 0]) };
+    }
+}
+
+/// `127.0.0.1:port`
+pub fn localhost(port: u16) -> SocketAddrV4 {
+    SocketAddrV4::new(Ipv4Addr::LOCALHOST, port)
+}
+
+#[cfg(feature = "solutions")]
+#[doc(hidden)]
+pub mod solution;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "solutions")]
+    use super::solution::*;
+    #[cfg(not(feature = "solutions"))]
+    use super::*;
+    use hints::{assert_eq_with_hint, Hints};
+
+    const HINTS: Hints = &[
+        (
+            "test_sockaddr_in_layout",
+            "sin_family = AF_INET, sin_port = port.to_be(), sin_addr = u32::from_ne_bytes(ip.octets())",
+        ),
+        (
+            "test_sockaddr_in_round_trip",
+            "undo both conversions: u16::from_be for the port and to_ne_bytes for the address",
+        ),
+        (
+            "test_bind_picks_a_port",
+            "bind passes SockAddrIn::new(addr) with length SOCKADDR_IN_LEN; local_addr then reports the port the kernel picked",
+        ),
+        (
+            "test_datagram_boundaries_and_truncation",
+            "recvfrom returns one datagram at most buf.len() long; the rest of a longer datagram is dropped",
+        ),
+        (
+            "test_errors",
+            "a negative syscall result is -errno: return it as Err, and close the fd if bind fails",
+        ),
+    ];
+
+    #[test]
+    fn test_sockaddr_in_layout() {
+        assert_eq_with_hint!(SOCKADDR_IN_LEN, 16);
+        let sa = SockAddrIn::new(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0x1234));
+        let bytes: [u8; 16] = unsafe { std::mem::transmute(sa) };
+        assert_eq_with_hint!(u16::from_ne_bytes([bytes[0], bytes[1]]), AF_INET);
+        assert_eq_with_hint!(bytes[2..4], [0x12, 0x34], "port in network byte order");
+        assert_eq_with_hint!(bytes[4..8], [127, 0, 0, 1], "address in network byte order");
+        assert_eq_with_hint!(bytes[8..], [0; 8]);
+    }
+
+    #[test]
+    fn test_sockaddr_in_round_trip() {
+        for addr in [
+            localhost(80),
+            SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 254), 65535),
+            SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+        ] {
+            assert_eq_with_hint!(SockAddrIn::new(addr).to_socket_addr(), addr);
+        }
+    }
+
+    #[cfg(all(target_os = "linux", not(miri)))]
+    mod linux_tests {
+        use super::*;
+
+        fn socket() -> UdpSocket {
+            let s = UdpSocket::bind(localhost(0)).expect("bind");
+            s.set_read_timeout(Duration::from_secs(5)).unwrap();
+            s
+        }
+
+        #[test]
+        fn test_bind_picks_a_port() {
+            let s = socket();
+            let addr = s.local_addr().unwrap();
+            assert_eq_with_hint!(*addr.ip(), Ipv4Addr::LOCALHOST);
+            assert_ne!(addr.port(), 0);
+        }
+
+        #[test]
+        fn test_loopback_self_send() {
+            let s = socket();
+            let me = s.local_addr().unwrap();
+            assert_eq!(s.send_to(b"ping", me), Ok(4));
+            let mut buf = [0u8; 64];
+            let (n, from) = s.recv_from(&mut buf).unwrap();
+            assert_eq!(&buf[..n], b"ping");
+            assert_eq!(from, me);
+        }
+
+        #[test]
+        fn test_reply_to_sender() {
+            let (a, b) = (socket(), socket());
+            let b_addr = b.local_addr().unwrap();
+            a.send_to(b"request", b_addr).unwrap();
+            let mut buf = [0u8; 64];
+            let (n, from) = b.recv_from(&mut buf).unwrap();
+            assert_eq!(
+                (&buf[..n], from),
+                (&b"request"[..], a.local_addr().unwrap())
+            );
+            b.send_to(b"reply", from).unwrap();
+            let (n, from) = a.recv_from(&mut buf).unwrap();
+            assert_eq!((&buf[..n], from), (&b"reply"[..], b_addr));
+        }
+
+        #[test]
+        fn test_datagram_boundaries_and_truncation() {
+            let s = socket();
+            let me = s.local_addr().unwrap();
+            s.send_to(b"first datagram", me).unwrap();
+            s.send_to(b"second", me).unwrap();
+            let mut small = [0u8; 5];
+            assert_eq_with_hint!(s.recv_from(&mut small).unwrap().0, 5);
+            assert_eq_with_hint!(&small, b"first");
+            let mut buf = [0u8; 64];
+            let (n, _) = s.recv_from(&mut buf).unwrap();
+            assert_eq_with_hint!(&buf[..n], b"second", "the rest of the first one is gone");
+        }
+
+        #[test]
+        fn test_errors() {
+            let s = socket();
+            let taken = s.local_addr().unwrap();
+            assert_eq_with_hint!(UdpSocket::bind(taken).err(), Some(-EADDRINUSE));
+            s.set_read_timeout(Duration::from_millis(20)).unwrap();
+            let mut buf = [0u8; 8];
+            assert_eq_with_hint!(s.recv_from(&mut buf).err(), Some(-EAGAIN));
+        }
+
+        #[test]
+        fn test_interoperates_with_std() {
+            let ours = socket();
+            let theirs = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+            theirs
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let std::net::SocketAddr::V4(their_addr) = theirs.local_addr().unwrap() else {
+                unreachable!()
+            };
+            ours.send_to(b"from raw", their_addr).unwrap();
+            let mut buf = [0u8; 64];
+            let (n, from) = theirs.recv_from(&mut buf).unwrap();
+            assert_eq!(&buf[..n], b"from raw");
+            assert_eq!(from, ours.local_addr().unwrap().into());
+            theirs.send_to(b"from std", from).unwrap();
+            let (n, from) = ours.recv_from(&mut buf).unwrap();
+            assert_eq!((&buf[..n], from), (&b"from std"[..], their_addr));
+        }
+    }
+}
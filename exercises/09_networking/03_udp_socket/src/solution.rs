@@ -0,0 +1,246 @@
+//! # UDP Socket from Raw Syscalls
+//!
+//! In this exercise, you open a UDP socket without `std::net` or libc: `socket`, `bind`,
+//! `sendto` and `recvfrom` through `syscall6` (from `02_no_std_dev/04_syscall_wrapper`, finish
+//! that first), with the socket address built by hand.
+//!
+//! A socket is a file descriptor like any other — `close` releases it — but it is not a byte
+//! stream at an offset: every `sendto` is one datagram with a destination, every `recvfrom`
+//! returns one datagram and tells who sent it.
+//!
+//! ## `struct sockaddr_in` (16 bytes)
+//! ```text
+//! 0 sin_family u16   host byte order (AF_INET = 2)
+//! 2 sin_port   u16   NETWORK byte order (big endian): port 0x1234 is stored as 12 34
+//! 4 sin_addr   u32   network byte order: 127.0.0.1 is stored as 7f 00 00 01
+//! 8 sin_zero   [u8; 8]
+//! ```
+//! Mixing up the byte orders is the classic bug: on a little-endian machine, port 80 stored
+//! without `to_be` is port 20480 on the wire.
+//!
+//! ## Calls (errors are `-errno`, as in the other syscall exercises)
+//! - `socket(AF_INET, SOCK_DGRAM | SOCK_CLOEXEC, 0)` -> fd
+//! - `bind(fd, &addr, 16)`; port 0 lets the kernel pick a free port (`getsockname` tells which)
+//! - `sendto(fd, buf, len, flags, &addr, 16)` -> bytes sent
+//! - `recvfrom(fd, buf, len, flags, &mut addr, &mut addrlen)` -> bytes received; `addrlen` is
+//!   in/out: our buffer size in, the size of the sender address out. A datagram longer than
+//!   `buf` is truncated, and the rest is lost.
+
+pub(crate) use std::net::{Ipv4Addr, SocketAddrV4};
+pub(crate) use std::time::Duration;
+use syscall_wrapper::solution::syscall6;
+
+#[cfg(target_arch = "x86_64")]
+mod nr {
+    pub const SOCKET: usize = 41;
+    pub const SENDTO: usize = 44;
+    pub const RECVFROM: usize = 45;
+    pub const BIND: usize = 49;
+    pub const GETSOCKNAME: usize = 51;
+    pub const SETSOCKOPT: usize = 54;
+    pub const CLOSE: usize = 3;
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod nr {
+    pub const SOCKET: usize = 198;
+    pub const BIND: usize = 200;
+    pub const GETSOCKNAME: usize = 204;
+    pub const SENDTO: usize = 206;
+    pub const RECVFROM: usize = 207;
+    pub const SETSOCKOPT: usize = 208;
+    pub const CLOSE: usize = 57;
+}
+
+pub use nr::*;
+
+pub const AF_INET: u16 = 2;
+pub const SOCK_DGRAM: usize = 2;
+pub const SOCK_CLOEXEC: usize = 0o2000000;
+const SOL_SOCKET: usize = 1;
+const SO_RCVTIMEO: usize = 20;
+
+pub const EAGAIN: isize = 11;
+pub const EADDRINUSE: isize = 98;
+
+/// `struct sockaddr_in`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SockAddrIn {
+    pub sin_family: u16,
+    /// Big endian.
+    pub sin_port: u16,
+    /// The four octets in order, i.e. big endian.
+    pub sin_addr: u32,
+    pub sin_zero: [u8; 8],
+}
+
+pub const SOCKADDR_IN_LEN: usize = std::mem::size_of::<SockAddrIn>();
+
+impl SockAddrIn {
+    /// The `sockaddr_in` for `addr`: family `AF_INET`, port with `to_be()`, and the octets
+    /// stored in memory order (`u32::from_ne_bytes(ip.octets())`).
+    pub fn new(addr: SocketAddrV4) -> Self {
+        Self {
+            sin_family: AF_INET,
+            sin_port: addr.port().to_be(),
+            sin_addr: u32::from_ne_bytes(addr.ip().octets()),
+            sin_zero: [0; 8],
+        }
+    }
+
+    /// Back to a `SocketAddrV4` (undo both byte-order conversions).
+    pub fn to_socket_addr(&self) -> SocketAddrV4 {
+        SocketAddrV4::new(
+            Ipv4Addr::from(self.sin_addr.to_ne_bytes()),
+            u16::from_be(self.sin_port),
+        )
+    }
+}
+
+/// `struct timeval`
+#[repr(C)]
+struct Timeval {
+    tv_sec: i64,
+    tv_usec: i64,
+}
+
+/// Result of a raw syscall: negative values are `-errno`.
+fn check(ret: isize) -> Result<usize, isize> {
+    if ret < 0 {
+        Err(ret)
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+pub struct UdpSocket {
+    fd: usize,
+}
+
+impl UdpSocket {
+    /// Create an `AF_INET` datagram socket (close-on-exec) and bind it to `addr`.
+    /// If `bind` fails, close the new fd before returning the error.
+    pub fn bind(addr: SocketAddrV4) -> Result<Self, isize> {
+        let fd = check(unsafe {
+            syscall6(
+                SOCKET,
+                [AF_INET as usize, SOCK_DGRAM | SOCK_CLOEXEC, 0, 0, 0, 0],
+            )
+        })?;
+        let sock = Self { fd };
+        let sa = SockAddrIn::new(addr);
+        check(unsafe {
+            syscall6(
+                BIND,
+                [
+                    fd,
+                    &sa as *const SockAddrIn as usize,
+                    SOCKADDR_IN_LEN,
+                    0,
+                    0,
+                    0,
+                ],
+            )
+        })?;
+        Ok(sock)
+    }
+
+    pub fn fd(&self) -> usize {
+        self.fd
+    }
+
+    /// The address the socket is bound to (`getsockname`): with port 0 in `bind`, this is how
+    /// to learn the port the kernel picked.
+    pub fn local_addr(&self) -> Result<SocketAddrV4, isize> {
+        let mut sa = SockAddrIn::default();
+        let mut len = SOCKADDR_IN_LEN as u32;
+        let ret = unsafe {
+            syscall6(
+                GETSOCKNAME,
+                [
+                    self.fd,
+                    &mut sa as *mut SockAddrIn as usize,
+                    &mut len as *mut u32 as usize,
+                    0,
+                    0,
+                    0,
+                ],
+            )
+        };
+        check(ret)?;
+        Ok(sa.to_socket_addr())
+    }
+
+    /// Send `buf` as one datagram to `addr`; returns the bytes sent.
+    pub fn send_to(&self, buf: &[u8], addr: SocketAddrV4) -> Result<usize, isize> {
+        let sa = SockAddrIn::new(addr);
+        check(unsafe {
+            syscall6(
+                SENDTO,
+                [
+                    self.fd,
+                    buf.as_ptr() as usize,
+                    buf.len(),
+                    0,
+                    &sa as *const SockAddrIn as usize,
+                    SOCKADDR_IN_LEN,
+                ],
+            )
+        })
+    }
+
+    /// Receive one datagram into `buf`; returns its length (at most `buf.len()`) and sender.
+    /// Pass `addrlen` as a `u32` initialised to `SOCKADDR_IN_LEN`.
+    pub fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), isize> {
+        let mut sa = SockAddrIn::default();
+        let mut len = SOCKADDR_IN_LEN as u32;
+        let n = check(unsafe {
+            syscall6(
+                RECVFROM,
+                [
+                    self.fd,
+                    buf.as_mut_ptr() as usize,
+                    buf.len(),
+                    0,
+                    &mut sa as *mut SockAddrIn as usize,
+                    &mut len as *mut u32 as usize,
+                ],
+            )
+        })?;
+        Ok((n, sa.to_socket_addr()))
+    }
+
+    /// Make `recv_from` fail with `-EAGAIN` after waiting `timeout` (`SO_RCVTIMEO`).
+    pub fn set_read_timeout(&self, timeout: Duration) -> Result<(), isize> {
+        let tv = Timeval {
+            tv_sec: timeout.as_secs() as i64,
+            tv_usec: timeout.subsec_micros() as i64,
+        };
+        let ret = unsafe {
+            syscall6(
+                SETSOCKOPT,
+                [
+                    self.fd,
+                    SOL_SOCKET,
+                    SO_RCVTIMEO,
+                    &tv as *const Timeval as usize,
+                    std::mem::size_of::<Timeval>(),
+                    0,
+                ],
+            )
+        };
+        check(ret).map(drop)
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        unsafe { syscall6(CLOSE, [self.fd, 0, 0, 0, 0, 0]) };
+    }
+}
+
+/// `127.0.0.1:port`
+pub fn localhost(port: u16) -> SocketAddrV4 {
+    SocketAddrV4::new(Ipv4Addr::LOCALHOST, port)
+}
@@ -0,0 +1,386 @@
+//! Machine-readable grading results.
+//!
+//! Runs `cargo test -p <package>` for an exercise and turns the libtest
+//! output into an [`ExerciseResult`]: pass/fail, test counts, the names of the
+//! failing tests, how long the run took and which architecture it ran on.
+//! A [`Report`] collects the results of a whole run and (de)serializes to
+//! JSON, so that dashboards and CI autograding can consume structured data
+//! instead of scraping test output.
+//!
+//! ```no_run
+//! let ex = grader::run_exercise("thread_spawn", "Thread Creation", "Concurrency (Synchronous)");
+//! let report = grader::Report::new(vec![ex]);
+//! std::fs::write("report.json", report.to_json()).unwrap();
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Module 4 (context switching) must run on riscv64, through QEMU on other hosts.
+pub const RISCV64_TARGET: &str = "riscv64gc-unknown-linux-gnu";
+pub const RISCV64_PACKAGES: &[&str] = &[
+    "stack_coroutine",
+    "green_threads",
+    "generator",
+    "switch_bench",
+];
+
+/// The `--target` an exercise has to be tested with, `None` for the host.
+pub fn target_for(package: &str) -> Option<&'static str> {
+    RISCV64_PACKAGES
+        .contains(&package)
+        .then_some(RISCV64_TARGET)
+}
+
+/// Architecture the tests of `package` run on, e.g. `"x86_64"` or `"riscv64"`.
+pub fn arch_for(package: &str) -> &'static str {
+    match target_for(package) {
+        Some(_) => "riscv64",
+        None => std::env::consts::ARCH,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pass,
+    Fail,
+    /// The crate did not compile, so no test ran.
+    BuildError,
+}
+
+/// What libtest printed for one `cargo test` invocation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestSummary {
+    pub passed: u32,
+    pub failed: u32,
+    /// Full paths of the failing tests, e.g. `tests::test_basic`.
+    pub failing: Vec<String>,
+    /// At least one `test result:` line was seen, i.e. the crate compiled.
+    pub ran: bool,
+}
+
+/// Parse libtest's (non-JSON) output, summing over every test binary.
+pub fn parse_test_output(stdout: &str) -> TestSummary {
+    let mut summary = TestSummary::default();
+    for line in stdout.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("test result: ") {
+            summary.ran = true;
+            for field in rest.split([';', '.']) {
+                let mut words = field.split_whitespace();
+                let (Some(n), Some(kind)) = (words.next(), words.next()) else {
+                    continue;
+                };
+                let Ok(n) = n.parse::<u32>() else { continue };
+                match kind {
+                    "passed" => summary.passed += n,
+                    "failed" => summary.failed += n,
+                    _ => {}
+                }
+            }
+        } else if let Some(name) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.strip_suffix(" ... FAILED"))
+        {
+            summary.failing.push(name.to_string());
+        }
+    }
+    summary
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExerciseResult {
+    pub package: String,
+    pub name: String,
+    pub module: String,
+    pub status: Status,
+    pub passed: u32,
+    pub failed: u32,
+    pub failing_tests: Vec<String>,
+    pub duration_ms: u64,
+    /// Architecture the tests ran on, see [`arch_for`].
+    pub arch: String,
+}
+
+impl ExerciseResult {
+    /// Build a result from the outcome of `cargo test`.
+    pub fn from_output(
+        package: &str,
+        name: &str,
+        module: &str,
+        success: bool,
+        stdout: &str,
+        duration_ms: u64,
+    ) -> Self {
+        let summary = parse_test_output(stdout);
+        let status = if !summary.ran {
+            Status::BuildError
+        } else if success && summary.failed == 0 {
+            Status::Pass
+        } else {
+            Status::Fail
+        };
+        Self {
+            package: package.to_string(),
+            name: name.to_string(),
+            module: module.to_string(),
+            status,
+            passed: summary.passed,
+            failed: summary.failed,
+            failing_tests: summary.failing,
+            duration_ms,
+            arch: arch_for(package).to_string(),
+        }
+    }
+}
+
+/// Run the tests of one exercise; must be called from the workspace root.
+pub fn run_exercise(package: &str, name: &str, module: &str) -> ExerciseResult {
+    let mut args = vec!["test", "-p", package];
+    if let Some(target) = target_for(package) {
+        args.extend(["--target", target]);
+    }
+    args.extend(["--no-fail-fast", "--", "--color=never"]);
+
+    let start = Instant::now();
+    let (success, stdout) = match Command::new("cargo").args(&args).output() {
+        Ok(out) => (
+            out.status.success(),
+            String::from_utf8_lossy(&out.stdout).into_owned(),
+        ),
+        Err(_) => (false, String::new()),
+    };
+    let duration_ms = start.elapsed().as_millis() as u64;
+    ExerciseResult::from_output(package, name, module, success, &stdout, duration_ms)
+}
+
+/// The results of one grading run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Report {
+    /// Seconds since the Unix epoch.
+    pub generated: u64,
+    /// Architecture of the machine that produced the report.
+    pub host_arch: String,
+    pub completed: usize,
+    pub total: usize,
+    pub exercises: Vec<ExerciseResult>,
+}
+
+impl Report {
+    pub fn new(exercises: Vec<ExerciseResult>) -> Self {
+        let generated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            generated,
+            host_arch: std::env::consts::ARCH.to_string(),
+            completed: exercises
+                .iter()
+                .filter(|r| r.status == Status::Pass)
+                .count(),
+            total: exercises.len(),
+            exercises,
+        }
+    }
+
+    pub fn get(&self, package: &str) -> Option<&ExerciseResult> {
+        self.exercises.iter().find(|r| r.package == package)
+    }
+
+    /// Pretty-printed JSON, terminated by a newline.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a report always serializes") + "\n"
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// The report in the result format of the classroom autograding action, every exercise
+    /// worth the same share of `total_possible` points (the total score is rounded down).
+    pub fn to_classroom(&self, total_possible: u32) -> ClassroomResult {
+        let total_score = match self.total {
+            0 => 0,
+            total => (total_possible as usize * self.completed / total) as u32,
+        };
+        ClassroomResult {
+            exercises: self
+                .exercises
+                .iter()
+                .map(|r| ClassroomExercise {
+                    name: r.package.clone(),
+                    result: r.status == Status::Pass,
+                })
+                .collect(),
+            statistics: ClassroomStatistics {
+                total_exercations: self.total,
+                total_succeeds: self.completed,
+                total_failures: self.total - self.completed,
+                total_score,
+                total_possible,
+            },
+        }
+    }
+}
+
+/// What the `os-autograding` action of the CI scoring workflow reads
+/// (`.github/result/check_result.json`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassroomResult {
+    pub exercises: Vec<ClassroomExercise>,
+    pub statistics: ClassroomStatistics,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassroomExercise {
+    /// The exercise's package.
+    pub name: String,
+    /// Whether it passed.
+    pub result: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassroomStatistics {
+    /// Number of exercises (the action's spelling).
+    pub total_exercations: usize,
+    pub total_succeeds: usize,
+    pub total_failures: usize,
+    pub total_score: u32,
+    pub total_possible: u32,
+}
+
+impl ClassroomResult {
+    /// Pretty-printed JSON, terminated by a newline.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a classroom result always serializes") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAILING: &str = "\
+running 3 tests
+test tests::test_a ... ok
+test tests::test_b ... FAILED
+test tests::test_c ... ok
+
+failures:
+
+---- tests::test_b stdout ----
+thread 'tests::test_b' panicked at src/lib.rs:10:5:
+not yet implemented
+
+failures:
+    tests::test_b
+
+test result: FAILED. 2 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.01s
+";
+
+    const PASSING: &str = "\
+running 2 tests
+test tests::test_a ... ok
+test tests::test_b ... ok
+
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+running 0 tests
+
+test result: ok. 0 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+
+    #[test]
+    fn test_parse_failing_run() {
+        let s = parse_test_output(FAILING);
+        assert_eq!((s.passed, s.failed, s.ran), (2, 1, true));
+        assert_eq!(s.failing, vec!["tests::test_b".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_sums_every_binary() {
+        let s = parse_test_output(PASSING);
+        assert_eq!((s.passed, s.failed, s.ran), (2, 0, true));
+        assert!(s.failing.is_empty());
+    }
+
+    #[test]
+    fn test_parse_build_error() {
+        let s = parse_test_output("");
+        assert!(!s.ran);
+        let r = ExerciseResult::from_output("p", "P", "M", false, "", 5);
+        assert_eq!(r.status, Status::BuildError);
+    }
+
+    #[test]
+    fn test_status_needs_success_and_no_failure() {
+        let ok = ExerciseResult::from_output("p", "P", "M", true, PASSING, 1);
+        assert_eq!(ok.status, Status::Pass);
+        let bad = ExerciseResult::from_output("p", "P", "M", false, FAILING, 1);
+        assert_eq!(bad.status, Status::Fail);
+        assert_eq!(bad.failing_tests, vec!["tests::test_b".to_string()]);
+        // A doc test failing after the unit tests passed still fails the exercise.
+        let doc = ExerciseResult::from_output("p", "P", "M", false, PASSING, 1);
+        assert_eq!(doc.status, Status::Fail);
+    }
+
+    #[test]
+    fn test_arch() {
+        assert_eq!(arch_for("green_threads"), "riscv64");
+        assert_eq!(target_for("green_threads"), Some(RISCV64_TARGET));
+        assert_eq!(arch_for("thread_spawn"), std::env::consts::ARCH);
+        assert_eq!(target_for("thread_spawn"), None);
+    }
+
+    #[test]
+    fn test_report_json_roundtrip() {
+        let report = Report::new(vec![
+            ExerciseResult::from_output("a", "A", "M", true, PASSING, 10),
+            ExerciseResult::from_output("b", "B", "M", false, FAILING, 20),
+        ]);
+        assert_eq!((report.completed, report.total), (1, 2));
+        let json = report.to_json();
+        assert!(json.contains("\"status\": \"fail\""));
+        assert!(json.contains("\"failing_tests\""));
+        let back = Report::from_json(&json).unwrap();
+        assert_eq!(back, report);
+        assert_eq!(back.get("b").unwrap().duration_ms, 20);
+    }
+
+    #[test]
+    fn test_classroom_result() {
+        let report = Report::new(vec![
+            ExerciseResult::from_output("a", "A", "M", true, PASSING, 10),
+            ExerciseResult::from_output("b", "B", "M", false, FAILING, 20),
+            ExerciseResult::from_output("c", "C", "M", false, "", 30),
+        ]);
+        let classroom = report.to_classroom(100);
+        let results: Vec<_> = classroom
+            .exercises
+            .iter()
+            .map(|e| (e.name.as_str(), e.result))
+            .collect();
+        assert_eq!(results, [("a", true), ("b", false), ("c", false)]);
+        let stats = &classroom.statistics;
+        assert_eq!(
+            (
+                stats.total_exercations,
+                stats.total_succeeds,
+                stats.total_failures
+            ),
+            (3, 1, 2)
+        );
+        assert_eq!((stats.total_score, stats.total_possible), (33, 100));
+        assert!(classroom.to_json().contains("\"total_exercations\": 3"));
+        assert_eq!(
+            Report::new(Vec::new())
+                .to_classroom(100)
+                .statistics
+                .total_score,
+            0
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Hints attached to failing exercise tests.
+//!
+//! An exercise's test module declares a `HINTS` table mapping test names to
+//! the most likely mistake behind that test failing. [`assert_with_hint!`] and
+//! [`assert_eq_with_hint!`] behave like `assert!` and `assert_eq!`, except
+//! that when they fail inside a test listed in the table, the hint is
+//! appended to the panic message:
+//!
+//! ```should_panic
+//! use hints::{assert_eq_with_hint, Hints};
+//!
+//! const HINTS: Hints = &[(
+//!     "test_tlb_fifo_eviction",
+//!     "did you advance fifo_ptr after reuse of an existing entry?",
+//! )];
+//!
+//! fn test_tlb_fifo_eviction() {
+//!     let evicted = Some(0x20);
+//!     assert_eq_with_hint!(evicted, None::<u64>);
+//! }
+//! # test_tlb_fifo_eviction();
+//! ```
+//!
+//! prints
+//!
+//! ```text
+//! assertion `left == right` failed
+//!   left: Some(32)
+//!  right: None
+//!
+//! hint (test_tlb_fifo_eviction): did you advance fifo_ptr after reuse of an existing entry?
+//! ```
+
+use std::fmt;
+
+/// `(test name, hint)` pairs; the test name is the bare function name.
+pub type Hints = &'static [(&'static str, &'static str)];
+
+/// The hint registered for `test`, if any.
+pub fn lookup(hints: Hints, test: &str) -> Option<&'static str> {
+    hints
+        .iter()
+        .find(|(name, _)| *name == test)
+        .map(|(_, hint)| *hint)
+}
+
+/// Test name from the path of the probe fn [`__fn_path!`] declares, e.g.
+/// `tlb_sim::tests::test_flush_all::__hint_probe` -> `test_flush_all`.
+/// Async tests run their body in a closure, so `{{closure}}` is skipped too.
+#[doc(hidden)]
+pub fn test_name(path: &'static str) -> &'static str {
+    path.rsplit("::")
+        .find(|seg| *seg != "__hint_probe" && *seg != "{{closure}}")
+        .unwrap_or(path)
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn fail(hints: Hints, path: &'static str, msg: fmt::Arguments) -> ! {
+    let test = test_name(path);
+    match lookup(hints, test) {
+        Some(hint) => panic!("{msg}\n\nhint ({test}): {hint}"),
+        None => panic!("{msg}"),
+    }
+}
+
+/// Path of the function the macro is expanded in.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fn_path {
+    () => {{
+        fn __hint_probe() {}
+        fn type_name_of<T>(_: T) -> &'static str {
+            ::core::any::type_name::<T>()
+        }
+        type_name_of(__hint_probe)
+    }};
+}
+
+/// `assert!` that appends the current test's entry of `HINTS` on failure.
+///
+/// A `const HINTS: hints::Hints` must be in scope where the macro is used.
+#[macro_export]
+macro_rules! assert_with_hint {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::fail(
+                HINTS,
+                $crate::__fn_path!(),
+                ::core::format_args!("assertion failed: {}", ::core::stringify!($cond)),
+            );
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::fail(HINTS, $crate::__fn_path!(), ::core::format_args!($($arg)+));
+        }
+    };
+}
+
+/// `assert_eq!` that appends the current test's entry of `HINTS` on failure.
+///
+/// A `const HINTS: hints::Hints` must be in scope where the macro is used.
+#[macro_export]
+macro_rules! assert_eq_with_hint {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left != *right {
+                    $crate::fail(
+                        HINTS,
+                        $crate::__fn_path!(),
+                        ::core::format_args!(
+                            "assertion `left == right` failed\n  left: {:?}\n right: {:?}",
+                            left,
+                            right
+                        ),
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left != *right {
+                    $crate::fail(
+                        HINTS,
+                        $crate::__fn_path!(),
+                        ::core::format_args!(
+                            "assertion `left == right` failed: {}\n  left: {:?}\n right: {:?}",
+                            ::core::format_args!($($arg)+),
+                            left,
+                            right
+                        ),
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+
+    const HINTS: Hints = &[
+        ("test_hint_is_appended", "check the off-by-one"),
+        ("test_custom_message", "look at the flags"),
+    ];
+
+    fn panic_message(f: impl FnOnce() + panic::UnwindSafe) -> String {
+        let payload = panic::catch_unwind(f).unwrap_err();
+        match payload.downcast::<String>() {
+            Ok(s) => *s,
+            Err(p) => p.downcast_ref::<&str>().unwrap().to_string(),
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            lookup(HINTS, "test_custom_message"),
+            Some("look at the flags")
+        );
+        assert_eq!(lookup(HINTS, "test_missing"), None);
+    }
+
+    #[test]
+    fn test_test_name() {
+        assert_eq!(test_name("a::tests::test_x::__hint_probe"), "test_x");
+        assert_eq!(
+            test_name("a::tests::test_y::{{closure}}::{{closure}}::__hint_probe"),
+            "test_y"
+        );
+        assert!(__fn_path!().ends_with("tests::test_test_name::__hint_probe"));
+    }
+
+    #[test]
+    fn test_hint_is_appended() {
+        let msg = panic_message(|| assert_eq_with_hint!(1 + 1, 3));
+        assert!(msg.starts_with("assertion `left == right` failed\n  left: 2\n right: 3"));
+        assert!(msg.ends_with("\n\nhint (test_hint_is_appended): check the off-by-one"));
+    }
+
+    #[test]
+    fn test_custom_message() {
+        let flags = 0b101;
+        let msg = panic_message(|| assert_with_hint!(flags & 0b10 != 0, "flags = {flags:#b}"));
+        assert!(msg.starts_with("flags = 0b101\n\nhint (test_custom_message)"));
+        let msg = panic_message(|| assert_eq_with_hint!(flags, 1, "flags"));
+        assert!(msg.starts_with("assertion `left == right` failed: flags\n  left: 5"));
+    }
+
+    #[test]
+    fn test_without_hint() {
+        assert_with_hint!(true);
+        assert_eq_with_hint!("a", "a");
+        let msg = panic_message(|| assert_with_hint!(1 > 2));
+        assert_eq!(msg, "assertion failed: 1 > 2");
+    }
+}
@@ -0,0 +1,250 @@
+//! `progress` — run every exercise's tests and print a per-module checklist.
+//!
+//! Results are written to `.oscamp-progress.json` in the project root so
+//! that students and instructors can look at the last run (`progress show`)
+//! without re-running the whole workspace.
+
+use grader::{ExerciseResult, Report, Status};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+const DEFAULT_OUTPUT: &str = ".oscamp-progress.json";
+
+#[derive(Debug, Deserialize)]
+struct Exercise {
+    name: String,
+    package: String,
+    path: String,
+    module: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    exercise: Vec<Exercise>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut output = DEFAULT_OUTPUT.to_string();
+    let mut filter = None;
+    let mut show_only = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "show" => show_only = true,
+            "-o" | "--output" => {
+                output = iter.next().cloned().unwrap_or_else(|| usage_error(arg));
+            }
+            "-m" | "--module" => {
+                filter = Some(iter.next().cloned().unwrap_or_else(|| usage_error(arg)));
+            }
+            "help" | "--help" | "-h" => {
+                print_usage();
+                return;
+            }
+            other => {
+                eprintln!("Unknown argument: {other}");
+                print_usage();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if show_only {
+        match load_report(&output) {
+            Some(report) => render(&report, None),
+            None => {
+                eprintln!("{RED}Error:{RESET} no saved progress in {output}, run `progress` first");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let exercises = load_exercises();
+    let previous = load_report(&output);
+    let selected: Vec<&Exercise> = exercises
+        .iter()
+        .filter(|ex| filter.as_ref().is_none_or(|m| module_matches(ex, m)))
+        .collect();
+    if selected.is_empty() {
+        eprintln!("{RED}Error:{RESET} no exercise matches the given module");
+        std::process::exit(1);
+    }
+
+    println!("{BOLD}{BLUE}OS Camp - Progress{RESET}\n");
+    let total = selected.len();
+    let mut results = Vec::with_capacity(total);
+    for (i, ex) in selected.iter().enumerate() {
+        print!(
+            "\r\x1b[2K  {DIM}[{:2}/{total}] testing {}...{RESET}",
+            i + 1,
+            ex.package
+        );
+        io::stdout().flush().unwrap();
+        results.push(grader::run_exercise(&ex.package, &ex.name, &ex.module));
+    }
+    print!("\r\x1b[2K");
+
+    // A filtered run only refreshes the selected exercises; keep the rest.
+    if filter.is_some() {
+        if let Some(prev) = &previous {
+            for old in &prev.exercises {
+                if !results.iter().any(|r| r.package == old.package) {
+                    results.push(old.clone());
+                }
+            }
+            let order: HashMap<&str, usize> = exercises
+                .iter()
+                .enumerate()
+                .map(|(i, ex)| (ex.package.as_str(), i))
+                .collect();
+            results.retain(|r| order.contains_key(r.package.as_str()));
+            results.sort_by_key(|r| order[r.package.as_str()]);
+        }
+    }
+
+    let report = Report::new(results);
+    render(&report, previous.as_ref());
+
+    match std::fs::write(&output, report.to_json()) {
+        Ok(()) => println!("  {DIM}Saved to {output}{RESET}"),
+        Err(e) => eprintln!("{RED}Error:{RESET} could not write {output}: {e}"),
+    }
+}
+
+fn load_exercises() -> Vec<Exercise> {
+    for path in ["exercises.toml", "../exercises.toml"] {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let config: Config = toml::from_str(&content).expect("exercises.toml format error");
+            return config.exercise;
+        }
+    }
+    eprintln!(
+        "{RED}Error:{RESET} Could not find exercises.toml, please run in project root directory"
+    );
+    std::process::exit(1);
+}
+
+fn load_report(path: &str) -> Option<Report> {
+    let content = std::fs::read_to_string(path).ok()?;
+    Report::from_json(&content).ok()
+}
+
+/// `--module 06`, `--module page` and `--module "Page Tables"` all select Module 6:
+/// the filter is matched against the module name and the chapter directory.
+fn module_matches(ex: &Exercise, filter: &str) -> bool {
+    let filter = filter.to_lowercase();
+    let chapter = ex.path.split('/').nth(1).unwrap_or_default();
+    ex.module.to_lowercase().contains(&filter) || chapter.contains(&filter)
+}
+
+fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (done * width).checked_div(total).unwrap_or(0);
+    let pct = if total == 0 {
+        0.0
+    } else {
+        done as f64 * 100.0 / total as f64
+    };
+    format!(
+        "{GREEN}{}{RESET}{DIM}{}{RESET} {done}/{total} ({pct:.1}%)",
+        "█".repeat(filled),
+        "░".repeat(width - filled)
+    )
+}
+
+fn render(report: &Report, previous: Option<&Report>) {
+    let was_passing = |pkg: &str| {
+        previous.is_some_and(|p| {
+            p.exercises
+                .iter()
+                .any(|r| r.package == pkg && r.status == Status::Pass)
+        })
+    };
+
+    let mut modules: Vec<&str> = Vec::new();
+    for r in &report.exercises {
+        if !modules.contains(&r.module.as_str()) {
+            modules.push(&r.module);
+        }
+    }
+
+    for module in modules {
+        let rows: Vec<&ExerciseResult> = report
+            .exercises
+            .iter()
+            .filter(|r| r.module == module)
+            .collect();
+        let done = rows.iter().filter(|r| r.status == Status::Pass).count();
+        println!("  {YELLOW}[{module}]{RESET} {done}/{}", rows.len());
+
+        for r in rows {
+            let mark = match r.status {
+                Status::Pass => format!("{GREEN}[x]{RESET}"),
+                Status::Fail => format!("{RED}[ ]{RESET}"),
+                Status::BuildError => format!("{RED}[!]{RESET}"),
+            };
+            let detail = match r.status {
+                Status::BuildError => format!("{RED}build error{RESET}"),
+                _ => format!("{} passed, {} failed", r.passed, r.failed),
+            };
+            let new = if previous.is_some() && r.status == Status::Pass && !was_passing(&r.package)
+            {
+                format!(" {GREEN}{BOLD}new!{RESET}")
+            } else {
+                String::new()
+            };
+            println!("    {mark} {:<22} {DIM}{detail}{RESET}{new}", r.name);
+        }
+        println!();
+    }
+
+    let bar = progress_bar(report.completed, report.total, 30);
+    println!("  {BOLD}Overall:{RESET} {bar}");
+}
+
+fn usage_error(flag: &str) -> String {
+    eprintln!("Missing value for {flag}");
+    print_usage();
+    std::process::exit(1);
+}
+
+fn print_usage() {
+    println!("{BOLD}{BLUE}OS Camp{RESET} - progress tracker\n");
+    println!("Usage: progress [show] [-m <module>] [-o <file>]\n");
+    println!("  {BOLD}(none){RESET}        Run every exercise's tests and save the results");
+    println!("  {BOLD}show{RESET}          Print the last saved results without running tests");
+    println!(
+        "  {BOLD}-m, --module{RESET}  Only re-run exercises whose module matches (e.g. 06, page)"
+    );
+    println!("  {BOLD}-o, --output{RESET}  Results file (default: {DEFAULT_OUTPUT})");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_matches() {
+        let ex = Exercise {
+            name: "Sv39".into(),
+            package: "sv39_mapping".into(),
+            path: "exercises/06_page_table/03_sv39_mapping/src/lib.rs".into(),
+            module: "Page Tables".into(),
+        };
+        assert!(module_matches(&ex, "page"));
+        assert!(module_matches(&ex, "06"));
+        assert!(module_matches(&ex, "Page Tables"));
+        assert!(!module_matches(&ex, "elf"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Run the tests of riscv64-only exercises under QEMU user-mode.
+//!
+//! `stack_coroutine` is `#![cfg(target_arch = "riscv64")]`: on any other host
+//! `cargo test -p stack_coroutine` compiles an empty crate and reports
+//! nothing. Its `tests/riscv64.rs` calls [`check_package`] instead,
+//! which cross-compiles the exercise for [`grader::RISCV64_TARGET`] and runs
+//! the test binary under `qemu-riscv64` (the runner set in
+//! `.cargo/config.toml`). The `qemu-runner` bin does the same from the
+//! command line.
+
+use grader::{parse_test_output, TestSummary, RISCV64_TARGET};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Set to `1` to let [`check_package`] pass without the riscv64 tools instead
+/// of failing.
+pub const SKIP_ENV: &str = "OSCAMP_SKIP_QEMU";
+
+/// What one cross-compiled `cargo test` run produced.
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub success: bool,
+    pub summary: TestSummary,
+    /// libtest's output (stdout).
+    pub stdout: String,
+    /// Cargo's output (stderr): build progress and compile errors.
+    pub stderr: String,
+}
+
+/// The tools needed to build and run riscv64 tests on this host that are
+/// missing.
+pub fn missing_tools() -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    if !target_installed() {
+        missing.push("the riscv64gc-unknown-linux-gnu Rust target");
+    }
+    if !runs("riscv64-linux-gnu-gcc") {
+        missing.push("riscv64-linux-gnu-gcc");
+    }
+    if !runs("qemu-riscv64") {
+        missing.push("qemu-riscv64");
+    }
+    missing
+}
+
+fn runs(tool: &str) -> bool {
+    Command::new(tool)
+        .arg("--version")
+        .output()
+        .is_ok_and(|out| out.status.success())
+}
+
+fn target_installed() -> bool {
+    Command::new("rustc")
+        .args(["--print", "target-libdir", "--target", RISCV64_TARGET])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .is_some_and(|out| PathBuf::from(String::from_utf8_lossy(&out.stdout).trim()).exists())
+}
+
+/// The workspace root: the closest ancestor of the current directory whose
+/// `Cargo.toml` declares `[workspace]`.
+fn workspace_root() -> io::Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    cwd.ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .is_ok_and(|toml| toml.lines().any(|l| l.trim() == "[workspace]"))
+        })
+        .map(|dir| dir.to_path_buf())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not inside the oscamp workspace"))
+}
+
+/// `cargo test -p <package>` for riscv64, with `features` enabled and
+/// `test_args` passed to the test binaries.
+///
+/// Builds into `target/qemu-riscv64`: when called from a test, the outer
+/// `cargo test` still holds the lock on `target/`.
+pub fn run_tests(package: &str, features: &[&str], test_args: &[String]) -> io::Result<Run> {
+    let root = workspace_root()?;
+    // `cargo test` tells its tests which cargo it is.
+    let mut cmd = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    cmd.current_dir(&root)
+        .args(["test", "-p", package, "--target", RISCV64_TARGET])
+        .arg("--target-dir")
+        .arg(root.join("target/qemu-riscv64"));
+    if !features.is_empty() {
+        cmd.args(["--features", &features.join(",")]);
+    }
+    cmd.args(["--", "--color=never"]).args(test_args);
+
+    let out = cmd.output()?;
+    let stdout = String::from_utf8_lossy(&out.stdout).into_owned();
+    Ok(Run {
+        success: out.status.success(),
+        summary: parse_test_output(&stdout),
+        stdout,
+        stderr: String::from_utf8_lossy(&out.stderr).into_owned(),
+    })
+}
+
+/// Test body for riscv64-only exercises: run `package`'s tests under QEMU
+/// and fail with their output if any fails.
+///
+/// Without the tools (see [`missing_tools`]) nothing can run, and the test
+/// fails, unless [`SKIP_ENV`] is `1`: then it passes with a note, printed past
+/// libtest's output capture, that the riscv64 tests were skipped.
+#[track_caller]
+pub fn check_package(package: &str, features: &[&str]) {
+    let missing = missing_tools();
+    if !missing.is_empty() {
+        let missing = missing.join(", ");
+        if skip_requested() {
+            note(&format!(
+                "{package}: riscv64 tests SKIPPED ({SKIP_ENV}=1), missing {missing}"
+            ));
+            return;
+        }
+        panic!(
+            "{package}: riscv64 tests cannot run, missing {missing}. Install them with \
+             `bash scripts/setup_riscv64.sh`, or set {SKIP_ENV}=1 to skip these tests."
+        );
+    }
+
+    let run = run_tests(package, features, &[])
+        .unwrap_or_else(|e| panic!("{package}: could not run cargo: {e}"));
+    if run.success {
+        note(&format!(
+            "{package}: {} riscv64 tests passed under qemu-riscv64",
+            run.summary.passed
+        ));
+    } else if !run.summary.ran {
+        panic!(
+            "{package} does not build for {RISCV64_TARGET}:\n{}",
+            run.stderr
+        );
+    } else {
+        panic!(
+            "{package}: {} riscv64 tests failed under qemu-riscv64: {}\n\n{}",
+            run.summary.failed,
+            run.summary.failing.join(", "),
+            run.stdout
+        );
+    }
+}
+
+fn skip_requested() -> bool {
+    std::env::var(SKIP_ENV).is_ok_and(|v| v == "1")
+}
+
+/// Print to the real stderr: libtest only captures `print!`/`eprint!`.
+fn note(msg: &str) {
+    let _ = writeln!(io::stderr(), "\n    note: {msg}");
+}
@@ -0,0 +1,94 @@
+//! `qemu-runner` — run the riscv64-only exercises' tests under QEMU user-mode.
+//!
+//! Without packages it runs every Module 4 exercise. Arguments after `--` go
+//! to the test binaries, e.g. `qemu-runner green_threads -- test_single_thread`.
+
+use grader::RISCV64_PACKAGES;
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const BLUE: &str = "\x1b[34m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut packages = Vec::new();
+    let mut features = Vec::new();
+    let mut test_args = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => test_args.extend(args.by_ref()),
+            "-F" | "--features" => match args.next() {
+                Some(list) => features.extend(list.split(',').map(str::to_string)),
+                None => usage_error(&arg),
+            },
+            "help" | "--help" | "-h" => {
+                print_usage();
+                return;
+            }
+            pkg if RISCV64_PACKAGES.contains(&pkg) => packages.push(arg),
+            other => {
+                eprintln!("{RED}Error:{RESET} {other} is not a riscv64-only exercise");
+                print_usage();
+                std::process::exit(1);
+            }
+        }
+    }
+    if packages.is_empty() {
+        packages = RISCV64_PACKAGES.iter().map(|p| p.to_string()).collect();
+    }
+
+    let missing = qemu_runner::missing_tools();
+    if !missing.is_empty() {
+        eprintln!(
+            "{RED}Error:{RESET} missing {}; install them with `bash scripts/setup_riscv64.sh`",
+            missing.join(", ")
+        );
+        std::process::exit(1);
+    }
+
+    let features: Vec<&str> = features.iter().map(String::as_str).collect();
+    let mut failed = 0;
+    for pkg in &packages {
+        println!("{BOLD}{BLUE}==> {pkg}{RESET} {DIM}(riscv64, qemu-riscv64){RESET}");
+        let run = match qemu_runner::run_tests(pkg, &features, &test_args) {
+            Ok(run) => run,
+            Err(e) => {
+                eprintln!("{RED}Error:{RESET} could not run cargo: {e}");
+                std::process::exit(1);
+            }
+        };
+        if run.summary.ran {
+            print!("{}", run.stdout);
+        } else {
+            print!("{}", run.stderr);
+        }
+        if run.success {
+            println!("{GREEN}PASS{RESET} {} passed\n", run.summary.passed);
+        } else {
+            failed += 1;
+            println!(
+                "{RED}FAIL{RESET} {} passed, {} failed\n",
+                run.summary.passed, run.summary.failed
+            );
+        }
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn usage_error(flag: &str) -> ! {
+    eprintln!("Missing value for {flag}");
+    print_usage();
+    std::process::exit(1);
+}
+
+fn print_usage() {
+    println!("{BOLD}{BLUE}OS Camp{RESET} - riscv64 test runner\n");
+    println!("Usage: qemu-runner [-F <features>] [<package>...] [-- <test args>]\n");
+    println!("  Cross-compiles riscv64-only exercises and runs their tests under qemu-riscv64.");
+    println!("  Packages: {} (default: all)", RISCV64_PACKAGES.join(", "));
+}
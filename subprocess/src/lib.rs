@@ -0,0 +1,169 @@
+//! Run a test's scenario in a child process.
+//!
+//! Some code cannot be tested in-process: `sys_exit_group` ends the test
+//! binary, a stack overflow onto a guard page kills it with `SIGSEGV`, a
+//! panic handler never returns. [`in_child`] re-executes the test binary with
+//! only the current test selected; in that child the scenario runs, and the
+//! parent gets the child's exit status and output to assert on:
+//!
+//! ```no_run
+//! #[test]
+//! fn test_exit_code() {
+//!     let out = subprocess::in_child(|| std::process::exit(42));
+//!     assert_eq!(out.status.code(), Some(42));
+//! }
+//! ```
+//!
+//! A child that is still running after [`TIMEOUT`] is killed with `SIGKILL`,
+//! so a scenario that was meant to end the process but only ended its thread
+//! fails the test instead of hanging it.
+
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Set in a child process to the name of the test it was started for.
+pub const CHILD_ENV: &str = "OSCAMP_CHILD_TEST";
+
+/// How long a child may run before it is killed.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether this process is the child that [`spawn_self`] started for `test`.
+pub fn is_child(test: &str) -> bool {
+    std::env::var(CHILD_ENV).is_ok_and(|name| name == test)
+}
+
+/// Run the test binary again with only `test` selected (its full path, such as
+/// `tests::test_exit_code`) and [`CHILD_ENV`] set to it; wait for the child and
+/// return its exit status, stdout and stderr.
+///
+/// The test itself decides what to do in the child, by checking [`is_child`].
+pub fn spawn_self(test: &str) -> Output {
+    run(test, TIMEOUT)
+}
+
+/// In the child process, run `scenario`; in the parent, start that child and
+/// return its output. The test is the one running on the current thread.
+///
+/// A scenario that returns ends the child with status 0; one that panics fails
+/// the test in the child, which exits with status 101.
+#[track_caller]
+pub fn in_child(scenario: impl FnOnce()) -> Output {
+    // libtest names each test's thread after the test (and so does `watchdog`).
+    let test = thread::current()
+        .name()
+        .expect("in_child must be called on a test's thread")
+        .to_string();
+    if is_child(&test) {
+        scenario();
+        std::process::exit(0);
+    }
+    spawn_self(&test)
+}
+
+fn run(test: &str, timeout: Duration) -> Output {
+    let exe = std::env::current_exe().expect("cannot locate the test binary");
+    let mut child = Command::new(exe)
+        .args(["--exact", test, "--nocapture"])
+        .env(CHILD_ENV, test)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to start the test binary again");
+    // Read both pipes while the child runs: once one fills up, the child blocks on its next
+    // write and would be killed as if it hung.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // Does nothing if the child has exited in the meantime.
+            let _ = child.kill();
+            break child.wait().unwrap();
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    Output {
+        status,
+        stdout: stdout.join().unwrap(),
+        stderr: stderr.join().unwrap(),
+    }
+}
+
+/// Read `pipe` to the end on a thread of its own.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    let mut pipe = pipe.expect("the child's output is piped");
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        // A read error ends the output early; the exit status still tells what happened.
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn test_exit_status() {
+        let out = in_child(|| std::process::exit(42));
+        assert_eq!(out.status.code(), Some(42));
+    }
+
+    #[test]
+    fn test_returning_scenario_exits_zero() {
+        let out = in_child(|| print!("scenario ran"));
+        assert!(out.status.success());
+        assert!(String::from_utf8_lossy(&out.stdout).contains("scenario ran"));
+    }
+
+    #[test]
+    fn test_panic_fails_the_child() {
+        let out = in_child(|| panic!("boom {}", 42));
+        assert_eq!(out.status.code(), Some(101));
+        assert!(String::from_utf8_lossy(&out.stderr).contains("boom 42"));
+    }
+
+    #[test]
+    fn test_signal() {
+        let out = in_child(|| std::process::abort());
+        assert_eq!(out.status.signal(), Some(6), "{out:?}");
+    }
+
+    #[test]
+    fn test_large_output_is_read_while_the_child_runs() {
+        // Far more than a pipe buffer holds, on both streams.
+        const LEN: usize = 1 << 20;
+        let out = in_child(|| {
+            let line = "x".repeat(1023) + "\n";
+            for _ in 0..LEN / line.len() {
+                print!("{line}");
+                eprint!("{line}");
+            }
+        });
+        assert!(out.status.success(), "{:?}", out.status);
+        assert!(out.stdout.len() >= LEN);
+        assert!(out.stderr.len() >= LEN);
+    }
+
+    #[test]
+    fn test_hung_child_is_killed() {
+        let test = "tests::test_hung_child_is_killed";
+        if is_child(test) {
+            loop {
+                thread::park();
+            }
+        }
+        let start = Instant::now();
+        let out = run(test, Duration::from_millis(200));
+        assert_eq!(out.status.signal(), Some(9));
+        assert!(start.elapsed() < TIMEOUT);
+    }
+}
@@ -0,0 +1,107 @@
+//! Timeouts for exercise tests that can hang.
+//!
+//! A lock that is never released or a wake-up that gets lost makes a test
+//! block forever, and with it `cargo test`, `check.sh` and the CLI's watch
+//! mode. [`run_with_timeout`] runs the test body on its own thread and fails
+//! the test once the time is up, so a deadlock shows up as a failing test:
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! let sum = watchdog::run_with_timeout(Duration::from_secs(5), || 1 + 1);
+//! assert_eq!(sum, 2);
+//! ```
+//!
+//! A stuck body cannot be killed: its thread keeps running (or blocking)
+//! until the test binary exits.
+
+use std::panic;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Run `test_fn` and return its result, panicking if it takes longer than `dur`.
+///
+/// A panic of `test_fn` is passed on unchanged, so `#[should_panic]` tests
+/// keep working. Under Miri the body runs directly: Miri reports deadlocks
+/// itself and is far too slow for wall-clock timeouts.
+#[track_caller]
+pub fn run_with_timeout<T, F>(dur: Duration, test_fn: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    if cfg!(miri) {
+        return test_fn();
+    }
+
+    // libtest names each test's thread after the test.
+    let name = thread::current().name().unwrap_or("test").to_string();
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::Builder::new()
+        .name(name.clone())
+        .spawn(move || {
+            let _ = tx.send(test_fn());
+        })
+        .expect("failed to spawn the test thread");
+
+    match rx.recv_timeout(dur) {
+        Ok(value) => {
+            let _ = handle.join();
+            value
+        }
+        Err(RecvTimeoutError::Disconnected) => match handle.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => unreachable!("the test thread sends its result before exiting"),
+        },
+        Err(RecvTimeoutError::Timeout) => panic!("{}", timeout_message(&name, dur)),
+    }
+}
+
+fn timeout_message(name: &str, dur: Duration) -> String {
+    let rerun = match std::env::var("CARGO_PKG_NAME") {
+        Ok(pkg) => format!("cargo test -p {pkg} {name} -- --nocapture"),
+        Err(_) => format!("cargo test {name} -- --nocapture"),
+    };
+    format!(
+        "`{name}` did not finish within {dur:?}.\n\
+         The code under test is most likely deadlocked (a lock that is never released, \
+         a waiter that is never woken) or spinning forever.\n\
+         The stuck thread keeps running until the test binary exits. To run this test \
+         alone:\n    {rerun}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_returns_the_result() {
+        let v = run_with_timeout(Duration::from_secs(5), || vec![1, 2, 3]);
+        assert_eq!(v, [1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "boom 42")]
+    fn test_panic_is_passed_on() {
+        run_with_timeout(Duration::from_secs(5), || panic!("boom {}", 42));
+    }
+
+    #[test]
+    fn test_deadlock_times_out() {
+        let lock = Arc::new(Mutex::new(()));
+        let held = lock.lock().unwrap();
+        let payload = panic::catch_unwind(|| {
+            let lock = lock.clone();
+            run_with_timeout(Duration::from_millis(50), move || drop(lock.lock()));
+        })
+        .unwrap_err();
+        let msg = payload.downcast::<String>().unwrap();
+        assert!(msg.contains("test_deadlock_times_out` did not finish within 50ms"));
+        assert!(msg.contains("deadlocked"));
+        // Releasing the lock lets the leaked thread finish.
+        drop(held);
+    }
+}